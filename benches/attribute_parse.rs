@@ -0,0 +1,123 @@
+//! Times parsing every class of a jar into a `ClassFile` and counts the
+//! allocations it makes, the measure of how attribute tables are parsed:
+//! run it on a build before and after a change to the attribute parser to
+//! compare the two.
+//!
+//! cargo bench --bench attribute_parse -- <jar> [rounds]
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Instant,
+};
+
+use jvmb::{classfile::ClassFile, jar::JarFile};
+
+/// The system allocator, keeping count of the allocations made and the
+/// bytes they asked for.
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED.fetch_add(new_size, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+fn main() {
+    let args: Vec<String> = std::env::args()
+        .skip(1)
+        .filter(|arg| !arg.starts_with("--"))
+        .collect();
+    let jar_path = match args.first() {
+        Some(path) => path,
+        None => {
+            eprintln!("usage: cargo bench --bench attribute_parse -- <jar> [rounds]");
+            return;
+        }
+    };
+    let rounds: u32 = args
+        .get(1)
+        .and_then(|rounds| rounds.parse().ok())
+        .unwrap_or(5);
+
+    let jar = JarFile::open(jar_path).unwrap();
+    let classes: Vec<Vec<u8>> = jar
+        .class_entries()
+        .filter_map(|entry| jar.read(entry).ok())
+        .collect();
+
+    let parse_all = || {
+        let mut attributes = 0;
+        for buf in &classes {
+            if let Ok((_, class_file)) = ClassFile::parse_class_file(buf) {
+                attributes += class_file.attributes.len()
+                    + class_file
+                        .fields
+                        .iter()
+                        .map(|field| field.attributes().len())
+                        .sum::<usize>()
+                    + class_file
+                        .methods
+                        .iter()
+                        .map(|method| method.attributes().len())
+                        .sum::<usize>();
+            }
+        }
+        attributes
+    };
+
+    // one round on its own for the allocations, which are the same every
+    // round
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let allocated = ALLOCATED.load(Ordering::Relaxed);
+    let attributes = parse_all();
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - allocations;
+    let allocated = ALLOCATED.load(Ordering::Relaxed) - allocated;
+
+    let start = Instant::now();
+    for _ in 0..rounds {
+        parse_all();
+    }
+    let elapsed = start.elapsed() / rounds;
+
+    let per_class = |total: usize| total as f64 / classes.len().max(1) as f64;
+    println!(
+        "{} classes, {} attributes of classes and members, {} rounds",
+        classes.len(),
+        attributes,
+        rounds
+    );
+    println!(
+        "parse:       {:>10.2?} ({:.2} µs a class)",
+        elapsed,
+        elapsed.as_secs_f64() * 1e6 / classes.len().max(1) as f64
+    );
+    println!(
+        "allocations: {:>10} ({:.1} a class)",
+        allocations,
+        per_class(allocations)
+    );
+    println!(
+        "allocated:   {:>10} bytes ({:.0} a class)",
+        allocated,
+        per_class(allocated)
+    );
+}
@@ -0,0 +1,93 @@
+//! Compares parsing every class of a jar into a `ClassFile` with parsing it
+//! into a `BorrowedClassFile`, each then totting up the bytecode of every
+//! method, and times converting the borrowed classes to owned ones.
+//!
+//! cargo bench --bench borrowed_parse -- <jar> [rounds]
+
+use std::{panic, time::Instant};
+
+use jvmb::{
+    borrowed::BorrowedClassFile,
+    classfile::{ClassFile, ParseOptions},
+    jar::JarFile,
+};
+
+fn main() {
+    let args: Vec<String> = std::env::args()
+        .skip(1)
+        .filter(|arg| !arg.starts_with("--"))
+        .collect();
+    let jar_path = match args.first() {
+        Some(path) => path,
+        None => {
+            eprintln!("usage: cargo bench --bench borrowed_parse -- <jar> [rounds]");
+            return;
+        }
+    };
+    let rounds: u32 = args
+        .get(1)
+        .and_then(|rounds| rounds.parse().ok())
+        .unwrap_or(5);
+
+    let jar = JarFile::open(jar_path).unwrap();
+    let classes: Vec<Vec<u8>> = jar
+        .class_entries()
+        .filter_map(|entry| jar.read(entry).ok())
+        .collect();
+
+    // Some classes still trip the full parser; skip them rather than abort.
+    panic::set_hook(Box::new(|_| {}));
+    let parse_all = |parse: &dyn Fn(&[u8]) -> usize| {
+        let mut code_bytes = 0;
+        let start = Instant::now();
+        for _ in 0..rounds {
+            code_bytes = 0;
+            for buf in &classes {
+                code_bytes +=
+                    panic::catch_unwind(panic::AssertUnwindSafe(|| parse(buf))).unwrap_or(0);
+            }
+        }
+        (start.elapsed() / rounds, code_bytes)
+    };
+
+    let (owned, owned_bytes) = parse_all(&|buf| match ClassFile::parse_class_file(buf) {
+        Ok((_, class_file)) => class_file
+            .methods
+            .iter()
+            .filter_map(|method| method.code())
+            .map(|code| code.code.len())
+            .sum(),
+        Err(_) => 0,
+    });
+    let (borrowed, borrowed_bytes) = parse_all(&|buf| match BorrowedClassFile::parse(buf) {
+        Ok((_, class_file)) => class_file
+            .methods
+            .iter()
+            .filter_map(|method| method.code(&class_file.constant_pool)?.ok())
+            .map(|code| code.code.len())
+            .sum(),
+        Err(_) => 0,
+    });
+    let options = ParseOptions::new();
+    let (converted, _) = parse_all(&|buf| {
+        BorrowedClassFile::parse(buf)
+            .ok()
+            .and_then(|(_, class_file)| class_file.into_owned(&options).ok())
+            .map_or(0, |class_file| class_file.methods.len())
+    });
+
+    println!("{} classes, {} rounds", classes.len(), rounds);
+    println!(
+        "owned:          {:>10.3?} ({} bytes of code)",
+        owned, owned_bytes
+    );
+    println!(
+        "borrowed:       {:>10.3?} ({} bytes of code)",
+        borrowed, borrowed_bytes
+    );
+    println!(
+        "speedup:        {:>10.1}x",
+        owned.as_secs_f64() / borrowed.as_secs_f64()
+    );
+    println!("borrowed+owned: {:>10.3?}", converted);
+}
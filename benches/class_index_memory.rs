@@ -0,0 +1,259 @@
+//! Compares the heap held by a `ClassIndex` of a generated corpus of classes
+//! with that held by summaries storing every name as its own `String`, the
+//! way the index used to, and checks `ClassIndex::approx_bytes` against
+//! what was actually allocated.
+//!
+//! cargo bench --bench class_index_memory -- [classes]
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use jvmb::{classfile::ClassFile, classindex::ClassIndex, constantpool::ConstantPool};
+
+/// The system allocator, keeping count of the bytes allocated and not yet
+/// freed.
+struct Counting;
+
+static LIVE: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        LIVE.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        LIVE.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        LIVE.fetch_sub(layout.size(), Ordering::Relaxed);
+        LIVE.fetch_add(new_size, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+/// What the index kept of a class before it had a string table.
+#[allow(dead_code)]
+struct NaiveClass {
+    name: String,
+    access_flags: u16,
+    super_class: Option<String>,
+    interfaces: Vec<String>,
+    fields: Vec<NaiveMember>,
+    methods: Vec<NaiveMember>,
+    source: String,
+}
+
+#[allow(dead_code)]
+struct NaiveMember {
+    access_flags: u16,
+    name: String,
+    descriptor: String,
+    bridge_to: Option<String>,
+}
+
+impl NaiveClass {
+    fn new(class_file: &ClassFile, source: &str) -> Self {
+        let constant_pool = &class_file.constant_pool;
+        let class_name = |index| ConstantPool::class_name(constant_pool, index).map(str::to_string);
+        let member = |access_flags, name_index, descriptor_index| NaiveMember {
+            access_flags,
+            name: ConstantPool::utf8(constant_pool, name_index)
+                .unwrap_or_default()
+                .to_string(),
+            descriptor: ConstantPool::utf8(constant_pool, descriptor_index)
+                .unwrap_or_default()
+                .to_string(),
+            bridge_to: None,
+        };
+        NaiveClass {
+            name: class_name(class_file.this_class).unwrap_or_default(),
+            access_flags: class_file.access_flags.bits(),
+            super_class: class_name(class_file.super_class),
+            interfaces: class_file
+                .interfaces
+                .iter()
+                .filter_map(|&index| class_name(index))
+                .collect(),
+            fields: class_file
+                .fields
+                .iter()
+                .map(|field| {
+                    member(
+                        field.access_flags().bits(),
+                        field.name_index(),
+                        field.descriptor_index(),
+                    )
+                })
+                .collect(),
+            methods: class_file
+                .methods
+                .iter()
+                .map(|method| {
+                    member(
+                        method.access_flags().bits(),
+                        method.name_index(),
+                        method.descriptor_index(),
+                    )
+                })
+                .collect(),
+            source: source.to_string(),
+        }
+    }
+}
+
+const FIELD_TYPES: [&str; 6] = [
+    "I",
+    "J",
+    "Z",
+    "Ljava/lang/String;",
+    "Ljava/util/List;",
+    "Ljava/util/Map;",
+];
+const METHODS: [(&str, &str); 10] = [
+    ("<init>", "()V"),
+    ("toString", "()Ljava/lang/String;"),
+    ("hashCode", "()I"),
+    ("equals", "(Ljava/lang/Object;)Z"),
+    ("run", "()V"),
+    ("getName", "()Ljava/lang/String;"),
+    ("setName", "(Ljava/lang/String;)V"),
+    ("size", "()I"),
+    ("apply", "(Ljava/lang/Object;)Ljava/lang/Object;"),
+    ("close", "()V"),
+];
+const INTERFACES: [&str; 4] = [
+    "java/lang/Runnable",
+    "java/io/Serializable",
+    "java/lang/AutoCloseable",
+    "java/util/function/Function",
+];
+
+/// A class file of the corpus: one of a hundred packages, extending the
+/// class before it in its package, with two interfaces, a few fields named
+/// after the class and ten methods most classes share.
+fn class_bytes(i: usize) -> Vec<u8> {
+    let name = format!("com/example/module{}/Generated{}", i % 100, i);
+    let super_class = match i / 100 {
+        0 => "java/lang/Object".to_string(),
+        n => format!(
+            "com/example/module{}/Generated{}",
+            i % 100,
+            (n - 1) * 100 + i % 100
+        ),
+    };
+    let mut pool: Vec<Vec<u8>> = Vec::new();
+    let utf8 = |pool: &mut Vec<Vec<u8>>, value: &str| {
+        let mut entry = vec![1];
+        entry.extend((value.len() as u16).to_be_bytes());
+        entry.extend(value.as_bytes());
+        let index = pool.iter().position(|e| *e == entry).unwrap_or_else(|| {
+            pool.push(entry);
+            pool.len() - 1
+        });
+        index as u16 + 1
+    };
+    let class = |pool: &mut Vec<Vec<u8>>, value: &str| {
+        let name_index = utf8(pool, value);
+        let mut entry = vec![7];
+        entry.extend(name_index.to_be_bytes());
+        pool.push(entry);
+        pool.len() as u16
+    };
+    let this_class = class(&mut pool, &name);
+    let super_index = class(&mut pool, &super_class);
+    let interfaces = [
+        class(&mut pool, INTERFACES[i % 4]),
+        class(&mut pool, INTERFACES[(i + 1) % 4]),
+    ];
+    let members = |pool: &mut Vec<Vec<u8>>, members: &[(String, &str)]| {
+        let mut bytes = Vec::new();
+        bytes.extend((members.len() as u16).to_be_bytes());
+        for (name, descriptor) in members {
+            bytes.extend(0x0001u16.to_be_bytes());
+            bytes.extend(utf8(pool, name).to_be_bytes());
+            bytes.extend(utf8(pool, descriptor).to_be_bytes());
+            bytes.extend(0u16.to_be_bytes());
+        }
+        bytes
+    };
+    let fields: Vec<(String, &str)> = (0..4 + i % 4)
+        .map(|field| {
+            (
+                format!("field{}_{}", i, field),
+                FIELD_TYPES[(i + field) % 6],
+            )
+        })
+        .collect();
+    let methods: Vec<(String, &str)> = METHODS
+        .iter()
+        .map(|&(name, descriptor)| (name.to_string(), descriptor))
+        .collect();
+    let fields = members(&mut pool, &fields);
+    let methods = members(&mut pool, &methods);
+
+    let mut bytes = vec![0xca, 0xfe, 0xba, 0xbe, 0, 0, 0, 52];
+    bytes.extend((pool.len() as u16 + 1).to_be_bytes());
+    bytes.extend(pool.concat());
+    bytes.extend(0x0021u16.to_be_bytes());
+    bytes.extend(this_class.to_be_bytes());
+    bytes.extend(super_index.to_be_bytes());
+    bytes.extend((interfaces.len() as u16).to_be_bytes());
+    for interface in interfaces {
+        bytes.extend(interface.to_be_bytes());
+    }
+    bytes.extend(fields);
+    bytes.extend(methods);
+    bytes.extend(0u16.to_be_bytes());
+    bytes
+}
+
+fn main() {
+    let count: usize = std::env::args()
+        .skip(1)
+        .find(|arg| !arg.starts_with("--"))
+        .and_then(|count| count.parse().ok())
+        .unwrap_or(10_000);
+    let corpus: Vec<Vec<u8>> = (0..count).map(class_bytes).collect();
+    let source = "generated.jar";
+
+    let before = LIVE.load(Ordering::Relaxed);
+    let mut naive = Vec::new();
+    for buf in &corpus {
+        let (_, class_file) = ClassFile::parse_class_file(buf).unwrap();
+        naive.push(NaiveClass::new(&class_file, source));
+    }
+    naive.shrink_to_fit();
+    let naive_bytes = LIVE.load(Ordering::Relaxed) - before;
+    drop(naive);
+
+    let before = LIVE.load(Ordering::Relaxed);
+    let mut index = ClassIndex::new();
+    for buf in &corpus {
+        let (_, class_file) = ClassFile::parse_class_file(buf).unwrap();
+        index.add_class(&class_file, source);
+    }
+    let index_bytes = LIVE.load(Ordering::Relaxed) - before;
+    assert_eq!(index.len(), count);
+
+    println!("{} classes", count);
+    println!(
+        "naive summaries: {:>10} bytes ({} a class)",
+        naive_bytes,
+        naive_bytes / count
+    );
+    println!(
+        "class index:     {:>10} bytes ({} a class, {:.1}x smaller)",
+        index_bytes,
+        index_bytes / count,
+        naive_bytes as f64 / index_bytes as f64
+    );
+    println!("approx_bytes:    {:>10} bytes", index.approx_bytes());
+}
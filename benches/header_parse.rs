@@ -0,0 +1,93 @@
+//! Compares parsing every class of a jar in full with parsing only its
+//! header, and with stepping over its members, and checks that the headers
+//! agree with the full parse on names, version and flags.
+//!
+//! cargo bench --bench header_parse -- <jar> [rounds]
+
+use std::{panic, time::Instant};
+
+use jvmb::{
+    classfile::{ClassFile, ParseOptions},
+    jar::JarFile,
+};
+
+fn main() {
+    let args: Vec<String> = std::env::args()
+        .skip(1)
+        .filter(|arg| !arg.starts_with("--"))
+        .collect();
+    let jar_path = match args.first() {
+        Some(path) => path,
+        None => {
+            eprintln!("usage: cargo bench --bench header_parse -- <jar> [rounds]");
+            return;
+        }
+    };
+    let rounds: u32 = args
+        .get(1)
+        .and_then(|rounds| rounds.parse().ok())
+        .unwrap_or(5);
+
+    let jar = JarFile::open(jar_path).unwrap();
+    let classes: Vec<Vec<u8>> = jar
+        .class_entries()
+        .filter_map(|entry| jar.read(entry).ok())
+        .collect();
+
+    // Some classes still trip the full parser; skip them rather than abort.
+    panic::set_hook(Box::new(|_| {}));
+    let parse_all = |parse: &dyn Fn(&[u8]) -> usize| {
+        let mut parsed = 0;
+        let start = Instant::now();
+        for _ in 0..rounds {
+            parsed = 0;
+            for buf in &classes {
+                parsed += panic::catch_unwind(panic::AssertUnwindSafe(|| parse(buf))).unwrap_or(0);
+            }
+        }
+        (start.elapsed() / rounds, parsed)
+    };
+
+    let (full, full_parsed) = parse_all(&|buf| ClassFile::parse_class_file(buf).map_or(0, |_| 1));
+    let (header, header_parsed) = parse_all(&|buf| ClassFile::parse_header(buf).map_or(0, |_| 1));
+    let options = ParseOptions::new();
+    let (skipping, skipping_parsed) =
+        parse_all(&|buf| ClassFile::parse_skipping_members(buf, &options).map_or(0, |_| 1));
+
+    let (_, agreeing) = parse_all(&|buf| {
+        let (Ok((_, class_file)), Ok(header)) = (
+            ClassFile::parse_class_file(buf),
+            ClassFile::parse_header(buf),
+        ) else {
+            return 0;
+        };
+        let agrees = Ok(header.name.as_str()) == class_file.class_name()
+            && Ok(header.super_class.as_deref()) == class_file.super_class_name()
+            && Ok(header.interfaces.iter().map(String::as_str).collect())
+                == class_file.interface_names()
+            && header.java_version == class_file.java_version()
+            && (header.major_version, header.minor_version)
+                == (class_file.major_version, class_file.minor_version)
+            && header.access_flags == class_file.access_flags;
+        agrees as usize
+    });
+
+    println!("{} classes, {} rounds", classes.len(), rounds);
+    println!("full:     {:>10.3?} ({} parsed)", full, full_parsed);
+    println!(
+        "header:   {:>10.3?} ({} parsed, {:.1}x)",
+        header,
+        header_parsed,
+        full.as_secs_f64() / header.as_secs_f64()
+    );
+    println!(
+        "skipping: {:>10.3?} ({} parsed, {:.1}x)",
+        skipping,
+        skipping_parsed,
+        full.as_secs_f64() / skipping.as_secs_f64()
+    );
+    println!(
+        "headers agreeing with the full parse: {} of {}",
+        agreeing, full_parsed
+    );
+}
@@ -0,0 +1,123 @@
+//! Compares finding the classes of one package in a jar by reading every
+//! entry of a `JarFile` against picking entries of a `JarReader` by name,
+//! which reads and inflates only those picked, counting the bytes each
+//! reads. tests/jar.rs checks that no byte of a skipped entry is read.
+//!
+//! cargo bench --bench jar_streaming -- <jar> [package]
+
+use std::{
+    cell::RefCell,
+    fs::File,
+    io::{self, Read, Seek, SeekFrom},
+    rc::Rc,
+    time::Instant,
+};
+
+use jvmb::{
+    classfile::ClassFile,
+    jar::{JarFile, JarReader},
+};
+
+/// A reader recording the range of every read it passes on.
+struct CountingReader<R> {
+    inner: R,
+    position: u64,
+    reads: Rc<RefCell<Vec<(u64, u64)>>>,
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.reads
+            .borrow_mut()
+            .push((self.position, self.position + read as u64));
+        self.position += read as u64;
+        Ok(read)
+    }
+}
+
+impl<R: Seek> Seek for CountingReader<R> {
+    fn seek(&mut self, to: SeekFrom) -> io::Result<u64> {
+        self.position = self.inner.seek(to)?;
+        Ok(self.position)
+    }
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args()
+        .skip(1)
+        .filter(|arg| !arg.starts_with("--"))
+        .collect();
+    let jar_path = match args.first() {
+        Some(path) => path,
+        None => {
+            eprintln!("usage: cargo bench --bench jar_streaming -- <jar> [package]");
+            return;
+        }
+    };
+    let package = args.get(1).map_or("java/lang/", String::as_str);
+    let wanted = |name: &str| name.starts_with(package) && name.ends_with(".class");
+
+    let start = Instant::now();
+    let jar = JarFile::open(jar_path).unwrap();
+    let mut full_inflated = 0;
+    let mut full_matches = 0;
+    for entry in jar.class_entries() {
+        let buf = match jar.read(entry) {
+            Ok(buf) => buf,
+            Err(_) => continue,
+        };
+        full_inflated += buf.len();
+        if wanted(&entry.name) && ClassFile::parse_class_file(&buf).is_ok() {
+            full_matches += 1;
+        }
+    }
+    let full = start.elapsed();
+    let file_len = std::fs::metadata(jar_path).unwrap().len();
+
+    let reads = Rc::new(RefCell::new(Vec::new()));
+    let start = Instant::now();
+    let reader = JarReader::new(CountingReader {
+        inner: File::open(jar_path).unwrap(),
+        position: 0,
+        reads: reads.clone(),
+    })
+    .unwrap();
+    let mut streamed_inflated = 0;
+    let mut streamed_matches = 0;
+    let mut skipped = 0;
+    for handle in reader.class_entries() {
+        if !wanted(handle.name()) {
+            skipped += 1;
+            continue;
+        }
+        streamed_inflated += handle.size() as usize;
+        if handle.parse_summary().is_ok() {
+            streamed_matches += 1;
+        }
+    }
+    let streamed = start.elapsed();
+
+    let read_bytes: u64 = reads.borrow().iter().map(|(start, end)| end - start).sum();
+
+    let classes = reader.class_entries().count();
+    println!(
+        "{} classes, {} of them in {}, {} skipped",
+        classes,
+        classes - skipped,
+        package,
+        skipped
+    );
+    println!(
+        "read all:  {:>10.3?}, {} bytes read, {} inflated ({} matches)",
+        full, file_len, full_inflated, full_matches
+    );
+    println!(
+        "streamed:  {:>10.3?}, {} bytes read, {} inflated ({} matches)",
+        streamed, read_bytes, streamed_inflated, streamed_matches
+    );
+    println!(
+        "speedup:   {:>10.1}x",
+        full.as_secs_f64() / streamed.as_secs_f64()
+    );
+}
@@ -0,0 +1,76 @@
+//! Compares parsing every class of a jar on one thread with parsing them on
+//! every core through `parallel::map`, each totting up the bytecode of every
+//! method.
+//!
+//! cargo bench --bench parallel_scan -- <jar> [rounds]
+
+use std::{panic, time::Instant};
+
+use jvmb::{classfile::ClassFile, jar::JarFile, parallel};
+
+fn main() {
+    let args: Vec<String> = std::env::args()
+        .skip(1)
+        .filter(|arg| !arg.starts_with("--"))
+        .collect();
+    let jar_path = match args.first() {
+        Some(path) => path,
+        None => {
+            eprintln!("usage: cargo bench --bench parallel_scan -- <jar> [rounds]");
+            return;
+        }
+    };
+    let rounds: u32 = args
+        .get(1)
+        .and_then(|rounds| rounds.parse().ok())
+        .unwrap_or(5);
+
+    let jar = JarFile::open(jar_path).unwrap();
+    let classes: Vec<Vec<u8>> = jar
+        .class_entries()
+        .filter_map(|entry| jar.read(entry).ok())
+        .collect();
+
+    // Some classes still trip the full parser; skip them rather than abort.
+    panic::set_hook(Box::new(|_| {}));
+    let code_bytes = |buf: &Vec<u8>| {
+        panic::catch_unwind(|| match ClassFile::parse_class_file(buf) {
+            Ok((_, class_file)) => class_file
+                .methods
+                .iter()
+                .filter_map(|method| method.code())
+                .map(|code| code.code.len())
+                .sum(),
+            Err(_) => 0,
+        })
+        .unwrap_or(0)
+    };
+    let parse_all = |jobs: usize| {
+        let mut total = 0;
+        let start = Instant::now();
+        for _ in 0..rounds {
+            total = parallel::map(&classes, jobs, code_bytes)
+                .into_iter()
+                .sum::<usize>();
+        }
+        (start.elapsed() / rounds, total)
+    };
+
+    let jobs = parallel::default_jobs();
+    let (sequential, sequential_bytes) = parse_all(1);
+    let (spread, spread_bytes) = parse_all(jobs);
+
+    println!("{} classes, {} rounds", classes.len(), rounds);
+    println!(
+        "1 thread:   {:>10.3?} ({} bytes of code)",
+        sequential, sequential_bytes
+    );
+    println!(
+        "{:<2} threads: {:>10.3?} ({} bytes of code)",
+        jobs, spread, spread_bytes
+    );
+    println!(
+        "speedup:    {:>10.1}x",
+        sequential.as_secs_f64() / spread.as_secs_f64()
+    );
+}
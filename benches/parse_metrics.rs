@@ -0,0 +1,64 @@
+//! Compares parsing every class of a jar with and without
+//! `ParseOptions::collect_metrics`, to check that collection costs little
+//! and that leaving it off costs nothing over a plain parse.
+//!
+//! cargo bench --bench parse_metrics -- <jar> [rounds]
+
+use std::{panic, time::Instant};
+
+use jvmb::{
+    classfile::{ClassFile, ParseOptions},
+    jar::JarFile,
+};
+
+fn main() {
+    let args: Vec<String> = std::env::args()
+        .skip(1)
+        .filter(|arg| !arg.starts_with("--"))
+        .collect();
+    let jar_path = match args.first() {
+        Some(path) => path,
+        None => {
+            eprintln!("usage: cargo bench --bench parse_metrics -- <jar> [rounds]");
+            return;
+        }
+    };
+    let rounds: u32 = args
+        .get(1)
+        .and_then(|rounds| rounds.parse().ok())
+        .unwrap_or(5);
+
+    let jar = JarFile::open(jar_path).unwrap();
+    let classes: Vec<Vec<u8>> = jar
+        .class_entries()
+        .filter_map(|entry| jar.read(entry).ok())
+        .collect();
+
+    // Some classes still trip the full parser; skip them rather than abort.
+    panic::set_hook(Box::new(|_| {}));
+    let parse_all = |parse: &dyn Fn(&[u8]) -> bool| {
+        let start = Instant::now();
+        for _ in 0..rounds {
+            for buf in &classes {
+                let _ = panic::catch_unwind(panic::AssertUnwindSafe(|| parse(buf)));
+            }
+        }
+        start.elapsed() / rounds
+    };
+
+    let baseline = parse_all(&|buf| ClassFile::parse_class_file(buf).is_ok());
+    let options = ParseOptions::new();
+    let off = parse_all(&|buf| ClassFile::parse_with(buf, &options).is_ok());
+    let mut collecting = ParseOptions::new();
+    collecting.collect_metrics();
+    let on = parse_all(&|buf| ClassFile::parse_with(buf, &collecting).is_ok());
+
+    println!("{} classes, {} rounds", classes.len(), rounds);
+    println!("parse_class_file:  {:>10.3?}", baseline);
+    println!("metrics off:       {:>10.3?}", off);
+    println!(
+        "metrics on:        {:>10.3?} ({:+.1}%)",
+        on,
+        (on.as_secs_f64() / off.as_secs_f64() - 1.0) * 100.0
+    );
+}
@@ -0,0 +1,70 @@
+//! Compares `ClassFile::quick_contains_utf8` against a full parse when
+//! looking for a rare symbol across every class of a jar.
+//!
+//! cargo bench --bench quick_contains -- <jar> [symbol]
+
+use std::{panic, time::Instant};
+
+use jvmb::{classfile::ClassFile, jar::JarFile};
+
+fn main() {
+    let args: Vec<String> = std::env::args()
+        .skip(1)
+        .filter(|arg| !arg.starts_with("--"))
+        .collect();
+    let jar_path = match args.first() {
+        Some(path) => path,
+        None => {
+            eprintln!("usage: cargo bench --bench quick_contains -- <jar> [symbol]");
+            return;
+        }
+    };
+    let symbol = args
+        .get(1)
+        .map_or("com/example/DoesNotExist", String::as_str);
+
+    let jar = JarFile::open(jar_path).unwrap();
+    let classes: Vec<Vec<u8>> = jar
+        .class_entries()
+        .filter_map(|entry| jar.read(entry).ok())
+        .collect();
+
+    let start = Instant::now();
+    let quick_matches = classes
+        .iter()
+        .filter(|buf| ClassFile::quick_contains_utf8(buf, symbol))
+        .count();
+    let quick = start.elapsed();
+
+    // Some classes still trip the full parser; count them rather than abort.
+    panic::set_hook(Box::new(|_| {}));
+    let start = Instant::now();
+    let mut full_failures = 0;
+    let full_matches = classes
+        .iter()
+        .filter(|buf| {
+            match panic::catch_unwind(|| {
+                ClassFile::parse_class_file(buf)
+                    .map(|(_, class_file)| class_file.references_symbol(symbol))
+            }) {
+                Ok(Ok(found)) => found,
+                _ => {
+                    full_failures += 1;
+                    false
+                }
+            }
+        })
+        .count();
+    let full = start.elapsed();
+
+    println!("{} classes, symbol {:?}", classes.len(), symbol);
+    println!("quick scan: {:>10.3?} ({} matches)", quick, quick_matches);
+    println!(
+        "full parse: {:>10.3?} ({} matches, {} failed to parse)",
+        full, full_matches, full_failures
+    );
+    println!(
+        "speedup:    {:>10.1}x",
+        full.as_secs_f64() / quick.as_secs_f64()
+    );
+}
@@ -0,0 +1,125 @@
+//! Compares running the four `jvmb report` analyses over every class of a
+//! jar through one pipeline, which parses each class once, with running
+//! each analysis on its own, which parses every class four times, and
+//! shows what leaving `Code` unread saves the analyses which don't need it.
+//!
+//! cargo bench --bench report_pipeline -- <jar> [rounds]
+
+use std::{panic, time::Instant};
+
+use jvmb::{
+    classfile::ClassFile,
+    classindex::ClassIndex,
+    jar::JarFile,
+    json::Json,
+    pipeline::{Analysis, Needs, Pipeline, Section, SectionStatus},
+    report::{Deps, Lint, Metrics, Verify},
+};
+
+fn main() {
+    let args: Vec<String> = std::env::args()
+        .skip(1)
+        .filter(|arg| !arg.starts_with("--"))
+        .collect();
+    let jar_path = match args.first() {
+        Some(path) => path,
+        None => {
+            eprintln!("usage: cargo bench --bench report_pipeline -- <jar> [rounds]");
+            return;
+        }
+    };
+    let rounds: u32 = args
+        .get(1)
+        .and_then(|rounds| rounds.parse().ok())
+        .unwrap_or(5);
+
+    let jar = JarFile::open(jar_path).unwrap();
+    let classes: Vec<Vec<u8>> = jar
+        .class_entries()
+        .filter_map(|entry| jar.read(entry).ok())
+        .collect();
+
+    // Some classes still trip the full parser; skip them rather than abort.
+    panic::set_hook(Box::new(|_| {}));
+    let run = |pipelines: &dyn Fn() -> Vec<Pipeline>| {
+        let start = Instant::now();
+        for _ in 0..rounds {
+            for mut pipeline in pipelines() {
+                let options = pipeline.parse_options();
+                for buf in &classes {
+                    let _ = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+                        if let Ok((_, class_file)) = ClassFile::parse_with(buf, &options) {
+                            pipeline.visit(jar_path, "", &class_file);
+                        }
+                    }));
+                }
+                pipeline.finish();
+            }
+        }
+        start.elapsed() / rounds
+    };
+    let deps = || Deps::new(None, ClassIndex::new());
+
+    let combined = run(&|| {
+        let mut pipeline = Pipeline::new();
+        pipeline
+            .add_check(Box::new(Verify::new()))
+            .add_check(Box::new(Lint::new()))
+            .add(Metrics::new())
+            .add(deps());
+        vec![pipeline]
+    });
+    let separate = run(&|| {
+        let mut pipelines: Vec<Pipeline> = (0..4).map(|_| Pipeline::new()).collect();
+        pipelines[0].add_check(Box::new(Verify::new()));
+        pipelines[1].add_check(Box::new(Lint::new()));
+        pipelines[2].add(Metrics::new());
+        pipelines[3].add(deps());
+        pipelines
+    });
+    let declarations = run(&|| {
+        let mut pipeline = Pipeline::new();
+        pipeline.add(Metrics::new()).add(deps());
+        vec![pipeline]
+    });
+    let declarations_with_code = run(&|| {
+        let mut pipeline = Pipeline::new();
+        pipeline.add(Metrics::new()).add(deps()).add(ReadsCode);
+        vec![pipeline]
+    });
+
+    println!("{} classes, {} rounds", classes.len(), rounds);
+    println!("all four, one pass:      {:>10.3?}", combined);
+    println!(
+        "all four, one pass each: {:>10.3?} ({:.2}x)",
+        separate,
+        separate.as_secs_f64() / combined.as_secs_f64()
+    );
+    println!("metrics and deps:        {:>10.3?}", declarations);
+    println!("  parsing code anyway:   {:>10.3?}", declarations_with_code);
+}
+
+/// Does nothing, but makes the pipeline parse method bodies.
+struct ReadsCode;
+
+impl Analysis for ReadsCode {
+    fn name(&self) -> &'static str {
+        "reads-code"
+    }
+
+    fn needs(&self) -> Needs {
+        Needs::Code
+    }
+
+    fn visit(&mut self, _: &str, _: &str, _: &ClassFile) {}
+
+    fn finish(self: Box<Self>) -> Section {
+        Section {
+            name: "reads-code",
+            summary: String::new(),
+            document: Json::Null,
+            status: SectionStatus::Complete,
+            violations: 0,
+        }
+    }
+}
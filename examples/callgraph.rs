@@ -0,0 +1,65 @@
+//! Prints the calls made by the methods of class files and jars as a
+//! Graphviz graph.
+//!
+//! cargo run --example callgraph -- <class or jar>... | dot -Tsvg > calls.svg
+
+use std::{collections::BTreeSet, error::Error};
+
+use jvmb::{classfile::ClassFile, constantpool::ConstantPool, instruction, jar::JarFile};
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut edges = BTreeSet::new();
+    for path in std::env::args().skip(1) {
+        if path.ends_with(".jar") {
+            let jar = JarFile::open(&path)?;
+            for entry in jar.class_entries() {
+                collect_calls(&jar.read(entry)?, &mut edges)?;
+            }
+        } else {
+            collect_calls(&std::fs::read(&path)?, &mut edges)?;
+        }
+    }
+
+    println!("digraph calls {{");
+    println!("  node [shape=box];");
+    for (caller, callee) in edges {
+        println!("  {:?} -> {:?};", caller, callee);
+    }
+    println!("}}");
+    Ok(())
+}
+
+fn collect_calls(buf: &[u8], edges: &mut BTreeSet<(String, String)>) -> Result<(), Box<dyn Error>> {
+    let (_, class_file) =
+        ClassFile::parse_class_file(buf).map_err(|e| format!("{:?}", e.map(|e| e.code)))?;
+    let pool = &class_file.constant_pool;
+    let class_name = ConstantPool::class_name(pool, class_file.this_class).unwrap_or("?");
+
+    for method in &class_file.methods {
+        let code = match method.code() {
+            Some(code) => code,
+            None => continue,
+        };
+        let caller = format!(
+            "{}.{}",
+            class_name,
+            ConstantPool::utf8(pool, method.name_index()).unwrap_or("?")
+        );
+        for instruction in instruction::decode(&code.code) {
+            let instruction = instruction?;
+            if !instruction.is_invoke() {
+                continue;
+            }
+            let callee = instruction
+                .cp_index()
+                .and_then(|index| ConstantPool::member_ref(pool, index));
+            if let Some(callee) = callee {
+                edges.insert((
+                    caller.clone(),
+                    format!("{}.{}", callee.class_name, callee.name),
+                ));
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,63 @@
+//! A `jvmb` with a check of its own: public fields which aren't final,
+//! reported by `report` as the section `public-fields`, with the code
+//! X0001, alongside the built-in sections.
+//!
+//! cargo run --example custom_check -- report [--severity X0001=error] <jar>
+
+use std::process::ExitCode;
+
+use jvmb::{
+    cli,
+    constantpool::ConstantPool,
+    pipeline::{Check, ClassContext, Finding, Needs, Registry, ANALYSIS_API_VERSION},
+    rdjson::Severity,
+};
+
+// Fails to build once the check API changes, rather than misbehaving.
+const _: () = assert!(ANALYSIS_API_VERSION == 1);
+
+struct PublicFields;
+
+impl Check for PublicFields {
+    fn name(&self) -> &'static str {
+        "public-fields"
+    }
+
+    fn needs(&self) -> Needs {
+        Needs::Declarations
+    }
+
+    fn check(&mut self, class: &ClassContext) -> Vec<Finding> {
+        let constant_pool = &class.class_file.constant_pool;
+        class
+            .class_file
+            .fields
+            .iter()
+            .filter(|field| {
+                let flags = field.access_flags();
+                flags.is_public() && !flags.is_final()
+            })
+            .map(|field| {
+                let name = ConstantPool::utf8(constant_pool, field.name_index()).unwrap_or("?");
+                Finding {
+                    code: "X0001".to_string(),
+                    severity: Severity::Warning,
+                    location: format!("field {}", name),
+                    message: format!("{} is public but not final", name),
+                }
+            })
+            .collect()
+    }
+}
+
+fn main() -> ExitCode {
+    let mut registry = Registry::new();
+    registry.register("public-fields", || Box::new(PublicFields));
+    match cli::run_with(std::env::args().skip(1).collect(), &registry) {
+        Ok(code) => code.into(),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            cli::ExitCode::of(e.as_ref()).into()
+        }
+    }
+}
@@ -0,0 +1,49 @@
+//! Lists the classes of a jar whose string literals contain some text.
+//!
+//! cargo run --example find_string -- <jar> <text>
+
+use std::error::Error;
+
+use jvmb::{classfile::ClassFile, constantpool::ConstantPool, jar::JarFile};
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut args = std::env::args().skip(1);
+    let (jar_path, text) = match (args.next(), args.next()) {
+        (Some(jar_path), Some(text)) => (jar_path, text),
+        _ => return Err("usage: find_string <jar> <text>".into()),
+    };
+
+    let jar = JarFile::open(&jar_path)?;
+    for entry in jar.class_entries() {
+        let buf = jar.read(entry)?;
+        // Strings are stored as is, so classes without the bytes can be
+        // skipped without parsing them.
+        if !contains_substring(&buf, &text) {
+            continue;
+        }
+        let class_file = match ClassFile::parse_class_file(&buf) {
+            Ok((_, class_file)) => class_file,
+            Err(e) => {
+                eprintln!("{}: {:?}", entry.name, e.map(|e| e.code));
+                continue;
+            }
+        };
+        let pool = &class_file.constant_pool;
+        for constant in pool {
+            if let ConstantPool::String(index) = constant {
+                match ConstantPool::utf8(pool, *index) {
+                    Some(value) if value.contains(&text) => {
+                        println!("{}: {:?}", entry.name, value)
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn contains_substring(buf: &[u8], text: &str) -> bool {
+    buf.windows(text.len().max(1))
+        .any(|window| window == text.as_bytes())
+}
@@ -0,0 +1,49 @@
+//! Prints the signature of every method of the given class files, with the
+//! generic types of their `Signature` attributes.
+//!
+//! cargo run --example list_methods -- <class>...
+
+use std::error::Error;
+
+use jvmb::{
+    accessflags::{
+        MethodAccessFlags, ACC_ABSTRACT, ACC_FINAL, ACC_PRIVATE, ACC_PROTECTED, ACC_PUBLIC,
+        ACC_STATIC,
+    },
+    classfile::ClassFile,
+    signature,
+};
+
+fn main() -> Result<(), Box<dyn Error>> {
+    for path in std::env::args().skip(1) {
+        let buf = std::fs::read(&path)?;
+        let (_, class_file) = ClassFile::parse_class_file(&buf)
+            .map_err(|e| format!("{}: {:?}", path, e.map(|e| e.code)))?;
+        println!("{}:", signature::best_class_signature(&class_file));
+
+        for method in &class_file.methods {
+            let best = signature::best_method_signature(&class_file, method)
+                .ok_or_else(|| format!("{}: invalid method descriptor", path))?;
+            match best.name.as_str() {
+                "<clinit>" => println!("  {}", best),
+                _ => println!("  {}{}", modifiers(method.access_flags()), best),
+            }
+        }
+    }
+    Ok(())
+}
+
+fn modifiers(access_flags: MethodAccessFlags) -> String {
+    [
+        (ACC_PUBLIC, "public "),
+        (ACC_PROTECTED, "protected "),
+        (ACC_PRIVATE, "private "),
+        (ACC_STATIC, "static "),
+        (ACC_FINAL, "final "),
+        (ACC_ABSTRACT, "abstract "),
+    ]
+    .iter()
+    .filter(|&&(flag, _)| access_flags.contains(flag))
+    .map(|(_, modifier)| *modifier)
+    .collect()
+}
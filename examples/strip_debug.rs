@@ -0,0 +1,26 @@
+//! Removes debugging attributes from a class and writes the result.
+//!
+//! cargo run --example strip_debug -- <in.class> <out.class>
+
+use std::error::Error;
+
+use jvmb::classfile::ClassFile;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut args = std::env::args().skip(1);
+    let (input, output) = match (args.next(), args.next()) {
+        (Some(input), Some(output)) => (input, output),
+        _ => return Err("usage: strip_debug <in.class> <out.class>".into()),
+    };
+
+    let buf = std::fs::read(&input)?;
+    let (_, mut class_file) = ClassFile::parse_class_file(&buf)
+        .map_err(|e| format!("{}: {:?}", input, e.map(|e| e.code)))?;
+
+    class_file.strip_debug_info();
+
+    let out = class_file.to_bytes()?;
+    println!("{}: {} -> {} bytes", output, buf.len(), out.len());
+    std::fs::write(&output, out)?;
+    Ok(())
+}
@@ -0,0 +1,253 @@
+//! The `ACC_*` access flags, and typed sets of them for each kind of
+//! element that has some. Several flags share a bit, e.g. `ACC_SUPER` of a
+//! class and `ACC_SYNCHRONIZED` of a method, so each set names its bits as
+//! its kind of element means them. Bits a set doesn't know are kept as they
+//! are, for flags of later class file versions.
+
+use crate::prelude::*;
+use core::fmt;
+
+pub const ACC_PUBLIC: u16 = 0x0001;
+pub const ACC_PRIVATE: u16 = 0x0002;
+pub const ACC_PROTECTED: u16 = 0x0004;
+pub const ACC_STATIC: u16 = 0x0008;
+pub const ACC_FINAL: u16 = 0x0010;
+pub const ACC_SUPER: u16 = 0x0020;
+pub const ACC_SYNCHRONIZED: u16 = 0x0020;
+pub const ACC_OPEN: u16 = 0x0020;
+pub const ACC_TRANSITIVE: u16 = 0x0020;
+pub const ACC_VOLATILE: u16 = 0x0040;
+pub const ACC_BRIDGE: u16 = 0x0040;
+pub const ACC_STATIC_PHASE: u16 = 0x0040;
+pub const ACC_TRANSIENT: u16 = 0x0080;
+pub const ACC_VARARGS: u16 = 0x0080;
+pub const ACC_NATIVE: u16 = 0x0100;
+pub const ACC_INTERFACE: u16 = 0x0200;
+pub const ACC_ABSTRACT: u16 = 0x0400;
+pub const ACC_STRICT: u16 = 0x0800;
+pub const ACC_SYNTHETIC: u16 = 0x1000;
+pub const ACC_ANNOTATION: u16 = 0x2000;
+pub const ACC_ENUM: u16 = 0x4000;
+pub const ACC_MODULE: u16 = 0x8000;
+pub const ACC_MANDATED: u16 = 0x8000;
+
+/// A typed set of access flags, for code which handles those of more than
+/// one kind of element.
+pub trait AccessFlags: Copy {
+    fn from_bits(bits: u16) -> Self;
+
+    /// The flags as the class file holds them, unknown bits included.
+    fn bits(self) -> u16;
+
+    /// Sets the bits of `flags`, or clears them.
+    fn set(&mut self, flags: u16, value: bool) {
+        *self = if value {
+            Self::from_bits(self.bits() | flags)
+        } else {
+            Self::from_bits(self.bits() & !flags)
+        };
+    }
+}
+
+macro_rules! access_flags {
+    (
+        $(#[$doc:meta])*
+        $name:ident { $($flag:ident => $predicate:ident,)* }
+    ) => {
+        $(#[$doc])*
+        #[derive(Clone, Copy, Default, PartialEq, Eq, Hash)]
+        #[cfg_attr(
+            feature = "serde",
+            derive(serde::Serialize, serde::Deserialize),
+            serde(transparent)
+        )]
+        pub struct $name(u16);
+
+        impl $name {
+            /// Every flag with a name, and its bit.
+            pub const NAMED: &'static [(&'static str, u16)] =
+                &[$((stringify!($flag), $flag),)*];
+
+            pub const fn new(bits: u16) -> Self {
+                $name(bits)
+            }
+
+            /// The flags as the class file holds them, unknown bits
+            /// included.
+            pub const fn bits(self) -> u16 {
+                self.0
+            }
+
+            /// Whether every bit of `flags` is set.
+            pub fn contains(self, flags: u16) -> bool {
+                self.0 & flags == flags
+            }
+
+            /// Whether any bit of `flags` is set.
+            pub fn intersects(self, flags: u16) -> bool {
+                self.0 & flags != 0
+            }
+
+            /// The names of the flags set, e.g. `ACC_PUBLIC`, in the order
+            /// of their bits.
+            pub fn names(self) -> Vec<&'static str> {
+                Self::NAMED
+                    .iter()
+                    .filter(|&&(_, flag)| self.0 & flag != 0)
+                    .map(|&(name, _)| name)
+                    .collect()
+            }
+
+            /// The bits set which have no name for this kind of element.
+            pub fn unknown(self) -> u16 {
+                Self::NAMED
+                    .iter()
+                    .fold(self.0, |bits, &(_, flag)| bits & !flag)
+            }
+
+            $(
+                #[doc = concat!("Whether `", stringify!($flag), "` is set.")]
+                pub fn $predicate(self) -> bool {
+                    self.0 & $flag != 0
+                }
+            )*
+        }
+
+        impl AccessFlags for $name {
+            fn from_bits(bits: u16) -> Self {
+                $name(bits)
+            }
+
+            fn bits(self) -> u16 {
+                self.0
+            }
+        }
+
+        impl From<u16> for $name {
+            fn from(bits: u16) -> Self {
+                $name(bits)
+            }
+        }
+
+        impl From<$name> for u16 {
+            fn from(flags: $name) -> Self {
+                flags.0
+            }
+        }
+
+        impl fmt::Display for $name {
+            /// The names of the flags set, then any unknown bits in hex,
+            /// e.g. `ACC_PUBLIC, ACC_FINAL, 0x0100`.
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write_flags(f, self.names(), self.unknown())
+            }
+        }
+
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "{}({:#06x}", stringify!($name), self.0)?;
+                if self.0 != 0 {
+                    f.write_str(": ")?;
+                    write_flags(f, self.names(), self.unknown())?;
+                }
+                f.write_str(")")
+            }
+        }
+    };
+}
+
+access_flags! {
+    /// The flags of a class, or of a nested class in `InnerClasses`, which
+    /// may also be private, protected or static.
+    ClassAccessFlags {
+        ACC_PUBLIC => is_public,
+        ACC_PRIVATE => is_private,
+        ACC_PROTECTED => is_protected,
+        ACC_STATIC => is_static,
+        ACC_FINAL => is_final,
+        ACC_SUPER => is_super,
+        ACC_INTERFACE => is_interface,
+        ACC_ABSTRACT => is_abstract,
+        ACC_SYNTHETIC => is_synthetic,
+        ACC_ANNOTATION => is_annotation,
+        ACC_ENUM => is_enum,
+        ACC_MODULE => is_module,
+    }
+}
+
+access_flags! {
+    /// The flags of a field.
+    FieldAccessFlags {
+        ACC_PUBLIC => is_public,
+        ACC_PRIVATE => is_private,
+        ACC_PROTECTED => is_protected,
+        ACC_STATIC => is_static,
+        ACC_FINAL => is_final,
+        ACC_VOLATILE => is_volatile,
+        ACC_TRANSIENT => is_transient,
+        ACC_SYNTHETIC => is_synthetic,
+        ACC_ENUM => is_enum,
+    }
+}
+
+access_flags! {
+    /// The flags of a method.
+    MethodAccessFlags {
+        ACC_PUBLIC => is_public,
+        ACC_PRIVATE => is_private,
+        ACC_PROTECTED => is_protected,
+        ACC_STATIC => is_static,
+        ACC_FINAL => is_final,
+        ACC_SYNCHRONIZED => is_synchronized,
+        ACC_BRIDGE => is_bridge,
+        ACC_VARARGS => is_varargs,
+        ACC_NATIVE => is_native,
+        ACC_ABSTRACT => is_abstract,
+        ACC_STRICT => is_strict,
+        ACC_SYNTHETIC => is_synthetic,
+    }
+}
+
+access_flags! {
+    /// The flags of a parameter in `MethodParameters`.
+    ParameterAccessFlags {
+        ACC_FINAL => is_final,
+        ACC_SYNTHETIC => is_synthetic,
+        ACC_MANDATED => is_mandated,
+    }
+}
+
+access_flags! {
+    /// The flags of a module in its `Module` attribute.
+    ModuleAccessFlags {
+        ACC_OPEN => is_open,
+        ACC_SYNTHETIC => is_synthetic,
+        ACC_MANDATED => is_mandated,
+    }
+}
+
+access_flags! {
+    /// The flags of a `requires` directive of a module.
+    RequiresAccessFlags {
+        ACC_TRANSITIVE => is_transitive,
+        ACC_STATIC_PHASE => is_static_phase,
+        ACC_SYNTHETIC => is_synthetic,
+        ACC_MANDATED => is_mandated,
+    }
+}
+
+access_flags! {
+    /// The flags of an `exports` or `opens` directive of a module.
+    ExportsAccessFlags {
+        ACC_SYNTHETIC => is_synthetic,
+        ACC_MANDATED => is_mandated,
+    }
+}
+
+fn write_flags(f: &mut fmt::Formatter<'_>, names: Vec<&str>, unknown: u16) -> fmt::Result {
+    let mut parts: Vec<String> = names.into_iter().map(str::to_string).collect();
+    if unknown != 0 {
+        parts.push(format!("{:#06x}", unknown));
+    }
+    f.write_str(&parts.join(", "))
+}
@@ -0,0 +1,296 @@
+//! How often each annotation type is used across classes, by the kind of
+//! element carrying it, and how the values of chosen members are spread.
+//!
+//! Annotations held in the `value` array of another, the way javac stores
+//! repeated annotations in their container, count as carried by the element
+//! as well. Type annotations and those of record components (which javac
+//! copies onto the field and accessor) aren't counted.
+
+use alloc::collections::{BTreeMap, BTreeSet};
+
+use crate::prelude::*;
+use crate::{
+    attribute::{Annotation, Attribute, ElementValue},
+    classfile::ClassFile,
+    constantpool::ConstantPool,
+    descriptor::FieldType,
+};
+
+/// The value counted for a member an annotation leaves out, whose value is
+/// the default its type declares.
+pub const DEFAULT_VALUE: &str = "(default)";
+/// The value counted for a member whose value is an empty array.
+pub const EMPTY_ARRAY: &str = "(empty)";
+
+/// The uses of one annotation type.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct AnnotationUsage {
+    /// Classes carrying the annotation, once however often they do.
+    pub classes: usize,
+    pub fields: usize,
+    pub methods: usize,
+    /// Method parameters carrying the annotation.
+    pub parameters: usize,
+    /// Every use, counting each repetition.
+    pub uses: usize,
+}
+
+/// How many uses of an annotation type give one of its members each value.
+/// Every element of an array value is counted on its own.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Histogram {
+    /// The descriptor of the annotation type, e.g.
+    /// `Lorg/springframework/web/bind/annotation/RequestMapping;`.
+    pub annotation: String,
+    pub member: String,
+    /// Values as written in Java source, e.g. `RequestMethod.GET`, with
+    /// [`DEFAULT_VALUE`] and [`EMPTY_ARRAY`] for what has none.
+    pub values: BTreeMap<String, usize>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Element {
+    Class,
+    Field,
+    Method,
+    Parameter,
+}
+
+/// The usage of every annotation type among the classes added, by
+/// descriptor, and the histograms asked for.
+#[derive(Debug, Default, Clone)]
+pub struct AnnotationStats {
+    usage: BTreeMap<String, AnnotationUsage>,
+    histograms: Vec<Histogram>,
+}
+
+impl AnnotationStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Counts the values of `member` of the annotation type with the
+    /// descriptor `annotation` in the classes added from now on.
+    pub fn histogram(&mut self, annotation: &str, member: &str) {
+        self.histograms.push(Histogram {
+            annotation: annotation.to_string(),
+            member: member.to_string(),
+            values: BTreeMap::new(),
+        });
+    }
+
+    pub fn add_class(&mut self, class_file: &ClassFile) {
+        let constant_pool = &class_file.constant_pool;
+        self.add_element(
+            Element::Class,
+            annotations(&class_file.attributes),
+            constant_pool,
+        );
+        for field in &class_file.fields {
+            self.add_element(
+                Element::Field,
+                annotations(field.attributes()),
+                constant_pool,
+            );
+        }
+        for method in &class_file.methods {
+            self.add_element(
+                Element::Method,
+                annotations(method.attributes()),
+                constant_pool,
+            );
+            // a parameter's annotations may be split between the visible
+            // and invisible attribute
+            let mut parameters: Vec<Vec<&Annotation>> = Vec::new();
+            for attribute in method.attributes() {
+                if let Attribute::RuntimeVisibleParameterAnnotations(annotations)
+                | Attribute::RuntimeInvisibleParameterAnnotations(annotations) = attribute
+                {
+                    if parameters.len() < annotations.len() {
+                        parameters.resize(annotations.len(), Vec::new());
+                    }
+                    for (i, parameter) in annotations.iter().enumerate() {
+                        parameters[i].extend(&parameter.annotations);
+                    }
+                }
+            }
+            for parameter in parameters {
+                self.add_element(Element::Parameter, parameter, constant_pool);
+            }
+        }
+    }
+
+    /// The usage of every annotation type seen, by descriptor.
+    pub fn usage(&self) -> &BTreeMap<String, AnnotationUsage> {
+        &self.usage
+    }
+
+    /// The histograms, in the order they were asked for.
+    pub fn histograms(&self) -> &[Histogram] {
+        &self.histograms
+    }
+
+    fn add_element(
+        &mut self,
+        element: Element,
+        annotations: Vec<&Annotation>,
+        constant_pool: &[ConstantPool],
+    ) {
+        let mut uses = Vec::new();
+        for annotation in annotations {
+            collect_uses(annotation, constant_pool, &mut uses);
+        }
+        let mut carried = BTreeSet::new();
+        for (descriptor, annotation) in uses {
+            let usage = self.usage.entry(descriptor.to_string()).or_default();
+            usage.uses += 1;
+            if carried.insert(descriptor) {
+                match element {
+                    Element::Class => usage.classes += 1,
+                    Element::Field => usage.fields += 1,
+                    Element::Method => usage.methods += 1,
+                    Element::Parameter => usage.parameters += 1,
+                }
+            }
+            for histogram in &mut self.histograms {
+                if histogram.annotation == descriptor {
+                    count_values(histogram, annotation, constant_pool);
+                }
+            }
+        }
+    }
+}
+
+/// The annotations, visible or not, among `attributes`.
+fn annotations(attributes: &[Attribute]) -> Vec<&Annotation> {
+    attributes
+        .iter()
+        .filter_map(|attribute| match attribute {
+            Attribute::RuntimeVisibleAnnotations(annotations)
+            | Attribute::RuntimeInvisibleAnnotations(annotations) => Some(annotations),
+            _ => None,
+        })
+        .flatten()
+        .collect()
+}
+
+/// Adds `annotation` to `uses` with the descriptor of its type, and the
+/// annotations its `value` holds if it's a container of them.
+fn collect_uses<'a>(
+    annotation: &'a Annotation,
+    constant_pool: &'a [ConstantPool],
+    uses: &mut Vec<(&'a str, &'a Annotation)>,
+) {
+    let descriptor = match ConstantPool::utf8(constant_pool, annotation.type_index) {
+        Some(descriptor) => descriptor,
+        None => return,
+    };
+    uses.push((descriptor, annotation));
+    if let [(name_index, ElementValue::ArrayValue(values))] =
+        annotation.element_value_pairs.as_slice()
+    {
+        if ConstantPool::utf8(constant_pool, *name_index) != Some("value") {
+            return;
+        }
+        for value in values {
+            if let ElementValue::AnnotationValue(contained) = value {
+                collect_uses(contained, constant_pool, uses);
+            }
+        }
+    }
+}
+
+fn count_values(
+    histogram: &mut Histogram,
+    annotation: &Annotation,
+    constant_pool: &[ConstantPool],
+) {
+    let value = annotation
+        .element_value_pairs
+        .iter()
+        .find(|(name_index, _)| {
+            ConstantPool::utf8(constant_pool, *name_index) == Some(histogram.member.as_str())
+        })
+        .map(|(_, value)| value);
+    let mut count = |value: String| *histogram.values.entry(value).or_default() += 1;
+    match value {
+        None => count(DEFAULT_VALUE.to_string()),
+        Some(ElementValue::ArrayValue(values)) if values.is_empty() => {
+            count(EMPTY_ARRAY.to_string())
+        }
+        Some(ElementValue::ArrayValue(values)) => {
+            for value in values {
+                count(source_value(value, constant_pool));
+            }
+        }
+        Some(value) => count(source_value(value, constant_pool)),
+    }
+}
+
+/// An element value as written in Java source, with enum constants and
+/// annotations by the simple name of their type.
+fn source_value(value: &ElementValue, constant_pool: &[ConstantPool]) -> String {
+    let utf8 = |index| ConstantPool::utf8(constant_pool, index).unwrap_or("?");
+    match value {
+        ElementValue::ConstValue {
+            tag,
+            const_value_index: index,
+        } => {
+            let constant = (*index as usize)
+                .checked_sub(1)
+                .and_then(|i| constant_pool.get(i));
+            let int = match constant {
+                Some(ConstantPool::Integer(value)) => *value,
+                _ => 0,
+            };
+            match (tag, constant) {
+                (b'Z', _) => (int != 0).to_string(),
+                (b'C', _) => match char::from_u32(int as u32) {
+                    Some(c) => format!("{:?}", c),
+                    None => int.to_string(),
+                },
+                (b'J', Some(ConstantPool::Long(value))) => format!("{}L", value),
+                (b'F', Some(ConstantPool::Float(bits))) => format!("{}f", bits.value()),
+                (b'D', Some(ConstantPool::Double(bits))) => bits.value().to_string(),
+                (b's', _) => format!("{:?}", utf8(*index)),
+                _ => int.to_string(),
+            }
+        }
+        ElementValue::EnumConstValue {
+            type_name_index,
+            const_name_index,
+        } => format!(
+            "{}.{}",
+            simple_name(utf8(*type_name_index)),
+            utf8(*const_name_index)
+        ),
+        ElementValue::ClassInfoIndex(index) => {
+            let descriptor = utf8(*index);
+            match FieldType::parse(descriptor) {
+                Ok(field_type) => format!("{}.class", field_type),
+                Err(_) if descriptor == "V" => "void.class".to_string(),
+                Err(_) => format!("{}.class", descriptor),
+            }
+        }
+        ElementValue::AnnotationValue(annotation) => {
+            format!("@{}", simple_name(utf8(annotation.type_index)))
+        }
+        ElementValue::ArrayValue(values) => {
+            let values: Vec<String> = values
+                .iter()
+                .map(|value| source_value(value, constant_pool))
+                .collect();
+            format!("{{{}}}", values.join(", "))
+        }
+    }
+}
+
+/// The simple name of the class with the descriptor `descriptor`, nested
+/// classes by their own name.
+fn simple_name(descriptor: &str) -> &str {
+    let name = descriptor
+        .strip_prefix('L')
+        .and_then(|name| name.strip_suffix(';'))
+        .unwrap_or(descriptor);
+    name.rsplit(['/', '$']).next().unwrap_or(name)
+}
@@ -0,0 +1,360 @@
+//! A check of a jar's zip structure itself, below the classes in it:
+//! entries whose contents don't match the CRC or size the central directory
+//! declares for them, names the central directory lists twice, local
+//! headers which disagree with the central directory, and entries whose
+//! contents overlap or which only a local header declares.
+//!
+//! Readers disagree on which of two copies of an entry they take: the JVM
+//! and most zip libraries go by the central directory, while streaming
+//! readers go by the local headers, first to last. An archive built to be
+//! read differently by the two, to slip a class past a scanner, shows up as
+//! one of these findings.
+
+use std::{collections::BTreeMap, fmt};
+
+use crate::jar::{
+    crc32, decompress, read_central_directory, JarEntry, JarError, LocalHeader,
+    LOCAL_HEADER_SIGNATURE,
+};
+
+/// A disagreement within a jar's zip structure.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArchiveFinding {
+    /// The contents of a class entry don't have the CRC-32 the central
+    /// directory declares.
+    CrcMismatch {
+        entry: String,
+        declared: u32,
+        actual: u32,
+    },
+    /// The contents of a class entry don't inflate to the size the central
+    /// directory declares.
+    SizeMismatch {
+        entry: String,
+        declared: u32,
+        actual: usize,
+    },
+    /// The contents of a class entry can't be read at all.
+    Unreadable { entry: String, reason: String },
+    /// The central directory lists `entry` more than once, at each of
+    /// `offsets`.
+    DuplicateEntry { entry: String, offsets: Vec<u32> },
+    /// The local header of an entry says otherwise than the central
+    /// directory about `field`.
+    HeaderMismatch {
+        entry: String,
+        field: &'static str,
+        central: String,
+        local: String,
+    },
+    /// No local header is where the central directory says the entry
+    /// starts.
+    MissingLocalHeader { entry: String, offset: u32 },
+    /// The contents of two entries share bytes.
+    Overlap { first: String, second: String },
+    /// A local header which no entry of the central directory points to.
+    UnlistedEntry { name: String, offset: usize },
+}
+
+impl ArchiveFinding {
+    /// The stable code of the finding, e.g. `V0501`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ArchiveFinding::CrcMismatch { .. } => "V0501",
+            ArchiveFinding::SizeMismatch { .. } => "V0502",
+            ArchiveFinding::Unreadable { .. } => "V0503",
+            ArchiveFinding::DuplicateEntry { .. } => "V0504",
+            ArchiveFinding::HeaderMismatch { .. } => "V0505",
+            ArchiveFinding::MissingLocalHeader { .. } => "V0506",
+            ArchiveFinding::Overlap { .. } => "V0507",
+            ArchiveFinding::UnlistedEntry { .. } => "V0508",
+        }
+    }
+
+    /// The entry the finding is about, as the central directory names it,
+    /// or the local header for an unlisted entry.
+    pub fn entry(&self) -> &str {
+        match self {
+            ArchiveFinding::CrcMismatch { entry, .. }
+            | ArchiveFinding::SizeMismatch { entry, .. }
+            | ArchiveFinding::Unreadable { entry, .. }
+            | ArchiveFinding::DuplicateEntry { entry, .. }
+            | ArchiveFinding::HeaderMismatch { entry, .. }
+            | ArchiveFinding::MissingLocalHeader { entry, .. } => entry,
+            ArchiveFinding::Overlap { second, .. } => second,
+            ArchiveFinding::UnlistedEntry { name, .. } => name,
+        }
+    }
+
+    /// Why the finding matters, in a sentence or two.
+    pub fn explanation(&self) -> &'static str {
+        match self {
+            ArchiveFinding::CrcMismatch { .. } | ArchiveFinding::SizeMismatch { .. } => {
+                "The JVM fails to load the class with a ZipException, and the contents may have \
+                 been altered since the jar was built."
+            }
+            ArchiveFinding::Unreadable { .. } => {
+                "The JVM fails to load the class, and a scanner skipping it sees nothing of it."
+            }
+            ArchiveFinding::DuplicateEntry { .. } => {
+                "Readers pick different copies of a duplicated entry, so the class a scanner \
+                 checks may not be the one the JVM loads."
+            }
+            ArchiveFinding::HeaderMismatch { .. } => {
+                "Streaming readers go by the local header and the JVM by the central directory, \
+                 so the two may see different contents under the name."
+            }
+            ArchiveFinding::MissingLocalHeader { .. } => {
+                "The entry can't be read where the central directory says it is."
+            }
+            ArchiveFinding::Overlap { .. } => {
+                "Entries sharing contents are a sign of a crafted archive, such as a zip bomb \
+                 or one hiding an entry inside another."
+            }
+            ArchiveFinding::UnlistedEntry { .. } => {
+                "Streaming readers see an entry which the JVM, reading the central directory, \
+                 never loads, or the other way round for the entries it hides."
+            }
+        }
+    }
+}
+
+impl fmt::Display for ArchiveFinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArchiveFinding::CrcMismatch {
+                entry,
+                declared,
+                actual,
+            } => write!(
+                f,
+                "{}: CRC-32 is {:08x}, but the central directory declares {:08x}",
+                entry, actual, declared
+            ),
+            ArchiveFinding::SizeMismatch {
+                entry,
+                declared,
+                actual,
+            } => write!(
+                f,
+                "{}: {} bytes inflated, but the central directory declares {}",
+                entry, actual, declared
+            ),
+            ArchiveFinding::Unreadable { entry, reason } => write!(f, "{}: {}", entry, reason),
+            ArchiveFinding::DuplicateEntry { entry, offsets } => {
+                let offsets: Vec<String> = offsets.iter().map(u32::to_string).collect();
+                write!(
+                    f,
+                    "{}: listed {} times, at offsets {}",
+                    entry,
+                    offsets.len(),
+                    offsets.join(", ")
+                )
+            }
+            ArchiveFinding::HeaderMismatch {
+                entry,
+                field,
+                central,
+                local,
+            } => write!(
+                f,
+                "{}: the local header has {} {}, the central directory {}",
+                entry, field, local, central
+            ),
+            ArchiveFinding::MissingLocalHeader { entry, offset } => {
+                write!(f, "{}: no local header at offset {}", entry, offset)
+            }
+            ArchiveFinding::Overlap { first, second } => {
+                write!(f, "{}: its contents overlap those of {}", second, first)
+            }
+            ArchiveFinding::UnlistedEntry { name, offset } => write!(
+                f,
+                "{}: a local header at offset {} which the central directory doesn't list",
+                name, offset
+            ),
+        }
+    }
+}
+
+/// Checks the zip structure of the archive `data` against itself, reading
+/// both its central directory and its local headers, and inflating every
+/// class entry to check its CRC and size. Fails only when there is no
+/// central directory to check against.
+pub fn check_archive(data: &[u8]) -> Result<Vec<ArchiveFinding>, JarError> {
+    let (entries, directory_offset) = read_central_directory(data)?;
+    let mut findings = Vec::new();
+
+    let mut by_name: BTreeMap<&str, Vec<u32>> = BTreeMap::new();
+    for entry in &entries {
+        by_name
+            .entry(&entry.name)
+            .or_default()
+            .push(entry.local_header_offset);
+    }
+    for (name, offsets) in by_name {
+        if offsets.len() > 1 {
+            findings.push(ArchiveFinding::DuplicateEntry {
+                entry: name.to_string(),
+                offsets,
+            });
+        }
+    }
+
+    // The bytes each entry takes up, from its local header to the end of its
+    // contents.
+    let mut extents = Vec::new();
+    for entry in &entries {
+        let offset = entry.local_header_offset;
+        let header = data
+            .get(offset as usize..)
+            .and_then(|buf| LocalHeader::parse(buf).ok())
+            .map(|(_, header)| header);
+        let header = match header {
+            Some(header) => header,
+            None => {
+                findings.push(ArchiveFinding::MissingLocalHeader {
+                    entry: entry.name.clone(),
+                    offset,
+                });
+                continue;
+            }
+        };
+        compare_headers(entry, &header, &mut findings);
+        let start = offset as usize;
+        let end = start + header.len + entry.compressed_size as usize;
+        extents.push((start, end, entry));
+
+        if entry.is_class() && !entry.is_dir() {
+            check_contents(entry, data.get(start + header.len..end), &mut findings);
+        }
+    }
+
+    extents.sort_by_key(|&(start, end, _)| (start, end));
+    let mut covered = 0;
+    let mut furthest: Option<&JarEntry> = None;
+    for &(start, end, entry) in &extents {
+        match furthest {
+            Some(first) if start < covered => findings.push(ArchiveFinding::Overlap {
+                first: first.name.clone(),
+                second: entry.name.clone(),
+            }),
+            _ => unlisted_entries(data, covered, start, &mut findings),
+        }
+        if end > covered {
+            covered = end;
+            furthest = Some(entry);
+        }
+    }
+    unlisted_entries(data, covered, directory_offset as usize, &mut findings);
+
+    Ok(findings)
+}
+
+/// Compares the local `header` of `entry` with what the central directory
+/// says of it. The CRC and sizes are only compared when they are in the
+/// header rather than a data descriptor after the contents.
+fn compare_headers(entry: &JarEntry, header: &LocalHeader, findings: &mut Vec<ArchiveFinding>) {
+    let mut mismatch = |field, central: String, local: String| {
+        if central != local {
+            findings.push(ArchiveFinding::HeaderMismatch {
+                entry: entry.name.clone(),
+                field,
+                central,
+                local,
+            });
+        }
+    };
+    mismatch(
+        "name",
+        format!("{:?}", entry.name),
+        format!("{:?}", header.name),
+    );
+    mismatch(
+        "compression method",
+        entry.method.to_string(),
+        header.method.to_string(),
+    );
+    if !header.has_data_descriptor() {
+        mismatch(
+            "CRC-32",
+            format!("{:08x}", entry.crc32),
+            format!("{:08x}", header.crc32),
+        );
+        mismatch(
+            "compressed size",
+            entry.compressed_size.to_string(),
+            header.compressed_size.to_string(),
+        );
+        mismatch(
+            "size",
+            entry.uncompressed_size.to_string(),
+            header.uncompressed_size.to_string(),
+        );
+    }
+}
+
+/// Inflates the `raw` contents of `entry` and checks them against its CRC
+/// and size.
+fn check_contents(entry: &JarEntry, raw: Option<&[u8]>, findings: &mut Vec<ArchiveFinding>) {
+    let contents = match raw {
+        Some(raw) => decompress(entry, raw).map_err(|e| e.to_string()),
+        None => Err("contents past the end of the archive".to_string()),
+    };
+    let contents = match contents {
+        Ok(contents) => contents,
+        Err(reason) => {
+            findings.push(ArchiveFinding::Unreadable {
+                entry: entry.name.clone(),
+                reason,
+            });
+            return;
+        }
+    };
+    if contents.len() != entry.uncompressed_size as usize {
+        findings.push(ArchiveFinding::SizeMismatch {
+            entry: entry.name.clone(),
+            declared: entry.uncompressed_size,
+            actual: contents.len(),
+        });
+    }
+    let actual = crc32(&contents);
+    if actual != entry.crc32 {
+        findings.push(ArchiveFinding::CrcMismatch {
+            entry: entry.name.clone(),
+            declared: entry.crc32,
+            actual,
+        });
+    }
+}
+
+/// Reports the local headers in `data[start..end]`, a stretch no entry of
+/// the central directory takes up.
+fn unlisted_entries(data: &[u8], start: usize, end: usize, findings: &mut Vec<ArchiveFinding>) {
+    let gap = match data.get(start..end) {
+        Some(gap) => gap,
+        None => return,
+    };
+    let signature = LOCAL_HEADER_SIGNATURE.to_le_bytes();
+    let mut i = 0;
+    while i + signature.len() <= gap.len() {
+        if gap[i..i + signature.len()] != signature {
+            i += 1;
+            continue;
+        }
+        match LocalHeader::parse(&data[start + i..]) {
+            Ok((_, header)) => {
+                let len = if header.has_data_descriptor() {
+                    header.len
+                } else {
+                    header.len + header.compressed_size as usize
+                };
+                findings.push(ArchiveFinding::UnlistedEntry {
+                    name: header.name,
+                    offset: start + i,
+                });
+                i += len;
+            }
+            Err(_) => i += 1,
+        }
+    }
+}
@@ -1,21 +1,71 @@
-use nom::{
-    bytes::complete::take,
-    multi::count,
-    number::complete::{be_u16, be_u32, u8},
-    IResult,
+#[cfg(feature = "analysis")]
+use alloc::collections::BTreeMap;
+use core::fmt;
+#[cfg(feature = "std")]
+use std::{any::Any, io};
+
+#[cfg(feature = "std")]
+use crate::json::Json;
+use crate::prelude::*;
+#[cfg(feature = "write")]
+use crate::write::WriteBe;
+use crate::{
+    accessflags::{
+        ClassAccessFlags, ExportsAccessFlags, ModuleAccessFlags, ParameterAccessFlags,
+        RequiresAccessFlags,
+    },
+    classfile::{ParseContext, ParseOptions},
+    constantpool::ConstantPool,
+    descriptor::FieldType,
+    error::{self, be_u16, be_u32, count, count_within, take, u8, ErrorAt, IResult, ParseError},
+    signature::{self, ParsedSignature, SignatureError, TypeSignature},
+    spec,
+};
+#[cfg(feature = "analysis")]
+use crate::{
+    budget::{AnalysisBudget, Partial},
+    cfg::ControlFlowGraph,
+    frames::Frame,
+    pattern::{Pattern, PatternMatch},
+    regions::{self, Region},
+};
+#[cfg(feature = "disasm")]
+use crate::{
+    constantpool::Utf8Roles,
+    instruction::{self, DecodeError},
+    layout::CodeLayout,
+    stackmap::{self, StackMapIssue},
 };
 
-use crate::constantpool::ConstantPool;
-
-#[derive(Debug)]
-pub struct AttributeInfo {
-    attribute_name_index: u16,
-    attribute_length: u32,
-    info: Vec<u8>,
-}
-
-impl AttributeInfo {
-    pub fn parse(buf: &[u8]) -> IResult<&[u8], AttributeInfo> {
+/// How deep attributes, and element values, may nest unless
+/// [`ParseOptions::max_nesting`](crate::classfile::ParseOptions::max_nesting)
+/// says otherwise. Compilers nest attributes two deep and annotations little
+/// more, but nothing in the format stops a crafted file from nesting them
+/// until parsing runs out of stack. A `Code` attribute takes tens of KiB of
+/// stack in a debug build, so 16 of them fit in the 2 MiB a spawned thread
+/// gets.
+pub(crate) const MAX_NESTING: usize = 16;
+
+/// The attributes only debuggers and stack traces read, which a release
+/// build can do without.
+pub const DEBUG_ATTRIBUTES: &[&str] = &[
+    "SourceFile",
+    "SourceDebugExtension",
+    "LineNumberTable",
+    "LocalVariableTable",
+    "LocalVariableTypeTable",
+];
+
+/// An attribute as stored, with its body still unparsed.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AttributeInfo<'a> {
+    pub attribute_name_index: u16,
+    pub attribute_length: u32,
+    pub info: &'a [u8],
+}
+
+impl<'a> AttributeInfo<'a> {
+    pub fn parse(buf: &'a [u8]) -> IResult<&'a [u8], AttributeInfo<'a>> {
         let (buf, attribute_name_index) = be_u16(buf)?;
         let (buf, attribute_length) = be_u32(buf)?;
         let (buf, info) = take(attribute_length as usize)(buf)?;
@@ -25,13 +75,19 @@ impl AttributeInfo {
             AttributeInfo {
                 attribute_name_index,
                 attribute_length,
-                info: info.to_vec(),
+                info,
             },
         ))
     }
+
+    /// The attribute's name, looked up in the class's constant pool.
+    pub fn name<'b>(&self, constant_pool: &'b [ConstantPool]) -> Option<&'b str> {
+        ConstantPool::utf8(constant_pool, self.attribute_name_index)
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Attribute {
     ConstantValue(u16),
     Code(Code),
@@ -39,14 +95,22 @@ pub enum Attribute {
     Exceptions(Exceptions),
     InnerClasses(InnerClasses),
     EnclosingMethod(EnclosingMethod),
-    Synthetic,
+    /// The payload is always empty in a well-formed class file. One which
+    /// isn't fails the parse unless
+    /// [`ParseOptions::lenient_attribute_lengths`] is set, and is then kept
+    /// so that it can be reported by the verifier.
+    Synthetic(Vec<u8>),
     Signature(Signature),
     SourceFile(SourceFile),
-    SourceDebugExtension(String),
+    /// The bytes as written. The JVMS leaves their encoding open, and
+    /// though they're usually UTF-8, some tools write another charset.
+    SourceDebugExtension(Vec<u8>),
     LineNumberTable(Vec<LineNumber>),
     LocalVariableTable(Vec<LocalVariable>),
     LocalVariableTypeTable(Vec<LocalVariableType>),
-    Deprecated,
+    /// The payload is always empty in a well-formed class file, and is
+    /// treated as that of [`Attribute::Synthetic`] is.
+    Deprecated(Vec<u8>),
     RuntimeVisibleAnnotations(Vec<Annotation>),
     RuntimeInvisibleAnnotations(Vec<Annotation>),
     RuntimeVisibleParameterAnnotations(Vec<ParameterAnnotation>),
@@ -63,192 +127,896 @@ pub enum Attribute {
     NestMembers(Vec<u16>),
     Record(Vec<RecordComponentInfo>),
     PermittedSubclasses(Vec<u16>),
+    /// An attribute read by a parser registered with
+    /// [`ParseOptions::register_attribute`](crate::classfile::ParseOptions::register_attribute).
+    /// Serialized by its name alone, and never deserialized. Needs `std`.
+    #[cfg(feature = "std")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "serialize_custom", skip_deserializing)
+    )]
+    Custom(Box<dyn CustomAttribute>),
+    /// An attribute jvmb doesn't know and no parser is registered for,
+    /// such as `ScalaSig` or an obfuscator's marker, with its body as
+    /// stored. The JVM ignores such attributes, and so does the parser.
+    Unknown {
+        name: String,
+        info: Vec<u8>,
+    },
+    /// An attribute whose body was larger than
+    /// [`ParseOptions::defer_attributes_over`](crate::classfile::ParseOptions::defer_attributes_over)
+    /// and was left unread.
+    Deferred(DeferredAttribute),
+}
+
+/// Where the body of an attribute which wasn't read at parse time lies. It
+/// can be read later through
+/// [`ClassFile::load_deferred`](crate::classfile::ClassFile::load_deferred).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DeferredAttribute {
+    pub name: String,
+    /// The offset of the body from the start of the class file.
+    pub offset: usize,
+    pub len: usize,
+}
+
+/// A vendor-specific attribute parsed into a type of the caller's own.
+#[cfg(feature = "std")]
+pub trait CustomAttribute: fmt::Debug + Any {
+    /// The name this attribute is stored under in the constant pool.
+    fn name(&self) -> &str;
+
+    /// Writes the attribute's payload, without its name index and length.
+    fn write_info(&self, out: &mut Vec<u8>) -> io::Result<()>;
+
+    /// The attribute's value in the JSON output, if it has one.
+    fn to_json(&self) -> Option<Json> {
+        None
+    }
+
+    /// Calls `visit` with every constant pool index the payload refers to,
+    /// so that those entries aren't reported as unused.
+    fn visit_constants(&self, _visit: &mut dyn FnMut(u16)) {}
+
+    /// A copy of the attribute, for cloning the class which holds it.
+    fn clone_box(&self) -> Box<dyn CustomAttribute>;
+
+    /// Whether `other` is the same attribute: by default, whether it has
+    /// the same name and writes the same bytes, so far as either gets
+    /// before failing.
+    fn eq_dyn(&self, other: &dyn CustomAttribute) -> bool {
+        let (mut ours, mut theirs) = (Vec::new(), Vec::new());
+        let _ = self.write_info(&mut ours);
+        let _ = other.write_info(&mut theirs);
+        self.name() == other.name() && ours == theirs
+    }
+}
+
+#[cfg(feature = "std")]
+impl Clone for Box<dyn CustomAttribute> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+#[cfg(feature = "std")]
+impl PartialEq for dyn CustomAttribute {
+    fn eq(&self, other: &Self) -> bool {
+        self.eq_dyn(other)
+    }
+}
+
+// an eq_dyn of the implementor's own is trusted to be reflexive
+#[cfg(feature = "std")]
+impl Eq for dyn CustomAttribute {}
+
+// serialize_with hands over the field as it is, a `&Box`
+#[cfg(feature = "serde")]
+#[allow(clippy::borrowed_box)]
+fn serialize_custom<S: serde::Serializer>(
+    custom: &Box<dyn CustomAttribute>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    use serde::ser::SerializeStruct;
+
+    let mut state = serializer.serialize_struct("Custom", 1)?;
+    state.serialize_field("name", custom.name())?;
+    state.end()
+}
+
+#[cfg(feature = "std")]
+impl dyn CustomAttribute {
+    pub fn downcast_ref<T: CustomAttribute>(&self) -> Option<&T> {
+        (self as &dyn Any).downcast_ref()
+    }
+
+    pub fn downcast_mut<T: CustomAttribute>(&mut self) -> Option<&mut T> {
+        (self as &mut dyn Any).downcast_mut()
+    }
 }
 
 impl Attribute {
-    pub fn from_attribute_info(
-        attributes: Vec<AttributeInfo>,
+    /// Parses the `attributes_count` attributes at the start of `buf`, each
+    /// straight from its header and body in `buf`.
+    pub(crate) fn parse_table<'a>(
+        buf: &'a [u8],
+        attributes_count: u16,
         constant_pool: &[ConstantPool],
-    ) -> Vec<Attribute> {
+        context: &ParseContext,
+    ) -> IResult<&'a [u8], Vec<Attribute>> {
+        // every header is stepped over before any body is parsed, so that a
+        // table cut short fails as that whatever the attributes before the
+        // cut hold
+        let mut rest = buf;
+        for _ in 0..error::capacity(buf, attributes_count as usize, 6)? {
+            (rest, _) = AttributeInfo::parse(rest)?;
+        }
+        count(
+            |buf| Attribute::parse_from(buf, constant_pool, context),
+            attributes_count as usize,
+            6,
+        )(buf)
+    }
+
+    /// Parses the attribute at the start of `buf`, header and body, or
+    /// defers it as `context` says.
+    pub(crate) fn parse_from<'a>(
+        buf: &'a [u8],
+        constant_pool: &[ConstantPool],
+        context: &ParseContext,
+    ) -> IResult<&'a [u8], Attribute> {
+        let (buf, attr) = AttributeInfo::parse(buf)?;
+        let attribute = Attribute::from_info(attr, constant_pool, context)?;
+        Ok((buf, attribute))
+    }
+
+    /// Parses attributes already split from their table, as the borrowed
+    /// form and salvaging do.
+    pub(crate) fn from_attribute_info<'a>(
+        attributes: Vec<AttributeInfo<'a>>,
+        constant_pool: &[ConstantPool],
+        context: &ParseContext,
+    ) -> Result<Vec<Attribute>, nom::Err<ErrorAt<&'a [u8]>>> {
         attributes
             .into_iter()
-            .map(|attr| {
-                Attribute::parse(
-                    attr.attribute_name_index as usize,
-                    &attr.info,
-                    constant_pool,
-                )
-                .unwrap()
-            })
+            .map(|attr| Attribute::from_info(attr, constant_pool, context))
             .collect()
     }
+
+    fn from_info<'a>(
+        attr: AttributeInfo<'a>,
+        constant_pool: &[ConstantPool],
+        context: &ParseContext,
+    ) -> Result<Attribute, nom::Err<ErrorAt<&'a [u8]>>> {
+        let deferred = context.options.defers(attr.info.len(), || {
+            ConstantPool::utf8(constant_pool, attr.attribute_name_index)
+                .unwrap_or_default()
+                .to_string()
+        });
+        if let Some(name) = deferred {
+            return Ok(Attribute::Deferred(DeferredAttribute {
+                name,
+                offset: context.offset_of(attr.info),
+                len: attr.info.len(),
+            }));
+        }
+        let started = context.start();
+        let attribute = error::within(
+            Attribute::parse(attr.attribute_name_index, attr.info, constant_pool, context),
+            || match ConstantPool::utf8(constant_pool, attr.attribute_name_index) {
+                Some(name) => name.to_string(),
+                None => format!("attribute #{}", attr.attribute_name_index),
+            },
+        )?;
+        context.record(started, |metrics, time| {
+            metrics
+                .attributes
+                .entry(attribute.name().to_string())
+                .or_default()
+                .record(time, 1)
+        });
+        Ok(attribute)
+    }
+
+    /// The name this attribute is stored under in the constant pool.
+    pub fn name(&self) -> &str {
+        match self {
+            Attribute::ConstantValue(_) => "ConstantValue",
+            Attribute::Code(_) => "Code",
+            Attribute::StackMapTable(_) => "StackMapTable",
+            Attribute::Exceptions(_) => "Exceptions",
+            Attribute::InnerClasses(_) => "InnerClasses",
+            Attribute::EnclosingMethod(_) => "EnclosingMethod",
+            Attribute::Synthetic(_) => "Synthetic",
+            Attribute::Signature(_) => "Signature",
+            Attribute::SourceFile(_) => "SourceFile",
+            Attribute::SourceDebugExtension(_) => "SourceDebugExtension",
+            Attribute::LineNumberTable(_) => "LineNumberTable",
+            Attribute::LocalVariableTable(_) => "LocalVariableTable",
+            Attribute::LocalVariableTypeTable(_) => "LocalVariableTypeTable",
+            Attribute::Deprecated(_) => "Deprecated",
+            Attribute::RuntimeVisibleAnnotations(_) => "RuntimeVisibleAnnotations",
+            Attribute::RuntimeInvisibleAnnotations(_) => "RuntimeInvisibleAnnotations",
+            Attribute::RuntimeVisibleParameterAnnotations(_) => {
+                "RuntimeVisibleParameterAnnotations"
+            }
+            Attribute::RuntimeInvisibleParameterAnnotations(_) => {
+                "RuntimeInvisibleParameterAnnotations"
+            }
+            Attribute::RuntimeVisibleTypeAnnotations(_) => "RuntimeVisibleTypeAnnotations",
+            Attribute::RuntimeInvisibleTypeAnnotations(_) => "RuntimeInvisibleTypeAnnotations",
+            Attribute::AnnotationDefault(_) => "AnnotationDefault",
+            Attribute::BootstrapMethods(_) => "BootstrapMethods",
+            Attribute::MethodParameters(_) => "MethodParameters",
+            Attribute::Module(_) => "Module",
+            Attribute::ModulePackages(_) => "ModulePackages",
+            Attribute::ModuleMainClass(_) => "ModuleMainClass",
+            Attribute::NestHost(_) => "NestHost",
+            Attribute::NestMembers(_) => "NestMembers",
+            Attribute::Record(_) => "Record",
+            Attribute::PermittedSubclasses(_) => "PermittedSubclasses",
+            #[cfg(feature = "std")]
+            Attribute::Custom(custom) => custom.name(),
+            Attribute::Unknown { name, .. } => name,
+            Attribute::Deferred(deferred) => &deferred.name,
+        }
+    }
+
+    /// Removes the attributes with one of `names` from `attributes`, and
+    /// from the `Code` attribute among them, returning how many went.
+    pub fn remove_named(attributes: &mut Vec<Attribute>, names: &[&str]) -> usize {
+        let len = attributes.len();
+        attributes.retain(|attribute| !names.contains(&attribute.name()));
+        let mut removed = len - attributes.len();
+        for attribute in attributes {
+            if let Attribute::Code(code) = attribute {
+                removed += Attribute::remove_named(&mut code.attributes, names);
+                code.attributes_count = code.attributes.len() as u16;
+            }
+        }
+        removed
+    }
+
+    pub fn has_synthetic(attributes: &[Attribute]) -> bool {
+        attributes
+            .iter()
+            .any(|attr| matches!(attr, Attribute::Synthetic(_)))
+    }
+
+    pub fn has_deprecated(attributes: &[Attribute]) -> bool {
+        attributes
+            .iter()
+            .any(|attr| matches!(attr, Attribute::Deprecated(_)))
+    }
+
+    /// The first `Signature` among `attributes`.
+    pub fn signature(attributes: &[Attribute]) -> Option<&Signature> {
+        attributes.iter().find_map(|attribute| match attribute {
+            Attribute::Signature(signature) => Some(signature),
+            _ => None,
+        })
+    }
+
+    /// The visible and then the invisible annotations among `attributes`.
+    /// Annotations whose type can't be resolved are left out.
+    pub fn annotations<'a>(
+        attributes: &'a [Attribute],
+        constant_pool: &'a [ConstantPool],
+    ) -> Vec<ResolvedAnnotation<'a>> {
+        let annotations = |visible: bool| {
+            attributes
+                .iter()
+                .filter_map(move |attribute| match attribute {
+                    Attribute::RuntimeVisibleAnnotations(annotations) if visible => {
+                        Some(annotations)
+                    }
+                    Attribute::RuntimeInvisibleAnnotations(annotations) if !visible => {
+                        Some(annotations)
+                    }
+                    _ => None,
+                })
+                .flatten()
+                .filter_map(move |annotation| {
+                    ResolvedAnnotation::new(annotation, visible, constant_pool)
+                })
+        };
+        annotations(true).chain(annotations(false)).collect()
+    }
     fn parse<'a>(
-        attribute_name_index: usize,
+        attribute_name_index: u16,
         info: &'a [u8],
         constant_pool: &[ConstantPool],
-    ) -> Result<Self, nom::Err<nom::error::Error<&'a [u8]>>> {
-        let attribute_type = constant_pool
-            .get(attribute_name_index as usize - 1)
-            .unwrap();
-
-        if let ConstantPool::UTF8(name) = attribute_type {
-            match name.as_str() {
-                "ConstantValue" => {
-                    let (_, constantvalue_index) = be_u16(info)?;
-                    Ok(Attribute::ConstantValue(constantvalue_index))
-                }
-                "Code" => {
-                    let (_, code) = Code::parse(info, constant_pool)?;
-                    Ok(Attribute::Code(code))
-                }
-                "StackMapTable" => {
-                    let (_, stack_map_table) = StackMapTable::parse(info)?;
-                    Ok(Attribute::StackMapTable(stack_map_table))
-                }
-                "Exceptions" => {
-                    let (_, exceptions) = Exceptions::parse(info)?;
-                    Ok(Attribute::Exceptions(exceptions))
-                }
-                "InnerClasses" => {
-                    let (_, inner_classes) = InnerClasses::parse(info)?;
-                    Ok(Attribute::InnerClasses(inner_classes))
+        context: &ParseContext,
+    ) -> Result<Self, nom::Err<ErrorAt<&'a [u8]>>> {
+        let name = ConstantPool::get_utf8(constant_pool, attribute_name_index)
+            .map_err(|e| ErrorAt::failure(info, ParseError::InvalidConstantPoolIndex(e)))?;
+        let declared = info.len() as u32;
+        if let (true, Some(major_version), Some(spec)) = (
+            context.options.strict_attribute_versions,
+            context.major_version.get(),
+            spec::attribute_spec(name),
+        ) {
+            if major_version < spec.since_major {
+                return Err(ErrorAt::failure(
+                    info,
+                    ParseError::AttributeBeforeVersion {
+                        name: name.to_string(),
+                        since_major: spec.since_major,
+                        major_version,
+                    },
+                ));
+            }
+        }
+        let depth = context.depth.get();
+        if depth >= context.options.nesting_limit() {
+            return Err(ErrorAt::failure(
+                info,
+                ParseError::NestingTooDeep {
+                    structure: "attribute",
+                },
+            ));
+        }
+        context.depth.set(depth + 1);
+        let parsed = Self::parse_body(name, info, constant_pool, context);
+        context.depth.set(depth);
+        let (rest, attribute) = parsed.map_err(|e| match e {
+            nom::Err::Error(e) | nom::Err::Failure(e)
+                if matches!(
+                    e.code,
+                    ParseError::UnexpectedEof | ParseError::Truncated { .. }
+                ) =>
+            {
+                nom::Err::Failure(ErrorAt {
+                    code: ParseError::AttributeTruncated {
+                        name: name.to_string(),
+                        declared,
+                    },
+                    ..e
+                })
+            }
+            e => e,
+        })?;
+        if !rest.is_empty() && !context.options.lenient_attribute_lengths {
+            return Err(ErrorAt::failure(
+                rest,
+                ParseError::AttributeLengthMismatch {
+                    name: name.to_string(),
+                    declared,
+                    consumed: declared - rest.len() as u32,
+                },
+            ));
+        }
+        Ok(attribute)
+    }
+
+    /// Parses the body of the attribute called `name`, returning what the
+    /// structure left of `info`.
+    fn parse_body<'a>(
+        name: &str,
+        info: &'a [u8],
+        constant_pool: &[ConstantPool],
+        context: &ParseContext,
+    ) -> IResult<&'a [u8], Self> {
+        let end = &info[info.len()..];
+        // how deep element values may nest
+        let levels = context.options.nesting_limit();
+        match name {
+            "ConstantValue" => {
+                let (buf, constantvalue_index) = be_u16(info)?;
+                Ok((buf, Attribute::ConstantValue(constantvalue_index)))
+            }
+            "Code" => {
+                let (buf, code) = Code::parse(info, constant_pool, context)?;
+                Ok((buf, Attribute::Code(code)))
+            }
+            "StackMapTable" => {
+                let (buf, stack_map_table) = StackMapTable::parse(info)?;
+                Ok((buf, Attribute::StackMapTable(stack_map_table)))
+            }
+            "Exceptions" => {
+                let (buf, exceptions) = Exceptions::parse(info)?;
+                Ok((buf, Attribute::Exceptions(exceptions)))
+            }
+            "InnerClasses" => {
+                let (buf, inner_classes) = InnerClasses::parse(info)?;
+                Ok((buf, Attribute::InnerClasses(inner_classes)))
+            }
+            "EnclosingMethod" => {
+                let (buf, enclosing_method) = EnclosingMethod::parse(info)?;
+                Ok((buf, Attribute::EnclosingMethod(enclosing_method)))
+            }
+            "Synthetic" => Ok((info, Attribute::Synthetic(info.to_vec()))),
+            "Signature" => {
+                let (buf, signature) = Signature::parse(info)?;
+                Ok((buf, Attribute::Signature(signature)))
+            }
+            "SourceFile" => {
+                let (buf, source_file) = SourceFile::parse(info)?;
+                Ok((buf, Attribute::SourceFile(source_file)))
+            }
+            "SourceDebugExtension" => Ok((end, Attribute::SourceDebugExtension(info.to_vec()))),
+            "LineNumberTable" => {
+                let (buf, line_number_table_length) = be_u16(info)?;
+                let (buf, line_number_table) =
+                    count(LineNumber::parse, line_number_table_length as usize, 4)(buf)?;
+                Ok((buf, Attribute::LineNumberTable(line_number_table)))
+            }
+            "LocalVariableTable" => {
+                let (buf, local_variable_table_length) = be_u16(info)?;
+                let (buf, local_variable_table) = count(
+                    LocalVariable::parse,
+                    local_variable_table_length as usize,
+                    10,
+                )(buf)?;
+                Ok((buf, Attribute::LocalVariableTable(local_variable_table)))
+            }
+            "LocalVariableTypeTable" => {
+                let (buf, local_variable_type_table_length) = be_u16(info)?;
+                let (buf, local_variable_type_table) = count(
+                    LocalVariableType::parse,
+                    local_variable_type_table_length as usize,
+                    10,
+                )(buf)?;
+                Ok((
+                    buf,
+                    Attribute::LocalVariableTypeTable(local_variable_type_table),
+                ))
+            }
+            "Deprecated" => Ok((info, Attribute::Deprecated(info.to_vec()))),
+            "RuntimeVisibleAnnotations" => {
+                let (buf, num_annotations) = be_u16(info)?;
+                let (buf, annotations) = count(
+                    |buf| Annotation::parse(buf, levels),
+                    num_annotations as usize,
+                    4,
+                )(buf)?;
+                Ok((buf, Attribute::RuntimeVisibleAnnotations(annotations)))
+            }
+            "RuntimeInvisibleAnnotations" => {
+                let (buf, num_annotations) = be_u16(info)?;
+                let (buf, annotations) = count(
+                    |buf| Annotation::parse(buf, levels),
+                    num_annotations as usize,
+                    4,
+                )(buf)?;
+                Ok((buf, Attribute::RuntimeInvisibleAnnotations(annotations)))
+            }
+            "RuntimeVisibleParameterAnnotations" => {
+                let (buf, num_parameters) = u8(info)?;
+                let (buf, parameter_annotations) = count(
+                    |buf| ParameterAnnotation::parse(buf, levels),
+                    num_parameters as usize,
+                    2,
+                )(buf)?;
+                Ok((
+                    buf,
+                    Attribute::RuntimeVisibleParameterAnnotations(parameter_annotations),
+                ))
+            }
+            "RuntimeInvisibleParameterAnnotations" => {
+                let (buf, num_parameters) = u8(info)?;
+                let (buf, parameter_annotations) = count(
+                    |buf| ParameterAnnotation::parse(buf, levels),
+                    num_parameters as usize,
+                    2,
+                )(buf)?;
+                Ok((
+                    buf,
+                    Attribute::RuntimeInvisibleParameterAnnotations(parameter_annotations),
+                ))
+            }
+            "RuntimeVisibleTypeAnnotations" => {
+                let (buf, num_annotations) = be_u16(info)?;
+                let (buf, annotations) = count(
+                    |buf| TypeAnnotation::parse(buf, levels),
+                    num_annotations as usize,
+                    6,
+                )(buf)?;
+                Ok((buf, Attribute::RuntimeVisibleTypeAnnotations(annotations)))
+            }
+            "RuntimeInvisibleTypeAnnotations" => {
+                let (buf, num_annotations) = be_u16(info)?;
+                let (buf, annotations) = count(
+                    |buf| TypeAnnotation::parse(buf, levels),
+                    num_annotations as usize,
+                    6,
+                )(buf)?;
+                Ok((buf, Attribute::RuntimeInvisibleTypeAnnotations(annotations)))
+            }
+            "AnnotationDefault" => {
+                let (buf, default_value) = ElementValue::parse(info, levels)?;
+                Ok((buf, Attribute::AnnotationDefault(default_value)))
+            }
+            "BootstrapMethods" => {
+                let (buf, num_bootstrap_methods) = be_u16(info)?;
+                let (buf, bootstrap_methods) =
+                    count(BootstrapMethod::parse, num_bootstrap_methods as usize, 4)(buf)?;
+                Ok((buf, Attribute::BootstrapMethods(bootstrap_methods)))
+            }
+            "MethodParameters" => {
+                let (buf, parameters_count) = u8(info)?;
+                let (buf, parameters) = count(Parameter::parse, parameters_count as usize, 4)(buf)?;
+                Ok((buf, Attribute::MethodParameters(parameters)))
+            }
+            "Module" => {
+                let (buf, module) = Module::parse(info)?;
+                Ok((buf, Attribute::Module(module)))
+            }
+            "ModulePackages" => {
+                let (buf, package_count) = be_u16(info)?;
+                let (buf, package_index) = count(be_u16, package_count as usize, 2)(buf)?;
+                Ok((buf, Attribute::ModulePackages(package_index)))
+            }
+            "ModuleMainClass" => {
+                let (buf, main_class_index) = be_u16(info)?;
+                Ok((buf, Attribute::ModuleMainClass(main_class_index)))
+            }
+            "NestHost" => {
+                let (buf, host_class_index) = be_u16(info)?;
+                Ok((buf, Attribute::NestHost(host_class_index)))
+            }
+            "NestMembers" => {
+                let (buf, number_of_classes) = be_u16(info)?;
+                let (buf, classes) = count(be_u16, number_of_classes as usize, 2)(buf)?;
+                Ok((buf, Attribute::NestMembers(classes)))
+            }
+            "Record" => {
+                let (buf, components_count) = be_u16(info)?;
+                let (buf, components) = count(
+                    |buf| RecordComponentInfo::parse(buf, constant_pool, context),
+                    components_count as usize,
+                    6,
+                )(buf)?;
+                Ok((buf, Attribute::Record(components)))
+            }
+            "PermittedSubclasses" => {
+                let (buf, number_of_classes) = be_u16(info)?;
+                let (buf, classes) = count(be_u16, number_of_classes as usize, 2)(buf)?;
+                Ok((buf, Attribute::PermittedSubclasses(classes)))
+            }
+            attr_type => {
+                #[cfg(feature = "std")]
+                if let Some(parser) = context.options.attribute_parser(attr_type) {
+                    return parser(info, constant_pool)
+                        .map(|custom| (end, Attribute::Custom(custom)))
+                        .map_err(|e| {
+                            ErrorAt::failure(
+                                info,
+                                ParseError::InvalidAttribute {
+                                    name: attr_type.to_string(),
+                                    message: e.to_string(),
+                                },
+                            )
+                        });
                 }
-                "EnclosingMethod" => {
-                    let (_, enclosing_method) = EnclosingMethod::parse(info)?;
-                    Ok(Attribute::EnclosingMethod(enclosing_method))
+                if context.options.reject_unknown_attributes {
+                    return Err(ErrorAt::failure(
+                        info,
+                        ParseError::UnknownAttribute(attr_type.to_string()),
+                    ));
                 }
-                "Synthetic" => Ok(Attribute::Synthetic),
-                "Signature" => {
-                    let (_, signature) = Signature::parse(info)?;
-                    Ok(Attribute::Signature(signature))
+                Ok((
+                    end,
+                    Attribute::Unknown {
+                        name: attr_type.to_string(),
+                        info: info.to_vec(),
+                    },
+                ))
+            }
+        }
+    }
+
+    /// Writes the attribute, including its name index and length. The name
+    /// must already be present in `constant_pool`.
+    #[cfg(feature = "write")]
+    pub fn write(&self, out: &mut Vec<u8>, constant_pool: &[ConstantPool]) -> io::Result<()> {
+        let name_index = ConstantPool::utf8_index(constant_pool, self.name()).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("attribute name {} is not in the constant pool", self.name()),
+            )
+        })?;
+        out.put_u16(name_index);
+        let length_offset = out.len();
+        out.put_u32(0);
+        self.write_info(out, constant_pool)?;
+        let attribute_length = u32::try_from(out.len() - length_offset - 4)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "attribute too long"))?;
+        out[length_offset..length_offset + 4].copy_from_slice(&attribute_length.to_be_bytes());
+        Ok(())
+    }
+
+    /// Writes an attribute table preceded by its count.
+    #[cfg(feature = "write")]
+    pub fn write_all(
+        attributes: &[Attribute],
+        out: &mut Vec<u8>,
+        constant_pool: &[ConstantPool],
+    ) -> io::Result<()> {
+        out.put_count(attributes.len(), "attribute table")?;
+        for attribute in attributes {
+            attribute.write(out, constant_pool)?;
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "write")]
+    fn write_info(&self, out: &mut Vec<u8>, constant_pool: &[ConstantPool]) -> io::Result<()> {
+        match self {
+            Attribute::ConstantValue(index)
+            | Attribute::ModuleMainClass(index)
+            | Attribute::NestHost(index) => out.put_u16(*index),
+            Attribute::Code(code) => code.write(out, constant_pool)?,
+            Attribute::StackMapTable(stack_map_table) => stack_map_table.write(out)?,
+            Attribute::Exceptions(exceptions) => exceptions.write(out)?,
+            Attribute::InnerClasses(inner_classes) => inner_classes.write(out)?,
+            Attribute::EnclosingMethod(enclosing_method) => enclosing_method.write(out),
+            Attribute::Synthetic(info)
+            | Attribute::Deprecated(info)
+            | Attribute::Unknown { info, .. } => out.extend_from_slice(info),
+            Attribute::Signature(signature) => signature.write(out),
+            Attribute::SourceFile(source_file) => source_file.write(out),
+            Attribute::SourceDebugExtension(debug_extension) => {
+                out.extend_from_slice(debug_extension)
+            }
+            Attribute::LineNumberTable(table) => {
+                out.put_count(table.len(), "LineNumberTable")?;
+                table.iter().for_each(|entry| entry.write(out));
+            }
+            Attribute::LocalVariableTable(table) => {
+                out.put_count(table.len(), "LocalVariableTable")?;
+                table.iter().for_each(|entry| entry.write(out));
+            }
+            Attribute::LocalVariableTypeTable(table) => {
+                out.put_count(table.len(), "LocalVariableTypeTable")?;
+                table.iter().for_each(|entry| entry.write(out));
+            }
+            Attribute::RuntimeVisibleAnnotations(annotations)
+            | Attribute::RuntimeInvisibleAnnotations(annotations) => {
+                out.put_count(annotations.len(), "annotations")?;
+                for annotation in annotations {
+                    annotation.write(out)?;
                 }
-                "SourceFile" => {
-                    let (_, source_file) = SourceFile::parse(info)?;
-                    Ok(Attribute::SourceFile(source_file))
+            }
+            Attribute::RuntimeVisibleParameterAnnotations(parameters)
+            | Attribute::RuntimeInvisibleParameterAnnotations(parameters) => {
+                out.put_count_u8(parameters.len(), "parameter annotations")?;
+                for parameter in parameters {
+                    parameter.write(out)?;
                 }
-                "SourceDebugExtension" => Ok(Attribute::SourceDebugExtension(
-                    String::from_utf8(info.to_vec()).unwrap(),
-                )),
-                "LineNumberTable" => {
-                    let (buf, line_number_table_length) = be_u16(info)?;
-                    let (_, line_number_table) =
-                        count(LineNumber::parse, line_number_table_length as usize)(buf)?;
-                    Ok(Attribute::LineNumberTable(line_number_table))
+            }
+            Attribute::RuntimeVisibleTypeAnnotations(annotations)
+            | Attribute::RuntimeInvisibleTypeAnnotations(annotations) => {
+                out.put_count(annotations.len(), "type annotations")?;
+                for annotation in annotations {
+                    annotation.write(out)?;
                 }
-                "LocalVariableTable" => {
-                    let (buf, local_variable_table_length) = be_u16(info)?;
-                    let (_, local_variable_table) =
-                        count(LocalVariable::parse, local_variable_table_length as usize)(buf)?;
-                    Ok(Attribute::LocalVariableTable(local_variable_table))
+            }
+            Attribute::AnnotationDefault(default_value) => default_value.write(out)?,
+            Attribute::BootstrapMethods(bootstrap_methods) => {
+                out.put_count(bootstrap_methods.len(), "BootstrapMethods")?;
+                for bootstrap_method in bootstrap_methods {
+                    bootstrap_method.write(out)?;
                 }
-                "LocalVariableTypeTable" => {
-                    let (buf, local_variable_type_table_length) = be_u16(info)?;
-                    let (_, local_variable_type_table) = count(
-                        LocalVariableType::parse,
-                        local_variable_type_table_length as usize,
-                    )(buf)?;
-                    Ok(Attribute::LocalVariableTypeTable(local_variable_type_table))
+            }
+            Attribute::MethodParameters(parameters) => {
+                out.put_count_u8(parameters.len(), "MethodParameters")?;
+                parameters.iter().for_each(|parameter| parameter.write(out));
+            }
+            Attribute::Module(module) => module.write(out)?,
+            Attribute::ModulePackages(indices)
+            | Attribute::NestMembers(indices)
+            | Attribute::PermittedSubclasses(indices) => write_indices(out, indices, self.name())?,
+            Attribute::Record(components) => {
+                out.put_count(components.len(), "Record")?;
+                for component in components {
+                    component.write(out, constant_pool)?;
                 }
-                "Deprecated" => Ok(Attribute::Deprecated),
-                "RuntimeVisibleAnnotations" => {
-                    let (buf, num_annotations) = be_u16(info)?;
-                    let (_, annotations) = count(Annotation::parse, num_annotations as usize)(buf)?;
-                    Ok(Attribute::RuntimeVisibleAnnotations(annotations))
+            }
+            Attribute::Custom(custom) => custom.write_info(out)?,
+            Attribute::Deferred(deferred) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("the body of {} attribute was never loaded", deferred.name),
+                ))
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "disasm")]
+impl Attribute {
+    /// Calls `visit` with every constant pool index this attribute refers
+    /// to: the name it's stored under, the indices it holds, those held by
+    /// nested attributes, and the operands of any bytecode. Optional
+    /// references which are left empty (index 0) are skipped.
+    pub fn visit_constants(&self, constant_pool: &[ConstantPool], visit: &mut dyn FnMut(u16)) {
+        self.visit_constant_roles(constant_pool, &mut |index, _| visit(index));
+    }
+
+    /// Like [`Attribute::visit_constants`], along with the role the entry
+    /// plays when it's a UTF8 entry, or the role of the name when it's a
+    /// `NameAndType`. Indices of other entries come with
+    /// [`Utf8Roles::OTHER`]; what they lead to is for the pool to tell.
+    pub fn visit_constant_roles(
+        &self,
+        constant_pool: &[ConstantPool],
+        visit: &mut dyn FnMut(u16, Utf8Roles),
+    ) {
+        self.visit_indices(constant_pool, &mut |index, role| {
+            if index != 0 {
+                visit(index, role)
+            }
+        });
+    }
+
+    fn visit_indices(&self, constant_pool: &[ConstantPool], visit: &mut dyn FnMut(u16, Utf8Roles)) {
+        visit(
+            ConstantPool::utf8_index(constant_pool, self.name()).unwrap_or(0),
+            Utf8Roles::ATTRIBUTE_NAME,
+        );
+        match self {
+            Attribute::ConstantValue(index)
+            | Attribute::ModuleMainClass(index)
+            | Attribute::NestHost(index) => visit(*index, Utf8Roles::OTHER),
+            Attribute::Code(code) => {
+                instruction::decode(&code.code)
+                    .flatten()
+                    .filter_map(|instruction| instruction.cp_index())
+                    .for_each(|index| visit(index, Utf8Roles::OTHER));
+                for exception in &code.exception_table {
+                    visit(exception.catch_type, Utf8Roles::OTHER);
                 }
-                "RuntimeInvisibleAnnotations" => {
-                    let (buf, num_annotations) = be_u16(info)?;
-                    let (_, annotations) = count(Annotation::parse, num_annotations as usize)(buf)?;
-                    Ok(Attribute::RuntimeInvisibleAnnotations(annotations))
+                for attribute in &code.attributes {
+                    attribute.visit_indices(constant_pool, visit);
                 }
-                "RuntimeVisibleParameterAnnotations" => {
-                    let (buf, num_parameters) = u8(info)?;
-                    let (_, parameter_annotations) =
-                        count(ParameterAnnotation::parse, num_parameters as usize)(buf)?;
-                    Ok(Attribute::RuntimeVisibleParameterAnnotations(
-                        parameter_annotations,
-                    ))
+            }
+            Attribute::StackMapTable(table) => {
+                for frame in &table.entries {
+                    let types: Vec<&VerificationTypeInfo> = match frame {
+                        StackMapFrame::SameLocals1StackItemFrame { stack, .. }
+                        | StackMapFrame::SameLocals1StackItemFrameExtended { stack, .. } => {
+                            vec![stack]
+                        }
+                        StackMapFrame::AppendFrame { locals, .. } => locals.iter().collect(),
+                        StackMapFrame::FullFrame { locals, stack, .. } => {
+                            locals.iter().chain(stack).collect()
+                        }
+                        _ => Vec::new(),
+                    };
+                    for info in types {
+                        if let VerificationTypeInfo::ObjectVariableInfo(index) = info {
+                            visit(*index, Utf8Roles::OTHER);
+                        }
+                    }
                 }
-                "RuntimeInvisibleParameterAnnotations" => {
-                    let (buf, num_parameters) = u8(info)?;
-                    let (_, parameter_annotations) =
-                        count(ParameterAnnotation::parse, num_parameters as usize)(buf)?;
-                    Ok(Attribute::RuntimeInvisibleParameterAnnotations(
-                        parameter_annotations,
-                    ))
+            }
+            Attribute::Exceptions(exceptions) => exceptions
+                .exception_index_table
+                .iter()
+                .for_each(|&i| visit(i, Utf8Roles::OTHER)),
+            Attribute::InnerClasses(inner_classes) => {
+                for class in &inner_classes.classes {
+                    visit(class.inner_class_info_index, Utf8Roles::OTHER);
+                    visit(class.outer_class_info_index, Utf8Roles::OTHER);
+                    visit(class.inner_name_index, Utf8Roles::OTHER);
                 }
-                "RuntimeVisibleTypeAnnotations" => {
-                    let (buf, num_annotations) = be_u16(info)?;
-                    let (_, annotations) =
-                        count(TypeAnnotation::parse, num_annotations as usize)(buf)?;
-                    Ok(Attribute::RuntimeVisibleTypeAnnotations(annotations))
+            }
+            Attribute::EnclosingMethod(enclosing_method) => {
+                visit(enclosing_method.class_index, Utf8Roles::OTHER);
+                visit(enclosing_method.method_index, Utf8Roles::METHOD_NAME);
+            }
+            Attribute::Synthetic(_)
+            | Attribute::SourceDebugExtension(_)
+            | Attribute::Unknown { .. }
+            | Attribute::LineNumberTable(_)
+            | Attribute::Deprecated(_) => {}
+            Attribute::Signature(signature) => {
+                visit(signature.signature_index, Utf8Roles::SIGNATURE)
+            }
+            Attribute::SourceFile(source_file) => {
+                visit(source_file.sourcefile_index, Utf8Roles::OTHER)
+            }
+            Attribute::LocalVariableTable(table) => {
+                for variable in table {
+                    visit(variable.name_index, Utf8Roles::OTHER);
+                    visit(variable.descriptor_index, Utf8Roles::DESCRIPTOR);
                 }
-                "RuntimeInvisibleTypeAnnotations" => {
-                    let (buf, num_annotations) = be_u16(info)?;
-                    let (_, annotations) =
-                        count(TypeAnnotation::parse, num_annotations as usize)(buf)?;
-                    Ok(Attribute::RuntimeInvisibleTypeAnnotations(annotations))
+            }
+            Attribute::LocalVariableTypeTable(table) => {
+                for variable in table {
+                    visit(variable.name_index, Utf8Roles::OTHER);
+                    visit(variable.signature_index, Utf8Roles::SIGNATURE);
                 }
-                "AnnotationDefault" => {
-                    let (_, default_value) = ElementValue::parse(info)?;
-                    Ok(Attribute::AnnotationDefault(default_value))
+            }
+            Attribute::RuntimeVisibleAnnotations(annotations)
+            | Attribute::RuntimeInvisibleAnnotations(annotations) => {
+                for annotation in annotations {
+                    annotation.visit_indices(visit);
                 }
-                "BootstrapMethods" => {
-                    let (buf, num_bootstrap_methods) = be_u16(info)?;
-                    let (_, bootstrap_methods) =
-                        count(BootstrapMethod::parse, num_bootstrap_methods as usize)(buf)?;
-                    Ok(Attribute::BootstrapMethods(bootstrap_methods))
+            }
+            Attribute::RuntimeVisibleParameterAnnotations(parameters)
+            | Attribute::RuntimeInvisibleParameterAnnotations(parameters) => {
+                for annotation in parameters.iter().flat_map(|p| &p.annotations) {
+                    annotation.visit_indices(visit);
                 }
-                "MethodParameters" => {
-                    let (buf, parameters_count) = u8(info)?;
-                    let (_, parameters) = count(Parameter::parse, parameters_count as usize)(buf)?;
-                    Ok(Attribute::MethodParameters(parameters))
+            }
+            Attribute::RuntimeVisibleTypeAnnotations(annotations)
+            | Attribute::RuntimeInvisibleTypeAnnotations(annotations) => {
+                for annotation in annotations {
+                    visit(annotation.type_index, Utf8Roles::DESCRIPTOR);
+                    visit_element_value_pairs(&annotation.element_value_pairs, visit);
                 }
-                "Module" => {
-                    let (_, module) = Module::parse(info)?;
-                    Ok(Attribute::Module(module))
+            }
+            Attribute::AnnotationDefault(default_value) => default_value.visit_indices(visit),
+            Attribute::BootstrapMethods(bootstrap_methods) => {
+                for bootstrap_method in bootstrap_methods {
+                    visit(bootstrap_method.bootstrap_method_ref, Utf8Roles::OTHER);
+                    bootstrap_method
+                        .bootstrap_arguments
+                        .iter()
+                        .for_each(|&i| visit(i, Utf8Roles::OTHER));
                 }
-                "ModulePackages" => {
-                    let (buf, package_count) = be_u16(info)?;
-                    let (_, package_index) = count(be_u16, package_count as usize)(buf)?;
-                    Ok(Attribute::ModulePackages(package_index))
+            }
+            Attribute::MethodParameters(parameters) => parameters
+                .iter()
+                .for_each(|p| visit(p.name_index, Utf8Roles::OTHER)),
+            Attribute::Module(module) => {
+                visit(module.module_name_index, Utf8Roles::OTHER);
+                visit(module.module_version_index, Utf8Roles::OTHER);
+                for requires in &module.requires {
+                    visit(requires.requires_index, Utf8Roles::OTHER);
+                    visit(requires.requires_version_index, Utf8Roles::OTHER);
                 }
-                "ModuleMainClass" => {
-                    let (_, main_class_index) = be_u16(info)?;
-                    Ok(Attribute::ModuleMainClass(main_class_index))
+                for exports in &module.exports {
+                    visit(exports.exports_index, Utf8Roles::OTHER);
+                    exports
+                        .exports_to_index
+                        .iter()
+                        .for_each(|&i| visit(i, Utf8Roles::OTHER));
                 }
-                "NestHost" => {
-                    let (_, host_class_index) = be_u16(info)?;
-                    Ok(Attribute::NestHost(host_class_index))
+                for opens in &module.opens {
+                    visit(opens.opens_index, Utf8Roles::OTHER);
+                    opens
+                        .opens_to_index
+                        .iter()
+                        .for_each(|&i| visit(i, Utf8Roles::OTHER));
                 }
-                "NestMembers" => {
-                    let (buf, number_of_classes) = be_u16(info)?;
-                    let (_, classes) = count(be_u16, number_of_classes as usize)(buf)?;
-                    Ok(Attribute::NestMembers(classes))
+                module.uses.iter().for_each(|&i| visit(i, Utf8Roles::OTHER));
+                for provides in &module.provides {
+                    visit(provides.provides_index, Utf8Roles::OTHER);
+                    provides
+                        .provides_with_index
+                        .iter()
+                        .for_each(|&i| visit(i, Utf8Roles::OTHER));
                 }
-                "RecordComponentInfo" => {
-                    let (mut buf, components_count) = be_u16(info)?;
-                    let mut components = Vec::with_capacity(components_count as usize);
-                    for _ in 0..components_count {
-                        let (temp_buf, component) = RecordComponentInfo::parse(buf, constant_pool)?;
-                        buf = temp_buf;
-                        components.push(component);
+            }
+            Attribute::ModulePackages(indices)
+            | Attribute::NestMembers(indices)
+            | Attribute::PermittedSubclasses(indices) => {
+                indices.iter().for_each(|&i| visit(i, Utf8Roles::OTHER))
+            }
+            Attribute::Record(components) => {
+                for component in components {
+                    visit(component.name_index, Utf8Roles::FIELD_NAME);
+                    visit(component.descriptor_index, Utf8Roles::DESCRIPTOR);
+                    for attribute in &component.attributes {
+                        attribute.visit_indices(constant_pool, visit);
                     }
-                    Ok(Attribute::Record(components))
-                }
-                "PermittedSubclasses" => {
-                    let (buf, number_of_classes) = be_u16(info)?;
-                    let (_, classes) = count(be_u16, number_of_classes as usize)(buf)?;
-                    Ok(Attribute::PermittedSubclasses(classes))
                 }
-                attr_type => unimplemented!("Unimplemented attribute type: {}", attr_type),
             }
-        } else {
-            unimplemented!()
+            Attribute::Custom(custom) => {
+                custom.visit_constants(&mut |index| visit(index, Utf8Roles::OTHER))
+            }
+            Attribute::Deferred(_) => {}
         }
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Code {
     pub max_stack: u16,
     pub max_locals: u16,
@@ -261,16 +1029,53 @@ pub struct Code {
 }
 
 impl Code {
-    fn parse<'a>(buf: &'a [u8], constant_pool: &[ConstantPool]) -> IResult<&'a [u8], Self> {
+    /// A method body of the bytecode `code`, without exception handlers or
+    /// nested attributes. The lengths are taken from `code` when written.
+    pub fn new(max_stack: u16, max_locals: u16, code: Vec<u8>) -> Self {
+        Code {
+            max_stack,
+            max_locals,
+            code_length: code.len() as u32,
+            code,
+            exception_table_length: 0,
+            exception_table: Vec::new(),
+            attributes_count: 0,
+            attributes: Vec::new(),
+        }
+    }
+
+    /// Parses the body of a `Code` attribute, from `max_stack` on, found
+    /// outside any class file, such as a method a JVMTI agent dumped along
+    /// with its class's constant pool. `constant_pool` is only needed to
+    /// name the nested attributes; see [`ConstantPool::parse_standalone`].
+    /// `buf` must hold the body and nothing else, so the input left is
+    /// always empty and trailing bytes fail with
+    /// [`ParseError::TrailingBytes`].
+    pub fn parse_standalone<'a>(
+        buf: &'a [u8],
+        constant_pool: &[ConstantPool],
+    ) -> IResult<&'a [u8], Self> {
+        let options = ParseOptions::default();
+        let context = ParseContext::new(&options, buf);
+        let parsed = error::all_consuming(buf, |buf| Code::parse(buf, constant_pool, &context));
+        parsed.map_err(|e| error::locate(e, buf))
+    }
+
+    fn parse<'a>(
+        buf: &'a [u8],
+        constant_pool: &[ConstantPool],
+        context: &ParseContext,
+    ) -> IResult<&'a [u8], Self> {
         let (buf, max_stack) = be_u16(buf)?;
         let (buf, max_locals) = be_u16(buf)?;
         let (buf, code_length) = be_u32(buf)?;
         let (buf, code) = take(code_length as usize)(buf)?;
         let (buf, exception_table_length) = be_u16(buf)?;
-        let (buf, exception_table) = count(Exception::parse, exception_table_length as usize)(buf)?;
+        let (buf, exception_table) =
+            count(Exception::parse, exception_table_length as usize, 8)(buf)?;
         let (buf, attributes_count) = be_u16(buf)?;
-        let (buf, attributes) = count(AttributeInfo::parse, attributes_count as usize)(buf)?;
-        let attributes = Attribute::from_attribute_info(attributes, constant_pool);
+        let (buf, attributes) =
+            Attribute::parse_table(buf, attributes_count, constant_pool, context)?;
 
         Ok((
             buf,
@@ -286,9 +1091,415 @@ impl Code {
             },
         ))
     }
+
+    #[cfg(feature = "write")]
+    fn write(&self, out: &mut Vec<u8>, constant_pool: &[ConstantPool]) -> io::Result<()> {
+        out.put_u16(self.max_stack);
+        out.put_u16(self.max_locals);
+        let code_length = u32::try_from(self.code.len())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "code too long"))?;
+        out.put_u32(code_length);
+        out.extend_from_slice(&self.code);
+        out.put_count(self.exception_table.len(), "exception table")?;
+        self.exception_table
+            .iter()
+            .for_each(|exception| exception.write(out));
+        Attribute::write_all(&self.attributes, out, constant_pool)
+    }
+}
+
+impl Code {
+    /// The exception table with the caught classes looked up in
+    /// `constant_pool`.
+    pub fn resolved_exception_table<'a>(
+        &'a self,
+        constant_pool: &'a [ConstantPool],
+    ) -> Vec<ResolvedExceptionHandler<'a>> {
+        self.exception_table
+            .iter()
+            .map(|exception| ResolvedExceptionHandler {
+                start_pc: exception.start_pc,
+                end_pc: exception.end_pc,
+                handler_pc: exception.handler_pc,
+                catch_type: match exception.catch_type {
+                    0 => None,
+                    index => {
+                        Some(ConstantPool::class_name(constant_pool, index).unwrap_or_default())
+                    }
+                },
+            })
+            .collect()
+    }
+
+    /// What's wrong with the ranges of the exception table: empty when
+    /// every handler covers some code and starts within it.
+    pub fn validate_exception_table(&self) -> Vec<ExceptionTableIssue> {
+        let code_length = self.code.len();
+        let mut issues = Vec::new();
+        for (index, exception) in self.exception_table.iter().enumerate() {
+            if exception.start_pc >= exception.end_pc {
+                issues.push(ExceptionTableIssue::EmptyRange {
+                    index,
+                    start_pc: exception.start_pc,
+                    end_pc: exception.end_pc,
+                });
+            }
+            if exception.end_pc as usize > code_length {
+                issues.push(ExceptionTableIssue::RangePastEnd {
+                    index,
+                    end_pc: exception.end_pc,
+                    code_length,
+                });
+            }
+            if exception.handler_pc as usize >= code_length {
+                issues.push(ExceptionTableIssue::HandlerPastEnd {
+                    index,
+                    handler_pc: exception.handler_pc,
+                    code_length,
+                });
+            }
+        }
+        issues
+    }
+
+    /// The `LineNumberTable` nested in this attribute, if any.
+    pub fn line_number_table(&self) -> Option<&[LineNumber]> {
+        self.attributes
+            .iter()
+            .find_map(|attribute| match attribute {
+                Attribute::LineNumberTable(table) => Some(table.as_slice()),
+                _ => None,
+            })
+    }
+
+    /// The source line of the instruction at `pc`: that of the entry with
+    /// the greatest `start_pc` at or before it, among the entries of every
+    /// `LineNumberTable` nested in this attribute, which needn't be in
+    /// order. `None` if no entry starts at or before `pc`.
+    pub fn line_number_for_pc(&self, pc: u16) -> Option<u16> {
+        self.attributes
+            .iter()
+            .filter_map(|attribute| match attribute {
+                Attribute::LineNumberTable(table) => Some(table),
+                _ => None,
+            })
+            .flatten()
+            .filter(|line| line.start_pc <= pc)
+            .max_by_key(|line| line.start_pc)
+            .map(|line| line.line_number)
+    }
+
+    /// The `LocalVariableTable` nested in this attribute, if any.
+    pub fn local_variable_table(&self) -> Option<&[LocalVariable]> {
+        self.attributes
+            .iter()
+            .find_map(|attribute| match attribute {
+                Attribute::LocalVariableTable(table) => Some(table.as_slice()),
+                _ => None,
+            })
+    }
+
+    /// The local variable in slot `index` at `pc`, from the entry of a
+    /// `LocalVariableTable` whose range holds `pc`, with the generic type
+    /// the matching entry of a `LocalVariableTypeTable` gives, if any. When
+    /// the slot is reused, entries for different scopes don't overlap; in a
+    /// malformed table which has them overlap, the one starting last wins.
+    /// `None` when no entry holds the slot at `pc` or its name or
+    /// descriptor can't be looked up.
+    pub fn local_variable_at<'a>(
+        &'a self,
+        index: u16,
+        pc: u16,
+        constant_pool: &'a [ConstantPool],
+    ) -> Option<ResolvedLocalVariable<'a>> {
+        let live = |start_pc: u16, length: u16| {
+            start_pc <= pc && u32::from(pc) < u32::from(start_pc) + u32::from(length)
+        };
+        let variable = self
+            .attributes
+            .iter()
+            .filter_map(|attribute| match attribute {
+                Attribute::LocalVariableTable(table) => Some(table),
+                _ => None,
+            })
+            .flatten()
+            .filter(|variable| variable.index == index && live(variable.start_pc, variable.length))
+            .max_by_key(|variable| variable.start_pc)?;
+        let signature = self
+            .attributes
+            .iter()
+            .filter_map(|attribute| match attribute {
+                Attribute::LocalVariableTypeTable(table) => Some(table),
+                _ => None,
+            })
+            .flatten()
+            .find(|typed| {
+                typed.index == index
+                    && typed.start_pc == variable.start_pc
+                    && typed.length == variable.length
+            })
+            .and_then(|typed| ConstantPool::utf8(constant_pool, typed.signature_index));
+        Some(ResolvedLocalVariable {
+            index,
+            start_pc: variable.start_pc,
+            length: variable.length,
+            name: ConstantPool::utf8(constant_pool, variable.name_index)?,
+            descriptor: ConstantPool::utf8(constant_pool, variable.descriptor_index)?,
+            signature,
+        })
+    }
+
+    /// The `LocalVariableTypeTable` nested in this attribute, if any.
+    pub fn local_variable_type_table(&self) -> Option<&[LocalVariableType]> {
+        self.attributes
+            .iter()
+            .find_map(|attribute| match attribute {
+                Attribute::LocalVariableTypeTable(table) => Some(table.as_slice()),
+                _ => None,
+            })
+    }
+
+    /// The `StackMapTable` nested in this attribute, if any.
+    pub fn stack_map_table(&self) -> Option<&StackMapTable> {
+        self.attributes
+            .iter()
+            .find_map(|attribute| match attribute {
+                Attribute::StackMapTable(table) => Some(table),
+                _ => None,
+            })
+    }
+
+    /// Type annotations on instructions, local variables, casts and the like
+    /// within this method body.
+    pub fn type_annotations(&self) -> Option<TypeAnnotations<'_>> {
+        TypeAnnotations::from_attributes(&self.attributes)
+    }
+
+    /// The size of every instruction and where the method's bytes go.
+    #[cfg(feature = "disasm")]
+    pub fn layout(&self) -> Result<CodeLayout, DecodeError> {
+        CodeLayout::build(self)
+    }
+
+    /// What's wrong with the frames of the `StackMapTable`, checked against
+    /// the bytecode and `constant_pool`: empty when nothing is, or there is
+    /// no table. Not knowing the method, local counts are only checked from
+    /// the first full frame on; [`MethodInfo::validate_stack_map`] checks
+    /// them all.
+    ///
+    /// [`MethodInfo::validate_stack_map`]: crate::methodinfo::MethodInfo::validate_stack_map
+    #[cfg(feature = "disasm")]
+    pub fn validate_stack_map(&self, constant_pool: &[ConstantPool]) -> Vec<StackMapIssue> {
+        stackmap::validate(self, constant_pool, None)
+    }
+
+    /// Every place in the method body where `pattern` matches.
+    #[cfg(feature = "analysis")]
+    pub fn find_pattern(
+        &self,
+        pattern: &Pattern,
+        constant_pool: &[ConstantPool],
+    ) -> Result<Vec<PatternMatch>, DecodeError> {
+        pattern.find(self, constant_pool)
+    }
+
+    /// The `synchronized` blocks and try-with-resources bodies of the
+    /// method, as [`regions::find_regions`] recovers them. `frames` type the
+    /// locks, and may be empty.
+    #[cfg(feature = "analysis")]
+    pub fn regions(
+        &self,
+        constant_pool: &[ConstantPool],
+        frames: &BTreeMap<u32, Frame>,
+    ) -> Result<Vec<Region>, DecodeError> {
+        regions::find_regions(self, constant_pool, frames)
+    }
+
+    /// Catch-all handlers, i.e. those with no catch type or catching
+    /// `java/lang/Throwable`, which appear to swallow what they catch. A
+    /// handler is flagged when its blocks contain no `athrow` and no call to
+    /// a method whose name contains `throw`. Its blocks are those reachable
+    /// from the handler entry that the method entry can't reach without an
+    /// exception being thrown. Code which fails to decode yields nothing.
+    #[cfg(feature = "analysis")]
+    pub fn suspicious_handlers(&self, constant_pool: &[ConstantPool]) -> Vec<SuspiciousHandler> {
+        self.suspicious_handlers_within(constant_pool, &AnalysisBudget::unlimited())
+            .into_value()
+    }
+
+    /// [`Code::suspicious_handlers`] unless it takes more than `budget`
+    /// allows, every block of a handler looked through counting as a state.
+    /// Cut short, it holds the handlers flagged by then.
+    #[cfg(feature = "analysis")]
+    pub fn suspicious_handlers_within(
+        &self,
+        constant_pool: &[ConstantPool],
+        budget: &AnalysisBudget,
+    ) -> Partial<Vec<SuspiciousHandler>> {
+        let meter = &mut budget.meter();
+        let cfg = match ControlFlowGraph::build_metered(self, meter) {
+            Ok(Partial {
+                value,
+                exceeded: None,
+            }) => value,
+            Ok(Partial {
+                exceeded: Some(exceeded),
+                ..
+            }) => return Partial::cut_short(Vec::new(), exceeded),
+            Err(_) => return Partial::complete(Vec::new()),
+        };
+        let normal = cfg.reachable(0);
+        // already decoded, and charged for, building the graph
+        let instructions: Vec<_> = instruction::decode(&self.code).flatten().collect();
+
+        let mut suspicious = Vec::new();
+        for (index, exception) in self.exception_table.iter().enumerate() {
+            let caught = match exception.catch_type {
+                0 => "any exception",
+                catch_type => match ConstantPool::class_name(constant_pool, catch_type) {
+                    Some("java/lang/Throwable") => "java/lang/Throwable",
+                    _ => continue,
+                },
+            };
+            let root = match cfg.block_at(exception.handler_pc as u32) {
+                Some(root) => root,
+                None => continue,
+            };
+            let blocks = cfg
+                .reachable(root)
+                .into_iter()
+                .filter(|&block| block == root || !normal.contains(&block));
+
+            let (mut rethrows, mut calls) = (false, false);
+            for block in blocks {
+                if let Err(exceeded) = meter.states(1) {
+                    return Partial::cut_short(suspicious, exceeded);
+                }
+                let block = &cfg.blocks[block];
+                for instruction in instructions
+                    .iter()
+                    .filter(|i| block.start <= i.offset && i.offset < block.end)
+                {
+                    if instruction.opcode == 0xbf {
+                        rethrows = true;
+                    } else if instruction.is_invoke() {
+                        calls = true;
+                        let name = instruction
+                            .cp_index()
+                            .and_then(|index| ConstantPool::member_ref(constant_pool, index))
+                            .map(|member| member.name.to_lowercase());
+                        rethrows |= name.is_some_and(|name| name.contains("throw"));
+                    }
+                }
+            }
+            if rethrows {
+                continue;
+            }
+            let reason = if calls {
+                format!(
+                    "catches {} and neither rethrows nor calls a throwing method",
+                    caught
+                )
+            } else {
+                format!("catches {} and discards it", caught)
+            };
+            suspicious.push(SuspiciousHandler { index, reason });
+        }
+        Partial::complete(suspicious)
+    }
+}
+
+/// An exception handler flagged by [`Code::suspicious_handlers`].
+#[cfg(feature = "analysis")]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SuspiciousHandler {
+    /// The handler's index in the exception table.
+    pub index: usize,
+    pub reason: String,
 }
 
-#[derive(Debug)]
+/// An entry of a [`Code`] exception table with its caught class looked up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResolvedExceptionHandler<'a> {
+    pub start_pc: u16,
+    /// Exclusive.
+    pub end_pc: u16,
+    pub handler_pc: u16,
+    /// The internal name of the class caught, or `None` for a handler of
+    /// any exception, as a `finally` block compiles to. Empty when it
+    /// can't be resolved.
+    pub catch_type: Option<&'a str>,
+}
+
+impl ResolvedExceptionHandler<'_> {
+    /// Whether the handler catches any exception, rather than instances
+    /// of one class.
+    pub fn is_catch_all(&self) -> bool {
+        self.catch_type.is_none()
+    }
+}
+
+/// An entry of a [`Code`] exception table whose pcs don't fit the code,
+/// found by [`Code::validate_exception_table`]. `index` is the entry's
+/// index in the table.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ExceptionTableIssue {
+    /// The range covers no code, its start being at or past its end.
+    EmptyRange {
+        index: usize,
+        start_pc: u16,
+        end_pc: u16,
+    },
+    /// The range ends past the end of the code.
+    RangePastEnd {
+        index: usize,
+        end_pc: u16,
+        code_length: usize,
+    },
+    /// The handler starts at or past the end of the code.
+    HandlerPastEnd {
+        index: usize,
+        handler_pc: u16,
+        code_length: usize,
+    },
+}
+
+impl fmt::Display for ExceptionTableIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExceptionTableIssue::EmptyRange {
+                index,
+                start_pc,
+                end_pc,
+            } => write!(
+                f,
+                "handler {}: range {}..{} is empty",
+                index, start_pc, end_pc
+            ),
+            ExceptionTableIssue::RangePastEnd {
+                index,
+                end_pc,
+                code_length,
+            } => write!(
+                f,
+                "handler {}: range ends at {}, past the {} bytes of code",
+                index, end_pc, code_length
+            ),
+            ExceptionTableIssue::HandlerPastEnd {
+                index,
+                handler_pc,
+                code_length,
+            } => write!(
+                f,
+                "handler {}: handler at {} is outside the {} bytes of code",
+                index, handler_pc, code_length
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Exception {
     pub start_pc: u16,
     pub end_pc: u16,
@@ -313,49 +1524,92 @@ impl Exception {
             },
         ))
     }
+
+    #[cfg(feature = "write")]
+    fn write(&self, out: &mut Vec<u8>) {
+        out.put_u16(self.start_pc);
+        out.put_u16(self.end_pc);
+        out.put_u16(self.handler_pc);
+        out.put_u16(self.catch_type);
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct StackMapTable {
     pub entries: Vec<StackMapFrame>,
 }
 
 impl StackMapTable {
-    fn parse<'a>(buf: &'a [u8]) -> IResult<&'a [u8], Self> {
+    fn parse(buf: &[u8]) -> IResult<&[u8], Self> {
         let (buf, number_of_entries) = be_u16(buf)?;
-        let (buf, entries) = count(StackMapFrame::parse, number_of_entries as usize)(buf)?;
+        let (buf, entries) =
+            count_within(StackMapFrame::parse, number_of_entries as usize, 1, "entry")(buf)?;
 
         Ok((buf, StackMapTable { entries }))
     }
+
+    #[cfg(feature = "write")]
+    fn write(&self, out: &mut Vec<u8>) -> io::Result<()> {
+        out.put_count(self.entries.len(), "StackMapTable")?;
+        for frame in &self.entries {
+            frame.write(out)?;
+        }
+        Ok(())
+    }
 }
 
-#[derive(Debug)]
+/// A frame of a `StackMapTable`. Each variant is one form of the frame
+/// encoding, so a parsed frame is written back with exactly the frame type
+/// it was read with, even where a more compact form would do; frames built
+/// for new code should come from [`StackMapFrame::compact`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum StackMapFrame {
-    SameFrame,
-    SameLocals1StackItemFrame(VerificationTypeInfo),
-    SameLocals1StackItemFrameExtended(u16, VerificationTypeInfo),
-    ChopFrame(u16),
+    /// The offset delta, which is also the frame type.
+    SameFrame(u8),
+    SameLocals1StackItemFrame {
+        /// Stored in the frame type as `64 + offset_delta`.
+        offset_delta: u8,
+        stack: VerificationTypeInfo,
+    },
+    SameLocals1StackItemFrameExtended {
+        offset_delta: u16,
+        stack: VerificationTypeInfo,
+    },
+    ChopFrame {
+        /// The number of locals chopped, stored in the frame type as
+        /// `251 - chopped`.
+        chopped: u8,
+        offset_delta: u16,
+    },
     SameFrameExtended(u16),
-    AppendFrame(u16, Vec<VerificationTypeInfo>),
-    FullFrame(
-        u16,
-        u16,
-        Vec<VerificationTypeInfo>,
-        u16,
-        Vec<VerificationTypeInfo>,
-    ),
+    AppendFrame {
+        offset_delta: u16,
+        locals: Vec<VerificationTypeInfo>,
+    },
+    /// The counts of locals and stack items are those of `locals` and
+    /// `stack`.
+    FullFrame {
+        offset_delta: u16,
+        locals: Vec<VerificationTypeInfo>,
+        stack: Vec<VerificationTypeInfo>,
+    },
 }
 
 impl StackMapFrame {
-    fn parse<'a>(buf: &'a [u8]) -> IResult<&'a [u8], Self> {
-        let (buf, frame_type) = u8(buf)?;
+    fn parse(input: &[u8]) -> IResult<&[u8], Self> {
+        let (buf, frame_type) = u8(input)?;
         match frame_type {
-            0..=63 => Ok((buf, StackMapFrame::SameFrame)),
+            0..=63 => Ok((buf, StackMapFrame::SameFrame(frame_type))),
             64..=127 => {
                 let (buf, verification_type_info) = VerificationTypeInfo::parse(buf)?;
                 Ok((
                     buf,
-                    StackMapFrame::SameLocals1StackItemFrame(verification_type_info),
+                    StackMapFrame::SameLocals1StackItemFrame {
+                        offset_delta: frame_type - 64,
+                        stack: verification_type_info,
+                    },
                 ))
             }
             247 => {
@@ -363,52 +1617,188 @@ impl StackMapFrame {
                 let (buf, verification_type_info) = VerificationTypeInfo::parse(buf)?;
                 Ok((
                     buf,
-                    StackMapFrame::SameLocals1StackItemFrameExtended(
+                    StackMapFrame::SameLocals1StackItemFrameExtended {
                         offset_delta,
-                        verification_type_info,
-                    ),
+                        stack: verification_type_info,
+                    },
                 ))
             }
             248..=250 => {
                 let (buf, offset_delta) = be_u16(buf)?;
-                Ok((buf, StackMapFrame::ChopFrame(offset_delta)))
+                Ok((
+                    buf,
+                    StackMapFrame::ChopFrame {
+                        chopped: 251 - frame_type,
+                        offset_delta,
+                    },
+                ))
             }
             251 => {
                 let (buf, offset_delta) = be_u16(buf)?;
                 Ok((buf, StackMapFrame::SameFrameExtended(offset_delta)))
             }
-            x if (242..=254).contains(&x) => {
-                dbg!(x);
+            252..=254 => {
                 let (buf, offset_delta) = be_u16(buf)?;
-                dbg!(buf, offset_delta);
-                let (buf, locals) = count(VerificationTypeInfo::parse, x as usize - 251)(buf)?;
-                Ok((buf, StackMapFrame::AppendFrame(offset_delta, locals)))
+                let (buf, locals) =
+                    count(VerificationTypeInfo::parse, frame_type as usize - 251, 1)(buf)?;
+                Ok((
+                    buf,
+                    StackMapFrame::AppendFrame {
+                        offset_delta,
+                        locals,
+                    },
+                ))
             }
             255 => {
                 let (buf, offset_delta) = be_u16(buf)?;
                 let (buf, number_of_locals) = be_u16(buf)?;
                 let (buf, locals) =
-                    count(VerificationTypeInfo::parse, number_of_locals as usize)(buf)?;
+                    count(VerificationTypeInfo::parse, number_of_locals as usize, 1)(buf)?;
                 let (buf, number_of_stack_items) = be_u16(buf)?;
-                let (buf, stack) =
-                    count(VerificationTypeInfo::parse, number_of_stack_items as usize)(buf)?;
+                let (buf, stack) = count(
+                    VerificationTypeInfo::parse,
+                    number_of_stack_items as usize,
+                    1,
+                )(buf)?;
                 Ok((
                     buf,
-                    StackMapFrame::FullFrame(
+                    StackMapFrame::FullFrame {
                         offset_delta,
-                        number_of_locals,
                         locals,
-                        number_of_stack_items,
                         stack,
-                    ),
+                    },
                 ))
             }
-            _ => unimplemented!(),
+            // 128..=246 are reserved.
+            _ => Err(ErrorAt::failure(
+                input,
+                ParseError::UnknownTag {
+                    structure: "stack map frame",
+                    tag: frame_type,
+                },
+            )),
         }
     }
+
+    /// The compactest frame which takes the locals from `previous_locals`
+    /// to `locals` with `stack` on the operand stack, as javac would write
+    /// it. Locals are given as in frames, with a single entry for a long or
+    /// double.
+    pub fn compact(
+        offset_delta: u16,
+        previous_locals: &[VerificationTypeInfo],
+        locals: &[VerificationTypeInfo],
+        stack: &[VerificationTypeInfo],
+    ) -> Self {
+        let short_delta = u8::try_from(offset_delta).ok().filter(|&delta| delta < 64);
+        let same_locals = locals == previous_locals;
+        match stack {
+            [] if same_locals => match short_delta {
+                Some(delta) => StackMapFrame::SameFrame(delta),
+                None => StackMapFrame::SameFrameExtended(offset_delta),
+            },
+            [item] if same_locals => match short_delta {
+                Some(delta) => StackMapFrame::SameLocals1StackItemFrame {
+                    offset_delta: delta,
+                    stack: item.clone(),
+                },
+                None => StackMapFrame::SameLocals1StackItemFrameExtended {
+                    offset_delta,
+                    stack: item.clone(),
+                },
+            },
+            [] if locals.len() > previous_locals.len()
+                && locals.len() - previous_locals.len() <= 3
+                && locals.starts_with(previous_locals) =>
+            {
+                StackMapFrame::AppendFrame {
+                    offset_delta,
+                    locals: locals[previous_locals.len()..].to_vec(),
+                }
+            }
+            [] if previous_locals.len() > locals.len()
+                && previous_locals.len() - locals.len() <= 3
+                && previous_locals.starts_with(locals) =>
+            {
+                StackMapFrame::ChopFrame {
+                    chopped: (previous_locals.len() - locals.len()) as u8,
+                    offset_delta,
+                }
+            }
+            _ => StackMapFrame::FullFrame {
+                offset_delta,
+                locals: locals.to_vec(),
+                stack: stack.to_vec(),
+            },
+        }
+    }
+
+    /// The offset delta, whichever way the frame stores it.
+    pub fn offset_delta(&self) -> u16 {
+        match self {
+            StackMapFrame::SameFrame(offset_delta)
+            | StackMapFrame::SameLocals1StackItemFrame { offset_delta, .. } => *offset_delta as u16,
+            StackMapFrame::SameLocals1StackItemFrameExtended { offset_delta, .. }
+            | StackMapFrame::ChopFrame { offset_delta, .. }
+            | StackMapFrame::SameFrameExtended(offset_delta)
+            | StackMapFrame::AppendFrame { offset_delta, .. }
+            | StackMapFrame::FullFrame { offset_delta, .. } => *offset_delta,
+        }
+    }
+
+    /// The `frame_type` byte this frame is written with.
+    pub fn frame_type(&self) -> u8 {
+        match self {
+            StackMapFrame::SameFrame(offset_delta) => *offset_delta,
+            StackMapFrame::SameLocals1StackItemFrame { offset_delta, .. } => 64 + offset_delta,
+            StackMapFrame::SameLocals1StackItemFrameExtended { .. } => 247,
+            StackMapFrame::ChopFrame { chopped, .. } => 251 - chopped,
+            StackMapFrame::SameFrameExtended(_) => 251,
+            StackMapFrame::AppendFrame { locals, .. } => 251 + locals.len() as u8,
+            StackMapFrame::FullFrame { .. } => 255,
+        }
+    }
+
+    #[cfg(feature = "write")]
+    fn write(&self, out: &mut Vec<u8>) -> io::Result<()> {
+        out.put_u8(self.frame_type());
+        match self {
+            StackMapFrame::SameFrame(_) => {}
+            StackMapFrame::SameLocals1StackItemFrame { stack, .. } => stack.write(out),
+            StackMapFrame::SameLocals1StackItemFrameExtended {
+                offset_delta,
+                stack,
+            } => {
+                out.put_u16(*offset_delta);
+                stack.write(out);
+            }
+            StackMapFrame::ChopFrame { offset_delta, .. }
+            | StackMapFrame::SameFrameExtended(offset_delta) => out.put_u16(*offset_delta),
+            StackMapFrame::AppendFrame {
+                offset_delta,
+                locals,
+            } => {
+                out.put_u16(*offset_delta);
+                locals.iter().for_each(|local| local.write(out));
+            }
+            StackMapFrame::FullFrame {
+                offset_delta,
+                locals,
+                stack,
+            } => {
+                out.put_u16(*offset_delta);
+                out.put_count(locals.len(), "full_frame locals")?;
+                locals.iter().for_each(|local| local.write(out));
+                out.put_count(stack.len(), "full_frame stack")?;
+                stack.iter().for_each(|item| item.write(out));
+            }
+        }
+        Ok(())
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum VerificationTypeInfo {
     TopVariableInfo,
     IntegerVariableInfo,
@@ -422,8 +1812,8 @@ pub enum VerificationTypeInfo {
 }
 
 impl VerificationTypeInfo {
-    fn parse(buf: &[u8]) -> IResult<&[u8], Self> {
-        let (buf, tag) = u8(buf)?;
+    fn parse(input: &[u8]) -> IResult<&[u8], Self> {
+        let (buf, tag) = u8(input)?;
         match tag {
             0 => Ok((buf, VerificationTypeInfo::TopVariableInfo)),
             1 => Ok((buf, VerificationTypeInfo::IntegerVariableInfo)),
@@ -440,12 +1830,40 @@ impl VerificationTypeInfo {
                 let (buf, offset) = be_u16(buf)?;
                 Ok((buf, VerificationTypeInfo::UninitializedVariableInfo(offset)))
             }
-            _ => unimplemented!(),
+            _ => Err(ErrorAt::failure(
+                input,
+                ParseError::UnknownTag {
+                    structure: "verification type",
+                    tag,
+                },
+            )),
+        }
+    }
+
+    #[cfg(feature = "write")]
+    fn write(&self, out: &mut Vec<u8>) {
+        match self {
+            VerificationTypeInfo::TopVariableInfo => out.put_u8(0),
+            VerificationTypeInfo::IntegerVariableInfo => out.put_u8(1),
+            VerificationTypeInfo::FloatVariableInfo => out.put_u8(2),
+            VerificationTypeInfo::DoubleVariableInfo => out.put_u8(3),
+            VerificationTypeInfo::LongVariableInfo => out.put_u8(4),
+            VerificationTypeInfo::NullVariableInfo => out.put_u8(5),
+            VerificationTypeInfo::UninitializedThisVariableInfo => out.put_u8(6),
+            VerificationTypeInfo::ObjectVariableInfo(cpool_index) => {
+                out.put_u8(7);
+                out.put_u16(*cpool_index);
+            }
+            VerificationTypeInfo::UninitializedVariableInfo(offset) => {
+                out.put_u8(8);
+                out.put_u16(*offset);
+            }
         }
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Exceptions {
     pub exception_index_table: Vec<u16>,
 }
@@ -453,7 +1871,7 @@ pub struct Exceptions {
 impl Exceptions {
     fn parse(buf: &[u8]) -> IResult<&[u8], Self> {
         let (buf, number_of_exceptions) = be_u16(buf)?;
-        let (buf, exception_index_table) = count(be_u16, number_of_exceptions as usize)(buf)?;
+        let (buf, exception_index_table) = count(be_u16, number_of_exceptions as usize, 2)(buf)?;
         Ok((
             buf,
             Exceptions {
@@ -461,28 +1879,63 @@ impl Exceptions {
             },
         ))
     }
+
+    #[cfg(feature = "write")]
+    fn write(&self, out: &mut Vec<u8>) -> io::Result<()> {
+        write_indices(out, &self.exception_index_table, "Exceptions")
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct InnerClasses {
     pub classes: Vec<InnerClass>,
 }
 
 impl InnerClasses {
+    /// The entries with their names looked up. Names which can't be
+    /// resolved are empty.
+    pub fn resolved<'a>(
+        &'a self,
+        constant_pool: &'a [ConstantPool],
+    ) -> Vec<ResolvedInnerClass<'a>> {
+        let optional = |index: u16, lookup: fn(&'a [ConstantPool], u16) -> Option<&'a str>| {
+            (index != 0).then(|| lookup(constant_pool, index).unwrap_or_default())
+        };
+        self.classes
+            .iter()
+            .map(|class| ResolvedInnerClass {
+                inner_class: ConstantPool::class_name(constant_pool, class.inner_class_info_index)
+                    .unwrap_or_default(),
+                outer_class: optional(class.outer_class_info_index, ConstantPool::class_name),
+                inner_name: optional(class.inner_name_index, ConstantPool::utf8),
+                access_flags: class.inner_class_access_flags,
+            })
+            .collect()
+    }
+
     fn parse(buf: &[u8]) -> IResult<&[u8], Self> {
         let (buf, number_of_classes) = be_u16(buf)?;
-        let (buf, classes) = count(InnerClass::parse, number_of_classes as usize)(buf)?;
+        let (buf, classes) = count(InnerClass::parse, number_of_classes as usize, 8)(buf)?;
 
         Ok((buf, InnerClasses { classes }))
     }
+
+    #[cfg(feature = "write")]
+    fn write(&self, out: &mut Vec<u8>) -> io::Result<()> {
+        out.put_count(self.classes.len(), "InnerClasses")?;
+        self.classes.iter().for_each(|class| class.write(out));
+        Ok(())
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct InnerClass {
     pub inner_class_info_index: u16,
     pub outer_class_info_index: u16,
     pub inner_name_index: u16,
-    pub inner_class_access_flags: u16,
+    pub inner_class_access_flags: ClassAccessFlags,
 }
 
 impl InnerClass {
@@ -498,19 +1951,42 @@ impl InnerClass {
                 inner_class_info_index,
                 outer_class_info_index,
                 inner_name_index,
-                inner_class_access_flags,
+                inner_class_access_flags: ClassAccessFlags::new(inner_class_access_flags),
             },
         ))
     }
+
+    #[cfg(feature = "write")]
+    fn write(&self, out: &mut Vec<u8>) {
+        out.put_u16(self.inner_class_info_index);
+        out.put_u16(self.outer_class_info_index);
+        out.put_u16(self.inner_name_index);
+        out.put_u16(self.inner_class_access_flags.bits());
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct EnclosingMethod {
     pub class_index: u16,
     pub method_index: u16,
 }
 
 impl EnclosingMethod {
+    /// The enclosing class and method with their names looked up. Names
+    /// which can't be resolved are empty.
+    pub fn resolved<'a>(&self, constant_pool: &'a [ConstantPool]) -> ResolvedEnclosingMethod<'a> {
+        let method = (self.method_index != 0).then(|| {
+            ConstantPool::name_and_type(constant_pool, self.method_index).unwrap_or_default()
+        });
+        ResolvedEnclosingMethod {
+            class_name: ConstantPool::class_name(constant_pool, self.class_index)
+                .unwrap_or_default(),
+            method_name: method.map(|(name, _)| name),
+            method_descriptor: method.map(|(_, descriptor)| descriptor),
+        }
+    }
+
     fn parse(buf: &[u8]) -> IResult<&[u8], Self> {
         let (buf, class_index) = be_u16(buf)?;
         let (buf, method_index) = be_u16(buf)?;
@@ -523,9 +1999,16 @@ impl EnclosingMethod {
             },
         ))
     }
+
+    #[cfg(feature = "write")]
+    fn write(&self, out: &mut Vec<u8>) {
+        out.put_u16(self.class_index);
+        out.put_u16(self.method_index);
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Signature {
     pub signature_index: u16,
 }
@@ -536,9 +2019,30 @@ impl Signature {
 
         Ok((buf, Signature { signature_index }))
     }
+
+    /// The signature, looked up in the class's constant pool.
+    pub fn signature<'a>(&self, constant_pool: &'a [ConstantPool]) -> Option<&'a str> {
+        ConstantPool::utf8(constant_pool, self.signature_index)
+    }
+
+    /// The signature, parsed as what its shape says it is, see
+    /// [`ParsedSignature::parse`]. `None` if `signature_index` isn't a UTF8
+    /// entry.
+    pub fn parse_signature(
+        &self,
+        constant_pool: &[ConstantPool],
+    ) -> Option<Result<ParsedSignature, SignatureError>> {
+        self.signature(constant_pool).map(ParsedSignature::parse)
+    }
+
+    #[cfg(feature = "write")]
+    fn write(&self, out: &mut Vec<u8>) {
+        out.put_u16(self.signature_index);
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SourceFile {
     pub sourcefile_index: u16,
 }
@@ -549,9 +2053,15 @@ impl SourceFile {
 
         Ok((buf, SourceFile { sourcefile_index }))
     }
+
+    #[cfg(feature = "write")]
+    fn write(&self, out: &mut Vec<u8>) {
+        out.put_u16(self.sourcefile_index);
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LineNumber {
     pub start_pc: u16,
     pub line_number: u16,
@@ -570,9 +2080,16 @@ impl LineNumber {
             },
         ))
     }
+
+    #[cfg(feature = "write")]
+    fn write(&self, out: &mut Vec<u8>) {
+        out.put_u16(self.start_pc);
+        out.put_u16(self.line_number);
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LocalVariable {
     pub start_pc: u16,
     pub length: u16,
@@ -600,9 +2117,19 @@ impl LocalVariable {
             },
         ))
     }
+
+    #[cfg(feature = "write")]
+    fn write(&self, out: &mut Vec<u8>) {
+        out.put_u16(self.start_pc);
+        out.put_u16(self.length);
+        out.put_u16(self.name_index);
+        out.put_u16(self.descriptor_index);
+        out.put_u16(self.index);
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LocalVariableType {
     pub start_pc: u16,
     pub length: u16,
@@ -630,22 +2157,34 @@ impl LocalVariableType {
             },
         ))
     }
+
+    #[cfg(feature = "write")]
+    fn write(&self, out: &mut Vec<u8>) {
+        out.put_u16(self.start_pc);
+        out.put_u16(self.length);
+        out.put_u16(self.name_index);
+        out.put_u16(self.signature_index);
+        out.put_u16(self.index);
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Annotation {
     pub type_index: u16,
     pub element_value_pairs: Vec<(u16, ElementValue)>,
 }
 
 impl Annotation {
-    fn parse(buf: &[u8]) -> IResult<&[u8], Self> {
+    /// Parses an annotation whose element values may nest `levels` deep.
+    fn parse(buf: &[u8], levels: usize) -> IResult<&[u8], Self> {
         let (buf, type_index) = be_u16(buf)?;
         let (mut buf, num_element_value_pairs) = be_u16(buf)?;
-        let mut element_value_pairs = Vec::with_capacity(num_element_value_pairs as usize);
+        let mut element_value_pairs =
+            Vec::with_capacity(error::capacity(buf, num_element_value_pairs as usize, 5)?);
         for _ in 0..num_element_value_pairs {
             let (temp_buf, element_name_index) = be_u16(buf)?;
-            let (temp_buf, element_value) = ElementValue::parse(temp_buf)?;
+            let (temp_buf, element_value) = ElementValue::parse(temp_buf, levels)?;
             buf = temp_buf;
             element_value_pairs.push((element_name_index, element_value));
         }
@@ -658,25 +2197,61 @@ impl Annotation {
             },
         ))
     }
+
+    #[cfg(feature = "write")]
+    fn write(&self, out: &mut Vec<u8>) -> io::Result<()> {
+        out.put_u16(self.type_index);
+        write_element_value_pairs(out, &self.element_value_pairs)
+    }
+
+    #[cfg(feature = "disasm")]
+    fn visit_indices(&self, visit: &mut dyn FnMut(u16, Utf8Roles)) {
+        visit(self.type_index, Utf8Roles::DESCRIPTOR);
+        visit_element_value_pairs(&self.element_value_pairs, visit);
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ElementValue {
-    ConstValue(u16),
-    EnumConstValue(u16, u16),
+    ConstValue {
+        /// The tag character: `B`, `C`, `D`, `F`, `I`, `J`, `S`, `Z` or `s`.
+        tag: u8,
+        const_value_index: u16,
+    },
+    EnumConstValue {
+        type_name_index: u16,
+        const_name_index: u16,
+    },
     ClassInfoIndex(u16),
     AnnotationValue(Annotation),
     ArrayValue(Vec<ElementValue>),
 }
 
 impl ElementValue {
-    fn parse(buf: &[u8]) -> IResult<&[u8], Self> {
-        let (buf, tag) = u8(buf)?;
+    /// Parses an element value which may nest `levels` deep, itself
+    /// included.
+    fn parse(input: &[u8], levels: usize) -> IResult<&[u8], Self> {
+        if levels == 0 {
+            return Err(ErrorAt::failure(
+                input,
+                ParseError::NestingTooDeep {
+                    structure: "element value",
+                },
+            ));
+        }
+        let (buf, tag) = u8(input)?;
         match tag {
             // B | C | D | F | I | J | S | Z | s
             0x42 | 0x43 | 0x44 | 0x46 | 0x49 | 0x4A | 0x53 | 0x5A | 0x73 => {
                 let (buf, const_value_index) = be_u16(buf)?;
-                Ok((buf, ElementValue::ConstValue(const_value_index)))
+                Ok((
+                    buf,
+                    ElementValue::ConstValue {
+                        tag,
+                        const_value_index,
+                    },
+                ))
             }
             // e
             0x65 => {
@@ -684,7 +2259,10 @@ impl ElementValue {
                 let (buf, const_name_index) = be_u16(buf)?;
                 Ok((
                     buf,
-                    ElementValue::EnumConstValue(type_name_index, const_name_index),
+                    ElementValue::EnumConstValue {
+                        type_name_index,
+                        const_name_index,
+                    },
                 ))
             }
             // c
@@ -694,54 +2272,174 @@ impl ElementValue {
             }
             // @
             0x40 => {
-                let (buf, annotation) = Annotation::parse(buf)?;
+                let (buf, annotation) = Annotation::parse(buf, levels - 1)?;
                 Ok((buf, ElementValue::AnnotationValue(annotation)))
             }
             // {
             0x5b => {
                 let (buf, num_values) = be_u16(buf)?;
-                let (buf, array) = count(ElementValue::parse, num_values as usize)(buf)?;
+                let (buf, array) = count(
+                    |buf| ElementValue::parse(buf, levels - 1),
+                    num_values as usize,
+                    3,
+                )(buf)?;
                 Ok((buf, ElementValue::ArrayValue(array)))
             }
-            _ => unimplemented!(),
+            _ => Err(ErrorAt::failure(
+                input,
+                ParseError::UnknownTag {
+                    structure: "element value",
+                    tag,
+                },
+            )),
+        }
+    }
+
+    #[cfg(feature = "write")]
+    fn write(&self, out: &mut Vec<u8>) -> io::Result<()> {
+        match self {
+            ElementValue::ConstValue {
+                tag,
+                const_value_index,
+            } => {
+                out.put_u8(*tag);
+                out.put_u16(*const_value_index);
+            }
+            ElementValue::EnumConstValue {
+                type_name_index,
+                const_name_index,
+            } => {
+                out.put_u8(b'e');
+                out.put_u16(*type_name_index);
+                out.put_u16(*const_name_index);
+            }
+            ElementValue::ClassInfoIndex(class_info_index) => {
+                out.put_u8(b'c');
+                out.put_u16(*class_info_index);
+            }
+            ElementValue::AnnotationValue(annotation) => {
+                out.put_u8(b'@');
+                annotation.write(out)?;
+            }
+            ElementValue::ArrayValue(values) => {
+                out.put_u8(b'[');
+                out.put_count(values.len(), "array element value")?;
+                for value in values {
+                    value.write(out)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "disasm")]
+    fn visit_indices(&self, visit: &mut dyn FnMut(u16, Utf8Roles)) {
+        match self {
+            // the value of a string element is a UTF8 entry, not a String
+            ElementValue::ConstValue {
+                tag: b's',
+                const_value_index,
+            } => visit(*const_value_index, Utf8Roles::STRING_LITERAL),
+            ElementValue::ConstValue {
+                const_value_index, ..
+            } => visit(*const_value_index, Utf8Roles::OTHER),
+            ElementValue::ClassInfoIndex(index) => visit(*index, Utf8Roles::DESCRIPTOR),
+            ElementValue::EnumConstValue {
+                type_name_index,
+                const_name_index,
+            } => {
+                visit(*type_name_index, Utf8Roles::DESCRIPTOR);
+                visit(*const_name_index, Utf8Roles::FIELD_NAME);
+            }
+            ElementValue::AnnotationValue(annotation) => annotation.visit_indices(visit),
+            ElementValue::ArrayValue(values) => {
+                values.iter().for_each(|value| value.visit_indices(visit))
+            }
         }
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ParameterAnnotation {
     pub annotations: Vec<Annotation>,
 }
 
 impl ParameterAnnotation {
-    fn parse(buf: &[u8]) -> IResult<&[u8], Self> {
+    fn parse(buf: &[u8], levels: usize) -> IResult<&[u8], Self> {
         let (buf, num_annotations) = be_u16(buf)?;
-        let (buf, annotations) = count(Annotation::parse, num_annotations as usize)(buf)?;
+        let (buf, annotations) = count(
+            |buf| Annotation::parse(buf, levels),
+            num_annotations as usize,
+            4,
+        )(buf)?;
 
         Ok((buf, ParameterAnnotation { annotations }))
     }
+
+    #[cfg(feature = "write")]
+    fn write(&self, out: &mut Vec<u8>) -> io::Result<()> {
+        out.put_count(self.annotations.len(), "parameter annotations")?;
+        for annotation in &self.annotations {
+            annotation.write(out)?;
+        }
+        Ok(())
+    }
 }
 
-#[derive(Debug)]
+/// The visible and invisible type annotations held by one attribute table.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TypeAnnotations<'a> {
+    pub visible: &'a [TypeAnnotation],
+    pub invisible: &'a [TypeAnnotation],
+}
+
+impl<'a> TypeAnnotations<'a> {
+    /// Collects the `Runtime{Visible,Invisible}TypeAnnotations` attributes
+    /// among `attributes`, or `None` when there are neither.
+    pub fn from_attributes(attributes: &'a [Attribute]) -> Option<Self> {
+        let mut found = None;
+        for attribute in attributes {
+            match attribute {
+                Attribute::RuntimeVisibleTypeAnnotations(annotations) => {
+                    found.get_or_insert_with(Self::default).visible = annotations
+                }
+                Attribute::RuntimeInvisibleTypeAnnotations(annotations) => {
+                    found.get_or_insert_with(Self::default).invisible = annotations
+                }
+                _ => {}
+            }
+        }
+        found
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &'a TypeAnnotation> {
+        self.visible.iter().chain(self.invisible)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TypeAnnotation {
-    target_type: u8,
-    target_info: TargetInfo,
-    target_path: TypePath,
-    type_index: u16,
-    element_value_pairs: Vec<(u16, ElementValue)>,
+    pub target_type: u8,
+    pub target_info: TargetInfo,
+    pub target_path: TypePath,
+    pub type_index: u16,
+    pub element_value_pairs: Vec<(u16, ElementValue)>,
 }
 
 impl TypeAnnotation {
-    fn parse(buf: &[u8]) -> IResult<&[u8], Self> {
+    fn parse(buf: &[u8], levels: usize) -> IResult<&[u8], Self> {
         let (buf, target_type) = u8(buf)?;
         let (buf, target_info) = TargetInfo::parse(buf, target_type)?;
         let (buf, target_path) = TypePath::parse(buf)?;
         let (buf, type_index) = be_u16(buf)?;
         let (mut buf, num_element_value_pairs) = be_u16(buf)?;
-        let mut element_value_pairs = Vec::with_capacity(num_element_value_pairs as usize);
+        let mut element_value_pairs =
+            Vec::with_capacity(error::capacity(buf, num_element_value_pairs as usize, 5)?);
         for _ in 0..num_element_value_pairs {
             let (temp_buf, element_name_index) = be_u16(buf)?;
-            let (temp_buf, element_value) = ElementValue::parse(temp_buf)?;
+            let (temp_buf, element_value) = ElementValue::parse(temp_buf, levels)?;
             buf = temp_buf;
             element_value_pairs.push((element_name_index, element_value));
         }
@@ -757,27 +2455,44 @@ impl TypeAnnotation {
             },
         ))
     }
+
+    #[cfg(feature = "write")]
+    fn write(&self, out: &mut Vec<u8>) -> io::Result<()> {
+        out.put_u8(self.target_type);
+        self.target_info.write(out)?;
+        self.target_path.write(out)?;
+        out.put_u16(self.type_index);
+        write_element_value_pairs(out, &self.element_value_pairs)
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TargetInfo {
     TypeParameter(u8),
     SuperType(u16),
-    TypeParameterBound(u8, u8),
+    TypeParameterBound {
+        type_parameter_index: u8,
+        bound_index: u8,
+    },
     Empty,
     FormalParameter(u8),
     Throws(u16),
     LocalVar(Vec<LocalVar>),
     Catch(u16),
     Offset(u16),
-    TypeArgument(u16, u8),
+    TypeArgument {
+        offset: u16,
+        type_argument_index: u8,
+    },
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LocalVar {
-    start_pc: u16,
-    length: u16,
-    index: u16,
+    pub start_pc: u16,
+    pub length: u16,
+    pub index: u16,
 }
 
 impl LocalVar {
@@ -795,6 +2510,13 @@ impl LocalVar {
             },
         ))
     }
+
+    #[cfg(feature = "write")]
+    fn write(&self, out: &mut Vec<u8>) {
+        out.put_u16(self.start_pc);
+        out.put_u16(self.length);
+        out.put_u16(self.index);
+    }
 }
 
 impl TargetInfo {
@@ -813,10 +2535,13 @@ impl TargetInfo {
                 let (buf, bound_index) = u8(buf)?;
                 Ok((
                     buf,
-                    TargetInfo::TypeParameterBound(type_parameter_index, bound_index),
+                    TargetInfo::TypeParameterBound {
+                        type_parameter_index,
+                        bound_index,
+                    },
                 ))
             }
-            0x13 | 0x14 | 0x15 => Ok((buf, TargetInfo::Empty)),
+            0x13..=0x15 => Ok((buf, TargetInfo::Empty)),
             0x16 => {
                 let (buf, formal_parameter_index) = u8(buf)?;
                 Ok((buf, TargetInfo::FormalParameter(formal_parameter_index)))
@@ -827,28 +2552,75 @@ impl TargetInfo {
             }
             0x40 | 0x41 => {
                 let (buf, table_length) = be_u16(buf)?;
-                let (buf, table) = count(LocalVar::parse, table_length as usize)(buf)?;
+                let (buf, table) = count(LocalVar::parse, table_length as usize, 6)(buf)?;
                 Ok((buf, TargetInfo::LocalVar(table)))
             }
             0x42 => {
                 let (buf, exception_table_index) = be_u16(buf)?;
                 Ok((buf, TargetInfo::Catch(exception_table_index)))
             }
-            0x43 | 0x44 | 0x45 | 0x46 => {
+            0x43..=0x46 => {
                 let (buf, offset) = be_u16(buf)?;
                 Ok((buf, TargetInfo::Offset(offset)))
             }
-            0x47 | 0x48 | 0x49 | 0x4A | 0x4B => {
+            0x47..=0x4B => {
                 let (buf, offset) = be_u16(buf)?;
                 let (buf, type_argument_index) = u8(buf)?;
-                Ok((buf, TargetInfo::TypeArgument(offset, type_argument_index)))
+                Ok((
+                    buf,
+                    TargetInfo::TypeArgument {
+                        offset,
+                        type_argument_index,
+                    },
+                ))
             }
-            _ => unimplemented!(),
+            _ => Err(ErrorAt::failure(
+                buf,
+                ParseError::UnknownTag {
+                    structure: "type annotation target",
+                    tag: target_type,
+                },
+            )),
         }
     }
+
+    #[cfg(feature = "write")]
+    fn write(&self, out: &mut Vec<u8>) -> io::Result<()> {
+        match self {
+            TargetInfo::TypeParameter(type_parameter_index) => out.put_u8(*type_parameter_index),
+            TargetInfo::SuperType(supertype_index) => out.put_u16(*supertype_index),
+            TargetInfo::TypeParameterBound {
+                type_parameter_index,
+                bound_index,
+            } => {
+                out.put_u8(*type_parameter_index);
+                out.put_u8(*bound_index);
+            }
+            TargetInfo::Empty => {}
+            TargetInfo::FormalParameter(formal_parameter_index) => {
+                out.put_u8(*formal_parameter_index)
+            }
+            TargetInfo::Throws(throws_type_index) => out.put_u16(*throws_type_index),
+            TargetInfo::LocalVar(table) => {
+                out.put_count(table.len(), "localvar_target table")?;
+                table.iter().for_each(|local_var| local_var.write(out));
+            }
+            TargetInfo::Catch(exception_table_index) => out.put_u16(*exception_table_index),
+            TargetInfo::Offset(offset) => out.put_u16(*offset),
+            TargetInfo::TypeArgument {
+                offset,
+                type_argument_index,
+            } => {
+                out.put_u16(*offset);
+                out.put_u8(*type_argument_index);
+            }
+        }
+        Ok(())
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TypePath {
     pub path: Vec<Path>,
 }
@@ -856,13 +2628,24 @@ pub struct TypePath {
 impl TypePath {
     fn parse(buf: &[u8]) -> IResult<&[u8], Self> {
         let (buf, path_length) = u8(buf)?;
-        let (buf, path) = count(Path::parse, path_length as usize)(buf)?;
+        let (buf, path) = count(Path::parse, path_length as usize, 2)(buf)?;
 
         Ok((buf, TypePath { path }))
     }
+
+    #[cfg(feature = "write")]
+    fn write(&self, out: &mut Vec<u8>) -> io::Result<()> {
+        out.put_count_u8(self.path.len(), "type_path")?;
+        for path in &self.path {
+            out.put_u8(path.type_path_kind);
+            out.put_u8(path.type_argument_index);
+        }
+        Ok(())
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Path {
     pub type_path_kind: u8,
     pub type_argument_index: u8,
@@ -883,7 +2666,8 @@ impl Path {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BootstrapMethod {
     pub bootstrap_method_ref: u16,
     pub bootstrap_arguments: Vec<u16>,
@@ -893,7 +2677,7 @@ impl BootstrapMethod {
     fn parse(buf: &[u8]) -> IResult<&[u8], Self> {
         let (buf, bootstrap_method_ref) = be_u16(buf)?;
         let (buf, num_bootstrap_arguments) = be_u16(buf)?;
-        let (buf, bootstrap_arguments) = count(be_u16, num_bootstrap_arguments as usize)(buf)?;
+        let (buf, bootstrap_arguments) = count(be_u16, num_bootstrap_arguments as usize, 2)(buf)?;
 
         Ok((
             buf,
@@ -903,12 +2687,19 @@ impl BootstrapMethod {
             },
         ))
     }
+
+    #[cfg(feature = "write")]
+    fn write(&self, out: &mut Vec<u8>) -> io::Result<()> {
+        out.put_u16(self.bootstrap_method_ref);
+        write_indices(out, &self.bootstrap_arguments, "bootstrap_arguments")
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Parameter {
     pub name_index: u16,
-    pub access_flags: u16,
+    pub access_flags: ParameterAccessFlags,
 }
 
 impl Parameter {
@@ -920,13 +2711,20 @@ impl Parameter {
             buf,
             Parameter {
                 name_index,
-                access_flags,
+                access_flags: ParameterAccessFlags::new(access_flags),
             },
         ))
     }
+
+    #[cfg(feature = "write")]
+    fn write(&self, out: &mut Vec<u8>) {
+        out.put_u16(self.name_index);
+        out.put_u16(self.access_flags.bits());
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Module {
     pub module_name_index: u16,
     pub module_flags: u16,
@@ -939,20 +2737,78 @@ pub struct Module {
 }
 
 impl Module {
+    /// The module's directives with their names looked up. Names which
+    /// can't be resolved are empty. `packages` and `main_class` are left
+    /// for [`ClassFile::module`](crate::classfile::ClassFile::module) to
+    /// fill in from the attributes beside this one.
+    pub fn resolved<'a>(&self, constant_pool: &'a [ConstantPool]) -> ResolvedModule<'a> {
+        let module = |index| ConstantPool::module_name(constant_pool, index).unwrap_or_default();
+        let package = |index| ConstantPool::package_name(constant_pool, index).unwrap_or_default();
+        let class = |index| ConstantPool::class_name(constant_pool, index).unwrap_or_default();
+        let version = |index| match index {
+            0 => None,
+            index => Some(ConstantPool::utf8(constant_pool, index).unwrap_or_default()),
+        };
+        let exports = |index, flags, to: &[u16]| ResolvedExports {
+            package: package(index),
+            flags: ExportsAccessFlags::new(flags),
+            to: to.iter().map(|&index| module(index)).collect(),
+        };
+        ResolvedModule {
+            name: module(self.module_name_index),
+            flags: ModuleAccessFlags::new(self.module_flags),
+            version: version(self.module_version_index),
+            requires: self
+                .requires
+                .iter()
+                .map(|requires| ResolvedRequires {
+                    module: module(requires.requires_index),
+                    flags: RequiresAccessFlags::new(requires.requires_flags),
+                    version: version(requires.requires_version_index),
+                })
+                .collect(),
+            exports: self
+                .exports
+                .iter()
+                .map(|e| exports(e.exports_index, e.exports_flags, &e.exports_to_index))
+                .collect(),
+            opens: self
+                .opens
+                .iter()
+                .map(|o| exports(o.opens_index, o.opens_flags, &o.opens_to_index))
+                .collect(),
+            uses: self.uses.iter().map(|&index| class(index)).collect(),
+            provides: self
+                .provides
+                .iter()
+                .map(|provides| ResolvedProvides {
+                    service: class(provides.provides_index),
+                    with: provides
+                        .provides_with_index
+                        .iter()
+                        .map(|&index| class(index))
+                        .collect(),
+                })
+                .collect(),
+            packages: Vec::new(),
+            main_class: None,
+        }
+    }
+
     fn parse(buf: &[u8]) -> IResult<&[u8], Self> {
         let (buf, module_name_index) = be_u16(buf)?;
         let (buf, module_flags) = be_u16(buf)?;
         let (buf, module_version_index) = be_u16(buf)?;
         let (buf, requires_count) = be_u16(buf)?;
-        let (buf, requires) = count(Requires::parse, requires_count as usize)(buf)?;
+        let (buf, requires) = count(Requires::parse, requires_count as usize, 6)(buf)?;
         let (buf, exports_count) = be_u16(buf)?;
-        let (buf, exports) = count(Exports::parse, exports_count as usize)(buf)?;
+        let (buf, exports) = count(Exports::parse, exports_count as usize, 6)(buf)?;
         let (buf, opens_count) = be_u16(buf)?;
-        let (buf, opens) = count(Opens::parse, opens_count as usize)(buf)?;
+        let (buf, opens) = count(Opens::parse, opens_count as usize, 6)(buf)?;
         let (buf, uses_count) = be_u16(buf)?;
-        let (buf, uses) = count(be_u16, uses_count as usize)(buf)?;
+        let (buf, uses) = count(be_u16, uses_count as usize, 2)(buf)?;
         let (buf, provides_count) = be_u16(buf)?;
-        let (buf, provides) = count(Provides::parse, provides_count as usize)(buf)?;
+        let (buf, provides) = count(Provides::parse, provides_count as usize, 4)(buf)?;
 
         Ok((
             buf,
@@ -968,9 +2824,35 @@ impl Module {
             },
         ))
     }
+
+    #[cfg(feature = "write")]
+    fn write(&self, out: &mut Vec<u8>) -> io::Result<()> {
+        out.put_u16(self.module_name_index);
+        out.put_u16(self.module_flags);
+        out.put_u16(self.module_version_index);
+        out.put_count(self.requires.len(), "Module requires")?;
+        self.requires
+            .iter()
+            .for_each(|requires| requires.write(out));
+        out.put_count(self.exports.len(), "Module exports")?;
+        for exports in &self.exports {
+            exports.write(out)?;
+        }
+        out.put_count(self.opens.len(), "Module opens")?;
+        for opens in &self.opens {
+            opens.write(out)?;
+        }
+        write_indices(out, &self.uses, "Module uses")?;
+        out.put_count(self.provides.len(), "Module provides")?;
+        for provides in &self.provides {
+            provides.write(out)?;
+        }
+        Ok(())
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Requires {
     pub requires_index: u16,
     pub requires_flags: u16,
@@ -992,9 +2874,17 @@ impl Requires {
             },
         ))
     }
+
+    #[cfg(feature = "write")]
+    fn write(&self, out: &mut Vec<u8>) {
+        out.put_u16(self.requires_index);
+        out.put_u16(self.requires_flags);
+        out.put_u16(self.requires_version_index);
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Exports {
     pub exports_index: u16,
     pub exports_flags: u16,
@@ -1005,7 +2895,7 @@ impl Exports {
         let (buf, exports_index) = be_u16(buf)?;
         let (buf, exports_flags) = be_u16(buf)?;
         let (buf, exports_to_count) = be_u16(buf)?;
-        let (buf, exports_to_index) = count(be_u16, exports_to_count as usize)(buf)?;
+        let (buf, exports_to_index) = count(be_u16, exports_to_count as usize, 2)(buf)?;
 
         Ok((
             buf,
@@ -1016,9 +2906,17 @@ impl Exports {
             },
         ))
     }
+
+    #[cfg(feature = "write")]
+    fn write(&self, out: &mut Vec<u8>) -> io::Result<()> {
+        out.put_u16(self.exports_index);
+        out.put_u16(self.exports_flags);
+        write_indices(out, &self.exports_to_index, "Module exports_to")
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Opens {
     pub opens_index: u16,
     pub opens_flags: u16,
@@ -1029,7 +2927,7 @@ impl Opens {
         let (buf, opens_index) = be_u16(buf)?;
         let (buf, opens_flags) = be_u16(buf)?;
         let (buf, opens_to_count) = be_u16(buf)?;
-        let (buf, opens_to_index) = count(be_u16, opens_to_count as usize)(buf)?;
+        let (buf, opens_to_index) = count(be_u16, opens_to_count as usize, 2)(buf)?;
 
         Ok((
             buf,
@@ -1040,9 +2938,17 @@ impl Opens {
             },
         ))
     }
+
+    #[cfg(feature = "write")]
+    fn write(&self, out: &mut Vec<u8>) -> io::Result<()> {
+        out.put_u16(self.opens_index);
+        out.put_u16(self.opens_flags);
+        write_indices(out, &self.opens_to_index, "Module opens_to")
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Provides {
     pub provides_index: u16,
     pub provides_with_index: Vec<u16>,
@@ -1052,7 +2958,7 @@ impl Provides {
     fn parse(buf: &[u8]) -> IResult<&[u8], Self> {
         let (buf, provides_index) = be_u16(buf)?;
         let (buf, provides_to_count) = be_u16(buf)?;
-        let (buf, provides_with_index) = count(be_u16, provides_to_count as usize)(buf)?;
+        let (buf, provides_with_index) = count(be_u16, provides_to_count as usize, 2)(buf)?;
 
         Ok((
             buf,
@@ -1062,22 +2968,361 @@ impl Provides {
             },
         ))
     }
+
+    #[cfg(feature = "write")]
+    fn write(&self, out: &mut Vec<u8>) -> io::Result<()> {
+        out.put_u16(self.provides_index);
+        write_indices(out, &self.provides_with_index, "Module provides_with")
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct RecordComponentInfo {
     pub name_index: u16,
     pub descriptor_index: u16,
     pub attributes: Vec<Attribute>,
 }
 
+/// A local variable with its name and types looked up in the constant
+/// pool, as [`Code::local_variable_at`] finds it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedLocalVariable<'a> {
+    /// The slot it's held in.
+    pub index: u16,
+    /// Where its scope starts.
+    pub start_pc: u16,
+    /// How many bytes of code its scope spans.
+    pub length: u16,
+    pub name: &'a str,
+    /// Its erased type, e.g. `Ljava/util/List;`.
+    pub descriptor: &'a str,
+    /// Its generic type, e.g. `Ljava/util/List<Ljava/lang/String;>;`, when
+    /// a `LocalVariableTypeTable` gives one.
+    pub signature: Option<&'a str>,
+}
+
+/// An entry of `InnerClasses` with its names looked up in the constant
+/// pool, as [`InnerClasses::resolved`] gives it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResolvedInnerClass<'a> {
+    /// E.g. `java/util/Map$Entry`.
+    pub inner_class: &'a str,
+    /// The class it's a member of, `None` for local and anonymous classes.
+    pub outer_class: Option<&'a str>,
+    /// Its simple name as written in the source, e.g. `Entry`, `None` for
+    /// anonymous classes.
+    pub inner_name: Option<&'a str>,
+    /// Its flags as declared in the source, which may include private,
+    /// protected and static.
+    pub access_flags: ClassAccessFlags,
+}
+
+impl ResolvedInnerClass<'_> {
+    /// Whether the class is anonymous, e.g. `new Runnable() { ... }`.
+    pub fn is_anonymous(&self) -> bool {
+        self.inner_name.is_none()
+    }
+
+    /// Whether the class is declared in a block, with a name but no
+    /// enclosing class of which it's a member.
+    pub fn is_local(&self) -> bool {
+        self.outer_class.is_none() && self.inner_name.is_some()
+    }
+
+    /// Whether the class is a static member of another, as the nested
+    /// interfaces, enums and records all are.
+    pub fn is_static_nested(&self) -> bool {
+        self.outer_class.is_some() && self.access_flags.is_static()
+    }
+}
+
+/// An `EnclosingMethod` attribute with its names looked up in the constant
+/// pool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResolvedEnclosingMethod<'a> {
+    pub class_name: &'a str,
+    /// `None` when the class isn't enclosed by a method or constructor, as
+    /// with one in an initializer.
+    pub method_name: Option<&'a str>,
+    pub method_descriptor: Option<&'a str>,
+}
+
+/// A `Module` attribute with its names looked up in the constant pool.
+/// Packages and classes are named in internal form, e.g. `java/lang` and
+/// `java/lang/Runnable`, as the constant pool holds them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedModule<'a> {
+    pub name: &'a str,
+    pub flags: ModuleAccessFlags,
+    pub version: Option<&'a str>,
+    pub requires: Vec<ResolvedRequires<'a>>,
+    pub exports: Vec<ResolvedExports<'a>>,
+    pub opens: Vec<ResolvedExports<'a>>,
+    /// The services the module looks up.
+    pub uses: Vec<&'a str>,
+    pub provides: Vec<ResolvedProvides<'a>>,
+    /// Every package of the module, as its `ModulePackages` attribute
+    /// lists them.
+    pub packages: Vec<&'a str>,
+    /// The class its `ModuleMainClass` attribute names.
+    pub main_class: Option<&'a str>,
+}
+
+/// A `requires` directive of a [`ResolvedModule`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResolvedRequires<'a> {
+    pub module: &'a str,
+    pub flags: RequiresAccessFlags,
+    /// The version of the module the module was compiled against.
+    pub version: Option<&'a str>,
+}
+
+/// An `exports` or `opens` directive of a [`ResolvedModule`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedExports<'a> {
+    pub package: &'a str,
+    pub flags: ExportsAccessFlags,
+    /// The only modules the package is exported or opened to, or empty when
+    /// it's to all of them.
+    pub to: Vec<&'a str>,
+}
+
+/// A `provides` directive of a [`ResolvedModule`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedProvides<'a> {
+    pub service: &'a str,
+    /// The classes implementing the service.
+    pub with: Vec<&'a str>,
+}
+
+/// A record component with its name, type and annotations looked up in
+/// the constant pool.
+#[derive(Debug, Clone)]
+pub struct ResolvedRecordComponent<'a> {
+    pub name: &'a str,
+    pub descriptor: &'a str,
+    /// The generic type its `Signature` attribute gives, or else the type
+    /// of its descriptor. `None` when neither parses.
+    pub signature: Option<TypeSignature>,
+    pub visible_annotations: Vec<ResolvedAnnotation<'a>>,
+    pub invisible_annotations: Vec<ResolvedAnnotation<'a>>,
+}
+
+/// An annotation with its type and the names and values of its elements
+/// looked up.
+#[derive(Debug, Clone)]
+pub struct ResolvedAnnotation<'a> {
+    /// E.g. `Ljavax/annotation/Nonnull;`.
+    pub descriptor: &'a str,
+    /// Whether it comes from a `RuntimeVisibleAnnotations` attribute rather
+    /// than a `RuntimeInvisibleAnnotations` one. Nested annotations take
+    /// this from the one they're in.
+    pub visible: bool,
+    /// The elements given a value in the source, in class file order.
+    /// Elements left at their default aren't listed, and ones whose name
+    /// or value can't be resolved are left out.
+    pub elements: Vec<(&'a str, ResolvedElementValue<'a>)>,
+    pub annotation: &'a Annotation,
+}
+
+impl<'a> ResolvedAnnotation<'a> {
+    /// `None` when the annotation's type can't be resolved.
+    fn new(
+        annotation: &'a Annotation,
+        visible: bool,
+        constant_pool: &'a [ConstantPool],
+    ) -> Option<Self> {
+        Some(ResolvedAnnotation {
+            descriptor: ConstantPool::utf8(constant_pool, annotation.type_index)?,
+            visible,
+            elements: ResolvedElementValue::pairs(
+                &annotation.element_value_pairs,
+                visible,
+                constant_pool,
+            ),
+            annotation,
+        })
+    }
+
+    /// The annotation type as written in Java source, or the descriptor
+    /// itself when it doesn't parse.
+    pub fn type_name(&self) -> String {
+        FieldType::parse(self.descriptor)
+            .map(|field_type| field_type.to_string())
+            .unwrap_or_else(|_| self.descriptor.to_string())
+    }
+
+    /// The value given to the element called `name`.
+    pub fn element(&self, name: &str) -> Option<&ResolvedElementValue<'a>> {
+        self.elements
+            .iter()
+            .find(|(element, _)| *element == name)
+            .map(|(_, value)| value)
+    }
+}
+
+/// The value of an annotation element, with its constant pool entries
+/// looked up.
+#[derive(Debug, Clone)]
+pub enum ResolvedElementValue<'a> {
+    Byte(i8),
+    /// A UTF-16 code unit, which may be half a surrogate pair.
+    Char(u16),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    Boolean(bool),
+    String(&'a str),
+    Enum {
+        /// E.g. `Ljava/lang/annotation/RetentionPolicy;`.
+        type_descriptor: &'a str,
+        const_name: &'a str,
+    },
+    /// The return descriptor of a class literal, e.g. `Ljava/lang/String;`,
+    /// `[I` or `V` for `void.class`.
+    Class(&'a str),
+    Annotation(ResolvedAnnotation<'a>),
+    Array(Vec<ResolvedElementValue<'a>>),
+}
+
+impl<'a> ResolvedElementValue<'a> {
+    /// The names and values of `pairs`, leaving out those which can't be
+    /// resolved.
+    pub(crate) fn pairs(
+        pairs: &'a [(u16, ElementValue)],
+        visible: bool,
+        constant_pool: &'a [ConstantPool],
+    ) -> Vec<(&'a str, Self)> {
+        pairs
+            .iter()
+            .filter_map(|(name_index, value)| {
+                Some((
+                    ConstantPool::utf8(constant_pool, *name_index)?,
+                    Self::new(value, visible, constant_pool)?,
+                ))
+            })
+            .collect()
+    }
+
+    /// `None` when an entry it refers to is missing or of a kind its tag
+    /// doesn't allow, or when that's so of anything inside it.
+    fn new(
+        value: &'a ElementValue,
+        visible: bool,
+        constant_pool: &'a [ConstantPool],
+    ) -> Option<Self> {
+        let utf8 = |index| ConstantPool::utf8(constant_pool, index);
+        Some(match value {
+            ElementValue::ConstValue {
+                tag,
+                const_value_index: index,
+            } => match (tag, ConstantPool::entry(constant_pool, *index).ok()?) {
+                (b'B', ConstantPool::Integer(value)) => Self::Byte(*value as i8),
+                (b'C', ConstantPool::Integer(value)) => Self::Char(*value as u16),
+                (b'S', ConstantPool::Integer(value)) => Self::Short(*value as i16),
+                (b'Z', ConstantPool::Integer(value)) => Self::Boolean(*value != 0),
+                (b'I', ConstantPool::Integer(value)) => Self::Int(*value),
+                (b'J', ConstantPool::Long(value)) => Self::Long(*value),
+                (b'F', ConstantPool::Float(bits)) => Self::Float(bits.value()),
+                (b'D', ConstantPool::Double(bits)) => Self::Double(bits.value()),
+                (b's', ConstantPool::UTF8(value)) => Self::String(value),
+                _ => return None,
+            },
+            ElementValue::EnumConstValue {
+                type_name_index,
+                const_name_index,
+            } => Self::Enum {
+                type_descriptor: utf8(*type_name_index)?,
+                const_name: utf8(*const_name_index)?,
+            },
+            ElementValue::ClassInfoIndex(index) => Self::Class(utf8(*index)?),
+            ElementValue::AnnotationValue(annotation) => {
+                Self::Annotation(ResolvedAnnotation::new(annotation, visible, constant_pool)?)
+            }
+            ElementValue::ArrayValue(values) => Self::Array(
+                values
+                    .iter()
+                    .map(|value| Self::new(value, visible, constant_pool))
+                    .collect::<Option<_>>()?,
+            ),
+        })
+    }
+
+    /// The value of a `String` element.
+    pub fn as_str(&self) -> Option<&'a str> {
+        match self {
+            Self::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// The value of an `int` element.
+    pub fn as_int(&self) -> Option<i32> {
+        match self {
+            Self::Int(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// The values of a `String[]` element, `None` when any isn't a string.
+    pub fn as_str_array(&self) -> Option<Vec<&'a str>> {
+        match self {
+            Self::Array(values) => values.iter().map(Self::as_str).collect(),
+            _ => None,
+        }
+    }
+
+    /// The values of an `int[]` element, `None` when any isn't an `int`.
+    pub fn as_int_array(&self) -> Option<Vec<i32>> {
+        match self {
+            Self::Array(values) => values.iter().map(Self::as_int).collect(),
+            _ => None,
+        }
+    }
+}
+
 impl RecordComponentInfo {
-    fn parse<'a>(buf: &'a [u8], constant_pool: &[ConstantPool]) -> IResult<&'a [u8], Self> {
+    /// The component's name, type and annotations. Entries which can't be
+    /// resolved are empty, and annotations of such types are left out.
+    pub fn resolved<'a>(
+        &'a self,
+        constant_pool: &'a [ConstantPool],
+    ) -> ResolvedRecordComponent<'a> {
+        let utf8 = |index| ConstantPool::utf8(constant_pool, index).unwrap_or_default();
+        let descriptor = utf8(self.descriptor_index);
+        let signature = signature::signature(&self.attributes, constant_pool)
+            .and_then(|signature| TypeSignature::parse(signature).ok())
+            .or_else(|| {
+                Some(TypeSignature::from_field_type(
+                    &FieldType::parse(descriptor).ok()?,
+                ))
+            });
+        let (visible_annotations, invisible_annotations) =
+            Attribute::annotations(&self.attributes, constant_pool)
+                .into_iter()
+                .partition(|annotation| annotation.visible);
+        ResolvedRecordComponent {
+            name: utf8(self.name_index),
+            descriptor,
+            signature,
+            visible_annotations,
+            invisible_annotations,
+        }
+    }
+
+    fn parse<'a>(
+        buf: &'a [u8],
+        constant_pool: &[ConstantPool],
+        context: &ParseContext,
+    ) -> IResult<&'a [u8], Self> {
         let (buf, name_index) = be_u16(buf)?;
         let (buf, descriptor_index) = be_u16(buf)?;
         let (buf, attributes_count) = be_u16(buf)?;
-        let (buf, attributes) = count(AttributeInfo::parse, attributes_count as usize)(buf)?;
-        let attributes = Attribute::from_attribute_info(attributes, &constant_pool);
+        let (buf, attributes) =
+            Attribute::parse_table(buf, attributes_count, constant_pool, context)?;
 
         Ok((
             buf,
@@ -1088,4 +3333,95 @@ impl RecordComponentInfo {
             },
         ))
     }
+
+    #[cfg(feature = "write")]
+    fn write(&self, out: &mut Vec<u8>, constant_pool: &[ConstantPool]) -> io::Result<()> {
+        out.put_u16(self.name_index);
+        out.put_u16(self.descriptor_index);
+        Attribute::write_all(&self.attributes, out, constant_pool)
+    }
+}
+
+#[cfg(feature = "write")]
+fn write_indices(out: &mut Vec<u8>, indices: &[u16], what: &str) -> io::Result<()> {
+    out.put_count(indices.len(), what)?;
+    indices.iter().for_each(|&index| out.put_u16(index));
+    Ok(())
+}
+
+#[cfg(feature = "disasm")]
+fn visit_element_value_pairs(
+    element_value_pairs: &[(u16, ElementValue)],
+    visit: &mut dyn FnMut(u16, Utf8Roles),
+) {
+    for (element_name_index, element_value) in element_value_pairs {
+        visit(*element_name_index, Utf8Roles::ANNOTATION_ELEMENT_NAME);
+        element_value.visit_indices(visit);
+    }
+}
+
+#[cfg(feature = "write")]
+fn write_element_value_pairs(
+    out: &mut Vec<u8>,
+    element_value_pairs: &[(u16, ElementValue)],
+) -> io::Result<()> {
+    out.put_count(element_value_pairs.len(), "element_value_pairs")?;
+    for (element_name_index, element_value) in element_value_pairs {
+        out.put_u16(*element_name_index);
+        element_value.write(out)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn append_and_full_frames_parse() {
+        // append an int, at 2
+        let (rest, frame) = StackMapFrame::parse(&[252, 0, 2, 1]).unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(
+            frame,
+            StackMapFrame::AppendFrame {
+                offset_delta: 2,
+                locals: vec![VerificationTypeInfo::IntegerVariableInfo],
+            }
+        );
+
+        // an int and a long, with a float on the stack, at 1
+        let (rest, frame) = StackMapFrame::parse(&[255, 0, 1, 0, 2, 1, 4, 0, 1, 2]).unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(
+            frame,
+            StackMapFrame::FullFrame {
+                offset_delta: 1,
+                locals: vec![
+                    VerificationTypeInfo::IntegerVariableInfo,
+                    VerificationTypeInfo::LongVariableInfo,
+                ],
+                stack: vec![VerificationTypeInfo::FloatVariableInfo],
+            }
+        );
+    }
+
+    #[test]
+    fn reserved_frame_types_fail() {
+        for tag in [128, 200, 242, 246] {
+            // as though it were an append frame of one int
+            let frame = [tag, 0, 0, 1];
+            let err = StackMapFrame::parse(&frame).unwrap_err();
+            let nom::Err::Failure(ErrorAt { code, .. }) = err else {
+                panic!("{:?}", err);
+            };
+            assert_eq!(
+                code,
+                ParseError::UnknownTag {
+                    structure: "stack map frame",
+                    tag,
+                }
+            );
+        }
+    }
 }
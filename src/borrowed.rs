@@ -0,0 +1,387 @@
+//! A class file read without copying out of the buffer it was read from,
+//! for scans over many classes which look at a few parts of each.
+//!
+//! [`BorrowedClassFile`] stops where [`ClassFile`] would parse attribute
+//! bodies: every attribute is left as an [`AttributeInfo`] whose body is a
+//! slice of the buffer, a method's code is a slice too, and a UTF8 constant
+//! borrows its bytes whenever modified UTF-8 and UTF-8 agree on them, which
+//! is every string without a NUL or a supplementary character. Parsing a
+//! class then allocates the tables of constants and members and nothing
+//! per entry. [`BorrowedClassFile::into_owned`] parses the rest, for callers
+//! which need a [`ClassFile`] after all.
+//!
+//! ```no_run
+//! use jvmb::borrowed::BorrowedClassFile;
+//!
+//! let bytes = std::fs::read("Foo.class").unwrap();
+//! let (_, class_file) = BorrowedClassFile::parse(&bytes).unwrap();
+//! for method in &class_file.methods {
+//!     if let Some(Ok(code)) = method.code(&class_file.constant_pool) {
+//!         println!("{} bytes of code", code.code.len());
+//!     }
+//! }
+//! ```
+
+use alloc::borrow::Cow;
+use core::str;
+
+use crate::prelude::*;
+use crate::{
+    accessflags::ClassAccessFlags,
+    attribute::{Attribute, AttributeInfo, Exception},
+    classfile::{magic, ClassFile, ParseContext, ParseOptions},
+    constantpool::ConstantPool,
+    error::{self, be_u16, be_u32, count, take, u8, ErrorAt, IResult, ParseError},
+    fieldinfo::FieldInfo,
+    methodinfo::MethodInfo,
+    spec::{LATEST_MAJOR_VERSION, OLDEST_MAJOR_VERSION},
+};
+
+const CONSTANT_UTF8: u8 = 1;
+
+/// A constant pool entry, with UTF8 constants borrowed where they can be.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum BorrowedConstant<'a> {
+    /// A UTF8 constant, borrowed unless its bytes had to be decoded.
+    UTF8(Cow<'a, str>),
+    /// Every other entry, as [`ConstantPool`] holds it, including the
+    /// [`Utf16`](ConstantPool::Utf16) form of strings with an unpaired
+    /// surrogate and the [`RawUtf8`](ConstantPool::RawUtf8) form of invalid
+    /// ones.
+    Other(ConstantPool),
+}
+
+impl<'a> BorrowedConstant<'a> {
+    /// Looks up the UTF8 entry at `index`.
+    pub fn utf8<'b>(constant_pool: &'b [BorrowedConstant<'a>], index: u16) -> Option<&'b str> {
+        match constant_pool.get((index as usize).checked_sub(1)?) {
+            Some(BorrowedConstant::UTF8(value)) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Looks up the name held by the `CONSTANT_Class` entry at `index`.
+    pub fn class_name<'b>(
+        constant_pool: &'b [BorrowedConstant<'a>],
+        index: u16,
+    ) -> Option<&'b str> {
+        match constant_pool.get((index as usize).checked_sub(1)?) {
+            Some(BorrowedConstant::Other(ConstantPool::Class(name_index))) => {
+                Self::utf8(constant_pool, *name_index)
+            }
+            _ => None,
+        }
+    }
+
+    /// The entry as [`ConstantPool`] holds it, copying a borrowed string.
+    pub fn into_owned(self) -> ConstantPool {
+        match self {
+            BorrowedConstant::UTF8(value) => ConstantPool::UTF8(value.into_owned()),
+            BorrowedConstant::Other(constant) => constant,
+        }
+    }
+
+    fn parse(
+        buf: &'a [u8],
+        options: &ParseOptions,
+        major_version: u16,
+    ) -> IResult<&'a [u8], BorrowedConstant<'a>> {
+        let (body, constant_tag) = u8(buf)?;
+        if constant_tag != CONSTANT_UTF8 {
+            let constant_options = options.constant_options(major_version);
+            let (rest, constant) = ConstantPool::parse_constant(buf, constant_options)?;
+            return Ok((rest, BorrowedConstant::Other(constant)));
+        }
+        let (rest, length) = be_u16(body)?;
+        let (rest, bytes) = take(length as usize)(rest)?;
+        // Modified UTF-8 differs from UTF-8 only in encoding NUL in two bytes
+        // and supplementary characters as surrogate pairs, both of which UTF-8
+        // rejects, and in having no four-byte form, which UTF-8 accepts.
+        let plain = !bytes.iter().any(|&byte| byte == 0 || byte >= 0xF0);
+        if let Some(value) = plain.then(|| str::from_utf8(bytes).ok()).flatten() {
+            return Ok((rest, BorrowedConstant::UTF8(Cow::Borrowed(value))));
+        }
+        // The owned parser decodes the rest, surrogates and all.
+        let constant_options = options.constant_options(major_version);
+        let (rest, constant) = ConstantPool::parse_constant(buf, constant_options)?;
+        Ok((rest, BorrowedConstant::from(constant)))
+    }
+}
+
+impl From<ConstantPool> for BorrowedConstant<'_> {
+    fn from(constant: ConstantPool) -> Self {
+        match constant {
+            ConstantPool::UTF8(value) => BorrowedConstant::UTF8(Cow::Owned(value)),
+            constant => BorrowedConstant::Other(constant),
+        }
+    }
+}
+
+/// A field or method, with its attributes unparsed.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BorrowedMember<'a> {
+    pub access_flags: u16,
+    pub name_index: u16,
+    pub descriptor_index: u16,
+    pub attributes: Vec<AttributeInfo<'a>>,
+}
+
+impl<'a> BorrowedMember<'a> {
+    fn parse(buf: &'a [u8]) -> IResult<&'a [u8], BorrowedMember<'a>> {
+        let (buf, access_flags) = be_u16(buf)?;
+        let (buf, name_index) = be_u16(buf)?;
+        let (buf, descriptor_index) = be_u16(buf)?;
+        let (buf, attributes_count) = be_u16(buf)?;
+        let (buf, attributes) = count(AttributeInfo::parse, attributes_count as usize, 6)(buf)?;
+        Ok((
+            buf,
+            BorrowedMember {
+                access_flags,
+                name_index,
+                descriptor_index,
+                attributes,
+            },
+        ))
+    }
+
+    pub fn name<'b>(&self, constant_pool: &'b [BorrowedConstant]) -> Option<&'b str> {
+        BorrowedConstant::utf8(constant_pool, self.name_index)
+    }
+
+    pub fn descriptor<'b>(&self, constant_pool: &'b [BorrowedConstant]) -> Option<&'b str> {
+        BorrowedConstant::utf8(constant_pool, self.descriptor_index)
+    }
+
+    /// The first attribute named `name`.
+    pub fn attribute(&self, constant_pool: &[BorrowedConstant], name: &str) -> Option<&'a [u8]> {
+        self.attributes
+            .iter()
+            .find(|attribute| {
+                BorrowedConstant::utf8(constant_pool, attribute.attribute_name_index) == Some(name)
+            })
+            .map(|attribute| attribute.info)
+    }
+
+    /// The method's `Code` attribute, or `None` if it has none.
+    pub fn code(
+        &self,
+        constant_pool: &[BorrowedConstant],
+    ) -> Option<Result<BorrowedCode<'a>, ParseError>> {
+        let body = self.attribute(constant_pool, "Code")?;
+        Some(
+            BorrowedCode::parse(body)
+                .map(|(_, code)| code)
+                .map_err(ParseError::from),
+        )
+    }
+}
+
+/// The body of a `Code` attribute, with the bytecode and exception table
+/// left in the class file.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BorrowedCode<'a> {
+    pub max_stack: u16,
+    pub max_locals: u16,
+    pub code: &'a [u8],
+    /// The exception table as stored, eight bytes an entry; see
+    /// [`BorrowedCode::exceptions`].
+    pub exception_table: &'a [u8],
+    pub attributes: Vec<AttributeInfo<'a>>,
+}
+
+impl<'a> BorrowedCode<'a> {
+    pub fn parse(buf: &'a [u8]) -> IResult<&'a [u8], BorrowedCode<'a>> {
+        let (buf, max_stack) = be_u16(buf)?;
+        let (buf, max_locals) = be_u16(buf)?;
+        let (buf, code_length) = be_u32(buf)?;
+        let (buf, code) = take(code_length as usize)(buf)?;
+        let (buf, exception_table_length) = be_u16(buf)?;
+        let (buf, exception_table) = take(exception_table_length as usize * 8)(buf)?;
+        let (buf, attributes_count) = be_u16(buf)?;
+        let (buf, attributes) = count(AttributeInfo::parse, attributes_count as usize, 6)(buf)?;
+        Ok((
+            buf,
+            BorrowedCode {
+                max_stack,
+                max_locals,
+                code,
+                exception_table,
+                attributes,
+            },
+        ))
+    }
+
+    /// The entries of the exception table.
+    pub fn exceptions(&self) -> impl Iterator<Item = Exception> + 'a {
+        self.exception_table.chunks_exact(8).map(|entry| {
+            let field = |at: usize| u16::from_be_bytes([entry[at], entry[at + 1]]);
+            Exception {
+                start_pc: field(0),
+                end_pc: field(2),
+                handler_pc: field(4),
+                catch_type: field(6),
+            }
+        })
+    }
+}
+
+/// A class file borrowing from the buffer it was parsed from.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BorrowedClassFile<'a> {
+    pub minor_version: u16,
+    pub major_version: u16,
+    pub constant_pool: Vec<BorrowedConstant<'a>>,
+    pub access_flags: ClassAccessFlags,
+    pub this_class: u16,
+    pub super_class: u16,
+    pub interfaces: Vec<u16>,
+    pub fields: Vec<BorrowedMember<'a>>,
+    pub methods: Vec<BorrowedMember<'a>>,
+    pub attributes: Vec<AttributeInfo<'a>>,
+    /// The whole class file, which attribute offsets are taken from when
+    /// converting.
+    class: &'a [u8],
+}
+
+impl<'a> BorrowedClassFile<'a> {
+    pub fn parse(buf: &'a [u8]) -> IResult<&'a [u8], BorrowedClassFile<'a>> {
+        Self::parse_with(buf, &ParseOptions::default())
+    }
+
+    /// Parses the class file in `buf` as far as [`ClassFile::parse_with`]
+    /// would with `options`, short of the attribute bodies. Metrics aren't
+    /// recorded.
+    pub fn parse_with(
+        buf: &'a [u8],
+        options: &ParseOptions,
+    ) -> IResult<&'a [u8], BorrowedClassFile<'a>> {
+        Self::parse_class(buf, options).map_err(|e| error::locate(e, buf))
+    }
+
+    fn parse_class(
+        buf: &'a [u8],
+        options: &ParseOptions,
+    ) -> IResult<&'a [u8], BorrowedClassFile<'a>> {
+        let class = buf;
+        let (buf, ()) = magic(buf)?;
+        let (buf, minor_version) = be_u16(buf)?;
+        let version = buf;
+        let (buf, major_version) = be_u16(buf)?;
+        let too_new = options.reject_unsupported_versions && major_version > LATEST_MAJOR_VERSION;
+        if major_version < OLDEST_MAJOR_VERSION || too_new {
+            return Err(ErrorAt::failure(
+                version,
+                ParseError::UnsupportedVersion(major_version),
+            ));
+        }
+        let (mut buf, constant_pool_count) = be_u16(buf)?;
+        let mut constant_pool = Vec::with_capacity(
+            (constant_pool_count as usize)
+                .saturating_sub(1)
+                .min(buf.len() / 3),
+        );
+        while constant_pool.len() + 1 < constant_pool_count as usize {
+            let (rest, constant) = BorrowedConstant::parse(buf, options, major_version)?;
+            buf = rest;
+            let wide = matches!(
+                constant,
+                BorrowedConstant::Other(ConstantPool::Long(_) | ConstantPool::Double(_))
+            );
+            constant_pool.push(constant);
+            if wide {
+                constant_pool.push(BorrowedConstant::Other(ConstantPool::Unusable));
+            }
+        }
+        let (buf, access_flags) = be_u16(buf)?;
+        let (buf, this_class) = be_u16(buf)?;
+        let (buf, super_class) = be_u16(buf)?;
+        let (buf, interfaces_count) = be_u16(buf)?;
+        let (buf, interfaces) = count(be_u16, interfaces_count as usize, 2)(buf)?;
+        let (buf, fields_count) = be_u16(buf)?;
+        let (buf, fields) = count(BorrowedMember::parse, fields_count as usize, 8)(buf)?;
+        let (buf, methods_count) = be_u16(buf)?;
+        let (buf, methods) = count(BorrowedMember::parse, methods_count as usize, 8)(buf)?;
+        let (buf, attributes_count) = be_u16(buf)?;
+        let (buf, attributes) = count(AttributeInfo::parse, attributes_count as usize, 6)(buf)?;
+
+        Ok((
+            buf,
+            BorrowedClassFile {
+                minor_version,
+                major_version,
+                constant_pool,
+                access_flags: ClassAccessFlags::new(access_flags),
+                this_class,
+                super_class,
+                interfaces,
+                fields,
+                methods,
+                attributes,
+                class,
+            },
+        ))
+    }
+
+    /// The internal name of the class.
+    pub fn name(&self) -> Option<&str> {
+        BorrowedConstant::class_name(&self.constant_pool, self.this_class)
+    }
+
+    /// Parses the attribute bodies left unparsed into the [`ClassFile`]
+    /// that [`ClassFile::parse_with`] would have given with `options`.
+    pub fn into_owned(
+        self,
+        options: &ParseOptions,
+    ) -> Result<ClassFile, nom::Err<ErrorAt<&'a [u8]>>> {
+        let context = ParseContext::new(options, self.class);
+        context.major_version.set(Some(self.major_version));
+        let constant_pool: Vec<ConstantPool> = self
+            .constant_pool
+            .into_iter()
+            .map(BorrowedConstant::into_owned)
+            .collect();
+        let mut fields = Vec::with_capacity(self.fields.len());
+        for field in self.fields {
+            let attributes =
+                Attribute::from_attribute_info(field.attributes, &constant_pool, &context)?;
+            fields.push(FieldInfo::from_parts(
+                field.access_flags,
+                field.name_index,
+                field.descriptor_index,
+                attributes,
+            ));
+        }
+        let mut methods = Vec::with_capacity(self.methods.len());
+        for method in self.methods {
+            let attributes =
+                Attribute::from_attribute_info(method.attributes, &constant_pool, &context)?;
+            methods.push(MethodInfo::from_parts(
+                method.access_flags,
+                method.name_index,
+                method.descriptor_index,
+                attributes,
+            ));
+        }
+        let attributes = Attribute::from_attribute_info(self.attributes, &constant_pool, &context)?;
+
+        Ok(ClassFile {
+            minor_version: self.minor_version,
+            major_version: self.major_version,
+            constant_pool_count: (constant_pool.len() + 1) as u16,
+            constant_pool,
+            access_flags: self.access_flags,
+            this_class: self.this_class,
+            super_class: self.super_class,
+            interfaces_count: self.interfaces.len() as u16,
+            interfaces: self.interfaces,
+            fields_count: fields.len() as u16,
+            fields,
+            methods_count: methods.len() as u16,
+            methods,
+            attributes_count: attributes.len() as u16,
+            attributes,
+            #[cfg(feature = "std")]
+            source: None,
+        })
+    }
+}
@@ -0,0 +1,208 @@
+//! Bounds on how much work an analysis may do, for running analyses on
+//! classes which can't be trusted, as a service embedding them has to.
+//!
+//! An [`AnalysisBudget`] caps the basic blocks a control flow graph may
+//! have, the instructions decoded, the abstract states or match attempts
+//! an analysis goes through, and the time it may take. The analyses which
+//! accept one, such as [`ControlFlowGraph::build_within`], check it as they
+//! go and, once it's spent, stop and return what they had found so far as
+//! a [`Partial`] result naming the limit they ran into.
+//!
+//! [`ControlFlowGraph::build_within`]: crate::cfg::ControlFlowGraph::build_within
+
+use std::{
+    error::Error,
+    fmt,
+    time::{Duration, Instant},
+};
+
+use crate::instruction::{self, DecodeError, Instruction};
+
+/// How many charges go by between looks at the clock.
+const DEADLINE_INTERVAL: u32 = 256;
+
+/// The limits an analysis runs within. Each is counted separately for every
+/// call given the budget, except the deadline, which is a point in time
+/// and so shared by every call until it passes. The default has no limits.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AnalysisBudget {
+    pub deadline: Option<Instant>,
+    /// The most basic blocks a control flow graph may be split into.
+    pub max_blocks: Option<usize>,
+    /// The most instructions decoded.
+    pub max_instructions: Option<usize>,
+    /// The most abstract states an interpreter may compute, or places a
+    /// pattern may be tried at.
+    pub max_states: Option<usize>,
+}
+
+impl AnalysisBudget {
+    /// A budget with no limits.
+    pub fn unlimited() -> Self {
+        AnalysisBudget::default()
+    }
+
+    /// The same budget with a deadline `timeout` from now.
+    pub fn with_timeout(self, timeout: Duration) -> Self {
+        AnalysisBudget {
+            deadline: Some(Instant::now() + timeout),
+            ..self
+        }
+    }
+
+    /// A fresh count of what's spent against this budget.
+    pub fn meter(&self) -> Meter {
+        Meter {
+            budget: *self,
+            blocks: 0,
+            instructions: 0,
+            states: 0,
+            ticks: 0,
+        }
+    }
+}
+
+/// The limit of an [`AnalysisBudget`] which was exceeded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Limit {
+    Deadline,
+    Blocks,
+    Instructions,
+    States,
+}
+
+impl Limit {
+    pub fn name(self) -> &'static str {
+        match self {
+            Limit::Deadline => "deadline",
+            Limit::Blocks => "blocks",
+            Limit::Instructions => "instructions",
+            Limit::States => "states",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BudgetExceeded {
+    pub limit: Limit,
+}
+
+impl fmt::Display for BudgetExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.limit {
+            Limit::Deadline => f.write_str("analysis budget exceeded: deadline passed"),
+            limit => write!(f, "analysis budget exceeded: too many {}", limit.name()),
+        }
+    }
+}
+
+impl Error for BudgetExceeded {}
+
+/// What has been spent of a budget so far. Analyses charge it as they go,
+/// and stop at the first charge which fails.
+#[derive(Debug, Clone)]
+pub struct Meter {
+    budget: AnalysisBudget,
+    blocks: usize,
+    instructions: usize,
+    states: usize,
+    /// Charges since the clock was last looked at.
+    ticks: u32,
+}
+
+impl Meter {
+    /// Charges `count` basic blocks.
+    pub fn blocks(&mut self, count: usize) -> Result<(), BudgetExceeded> {
+        self.blocks += count;
+        self.charge(self.blocks, self.budget.max_blocks, Limit::Blocks)
+    }
+
+    /// Charges `count` decoded instructions.
+    pub fn instructions(&mut self, count: usize) -> Result<(), BudgetExceeded> {
+        self.instructions += count;
+        self.charge(
+            self.instructions,
+            self.budget.max_instructions,
+            Limit::Instructions,
+        )
+    }
+
+    /// Charges `count` abstract states or match attempts.
+    pub fn states(&mut self, count: usize) -> Result<(), BudgetExceeded> {
+        self.states += count;
+        self.charge(self.states, self.budget.max_states, Limit::States)
+    }
+
+    /// Decodes `code`, charging every instruction. The inner result is the
+    /// budget running out.
+    pub(crate) fn decode<'a>(
+        &mut self,
+        code: &'a [u8],
+    ) -> Result<Result<Vec<Instruction<'a>>, BudgetExceeded>, DecodeError> {
+        let mut instructions = Vec::new();
+        for instruction in instruction::decode(code) {
+            if let Err(exceeded) = self.instructions(1) {
+                return Ok(Err(exceeded));
+            }
+            instructions.push(instruction?);
+        }
+        Ok(Ok(instructions))
+    }
+
+    fn charge(
+        &mut self,
+        spent: usize,
+        max: Option<usize>,
+        limit: Limit,
+    ) -> Result<(), BudgetExceeded> {
+        if max.is_some_and(|max| spent > max) {
+            return Err(BudgetExceeded { limit });
+        }
+        let deadline = match self.budget.deadline {
+            Some(deadline) => deadline,
+            None => return Ok(()),
+        };
+        // the first charge always looks, so that nothing starts once it's late
+        self.ticks = (self.ticks + 1) % DEADLINE_INTERVAL;
+        if self.ticks == 1 && Instant::now() >= deadline {
+            return Err(BudgetExceeded {
+                limit: Limit::Deadline,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// What an analysis found within its budget. When `exceeded` is set the
+/// analysis stopped early and `value` holds only what it had found by then.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Partial<T> {
+    pub value: T,
+    pub exceeded: Option<BudgetExceeded>,
+}
+
+impl<T> Partial<T> {
+    pub fn complete(value: T) -> Self {
+        Partial {
+            value,
+            exceeded: None,
+        }
+    }
+
+    /// What an analysis which stopped early at `exceeded` had found.
+    pub fn cut_short(value: T, exceeded: BudgetExceeded) -> Self {
+        Partial {
+            value,
+            exceeded: Some(exceeded),
+        }
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.exceeded.is_none()
+    }
+
+    /// The value, whether or not the analysis finished.
+    pub fn into_value(self) -> T {
+        self.value
+    }
+}
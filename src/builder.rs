@@ -0,0 +1,287 @@
+//! Classes put together from scratch, for test fixtures and generated code.
+//!
+//! ```
+//! use jvmb::{
+//!     accessflags::{ACC_PUBLIC, ACC_STATIC},
+//!     attribute::Code,
+//!     builder::ClassFileBuilder,
+//! };
+//!
+//! let mut builder = ClassFileBuilder::new("com/example/Foo");
+//! let object_init = builder
+//!     .constant_pool()
+//!     .method_ref("java/lang/Object", "<init>", "()V");
+//! let [high, low] = object_init.to_be_bytes();
+//! builder
+//!     .add_field(ACC_PUBLIC | ACC_STATIC, "count", "I")
+//!     .add_method(
+//!         ACC_PUBLIC,
+//!         "<init>",
+//!         "()V",
+//!         // aload_0, invokespecial Object.<init>, return
+//!         Some(Code::new(1, 1, vec![0x2a, 0xb7, high, low, 0xb1])),
+//!     );
+//! let class_file = builder.build().unwrap();
+//! assert_eq!(class_file.class_name(), Ok("com/example/Foo"));
+//! ```
+
+use std::io;
+
+use crate::{
+    accessflags::{ClassAccessFlags, ACC_PUBLIC, ACC_SUPER},
+    attribute::{Attribute, Code},
+    classfile::ClassFile,
+    constantpool::{ConstantPool, DoubleBits, FloatBits},
+    fieldinfo::FieldInfo,
+    methodinfo::MethodInfo,
+    spec::JavaVersion,
+};
+
+/// A constant pool built up an entry at a time. Each method returns the
+/// index of an entry equal to the one asked for, adding it, and the entries
+/// it refers to, only if the pool doesn't have one yet.
+#[derive(Debug, Default)]
+pub struct ConstantPoolBuilder {
+    entries: Vec<ConstantPool>,
+    /// Set once an entry didn't fit in the 65,535 slots a pool can have.
+    full: bool,
+}
+
+impl ConstantPoolBuilder {
+    pub fn new() -> Self {
+        ConstantPoolBuilder::default()
+    }
+
+    pub fn utf8(&mut self, value: &str) -> u16 {
+        self.intern(ConstantPool::UTF8(value.to_string()))
+    }
+
+    /// A `CONSTANT_Class` naming `name`, e.g. `java/lang/String` or `[I`.
+    pub fn class(&mut self, name: &str) -> u16 {
+        let name = self.utf8(name);
+        self.intern(ConstantPool::Class(name))
+    }
+
+    pub fn string(&mut self, value: &str) -> u16 {
+        let value = self.utf8(value);
+        self.intern(ConstantPool::String(value))
+    }
+
+    pub fn integer(&mut self, value: i32) -> u16 {
+        self.intern(ConstantPool::Integer(value))
+    }
+
+    pub fn float(&mut self, value: f32) -> u16 {
+        self.intern(ConstantPool::Float(FloatBits(value.to_bits())))
+    }
+
+    /// Takes two slots, the second of them unusable.
+    pub fn long(&mut self, value: i64) -> u16 {
+        self.intern(ConstantPool::Long(value))
+    }
+
+    /// Takes two slots, the second of them unusable.
+    pub fn double(&mut self, value: f64) -> u16 {
+        self.intern(ConstantPool::Double(DoubleBits(value.to_bits())))
+    }
+
+    pub fn name_and_type(&mut self, name: &str, descriptor: &str) -> u16 {
+        let name = self.utf8(name);
+        let descriptor = self.utf8(descriptor);
+        self.intern(ConstantPool::NameAndType(name, descriptor))
+    }
+
+    /// A `CONSTANT_Fieldref` to the field `name` of type `descriptor` in
+    /// `class`.
+    pub fn field_ref(&mut self, class: &str, name: &str, descriptor: &str) -> u16 {
+        let (class, name_and_type) = self.member(class, name, descriptor);
+        self.intern(ConstantPool::FieldRef(class, name_and_type))
+    }
+
+    /// A `CONSTANT_Methodref` to the method `name` with `descriptor` in
+    /// `class`.
+    pub fn method_ref(&mut self, class: &str, name: &str, descriptor: &str) -> u16 {
+        let (class, name_and_type) = self.member(class, name, descriptor);
+        self.intern(ConstantPool::MethodRef(class, name_and_type))
+    }
+
+    pub fn interface_method_ref(&mut self, class: &str, name: &str, descriptor: &str) -> u16 {
+        let (class, name_and_type) = self.member(class, name, descriptor);
+        self.intern(ConstantPool::InterfaceMethodRef(class, name_and_type))
+    }
+
+    /// The entries, in the form [`ClassFile::constant_pool`] holds them.
+    /// Fails if they didn't all fit.
+    pub fn build(self) -> io::Result<Vec<ConstantPool>> {
+        if self.full {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "more constants than a constant pool can hold",
+            ));
+        }
+        Ok(self.entries)
+    }
+
+    fn member(&mut self, class: &str, name: &str, descriptor: &str) -> (u16, u16) {
+        (self.class(class), self.name_and_type(name, descriptor))
+    }
+
+    fn intern(&mut self, constant: ConstantPool) -> u16 {
+        if let Some(position) = self.entries.iter().position(|entry| *entry == constant) {
+            return position as u16 + 1;
+        }
+        let wide = matches!(constant, ConstantPool::Long(_) | ConstantPool::Double(_));
+        let slots = if wide { 2 } else { 1 };
+        // index 0 is never used, so a pool holds up to 65,534 slots
+        if self.entries.len() + slots > u16::MAX as usize - 1 {
+            self.full = true;
+            return 0;
+        }
+        self.entries.push(constant);
+        let index = self.entries.len() as u16;
+        if wide {
+            self.entries.push(ConstantPool::Unusable);
+        }
+        index
+    }
+}
+
+/// A class built up a member at a time. It starts out public, extending
+/// `java/lang/Object`, with the class file version of Java 8 so that code
+/// without branches needs no `StackMapTable`.
+#[derive(Debug)]
+pub struct ClassFileBuilder {
+    constant_pool: ConstantPoolBuilder,
+    major_version: u16,
+    minor_version: u16,
+    access_flags: u16,
+    this_class: u16,
+    super_class: String,
+    interfaces: Vec<u16>,
+    fields: Vec<FieldInfo>,
+    methods: Vec<MethodInfo>,
+}
+
+impl ClassFileBuilder {
+    /// A builder of the class with the internal name `name`, e.g.
+    /// `com/example/Foo`.
+    pub fn new(name: &str) -> Self {
+        let mut constant_pool = ConstantPoolBuilder::new();
+        let this_class = constant_pool.class(name);
+        ClassFileBuilder {
+            constant_pool,
+            major_version: JavaVersion::Java8.major_version(),
+            minor_version: 0,
+            access_flags: ACC_PUBLIC | ACC_SUPER,
+            this_class,
+            super_class: "java/lang/Object".to_string(),
+            interfaces: Vec::new(),
+            fields: Vec::new(),
+            methods: Vec::new(),
+        }
+    }
+
+    /// The pool the class is built with, for the constants its code refers
+    /// to.
+    pub fn constant_pool(&mut self) -> &mut ConstantPoolBuilder {
+        &mut self.constant_pool
+    }
+
+    pub fn version(&mut self, major_version: u16, minor_version: u16) -> &mut Self {
+        self.major_version = major_version;
+        self.minor_version = minor_version;
+        self
+    }
+
+    pub fn access_flags(&mut self, access_flags: u16) -> &mut Self {
+        self.access_flags = access_flags;
+        self
+    }
+
+    pub fn super_class(&mut self, name: &str) -> &mut Self {
+        self.super_class = name.to_string();
+        self
+    }
+
+    pub fn add_interface(&mut self, name: &str) -> &mut Self {
+        let interface = self.constant_pool.class(name);
+        self.interfaces.push(interface);
+        self
+    }
+
+    pub fn add_field(&mut self, access_flags: u16, name: &str, descriptor: &str) -> &mut Self {
+        let name = self.constant_pool.utf8(name);
+        let descriptor = self.constant_pool.utf8(descriptor);
+        self.fields.push(FieldInfo::from_parts(
+            access_flags,
+            name,
+            descriptor,
+            Vec::new(),
+        ));
+        self
+    }
+
+    /// Adds a method, with `code` as its body unless it's abstract or
+    /// native.
+    pub fn add_method(
+        &mut self,
+        access_flags: u16,
+        name: &str,
+        descriptor: &str,
+        code: Option<Code>,
+    ) -> &mut Self {
+        let name = self.constant_pool.utf8(name);
+        let descriptor = self.constant_pool.utf8(descriptor);
+        let attributes = match code {
+            Some(code) => {
+                self.constant_pool.utf8("Code");
+                vec![Attribute::Code(code)]
+            }
+            None => Vec::new(),
+        };
+        self.methods.push(MethodInfo::from_parts(
+            access_flags,
+            name,
+            descriptor,
+            attributes,
+        ));
+        self
+    }
+
+    /// The class, ready for [`ClassFile::to_bytes`]. Fails if its
+    /// constants don't fit in a constant pool, or it has more interfaces,
+    /// fields or methods than a class file can.
+    pub fn build(mut self) -> io::Result<ClassFile> {
+        let super_class = self.constant_pool.class(&self.super_class);
+        let count = |len: usize, what: &str| {
+            u16::try_from(len).map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{} {}, too many for a class file", len, what),
+                )
+            })
+        };
+        let interfaces_count = count(self.interfaces.len(), "interfaces")?;
+        let fields_count = count(self.fields.len(), "fields")?;
+        let methods_count = count(self.methods.len(), "methods")?;
+        let constant_pool = self.constant_pool.build()?;
+        Ok(ClassFile {
+            minor_version: self.minor_version,
+            major_version: self.major_version,
+            constant_pool_count: constant_pool.len() as u16 + 1,
+            constant_pool,
+            access_flags: ClassAccessFlags::new(self.access_flags),
+            this_class: self.this_class,
+            super_class,
+            interfaces_count,
+            interfaces: self.interfaces,
+            fields_count,
+            fields: self.fields,
+            methods_count,
+            methods: self.methods,
+            attributes_count: 0,
+            attributes: Vec::new(),
+            source: None,
+        })
+    }
+}
@@ -0,0 +1,308 @@
+//! A call graph over a set of classes, such as the classes of a jar, built
+//! from the calls [`MemberRefs`] finds in their code.
+//!
+//! A call links to the method its instruction names. Where a class of the
+//! graph doesn't declare that method, the search for a path goes on to the
+//! method it inherits from its superclasses, as the JVM resolves the call,
+//! but no further: an `invokevirtual` doesn't reach the overrides of the
+//! method, nor an `invokeinterface` the implementations of the method of
+//! the interface. The methods handed to the bootstrap method of an
+//! `invokedynamic`, such as that of a lambda, count as called by it.
+//!
+//! ```
+//! use jvmb::{attribute::Code, builder::ClassFileBuilder, callgraph::CallGraph};
+//!
+//! // a class whose method `run()V` calls `to` and returns
+//! let class = |name: &str, to: &str| {
+//!     let mut builder = ClassFileBuilder::new(name);
+//!     let [high, low] = builder
+//!         .constant_pool()
+//!         .method_ref(to, "run", "()V")
+//!         .to_be_bytes();
+//!     // invokestatic, return
+//!     let code = Code::new(0, 0, vec![0xb8, high, low, 0xb1]);
+//!     builder.add_method(0x0009, "run", "()V", Some(code));
+//!     builder.build().unwrap()
+//! };
+//! let classes = [class("A", "B"), class("B", "C"), class("C", "java/lang/Runtime")];
+//! let graph = CallGraph::from_classes(&classes);
+//!
+//! let from = graph.node("A", "run", "()V").unwrap();
+//! let to = graph.node("java/lang/Runtime", "run", "()V").unwrap();
+//! assert!(graph.method(to).external);
+//! let path: Vec<String> = graph
+//!     .path(&[from], &[to])
+//!     .unwrap()
+//!     .into_iter()
+//!     .map(|node| graph.method(node).to_string())
+//!     .collect();
+//! assert_eq!(path, ["A.run()V", "B.run()V", "C.run()V", "java/lang/Runtime.run()V"]);
+//! assert_eq!(graph.callers_of(to), [graph.node("C", "run", "()V").unwrap()]);
+//! assert_eq!(graph.path(&[to], &[from]), None);
+//! ```
+
+use std::{
+    collections::{hash_map::Entry, BTreeSet, HashMap, HashSet, VecDeque},
+    fmt,
+};
+
+use crate::{classfile::ClassFile, constantpool::MemberRef, xref::MemberRefs};
+
+/// A method of the graph.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MethodNode {
+    pub class_name: String,
+    pub name: String,
+    pub descriptor: String,
+    /// Whether the class isn't among those the graph was built from, such
+    /// as a class of the JDK. Nothing is known of what its methods call.
+    pub external: bool,
+    /// Whether its class is among those the graph was built from and
+    /// declares it, rather than it being only called.
+    pub declared: bool,
+}
+
+impl fmt::Display for MethodNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}{}", self.class_name, self.name, self.descriptor)
+    }
+}
+
+/// A call from one method to another.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Call {
+    /// The index of the method called.
+    pub callee: usize,
+    /// The bytecode offset of the instruction in the caller.
+    pub offset: u32,
+    pub opcode: u8,
+}
+
+/// The methods of a set of classes and of the classes they call, and the
+/// calls between them. Methods are referred to by their index, which is
+/// stable as classes are added.
+#[derive(Debug, Default, Clone)]
+pub struct CallGraph {
+    methods: Vec<MethodNode>,
+    index: HashMap<(String, String, String), usize>,
+    /// The calls of each method, in code order.
+    calls: Vec<Vec<Call>>,
+    /// The methods calling each method, each once, in ascending order.
+    callers: Vec<BTreeSet<usize>>,
+    classes: HashSet<String>,
+    /// The methods of each class.
+    by_class: HashMap<String, Vec<usize>>,
+    super_classes: HashMap<String, String>,
+}
+
+impl CallGraph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_classes<'a>(classes: impl IntoIterator<Item = &'a ClassFile>) -> Self {
+        let mut graph = Self::new();
+        for class in classes {
+            graph.add_class(class);
+        }
+        graph
+    }
+
+    /// Adds the methods of `class` and the calls their code makes. A class
+    /// is best added once; the calls of a class added twice are counted
+    /// twice.
+    pub fn add_class(&mut self, class: &ClassFile) {
+        let class_name = match class.class_name() {
+            Ok(class_name) => class_name,
+            Err(_) => return,
+        };
+        if self.classes.insert(class_name.to_string()) {
+            // the methods of the class called by classes added before it
+            for &node in self.by_class.get(class_name).into_iter().flatten() {
+                self.methods[node].external = false;
+            }
+        }
+        if let Ok(Some(super_class)) = class.super_class_name() {
+            self.super_classes
+                .insert(class_name.to_string(), super_class.to_string());
+        }
+        let constant_pool = &class.constant_pool;
+        for method in &class.methods {
+            let name = method.name(constant_pool).unwrap_or_default();
+            let descriptor = method.descriptor(constant_pool).unwrap_or_default();
+            let caller = self.intern(class_name, name, descriptor);
+            self.methods[caller].declared = true;
+            let refs = MemberRefs::of(method, class);
+            let mut calls: Vec<(u32, u8, MemberRef)> = refs
+                .calls
+                .iter()
+                .map(|call| (call.offset, call.opcode, call.member))
+                .collect();
+            for call in &refs.dynamic_calls {
+                for handle in call.method_handles(constant_pool) {
+                    calls.push((call.offset, 0xba, handle));
+                }
+            }
+            calls.sort_by_key(|(offset, _, _)| *offset);
+            for (offset, opcode, member) in calls {
+                let callee = self.intern(member.class_name, member.name, member.descriptor);
+                self.calls[caller].push(Call {
+                    callee,
+                    offset,
+                    opcode,
+                });
+                self.callers[callee].insert(caller);
+            }
+        }
+    }
+
+    fn intern(&mut self, class_name: &str, name: &str, descriptor: &str) -> usize {
+        let key = (
+            class_name.to_string(),
+            name.to_string(),
+            descriptor.to_string(),
+        );
+        if let Some(&node) = self.index.get(&key) {
+            return node;
+        }
+        let node = self.methods.len();
+        self.methods.push(MethodNode {
+            class_name: key.0.clone(),
+            name: key.1.clone(),
+            descriptor: key.2.clone(),
+            external: !self.classes.contains(class_name),
+            declared: false,
+        });
+        self.index.insert(key, node);
+        self.by_class
+            .entry(class_name.to_string())
+            .or_default()
+            .push(node);
+        self.calls.push(Vec::new());
+        self.callers.push(BTreeSet::new());
+        node
+    }
+
+    /// The number of methods.
+    pub fn len(&self) -> usize {
+        self.methods.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.methods.is_empty()
+    }
+
+    /// The method at index `node`.
+    ///
+    /// # Panics
+    ///
+    /// If there's no method at `node`.
+    pub fn method(&self, node: usize) -> &MethodNode {
+        &self.methods[node]
+    }
+
+    pub fn methods(&self) -> impl Iterator<Item = (usize, &MethodNode)> {
+        self.methods.iter().enumerate()
+    }
+
+    /// The index of a method, declared or called.
+    pub fn node(&self, class_name: &str, name: &str, descriptor: &str) -> Option<usize> {
+        self.index
+            .get(&(
+                class_name.to_string(),
+                name.to_string(),
+                descriptor.to_string(),
+            ))
+            .copied()
+    }
+
+    /// The calls `node` makes, in code order.
+    pub fn calls(&self, node: usize) -> &[Call] {
+        self.calls.get(node).map_or(&[], Vec::as_slice)
+    }
+
+    /// The methods calling `node`, in ascending order.
+    pub fn callers_of(&self, node: usize) -> Vec<usize> {
+        self.callers
+            .get(node)
+            .map_or_else(Vec::new, |callers| callers.iter().copied().collect())
+    }
+
+    /// For a method a class of the graph is called through without
+    /// declaring it, the method it inherits from the nearest of its
+    /// superclasses among the classes of the graph which declares one.
+    pub fn inherited(&self, node: usize) -> Option<usize> {
+        let method = self.methods.get(node)?;
+        if method.declared || method.external {
+            return None;
+        }
+        let mut class_name = method.class_name.as_str();
+        // no more steps than there are classes, should the chain loop
+        for _ in 0..self.super_classes.len() {
+            class_name = self.super_classes.get(class_name)?;
+            let found = self.node(class_name, &method.name, &method.descriptor);
+            if let Some(found) = found.filter(|&found| self.methods[found].declared) {
+                return Some(found);
+            }
+        }
+        None
+    }
+
+    /// The methods `node` calls, then the method it inherits.
+    fn successors(&self, node: usize) -> impl Iterator<Item = usize> + '_ {
+        self.calls[node]
+            .iter()
+            .map(|call| call.callee)
+            .chain(self.inherited(node))
+    }
+
+    /// Every method reachable from `entry` by calls, `entry` first, in the
+    /// order a breadth-first search finds them. A method called through a
+    /// class which inherits it is followed by the method inherited.
+    pub fn reachable_from(&self, entry: usize) -> Vec<usize> {
+        let mut seen = HashSet::new();
+        let mut order = Vec::new();
+        let mut pending = VecDeque::from([entry]);
+        while let Some(node) = pending.pop_front() {
+            if node < self.methods.len() && seen.insert(node) {
+                order.push(node);
+                pending.extend(self.successors(node));
+            }
+        }
+        order
+    }
+
+    /// One of the shortest chains of calls from any of `from` to any of
+    /// `to`, both ends included; `None` when there's none. A method of both
+    /// is a chain of itself. A method called through a class which inherits
+    /// it is followed in the chain by the method inherited, which
+    /// [`inherited`](Self::inherited) gives, rather than by a call.
+    pub fn path(&self, from: &[usize], to: &[usize]) -> Option<Vec<usize>> {
+        let targets: HashSet<usize> = to.iter().copied().collect();
+        // the method each was first reached from, `None` for those of `from`
+        let mut reached_from: HashMap<usize, Option<usize>> = HashMap::new();
+        let mut pending = VecDeque::new();
+        for &node in from {
+            if node < self.methods.len() && reached_from.insert(node, None).is_none() {
+                pending.push_back(node);
+            }
+        }
+        while let Some(node) = pending.pop_front() {
+            if targets.contains(&node) {
+                let mut path = vec![node];
+                while let Some(&Some(previous)) = reached_from.get(path.last()?) {
+                    path.push(previous);
+                }
+                path.reverse();
+                return Some(path);
+            }
+            for next in self.successors(node) {
+                if let Entry::Vacant(entry) = reached_from.entry(next) {
+                    entry.insert(Some(node));
+                    pending.push_back(next);
+                }
+            }
+        }
+        None
+    }
+}
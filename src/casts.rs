@@ -0,0 +1,82 @@
+//! Casts and type checks whose outcome the bytecode already guarantees.
+//!
+//! A `checkcast` is redundant when the inferred type of the value it checks
+//! is assignable to its target, or the value is always `null`. An
+//! `instanceof` with such a target can only fail for `null`, so it's no
+//! more than a null check. Whether one type is assignable to another is
+//! taken from a [`ClassIndex`]; types it can't settle are never reported.
+
+use crate::{
+    classfile::ClassFile,
+    classindex::ClassIndex,
+    constantpool::ConstantPool,
+    frames::{infer_frames, FrameType},
+    instruction::{self, DecodeError},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RedundantCast {
+    pub method_name: String,
+    pub method_descriptor: String,
+    pub offset: u32,
+    /// `checkcast` or `instanceof`.
+    pub mnemonic: &'static str,
+    /// The class or array type cast to.
+    pub target: String,
+    /// The type inferred for the value, or `null`.
+    pub inferred: String,
+}
+
+/// Finds the redundant casts and type checks in the methods of the class.
+pub fn redundant_casts(
+    class_file: &ClassFile,
+    index: &ClassIndex,
+) -> Result<Vec<RedundantCast>, DecodeError> {
+    let constant_pool = &class_file.constant_pool;
+    let mut casts = Vec::new();
+    for method in &class_file.methods {
+        let code = match method.code() {
+            Some(code) => code,
+            None => continue,
+        };
+        let frames = infer_frames(class_file, method)?;
+        for instruction in instruction::decode(&code.code).flatten() {
+            if !matches!(instruction.opcode, 0xc0 | 0xc1) {
+                continue;
+            }
+            let target = match instruction
+                .cp_index()
+                .and_then(|index| ConstantPool::class_name(constant_pool, index))
+            {
+                Some(target) => target,
+                None => continue,
+            };
+            let inferred = match frames
+                .get(&instruction.offset)
+                .and_then(|frame| frame.stack.last())
+            {
+                Some(FrameType::Reference(inferred))
+                    if index.is_assignable(inferred, target) == Some(true) =>
+                {
+                    inferred.as_str()
+                }
+                // instanceof null is always false, which is no redundancy
+                Some(FrameType::Null) if instruction.opcode == 0xc0 => "null",
+                _ => continue,
+            };
+            casts.push(RedundantCast {
+                method_name: ConstantPool::utf8(constant_pool, method.name_index())
+                    .unwrap_or_default()
+                    .to_string(),
+                method_descriptor: ConstantPool::utf8(constant_pool, method.descriptor_index())
+                    .unwrap_or_default()
+                    .to_string(),
+                offset: instruction.offset,
+                mnemonic: instruction.mnemonic(),
+                target: target.to_string(),
+                inferred: inferred.to_string(),
+            });
+        }
+    }
+    Ok(casts)
+}
@@ -0,0 +1,225 @@
+//! The control flow graph of a method body, split into basic blocks.
+
+use std::collections::BTreeSet;
+
+use crate::{
+    attribute::Code,
+    budget::{AnalysisBudget, Meter, Partial},
+    instruction::{DecodeError, Instruction},
+};
+
+/// A maximal run of instructions which is only entered at its first
+/// instruction and only left after its last.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BasicBlock {
+    /// The offset of the first instruction.
+    pub start: u32,
+    /// The offset just past the last instruction.
+    pub end: u32,
+    /// Blocks reached by branching or falling through, by index.
+    pub successors: Vec<usize>,
+    /// Handler blocks covering any instruction in this block, by index.
+    pub handlers: Vec<usize>,
+}
+
+/// How control gets from one block to another.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum EdgeKind {
+    /// On to the next block, after an instruction which doesn't branch or
+    /// a conditional branch not taken.
+    FallThrough,
+    /// A `goto`, `jsr` or conditional branch taken.
+    Jump,
+    /// A case, or the default, of a `tableswitch` or `lookupswitch`.
+    SwitchCase,
+    /// To a handler covering the block, when something is thrown.
+    Exception,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ControlFlowGraph {
+    /// Blocks in order of their offset; the first is the method entry.
+    pub blocks: Vec<BasicBlock>,
+    /// Every way between blocks as `(from, to, kind)`, by index, sorted.
+    /// The same two blocks can be joined by edges of more than one kind,
+    /// e.g. a conditional branch to the next instruction.
+    pub edges: Vec<(usize, usize, EdgeKind)>,
+}
+
+impl ControlFlowGraph {
+    pub fn build(code: &Code) -> Result<Self, DecodeError> {
+        Self::build_within(code, &AnalysisBudget::unlimited()).map(Partial::into_value)
+    }
+
+    /// Builds the graph unless it takes more than `budget` allows. A graph
+    /// cut short holds the blocks built by then, whose successors and
+    /// handlers may name blocks it doesn't have.
+    pub fn build_within(
+        code: &Code,
+        budget: &AnalysisBudget,
+    ) -> Result<Partial<Self>, DecodeError> {
+        Self::build_metered(code, &mut budget.meter())
+    }
+
+    pub(crate) fn build_metered(
+        code: &Code,
+        meter: &mut Meter,
+    ) -> Result<Partial<Self>, DecodeError> {
+        let cut_short = |blocks, edges, exceeded| {
+            Partial::cut_short(ControlFlowGraph { blocks, edges }, exceeded)
+        };
+        let instructions = match meter.decode(&code.code)? {
+            Ok(instructions) => instructions,
+            Err(exceeded) => return Ok(cut_short(Vec::new(), Vec::new(), exceeded)),
+        };
+        let code_length = code.code.len() as u32;
+
+        let mut leaders = BTreeSet::new();
+        leaders.insert(0);
+        for instruction in &instructions {
+            // a target outside the code starts no block, and gets no edge
+            leaders.extend(
+                instruction
+                    .branch_targets()
+                    .into_iter()
+                    .filter_map(|target| u32::try_from(target).ok()),
+            );
+            if ends_block(instruction) {
+                leaders.insert(instruction.offset + instruction.size() as u32);
+            }
+        }
+        for exception in &code.exception_table {
+            leaders.insert(exception.start_pc as u32);
+            leaders.insert(exception.end_pc as u32);
+            leaders.insert(exception.handler_pc as u32);
+        }
+        let starts: Vec<u32> = instructions
+            .iter()
+            .map(|instruction| instruction.offset)
+            .filter(|offset| leaders.contains(offset))
+            .collect();
+        let block_at = |offset: u32| starts.binary_search(&offset).ok();
+
+        let mut blocks = Vec::with_capacity(starts.len());
+        let mut edges = Vec::new();
+        for (i, &start) in starts.iter().enumerate() {
+            if let Err(exceeded) = meter.blocks(1) {
+                return Ok(cut_short(blocks, edges, exceeded));
+            }
+            let end = starts.get(i + 1).copied().unwrap_or(code_length);
+            let last = instructions
+                .partition_point(|instruction| instruction.offset < end)
+                .checked_sub(1)
+                .map(|last| &instructions[last])
+                .filter(|instruction| instruction.offset >= start);
+
+            let mut successors = Vec::new();
+            if let Some(last) = last {
+                let kind = if is_switch(last) {
+                    EdgeKind::SwitchCase
+                } else {
+                    EdgeKind::Jump
+                };
+                let targets = last.branch_targets().into_iter();
+                let targets = targets.filter_map(|target| u32::try_from(target).ok());
+                for target in targets.filter_map(block_at) {
+                    successors.push(target);
+                    edges.push((i, target, kind));
+                }
+                if falls_through(last) && end < code_length {
+                    if let Some(next) = block_at(end) {
+                        successors.push(next);
+                        edges.push((i, next, EdgeKind::FallThrough));
+                    }
+                }
+            }
+            successors.sort_unstable();
+            successors.dedup();
+
+            let mut handlers: Vec<usize> = code
+                .exception_table
+                .iter()
+                .filter(|exception| {
+                    (exception.start_pc as u32) < end && start < exception.end_pc as u32
+                })
+                .filter_map(|exception| block_at(exception.handler_pc as u32))
+                .collect();
+            handlers.sort_unstable();
+            handlers.dedup();
+            edges.extend(
+                handlers
+                    .iter()
+                    .map(|&handler| (i, handler, EdgeKind::Exception)),
+            );
+
+            blocks.push(BasicBlock {
+                start,
+                end,
+                successors,
+                handlers,
+            });
+        }
+
+        edges.sort_unstable();
+        edges.dedup();
+        Ok(Partial::complete(ControlFlowGraph { blocks, edges }))
+    }
+
+    /// The index of the block starting at `offset`.
+    pub fn block_at(&self, offset: u32) -> Option<usize> {
+        self.blocks
+            .binary_search_by_key(&offset, |block| block.start)
+            .ok()
+    }
+
+    /// Every block reachable from `root` by branching or falling through,
+    /// including `root` itself, in ascending order. Exception edges are not
+    /// followed.
+    pub fn reachable(&self, root: usize) -> BTreeSet<usize> {
+        let mut seen = BTreeSet::new();
+        let mut pending = vec![root];
+        while let Some(block) = pending.pop() {
+            if block < self.blocks.len() && seen.insert(block) {
+                pending.extend(&self.blocks[block].successors);
+            }
+        }
+        seen
+    }
+
+    /// The cyclomatic complexity of the method: one, plus one for every
+    /// way past the first out of a block, plus one for every exception
+    /// handler, each being a branch taken when something is thrown.
+    pub fn cyclomatic_complexity(&self) -> usize {
+        let branches: usize = self
+            .blocks
+            .iter()
+            .map(|block| block.successors.len().saturating_sub(1))
+            .sum();
+        let handlers: BTreeSet<usize> = self
+            .blocks
+            .iter()
+            .flat_map(|block| &block.handlers)
+            .copied()
+            .collect();
+        1 + branches + handlers.len()
+    }
+}
+
+/// Whether the instruction after `instruction` starts a new block.
+fn ends_block(instruction: &Instruction) -> bool {
+    // the conditional branches, goto, jsr, ret, the switches, the returns,
+    // athrow, ifnull, ifnonnull, goto_w and jsr_w
+    matches!(instruction.opcode, 0x99..=0xb1 | 0xbf | 0xc6..=0xc9)
+}
+
+/// Whether `instruction` is a `tableswitch` or `lookupswitch`.
+fn is_switch(instruction: &Instruction) -> bool {
+    matches!(instruction.opcode, 0xaa | 0xab)
+}
+
+/// Whether control may continue with the next instruction.
+fn falls_through(instruction: &Instruction) -> bool {
+    // goto, ret, the switches, the returns, athrow and goto_w never do; jsr
+    // and jsr_w are assumed to return to the instruction after them.
+    !matches!(instruction.opcode, 0xa7 | 0xa9..=0xb1 | 0xbf | 0xc8)
+}
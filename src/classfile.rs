@@ -1,19 +1,708 @@
-use nom::{bytes::complete::tag, multi::count, number::complete::be_u16, IResult};
+use alloc::collections::{BTreeMap, BTreeSet};
+use core::{
+    cell::{Cell, RefCell},
+    fmt,
+    ops::Range,
+    time::Duration,
+};
+#[cfg(feature = "std")]
+use std::{
+    collections::HashMap,
+    error::Error,
+    fs::File,
+    io::{self, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    time::Instant,
+};
 
+use nom::{bytes::complete::tag, sequence::tuple};
+
+#[cfg(feature = "archive")]
+use crate::jar::JarFile;
+use crate::prelude::*;
 use crate::{
-    attribute::{Attribute, AttributeInfo},
-    constantpool::ConstantPool,
+    accessflags::{AccessFlags, ClassAccessFlags, ACC_SYNTHETIC},
+    attribute::{
+        Attribute, AttributeInfo, BootstrapMethod, EnclosingMethod, InnerClasses,
+        RecordComponentInfo, ResolvedAnnotation, ResolvedModule, MAX_NESTING,
+    },
+    constantpool::{
+        ConstantOptions, ConstantPool, ConstantPoolError, DoubleBits, FloatBits, MemberRef,
+    },
+    descriptor,
+    error::{self, be_u16, be_u32, count, take, ErrorAt, IResult, ParseError},
     fieldinfo::FieldInfo,
+    kotlin::KotlinMetadata,
     methodinfo::MethodInfo,
+    metrics::ParseMetrics,
+    mutf8,
+    sizes::SizeReport,
+    smap::{Smap, SourceDebugExtension},
+    spec::{JavaVersion, LATEST_MAJOR_VERSION, OLDEST_MAJOR_VERSION},
+    validate::{self, ValidationError},
+};
+#[cfg(feature = "write")]
+use crate::{attribute::DEBUG_ATTRIBUTES, write::WriteBe};
+#[cfg(feature = "std")]
+use crate::{
+    attribute::{CustomAttribute, DeferredAttribute},
+    input::{Input, ReadFileError},
+};
+#[cfg(feature = "disasm")]
+use crate::{constantpool::Utf8Roles, symbols::SymbolTable, xref::MemberRefs};
+#[cfg(feature = "analysis")]
+use crate::{
+    diff::{self, FingerprintOptions},
+    switchmap::{self, EnumSwitchMap},
+};
+#[cfg(feature = "write")]
+use crate::{
+    patch::{self, ConstantReference, ScalarConstant, SetConstantError, SharedConstant},
+    remap::{self, PoolFull},
 };
 
-#[derive(Debug)]
+/// The way compiler-generated classes and members are marked.
+///
+/// Before class file version 49 (Java 5) the `ACC_SYNTHETIC` flag wasn't
+/// defined for every kind of element, so older compilers emitted a `Synthetic`
+/// attribute instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyntheticForm {
+    Flag,
+    Attribute,
+}
+
+impl SyntheticForm {
+    pub fn for_major_version(major_version: u16) -> Self {
+        if major_version < 49 {
+            SyntheticForm::Attribute
+        } else {
+            SyntheticForm::Flag
+        }
+    }
+
+    /// Rewrites the marking of a single class or member into this form. Does
+    /// nothing to elements which aren't synthetic.
+    pub(crate) fn apply(
+        self,
+        access_flags: &mut impl AccessFlags,
+        attributes: &mut Vec<Attribute>,
+    ) {
+        if access_flags.bits() & ACC_SYNTHETIC == 0 && !Attribute::has_synthetic(attributes) {
+            return;
+        }
+        match self {
+            SyntheticForm::Flag => {
+                access_flags.set(ACC_SYNTHETIC, true);
+                attributes.retain(|attr| !matches!(attr, Attribute::Synthetic(_)));
+            }
+            SyntheticForm::Attribute => {
+                access_flags.set(ACC_SYNTHETIC, false);
+                if !Attribute::has_synthetic(attributes) {
+                    attributes.push(Attribute::Synthetic(Vec::new()));
+                }
+            }
+        }
+    }
+}
+
+/// Reads the payload of a custom attribute, given its bytes and the constant
+/// pool of the class it's in.
+#[cfg(feature = "std")]
+pub type AttributeParser =
+    dyn Fn(&[u8], &[ConstantPool]) -> Result<Box<dyn CustomAttribute>, Box<dyn Error>>;
+
+/// Settings for [`ClassFile::parse_with`].
+#[derive(Default)]
+pub struct ParseOptions {
+    #[cfg(feature = "std")]
+    attribute_parsers: HashMap<String, Box<AttributeParser>>,
+    defer_over: Option<usize>,
+    defer_names: Vec<String>,
+    metrics: Option<RefCell<ParseMetrics>>,
+    pub(crate) reject_unsupported_versions: bool,
+    pub(crate) lenient_attribute_lengths: bool,
+    lenient_utf8: bool,
+    pub(crate) strict_attribute_versions: bool,
+    pub(crate) reject_unknown_attributes: bool,
+    max_nesting: Option<usize>,
+    /// tag -> size of the body, for constants unknown to jvmb
+    constant_sizes: BTreeMap<u8, usize>,
+}
+
+impl ParseOptions {
+    pub fn new() -> Self {
+        ParseOptions::default()
+    }
+
+    /// Options for a verifier, which wants every departure from the JVMS to
+    /// fail the parse: classes of unsupported versions, attributes their
+    /// version predates and attributes of unknown names are rejected, on
+    /// top of what the defaults already reject.
+    pub fn strict() -> Self {
+        let mut options = ParseOptions::new();
+        options
+            .reject_unsupported_versions()
+            .strict_attribute_versions()
+            .reject_unknown_attributes();
+        options
+    }
+
+    /// Options for scanning jars, which wants as much of every class as can
+    /// be read: attributes with bytes left over and UTF8 constants which
+    /// aren't modified UTF-8 are kept. What still fails the parse can be
+    /// salvaged with [`ClassFile::parse_salvage_with`].
+    ///
+    /// ```
+    /// use jvmb::{
+    ///     builder::ClassFileBuilder,
+    ///     classfile::{ClassFile, ParseOptions},
+    ///     constantpool::ConstantPool,
+    ///     error::ParseError,
+    /// };
+    ///
+    /// let mut builder = ClassFileBuilder::new("Damaged");
+    /// builder.add_field(0x0002, "name", "I");
+    /// let mut class = builder.build().unwrap();
+    /// let index = class.fields[0].name_index();
+    /// class.constant_pool[index as usize - 1] = ConstantPool::RawUtf8(vec![0xFF]);
+    /// let bytes = class.to_bytes().unwrap();
+    ///
+    /// let error = ClassFile::parse_with(&bytes, &ParseOptions::strict()).unwrap_err();
+    /// assert!(matches!(error, nom::Err::Failure(e) if e.code == ParseError::InvalidUtf8));
+    /// let (_, class) = ClassFile::parse_with(&bytes, &ParseOptions::lenient()).unwrap();
+    /// assert_eq!(class.validate().len(), 1);
+    /// ```
+    pub fn lenient() -> Self {
+        let mut options = ParseOptions::new();
+        options.lenient_attribute_lengths().lenient_utf8();
+        options
+    }
+
+    /// Parses attributes stored under `name` with `parser`, wherever they
+    /// appear, into [`Attribute::Custom`]. Names of attributes defined by the
+    /// JVM specification are always parsed by jvmb itself, and attributes of
+    /// other names without a parser are kept as [`Attribute::Unknown`].
+    #[cfg(feature = "std")]
+    pub fn register_attribute<F>(&mut self, name: impl Into<String>, parser: F) -> &mut Self
+    where
+        F: Fn(&[u8], &[ConstantPool]) -> Result<Box<dyn CustomAttribute>, Box<dyn Error>> + 'static,
+    {
+        self.attribute_parsers.insert(name.into(), Box::new(parser));
+        self
+    }
+
+    /// Leaves the body of any attribute longer than `bytes` unread, as an
+    /// [`Attribute::Deferred`] which can be loaded later through
+    /// [`ClassFile::load_deferred`] once [`ClassFile::source`] is set.
+    pub fn defer_attributes_over(&mut self, bytes: usize) -> &mut Self {
+        self.defer_over = Some(bytes);
+        self
+    }
+
+    /// Leaves the body of every attribute called `name` unread, whatever its
+    /// size, as [`ParseOptions::defer_attributes_over`] does for large ones.
+    pub fn defer_attribute(&mut self, name: impl Into<String>) -> &mut Self {
+        self.defer_names.push(name.into());
+        self
+    }
+
+    /// Records how long each phase of parsing takes, totalled over every
+    /// class parsed with these options, for [`ParseOptions::metrics`].
+    /// Without this, parsing doesn't look at the clock at all. Needs `std`.
+    #[cfg(feature = "std")]
+    pub fn collect_metrics(&mut self) -> &mut Self {
+        self.metrics = Some(RefCell::default());
+        self
+    }
+
+    /// The metrics collected so far, if they're being collected.
+    pub fn metrics(&self) -> Option<ParseMetrics> {
+        self.metrics
+            .as_ref()
+            .map(|metrics| metrics.borrow().clone())
+    }
+
+    /// Fails to parse classes of a version newer than
+    /// [`LATEST_MAJOR_VERSION`], with [`ParseError::UnsupportedVersion`] at
+    /// the version.
+    /// [`UnsupportedVersion::of`] tells that failure apart. By default such
+    /// classes are parsed as far as jvmb understands them.
+    pub fn reject_unsupported_versions(&mut self) -> &mut Self {
+        self.reject_unsupported_versions = true;
+        self
+    }
+
+    /// Accepts attributes with bytes left over after their contents, as some
+    /// obfuscators and older tools write them, dropping those bytes, and
+    /// `Synthetic` and `Deprecated` attributes which aren't empty, keeping
+    /// theirs. By default
+    /// such attributes fail the parse with
+    /// [`ParseError::AttributeLengthMismatch`].
+    pub fn lenient_attribute_lengths(&mut self) -> &mut Self {
+        self.lenient_attribute_lengths = true;
+        self
+    }
+
+    /// Keeps UTF8 constants whose bytes aren't modified UTF-8, such as the
+    /// `0xFF` bytes some obfuscators put in names, as
+    /// [`ConstantPool::RawUtf8`], which [`ClassFile::validate`] reports. By
+    /// default they fail the parse with [`ParseError::InvalidUtf8`] in the
+    /// constant.
+    ///
+    /// ```
+    /// use jvmb::{
+    ///     attribute::Code,
+    ///     builder::ClassFileBuilder,
+    ///     classfile::{ClassFile, ParseOptions},
+    ///     constantpool::ConstantPool,
+    /// };
+    ///
+    /// let mut builder = ClassFileBuilder::new("Obfuscated");
+    /// builder.add_field(0x0002, "name", "I");
+    /// builder.add_method(0x0009, "run", "()V", Some(Code::new(0, 0, vec![0xb1])));
+    /// let mut class = builder.build().unwrap();
+    /// // the name of the field, made `a` `0xFF` `b`
+    /// let index = class.fields[0].name_index();
+    /// class.constant_pool[index as usize - 1] = ConstantPool::RawUtf8(vec![b'a', 0xFF, b'b']);
+    /// let bytes = class.to_bytes().unwrap();
+    ///
+    /// let error = ClassFile::parse_class_file(&bytes).unwrap_err();
+    /// assert!(error.to_string().contains(&format!("constant #{}", index)));
+    ///
+    /// let (_, class) = ClassFile::parse_with(&bytes, ParseOptions::new().lenient_utf8()).unwrap();
+    /// assert_eq!(class.fields[0].name(&class.constant_pool), None);
+    /// // the rest of the pool reads as ever, the name of the Code attribute too
+    /// assert_eq!(class.methods[0].name(&class.constant_pool).unwrap(), "run");
+    /// assert!(class.methods[0].code().is_some());
+    /// let errors = class.validate();
+    /// assert_eq!(errors.len(), 1);
+    /// assert_eq!(errors[0].location, format!("constant pool #{}", index));
+    /// ```
+    pub fn lenient_utf8(&mut self) -> &mut Self {
+        self.lenient_utf8 = true;
+        self
+    }
+
+    /// Fails to parse attributes which the class's version predates, such
+    /// as a `StackMapTable` before version 50 or a `Record` before 60, with
+    /// [`ParseError::AttributeBeforeVersion`]. By default they're parsed
+    /// like any other, for the verifier to warn of.
+    pub fn strict_attribute_versions(&mut self) -> &mut Self {
+        self.strict_attribute_versions = true;
+        self
+    }
+
+    /// Fails to parse attributes of names the JVMS doesn't define and no
+    /// parser is registered for, with [`ParseError::UnknownAttribute`]. By
+    /// default they're kept as [`Attribute::Unknown`], as the JVM ignores
+    /// them. Deferred attributes aren't read, so aren't rejected.
+    pub fn reject_unknown_attributes(&mut self) -> &mut Self {
+        self.reject_unknown_attributes = true;
+        self
+    }
+
+    /// Fails to parse attributes nested more than `levels` deep, or element
+    /// values of annotations, with [`ParseError::NestingTooDeep`], before
+    /// hostile input can run parsing out of stack. The default is 16, well
+    /// past what compilers write.
+    pub fn max_nesting(&mut self, levels: usize) -> &mut Self {
+        self.max_nesting = Some(levels);
+        self
+    }
+
+    /// Reads constant pool entries tagged `tag`, which jvmb doesn't know, as
+    /// [`ConstantPool::Unknown`] with a body of `bytes`, in classes of an
+    /// unsupported version. Without a size for it, an unknown tag fails the
+    /// parse with [`ParseError::UnknownConstantTag`], as it always does in
+    /// classes of a supported version.
+    pub fn assume_constant_size(&mut self, tag: u8, bytes: usize) -> &mut Self {
+        self.constant_sizes.insert(tag, bytes);
+        self
+    }
+
+    /// How to read the pool of a class of `major_version`: the sizes to
+    /// skip unknown constants by, and what to make of invalid UTF8 ones.
+    pub(crate) fn constant_options(&self, major_version: u16) -> ConstantOptions<'_> {
+        ConstantOptions {
+            unknown_sizes: (major_version > LATEST_MAJOR_VERSION).then_some(&self.constant_sizes),
+            lenient_utf8: self.lenient_utf8,
+        }
+    }
+
+    /// How deep attributes and element values may nest.
+    pub(crate) fn nesting_limit(&self) -> usize {
+        self.max_nesting.unwrap_or(MAX_NESTING)
+    }
+
+    #[cfg(feature = "std")]
+    pub(crate) fn attribute_parser(&self, name: &str) -> Option<&AttributeParser> {
+        self.attribute_parsers
+            .get(name)
+            .map(|parser| parser.as_ref())
+    }
+
+    /// Whether an attribute is to be left unread. `name` is only called
+    /// when some attribute is deferred by name.
+    pub(crate) fn defers(&self, len: usize, name: impl FnOnce() -> String) -> Option<String> {
+        let by_size = self.defer_over.is_some_and(|threshold| len > threshold);
+        if !by_size && self.defer_names.is_empty() {
+            return None;
+        }
+        let name = name();
+        (by_size || self.defer_names.contains(&name)).then_some(name)
+    }
+}
+
+/// What parsing passes down to every attribute table.
+pub(crate) struct ParseContext<'a> {
+    pub(crate) options: &'a ParseOptions,
+    /// The whole class file, of which every attribute body is a slice.
+    pub(crate) class: &'a [u8],
+    /// How many attributes enclose the one being parsed.
+    pub(crate) depth: Cell<usize>,
+    /// The major version of the class, once its header has been read.
+    pub(crate) major_version: Cell<Option<u16>>,
+}
+
+impl<'a> ParseContext<'a> {
+    pub(crate) fn new(options: &'a ParseOptions, class: &'a [u8]) -> Self {
+        ParseContext {
+            options,
+            class,
+            depth: Cell::new(0),
+            major_version: Cell::new(None),
+        }
+    }
+
+    /// The offset of `part`, a slice of the class file, from its start.
+    pub(crate) fn offset_of(&self, part: &[u8]) -> usize {
+        part.as_ptr() as usize - self.class.as_ptr() as usize
+    }
+
+    /// The start of a phase to pass to [`ParseContext::record`], or `None`
+    /// when metrics aren't being collected.
+    #[cfg(feature = "std")]
+    pub(crate) fn start(&self) -> Option<Instant> {
+        self.options.metrics.as_ref().map(|_| Instant::now())
+    }
+
+    /// Without `std` there's no clock, so metrics are never collected.
+    #[cfg(not(feature = "std"))]
+    pub(crate) fn start(&self) -> Option<Instant> {
+        None
+    }
+
+    pub(crate) fn record(
+        &self,
+        started: Option<Instant>,
+        record: impl FnOnce(&mut ParseMetrics, Duration),
+    ) {
+        if let (Some(started), Some(metrics)) = (started, &self.options.metrics) {
+            record(&mut metrics.borrow_mut(), started.elapsed());
+        }
+    }
+}
+
+/// The start of a phase, of which there's none without a clock.
+#[cfg(not(feature = "std"))]
+#[derive(Clone, Copy)]
+pub(crate) enum Instant {}
+
+#[cfg(not(feature = "std"))]
+impl Instant {
+    fn elapsed(self) -> Duration {
+        match self {}
+    }
+}
+
+/// Where a class file was read from, for loading attributes whose bodies
+/// were deferred.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ClassSource {
+    /// A file containing the class at `offset`, which is 0 unless classes
+    /// were laid back to back.
+    File { path: PathBuf, offset: u64 },
+    /// An entry of a jar file.
+    JarEntry { jar: PathBuf, entry: String },
+}
+
+impl fmt::Debug for ParseOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("ParseOptions");
+        #[cfg(feature = "std")]
+        {
+            let mut names: Vec<&String> = self.attribute_parsers.keys().collect();
+            names.sort();
+            debug.field("attribute_parsers", &names);
+        }
+        debug
+            .field("defer_over", &self.defer_over)
+            .field("defer_names", &self.defer_names)
+            .field("metrics", &self.metrics)
+            .field(
+                "reject_unsupported_versions",
+                &self.reject_unsupported_versions,
+            )
+            .field("lenient_attribute_lengths", &self.lenient_attribute_lengths)
+            .field("lenient_utf8", &self.lenient_utf8)
+            .field("strict_attribute_versions", &self.strict_attribute_versions)
+            .field("reject_unknown_attributes", &self.reject_unknown_attributes)
+            .field("max_nesting", &self.max_nesting)
+            .field("constant_sizes", &self.constant_sizes)
+            .finish()
+    }
+}
+
+/// The start of a class file, up to its interfaces: what the class is,
+/// without its members and attributes.
+#[derive(Debug, Clone)]
+pub struct ClassHeader {
+    pub minor_version: u16,
+    pub major_version: u16,
+    pub constant_pool_count: u16,
+    pub constant_pool: Vec<ConstantPool>,
+    pub access_flags: ClassAccessFlags,
+    pub this_class: u16,
+    pub super_class: u16,
+    pub interfaces_count: u16,
+    pub interfaces: Vec<u16>,
+}
+
+impl ClassHeader {
+    /// Parses the start of the class file in `buf`, returning the rest of
+    /// it from the fields on.
+    pub fn parse_with<'a>(buf: &'a [u8], options: &ParseOptions) -> IResult<&'a [u8], ClassHeader> {
+        let context = ParseContext::new(options, buf);
+        Self::parse(buf, &context).map_err(|e| error::locate(e, buf))
+    }
+
+    fn parse<'a>(buf: &'a [u8], context: &ParseContext) -> IResult<&'a [u8], ClassHeader> {
+        let options = context.options;
+        let (buf, ()) = magic(buf)?;
+        let (buf, minor_version) = be_u16(buf)?;
+        let version = buf;
+        let (buf, major_version) = be_u16(buf)?;
+        let too_new = options.reject_unsupported_versions && major_version > LATEST_MAJOR_VERSION;
+        if major_version < OLDEST_MAJOR_VERSION || too_new {
+            return Err(ErrorAt::failure(
+                version,
+                ParseError::UnsupportedVersion(major_version),
+            ));
+        }
+        context.major_version.set(Some(major_version));
+        let (buf, constant_pool_count) = be_u16(buf)?;
+        let phase = context.start();
+        let (buf, constant_pool) = ConstantPool::parse_skipping(
+            buf,
+            constant_pool_count as usize,
+            options.constant_options(major_version),
+        )?;
+        context.record(phase, |metrics, time| {
+            metrics.constant_pool.record(time, constant_pool.len())
+        });
+        let (buf, access_flags) = be_u16(buf)?;
+        let access_flags = ClassAccessFlags::new(access_flags);
+        let (buf, this_class) = be_u16(buf)?;
+        let (buf, super_class) = be_u16(buf)?;
+        let (buf, interfaces_count) = be_u16(buf)?;
+        let (buf, interfaces) = count(be_u16, interfaces_count as usize, 2)(buf)?;
+        Ok((
+            buf,
+            ClassHeader {
+                minor_version,
+                major_version,
+                constant_pool_count,
+                constant_pool,
+                access_flags,
+                this_class,
+                super_class,
+                interfaces_count,
+                interfaces,
+            },
+        ))
+    }
+
+    /// The internal name of the class.
+    pub fn name(&self) -> Option<&str> {
+        ConstantPool::class_name(&self.constant_pool, self.this_class)
+    }
+
+    /// The internal name of the superclass, `None` for `java/lang/Object`
+    /// and module descriptors.
+    pub fn super_class_name(&self) -> Option<&str> {
+        ConstantPool::class_name(&self.constant_pool, self.super_class)
+    }
+
+    /// The internal names of the interfaces.
+    pub fn interface_names(&self) -> impl Iterator<Item = &str> {
+        self.interfaces
+            .iter()
+            .filter_map(|&index| ConstantPool::class_name(&self.constant_pool, index))
+    }
+
+    /// The values of the class's string literals, as
+    /// [`ClassFile::string_constants`] gives them.
+    pub fn string_constants(&self) -> Vec<&str> {
+        ConstantPool::string_values(&self.constant_pool)
+    }
+}
+
+/// What a class is, as [`ClassFile::parse_header`] reads it, with its
+/// names resolved and nothing of its constant pool kept.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeaderSummary {
+    pub minor_version: u16,
+    pub major_version: u16,
+    pub java_version: JavaVersion,
+    pub access_flags: ClassAccessFlags,
+    /// The internal name of the class, e.g. `com/example/Foo`.
+    pub name: String,
+    /// `None` for `java/lang/Object` and module descriptors.
+    pub super_class: Option<String>,
+    pub interfaces: Vec<String>,
+}
+
+const CONSTANT_UTF8: u8 = 1;
+
+/// A constant pool entry as [`HeaderSummary::parse`] keeps it: a UTF8
+/// entry as its undecoded bytes.
+enum HeaderConstant<'a> {
+    Utf8(&'a [u8]),
+    Other(ConstantPool),
+}
+
+/// The header with its names still indices into the walked pool.
+struct RawHeader<'a> {
+    minor_version: u16,
+    major_version: u16,
+    constant_pool: Vec<HeaderConstant<'a>>,
+    access_flags: ClassAccessFlags,
+    this_class: u16,
+    super_class: u16,
+    interfaces: Vec<u16>,
+}
+
+impl HeaderSummary {
+    fn parse(buf: &[u8]) -> Result<HeaderSummary, ParseError> {
+        let (_, raw) = Self::parse_raw(buf).map_err(|e| error::locate(e, buf))?;
+        let class_name = |index| {
+            Self::class_name(&raw.constant_pool, index).map_err(|e| match e {
+                Some(e) => ParseError::InvalidConstantPoolIndex(e),
+                None => ParseError::InvalidUtf8,
+            })
+        };
+        Ok(HeaderSummary {
+            minor_version: raw.minor_version,
+            major_version: raw.major_version,
+            java_version: JavaVersion::from_major(raw.major_version),
+            access_flags: raw.access_flags,
+            name: class_name(raw.this_class)?,
+            super_class: match raw.super_class {
+                0 => None,
+                index => Some(class_name(index)?),
+            },
+            interfaces: raw
+                .interfaces
+                .iter()
+                .map(|&index| class_name(index))
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    fn parse_raw(buf: &[u8]) -> IResult<&[u8], RawHeader<'_>> {
+        let (buf, ()) = magic(buf)?;
+        let (buf, minor_version) = be_u16(buf)?;
+        let version = buf;
+        let (buf, major_version) = be_u16(buf)?;
+        if major_version < OLDEST_MAJOR_VERSION {
+            return Err(ErrorAt::failure(
+                version,
+                ParseError::UnsupportedVersion(major_version),
+            ));
+        }
+        let (mut buf, constant_pool_count) = be_u16(buf)?;
+        let mut constant_pool = Vec::with_capacity(
+            (constant_pool_count as usize)
+                .saturating_sub(1)
+                .min(buf.len() / 3),
+        );
+        while constant_pool.len() + 1 < constant_pool_count as usize {
+            if buf.first() == Some(&CONSTANT_UTF8) {
+                let (rest, length) = be_u16(&buf[1..])?;
+                let (rest, bytes) = take(length as usize)(rest)?;
+                constant_pool.push(HeaderConstant::Utf8(bytes));
+                buf = rest;
+                continue;
+            }
+            let (rest, constant) = ConstantPool::parse_constant(buf, ConstantOptions::default())?;
+            let wide = matches!(constant, ConstantPool::Long(_) | ConstantPool::Double(_));
+            constant_pool.push(HeaderConstant::Other(constant));
+            if wide {
+                constant_pool.push(HeaderConstant::Other(ConstantPool::Unusable));
+            }
+            buf = rest;
+        }
+        let (buf, access_flags) = be_u16(buf)?;
+        let (buf, this_class) = be_u16(buf)?;
+        let (buf, super_class) = be_u16(buf)?;
+        let (buf, interfaces_count) = be_u16(buf)?;
+        let (buf, interfaces) = count(be_u16, interfaces_count as usize, 2)(buf)?;
+        Ok((
+            buf,
+            RawHeader {
+                minor_version,
+                major_version,
+                constant_pool,
+                access_flags: ClassAccessFlags::new(access_flags),
+                this_class,
+                super_class,
+                interfaces,
+            },
+        ))
+    }
+
+    /// The name the `CONSTANT_Class` entry at `index` holds, decoded; the
+    /// error is `None` when it isn't modified UTF-8.
+    fn class_name(
+        constant_pool: &[HeaderConstant],
+        index: u16,
+    ) -> Result<String, Option<ConstantPoolError>> {
+        let entry = |index: u16| {
+            constant_pool.get((index as usize).wrapping_sub(1)).ok_or(
+                ConstantPoolError::OutOfRange {
+                    index,
+                    count: constant_pool.len(),
+                },
+            )
+        };
+        let wrong_kind = |found: &HeaderConstant, index, expected| match found {
+            HeaderConstant::Utf8(_) => {
+                ConstantPool::UTF8(String::new()).wrong_kind(index, expected)
+            }
+            HeaderConstant::Other(found) => found.wrong_kind(index, expected),
+        };
+        let name_index = match entry(index)? {
+            HeaderConstant::Other(ConstantPool::Class(name_index)) => *name_index,
+            found => return Err(Some(wrong_kind(found, index, "Class"))),
+        };
+        match entry(name_index)? {
+            HeaderConstant::Utf8(bytes) => mutf8::decode(bytes).ok_or(None),
+            found => Err(Some(wrong_kind(found, name_index, "Utf8"))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ClassFile {
     pub minor_version: u16,
     pub major_version: u16,
     pub constant_pool_count: u16,
     pub constant_pool: Vec<ConstantPool>,
-    pub access_flags: u16,
+    pub access_flags: ClassAccessFlags,
     pub this_class: u16,
     pub super_class: u16,
     pub interfaces_count: u16,
@@ -24,28 +713,205 @@ pub struct ClassFile {
     pub methods: Vec<MethodInfo>,
     pub attributes_count: u16,
     pub attributes: Vec<Attribute>,
+    /// Where the class was read from. Parsing leaves this unset; it's only
+    /// needed to load deferred attributes.
+    #[cfg(feature = "std")]
+    pub source: Option<ClassSource>,
 }
 
 impl ClassFile {
+    /// The class file which is the whole of `buf`. Bytes left after the end
+    /// of the class are an error, [`ParseError::TrailingBytes`], where
+    /// [`parse_class_file`](Self::parse_class_file) returns them for the
+    /// caller to go on parsing.
+    ///
+    /// ```
+    /// use jvmb::{builder::ClassFileBuilder, error::ParseError, ClassFile};
+    ///
+    /// let mut bytes = ClassFileBuilder::new("Foo").build().unwrap().to_bytes().unwrap();
+    /// assert!(ClassFile::from_bytes(&bytes).is_ok());
+    ///
+    /// bytes.extend_from_slice(b"garbage");
+    /// assert_eq!(ClassFile::from_bytes(&bytes).unwrap_err(), ParseError::TrailingBytes);
+    /// let (rest, _) = ClassFile::parse_class_file(&bytes).unwrap();
+    /// assert_eq!(rest, b"garbage");
+    /// ```
+    ///
+    /// A class cut short, wherever that is, fails with
+    /// [`ParseError::Truncated`], where a class whose bytes make no sense
+    /// fails otherwise:
+    ///
+    /// ```
+    /// use jvmb::{attribute::Code, builder::ClassFileBuilder, error::ParseError, ClassFile};
+    ///
+    /// let mut builder = ClassFileBuilder::new("Foo");
+    /// builder.constant_pool().string("a constant");
+    /// builder.add_method(0x0009, "run", "()V", Some(Code::new(0, 0, vec![0xb1])));
+    /// let bytes = builder.build().unwrap().to_bytes().unwrap();
+    /// for len in 0..bytes.len() {
+    ///     match ClassFile::from_bytes(&bytes[..len]).unwrap_err() {
+    ///         ParseError::Truncated { expected, available, at_offset, .. } => {
+    ///             assert_eq!(at_offset + available, len);
+    ///             assert!(expected > available);
+    ///         }
+    ///         e => panic!("{} bytes: {}", len, e),
+    ///     }
+    /// }
+    ///
+    /// // the Code attribute's length cut by one, so that its body, all
+    /// // there, runs past it
+    /// let mut malformed = bytes.clone();
+    /// let code = bytes.windows(4).position(|w| w == b"Code").unwrap();
+    /// let length = bytes.windows(4).rposition(|w| w == [0, 0, 0, 13]).unwrap();
+    /// assert!(length > code);
+    /// malformed[length + 3] = 12;
+    /// malformed.pop();
+    /// let e = ClassFile::from_bytes(&malformed).unwrap_err();
+    /// assert!(matches!(e, ParseError::AttributeTruncated { .. }), "{}", e);
+    /// ```
+    pub fn from_bytes(buf: &[u8]) -> Result<ClassFile, ParseError> {
+        let (_, class_file) = error::all_consuming(buf, Self::parse_class_file)?;
+        Ok(class_file)
+    }
+
+    /// The class file which is all that's left to read of `reader`, as
+    /// [`from_bytes`](Self::from_bytes) parses it.
+    #[cfg(feature = "std")]
+    pub fn from_reader(mut reader: impl Read) -> Result<ClassFile, ReadFileError> {
+        let mut buf = Vec::new();
+        reader.read_to_end(&mut buf)?;
+        Ok(Self::from_bytes(&buf)?)
+    }
+
+    /// The class file at `path`, as [`from_bytes`](Self::from_bytes)
+    /// parses it. An error opening or reading the file names it:
+    ///
+    /// ```
+    /// use jvmb::ClassFile;
+    ///
+    /// let e = ClassFile::from_path("no/such/Foo.class").unwrap_err();
+    /// assert!(e.to_string().starts_with("no/such/Foo.class: "));
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn from_path(path: impl AsRef<Path>) -> Result<ClassFile, ReadFileError> {
+        let path = path.as_ref();
+        let input = Input::map(path).map_err(|e| ReadFileError::file(path, e))?;
+        Ok(Self::from_bytes(&input)?)
+    }
+
+    /// The class file at the start of `buf`, and the bytes after it. This
+    /// is the parser to compose with others; a class file on its own is
+    /// better read with [`from_bytes`](Self::from_bytes) or
+    /// [`from_path`](Self::from_path).
     pub fn parse_class_file(buf: &[u8]) -> IResult<&[u8], ClassFile> {
-        println!("{:X?}", buf);
-        let (buf, _magic) = tag(0xCAFEBABEu32.to_be_bytes())(buf)?;
-        let (buf, minor_version) = be_u16(buf)?;
-        let (buf, major_version) = be_u16(buf)?;
-        let (buf, constant_pool_count) = be_u16(buf)?;
-        let (buf, constant_pool) = ConstantPool::parse(buf, constant_pool_count as usize)?;
-        let (buf, access_flags) = be_u16(buf)?;
-        let (buf, this_class) = be_u16(buf)?;
-        let (buf, super_class) = be_u16(buf)?;
-        let (buf, interfaces_count) = be_u16(buf)?;
-        let (buf, interfaces) = count(be_u16, interfaces_count as usize)(buf)?;
+        Self::parse_with(buf, &ParseOptions::default())
+    }
+
+    /// Parses the class file at `path` with `options`, mapping it with the
+    /// `mmap` feature and reading it otherwise. The class owns what it
+    /// holds, so it outlives the mapping.
+    #[cfg(feature = "std")]
+    pub fn parse_from_path(
+        path: impl AsRef<Path>,
+        options: &ParseOptions,
+    ) -> Result<ClassFile, ReadFileError> {
+        let path = path.as_ref();
+        let input = Input::map(path).map_err(|e| ReadFileError::file(path, e))?;
+        let (_, class_file) =
+            ClassFile::parse_with(&input, options).map_err(|e| ReadFileError::Parse(e.into()))?;
+        Ok(class_file)
+    }
+
+    pub fn parse_with<'a>(buf: &'a [u8], options: &ParseOptions) -> IResult<&'a [u8], ClassFile> {
+        Self::parse_members(buf, options, false)
+    }
+
+    /// What the class file in `buf` is, read no further than its
+    /// interfaces. The constant pool is walked without decoding any string
+    /// but the names of the class and its supertypes, so that this takes a
+    /// fraction of the time of a full parse when they, the version and the
+    /// flags are all that's wanted.
+    pub fn parse_header(buf: &[u8]) -> Result<HeaderSummary, ParseError> {
+        HeaderSummary::parse(buf)
+    }
+
+    /// Where the bytes of the class file in `buf` go, as [`SizeReport`]
+    /// breaks them down.
+    pub fn size_report(buf: &[u8]) -> Result<SizeReport, ParseError> {
+        SizeReport::measure(buf)
+    }
+
+    /// The class in `buf` with its fields and methods stepped over by the
+    /// lengths of their attributes rather than parsed: `fields` and
+    /// `methods` are left empty, while `fields_count` and `methods_count`
+    /// still say how many there are. Only the attributes of the class itself
+    /// are parsed.
+    pub fn parse_skipping_members<'a>(
+        buf: &'a [u8],
+        options: &ParseOptions,
+    ) -> IResult<&'a [u8], ClassFile> {
+        Self::parse_members(buf, options, true)
+    }
+
+    fn parse_members<'a>(
+        buf: &'a [u8],
+        options: &ParseOptions,
+        skip: bool,
+    ) -> IResult<&'a [u8], ClassFile> {
+        Self::parse_class(buf, options, skip).map_err(|e| error::locate(e, buf))
+    }
+
+    fn parse_class<'a>(
+        buf: &'a [u8],
+        options: &ParseOptions,
+        skip: bool,
+    ) -> IResult<&'a [u8], ClassFile> {
+        let context = ParseContext::new(options, buf);
+        let started = context.start();
+        let (
+            buf,
+            ClassHeader {
+                minor_version,
+                major_version,
+                constant_pool_count,
+                constant_pool,
+                access_flags,
+                this_class,
+                super_class,
+                interfaces_count,
+                interfaces,
+            },
+        ) = ClassHeader::parse(buf, &context)?;
         let (buf, fields_count) = be_u16(buf)?;
-        let (buf, fields) = FieldInfo::parse(buf, fields_count, &constant_pool)?;
+        let phase = context.start();
+        let (buf, fields) = if skip {
+            let (buf, ()) = skip_members(buf, fields_count, "field", &constant_pool)?;
+            (buf, Vec::new())
+        } else {
+            FieldInfo::parse(buf, fields_count, &constant_pool, &context)?
+        };
+        context.record(phase, |metrics, time| {
+            metrics.fields.record(time, fields.len())
+        });
         let (buf, methods_count) = be_u16(buf)?;
-        let (buf, methods) = MethodInfo::parse(buf, methods_count, &constant_pool)?;
+        let phase = context.start();
+        let (buf, methods) = if skip {
+            let (buf, ()) = skip_members(buf, methods_count, "method", &constant_pool)?;
+            (buf, Vec::new())
+        } else {
+            MethodInfo::parse(buf, methods_count, &constant_pool, &context)?
+        };
+        context.record(phase, |metrics, time| {
+            metrics.methods.record(time, methods.len())
+        });
         let (buf, attributes_count) = be_u16(buf)?;
-        let (buf, attributes) = count(AttributeInfo::parse, attributes_count as usize)(buf)?;
-        let attributes = Attribute::from_attribute_info(attributes, &constant_pool);
+        let phase = context.start();
+        let (buf, attributes) =
+            Attribute::parse_table(buf, attributes_count, &constant_pool, &context)?;
+        context.record(phase, |metrics, time| {
+            metrics.class_attributes.record(time, attributes.len())
+        });
+        context.record(started, |metrics, time| metrics.total.record(time, 1));
 
         Ok((
             buf,
@@ -65,7 +931,1341 @@ impl ClassFile {
                 methods,
                 attributes_count,
                 attributes,
+                #[cfg(feature = "std")]
+                source: None,
             },
         ))
     }
+
+    /// Reads the body of a deferred attribute of this class from its
+    /// [`source`](ClassFile::source).
+    #[cfg(feature = "std")]
+    pub fn load_deferred(&self, deferred: &DeferredAttribute) -> io::Result<Vec<u8>> {
+        let mut body = vec![0; deferred.len];
+        match &self.source {
+            Some(ClassSource::File { path, offset }) => {
+                let mut file = File::open(path)?;
+                file.seek(SeekFrom::Start(offset + deferred.offset as u64))?;
+                file.read_exact(&mut body)?;
+            }
+            #[cfg(feature = "archive")]
+            Some(ClassSource::JarEntry { jar, entry }) => {
+                let to_io = io::Error::other;
+                let jar = JarFile::open(jar).map_err(to_io)?;
+                let entry = jar.entry(entry).ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::NotFound, format!("no entry {}", entry))
+                })?;
+                let class = jar.read(entry).map_err(to_io)?;
+                let bytes = class
+                    .get(deferred.offset..deferred.offset + deferred.len)
+                    .ok_or_else(|| {
+                        io::Error::new(io::ErrorKind::UnexpectedEof, "jar entry is too short")
+                    })?;
+                body.copy_from_slice(bytes);
+            }
+            #[cfg(not(feature = "archive"))]
+            Some(ClassSource::JarEntry { .. }) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "reading jars needs the archive feature",
+                ))
+            }
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    "the class has no source to load attributes from",
+                ))
+            }
+        }
+        Ok(body)
+    }
+
+    /// Parses as much of a damaged or truncated class file as it can. Every
+    /// table holds the entries which were complete before the damage, and
+    /// the rest of the class is left empty or zero. Attributes are only
+    /// kept whole, so a member whose attributes are cut short is dropped.
+    pub fn parse_salvage(buf: &[u8]) -> Salvaged {
+        Self::parse_salvage_with(buf, &ParseOptions::default())
+    }
+
+    /// Parses as much of a damaged class file as it can, as
+    /// [`ClassFile::parse_salvage`] does, with `options`, so that what they
+    /// let through doesn't count as damage.
+    pub fn parse_salvage_with(buf: &[u8], options: &ParseOptions) -> Salvaged {
+        let context = ParseContext::new(options, buf);
+        let mut class_file = ClassFile {
+            minor_version: 0,
+            major_version: 0,
+            constant_pool_count: 0,
+            constant_pool: Vec::new(),
+            access_flags: ClassAccessFlags::default(),
+            this_class: 0,
+            super_class: 0,
+            interfaces_count: 0,
+            interfaces: Vec::new(),
+            fields_count: 0,
+            fields: Vec::new(),
+            methods_count: 0,
+            methods: Vec::new(),
+            attributes_count: 0,
+            attributes: Vec::new(),
+            #[cfg(feature = "std")]
+            source: None,
+        };
+        let truncation = class_file.salvage(buf, &context).err();
+        Salvaged {
+            class_file,
+            truncation,
+        }
+    }
+
+    fn salvage(&mut self, buf: &[u8], context: &ParseContext) -> Result<(), Truncation> {
+        let stop = |stage, at: &[u8]| Truncation {
+            stage,
+            offset: context.offset_of(at),
+        };
+
+        let (buf, (_magic, minor_version, major_version, constant_pool_count)) = or_stop(
+            tuple((tag(0xCAFEBABEu32.to_be_bytes()), be_u16, be_u16, be_u16))(buf),
+            || stop(ParseStage::Header, buf),
+        )?;
+        self.minor_version = minor_version;
+        self.major_version = major_version;
+        self.constant_pool_count = constant_pool_count;
+        let buf = ConstantPool::parse_partial(
+            buf,
+            constant_pool_count as usize,
+            context.options.constant_options(major_version),
+            &mut self.constant_pool,
+        )
+        .map_err(|(at, _)| stop(ParseStage::ConstantPool, at))?;
+
+        let (buf, (access_flags, this_class, super_class)) =
+            or_stop(tuple((be_u16, be_u16, be_u16))(buf), || {
+                stop(ParseStage::ClassInfo, buf)
+            })?;
+        self.access_flags = ClassAccessFlags::new(access_flags);
+        self.this_class = this_class;
+        self.super_class = super_class;
+
+        let (buf, interfaces_count) = or_stop(be_u16(buf), || stop(ParseStage::Interfaces, buf))?;
+        self.interfaces_count = interfaces_count;
+        let buf = parse_partial(buf, interfaces_count, be_u16, &mut self.interfaces)
+            .map_err(|at| stop(ParseStage::Interfaces, at))?;
+
+        let (buf, fields_count) = or_stop(be_u16(buf), || stop(ParseStage::Fields, buf))?;
+        self.fields_count = fields_count;
+        let buf = parse_partial(
+            buf,
+            fields_count,
+            |buf| FieldInfo::parse_field_info(buf, &self.constant_pool, context),
+            &mut self.fields,
+        )
+        .map_err(|at| stop(ParseStage::Fields, at))?;
+
+        let (buf, methods_count) = or_stop(be_u16(buf), || stop(ParseStage::Methods, buf))?;
+        self.methods_count = methods_count;
+        let buf = parse_partial(
+            buf,
+            methods_count,
+            |buf| MethodInfo::parse_method_info(buf, &self.constant_pool, context),
+            &mut self.methods,
+        )
+        .map_err(|at| stop(ParseStage::Methods, at))?;
+
+        let (buf, attributes_count) = or_stop(be_u16(buf), || stop(ParseStage::Attributes, buf))?;
+        self.attributes_count = attributes_count;
+        let mut attributes = Vec::new();
+        let result = parse_partial(buf, attributes_count, AttributeInfo::parse, &mut attributes);
+        for attribute in attributes {
+            let at = attribute.info;
+            let parsed =
+                Attribute::from_attribute_info(vec![attribute], &self.constant_pool, context)
+                    .map_err(|_| stop(ParseStage::Attributes, at))?;
+            self.attributes.extend(parsed);
+        }
+        result
+            .map(|_| ())
+            .map_err(|at| stop(ParseStage::Attributes, at))
+    }
+
+    /// Parses class files laid back to back in `buf`, as found in some
+    /// class-load dumps. Each class comes with the range of bytes it was read
+    /// from. Iteration ends cleanly at the end of the buffer, or after the
+    /// first class which fails to parse.
+    pub fn parse_many(buf: &[u8]) -> ParseMany<'_> {
+        ParseMany {
+            buf,
+            offset: 0,
+            failed: false,
+        }
+    }
+
+    /// Serializes the class. Counts and attribute lengths are computed from
+    /// the data rather than taken from the stored `*_count` fields.
+    #[cfg(feature = "write")]
+    pub fn write(&self, out: &mut impl io::Write) -> io::Result<()> {
+        out.write_all(&self.to_bytes()?)
+    }
+
+    /// The class serialized, as [`write`](Self::write) writes it. The bytes
+    /// parse back to equal classes every time, NaN constants and all:
+    ///
+    /// ```
+    /// use jvmb::{builder::ClassFileBuilder, classfile::ClassFile};
+    ///
+    /// let mut builder = ClassFileBuilder::new("com/example/Foo");
+    /// builder.constant_pool().float(f32::NAN);
+    /// let bytes = builder.build().unwrap().to_bytes().unwrap();
+    /// let (_, first) = ClassFile::parse_class_file(&bytes).unwrap();
+    /// let (_, second) = ClassFile::parse_class_file(&bytes).unwrap();
+    /// assert_eq!(first, second);
+    /// assert_eq!(first.clone(), first);
+    /// ```
+    #[cfg(feature = "write")]
+    pub fn to_bytes(&self) -> io::Result<Vec<u8>> {
+        let mut out = Vec::with_capacity(1 << 12);
+        out.put_u32(0xCAFEBABE);
+        out.put_u16(self.minor_version);
+        out.put_u16(self.major_version);
+        ConstantPool::write_all(&self.constant_pool, &mut out)?;
+        out.put_u16(self.access_flags.bits());
+        out.put_u16(self.this_class);
+        out.put_u16(self.super_class);
+        out.put_count(self.interfaces.len(), "interfaces")?;
+        self.interfaces
+            .iter()
+            .for_each(|&interface| out.put_u16(interface));
+        out.put_count(self.fields.len(), "fields")?;
+        for field in &self.fields {
+            field.write(&mut out, &self.constant_pool)?;
+        }
+        out.put_count(self.methods.len(), "methods")?;
+        for method in &self.methods {
+            method.write(&mut out, &self.constant_pool)?;
+        }
+        Attribute::write_all(&self.attributes, &mut out, &self.constant_pool)?;
+        Ok(out)
+    }
+}
+
+/// What [`ClassFile::replace_in_string_literals`] does with a string
+/// literal whose UTF8 entry is also used as a name or descriptor.
+#[cfg(feature = "write")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SharedLiteral {
+    /// Fail without changing anything.
+    Refuse,
+    /// Give the literal a UTF8 entry of its own holding the new text, and
+    /// leave the shared entry as it is.
+    Split,
+}
+
+/// A string literal which couldn't be rewritten in place because its UTF8
+/// entry is also used structurally.
+#[cfg(feature = "write")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SharedLiteralError {
+    pub index: u16,
+    pub value: String,
+}
+
+#[cfg(feature = "write")]
+impl fmt::Display for SharedLiteralError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "constant #{} {:?} is also used as a name or descriptor, and rewriting it would \
+             corrupt the class",
+            self.index, self.value
+        )
+    }
+}
+
+#[cfg(feature = "write")]
+impl std::error::Error for SharedLiteralError {}
+
+/// A constant pool entry found by [`ClassFile::unused_constants`].
+#[cfg(feature = "disasm")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnusedConstant {
+    pub index: u16,
+    pub tag: u8,
+    /// The bytes removing the entry would save.
+    pub byte_cost: usize,
+}
+
+/// A `Dynamic` or `InvokeDynamic` constant resolved through the class's
+/// `BootstrapMethods` attribute, found by [`ClassFile::dynamic_site`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DynamicSite<'a> {
+    pub name: &'a str,
+    pub descriptor: &'a str,
+    /// The reference kind of the bootstrap method handle.
+    pub bootstrap_kind: u8,
+    pub bootstrap: MemberRef<'a>,
+    /// Constant pool indices of the static arguments.
+    pub arguments: &'a [u16],
+}
+
+impl<'a> DynamicSite<'a> {
+    /// The static arguments, each resolved through `constant_pool`, or
+    /// `None` where it can't be.
+    pub fn resolved_arguments(
+        &self,
+        constant_pool: &'a [ConstantPool],
+    ) -> Vec<Option<BootstrapArgument<'a>>> {
+        self.arguments
+            .iter()
+            .map(|&index| BootstrapArgument::resolve(constant_pool, index))
+            .collect()
+    }
+}
+
+/// A static argument of a bootstrap method, resolved through the constant
+/// pool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BootstrapArgument<'a> {
+    Integer(i32),
+    Float(FloatBits),
+    Long(i64),
+    Double(DoubleBits),
+    String(&'a str),
+    /// The internal name of a class, or the descriptor of an array type.
+    Class(&'a str),
+    /// A method descriptor.
+    MethodType(&'a str),
+    MethodHandle {
+        kind: u8,
+        member: MemberRef<'a>,
+    },
+    /// A `Dynamic` constant, by its index, for [`ClassFile::dynamic_site`].
+    Dynamic(u16),
+}
+
+impl<'a> BootstrapArgument<'a> {
+    /// Resolves the loadable constant at `index`. `None` when the entry
+    /// isn't loadable or anything it leads to is missing.
+    pub fn resolve(constant_pool: &'a [ConstantPool], index: u16) -> Option<Self> {
+        Some(match constant_pool.get((index as usize).checked_sub(1)?)? {
+            ConstantPool::Integer(value) => BootstrapArgument::Integer(*value),
+            ConstantPool::Float(bits) => BootstrapArgument::Float(*bits),
+            ConstantPool::Long(value) => BootstrapArgument::Long(*value),
+            ConstantPool::Double(bits) => BootstrapArgument::Double(*bits),
+            ConstantPool::String(string_index) => {
+                BootstrapArgument::String(ConstantPool::utf8(constant_pool, *string_index)?)
+            }
+            ConstantPool::Class(_) => {
+                BootstrapArgument::Class(ConstantPool::class_name(constant_pool, index)?)
+            }
+            ConstantPool::MethodType(descriptor_index) => {
+                BootstrapArgument::MethodType(ConstantPool::utf8(constant_pool, *descriptor_index)?)
+            }
+            ConstantPool::MethodHandle(..) => {
+                let (kind, member) = ConstantPool::method_handle(constant_pool, index)?;
+                BootstrapArgument::MethodHandle { kind, member }
+            }
+            ConstantPool::Dynamic(..) => BootstrapArgument::Dynamic(index),
+            _ => return None,
+        })
+    }
+}
+
+/// An internal name with dots for slashes.
+fn dotted(name: &str) -> String {
+    name.replace('/', ".")
+}
+
+fn or_stop<T>(
+    result: IResult<&[u8], T>,
+    stop: impl FnOnce() -> Truncation,
+) -> Result<(&[u8], T), Truncation> {
+    result.map_err(|_| stop())
+}
+
+/// Steps over `n` fields or methods, by the lengths of their attributes.
+/// The `0xCAFEBABE` a class file starts with. Input which ends before it
+/// does, but is the start of it, is [`ParseError::Truncated`] rather than
+/// [`ParseError::BadMagic`].
+pub(crate) fn magic(buf: &[u8]) -> IResult<&[u8], ()> {
+    let magic = 0xCAFEBABEu32.to_be_bytes();
+    match buf.strip_prefix(&magic[..]) {
+        Some(rest) => Ok((rest, ())),
+        None if magic.starts_with(buf) => take(magic.len())(buf).map(|(rest, _)| (rest, ())),
+        None => Err(ErrorAt::failure(buf, ParseError::BadMagic)),
+    }
+}
+
+fn skip_members<'a>(
+    mut buf: &'a [u8],
+    n: u16,
+    kind: &str,
+    constant_pool: &[ConstantPool],
+) -> IResult<&'a [u8], ()> {
+    for i in 0..n {
+        let (rest, ()) = error::within(skip_member(buf), || {
+            error::member_context(kind, i, buf, constant_pool)
+        })?;
+        buf = rest;
+    }
+    Ok((buf, ()))
+}
+
+fn skip_member(buf: &[u8]) -> IResult<&[u8], ()> {
+    // access flags, name and descriptor
+    let (rest, _) = take(6)(buf)?;
+    let (mut rest, attributes_count) = be_u16(rest)?;
+    for _ in 0..attributes_count {
+        let (after_name, _) = take(2)(rest)?;
+        let (after_length, length) = be_u32(after_name)?;
+        (rest, _) = take(length as usize)(after_length)?;
+    }
+    Ok((rest, ()))
+}
+
+/// Parses up to `n` items into `items`, keeping every complete item when
+/// one fails. The error is the input at the item which failed.
+fn parse_partial<'a, T>(
+    mut buf: &'a [u8],
+    n: u16,
+    mut parser: impl FnMut(&'a [u8]) -> IResult<&'a [u8], T>,
+    items: &mut Vec<T>,
+) -> Result<&'a [u8], &'a [u8]> {
+    for _ in 0..n {
+        let (rest, item) = parser(buf).map_err(|_| buf)?;
+        items.push(item);
+        buf = rest;
+    }
+    Ok(buf)
+}
+
+/// The part of a class file being read when [`ClassFile::parse_salvage`]
+/// ran into damage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseStage {
+    /// The magic number, versions and constant pool count.
+    Header,
+    ConstantPool,
+    /// The access flags, this class and super class.
+    ClassInfo,
+    Interfaces,
+    Fields,
+    Methods,
+    Attributes,
+}
+
+impl ParseStage {
+    pub const ALL: [ParseStage; 7] = [
+        ParseStage::Header,
+        ParseStage::ConstantPool,
+        ParseStage::ClassInfo,
+        ParseStage::Interfaces,
+        ParseStage::Fields,
+        ParseStage::Methods,
+        ParseStage::Attributes,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ParseStage::Header => "header",
+            ParseStage::ConstantPool => "constant pool",
+            ParseStage::ClassInfo => "class info",
+            ParseStage::Interfaces => "interfaces",
+            ParseStage::Fields => "fields",
+            ParseStage::Methods => "methods",
+            ParseStage::Attributes => "attributes",
+        }
+    }
+}
+
+/// Where a damaged class file stopped making sense.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Truncation {
+    pub stage: ParseStage,
+    /// The offset of the first item of the stage which couldn't be read.
+    pub offset: usize,
+}
+
+impl fmt::Display for Truncation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "truncated in {} at byte {}",
+            self.stage.name(),
+            self.offset
+        )
+    }
+}
+
+/// The result of [`ClassFile::parse_salvage`].
+#[derive(Debug)]
+pub struct Salvaged {
+    pub class_file: ClassFile,
+    /// Where parsing stopped, or `None` if the class file was whole.
+    pub truncation: Option<Truncation>,
+}
+
+/// A class file of a version newer than [`LATEST_MAJOR_VERSION`]. Such
+/// classes are parsed as far as jvmb understands them unless
+/// [`ParseOptions::reject_unsupported_versions`] is set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnsupportedVersion {
+    pub major_version: u16,
+    pub minor_version: u16,
+}
+
+impl UnsupportedVersion {
+    /// The version of the class file in `buf`, if it's unsupported. Looks
+    /// at the header only.
+    pub fn of(buf: &[u8]) -> Option<Self> {
+        let header: IResult<&[u8], _> =
+            tuple((tag(0xCAFEBABEu32.to_be_bytes()), be_u16, be_u16))(buf);
+        let (_, (_magic, minor_version, major_version)) = header.ok()?;
+        (major_version > LATEST_MAJOR_VERSION).then_some(UnsupportedVersion {
+            major_version,
+            minor_version,
+        })
+    }
+}
+
+impl fmt::Display for UnsupportedVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "class file version {} is newer than supported {}",
+            self.major_version, LATEST_MAJOR_VERSION
+        )
+    }
+}
+
+impl core::error::Error for UnsupportedVersion {}
+
+/// Iterator returned by [`ClassFile::parse_many`].
+#[derive(Debug, Clone)]
+pub struct ParseMany<'a> {
+    buf: &'a [u8],
+    offset: usize,
+    failed: bool,
+}
+
+/// A class in a concatenated buffer which failed to parse.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseManyError {
+    /// Where the failing class starts.
+    pub offset: usize,
+    pub error: ParseError,
+}
+
+impl fmt::Display for ParseManyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "class file at offset {} failed to parse: {}",
+            self.offset, self.error
+        )
+    }
+}
+
+impl core::error::Error for ParseManyError {}
+
+impl Iterator for ParseMany<'_> {
+    type Item = Result<(Range<usize>, ClassFile), ParseManyError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.offset >= self.buf.len() {
+            return None;
+        }
+        let start = self.offset;
+        match ClassFile::parse_class_file(&self.buf[start..]) {
+            Ok((rest, class_file)) => {
+                self.offset = self.buf.len() - rest.len();
+                Some(Ok((start..self.offset, class_file)))
+            }
+            Err(e) => {
+                self.failed = true;
+                Some(Err(ParseManyError {
+                    offset: start,
+                    error: e.into(),
+                }))
+            }
+        }
+    }
+}
+
+impl ClassFile {
+    /// The class's version, if it's newer than jvmb supports.
+    pub fn unsupported_version(&self) -> Option<UnsupportedVersion> {
+        (self.major_version > LATEST_MAJOR_VERSION).then_some(UnsupportedVersion {
+            major_version: self.major_version,
+            minor_version: self.minor_version,
+        })
+    }
+
+    /// The Java release the class's major version belongs to.
+    pub fn java_version(&self) -> JavaVersion {
+        JavaVersion::from_major(self.major_version)
+    }
+
+    /// Whether the class uses the preview features of its release, marked
+    /// by a minor version of 0xFFFF.
+    pub fn is_preview(&self) -> bool {
+        self.minor_version == 0xFFFF
+    }
+
+    /// Whether the class is compiler-generated, marked either by the
+    /// `ACC_SYNTHETIC` flag or by a `Synthetic` attribute.
+    pub fn is_synthetic(&self) -> bool {
+        self.access_flags.is_synthetic() || Attribute::has_synthetic(&self.attributes)
+    }
+
+    /// The name of the source file the class was compiled from, as its
+    /// `SourceFile` attribute gives it, e.g. `Foo.java`.
+    pub fn source_file(&self) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find_map(|attribute| match attribute {
+                Attribute::SourceFile(source_file) => {
+                    ConstantPool::utf8(&self.constant_pool, source_file.sourcefile_index)
+                }
+                _ => None,
+            })
+    }
+
+    /// The class's `InnerClasses` attribute, the nested classes it refers
+    /// to or declares, and itself if it's one.
+    pub fn inner_classes(&self) -> Option<&InnerClasses> {
+        self.attributes
+            .iter()
+            .find_map(|attribute| match attribute {
+                Attribute::InnerClasses(inner_classes) => Some(inner_classes),
+                _ => None,
+            })
+    }
+
+    /// The class's `EnclosingMethod` attribute, which local and anonymous
+    /// classes have.
+    pub fn enclosing_method(&self) -> Option<&EnclosingMethod> {
+        self.attributes
+            .iter()
+            .find_map(|attribute| match attribute {
+                Attribute::EnclosingMethod(enclosing_method) => Some(enclosing_method),
+                _ => None,
+            })
+    }
+
+    /// The components of a record class, as its `Record` attribute lists
+    /// them. `None` for a class which isn't a record.
+    pub fn record_components(&self) -> Option<&[RecordComponentInfo]> {
+        self.attributes
+            .iter()
+            .find_map(|attribute| match attribute {
+                Attribute::Record(components) => Some(components.as_slice()),
+                _ => None,
+            })
+    }
+
+    /// Every constant pool index the class holds which doesn't lead to an
+    /// entry of the kind needed there; see [`validate`](crate::validate).
+    pub fn validate(&self) -> Vec<ValidationError> {
+        validate::validate(self)
+    }
+
+    /// The class's `SourceDebugExtension`, as text and parsed as the SMAP
+    /// it's meant to be.
+    pub fn source_debug_extension(&self) -> Option<SourceDebugExtension> {
+        self.attributes
+            .iter()
+            .find_map(|attribute| match attribute {
+                Attribute::SourceDebugExtension(debug_extension) => {
+                    let text = mutf8::decode_lossy(debug_extension);
+                    let smap = Smap::parse(&text);
+                    Some(SourceDebugExtension { text, smap })
+                }
+                _ => None,
+            })
+    }
+
+    /// The values of the class's `CONSTANT_String` entries, its string
+    /// literals, in the order of the pool. Unlike the UTF8 entries in
+    /// general, these aren't names or descriptors.
+    pub fn string_constants(&self) -> Vec<&str> {
+        ConstantPool::string_values(&self.constant_pool)
+    }
+
+    /// The internal name of the class, e.g. `com/example/Foo`.
+    pub fn class_name(&self) -> Result<&str, ConstantPoolError> {
+        ConstantPool::get_class_name(&self.constant_pool, self.this_class)
+    }
+
+    /// The internal name of the superclass, `None` when `super_class` is 0,
+    /// as it is for `java/lang/Object` and module descriptors.
+    pub fn super_class_name(&self) -> Result<Option<&str>, ConstantPoolError> {
+        match self.super_class {
+            0 => Ok(None),
+            index => ConstantPool::get_class_name(&self.constant_pool, index).map(Some),
+        }
+    }
+
+    /// The internal names of the interfaces, in the order they're listed.
+    pub fn interface_names(&self) -> Result<Vec<&str>, ConstantPoolError> {
+        self.interfaces
+            .iter()
+            .map(|&index| ConstantPool::get_class_name(&self.constant_pool, index))
+            .collect()
+    }
+
+    /// The name of the class with dots for slashes, e.g. `com.example.Foo`.
+    pub fn class_name_dotted(&self) -> Result<String, ConstantPoolError> {
+        self.class_name().map(dotted)
+    }
+
+    /// [`ClassFile::super_class_name`] with dots for slashes.
+    pub fn super_class_name_dotted(&self) -> Result<Option<String>, ConstantPoolError> {
+        Ok(self.super_class_name()?.map(dotted))
+    }
+
+    /// [`ClassFile::interface_names`] with dots for slashes.
+    pub fn interface_names_dotted(&self) -> Result<Vec<String>, ConstantPoolError> {
+        Ok(self.interface_names()?.into_iter().map(dotted).collect())
+    }
+
+    /// The internal name of the host of the nest the class is a member of,
+    /// from its `NestHost` attribute. `None` when it has none, as a nest
+    /// host doesn't; empty when it can't be resolved.
+    pub fn nest_host(&self) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find_map(|attribute| match attribute {
+                Attribute::NestHost(index) => {
+                    Some(ConstantPool::class_name(&self.constant_pool, *index).unwrap_or_default())
+                }
+                _ => None,
+            })
+    }
+
+    /// The internal names of the other members of the nest the class hosts,
+    /// from its `NestMembers` attribute. Names which can't be resolved are
+    /// empty.
+    pub fn nest_members(&self) -> Vec<&str> {
+        self.class_names_of(|attribute| match attribute {
+            Attribute::NestMembers(classes) => Some(classes),
+            _ => None,
+        })
+    }
+
+    /// The internal names of the classes allowed to extend or implement a
+    /// sealed class, from its `PermittedSubclasses` attribute. Names which
+    /// can't be resolved are empty.
+    pub fn permitted_subclasses(&self) -> Vec<&str> {
+        self.class_names_of(|attribute| match attribute {
+            Attribute::PermittedSubclasses(classes) => Some(classes),
+            _ => None,
+        })
+    }
+
+    /// Whether the class is sealed, i.e. lists the subclasses it permits.
+    pub fn is_sealed(&self) -> bool {
+        self.attributes.iter().any(|attribute| {
+            matches!(attribute, Attribute::PermittedSubclasses(classes) if !classes.is_empty())
+        })
+    }
+
+    /// The names of the classes in the index lists `select` picks out of
+    /// the class's attributes.
+    fn class_names_of(&self, select: impl Fn(&Attribute) -> Option<&Vec<u16>>) -> Vec<&str> {
+        self.attributes
+            .iter()
+            .filter_map(select)
+            .flatten()
+            .map(|&index| ConstantPool::class_name(&self.constant_pool, index).unwrap_or_default())
+            .collect()
+    }
+
+    /// The class's visible and then invisible annotations, with their
+    /// element values looked up.
+    pub fn annotations(&self) -> Vec<ResolvedAnnotation<'_>> {
+        Attribute::annotations(&self.attributes, &self.constant_pool)
+    }
+
+    /// What the class is to Kotlin, from the `kotlin.Metadata` annotation
+    /// kotlinc puts on it. `None` for a class kotlinc didn't write.
+    pub fn kotlin_metadata(&self) -> Option<KotlinMetadata<'_>> {
+        self.annotations()
+            .iter()
+            .find_map(KotlinMetadata::from_annotation)
+    }
+
+    /// The module a `module-info` class declares, with the packages and main
+    /// class of its `ModulePackages` and `ModuleMainClass` attributes.
+    /// `None` when it has no `Module` attribute.
+    pub fn module(&self) -> Option<ResolvedModule<'_>> {
+        let constant_pool = &self.constant_pool;
+        let mut module = self
+            .attributes
+            .iter()
+            .find_map(|attribute| match attribute {
+                Attribute::Module(module) => Some(module.resolved(constant_pool)),
+                _ => None,
+            })?;
+        for attribute in &self.attributes {
+            match attribute {
+                Attribute::ModulePackages(indices) => {
+                    module.packages.extend(indices.iter().map(|&index| {
+                        ConstantPool::package_name(constant_pool, index).unwrap_or_default()
+                    }))
+                }
+                Attribute::ModuleMainClass(index) => {
+                    module.main_class = ConstantPool::class_name(constant_pool, *index)
+                }
+                _ => {}
+            }
+        }
+        Some(module)
+    }
+
+    /// The method `name` with `descriptor`, e.g. `main` and
+    /// `([Ljava/lang/String;)V`, which the class declares.
+    pub fn find_method(&self, name: &str, descriptor: &str) -> Option<&MethodInfo> {
+        self.methods.iter().find(|method| {
+            method.name(&self.constant_pool) == Some(name)
+                && method.descriptor(&self.constant_pool) == Some(descriptor)
+        })
+    }
+
+    /// Every method called `name` which the class declares, overloads
+    /// and all, in the order they're declared.
+    pub fn find_methods(&self, name: &str) -> Vec<&MethodInfo> {
+        self.methods
+            .iter()
+            .filter(|method| method.name(&self.constant_pool) == Some(name))
+            .collect()
+    }
+
+    /// The field `name` which the class declares. The format allows fields
+    /// of one name with different types, which no compiler emits; the first
+    /// is returned.
+    pub fn find_field(&self, name: &str) -> Option<&FieldInfo> {
+        self.fields
+            .iter()
+            .find(|field| field.name(&self.constant_pool) == Some(name))
+    }
+
+    /// Whether the class declares `public static void main(String[])`, the
+    /// method `java` launches it through.
+    pub fn has_main_method(&self) -> bool {
+        self.find_method("main", "([Ljava/lang/String;)V")
+            .is_some_and(|method| {
+                let access_flags = method.access_flags();
+                access_flags.is_public() && access_flags.is_static()
+            })
+    }
+
+    /// Normalizes how the class and all of its fields and methods are marked
+    /// as synthetic, so that they're serialized in the given form.
+    pub fn set_synthetic_form(&mut self, form: SyntheticForm) {
+        form.apply(&mut self.access_flags, &mut self.attributes);
+        self.attributes_count = self.attributes.len() as u16;
+        for field in &mut self.fields {
+            field.set_synthetic_form(form);
+        }
+        for method in &mut self.methods {
+            method.set_synthetic_form(form);
+        }
+
+        if form == SyntheticForm::Attribute && self.uses_synthetic_attribute() {
+            let len = self.constant_pool.len();
+            ConstantPool::intern_utf8(&mut self.constant_pool, "Synthetic");
+            self.constant_pool_count += (self.constant_pool.len() - len) as u16;
+        }
+    }
+
+    /// Names of all classes the class mentions, itself included: those of
+    /// its `CONSTANT_Class` entries, which the owners of field and method
+    /// refs, `Exceptions` attributes and the catch types of exception
+    /// handlers all point at, and those in the descriptors of its fields,
+    /// methods, refs and method types. Array types contribute their element
+    /// class, and arrays of primitives are skipped.
+    pub fn referenced_classes(&self) -> BTreeSet<String> {
+        let constant_pool = &self.constant_pool;
+        let mut classes: BTreeSet<String> = (1..=constant_pool.len() as u16)
+            .filter_map(|index| ConstantPool::class_ref(constant_pool, index))
+            .filter_map(|class_ref| class_ref.class_name().map(str::to_string))
+            .collect();
+        let descriptors = constant_pool
+            .iter()
+            .filter_map(|constant| match constant {
+                ConstantPool::NameAndType(_, descriptor_index)
+                | ConstantPool::MethodType(descriptor_index) => Some(*descriptor_index),
+                _ => None,
+            })
+            .chain(self.fields.iter().map(FieldInfo::descriptor_index))
+            .chain(self.methods.iter().map(MethodInfo::descriptor_index));
+        for index in descriptors {
+            if let Some(descriptor) = ConstantPool::utf8(constant_pool, index) {
+                classes.extend(descriptor::referenced_classes(descriptor).map(String::from));
+            }
+        }
+        classes
+    }
+
+    /// Checks whether any UTF8 constant contains `needle` as a substring,
+    /// walking only the constant pool of the class in `buf` and building no
+    /// structures along the way. Input that isn't a class file yields `false`.
+    pub fn quick_contains_utf8(buf: &[u8], needle: &str) -> bool {
+        let needle = needle.as_bytes();
+        if buf.len() < 10 || buf[..4] != 0xCAFEBABEu32.to_be_bytes() {
+            return false;
+        }
+        let constant_pool_count = u16::from_be_bytes([buf[8], buf[9]]);
+
+        let mut pos = 10;
+        let mut index = 1;
+        while index < constant_pool_count {
+            let size = match buf.get(pos) {
+                Some(1) => match buf.get(pos + 1..pos + 3) {
+                    Some(len) => {
+                        let len = u16::from_be_bytes([len[0], len[1]]) as usize;
+                        let value = match buf.get(pos + 3..pos + 3 + len) {
+                            Some(value) => value,
+                            None => return false,
+                        };
+                        if needle.is_empty() || value.windows(needle.len()).any(|w| w == needle) {
+                            return true;
+                        }
+                        2 + len
+                    }
+                    None => return false,
+                },
+                Some(3) | Some(4) => 4,
+                Some(5) | Some(6) => {
+                    index += 1;
+                    8
+                }
+                Some(7) | Some(8) | Some(16) | Some(19) | Some(20) => 2,
+                Some(9) | Some(10) | Some(11) | Some(12) | Some(17) | Some(18) => 4,
+                Some(15) => 3,
+                _ => return false,
+            };
+            pos += 1 + size;
+            index += 1;
+        }
+
+        false
+    }
+
+    /// Whether `needle` occurs in a class name, member name or descriptor
+    /// used by this class, as opposed to only in string literals.
+    pub fn references_symbol(&self, needle: &str) -> bool {
+        let constant_pool = &self.constant_pool;
+        let utf8 = |index| ConstantPool::utf8(constant_pool, index).unwrap_or("");
+        let in_pool = constant_pool.iter().any(|constant| match constant {
+            ConstantPool::Class(index)
+            | ConstantPool::MethodType(index)
+            | ConstantPool::Module(index)
+            | ConstantPool::Package(index) => utf8(*index).contains(needle),
+            ConstantPool::NameAndType(name_index, descriptor_index) => {
+                utf8(*name_index).contains(needle) || utf8(*descriptor_index).contains(needle)
+            }
+            _ => false,
+        });
+        let in_fields = self.fields.iter().any(|field| {
+            utf8(field.name_index()).contains(needle)
+                || utf8(field.descriptor_index()).contains(needle)
+        });
+        let in_methods = self.methods.iter().any(|method| {
+            utf8(method.name_index()).contains(needle)
+                || utf8(method.descriptor_index()).contains(needle)
+        });
+
+        in_pool || in_fields || in_methods
+    }
+
+    /// Calls `visit` with every constant pool index referenced from outside
+    /// the pool: by the class header, fields, methods, attributes (including
+    /// the names they're stored under) and bytecode operands.
+    #[cfg(feature = "disasm")]
+    pub fn visit_constants(&self, visit: &mut dyn FnMut(u16)) {
+        self.visit_constant_roles(&mut |index, _| visit(index));
+    }
+
+    /// Like [`ClassFile::visit_constants`], along with the role each entry
+    /// is referenced in, as [`Attribute::visit_constant_roles`] gives it.
+    #[cfg(feature = "disasm")]
+    pub fn visit_constant_roles(&self, visit: &mut dyn FnMut(u16, Utf8Roles)) {
+        let constant_pool = &self.constant_pool;
+        visit(self.this_class, Utf8Roles::OTHER);
+        if self.super_class != 0 {
+            visit(self.super_class, Utf8Roles::OTHER);
+        }
+        self.interfaces
+            .iter()
+            .for_each(|&index| visit(index, Utf8Roles::OTHER));
+        for field in &self.fields {
+            visit(field.name_index(), Utf8Roles::FIELD_NAME);
+            visit(field.descriptor_index(), Utf8Roles::DESCRIPTOR);
+            for attribute in field.attributes() {
+                attribute.visit_constant_roles(constant_pool, visit);
+            }
+        }
+        for method in &self.methods {
+            visit(method.name_index(), Utf8Roles::METHOD_NAME);
+            visit(method.descriptor_index(), Utf8Roles::DESCRIPTOR);
+            for attribute in method.attributes() {
+                attribute.visit_constant_roles(constant_pool, visit);
+            }
+        }
+        for attribute in &self.attributes {
+            attribute.visit_constant_roles(constant_pool, visit);
+        }
+    }
+
+    /// The entries of the class's `BootstrapMethods` attribute, empty when
+    /// it has none.
+    pub fn bootstrap_methods(&self) -> &[BootstrapMethod] {
+        self.attributes
+            .iter()
+            .find_map(|attribute| match attribute {
+                Attribute::BootstrapMethods(methods) => Some(methods.as_slice()),
+                _ => None,
+            })
+            .unwrap_or(&[])
+    }
+
+    /// The entries reachable from the one at `index`, itself included: those
+    /// it refers to, those they refer to and so on. From a `Dynamic` or
+    /// `InvokeDynamic` entry the walk goes on to the method handle and
+    /// static arguments of its bootstrap method. Indices past the end of the
+    /// pool are left out.
+    pub fn reachable_constants(&self, index: u16) -> BTreeSet<u16> {
+        let bootstrap_methods = self.bootstrap_methods();
+        let mut reachable = BTreeSet::new();
+        let mut pending = vec![index];
+        while let Some(index) = pending.pop() {
+            let constant = match (index as usize)
+                .checked_sub(1)
+                .and_then(|i| self.constant_pool.get(i))
+            {
+                Some(constant) if reachable.insert(index) => constant,
+                _ => continue,
+            };
+            pending.extend(constant.referenced_indices());
+            if let ConstantPool::Dynamic(bootstrap_index, _)
+            | ConstantPool::InvokeDynamic(bootstrap_index, _) = constant
+            {
+                if let Some(bootstrap_method) = bootstrap_methods.get(*bootstrap_index as usize) {
+                    pending.push(bootstrap_method.bootstrap_method_ref);
+                    pending.extend(&bootstrap_method.bootstrap_arguments);
+                }
+            }
+        }
+        reachable
+    }
+
+    /// Resolves the `Dynamic` or `InvokeDynamic` entry at `index` to its name
+    /// and type, bootstrap method and static arguments. `None` when the entry
+    /// is of another kind or anything it leads to is missing.
+    pub fn dynamic_site(&self, index: u16) -> Option<DynamicSite<'_>> {
+        let constant_pool = &self.constant_pool;
+        let (bootstrap_index, name_and_type_index) =
+            match constant_pool.get((index as usize).checked_sub(1)?)? {
+                ConstantPool::Dynamic(bootstrap_index, name_and_type_index)
+                | ConstantPool::InvokeDynamic(bootstrap_index, name_and_type_index) => {
+                    (*bootstrap_index, *name_and_type_index)
+                }
+                _ => return None,
+            };
+        let (name, descriptor) = ConstantPool::name_and_type(constant_pool, name_and_type_index)?;
+        let bootstrap_method = self.bootstrap_methods().get(bootstrap_index as usize)?;
+        let (bootstrap_kind, bootstrap) =
+            ConstantPool::method_handle(constant_pool, bootstrap_method.bootstrap_method_ref)?;
+        Some(DynamicSite {
+            name,
+            descriptor,
+            bootstrap_kind,
+            bootstrap,
+            arguments: &bootstrap_method.bootstrap_arguments,
+        })
+    }
+
+    /// Every `Dynamic` and `InvokeDynamic` entry of the pool which
+    /// [`ClassFile::dynamic_site`] resolves, by index in ascending order:
+    /// the call sites of `invokedynamic` and the dynamically computed
+    /// constants.
+    pub fn dynamic_sites(&self) -> Vec<(u16, DynamicSite<'_>)> {
+        (1..=self.constant_pool.len() as u16)
+            .filter_map(|index| Some((index, self.dynamic_site(index)?)))
+            .collect()
+    }
+
+    /// Pool entries which nothing refers to, directly or through other
+    /// entries, in ascending index order.
+    #[cfg(feature = "disasm")]
+    pub fn unused_constants(&self) -> Vec<UnusedConstant> {
+        let mut used = vec![false; self.constant_pool.len() + 1];
+        used[0] = true;
+        let mut pending = Vec::new();
+        self.visit_constants(&mut |index| pending.push(index));
+        while let Some(index) = pending.pop() {
+            match used.get_mut(index as usize) {
+                Some(used) if !*used => *used = true,
+                _ => continue,
+            }
+            if let Some(constant) = self.constant_pool.get(index as usize - 1) {
+                pending.extend(constant.referenced_indices());
+            }
+        }
+
+        self.constant_pool
+            .iter()
+            .enumerate()
+            .filter(|&(i, constant)| !used[i + 1] && *constant != ConstantPool::Unusable)
+            .map(|(i, constant)| UnusedConstant {
+                index: i as u16 + 1,
+                tag: constant.tag(),
+                byte_cost: constant.encoded_len(),
+            })
+            .collect()
+    }
+
+    /// The references [`MethodInfo::referenced_members`] finds in each
+    /// method with code, in declaration order.
+    #[cfg(feature = "disasm")]
+    pub fn member_references(&self) -> Vec<(&MethodInfo, MemberRefs<'_>)> {
+        self.methods
+            .iter()
+            .filter(|method| method.code().is_some())
+            .map(|method| (method, method.referenced_members(self)))
+            .collect()
+    }
+
+    /// The class's definitions and the member references its code makes,
+    /// with their source lines where the class has them.
+    #[cfg(feature = "disasm")]
+    pub fn symbols(&self) -> SymbolTable {
+        SymbolTable::new(self)
+    }
+
+    /// The enum switch maps the class holds when it's one javac generated
+    /// for switching over enums, as [`switchmap::enum_switch_maps`] reads
+    /// them.
+    #[cfg(feature = "analysis")]
+    pub fn enum_switch_maps(&self) -> Vec<EnumSwitchMap> {
+        switchmap::enum_switch_maps(self)
+    }
+
+    /// A hash of what the class holds, the same across constant pool
+    /// layouts and member orders, as [`diff::fingerprint`] takes it.
+    #[cfg(feature = "analysis")]
+    pub fn fingerprint(&self, options: FingerprintOptions) -> [u8; 32] {
+        diff::fingerprint(self, options)
+    }
+
+    /// Indices of the UTF8 entries used as something other than the text of
+    /// a string literal: names, descriptors, signatures, attribute names and
+    /// the like. Entries referenced only by `CONSTANT_String` are left out.
+    #[cfg(feature = "disasm")]
+    pub fn structural_utf8_indices(&self) -> BTreeSet<u16> {
+        let mut indices = BTreeSet::new();
+        self.visit_constants(&mut |index| {
+            indices.insert(index);
+        });
+        for constant in &self.constant_pool {
+            if !matches!(constant, ConstantPool::String(_)) {
+                indices.extend(constant.referenced_indices());
+            }
+        }
+        indices.retain(|&index| ConstantPool::utf8(&self.constant_pool, index).is_some());
+        indices
+    }
+
+    /// Replaces every occurrence of `from` with `to` in the string literals
+    /// of the class, returning the indices of the UTF8 entries rewritten or
+    /// added. Names and descriptors which merely contain `from` are never
+    /// touched. A literal whose UTF8 entry is also used structurally is
+    /// handled according to `shared`; with [`SharedLiteral::Refuse`] the
+    /// class is left unchanged.
+    #[cfg(feature = "write")]
+    pub fn replace_in_string_literals(
+        &mut self,
+        from: &str,
+        to: &str,
+        shared: SharedLiteral,
+    ) -> Result<Vec<u16>, SharedLiteralError> {
+        let structural = self.structural_utf8_indices();
+        let literals: Vec<u16> = self
+            .constant_pool
+            .iter()
+            .filter_map(|constant| match constant {
+                ConstantPool::String(index) => Some(*index),
+                _ => None,
+            })
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .filter(|&index| {
+                ConstantPool::utf8(&self.constant_pool, index)
+                    .is_some_and(|value| value.contains(from))
+            })
+            .collect();
+
+        if shared == SharedLiteral::Refuse {
+            if let Some(&index) = literals.iter().find(|index| structural.contains(index)) {
+                return Err(SharedLiteralError {
+                    index,
+                    value: ConstantPool::utf8(&self.constant_pool, index)
+                        .unwrap_or("")
+                        .to_string(),
+                });
+            }
+        }
+
+        let mut rewritten = Vec::with_capacity(literals.len());
+        for index in literals {
+            let value = ConstantPool::utf8(&self.constant_pool, index)
+                .unwrap_or("")
+                .replace(from, to);
+            if structural.contains(&index) {
+                self.constant_pool.push(ConstantPool::UTF8(value));
+                self.constant_pool_count += 1;
+                let new_index = self.constant_pool.len() as u16;
+                for constant in &mut self.constant_pool {
+                    if let ConstantPool::String(string_index) = constant {
+                        if *string_index == index {
+                            *string_index = new_index;
+                        }
+                    }
+                }
+                rewritten.push(new_index);
+            } else {
+                self.constant_pool[index as usize - 1] = ConstantPool::UTF8(value);
+                rewritten.push(index);
+            }
+        }
+        Ok(rewritten)
+    }
+
+    /// Renames the classes `mapper` gives new names to wherever the class
+    /// refers to them, as [`remap::remap`] does: in its own name and those
+    /// of its superclass and interfaces, the pool, descriptors, signatures
+    /// and attributes, adding the UTF8 entries the new names need to the
+    /// pool. String literals keep the names they hold.
+    ///
+    /// ```
+    /// use jvmb::{
+    ///     builder::ClassFileBuilder,
+    ///     classfile::ClassFile,
+    ///     remap::{Relocation, Remapper},
+    /// };
+    ///
+    /// let mut builder = ClassFileBuilder::new("com/thirdparty/Foo");
+    /// builder.add_interface("com/thirdparty/Api");
+    /// builder.add_field(0x0002, "bars", "[Lcom/thirdparty/bar/Bar;");
+    /// builder.add_method(0x0401, "get", "(Lcom/thirdparty/Api;I)Lcom/thirdparty/Foo;", None);
+    /// builder.constant_pool().string("com/thirdparty/Foo");
+    /// let mut class_file = builder.build().unwrap();
+    ///
+    /// let relocation =
+    ///     Relocation::parse("com/thirdparty/** -> shaded/thirdparty/**").unwrap();
+    /// class_file.remap(&|name| relocation.class(name)).unwrap();
+    ///
+    /// let (_, class_file) = ClassFile::parse_class_file(&class_file.to_bytes().unwrap()).unwrap();
+    /// let pool = &class_file.constant_pool;
+    /// assert_eq!(class_file.class_name().unwrap(), "shaded/thirdparty/Foo");
+    /// assert_eq!(class_file.interface_names().unwrap(), ["shaded/thirdparty/Api"]);
+    /// assert_eq!(
+    ///     class_file.fields[0].descriptor(pool).unwrap(),
+    ///     "[Lshaded/thirdparty/bar/Bar;",
+    /// );
+    /// assert_eq!(
+    ///     class_file.methods[0].descriptor(pool).unwrap(),
+    ///     "(Lshaded/thirdparty/Api;I)Lshaded/thirdparty/Foo;",
+    /// );
+    /// assert_eq!(class_file.string_constants(), ["com/thirdparty/Foo"]);
+    /// ```
+    #[cfg(feature = "write")]
+    pub fn remap(&mut self, mapper: &dyn Fn(&str) -> Option<String>) -> Result<(), PoolFull> {
+        remap::remap(self, &mapper)
+    }
+
+    /// Removes the attributes with one of `names` from the class, its
+    /// fields and methods and their `Code`, returning how many went. The
+    /// constant pool is left as it is; see [`ClassFile::clear_unused_utf8`].
+    #[cfg(feature = "write")]
+    pub fn strip_attributes(&mut self, names: &[&str]) -> usize {
+        let mut removed = Attribute::remove_named(&mut self.attributes, names);
+        self.attributes_count = self.attributes.len() as u16;
+        for field in &mut self.fields {
+            removed += field.remove_attributes(names);
+        }
+        for method in &mut self.methods {
+            removed += method.remove_attributes(names);
+        }
+        removed
+    }
+
+    /// Removes every attribute of [`DEBUG_ATTRIBUTES`], as
+    /// [`ClassFile::strip_attributes`] does, leaving the code itself
+    /// untouched.
+    #[cfg(feature = "write")]
+    pub fn strip_debug_info(&mut self) -> usize {
+        self.strip_attributes(DEBUG_ATTRIBUTES)
+    }
+
+    /// Empties the UTF8 entries nothing refers to any longer, such as the
+    /// names of locals once their tables are stripped, returning how many
+    /// were. They stay in the pool, as removing them would renumber every
+    /// entry after them, but take three bytes each.
+    #[cfg(feature = "write")]
+    pub fn clear_unused_utf8(&mut self) -> usize {
+        let mut cleared = 0;
+        for unused in self.unused_constants() {
+            let constant = &mut self.constant_pool[unused.index as usize - 1];
+            match constant {
+                ConstantPool::UTF8(value) if value.is_empty() => {}
+                ConstantPool::UTF8(_) | ConstantPool::Utf16(_) | ConstantPool::RawUtf8(_) => {
+                    *constant = ConstantPool::UTF8(String::new());
+                    cleared += 1;
+                }
+                _ => {}
+            }
+        }
+        cleared
+    }
+
+    /// Every use of the constant pool entry at `index`, as
+    /// [`patch::constant_references`] lists them, for confirming what
+    /// changing it affects.
+    #[cfg(feature = "write")]
+    pub fn find_constant_references(&self, index: u16) -> Vec<ConstantReference> {
+        patch::constant_references(self, index)
+    }
+
+    /// Sets the entry at `index` to `value`, as [`patch::set_constant`]
+    /// does.
+    #[cfg(feature = "write")]
+    pub fn set_constant(
+        &mut self,
+        index: u16,
+        value: ScalarConstant,
+        shared: SharedConstant,
+    ) -> Result<u16, SetConstantError> {
+        patch::set_constant(self, index, value, shared)
+    }
+
+    /// Sets the constant value of the field `name`, as
+    /// [`patch::set_field_constant`] does.
+    #[cfg(feature = "write")]
+    pub fn set_field_constant(
+        &mut self,
+        name: &str,
+        value: ScalarConstant,
+        shared: SharedConstant,
+    ) -> Result<u16, SetConstantError> {
+        patch::set_field_constant(self, name, value, shared)
+    }
+
+    fn uses_synthetic_attribute(&self) -> bool {
+        Attribute::has_synthetic(&self.attributes)
+            || self.fields.iter().any(|field| field.is_synthetic())
+            || self.methods.iter().any(|method| method.is_synthetic())
+    }
 }
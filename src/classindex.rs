@@ -0,0 +1,638 @@
+//! An index of the classes found in a set of jars and directories, by
+//! internal name, keeping what's needed to answer questions about the type
+//! hierarchy without holding every class file in memory.
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    error::Error,
+    fmt, fs, io, mem,
+    path::Path,
+};
+
+#[cfg(feature = "archive")]
+use crate::jar::JarFile;
+use crate::{
+    accessflags::{
+        ClassAccessFlags, ACC_BRIDGE, ACC_FINAL, ACC_PRIVATE, ACC_PROTECTED, ACC_PUBLIC, ACC_STATIC,
+    },
+    classfile::ClassFile,
+    constantpool::ConstantPool,
+    error::ParseError,
+    instruction,
+    methodinfo::MethodInfo,
+    stringtable::{StringTable, Symbol},
+    switchmap::{self, EnumSwitchMap},
+};
+
+/// The most suggestions a failed lookup offers.
+const MAX_SUGGESTIONS: usize = 5;
+
+/// A field or method of an indexed class, viewed through the index's
+/// string table.
+#[derive(Clone, Copy)]
+pub struct MemberSummary<'a> {
+    strings: &'a StringTable,
+    member: &'a CompactMember,
+}
+
+impl<'a> MemberSummary<'a> {
+    pub fn access_flags(&self) -> u16 {
+        self.member.access_flags
+    }
+
+    pub fn name(&self) -> &'a str {
+        self.strings.resolve(self.member.name)
+    }
+
+    pub fn descriptor(&self) -> &'a str {
+        self.strings.resolve(self.member.descriptor)
+    }
+
+    /// For a bridge method, the descriptor of the method of the same name
+    /// and class it calls. `None` for anything else, and for bridges whose
+    /// code wasn't read.
+    pub fn bridge_to(&self) -> Option<&'a str> {
+        self.member
+            .bridge_to
+            .map(|symbol| self.strings.resolve(symbol))
+    }
+}
+
+impl fmt::Debug for MemberSummary<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MemberSummary")
+            .field("access_flags", &self.access_flags())
+            .field("name", &self.name())
+            .field("descriptor", &self.descriptor())
+            .field("bridge_to", &self.bridge_to())
+            .finish()
+    }
+}
+
+/// What the index keeps of a class, viewed through the index's string
+/// table.
+#[derive(Clone, Copy)]
+pub struct ClassSummary<'a> {
+    strings: &'a StringTable,
+    class: &'a CompactClass,
+}
+
+impl<'a> ClassSummary<'a> {
+    /// The internal name, e.g. `com/example/Outer$Inner`.
+    pub fn name(&self) -> &'a str {
+        self.strings.resolve(self.class.name)
+    }
+
+    pub fn access_flags(&self) -> ClassAccessFlags {
+        ClassAccessFlags::new(self.class.access_flags)
+    }
+
+    /// `None` only for `java/lang/Object` and module descriptors.
+    pub fn super_class(&self) -> Option<&'a str> {
+        self.class
+            .super_class
+            .map(|symbol| self.strings.resolve(symbol))
+    }
+
+    pub fn interfaces(&self) -> impl ExactSizeIterator<Item = &'a str> + 'a {
+        let strings = self.strings;
+        self.class
+            .interfaces
+            .iter()
+            .map(move |&symbol| strings.resolve(symbol))
+    }
+
+    pub fn fields(&self) -> impl ExactSizeIterator<Item = MemberSummary<'a>> + 'a {
+        let strings = self.strings;
+        self.class
+            .fields
+            .iter()
+            .map(move |member| MemberSummary { strings, member })
+    }
+
+    pub fn methods(&self) -> impl ExactSizeIterator<Item = MemberSummary<'a>> + 'a {
+        let strings = self.strings;
+        self.class
+            .methods
+            .iter()
+            .map(move |member| MemberSummary { strings, member })
+    }
+
+    /// The jar or directory the class was found in.
+    pub fn source(&self) -> &'a str {
+        self.strings.resolve(self.class.source)
+    }
+
+    /// The name without its package, e.g. `Outer$Inner`.
+    pub fn simple_name(&self) -> &'a str {
+        simple_name(self.name())
+    }
+}
+
+impl fmt::Debug for ClassSummary<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ClassSummary")
+            .field("name", &self.name())
+            .field("access_flags", &self.access_flags())
+            .field("super_class", &self.super_class())
+            .field("interfaces", &self.interfaces().collect::<Vec<_>>())
+            .field("fields", &self.fields().collect::<Vec<_>>())
+            .field("methods", &self.methods().collect::<Vec<_>>())
+            .field("source", &self.source())
+            .finish()
+    }
+}
+
+/// A class as the index stores it, every string a symbol in the index's
+/// table and every list allocated to size.
+#[derive(Debug, Clone)]
+struct CompactClass {
+    name: Symbol,
+    access_flags: u16,
+    super_class: Option<Symbol>,
+    interfaces: Box<[Symbol]>,
+    fields: Box<[CompactMember]>,
+    methods: Box<[CompactMember]>,
+    source: Symbol,
+}
+
+#[derive(Debug, Clone)]
+struct CompactMember {
+    access_flags: u16,
+    name: Symbol,
+    descriptor: Symbol,
+    bridge_to: Option<Symbol>,
+}
+
+/// A method found by [`ClassIndex::overrides_of`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Override {
+    /// The internal name of the class declaring the override.
+    pub class: String,
+    /// The override's descriptor. It differs from that of the overridden
+    /// method when the override narrows the return type or, through
+    /// generics, the parameter types, and is reached through `bridge`.
+    pub descriptor: String,
+    /// The descriptor of the bridge method the compiler generated with the
+    /// overridden descriptor, which calls the override.
+    pub bridge: Option<String>,
+}
+
+/// A class which couldn't be added to the index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexError {
+    /// The jar and entry, or the file, the class was read from.
+    pub location: String,
+    pub message: String,
+}
+
+impl fmt::Display for IndexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.location, self.message)
+    }
+}
+
+impl Error for IndexError {}
+
+/// A lookup of a class which isn't in the index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LookupError {
+    /// The name as it was looked up.
+    pub name: String,
+    /// Indexed classes the name may have been meant as, closest first.
+    pub suggestions: Vec<String>,
+}
+
+impl fmt::Display for LookupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "class {} not found", self.name)?;
+        if !self.suggestions.is_empty() {
+            write!(f, "; did you mean {}?", self.suggestions.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for LookupError {}
+
+/// Every string the index holds, names, descriptors and sources alike, is
+/// kept once in its string table, so that an index of a large class path
+/// costs a few dozen bytes a class beyond the text of its distinct names.
+#[derive(Debug, Default)]
+pub struct ClassIndex {
+    strings: StringTable,
+    /// In the order they were added.
+    classes: Vec<CompactClass>,
+    /// name -> position in `classes`
+    by_name: HashMap<Symbol, u32>,
+    /// The switch maps of the classes which have any, by class name.
+    switch_maps: HashMap<Symbol, Vec<EnumSwitchMap>>,
+}
+
+impl ClassIndex {
+    pub fn new() -> Self {
+        ClassIndex::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.classes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.classes.is_empty()
+    }
+
+    /// Every indexed class, by name.
+    pub fn classes(&self) -> impl Iterator<Item = ClassSummary<'_>> {
+        let mut classes: Vec<ClassSummary> =
+            self.classes.iter().map(|class| self.view(class)).collect();
+        classes.sort_unstable_by_key(|class| class.name());
+        classes.into_iter()
+    }
+
+    /// Roughly how many bytes of heap the index holds.
+    pub fn approx_bytes(&self) -> usize {
+        let members = |members: &[CompactMember]| mem::size_of_val(members);
+        self.strings.approx_bytes()
+            + self.classes.capacity() * mem::size_of::<CompactClass>()
+            + self
+                .classes
+                .iter()
+                .map(|class| {
+                    mem::size_of_val(&*class.interfaces)
+                        + members(&class.fields)
+                        + members(&class.methods)
+                })
+                .sum::<usize>()
+            + self.by_name.capacity() * (mem::size_of::<(Symbol, u32)>() + 1)
+            + self.switch_maps.capacity() * (mem::size_of::<(Symbol, Vec<EnumSwitchMap>)>() + 1)
+    }
+
+    /// Adds a class found in `source`. When a class of the same name is
+    /// already indexed, the one added first is kept, as it would be on a
+    /// class path.
+    pub fn add_class(&mut self, class_file: &ClassFile, source: &str) {
+        let constant_pool = &class_file.constant_pool;
+        let name =
+            ConstantPool::class_name(constant_pool, class_file.this_class).unwrap_or_default();
+        if self.lookup(name).is_some() {
+            return;
+        }
+        let strings = &mut self.strings;
+        let mut class_name =
+            |index| ConstantPool::class_name(constant_pool, index).map(|name| strings.intern(name));
+        let super_class = class_name(class_file.super_class);
+        let interfaces = class_file
+            .interfaces
+            .iter()
+            .filter_map(|&index| class_name(index))
+            .collect();
+        let mut member =
+            |access_flags, name_index, descriptor_index, bridge_to: Option<&str>| CompactMember {
+                access_flags,
+                name: strings
+                    .intern(ConstantPool::utf8(constant_pool, name_index).unwrap_or_default()),
+                descriptor: strings.intern(
+                    ConstantPool::utf8(constant_pool, descriptor_index).unwrap_or_default(),
+                ),
+                bridge_to: bridge_to.map(|descriptor| strings.intern(descriptor)),
+            };
+        let fields = class_file
+            .fields
+            .iter()
+            .map(|field| {
+                member(
+                    field.access_flags().bits(),
+                    field.name_index(),
+                    field.descriptor_index(),
+                    None,
+                )
+            })
+            .collect();
+        let methods = class_file
+            .methods
+            .iter()
+            .map(|method| {
+                member(
+                    method.access_flags().bits(),
+                    method.name_index(),
+                    method.descriptor_index(),
+                    bridge_target(class_file, method),
+                )
+            })
+            .collect();
+        let name = strings.intern(name);
+        let source = strings.intern(source);
+        let switch_maps = switchmap::enum_switch_maps(class_file);
+        if !switch_maps.is_empty() {
+            self.switch_maps.insert(name, switch_maps);
+        }
+        let position = u32::try_from(self.classes.len()).expect("under 4G classes");
+        self.by_name.insert(name, position);
+        self.classes.push(CompactClass {
+            name,
+            access_flags: class_file.access_flags.bits(),
+            super_class,
+            interfaces,
+            fields,
+            methods,
+            source,
+        });
+    }
+
+    /// Adds every class in `jar`, naming `name` as their source. For a
+    /// multi-release jar the unversioned classes win. Entries which can't be
+    /// read or parsed are skipped and returned.
+    #[cfg(feature = "archive")]
+    pub fn add_jar(&mut self, name: &str, jar: &JarFile) -> Vec<IndexError> {
+        let mut errors = Vec::new();
+        let (versioned, unversioned): (Vec<_>, Vec<_>) = jar
+            .entries()
+            .iter()
+            .filter(|entry| entry.class_name().is_some())
+            .partition(|entry| entry.name.starts_with("META-INF/"));
+        for entry in unversioned.into_iter().chain(versioned) {
+            let location = format!("{}!/{}", name, entry.name);
+            match jar.read(entry) {
+                Ok(buf) => self.add_bytes(&buf, name, location, &mut errors),
+                Err(e) => errors.push(IndexError {
+                    location,
+                    message: e.to_string(),
+                }),
+            }
+        }
+        errors
+    }
+
+    /// Adds every class file under `dir`, in path order. Jars in it are not
+    /// opened. Files which can't be parsed are skipped and returned.
+    pub fn add_dir(&mut self, dir: &Path) -> io::Result<Vec<IndexError>> {
+        let mut errors = Vec::new();
+        let source = dir.display().to_string();
+        let mut pending = vec![dir.to_path_buf()];
+        while let Some(path) = pending.pop() {
+            if path.is_dir() {
+                let mut children = fs::read_dir(&path)?
+                    .map(|entry| entry.map(|entry| entry.path()))
+                    .collect::<io::Result<Vec<_>>>()?;
+                children.sort();
+                pending.extend(children.into_iter().rev());
+            } else if path
+                .extension()
+                .is_some_and(|extension| extension == "class")
+            {
+                let buf = fs::read(&path)?;
+                self.add_bytes(&buf, &source, path.display().to_string(), &mut errors);
+            }
+        }
+        Ok(errors)
+    }
+
+    fn add_bytes(
+        &mut self,
+        buf: &[u8],
+        source: &str,
+        location: String,
+        errors: &mut Vec<IndexError>,
+    ) {
+        match ClassFile::parse_class_file(buf) {
+            Ok((_, class_file)) => self.add_class(&class_file, source),
+            Err(e) => errors.push(IndexError {
+                location,
+                message: format!("failed to parse: {}", ParseError::from(e)),
+            }),
+        }
+    }
+
+    /// Looks up a class by internal name. Binary names (`com.example.Foo`)
+    /// are accepted too, with trailing dots standing for `$` where that's
+    /// needed to find a nested class. A miss comes with suggestions: classes
+    /// of the same simple name in other packages and names differing only
+    /// in case, closest first.
+    pub fn get(&self, name: &str) -> Result<ClassSummary<'_>, LookupError> {
+        if let Some(class) = self.lookup(name) {
+            return Ok(class);
+        }
+        let internal = name.replace('.', "/");
+        let mut candidate = internal.clone();
+        loop {
+            if let Some(class) = self.lookup(&candidate) {
+                return Ok(class);
+            }
+            // com/example/Outer/Inner -> com/example/Outer$Inner
+            match candidate.rfind('/') {
+                Some(slash) if name.contains('.') => candidate.replace_range(slash..=slash, "$"),
+                _ => break,
+            }
+        }
+        Err(LookupError {
+            name: name.to_string(),
+            suggestions: self.suggestions(&internal),
+        })
+    }
+
+    /// The switch map `field_name` of the class `class_name`, when that's a
+    /// switch map class the index holds. See [`switchmap`].
+    pub fn enum_switch_map(&self, class_name: &str, field_name: &str) -> Option<&EnumSwitchMap> {
+        self.switch_maps
+            .get(&self.strings.get(class_name)?)?
+            .iter()
+            .find(|map| map.field_name == field_name)
+    }
+
+    /// Whether a value of type `from` can be assigned to type `to`, both
+    /// given as internal names or array descriptors the way `CONSTANT_Class`
+    /// entries hold them. `None` when the answer depends on classes which
+    /// aren't indexed.
+    pub fn is_assignable(&self, from: &str, to: &str) -> Option<bool> {
+        if from == to || to == "java/lang/Object" {
+            return Some(true);
+        }
+        match (from.strip_prefix('['), to.strip_prefix('[')) {
+            (Some(from), Some(to)) => {
+                return match (reference_element(from), reference_element(to)) {
+                    (Some(from), Some(to)) => self.is_assignable(from, to),
+                    _ => Some(from == to),
+                }
+            }
+            (Some(_), None) => {
+                return Some(matches!(to, "java/lang/Cloneable" | "java/io/Serializable"))
+            }
+            (None, Some(_)) => return Some(false),
+            (None, None) => {}
+        }
+
+        let mut complete = true;
+        let mut seen = BTreeSet::new();
+        let mut pending = vec![from];
+        while let Some(name) = pending.pop() {
+            if !seen.insert(name) {
+                continue;
+            }
+            let class = match self.lookup(name) {
+                Some(class) => class,
+                None => {
+                    complete = false;
+                    continue;
+                }
+            };
+            for supertype in class.super_class().into_iter().chain(class.interfaces()) {
+                if supertype == to {
+                    return Some(true);
+                }
+                pending.push(supertype);
+            }
+        }
+        complete.then_some(false)
+    }
+
+    /// The methods of indexed subtypes of `class_name` which override its
+    /// method `method_name` with `descriptor`, by class name. A method
+    /// overrides when it has the same name and descriptor and is neither
+    /// private nor static, and, if the overridden method is package-private,
+    /// is in the same package. A bridge with the overridden descriptor is
+    /// attributed to the method it calls. Private, static, final and
+    /// undeclared methods, and constructors, have no overrides.
+    ///
+    /// Package-private methods are only taken as overridden from within
+    /// their own package, though the JVM also lets a public method override
+    /// one in another package through an intermediate override.
+    pub fn overrides_of(
+        &self,
+        class_name: &str,
+        method_name: &str,
+        descriptor: &str,
+    ) -> Result<Vec<Override>, LookupError> {
+        let class = self.get(class_name)?;
+        let overridable = |method: &MemberSummary| {
+            method.access_flags() & (ACC_PRIVATE | ACC_STATIC) == 0 && method.name() == method_name
+        };
+        let base = class
+            .methods()
+            .filter(overridable)
+            .find(|method| method.descriptor() == descriptor && !method.name().starts_with('<'));
+        let base = match base {
+            Some(base) if base.access_flags() & ACC_FINAL == 0 => base,
+            _ => return Ok(Vec::new()),
+        };
+        let package_private = base.access_flags() & (ACC_PUBLIC | ACC_PROTECTED) == 0;
+
+        let mut overrides = Vec::new();
+        for subtype in self.classes() {
+            if subtype.name() == class.name()
+                || (package_private && package(subtype.name()) != package(class.name()))
+                || self.is_assignable(subtype.name(), class.name()) != Some(true)
+            {
+                continue;
+            }
+            let methods: Vec<MemberSummary> = subtype.methods().filter(overridable).collect();
+            for method in methods
+                .iter()
+                .filter(|method| method.descriptor() == descriptor)
+            {
+                let target = method.bridge_to().and_then(|target| {
+                    methods.iter().find(|real| {
+                        real.descriptor() == target && real.access_flags() & ACC_BRIDGE == 0
+                    })
+                });
+                overrides.push(match target {
+                    Some(target) => Override {
+                        class: subtype.name().to_string(),
+                        descriptor: target.descriptor().to_string(),
+                        bridge: Some(method.descriptor().to_string()),
+                    },
+                    None => Override {
+                        class: subtype.name().to_string(),
+                        descriptor: method.descriptor().to_string(),
+                        bridge: None,
+                    },
+                });
+            }
+        }
+        Ok(overrides)
+    }
+
+    fn lookup(&self, name: &str) -> Option<ClassSummary<'_>> {
+        let position = *self.by_name.get(&self.strings.get(name)?)?;
+        Some(self.view(&self.classes[position as usize]))
+    }
+
+    fn view<'a>(&'a self, class: &'a CompactClass) -> ClassSummary<'a> {
+        ClassSummary {
+            strings: &self.strings,
+            class,
+        }
+    }
+
+    /// Classes of the same simple name in other packages, and names
+    /// differing only in case. Misses are rare enough to be worth a scan of
+    /// the whole index rather than keeping maps of every name folded.
+    fn suggestions(&self, internal: &str) -> Vec<String> {
+        let simple = simple_name(internal);
+        let folded = internal.to_lowercase();
+        let mut candidates: Vec<&str> = self
+            .classes
+            .iter()
+            .map(|class| self.strings.resolve(class.name))
+            .filter(|name| simple_name(name) == simple || name.to_lowercase() == folded)
+            .collect();
+        candidates.sort_by_key(|candidate| (edit_distance(internal, candidate), *candidate));
+        candidates
+            .into_iter()
+            .take(MAX_SUGGESTIONS)
+            .map(str::to_string)
+            .collect()
+    }
+}
+
+/// The descriptor of the method of the same name on the same class that
+/// the bridge `method` calls.
+fn bridge_target<'a>(class_file: &'a ClassFile, method: &MethodInfo) -> Option<&'a str> {
+    if !method.access_flags().is_bridge() {
+        return None;
+    }
+    let constant_pool = &class_file.constant_pool;
+    let class_name = ConstantPool::class_name(constant_pool, class_file.this_class)?;
+    let name = ConstantPool::utf8(constant_pool, method.name_index())?;
+    instruction::decode(&method.code()?.code)
+        .map_while(Result::ok)
+        .filter(|instruction| instruction.is_invoke())
+        .filter_map(|instruction| ConstantPool::member_ref(constant_pool, instruction.cp_index()?))
+        .find(|member| member.class_name == class_name && member.name == name)
+        .map(|member| member.descriptor)
+}
+
+/// The package part of an internal name, empty for the unnamed package.
+fn package(internal: &str) -> &str {
+    internal.rfind('/').map_or("", |slash| &internal[..slash])
+}
+
+fn simple_name(internal: &str) -> &str {
+    internal.rsplit('/').next().unwrap_or(internal)
+}
+
+/// The class an array element descriptor names, or `None` for primitives.
+/// Nested arrays keep their descriptor.
+fn reference_element(element: &str) -> Option<&str> {
+    match element.strip_prefix('L') {
+        Some(class) => class.strip_suffix(';'),
+        None if element.starts_with('[') => Some(element),
+        None => None,
+    }
+}
+
+/// The Levenshtein distance between `a` and `b`, counted in characters.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, a) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &b) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(a != b);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(row[j + 1] + 1);
+        }
+    }
+    row[b.len()]
+}
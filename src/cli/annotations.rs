@@ -0,0 +1,336 @@
+use std::{
+    error::Error,
+    io::{self, Write},
+};
+
+use crate::{
+    annotationstats::AnnotationStats,
+    attribute::{Annotation, Attribute, TypeAnnotation},
+    classfile::ClassFile,
+    constantpool::ConstantPool,
+    descriptor::FieldType,
+    json::Json,
+    signature::{self, TypeParameter},
+    typeannotation::TypeAnnotationContext,
+};
+
+use super::{
+    exit::Failure,
+    output::{Format, Outputs, Results, Table},
+    scan::{parse_entry, Scan, ScanOptions},
+};
+
+/// The formats `annotations --stats` writes.
+pub const FORMATS: &[Format] = &[Format::Text, Format::Json, Format::Csv];
+
+/// Prints every annotation in the class along with the element or type use
+/// it applies to.
+pub fn print(class_file: &ClassFile) {
+    let constant_pool = &class_file.constant_pool;
+    let class_name = ConstantPool::class_name(constant_pool, class_file.this_class).unwrap_or("?");
+    let interfaces = class_file
+        .interfaces
+        .iter()
+        .map(|&index| {
+            ConstantPool::class_name(constant_pool, index)
+                .unwrap_or("?")
+                .to_string()
+        })
+        .collect();
+    print_attributes(
+        &class_file.attributes,
+        TypeAnnotationContext {
+            element: format!("class {}", class_name),
+            type_parameters: names(
+                &signature::best_class_signature(class_file)
+                    .signature
+                    .type_parameters,
+            ),
+            interfaces,
+            constant_pool,
+            ..Default::default()
+        },
+    );
+
+    for component in class_file.record_components().unwrap_or_default() {
+        let resolved = component.resolved(constant_pool);
+        let element = match &resolved.signature {
+            Some(signature) => format!("record component {} {}", signature, resolved.name),
+            None => format!("record component {}", resolved.name),
+        };
+        let annotations = resolved
+            .visible_annotations
+            .iter()
+            .chain(&resolved.invisible_annotations);
+        for annotation in annotations {
+            println!("@{} on {}", annotation.type_name(), element);
+        }
+        let context = TypeAnnotationContext {
+            element,
+            constant_pool,
+            ..Default::default()
+        };
+        for attribute in &component.attributes {
+            if let Attribute::RuntimeVisibleTypeAnnotations(annotations)
+            | Attribute::RuntimeInvisibleTypeAnnotations(annotations) = attribute
+            {
+                for annotation in annotations {
+                    print_type_annotation(annotation, &context);
+                }
+            }
+        }
+    }
+
+    for field in &class_file.fields {
+        let name = ConstantPool::utf8(constant_pool, field.name_index()).unwrap_or("?");
+        print_attributes(
+            field.attributes(),
+            TypeAnnotationContext {
+                element: format!("field {}", name),
+                constant_pool,
+                ..Default::default()
+            },
+        );
+    }
+
+    for method in &class_file.methods {
+        let name = ConstantPool::utf8(constant_pool, method.name_index()).unwrap_or("?");
+        let descriptor = ConstantPool::utf8(constant_pool, method.descriptor_index()).unwrap_or("");
+        let (element, type_parameters) = match signature::best_method_signature(class_file, method)
+        {
+            Some(best) => (
+                format!("{}{}", name, best.java_parameters()),
+                names(&best.signature.type_parameters),
+            ),
+            None => (format!("{}{}", name, descriptor), Vec::new()),
+        };
+        print_attributes(
+            method.attributes(),
+            TypeAnnotationContext {
+                element: element.clone(),
+                type_parameters: type_parameters.clone(),
+                constant_pool,
+                ..Default::default()
+            },
+        );
+
+        if let Some(code) = method.code() {
+            print_attributes(
+                &code.attributes,
+                TypeAnnotationContext {
+                    element: element.clone(),
+                    type_parameters: type_parameters.clone(),
+                    local_variables: code.local_variable_table().unwrap_or(&[]),
+                    constant_pool,
+                    ..Default::default()
+                },
+            );
+        }
+    }
+}
+
+/// Reports how many classes, fields, methods and parameters among `inputs`
+/// carry each annotation type to `outputs`, with a histogram of the values
+/// of every member `histograms` names as `<descriptor>#<member>`. The CSV
+/// has a row per annotation type; the histograms are left out of it.
+pub fn stats(
+    inputs: &[&str],
+    histograms: &[&str],
+    outputs: &Outputs,
+    options: ScanOptions,
+) -> Result<(), Box<dyn Error>> {
+    let mut stats = AnnotationStats::new();
+    for histogram in histograms {
+        match histogram.split_once('#') {
+            Some((annotation, member))
+                if annotation.starts_with('L') && annotation.ends_with(';') && !member.is_empty() =>
+            {
+                stats.histogram(annotation, member)
+            }
+            _ => {
+                return Err(Failure::usage(format!(
+                    "expected --histogram <descriptor>#<member>, e.g. 'Ljava/lang/Deprecated;#forRemoval': {}",
+                    histogram
+                ))
+                .into())
+            }
+        }
+    }
+    let mut classes = 0;
+    let mut scan = Scan::new(options);
+    scan.run(inputs, |_, buf, parse_options| {
+        stats.add_class(&parse_entry(buf, parse_options)?);
+        classes += 1;
+        Ok(())
+    })?;
+    scan.finish()?;
+    outputs.write(&Stats { stats, classes })
+}
+
+struct Stats {
+    stats: AnnotationStats,
+    classes: usize,
+}
+
+impl Results for Stats {
+    fn write_text(&self, out: &mut dyn Write) -> io::Result<()> {
+        writeln!(
+            out,
+            "{:>8} {:>8} {:>8} {:>10} {:>8}  annotation",
+            "classes", "fields", "methods", "parameters", "uses"
+        )?;
+        for (descriptor, usage) in self.stats.usage() {
+            writeln!(
+                out,
+                "{:>8} {:>8} {:>8} {:>10} {:>8}  @{}",
+                usage.classes,
+                usage.fields,
+                usage.methods,
+                usage.parameters,
+                usage.uses,
+                java_name(descriptor)
+            )?;
+        }
+        for histogram in self.stats.histograms() {
+            writeln!(
+                out,
+                "\n@{}#{}:",
+                java_name(&histogram.annotation),
+                histogram.member
+            )?;
+            if histogram.values.is_empty() {
+                writeln!(out, "  (not used)")?;
+            }
+            for (value, count) in &histogram.values {
+                writeln!(out, "  {:>6}  {}", count, value)?;
+            }
+        }
+        writeln!(
+            out,
+            "{} annotation types in {} classes",
+            self.stats.usage().len(),
+            self.classes
+        )
+    }
+
+    fn to_json(&self) -> Json {
+        let annotations = self.stats.usage().iter().map(|(descriptor, usage)| {
+            Json::object([
+                ("descriptor", descriptor.as_str().into()),
+                ("classes", Json::Int(usage.classes as i64)),
+                ("fields", Json::Int(usage.fields as i64)),
+                ("methods", Json::Int(usage.methods as i64)),
+                ("parameters", Json::Int(usage.parameters as i64)),
+                ("uses", Json::Int(usage.uses as i64)),
+            ])
+        });
+        let histograms = self.stats.histograms().iter().map(|histogram| {
+            Json::object([
+                ("annotation", histogram.annotation.as_str().into()),
+                ("member", histogram.member.as_str().into()),
+                (
+                    "values",
+                    Json::array(histogram.values.iter().map(|(value, count)| {
+                        Json::object([
+                            ("value", value.as_str().into()),
+                            ("count", Json::Int(*count as i64)),
+                        ])
+                    })),
+                ),
+            ])
+        });
+        Json::object([
+            ("classes", Json::Int(self.classes as i64)),
+            ("annotations", Json::array(annotations)),
+            ("histograms", Json::array(histograms)),
+        ])
+    }
+
+    fn to_table(&self) -> Option<Table> {
+        let mut rows = Table::new(&[
+            "descriptor",
+            "classes",
+            "fields",
+            "methods",
+            "parameters",
+            "uses",
+        ]);
+        for (descriptor, usage) in self.stats.usage() {
+            rows.push(vec![
+                descriptor.clone(),
+                usage.classes.to_string(),
+                usage.fields.to_string(),
+                usage.methods.to_string(),
+                usage.parameters.to_string(),
+                usage.uses.to_string(),
+            ]);
+        }
+        Some(rows)
+    }
+}
+
+/// The annotation type with `descriptor` as written in Java source.
+fn java_name(descriptor: &str) -> String {
+    FieldType::parse(descriptor)
+        .map(|field_type| field_type.to_string())
+        .unwrap_or_else(|_| descriptor.to_string())
+}
+
+fn print_attributes(attributes: &[Attribute], context: TypeAnnotationContext) {
+    for attribute in attributes {
+        match attribute {
+            Attribute::RuntimeVisibleAnnotations(annotations)
+            | Attribute::RuntimeInvisibleAnnotations(annotations) => {
+                for annotation in annotations {
+                    print_annotation(annotation, &context.element, context.constant_pool);
+                }
+            }
+            Attribute::RuntimeVisibleParameterAnnotations(parameters)
+            | Attribute::RuntimeInvisibleParameterAnnotations(parameters) => {
+                for (i, parameter) in parameters.iter().enumerate() {
+                    for annotation in &parameter.annotations {
+                        print_annotation(
+                            annotation,
+                            &format!("parameter {} of {}", i, context.element),
+                            context.constant_pool,
+                        );
+                    }
+                }
+            }
+            Attribute::RuntimeVisibleTypeAnnotations(annotations)
+            | Attribute::RuntimeInvisibleTypeAnnotations(annotations) => {
+                for annotation in annotations {
+                    print_type_annotation(annotation, &context);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn print_annotation(annotation: &Annotation, element: &str, constant_pool: &[ConstantPool]) {
+    println!(
+        "@{} on {}",
+        type_name(annotation.type_index, constant_pool),
+        element
+    );
+}
+
+fn print_type_annotation(annotation: &TypeAnnotation, context: &TypeAnnotationContext) {
+    println!(
+        "@{} on {}",
+        type_name(annotation.type_index, context.constant_pool),
+        annotation.location(context)
+    );
+}
+
+fn type_name(type_index: u16, constant_pool: &[ConstantPool]) -> String {
+    java_name(ConstantPool::utf8(constant_pool, type_index).unwrap_or("?"))
+}
+
+fn names(type_parameters: &[TypeParameter]) -> Vec<String> {
+    type_parameters
+        .iter()
+        .map(|parameter| parameter.name.clone())
+        .collect()
+}
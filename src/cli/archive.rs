@@ -0,0 +1,223 @@
+use std::error::Error;
+
+use crate::{
+    archivecheck::{self, ArchiveFinding},
+    classfile::{ClassFile, ParseOptions},
+    constantpool::ConstantPool,
+    error::ParseError,
+    jar::{JarFile, Release},
+    policy::Policy,
+    rdjson::{Diagnostic, Severity, SourceLocation, SourceLocator},
+    verify::{self, Violation},
+};
+
+use super::{
+    exit::{ExitCode, Failure},
+    output::{severity_prefix, FindingFormat},
+};
+
+/// Prints the internal name of every class in the jar `input`, followed by
+/// its entry when that's named otherwise, and each entry which couldn't be
+/// read or parsed with why on stderr, carrying on past it. Of a
+/// multi-release jar, only the classes `release` sees are printed.
+pub fn list(input: &str, release: Release, mmap: bool) -> Result<ExitCode, Box<dyn Error>> {
+    let jar = open(input, mmap)?.with_release(release);
+    let options = ParseOptions::default();
+    let mut failed = false;
+    for (entry, class_file) in jar.classes(&options) {
+        match class_file {
+            Ok(class_file) => {
+                let name =
+                    ConstantPool::class_name(&class_file.constant_pool, class_file.this_class)
+                        .unwrap_or("?");
+                if Some(name) == entry.strip_suffix(".class") {
+                    println!("{}", name);
+                } else {
+                    println!("{} ({})", name, entry);
+                }
+            }
+            Err(e) => {
+                eprintln!("{}: {}: {}", input, entry, e);
+                failed = true;
+            }
+        }
+    }
+    Ok(if failed {
+        ExitCode::Parse
+    } else {
+        ExitCode::Success
+    })
+}
+
+/// Reads the class `class_name`, an internal name, out of the jar `input`.
+/// Of a multi-release jar, the variant `release` sees is taken. Without an
+/// entry of that name, the first class which names itself so is, wherever
+/// it is.
+pub fn read_class(
+    input: &str,
+    class_name: &str,
+    release: Release,
+    mmap: bool,
+) -> Result<ClassFile, Box<dyn Error>> {
+    let jar = open(input, mmap)?.with_release(release);
+    let entry = jar.class_entry(class_name).or_else(|| {
+        jar.class_variants()
+            .find(|entry| entry.class_name() == Some(class_name))
+    });
+    if let Some(entry) = entry {
+        let buf = jar
+            .read(entry)
+            .map_err(|e| Failure::parse(format!("{}: {}: {}", input, entry.name, e)))?;
+        return super::parse_class_file(&buf, &format!("{}: {}", input, entry.name));
+    }
+    let options = ParseOptions::default();
+    let class_file = jar
+        .classes(&options)
+        .filter_map(|(_, class_file)| class_file.ok())
+        .find(|class_file| {
+            ConstantPool::class_name(&class_file.constant_pool, class_file.this_class)
+                == Some(class_name)
+        });
+    class_file.ok_or_else(|| Failure::usage(format!("{}: no class {}", input, class_name)).into())
+}
+
+/// Opens the jar `input`, mapped into memory with `mmap`.
+fn open(input: &str, mmap: bool) -> Result<JarFile, Box<dyn Error>> {
+    let jar = if mmap {
+        JarFile::open_mapped(input)
+    } else {
+        JarFile::open(input)
+    };
+    Ok(jar.map_err(|e| Failure::parse(format!("{}: {}", input, e)))?)
+}
+
+/// A class entry with violations: its name, the class and what was found.
+type VerifiedClass = (String, ClassFile, Vec<(Violation, Severity)>);
+
+/// Checks the zip structure of the jar `input` and then verifies each of
+/// its classes, printing the archive's findings with why they matter, then
+/// each class's violations under its entry name, or with `--format
+/// annotations` all of them as diagnostics. Archive findings are resolved
+/// against `policy` by entry name, class violations by class name. With
+/// `check`, prints nothing and reports findings which are more than
+/// information through the exit status.
+pub fn verify(
+    input: &str,
+    check: bool,
+    format: FindingFormat,
+    policy: &mut Policy,
+) -> Result<ExitCode, Box<dyn Error>> {
+    let jar = JarFile::open(input).map_err(|e| Failure::parse(format!("{}: {}", input, e)))?;
+    let findings: Vec<(ArchiveFinding, Severity)> = archivecheck::check_archive(jar.data())
+        .map_err(|e| Failure::parse(format!("{}: {}", input, e)))?
+        .into_iter()
+        .filter_map(|finding| {
+            let severity = policy.resolve(finding.code(), finding.entry(), Severity::Error)?;
+            Some((finding, severity))
+        })
+        .collect();
+
+    let mut failed = false;
+    let mut classes: Vec<VerifiedClass> = Vec::new();
+    for entry in jar.class_variants() {
+        let class_file = jar.read(entry).map_err(|e| e.to_string()).and_then(|buf| {
+            ClassFile::parse_class_file(&buf)
+                .map(|(_, class_file)| class_file)
+                .map_err(|e| ParseError::from(e).to_string())
+        });
+        let class_file = match class_file {
+            Ok(class_file) => class_file,
+            Err(e) => {
+                eprintln!("{}!{}: {}", input, entry.name, e);
+                failed = true;
+                continue;
+            }
+        };
+        let class_name = ConstantPool::class_name(&class_file.constant_pool, class_file.this_class)
+            .unwrap_or("")
+            .to_string();
+        let violations: Vec<_> = verify::verify(&class_file)
+            .into_iter()
+            .filter_map(|violation| {
+                let severity =
+                    policy.resolve(violation.code, &class_name, violation.severity.into())?;
+                Some((violation, severity))
+            })
+            .collect();
+        if !violations.is_empty() {
+            classes.push((entry.name.clone(), class_file, violations));
+        }
+    }
+
+    if check {
+        let found = findings
+            .iter()
+            .map(|(_, severity)| severity)
+            .chain(
+                classes
+                    .iter()
+                    .flat_map(|(_, _, violations)| violations.iter().map(|(_, severity)| severity)),
+            )
+            .any(|severity| *severity != Severity::Info);
+        if found {
+            return Ok(ExitCode::Violations);
+        }
+    } else if format == FindingFormat::Annotations {
+        for (finding, severity) in &findings {
+            let diagnostic = Diagnostic {
+                severity: *severity,
+                code: finding.code().to_string(),
+                message: format!("{}. {}", finding, finding.explanation()),
+                location: SourceLocation::unmapped(
+                    input,
+                    "the finding is about the archive, not a class in it",
+                ),
+            };
+            println!("{}", diagnostic.to_json());
+        }
+        for (entry, class_file, violations) in &classes {
+            let entry = format!("{}!{}", input, entry);
+            let locator = SourceLocator::new(class_file, &entry);
+            for (violation, severity) in violations {
+                let method = violation.method().and_then(|i| class_file.methods.get(i));
+                let diagnostic = Diagnostic {
+                    severity: *severity,
+                    code: violation.code.to_string(),
+                    message: format!("{}: {}", violation.location, violation.message),
+                    location: match method {
+                        Some(method) => locator.method(method),
+                        None => locator.class(),
+                    },
+                };
+                println!("{}", diagnostic.to_json());
+            }
+        }
+    } else {
+        for (finding, severity) in &findings {
+            println!(
+                "{}{} [{}]",
+                severity_prefix(*severity),
+                finding,
+                finding.code()
+            );
+            println!("  {}", finding.explanation());
+        }
+        for (entry, _, violations) in &classes {
+            for (violation, severity) in violations {
+                println!(
+                    "{}{}: {}: {} [{}]",
+                    severity_prefix(*severity),
+                    entry,
+                    violation.location,
+                    violation.message,
+                    violation.code
+                );
+            }
+        }
+    }
+
+    if failed {
+        return Err(Failure::parse("some classes couldn't be parsed").into());
+    }
+    Ok(ExitCode::Success)
+}
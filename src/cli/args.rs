@@ -0,0 +1,85 @@
+/// Command line arguments split into positionals and `--options`.
+pub struct Args {
+    positionals: Vec<String>,
+    options: Vec<(String, Option<String>)>,
+}
+
+impl Args {
+    /// `value_options` lists the options which take a value, given either as
+    /// `--name value` or `--name=value`. `short_options` maps single letter
+    /// aliases such as `-o` to the long name they stand for.
+    pub fn parse(
+        args: &[String],
+        value_options: &[&str],
+        short_options: &[(char, &str)],
+    ) -> Result<Self, String> {
+        let mut positionals = Vec::new();
+        let mut options = Vec::new();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let short = arg.strip_prefix('-').and_then(|name| {
+                short_options
+                    .iter()
+                    .find(|(short, _)| name.chars().eq([*short]))
+                    .map(|(_, long)| *long)
+            });
+            match short.or_else(|| arg.strip_prefix("--")) {
+                Some(option) => match option.split_once('=') {
+                    Some((name, value)) => {
+                        options.push((name.to_string(), Some(value.to_string())))
+                    }
+                    None if value_options.contains(&option) => {
+                        let value = args
+                            .next()
+                            .ok_or_else(|| format!("missing value for --{}", option))?;
+                        options.push((option.to_string(), Some(value.clone())));
+                    }
+                    None => options.push((option.to_string(), None)),
+                },
+                None => positionals.push(arg.clone()),
+            }
+        }
+        Ok(Args {
+            positionals,
+            options,
+        })
+    }
+
+    pub fn positional(&self, index: usize) -> Option<&str> {
+        self.positionals.get(index).map(String::as_str)
+    }
+
+    pub fn positionals(&self) -> Vec<&str> {
+        self.positionals.iter().map(String::as_str).collect()
+    }
+
+    pub fn flag(&self, name: &str) -> bool {
+        self.options.iter().any(|(option, _)| option == name)
+    }
+
+    /// Adds `values` for the option `name` unless the command line gave it.
+    pub fn set_default(&mut self, name: &str, values: &[String]) {
+        if !self.flag(name) {
+            self.options.extend(
+                values
+                    .iter()
+                    .map(|value| (name.to_string(), Some(value.clone()))),
+            );
+        }
+    }
+
+    /// Sets the flag `name` unless the command line gave it.
+    pub fn set_default_flag(&mut self, name: &str) {
+        if !self.flag(name) {
+            self.options.push((name.to_string(), None));
+        }
+    }
+
+    pub fn values(&self, name: &str) -> Vec<&str> {
+        self.options
+            .iter()
+            .filter(|(option, _)| option == name)
+            .filter_map(|(_, value)| value.as_deref())
+            .collect()
+    }
+}
@@ -0,0 +1,718 @@
+use std::{collections::HashMap, error::Error};
+
+use crate::{
+    classfile::ClassFile,
+    constantpool::ConstantPool,
+    spec,
+    symbols::{field_id, method_id, SymbolIndex},
+    textify::{self, PrintOptions},
+};
+
+use super::{
+    constants,
+    scan::{parse_entry, Scan, ScanOptions},
+    terminal::Terminal,
+};
+
+/// Opens the browser on the classes among `inputs` (class files, jars or
+/// directories), until it's quit.
+pub fn run(inputs: &[&str], options: ScanOptions) -> Result<(), Box<dyn Error>> {
+    let mut classes = Vec::new();
+    let mut scan = Scan::new(options);
+    scan.run(inputs, |_, buf, parse_options| {
+        classes.push(parse_entry(buf, parse_options)?);
+        Ok(())
+    })?;
+    scan.finish()?;
+    if classes.is_empty() {
+        return Err("no classes among the inputs".into());
+    }
+
+    let mut browser = Browser::new(classes);
+    let mut terminal = Terminal::open()?;
+    while !browser.is_done() {
+        let (width, height) = terminal.size();
+        browser.set_size(width, height);
+        terminal.draw(&browser.render())?;
+        let key = loop {
+            if let Some(key) = terminal.read_key()? {
+                break key;
+            }
+        };
+        browser.handle(key);
+    }
+    Ok(())
+}
+
+/// A key the browser acts on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    Char(char),
+    Up,
+    Down,
+    Left,
+    Right,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    Tab,
+    BackTab,
+    Enter,
+    Esc,
+    Backspace,
+    /// Ctrl-C, which quits whatever the browser is doing.
+    Interrupt,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Pane {
+    Classes,
+    Members,
+    Detail,
+}
+
+/// A class, and one of its members unless the class itself is selected.
+type Position = (usize, Option<usize>);
+
+struct Class {
+    name: String,
+    class_file: ClassFile,
+    /// Fields, then methods, in declaration order.
+    members: Vec<Member>,
+}
+
+struct Member {
+    /// As [`crate::symbols`] names it, e.g. `com/example/Foo#run(I)V`.
+    id: String,
+    kind: MemberKind,
+}
+
+impl Member {
+    /// The ID without the class: the name and descriptor.
+    fn label(&self) -> &str {
+        self.id.split_once('#').map_or(&self.id, |(_, label)| label)
+    }
+
+    fn name(&self) -> &str {
+        let label = self.label();
+        label.split([':', '(']).next().unwrap_or(label)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum MemberKind {
+    Field(usize),
+    Method(usize),
+}
+
+enum Row {
+    Package(String),
+    Class(usize),
+}
+
+enum Mode {
+    Normal,
+    /// Typing a search, with the position it started from.
+    Search {
+        query: String,
+        origin: Position,
+    },
+    /// Typing the index of a constant pool entry.
+    Goto(String),
+}
+
+/// What the detail pane shows, with the position each line leads to.
+struct Detail {
+    title: String,
+    lines: Vec<String>,
+    links: Vec<Option<Position>>,
+    cursor: usize,
+}
+
+impl Detail {
+    fn new(title: String, lines: Vec<String>) -> Self {
+        Detail {
+            title,
+            links: vec![None; lines.len()],
+            lines,
+            cursor: 0,
+        }
+    }
+
+    fn text(title: String, text: &str) -> Self {
+        let lines = text
+            .trim_start_matches('\n')
+            .lines()
+            .map(|line| line.replace('\t', "    "))
+            .collect();
+        Detail::new(title, lines)
+    }
+}
+
+/// The state of `jvmb browse`: the classes by package, the members of the
+/// one selected and the dump of the selection, searched and navigated by
+/// [`Browser::handle`] and drawn by [`Browser::render`]. It does no I/O, so
+/// that it can be driven without a terminal.
+pub struct Browser {
+    /// By package, then by name.
+    classes: Vec<Class>,
+    symbols: SymbolIndex,
+    /// The position of every member, by ID.
+    positions: HashMap<String, Position>,
+    rows: Vec<Row>,
+    /// The row of each class.
+    class_rows: Vec<usize>,
+    /// Where each class starts in the order searches go through, each
+    /// class followed by its members.
+    search_starts: Vec<usize>,
+    position: Position,
+    focus: Pane,
+    detail: Detail,
+    mode: Mode,
+    last_search: Option<String>,
+    status: String,
+    size: (usize, usize),
+    done: bool,
+}
+
+impl Browser {
+    pub fn new(class_files: Vec<ClassFile>) -> Self {
+        let mut symbols = SymbolIndex::new();
+        let mut classes: Vec<Class> = class_files
+            .into_iter()
+            .map(|class_file| {
+                symbols.add(class_file.symbols());
+                let constant_pool = &class_file.constant_pool;
+                let name = ConstantPool::class_name(constant_pool, class_file.this_class)
+                    .unwrap_or("?")
+                    .to_string();
+                let utf8 = |index| ConstantPool::utf8(constant_pool, index).unwrap_or("?");
+                let fields = class_file
+                    .fields
+                    .iter()
+                    .enumerate()
+                    .map(|(i, field)| Member {
+                        id: field_id(
+                            &name,
+                            utf8(field.name_index()),
+                            utf8(field.descriptor_index()),
+                        ),
+                        kind: MemberKind::Field(i),
+                    });
+                let methods = class_file
+                    .methods
+                    .iter()
+                    .enumerate()
+                    .map(|(i, method)| Member {
+                        id: method_id(
+                            &name,
+                            utf8(method.name_index()),
+                            utf8(method.descriptor_index()),
+                        ),
+                        kind: MemberKind::Method(i),
+                    });
+                let members = fields.chain(methods).collect();
+                Class {
+                    name,
+                    class_file,
+                    members,
+                }
+            })
+            .collect();
+        classes.sort_by(|a, b| {
+            package(&a.name)
+                .cmp(package(&b.name))
+                .then(a.name.cmp(&b.name))
+        });
+
+        let mut positions = HashMap::new();
+        let mut rows = Vec::new();
+        let mut class_rows = Vec::new();
+        let mut search_starts = Vec::new();
+        let mut searched = 0;
+        for (i, class) in classes.iter().enumerate() {
+            let package = package(&class.name);
+            if i == 0 || package != self::package(&classes[i - 1].name) {
+                rows.push(Row::Package(package.replace('/', ".")));
+            }
+            class_rows.push(rows.len());
+            rows.push(Row::Class(i));
+            search_starts.push(searched);
+            searched += 1 + class.members.len();
+            for (j, member) in class.members.iter().enumerate() {
+                positions.entry(member.id.clone()).or_insert((i, Some(j)));
+            }
+        }
+
+        let mut browser = Browser {
+            classes,
+            symbols,
+            positions,
+            rows,
+            class_rows,
+            search_starts,
+            position: (0, None),
+            focus: Pane::Classes,
+            detail: Detail::new(String::new(), Vec::new()),
+            mode: Mode::Normal,
+            last_search: None,
+            status: String::new(),
+            size: (80, 24),
+            done: false,
+        };
+        browser.detail = browser.view();
+        browser
+    }
+
+    /// Whether the browser was quit.
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    /// Sets the width and height `render` draws in.
+    pub fn set_size(&mut self, width: usize, height: usize) {
+        self.size = (width, height);
+    }
+
+    pub fn handle(&mut self, key: Key) {
+        if key == Key::Interrupt {
+            self.done = true;
+            return;
+        }
+        match std::mem::replace(&mut self.mode, Mode::Normal) {
+            Mode::Normal => self.handle_normal(key),
+            Mode::Search { query, origin } => self.handle_search(key, query, origin),
+            Mode::Goto(digits) => self.handle_goto(key, digits),
+        }
+    }
+
+    fn handle_normal(&mut self, key: Key) {
+        self.status.clear();
+        let page = self.size.1.saturating_sub(4).max(1) as isize;
+        match key {
+            Key::Char('q') => self.done = true,
+            Key::Tab | Key::Right | Key::Char('l') => self.focus_pane(match self.focus {
+                Pane::Classes => Pane::Members,
+                _ => Pane::Detail,
+            }),
+            Key::BackTab | Key::Left | Key::Char('h') => self.focus_pane(match self.focus {
+                Pane::Detail => Pane::Members,
+                _ => Pane::Classes,
+            }),
+            Key::Up | Key::Char('k') => self.move_by(-1),
+            Key::Down | Key::Char('j') => self.move_by(1),
+            Key::PageUp => self.move_by(-page),
+            Key::PageDown => self.move_by(page),
+            Key::Home => self.move_by(isize::MIN / 2),
+            Key::End => self.move_by(isize::MAX / 2),
+            Key::Enter => match self.focus {
+                Pane::Classes => self.focus_pane(Pane::Members),
+                Pane::Members => self.focus_pane(Pane::Detail),
+                Pane::Detail => {
+                    if let Some(&Some(position)) = self.detail.links.get(self.detail.cursor) {
+                        self.select_match(position);
+                    }
+                }
+            },
+            Key::Esc => self.detail = self.view(),
+            Key::Char('/') => {
+                self.mode = Mode::Search {
+                    query: String::new(),
+                    origin: self.position,
+                }
+            }
+            Key::Char('n') => self.search_again(true),
+            Key::Char('N') => self.search_again(false),
+            Key::Char('g') => self.mode = Mode::Goto(String::new()),
+            Key::Char('x') => self.show_references(),
+            _ => {}
+        }
+    }
+
+    fn handle_search(&mut self, key: Key, mut query: String, origin: Position) {
+        match key {
+            Key::Enter => {
+                if !query.is_empty() {
+                    self.last_search = Some(query);
+                }
+                self.status.clear();
+                return;
+            }
+            Key::Esc => {
+                self.select(origin);
+                self.status.clear();
+                return;
+            }
+            Key::Backspace => {
+                query.pop();
+            }
+            Key::Char(c) => query.push(c),
+            _ => {}
+        }
+        self.status.clear();
+        if query.is_empty() {
+            self.select(origin);
+        } else {
+            match self.find(&query, origin, true, true) {
+                Some(position) => self.select_match(position),
+                None => self.status = " (no match)".to_string(),
+            }
+        }
+        self.mode = Mode::Search { query, origin };
+    }
+
+    fn handle_goto(&mut self, key: Key, mut digits: String) {
+        match key {
+            Key::Enter => {
+                let constant_pool = &self.classes[self.position.0].class_file.constant_pool;
+                match digits.parse::<u16>() {
+                    Ok(index) if (1..=constant_pool.len()).contains(&(index as usize)) => {
+                        self.detail = self.constants_view(index);
+                        self.focus = Pane::Detail;
+                    }
+                    _ => self.status = format!("no constant #{}", digits),
+                }
+                return;
+            }
+            Key::Esc => return,
+            Key::Backspace => {
+                digits.pop();
+            }
+            Key::Char(c) if c.is_ascii_digit() => digits.push(c),
+            _ => {}
+        }
+        self.mode = Mode::Goto(digits);
+    }
+
+    fn focus_pane(&mut self, pane: Pane) {
+        if pane != Pane::Classes && self.position.1.is_none() {
+            if self.classes[self.position.0].members.is_empty() {
+                if pane == Pane::Members {
+                    self.status = "the class has no members".to_string();
+                    return;
+                }
+            } else {
+                self.select((self.position.0, Some(0)));
+            }
+        }
+        self.focus = pane;
+    }
+
+    /// Moves the selection of the focused pane by `delta` items, stopping
+    /// at either end.
+    fn move_by(&mut self, delta: isize) {
+        let step = |from: usize, len: usize| {
+            (from as isize)
+                .saturating_add(delta)
+                .clamp(0, len.saturating_sub(1) as isize) as usize
+        };
+        match self.focus {
+            Pane::Classes => self.select((step(self.position.0, self.classes.len()), None)),
+            Pane::Members => {
+                let members = self.classes[self.position.0].members.len();
+                if members > 0 {
+                    let member = step(self.position.1.unwrap_or(0), members);
+                    self.select((self.position.0, Some(member)));
+                }
+            }
+            Pane::Detail => {
+                self.detail.cursor = step(self.detail.cursor, self.detail.lines.len());
+            }
+        }
+    }
+
+    fn select(&mut self, position: Position) {
+        self.position = position;
+        self.detail = self.view();
+    }
+
+    /// Selects `position` and focuses the pane it's in.
+    fn select_match(&mut self, position: Position) {
+        self.select(position);
+        self.focus = match position.1 {
+            Some(_) => Pane::Members,
+            None => Pane::Classes,
+        };
+    }
+
+    /// The dump of the class selected, or of its member.
+    fn view(&self) -> Detail {
+        let class = &self.classes[self.position.0];
+        let class_file = &class.class_file;
+        match self.position.1.map(|member| &class.members[member]) {
+            None => Detail::text(class.name.clone(), &textify::textify(class_file)),
+            Some(member) => {
+                let text = match member.kind {
+                    MemberKind::Field(i) => {
+                        textify::textify_field(class_file, &class_file.fields[i])
+                    }
+                    MemberKind::Method(i) => textify::textify_method(
+                        class_file,
+                        &class_file.methods[i],
+                        &PrintOptions::full(),
+                    ),
+                };
+                Detail::text(member.id.clone(), &text)
+            }
+        }
+    }
+
+    /// Entry `index` of the selected class's pool and every entry it leads
+    /// to, as `cp --follow` prints them.
+    fn constants_view(&self, index: u16) -> Detail {
+        let class = &self.classes[self.position.0];
+        let constant_pool = &class.class_file.constant_pool;
+        let lines = class
+            .class_file
+            .reachable_constants(index)
+            .into_iter()
+            .map(|i| {
+                let constant = &constant_pool[i as usize - 1];
+                let kind = spec::constant_spec(constant.tag()).map_or("?", |spec| spec.name);
+                let (operands, comment) = constants::describe(constant, constant_pool);
+                let line = format!("{:>6} = {:<18} {}", format!("#{}", i), kind, operands);
+                match comment {
+                    Some(comment) => format!("{:<48} // {}", line, comment),
+                    None => line,
+                }
+            })
+            .collect();
+        Detail::new(format!("{} #{}", class.name, index), lines)
+    }
+
+    /// Lists the instructions among the classes referring to the selected
+    /// member, whether they name its class or a subclass inheriting it.
+    fn show_references(&mut self) {
+        let class = &self.classes[self.position.0];
+        let member = match self.position.1 {
+            Some(member) => &class.members[member],
+            None => {
+                self.status = "x lists the references to a member; select one first".to_string();
+                return;
+            }
+        };
+        let suffix = &member.id[member.id.find('#').unwrap_or(0)..];
+        let mut lines = Vec::new();
+        let mut links = Vec::new();
+        for table in self.symbols.tables() {
+            for reference in &table.references {
+                if !reference.target.ends_with(suffix) {
+                    continue;
+                }
+                let resolves = reference.target == member.id
+                    || self
+                        .symbols
+                        .resolve(&reference.target)
+                        .is_some_and(|(_, definition)| definition.id == member.id);
+                if !resolves {
+                    continue;
+                }
+                let line = reference
+                    .line
+                    .map_or(String::new(), |line| format!(" (line {})", line));
+                lines.push(format!("{} +{}{}", reference.from, reference.offset, line));
+                links.push(self.positions.get(&reference.from).copied());
+            }
+        }
+        self.status = match lines.len() {
+            1 => "1 reference; Enter goes to it".to_string(),
+            n => format!("{} references; Enter goes to one", n),
+        };
+        let title = format!("references to {}", member.id);
+        self.detail = Detail {
+            title,
+            lines,
+            links,
+            cursor: 0,
+        };
+        self.focus = Pane::Detail;
+    }
+
+    /// Goes to the next match of the last search, or the previous one.
+    fn search_again(&mut self, forward: bool) {
+        let query = match &self.last_search {
+            Some(query) => query.clone(),
+            None => return,
+        };
+        match self.find(&query, self.position, forward, false) {
+            Some(position) => self.select_match(position),
+            None => self.status = format!("no match for {}", query),
+        }
+    }
+
+    /// The first class or member, in tree order from `from` and wrapping
+    /// around, whose name contains `query`, ignoring case. A class matches
+    /// by its internal or its dotted name.
+    fn find(
+        &self,
+        query: &str,
+        from: Position,
+        forward: bool,
+        inclusive: bool,
+    ) -> Option<Position> {
+        let query = query.to_lowercase();
+        let total = self.search_starts.last()? + 1 + self.classes.last()?.members.len();
+        let start = self.search_starts[from.0] + from.1.map_or(0, |member| member + 1);
+        (0..total)
+            .map(|k| {
+                let k = if inclusive { k } else { k + 1 };
+                if forward {
+                    (start + k) % total
+                } else {
+                    (start + total * 2 - k) % total
+                }
+            })
+            .map(|k| self.position_at(k))
+            .find(|&(class, member)| {
+                let class = &self.classes[class];
+                match member {
+                    None => {
+                        let name = class.name.to_lowercase();
+                        name.contains(&query) || name.replace('/', ".").contains(&query)
+                    }
+                    Some(member) => class.members[member].name().to_lowercase().contains(&query),
+                }
+            })
+    }
+
+    /// The position `k` steps into the order searches go through.
+    fn position_at(&self, k: usize) -> Position {
+        let class = self.search_starts.partition_point(|&start| start <= k) - 1;
+        match k - self.search_starts[class] {
+            0 => (class, None),
+            member => (class, Some(member - 1)),
+        }
+    }
+
+    /// The screen, as lines of text with ANSI styles, for the size last
+    /// set.
+    pub fn render(&self) -> Vec<String> {
+        let (width, height) = self.size;
+        let body = height.saturating_sub(2);
+        let classes_width = (width * 3 / 10).max(12);
+        let members_width = (width * 3 / 10).max(12);
+        let detail_width = width.saturating_sub(classes_width + members_width + 2);
+
+        let class_items: Vec<String> = self
+            .rows
+            .iter()
+            .map(|row| match row {
+                Row::Package(package) if package.is_empty() => "(default package)".to_string(),
+                Row::Package(package) => package.clone(),
+                Row::Class(i) => format!("  {}", simple_name(&self.classes[*i].name)),
+            })
+            .collect();
+        let class = &self.classes[self.position.0];
+        let member_items: Vec<String> = class
+            .members
+            .iter()
+            .map(|member| member.label().to_string())
+            .collect();
+        let classes = self.pane(
+            Pane::Classes,
+            format!("Classes ({})", self.classes.len()),
+            &class_items,
+            Some(self.class_rows[self.position.0]),
+            body,
+        );
+        let members = self.pane(
+            Pane::Members,
+            format!("Members of {}", simple_name(&class.name)),
+            &member_items,
+            self.position.1,
+            body,
+        );
+        let detail = self.pane(
+            Pane::Detail,
+            self.detail.title.clone(),
+            &self.detail.lines,
+            (self.focus == Pane::Detail).then_some(self.detail.cursor),
+            body,
+        );
+
+        let mut screen = Vec::with_capacity(height);
+        screen.push(styled(
+            STYLE_REVERSE,
+            &format!(" jvmb browse: {} classes", self.classes.len()),
+            width,
+        ));
+        for i in 0..body {
+            let (class_style, class_line) = &classes[i];
+            let (member_style, member_line) = &members[i];
+            let (detail_style, detail_line) = &detail[i];
+            screen.push(format!(
+                "{}│{}│{}",
+                styled(class_style, class_line, classes_width),
+                styled(member_style, member_line, members_width),
+                styled(detail_style, detail_line, detail_width),
+            ));
+        }
+        let status = match &self.mode {
+            Mode::Search { query, .. } => format!("/{}{}", query, self.status),
+            Mode::Goto(digits) => format!("constant #{}", digits),
+            Mode::Normal if !self.status.is_empty() => self.status.clone(),
+            Mode::Normal => HELP.to_string(),
+        };
+        screen.push(styled("", &status, width));
+        screen.truncate(height);
+        screen
+    }
+
+    /// The `height` lines of a pane: its title, then as many `items` as
+    /// fit around the one `selected`.
+    fn pane(
+        &self,
+        pane: Pane,
+        title: String,
+        items: &[String],
+        selected: Option<usize>,
+        height: usize,
+    ) -> Vec<(&'static str, String)> {
+        let focused = self.focus == pane;
+        let mut lines = vec![(if focused { STYLE_REVERSE } else { STYLE_BOLD }, title)];
+        let visible = height.saturating_sub(1);
+        let first = match selected {
+            Some(selected) if items.len() > visible => selected
+                .saturating_sub(visible / 2)
+                .min(items.len() - visible),
+            _ => 0,
+        };
+        for (i, item) in items.iter().enumerate().skip(first).take(visible) {
+            let style = match selected {
+                Some(selected) if selected == i && focused => STYLE_REVERSE,
+                Some(selected) if selected == i => STYLE_BOLD,
+                _ => "",
+            };
+            lines.push((style, item.clone()));
+        }
+        lines.resize(height, ("", String::new()));
+        lines
+    }
+}
+
+const HELP: &str =
+    "/ search  n N next, previous  g constant  x references  Tab pane  Esc back  q quit";
+const STYLE_REVERSE: &str = "\x1b[7m";
+const STYLE_BOLD: &str = "\x1b[1m";
+
+/// `text` cut or padded to `width` characters, in `style`.
+fn styled(style: &str, text: &str, width: usize) -> String {
+    let mut cell: String = text.chars().take(width).collect();
+    let len = cell.chars().count();
+    cell.extend(std::iter::repeat_n(' ', width - len));
+    if style.is_empty() {
+        cell
+    } else {
+        format!("{}{}\x1b[0m", style, cell)
+    }
+}
+
+/// The package of the internal name `class`, or `""` for the default one.
+fn package(class: &str) -> &str {
+    class.rsplit_once('/').map_or("", |(package, _)| package)
+}
+
+fn simple_name(class: &str) -> &str {
+    class.rsplit_once('/').map_or(class, |(_, name)| name)
+}
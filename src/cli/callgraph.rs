@@ -0,0 +1,67 @@
+use std::error::Error;
+
+use crate::{callgraph::CallGraph, instruction};
+
+use super::{
+    exit::Failure,
+    scan::{parse_entry, Scan, ScanOptions},
+    xref::MemberPattern,
+};
+
+/// Builds the call graph of the classes among `inputs` (class files, jars
+/// or directories) and prints one of the shortest chains of calls from a
+/// method `from` matches to one `to` matches: the first method, then each
+/// call on a line of its own with its offset in the caller, or the method
+/// an undeclared one is inherited from. Prints `unreachable` when there's
+/// none.
+pub fn run(
+    inputs: &[&str],
+    from: &MemberPattern,
+    to: &MemberPattern,
+    options: ScanOptions,
+) -> Result<(), Box<dyn Error>> {
+    let mut graph = CallGraph::new();
+    let mut scan = Scan::new(options);
+    scan.run(inputs, |_, buf, parse_options| {
+        graph.add_class(&parse_entry(buf, parse_options)?);
+        Ok(())
+    })?;
+
+    let matching = |pattern: &MemberPattern| -> Vec<usize> {
+        graph
+            .methods()
+            .filter(|(_, method)| {
+                pattern.matches(&method.class_name, &method.name, &method.descriptor)
+            })
+            .map(|(node, _)| node)
+            .collect()
+    };
+    let starts = matching(from);
+    if starts.is_empty() {
+        return Err(Failure::usage(format!("no method matches --from {}", from)).into());
+    }
+    match graph.path(&starts, &matching(to)) {
+        Some(path) => {
+            println!("{}", graph.method(path[0]));
+            for pair in path.windows(2) {
+                let callee = graph.method(pair[1]);
+                match graph
+                    .calls(pair[0])
+                    .iter()
+                    .find(|call| call.callee == pair[1])
+                {
+                    Some(call) => println!(
+                        "  {}: {} {}{}",
+                        call.offset,
+                        instruction::mnemonic(call.opcode).unwrap_or("<invalid>"),
+                        callee,
+                        if callee.external { " (external)" } else { "" }
+                    ),
+                    None => println!("  inherited from {}", callee),
+                }
+            }
+        }
+        None => println!("unreachable"),
+    }
+    scan.finish()
+}
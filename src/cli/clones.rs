@@ -0,0 +1,46 @@
+use std::error::Error;
+
+use crate::clones::CloneDetector;
+
+use super::scan::{parse_entry, EntryError, Scan, ScanOptions};
+
+/// Prints groups of methods among `inputs` (class files, jars or
+/// directories) with copied bodies, largest saving first.
+pub fn run(
+    inputs: &[&str],
+    min_size: usize,
+    similarity: Option<f64>,
+    friendly_names: bool,
+    options: ScanOptions,
+) -> Result<(), Box<dyn Error>> {
+    let mut detector = CloneDetector::new(min_size);
+    let mut scan = Scan::new(options);
+    scan.run(inputs, |_, buf, parse_options| {
+        let class_file = parse_entry(buf, parse_options)?;
+        detector
+            .add_class(&class_file)
+            .map_err(|e| EntryError::new("decode", e.to_string()))
+    })?;
+    scan.finish()?;
+
+    for group in detector.groups(similarity) {
+        println!(
+            "{} {} methods, {} duplicated bytes",
+            if group.identical {
+                "identical:"
+            } else {
+                "similar:"
+            },
+            group.members.len(),
+            group.duplicated_bytes()
+        );
+        for member in &group.members {
+            let name = match &member.friendly_name {
+                Some(friendly_name) if friendly_names => friendly_name,
+                _ => &member.name,
+            };
+            println!("  {:>6}  {}", member.size, name);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,219 @@
+use std::error::Error;
+
+use crate::{
+    cfg::{ControlFlowGraph, EdgeKind},
+    classfile::ClassFile,
+    constantpool::ConstantPool,
+    instruction, javap,
+    json::Json,
+    layout::{CodeLayout, LayoutSummary},
+    methodinfo::MethodInfo,
+};
+
+use super::exit::Failure;
+
+/// Prints the methods `method` names, by name alone or with the descriptor
+/// as well, e.g. `run()V`, each with its code as javap does, or with
+/// `lines` only their instructions, with the source lines they start
+/// between them.
+pub fn print_method(
+    class_file: &ClassFile,
+    method: &str,
+    lines: bool,
+) -> Result<(), Box<dyn Error>> {
+    let constant_pool = &class_file.constant_pool;
+    for (i, method) in named_methods(class_file, method)?.into_iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        if !lines {
+            print!("{}", javap::javap_method(class_file, method));
+            continue;
+        }
+        let name = format!(
+            "{}{}",
+            method.name(constant_pool).unwrap_or("?"),
+            method.descriptor(constant_pool).unwrap_or("")
+        );
+        match javap::javap_code(class_file, method) {
+            Some(listing) => print!("{}:\n{}", name, listing),
+            None => eprintln!("{} has no code", name),
+        }
+    }
+    Ok(())
+}
+
+/// Prints the control flow graph of each method `method` names, as
+/// [`print_method`] picks them: its blocks with the edges out of each, or
+/// with `dot` a DOT digraph of the blocks with their instructions.
+pub fn print_cfg(class_file: &ClassFile, method: &str, dot: bool) -> Result<(), Box<dyn Error>> {
+    let constant_pool = &class_file.constant_pool;
+    for (i, method) in named_methods(class_file, method)?.into_iter().enumerate() {
+        let name = format!(
+            "{}{}",
+            method.name(constant_pool).unwrap_or("?"),
+            method.descriptor(constant_pool).unwrap_or("")
+        );
+        let code = match method.code() {
+            Some(code) => code,
+            None => {
+                eprintln!("{} has no code", name);
+                continue;
+            }
+        };
+        let cfg = ControlFlowGraph::build(code)?;
+        if i > 0 {
+            println!();
+        }
+        if !dot {
+            println!("{}:", name);
+            for (index, block) in cfg.blocks.iter().enumerate() {
+                let edges: Vec<String> = cfg
+                    .edges
+                    .iter()
+                    .filter(|(from, _, _)| *from == index)
+                    .map(|(_, to, kind)| format!("B{} ({})", to, edge_name(*kind)))
+                    .collect();
+                let edges = if edges.is_empty() {
+                    String::new()
+                } else {
+                    format!(" -> {}", edges.join(", "))
+                };
+                println!("  B{} [{}, {}){}", index, block.start, block.end, edges);
+            }
+            continue;
+        }
+
+        println!("digraph \"{}\" {{", name);
+        println!("  node [shape=box, fontname=monospace];");
+        let instructions = instruction::decode(&code.code).collect::<Result<Vec<_>, _>>()?;
+        for (index, block) in cfg.blocks.iter().enumerate() {
+            let mut label = format!("B{}\\l", index);
+            for instruction in instructions
+                .iter()
+                .filter(|instruction| (block.start..block.end).contains(&instruction.offset))
+            {
+                label += &format!("{}: {}\\l", instruction.offset, instruction.mnemonic());
+            }
+            println!("  B{} [label=\"{}\"];", index, label);
+        }
+        for (from, to, kind) in &cfg.edges {
+            let style = match kind {
+                EdgeKind::FallThrough => "",
+                EdgeKind::Jump => " [style=bold]",
+                EdgeKind::SwitchCase => " [style=bold, color=blue]",
+                EdgeKind::Exception => " [style=dashed, color=red]",
+            };
+            println!("  B{} -> B{}{};", from, to, style);
+        }
+        println!("}}");
+    }
+    Ok(())
+}
+
+fn edge_name(kind: EdgeKind) -> &'static str {
+    match kind {
+        EdgeKind::FallThrough => "fall-through",
+        EdgeKind::Jump => "jump",
+        EdgeKind::SwitchCase => "switch case",
+        EdgeKind::Exception => "exception",
+    }
+}
+
+/// The methods `method` names, by name alone or with the descriptor as
+/// well, e.g. `run()V`.
+pub(super) fn named_methods<'a>(
+    class_file: &'a ClassFile,
+    method: &str,
+) -> Result<Vec<&'a MethodInfo>, Box<dyn Error>> {
+    let constant_pool = &class_file.constant_pool;
+    let methods: Vec<_> = class_file
+        .methods
+        .iter()
+        .filter(|candidate| {
+            let name = ConstantPool::utf8(constant_pool, candidate.name_index()).unwrap_or("?");
+            let descriptor =
+                ConstantPool::utf8(constant_pool, candidate.descriptor_index()).unwrap_or("");
+            method == name || method.strip_prefix(name) == Some(descriptor)
+        })
+        .collect();
+    if methods.is_empty() {
+        return Err(Failure::usage(format!("no method {} in the class", method)).into());
+    }
+    Ok(methods)
+}
+
+/// Prints the layout of every method body in the class: each instruction's
+/// size and how far into the method it ends, then a summary by kind.
+pub fn print_layout(class_file: &ClassFile, json: bool) -> Result<(), Box<dyn Error>> {
+    let constant_pool = &class_file.constant_pool;
+    let mut methods = Vec::new();
+    for method in &class_file.methods {
+        let code = match method.code() {
+            Some(code) => code,
+            None => continue,
+        };
+        let name = format!(
+            "{}{}",
+            ConstantPool::utf8(constant_pool, method.name_index()).unwrap_or("?"),
+            ConstantPool::utf8(constant_pool, method.descriptor_index()).unwrap_or("")
+        );
+        let layout = code.layout()?;
+        if json {
+            methods.push(layout_json(name, &layout));
+            continue;
+        }
+
+        println!("{}: {}", name, summary_text(&layout.summary));
+        println!("  {:>6}  {:>5}  {:>6}  mnemonic", "offset", "size", "cumul");
+        for instruction in &layout.instructions {
+            println!(
+                "  {:>6}  {:>5}  {:>5.1}%  {}",
+                instruction.offset,
+                instruction.size,
+                instruction.cumulative_percent,
+                instruction.mnemonic
+            );
+        }
+    }
+    if json {
+        println!("{}", Json::Array(methods).pretty());
+    }
+    Ok(())
+}
+
+fn summary_text(summary: &LayoutSummary) -> String {
+    format!(
+        "{} bytes (invocations {}, branches {}, loads/stores {}, other {})",
+        summary.total, summary.invocations, summary.branches, summary.loads_stores, summary.other
+    )
+}
+
+fn layout_json(method: String, layout: &CodeLayout) -> Json {
+    let size = |bytes: usize| Json::Int(bytes as i64);
+    let summary = &layout.summary;
+    Json::object([
+        ("method", method.into()),
+        (
+            "summary",
+            Json::object([
+                ("total", size(summary.total)),
+                ("invocations", size(summary.invocations)),
+                ("branches", size(summary.branches)),
+                ("loads_stores", size(summary.loads_stores)),
+                ("other", size(summary.other)),
+            ]),
+        ),
+        (
+            "instructions",
+            Json::array(layout.instructions.iter().map(|instruction| {
+                Json::object([
+                    ("offset", instruction.offset.into()),
+                    ("mnemonic", instruction.mnemonic.into()),
+                    ("size", size(instruction.size)),
+                    ("cumulative_percent", instruction.cumulative_percent.into()),
+                ])
+            })),
+        ),
+    ])
+}
@@ -0,0 +1,125 @@
+use std::{
+    error::Error,
+    fs,
+    io::{self, Write},
+};
+
+use crate::{
+    compat::{CompatCheck, Location, Missing, SymbolList},
+    json::Json,
+};
+
+use super::{
+    exit::{ExitCode, Failure},
+    output::{Format, Outputs, Results},
+    scan::{parse_entry, Scan, ScanOptions},
+};
+
+/// The formats `compat` writes.
+pub const FORMATS: &[Format] = &[Format::Text, Format::Json];
+
+/// Reports every reference the classes among `inputs` make to a class or
+/// member missing from the symbol list in the file `against`, to
+/// `outputs`. Finding any is a violation.
+pub fn run(
+    against: &str,
+    inputs: &[&str],
+    outputs: &Outputs,
+    options: ScanOptions,
+) -> Result<ExitCode, Box<dyn Error>> {
+    let text =
+        fs::read_to_string(against).map_err(|e| Failure::usage(format!("{}: {}", against, e)))?;
+    let list =
+        SymbolList::parse(&text).map_err(|e| Failure::usage(format!("{}: {}", against, e)))?;
+
+    let mut check = CompatCheck::new(&list);
+    let mut scan = Scan::new(options);
+    scan.run(inputs, |name, buf, parse_options| {
+        check.add(name, &parse_entry(buf, parse_options)?);
+        Ok(())
+    })?;
+    scan.finish()?;
+
+    let missing = check.finish();
+    let found = !missing.is_empty();
+    outputs.write(&Compat {
+        against,
+        member_level: list.is_member_level(),
+        missing,
+    })?;
+    if found {
+        Ok(ExitCode::Violations)
+    } else {
+        Ok(ExitCode::Success)
+    }
+}
+
+struct Compat<'a> {
+    against: &'a str,
+    member_level: bool,
+    missing: Vec<Missing>,
+}
+
+impl Results for Compat<'_> {
+    fn write_text(&self, out: &mut dyn Write) -> io::Result<()> {
+        for missing in &self.missing {
+            writeln!(out, "missing {} {}", missing.kind.name(), missing.symbol)?;
+            for location in &missing.locations {
+                writeln!(out, "  {}", location_text(location))?;
+            }
+        }
+        writeln!(
+            out,
+            "{} missing symbols, checked at {} level against {}",
+            self.missing.len(),
+            if self.member_level { "member" } else { "class" },
+            self.against
+        )
+    }
+
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("against", self.against.into()),
+            (
+                "granularity",
+                if self.member_level { "member" } else { "class" }.into(),
+            ),
+            (
+                "missing",
+                Json::array(self.missing.iter().map(|missing| {
+                    Json::object([
+                        ("symbol", missing.symbol.as_str().into()),
+                        ("kind", missing.kind.name().into()),
+                        (
+                            "references",
+                            Json::array(missing.locations.iter().map(location_json)),
+                        ),
+                    ])
+                })),
+            ),
+        ])
+    }
+}
+
+/// `<class>`, or `<method>+<offset> line <n>` for a member reference.
+fn location_text(location: &Location) -> String {
+    match (&location.method, location.offset) {
+        (Some(method), Some(offset)) => {
+            let line = location
+                .line
+                .map_or_else(String::new, |line| format!(" line {}", line));
+            format!("{}+{}{}", method, offset, line)
+        }
+        _ => location.class.clone(),
+    }
+}
+
+fn location_json(location: &Location) -> Json {
+    Json::object([
+        ("entry", location.entry.as_str().into()),
+        ("class", location.class.as_str().into()),
+        ("method", location.method.as_deref().into()),
+        ("offset", location.offset.into()),
+        ("line", location.line.into()),
+    ])
+}
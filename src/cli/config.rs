@@ -0,0 +1,363 @@
+use std::{
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::toml::Toml;
+
+use super::{args::Args, exit::Failure};
+
+/// The name of the configuration file looked for in the working directory
+/// and each of its parents.
+pub const FILE_NAME: &str = "jvmb.toml";
+
+/// The keys allowed at the top level and in each table, for the messages
+/// about unknown ones.
+const TOP_LEVEL_KEYS: &[&str] = &[
+    "classpath",
+    "exclude",
+    "redact",
+    "redact-all-strings",
+    "scan",
+    "output",
+    "print",
+    "jit",
+    "severity",
+    "suppress",
+];
+const SCAN_KEYS: &[&str] = &["policy", "show-errors", "max-class-size"];
+const PRINT_KEYS: &[&str] = &[
+    "max-instructions-per-method",
+    "max-string-len",
+    "max-methods",
+];
+const JIT_KEYS: &[&str] = &["huge-method-limit", "inline-limit"];
+const POLICIES: &[&str] = &["keep-going", "fail-fast", "fail-on-error"];
+const SEVERITIES: &[&str] = &["error", "warning", "info"];
+
+/// The output formats each command can default to. `text` and `json` pick
+/// between the plain and `--json` output, the others are `--format` values.
+const OUTPUT_FORMATS: &[(&str, &[&str])] = &[
+    ("code", &["text", "json"]),
+    ("deps", &["jdeps"]),
+    ("disasm", &["asm-textify"]),
+    ("report", &["text", "json"]),
+    ("split-packages", &["text", "json"]),
+    ("symbols", &["text", "json"]),
+];
+
+/// Defaults for command line options, read from a `jvmb.toml`:
+///
+/// ```toml
+/// classpath = ["lib/a.jar", "lib/b.jar"]   # for deps and lint
+/// exclude = ["**/generated/**"]            # entries scans skip
+/// redact = ["(?i)password=.*"]
+/// redact-all-strings = false
+/// suppress = ["L0005", "V0206:com/thirdparty/**"]
+///
+/// [scan]
+/// policy = "fail-on-error"                 # or keep-going, fail-fast
+/// show-errors = 10
+/// max-class-size = 10_000_000
+///
+/// [output]
+/// split-packages = "json"
+///
+/// [print]                                  # limits for disasm, see --full
+/// max-instructions-per-method = 2000
+/// max-string-len = 200
+/// max-methods = 500
+///
+/// [jit]                                    # thresholds for lint --jit
+/// huge-method-limit = 8000
+/// inline-limit = 325
+///
+/// [severity]                               # for lint, metrics and verify
+/// V0203 = "info"
+/// M0001 = "error"
+/// ```
+///
+/// An option given on the command line replaces its default entirely.
+/// Relative classpath entries are relative to the file's directory.
+#[derive(Debug, Default)]
+pub struct Config {
+    classpath: Vec<String>,
+    exclude: Vec<String>,
+    redact: Vec<String>,
+    redact_all_strings: bool,
+    policy: Option<String>,
+    show_errors: Option<i64>,
+    max_class_size: Option<i64>,
+    /// command -> format
+    output: Vec<(String, String)>,
+    /// option -> limit
+    print: Vec<(&'static str, i64)>,
+    /// key -> threshold
+    jit: Vec<(&'static str, i64)>,
+    /// as `--severity` takes them, `<code>=<severity>`
+    severity: Vec<String>,
+    suppress: Vec<String>,
+}
+
+impl Config {
+    /// The configuration `args` ask for: none with `--no-config`, the file
+    /// named by `--config`, or else the nearest `jvmb.toml` from the working
+    /// directory up, if there is one.
+    pub fn load(args: &Args) -> Result<Option<Self>, Box<dyn Error>> {
+        if args.flag("no-config") {
+            return Ok(None);
+        }
+        let path = match args.values("config").pop() {
+            Some(path) => PathBuf::from(path),
+            None => match discover(&std::env::current_dir()?) {
+                Some(path) => path,
+                None => return Ok(None),
+            },
+        };
+        let text = fs::read_to_string(&path)
+            .map_err(|e| Failure::usage(format!("{}: {}", path.display(), e)))?;
+        let base = path.parent().unwrap_or(Path::new(""));
+        Config::parse(&text, base).map(Some).map_err(|message| {
+            Failure::usage(format!(
+                "{}: {} (use --no-config to ignore the file)",
+                path.display(),
+                message
+            ))
+            .into()
+        })
+    }
+
+    fn parse(text: &str, base: &Path) -> Result<Self, String> {
+        let document = Toml::parse(text).map_err(|e| e.to_string())?;
+        let mut config = Config::default();
+        for (key, value) in entries(&document, "", TOP_LEVEL_KEYS)? {
+            match key {
+                "classpath" => {
+                    config.classpath = strings(key, value)?
+                        .into_iter()
+                        .map(|entry| base.join(entry).display().to_string())
+                        .collect()
+                }
+                "exclude" => config.exclude = strings(key, value)?,
+                "redact" => config.redact = strings(key, value)?,
+                "redact-all-strings" => config.redact_all_strings = boolean(key, value)?,
+                "suppress" => config.suppress = strings(key, value)?,
+                "severity" => {
+                    let codes = match value {
+                        Toml::Table(entries) => entries,
+                        value => {
+                            return Err(format!(
+                                "severity: expected a table, found {}",
+                                described(value)
+                            ))
+                        }
+                    };
+                    for (code, value) in codes {
+                        let severity = one_of(&format!("severity.{}", code), value, SEVERITIES)?;
+                        config.severity.push(format!("{}={}", code, severity));
+                    }
+                }
+                "scan" => {
+                    for (key, value) in entries(value, "scan.", SCAN_KEYS)? {
+                        match key {
+                            "policy" => {
+                                config.policy = Some(one_of("scan.policy", value, POLICIES)?)
+                            }
+                            "show-errors" => {
+                                config.show_errors = Some(count("scan.show-errors", value)?)
+                            }
+                            _ => config.max_class_size = Some(count("scan.max-class-size", value)?),
+                        }
+                    }
+                }
+                "print" => {
+                    for (key, value) in entries(value, "print.", PRINT_KEYS)? {
+                        let option = PRINT_KEYS
+                            .iter()
+                            .find(|&&option| option == key)
+                            .expect("entries only returns known keys");
+                        config
+                            .print
+                            .push((option, count(&format!("print.{}", key), value)?));
+                    }
+                }
+                "jit" => {
+                    for (key, value) in entries(value, "jit.", JIT_KEYS)? {
+                        let key = JIT_KEYS
+                            .iter()
+                            .find(|&&known| known == key)
+                            .expect("entries only returns known keys");
+                        config
+                            .jit
+                            .push((key, count(&format!("jit.{}", key), value)?));
+                    }
+                }
+                _ => {
+                    let commands: Vec<&str> =
+                        OUTPUT_FORMATS.iter().map(|(command, _)| *command).collect();
+                    for (command, value) in entries(value, "output.", &commands)? {
+                        let (_, formats) = OUTPUT_FORMATS
+                            .iter()
+                            .find(|(name, _)| *name == command)
+                            .expect("entries only returns known keys");
+                        let format = one_of(&format!("output.{}", command), value, formats)?;
+                        config.output.push((command.to_string(), format));
+                    }
+                }
+            }
+        }
+        Ok(config)
+    }
+
+    /// Adds the configured defaults for `command` to `args`, leaving alone
+    /// every option already given on the command line.
+    pub fn apply(&self, command: &str, args: &mut Args) {
+        if matches!(command, "deps" | "lint") {
+            args.set_default("classpath", &self.classpath);
+        }
+        if matches!(command, "lint" | "metrics" | "verify") {
+            args.set_default("severity", &self.severity);
+            args.set_default("suppress", &self.suppress);
+        }
+        args.set_default("exclude", &self.exclude);
+        args.set_default("redact", &self.redact);
+        if self.redact_all_strings {
+            args.set_default_flag("redact-all-strings");
+        }
+        if let Some(policy) = &self.policy {
+            if !POLICIES.iter().any(|&name| args.flag(name)) {
+                args.set_default_flag(policy);
+            }
+        }
+        if let Some(show_errors) = self.show_errors {
+            args.set_default("show-errors", &[show_errors.to_string()]);
+        }
+        if let Some(max_class_size) = self.max_class_size {
+            args.set_default("max-class-size", &[max_class_size.to_string()]);
+        }
+        for (option, limit) in &self.print {
+            args.set_default(option, &[limit.to_string()]);
+        }
+        for (key, threshold) in &self.jit {
+            args.set_default(&format!("jit-{}", key), &[threshold.to_string()]);
+        }
+        for (name, format) in &self.output {
+            match format.as_str() {
+                _ if name != command => {}
+                "text" => {}
+                "json" => args.set_default_flag("json"),
+                format => args.set_default("format", &[format.to_string()]),
+            }
+        }
+    }
+}
+
+/// The nearest `jvmb.toml` in `dir` or one of its parents.
+fn discover(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .map(|dir| dir.join(FILE_NAME))
+        .find(|path| path.is_file())
+}
+
+/// The entries of a table, checked against the keys it may have.
+fn entries<'a>(
+    value: &'a Toml,
+    prefix: &str,
+    known: &[&str],
+) -> Result<Vec<(&'a str, &'a Toml)>, String> {
+    let entries = match value {
+        Toml::Table(entries) => entries,
+        value => {
+            return Err(format!(
+                "{}: expected a table, found {}",
+                prefix.trim_end_matches('.'),
+                described(value)
+            ))
+        }
+    };
+    entries
+        .iter()
+        .map(|(key, value)| {
+            if known.contains(&key.as_str()) {
+                Ok((key.as_str(), value))
+            } else {
+                Err(format!(
+                    "unknown key {}{}, expected one of: {}",
+                    prefix,
+                    key,
+                    known.join(", ")
+                ))
+            }
+        })
+        .collect()
+}
+
+fn strings(key: &str, value: &Toml) -> Result<Vec<String>, String> {
+    let expected = || format!("{}: expected an array of strings", key);
+    match value {
+        Toml::Array(values) => values
+            .iter()
+            .map(|value| match value {
+                Toml::String(value) => Ok(value.clone()),
+                value => Err(format!("{}, found {} in it", expected(), described(value))),
+            })
+            .collect(),
+        value => Err(format!("{}, found {}", expected(), described(value))),
+    }
+}
+
+fn boolean(key: &str, value: &Toml) -> Result<bool, String> {
+    match value {
+        Toml::Boolean(value) => Ok(*value),
+        value => Err(format!(
+            "{}: expected true or false, found {}",
+            key,
+            described(value)
+        )),
+    }
+}
+
+fn count(key: &str, value: &Toml) -> Result<i64, String> {
+    match value {
+        Toml::Integer(value) if *value >= 0 => Ok(*value),
+        Toml::Integer(value) => Err(format!(
+            "{}: expected a count, found the negative {}",
+            key, value
+        )),
+        value => Err(format!(
+            "{}: expected an integer, found {}",
+            key,
+            described(value)
+        )),
+    }
+}
+
+fn one_of(key: &str, value: &Toml, accepted: &[&str]) -> Result<String, String> {
+    match value {
+        Toml::String(value) if accepted.contains(&value.as_str()) => Ok(value.clone()),
+        Toml::String(value) => Err(format!(
+            "{}: unknown value {:?}, expected one of: {}",
+            key,
+            value,
+            accepted.join(", ")
+        )),
+        value => Err(format!(
+            "{}: expected one of: {}, found {}",
+            key,
+            accepted.join(", "),
+            described(value)
+        )),
+    }
+}
+
+/// The kind of `value` with its article, for error messages.
+fn described(value: &Toml) -> String {
+    let kind = value.type_name();
+    let article = if kind.starts_with(['a', 'i']) {
+        "an"
+    } else {
+        "a"
+    };
+    format!("{} {}", article, kind)
+}
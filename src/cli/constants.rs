@@ -0,0 +1,299 @@
+use std::collections::BTreeSet;
+
+use crate::{classfile::ClassFile, constantpool::ConstantPool, mutf8, redact::Redactor, spec};
+
+/// How `#N` references to pool entries are written.
+pub enum Links {
+    Plain,
+    /// HTML anchors, which Markdown renderers keep: `<a id="cp-12"></a>`
+    /// before every entry and `[#12](#cp-12)` for every reference.
+    Anchors,
+    /// OSC 8 terminal hyperlinks to `<uri>#cp-12`, where `uri` is the
+    /// `file://` URI of the class.
+    Hyperlinks(String),
+}
+
+impl Links {
+    fn anchor(&self, index: u16) -> String {
+        match self {
+            Links::Anchors => format!("<a id=\"cp-{}\"></a>", index),
+            _ => String::new(),
+        }
+    }
+
+    fn reference(&self, index: u16) -> String {
+        match self {
+            Links::Plain => format!("#{}", index),
+            Links::Anchors => format!("[#{}](#cp-{})", index, index),
+            Links::Hyperlinks(uri) => {
+                format!("\x1b]8;;{}#cp-{}\x1b\\#{}\x1b]8;;\x1b\\", uri, index, index)
+            }
+        }
+    }
+
+    /// `operands` as [`describe`] renders them for `constant`, with every
+    /// reference to a pool entry linked. The first operand of `Dynamic` and
+    /// `InvokeDynamic` indexes the bootstrap methods instead, and is left
+    /// as it is.
+    fn operands(&self, constant: &ConstantPool, operands: &str) -> String {
+        let links_references = !matches!(self, Links::Plain)
+            && !constant.referenced_indices().is_empty()
+            && !matches!(constant, ConstantPool::Unknown { .. });
+        if !links_references {
+            return operands.to_string();
+        }
+        let mut skip = usize::from(matches!(
+            constant,
+            ConstantPool::Dynamic(..) | ConstantPool::InvokeDynamic(..)
+        ));
+        let mut linked = String::new();
+        let mut rest = operands;
+        while let Some(start) = rest.find('#') {
+            linked.push_str(&rest[..start]);
+            let digits = rest[start + 1..]
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(rest.len() - start - 1);
+            let token = &rest[start..start + 1 + digits];
+            match token[1..].parse() {
+                Ok(index) if skip == 0 => linked.push_str(&self.reference(index)),
+                _ => {
+                    skip = skip.saturating_sub(1);
+                    linked.push_str(token);
+                }
+            }
+            rest = &rest[start + 1 + digits..];
+        }
+        linked.push_str(rest);
+        linked
+    }
+}
+
+/// Prints the pool, or with `only` the entries it lists, linking references
+/// as `links` says. Bootstrap methods the entries printed lead to are
+/// listed after them.
+pub fn print(
+    class_file: &ClassFile,
+    explain: bool,
+    redactor: &Redactor,
+    links: &Links,
+    only: Option<&BTreeSet<u16>>,
+) {
+    let constant_pool = &class_file.constant_pool;
+    let string_literals = Redactor::string_literal_indices(constant_pool);
+    let mut bootstrap_indices = BTreeSet::new();
+    for (i, constant) in constant_pool.iter().enumerate() {
+        let index = i as u16 + 1;
+        if only.is_some_and(|only| !only.contains(&index)) || *constant == ConstantPool::Unusable {
+            continue;
+        }
+        if let ConstantPool::Dynamic(bootstrap_index, _)
+        | ConstantPool::InvokeDynamic(bootstrap_index, _) = constant
+        {
+            bootstrap_indices.insert(*bootstrap_index);
+        }
+        let kind = spec::constant_spec(constant.tag()).map_or("?", |spec| spec.name);
+        let (operands, comment) = match constant {
+            ConstantPool::UTF8(value) if string_literals.binary_search(&index).is_ok() => {
+                (redactor.string(value).into_owned(), None)
+            }
+            ConstantPool::String(index) => (
+                format!("#{}", index),
+                ConstantPool::utf8(constant_pool, *index)
+                    .map(|value| redactor.string(value).into_owned()),
+            ),
+            constant => describe(constant, constant_pool),
+        };
+        // padded by the width of the plain line, links being invisible
+        let plain = format!("{:>6} = {:<18} {}", format!("#{}", index), kind, operands);
+        let line = format!(
+            "{}{:>6} = {:<18} {}",
+            links.anchor(index),
+            format!("#{}", index),
+            kind,
+            links.operands(constant, &operands)
+        );
+        match comment {
+            Some(comment) => {
+                let padding = 48usize.saturating_sub(plain.chars().count());
+                println!("{}{} // {}", line, " ".repeat(padding), comment)
+            }
+            None => println!("{}", line),
+        }
+    }
+
+    if only.is_some() {
+        let bootstrap_methods = class_file.bootstrap_methods();
+        for index in bootstrap_indices {
+            if let Some(bootstrap_method) = bootstrap_methods.get(index as usize) {
+                let arguments: Vec<String> = bootstrap_method
+                    .bootstrap_arguments
+                    .iter()
+                    .map(|&argument| links.reference(argument))
+                    .collect();
+                println!(
+                    "BootstrapMethods[{}]: {} ({})",
+                    index,
+                    links.reference(bootstrap_method.bootstrap_method_ref),
+                    arguments.join(", ")
+                );
+            }
+        }
+    }
+
+    if explain {
+        let mut tags: Vec<u8> = constant_pool
+            .iter()
+            .enumerate()
+            .filter(|(i, constant)| {
+                only.is_none_or(|only| only.contains(&(*i as u16 + 1)))
+                    && **constant != ConstantPool::Unusable
+            })
+            .map(|(_, constant)| constant.tag())
+            .collect();
+        tags.sort_unstable();
+        tags.dedup();
+        println!();
+        for spec in tags.into_iter().filter_map(spec::constant_spec) {
+            println!(
+                "{:<18} {} (JVMS §{})",
+                spec.name, spec.description, spec.section
+            );
+        }
+    }
+}
+
+/// Lists the entries nothing in the class refers to, with the bytes each
+/// one takes up.
+pub fn print_unused(class_file: &ClassFile) {
+    let unused = class_file.unused_constants();
+    for constant in &unused {
+        let kind = spec::constant_spec(constant.tag).map_or("?", |spec| spec.name);
+        println!(
+            "{:>6} = {:<18} {} bytes",
+            format!("#{}", constant.index),
+            kind,
+            constant.byte_cost
+        );
+    }
+    let total: usize = unused.iter().map(|constant| constant.byte_cost).sum();
+    println!("{} unused entries, {} bytes", unused.len(), total);
+}
+
+/// Lists every UTF8 entry with the roles it plays in the class.
+pub fn print_roles(class_file: &ClassFile) {
+    let constant_pool = &class_file.constant_pool;
+    for (index, roles) in ConstantPool::utf8_roles(class_file) {
+        let value = ConstantPool::utf8(constant_pool, index).unwrap_or_default();
+        let roles = if roles.is_empty() {
+            "unreferenced".to_string()
+        } else {
+            roles.names().join(", ")
+        };
+        println!("{:>6} = {:<40} // {}", format!("#{}", index), value, roles);
+    }
+}
+
+/// Renders an entry's operands and, for entries that point at other entries,
+/// a comment with the resolved value.
+pub fn describe(
+    constant: &ConstantPool,
+    constant_pool: &[ConstantPool],
+) -> (String, Option<String>) {
+    let resolved = |index| resolve(index, constant_pool);
+    match constant {
+        ConstantPool::UTF8(value) => (value.clone(), None),
+        ConstantPool::Utf16(units) => (
+            String::from_utf16_lossy(units),
+            Some("holds an unpaired surrogate, shown as U+FFFD".to_string()),
+        ),
+        ConstantPool::RawUtf8(bytes) => (
+            mutf8::decode_lossy(bytes),
+            Some("isn't valid modified UTF-8, shown with U+FFFD".to_string()),
+        ),
+        ConstantPool::Integer(value) => (value.to_string(), None),
+        ConstantPool::Float(bits) => (
+            format!("{}f (0x{:08X})", bits.value(), bits.0),
+            float_warning(bits.is_nan(), bits.0 == 0x7FC0_0000, bits.is_subnormal()),
+        ),
+        ConstantPool::Long(value) => (format!("{}l", value), None),
+        ConstantPool::Double(bits) => (
+            format!("{}d (0x{:016X})", bits.value(), bits.0),
+            float_warning(
+                bits.is_nan(),
+                bits.0 == 0x7FF8_0000_0000_0000,
+                bits.is_subnormal(),
+            ),
+        ),
+        ConstantPool::Class(index)
+        | ConstantPool::String(index)
+        | ConstantPool::MethodType(index)
+        | ConstantPool::Module(index)
+        | ConstantPool::Package(index) => (format!("#{}", index), Some(resolved(*index))),
+        ConstantPool::FieldRef(class_index, name_and_type_index)
+        | ConstantPool::MethodRef(class_index, name_and_type_index)
+        | ConstantPool::InterfaceMethodRef(class_index, name_and_type_index) => (
+            format!("#{}.#{}", class_index, name_and_type_index),
+            Some(format!(
+                "{}.{}",
+                resolved(*class_index),
+                resolved(*name_and_type_index)
+            )),
+        ),
+        ConstantPool::NameAndType(name_index, descriptor_index) => (
+            format!("#{}:#{}", name_index, descriptor_index),
+            Some(format!(
+                "{}:{}",
+                resolved(*name_index),
+                resolved(*descriptor_index)
+            )),
+        ),
+        ConstantPool::MethodHandle(reference_kind, reference_index) => (
+            format!("{}:#{}", reference_kind, reference_index),
+            Some(resolved(*reference_index)),
+        ),
+        ConstantPool::Dynamic(bootstrap_method_attr_index, name_and_type_index)
+        | ConstantPool::InvokeDynamic(bootstrap_method_attr_index, name_and_type_index) => (
+            format!("#{}:#{}", bootstrap_method_attr_index, name_and_type_index),
+            Some(format!(
+                "#{}:{}",
+                bootstrap_method_attr_index,
+                resolved(*name_and_type_index)
+            )),
+        ),
+        ConstantPool::Unknown { tag, body } => (
+            body.iter().map(|byte| format!("{:02x}", byte)).collect(),
+            Some(format!("unknown tag {}", tag)),
+        ),
+        ConstantPool::Unusable => (
+            String::new(),
+            Some("the second slot of the entry before".to_string()),
+        ),
+    }
+}
+
+/// javac only ever emits the canonical NaN, so other NaN patterns in the pool
+/// usually mean packed data or obfuscation.
+fn float_warning(is_nan: bool, is_canonical_nan: bool, is_subnormal: bool) -> Option<String> {
+    if is_canonical_nan {
+        Some("NaN".to_string())
+    } else if is_nan {
+        Some("warning: non-canonical NaN".to_string())
+    } else if is_subnormal {
+        Some("warning: subnormal".to_string())
+    } else {
+        None
+    }
+}
+
+fn resolve(index: u16, constant_pool: &[ConstantPool]) -> String {
+    match index
+        .checked_sub(1)
+        .and_then(|i| constant_pool.get(i as usize))
+    {
+        Some(ConstantPool::UTF8(value)) => value.clone(),
+        Some(constant) => describe(constant, constant_pool)
+            .1
+            .unwrap_or_else(|| format!("#{}", index)),
+        None => format!("<invalid #{}>", index),
+    }
+}
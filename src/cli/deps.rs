@@ -0,0 +1,69 @@
+use std::{collections::BTreeSet, error::Error};
+
+use crate::deps::{archive_name, locate, package_dependencies, SystemModules};
+
+use super::scan::{index_classes, parse_entry, Scan, ScanOptions};
+
+/// Prints the class dependencies of every input (class file, jar or
+/// directory) in the layout of `jdeps -verbose:class`, or with `summary`
+/// only the archive to archive lines as `jdeps -s` does. Classes are looked
+/// up among the `system` modules, then the inputs, then the `classpath`.
+/// Dependencies within a package are left out, as `jdeps` does by default.
+pub fn run(
+    inputs: &[&str],
+    classpath: &[&str],
+    system: Option<&SystemModules>,
+    summary: bool,
+    options: ScanOptions,
+) -> Result<(), Box<dyn Error>> {
+    let searched: Vec<&str> = inputs.iter().chain(classpath).copied().collect();
+    let index = index_classes(&searched, options.clone())?;
+
+    let mut scan = Scan::new(options);
+    for input in inputs {
+        let archive = archive_name(input);
+        let mut edges = BTreeSet::new();
+        scan.run(&[input], |_, buf, parse_options| {
+            let class_file = parse_entry(buf, parse_options)?;
+            if let Some((origin, dependencies)) = package_dependencies(&class_file) {
+                for target in dependencies {
+                    let found_in = locate(&target, system, &[&index]);
+                    edges.insert((origin.replace('/', "."), target.replace('/', "."), found_in));
+                }
+            }
+            Ok(())
+        })?;
+
+        let archives: BTreeSet<&str> = edges
+            .iter()
+            .map(|(_, _, found_in)| found_in.as_str())
+            .filter(|&found_in| found_in != archive)
+            .collect();
+        for found_in in archives {
+            println!("{} -> {}", archive, found_in);
+        }
+        if !summary {
+            for (origin, target, found_in) in &edges {
+                println!("   {:<50} -> {:<50} {}", origin, target, found_in);
+            }
+        }
+    }
+    scan.finish()
+}
+
+/// Prints, sorted, the internal name of every class the classes of the
+/// inputs (class files, jars or directories) reference, themselves
+/// included.
+pub fn list(inputs: &[&str], options: ScanOptions) -> Result<(), Box<dyn Error>> {
+    let mut classes = BTreeSet::new();
+    let mut scan = Scan::new(options);
+    scan.run(inputs, |_, buf, parse_options| {
+        classes.append(&mut parse_entry(buf, parse_options)?.referenced_classes());
+        Ok(())
+    })?;
+    scan.finish()?;
+    for class in classes {
+        println!("{}", class);
+    }
+    Ok(())
+}
@@ -0,0 +1,165 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    classfile::ClassFile,
+    classindex::ClassIndex,
+    constantpool::ConstantPool,
+    frames, instruction,
+    mapping::NameMapper,
+    methodinfo::MethodInfo,
+    switchmap,
+    textify::{self, PrintOptions},
+};
+
+/// Prints the class the way ASM's Textifier does, within the limits of
+/// `options`. Instructions are commented with the operand stack after them
+/// with `show_stack`, the starts of `synchronized` blocks and
+/// try-with-resources bodies with what they are with `show_regions`, switches over enums with the constant behind each case
+/// when the switch maps are among the classes of `enum_switches`, and the
+/// classes and members instructions refer to with their original names
+/// when the `mapping` renames them.
+pub fn run(
+    class_file: &ClassFile,
+    options: &PrintOptions,
+    show_stack: bool,
+    show_regions: bool,
+    enum_switches: Option<&ClassIndex>,
+    mapping: Option<&NameMapper>,
+) {
+    if !show_stack && !show_regions && enum_switches.is_none() && mapping.is_none() {
+        print!("{}", textify::textify_with(class_file, options));
+        return;
+    }
+    let comments = |method: &MethodInfo| {
+        let mut comments = BTreeMap::new();
+        if show_stack {
+            let stacks = frames::infer_stacks(class_file, method).unwrap_or_default();
+            for (offset, stack) in stacks {
+                add_comment(&mut comments, offset, stack.to_string());
+            }
+        }
+        if let (true, Some(code)) = (show_regions, method.code()) {
+            let frames = frames::infer_frames(class_file, method).unwrap_or_default();
+            let regions = code
+                .regions(&class_file.constant_pool, &frames)
+                .unwrap_or_default();
+            for region in regions {
+                add_comment(&mut comments, region.start, region.to_string());
+            }
+        }
+        if let Some(mapping) = mapping {
+            for (offset, name) in original_names(class_file, method, mapping) {
+                add_comment(&mut comments, offset, name);
+            }
+        }
+        let switches = enum_switches
+            .into_iter()
+            .flat_map(|index| switchmap::enum_switches(class_file, method, index));
+        for switch in switches {
+            let cases = switch
+                .cases
+                .iter()
+                .map(|(key, constant)| format!("{} {}", key, constant))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let text = format!("{}: {}", switch.enum_class.replace('/', "."), cases);
+            add_comment(&mut comments, switch.offset, text);
+        }
+        comments
+    };
+    print!(
+        "{}",
+        textify::textify_commented(class_file, options, &comments)
+    );
+}
+
+/// Adds `text` to the comment of the instruction at `offset`.
+fn add_comment(comments: &mut BTreeMap<u32, String>, offset: u32, text: String) {
+    comments
+        .entry(offset)
+        .and_modify(|comment| *comment = format!("{}; {}", comment, text))
+        .or_insert(text);
+}
+
+/// The original names of the classes and members the instructions of
+/// `method` refer to, by offset, where the mapping renames any of them.
+fn original_names(
+    class_file: &ClassFile,
+    method: &MethodInfo,
+    mapping: &NameMapper,
+) -> BTreeMap<u32, String> {
+    let constant_pool = &class_file.constant_pool;
+    let code = match method.code() {
+        Some(code) => code,
+        None => return BTreeMap::new(),
+    };
+    let mut names = BTreeMap::new();
+    for instruction in instruction::decode(&code.code).map_while(Result::ok) {
+        let index = match instruction.cp_index() {
+            Some(index) => index,
+            None => continue,
+        };
+        let printed = reference(constant_pool, instruction.opcode, index, None);
+        match reference(constant_pool, instruction.opcode, index, Some(mapping)) {
+            Some(original) if Some(&original) != printed.as_ref() => {
+                names.insert(instruction.offset, original);
+            }
+            _ => {}
+        }
+    }
+    names
+}
+
+/// The class or member the instruction `opcode` refers to at `index`, as
+/// the textifier prints it, with the names the `mapping` gives.
+fn reference(
+    constant_pool: &[ConstantPool],
+    opcode: u8,
+    index: u16,
+    mapping: Option<&NameMapper>,
+) -> Option<String> {
+    let class_name = |name: &str| match mapping {
+        Some(mapping) if name.starts_with('[') => mapping.descriptor(name),
+        Some(mapping) => mapping.class_name(name).to_string(),
+        None => name.to_string(),
+    };
+    let descriptor = |descriptor: &str| match mapping {
+        Some(mapping) => mapping.descriptor(descriptor),
+        None => descriptor.to_string(),
+    };
+    match opcode {
+        // getstatic, putstatic, getfield, putfield
+        0xb2..=0xb5 => ConstantPool::member_ref(constant_pool, index).map(|field| {
+            let name = mapping
+                .and_then(|mapping| {
+                    mapping.field_name(field.class_name, field.name, field.descriptor)
+                })
+                .unwrap_or(field.name);
+            format!(
+                "{}.{} : {}",
+                class_name(field.class_name),
+                name,
+                descriptor(field.descriptor)
+            )
+        }),
+        // invokevirtual, invokespecial, invokestatic, invokeinterface
+        0xb6..=0xb9 => ConstantPool::member_ref(constant_pool, index).map(|method| {
+            let name = mapping
+                .and_then(|mapping| {
+                    mapping.method_name(method.class_name, method.name, method.descriptor)
+                })
+                .unwrap_or(method.name);
+            format!(
+                "{}.{} {}",
+                class_name(method.class_name),
+                name,
+                descriptor(method.descriptor)
+            )
+        }),
+        // new, anewarray, checkcast, instanceof, multianewarray
+        0xbb | 0xbd | 0xc0 | 0xc1 | 0xc5 => {
+            ConstantPool::class_name(constant_pool, index).map(class_name)
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,65 @@
+use std::{error::Error, fmt, process};
+
+/// The exit status of every command. Scripts rely on these values, so they
+/// never change meaning; new outcomes get new numbers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitCode {
+    Success = 0,
+    /// The command line was malformed or named something unknown.
+    Usage = 1,
+    /// An input couldn't be read or parsed.
+    Parse = 2,
+    /// `--check` found verification violations or lint findings, or
+    /// `--validate` bad constant pool indices.
+    Violations = 3,
+    /// `--check` found differences between two classes.
+    Differences = 4,
+    /// Anything else, such as a failure to write the output.
+    Failure = 5,
+}
+
+impl ExitCode {
+    /// The code to exit with when a command fails with `error`.
+    pub fn of(error: &(dyn Error + 'static)) -> Self {
+        error
+            .downcast_ref::<Failure>()
+            .map_or(ExitCode::Failure, |failure| failure.code)
+    }
+}
+
+impl From<ExitCode> for process::ExitCode {
+    fn from(code: ExitCode) -> Self {
+        process::ExitCode::from(code as u8)
+    }
+}
+
+/// An error which ends the command with a particular exit code.
+#[derive(Debug)]
+pub struct Failure {
+    pub code: ExitCode,
+    pub message: String,
+}
+
+impl Failure {
+    pub fn usage(message: impl Into<String>) -> Self {
+        Failure {
+            code: ExitCode::Usage,
+            message: message.into(),
+        }
+    }
+
+    pub fn parse(message: impl Into<String>) -> Self {
+        Failure {
+            code: ExitCode::Parse,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl Error for Failure {}
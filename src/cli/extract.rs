@@ -0,0 +1,222 @@
+use std::{
+    error::Error,
+    fs,
+    io::{self, Write},
+};
+
+use crate::{
+    attribute::Attribute,
+    classfile::{ClassFile, ParseOptions},
+    fieldinfo::FieldInfo,
+    methodinfo::MethodInfo,
+};
+
+use super::{code, exit::Failure, scan};
+
+/// What to extract from the class.
+pub enum What<'a> {
+    /// The bytecode of a method.
+    Code,
+    /// The body of the first attribute with this name.
+    Attribute(&'a str),
+}
+
+impl<'a> What<'a> {
+    /// Parses the value of `--what`: `code` or `attribute:<name>`.
+    pub fn parse(what: &'a str) -> Result<Self, Failure> {
+        match what {
+            "code" => Ok(What::Code),
+            _ => match what.strip_prefix("attribute:") {
+                Some(name) if !name.is_empty() => Ok(What::Attribute(name)),
+                _ => Err(Failure::usage(format!(
+                    "expected --what code or attribute:<name>: {}",
+                    what
+                ))),
+            },
+        }
+    }
+}
+
+/// Where the bytes go.
+pub enum Output<'a> {
+    File(&'a str),
+    /// Printed as a hex dump, each line with its offset.
+    Hex,
+    Stdout,
+}
+
+/// Extracts the bytes `what` names from the class in `buf`, read from
+/// `file_name`: the bytecode of `method`, or the body of an attribute of
+/// `method`, of `field` or, with neither, of the class, exactly as stored.
+/// A method's attributes are searched before those of its `Code`.
+pub fn run(
+    buf: &[u8],
+    file_name: &str,
+    method: Option<&str>,
+    field: Option<&str>,
+    what: What,
+    output: Output,
+) -> Result<(), Box<dyn Error>> {
+    scan::warn_unsupported_version(file_name, buf);
+    // an attribute is left unread, its place in `buf` kept, and sliced out
+    // of it as it is
+    let mut options = ParseOptions::new();
+    if let What::Attribute(name) = what {
+        options.defer_attribute(name);
+    }
+    let (_, class_file) = ClassFile::parse_with(buf, &options).map_err(|e| {
+        Failure::parse(format!(
+            "{}: {}",
+            file_name,
+            scan::parse_error(buf, e).message
+        ))
+    })?;
+    let constant_pool = &class_file.constant_pool;
+
+    let bytes = match (what, method, field) {
+        (What::Code, Some(method), None) => {
+            let method = find_method(&class_file, method)?;
+            let code = method.code().ok_or_else(|| {
+                Failure::usage(format!("{} has no code", method_name(&class_file, method)))
+            })?;
+            &code.code[..]
+        }
+        (What::Code, _, _) => return Err(Failure::usage("--what code needs --method").into()),
+        (What::Attribute(_), Some(_), Some(_)) => {
+            return Err(Failure::usage("give either --method or --field").into())
+        }
+        (What::Attribute(name), Some(method), None) => {
+            let method = find_method(&class_file, method)?;
+            let nested = method.code().into_iter().flat_map(|code| &code.attributes);
+            body(buf, method.attributes().iter().chain(nested), name)
+                .ok_or_else(|| no_attribute(name, method_name(&class_file, method)))?
+        }
+        (What::Attribute(name), None, Some(field)) => {
+            let field = find_field(&class_file, field)?;
+            body(buf, field.attributes().iter(), name).ok_or_else(|| {
+                let field_name = field.name(constant_pool).unwrap_or("?");
+                no_attribute(name, format!("field {}", field_name))
+            })?
+        }
+        (What::Attribute(name), None, None) => body(buf, class_file.attributes.iter(), name)
+            .ok_or_else(|| no_attribute(name, "the class".to_string()))?,
+    };
+
+    match output {
+        Output::File(path) => fs::write(path, bytes)?,
+        Output::Hex => print!("{}", hex_dump(bytes)),
+        Output::Stdout => io::stdout().lock().write_all(bytes)?,
+    }
+    Ok(())
+}
+
+/// The method `method` names, by name alone or with the descriptor as
+/// well, e.g. `run(I)V`. A name alone must be that of one method.
+fn find_method<'a>(class_file: &'a ClassFile, method: &str) -> Result<&'a MethodInfo, Failure> {
+    let methods =
+        code::named_methods(class_file, method).map_err(|e| Failure::usage(e.to_string()))?;
+    match methods[..] {
+        [found] => Ok(found),
+        _ => {
+            let candidates: Vec<String> = methods
+                .iter()
+                .map(|method| method_name(class_file, method))
+                .collect();
+            Err(Failure::usage(format!(
+                "method {} is overloaded, give one of: {}",
+                method,
+                candidates.join(", ")
+            )))
+        }
+    }
+}
+
+/// The field `field` names, by name alone or as `<name>:<descriptor>`.
+fn find_field<'a>(class_file: &'a ClassFile, field: &str) -> Result<&'a FieldInfo, Failure> {
+    let constant_pool = &class_file.constant_pool;
+    let (name, descriptor) = match field.split_once(':') {
+        Some((name, descriptor)) => (name, Some(descriptor)),
+        None => (field, None),
+    };
+    let fields: Vec<&FieldInfo> = class_file
+        .fields
+        .iter()
+        .filter(|candidate| {
+            candidate.name(constant_pool) == Some(name)
+                && descriptor.is_none_or(|descriptor| {
+                    candidate.descriptor(constant_pool) == Some(descriptor)
+                })
+        })
+        .collect();
+    match fields[..] {
+        [] => Err(Failure::usage(format!("no field {} in the class", field))),
+        [found] => Ok(found),
+        _ => {
+            let candidates: Vec<String> = fields
+                .iter()
+                .map(|field| {
+                    let descriptor = field.descriptor(constant_pool).unwrap_or("");
+                    format!("{}:{}", name, descriptor)
+                })
+                .collect();
+            Err(Failure::usage(format!(
+                "field {} is ambiguous, give one of: {}",
+                field,
+                candidates.join(", ")
+            )))
+        }
+    }
+}
+
+fn method_name(class_file: &ClassFile, method: &MethodInfo) -> String {
+    let constant_pool = &class_file.constant_pool;
+    format!(
+        "{}{}",
+        method.name(constant_pool).unwrap_or("?"),
+        method.descriptor(constant_pool).unwrap_or("")
+    )
+}
+
+/// The body of the first of `attributes` named `name`, which the parse
+/// deferred, as stored in `buf`.
+fn body<'a, 'b>(
+    buf: &'a [u8],
+    mut attributes: impl Iterator<Item = &'b Attribute>,
+    name: &str,
+) -> Option<&'a [u8]> {
+    attributes.find_map(|attribute| match attribute {
+        Attribute::Deferred(deferred) if deferred.name == name => {
+            buf.get(deferred.offset..deferred.offset + deferred.len)
+        }
+        _ => None,
+    })
+}
+
+fn no_attribute(name: &str, owner: String) -> Failure {
+    Failure::usage(format!("no {} attribute in {}", name, owner))
+}
+
+/// `bytes` as `hexdump -C` prints them: sixteen to a line after the offset
+/// of the first, with those which are printable ASCII alongside.
+fn hex_dump(bytes: &[u8]) -> String {
+    let mut out = String::new();
+    for (line, chunk) in bytes.chunks(16).enumerate() {
+        let mut hex = String::new();
+        for (i, byte) in chunk.iter().enumerate() {
+            if i == 8 {
+                hex.push(' ');
+            }
+            hex += &format!(" {:02x}", byte);
+        }
+        let text: String = chunk
+            .iter()
+            .map(|&byte| match byte {
+                0x20..=0x7e => byte as char,
+                _ => '.',
+            })
+            .collect();
+        out += &format!("{:08x} {:<49}  |{}|\n", line * 16, hex, text);
+    }
+    out += &format!("{:08x}\n", bytes.len());
+    out
+}
@@ -0,0 +1,40 @@
+use std::error::Error;
+
+use crate::classfile::{ClassFile, ParseOptions};
+
+use super::scan::{parse_entry, EntryError, Scan, ScanOptions};
+
+/// Prints every class among `inputs` (class files, jars or directories)
+/// whose constant pool mentions `symbol`. With `verify_usage`, candidates
+/// are fully parsed and kept only when the symbol is used as a name or
+/// descriptor.
+pub fn run(
+    symbol: &str,
+    inputs: &[&str],
+    verify_usage: bool,
+    options: ScanOptions,
+) -> Result<(), Box<dyn Error>> {
+    let mut scan = Scan::new(options);
+    scan.run(inputs, |name, buf, parse_options| {
+        if check(buf, parse_options, symbol, verify_usage)? {
+            println!("{}", name);
+        }
+        Ok(())
+    })?;
+    scan.finish()
+}
+
+fn check(
+    buf: &[u8],
+    parse_options: &ParseOptions,
+    symbol: &str,
+    verify_usage: bool,
+) -> Result<bool, EntryError> {
+    if !ClassFile::quick_contains_utf8(buf, symbol) {
+        return Ok(false);
+    }
+    if verify_usage {
+        return Ok(parse_entry(buf, parse_options)?.references_symbol(symbol));
+    }
+    Ok(true)
+}
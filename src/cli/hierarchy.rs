@@ -0,0 +1,84 @@
+use std::{error::Error, path::Path};
+
+use crate::{
+    classindex::{ClassIndex, ClassSummary},
+    jar::JarFile,
+};
+
+use super::{
+    exit::{ExitCode, Failure},
+    parse_class_file,
+};
+
+/// Prints the superclasses and interfaces of `class_name`, as far as they're
+/// among `inputs` (classes, jars or directories of classes), and the indexed
+/// classes which directly extend or implement it.
+pub fn run(class_name: &str, inputs: &[&str]) -> Result<ExitCode, Box<dyn Error>> {
+    let index = build_index(inputs)?;
+    let class = index
+        .get(class_name)
+        .map_err(|e| Failure::usage(e.to_string()))?;
+
+    println!("{}", class.name());
+    let mut depth = 1;
+    let mut super_class = class.super_class();
+    while let Some(name) = super_class {
+        match index.get(name) {
+            Ok(summary) => {
+                println!("{:indent$}extends {}", "", name, indent = depth * 2);
+                super_class = summary.super_class();
+            }
+            Err(_) => {
+                println!(
+                    "{:indent$}extends {} (not indexed)",
+                    "",
+                    name,
+                    indent = depth * 2
+                );
+                super_class = None;
+            }
+        }
+        depth += 1;
+    }
+    for interface in class.interfaces() {
+        println!("  implements {}", interface);
+    }
+    let subtypes: Vec<ClassSummary> = index
+        .classes()
+        .filter(|summary| {
+            summary.super_class() == Some(class.name())
+                || summary
+                    .interfaces()
+                    .any(|interface| interface == class.name())
+        })
+        .collect();
+    for subtype in subtypes {
+        println!("  subtype {}", subtype.name());
+    }
+    Ok(ExitCode::Success)
+}
+
+/// Indexes every class among `inputs`. Classes which can't be parsed are
+/// reported and left out.
+pub fn build_index(inputs: &[&str]) -> Result<ClassIndex, Box<dyn Error>> {
+    let mut index = ClassIndex::new();
+    for input in inputs {
+        let read_error = |e: &dyn Error| Failure::parse(format!("{}: {}", input, e));
+        let path = Path::new(input);
+        let errors = if path.is_dir() {
+            index.add_dir(path).map_err(|e| read_error(&e))?
+        } else if input.ends_with(".jar") {
+            let jar = JarFile::open(path).map_err(|e| read_error(&e))?;
+            index.add_jar(input, &jar)
+        } else {
+            let buf = std::fs::read(path).map_err(|e| read_error(&e))?;
+            let class_file = parse_class_file(&buf, input)?;
+            index.add_class(&class_file, input);
+            Vec::new()
+        };
+        for error in errors {
+            eprintln!("{}", error);
+        }
+    }
+    Ok(index)
+}
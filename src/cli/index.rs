@@ -0,0 +1,95 @@
+use std::{
+    error::Error,
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+#[cfg(feature = "sqlite")]
+use crate::sqlexport::SqliteExporter;
+use crate::{classfile::ClassFile, sqlexport::SqlExporter};
+
+use super::scan::{parse_entry, Scan, ScanOptions};
+
+/// What `run` needs of an exporter, the script or the database.
+trait Exporter {
+    fn begin_source(&mut self, source: &str) -> io::Result<()>;
+    fn add_class(&mut self, class_file: &ClassFile) -> io::Result<()>;
+    fn finish(self: Box<Self>) -> io::Result<()>;
+}
+
+impl<W: Write> Exporter for SqlExporter<W> {
+    fn begin_source(&mut self, source: &str) -> io::Result<()> {
+        SqlExporter::begin_source(self, source)
+    }
+
+    fn add_class(&mut self, class_file: &ClassFile) -> io::Result<()> {
+        SqlExporter::add_class(self, class_file)
+    }
+
+    fn finish(self: Box<Self>) -> io::Result<()> {
+        SqlExporter::finish(*self)?.flush()
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl Exporter for SqliteExporter {
+    fn begin_source(&mut self, source: &str) -> io::Result<()> {
+        SqliteExporter::begin_source(self, source)
+    }
+
+    fn add_class(&mut self, class_file: &ClassFile) -> io::Result<()> {
+        SqliteExporter::add_class(self, class_file)
+    }
+
+    fn finish(self: Box<Self>) -> io::Result<()> {
+        SqliteExporter::finish(*self).map(drop)
+    }
+}
+
+/// Writes the classes among `inputs` to `output`, each input as a source of
+/// its own: into an SQLite database for an `output` of `.db`, `.sqlite` or
+/// `.sqlite3`, else as an SQL script.
+pub fn run(inputs: &[&str], output: &Path, options: ScanOptions) -> Result<(), Box<dyn Error>> {
+    let database = output.extension().is_some_and(|extension| {
+        ["db", "sqlite", "sqlite3"].contains(&&*extension.to_string_lossy())
+    });
+    let mut exporter: Box<dyn Exporter> = if database {
+        open_database(output)?
+    } else {
+        Box::new(SqlExporter::new(BufWriter::new(File::create(output)?))?)
+    };
+    // a failure to write stops the export, unlike one to parse an entry
+    let mut write_error: Option<io::Error> = None;
+    let mut scan = Scan::new(options);
+    for input in inputs {
+        exporter.begin_source(input)?;
+        scan.run(&[input], |_, buf, parse_options| {
+            let class_file = parse_entry(buf, parse_options)?;
+            if write_error.is_none() {
+                write_error = exporter.add_class(&class_file).err();
+            }
+            Ok(())
+        })?;
+        if let Some(e) = write_error {
+            return Err(e.into());
+        }
+    }
+    exporter.finish()?;
+    scan.finish()
+}
+
+#[cfg(feature = "sqlite")]
+fn open_database(output: &Path) -> Result<Box<dyn Exporter>, Box<dyn Error>> {
+    Ok(Box::new(SqliteExporter::open(output)?))
+}
+
+#[cfg(not(feature = "sqlite"))]
+fn open_database(output: &Path) -> Result<Box<dyn Exporter>, Box<dyn Error>> {
+    Err(super::exit::Failure::usage(format!(
+        "{}: writing a database needs jvmb built with the sqlite feature; \
+         write an SQL script, e.g. to classes.sql, and load it with sqlite3",
+        output.display()
+    ))
+    .into())
+}
@@ -0,0 +1,56 @@
+use std::{error::Error, fs};
+
+use crate::{json::Json, linemap::LineMap};
+
+use super::scan::{parse_entry, Scan, ScanOptions};
+
+/// Writes the lines with bytecode of every source file the classes among
+/// `inputs` were compiled from as JSON, to `output` or else to stdout.
+pub fn run(
+    inputs: &[&str],
+    output: Option<&str>,
+    options: ScanOptions,
+) -> Result<(), Box<dyn Error>> {
+    let mut line_map = LineMap::new();
+    let mut scan = Scan::new(options);
+    scan.run(inputs, |_, buf, parse_options| {
+        line_map.add_class(&parse_entry(buf, parse_options)?);
+        Ok(())
+    })?;
+    scan.finish()?;
+
+    let files = line_map.files.iter().map(|(path, source)| {
+        let classes = source.classes.iter().map(|(name, class)| {
+            let methods = class.methods.iter().map(|(method, method_lines)| {
+                (method.as_str(), Json::array(method_lines.iter().copied()))
+            });
+            (
+                name.as_str(),
+                Json::object([
+                    ("lines", Json::array(class.lines())),
+                    ("methods", Json::object(methods)),
+                ]),
+            )
+        });
+        (
+            path.as_str(),
+            Json::object([
+                ("lines", Json::array(source.lines())),
+                ("classes", Json::object(classes)),
+            ]),
+        )
+    });
+    let document = Json::object([
+        ("files", Json::object(files)),
+        (
+            "without_debug_info",
+            Json::array(line_map.without_debug_info.iter().map(String::as_str)),
+        ),
+    ]);
+
+    match output {
+        Some(path) => fs::write(path, document.pretty() + "\n")?,
+        None => println!("{}", document.pretty()),
+    }
+    Ok(())
+}
@@ -0,0 +1,240 @@
+use std::{collections::BTreeMap, error::Error};
+
+use crate::{
+    casts,
+    constantpool::ConstantPool,
+    debuginfo,
+    jit::{self, JitThreshold, JitThresholds},
+    methodinfo::MethodInfo,
+    policy::Policy,
+    rdjson::{Diagnostic, Severity, SourceLocator},
+    regex::Regex,
+    regions, sourcepaths,
+};
+
+use super::{
+    method_label,
+    output::{severity_prefix, FindingFormat},
+    scan::{index_classes, parse_entry, EntryError, Scan, ScanOptions},
+};
+
+/// The checks `jvmb lint` makes beyond the one for exception handlers.
+#[derive(Debug, Clone, Default)]
+pub struct Checks {
+    /// Casts the inferred types already guarantee, as far as the hierarchy
+    /// among the inputs and the classpath tells.
+    pub redundant_casts: bool,
+    /// Debug attributes at odds with the code they describe.
+    pub debug_info: bool,
+    /// Monitors not entered and exited in pairs.
+    pub monitors: bool,
+    /// `SourceFile` values which are paths or don't match the pattern, and
+    /// `SourceDebugExtension`s with CRLF line endings or which aren't UTF-8.
+    pub source_paths: Option<Regex>,
+    /// Methods over these sizes, with a count of them by package to end.
+    pub jit: Option<JitThresholds>,
+}
+
+/// Prints every catch-all exception handler among `inputs` (class files,
+/// jars or directories) which appears to swallow what it catches, and what
+/// the other `checks` find, unless `quiet`, in `format`. The `classpath`
+/// is only read for redundant casts. The `policy` sets the severity of
+/// each finding, or suppresses it. Returns whether anything was found
+/// which is more than information.
+#[allow(clippy::too_many_arguments)]
+pub fn run(
+    inputs: &[&str],
+    classpath: &[&str],
+    quiet: bool,
+    checks: Checks,
+    friendly_names: bool,
+    format: FindingFormat,
+    policy: &mut Policy,
+    options: ScanOptions,
+) -> Result<bool, Box<dyn Error>> {
+    let index = if checks.redundant_casts {
+        let searched: Vec<&str> = inputs.iter().chain(classpath).copied().collect();
+        Some(index_classes(&searched, options.clone())?)
+    } else {
+        None
+    };
+    let mut found = false;
+    // package -> count of each threshold crossed
+    let mut jit_counts: BTreeMap<String, BTreeMap<JitThreshold, usize>> = BTreeMap::new();
+    let mut scan = Scan::new(options);
+    scan.run(inputs, |name, buf, parse_options| {
+        let class_file = parse_entry(buf, parse_options)?;
+        let constant_pool = &class_file.constant_pool;
+        let mut findings = Vec::new();
+        for method in &class_file.methods {
+            let code = match method.code() {
+                Some(code) => code,
+                None => continue,
+            };
+            for handler in code.suspicious_handlers(constant_pool) {
+                findings.push(Finding {
+                    method: Some(method),
+                    code: "L0001",
+                    severity: Severity::Error,
+                    message: format!(
+                        "handler {} (pc {}): {}",
+                        handler.index,
+                        code.exception_table[handler.index].handler_pc,
+                        handler.reason
+                    ),
+                });
+            }
+        }
+        if checks.debug_info {
+            for mismatch in debuginfo::check_debug_info(&class_file) {
+                findings.push(Finding {
+                    method: class_file
+                        .find_method(&mismatch.method_name, &mismatch.method_descriptor),
+                    code: "L0002",
+                    severity: Severity::Warning,
+                    message: mismatch.kind.to_string(),
+                });
+            }
+        }
+        if checks.monitors {
+            for method in &class_file.methods {
+                let code = match method.code() {
+                    Some(code) => code,
+                    None => continue,
+                };
+                let issues = regions::check_monitors(code, constant_pool)
+                    .map_err(|e| EntryError::new("decode", e.to_string()))?;
+                for issue in issues {
+                    findings.push(Finding {
+                        method: Some(method),
+                        code: "L0003",
+                        severity: Severity::Warning,
+                        message: issue.to_string(),
+                    });
+                }
+            }
+        }
+        if let Some(pattern) = &checks.source_paths {
+            for issue in sourcepaths::check_source_info(&class_file, pattern) {
+                findings.push(Finding {
+                    method: None,
+                    code: "L0004",
+                    severity: Severity::Warning,
+                    message: issue.to_string(),
+                });
+            }
+        }
+        if let Some(thresholds) = &checks.jit {
+            let package =
+                ConstantPool::class_name(&class_file.constant_pool, class_file.this_class)
+                    .map(|class| class.rsplit_once('/').map_or("", |(package, _)| package))
+                    .unwrap_or("");
+            for finding in jit::check_jit(&class_file, thresholds) {
+                *jit_counts
+                    .entry(package.to_string())
+                    .or_default()
+                    .entry(finding.threshold)
+                    .or_default() += 1;
+                findings.push(Finding {
+                    method: class_file
+                        .find_method(&finding.method_name, &finding.method_descriptor),
+                    code: "L0005",
+                    severity: Severity::Warning,
+                    message: finding.to_string(),
+                });
+            }
+        }
+        if let Some(index) = &index {
+            let casts = casts::redundant_casts(&class_file, index)
+                .map_err(|e| EntryError::new("decode", e.to_string()))?;
+            for cast in casts {
+                findings.push(Finding {
+                    method: class_file.find_method(&cast.method_name, &cast.method_descriptor),
+                    code: "L0006",
+                    severity: Severity::Error,
+                    message: format!(
+                        "{} {} at {} is redundant: the value is {}",
+                        cast.mnemonic, cast.target, cast.offset, cast.inferred
+                    ),
+                });
+            }
+        }
+        let class_name =
+            ConstantPool::class_name(constant_pool, class_file.this_class).unwrap_or("");
+        let findings: Vec<(Finding, Severity)> = findings
+            .into_iter()
+            .filter_map(|finding| {
+                let severity = policy.resolve(finding.code, class_name, finding.severity)?;
+                Some((finding, severity))
+            })
+            .collect();
+        found |= findings
+            .iter()
+            .any(|(_, severity)| *severity != Severity::Info);
+        if quiet {
+            return Ok(());
+        }
+        let locator = SourceLocator::new(&class_file, name);
+        for (finding, severity) in findings {
+            match format {
+                FindingFormat::Text => {
+                    let mut line = format!("{}: ", name);
+                    if let Some(method) = finding.method {
+                        line.push_str(&method_label(&class_file, method, friendly_names));
+                        line.push_str(": ");
+                    }
+                    line.push_str(severity_prefix(severity));
+                    line.push_str(&finding.message);
+                    println!("{} [{}]", line, finding.code);
+                }
+                FindingFormat::Annotations => {
+                    let diagnostic = Diagnostic {
+                        severity,
+                        code: finding.code.to_string(),
+                        message: match finding.method {
+                            Some(method) => format!(
+                                "{}: {}",
+                                method_label(&class_file, method, friendly_names),
+                                finding.message
+                            ),
+                            None => finding.message,
+                        },
+                        location: match finding.method {
+                            Some(method) => locator.method(method),
+                            None => locator.class(),
+                        },
+                    };
+                    println!("{}", diagnostic.to_json());
+                }
+            }
+        }
+        Ok(())
+    })?;
+    scan.finish()?;
+    if !quiet && format == FindingFormat::Text && !jit_counts.is_empty() {
+        println!("methods over JIT thresholds, by package:");
+        for (package, counts) in &jit_counts {
+            let counts: Vec<String> = counts
+                .iter()
+                .map(|(threshold, count)| format!("{} {}", count, threshold.name()))
+                .collect();
+            let package = match package.as_str() {
+                "" => "(default package)".to_string(),
+                package => package.replace('/', "."),
+            };
+            println!("  {}: {}", package, counts.join(", "));
+        }
+    }
+    Ok(found)
+}
+
+/// A check's finding in one class.
+struct Finding<'a> {
+    /// The method it's in, if it's in one.
+    method: Option<&'a MethodInfo>,
+    /// The stable code of the check, e.g. `L0003`.
+    code: &'static str,
+    /// The severity it has unless the policy says otherwise.
+    severity: Severity,
+    message: String,
+}
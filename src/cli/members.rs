@@ -0,0 +1,54 @@
+use crate::{classfile::ClassFile, constantpool::ConstantPool};
+
+/// Prints a line for every field of the class: its name, descriptor and
+/// flags.
+pub fn print_fields(class_file: &ClassFile) {
+    let rows = class_file.fields.iter().map(|field| {
+        let flags = field.access_flags();
+        (
+            field.name_index(),
+            field.descriptor_index(),
+            flags.to_string(),
+        )
+    });
+    print_rows(class_file, rows.collect());
+}
+
+/// Prints a line for every method of the class: its name, descriptor and
+/// flags.
+pub fn print_methods(class_file: &ClassFile) {
+    let rows = class_file.methods.iter().map(|method| {
+        let flags = method.access_flags();
+        (
+            method.name_index(),
+            method.descriptor_index(),
+            flags.to_string(),
+        )
+    });
+    print_rows(class_file, rows.collect());
+}
+
+/// Prints the names and descriptors `rows` index, and the flags, in
+/// columns.
+fn print_rows(class_file: &ClassFile, rows: Vec<(u16, u16, String)>) {
+    let utf8 = |index| ConstantPool::utf8(&class_file.constant_pool, index).unwrap_or("?");
+    let width = |column: fn(&(u16, u16, String)) -> u16| {
+        rows.iter()
+            .map(|row| utf8(column(row)).chars().count())
+            .max()
+            .unwrap_or(0)
+    };
+    let name_width = width(|row| row.0);
+    let descriptor_width = width(|row| row.1);
+    for (name_index, descriptor_index, flags) in &rows {
+        let line = format!(
+            "{:<name_width$}  {:<descriptor_width$}  {}",
+            utf8(*name_index),
+            utf8(*descriptor_index),
+            flags,
+            name_width = name_width,
+            descriptor_width = descriptor_width
+        );
+        println!("{}", line.trim_end());
+    }
+}
@@ -0,0 +1,141 @@
+use std::error::Error;
+
+use crate::{
+    cfg::ControlFlowGraph,
+    constantpool::ConstantPool,
+    ordering,
+    policy::Policy,
+    rdjson::{Diagnostic, Severity, SourceLocator},
+};
+
+use super::{
+    method_label,
+    output::{severity_prefix, FindingFormat},
+    scan::{parse_entry, EntryError, Scan, ScanOptions},
+};
+
+/// The sizes past which `jvmb metrics` reports a method.
+#[derive(Debug, Clone, Copy)]
+pub struct Limits {
+    /// The most cyclomatic complexity a method may have.
+    pub complexity: usize,
+    /// The most bytes of code a method may have.
+    pub code_size: usize,
+}
+
+impl Default for Limits {
+    /// McCabe's limit of 10, and HotSpot's huge method limit, past which it
+    /// never compiles a method.
+    fn default() -> Self {
+        Limits {
+            complexity: 10,
+            code_size: 8000,
+        }
+    }
+}
+
+/// Prints the code size and cyclomatic complexity of every method with a
+/// body among `inputs`, the most complex first, or with `--format
+/// annotations` a finding for each of those over the `limits`, unless
+/// `quiet`. Returns whether any method
+/// was over them.
+pub fn run(
+    inputs: &[&str],
+    limits: Limits,
+    quiet: bool,
+    friendly_names: bool,
+    format: FindingFormat,
+    policy: &mut Policy,
+    options: ScanOptions,
+) -> Result<bool, Box<dyn Error>> {
+    let mut over = false;
+    // (complexity, lines) of each method for the text report
+    let mut rows = Vec::new();
+    let mut scan = Scan::new(options);
+    scan.run(inputs, |name, buf, parse_options| {
+        let class_file = parse_entry(buf, parse_options)?;
+        let locator = SourceLocator::new(&class_file, name);
+        let class_name = ConstantPool::class_name(&class_file.constant_pool, class_file.this_class)
+            .unwrap_or("");
+        for method in &class_file.methods {
+            let code = match method.code() {
+                Some(code) => code,
+                None => continue,
+            };
+            let complexity = ControlFlowGraph::build(code)
+                .map_err(|e| EntryError::new("decode", e.to_string()))?
+                .cyclomatic_complexity();
+            let size = code.code.len();
+            let label = method_label(&class_file, method, friendly_names);
+            let mut findings = Vec::new();
+            if complexity > limits.complexity {
+                findings.push((
+                    "M0001",
+                    format!(
+                        "cyclomatic complexity {} is over the limit of {}",
+                        complexity, limits.complexity
+                    ),
+                ));
+            }
+            if size > limits.code_size {
+                findings.push((
+                    "M0002",
+                    format!(
+                        "{} bytes of code are over the limit of {}",
+                        size, limits.code_size
+                    ),
+                ));
+            }
+            let findings: Vec<_> = findings
+                .into_iter()
+                .filter_map(|(code, message)| {
+                    let severity = policy.resolve(code, class_name, Severity::Warning)?;
+                    Some((code, severity, message))
+                })
+                .collect();
+            over |= findings
+                .iter()
+                .any(|(_, severity, _)| *severity != Severity::Info);
+            if quiet {
+                continue;
+            }
+            match format {
+                FindingFormat::Text => {
+                    let mut lines = vec![format!(
+                        "{}: {}: {} bytes of code, cyclomatic complexity {}",
+                        name, label, size, complexity
+                    )];
+                    for (code, severity, message) in findings {
+                        lines.push(format!(
+                            "{}: {}: {}{} [{}]",
+                            name,
+                            label,
+                            severity_prefix(severity),
+                            message,
+                            code
+                        ));
+                    }
+                    rows.push((complexity, lines));
+                }
+                FindingFormat::Annotations => {
+                    for (code, severity, message) in findings {
+                        let diagnostic = Diagnostic {
+                            severity,
+                            code: code.to_string(),
+                            message: format!("{}: {}", label, message),
+                            location: locator.method(method),
+                        };
+                        println!("{}", diagnostic.to_json());
+                    }
+                }
+            }
+        }
+        Ok(())
+    })?;
+    ordering::rank(&mut rows, |row| row.0, |row| &row.1[0]);
+    for line in rows.iter().flat_map(|row| &row.1) {
+        println!("{}", line);
+    }
+    scan.finish()?;
+    Ok(over)
+}
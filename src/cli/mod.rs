@@ -0,0 +1,1572 @@
+//! The `jvmb` command line. [`run`] is what the binary runs; [`run_with`]
+//! is for binaries of their own which add checks to `jvmb report`.
+
+mod annotations;
+mod archive;
+mod args;
+#[cfg(feature = "tui")]
+mod browse;
+mod callgraph;
+mod clones;
+mod code;
+mod compat;
+mod config;
+mod constants;
+mod deps;
+mod disasm;
+mod exit;
+mod extract;
+mod grep;
+mod hierarchy;
+mod index;
+mod linemap;
+mod lint;
+mod members;
+mod metrics;
+mod modules;
+mod nativeconfig;
+mod output;
+mod overrides;
+mod pattern;
+mod rename;
+mod report;
+mod scan;
+mod scanreport;
+mod scrubpaths;
+mod selftest;
+mod setconst;
+mod splitpackages;
+mod stats;
+mod stringbuilding;
+mod strings;
+mod symbols;
+#[cfg(feature = "tui")]
+mod terminal;
+mod xref;
+
+use std::{
+    error::Error,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use crate::{
+    attribute::DEBUG_ATTRIBUTES,
+    classfile::{ClassFile, ParseOptions, SharedLiteral},
+    classindex::ClassIndex,
+    constantpool::ConstantPool,
+    deps::SystemModules,
+    diff::{self, FingerprintOptions},
+    export, friendly,
+    input::Input,
+    javap,
+    jit::JitThresholds,
+    json::Json,
+    mapping::NameMapper,
+    methodinfo::MethodInfo,
+    parallel,
+    patch::SharedConstant,
+    pattern::Pattern,
+    pipeline::Registry,
+    rdjson::{self, Diagnostic, SourceLocator},
+    redact::Redactor,
+    regex::Regex,
+    sha256,
+    sourcepaths::{self, ScrubOptions},
+    spec,
+    textify::PrintOptions,
+    verify,
+};
+
+pub use self::exit::ExitCode;
+use self::{
+    args::Args,
+    config::Config,
+    exit::Failure,
+    output::{finding_policy, report_suppressions, FindingFormat, Format, Outputs},
+    scan::{index_classes, ScanOptions},
+};
+
+const USAGE: &str = "usage: jvmb <file> [--json] [--concatenated | --salvage] [--mmap]
+       jvmb <file> --validate [--mmap]
+       jvmb <jar> [--class <internal name> [--json]] [--release <n>] [--mmap]
+       jvmb annotations <file>
+       jvmb attributes <file>
+       jvmb annotations --stats [--histogram <descriptor>#<member>]... [<output options>] [<scan options>] <class, jar or dir>...
+       jvmb browse [<scan options>] <class, jar or dir>...
+       jvmb clones [--min-size <bytes>] [--similarity <0 to 1>] [--friendly-names] [<scan options>] <class, jar or dir>...
+       jvmb cfg <file> <method> [--dot]
+       jvmb code <file> <method> [--lines]
+       jvmb code <file> --layout [--json]
+       jvmb compat --against <symbol list> [<output options>] [<scan options>] <class, jar or dir>...
+       jvmb cp <file> [--explain] [--redact <regex>]... [--redact-all-strings] [--anchors | --hyperlinks] [--follow <n>]
+       jvmb cp <file> --unused
+       jvmb cp <file> --roles
+       jvmb constants <file>
+       jvmb diff [--check] <file> <file>
+       jvmb deps [--format jdeps | list] [--classpath <path>] [--system <java home>] [-s] [<scan options>] <class, jar or dir>...
+       jvmb disasm <file> [--format asm-textify] [--show-stack] [--show-regions] [--resolve-enum-switches [--classpath <path>]] [--mapping <file>] [<print limits>]
+       jvmb explain [<attribute or constant kind>]
+       jvmb extract <file> [--method <method> | --field <field>] [--what code | attribute:<name>] [-o <file> | --hex]
+       jvmb fields <file>
+       jvmb fingerprint [--no-debug] [--no-synthetic] <file>
+       jvmb header <file>
+       jvmb schema
+       jvmb selftest [--system <jdk>] [--sample <n>] [<class, jar or dir>...]
+       jvmb split-packages [<output options>] [--allow <package pattern>]... <jar>...
+       jvmb report [--sections <section>,...] [--check] [<finding options>] [<output options>] [-o <file>] [--group-by package [--package-depth <n>]] [--classpath <path>] [--system <java home>] [--time-limit <seconds>] [<scan options>] <class, jar or dir>...
+       jvmb scan [--report versions | deps | strings | errors] [--jobs <n>] [--no-jars] [--top <n>] [<scan options>] <class, jar or dir>...
+       jvmb string-building [--top <n>] [--friendly-names] [<scan options>] <class, jar or dir>...
+       jvmb symbols [<output options>] [--mapping <file>] [<scan options>] <class, jar or dir>...
+       jvmb set-const (--index <n> (--int | --long | --float | --double | --utf8) <value> | --field <name>=<value>) [--duplicate-if-shared] <file> -o <file>
+       jvmb set-string --match <text> --replace <text> [--force-literal-only] <file> -o <file>
+       jvmb rename --rules <file> <jar> -o <file>
+       jvmb scrub-paths [--drop-source-debug-extension] <jar> -o <file>
+       jvmb strip [--attributes <name>,...] [--clear-unused-utf8] <file> -o <file>
+       jvmb hierarchy <class> <class, jar or dir>...
+       jvmb index [<scan options>] <class, jar or dir>... -o <file>
+       jvmb overrides --method <class>#<name>(<descriptor>) <class, jar or dir>...
+       jvmb grep --symbol <text> [--verify-usage] [<scan options>] <class, jar or dir>...
+       jvmb strings [--all-utf8] [<scan options>] <class, jar or dir>...
+       jvmb stats [--top <n>] [<scan options>] <class, jar or dir>...
+       jvmb callgraph --from <class>.<name>[<descriptor>] --to <class>.<name>[<descriptor>] [<scan options>] <class, jar or dir>...
+       jvmb xref [--calls <class>.<name>[<descriptor>]]... [--fields <class>.<name>[:<descriptor>]]... [<scan options>] <class, jar or dir>...
+       jvmb linemap [<scan options>] <class, jar or dir>... [-o <file>]
+       jvmb lint [--check] [--format annotations] [<finding options>] [--redundant-casts] [--debug-info] [--monitors] [--source-paths [--source-file-pattern <regex>]] [--jit [--jit-huge-method-limit <bytes>] [--jit-inline-limit <bytes>]] [--classpath <path>] [--friendly-names] [<scan options>] <class, jar or dir>...
+       jvmb metrics [--check] [--format annotations] [<finding options>] [--max-complexity <n>] [--max-code-size <bytes>] [--friendly-names] [<scan options>] <class, jar or dir>...
+       jvmb methods <file>
+       jvmb match --pattern <pattern> [--across-blocks] [--friendly-names] [<scan options>] <class, jar or dir>...
+       jvmb module <module-info.class>
+       jvmb modules [--dot] <jar or dir>...
+       jvmb native-config [--include-annotated <annotation>]... [<scan options>] <class, jar or dir>... -o <dir>
+       jvmb verify [--check] [--format annotations] [<finding options>] <file>
+       jvmb verify --module [--check] [--format annotations] [<finding options>] [--classpath <path>] <jar>
+       jvmb verify --archive [--check] [--format annotations] [<finding options>] <jar>
+
+scan options:
+       --keep-going        record failing entries and carry on (default)
+       --fail-fast         stop at the first failing entry
+       --fail-on-error     carry on, but exit with an error if anything failed
+       --show-errors <n>   print the first <n> failures in full
+       --timings           print the time spent in each phase of parsing
+       --exclude <glob>    skip the files and jar entries matching <glob>
+       --max-class-size <bytes>
+                           fail entries larger than <bytes> without parsing
+       --lenient-attributes
+                           accept attributes with bytes left over after their
+                           contents, as some obfuscators write them
+       --lenient-utf8      keep UTF8 constants which aren't modified UTF-8,
+                           shown with U+FFFD, rather than failing the class
+       --strict-versions   fail classes with attributes their version
+                           predates, e.g. a StackMapTable before Java 6
+       --mmap              map files and jars into memory instead of reading
+                           them
+       --release <n>       read multi-release jars as Java <n> sees them, or
+                           as the latest release does with latest (default)
+
+finding options, of lint, metrics, report and verify:
+       --severity <code>=<severity>
+                           report the findings with <code> as error, warning
+                           or info, as many times as there are codes
+       --suppress <code>[:<class glob>]
+                           leave out the findings with <code>, or only those
+                           in the classes matching <class glob>
+
+output options, of annotations --stats, compat, report, split-packages and symbols:
+       --json              write JSON to stdout instead of text
+       --quiet             write nothing to stdout
+       --out <format>=<file>
+                           also write <format>, text, json or csv, to <file>,
+                           as many times as there are formats and files
+
+print limits:
+       --max-instructions-per-method <n>
+                           print the first and last <n>/2 instructions of
+                           longer methods
+       --max-string-len <n>
+                           print the first <n> characters of longer strings
+       --max-methods <n>   print the first <n> methods of a class
+       --full              print everything, whatever the limits
+What is left out is marked with how much, e.g. … 12,345 instructions elided …
+
+options of every command:
+       --config <file>     read defaults from <file> instead of the nearest
+                           jvmb.toml in the working directory or its parents
+       --no-config         read no defaults at all
+
+Options on the command line replace the defaults from the configuration.
+annotations --stats counts the classes, fields, methods and parameters carrying
+each annotation type, repeated annotations included, and --histogram counts
+the values of a member, each element of an array on its own, e.g.
+'Ljava/lang/Deprecated;#forRemoval'; members left out count as (default).
+browse opens a terminal browser of the classes by package, the members of the
+one selected and its dump or a method's disassembly: / searches class and
+member names as you type, n and N go to the next and previous match, g shows a
+constant pool entry and those it leads to, x lists the references to the
+selected member among the inputs, and q quits. It needs the tui feature.
+jvmb <file> prints the class the way javap -p -v does, or as JSON with --json.
+--validate prints every constant pool index of the class which doesn't lead to
+an entry of the kind needed there instead, with where it is, e.g. method 3 >
+Code > offset 12.
+jvmb <jar> lists the classes in the jar, and with --class prints the one named,
+e.g. com/example/Foo, the same way. Of a multi-release jar, each class is the
+variant under the highest META-INF/versions/<n> no newer than --release, or
+else the one outside META-INF/versions.
+header, constants, fields, methods, code and attributes print one part of it:
+header the version, flags and names of the class, stepping over its members
+rather than parsing them, constants the pool as cp does, fields and methods a
+line for each with its descriptor and flags, code the methods named <method>,
+e.g. run or run()V, with their code, and attributes those of the class.
+code --lines prints only the instructions of those methods, with a comment
+before the first instruction of each source line, e.g. // line 12.
+cfg prints the basic blocks of the methods named <method>, each with its offset
+range and the blocks control goes to next, by falling through, a jump, a switch
+case or an exception; --dot prints a Graphviz digraph of them instead.
+extract writes the bytecode of the method named <method>, e.g. run or run(I)V,
+or with --what attribute:<name> the body of the first attribute so named of the
+method, including those of its Code, of the field named <field>, e.g. x or x:I,
+or else of the class, exactly as stored: to the file -o names, as a hex dump
+with the offset of each line with --hex, or else to standard output. A method
+or field must be named with its descriptor when others share its name.
+--salvage prints whatever could be read of a truncated or damaged class.
+A <file> of - is read from standard input. --mmap maps <file>, or the <jar>
+of jvmb <jar>, into memory instead of reading it, as it does the inputs of a
+scan, so that a class can be printed out of a jar of hundreds of megabytes
+without reading the rest; what can't be mapped, such as a pipe, is read.
+diff prints what differs between two builds of a class: the version, names,
+flags, fields and methods, by name and descriptor, and their attributes,
+with the bytecode and constant values apart. Constants are compared by
+value, so that classes differing only in the layout of their constant pools
+have no semantic differences. --check prints nothing and exits with 4 when
+there are differences.
+fingerprint prints a SHA-256 hash of what diff compares, so that two builds
+of a class without semantic differences have the same fingerprint; it leaves
+out debug info with --no-debug and the members the compiler generated with
+--no-synthetic.
+deps prints the layout of jdeps -verbose:class, or of jdeps -s with -s;
+--format list prints every class referenced, sorted, one to a line.
+Platform classes are found in the jmods of --system, or else of $JAVA_HOME.
+report runs verify, lint, metrics and deps, or the --sections named, parsing
+every class once, and sums each up; the full report is JSON. Past its
+--time-limit, it stops looking through method bodies and marks the sections
+which needed to partial. -o writes the JSON to a file, like --out json=<file>.
+--group-by package also rolls every section up by package, or by the first
+<n> segments of the package with --package-depth, e.g. com.foo for com.foo.*;
+the JSON says whether each measure is summed, averaged or the maximum.
+verify, lint and any checks a binary built on jvmb adds report their findings
+alike, with --severity and --suppress applying to all of them; --check makes
+report exit with 3 when any are errors or warnings.
+--friendly-names shows lambdas, accessors and switch map classes the compiler
+generated by what they are, e.g. λ2 in handleRequest.
+--show-stack comments every instruction disasm prints with the operand stack
+after it, as the stack map frames and the instructions since tell.
+--show-regions comments the start of every synchronized block, with the type
+of its lock, and of every try-with-resources body, with the resource's class.
+--resolve-enum-switches comments switches over enums with the constant behind
+each case, from the switch map classes beside the file or on the --classpath.
+--mapping reads a ProGuard or R8 mapping file and shows the original names of
+an obfuscated build: symbols prints them, with both in its JSON, and disasm
+comments instructions with them.
+--redundant-casts also reports casts the inferred types already guarantee,
+as far as the class hierarchy among the inputs can tell.
+--debug-info also warns of line numbers, local variables and parameter lists
+which don't fit the code and descriptors they describe.
+--monitors also warns of monitors which aren't exited on every path, or not
+by a catch-all handler when an exception is thrown, and of monitorexits
+without a monitorenter.
+--source-paths also warns of SourceFile values which are paths, or don't match
+--source-file-pattern (by default a simple name like Foo.java), and of
+SourceDebugExtensions with CRLF line endings or which aren't modified UTF-8.
+--jit also warns of methods over 8000 bytes of code, which HotSpot never
+compiles, and of those and constructors over 325, which it never inlines, and
+counts them by package.
+strings prints the string literals of each class, one to a line with line
+breaks escaped, after the entry they're in for a jar or directory; --all-utf8
+prints every UTF8 constant, names and descriptors too. Only the constant pool
+of each class is parsed.
+stats prints where the bytes of the classes go: the parts of the class files,
+the constants by kind and the attributes by name, with their share of the
+bytes, then the --top (10 by default) largest methods and UTF8 constants of
+them all. A Code is counted without the attributes in it, which are counted
+under their own names.
+callgraph prints a shortest chain of calls from a --from method to a --to
+method, given as com/example/Main.main or java/lang/Runtime#exec, optionally
+with the descriptor, or unreachable: the first method, then each call with
+its offset in the caller. A call links to the method the instruction names, or
+the one it inherits from a superclass, so an interface call reaches the method
+of the interface and not those of its implementations; a method handle passed
+to a bootstrap method, as a lambda's is, counts as called.
+xref prints the methods and fields each method refers to, one to a line after
+the method and the offset of the instruction, and its invokedynamic call sites
+with their bootstrap methods. With --calls or --fields, given as
+java/lang/Runtime.exec or java/lang/System#out, optionally with the
+descriptor, only the instructions using those members are printed, a method
+handle passed to a bootstrap method, as a method reference is, counting as a
+call.
+scan parses every class on --jobs threads, one per core by default, and prints
+a --report of them all: versions counts the classes of each class file
+version, deps ranks the classes referred to from outside the inputs by the
+number of classes referring to them, strings ranks the string literals by the
+number of classes using them, and errors lists the entries which failed, in
+the order they were found whatever the number of threads. --top prints only
+the first <n> of deps or strings; --no-jars skips the jars found in
+directories.
+index writes the classes, members, member references, string constants and
+attributes as an SQL script to load into SQLite, e.g. sqlite3 classes.db <
+classes.sql, or, built with the sqlite feature, into the database itself given
+an -o of .db, .sqlite or .sqlite3; each input replaces what an earlier export
+wrote of it.
+set-const sets a constant pool entry, or the constant value of a field, and
+prints what uses the entry. With --field it refuses when anything else uses
+the field's entry, unless --duplicate-if-shared gives the field one of its
+own, and with --index so it does for a UTF8 entry which is also a name.
+rename renames classes and members throughout a jar, from the [classes],
+[members] and [patterns] tables of a TOML file, e.g. 'a/b/c' = 'com/foo/Parser'
+and 'a/b/c#a(I)V' = 'parseHeader'; a pattern replaces what it matches of the
+names of the classes no rule names. Methods are renamed in the subclasses
+overriding them too. Every conflict is reported and nothing is written.
+scrub-paths reduces every SourceFile which is a path to the name of the file
+and normalizes the line endings of SourceDebugExtensions to LF, or drops them
+with --drop-source-debug-extension, and prints what it changed in each class.
+strip removes the debugging attributes, SourceFile, SourceDebugExtension,
+LineNumberTable, LocalVariableTable and LocalVariableTypeTable, or those of
+them --attributes names, from a class and its members. --clear-unused-utf8
+also empties the UTF8 constants nothing refers to any longer, such as the
+names of locals; the entries stay, so that no index changes.
+compat reports every reference to a class or member missing from the symbol
+list of a target runtime: one internal name per line, a class optionally
+followed by ': <supertype> ...', a member as <class>#<name>(<descriptor>) or
+<class>#<name>:<descriptor>. Members are only checked when the list has some.
+cp --anchors writes an HTML anchor before every entry and links every #<n>
+to it, for Markdown; --hyperlinks makes every #<n> a terminal hyperlink to
+the entry instead. --follow <n> prints entry <n> and every entry it leads to,
+through the bootstrap methods of dynamic constants too.
+verify --module checks a modular jar: that the packages it exports and opens
+have classes in it, that its ModulePackages lists every package which does,
+and that the services it uses and provides are in the jar or on --classpath,
+with every provider a class of the jar extending or implementing its service.
+module prints the module a module-info.class declares, as java
+--describe-module prints it.
+verify --archive checks the zip structure of a jar before verifying its
+classes: that every class entry inflates to the size and CRC-32 the central
+directory declares, that no name is listed twice, that every local header
+agrees with the central directory and no entry's contents overlap another's,
+and that there are no local headers the central directory doesn't list.
+Suppressions of its V05 codes take a glob of entry names.
+selftest parses --sample classes of java.base (500 by default) strictly,
+verifies them and writes them back, and prints every failure by its code
+under the versions of jvmb, the OS and the JDK, to paste into a bug report.
+The JDK is --system, or else $JAVA_HOME, or else the one of the java on the
+PATH; with class files, jars or directories, it tests their classes instead.
+A JDK without jmods has its classes read from rt.jar, as JDK 8 keeps them, or
+else a few well-known ones dumped by running its java.
+metrics prints the size and cyclomatic complexity of every method with code,
+the most complex first, and warns of those over --max-complexity (10 by
+default) or --max-code-size (8000 bytes by default).
+--format annotations makes lint, metrics and verify print what they find as
+reviewdog diagnostics, one rdjson object per line, at the first line of the
+method concerned in its source file, e.g. com/app/Main.java, or at line 1 of
+the source file for the class as a whole; a class without a SourceFile or a
+method without line numbers is reported at line 1 of its class file, with a
+note why. Compile with -g for line numbers.
+Every finding of lint, metrics and verify has a stable code, printed after it
+in brackets: V for verify, e.g. V0203, L for lint and M for metrics.
+--suppress takes a class glob of internal names, e.g. L0005:com/thirdparty/**,
+where * stands for any run of characters within a package and ** for any run
+at all. Each suppression of the command's codes is reported on stderr with the
+number of findings it left out, and with a warning when that's none, so it can
+be removed.
+--check of lint, metrics and verify prints nothing and reports findings through
+the exit status alone.
+
+exit status:
+       0  success, and nothing found by --check
+       1  usage error
+       2  an input couldn't be read or parsed
+       3  --check found verification violations, lint findings or
+          methods over the metrics limits, other than those --severity
+          made info,
+          report --check found errors or warnings,
+          split-packages found a package which isn't allowed, or compat
+          found missing symbols, selftest found failures, or
+          --validate found bad constant pool indices
+       4  diff --check found differences
+       5  any other failure";
+
+/// Options which take a value.
+const VALUE_OPTIONS: &[&str] = &[
+    "against",
+    "allow",
+    "attributes",
+    "calls",
+    "class",
+    "classpath",
+    "config",
+    "double",
+    "exclude",
+    "field",
+    "fields",
+    "float",
+    "follow",
+    "format",
+    "from",
+    "group-by",
+    "histogram",
+    "include-annotated",
+    "index",
+    "int",
+    "jobs",
+    "jit-huge-method-limit",
+    "jit-inline-limit",
+    "long",
+    "mapping",
+    "match",
+    "method",
+    "max-class-size",
+    "max-code-size",
+    "max-complexity",
+    "max-instructions-per-method",
+    "max-methods",
+    "max-string-len",
+    "min-size",
+    "out",
+    "output",
+    "package-depth",
+    "pattern",
+    "redact",
+    "release",
+    "replace",
+    "report",
+    "rules",
+    "sample",
+    "sections",
+    "severity",
+    "show-errors",
+    "similarity",
+    "source-file-pattern",
+    "suppress",
+    "symbol",
+    "system",
+    "time-limit",
+    "to",
+    "top",
+    "utf8",
+    "what",
+];
+
+/// Single letter aliases for long options.
+const SHORT_OPTIONS: &[(char, &str)] = &[('o', "output"), ('s', "summary")];
+
+pub fn run(args: Vec<String>) -> Result<ExitCode, Box<dyn Error>> {
+    run_with(args, &Registry::new())
+}
+
+/// Runs the command `args` name, like [`run`], with the checks of
+/// `registry` as further sections of `report`, which runs them by default
+/// after the built-in ones. A binary of its own built on this, as
+/// `examples/custom_check.rs` is, gets the options, the output formats,
+/// the severities and suppressions and the exit status of `jvmb` for its
+/// checks too.
+pub fn run_with(args: Vec<String>, registry: &Registry) -> Result<ExitCode, Box<dyn Error>> {
+    let (command, rest) = match args.split_first() {
+        Some((command, rest)) => (command.as_str(), rest),
+        None => return Err(usage().into()),
+    };
+    let mut args = Args::parse(rest, VALUE_OPTIONS, SHORT_OPTIONS).map_err(Failure::usage)?;
+    if let Some(config) = Config::load(&args)? {
+        config.apply(command, &mut args);
+    }
+    let mmap = args.flag("mmap");
+
+    match command {
+        #[cfg(feature = "tui")]
+        "browse" => {
+            let inputs = args.positionals();
+            if inputs.is_empty() {
+                return Err(usage().into());
+            }
+            browse::run(&inputs, ScanOptions::from_args(&args)?)?;
+        }
+        #[cfg(not(feature = "tui"))]
+        "browse" => {
+            return Err(Failure::usage("browse needs jvmb built with the tui feature").into());
+        }
+        "annotations" if args.flag("stats") => {
+            let inputs = args.positionals();
+            if inputs.is_empty() {
+                return Err(usage().into());
+            }
+            annotations::stats(
+                &inputs,
+                &args.values("histogram"),
+                &Outputs::from_args(&args, command, annotations::FORMATS)?,
+                ScanOptions::from_args(&args)?,
+            )?;
+        }
+        "annotations" => {
+            let file_name = args.positional(0).ok_or_else(usage)?;
+            annotations::print(&read_class_file(file_name, mmap)?);
+        }
+        "clones" => {
+            let inputs = args.positionals();
+            if inputs.is_empty() {
+                return Err(usage().into());
+            }
+            let min_size = match args.values("min-size").pop() {
+                Some(size) => size
+                    .parse()
+                    .map_err(|_| Failure::usage(format!("invalid --min-size: {}", size)))?,
+                None => 16,
+            };
+            let similarity = match args.values("similarity").pop() {
+                Some(value) => match value.parse::<f64>() {
+                    Ok(similarity) if (0.0..=1.0).contains(&similarity) => Some(similarity),
+                    _ => {
+                        return Err(
+                            Failure::usage(format!("invalid --similarity: {}", value)).into()
+                        )
+                    }
+                },
+                None => None,
+            };
+            clones::run(
+                &inputs,
+                min_size,
+                similarity,
+                args.flag("friendly-names"),
+                ScanOptions::from_args(&args)?,
+            )?;
+        }
+        "compat" => {
+            let against = args.values("against").pop().ok_or_else(usage)?;
+            let inputs = args.positionals();
+            if inputs.is_empty() {
+                return Err(usage().into());
+            }
+            let outputs = Outputs::from_args(&args, command, compat::FORMATS)?;
+            return compat::run(against, &inputs, &outputs, ScanOptions::from_args(&args)?);
+        }
+        "attributes" => {
+            let file_name = args.positional(0).ok_or_else(usage)?;
+            print!(
+                "{}",
+                javap::javap_attributes(&read_class_file(file_name, mmap)?)
+            );
+        }
+        "code" if args.flag("layout") => {
+            let file_name = args.positional(0).ok_or_else(usage)?;
+            code::print_layout(&read_class_file(file_name, mmap)?, args.flag("json"))?;
+        }
+        "code" => {
+            let file_name = args.positional(0).ok_or_else(usage)?;
+            let method = args.positional(1).ok_or_else(usage)?;
+            let lines = args.flag("lines");
+            code::print_method(&read_class_file(file_name, mmap)?, method, lines)?;
+        }
+        "cfg" => {
+            let file_name = args.positional(0).ok_or_else(usage)?;
+            let method = args.positional(1).ok_or_else(usage)?;
+            code::print_cfg(&read_class_file(file_name, mmap)?, method, args.flag("dot"))?;
+        }
+        "fields" => {
+            let file_name = args.positional(0).ok_or_else(usage)?;
+            members::print_fields(&read_class_file(file_name, mmap)?);
+        }
+        "fingerprint" => {
+            let file_name = args.positional(0).ok_or_else(usage)?;
+            let options = FingerprintOptions {
+                exclude_debug_info: args.flag("no-debug"),
+                exclude_synthetic: args.flag("no-synthetic"),
+            };
+            let fingerprint = read_class_file(file_name, mmap)?.fingerprint(options);
+            println!("{}", sha256::hex(&fingerprint));
+        }
+        "header" => {
+            let file_name = args.positional(0).ok_or_else(usage)?;
+            let buf = read_input(file_name, mmap)?;
+            scan::warn_unsupported_version(file_name, &buf);
+            // the header needs only the class's own attributes
+            let (_, class_file) = ClassFile::parse_skipping_members(&buf, &ParseOptions::default())
+                .map_err(|e| {
+                    Failure::parse(format!(
+                        "{}: {}",
+                        file_name,
+                        scan::parse_error(&buf, e).message
+                    ))
+                })?;
+            print!("{}", javap::javap_header(&class_file));
+            println!(
+                "  release: {} (major {}){}",
+                class_file.java_version(),
+                class_file.major_version,
+                if class_file.is_preview() {
+                    ", preview"
+                } else {
+                    ""
+                }
+            );
+        }
+        "methods" => {
+            let file_name = args.positional(0).ok_or_else(usage)?;
+            members::print_methods(&read_class_file(file_name, mmap)?);
+        }
+        "cp" | "constants" => {
+            let file_name = args.positional(0).ok_or_else(usage)?;
+            let class_file = read_class_file(file_name, mmap)?;
+            if args.flag("unused") {
+                constants::print_unused(&class_file);
+            } else if args.flag("roles") {
+                constants::print_roles(&class_file);
+            } else {
+                let links = if args.flag("anchors") {
+                    constants::Links::Anchors
+                } else if args.flag("hyperlinks") {
+                    let path = std::fs::canonicalize(file_name)?;
+                    constants::Links::Hyperlinks(format!("file://{}", path.display()))
+                } else {
+                    constants::Links::Plain
+                };
+                let only = match args.values("follow").pop() {
+                    Some(index) => match index.parse::<u16>() {
+                        Ok(index)
+                            if (1..=class_file.constant_pool.len()).contains(&(index as usize)) =>
+                        {
+                            Some(class_file.reachable_constants(index))
+                        }
+                        _ => {
+                            return Err(
+                                Failure::usage(format!("invalid --follow: {}", index)).into()
+                            )
+                        }
+                    },
+                    None => None,
+                };
+                constants::print(
+                    &class_file,
+                    args.flag("explain"),
+                    &redactor(&args)?,
+                    &links,
+                    only.as_ref(),
+                );
+            }
+        }
+        "diff" => {
+            let (old_name, new_name) = match (args.positional(0), args.positional(1)) {
+                (Some(old_name), Some(new_name)) => (old_name, new_name),
+                _ => return Err(usage().into()),
+            };
+            let entries = diff::diff(
+                &read_class_file(old_name, mmap)?,
+                &read_class_file(new_name, mmap)?,
+            );
+            if args.flag("check") {
+                if !entries.is_empty() {
+                    return Ok(ExitCode::Differences);
+                }
+            } else if entries.is_empty() {
+                println!("no semantic differences");
+            } else {
+                for entry in entries {
+                    println!("{}", entry);
+                }
+            }
+        }
+        "disasm" => {
+            let file_name = args.positional(0).ok_or_else(usage)?;
+            match args.values("format").pop().unwrap_or("asm-textify") {
+                "asm-textify" => {
+                    let class_file = read_class_file(file_name, mmap)?;
+                    let enum_switches = if args.flag("resolve-enum-switches") {
+                        Some(enum_switch_index(file_name, &args)?)
+                    } else {
+                        None
+                    };
+                    disasm::run(
+                        &class_file,
+                        &print_options(&args)?,
+                        args.flag("show-stack"),
+                        args.flag("show-regions"),
+                        enum_switches.as_ref(),
+                        name_mapper(&args)?.as_ref(),
+                    );
+                }
+                format => {
+                    return Err(Failure::usage(format!("unknown disasm format: {}", format)).into())
+                }
+            }
+        }
+        "extract" => {
+            let file_name = args.positional(0).ok_or_else(usage)?;
+            let what = extract::What::parse(args.values("what").pop().unwrap_or("code"))?;
+            let output = match (args.values("output").pop(), args.flag("hex")) {
+                (Some(output), false) => extract::Output::File(output),
+                (None, true) => extract::Output::Hex,
+                (None, false) => extract::Output::Stdout,
+                (Some(_), true) => return Err(usage().into()),
+            };
+            extract::run(
+                &read_input(file_name, mmap)?,
+                file_name,
+                args.values("method").pop(),
+                args.values("field").pop(),
+                what,
+                output,
+            )?;
+        }
+        "explain" => match args.positional(0) {
+            Some(name) => {
+                let text = spec::explain(name).ok_or_else(|| {
+                    Failure::usage(format!("unknown attribute or constant kind: {}", name))
+                })?;
+                println!("{}", text);
+            }
+            None => {
+                for attribute in spec::ATTRIBUTES {
+                    println!("{:<40} {}", attribute.name, attribute.description);
+                }
+                for constant in spec::CONSTANTS {
+                    println!(
+                        "{:<40} {}",
+                        format!("CONSTANT_{}", constant.name),
+                        constant.description
+                    );
+                }
+            }
+        },
+        "grep" => {
+            let symbol = args.values("symbol").pop().ok_or_else(usage)?;
+            let inputs = args.positionals();
+            if inputs.is_empty() {
+                return Err(usage().into());
+            }
+            grep::run(
+                symbol,
+                &inputs,
+                args.flag("verify-usage"),
+                ScanOptions::from_args(&args)?,
+            )?;
+        }
+        "strings" => {
+            let inputs = args.positionals();
+            if inputs.is_empty() {
+                return Err(usage().into());
+            }
+            strings::run(
+                &inputs,
+                args.flag("all-utf8"),
+                ScanOptions::from_args(&args)?,
+            )?;
+        }
+        "stats" => {
+            let inputs = args.positionals();
+            if inputs.is_empty() {
+                return Err(usage().into());
+            }
+            let top = match args.values("top").pop() {
+                Some(top) => top
+                    .parse()
+                    .map_err(|_| Failure::usage(format!("invalid --top: {}", top)))?,
+                None => 10,
+            };
+            stats::run(&inputs, top, ScanOptions::from_args(&args)?)?;
+        }
+        "callgraph" => {
+            let inputs = args.positionals();
+            let (from, to) = match (args.values("from").pop(), args.values("to").pop()) {
+                (Some(from), Some(to)) if !inputs.is_empty() => (from, to),
+                _ => return Err(usage().into()),
+            };
+            callgraph::run(
+                &inputs,
+                &xref::MemberPattern::parse(from)?,
+                &xref::MemberPattern::parse(to)?,
+                ScanOptions::from_args(&args)?,
+            )?;
+        }
+        "xref" => {
+            let inputs = args.positionals();
+            if inputs.is_empty() {
+                return Err(usage().into());
+            }
+            let patterns = |option| {
+                args.values(option)
+                    .into_iter()
+                    .map(xref::MemberPattern::parse)
+                    .collect::<Result<Vec<_>, _>>()
+            };
+            xref::run(
+                &inputs,
+                &patterns("calls")?,
+                &patterns("fields")?,
+                ScanOptions::from_args(&args)?,
+            )?;
+        }
+        "deps" => {
+            let inputs = args.positionals();
+            if inputs.is_empty() {
+                return Err(usage().into());
+            }
+            match args.values("format").pop() {
+                None | Some("jdeps") => {}
+                Some("list") => {
+                    deps::list(&inputs, ScanOptions::from_args(&args)?)?;
+                    return Ok(ExitCode::Success);
+                }
+                Some(format) => {
+                    return Err(Failure::usage(format!("unknown format: {}", format)).into())
+                }
+            }
+            let classpath = classpath(&args);
+            let classpath: Vec<&str> = classpath.iter().map(String::as_str).collect();
+            let system = system_modules(&args)?;
+            deps::run(
+                &inputs,
+                &classpath,
+                system.as_ref(),
+                args.flag("summary"),
+                ScanOptions::from_args(&args)?,
+            )?;
+        }
+        "hierarchy" => {
+            let positionals = args.positionals();
+            match positionals.split_first() {
+                Some((class_name, inputs)) if !inputs.is_empty() => {
+                    return hierarchy::run(class_name, inputs)
+                }
+                _ => return Err(usage().into()),
+            }
+        }
+        "overrides" => {
+            let inputs = args.positionals();
+            match args.values("method").pop() {
+                Some(method) if !inputs.is_empty() => return overrides::run(method, &inputs),
+                _ => return Err(usage().into()),
+            }
+        }
+        "lint" => {
+            let inputs = args.positionals();
+            if inputs.is_empty() {
+                return Err(usage().into());
+            }
+            let classpath = classpath(&args);
+            let classpath: Vec<&str> = classpath.iter().map(String::as_str).collect();
+            let checks = lint::Checks {
+                redundant_casts: args.flag("redundant-casts"),
+                debug_info: args.flag("debug-info"),
+                monitors: args.flag("monitors"),
+                source_paths: if args.flag("source-paths") {
+                    let pattern = args
+                        .values("source-file-pattern")
+                        .pop()
+                        .unwrap_or(sourcepaths::DEFAULT_SOURCE_FILE_PATTERN);
+                    Some(Regex::new(pattern).map_err(|e| {
+                        Failure::usage(format!("invalid --source-file-pattern: {}", e))
+                    })?)
+                } else {
+                    None
+                },
+                jit: if args.flag("jit") {
+                    Some(jit_thresholds(&args)?)
+                } else {
+                    None
+                },
+            };
+            let mut policy = finding_policy(&args)?;
+            let found = lint::run(
+                &inputs,
+                &classpath,
+                args.flag("check"),
+                checks,
+                args.flag("friendly-names"),
+                FindingFormat::from_args(&args)?,
+                &mut policy,
+                ScanOptions::from_args(&args)?,
+            )?;
+            report_suppressions(&policy, "L");
+            if found && args.flag("check") {
+                return Ok(ExitCode::Violations);
+            }
+        }
+        "metrics" => {
+            let inputs = args.positionals();
+            if inputs.is_empty() {
+                return Err(usage().into());
+            }
+            let mut limits = metrics::Limits::default();
+            for (name, limit) in [
+                ("max-complexity", &mut limits.complexity),
+                ("max-code-size", &mut limits.code_size),
+            ] {
+                if let Some(value) = args.values(name).pop() {
+                    *limit = value
+                        .parse()
+                        .map_err(|_| Failure::usage(format!("invalid --{}: {}", name, value)))?;
+                }
+            }
+            let mut policy = finding_policy(&args)?;
+            let over = metrics::run(
+                &inputs,
+                limits,
+                args.flag("check"),
+                args.flag("friendly-names"),
+                FindingFormat::from_args(&args)?,
+                &mut policy,
+                ScanOptions::from_args(&args)?,
+            )?;
+            report_suppressions(&policy, "M");
+            if over && args.flag("check") {
+                return Ok(ExitCode::Violations);
+            }
+        }
+        "index" => {
+            let output = args.values("output").pop().ok_or_else(usage)?;
+            let inputs = args.positionals();
+            if inputs.is_empty() {
+                return Err(usage().into());
+            }
+            index::run(&inputs, Path::new(output), ScanOptions::from_args(&args)?)?;
+        }
+        "linemap" => {
+            let inputs = args.positionals();
+            if inputs.is_empty() {
+                return Err(usage().into());
+            }
+            linemap::run(
+                &inputs,
+                args.values("output").pop(),
+                ScanOptions::from_args(&args)?,
+            )?;
+        }
+        "match" => {
+            let pattern = args.values("pattern").pop().ok_or_else(usage)?;
+            let pattern = Pattern::parse(pattern)
+                .map_err(|e| Failure::usage(e.to_string()))?
+                .across_blocks(args.flag("across-blocks"));
+            let inputs = args.positionals();
+            if inputs.is_empty() {
+                return Err(usage().into());
+            }
+            pattern::run(
+                &pattern,
+                &inputs,
+                args.flag("friendly-names"),
+                ScanOptions::from_args(&args)?,
+            )?;
+        }
+        "report" => {
+            let inputs = args.positionals();
+            if inputs.is_empty() {
+                return Err(usage().into());
+            }
+            let known: Vec<&str> = crate::report::SECTIONS
+                .iter()
+                .copied()
+                .chain(registry.names())
+                .collect();
+            let sections: Vec<&str> = match args.values("sections").pop() {
+                Some(sections) => sections.split(',').map(str::trim).collect(),
+                None => known.clone(),
+            };
+            if let Some(unknown) = sections.iter().find(|section| !known.contains(section)) {
+                return Err(Failure::usage(format!(
+                    "unknown section: {} (expected {})",
+                    unknown,
+                    known.join(", ")
+                ))
+                .into());
+            }
+            let classpath = classpath(&args);
+            let classpath: Vec<&str> = classpath.iter().map(String::as_str).collect();
+            let system = if sections.contains(&"deps") {
+                system_modules(&args)?
+            } else {
+                None
+            };
+            let time_limit = match args.values("time-limit").pop() {
+                Some(value) => match value.parse::<f64>() {
+                    Ok(seconds) if seconds.is_finite() && seconds >= 0.0 => {
+                        Some(Duration::from_secs_f64(seconds))
+                    }
+                    _ => {
+                        return Err(
+                            Failure::usage(format!("invalid --time-limit: {}", value)).into()
+                        )
+                    }
+                },
+                None => None,
+            };
+            let mut outputs = Outputs::from_args(&args, command, report::FORMATS)?;
+            if let Some(output) = args.values("output").pop() {
+                outputs.add_file(Format::Json, output);
+            }
+            let package_depth = match args.values("group-by").pop() {
+                Some("package") => Some(match args.values("package-depth").pop() {
+                    Some(depth) => match depth.parse::<usize>() {
+                        Ok(depth) if depth > 0 => Some(depth),
+                        _ => {
+                            return Err(Failure::usage(format!(
+                                "invalid --package-depth: {}",
+                                depth
+                            ))
+                            .into())
+                        }
+                    },
+                    None => None,
+                }),
+                Some(group_by) => {
+                    return Err(Failure::usage(format!(
+                        "unknown --group-by: {} (expected package)",
+                        group_by
+                    ))
+                    .into())
+                }
+                None if args.flag("package-depth") => {
+                    return Err(Failure::usage("--package-depth needs --group-by package").into())
+                }
+                None => None,
+            };
+            let found = report::run(
+                &inputs,
+                &sections,
+                registry,
+                finding_policy(&args)?,
+                &classpath,
+                system,
+                &outputs,
+                time_limit,
+                package_depth,
+                ScanOptions::from_args(&args)?,
+            )?;
+            if found && args.flag("check") {
+                return Ok(ExitCode::Violations);
+            }
+        }
+        "string-building" => {
+            let inputs = args.positionals();
+            if inputs.is_empty() {
+                return Err(usage().into());
+            }
+            let top = match args.values("top").pop() {
+                Some(top) => top
+                    .parse()
+                    .map_err(|_| Failure::usage(format!("invalid --top: {}", top)))?,
+                None => 20,
+            };
+            stringbuilding::run(
+                &inputs,
+                top,
+                args.flag("friendly-names"),
+                ScanOptions::from_args(&args)?,
+            )?;
+        }
+        "scan" => {
+            let inputs = args.positionals();
+            if inputs.is_empty() {
+                return Err(usage().into());
+            }
+            let report = args.values("report").pop().unwrap_or("versions");
+            if !scanreport::REPORTS.contains(&report) {
+                return Err(Failure::usage(format!(
+                    "unknown report: {} (expected {})",
+                    report,
+                    scanreport::REPORTS.join(", ")
+                ))
+                .into());
+            }
+            let jobs = match args.values("jobs").pop() {
+                Some(jobs) => match jobs.parse() {
+                    Ok(jobs) if jobs > 0 => jobs,
+                    _ => return Err(Failure::usage(format!("invalid --jobs: {}", jobs)).into()),
+                },
+                None => parallel::default_jobs(),
+            };
+            let top = match args.values("top").pop() {
+                Some(top) => Some(
+                    top.parse()
+                        .map_err(|_| Failure::usage(format!("invalid --top: {}", top)))?,
+                ),
+                None => None,
+            };
+            let mut options = ScanOptions::from_args(&args)?;
+            if args.flag("no-jars") {
+                options.exclude.push("*.jar".to_string());
+            }
+            scanreport::run(&inputs, report, top, jobs, options)?;
+        }
+        "symbols" => {
+            let inputs = args.positionals();
+            if inputs.is_empty() {
+                return Err(usage().into());
+            }
+            symbols::run(
+                &inputs,
+                &Outputs::from_args(&args, command, symbols::FORMATS)?,
+                name_mapper(&args)?.as_ref(),
+                ScanOptions::from_args(&args)?,
+            )?;
+        }
+        "module" => {
+            let file_name = args.positional(0).ok_or_else(usage)?;
+            modules::describe(&read_class_file(file_name, mmap)?)?;
+        }
+        "modules" => {
+            let inputs = args.positionals();
+            if inputs.is_empty() {
+                return Err(usage().into());
+            }
+            return modules::run(&inputs, args.flag("dot"));
+        }
+        "native-config" => {
+            let output = args.values("output").pop().ok_or_else(usage)?;
+            let inputs = args.positionals();
+            if inputs.is_empty() {
+                return Err(usage().into());
+            }
+            nativeconfig::run(
+                &inputs,
+                &args.values("include-annotated"),
+                Path::new(output),
+                ScanOptions::from_args(&args)?,
+            )?;
+        }
+        "split-packages" => {
+            let jars = args.positionals();
+            if jars.is_empty() {
+                return Err(usage().into());
+            }
+            let outputs = Outputs::from_args(&args, command, splitpackages::FORMATS)?;
+            return splitpackages::run(&jars, &args.values("allow"), &outputs);
+        }
+        "set-const" => {
+            let (file_name, output) = match (args.positional(0), args.values("output").pop()) {
+                (Some(file_name), Some(output)) => (file_name, output),
+                _ => return Err(usage().into()),
+            };
+            let kinds = ["int", "long", "float", "double", "utf8"];
+            let values: Vec<(&str, &str)> = kinds
+                .iter()
+                .filter_map(|&kind| Some((kind, args.values(kind).pop()?)))
+                .collect();
+            let target = match (
+                args.values("index").pop(),
+                args.values("field").pop(),
+                &values[..],
+            ) {
+                (Some(index), None, &[(kind, value)]) => {
+                    let index = index
+                        .parse()
+                        .map_err(|_| Failure::usage(format!("invalid --index: {}", index)))?;
+                    setconst::Target::Index(index, setconst::parse_value(kind, value)?)
+                }
+                (None, Some(field), []) => {
+                    let (name, value) = field.split_once('=').ok_or_else(|| {
+                        Failure::usage(format!("expected --field <name>=<value>: {}", field))
+                    })?;
+                    setconst::Target::Field(name, value)
+                }
+                _ => return Err(usage().into()),
+            };
+            let shared = if args.flag("duplicate-if-shared") {
+                SharedConstant::Duplicate
+            } else {
+                SharedConstant::Refuse
+            };
+            setconst::run(
+                read_class_file(file_name, mmap)?,
+                file_name,
+                target,
+                shared,
+                output,
+            )?;
+        }
+        "rename" => {
+            let (rules, input, output) = match (
+                args.values("rules").pop(),
+                args.positional(0),
+                args.values("output").pop(),
+            ) {
+                (Some(rules), Some(input), Some(output)) => (rules, input, output),
+                _ => return Err(usage().into()),
+            };
+            rename::run(rules, input, output)?;
+        }
+        "scrub-paths" => {
+            let (input, output) = match (args.positional(0), args.values("output").pop()) {
+                (Some(input), Some(output)) => (input, output),
+                _ => return Err(usage().into()),
+            };
+            let options = ScrubOptions {
+                drop_debug_extension: args.flag("drop-source-debug-extension"),
+            };
+            scrubpaths::run(input, output, options)?;
+        }
+        "strip" => {
+            let (file_name, output) = match (args.positional(0), args.values("output").pop()) {
+                (Some(file_name), Some(output)) => (file_name, output),
+                _ => return Err(usage().into()),
+            };
+            let names: Vec<&str> = match args.values("attributes").pop() {
+                Some(names) => names.split(',').map(str::trim).collect(),
+                None => DEBUG_ATTRIBUTES.to_vec(),
+            };
+            if let Some(unknown) = names.iter().find(|name| !DEBUG_ATTRIBUTES.contains(name)) {
+                return Err(Failure::usage(format!(
+                    "not a debugging attribute: {} (expected {})",
+                    unknown,
+                    DEBUG_ATTRIBUTES.join(", ")
+                ))
+                .into());
+            }
+            let mut class_file = read_class_file(file_name, mmap)?;
+            let removed = class_file.strip_attributes(&names);
+            let cleared = if args.flag("clear-unused-utf8") {
+                class_file.clear_unused_utf8()
+            } else {
+                0
+            };
+            std::fs::write(output, class_file.to_bytes()?)?;
+            println!(
+                "{}: {} attributes removed, {} unused UTF8 constants cleared",
+                output, removed, cleared
+            );
+        }
+        "set-string" => {
+            let (file_name, output) = match (args.positional(0), args.values("output").pop()) {
+                (Some(file_name), Some(output)) => (file_name, output),
+                _ => return Err(usage().into()),
+            };
+            let from = args.values("match").pop().ok_or_else(usage)?;
+            let to = args.values("replace").pop().ok_or_else(usage)?;
+            let shared = if args.flag("force-literal-only") {
+                SharedLiteral::Split
+            } else {
+                SharedLiteral::Refuse
+            };
+            let mut class_file = read_class_file(file_name, mmap)?;
+            let rewritten = class_file
+                .replace_in_string_literals(from, to, shared)
+                .map_err(|e| {
+                    format!(
+                        "{}: {} (use --force-literal-only to split it)",
+                        file_name, e
+                    )
+                })?;
+            std::fs::write(output, class_file.to_bytes()?)?;
+            println!("{}: {} string constants rewritten", output, rewritten.len());
+        }
+        "selftest" => {
+            let sample = match args.values("sample").pop() {
+                Some(value) => value
+                    .parse()
+                    .map_err(|_| Failure::usage(format!("invalid --sample: {}", value)))?,
+                None => 500,
+            };
+            return selftest::run(
+                args.values("system").pop().map(PathBuf::from),
+                &args.positionals(),
+                sample,
+            );
+        }
+        "schema" => println!("{}", export::schema().pretty()),
+        "verify" if args.flag("module") => {
+            let input = args.positional(0).ok_or_else(usage)?;
+            let classpath = classpath(&args);
+            let classpath: Vec<&str> = classpath.iter().map(String::as_str).collect();
+            let mut policy = finding_policy(&args)?;
+            let exit_code = modules::verify(
+                input,
+                &classpath,
+                args.flag("check"),
+                FindingFormat::from_args(&args)?,
+                &mut policy,
+                ScanOptions::from_args(&args)?,
+            )?;
+            report_suppressions(&policy, "V");
+            return Ok(exit_code);
+        }
+        "verify" if args.flag("archive") => {
+            let input = args.positional(0).ok_or_else(usage)?;
+            let mut policy = finding_policy(&args)?;
+            let exit_code = archive::verify(
+                input,
+                args.flag("check"),
+                FindingFormat::from_args(&args)?,
+                &mut policy,
+            )?;
+            report_suppressions(&policy, "V");
+            return Ok(exit_code);
+        }
+        "verify" => {
+            let file_name = args.positional(0).ok_or_else(usage)?;
+            let format = FindingFormat::from_args(&args)?;
+            let mut policy = finding_policy(&args)?;
+            let class_file = read_class_file(file_name, mmap)?;
+            let class_name = class_file.class_name().unwrap_or("");
+            let violations: Vec<_> = verify::verify(&class_file)
+                .into_iter()
+                .filter_map(|violation| {
+                    let severity =
+                        policy.resolve(violation.code, class_name, violation.severity.into())?;
+                    Some((violation, severity))
+                })
+                .collect();
+            report_suppressions(&policy, "V");
+            if args.flag("check") {
+                if violations
+                    .iter()
+                    .any(|(_, severity)| *severity != rdjson::Severity::Info)
+                {
+                    return Ok(ExitCode::Violations);
+                }
+            } else if format == FindingFormat::Annotations {
+                let locator = SourceLocator::new(&class_file, file_name);
+                for (violation, severity) in violations {
+                    let method = violation.method().and_then(|i| class_file.methods.get(i));
+                    let diagnostic = Diagnostic {
+                        severity,
+                        code: violation.code.to_string(),
+                        message: format!("{}: {}", violation.location, violation.message),
+                        location: match method {
+                            Some(method) => locator.method(method),
+                            None => locator.class(),
+                        },
+                    };
+                    println!("{}", diagnostic.to_json());
+                }
+            } else {
+                for (violation, severity) in violations {
+                    println!(
+                        "{:?}: {}: {} [{}]",
+                        severity, violation.location, violation.message, violation.code
+                    );
+                }
+            }
+        }
+        file_name if file_name.ends_with(".jar") => match args.values("class").pop() {
+            Some(class_name) => {
+                let release = scan::release(&args)?;
+                let class_file = archive::read_class(file_name, class_name, release, mmap)?;
+                if args.flag("json") {
+                    println!("{}", export::class_file(&class_file).pretty());
+                } else {
+                    print!("{}", javap::javap(&class_file));
+                }
+            }
+            None => return archive::list(file_name, scan::release(&args)?, mmap),
+        },
+        file_name if args.flag("concatenated") => {
+            let buf = read_input(file_name, mmap)?;
+            let mut documents = Vec::new();
+            for class in ClassFile::parse_many(&buf) {
+                let (range, class_file) =
+                    class.map_err(|e| Failure::parse(format!("{}: {}", file_name, e)))?;
+                if args.flag("json") {
+                    documents.push(export::class_file(&class_file));
+                } else {
+                    println!("// bytes {}..{}", range.start, range.end);
+                    print!("{}", javap::javap(&class_file));
+                }
+            }
+            if args.flag("json") {
+                println!("{}", Json::Array(documents).pretty());
+            }
+        }
+        file_name if args.flag("salvage") => {
+            let buf = read_input(file_name, mmap)?;
+            let salvaged = ClassFile::parse_salvage(&buf);
+            if let Some(truncation) = &salvaged.truncation {
+                eprintln!("{}: partial result, {}", file_name, truncation);
+            }
+            if args.flag("json") {
+                let mut document = export::class_file(&salvaged.class_file);
+                if let Some(truncation) = &salvaged.truncation {
+                    document.push("truncation", export::truncation(truncation));
+                }
+                println!("{}", document.pretty());
+            } else {
+                print!("{}", javap::javap(&salvaged.class_file));
+            }
+        }
+        // a word which is no file is most likely a misspelt command
+        file_name
+            if file_name != "-"
+                && !file_name.contains(['.', '/', std::path::MAIN_SEPARATOR])
+                && !Path::new(file_name).exists() =>
+        {
+            return Err(
+                Failure::usage(format!("unknown command: {}\n\n{}", file_name, USAGE)).into(),
+            );
+        }
+        file_name => {
+            let class_file = read_class_file(file_name, mmap)?;
+            if args.flag("validate") {
+                let errors = class_file.validate();
+                for error in &errors {
+                    println!("{}", error);
+                }
+                if !errors.is_empty() {
+                    return Ok(ExitCode::Violations);
+                }
+            } else if args.flag("json") {
+                println!("{}", export::class_file(&class_file).pretty());
+            } else {
+                print!("{}", javap::javap(&class_file));
+            }
+        }
+    }
+
+    Ok(ExitCode::Success)
+}
+
+fn usage() -> Failure {
+    Failure::usage(USAGE)
+}
+
+/// The entries of every `--classpath`, each a list in the platform's path
+/// syntax.
+fn classpath(args: &Args) -> Vec<String> {
+    args.values("classpath")
+        .iter()
+        .flat_map(std::env::split_paths)
+        .map(|path| path.display().to_string())
+        .collect()
+}
+
+/// The thresholds of `--jit-huge-method-limit` and `--jit-inline-limit`,
+/// HotSpot's defaults for those not given.
+fn jit_thresholds(args: &Args) -> Result<JitThresholds, Box<dyn Error>> {
+    let mut thresholds = JitThresholds::default();
+    for (name, threshold) in [
+        ("jit-huge-method-limit", &mut thresholds.huge_method),
+        ("jit-inline-limit", &mut thresholds.inline),
+    ] {
+        if let Some(value) = args.values(name).pop() {
+            *threshold = value
+                .parse()
+                .map_err(|_| Failure::usage(format!("invalid --{}: {}", name, value)))?;
+        }
+    }
+    Ok(thresholds)
+}
+
+/// The mapping of `--mapping`, if any.
+fn name_mapper(args: &Args) -> Result<Option<NameMapper>, Box<dyn Error>> {
+    let path = match args.values("mapping").pop() {
+        Some(path) => path,
+        None => return Ok(None),
+    };
+    let text =
+        std::fs::read_to_string(path).map_err(|e| Failure::usage(format!("{}: {}", path, e)))?;
+    let mapper =
+        NameMapper::parse(&text).map_err(|e| Failure::usage(format!("{}: {}", path, e)))?;
+    Ok(Some(mapper))
+}
+
+/// The classes whose switch maps `disasm --resolve-enum-switches` looks in:
+/// those in the directory of `file_name`, where javac puts the switch map
+/// classes of the classes it compiles, and on the `--classpath`.
+fn enum_switch_index(file_name: &str, args: &Args) -> Result<ClassIndex, Box<dyn Error>> {
+    let directory = match Path::new(file_name).parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.display().to_string(),
+        _ => ".".to_string(),
+    };
+    let mut searched = vec![directory];
+    searched.extend(classpath(args));
+    let searched: Vec<&str> = searched.iter().map(String::as_str).collect();
+    index_classes(&searched, ScanOptions::from_args(args)?)
+}
+
+/// The limits of `--max-instructions-per-method`, `--max-string-len` and
+/// `--max-methods`, or none at all with `--full`.
+fn print_options(args: &Args) -> Result<PrintOptions, Box<dyn Error>> {
+    if args.flag("full") {
+        return Ok(PrintOptions::full());
+    }
+    let limit = |name: &str| match args.values(name).pop() {
+        Some(value) => value
+            .parse()
+            .map(Some)
+            .map_err(|_| Failure::usage(format!("invalid --{}: {}", name, value))),
+        None => Ok(None),
+    };
+    Ok(PrintOptions {
+        max_instructions_per_method: limit("max-instructions-per-method")?,
+        max_string_len: limit("max-string-len")?,
+        max_methods: limit("max-methods")?,
+    })
+}
+
+/// The platform modules of the JDK named by `--system`, or else by
+/// `JAVA_HOME`.
+fn system_modules(args: &Args) -> Result<Option<SystemModules>, Box<dyn Error>> {
+    let java_home = args
+        .values("system")
+        .pop()
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("JAVA_HOME").map(PathBuf::from));
+    match java_home {
+        Some(java_home) => {
+            Ok(Some(SystemModules::open(&java_home).map_err(|e| {
+                Failure::parse(format!("{}: {}", java_home.display(), e))
+            })?))
+        }
+        None => {
+            eprintln!("no --system or JAVA_HOME; platform classes will not be found");
+            Ok(None)
+        }
+    }
+}
+
+/// Builds the redaction policy shared by every command that prints string
+/// constants.
+fn redactor(args: &Args) -> Result<Redactor, Box<dyn Error>> {
+    let mut redactor = Redactor::new();
+    for pattern in args.values("redact") {
+        redactor
+            .add_pattern(pattern)
+            .map_err(|e| Failure::usage(e.to_string()))?;
+    }
+    redactor.redact_all_strings(args.flag("redact-all-strings"));
+    Ok(redactor)
+}
+
+/// Reads and parses the class `file_name`, or standard input when that's
+/// `-`, mapping the file into memory with `mmap`.
+fn read_class_file(file_name: &str, mmap: bool) -> Result<ClassFile, Box<dyn Error>> {
+    let input = read_input(file_name, mmap)?;
+    parse_class_file(&input, file_name)
+}
+
+fn read_input(file_name: &str, mmap: bool) -> Result<Input, Box<dyn Error>> {
+    let input = match file_name {
+        "-" => Input::stdin(),
+        _ => Input::open(file_name, mmap),
+    };
+    Ok(input.map_err(|e| Failure::parse(format!("{}: {}", file_name, e)))?)
+}
+
+/// The name and descriptor of `method` as reports print them. With
+/// `friendly_names`, a name the compiler made up is replaced by a readable
+/// one, set apart from the descriptor.
+fn method_label(class_file: &ClassFile, method: &MethodInfo, friendly_names: bool) -> String {
+    let constant_pool = &class_file.constant_pool;
+    let descriptor = ConstantPool::utf8(constant_pool, method.descriptor_index()).unwrap_or("");
+    match friendly_names
+        .then(|| friendly::friendly_method_name(class_file, method))
+        .flatten()
+    {
+        Some(friendly_name) => format!("{} {}", friendly_name, descriptor),
+        None => format!(
+            "{}{}",
+            ConstantPool::utf8(constant_pool, method.name_index()).unwrap_or("?"),
+            descriptor
+        ),
+    }
+}
+
+fn parse_class_file(buf: &[u8], name: &str) -> Result<ClassFile, Box<dyn Error>> {
+    scan::warn_unsupported_version(name, buf);
+    let (_, class_file) = ClassFile::parse_class_file(buf)
+        .map_err(|e| Failure::parse(format!("{}: {}", name, scan::parse_error(buf, e).message)))?;
+    Ok(class_file)
+}
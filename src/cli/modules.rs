@@ -0,0 +1,354 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    error::Error,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    attribute::ResolvedExports,
+    classfile::ClassFile,
+    jar::JarFile,
+    modules::{self, JarModule, ModuleKind},
+    policy::Policy,
+    rdjson::{Diagnostic, Severity, SourceLocation},
+};
+
+use super::{
+    exit::{ExitCode, Failure},
+    output::{severity_prefix, FindingFormat},
+    scan::{index_classes, read_dir_sorted, ScanOptions},
+};
+
+/// Prints the module each jar among `inputs` (jars or directories of them)
+/// defines, then the requirements of explicit modules which can't be met
+/// by an explicit module among them. Platform modules (`java.*`, `jdk.*`)
+/// are assumed present. With `dot`, prints the requires graph as a DOT
+/// digraph instead.
+pub fn run(inputs: &[&str], dot: bool) -> Result<ExitCode, Box<dyn Error>> {
+    let mut jars = Vec::new();
+    for input in inputs {
+        collect_jars(Path::new(input), &mut jars)
+            .map_err(|e| Failure::parse(format!("{}: {}", input, e)))?;
+    }
+
+    let mut failed = false;
+    let mut found: Vec<(String, JarModule)> = Vec::new();
+    for path in &jars {
+        let name = path.display().to_string();
+        let file_name = path.file_name().map_or(name.clone(), |file_name| {
+            file_name.to_string_lossy().into_owned()
+        });
+        match JarFile::open(path)
+            .map_err(Into::into)
+            .and_then(|jar| modules::jar_module(&file_name, &jar))
+        {
+            Ok(module) => found.push((name, module)),
+            Err(e) => {
+                eprintln!("{}: {}", name, e);
+                failed = true;
+            }
+        }
+    }
+
+    let mut by_name: BTreeMap<&str, Vec<&JarModule>> = BTreeMap::new();
+    for (_, module) in &found {
+        by_name.entry(&module.name).or_default().push(module);
+    }
+    let mut problems = Vec::new();
+    for (name, modules) in &by_name {
+        if modules.len() > 1 {
+            problems.push(format!("{}: defined by {} jars", name, modules.len()));
+        }
+    }
+    let mut edges = Vec::new();
+    for (_, module) in &found {
+        let requires = match &module.kind {
+            ModuleKind::Explicit { requires } => requires,
+            _ => continue,
+        };
+        for target in requires {
+            if target.starts_with("java.") || target.starts_with("jdk.") {
+                continue;
+            }
+            edges.push((module.name.as_str(), target.as_str()));
+            let explicit = by_name.get(target.as_str()).map(|targets| {
+                targets
+                    .iter()
+                    .any(|target| matches!(target.kind, ModuleKind::Explicit { .. }))
+            });
+            match explicit {
+                Some(true) => {}
+                Some(false) => problems.push(format!(
+                    "{} requires {}: only an automatic module",
+                    module.name, target
+                )),
+                None => problems.push(format!("{} requires {}: missing", module.name, target)),
+            }
+        }
+    }
+
+    if dot {
+        println!("digraph modules {{");
+        for (name, modules) in &by_name {
+            let explicit = modules
+                .iter()
+                .any(|module| matches!(module.kind, ModuleKind::Explicit { .. }));
+            let style = if explicit { "" } else { ", style=dashed" };
+            println!("  \"{}\" [shape=box{}];", name, style);
+        }
+        let missing: BTreeSet<&str> = edges
+            .iter()
+            .map(|(_, target)| *target)
+            .filter(|target| !by_name.contains_key(target))
+            .collect();
+        for target in missing {
+            println!("  \"{}\" [color=red];", target);
+        }
+        for (from, to) in &edges {
+            println!("  \"{}\" -> \"{}\";", from, to);
+        }
+        println!("}}");
+    } else {
+        println!("{:<40} {:<24} JAR", "MODULE", "KIND");
+        for (jar, module) in &found {
+            let kind = match module.kind {
+                ModuleKind::Explicit { .. } => "explicit",
+                ModuleKind::AutomaticFromManifest => "automatic (manifest)",
+                ModuleKind::AutomaticFromFileName => "automatic (file name)",
+            };
+            println!("{:<40} {:<24} {}", module.name, kind, jar);
+        }
+        if !problems.is_empty() {
+            println!();
+            for problem in &problems {
+                println!("{}", problem);
+            }
+        }
+    }
+
+    if failed {
+        return Err(Failure::parse("some jars couldn't be read").into());
+    }
+    Ok(ExitCode::Success)
+}
+
+/// Checks the descriptor of the modular jar `input` against its classes
+/// and the services it names against those of the jar and `classpath`,
+/// printing its packages and then every finding with why it matters, or
+/// with `--format annotations` just the findings, placed in the source of
+/// the descriptor. The `policy` sets the severity of each finding, or
+/// suppresses it. With `check`, prints nothing and reports findings which
+/// are more than information through the exit status.
+pub fn verify(
+    input: &str,
+    classpath: &[&str],
+    check: bool,
+    format: FindingFormat,
+    policy: &mut Policy,
+    options: ScanOptions,
+) -> Result<ExitCode, Box<dyn Error>> {
+    let jar = JarFile::open(input).map_err(|e| Failure::parse(format!("{}: {}", input, e)))?;
+    let searched: Vec<&str> = std::iter::once(input)
+        .chain(classpath.iter().copied())
+        .collect();
+    let index = index_classes(&searched, options)?;
+    let module = modules::check_module(&jar, &index)
+        .map_err(|e| Failure::parse(format!("{}: {}", input, e)))?;
+    let findings: Vec<_> = module
+        .findings
+        .iter()
+        .filter_map(|finding| {
+            let severity = policy.resolve(finding.code(), "module-info", Severity::Error)?;
+            Some((finding, severity))
+        })
+        .collect();
+    if check {
+        let found = findings
+            .iter()
+            .any(|(_, severity)| *severity != Severity::Info);
+        return Ok(if !found {
+            ExitCode::Success
+        } else {
+            ExitCode::Violations
+        });
+    }
+    if format == FindingFormat::Annotations {
+        let location = match &module.source_file {
+            Some(source_file) => SourceLocation {
+                path: source_file.clone(),
+                line: 1,
+                note: None,
+            },
+            None => SourceLocation::unmapped(
+                &format!("{}!{}", input, module.entry),
+                "the module descriptor has no SourceFile attribute",
+            ),
+        };
+        for (finding, severity) in findings {
+            let diagnostic = Diagnostic {
+                severity,
+                code: finding.code().to_string(),
+                message: format!("{}. {}", finding, finding.explanation()),
+                location: location.clone(),
+            };
+            println!("{}", diagnostic.to_json());
+        }
+        return Ok(ExitCode::Success);
+    }
+
+    println!("module {}", module.name);
+    println!();
+    println!(
+        "{:<48} {:>7}  {:<8} {:<6} LISTED",
+        "PACKAGE", "CLASSES", "EXPORTED", "OPENED"
+    );
+    let yes_no = |value| if value { "yes" } else { "no" };
+    for (name, status) in &module.packages {
+        let name = if name.is_empty() { "(unnamed)" } else { name };
+        println!(
+            "{:<48} {:>7}  {:<8} {:<6} {}",
+            name,
+            status.classes,
+            yes_no(status.exported),
+            yes_no(status.opened),
+            yes_no(status.listed)
+        );
+    }
+    for (finding, severity) in findings {
+        println!();
+        println!(
+            "{}{} [{}]",
+            severity_prefix(severity),
+            finding,
+            finding.code()
+        );
+        println!("  {}", finding.explanation());
+    }
+    Ok(ExitCode::Success)
+}
+
+fn collect_jars(path: &Path, jars: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    if path.is_dir() {
+        for child in read_dir_sorted(path)? {
+            if child.is_dir() || child.extension().is_some_and(|ext| ext == "jar") {
+                collect_jars(&child, jars)?;
+            }
+        }
+    } else {
+        jars.push(path.to_path_buf());
+    }
+    Ok(())
+}
+
+/// Prints the module `class_file` declares, as `java --describe-module`
+/// does: the directives of each kind sorted, packages and classes with
+/// dots, and the packages neither exported nor opened as `contains`.
+pub fn describe(class_file: &ClassFile) -> Result<(), Box<dyn Error>> {
+    let module = class_file
+        .module()
+        .ok_or_else(|| Failure::parse("not a module descriptor: no Module attribute"))?;
+    let dotted = |name: &str| name.replace('/', ".");
+    // " transitive static", as java names the flags
+    let modifiers = |names: Vec<&str>| -> String {
+        let mut names: Vec<String> = names
+            .iter()
+            .map(|name| match *name {
+                "ACC_STATIC_PHASE" => "static".to_string(),
+                name => name.trim_start_matches("ACC_").to_lowercase(),
+            })
+            .collect();
+        names.sort_unstable();
+        names.iter().map(|name| format!(" {}", name)).collect()
+    };
+    let directive = |keyword: &str, exports: &ResolvedExports| {
+        let line = format!(
+            "{} {}{}",
+            keyword,
+            dotted(exports.package),
+            modifiers(exports.flags.names())
+        );
+        if exports.to.is_empty() {
+            return line;
+        }
+        let mut to = exports.to.clone();
+        to.sort_unstable();
+        format!("qualified {} to {}", line, to.join(" "))
+    };
+
+    let mut heading = module.name.to_string();
+    if let Some(version) = module.version {
+        heading = format!("{}@{}", heading, version);
+    }
+    if module.flags.is_open() {
+        heading.push_str(" open");
+    }
+    println!("{}", heading);
+
+    let (qualified_exports, exports): (Vec<_>, Vec<_>) = module
+        .exports
+        .iter()
+        .partition(|exports| !exports.to.is_empty());
+    let (qualified_opens, opens): (Vec<_>, Vec<_>) =
+        module.opens.iter().partition(|opens| !opens.to.is_empty());
+    let mut concealed: BTreeSet<&str> = module.packages.iter().copied().collect();
+    for exports in module.exports.iter().chain(&module.opens) {
+        concealed.remove(exports.package);
+    }
+    let sections: [Vec<String>; 8] = [
+        exports
+            .into_iter()
+            .map(|exports| directive("exports", exports))
+            .collect(),
+        module
+            .requires
+            .iter()
+            .map(|requires| {
+                format!(
+                    "requires {}{}",
+                    requires.module,
+                    modifiers(requires.flags.names())
+                )
+            })
+            .collect(),
+        module
+            .uses
+            .iter()
+            .map(|&service| format!("uses {}", dotted(service)))
+            .collect(),
+        module
+            .provides
+            .iter()
+            .map(|provides| {
+                let with: Vec<String> = provides.with.iter().map(|&class| dotted(class)).collect();
+                format!(
+                    "provides {} with {}",
+                    dotted(provides.service),
+                    with.join(" ")
+                )
+            })
+            .collect(),
+        qualified_exports
+            .into_iter()
+            .map(|exports| directive("exports", exports))
+            .collect(),
+        opens
+            .into_iter()
+            .map(|opens| directive("opens", opens))
+            .collect(),
+        qualified_opens
+            .into_iter()
+            .map(|opens| directive("opens", opens))
+            .collect(),
+        concealed
+            .into_iter()
+            .map(|package| format!("contains {}", dotted(package)))
+            .collect(),
+    ];
+    for mut section in sections {
+        section.sort_unstable();
+        for line in section {
+            println!("{}", line);
+        }
+    }
+    Ok(())
+}
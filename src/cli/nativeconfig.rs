@@ -0,0 +1,126 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    error::Error,
+    fs,
+    path::Path,
+};
+
+use crate::{constantpool::ConstantPool, descriptor::MethodDescriptor, json::Json, reflection};
+
+use super::scan::{parse_entry, Scan, ScanOptions};
+
+/// A native method by name and Java parameter types.
+type NativeMethod = (String, Vec<String>);
+
+/// Writes GraalVM native-image `reflect-config.json`, `jni-config.json` and
+/// `resource-config.json` skeletons for the classes among `inputs` to the
+/// `output` directory. Classes looked up by literal name, and classes
+/// carrying any of the `include_annotated` annotations (given by binary
+/// name) on themselves or a member, are registered for reflection; the
+/// latter with all their members. Classes declaring native methods go in
+/// the JNI config.
+pub fn run(
+    inputs: &[&str],
+    include_annotated: &[&str],
+    output: &Path,
+    options: ScanOptions,
+) -> Result<(), Box<dyn Error>> {
+    let annotation_descriptors: Vec<String> = include_annotated
+        .iter()
+        .map(|name| format!("L{};", name.replace('.', "/")))
+        .collect();
+    // binary name -> whether all members are registered
+    let mut reflected: BTreeMap<String, bool> = BTreeMap::new();
+    let mut native: BTreeMap<String, BTreeSet<NativeMethod>> = BTreeMap::new();
+    let mut resources = BTreeSet::new();
+
+    let mut scan = Scan::new(options);
+    scan.run(inputs, |_, buf, parse_options| {
+        let class_file = parse_entry(buf, parse_options)?;
+        let constant_pool = &class_file.constant_pool;
+        let class_name = ConstantPool::class_name(constant_pool, class_file.this_class)
+            .unwrap_or("?")
+            .replace('/', ".");
+
+        let uses = reflection::reflective_uses(&class_file);
+        for name in uses.classes {
+            reflected.entry(name).or_insert(false);
+        }
+        resources.extend(uses.resources);
+        if annotation_descriptors
+            .iter()
+            .any(|descriptor| reflection::is_annotated_with(&class_file, descriptor))
+        {
+            reflected.insert(class_name.clone(), true);
+        }
+
+        for method in &class_file.methods {
+            if !method.access_flags().is_native() {
+                continue;
+            }
+            let name = ConstantPool::utf8(constant_pool, method.name_index()).unwrap_or("?");
+            let descriptor =
+                ConstantPool::utf8(constant_pool, method.descriptor_index()).unwrap_or("");
+            let parameters = match MethodDescriptor::parse(descriptor) {
+                Ok(descriptor) => descriptor
+                    .parameters
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect(),
+                Err(_) => continue,
+            };
+            native
+                .entry(class_name.clone())
+                .or_default()
+                .insert((name.to_string(), parameters));
+        }
+        Ok(())
+    })?;
+    scan.finish()?;
+
+    let reflect_config = Json::array(reflected.into_iter().map(|(name, all_members)| {
+        let mut entry = Json::object([("name", name.into())]);
+        if all_members {
+            entry.push("allDeclaredConstructors", true);
+            entry.push("allDeclaredMethods", true);
+            entry.push("allDeclaredFields", true);
+        }
+        entry
+    }));
+    let jni_config = Json::array(native.into_iter().map(|(name, methods)| {
+        Json::object([
+            ("name", name.into()),
+            (
+                "methods",
+                Json::array(methods.into_iter().map(|(name, parameters)| {
+                    Json::object([
+                        ("name", name.into()),
+                        ("parameterTypes", Json::array(parameters)),
+                    ])
+                })),
+            ),
+        ])
+    }));
+    let resource_config = Json::object([
+        (
+            "resources",
+            Json::object([(
+                "includes",
+                Json::array(resources.into_iter().map(|resource| {
+                    Json::object([("pattern", format!("\\Q{}\\E", resource).into())])
+                })),
+            )]),
+        ),
+        ("bundles", Json::Array(Vec::new())),
+    ]);
+
+    fs::create_dir_all(output)?;
+    for (file_name, config) in [
+        ("reflect-config.json", reflect_config),
+        ("jni-config.json", jni_config),
+        ("resource-config.json", resource_config),
+    ] {
+        fs::write(output.join(file_name), config.pretty() + "\n")?;
+    }
+    Ok(())
+}
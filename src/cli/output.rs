@@ -0,0 +1,257 @@
+use std::{
+    error::Error,
+    fs::File,
+    io::{self, BufWriter, Write},
+};
+
+use crate::{json::Json, policy::Policy, rdjson::Severity};
+
+use super::{args::Args, exit::Failure};
+
+/// A format results can be rendered in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Text,
+    Json,
+    Csv,
+}
+
+impl Format {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "text" => Some(Format::Text),
+            "json" => Some(Format::Json),
+            "csv" => Some(Format::Csv),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Format::Text => "text",
+            Format::Json => "json",
+            Format::Csv => "csv",
+        }
+    }
+}
+
+/// How `lint`, `metrics` and `verify` print what they find: as text, or
+/// with `--format annotations` as rdjsonl for code review bots.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FindingFormat {
+    Text,
+    Annotations,
+}
+
+impl FindingFormat {
+    pub fn from_args(args: &Args) -> Result<Self, Failure> {
+        match args.values("format").pop() {
+            None | Some("text") => Ok(FindingFormat::Text),
+            Some("annotations") => Ok(FindingFormat::Annotations),
+            Some(format) => Err(Failure::usage(format!(
+                "unknown format: {} (expected text or annotations)",
+                format
+            ))),
+        }
+    }
+}
+
+/// The severities and suppressions of `--severity <code>=<severity>` and
+/// `--suppress <code>[:<class glob>]`, for `lint`, `metrics` and `verify`.
+pub fn finding_policy(args: &Args) -> Result<Policy, Failure> {
+    let mut policy = Policy::default();
+    for value in args.values("severity") {
+        let severity = value
+            .split_once('=')
+            .and_then(|(code, name)| Some((code, Severity::parse(name)?)));
+        match severity {
+            Some((code, severity)) if !code.is_empty() => policy.set_severity(code, severity),
+            _ => {
+                return Err(Failure::usage(format!(
+                    "invalid --severity: {} (expected <code>=<error|warning|info>)",
+                    value
+                )))
+            }
+        }
+    }
+    for value in args.values("suppress") {
+        let (code, classes) = match value.split_once(':') {
+            Some((code, classes)) => (code, Some(classes)),
+            None => (value, None),
+        };
+        if code.is_empty() || classes == Some("") {
+            return Err(Failure::usage(format!(
+                "invalid --suppress: {} (expected <code>[:<class glob>])",
+                value
+            )));
+        }
+        policy.suppress(code, classes);
+    }
+    Ok(policy)
+}
+
+/// Prints to stderr how many findings each suppression of `policy` with a
+/// code starting with `prefix` (one command's, e.g. `L` for lint)
+/// suppressed, so that those which no longer suppress anything stand out.
+pub fn report_suppressions(policy: &Policy, prefix: &str) {
+    let suppressions = policy.suppressions().iter();
+    for suppression in suppressions.filter(|suppression| suppression.code.starts_with(prefix)) {
+        let name = match &suppression.classes {
+            Some(classes) => format!("{}:{}", suppression.code, classes),
+            None => suppression.code.clone(),
+        };
+        match suppression.matched {
+            0 => eprintln!("warning: suppression {} matched no findings", name),
+            1 => eprintln!("suppression {} matched 1 finding", name),
+            matched => eprintln!("suppression {} matched {} findings", name, matched),
+        }
+    }
+}
+
+/// What text output puts before a finding of `severity`.
+pub fn severity_prefix(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Error => "",
+        Severity::Warning => "warning: ",
+        Severity::Info => "info: ",
+    }
+}
+
+/// Rows for CSV, under a header of `columns`.
+pub struct Table {
+    pub columns: &'static [&'static str],
+    pub rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    pub fn new(columns: &'static [&'static str]) -> Self {
+        Table {
+            columns,
+            rows: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, row: Vec<String>) {
+        debug_assert_eq!(row.len(), self.columns.len());
+        self.rows.push(row);
+    }
+
+    /// Writes the table as RFC 4180 CSV: fields holding a comma, quote or
+    /// line break are quoted, with quotes doubled.
+    fn write(&self, out: &mut dyn Write) -> io::Result<()> {
+        let header = self.columns.iter().map(|column| column.to_string());
+        write_csv_row(out, header)?;
+        for row in &self.rows {
+            write_csv_row(out, row.iter().cloned())?;
+        }
+        Ok(())
+    }
+}
+
+fn write_csv_row(out: &mut dyn Write, fields: impl Iterator<Item = String>) -> io::Result<()> {
+    let fields: Vec<String> = fields
+        .map(|field| {
+            if field.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field
+            }
+        })
+        .collect();
+    write!(out, "{}\r\n", fields.join(","))
+}
+
+/// What a command found, computed once and rendered by every sink it's
+/// sent to.
+pub trait Results {
+    fn write_text(&self, out: &mut dyn Write) -> io::Result<()>;
+
+    fn to_json(&self) -> Json;
+
+    /// The results as one table, for commands which support CSV.
+    fn to_table(&self) -> Option<Table> {
+        None
+    }
+}
+
+/// Where a command's results go: text, or JSON with `--json`, on stdout
+/// unless `--quiet`, and a file for every `--out <format>=<file>`.
+pub struct Outputs {
+    stdout: Option<Format>,
+    files: Vec<(Format, String)>,
+}
+
+impl Outputs {
+    /// Reads `--json`, `--quiet` and `--out` from `args`, refusing any
+    /// format `command` doesn't list among its `formats`.
+    pub fn from_args(args: &Args, command: &str, formats: &[Format]) -> Result<Self, Failure> {
+        let stdout = if args.flag("quiet") {
+            None
+        } else if args.flag("json") {
+            Some(Format::Json)
+        } else {
+            Some(Format::Text)
+        };
+        let mut files = Vec::new();
+        for out in args.values("out") {
+            let (format, path) = out.split_once('=').ok_or_else(|| {
+                Failure::usage(format!("expected --out <format>=<file>: {}", out))
+            })?;
+            let format = Format::parse(format)
+                .ok_or_else(|| Failure::usage(format!("unknown format: {}", format)))?;
+            if !formats.contains(&format) {
+                return Err(Failure::usage(format!(
+                    "{} can't write {} (it writes {})",
+                    command,
+                    format.name(),
+                    formats
+                        .iter()
+                        .map(|format| format.name())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )));
+            }
+            files.push((format, path.to_string()));
+        }
+        Ok(Outputs { stdout, files })
+    }
+
+    /// Adds a file to write `format` to, as with `--out`.
+    pub fn add_file(&mut self, format: Format, path: &str) {
+        self.files.push((format, path.to_string()));
+    }
+
+    /// The format written to stdout, if anything is.
+    pub fn stdout(&self) -> Option<Format> {
+        self.stdout
+    }
+
+    /// Renders `results` to stdout and to every file.
+    pub fn write(&self, results: &dyn Results) -> Result<(), Box<dyn Error>> {
+        if let Some(format) = self.stdout {
+            let stdout = io::stdout();
+            let mut out = stdout.lock();
+            render(results, format, &mut out)?;
+            out.flush()?;
+        }
+        for (format, path) in &self.files {
+            let file =
+                File::create(path).map_err(|e| Failure::usage(format!("{}: {}", path, e)))?;
+            let mut out = BufWriter::new(file);
+            render(results, *format, &mut out)?;
+            out.flush()?;
+        }
+        Ok(())
+    }
+}
+
+fn render(results: &dyn Results, format: Format, out: &mut dyn Write) -> io::Result<()> {
+    match format {
+        Format::Text => results.write_text(out),
+        Format::Json => writeln!(out, "{}", results.to_json().pretty()),
+        Format::Csv => match results.to_table() {
+            Some(table) => table.write(out),
+            None => Ok(()),
+        },
+    }
+}
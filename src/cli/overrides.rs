@@ -0,0 +1,50 @@
+use std::error::Error;
+
+use crate::descriptor::MethodDescriptor;
+
+use super::{
+    exit::{ExitCode, Failure},
+    hierarchy::build_index,
+};
+
+/// Prints the methods among `inputs` (classes, jars or directories of
+/// classes) which override `method`, given as `class#name(descriptor)`, by
+/// class. An override reached through a bridge is followed by the bridge.
+pub fn run(method: &str, inputs: &[&str]) -> Result<ExitCode, Box<dyn Error>> {
+    let invalid = || {
+        Failure::usage(format!(
+            "invalid --method {}, expected <class>#<name>(<descriptor>)",
+            method
+        ))
+    };
+    let (class_name, signature) = method.split_once('#').ok_or_else(invalid)?;
+    let (name, descriptor) = signature.split_at(signature.find('(').ok_or_else(invalid)?);
+    MethodDescriptor::parse(descriptor).map_err(|_| invalid())?;
+
+    let index = build_index(inputs)?;
+    let class = index
+        .get(class_name)
+        .map_err(|e| Failure::usage(e.to_string()))?;
+    if !class
+        .methods()
+        .any(|method| method.name() == name && method.descriptor() == descriptor)
+    {
+        return Err(Failure::usage(format!(
+            "{} declares no method {}{}",
+            class.name(),
+            name,
+            descriptor
+        ))
+        .into());
+    }
+    for found in index.overrides_of(class.name(), name, descriptor)? {
+        match &found.bridge {
+            Some(bridge) => println!(
+                "{}#{}{} (bridge {}{})",
+                found.class, name, found.descriptor, name, bridge
+            ),
+            None => println!("{}#{}{}", found.class, name, found.descriptor),
+        }
+    }
+    Ok(ExitCode::Success)
+}
@@ -0,0 +1,49 @@
+use std::error::Error;
+
+use crate::pattern::Pattern;
+
+use super::{
+    method_label,
+    scan::{parse_entry, EntryError, Scan, ScanOptions},
+};
+
+/// Prints every place in the methods of the classes among `inputs` (class
+/// files, jars or directories) where `pattern` matches, with its captures.
+pub fn run(
+    pattern: &Pattern,
+    inputs: &[&str],
+    friendly_names: bool,
+    options: ScanOptions,
+) -> Result<(), Box<dyn Error>> {
+    let mut scan = Scan::new(options);
+    scan.run(inputs, |name, buf, parse_options| {
+        let class_file = parse_entry(buf, parse_options)?;
+        let constant_pool = &class_file.constant_pool;
+        for method in &class_file.methods {
+            let code = match method.code() {
+                Some(code) => code,
+                None => continue,
+            };
+            let matches = code
+                .find_pattern(pattern, constant_pool)
+                .map_err(|e| EntryError::new("decode", e.to_string()))?;
+            for found in matches {
+                let captures: Vec<String> = found
+                    .captures
+                    .iter()
+                    .map(|(name, value)| format!(" ${}={:?}", name, value))
+                    .collect();
+                println!(
+                    "{}: {}: {}..{}{}",
+                    name,
+                    method_label(&class_file, method, friendly_names),
+                    found.offset,
+                    found.end,
+                    captures.concat()
+                );
+            }
+        }
+        Ok(())
+    })?;
+    scan.finish()
+}
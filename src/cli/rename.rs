@@ -0,0 +1,151 @@
+use std::{error::Error, fs};
+
+use crate::{
+    classfile::ClassFile,
+    classindex::ClassIndex,
+    error::ParseError,
+    jar::{JarFile, JarWriter},
+    regex::Regex,
+    remap::{remap, Remapper},
+    rename::{RenameRules, Renamer},
+    toml::Toml,
+};
+
+use super::exit::Failure;
+
+/// The tables of a rules file.
+const TABLES: &[&str] = &["classes", "members", "patterns"];
+
+/// Renames what the rules in `rules_file` say throughout the jar `input`,
+/// writing the renamed jar to `output`. Nothing is written when any rule
+/// conflicts with the classes.
+pub fn run(rules_file: &str, input: &str, output: &str) -> Result<(), Box<dyn Error>> {
+    let text = fs::read_to_string(rules_file)
+        .map_err(|e| Failure::usage(format!("{}: {}", rules_file, e)))?;
+    let rules = parse_rules(&text).map_err(|e| Failure::usage(format!("{}: {}", rules_file, e)))?;
+
+    let jar = JarFile::open(input).map_err(|e| Failure::parse(format!("{}: {}", input, e)))?;
+    let mut index = ClassIndex::new();
+    let mut classes = Vec::new();
+    for entry in jar.entries() {
+        if entry.class_name().is_none() || entry.is_dir() {
+            continue;
+        }
+        let location = format!("{}!/{}", input, entry.name);
+        let buf = jar
+            .read(entry)
+            .map_err(|e| Failure::parse(format!("{}: {}", location, e)))?;
+        let (_, class_file) = ClassFile::parse_class_file(&buf)
+            .map_err(|e| Failure::parse(format!("{}: {}", location, ParseError::from(e))))?;
+        index.add_class(&class_file, input);
+        classes.push((entry.name.as_str(), buf, class_file));
+    }
+
+    let renamer = Renamer::new(&rules, &index).map_err(|conflicts| {
+        let lines: Vec<String> = conflicts
+            .iter()
+            .map(|conflict| format!("  {}", conflict))
+            .collect();
+        Failure::usage(format!(
+            "{}: {} conflicts, nothing written:\n{}",
+            rules_file,
+            conflicts.len(),
+            lines.join("\n")
+        ))
+    })?;
+
+    let mut writer = JarWriter::new();
+    let mut classes = classes.into_iter().peekable();
+    let mut changed = 0;
+    let total = classes.len();
+    for entry in jar.entries() {
+        let (buf, class_file) = match classes.peek() {
+            Some((name, ..)) if *name == entry.name => {
+                let (_, buf, class_file) = classes.next().expect("peeked");
+                (buf, class_file)
+            }
+            _ => {
+                writer.copy(&entry.name, entry, jar.raw(entry)?)?;
+                continue;
+            }
+        };
+        let mut class_file = class_file;
+        remap(&mut class_file, &renamer)
+            .map_err(|e| format!("{}!/{}: {}", input, entry.name, e))?;
+        let bytes = class_file.to_bytes()?;
+        if bytes == buf {
+            writer.copy(&entry.name, entry, jar.raw(entry)?)?;
+            continue;
+        }
+        changed += 1;
+        let class_name = entry.class_name().unwrap_or_default();
+        let name = match renamer.class(class_name) {
+            Some(renamed) => {
+                let prefix = &entry.name[..entry.name.len() - class_name.len() - ".class".len()];
+                format!("{}{}.class", prefix, renamed)
+            }
+            None => entry.name.clone(),
+        };
+        writer.add(&name, &bytes)?;
+    }
+    fs::write(output, writer.finish()?)?;
+    println!("{}: {} of {} classes changed", output, changed, total);
+    Ok(())
+}
+
+/// Reads rules like these, with the IDs of members as `symbols` writes
+/// them and patterns matched against class names:
+///
+/// ```toml
+/// [classes]
+/// 'a/b/c' = 'com/foo/Parser'
+///
+/// [members]
+/// 'a/b/c#a(I)V' = 'parseHeader'
+/// 'a/b/c#b:I' = 'offset'
+///
+/// [patterns]
+/// '^a/b/' = 'com/foo/'
+/// ```
+fn parse_rules(text: &str) -> Result<RenameRules, String> {
+    let document = Toml::parse(text).map_err(|e| e.to_string())?;
+    let mut rules = RenameRules::new();
+    let tables = match &document {
+        Toml::Table(tables) => tables,
+        _ => unreachable!("documents are tables"),
+    };
+    for (table, entries) in tables {
+        let entries = match entries {
+            Toml::Table(entries) if TABLES.contains(&table.as_str()) => entries,
+            _ => {
+                return Err(format!(
+                    "unknown table {}, expected one of: {}",
+                    table,
+                    TABLES.join(", ")
+                ))
+            }
+        };
+        for (from, to) in entries {
+            let to = match to {
+                Toml::String(to) => to,
+                value => {
+                    return Err(format!(
+                        "{}.{}: expected a string, found {}",
+                        table,
+                        from,
+                        value.type_name()
+                    ))
+                }
+            };
+            match table.as_str() {
+                "classes" => rules.class(from, to).map_err(|e| e.to_string())?,
+                "members" => rules.member(from, to).map_err(|e| e.to_string())?,
+                _ => rules.pattern(Regex::new(from).map_err(|e| e.to_string())?, to),
+            }
+        }
+    }
+    if rules.is_empty() {
+        return Err("no rules".to_string());
+    }
+    Ok(rules)
+}
@@ -0,0 +1,168 @@
+use std::{
+    error::Error,
+    io::{self, Write},
+    time::Duration,
+};
+
+use crate::{
+    budget::AnalysisBudget,
+    classindex::ClassIndex,
+    deps::SystemModules,
+    json::Json,
+    pipeline::{Pipeline, Registry, Section},
+    policy::Policy,
+    report::{self, Deps, Lint, Metrics, Verify},
+};
+
+use super::{
+    output::{report_suppressions, Format, Outputs, Results, Table},
+    scan::{index_classes, parse_entry, Scan, ScanOptions},
+};
+
+/// The formats `report` writes.
+pub const FORMATS: &[Format] = &[Format::Text, Format::Json, Format::Csv];
+
+/// Runs the analyses named in `sections` over `inputs` (class files, jars
+/// or directories), parsing every class once, and writes the report to
+/// `outputs`: as text, a line about each section; as JSON, the whole
+/// report; as CSV, a row per section. When the JSON goes to stdout, the
+/// text goes to stderr. The `classpath` is only read for `deps`. With a
+/// `time_limit` the analyses which look through method bodies stop once it
+/// has passed, and their sections are marked partial. With a
+/// `package_depth` every section is also rolled up by package, cut to the
+/// depth given if any, in the JSON and under each line of text. The
+/// sections named in `registry` follow the built-in ones, and the findings
+/// of every check are resolved through `policy`. Returns whether any
+/// section found errors or warnings.
+#[allow(clippy::too_many_arguments)]
+pub fn run(
+    inputs: &[&str],
+    sections: &[&str],
+    registry: &Registry,
+    policy: Policy,
+    classpath: &[&str],
+    mut system: Option<SystemModules>,
+    outputs: &Outputs,
+    time_limit: Option<Duration>,
+    package_depth: Option<Option<usize>>,
+    options: ScanOptions,
+) -> Result<bool, Box<dyn Error>> {
+    let mut pipeline = Pipeline::new();
+    pipeline.set_policy(policy);
+    if let Some(depth) = package_depth {
+        pipeline.group_by_package(depth);
+    }
+    if let Some(time_limit) = time_limit {
+        pipeline.set_budget(AnalysisBudget::unlimited().with_timeout(time_limit));
+    }
+    for section in report::SECTIONS {
+        if !sections.contains(section) {
+            continue;
+        }
+        match *section {
+            "verify" => pipeline.add_check(Box::new(Verify::new())),
+            "lint" => pipeline.add_check(Box::new(Lint::new())),
+            "metrics" => pipeline.add(Metrics::new()),
+            _ => {
+                let classpath = if classpath.is_empty() {
+                    ClassIndex::new()
+                } else {
+                    index_classes(classpath, options.clone())?
+                };
+                pipeline.add(Deps::new(system.take(), classpath))
+            }
+        };
+    }
+    for name in registry.names() {
+        if let Some(check) = registry.create(name).filter(|_| sections.contains(&name)) {
+            pipeline.add_check(check);
+        }
+    }
+
+    let mut scan = Scan::with_parse_options(options, pipeline.parse_options());
+    for input in inputs {
+        scan.run(&[input], |name, buf, parse_options| {
+            pipeline.visit(input, name, &parse_entry(buf, parse_options)?);
+            Ok(())
+        })?;
+    }
+    scan.finish()?;
+    report_suppressions(&pipeline.policy(), "");
+
+    let report = Report {
+        inputs,
+        sections: pipeline.finish(),
+    };
+    outputs.write(&report)?;
+    if outputs.stdout() == Some(Format::Json) {
+        report.write_text(&mut io::stderr())?;
+    }
+    Ok(report.sections.iter().any(|section| section.violations > 0))
+}
+
+struct Report<'a> {
+    inputs: &'a [&'a str],
+    sections: Vec<Section>,
+}
+
+impl Results for Report<'_> {
+    fn write_text(&self, out: &mut dyn Write) -> io::Result<()> {
+        for section in &self.sections {
+            writeln!(out, "{:<8} {}", section.name, section.summary)?;
+            let packages = section
+                .document
+                .get("by_package")
+                .and_then(|rollup| rollup.get("packages"));
+            if let Some(Json::Array(packages)) = packages {
+                for package in packages {
+                    write_package(out, package)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn to_json(&self) -> Json {
+        report::document(self.inputs, &self.sections)
+    }
+
+    fn to_table(&self) -> Option<Table> {
+        let mut rows = Table::new(&["section", "status", "summary"]);
+        for section in &self.sections {
+            rows.push(vec![
+                section.name.to_string(),
+                section.status.name().to_string(),
+                section.summary.clone(),
+            ]);
+        }
+        Some(rows)
+    }
+}
+
+/// A line about a package of a rollup: its name, then each measure.
+fn write_package(out: &mut dyn Write, package: &Json) -> io::Result<()> {
+    let members = match package {
+        Json::Object(members) => members,
+        _ => return Ok(()),
+    };
+    let mut name = "";
+    let mut values = Vec::new();
+    for (member, value) in members {
+        match (member.as_str(), value) {
+            ("package", Json::String(package)) => name = package,
+            ("classes", value) => values.push(format!("{} classes", value)),
+            (member, Json::Array(items)) => {
+                let items: Vec<String> = items
+                    .iter()
+                    .map(|item| match item {
+                        Json::String(item) => item.clone(),
+                        item => item.to_string(),
+                    })
+                    .collect();
+                values.push(format!("{} [{}]", member, items.join(", ")));
+            }
+            (member, value) => values.push(format!("{} {}", member, value)),
+        }
+    }
+    writeln!(out, "  {:<30} {}", name, values.join(", "))
+}
@@ -0,0 +1,577 @@
+use std::{
+    any::Any,
+    collections::BTreeMap,
+    error::Error,
+    fmt, fs, io,
+    panic::{self, AssertUnwindSafe},
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+use crate::{
+    classfile::{ClassFile, ParseOptions, UnsupportedVersion},
+    classindex::ClassIndex,
+    error::{ErrorAt, ParseError},
+    input::Input,
+    jar::{JarError, JarFile, Release},
+    metrics::{ParseMetrics, PhaseMetrics},
+    parallel,
+};
+
+use super::{args::Args, exit::Failure};
+
+/// What a scan over many inputs does when an entry fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Policy {
+    /// Stop at the first failure and exit with an error.
+    FailFast,
+    /// Scan everything, then exit with an error if anything failed.
+    FailOnError,
+    /// Scan everything and succeed as long as at least one entry was
+    /// processed.
+    AnyParsed,
+}
+
+#[derive(Debug, Clone)]
+pub struct ScanOptions {
+    pub policy: Policy,
+    /// How many failures to print in full in the summary.
+    pub show_errors: usize,
+    /// Whether to print where parsing spent its time.
+    pub timings: bool,
+    /// Globs for the files in directories and entries in jars to skip.
+    pub exclude: Vec<String>,
+    /// Entries larger than this many bytes fail instead of being parsed.
+    pub max_class_size: Option<usize>,
+    /// Whether to accept attributes longer than their contents.
+    pub lenient_attributes: bool,
+    /// Whether to keep UTF8 constants which aren't modified UTF-8.
+    pub lenient_utf8: bool,
+    /// Whether to reject attributes the class's version predates.
+    pub strict_versions: bool,
+    /// Whether to map files and jars into memory instead of reading them.
+    pub mmap: bool,
+    /// The release multi-release jars are read for.
+    pub release: Release,
+}
+
+impl ScanOptions {
+    /// `--keep-going` is the default for scans; `--fail-fast` and
+    /// `--fail-on-error` pick the stricter policies.
+    pub fn from_args(args: &Args) -> Result<Self, Box<dyn Error>> {
+        let policy = match (args.flag("fail-fast"), args.flag("fail-on-error")) {
+            (true, true) => {
+                return Err(Failure::usage("--fail-fast and --fail-on-error are exclusive").into())
+            }
+            (true, false) if args.flag("keep-going") => {
+                return Err(Failure::usage("--fail-fast and --keep-going are exclusive").into())
+            }
+            (true, false) => Policy::FailFast,
+            (false, true) => Policy::FailOnError,
+            (false, false) => Policy::AnyParsed,
+        };
+        let show_errors = match args.values("show-errors").pop() {
+            Some(count) => count
+                .parse()
+                .map_err(|_| Failure::usage(format!("invalid --show-errors count: {}", count)))?,
+            None => 0,
+        };
+        let max_class_size = match args.values("max-class-size").pop() {
+            Some(size) => Some(
+                size.parse()
+                    .map_err(|_| Failure::usage(format!("invalid --max-class-size: {}", size)))?,
+            ),
+            None => None,
+        };
+        Ok(ScanOptions {
+            policy,
+            show_errors,
+            timings: args.flag("timings"),
+            exclude: args
+                .values("exclude")
+                .into_iter()
+                .map(String::from)
+                .collect(),
+            max_class_size,
+            lenient_attributes: args.flag("lenient-attributes"),
+            lenient_utf8: args.flag("lenient-utf8"),
+            strict_versions: args.flag("strict-versions"),
+            mmap: args.flag("mmap"),
+            release: release(args)?,
+        })
+    }
+}
+
+/// The release of `--release`, a Java version or `latest`, the default.
+pub fn release(args: &Args) -> Result<Release, Box<dyn Error>> {
+    match args.values("release").pop() {
+        None | Some("latest") => Ok(Release::Latest),
+        Some(version) => version
+            .parse()
+            .map(Release::Version)
+            .map_err(|_| Failure::usage(format!("invalid --release: {}", version)).into()),
+    }
+}
+
+/// Why a single scanned entry couldn't be processed. `code` groups similar
+/// failures together in the summary.
+#[derive(Debug)]
+pub struct EntryError {
+    pub code: String,
+    pub message: String,
+}
+
+impl EntryError {
+    pub fn new(code: impl Into<String>, message: impl Into<String>) -> Self {
+        EntryError {
+            code: code.into(),
+            message: message.into(),
+        }
+    }
+
+    fn from_panic(payload: Box<dyn Any + Send>) -> Self {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".to_string());
+        EntryError::new("panic", message)
+    }
+}
+
+impl fmt::Display for EntryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] {}", self.code, self.message)
+    }
+}
+
+impl From<io::Error> for EntryError {
+    fn from(e: io::Error) -> Self {
+        EntryError::new("io", e.to_string())
+    }
+}
+
+impl From<JarError> for EntryError {
+    fn from(e: JarError) -> Self {
+        let code = match e {
+            JarError::Io(_) => "io",
+            JarError::Malformed(_) => "archive",
+            JarError::UnsupportedCompression(_) => "compression",
+            JarError::Inflate(_) => "inflate",
+        };
+        EntryError::new(code, e.to_string())
+    }
+}
+
+/// Parses a scanned entry, reporting failures by the kind of parse error.
+pub fn parse_entry(buf: &[u8], parse_options: &ParseOptions) -> Result<ClassFile, EntryError> {
+    ClassFile::parse_with(buf, parse_options)
+        .map(|(_, class_file)| class_file)
+        .map_err(|e| parse_error(buf, e))
+}
+
+/// The failure to report for a class which didn't parse, by the kind of
+/// [`ParseError`], with the offset it happened at and what was being parsed
+/// there, e.g. `error at offset 0x1A3C while parsing method <init>()V >
+/// Code > StackMapTable > entry 3: ...`. An unknown constant pool tag comes
+/// with the class's version when it's one jvmb doesn't support.
+pub fn parse_error(buf: &[u8], e: nom::Err<ErrorAt<&[u8]>>) -> EntryError {
+    let e = match e {
+        nom::Err::Incomplete(_) => {
+            return EntryError::new("parse/Incomplete", "malformed class file (Incomplete)")
+        }
+        nom::Err::Error(e) | nom::Err::Failure(e) => e,
+    };
+    let mut message = format!("error at offset {:#X}", e.offset_in(buf));
+    if !e.context.is_empty() {
+        message = format!("{} while parsing {}", message, e.breadcrumb());
+    }
+    let message = format!("{}: {}", message, e.code);
+    let message = match (&e.code, UnsupportedVersion::of(buf)) {
+        (ParseError::UnknownConstantTag(_), Some(version)) => {
+            format!("{} ({}, and the tag's size isn't known)", message, version)
+        }
+        _ => message,
+    };
+    EntryError::new(format!("parse/{}", e.code.kind()), message)
+}
+
+/// Warns that the class in `buf` is parsed best-effort, if its version is
+/// newer than jvmb supports.
+pub fn warn_unsupported_version(name: &str, buf: &[u8]) {
+    if let Some(version) = UnsupportedVersion::of(buf) {
+        eprintln!("warning: {}: {}; parsing best-effort", name, version);
+    }
+}
+
+/// Walks class files, jars and directories of either, handing every class
+/// entry to a visitor and collecting per-entry failures according to the
+/// scan policy.
+pub struct Scan {
+    options: ScanOptions,
+    parse_options: ParseOptions,
+    decompression: PhaseMetrics,
+    /// What the threads of [`Scan::run_parallel`] collected, each with
+    /// parse options of its own.
+    parallel_metrics: ParseMetrics,
+    processed: usize,
+    /// In scan order, as [`crate::ordering`] describes it, each with the
+    /// number of entries processed before it.
+    failures: Vec<(String, EntryError, usize)>,
+}
+
+impl Scan {
+    pub fn new(options: ScanOptions) -> Self {
+        Scan::with_parse_options(options, ParseOptions::new())
+    }
+
+    /// A scan which hands entries to the visitor with `parse_options`.
+    pub fn with_parse_options(options: ScanOptions, mut parse_options: ParseOptions) -> Self {
+        apply_options(&options, &mut parse_options);
+        Scan {
+            options,
+            parse_options,
+            decompression: PhaseMetrics::default(),
+            parallel_metrics: ParseMetrics::default(),
+            processed: 0,
+            failures: Vec::new(),
+        }
+    }
+
+    /// Visits every class among `inputs`. `visit` receives the display name
+    /// of the entry (`archive!entry` for jar members), its bytes and the
+    /// options to parse it with. Panics raised while visiting are recorded
+    /// as failures like any other error.
+    pub fn run<F>(&mut self, inputs: &[&str], mut visit: F) -> Result<(), Box<dyn Error>>
+    where
+        F: FnMut(&str, &[u8], &ParseOptions) -> Result<(), EntryError>,
+    {
+        let hook = panic::take_hook();
+        panic::set_hook(Box::new(|_| {}));
+        let result = inputs
+            .iter()
+            .try_for_each(|input| self.visit_path(Path::new(input), &mut visit));
+        panic::set_hook(hook);
+        result
+    }
+
+    /// Visits every class among `inputs` like [`Scan::run`], on `jobs`
+    /// threads, returning what `visit` made of each entry with its name, in
+    /// scan order. The entries are all read first, so that only parsing
+    /// and what `visit` does is spread over the threads. Each entry is
+    /// parsed with options of its own, made from the scan options alone;
+    /// those given to [`Scan::with_parse_options`] aren't used.
+    pub fn run_parallel<F, R>(
+        &mut self,
+        inputs: &[&str],
+        jobs: usize,
+        visit: F,
+    ) -> Result<Vec<(String, R)>, Box<dyn Error>>
+    where
+        F: Fn(&str, &[u8], &ParseOptions) -> Result<R, EntryError> + Sync,
+        R: Send,
+    {
+        let processed = self.processed;
+        let earlier_failures = self.failures.len();
+        let mut entries = Vec::new();
+        self.run(inputs, |name, buf, _| {
+            entries.push((name.to_string(), buf.to_vec()));
+            Ok(())
+        })?;
+        // the entries count as processed once visited, below
+        self.processed = processed;
+
+        let options = &self.options;
+        let hook = panic::take_hook();
+        panic::set_hook(Box::new(|_| {}));
+        let results = parallel::map(&entries, jobs, |(name, buf)| {
+            let mut parse_options = ParseOptions::new();
+            apply_options(options, &mut parse_options);
+            let result = panic::catch_unwind(AssertUnwindSafe(|| visit(name, buf, &parse_options)))
+                .unwrap_or_else(|payload| Err(EntryError::from_panic(payload)));
+            (result, parse_options.metrics())
+        });
+        panic::set_hook(hook);
+
+        // Failures to read entries were recorded as they were found; the
+        // failures to visit them go in among those.
+        let mut read_failures = self
+            .failures
+            .split_off(earlier_failures)
+            .into_iter()
+            .peekable();
+        let mut visited = Vec::new();
+        for (i, ((name, _), (result, metrics))) in entries.into_iter().zip(results).enumerate() {
+            while let Some(failure) = read_failures.next_if(|&(_, _, after)| after <= processed + i)
+            {
+                self.failures.push(failure);
+            }
+            if let Some(metrics) = metrics {
+                self.parallel_metrics.add(&metrics);
+            }
+            match result {
+                Ok(value) => {
+                    self.processed += 1;
+                    visited.push((name, value));
+                }
+                Err(e) => self.record(name, e)?,
+            }
+        }
+        self.failures.extend(read_failures);
+        Ok(visited)
+    }
+
+    fn visit_path<F>(&mut self, path: &Path, visit: &mut F) -> Result<(), Box<dyn Error>>
+    where
+        F: FnMut(&str, &[u8], &ParseOptions) -> Result<(), EntryError>,
+    {
+        let name = path.display().to_string();
+        if path.is_dir() {
+            let mut children = match read_dir_sorted(path) {
+                Ok(children) => children,
+                Err(e) => return self.record(name, e.into()),
+            };
+            children.retain(|child| {
+                (child.is_dir() || is_scannable(child))
+                    && !self.is_excluded(&child.display().to_string())
+            });
+            for child in children {
+                self.visit_path(&child, visit)?;
+            }
+        } else if path.extension().is_some_and(|ext| ext == "jar") {
+            let jar = if self.options.mmap {
+                JarFile::open_mapped(path)
+            } else {
+                JarFile::open(path)
+            };
+            let jar = match jar {
+                Ok(jar) => jar.with_release(self.options.release),
+                Err(e) => return self.record(name, e.into()),
+            };
+            for entry in jar.class_entries() {
+                if self.is_excluded(&entry.name) {
+                    continue;
+                }
+                let entry_name = format!("{}!{}", name, entry.name);
+                let started = self.options.timings.then(Instant::now);
+                let read = jar.read(entry);
+                if let Some(started) = started {
+                    self.decompression.record(started.elapsed(), 1);
+                }
+                match read {
+                    Ok(buf) => self.visit_entry(entry_name, &buf, visit)?,
+                    Err(e) => self.record(entry_name, e.into())?,
+                }
+            }
+        } else {
+            match Input::open(path, self.options.mmap) {
+                Ok(input) => self.visit_entry(name, &input, visit)?,
+                Err(e) => self.record(name, e.into())?,
+            }
+        }
+        Ok(())
+    }
+
+    fn visit_entry<F>(
+        &mut self,
+        name: String,
+        buf: &[u8],
+        visit: &mut F,
+    ) -> Result<(), Box<dyn Error>>
+    where
+        F: FnMut(&str, &[u8], &ParseOptions) -> Result<(), EntryError>,
+    {
+        if let Some(max_class_size) = self.options.max_class_size {
+            if buf.len() > max_class_size {
+                let message = format!("{} bytes, over the limit of {}", buf.len(), max_class_size);
+                return self.record(name, EntryError::new("limit", message));
+            }
+        }
+        warn_unsupported_version(&name, buf);
+        let parse_options = &self.parse_options;
+        match panic::catch_unwind(AssertUnwindSafe(|| visit(&name, buf, parse_options))) {
+            Ok(Ok(())) => {
+                self.processed += 1;
+                Ok(())
+            }
+            Ok(Err(e)) => self.record(name, e),
+            Err(payload) => self.record(name, EntryError::from_panic(payload)),
+        }
+    }
+
+    /// Whether a file or jar entry found while scanning is excluded. Inputs
+    /// named on the command line are always scanned.
+    fn is_excluded(&self, path: &str) -> bool {
+        self.options
+            .exclude
+            .iter()
+            .any(|pattern| exclude_match(pattern, path))
+    }
+
+    fn record(&mut self, name: String, error: EntryError) -> Result<(), Box<dyn Error>> {
+        if self.options.policy == Policy::FailFast {
+            return Err(Failure::parse(format!("{}: {}", name, error)).into());
+        }
+        self.failures.push((name, error, self.processed));
+        Ok(())
+    }
+
+    /// The entries which failed so far, in scan order.
+    pub fn failures(&self) -> impl Iterator<Item = (&str, &EntryError)> {
+        self.failures
+            .iter()
+            .map(|(name, error, _)| (name.as_str(), error))
+    }
+
+    /// Prints the failure summary, and the timings if asked for, to stderr
+    /// and applies the exit policy.
+    pub fn finish(self) -> Result<(), Box<dyn Error>> {
+        if let Some(mut metrics) = self.parse_options.metrics() {
+            metrics.add(&self.parallel_metrics);
+            metrics.decompression = self.decompression;
+            print_timings(&metrics);
+        }
+        if !self.failures.is_empty() {
+            let mut by_code = BTreeMap::new();
+            for (_, error, _) in &self.failures {
+                *by_code.entry(error.code.as_str()).or_insert(0) += 1;
+            }
+            eprintln!(
+                "{} entries processed, {} failed:",
+                self.processed,
+                self.failures.len()
+            );
+            for (code, count) in by_code {
+                eprintln!("  {:>6}  {}", count, code);
+            }
+            for (name, error) in self.failures().take(self.options.show_errors) {
+                eprintln!("{}: {}", name, error);
+            }
+            if self.options.show_errors == 0 {
+                eprintln!("(use --show-errors <n> for details)");
+            } else if self.failures.len() > self.options.show_errors {
+                eprintln!(
+                    "({} more not shown)",
+                    self.failures.len() - self.options.show_errors
+                );
+            }
+        }
+
+        match self.options.policy {
+            Policy::FailOnError if !self.failures.is_empty() => {
+                Err(Failure::parse(format!("{} entries failed", self.failures.len())).into())
+            }
+            Policy::AnyParsed if self.processed == 0 && !self.failures.is_empty() => {
+                Err(Failure::parse("no entries could be processed").into())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Sets the parse options the scan options ask for.
+fn apply_options(options: &ScanOptions, parse_options: &mut ParseOptions) {
+    if options.timings {
+        parse_options.collect_metrics();
+    }
+    if options.lenient_attributes {
+        parse_options.lenient_attribute_lengths();
+    }
+    if options.lenient_utf8 {
+        parse_options.lenient_utf8();
+    }
+    if options.strict_versions {
+        parse_options.strict_attribute_versions();
+    }
+}
+
+fn print_timings(metrics: &ParseMetrics) {
+    let print = |name: &str, phase: &PhaseMetrics| {
+        eprintln!(
+            "  {:<28} {:>10.3} ms {:>10}",
+            name,
+            phase.time.as_secs_f64() * 1000.0,
+            phase.count
+        )
+    };
+    eprintln!("timings:");
+    print("decompression", &metrics.decompression);
+    print("parsing", &metrics.total);
+    print("  constant pool", &metrics.constant_pool);
+    print("  fields", &metrics.fields);
+    print("  methods", &metrics.methods);
+    print("  class attributes", &metrics.class_attributes);
+    eprintln!("attributes:");
+    for (name, phase) in &metrics.attributes {
+        print(name, phase);
+    }
+}
+
+/// Whether `pattern` matches `path` or any of its trailing runs of path
+/// segments, so that `*Test.class` and `gen/**` match wherever they occur.
+/// `*` stands for any run of characters within a segment and `**` for any
+/// run at all.
+fn exclude_match(pattern: &str, path: &str) -> bool {
+    let path = path.replace('\\', "/");
+    glob_match(pattern, &path)
+        || path
+            .match_indices('/')
+            .any(|(slash, _)| glob_match(pattern, &path[slash + 1..]))
+}
+
+fn glob_match(pattern: &str, text: &str) -> bool {
+    // `**/` may also stand for no directory at all
+    if pattern
+        .strip_prefix("**/")
+        .is_some_and(|rest| glob_match(rest, text))
+    {
+        return true;
+    }
+    if let Some(rest) = pattern.strip_prefix("**") {
+        return (0..=text.len())
+            .any(|start| text.is_char_boundary(start) && glob_match(rest, &text[start..]));
+    }
+    if let Some(rest) = pattern.strip_prefix('*') {
+        let segment = text.find('/').unwrap_or(text.len());
+        return (0..=segment)
+            .any(|start| text.is_char_boundary(start) && glob_match(rest, &text[start..]));
+    }
+    match (pattern.chars().next(), text.chars().next()) {
+        (None, None) => true,
+        (Some(p), Some(t)) if p == t => glob_match(&pattern[p.len_utf8()..], &text[t.len_utf8()..]),
+        _ => false,
+    }
+}
+
+fn is_scannable(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext == "class" || ext == "jar")
+}
+
+/// Indexes the classes among `inputs`, naming the input each was found in as
+/// its source. Entries which fail are skipped; they're left for the scan
+/// that does the actual work to report.
+pub fn index_classes(inputs: &[&str], options: ScanOptions) -> Result<ClassIndex, Box<dyn Error>> {
+    let mut index = ClassIndex::new();
+    let mut scan = Scan::new(ScanOptions {
+        policy: Policy::AnyParsed,
+        timings: false,
+        ..options
+    });
+    for input in inputs {
+        scan.run(&[input], |_, buf, parse_options| {
+            let class_file = parse_entry(buf, parse_options)?;
+            index.add_class(&class_file, input);
+            Ok(())
+        })?;
+    }
+    Ok(index)
+}
+
+pub fn read_dir_sorted(path: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut children = fs::read_dir(path)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<io::Result<Vec<_>>>()?;
+    children.sort();
+    Ok(children)
+}
@@ -0,0 +1,139 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    error::Error,
+    time::Instant,
+};
+
+use crate::{ordering, spec::JavaVersion};
+
+use super::scan::{parse_entry, Scan, ScanOptions};
+
+/// The reports `jvmb scan` can make.
+pub const REPORTS: &[&str] = &["versions", "deps", "strings", "errors"];
+
+/// What a report needs of a class, taken while the class is at hand on the
+/// thread which parsed it.
+enum Summary {
+    Version(u16, u16),
+    /// The class's own name and the classes it refers to.
+    Deps(String, BTreeSet<String>),
+    /// The distinct string literals.
+    Strings(BTreeSet<String>),
+    Parsed,
+}
+
+/// Parses every class among `inputs` (class files, jars or directories) on
+/// `jobs` threads and prints `report` of them, one of [`REPORTS`]:
+///
+/// - versions, how many classes there are of each class file version;
+/// - deps, the classes referred to from outside the inputs, most referring
+///   classes first;
+/// - strings, the string literals, in the most classes first, at most
+///   `top` of them;
+/// - errors, the entries which fail to parse, in scan order.
+pub fn run(
+    inputs: &[&str],
+    report: &str,
+    top: Option<usize>,
+    jobs: usize,
+    options: ScanOptions,
+) -> Result<(), Box<dyn Error>> {
+    let timings = options.timings;
+    let mut scan = Scan::new(options);
+    let started = Instant::now();
+    let summaries = scan.run_parallel(inputs, jobs, |_, buf, parse_options| {
+        let class_file = parse_entry(buf, parse_options)?;
+        Ok(match report {
+            "versions" => Summary::Version(class_file.major_version, class_file.minor_version),
+            "deps" => Summary::Deps(
+                class_file.class_name().unwrap_or_default().to_string(),
+                class_file.referenced_classes(),
+            ),
+            "strings" => Summary::Strings(
+                class_file
+                    .string_constants()
+                    .into_iter()
+                    .map(str::to_string)
+                    .collect(),
+            ),
+            _ => Summary::Parsed,
+        })
+    })?;
+    if timings {
+        eprintln!(
+            "{} classes parsed in {:.3} ms on {} threads",
+            summaries.len(),
+            started.elapsed().as_secs_f64() * 1000.0,
+            jobs
+        );
+    }
+
+    match report {
+        "versions" => {
+            let mut versions: BTreeMap<(u16, u16), usize> = BTreeMap::new();
+            for (_, summary) in &summaries {
+                if let Summary::Version(major, minor) = summary {
+                    *versions.entry((*major, *minor)).or_default() += 1;
+                }
+            }
+            for ((major, minor), count) in versions {
+                println!(
+                    "{:>8}  {}.{} ({})",
+                    count,
+                    major,
+                    minor,
+                    JavaVersion::from_major(major)
+                );
+            }
+        }
+        "deps" => {
+            let scanned: BTreeSet<&str> = summaries
+                .iter()
+                .filter_map(|(_, summary)| match summary {
+                    Summary::Deps(name, _) => Some(name.as_str()),
+                    _ => None,
+                })
+                .collect();
+            let mut referenced: BTreeMap<&str, usize> = BTreeMap::new();
+            for (_, summary) in &summaries {
+                if let Summary::Deps(_, classes) = summary {
+                    for class in classes {
+                        if !scanned.contains(class.as_str()) {
+                            *referenced.entry(class).or_default() += 1;
+                        }
+                    }
+                }
+            }
+            print_ranked(referenced.into_iter().collect(), top, |class| {
+                class.to_string()
+            });
+        }
+        "strings" => {
+            let mut strings: BTreeMap<&str, usize> = BTreeMap::new();
+            for (_, summary) in &summaries {
+                if let Summary::Strings(values) = summary {
+                    for value in values {
+                        *strings.entry(value).or_default() += 1;
+                    }
+                }
+            }
+            print_ranked(strings.into_iter().collect(), top, |value| {
+                value.escape_debug().to_string()
+            });
+        }
+        _ => {
+            for (name, error) in scan.failures() {
+                println!("{}: {}", name, error);
+            }
+        }
+    }
+    scan.finish()
+}
+
+/// Prints the first `top` of `counts`, or all of them, heaviest first.
+fn print_ranked(mut counts: Vec<(&str, usize)>, top: Option<usize>, text: impl Fn(&str) -> String) {
+    ordering::rank(&mut counts, |&(_, count)| count, |&(name, _)| name);
+    for (name, count) in counts.iter().take(top.unwrap_or(usize::MAX)) {
+        println!("{:>8}  {}", count, text(name));
+    }
+}
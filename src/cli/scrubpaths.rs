@@ -0,0 +1,48 @@
+use std::{error::Error, fs};
+
+use crate::{
+    classfile::ClassFile,
+    error::ParseError,
+    jar::{JarFile, JarWriter},
+    sourcepaths::{scrub, ScrubOptions},
+};
+
+use super::exit::Failure;
+
+/// Scrubs the `SourceFile` and `SourceDebugExtension` attributes of every
+/// class in the jar `input` as `options` say, writing the jar to `output`
+/// and printing what changed in each class. Every other entry, and every
+/// class left unchanged, is copied as it is.
+pub fn run(input: &str, output: &str, options: ScrubOptions) -> Result<(), Box<dyn Error>> {
+    let jar = JarFile::open(input).map_err(|e| Failure::parse(format!("{}: {}", input, e)))?;
+    let mut writer = JarWriter::new();
+    let mut changed = 0;
+    let mut total = 0;
+    for entry in jar.entries() {
+        if entry.class_name().is_none() || entry.is_dir() {
+            writer.copy(&entry.name, entry, jar.raw(entry)?)?;
+            continue;
+        }
+        total += 1;
+        let location = format!("{}!/{}", input, entry.name);
+        let buf = jar
+            .read(entry)
+            .map_err(|e| Failure::parse(format!("{}: {}", location, e)))?;
+        let (_, mut class_file) = ClassFile::parse_class_file(&buf)
+            .map_err(|e| Failure::parse(format!("{}: {}", location, ParseError::from(e))))?;
+        let changes =
+            scrub(&mut class_file, options).map_err(|e| format!("{}: {}", location, e))?;
+        if changes.is_empty() {
+            writer.copy(&entry.name, entry, jar.raw(entry)?)?;
+            continue;
+        }
+        changed += 1;
+        for change in &changes {
+            println!("{}: {}", entry.name, change);
+        }
+        writer.add(&entry.name, &class_file.to_bytes()?)?;
+    }
+    fs::write(output, writer.finish()?)?;
+    println!("{}: {} of {} classes changed", output, changed, total);
+    Ok(())
+}
@@ -0,0 +1,368 @@
+use std::{
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use crate::{
+    accessflags::{ACC_PUBLIC, ACC_STATIC},
+    attribute::Code,
+    builder::ClassFileBuilder,
+    classfile::{ClassFile, ParseOptions},
+    jar::JarFile,
+    verify,
+};
+
+use super::{
+    exit::{ExitCode, Failure},
+    scan::{parse_error, read_dir_sorted},
+};
+
+/// The module whose classes are tested when no inputs are given.
+const MODULE: &str = "java.base";
+
+/// Classes to test, by display name, e.g. `app.jar!com/app/Main.class`.
+type Classes = Vec<(String, Vec<u8>)>;
+
+/// Parses a sample of `sample` classes of java.base in the JDK at
+/// `java_home`, or else found through `JAVA_HOME` or the `java` on the
+/// `PATH`, strictly, verifies them and writes them back, printing the
+/// environment and every class which fails along with the code of each
+/// failure, for pasting into a bug report. With `inputs` (class files,
+/// jars or directories), tests every class among them instead. Any
+/// failure is a violation.
+pub fn run(
+    java_home: Option<PathBuf>,
+    inputs: &[&str],
+    sample: usize,
+) -> Result<ExitCode, Box<dyn Error>> {
+    println!("jvmb {}", env!("CARGO_PKG_VERSION"));
+    println!("os: {} {}", std::env::consts::OS, std::env::consts::ARCH);
+    let classes = if inputs.is_empty() {
+        let java_home = java_home.or_else(find_java_home).ok_or_else(|| {
+            Failure::usage(
+                "no JDK found: use --system <jdk>, set JAVA_HOME or put java on the PATH, \
+                 or give class files, jars or directories to test",
+            )
+        })?;
+        println!(
+            "jdk: {} ({})",
+            java_home.display(),
+            jdk_version(&java_home).unwrap_or_else(|| "unknown version".to_string())
+        );
+        platform_classes(&java_home, sample)?
+    } else {
+        let mut classes = Vec::new();
+        for input in inputs {
+            collect_classes(Path::new(input), &mut classes)
+                .map_err(|e| Failure::parse(format!("{}: {}", input, e)))?;
+        }
+        classes
+    };
+    println!();
+
+    let mut failed = 0;
+    for (name, buf) in &classes {
+        let failures = check(buf);
+        if !failures.is_empty() {
+            failed += 1;
+        }
+        for (code, message) in failures {
+            println!("{}: [{}] {}", name, code, message);
+        }
+    }
+    if failed > 0 {
+        println!();
+    }
+    println!(
+        "{} classes tested: {} passed, {} failed",
+        classes.len(),
+        classes.len() - failed,
+        failed
+    );
+    Ok(if failed == 0 {
+        ExitCode::Success
+    } else {
+        ExitCode::Violations
+    })
+}
+
+/// The failures of the class in `buf`, by code: a parse error, including
+/// a class file version jvmb doesn't support and trailing bytes, a
+/// verification violation, or a class which isn't written back as it was
+/// read.
+fn check(buf: &[u8]) -> Vec<(String, String)> {
+    let mut options = ParseOptions::new();
+    options.reject_unsupported_versions();
+    let class_file = match ClassFile::parse_with(buf, &options) {
+        Ok((rest, _)) if !rest.is_empty() => {
+            let message = format!("{} bytes left over after the class", rest.len());
+            return vec![("parse/TrailingBytes".to_string(), message)];
+        }
+        Ok((_, class_file)) => class_file,
+        Err(e) => {
+            let error = parse_error(buf, e);
+            return vec![(error.code, error.message)];
+        }
+    };
+
+    let mut failures = Vec::new();
+    for violation in verify::verify(&class_file) {
+        failures.push((
+            violation.code.to_string(),
+            format!("{}: {}", violation.location, violation.message),
+        ));
+    }
+    match class_file.to_bytes() {
+        Ok(written) if written == buf => {}
+        Ok(written) => {
+            let offset = written
+                .iter()
+                .zip(buf)
+                .position(|(written, read)| written != read)
+                .unwrap_or(written.len().min(buf.len()));
+            failures.push((
+                "roundtrip".to_string(),
+                format!(
+                    "written back as {} bytes, read as {}, first differing at byte {}",
+                    written.len(),
+                    buf.len(),
+                    offset
+                ),
+            ));
+        }
+        Err(e) => failures.push(("write".to_string(), e.to_string())),
+    }
+    failures
+}
+
+/// The JDK `JAVA_HOME` names, or else the one holding the `java` found on
+/// the `PATH`, following links.
+fn find_java_home() -> Option<PathBuf> {
+    if let Some(java_home) = std::env::var_os("JAVA_HOME") {
+        return Some(PathBuf::from(java_home));
+    }
+    let java = std::env::split_paths(&std::env::var_os("PATH")?)
+        .map(|dir| dir.join("java"))
+        .find(|path| path.is_file())?;
+    let bin = fs::canonicalize(java).ok()?;
+    Some(bin.parent()?.parent()?.to_path_buf())
+}
+
+/// The `JAVA_VERSION` of the JDK's `release` file.
+fn jdk_version(java_home: &Path) -> Option<String> {
+    let release = fs::read_to_string(java_home.join("release")).ok()?;
+    release.lines().find_map(|line| {
+        let version = line.strip_prefix("JAVA_VERSION=")?;
+        Some(version.trim_matches('"').to_string())
+    })
+}
+
+/// `sample` classes of java.base, spread evenly over the module by name:
+/// read from its jmod, or from `rt.jar` in a JDK 8, or else, in a JRE or a
+/// JDK linked without jmods, dumped by the JDK's `java` from among
+/// [`DUMPED`].
+fn platform_classes(java_home: &Path, sample: usize) -> Result<Classes, Box<dyn Error>> {
+    let jmod = java_home.join("jmods").join(format!("{}.jmod", MODULE));
+    if jmod.is_file() {
+        let archive = JarFile::open_jmod(&jmod)
+            .map_err(|e| Failure::parse(format!("{}: {}", jmod.display(), e)))?;
+        return archive_classes(&jmod, &archive, "classes/", sample);
+    }
+    for rt in [
+        java_home.join("jre").join("lib").join("rt.jar"),
+        java_home.join("lib").join("rt.jar"),
+    ] {
+        if rt.is_file() {
+            let archive = JarFile::open(&rt)
+                .map_err(|e| Failure::parse(format!("{}: {}", rt.display(), e)))?;
+            return archive_classes(&rt, &archive, "java/", sample);
+        }
+    }
+    let java = java_home.join("bin").join("java");
+    if java.is_file() {
+        return dumped_classes(&java, sample);
+    }
+    Err(Failure::usage(format!(
+        "{} has neither jmods, an rt.jar nor bin/java; give class files, jars or \
+         directories to test instead",
+        java_home.display()
+    ))
+    .into())
+}
+
+/// `sample` of the classes of `archive`, at `path`, whose entries start
+/// with `prefix`.
+fn archive_classes(
+    path: &Path,
+    archive: &JarFile,
+    prefix: &str,
+    sample: usize,
+) -> Result<Classes, Box<dyn Error>> {
+    let mut entries: Vec<_> = archive
+        .class_variants()
+        .filter(|entry| entry.name.starts_with(prefix))
+        .collect();
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+    let count = entries.len();
+    let picked = spread(entries, sample);
+    println!("{}: {} of {} classes", path.display(), picked.len(), count);
+    picked
+        .into_iter()
+        .map(|entry| {
+            let name = format!("{}!{}", path.display(), entry.name);
+            let buf = archive
+                .read(entry)
+                .map_err(|e| Failure::parse(format!("{}: {}", name, e)))?;
+            Ok((name, buf))
+        })
+        .collect()
+}
+
+/// The classes dumped when the JDK has no jmods: some of the most used,
+/// and some of the largest, of java.base, in every JDK since 9.
+const DUMPED: &[&str] = &[
+    "java/io/File",
+    "java/io/InputStream",
+    "java/lang/Character",
+    "java/lang/Class",
+    "java/lang/Integer",
+    "java/lang/Math",
+    "java/lang/Object",
+    "java/lang/String",
+    "java/lang/StringBuilder",
+    "java/lang/System",
+    "java/lang/Thread",
+    "java/lang/invoke/MethodHandles",
+    "java/time/LocalDate",
+    "java/util/ArrayList",
+    "java/util/Collections",
+    "java/util/HashMap",
+    "java/util/Optional",
+    "java/util/concurrent/ConcurrentHashMap",
+    "java/util/regex/Pattern",
+    "java/util/stream/Collectors",
+];
+
+/// `sample` of [`DUMPED`], each written to stdout by a helper class run
+/// with `java`, which loads it as a resource of the system class loader.
+fn dumped_classes(java: &Path, sample: usize) -> Result<Classes, Box<dyn Error>> {
+    let dir = std::env::temp_dir().join(format!("jvmb-selftest-{}", std::process::id()));
+    fs::create_dir_all(&dir)?;
+    let dumped = dump_helper()
+        .and_then(|helper| fs::write(dir.join(format!("{}.class", DUMP_HELPER)), helper))
+        .map_err(Box::<dyn Error>::from)
+        .and_then(|()| {
+            let picked = spread(DUMPED.to_vec(), sample);
+            println!(
+                "{}: {} of {} classes dumped, no jmods",
+                java.display(),
+                picked.len(),
+                DUMPED.len()
+            );
+            picked
+                .into_iter()
+                .map(|class| dump(java, &dir, class))
+                .collect()
+        });
+    // the helper is left behind if this fails, in the temporary directory
+    let _ = fs::remove_dir_all(&dir);
+    dumped
+}
+
+/// The internal name of the helper class.
+const DUMP_HELPER: &str = "JvmbDump";
+
+/// The helper class: `main` writes the bytes of the resource its first
+/// argument names to stdout. Its code doesn't branch, so it needs no stack
+/// map frames.
+fn dump_helper() -> std::io::Result<Vec<u8>> {
+    let mut builder = ClassFileBuilder::new(DUMP_HELPER);
+    let constant_pool = builder.constant_pool();
+    let out = constant_pool.field_ref("java/lang/System", "out", "Ljava/io/PrintStream;");
+    let resource = constant_pool.method_ref(
+        "java/lang/ClassLoader",
+        "getSystemResourceAsStream",
+        "(Ljava/lang/String;)Ljava/io/InputStream;",
+    );
+    let read = constant_pool.method_ref("java/io/InputStream", "readAllBytes", "()[B");
+    let write = constant_pool.method_ref("java/io/PrintStream", "write", "([B)V");
+    let flush = constant_pool.method_ref("java/io/PrintStream", "flush", "()V");
+    let [out_high, out_low] = out.to_be_bytes();
+    // getstatic System.out, aload_0, iconst_0, aaload: args[0]
+    let mut code = vec![0xb2, out_high, out_low, 0x2a, 0x03, 0x32];
+    // invokestatic getSystemResourceAsStream, invokevirtual readAllBytes,
+    // invokevirtual write
+    for (opcode, method) in [(0xb8, resource), (0xb6, read), (0xb6, write)] {
+        let [high, low] = method.to_be_bytes();
+        code.extend([opcode, high, low]);
+    }
+    // getstatic System.out, invokevirtual flush, return
+    let [flush_high, flush_low] = flush.to_be_bytes();
+    code.extend([0xb2, out_high, out_low, 0xb6, flush_high, flush_low, 0xb1]);
+    builder.add_method(
+        ACC_PUBLIC | ACC_STATIC,
+        "main",
+        "([Ljava/lang/String;)V",
+        Some(Code::new(3, 1, code)),
+    );
+    builder.build()?.to_bytes()
+}
+
+/// The bytes of `class`, dumped by the helper in `dir`.
+fn dump(java: &Path, dir: &Path, class: &str) -> Result<(String, Vec<u8>), Box<dyn Error>> {
+    let output = Command::new(java)
+        .arg("-cp")
+        .arg(dir)
+        .arg(DUMP_HELPER)
+        .arg(format!("{}.class", class))
+        .output()?;
+    if !output.status.success() || output.stdout.is_empty() {
+        return Err(Failure::parse(format!(
+            "{} failed to dump {}: {}",
+            java.display(),
+            class,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+        .into());
+    }
+    Ok((format!("{}!{}.class", java.display(), class), output.stdout))
+}
+
+/// `sample` of `items`, spread evenly over them, or all of them.
+fn spread<T: Copy>(items: Vec<T>, sample: usize) -> Vec<T> {
+    let count = items.len();
+    if sample >= count {
+        items
+    } else {
+        (0..sample).map(|i| items[i * count / sample]).collect()
+    }
+}
+
+/// Adds the classes at `path`, a class file, jar or directory of them, to
+/// `classes`.
+fn collect_classes(path: &Path, classes: &mut Classes) -> std::io::Result<()> {
+    if path.is_dir() {
+        for child in read_dir_sorted(path)? {
+            if child.is_dir()
+                || child
+                    .extension()
+                    .is_some_and(|ext| ext == "class" || ext == "jar")
+            {
+                collect_classes(&child, classes)?;
+            }
+        }
+    } else if path.extension().is_some_and(|ext| ext == "jar") {
+        let jar = JarFile::open(path).map_err(|e| std::io::Error::other(e.to_string()))?;
+        for entry in jar.class_variants() {
+            let buf = jar
+                .read(entry)
+                .map_err(|e| std::io::Error::other(e.to_string()))?;
+            classes.push((format!("{}!{}", path.display(), entry.name), buf));
+        }
+    } else {
+        classes.push((path.display().to_string(), fs::read(path)?));
+    }
+    Ok(())
+}
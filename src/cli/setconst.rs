@@ -0,0 +1,135 @@
+use std::{error::Error, fs};
+
+use crate::{
+    classfile::ClassFile,
+    constantpool::ConstantPool,
+    patch::{ScalarConstant, SetConstantError, SharedConstant},
+};
+
+use super::exit::Failure;
+
+/// Which constant to set, and to what.
+pub enum Target<'a> {
+    /// The entry at an index.
+    Index(u16, ScalarConstant),
+    /// The constant value of a field, as written after `=` in `--field`.
+    Field(&'a str, &'a str),
+}
+
+/// Sets a constant of the class read from `file_name`, writes the class to
+/// `output` and prints what the entry written is used by.
+pub fn run(
+    mut class_file: ClassFile,
+    file_name: &str,
+    target: Target,
+    shared: SharedConstant,
+    output: &str,
+) -> Result<(), Box<dyn Error>> {
+    let (result, value) = match target {
+        Target::Index(index, value) => {
+            (class_file.set_constant(index, value.clone(), shared), value)
+        }
+        Target::Field(name, text) => {
+            let value = field_value(&class_file, file_name, name, text)?;
+            (
+                class_file.set_field_constant(name, value.clone(), shared),
+                value,
+            )
+        }
+    };
+    let index = result.map_err(|e| match e {
+        SetConstantError::Shared { .. } => format!(
+            "{}: {} (use --duplicate-if-shared to give the change an entry of its own)",
+            file_name, e
+        ),
+        e => format!("{}: {}", file_name, e),
+    })?;
+    fs::write(output, class_file.to_bytes()?)?;
+
+    let references = class_file.find_constant_references(index);
+    println!("{}: constant #{} set to {}", output, index, value);
+    for reference in references {
+        println!("  used by {}", reference);
+    }
+    Ok(())
+}
+
+/// Parses `--int`, `--long`, `--float`, `--double` or `--utf8`, whichever
+/// was given.
+pub fn parse_value(kind: &str, text: &str) -> Result<ScalarConstant, Box<dyn Error>> {
+    let invalid = || Failure::usage(format!("invalid --{}: {}", kind, text));
+    Ok(match kind {
+        "int" => ScalarConstant::Integer(text.parse().map_err(|_| invalid())?),
+        "long" => ScalarConstant::Long(text.parse().map_err(|_| invalid())?),
+        "float" => ScalarConstant::Float(text.parse().map_err(|_| invalid())?),
+        "double" => ScalarConstant::Double(text.parse().map_err(|_| invalid())?),
+        _ => ScalarConstant::Utf8(text.to_string()),
+    })
+}
+
+/// Parses the value of `--field <name>=<value>` by the type of the field:
+/// `true` and `false` for a `boolean`, a character for a `char`.
+fn field_value(
+    class_file: &ClassFile,
+    file_name: &str,
+    name: &str,
+    text: &str,
+) -> Result<ScalarConstant, Box<dyn Error>> {
+    let constant_pool = &class_file.constant_pool;
+    let descriptor = class_file
+        .fields
+        .iter()
+        .find(|field| ConstantPool::utf8(constant_pool, field.name_index()) == Some(name))
+        .and_then(|field| ConstantPool::utf8(constant_pool, field.descriptor_index()))
+        .ok_or_else(|| {
+            let e = SetConstantError::NoSuchField(name.to_string());
+            format!("{}: {}", file_name, e)
+        })?;
+    let invalid = || {
+        Failure::usage(format!(
+            "invalid value for {} {}: {}",
+            descriptor, name, text
+        ))
+    };
+    let integer = |text: &str| -> Result<i32, Failure> {
+        let value: i64 = text.parse().map_err(|_| invalid())?;
+        let fits = match descriptor {
+            "B" => i8::try_from(value).is_ok(),
+            "S" => i16::try_from(value).is_ok(),
+            _ => i32::try_from(value).is_ok(),
+        };
+        if fits {
+            Ok(value as i32)
+        } else {
+            Err(invalid())
+        }
+    };
+    Ok(match descriptor {
+        "Z" => match text {
+            "true" => ScalarConstant::Integer(1),
+            "false" => ScalarConstant::Integer(0),
+            _ => return Err(invalid().into()),
+        },
+        "C" => {
+            let mut chars = text.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) if u16::try_from(u32::from(c)).is_ok() => {
+                    ScalarConstant::Integer(u32::from(c) as i32)
+                }
+                _ => return Err(invalid().into()),
+            }
+        }
+        "B" | "S" | "I" => ScalarConstant::Integer(integer(text)?),
+        "J" => ScalarConstant::Long(text.parse().map_err(|_| invalid())?),
+        "F" => ScalarConstant::Float(text.parse().map_err(|_| invalid())?),
+        "D" => ScalarConstant::Double(text.parse().map_err(|_| invalid())?),
+        "Ljava/lang/String;" => ScalarConstant::Utf8(text.to_string()),
+        _ => {
+            return Err(Failure::usage(format!(
+                "field {} of type {} can't be constant",
+                name, descriptor
+            ))
+            .into())
+        }
+    })
+}
@@ -0,0 +1,124 @@
+use std::{
+    error::Error,
+    io::{self, Write},
+};
+
+use crate::{
+    jar::JarFile,
+    json::Json,
+    splitpackage::{PackageIndex, SplitPackage},
+};
+
+use super::{
+    exit::{ExitCode, Failure},
+    output::{Format, Outputs, Results, Table},
+};
+
+/// The formats `split-packages` writes.
+pub const FORMATS: &[Format] = &[Format::Text, Format::Json, Format::Csv];
+
+/// Reports packages with classes in more than one of `jars` to `outputs`.
+/// Packages matching one of the `allow` patterns, where `*` stands for any
+/// run of characters, are reported but don't fail the command.
+pub fn run(jars: &[&str], allow: &[&str], outputs: &Outputs) -> Result<ExitCode, Box<dyn Error>> {
+    let mut index = PackageIndex::new();
+    for name in jars {
+        let jar = JarFile::open(name).map_err(|e| Failure::parse(format!("{}: {}", name, e)))?;
+        index.add_jar(name, &jar);
+    }
+
+    let split_packages = index.split_packages();
+    let allowed: Vec<bool> = split_packages
+        .iter()
+        .map(|split| {
+            allow
+                .iter()
+                .any(|pattern| glob_match(pattern, &split.package))
+        })
+        .collect();
+    let failed = allowed.contains(&false);
+    outputs.write(&SplitPackages {
+        split_packages,
+        allowed,
+    })?;
+
+    if failed {
+        Ok(ExitCode::Violations)
+    } else {
+        Ok(ExitCode::Success)
+    }
+}
+
+struct SplitPackages {
+    split_packages: Vec<SplitPackage>,
+    allowed: Vec<bool>,
+}
+
+impl SplitPackages {
+    fn iter(&self) -> impl Iterator<Item = (&SplitPackage, bool)> {
+        self.split_packages.iter().zip(self.allowed.iter().copied())
+    }
+}
+
+impl Results for SplitPackages {
+    fn write_text(&self, out: &mut dyn Write) -> io::Result<()> {
+        for (split, allowed) in self.iter() {
+            writeln!(
+                out,
+                "{}{}",
+                split.package,
+                if allowed { " (allowed)" } else { "" }
+            )?;
+            for (jar, classes) in &split.jars {
+                writeln!(out, "  {:>6}  {}", classes, jar)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn to_json(&self) -> Json {
+        let packages = self.iter().map(|(split, allowed)| {
+            Json::object([
+                ("package", split.package.as_str().into()),
+                ("allowed", allowed.into()),
+                (
+                    "jars",
+                    Json::array(split.jars.iter().map(|(jar, classes)| {
+                        Json::object([
+                            ("jar", jar.as_str().into()),
+                            ("classes", Json::Int(*classes as i64)),
+                        ])
+                    })),
+                ),
+            ])
+        });
+        Json::object([("split_packages", Json::array(packages))])
+    }
+
+    /// A row for every jar a split package is found in.
+    fn to_table(&self) -> Option<Table> {
+        let mut rows = Table::new(&["package", "allowed", "jar", "classes"]);
+        for (split, allowed) in self.iter() {
+            for (jar, classes) in &split.jars {
+                rows.push(vec![
+                    split.package.clone(),
+                    allowed.to_string(),
+                    jar.clone(),
+                    classes.to_string(),
+                ]);
+            }
+        }
+        Some(rows)
+    }
+}
+
+fn glob_match(pattern: &str, text: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == text,
+        Some((prefix, rest)) => {
+            text.starts_with(prefix)
+                && (prefix.len()..=text.len())
+                    .any(|start| text.is_char_boundary(start) && glob_match(rest, &text[start..]))
+        }
+    }
+}
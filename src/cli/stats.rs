@@ -0,0 +1,154 @@
+use std::{collections::BTreeMap, error::Error, path::Path};
+
+use crate::{
+    ordering,
+    sizes::{SizeReport, Tally},
+};
+
+use super::scan::{parse_error, Scan, ScanOptions};
+
+/// The sizes of every class scanned, added up.
+#[derive(Default)]
+struct Totals {
+    classes: usize,
+    file_size: usize,
+    /// In the order of the file.
+    sections: Vec<(&'static str, usize)>,
+    constants: BTreeMap<&'static str, Tally>,
+    attributes: BTreeMap<String, Tally>,
+    /// The largest of each class, as (bytes, label, code length).
+    methods: Vec<(usize, String, Option<usize>)>,
+    /// The largest of each class, as (bytes, label).
+    utf8: Vec<(usize, String)>,
+}
+
+impl Totals {
+    fn add(&mut self, name: &str, report: &SizeReport, top: usize, single_class: bool) {
+        self.classes += 1;
+        self.file_size += report.file_size;
+        for section in &report.sections {
+            match self
+                .sections
+                .iter_mut()
+                .find(|(name, _)| *name == section.name)
+            {
+                Some((_, bytes)) => *bytes += section.bytes,
+                None => self.sections.push((section.name, section.bytes)),
+            }
+        }
+        for (kind, tally) in &report.constants {
+            let total = self.constants.entry(kind).or_default();
+            total.count += tally.count;
+            total.bytes += tally.bytes;
+        }
+        for (attribute, tally) in &report.attributes {
+            let total = self.attributes.entry(attribute.clone()).or_default();
+            total.count += tally.count;
+            total.bytes += tally.bytes;
+        }
+        let label = |value: &str| {
+            if single_class {
+                value.to_string()
+            } else {
+                format!("{}: {}", name, value)
+            }
+        };
+        for method in report.largest_methods().into_iter().take(top) {
+            self.methods
+                .push((method.bytes, label(&method.name), method.code_length));
+        }
+        for utf8 in report.largest_utf8().into_iter().take(top) {
+            let value = truncate(&utf8.value.escape_debug().to_string(), 100);
+            self.utf8.push((utf8.bytes, label(&value)));
+        }
+        // keep only what can still make the cut
+        if self.methods.len().max(self.utf8.len()) > top * 4 {
+            self.trim(top);
+        }
+    }
+
+    fn trim(&mut self, top: usize) {
+        ordering::rank(&mut self.methods, |method| method.0, |method| &method.1);
+        self.methods.truncate(top);
+        ordering::rank(&mut self.utf8, |utf8| utf8.0, |utf8| &utf8.1);
+        self.utf8.truncate(top);
+    }
+}
+
+/// Prints where the bytes of the classes among `inputs` (class files, jars
+/// or directories) go: the parts of the files, the constants by kind and
+/// the attributes by name, each with its share of the bytes, then the `top`
+/// largest methods and UTF8 constants.
+pub fn run(inputs: &[&str], top: usize, options: ScanOptions) -> Result<(), Box<dyn Error>> {
+    let single_class =
+        inputs.len() == 1 && inputs[0].ends_with(".class") && Path::new(inputs[0]).is_file();
+    let mut totals = Totals::default();
+    let mut scan = Scan::new(options);
+    scan.run(inputs, |name, buf, parse_options| {
+        let report =
+            SizeReport::measure_with(buf, parse_options).map_err(|e| parse_error(buf, e))?;
+        totals.add(name, &report, top, single_class);
+        Ok(())
+    })?;
+    totals.trim(top);
+
+    let percent = |bytes: usize| bytes as f64 * 100.0 / totals.file_size.max(1) as f64;
+    if !single_class {
+        println!("{} classes", totals.classes);
+    }
+    println!("{:<40} {:>12} {:>7}", "SECTION", "BYTES", "%");
+    for (name, bytes) in &totals.sections {
+        println!("{:<40} {:>12} {:>6.1}%", name, bytes, percent(*bytes));
+    }
+    println!("{:<40} {:>12}", "total", totals.file_size);
+    for (heading, tallies) in [
+        (
+            "CONSTANT",
+            totals
+                .constants
+                .iter()
+                .map(|(kind, tally)| (*kind, tally))
+                .collect::<Vec<_>>(),
+        ),
+        (
+            "ATTRIBUTE",
+            totals
+                .attributes
+                .iter()
+                .map(|(name, tally)| (name.as_str(), tally))
+                .collect(),
+        ),
+    ] {
+        println!();
+        println!("{:<40} {:>12} {:>7} {:>9}", heading, "BYTES", "%", "COUNT");
+        for (name, tally) in tallies {
+            println!(
+                "{:<40} {:>12} {:>6.1}% {:>9}",
+                name,
+                tally.bytes,
+                percent(tally.bytes),
+                tally.count
+            );
+        }
+    }
+    println!();
+    println!("{:>12} {:>9}  LARGEST METHODS", "BYTES", "CODE");
+    for (bytes, label, code_length) in &totals.methods {
+        let code_length = code_length.map_or("-".to_string(), |length| length.to_string());
+        println!("{:>12} {:>9}  {}", bytes, code_length, label);
+    }
+    println!();
+    println!("{:>12}  LARGEST UTF8 CONSTANTS", "BYTES");
+    for (bytes, label) in &totals.utf8 {
+        println!("{:>12}  {}", bytes, label);
+    }
+    scan.finish()
+}
+
+/// `text` cut to `max` characters, with an ellipsis when it's cut.
+fn truncate(text: &str, max: usize) -> String {
+    match text.char_indices().nth(max) {
+        Some((end, _)) => format!("{}...", &text[..end]),
+        None => text.to_string(),
+    }
+}
@@ -0,0 +1,80 @@
+use std::error::Error;
+
+use crate::{
+    constantpool::ConstantPool,
+    friendly::friendly_class_name,
+    ordering,
+    stringbuilding::{string_building, MethodStringBuilding},
+};
+
+use super::{
+    method_label,
+    scan::{parse_entry, Scan, ScanOptions},
+};
+
+/// Prints the `top` methods among `inputs` (class files, jars or
+/// directories) which join the most pieces into strings, heaviest first and
+/// ties by name, with the recipes of their `invokedynamic` concatenations.
+pub fn run(
+    inputs: &[&str],
+    top: usize,
+    friendly_names: bool,
+    options: ScanOptions,
+) -> Result<(), Box<dyn Error>> {
+    // (class.method label, counts), labelled while the class is at hand
+    let mut methods: Vec<(String, MethodStringBuilding)> = Vec::new();
+    let mut scan = Scan::new(options);
+    scan.run(inputs, |_, buf, parse_options| {
+        let class_file = parse_entry(buf, parse_options)?;
+        let constant_pool = &class_file.constant_pool;
+        let class_name = friendly_names
+            .then(|| friendly_class_name(&class_file))
+            .flatten()
+            .or_else(|| {
+                ConstantPool::class_name(constant_pool, class_file.this_class).map(String::from)
+            })
+            .unwrap_or_else(|| "?".to_string());
+        for method in string_building(&class_file) {
+            let label = class_file
+                .methods
+                .iter()
+                .find(|info| {
+                    ConstantPool::utf8(constant_pool, info.name_index()) == Some(&method.name)
+                        && ConstantPool::utf8(constant_pool, info.descriptor_index())
+                            == Some(&method.descriptor)
+                })
+                .map_or_else(
+                    || format!("{}{}", method.name, method.descriptor),
+                    |info| method_label(&class_file, info, friendly_names),
+                );
+            methods.push((format!("{}.{}", class_name, label), method));
+        }
+        Ok(())
+    })?;
+    scan.finish()?;
+
+    ordering::rank(
+        &mut methods,
+        |(_, method)| method.weight(),
+        |(label, _)| label.as_str(),
+    );
+    for (label, method) in methods.iter().take(top) {
+        println!(
+            "{:>5} {}: {} builders, {} appends, {} formats, {} concats, {} indy",
+            method.weight(),
+            label,
+            method.builders,
+            method.appends,
+            method.formats,
+            method.concats,
+            method.concat_sites.len()
+        );
+        for site in &method.concat_sites {
+            match &site.recipe {
+                Some(recipe) => println!("      {}: {:?}", site.offset, recipe),
+                None => println!("      {}: <undecodable recipe>", site.offset),
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,42 @@
+use std::{error::Error, path::Path};
+
+use crate::{classfile::ClassHeader, constantpool::ConstantPool};
+
+use super::scan::{parse_error, Scan, ScanOptions};
+
+/// Prints the string literals of every class among `inputs` (class files,
+/// jars or directories), or with `all_utf8` every UTF8 constant, names and
+/// descriptors included, one to a line with line breaks escaped. Those of a
+/// jar or directory are prefixed with the entry they're in. Only the
+/// constant pool of each class is read.
+pub fn run(inputs: &[&str], all_utf8: bool, options: ScanOptions) -> Result<(), Box<dyn Error>> {
+    let mut scan = Scan::new(options);
+    for input in inputs {
+        let single_class = input.ends_with(".class") && Path::new(input).is_file();
+        scan.run(&[input], |name, buf, parse_options| {
+            let (_, header) =
+                ClassHeader::parse_with(buf, parse_options).map_err(|e| parse_error(buf, e))?;
+            let values = if all_utf8 {
+                header
+                    .constant_pool
+                    .iter()
+                    .filter_map(|constant| match constant {
+                        ConstantPool::UTF8(value) => Some(value.as_str()),
+                        _ => None,
+                    })
+                    .collect()
+            } else {
+                header.string_constants()
+            };
+            for value in values {
+                if single_class {
+                    println!("{}", value.escape_debug());
+                } else {
+                    println!("{}: {}", name, value.escape_debug());
+                }
+            }
+            Ok(())
+        })?;
+    }
+    scan.finish()
+}
@@ -0,0 +1,249 @@
+use std::{
+    error::Error,
+    io::{self, Write},
+};
+
+use crate::{
+    json::Json,
+    mapping::NameMapper,
+    symbols::{field_id, method_id, Definition, Reference, SymbolIndex, SymbolTable},
+};
+
+use super::{
+    output::{Format, Outputs, Results, Table},
+    scan::{parse_entry, Scan, ScanOptions},
+};
+
+/// The formats `symbols` writes.
+pub const FORMATS: &[Format] = &[Format::Text, Format::Json, Format::Csv];
+
+/// Writes the definitions and member references of every class among
+/// `inputs`, in scan order, to `outputs`: as text, as a JSON document of the
+/// form `{"classes": [{"entry", "class", "super_class", "source_file",
+/// "definitions", "references"}]}`, or as CSV with a row per definition. A
+/// reference is resolved to the declaration it leads to when that's among
+/// the inputs. With a `mapping` the text shows the original names of an
+/// obfuscated build, every ID in the JSON becomes `{"obfuscated",
+/// "original"}` and the CSV fills in its `original_id` column.
+pub fn run(
+    inputs: &[&str],
+    outputs: &Outputs,
+    mapping: Option<&NameMapper>,
+    options: ScanOptions,
+) -> Result<(), Box<dyn Error>> {
+    let mut entries = Vec::new();
+    let mut index = SymbolIndex::new();
+    let mut scan = Scan::new(options);
+    scan.run(inputs, |name, buf, parse_options| {
+        index.add(parse_entry(buf, parse_options)?.symbols());
+        entries.push(name.to_string());
+        Ok(())
+    })?;
+    scan.finish()?;
+
+    outputs.write(&Symbols {
+        entries,
+        index,
+        mapping,
+    })
+}
+
+struct Symbols<'a> {
+    entries: Vec<String>,
+    index: SymbolIndex,
+    mapping: Option<&'a NameMapper>,
+}
+
+impl Results for Symbols<'_> {
+    fn write_text(&self, out: &mut dyn Write) -> io::Result<()> {
+        for table in self.index.tables() {
+            write_table(out, table, &self.index, self.mapping)?;
+        }
+        Ok(())
+    }
+
+    fn to_json(&self) -> Json {
+        let classes = self
+            .entries
+            .iter()
+            .zip(self.index.tables())
+            .map(|(entry, table)| table_json(entry, table, &self.index, self.mapping));
+        Json::object([("classes", Json::array(classes))])
+    }
+
+    fn to_table(&self) -> Option<Table> {
+        let mut rows = Table::new(&[
+            "entry",
+            "class",
+            "kind",
+            "id",
+            "original_id",
+            "access_flags",
+            "line",
+        ]);
+        for (entry, table) in self.entries.iter().zip(self.index.tables()) {
+            for definition in &table.definitions {
+                rows.push(vec![
+                    entry.clone(),
+                    table.class.clone(),
+                    definition.kind.name().to_string(),
+                    definition.id.clone(),
+                    self.mapping
+                        .map_or_else(String::new, |mapping| original_id(&definition.id, mapping)),
+                    definition.access_flags.to_string(),
+                    definition
+                        .line
+                        .map_or_else(String::new, |line| line.to_string()),
+                ]);
+            }
+        }
+        Some(rows)
+    }
+}
+
+/// The ID of the declaration `reference` leads to, if it's among the
+/// indexed classes.
+fn resolved<'a>(reference: &Reference, index: &'a SymbolIndex) -> Option<&'a str> {
+    index
+        .resolve(&reference.target)
+        .map(|(_, definition)| definition.id.as_str())
+}
+
+fn write_table(
+    out: &mut dyn Write,
+    table: &SymbolTable,
+    index: &SymbolIndex,
+    mapping: Option<&NameMapper>,
+) -> io::Result<()> {
+    let name = |id: &str| match mapping {
+        Some(mapping) => original_id(id, mapping),
+        None => id.to_string(),
+    };
+    match &table.source_file {
+        Some(source_file) => writeln!(out, "{} ({})", name(&table.class), source_file)?,
+        None => writeln!(out, "{}", name(&table.class))?,
+    }
+    for definition in &table.definitions {
+        writeln!(
+            out,
+            "  {} {}{}",
+            definition.kind.name(),
+            name(&definition.id),
+            line_suffix(definition.line)
+        )?;
+    }
+    for reference in &table.references {
+        let declared = match resolved(reference, index) {
+            Some(id) if id != reference.target => format!(" (declared as {})", name(id)),
+            _ => String::new(),
+        };
+        writeln!(
+            out,
+            "  ref {}+{}{} -> {} {}{}",
+            name(&reference.from),
+            reference.offset,
+            line_suffix(reference.line),
+            reference.kind.name(),
+            name(&reference.target),
+            declared
+        )?;
+    }
+    Ok(())
+}
+
+/// The ID of a symbol of an obfuscated build with its class, name and
+/// descriptor mapped back to the original ones.
+fn original_id(id: &str, mapping: &NameMapper) -> String {
+    let (class, member) = match id.split_once('#') {
+        Some(parts) => parts,
+        None => return mapping.class_name(id).to_string(),
+    };
+    let original_class = mapping.class_name(class);
+    if let Some((name, descriptor)) = member.split_once(':') {
+        let name = mapping.field_name(class, name, descriptor).unwrap_or(name);
+        return field_id(original_class, name, &mapping.descriptor(descriptor));
+    }
+    match member.find('(') {
+        Some(open) => {
+            let (name, descriptor) = member.split_at(open);
+            let name = mapping.method_name(class, name, descriptor).unwrap_or(name);
+            method_id(original_class, name, &mapping.descriptor(descriptor))
+        }
+        None => id.to_string(),
+    }
+}
+
+/// An ID, or with a `mapping` both the obfuscated and the original one.
+fn id_json(id: &str, mapping: Option<&NameMapper>) -> Json {
+    match mapping {
+        Some(mapping) => Json::object([
+            ("obfuscated", id.into()),
+            ("original", original_id(id, mapping).into()),
+        ]),
+        None => id.into(),
+    }
+}
+
+fn line_suffix(line: Option<u16>) -> String {
+    line.map_or_else(String::new, |line| format!(" line {}", line))
+}
+
+fn table_json(
+    entry: &str,
+    table: &SymbolTable,
+    index: &SymbolIndex,
+    mapping: Option<&NameMapper>,
+) -> Json {
+    let id = |id: Option<&str>| id.map_or(Json::Null, |id| id_json(id, mapping));
+    Json::object([
+        ("entry", entry.into()),
+        ("class", id(Some(&table.class))),
+        ("super_class", id(table.super_class.as_deref())),
+        ("source_file", table.source_file.as_deref().into()),
+        (
+            "definitions",
+            Json::array(
+                table
+                    .definitions
+                    .iter()
+                    .map(|definition| definition_json(definition, mapping)),
+            ),
+        ),
+        (
+            "references",
+            Json::array(
+                table
+                    .references
+                    .iter()
+                    .map(|reference| reference_json(reference, index, mapping)),
+            ),
+        ),
+    ])
+}
+
+fn definition_json(definition: &Definition, mapping: Option<&NameMapper>) -> Json {
+    Json::object([
+        ("id", id_json(&definition.id, mapping)),
+        ("kind", definition.kind.name().into()),
+        ("access_flags", definition.access_flags.into()),
+        ("line", definition.line.into()),
+    ])
+}
+
+fn reference_json(
+    reference: &Reference,
+    index: &SymbolIndex,
+    mapping: Option<&NameMapper>,
+) -> Json {
+    Json::object([
+        ("target", id_json(&reference.target, mapping)),
+        (
+            "resolved",
+            resolved(reference, index).map_or(Json::Null, |id| id_json(id, mapping)),
+        ),
+        ("kind", reference.kind.name().into()),
+        ("from", id_json(&reference.from, mapping)),
+        ("offset", reference.offset.into()),
+        ("line", reference.line.into()),
+    ])
+}
@@ -0,0 +1,165 @@
+use std::{
+    io::{self, IsTerminal, Read, Write},
+    process::{Command, Stdio},
+};
+
+use super::browse::Key;
+
+/// The terminal `browse` draws on: in raw mode on the alternate screen,
+/// until dropped. Raw mode is set with `stty`, so it needs a Unix terminal.
+pub struct Terminal {
+    /// The settings `stty -g` saved, to restore.
+    saved: String,
+}
+
+impl Terminal {
+    pub fn open() -> io::Result<Self> {
+        if !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+            return Err(io::Error::other("browse needs a terminal"));
+        }
+        let saved = stty(&["-g"])?;
+        // reads return after a tenth of a second without input, to tell a
+        // lone Esc from the start of an escape sequence
+        stty(&["raw", "-echo", "min", "0", "time", "1"])?;
+        let mut out = io::stdout();
+        out.write_all(b"\x1b[?1049h\x1b[?25l")?;
+        out.flush()?;
+        Ok(Terminal {
+            saved: saved.trim().to_string(),
+        })
+    }
+
+    /// The width and height of the terminal, or 80 by 24 if `stty` can't
+    /// tell.
+    pub fn size(&self) -> (usize, usize) {
+        let size = stty(&["size"]).unwrap_or_default();
+        match size.split_once(' ') {
+            Some((rows, columns)) => match (columns.trim().parse(), rows.parse()) {
+                (Ok(columns), Ok(rows)) if columns > 0 && rows > 0 => (columns, rows),
+                _ => (80, 24),
+            },
+            None => (80, 24),
+        }
+    }
+
+    /// Draws `lines` over the whole screen.
+    pub fn draw(&mut self, lines: &[String]) -> io::Result<()> {
+        let mut screen = String::from("\x1b[H");
+        for (i, line) in lines.iter().enumerate() {
+            if i > 0 {
+                screen.push_str("\r\n");
+            }
+            screen.push_str(line);
+            screen.push_str("\x1b[0m\x1b[K");
+        }
+        screen.push_str("\x1b[J");
+        let mut out = io::stdout();
+        out.write_all(screen.as_bytes())?;
+        out.flush()
+    }
+
+    /// The next key pressed, or `None` if there was none for a tenth of a
+    /// second. Keys which the browser doesn't know are skipped.
+    pub fn read_key(&mut self) -> io::Result<Option<Key>> {
+        let byte = match read_byte()? {
+            Some(byte) => byte,
+            None => return Ok(None),
+        };
+        let key = match byte {
+            0x1b => match read_byte()? {
+                None => Key::Esc,
+                Some(b'[') | Some(b'O') => match escape_sequence()? {
+                    Some(key) => key,
+                    None => return Ok(None),
+                },
+                Some(_) => return Ok(None),
+            },
+            b'\r' | b'\n' => Key::Enter,
+            b'\t' => Key::Tab,
+            0x7f | 0x08 => Key::Backspace,
+            0x03 => Key::Interrupt,
+            byte if byte < 0x20 => return Ok(None),
+            byte => Key::Char(read_char(byte)?),
+        };
+        Ok(Some(key))
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        let mut out = io::stdout();
+        let _ = out.write_all(b"\x1b[?25h\x1b[?1049l");
+        let _ = out.flush();
+        let _ = stty(&[&self.saved]);
+    }
+}
+
+/// Runs `stty` on the terminal, returning what it printed.
+fn stty(args: &[&str]) -> io::Result<String> {
+    let output = Command::new("stty")
+        .args(args)
+        .stdin(Stdio::inherit())
+        .stderr(Stdio::null())
+        .output()?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!("stty {} failed", args.join(" "))));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn read_byte() -> io::Result<Option<u8>> {
+    let mut byte = [0];
+    match io::stdin().read(&mut byte)? {
+        0 => Ok(None),
+        _ => Ok(Some(byte[0])),
+    }
+}
+
+/// The key of a CSI or SS3 sequence, after its `ESC [` or `ESC O`.
+fn escape_sequence() -> io::Result<Option<Key>> {
+    let mut parameters = String::new();
+    loop {
+        let byte = match read_byte()? {
+            Some(byte) => byte,
+            None => return Ok(None),
+        };
+        if byte.is_ascii_digit() || byte == b';' {
+            parameters.push(byte as char);
+            continue;
+        }
+        return Ok(match (byte, parameters.as_str()) {
+            (b'A', _) => Some(Key::Up),
+            (b'B', _) => Some(Key::Down),
+            (b'C', _) => Some(Key::Right),
+            (b'D', _) => Some(Key::Left),
+            (b'H', _) | (b'~', "1") | (b'~', "7") => Some(Key::Home),
+            (b'F', _) | (b'~', "4") | (b'~', "8") => Some(Key::End),
+            (b'~', "5") => Some(Key::PageUp),
+            (b'~', "6") => Some(Key::PageDown),
+            (b'Z', _) => Some(Key::BackTab),
+            _ => None,
+        });
+    }
+}
+
+/// The character UTF-8 encodes starting with `first`, reading the rest of
+/// its bytes.
+fn read_char(first: u8) -> io::Result<char> {
+    let len = match first {
+        0xc0..=0xdf => 2,
+        0xe0..=0xef => 3,
+        0xf0..=0xf7 => 4,
+        _ => 1,
+    };
+    let mut bytes = vec![first];
+    while bytes.len() < len {
+        match read_byte()? {
+            Some(byte) => bytes.push(byte),
+            None => break,
+        }
+    }
+    Ok(String::from_utf8_lossy(&bytes)
+        .chars()
+        .next()
+        .unwrap_or(char::REPLACEMENT_CHARACTER))
+}
@@ -0,0 +1,149 @@
+use std::{error::Error, fmt, path::Path};
+
+use crate::constantpool::MemberRef;
+
+use super::{
+    method_label,
+    scan::{parse_entry, Scan, ScanOptions},
+    Failure,
+};
+
+/// A member given on the command line as `<class>.<name>`, or with `#`
+/// for the dot, optionally followed by its descriptor.
+pub struct MemberPattern {
+    text: String,
+    class_name: String,
+    name: String,
+    descriptor: Option<String>,
+}
+
+impl MemberPattern {
+    pub fn parse(pattern: &str) -> Result<Self, Failure> {
+        let invalid = || {
+            Failure::usage(format!(
+                "invalid member: {} (expected <class>.<name>[<descriptor>])",
+                pattern
+            ))
+        };
+        let descriptor_start = pattern.find(['(', ':']).unwrap_or(pattern.len());
+        let (member, descriptor) = pattern.split_at(descriptor_start);
+        let (class_name, name) = member.rsplit_once(['.', '#']).ok_or_else(invalid)?;
+        if class_name.is_empty() || name.is_empty() {
+            return Err(invalid());
+        }
+        Ok(MemberPattern {
+            text: pattern.to_string(),
+            class_name: class_name.to_string(),
+            name: name.to_string(),
+            descriptor: match descriptor.strip_prefix(':').unwrap_or(descriptor) {
+                "" => None,
+                descriptor => Some(descriptor.to_string()),
+            },
+        })
+    }
+
+    pub fn matches(&self, class_name: &str, name: &str, descriptor: &str) -> bool {
+        class_name == self.class_name
+            && name == self.name
+            && self
+                .descriptor
+                .as_ref()
+                .is_none_or(|pattern| descriptor == pattern)
+    }
+
+    fn matches_member(&self, member: &MemberRef) -> bool {
+        self.matches(member.class_name, member.name, member.descriptor)
+    }
+}
+
+impl fmt::Display for MemberPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+/// Prints the references the methods of every class among `inputs` (class
+/// files, jars or directories) make, one to a line after the method and the
+/// offset of the instruction: the methods they call, the fields they read
+/// and write, and their `invokedynamic` call sites with the methods handed
+/// to the bootstrap method. With `calls` or `fields`, only the calls of and
+/// accesses to those members are printed, method handles counting as calls.
+pub fn run(
+    inputs: &[&str],
+    calls: &[MemberPattern],
+    fields: &[MemberPattern],
+    options: ScanOptions,
+) -> Result<(), Box<dyn Error>> {
+    let filtered = !calls.is_empty() || !fields.is_empty();
+    let single_class =
+        inputs.len() == 1 && inputs[0].ends_with(".class") && Path::new(inputs[0]).is_file();
+    let mut scan = Scan::new(options);
+    scan.run(inputs, |name, buf, parse_options| {
+        let class_file = parse_entry(buf, parse_options)?;
+        let constant_pool = &class_file.constant_pool;
+        for (method, refs) in class_file.member_references() {
+            let call_wanted = |member: &MemberRef| {
+                !filtered || calls.iter().any(|pattern| pattern.matches_member(member))
+            };
+            // (offset, instruction), in code order once sorted
+            let mut lines = Vec::new();
+            for call in &refs.calls {
+                if call_wanted(&call.member) {
+                    lines.push((
+                        call.offset,
+                        format!("{} {}", call.mnemonic(), call_text(&call.member)),
+                    ));
+                }
+            }
+            for access in &refs.fields {
+                if !filtered
+                    || fields
+                        .iter()
+                        .any(|pattern| pattern.matches_member(&access.member))
+                {
+                    let member = &access.member;
+                    let text = format!(
+                        "{}.{}:{}",
+                        member.class_name, member.name, member.descriptor
+                    );
+                    lines.push((access.offset, format!("{} {}", access.mnemonic(), text)));
+                }
+            }
+            for call in &refs.dynamic_calls {
+                if !filtered {
+                    let bootstrap = call.site.map_or("?".to_string(), |site| {
+                        format!("{}.{}", site.bootstrap.class_name, site.bootstrap.name)
+                    });
+                    let text = format!(
+                        "invokedynamic {}{} via {}",
+                        call.name, call.descriptor, bootstrap
+                    );
+                    lines.push((call.offset, text));
+                }
+                for handle in call.method_handles(constant_pool) {
+                    if call_wanted(&handle) {
+                        lines.push((
+                            call.offset,
+                            format!("invokedynamic handle {}", call_text(&handle)),
+                        ));
+                    }
+                }
+            }
+            lines.sort_by_key(|(offset, _)| *offset);
+            let label = method_label(&class_file, method, false);
+            for (offset, text) in lines {
+                if single_class {
+                    println!("{}: {}: {}", label, offset, text);
+                } else {
+                    println!("{}: {}: {}: {}", name, label, offset, text);
+                }
+            }
+        }
+        Ok(())
+    })?;
+    scan.finish()
+}
+
+fn call_text(member: &MemberRef) -> String {
+    format!("{}.{}{}", member.class_name, member.name, member.descriptor)
+}
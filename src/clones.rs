@@ -0,0 +1,307 @@
+//! Finding methods whose bodies are copies, or near copies, of each other.
+//!
+//! Method bodies are compared through a fingerprint of their normalized
+//! instruction stream, in which constant pool operands are replaced by the
+//! constants they name and switch padding is dropped. Two methods with the
+//! same fingerprint therefore do the same thing even when they come from
+//! classes with differently laid out constant pools. Near copies are found
+//! by comparing the sets of opcode n-grams of the two bodies.
+
+use std::collections::{BTreeSet, HashMap};
+
+use crate::{
+    attribute::Code,
+    classfile::ClassFile,
+    constantpool::ConstantPool,
+    friendly::{friendly_class_name, friendly_method_name},
+    instruction::{self, DecodeError, LOOKUPSWITCH, TABLESWITCH, WIDE},
+    ordering,
+    sha256::Sha256,
+};
+
+/// The length of the opcode sequences compared for near copies.
+const NGRAM_LENGTH: usize = 4;
+
+/// How deep constants referring to other constants are followed when
+/// normalizing, which also stops cycles in malformed pools.
+const MAX_CONSTANT_DEPTH: usize = 4;
+
+/// A method taking part in a [`CloneGroup`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CloneMember {
+    /// The declaring class's internal name, method name and descriptor,
+    /// e.g. `com/example/Foo.bar(I)V`.
+    pub name: String,
+    /// The same with readable names for what the compiler generated, see
+    /// [`crate::friendly`], when either the class or method has one.
+    pub friendly_name: Option<String>,
+    /// The length of the method's code array.
+    pub size: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CloneGroup {
+    /// Members by name, those of the same name in the order they were added.
+    pub members: Vec<CloneMember>,
+    /// Whether every member has exactly the same normalized code.
+    pub identical: bool,
+}
+
+impl CloneGroup {
+    /// The bytes that could be saved by keeping only the largest member.
+    pub fn duplicated_bytes(&self) -> usize {
+        let sizes = self.members.iter().map(|member| member.size);
+        sizes.clone().sum::<usize>() - sizes.max().unwrap_or(0)
+    }
+}
+
+#[derive(Debug)]
+struct Method {
+    member: CloneMember,
+    fingerprint: [u8; 32],
+    ngrams: BTreeSet<[u8; NGRAM_LENGTH]>,
+}
+
+/// Collects methods from any number of classes and groups the copies among
+/// them.
+#[derive(Debug)]
+pub struct CloneDetector {
+    min_size: usize,
+    methods: Vec<Method>,
+}
+
+impl CloneDetector {
+    /// Methods with less than `min_size` bytes of code, such as getters,
+    /// setters and empty constructors, are ignored.
+    pub fn new(min_size: usize) -> Self {
+        CloneDetector {
+            min_size,
+            methods: Vec::new(),
+        }
+    }
+
+    /// Adds every method of the class with a body of at least the minimum
+    /// size.
+    pub fn add_class(&mut self, class_file: &ClassFile) -> Result<(), DecodeError> {
+        let constant_pool = &class_file.constant_pool;
+        let class_name =
+            ConstantPool::class_name(constant_pool, class_file.this_class).unwrap_or("?");
+        let friendly_class = friendly_class_name(class_file);
+        for method in &class_file.methods {
+            let code = match method.code() {
+                Some(code) if code.code.len() >= self.min_size => code,
+                _ => continue,
+            };
+            let method_name = ConstantPool::utf8(constant_pool, method.name_index()).unwrap_or("?");
+            let descriptor =
+                ConstantPool::utf8(constant_pool, method.descriptor_index()).unwrap_or("");
+            let name = format!("{}.{}{}", class_name, method_name, descriptor);
+            let friendly_method = friendly_method_name(class_file, method);
+            let friendly_name =
+                (friendly_class.is_some() || friendly_method.is_some()).then(|| {
+                    let class_name = friendly_class.as_deref().unwrap_or(class_name);
+                    match &friendly_method {
+                        Some(friendly_method) => {
+                            format!("{}.{} {}", class_name, friendly_method, descriptor)
+                        }
+                        None => format!("{}.{}{}", class_name, method_name, descriptor),
+                    }
+                });
+            self.methods.push(Method {
+                member: CloneMember {
+                    name,
+                    friendly_name,
+                    size: code.code.len(),
+                },
+                fingerprint: fingerprint(code, constant_pool)?,
+                ngrams: opcode_ngrams(code)?,
+            });
+        }
+        Ok(())
+    }
+
+    /// Groups methods with identical normalized code. With a `similarity`
+    /// threshold between 0 and 1, groups are also merged when the opcode
+    /// n-gram sets of their bodies have a Jaccard similarity of at least
+    /// that much. Groups come largest saving first, ties by the name of
+    /// their first member.
+    pub fn groups(&self, similarity: Option<f64>) -> Vec<CloneGroup> {
+        let mut exact: HashMap<[u8; 32], Vec<usize>> = HashMap::new();
+        let mut order = Vec::new();
+        for (i, method) in self.methods.iter().enumerate() {
+            exact
+                .entry(method.fingerprint)
+                .or_insert_with(|| {
+                    order.push(method.fingerprint);
+                    Vec::new()
+                })
+                .push(i);
+        }
+        let exact_groups: Vec<Vec<usize>> = order
+            .iter()
+            .map(|fingerprint| exact.remove(fingerprint).unwrap_or_default())
+            .collect();
+
+        // union-find over the exact groups, by their first member
+        let mut parent: Vec<usize> = (0..exact_groups.len()).collect();
+        if let Some(threshold) = similarity {
+            let mut by_size: Vec<usize> = (0..exact_groups.len()).collect();
+            by_size.sort_by_key(|&group| self.methods[exact_groups[group][0]].ngrams.len());
+            for (position, &a) in by_size.iter().enumerate() {
+                let a_ngrams = &self.methods[exact_groups[a][0]].ngrams;
+                for &b in &by_size[position + 1..] {
+                    let b_ngrams = &self.methods[exact_groups[b][0]].ngrams;
+                    // |A ∩ B| / |A ∪ B| can't reach the threshold once the
+                    // smaller set is too small a fraction of the larger one
+                    if (a_ngrams.len() as f64) < threshold * b_ngrams.len() as f64 {
+                        break;
+                    }
+                    if jaccard(a_ngrams, b_ngrams) >= threshold {
+                        let (root_a, root_b) = (find(&mut parent, a), find(&mut parent, b));
+                        parent[root_a.max(root_b)] = root_a.min(root_b);
+                    }
+                }
+            }
+        }
+
+        let mut merged: Vec<Vec<usize>> = vec![Vec::new(); exact_groups.len()];
+        for group in 0..exact_groups.len() {
+            let root = find(&mut parent, group);
+            merged[root].push(group);
+        }
+        let mut groups: Vec<CloneGroup> = merged
+            .into_iter()
+            .filter(|parts| {
+                parts
+                    .iter()
+                    .map(|&group| exact_groups[group].len())
+                    .sum::<usize>()
+                    > 1
+            })
+            .map(|parts| {
+                let mut members: Vec<usize> = parts
+                    .iter()
+                    .flat_map(|&group| exact_groups[group].iter().copied())
+                    .collect();
+                members.sort_unstable();
+                let mut members: Vec<CloneMember> = members
+                    .into_iter()
+                    .map(|i| self.methods[i].member.clone())
+                    .collect();
+                ordering::by_name(&mut members, |member| member.name.as_str());
+                CloneGroup {
+                    members,
+                    identical: parts.len() == 1,
+                }
+            })
+            .collect();
+        ordering::rank(&mut groups, CloneGroup::duplicated_bytes, |group| {
+            group.members[0].name.as_str()
+        });
+        groups
+    }
+}
+
+fn find(parent: &mut [usize], mut node: usize) -> usize {
+    while parent[node] != node {
+        parent[node] = parent[parent[node]];
+        node = parent[node];
+    }
+    node
+}
+
+fn jaccard<T: Ord>(a: &BTreeSet<T>, b: &BTreeSet<T>) -> f64 {
+    // bodies too short to have any n-grams can only match exactly
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f64 / union as f64
+}
+
+/// A hash of the method body with constant pool indices replaced by what
+/// they name and switch padding removed.
+pub fn fingerprint(code: &Code, constant_pool: &[ConstantPool]) -> Result<[u8; 32], DecodeError> {
+    let mut hasher = Sha256::new();
+    for instruction in instruction::decode(&code.code) {
+        let instruction = instruction?;
+        hasher.update(&[instruction.opcode]);
+        let operands = match instruction.cp_index() {
+            Some(index) => {
+                let mut key = String::new();
+                constant_key(constant_pool, index, 0, &mut key);
+                // the count and zero byte of invokeinterface and the zero
+                // bytes of invokedynamic follow the index
+                let mut operands = key.into_bytes();
+                operands
+                    .extend_from_slice(&instruction.operands[2.min(instruction.operands.len())..]);
+                operands
+            }
+            None if matches!(instruction.opcode, TABLESWITCH | LOOKUPSWITCH) => {
+                let padding = (3 - instruction.offset as usize % 4) % 4;
+                instruction.operands[padding..].to_vec()
+            }
+            None => instruction.operands.to_vec(),
+        };
+        hasher.update(&(operands.len() as u32).to_be_bytes());
+        hasher.update(&operands);
+    }
+    Ok(hasher.finish())
+}
+
+/// Appends a description of the constant at `index` which is the same for
+/// equal constants in different pools.
+pub(crate) fn constant_key(
+    constant_pool: &[ConstantPool],
+    index: u16,
+    depth: usize,
+    key: &mut String,
+) {
+    let constant = match (index as usize)
+        .checked_sub(1)
+        .and_then(|i| constant_pool.get(i))
+    {
+        Some(constant) if depth < MAX_CONSTANT_DEPTH => constant,
+        _ => {
+            key.push('?');
+            return;
+        }
+    };
+    key.push_str(&constant.tag().to_string());
+    key.push('(');
+    match constant {
+        ConstantPool::UTF8(value) => key.push_str(&format!("{:?}", value)),
+        ConstantPool::Integer(_)
+        | ConstantPool::Float(_)
+        | ConstantPool::Long(_)
+        | ConstantPool::Double(_) => key.push_str(&format!("{:?}", constant)),
+        ConstantPool::MethodHandle(kind, _) => key.push_str(&format!("{},", kind)),
+        _ => {}
+    }
+    for referenced in constant.referenced_indices() {
+        constant_key(constant_pool, referenced, depth + 1, key);
+        key.push(',');
+    }
+    key.push(')');
+}
+
+/// The set of runs of consecutive opcodes in the method body. A `wide`
+/// instruction counts as the opcode it widens.
+pub fn opcode_ngrams(code: &Code) -> Result<BTreeSet<[u8; NGRAM_LENGTH]>, DecodeError> {
+    let opcodes = instruction::decode(&code.code)
+        .map(|instruction| {
+            instruction.map(|instruction| match instruction.opcode {
+                WIDE => instruction.operands.first().copied().unwrap_or(WIDE),
+                opcode => opcode,
+            })
+        })
+        .collect::<Result<Vec<u8>, _>>()?;
+    Ok(opcodes
+        .windows(NGRAM_LENGTH)
+        .map(|window| {
+            let mut ngram = [0; NGRAM_LENGTH];
+            ngram.copy_from_slice(window);
+            ngram
+        })
+        .collect())
+}
@@ -0,0 +1,91 @@
+//! Counts over the code of a method, cheap enough to take of every method
+//! of a code base, for dashboards. They're read off the decoded
+//! instructions alone; [`cfg`](crate::cfg) gives the exact cyclomatic
+//! complexity where building the control flow graph is worth it.
+
+use crate::{attribute::Code, instruction};
+
+const TABLESWITCH: u8 = 0xaa;
+const LOOKUPSWITCH: u8 = 0xab;
+
+/// The size and shape of a method's code.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MethodMetrics {
+    /// The length of the bytecode in bytes.
+    pub code_length: usize,
+    pub instructions: usize,
+    /// As the `Code` attribute gives it.
+    pub max_stack: u16,
+    /// As the `Code` attribute gives it.
+    pub max_locals: u16,
+    pub exception_handlers: usize,
+    /// The conditional branches, `ifeq` to `if_acmpne`, `ifnull` and
+    /// `ifnonnull`. `goto` adds no way through the code, so isn't one.
+    pub branches: usize,
+    /// The cases of every `tableswitch` and `lookupswitch`, the default
+    /// not counted.
+    pub switch_cases: usize,
+    /// The `invoke*` instructions, `invokedynamic` included.
+    pub invokes: usize,
+}
+
+impl MethodMetrics {
+    /// The metrics of `code`. Decoding stops at the first malformed
+    /// instruction, counting those before it.
+    ///
+    /// ```
+    /// use jvmb::{attribute::Code, codemetrics::MethodMetrics};
+    ///
+    /// // static int f(int a, int b) {
+    /// //     if (a > 0) { if (b > 0) return 1; return 2; }
+    /// //     switch (b) { case 0: return 3; case 1: return 4; default: return 0; }
+    /// // }
+    /// let code = Code::new(1, 2, vec![
+    ///     0x1a, 0x9e, 0, 11, // 0: iload_0, ifle 12
+    ///     0x1b, 0x9e, 0, 5, // 4: iload_1, ifle 10
+    ///     0x04, 0xac, // 8: iconst_1, ireturn
+    ///     0x05, 0xac, // 10: iconst_2, ireturn
+    ///     0x1b, // 12: iload_1
+    ///     0xaa, 0, 0, // 13: tableswitch, padded to 16
+    ///     0, 0, 0, 27, 0, 0, 0, 0, 0, 0, 0, 1, // default 40, low 0, high 1
+    ///     0, 0, 0, 23, 0, 0, 0, 25, // 0: 36, 1: 38
+    ///     0x06, 0xac, // 36: iconst_3, ireturn
+    ///     0x07, 0xac, // 38: iconst_4, ireturn
+    ///     0x03, 0xac, // 40: iconst_0, ireturn
+    /// ]);
+    /// let metrics = MethodMetrics::of(&code);
+    /// assert_eq!(metrics.code_length, 42);
+    /// assert_eq!(metrics.instructions, 16);
+    /// assert_eq!(metrics.branches, 2);
+    /// assert_eq!(metrics.switch_cases, 2);
+    /// assert_eq!(metrics.invokes, 0);
+    /// assert_eq!(metrics.cyclomatic_complexity(), 5);
+    /// ```
+    pub fn of(code: &Code) -> Self {
+        let mut metrics = MethodMetrics {
+            code_length: code.code.len(),
+            max_stack: code.max_stack,
+            max_locals: code.max_locals,
+            exception_handlers: code.exception_table.len(),
+            ..MethodMetrics::default()
+        };
+        for instruction in instruction::decode(&code.code).map_while(Result::ok) {
+            metrics.instructions += 1;
+            match instruction.opcode {
+                0x99..=0xa6 | 0xc6 | 0xc7 => metrics.branches += 1,
+                TABLESWITCH | LOOKUPSWITCH => {
+                    metrics.switch_cases += instruction.branch_targets().len().saturating_sub(1)
+                }
+                _ if instruction.is_invoke() => metrics.invokes += 1,
+                _ => {}
+            }
+        }
+        metrics
+    }
+
+    /// An approximation of the cyclomatic complexity: one, plus one for
+    /// every conditional branch, switch case and exception handler.
+    pub fn cyclomatic_complexity(&self) -> usize {
+        1 + self.branches + self.switch_cases + self.exception_handlers
+    }
+}
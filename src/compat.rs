@@ -0,0 +1,293 @@
+//! Checking the classes of an application against the symbols a target
+//! runtime provides, listed in a plain text file: every reference to a class
+//! or member the runtime lacks is reported with where it's made from.
+//!
+//! The list holds a symbol per line, named as [`crate::symbols`] names
+//! them. Blank lines and lines starting with `#` are skipped.
+//!
+//! ```text
+//! # classes, each optionally followed by its supertypes
+//! java/lang/Object
+//! java/lang/String : java/lang/Object java/lang/CharSequence
+//! # members
+//! java/lang/String#length()I
+//! java/lang/System#out:Ljava/io/PrintStream;
+//! ```
+//!
+//! A list of classes alone is checked at class level: any member of a listed
+//! class is taken to exist. Once the list names a member of any class, it's
+//! checked at member level, and a member referred to must be listed for its
+//! class or one of the class's supertypes, or be one of `java/lang/Object`'s.
+//! Classes listed without supertypes are taken to extend `java/lang/Object`.
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt,
+};
+
+use crate::{
+    classfile::ClassFile,
+    deps::class_dependencies,
+    symbols::{SymbolKind, SymbolTable},
+};
+
+const OBJECT: &str = "java/lang/Object";
+
+/// The symbols a target runtime provides.
+#[derive(Debug, Clone, Default)]
+pub struct SymbolList {
+    classes: HashMap<String, ListedClass>,
+    member_level: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+struct ListedClass {
+    supertypes: Vec<String>,
+    /// The part of each member's ID after the `#`.
+    members: HashSet<String>,
+}
+
+/// A line of a symbol list which isn't a class, supertype list or member.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolListError {
+    /// 1-based.
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for SymbolListError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for SymbolListError {}
+
+impl SymbolList {
+    pub fn parse(text: &str) -> Result<Self, SymbolListError> {
+        let mut list = SymbolList::default();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = |message: &str| SymbolListError {
+                line: i + 1,
+                message: format!("{}: {}", message, line),
+            };
+            if let Some((class, member)) = line.split_once('#') {
+                let is_member = match member.find(['(', ':']) {
+                    Some(0) | None => false,
+                    Some(end) if member.as_bytes()[end] == b'(' => member[end..].contains(')'),
+                    Some(end) => end + 1 < member.len(),
+                };
+                if class.is_empty() || !is_member || member.contains(char::is_whitespace) {
+                    return Err(error(
+                        "expected <class>#<name>(<descriptor>) or <class>#<name>:<descriptor>",
+                    ));
+                }
+                list.classes
+                    .entry(class.to_string())
+                    .or_default()
+                    .members
+                    .insert(member.to_string());
+                list.member_level = true;
+                continue;
+            }
+            let (class, supertypes) = match line.split_once(':') {
+                Some((class, supertypes)) => {
+                    (class.trim(), supertypes.split_whitespace().collect())
+                }
+                None => (line, Vec::new()),
+            };
+            if class.is_empty() || class.contains(char::is_whitespace) {
+                return Err(error(
+                    "expected a class, or a class and its supertypes after ':'",
+                ));
+            }
+            let listed = list.classes.entry(class.to_string()).or_default();
+            listed
+                .supertypes
+                .extend(supertypes.into_iter().map(str::to_string));
+        }
+        Ok(list)
+    }
+
+    /// Whether members are checked, because the list names some.
+    pub fn is_member_level(&self) -> bool {
+        self.member_level
+    }
+
+    pub fn has_class(&self, class: &str) -> bool {
+        self.classes.contains_key(class)
+    }
+
+    /// Whether `member`, the part of an ID after the `#`, is provided by
+    /// `class`: declared by it or one of its supertypes, as far as the list
+    /// tells.
+    pub fn has_member(&self, class: &str, member: &str) -> bool {
+        if !self.member_level {
+            return self.has_class(class);
+        }
+        let mut pending = vec![class];
+        let mut seen = HashSet::new();
+        while let Some(class) = pending.pop() {
+            if !seen.insert(class) {
+                continue;
+            }
+            let listed = match self.classes.get(class) {
+                Some(listed) => listed,
+                None => continue,
+            };
+            if listed.members.contains(member) {
+                return true;
+            }
+            if listed.supertypes.is_empty() {
+                pending.push(OBJECT);
+            }
+            pending.extend(listed.supertypes.iter().map(String::as_str));
+        }
+        false
+    }
+}
+
+/// Where a missing symbol is referred to from: a class, and for a member
+/// reference, the method and instruction making it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Location {
+    /// The file or jar entry of the class.
+    pub entry: String,
+    pub class: String,
+    /// The ID of the method making the reference.
+    pub method: Option<String>,
+    pub offset: Option<u32>,
+    pub line: Option<u16>,
+}
+
+/// A class or member the target lacks, with every reference to it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Missing {
+    pub symbol: String,
+    pub kind: SymbolKind,
+    pub locations: Vec<Location>,
+}
+
+/// Checks classes against a [`SymbolList`]. Classes added are the
+/// application's own: references among them are never missing.
+#[derive(Debug)]
+pub struct CompatCheck<'a> {
+    against: &'a SymbolList,
+    classes: Vec<(String, SymbolTable, Vec<String>)>,
+}
+
+impl<'a> CompatCheck<'a> {
+    pub fn new(against: &'a SymbolList) -> Self {
+        CompatCheck {
+            against,
+            classes: Vec::new(),
+        }
+    }
+
+    /// Adds a class read from `entry`.
+    pub fn add(&mut self, entry: &str, class_file: &ClassFile) {
+        let dependencies = class_dependencies(class_file).into_iter().collect();
+        self.classes
+            .push((entry.to_string(), class_file.symbols(), dependencies));
+    }
+
+    /// Every missing symbol, classes and members alike, by ID, with the
+    /// references to it in the order the classes were added.
+    pub fn finish(self) -> Vec<Missing> {
+        let own: HashMap<&str, &SymbolTable> = self
+            .classes
+            .iter()
+            .map(|(_, table, _)| (table.class.as_str(), table))
+            .collect();
+        let mut missing: BTreeMap<String, Missing> = BTreeMap::new();
+        let mut report = |symbol: &str, kind, location| {
+            missing
+                .entry(symbol.to_string())
+                .or_insert_with(|| Missing {
+                    symbol: symbol.to_string(),
+                    kind,
+                    locations: Vec::new(),
+                })
+                .locations
+                .push(location)
+        };
+
+        for (entry, table, dependencies) in &self.classes {
+            let location = |method: Option<&str>, offset, line| Location {
+                entry: entry.clone(),
+                class: table.class.clone(),
+                method: method.map(str::to_string),
+                offset,
+                line,
+            };
+            // the classes referred to by the members, reported there instead
+            let mut referenced_by_members = HashSet::new();
+            for reference in &table.references {
+                let (class, member) = match reference.target.split_once('#') {
+                    Some(parts) => parts,
+                    None => continue,
+                };
+                let here = location(
+                    Some(&reference.from),
+                    Some(reference.offset),
+                    reference.line,
+                );
+                // arrays have only Object's members
+                let class = match declaring_class(class, member, &own) {
+                    Some(class) if class.starts_with('[') => OBJECT,
+                    Some(class) => class,
+                    None => continue,
+                };
+                if !self.against.has_class(class) {
+                    referenced_by_members.insert(class);
+                    report(class, SymbolKind::Class, here);
+                } else if !self.against.has_member(class, member) {
+                    let id = format!("{}#{}", class, member);
+                    report(&id, reference.kind, here);
+                }
+            }
+            for class in dependencies {
+                if !own.contains_key(class.as_str())
+                    && !self.against.has_class(class)
+                    && !referenced_by_members.contains(class.as_str())
+                {
+                    report(class, SymbolKind::Class, location(None, None, None));
+                }
+            }
+        }
+        missing.into_values().collect()
+    }
+}
+
+/// The class outside the application a reference to `class#member` is
+/// resolved in: `class` itself, or for a class of the application which
+/// doesn't declare the member, the first superclass outside it. `None` when
+/// the application declares the member.
+fn declaring_class<'a>(
+    class: &'a str,
+    member: &str,
+    own: &HashMap<&str, &'a SymbolTable>,
+) -> Option<&'a str> {
+    let mut class = class;
+    // a cycle of superclasses can't be longer than the application
+    for _ in 0..=own.len() {
+        let table = match own.get(class) {
+            Some(table) => table,
+            None => return Some(class),
+        };
+        let id = format!("{}#{}", class, member);
+        if table
+            .definitions
+            .iter()
+            .any(|definition| definition.id == id)
+        {
+            return None;
+        }
+        class = table.super_class.as_deref()?;
+    }
+    None
+}
@@ -1,7 +1,17 @@
-use nom::{
-    multi::length_data,
-    number::complete::{be_f32, be_f64, be_i32, be_i64, be_u16, u8},
-    IResult,
+use alloc::collections::BTreeMap;
+use core::fmt;
+#[cfg(feature = "write")]
+use std::io;
+
+#[cfg(feature = "disasm")]
+use crate::classfile::ClassFile;
+use crate::prelude::*;
+#[cfg(feature = "write")]
+use crate::write::WriteBe;
+use crate::{
+    descriptor::ClassRef,
+    error::{self, be_i32, be_i64, be_u16, be_u32, be_u64, take, u8, ErrorAt, IResult, ParseError},
+    mutf8, spec,
 };
 
 const CONSTANT_CLASS: u8 = 7;
@@ -22,7 +32,202 @@ const CONSTANT_INVOKE_DYNAMIC: u8 = 18;
 const CONSTANT_MODULE: u8 = 19;
 const CONSTANT_PACKAGE: u8 = 20;
 
-#[derive(Debug)]
+/// A float constant kept as its exact bit pattern, so that NaN payloads
+/// aren't canonicalized on the way through `f32`. Constants compare by
+/// their bits, so a NaN equals itself and `0.0` doesn't equal `-0.0`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FloatBits(pub u32);
+
+impl FloatBits {
+    pub fn value(self) -> f32 {
+        f32::from_bits(self.0)
+    }
+
+    pub fn is_nan(self) -> bool {
+        self.value().is_nan()
+    }
+
+    pub fn is_subnormal(self) -> bool {
+        self.value().is_subnormal()
+    }
+}
+
+/// A double constant kept as its exact bit pattern, so that NaN payloads
+/// aren't canonicalized on the way through `f64`. Constants compare by
+/// their bits, so a NaN equals itself and `0.0` doesn't equal `-0.0`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DoubleBits(pub u64);
+
+impl DoubleBits {
+    pub fn value(self) -> f64 {
+        f64::from_bits(self.0)
+    }
+
+    pub fn is_nan(self) -> bool {
+        self.value().is_nan()
+    }
+
+    pub fn is_subnormal(self) -> bool {
+        self.value().is_subnormal()
+    }
+}
+
+/// A field or method reference resolved to names.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemberRef<'a> {
+    pub class_name: &'a str,
+    pub name: &'a str,
+    pub descriptor: &'a str,
+}
+
+/// What a `MethodHandle` entry does with the member it refers to, the
+/// `reference_kind` of its entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ReferenceKind {
+    GetField = 1,
+    GetStatic = 2,
+    PutField = 3,
+    PutStatic = 4,
+    InvokeVirtual = 5,
+    InvokeStatic = 6,
+    InvokeSpecial = 7,
+    NewInvokeSpecial = 8,
+    InvokeInterface = 9,
+}
+
+impl ReferenceKind {
+    /// The kind numbered `tag`, `None` for 0 and anything past 9.
+    pub fn from_tag(tag: u8) -> Option<Self> {
+        Some(match tag {
+            1 => ReferenceKind::GetField,
+            2 => ReferenceKind::GetStatic,
+            3 => ReferenceKind::PutField,
+            4 => ReferenceKind::PutStatic,
+            5 => ReferenceKind::InvokeVirtual,
+            6 => ReferenceKind::InvokeStatic,
+            7 => ReferenceKind::InvokeSpecial,
+            8 => ReferenceKind::NewInvokeSpecial,
+            9 => ReferenceKind::InvokeInterface,
+            _ => return None,
+        })
+    }
+
+    pub fn tag(self) -> u8 {
+        self as u8
+    }
+
+    /// The name the JVMS gives the kind, e.g. `REF_invokeStatic`.
+    pub fn name(self) -> &'static str {
+        match self {
+            ReferenceKind::GetField => "REF_getField",
+            ReferenceKind::GetStatic => "REF_getStatic",
+            ReferenceKind::PutField => "REF_putField",
+            ReferenceKind::PutStatic => "REF_putStatic",
+            ReferenceKind::InvokeVirtual => "REF_invokeVirtual",
+            ReferenceKind::InvokeStatic => "REF_invokeStatic",
+            ReferenceKind::InvokeSpecial => "REF_invokeSpecial",
+            ReferenceKind::NewInvokeSpecial => "REF_newInvokeSpecial",
+            ReferenceKind::InvokeInterface => "REF_invokeInterface",
+        }
+    }
+
+    /// Whether the handle reads or writes a field rather than calling a
+    /// method.
+    pub fn is_field(self) -> bool {
+        self.tag() <= 4
+    }
+}
+
+impl fmt::Display for ReferenceKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// The roles a UTF8 entry plays in a class, as a set of bits. An empty set
+/// means nothing refers to the entry.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Utf8Roles(pub u16);
+
+impl Utf8Roles {
+    pub const CLASS_NAME: Utf8Roles = Utf8Roles(1);
+    pub const METHOD_NAME: Utf8Roles = Utf8Roles(1 << 1);
+    pub const FIELD_NAME: Utf8Roles = Utf8Roles(1 << 2);
+    /// A field or method descriptor, including the array and class
+    /// descriptors annotations and verification types use.
+    pub const DESCRIPTOR: Utf8Roles = Utf8Roles(1 << 3);
+    pub const SIGNATURE: Utf8Roles = Utf8Roles(1 << 4);
+    pub const STRING_LITERAL: Utf8Roles = Utf8Roles(1 << 5);
+    pub const ATTRIBUTE_NAME: Utf8Roles = Utf8Roles(1 << 6);
+    pub const ANNOTATION_ELEMENT_NAME: Utf8Roles = Utf8Roles(1 << 7);
+    pub const MODULE_NAME: Utf8Roles = Utf8Roles(1 << 8);
+    pub const PACKAGE_NAME: Utf8Roles = Utf8Roles(1 << 9);
+    /// Any other use: source file and local variable names, module
+    /// versions, the payload of custom attributes and so on.
+    pub const OTHER: Utf8Roles = Utf8Roles(1 << 10);
+
+    const NAMES: [(Utf8Roles, &'static str); 11] = [
+        (Utf8Roles::CLASS_NAME, "class name"),
+        (Utf8Roles::METHOD_NAME, "method name"),
+        (Utf8Roles::FIELD_NAME, "field name"),
+        (Utf8Roles::DESCRIPTOR, "descriptor"),
+        (Utf8Roles::SIGNATURE, "signature"),
+        (Utf8Roles::STRING_LITERAL, "string literal"),
+        (Utf8Roles::ATTRIBUTE_NAME, "attribute name"),
+        (
+            Utf8Roles::ANNOTATION_ELEMENT_NAME,
+            "annotation element name",
+        ),
+        (Utf8Roles::MODULE_NAME, "module name"),
+        (Utf8Roles::PACKAGE_NAME, "package name"),
+        (Utf8Roles::OTHER, "other"),
+    ];
+
+    pub fn contains(self, roles: Utf8Roles) -> bool {
+        self.0 & roles.0 == roles.0
+    }
+
+    pub fn insert(&mut self, roles: Utf8Roles) {
+        self.0 |= roles.0;
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// The names of the roles in the set, in the order of the constants
+    /// above.
+    pub fn names(self) -> Vec<&'static str> {
+        Utf8Roles::NAMES
+            .iter()
+            .filter(|(role, _)| self.contains(*role))
+            .map(|(_, name)| *name)
+            .collect()
+    }
+}
+
+impl core::ops::BitOr for Utf8Roles {
+    type Output = Utf8Roles;
+
+    fn bitor(self, other: Utf8Roles) -> Utf8Roles {
+        Utf8Roles(self.0 | other.0)
+    }
+}
+
+/// How [`ParseOptions`](crate::classfile::ParseOptions) has the pool read.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct ConstantOptions<'a> {
+    /// tag -> size of the body, for constants unknown to jvmb
+    pub(crate) unknown_sizes: Option<&'a BTreeMap<u8, usize>>,
+    /// Whether UTF8 entries which aren't modified UTF-8 are kept as
+    /// [`ConstantPool::RawUtf8`] rather than failing the parse.
+    pub(crate) lenient_utf8: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ConstantPool {
     Class(u16),
     FieldRef(u16, u16),
@@ -30,42 +235,670 @@ pub enum ConstantPool {
     InterfaceMethodRef(u16, u16),
     String(u16),
     Integer(i32),
-    Float(f32),
+    Float(FloatBits),
     Long(i64),
-    Double(f64),
+    Double(DoubleBits),
     NameAndType(u16, u16),
     UTF8(String),
+    /// A UTF8 entry holding a surrogate without its pair, which a Rust
+    /// string can't hold, as the UTF-16 code units Java holds strings in.
+    /// Lookups of UTF8 entries don't find it; compilers only emit one for
+    /// a string literal, such as Guava's `"\uD800"`.
+    Utf16(Vec<u16>),
+    /// A UTF8 entry whose bytes aren't modified UTF-8 at all, as some
+    /// obfuscators write them, kept as they are. Only parsing with
+    /// [`ParseOptions::lenient_utf8`](crate::classfile::ParseOptions::lenient_utf8)
+    /// reads one, and [`ClassFile::validate`](crate::classfile::ClassFile::validate)
+    /// reports it. Lookups of UTF8 entries don't find it;
+    /// [`mutf8::decode_lossy`] shows what can be made of it.
+    RawUtf8(Vec<u8>),
     MethodHandle(u8, u16),
     MethodType(u16),
     Dynamic(u16, u16),
     InvokeDynamic(u16, u16),
     Module(u16),
     Package(u16),
+    /// The slot after a `Long` or `Double`, which the JVMS counts as taken
+    /// (§4.4.5) but which holds nothing. It keeps every later entry at its
+    /// spec index, and isn't written.
+    Unusable,
+    /// A constant of a kind newer than jvmb knows, read from a class file
+    /// of an [unsupported version](crate::classfile::UnsupportedVersion)
+    /// by assuming its size, as set by
+    /// [`ParseOptions::assume_constant_size`](crate::classfile::ParseOptions::assume_constant_size).
+    /// `body` is everything after the tag.
+    Unknown {
+        tag: u8,
+        body: Vec<u8>,
+    },
 }
 
+/// Why an index doesn't lead to the constant pool entry needed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConstantPoolError {
+    /// Index 0, or an index past the end of a pool of `count` entries.
+    OutOfRange { index: u16, count: usize },
+    /// An entry of another kind than `expected`, e.g. `Utf8`, or a chain
+    /// of them such as `Methodref or InterfaceMethodref`.
+    WrongKind {
+        index: u16,
+        expected: &'static str,
+        found: &'static str,
+    },
+    /// A `MethodHandle` entry whose `reference_kind` isn't 1 to 9.
+    UnknownReferenceKind { index: u16, kind: u8 },
+}
+
+impl fmt::Display for ConstantPoolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConstantPoolError::OutOfRange { index, count } => write!(
+                f,
+                "constant pool index {} is out of range (1 to {})",
+                index, count
+            ),
+            ConstantPoolError::WrongKind {
+                index,
+                expected,
+                found,
+            } => write!(
+                f,
+                "constant pool entry {} is {}, expected {}",
+                index, found, expected
+            ),
+            ConstantPoolError::UnknownReferenceKind { index, kind } => write!(
+                f,
+                "constant pool entry {} is a MethodHandle of unknown kind {}",
+                index, kind
+            ),
+        }
+    }
+}
+
+impl core::error::Error for ConstantPoolError {}
+
+/// The rest of the input after a partial parse, or the input at the entry
+/// which failed along with the error.
+pub(crate) type PartialParse<'a> = Result<&'a [u8], (&'a [u8], nom::Err<ErrorAt<&'a [u8]>>)>;
+
 impl ConstantPool {
-    pub fn parse(mut buf: &[u8], constant_pool_count: usize) -> IResult<&[u8], Vec<ConstantPool>> {
-        let mut constant_pool = Vec::with_capacity(constant_pool_count - 1);
+    /// The entry at the 1-based `index`. Unlike [`ConstantPool::utf8`] and
+    /// its siblings, this and the other `get_` and `resolve_` lookups say
+    /// why they failed.
+    pub fn entry(
+        constant_pool: &[ConstantPool],
+        index: u16,
+    ) -> Result<&ConstantPool, ConstantPoolError> {
+        (index as usize)
+            .checked_sub(1)
+            .and_then(|position| constant_pool.get(position))
+            .ok_or(ConstantPoolError::OutOfRange {
+                index,
+                count: constant_pool.len(),
+            })
+    }
+
+    /// The UTF8 entry at `index`.
+    pub fn get_utf8(constant_pool: &[ConstantPool], index: u16) -> Result<&str, ConstantPoolError> {
+        match Self::entry(constant_pool, index)? {
+            ConstantPool::UTF8(value) => Ok(value),
+            found => Err(found.wrong_kind(index, "Utf8")),
+        }
+    }
+
+    /// The name held by the `CONSTANT_Class` entry at `index`.
+    pub fn get_class_name(
+        constant_pool: &[ConstantPool],
+        index: u16,
+    ) -> Result<&str, ConstantPoolError> {
+        match Self::entry(constant_pool, index)? {
+            ConstantPool::Class(name_index) => Self::get_utf8(constant_pool, *name_index),
+            found => Err(found.wrong_kind(index, "Class")),
+        }
+    }
+
+    /// The name and descriptor of the `NameAndType` entry at `index`.
+    pub fn get_name_and_type(
+        constant_pool: &[ConstantPool],
+        index: u16,
+    ) -> Result<(&str, &str), ConstantPoolError> {
+        match Self::entry(constant_pool, index)? {
+            ConstantPool::NameAndType(name_index, descriptor_index) => Ok((
+                Self::get_utf8(constant_pool, *name_index)?,
+                Self::get_utf8(constant_pool, *descriptor_index)?,
+            )),
+            found => Err(found.wrong_kind(index, "NameAndType")),
+        }
+    }
+
+    /// Resolves the `Methodref` or `InterfaceMethodref` entry at `index`.
+    pub fn resolve_method_ref(
+        constant_pool: &[ConstantPool],
+        index: u16,
+    ) -> Result<MemberRef<'_>, ConstantPoolError> {
+        match Self::entry(constant_pool, index)? {
+            ConstantPool::MethodRef(class_index, name_and_type_index)
+            | ConstantPool::InterfaceMethodRef(class_index, name_and_type_index) => {
+                Self::resolve_member(constant_pool, *class_index, *name_and_type_index)
+            }
+            found => Err(found.wrong_kind(index, "Methodref or InterfaceMethodref")),
+        }
+    }
+
+    /// Resolves the `Fieldref` entry at `index`.
+    pub fn resolve_field_ref(
+        constant_pool: &[ConstantPool],
+        index: u16,
+    ) -> Result<MemberRef<'_>, ConstantPoolError> {
+        match Self::entry(constant_pool, index)? {
+            ConstantPool::FieldRef(class_index, name_and_type_index) => {
+                Self::resolve_member(constant_pool, *class_index, *name_and_type_index)
+            }
+            found => Err(found.wrong_kind(index, "Fieldref")),
+        }
+    }
+
+    /// Resolves the `MethodHandle` entry at `index` to its kind and the
+    /// member it refers to, which must be of the kind of ref the kind calls
+    /// for: a `Fieldref` for the field kinds, an `InterfaceMethodref` for
+    /// `REF_invokeInterface` and a `Methodref` for the others, but for
+    /// `REF_invokeStatic` and `REF_invokeSpecial`, which take either.
+    pub fn resolve_method_handle(
+        constant_pool: &[ConstantPool],
+        index: u16,
+    ) -> Result<(ReferenceKind, MemberRef<'_>), ConstantPoolError> {
+        let (kind, reference_index) = match Self::entry(constant_pool, index)? {
+            ConstantPool::MethodHandle(kind, reference_index) => (*kind, *reference_index),
+            found => return Err(found.wrong_kind(index, "MethodHandle")),
+        };
+        let kind = ReferenceKind::from_tag(kind)
+            .ok_or(ConstantPoolError::UnknownReferenceKind { index, kind })?;
+        let expected = match kind {
+            _ if kind.is_field() => "Fieldref",
+            ReferenceKind::InvokeStatic | ReferenceKind::InvokeSpecial => {
+                "Methodref or InterfaceMethodref"
+            }
+            ReferenceKind::InvokeInterface => "InterfaceMethodref",
+            _ => "Methodref",
+        };
+        let (class_index, name_and_type_index) =
+            match (kind, Self::entry(constant_pool, reference_index)?) {
+                (_, ConstantPool::FieldRef(class_index, name_and_type_index))
+                    if kind.is_field() =>
+                {
+                    (*class_index, *name_and_type_index)
+                }
+                (
+                    ReferenceKind::InvokeVirtual
+                    | ReferenceKind::InvokeStatic
+                    | ReferenceKind::InvokeSpecial
+                    | ReferenceKind::NewInvokeSpecial,
+                    ConstantPool::MethodRef(class_index, name_and_type_index),
+                )
+                | (
+                    ReferenceKind::InvokeStatic
+                    | ReferenceKind::InvokeSpecial
+                    | ReferenceKind::InvokeInterface,
+                    ConstantPool::InterfaceMethodRef(class_index, name_and_type_index),
+                ) => (*class_index, *name_and_type_index),
+                (_, found) => return Err(found.wrong_kind(reference_index, expected)),
+            };
+        let member = Self::resolve_member(constant_pool, class_index, name_and_type_index)?;
+        Ok((kind, member))
+    }
 
+    fn resolve_member(
+        constant_pool: &[ConstantPool],
+        class_index: u16,
+        name_and_type_index: u16,
+    ) -> Result<MemberRef<'_>, ConstantPoolError> {
+        let (name, descriptor) = Self::get_name_and_type(constant_pool, name_and_type_index)?;
+        Ok(MemberRef {
+            class_name: Self::get_class_name(constant_pool, class_index)?,
+            name,
+            descriptor,
+        })
+    }
+
+    pub(crate) fn wrong_kind(&self, index: u16, expected: &'static str) -> ConstantPoolError {
+        ConstantPoolError::WrongKind {
+            index,
+            expected,
+            found: self.kind_name(),
+        }
+    }
+
+    /// The name of the kind of entry, as the JVMS has it, e.g. `Methodref`.
+    pub fn kind_name(&self) -> &'static str {
+        match self {
+            ConstantPool::Unusable => "unusable slot",
+            constant => {
+                spec::constant_spec(constant.tag()).map_or("unknown constant", |spec| spec.name)
+            }
+        }
+    }
+
+    /// Looks up the UTF8 entry at the 1-based `index`.
+    pub fn utf8(constant_pool: &[ConstantPool], index: u16) -> Option<&str> {
+        match constant_pool.get((index as usize).checked_sub(1)?) {
+            Some(ConstantPool::UTF8(value)) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// The values of the `String` entries, in the order of the pool. Those
+    /// held as [`ConstantPool::Utf16`], with unpaired surrogates, are left
+    /// out.
+    pub fn string_values(constant_pool: &[ConstantPool]) -> Vec<&str> {
+        constant_pool
+            .iter()
+            .filter_map(|constant| match constant {
+                ConstantPool::String(index) => Self::utf8(constant_pool, *index),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Looks up the name held by the `CONSTANT_Class` entry at `index`.
+    pub fn class_name(constant_pool: &[ConstantPool], index: u16) -> Option<&str> {
+        match constant_pool.get((index as usize).checked_sub(1)?) {
+            Some(ConstantPool::Class(name_index)) => Self::utf8(constant_pool, *name_index),
+            _ => None,
+        }
+    }
+
+    /// Looks up the name held by the `CONSTANT_Module` entry at `index`.
+    pub fn module_name(constant_pool: &[ConstantPool], index: u16) -> Option<&str> {
+        match constant_pool.get((index as usize).checked_sub(1)?) {
+            Some(ConstantPool::Module(name_index)) => Self::utf8(constant_pool, *name_index),
+            _ => None,
+        }
+    }
+
+    /// Looks up the name, in internal form, held by the `CONSTANT_Package`
+    /// entry at `index`.
+    pub fn package_name(constant_pool: &[ConstantPool], index: u16) -> Option<&str> {
+        match constant_pool.get((index as usize).checked_sub(1)?) {
+            Some(ConstantPool::Package(name_index)) => Self::utf8(constant_pool, *name_index),
+            _ => None,
+        }
+    }
+
+    /// Classifies the `CONSTANT_Class` entry at `index` as naming a class or
+    /// an array type.
+    pub fn class_ref(constant_pool: &[ConstantPool], index: u16) -> Option<ClassRef> {
+        ClassRef::from_name(Self::class_name(constant_pool, index)?).ok()
+    }
+
+    /// Resolves the `Fieldref`, `Methodref` or `InterfaceMethodref` entry at
+    /// `index`.
+    pub fn member_ref(constant_pool: &[ConstantPool], index: u16) -> Option<MemberRef<'_>> {
+        let (class_index, name_and_type_index) =
+            match constant_pool.get((index as usize).checked_sub(1)?)? {
+                ConstantPool::FieldRef(class_index, name_and_type_index)
+                | ConstantPool::MethodRef(class_index, name_and_type_index)
+                | ConstantPool::InterfaceMethodRef(class_index, name_and_type_index) => {
+                    (*class_index, *name_and_type_index)
+                }
+                _ => return None,
+            };
+        let (name_index, descriptor_index) =
+            match constant_pool.get((name_and_type_index as usize).checked_sub(1)?)? {
+                ConstantPool::NameAndType(name_index, descriptor_index) => {
+                    (*name_index, *descriptor_index)
+                }
+                _ => return None,
+            };
+        Some(MemberRef {
+            class_name: Self::class_name(constant_pool, class_index)?,
+            name: Self::utf8(constant_pool, name_index)?,
+            descriptor: Self::utf8(constant_pool, descriptor_index)?,
+        })
+    }
+
+    /// Resolves the `MethodHandle` entry at `index` to its reference kind and
+    /// the member it refers to.
+    pub fn method_handle(
+        constant_pool: &[ConstantPool],
+        index: u16,
+    ) -> Option<(u8, MemberRef<'_>)> {
+        match constant_pool.get((index as usize).checked_sub(1)?)? {
+            ConstantPool::MethodHandle(kind, reference_index) => {
+                Some((*kind, Self::member_ref(constant_pool, *reference_index)?))
+            }
+            _ => None,
+        }
+    }
+
+    /// Resolves the `NameAndType` entry at `index` to its name and
+    /// descriptor.
+    pub fn name_and_type(constant_pool: &[ConstantPool], index: u16) -> Option<(&str, &str)> {
+        match constant_pool.get((index as usize).checked_sub(1)?)? {
+            ConstantPool::NameAndType(name_index, descriptor_index) => Some((
+                Self::utf8(constant_pool, *name_index)?,
+                Self::utf8(constant_pool, *descriptor_index)?,
+            )),
+            _ => None,
+        }
+    }
+
+    /// Returns the index of the first UTF8 entry holding `value`.
+    pub fn utf8_index(constant_pool: &[ConstantPool], value: &str) -> Option<u16> {
+        constant_pool
+            .iter()
+            .position(|constant| matches!(constant, ConstantPool::UTF8(s) if s == value))
+            .map(|position| position as u16 + 1)
+    }
+
+    /// Returns the index of the UTF8 entry holding `value`, appending a new
+    /// entry to the end of the pool if there isn't one yet.
+    pub fn intern_utf8(constant_pool: &mut Vec<ConstantPool>, value: &str) -> u16 {
+        Self::utf8_index(constant_pool, value).unwrap_or_else(|| {
+            constant_pool.push(ConstantPool::UTF8(value.to_string()));
+            constant_pool.len() as u16
+        })
+    }
+
+    pub fn tag(&self) -> u8 {
+        match self {
+            ConstantPool::Class(_) => CONSTANT_CLASS,
+            ConstantPool::FieldRef(..) => CONSTANT_FIELD_REF,
+            ConstantPool::MethodRef(..) => CONSTANT_METHOD_REF,
+            ConstantPool::InterfaceMethodRef(..) => CONSTANT_INTERFACE_METHOD_REF,
+            ConstantPool::String(_) => CONSTANT_STRING,
+            ConstantPool::Integer(_) => CONSTANT_INTEGER,
+            ConstantPool::Float(_) => CONSTANT_FLOAT,
+            ConstantPool::Long(_) => CONSTANT_LONG,
+            ConstantPool::Double(_) => CONSTANT_DOUBLE,
+            ConstantPool::NameAndType(..) => CONSTANT_NAME_AND_TYPE,
+            ConstantPool::UTF8(_) | ConstantPool::Utf16(_) | ConstantPool::RawUtf8(_) => {
+                CONSTANT_UTF8
+            }
+            ConstantPool::MethodHandle(..) => CONSTANT_METHOD_HANDLE,
+            ConstantPool::MethodType(_) => CONSTANT_METHOD_TYPE,
+            ConstantPool::Dynamic(..) => CONSTANT_DYNAMIC,
+            ConstantPool::InvokeDynamic(..) => CONSTANT_INVOKE_DYNAMIC,
+            ConstantPool::Module(_) => CONSTANT_MODULE,
+            ConstantPool::Package(_) => CONSTANT_PACKAGE,
+            // no constant has tag 0
+            ConstantPool::Unusable => 0,
+            ConstantPool::Unknown { tag, .. } => *tag,
+        }
+    }
+
+    /// The roles of every UTF8 entry in the pool of `class_file`, keyed by
+    /// index. Roles are gathered from everything
+    /// [`ClassFile::visit_constant_roles`] reaches and carried through the
+    /// entries in between: the UTF8 entry of a `Class` is a class name, the
+    /// name of a `NameAndType` behind a `Fieldref` a field name, and so on.
+    /// Entries only reachable from unused entries are unreferenced, as in
+    /// [`ClassFile::unused_constants`].
+    #[cfg(feature = "disasm")]
+    pub fn utf8_roles(class_file: &ClassFile) -> BTreeMap<u16, Utf8Roles> {
+        let constant_pool = &class_file.constant_pool;
+        let mut roles: BTreeMap<u16, Utf8Roles> = constant_pool
+            .iter()
+            .enumerate()
+            .filter(|(_, constant)| {
+                matches!(
+                    constant,
+                    ConstantPool::UTF8(_) | ConstantPool::Utf16(_) | ConstantPool::RawUtf8(_)
+                )
+            })
+            .map(|(i, _)| (i as u16 + 1, Utf8Roles::default()))
+            .collect();
+        let mut seen = std::collections::HashSet::new();
+        let mut pending = Vec::new();
+        class_file.visit_constant_roles(&mut |index, role| pending.push((index, role)));
+        while let Some((index, role)) = pending.pop() {
+            if !seen.insert((index, role)) {
+                continue;
+            }
+            let constant = match (index as usize)
+                .checked_sub(1)
+                .and_then(|i| constant_pool.get(i))
+            {
+                Some(constant) => constant,
+                None => continue,
+            };
+            match constant {
+                ConstantPool::UTF8(_) | ConstantPool::Utf16(_) | ConstantPool::RawUtf8(_) => {
+                    roles.entry(index).or_default().insert(role)
+                }
+                ConstantPool::Class(index) => pending.push((*index, Utf8Roles::CLASS_NAME)),
+                ConstantPool::String(index) => pending.push((*index, Utf8Roles::STRING_LITERAL)),
+                ConstantPool::MethodType(index) => pending.push((*index, Utf8Roles::DESCRIPTOR)),
+                ConstantPool::Module(index) => pending.push((*index, Utf8Roles::MODULE_NAME)),
+                ConstantPool::Package(index) => pending.push((*index, Utf8Roles::PACKAGE_NAME)),
+                ConstantPool::FieldRef(class_index, name_and_type_index) => {
+                    pending.push((*class_index, Utf8Roles::CLASS_NAME));
+                    pending.push((*name_and_type_index, Utf8Roles::FIELD_NAME));
+                }
+                ConstantPool::MethodRef(class_index, name_and_type_index)
+                | ConstantPool::InterfaceMethodRef(class_index, name_and_type_index) => {
+                    pending.push((*class_index, Utf8Roles::CLASS_NAME));
+                    pending.push((*name_and_type_index, Utf8Roles::METHOD_NAME));
+                }
+                ConstantPool::Dynamic(_, name_and_type_index) => {
+                    pending.push((*name_and_type_index, Utf8Roles::FIELD_NAME))
+                }
+                ConstantPool::InvokeDynamic(_, name_and_type_index) => {
+                    pending.push((*name_and_type_index, Utf8Roles::METHOD_NAME))
+                }
+                // the name plays whatever part the entry was reached as
+                ConstantPool::NameAndType(name_index, descriptor_index) => {
+                    pending.push((*name_index, role));
+                    pending.push((*descriptor_index, Utf8Roles::DESCRIPTOR));
+                }
+                ConstantPool::MethodHandle(_, reference_index) => {
+                    pending.push((*reference_index, role))
+                }
+                ConstantPool::Integer(_)
+                | ConstantPool::Float(_)
+                | ConstantPool::Long(_)
+                | ConstantPool::Double(_)
+                | ConstantPool::Unusable
+                | ConstantPool::Unknown { .. } => {}
+            }
+        }
+        roles
+    }
+
+    /// Whether `ldc` can push the entry and a bootstrap method take it as a
+    /// static argument (JVMS §4.4, table 4.4-C).
+    pub fn is_loadable(&self) -> bool {
+        matches!(
+            self,
+            ConstantPool::Integer(_)
+                | ConstantPool::Float(_)
+                | ConstantPool::Long(_)
+                | ConstantPool::Double(_)
+                | ConstantPool::Class(_)
+                | ConstantPool::String(_)
+                | ConstantPool::MethodHandle(..)
+                | ConstantPool::MethodType(_)
+                | ConstantPool::Dynamic(..)
+        )
+    }
+
+    /// The pool indices this entry refers to. The bootstrap method index of
+    /// `Dynamic` and `InvokeDynamic` points into the `BootstrapMethods`
+    /// attribute, not the pool, and isn't included.
+    pub fn referenced_indices(&self) -> Vec<u16> {
+        match self {
+            ConstantPool::Class(index)
+            | ConstantPool::String(index)
+            | ConstantPool::MethodType(index)
+            | ConstantPool::Module(index)
+            | ConstantPool::Package(index)
+            | ConstantPool::MethodHandle(_, index)
+            | ConstantPool::Dynamic(_, index)
+            | ConstantPool::InvokeDynamic(_, index) => vec![*index],
+            ConstantPool::FieldRef(first, second)
+            | ConstantPool::MethodRef(first, second)
+            | ConstantPool::InterfaceMethodRef(first, second)
+            | ConstantPool::NameAndType(first, second) => vec![*first, *second],
+            ConstantPool::Integer(_)
+            | ConstantPool::Float(_)
+            | ConstantPool::Long(_)
+            | ConstantPool::Double(_)
+            | ConstantPool::UTF8(_)
+            | ConstantPool::Utf16(_)
+            | ConstantPool::RawUtf8(_)
+            | ConstantPool::Unusable
+            | ConstantPool::Unknown { .. } => Vec::new(),
+        }
+    }
+
+    /// The number of bytes the entry takes up in the class file, tag
+    /// included.
+    pub fn encoded_len(&self) -> usize {
+        if let ConstantPool::Unusable = self {
+            return 0;
+        }
+        1 + match self {
+            ConstantPool::UTF8(value) => 2 + mutf8::encoded_len(value),
+            ConstantPool::Utf16(units) => 2 + mutf8::encode_utf16(units).len(),
+            ConstantPool::RawUtf8(bytes) => 2 + bytes.len(),
+            ConstantPool::Class(_)
+            | ConstantPool::String(_)
+            | ConstantPool::MethodType(_)
+            | ConstantPool::Module(_)
+            | ConstantPool::Package(_) => 2,
+            ConstantPool::MethodHandle(..) => 3,
+            ConstantPool::Long(_) | ConstantPool::Double(_) => 8,
+            ConstantPool::Unknown { body, .. } => body.len(),
+            _ => 4,
+        }
+    }
+
+    pub fn parse(buf: &[u8], constant_pool_count: usize) -> IResult<&[u8], Vec<ConstantPool>> {
+        Self::parse_skipping(buf, constant_pool_count, ConstantOptions::default())
+    }
+
+    /// Parses a pool serialized on its own, as some dump formats and agents
+    /// hand it over, with `constant_pool_count` as a class file would give
+    /// it: one more than the number of slots. `buf` must hold the pool and
+    /// nothing else, so the input left is always empty and trailing bytes
+    /// fail with [`ParseError::TrailingBytes`].
+    pub fn parse_standalone(
+        buf: &[u8],
+        constant_pool_count: usize,
+    ) -> IResult<&[u8], Vec<ConstantPool>> {
+        error::all_consuming(buf, |buf| Self::parse(buf, constant_pool_count))
+    }
+
+    /// Parses the pool, reading constants of unknown kinds as
+    /// [`ConstantPool::Unknown`] where `options` gives the size of their
+    /// body. Any other unknown tag fails with
+    /// [`ParseError::UnknownConstantTag`] at the tag.
+    pub(crate) fn parse_skipping<'a>(
+        buf: &'a [u8],
+        constant_pool_count: usize,
+        options: ConstantOptions,
+    ) -> IResult<&'a [u8], Vec<ConstantPool>> {
+        // No constant takes less than three bytes. The count isn't checked
+        // against the input, so that parse_partial keeps what's there.
+        let mut constant_pool =
+            Vec::with_capacity(constant_pool_count.saturating_sub(1).min(buf.len() / 3));
+        let buf = Self::parse_partial(buf, constant_pool_count, options, &mut constant_pool)
+            .map_err(|(_, e)| e)?;
+        Ok((buf, constant_pool))
+    }
+
+    /// Parses the pool into `constant_pool` entry by entry, so that every
+    /// complete entry is kept when one fails. The error comes with the
+    /// input at the entry which failed.
+    pub(crate) fn parse_partial<'a>(
+        mut buf: &'a [u8],
+        constant_pool_count: usize,
+        options: ConstantOptions,
+        constant_pool: &mut Vec<ConstantPool>,
+    ) -> PartialParse<'a> {
         let mut i = 0;
-        while i < constant_pool_count - 1 {
-            let (temp_buf, constant) = Self::parse_constant(buf)?;
+        while i + 1 < constant_pool_count {
+            let (temp_buf, constant) = error::within(Self::parse_constant(buf, options), || {
+                format!("constant #{}", i + 1)
+            })
+            .map_err(|e| (buf, e))?;
             buf = temp_buf;
-            if let ConstantPool::Long(_) = constant {
-                i += 1;
-            } else if let ConstantPool::Float(_) = constant {
+            let wide = matches!(constant, ConstantPool::Long(_) | ConstantPool::Double(_));
+            constant_pool.push(constant);
+            i += 1;
+            if wide {
+                constant_pool.push(ConstantPool::Unusable);
                 i += 1;
             }
-            constant_pool.push(constant);
+        }
 
-            i += 1;
+        Ok(buf)
+    }
+
+    /// Writes the pool preceded by `constant_pool_count`, which counts the
+    /// [`Unusable`](ConstantPool::Unusable) slot after each Long and Double.
+    #[cfg(feature = "write")]
+    pub fn write_all(constant_pool: &[ConstantPool], out: &mut Vec<u8>) -> io::Result<()> {
+        out.put_count(constant_pool.len() + 1, "constant pool")?;
+        for constant in constant_pool {
+            constant.write(out)?;
         }
+        Ok(())
+    }
 
-        Ok((buf, constant_pool))
+    #[cfg(feature = "write")]
+    fn write(&self, out: &mut Vec<u8>) -> io::Result<()> {
+        if let ConstantPool::Unusable = self {
+            return Ok(());
+        }
+        out.put_u8(self.tag());
+        match self {
+            ConstantPool::Class(index)
+            | ConstantPool::String(index)
+            | ConstantPool::MethodType(index)
+            | ConstantPool::Module(index)
+            | ConstantPool::Package(index) => out.put_u16(*index),
+            ConstantPool::FieldRef(first, second)
+            | ConstantPool::MethodRef(first, second)
+            | ConstantPool::InterfaceMethodRef(first, second)
+            | ConstantPool::NameAndType(first, second)
+            | ConstantPool::Dynamic(first, second)
+            | ConstantPool::InvokeDynamic(first, second) => {
+                out.put_u16(*first);
+                out.put_u16(*second);
+            }
+            ConstantPool::Integer(value) => out.put_u32(*value as u32),
+            ConstantPool::Float(bits) => out.put_u32(bits.0),
+            ConstantPool::Long(value) => out.put_u64(*value as u64),
+            ConstantPool::Double(bits) => out.put_u64(bits.0),
+            ConstantPool::UTF8(value) => {
+                let bytes = mutf8::encode(value);
+                out.put_count(bytes.len(), "UTF8 constant")?;
+                out.extend_from_slice(&bytes);
+            }
+            ConstantPool::Utf16(units) => {
+                let bytes = mutf8::encode_utf16(units);
+                out.put_count(bytes.len(), "UTF8 constant")?;
+                out.extend_from_slice(&bytes);
+            }
+            ConstantPool::RawUtf8(bytes) => {
+                out.put_count(bytes.len(), "UTF8 constant")?;
+                out.extend_from_slice(bytes);
+            }
+            ConstantPool::MethodHandle(reference_kind, reference_index) => {
+                out.put_u8(*reference_kind);
+                out.put_u16(*reference_index);
+            }
+            ConstantPool::Unknown { body, .. } => out.extend_from_slice(body),
+            ConstantPool::Unusable => {}
+        }
+        Ok(())
     }
 
-    fn parse_constant(buf: &[u8]) -> IResult<&[u8], ConstantPool> {
-        let (buf, tag) = u8(buf)?;
+    pub(crate) fn parse_constant<'a>(
+        input: &'a [u8],
+        options: ConstantOptions,
+    ) -> IResult<&'a [u8], ConstantPool> {
+        let (buf, tag) = u8(input)?;
         match tag {
             CONSTANT_CLASS => {
                 let (buf, name_index) = be_u16(buf)?;
@@ -104,16 +937,16 @@ impl ConstantPool {
                 Ok((buf, ConstantPool::Integer(value)))
             }
             CONSTANT_FLOAT => {
-                let (buf, value) = be_f32(buf)?;
-                Ok((buf, ConstantPool::Float(value)))
+                let (buf, bits) = be_u32(buf)?;
+                Ok((buf, ConstantPool::Float(FloatBits(bits))))
             }
             CONSTANT_LONG => {
                 let (buf, value) = be_i64(buf)?;
                 Ok((buf, ConstantPool::Long(value)))
             }
             CONSTANT_DOUBLE => {
-                let (buf, value) = be_f64(buf)?;
-                Ok((buf, ConstantPool::Double(value)))
+                let (buf, bits) = be_u64(buf)?;
+                Ok((buf, ConstantPool::Double(DoubleBits(bits))))
             }
             CONSTANT_NAME_AND_TYPE => {
                 let (buf, name_index) = be_u16(buf)?;
@@ -121,14 +954,31 @@ impl ConstantPool {
                 Ok((buf, ConstantPool::NameAndType(name_index, descriptor_index)))
             }
             CONSTANT_UTF8 => {
-                let (buf, value) = length_data(be_u16)(buf)?;
-                Ok((
-                    buf,
-                    ConstantPool::UTF8(String::from_utf8(value.to_vec()).unwrap()),
-                ))
+                let (rest, length) = be_u16(buf)?;
+                let (rest, value) = take(length as usize)(rest)?;
+                if let Some(value) = mutf8::decode(value) {
+                    return Ok((rest, ConstantPool::UTF8(value)));
+                }
+                match mutf8::decode_utf16(value) {
+                    Some(units) => Ok((rest, ConstantPool::Utf16(units))),
+                    None if options.lenient_utf8 => {
+                        Ok((rest, ConstantPool::RawUtf8(value.to_vec())))
+                    }
+                    None => Err(ErrorAt::failure(buf, ParseError::InvalidUtf8)),
+                }
             }
             CONSTANT_METHOD_HANDLE => {
+                let kind = buf;
                 let (buf, reference_kind) = u8(buf)?;
+                if ReferenceKind::from_tag(reference_kind).is_none() {
+                    return Err(ErrorAt::failure(
+                        kind,
+                        ParseError::UnknownTag {
+                            structure: "method handle reference kind",
+                            tag: reference_kind,
+                        },
+                    ));
+                }
                 let (buf, reference_index) = be_u16(buf)?;
                 Ok((
                     buf,
@@ -163,7 +1013,19 @@ impl ConstantPool {
                 let (buf, name_index) = be_u16(buf)?;
                 Ok((buf, ConstantPool::Package(name_index)))
             }
-            _ => unimplemented!(),
+            _ => match options.unknown_sizes.and_then(|sizes| sizes.get(&tag)) {
+                Some(&size) => {
+                    let (buf, body) = take(size)(buf)?;
+                    Ok((
+                        buf,
+                        ConstantPool::Unknown {
+                            tag,
+                            body: body.to_vec(),
+                        },
+                    ))
+                }
+                None => Err(ErrorAt::failure(input, ParseError::UnknownConstantTag(tag))),
+            },
         }
     }
 }
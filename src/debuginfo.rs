@@ -0,0 +1,318 @@
+//! Cross-checks of a class's debug attributes against the code and
+//! descriptors they describe. Compilers get these right; a mismatch is
+//! the usual sign of a bytecode rewriter which moved or added code, locals
+//! or parameters without updating the tables.
+//!
+//! Checked are, for each method:
+//!
+//! - every `LineNumberTable` entry starts within the code
+//! - every `LocalVariableTable` entry covers only code, and its slot (two
+//!   for `long` and `double`) is below `max_locals`
+//! - every `LocalVariableTypeTable` entry has a `LocalVariableTable` entry
+//!   of the same slot, range and name
+//! - `MethodParameters` has an entry for every parameter of the descriptor
+//! - the `Signature` declares no more parameters than the descriptor, and
+//!   fewer only for the parameters the compiler adds: those `MethodParameters`
+//!   marks synthetic or mandated, or, without it, those of a constructor
+
+use std::fmt;
+
+use crate::{
+    accessflags::{ACC_MANDATED, ACC_SYNTHETIC},
+    attribute::Attribute,
+    classfile::ClassFile,
+    constantpool::ConstantPool,
+    descriptor::MethodDescriptor,
+    methodinfo::MethodInfo,
+    signature::MethodSignature,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DebugInfoMismatch {
+    pub method_name: String,
+    pub method_descriptor: String,
+    pub kind: MismatchKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MismatchKind {
+    /// A `LineNumberTable` entry starting at or past the end of the code.
+    LineOutsideCode {
+        start_pc: u16,
+        line_number: u16,
+        code_length: u32,
+    },
+    /// A `LocalVariableTable` entry whose range isn't within the code.
+    LocalOutsideCode {
+        name: String,
+        start_pc: u16,
+        length: u16,
+        code_length: u32,
+    },
+    /// A `LocalVariableTable` entry whose slot, or second slot for a
+    /// `long` or `double`, isn't below `max_locals`.
+    LocalSlotOutOfRange {
+        name: String,
+        slot: u16,
+        max_locals: u16,
+    },
+    /// A `LocalVariableTypeTable` entry no `LocalVariableTable` entry
+    /// matches.
+    UnmatchedLocalType {
+        name: String,
+        slot: u16,
+        start_pc: u16,
+        length: u16,
+    },
+    /// `MethodParameters` and the descriptor disagree on the number of
+    /// parameters.
+    MethodParametersCount { entries: usize, descriptor: usize },
+    /// The `Signature` attribute's parameters can't be reconciled with the
+    /// descriptor's. `implicit` is how many parameters the compiler is known
+    /// to have added, `None` when that's unknown.
+    SignatureParametersCount {
+        signature: usize,
+        descriptor: usize,
+        implicit: Option<usize>,
+    },
+}
+
+impl fmt::Display for MismatchKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MismatchKind::LineOutsideCode {
+                start_pc,
+                line_number,
+                code_length,
+            } => write!(
+                f,
+                "line {} starts at pc {}, past the end of the code ({} bytes)",
+                line_number, start_pc, code_length
+            ),
+            MismatchKind::LocalOutsideCode {
+                name,
+                start_pc,
+                length,
+                code_length,
+            } => write!(
+                f,
+                "local {} covers pc {} to {}, past the end of the code ({} bytes)",
+                name,
+                start_pc,
+                u32::from(*start_pc) + u32::from(*length),
+                code_length
+            ),
+            MismatchKind::LocalSlotOutOfRange {
+                name,
+                slot,
+                max_locals,
+            } => write!(
+                f,
+                "local {} is in slot {}, beyond max_locals {}",
+                name, slot, max_locals
+            ),
+            MismatchKind::UnmatchedLocalType {
+                name,
+                slot,
+                start_pc,
+                length,
+            } => write!(
+                f,
+                "generic local {} (slot {}, pc {} to {}) has no LocalVariableTable entry",
+                name,
+                slot,
+                start_pc,
+                u32::from(*start_pc) + u32::from(*length)
+            ),
+            MismatchKind::MethodParametersCount {
+                entries,
+                descriptor,
+            } => write!(
+                f,
+                "MethodParameters has {} entries for {} parameters",
+                entries, descriptor
+            ),
+            MismatchKind::SignatureParametersCount {
+                signature,
+                descriptor,
+                implicit: Some(implicit),
+            } => write!(
+                f,
+                "Signature declares {} parameters for {} in the descriptor, {} of them implicit",
+                signature, descriptor, implicit
+            ),
+            MismatchKind::SignatureParametersCount {
+                signature,
+                descriptor,
+                implicit: None,
+            } => write!(
+                f,
+                "Signature declares {} parameters for {} in the descriptor",
+                signature, descriptor
+            ),
+        }
+    }
+}
+
+/// Checks the debug attributes of every method of the class, in declaration
+/// order. Attributes which can't be resolved or parsed, such as a
+/// descriptor which isn't one, are left to the verifier.
+pub fn check_debug_info(class_file: &ClassFile) -> Vec<DebugInfoMismatch> {
+    let constant_pool = &class_file.constant_pool;
+    let mut mismatches = Vec::new();
+    for method in &class_file.methods {
+        let method_name = ConstantPool::utf8(constant_pool, method.name_index()).unwrap_or("?");
+        let method_descriptor =
+            ConstantPool::utf8(constant_pool, method.descriptor_index()).unwrap_or("?");
+        let kinds = check_code(class_file, method)
+            .into_iter()
+            .chain(check_parameters(
+                class_file,
+                method,
+                method_name,
+                method_descriptor,
+            ));
+        mismatches.extend(kinds.map(|kind| DebugInfoMismatch {
+            method_name: method_name.to_string(),
+            method_descriptor: method_descriptor.to_string(),
+            kind,
+        }));
+    }
+    mismatches
+}
+
+fn check_code(class_file: &ClassFile, method: &MethodInfo) -> Vec<MismatchKind> {
+    let constant_pool = &class_file.constant_pool;
+    let utf8 = |index| {
+        ConstantPool::utf8(constant_pool, index)
+            .unwrap_or("?")
+            .to_string()
+    };
+    let code = match method.code() {
+        Some(code) => code,
+        None => return Vec::new(),
+    };
+    let code_length = code.code.len() as u32;
+    let within = |start_pc: u16, length: u16| {
+        u32::from(start_pc) < code_length && u32::from(start_pc) + u32::from(length) <= code_length
+    };
+
+    let mut mismatches = Vec::new();
+    for line in code.line_number_table().unwrap_or(&[]) {
+        if u32::from(line.start_pc) >= code_length {
+            mismatches.push(MismatchKind::LineOutsideCode {
+                start_pc: line.start_pc,
+                line_number: line.line_number,
+                code_length,
+            });
+        }
+    }
+    let locals = code.local_variable_table().unwrap_or(&[]);
+    for local in locals {
+        if !within(local.start_pc, local.length) {
+            mismatches.push(MismatchKind::LocalOutsideCode {
+                name: utf8(local.name_index),
+                start_pc: local.start_pc,
+                length: local.length,
+                code_length,
+            });
+        }
+        let width = match ConstantPool::utf8(constant_pool, local.descriptor_index) {
+            Some("J" | "D") => 2,
+            _ => 1,
+        };
+        if u32::from(local.index) + width > u32::from(code.max_locals) {
+            mismatches.push(MismatchKind::LocalSlotOutOfRange {
+                name: utf8(local.name_index),
+                slot: local.index,
+                max_locals: code.max_locals,
+            });
+        }
+    }
+    for local_type in code.local_variable_type_table().unwrap_or(&[]) {
+        let name = ConstantPool::utf8(constant_pool, local_type.name_index);
+        let matched = locals.iter().any(|local| {
+            local.index == local_type.index
+                && local.start_pc == local_type.start_pc
+                && local.length == local_type.length
+                && ConstantPool::utf8(constant_pool, local.name_index) == name
+        });
+        if !matched {
+            mismatches.push(MismatchKind::UnmatchedLocalType {
+                name: utf8(local_type.name_index),
+                slot: local_type.index,
+                start_pc: local_type.start_pc,
+                length: local_type.length,
+            });
+        }
+    }
+    mismatches
+}
+
+fn check_parameters(
+    class_file: &ClassFile,
+    method: &MethodInfo,
+    method_name: &str,
+    method_descriptor: &str,
+) -> Vec<MismatchKind> {
+    let constant_pool = &class_file.constant_pool;
+    let descriptor = match MethodDescriptor::parse(method_descriptor) {
+        Ok(descriptor) => descriptor.parameters.len(),
+        Err(_) => return Vec::new(),
+    };
+    let mut mismatches = Vec::new();
+    let parameters = method
+        .attributes()
+        .iter()
+        .find_map(|attribute| match attribute {
+            Attribute::MethodParameters(parameters) => Some(parameters),
+            _ => None,
+        });
+    if let Some(parameters) = parameters {
+        if parameters.len() != descriptor {
+            mismatches.push(MismatchKind::MethodParametersCount {
+                entries: parameters.len(),
+                descriptor,
+            });
+        }
+    }
+
+    let signature = method
+        .attributes()
+        .iter()
+        .find_map(|attribute| match attribute {
+            Attribute::Signature(signature) => {
+                ConstantPool::utf8(constant_pool, signature.signature_index)
+            }
+            _ => None,
+        })
+        .and_then(|signature| MethodSignature::parse(signature).ok());
+    if let Some(signature) = signature {
+        let declared = signature.parameters.len();
+        let implicit = parameters
+            .filter(|parameters| parameters.len() == descriptor)
+            .map(|parameters| {
+                parameters
+                    .iter()
+                    .filter(|parameter| {
+                        parameter
+                            .access_flags
+                            .intersects(ACC_SYNTHETIC | ACC_MANDATED)
+                    })
+                    .count()
+            });
+        let reconciled = match implicit {
+            Some(implicit) => declared + implicit == descriptor,
+            // only constructors get parameters the source doesn't declare
+            None => declared == descriptor || (declared < descriptor && method_name == "<init>"),
+        };
+        if !reconciled {
+            mismatches.push(MismatchKind::SignatureParametersCount {
+                signature: declared,
+                descriptor,
+                implicit,
+            });
+        }
+    }
+    mismatches
+}
@@ -0,0 +1,257 @@
+//! Class level dependencies, counted the way `jdeps` counts them: every
+//! class in the constant pool, in a descriptor or generic signature, or
+//! named as the type of an annotation.
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs,
+    path::Path,
+};
+
+use crate::{
+    attribute::Attribute,
+    classfile::ClassFile,
+    classindex::ClassIndex,
+    constantpool::ConstantPool,
+    descriptor,
+    jar::{JarError, JarFile},
+};
+
+/// What `jdeps` prints for a class it can't find.
+pub const NOT_FOUND: &str = "not found";
+
+/// The classes `class_file` depends on by internal name, not counting
+/// itself. Arrays count as their element class and primitives not at all.
+pub fn class_dependencies(class_file: &ClassFile) -> BTreeSet<String> {
+    let constant_pool = &class_file.constant_pool;
+    let mut dependencies = class_file.referenced_classes();
+    let descriptor = |index: u16, dependencies: &mut BTreeSet<String>| {
+        if let Some(descriptor) = ConstantPool::utf8(constant_pool, index) {
+            dependencies.extend(descriptor::referenced_classes(descriptor).map(String::from));
+        }
+    };
+
+    let attributes = |attributes: &[Attribute], dependencies: &mut BTreeSet<String>| {
+        for attribute in attributes {
+            let annotations = match attribute {
+                Attribute::Signature(signature) => {
+                    if let Some(signature) =
+                        ConstantPool::utf8(constant_pool, signature.signature_index)
+                    {
+                        signature_classes(signature, dependencies);
+                    }
+                    continue;
+                }
+                Attribute::RuntimeVisibleAnnotations(annotations)
+                | Attribute::RuntimeInvisibleAnnotations(annotations) => {
+                    annotations.iter().collect()
+                }
+                Attribute::RuntimeVisibleParameterAnnotations(parameters)
+                | Attribute::RuntimeInvisibleParameterAnnotations(parameters) => parameters
+                    .iter()
+                    .flat_map(|parameter| &parameter.annotations)
+                    .collect(),
+                _ => Vec::new(),
+            };
+            for annotation in annotations {
+                descriptor(annotation.type_index, dependencies);
+            }
+        }
+    };
+    attributes(&class_file.attributes, &mut dependencies);
+    for field in &class_file.fields {
+        attributes(field.attributes(), &mut dependencies);
+    }
+    for method in &class_file.methods {
+        attributes(method.attributes(), &mut dependencies);
+    }
+
+    if let Some(this_class) = ConstantPool::class_name(constant_pool, class_file.this_class) {
+        dependencies.remove(this_class);
+    }
+    dependencies
+}
+
+/// The internal name of the class and the classes it depends on outside its
+/// own package, which are the ones `jdeps` reports by default. `None` for
+/// module descriptors, which `jdeps` leaves out.
+pub fn package_dependencies(class_file: &ClassFile) -> Option<(&str, BTreeSet<String>)> {
+    let origin = ConstantPool::class_name(&class_file.constant_pool, class_file.this_class)
+        .unwrap_or_default();
+    if origin == "module-info" {
+        return None;
+    }
+    let mut dependencies = class_dependencies(class_file);
+    dependencies.retain(|target| package(target) != package(origin));
+    Some((origin, dependencies))
+}
+
+/// Where `class_name` is found, named as `jdeps` names it: the platform
+/// module holding it, else the archive of the first index holding it, else
+/// [`NOT_FOUND`].
+pub fn locate(class_name: &str, system: Option<&SystemModules>, indexes: &[&ClassIndex]) -> String {
+    if let Some(module) = system.and_then(|system| system.module_of(class_name)) {
+        return module.to_string();
+    }
+    indexes
+        .iter()
+        .find_map(|index| index.get(class_name).ok())
+        .map_or(NOT_FOUND.to_string(), |class| archive_name(class.source()))
+}
+
+/// The file name of a jar, directory or class file, as `jdeps` names the
+/// archive it reads.
+pub fn archive_name(path: &str) -> String {
+    Path::new(path)
+        .file_name()
+        .map_or(path.to_string(), |name| name.to_string_lossy().into_owned())
+}
+
+fn package(class_name: &str) -> &str {
+    class_name
+        .rsplit_once('/')
+        .map_or("", |(package, _)| package)
+}
+
+/// Adds the classes named in a class, field or method signature, inner
+/// classes of generic classes (`Lp/Outer<TT;>.Inner;`) included.
+fn signature_classes(signature: &str, classes: &mut BTreeSet<String>) {
+    let bytes = signature.as_bytes();
+    let mut i = 0;
+    if bytes.first() == Some(&b'<') {
+        i = match type_parameters(bytes, 1, signature, classes) {
+            Some(i) => i,
+            None => return,
+        };
+    }
+    while i < bytes.len() {
+        i = match bytes[i] {
+            b'(' | b')' | b'^' | b'V' => i + 1,
+            _ => match reference_type(bytes, i, signature, classes) {
+                Some(i) => i,
+                None => return,
+            },
+        };
+    }
+}
+
+/// Skips the formal type parameters starting at `i`, just past the `<`,
+/// adding the classes in their bounds. Returns the index past the `>`.
+fn type_parameters(
+    bytes: &[u8],
+    mut i: usize,
+    signature: &str,
+    classes: &mut BTreeSet<String>,
+) -> Option<usize> {
+    while *bytes.get(i)? != b'>' {
+        // the parameter's name
+        i += bytes[i..].iter().position(|&b| b == b':')?;
+        while bytes.get(i) == Some(&b':') {
+            i += 1;
+            if !matches!(bytes.get(i), Some(b':' | b'>')) {
+                i = reference_type(bytes, i, signature, classes)?;
+            }
+        }
+    }
+    Some(i + 1)
+}
+
+/// Reads the type at `i`, adding the classes in it. Returns the index past
+/// it.
+fn reference_type(
+    bytes: &[u8],
+    i: usize,
+    signature: &str,
+    classes: &mut BTreeSet<String>,
+) -> Option<usize> {
+    match bytes.get(i)? {
+        b'[' => reference_type(bytes, i + 1, signature, classes),
+        b'T' => Some(i + bytes[i..].iter().position(|&b| b == b';')? + 1),
+        b'L' => {
+            let mut i = i + 1;
+            let mut name = String::new();
+            loop {
+                let end = i + bytes[i..]
+                    .iter()
+                    .position(|&b| matches!(b, b';' | b'<' | b'.'))?;
+                name.push_str(&signature[i..end]);
+                i = end;
+                if bytes[i] == b'<' {
+                    i += 1;
+                    while *bytes.get(i)? != b'>' {
+                        i = match bytes[i] {
+                            b'*' => i + 1,
+                            b'+' | b'-' => reference_type(bytes, i + 1, signature, classes)?,
+                            _ => reference_type(bytes, i, signature, classes)?,
+                        };
+                    }
+                    i += 1;
+                }
+                match bytes.get(i)? {
+                    b'.' => {
+                        name.push('$');
+                        i += 1;
+                    }
+                    b';' => break,
+                    _ => return None,
+                }
+            }
+            classes.insert(name);
+            Some(i + 1)
+        }
+        _ => Some(i + 1),
+    }
+}
+
+/// The packages of the platform, by the module which holds them, read from
+/// the `jmods` directory of a JDK.
+#[derive(Debug, Default)]
+pub struct SystemModules {
+    /// package -> module
+    packages: HashMap<String, String>,
+}
+
+impl SystemModules {
+    /// Reads the modules of the JDK installed at `java_home`. Only the names
+    /// of the entries are read, not the classes themselves.
+    pub fn open(java_home: &Path) -> Result<Self, JarError> {
+        let mut packages = HashMap::new();
+        let mut jmods = fs::read_dir(java_home.join("jmods"))?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<Vec<_>, _>>()?;
+        jmods.sort();
+        for path in jmods {
+            let module = match path.file_name().and_then(|name| name.to_str()) {
+                Some(name) => match name.strip_suffix(".jmod") {
+                    Some(module) => module.to_string(),
+                    None => continue,
+                },
+                None => continue,
+            };
+            let jmod = JarFile::open_jmod(&path)?;
+            for entry in jmod.entries() {
+                let class_name = match entry
+                    .name
+                    .strip_prefix("classes/")
+                    .and_then(|path| path.strip_suffix(".class"))
+                {
+                    Some(class_name) => class_name,
+                    None => continue,
+                };
+                if let Some((package, _)) = class_name.rsplit_once('/') {
+                    packages
+                        .entry(package.to_string())
+                        .or_insert_with(|| module.clone());
+                }
+            }
+        }
+        Ok(SystemModules { packages })
+    }
+
+    /// The module holding the class with internal name `class_name`, if
+    /// it's part of the platform.
+    pub fn module_of(&self, class_name: &str) -> Option<&str> {
+        let (package, _) = class_name.rsplit_once('/')?;
+        self.packages.get(package).map(String::as_str)
+    }
+}
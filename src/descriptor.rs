@@ -0,0 +1,259 @@
+//! Field and method descriptors, e.g. `[[D` and `(Ljava/lang/String;[I)V`,
+//! parsed into types.
+
+use crate::prelude::*;
+use core::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BaseType {
+    Byte,
+    Char,
+    Double,
+    Float,
+    Int,
+    Long,
+    Short,
+    Boolean,
+}
+
+impl BaseType {
+    pub fn java_name(self) -> &'static str {
+        match self {
+            BaseType::Byte => "byte",
+            BaseType::Char => "char",
+            BaseType::Double => "double",
+            BaseType::Float => "float",
+            BaseType::Int => "int",
+            BaseType::Long => "long",
+            BaseType::Short => "short",
+            BaseType::Boolean => "boolean",
+        }
+    }
+
+    pub(crate) fn from_char(c: char) -> Option<Self> {
+        match c {
+            'B' => Some(BaseType::Byte),
+            'C' => Some(BaseType::Char),
+            'D' => Some(BaseType::Double),
+            'F' => Some(BaseType::Float),
+            'I' => Some(BaseType::Int),
+            'J' => Some(BaseType::Long),
+            'S' => Some(BaseType::Short),
+            'Z' => Some(BaseType::Boolean),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum FieldType {
+    Base(BaseType),
+    /// A class or interface type, holding its internal (slash separated) name.
+    Object(String),
+    /// An array type; `element_type` is never itself an array.
+    Array {
+        dimensions: u8,
+        element_type: Box<FieldType>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DescriptorError {
+    pub descriptor: String,
+    /// Byte offset into the descriptor where parsing failed.
+    pub offset: usize,
+}
+
+impl fmt::Display for DescriptorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid descriptor {:?} at offset {}",
+            self.descriptor, self.offset
+        )
+    }
+}
+
+impl core::error::Error for DescriptorError {}
+
+impl FieldType {
+    pub fn parse(descriptor: &str) -> Result<Self, DescriptorError> {
+        match Self::parse_prefix(descriptor, 0)? {
+            (field_type, end) if end == descriptor.len() => Ok(field_type),
+            (_, end) => Err(DescriptorError {
+                descriptor: descriptor.to_string(),
+                offset: end,
+            }),
+        }
+    }
+
+    /// Parses a single field type starting at `start`, returning it together
+    /// with the offset just past it.
+    fn parse_prefix(descriptor: &str, start: usize) -> Result<(Self, usize), DescriptorError> {
+        let error = |offset| DescriptorError {
+            descriptor: descriptor.to_string(),
+            offset,
+        };
+        let rest = &descriptor[start..];
+        let dimensions = rest.bytes().take_while(|&b| b == b'[').count();
+        if dimensions > 255 {
+            return Err(error(start + 255));
+        }
+
+        let offset = start + dimensions;
+        let (element_type, end) = match descriptor[offset..].chars().next() {
+            Some('L') => match descriptor[offset..].find(';') {
+                Some(len) if len > 1 => (
+                    FieldType::Object(descriptor[offset + 1..offset + len].to_string()),
+                    offset + len + 1,
+                ),
+                _ => return Err(error(offset)),
+            },
+            Some(c) => match BaseType::from_char(c) {
+                Some(base_type) => (FieldType::Base(base_type), offset + 1),
+                None => return Err(error(offset)),
+            },
+            None => return Err(error(offset)),
+        };
+
+        if dimensions == 0 {
+            Ok((element_type, end))
+        } else {
+            Ok((
+                FieldType::Array {
+                    dimensions: dimensions as u8,
+                    element_type: Box::new(element_type),
+                },
+                end,
+            ))
+        }
+    }
+
+    /// The class this type ultimately names, looking through array types.
+    /// `None` for primitives and arrays of primitives.
+    pub fn class_name(&self) -> Option<&str> {
+        match self {
+            FieldType::Base(_) => None,
+            FieldType::Object(name) => Some(name),
+            FieldType::Array { element_type, .. } => element_type.class_name(),
+        }
+    }
+}
+
+/// The classes named in a field or method descriptor, in order, looking
+/// through array types, e.g. `java/lang/String` and `p/Foo` in
+/// `(Ljava/lang/String;[[Lp/Foo;I)V`. Stops at a class left unterminated.
+pub fn referenced_classes(descriptor: &str) -> impl Iterator<Item = &str> {
+    let mut rest = descriptor;
+    core::iter::from_fn(move || {
+        let start = rest.find('L')?;
+        let end = start + rest[start..].find(';')?;
+        let class_name = &rest[start + 1..end];
+        rest = &rest[end + 1..];
+        Some(class_name)
+    })
+}
+
+/// Renders the type the way it's written in Java source, e.g. `int[][]` or
+/// `java.lang.String`.
+impl fmt::Display for FieldType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldType::Base(base_type) => f.write_str(base_type.java_name()),
+            FieldType::Object(name) => f.write_str(&name.replace('/', ".")),
+            FieldType::Array {
+                dimensions,
+                element_type,
+            } => {
+                write!(f, "{}", element_type)?;
+                for _ in 0..*dimensions {
+                    f.write_str("[]")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MethodDescriptor {
+    pub parameters: Vec<FieldType>,
+    /// `None` for `void` methods.
+    pub return_type: Option<FieldType>,
+}
+
+impl MethodDescriptor {
+    pub fn parse(descriptor: &str) -> Result<Self, DescriptorError> {
+        let error = |offset| DescriptorError {
+            descriptor: descriptor.to_string(),
+            offset,
+        };
+        if !descriptor.starts_with('(') {
+            return Err(error(0));
+        }
+
+        let mut parameters = Vec::new();
+        let mut offset = 1;
+        loop {
+            match descriptor[offset..].chars().next() {
+                Some(')') => break,
+                Some(_) => {
+                    let (parameter, end) = FieldType::parse_prefix(descriptor, offset)?;
+                    parameters.push(parameter);
+                    offset = end;
+                }
+                None => return Err(error(offset)),
+            }
+        }
+
+        let return_type = match &descriptor[offset + 1..] {
+            "V" => None,
+            _ => match FieldType::parse_prefix(descriptor, offset + 1)? {
+                (return_type, end) if end == descriptor.len() => Some(return_type),
+                (_, end) => return Err(error(end)),
+            },
+        };
+
+        Ok(MethodDescriptor {
+            parameters,
+            return_type,
+        })
+    }
+
+    /// The parameter list as written in Java source, e.g. `(int, java.lang.String)`.
+    pub fn java_parameters(&self) -> String {
+        let parameters = self
+            .parameters
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        format!("({})", parameters.join(", "))
+    }
+}
+
+/// What a `CONSTANT_Class` entry names: either a class or interface, or an
+/// array type given by its descriptor (`[I`, `[[Ljava/lang/String;`).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ClassRef {
+    Object(String),
+    Array(FieldType),
+}
+
+impl ClassRef {
+    pub fn from_name(name: &str) -> Result<Self, DescriptorError> {
+        if name.starts_with('[') {
+            FieldType::parse(name).map(ClassRef::Array)
+        } else {
+            Ok(ClassRef::Object(name.to_string()))
+        }
+    }
+
+    /// The class this reference ultimately names, looking through array
+    /// types. `None` for arrays of primitives.
+    pub fn class_name(&self) -> Option<&str> {
+        match self {
+            ClassRef::Object(name) => Some(name),
+            ClassRef::Array(field_type) => field_type.class_name(),
+        }
+    }
+}
@@ -0,0 +1,875 @@
+//! Comparing two builds of a class by what they hold rather than by their
+//! bytes, for finding out what a nondeterministic build or a rewriting
+//! tool changed.
+//!
+//! Constants are compared by value wherever they're referred to, so the
+//! layout of the two constant pools never makes a difference by itself:
+//! two classes with the same content in reordered pools have no
+//! differences. Fields and methods are matched by name and descriptor,
+//! and attributes by name, with their order left aside; so are bootstrap
+//! methods, by what they call with which arguments. Only the layout of a
+//! `Module` is compared as it is.
+//!
+//! A [`fingerprint`] hashes a class the same way, so that two builds of it
+//! between which `diff` finds no differences have the same fingerprint.
+
+use std::{collections::BTreeMap, fmt, fmt::Write as _};
+
+use crate::{
+    accessflags::{ClassAccessFlags, FieldAccessFlags, MethodAccessFlags},
+    attribute::{
+        Annotation, Attribute, BootstrapMethod, Code, ElementValue, StackMapFrame,
+        VerificationTypeInfo, DEBUG_ATTRIBUTES,
+    },
+    classfile::ClassFile,
+    clones,
+    constantpool::ConstantPool,
+    instruction::{self, LOOKUPSWITCH, TABLESWITCH},
+    sha256::Sha256,
+};
+
+/// Where in the class a difference is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Location {
+    Class,
+    Field { name: String, descriptor: String },
+    Method { name: String, descriptor: String },
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Location::Class => f.write_str("class"),
+            Location::Field { name, descriptor } => write!(f, "field {}:{}", name, descriptor),
+            Location::Method { name, descriptor } => write!(f, "method {}{}", name, descriptor),
+        }
+    }
+}
+
+/// A difference between the old and the new class, found by [`diff`].
+/// Names are internal, e.g. `com/example/Foo`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiffEntry {
+    /// The major and minor versions of the class file.
+    Version {
+        old: (u16, u16),
+        new: (u16, u16),
+    },
+    ThisClass {
+        old: String,
+        new: String,
+    },
+    SuperClass {
+        old: Option<String>,
+        new: Option<String>,
+    },
+    /// The direct superinterfaces, in the order declared.
+    Interfaces {
+        old: Vec<String>,
+        new: Vec<String>,
+    },
+    /// A field or method only the new class has.
+    Added(Location),
+    /// A field or method only the old class has.
+    Removed(Location),
+    AccessFlags {
+        location: Location,
+        old: u16,
+        new: u16,
+    },
+    /// The `ConstantValue` of a field, each as [`constant_text`] writes it.
+    ConstantValue {
+        location: Location,
+        old: String,
+        new: String,
+    },
+    /// The bytecode of a method, with its lengths. Instructions are
+    /// compared with the constants they refer to, not their indices.
+    Code {
+        location: Location,
+        old_len: usize,
+        new_len: usize,
+    },
+    AttributeAdded {
+        location: Location,
+        name: String,
+    },
+    AttributeRemoved {
+        location: Location,
+        name: String,
+    },
+    /// An attribute both classes have, with other contents. For `Code`,
+    /// that's the stack and local sizes or the exception table; the
+    /// bytecode is a [`DiffEntry::Code`] and the attributes nested in it
+    /// are compared as those of the method.
+    AttributeChanged {
+        location: Location,
+        name: String,
+    },
+}
+
+impl fmt::Display for DiffEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DiffEntry::Version { old, new } => {
+                write!(f, "version {}.{} -> {}.{}", old.0, old.1, new.0, new.1)
+            }
+            DiffEntry::ThisClass { old, new } => write!(f, "class name {} -> {}", old, new),
+            DiffEntry::SuperClass { old, new } => write!(
+                f,
+                "superclass {} -> {}",
+                old.as_deref().unwrap_or("(none)"),
+                new.as_deref().unwrap_or("(none)")
+            ),
+            DiffEntry::Interfaces { old, new } => {
+                write!(f, "interfaces [{}] -> [{}]", old.join(", "), new.join(", "))
+            }
+            DiffEntry::Added(location) => write!(f, "{} added", location),
+            DiffEntry::Removed(location) => write!(f, "{} removed", location),
+            DiffEntry::AccessFlags { location, old, new } => {
+                let text = |bits: u16| match location {
+                    Location::Class => ClassAccessFlags::new(bits).to_string(),
+                    Location::Field { .. } => FieldAccessFlags::new(bits).to_string(),
+                    Location::Method { .. } => MethodAccessFlags::new(bits).to_string(),
+                };
+                write!(
+                    f,
+                    "{}: flags {:#06x} ({}) -> {:#06x} ({})",
+                    location,
+                    old,
+                    text(*old),
+                    new,
+                    text(*new)
+                )
+            }
+            DiffEntry::ConstantValue { location, old, new } => {
+                write!(f, "{}: constant value {} -> {}", location, old, new)
+            }
+            DiffEntry::Code {
+                location,
+                old_len,
+                new_len,
+            } => write!(
+                f,
+                "{}: code changed, {} -> {} bytes",
+                location, old_len, new_len
+            ),
+            DiffEntry::AttributeAdded { location, name } => {
+                write!(f, "{}: {} added", location, name)
+            }
+            DiffEntry::AttributeRemoved { location, name } => {
+                write!(f, "{}: {} removed", location, name)
+            }
+            DiffEntry::AttributeChanged { location, name } => {
+                write!(f, "{}: {} changed", location, name)
+            }
+        }
+    }
+}
+
+/// The differences between `old` and `new`: those of the class itself
+/// first, then of its fields and methods in the order of `old`, with those
+/// only `new` has last. Empty when they differ in layout alone.
+pub fn diff<'a>(old: &'a ClassFile, new: &'a ClassFile) -> Vec<DiffEntry> {
+    let mut entries = Vec::new();
+    let (old_pool, new_pool) = (Pool::of(old), Pool::of(new));
+
+    let old_version = (old.major_version, old.minor_version);
+    let new_version = (new.major_version, new.minor_version);
+    if old_version != new_version {
+        entries.push(DiffEntry::Version {
+            old: old_version,
+            new: new_version,
+        });
+    }
+    let old_name = old.class_name().unwrap_or_default();
+    let new_name = new.class_name().unwrap_or_default();
+    if old_name != new_name {
+        entries.push(DiffEntry::ThisClass {
+            old: old_name.to_string(),
+            new: new_name.to_string(),
+        });
+    }
+    let old_super = old.super_class_name().ok().flatten();
+    let new_super = new.super_class_name().ok().flatten();
+    if old_super != new_super {
+        entries.push(DiffEntry::SuperClass {
+            old: old_super.map(str::to_string),
+            new: new_super.map(str::to_string),
+        });
+    }
+    let old_interfaces = old.interface_names().unwrap_or_default();
+    let new_interfaces = new.interface_names().unwrap_or_default();
+    if old_interfaces != new_interfaces {
+        entries.push(DiffEntry::Interfaces {
+            old: old_interfaces.iter().map(|name| name.to_string()).collect(),
+            new: new_interfaces.iter().map(|name| name.to_string()).collect(),
+        });
+    }
+    flags(
+        &mut entries,
+        Location::Class,
+        old.access_flags.bits(),
+        new.access_flags.bits(),
+    );
+    attributes(
+        &mut entries,
+        &Location::Class,
+        (&old.attributes, old_pool),
+        (&new.attributes, new_pool),
+    );
+
+    let fields = |class_file: &'a ClassFile| {
+        let constant_pool = &class_file.constant_pool;
+        class_file
+            .fields
+            .iter()
+            .map(|field| {
+                let name = field.name(constant_pool).unwrap_or_default();
+                let descriptor = field.descriptor(constant_pool).unwrap_or_default();
+                (
+                    name,
+                    descriptor,
+                    field.access_flags().bits(),
+                    field.attributes(),
+                )
+            })
+            .collect()
+    };
+    members(
+        &mut entries,
+        (fields(old), old_pool),
+        (fields(new), new_pool),
+        |name, descriptor| Location::Field { name, descriptor },
+    );
+    let methods = |class_file: &'a ClassFile| {
+        let constant_pool = &class_file.constant_pool;
+        class_file
+            .methods
+            .iter()
+            .map(|method| {
+                let name = method.name(constant_pool).unwrap_or_default();
+                let descriptor = method.descriptor(constant_pool).unwrap_or_default();
+                (
+                    name,
+                    descriptor,
+                    method.access_flags().bits(),
+                    method.attributes(),
+                )
+            })
+            .collect()
+    };
+    members(
+        &mut entries,
+        (methods(old), old_pool),
+        (methods(new), new_pool),
+        |name, descriptor| Location::Method { name, descriptor },
+    );
+    entries
+}
+
+/// What a [`fingerprint`] leaves out of the class.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FingerprintOptions {
+    /// Leave out the attributes of [`DEBUG_ATTRIBUTES`], those `javac -g`
+    /// decides on, wherever they are.
+    pub exclude_debug_info: bool,
+    /// Leave out the fields and methods the compiler generated.
+    pub exclude_synthetic: bool,
+}
+
+/// A SHA-256 hash of what the class holds, as [`diff`] compares it:
+/// constants by value rather than by index, fields and methods by name and
+/// descriptor whatever their order, and attributes by name. Two builds of a
+/// class differing only in the layout of their constant pools and the order
+/// of their members have the same fingerprint, and with
+/// [`exclude_debug_info`](FingerprintOptions::exclude_debug_info) so do two
+/// differing only in debug info; a change to a method's code or to a
+/// constant changes it.
+///
+/// ```
+/// use jvmb::{
+///     attribute::{Attribute, Code, LineNumber},
+///     builder::ClassFileBuilder,
+///     diff::{fingerprint, FingerprintOptions},
+/// };
+///
+/// // a class whose method `f()I` returns `value`, with a line number when
+/// // `debug`, and some other constant ahead of it in the pool when
+/// // `shuffled`
+/// let class = |value: i32, debug: bool, shuffled: bool| {
+///     let mut builder = ClassFileBuilder::new("Foo");
+///     if shuffled {
+///         builder.constant_pool().utf8("padding");
+///     }
+///     let [high, low] = builder.constant_pool().integer(value).to_be_bytes();
+///     let mut code = Code::new(1, 0, vec![0x13, high, low, 0xac]); // ldc_w, ireturn
+///     if debug {
+///         let line = LineNumber { start_pc: 0, line_number: 3 };
+///         code.attributes.push(Attribute::LineNumberTable(vec![line]));
+///     }
+///     builder.add_method(0x0009, "f", "()I", Some(code));
+///     builder.build().unwrap()
+/// };
+/// let all = FingerprintOptions::default();
+/// let no_debug = FingerprintOptions { exclude_debug_info: true, ..all };
+///
+/// let base = class(100_000, false, false);
+/// assert_eq!(fingerprint(&base, all), fingerprint(&class(100_000, false, true), all));
+/// assert_ne!(fingerprint(&base, all), fingerprint(&class(100_001, false, false), all));
+/// let debug = class(100_000, true, true);
+/// assert_ne!(fingerprint(&base, all), fingerprint(&debug, all));
+/// assert_eq!(fingerprint(&base, no_debug), fingerprint(&debug, no_debug));
+/// ```
+pub fn fingerprint(class_file: &ClassFile, options: FingerprintOptions) -> [u8; 32] {
+    let pool = Pool::of(class_file);
+    let constant_pool = &class_file.constant_pool;
+    let mut hasher = Sha256::new();
+    // each part with its length ahead of it, so that no two sequences of
+    // parts hash the same bytes
+    let mut part = |bytes: &[u8]| {
+        hasher.update(&(bytes.len() as u64).to_be_bytes());
+        hasher.update(bytes);
+    };
+    let attributes = |part: &mut dyn FnMut(&[u8]), attributes: &[Attribute]| {
+        let mut keys = attribute_keys(attributes, pool);
+        if options.exclude_debug_info {
+            keys.retain(|name, _| !DEBUG_ATTRIBUTES.contains(name));
+        }
+        part(&(keys.len() as u64).to_be_bytes());
+        for (name, values) in keys {
+            part(name.as_bytes());
+            part(&(values.len() as u64).to_be_bytes());
+            for value in values {
+                part(value.as_bytes());
+            }
+        }
+    };
+
+    part(&class_file.major_version.to_be_bytes());
+    part(&class_file.minor_version.to_be_bytes());
+    part(&class_file.access_flags.bits().to_be_bytes());
+    part(class_file.class_name().unwrap_or_default().as_bytes());
+    let super_class = class_file.super_class_name().ok().flatten();
+    part(super_class.unwrap_or_default().as_bytes());
+    let interfaces = class_file.interface_names().unwrap_or_default();
+    part(&(interfaces.len() as u64).to_be_bytes());
+    for interface in interfaces {
+        part(interface.as_bytes());
+    }
+    attributes(&mut part, &class_file.attributes);
+
+    let fields = class_file
+        .fields
+        .iter()
+        .filter(|field| !(options.exclude_synthetic && field.is_synthetic()))
+        .map(|field| {
+            let name = field.name(constant_pool).unwrap_or_default();
+            let descriptor = field.descriptor(constant_pool).unwrap_or_default();
+            (
+                name,
+                descriptor,
+                field.access_flags().bits(),
+                field.attributes(),
+            )
+        });
+    let methods = class_file
+        .methods
+        .iter()
+        .filter(|method| !(options.exclude_synthetic && method.is_synthetic()))
+        .map(|method| {
+            let name = method.name(constant_pool).unwrap_or_default();
+            let descriptor = method.descriptor(constant_pool).unwrap_or_default();
+            (
+                name,
+                descriptor,
+                method.access_flags().bits(),
+                method.attributes(),
+            )
+        });
+    for members in [fields.collect::<Vec<Member>>(), methods.collect()] {
+        let mut members = members;
+        members.sort_by_key(|&(name, descriptor, ..)| (name, descriptor));
+        part(&(members.len() as u64).to_be_bytes());
+        for (name, descriptor, flags, member_attributes) in members {
+            part(name.as_bytes());
+            part(descriptor.as_bytes());
+            part(&flags.to_be_bytes());
+            attributes(&mut part, member_attributes);
+        }
+    }
+    hasher.finish()
+}
+
+/// A class's constant pool with its bootstrap methods, which the
+/// `Dynamic` and `InvokeDynamic` entries of the pool refer to by index.
+#[derive(Clone, Copy)]
+struct Pool<'a> {
+    constants: &'a [ConstantPool],
+    bootstrap_methods: &'a [BootstrapMethod],
+}
+
+impl<'a> Pool<'a> {
+    fn of(class_file: &'a ClassFile) -> Self {
+        Pool {
+            constants: &class_file.constant_pool,
+            bootstrap_methods: class_file.bootstrap_methods(),
+        }
+    }
+
+    /// Appends a description of the constant at `index` which is the same
+    /// for equal constants in different pools, with the bootstrap method
+    /// of a dynamic one.
+    fn constant_key(self, index: u16, key: &mut String) {
+        clones::constant_key(self.constants, index, 0, key);
+        let constant = (index as usize)
+            .checked_sub(1)
+            .and_then(|i| self.constants.get(i));
+        if let Some(
+            ConstantPool::Dynamic(bootstrap, _) | ConstantPool::InvokeDynamic(bootstrap, _),
+        ) = constant
+        {
+            match self.bootstrap_methods.get(*bootstrap as usize) {
+                Some(method) => {
+                    key.push_str(&bootstrap_key(method, self.constants));
+                }
+                None => key.push('?'),
+            }
+        }
+    }
+}
+
+/// A field or method: its name, descriptor, flags and attributes.
+type Member<'a> = (&'a str, &'a str, u16, &'a [Attribute]);
+
+fn members(
+    entries: &mut Vec<DiffEntry>,
+    (old, old_pool): (Vec<Member<'_>>, Pool),
+    (new, new_pool): (Vec<Member<'_>>, Pool),
+    location: impl Fn(String, String) -> Location,
+) {
+    let new_by_key: BTreeMap<(&str, &str), &Member> = new
+        .iter()
+        .map(|member| ((member.0, member.1), member))
+        .collect();
+    let old_keys: Vec<(&str, &str)> = old.iter().map(|member| (member.0, member.1)).collect();
+    for &(name, descriptor, old_flags, old_attributes) in &old {
+        let here = location(name.to_string(), descriptor.to_string());
+        let &&(_, _, new_flags, new_attributes) = match new_by_key.get(&(name, descriptor)) {
+            Some(member) => member,
+            None => {
+                entries.push(DiffEntry::Removed(here));
+                continue;
+            }
+        };
+        flags(entries, here.clone(), old_flags, new_flags);
+        attributes(
+            entries,
+            &here,
+            (old_attributes, old_pool),
+            (new_attributes, new_pool),
+        );
+    }
+    for &(name, descriptor, ..) in &new {
+        if !old_keys.contains(&(name, descriptor)) {
+            entries.push(DiffEntry::Added(location(
+                name.to_string(),
+                descriptor.to_string(),
+            )));
+        }
+    }
+}
+
+fn flags(entries: &mut Vec<DiffEntry>, location: Location, old: u16, new: u16) {
+    if old != new {
+        entries.push(DiffEntry::AccessFlags { location, old, new });
+    }
+}
+
+/// Compares two attribute tables by name. Those nested in a `Code`
+/// attribute are compared as part of the table holding it.
+fn attributes(
+    entries: &mut Vec<DiffEntry>,
+    location: &Location,
+    (old, old_pool): (&[Attribute], Pool),
+    (new, new_pool): (&[Attribute], Pool),
+) {
+    let old_keys = attribute_keys(old, old_pool);
+    let new_keys = attribute_keys(new, new_pool);
+    for (name, old_values) in &old_keys {
+        let name = name.to_string();
+        let new_values = match new_keys.get(name.as_str()) {
+            Some(values) => values,
+            None => {
+                entries.push(DiffEntry::AttributeRemoved {
+                    location: location.clone(),
+                    name,
+                });
+                continue;
+            }
+        };
+        if old_values == new_values {
+            continue;
+        }
+        match name.as_str() {
+            "Code" => {
+                if let (Some(old_code), Some(new_code)) = (code_of(old), code_of(new)) {
+                    if bytecode_key(old_code, old_pool) != bytecode_key(new_code, new_pool) {
+                        entries.push(DiffEntry::Code {
+                            location: location.clone(),
+                            old_len: old_code.code.len(),
+                            new_len: new_code.code.len(),
+                        });
+                    }
+                    if code_key(old_code, old_pool.constants)
+                        == code_key(new_code, new_pool.constants)
+                    {
+                        continue;
+                    }
+                }
+            }
+            "ConstantValue" => {
+                if let (Some(old_value), Some(new_value)) = (
+                    constant_value(old, old_pool.constants),
+                    constant_value(new, new_pool.constants),
+                ) {
+                    entries.push(DiffEntry::ConstantValue {
+                        location: location.clone(),
+                        old: old_value,
+                        new: new_value,
+                    });
+                    continue;
+                }
+            }
+            _ => {}
+        }
+        entries.push(DiffEntry::AttributeChanged {
+            location: location.clone(),
+            name,
+        });
+    }
+    for name in new_keys.keys() {
+        if !old_keys.contains_key(name) {
+            entries.push(DiffEntry::AttributeAdded {
+                location: location.clone(),
+                name: name.to_string(),
+            });
+        }
+    }
+}
+
+/// The keys of the attributes of a table by name, with those nested in its
+/// `Code` alongside.
+fn attribute_keys<'a>(attributes: &'a [Attribute], pool: Pool) -> BTreeMap<&'a str, Vec<String>> {
+    let mut keys: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+    for attribute in attributes {
+        let key = match attribute {
+            Attribute::Code(code) => {
+                for (name, nested) in attribute_keys(&code.attributes, pool) {
+                    keys.entry(name).or_default().extend(nested);
+                }
+                let mut key = bytecode_key(code, pool);
+                key.push_str(&code_key(code, pool.constants));
+                key
+            }
+            _ => attribute_key(attribute, pool.constants),
+        };
+        keys.entry(attribute.name()).or_default().push(key);
+    }
+    keys
+}
+
+fn code_of(attributes: &[Attribute]) -> Option<&Code> {
+    attributes.iter().find_map(|attribute| match attribute {
+        Attribute::Code(code) => Some(code),
+        _ => None,
+    })
+}
+
+fn constant_value(attributes: &[Attribute], constant_pool: &[ConstantPool]) -> Option<String> {
+    attributes.iter().find_map(|attribute| match attribute {
+        Attribute::ConstantValue(index) => Some(constant_text(constant_pool, *index)),
+        _ => None,
+    })
+}
+
+/// The bytecode of `code` with constant pool operands replaced by what
+/// they name and switch padding dropped, or the bytes as they are when
+/// they don't decode.
+fn bytecode_key(code: &Code, pool: Pool) -> String {
+    let mut key = String::new();
+    for instruction in instruction::decode(&code.code) {
+        let instruction = match instruction {
+            Ok(instruction) => instruction,
+            Err(_) => return format!("{:?}", code.code),
+        };
+        let _ = write!(key, "{}(", instruction.opcode);
+        let operands = match instruction.cp_index() {
+            Some(index) => {
+                pool.constant_key(index, &mut key);
+                // the count and zero byte of invokeinterface and the zero
+                // bytes of invokedynamic follow the index
+                &instruction.operands[2.min(instruction.operands.len())..]
+            }
+            None if matches!(instruction.opcode, TABLESWITCH | LOOKUPSWITCH) => {
+                let padding = (3 - instruction.offset as usize % 4) % 4;
+                &instruction.operands[padding.min(instruction.operands.len())..]
+            }
+            None => instruction.operands,
+        };
+        let _ = write!(key, "{:?});", operands);
+    }
+    key
+}
+
+/// The method handle and arguments of a bootstrap method.
+fn bootstrap_key(method: &BootstrapMethod, constant_pool: &[ConstantPool]) -> String {
+    let mut key = String::from("(");
+    clones::constant_key(constant_pool, method.bootstrap_method_ref, 0, &mut key);
+    for &argument in &method.bootstrap_arguments {
+        key.push(',');
+        clones::constant_key(constant_pool, argument, 0, &mut key);
+    }
+    key.push(')');
+    key
+}
+
+/// The sizes and exception table of `code`.
+fn code_key(code: &Code, constant_pool: &[ConstantPool]) -> String {
+    let mut key = format!("{},{};", code.max_stack, code.max_locals);
+    for exception in &code.exception_table {
+        let _ = write!(
+            key,
+            "{},{},{},",
+            exception.start_pc, exception.end_pc, exception.handler_pc
+        );
+        if exception.catch_type != 0 {
+            clones::constant_key(constant_pool, exception.catch_type, 0, &mut key);
+        }
+        key.push(';');
+    }
+    key
+}
+
+/// A description of `attribute` which is the same for attributes with the
+/// same content in different pools: what it holds besides constant pool
+/// indices, then the constants at those indices in the order
+/// [`Attribute::visit_constant_roles`] visits them.
+fn attribute_key(attribute: &Attribute, constant_pool: &[ConstantPool]) -> String {
+    let mut key = match attribute {
+        Attribute::ConstantValue(_)
+        | Attribute::Signature(_)
+        | Attribute::SourceFile(_)
+        | Attribute::NestHost(_)
+        | Attribute::ModuleMainClass(_)
+        | Attribute::Exceptions(_)
+        | Attribute::NestMembers(_)
+        | Attribute::ModulePackages(_)
+        | Attribute::PermittedSubclasses(_)
+        | Attribute::EnclosingMethod(_) => String::new(),
+        Attribute::Code(code) => code_key(code, constant_pool),
+        Attribute::StackMapTable(table) => {
+            let mut key = String::new();
+            for frame in &table.entries {
+                let (kind, types): (_, Vec<&VerificationTypeInfo>) = match frame {
+                    StackMapFrame::SameFrame(_) => ("same", Vec::new()),
+                    StackMapFrame::SameFrameExtended(_) => ("same extended", Vec::new()),
+                    StackMapFrame::SameLocals1StackItemFrame { stack, .. } => {
+                        ("one item", vec![stack])
+                    }
+                    StackMapFrame::SameLocals1StackItemFrameExtended { stack, .. } => {
+                        ("one item extended", vec![stack])
+                    }
+                    StackMapFrame::ChopFrame { chopped, .. } => {
+                        let _ = write!(key, "{}", chopped);
+                        ("chop", Vec::new())
+                    }
+                    StackMapFrame::AppendFrame { locals, .. } => {
+                        ("append", locals.iter().collect())
+                    }
+                    StackMapFrame::FullFrame { locals, stack, .. } => {
+                        let _ = write!(key, "{}", locals.len());
+                        ("full", locals.iter().chain(stack).collect())
+                    }
+                };
+                let _ = write!(key, "{} {}(", kind, frame.offset_delta());
+                for info in types {
+                    match info {
+                        VerificationTypeInfo::ObjectVariableInfo(_) => key.push_str("Object"),
+                        info => {
+                            let _ = write!(key, "{:?}", info);
+                        }
+                    }
+                    key.push(',');
+                }
+                key.push_str(");");
+            }
+            key
+        }
+        Attribute::InnerClasses(inner_classes) => inner_classes
+            .classes
+            .iter()
+            .map(|class| {
+                format!(
+                    "{},{},{};",
+                    class.inner_class_access_flags.bits(),
+                    class.outer_class_info_index != 0,
+                    class.inner_name_index != 0
+                )
+            })
+            .collect(),
+        Attribute::LocalVariableTable(table) => table
+            .iter()
+            .map(|variable| {
+                format!(
+                    "{},{},{};",
+                    variable.start_pc, variable.length, variable.index
+                )
+            })
+            .collect(),
+        Attribute::LocalVariableTypeTable(table) => table
+            .iter()
+            .map(|variable| {
+                format!(
+                    "{},{},{};",
+                    variable.start_pc, variable.length, variable.index
+                )
+            })
+            .collect(),
+        Attribute::RuntimeVisibleAnnotations(annotations)
+        | Attribute::RuntimeInvisibleAnnotations(annotations) => annotations_key(annotations),
+        Attribute::RuntimeVisibleParameterAnnotations(parameters)
+        | Attribute::RuntimeInvisibleParameterAnnotations(parameters) => parameters
+            .iter()
+            .map(|parameter| format!("({})", annotations_key(&parameter.annotations)))
+            .collect(),
+        Attribute::RuntimeVisibleTypeAnnotations(annotations)
+        | Attribute::RuntimeInvisibleTypeAnnotations(annotations) => {
+            let mut key = String::new();
+            for annotation in annotations {
+                let _ = write!(
+                    key,
+                    "{},{:?},{:?}",
+                    annotation.target_type, annotation.target_info, annotation.target_path
+                );
+                pairs_key(&annotation.element_value_pairs, &mut key);
+                key.push(';');
+            }
+            key
+        }
+        Attribute::AnnotationDefault(value) => {
+            let mut key = String::new();
+            element_value_key(value, &mut key);
+            key
+        }
+        Attribute::BootstrapMethods(methods) => {
+            // in any order, as instructions are compared with the methods
+            // they use
+            let mut keys: Vec<String> = methods
+                .iter()
+                .map(|method| bootstrap_key(method, constant_pool))
+                .collect();
+            keys.sort();
+            return keys.concat();
+        }
+        Attribute::MethodParameters(parameters) => parameters
+            .iter()
+            .map(|parameter| {
+                format!(
+                    "{},{};",
+                    parameter.name_index != 0,
+                    parameter.access_flags.bits()
+                )
+            })
+            .collect(),
+        Attribute::Record(components) => {
+            let mut key = String::new();
+            for component in components {
+                let _ = write!(key, "{}(", component.attributes.len());
+                for attribute in &component.attributes {
+                    let _ = write!(
+                        key,
+                        "{}={};",
+                        attribute.name(),
+                        attribute_key(attribute, constant_pool)
+                    );
+                }
+                key.push_str(");");
+            }
+            key
+        }
+        Attribute::Synthetic(_)
+        | Attribute::SourceDebugExtension(_)
+        | Attribute::LineNumberTable(_)
+        | Attribute::Deprecated(_)
+        | Attribute::Module(_)
+        | Attribute::Custom(_)
+        | Attribute::Unknown { .. }
+        | Attribute::Deferred(_) => format!("{:?}", attribute),
+    };
+    key.push('|');
+    attribute.visit_constant_roles(constant_pool, &mut |index, _| {
+        clones::constant_key(constant_pool, index, 0, &mut key);
+        key.push(',');
+    });
+    key
+}
+
+fn annotations_key(annotations: &[Annotation]) -> String {
+    let mut key = String::new();
+    for annotation in annotations {
+        pairs_key(&annotation.element_value_pairs, &mut key);
+        key.push(';');
+    }
+    key
+}
+
+/// The shape of element values: their tags and how many elements their
+/// arrays and annotations have.
+fn pairs_key(pairs: &[(u16, ElementValue)], key: &mut String) {
+    let _ = write!(key, "{}(", pairs.len());
+    for (_, value) in pairs {
+        element_value_key(value, key);
+    }
+    key.push(')');
+}
+
+fn element_value_key(value: &ElementValue, key: &mut String) {
+    match value {
+        ElementValue::ConstValue { tag, .. } => key.push(*tag as char),
+        ElementValue::EnumConstValue { .. } => key.push('e'),
+        ElementValue::ClassInfoIndex(_) => key.push('c'),
+        ElementValue::AnnotationValue(annotation) => {
+            key.push('@');
+            pairs_key(&annotation.element_value_pairs, key);
+        }
+        ElementValue::ArrayValue(values) => {
+            let _ = write!(key, "[{}", values.len());
+            for value in values {
+                element_value_key(value, key);
+            }
+            key.push(']');
+        }
+    }
+}
+
+/// A loadable constant as Java source writes it, e.g. `42`, `42L`, `1.5f`
+/// or `"text"`.
+pub fn constant_text(constant_pool: &[ConstantPool], index: u16) -> String {
+    let constant = (index as usize)
+        .checked_sub(1)
+        .and_then(|i| constant_pool.get(i));
+    match constant {
+        Some(ConstantPool::Integer(value)) => value.to_string(),
+        Some(ConstantPool::Long(value)) => format!("{}L", value),
+        Some(ConstantPool::Float(bits)) => format!("{:?}f", bits.value()),
+        Some(ConstantPool::Double(bits)) => format!("{:?}d", bits.value()),
+        Some(ConstantPool::String(value)) => {
+            format!(
+                "{:?}",
+                ConstantPool::utf8(constant_pool, *value).unwrap_or("")
+            )
+        }
+        _ => format!("#{}", index),
+    }
+}
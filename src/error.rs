@@ -0,0 +1,401 @@
+//! Why a class file failed to parse.
+//!
+//! The parsers of the class file model fail with [`ErrorAt`], a nom error
+//! holding a [`ParseError`] and the input where it happened, rather than
+//! panicking, whatever bytes they're given.
+//!
+//! A class file cut short, such as one half downloaded, fails with
+//! [`ParseError::Truncated`], to be told apart from one whose bytes are all
+//! there but make no sense. Running out inside an attribute, whose length
+//! bounds what's read of it, is the attribute being malformed,
+//! [`ParseError::AttributeTruncated`], rather than the input.
+
+use core::fmt;
+
+use nom::error::ErrorKind;
+
+use crate::constantpool::{ConstantPool, ConstantPoolError};
+use crate::prelude::*;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    /// The input ended in the middle of a structure.
+    UnexpectedEof,
+    /// The input ended short of what the class needs next: a field of a
+    /// fixed size, a length such as an `attribute_length` or the length of
+    /// a UTF8 constant, or the entries of a count, each at its least.
+    /// `expected` bytes were needed at `at_offset` in the class, with
+    /// `available` left, while parsing `context`, e.g. `method
+    /// <init>()V`.
+    Truncated {
+        expected: usize,
+        available: usize,
+        at_offset: usize,
+        context: String,
+    },
+    /// The input doesn't start with `0xCAFEBABE`.
+    BadMagic,
+    /// The class's major version is older than any there is, or newer than
+    /// jvmb supports and
+    /// [`ParseOptions::reject_unsupported_versions`](crate::classfile::ParseOptions::reject_unsupported_versions)
+    /// was set.
+    UnsupportedVersion(u16),
+    /// A constant pool entry with a tag jvmb doesn't know, and no size to
+    /// skip it by.
+    UnknownConstantTag(u8),
+    /// An index which doesn't lead to a constant pool entry of the kind
+    /// needed, such as the name of an attribute.
+    InvalidConstantPoolIndex(ConstantPoolError),
+    /// A UTF8 constant which isn't valid modified UTF-8.
+    InvalidUtf8,
+    /// An attribute with a name jvmb doesn't know, and no parser registered
+    /// for it.
+    UnknownAttribute(String),
+    /// An attribute the parser registered for it rejected.
+    InvalidAttribute { name: String, message: String },
+    /// An attribute whose structure ended `declared - consumed` bytes short
+    /// of its `attribute_length`, or a `Synthetic` or `Deprecated` attribute
+    /// which isn't empty. See
+    /// [`ParseOptions::lenient_attribute_lengths`](crate::classfile::ParseOptions::lenient_attribute_lengths).
+    AttributeLengthMismatch {
+        name: String,
+        declared: u32,
+        consumed: u32,
+    },
+    /// An attribute whose structure runs past its `attribute_length`.
+    AttributeTruncated { name: String, declared: u32 },
+    /// A tag of `structure`, such as a stack map frame or an element value,
+    /// which the format doesn't define.
+    UnknownTag { structure: &'static str, tag: u8 },
+    /// Input left over by a parse which must consume all of it.
+    TrailingBytes,
+    /// Attributes or element values nested deeper than jvmb follows.
+    NestingTooDeep { structure: &'static str },
+    /// An attribute first defined for class files of version `since_major`,
+    /// in a class of an older one, when
+    /// [`ParseOptions::strict_attribute_versions`](crate::classfile::ParseOptions::strict_attribute_versions)
+    /// was set.
+    AttributeBeforeVersion {
+        name: String,
+        since_major: u16,
+        major_version: u16,
+    },
+    /// Any other failure, by the nom parser which failed.
+    Nom(ErrorKind),
+}
+
+impl ParseError {
+    /// The name of the variant, to group failures by.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ParseError::UnexpectedEof => "UnexpectedEof",
+            ParseError::Truncated { .. } => "Truncated",
+            ParseError::BadMagic => "BadMagic",
+            ParseError::UnsupportedVersion(_) => "UnsupportedVersion",
+            ParseError::UnknownConstantTag(_) => "UnknownConstantTag",
+            ParseError::InvalidConstantPoolIndex(_) => "InvalidConstantPoolIndex",
+            ParseError::InvalidUtf8 => "InvalidUtf8",
+            ParseError::UnknownAttribute(_) => "UnknownAttribute",
+            ParseError::InvalidAttribute { .. } => "InvalidAttribute",
+            ParseError::AttributeLengthMismatch { .. } => "AttributeLengthMismatch",
+            ParseError::AttributeTruncated { .. } => "AttributeTruncated",
+            ParseError::UnknownTag { .. } => "UnknownTag",
+            ParseError::TrailingBytes => "TrailingBytes",
+            ParseError::NestingTooDeep { .. } => "NestingTooDeep",
+            ParseError::AttributeBeforeVersion { .. } => "AttributeBeforeVersion",
+            ParseError::Nom(_) => "Nom",
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::UnexpectedEof => f.write_str("unexpected end of input"),
+            ParseError::Truncated {
+                expected,
+                available,
+                ..
+            } => write!(
+                f,
+                "input truncated: {} bytes needed, {} left",
+                expected, available
+            ),
+            ParseError::BadMagic => f.write_str("not a class file (bad magic number)"),
+            ParseError::UnsupportedVersion(major_version) => {
+                write!(f, "unsupported class file version {}", major_version)
+            }
+            ParseError::UnknownConstantTag(tag) => write!(f, "unknown constant pool tag {}", tag),
+            ParseError::InvalidConstantPoolIndex(error) => write!(f, "{}", error),
+            ParseError::InvalidUtf8 => f.write_str("UTF8 constant isn't valid modified UTF-8"),
+            ParseError::UnknownAttribute(name) => write!(f, "unknown attribute {:?}", name),
+            ParseError::InvalidAttribute { name, message } => {
+                write!(f, "invalid {} attribute: {}", name, message)
+            }
+            ParseError::AttributeLengthMismatch {
+                name,
+                declared,
+                consumed,
+            } => write!(
+                f,
+                "{} attribute is {} bytes long but its contents take {}",
+                name, declared, consumed
+            ),
+            ParseError::AttributeTruncated { name, declared } => write!(
+                f,
+                "{} attribute is cut short by its length of {} bytes",
+                name, declared
+            ),
+            ParseError::UnknownTag { structure, tag } => {
+                write!(f, "unknown {} tag {}", structure, tag)
+            }
+            ParseError::TrailingBytes => f.write_str("trailing bytes after the end"),
+            ParseError::NestingTooDeep { structure } => write!(f, "{}s nested too deep", structure),
+            ParseError::AttributeBeforeVersion {
+                name,
+                since_major,
+                major_version,
+            } => write!(
+                f,
+                "{} attribute needs class file version {}, found {}",
+                name, since_major, major_version
+            ),
+            ParseError::Nom(kind) => write!(f, "malformed input ({:?})", kind),
+        }
+    }
+}
+
+impl core::error::Error for ParseError {}
+
+impl<I> From<nom::Err<ErrorAt<I>>> for ParseError {
+    fn from(e: nom::Err<ErrorAt<I>>) -> Self {
+        match e {
+            nom::Err::Error(e) | nom::Err::Failure(e) => e.code,
+            nom::Err::Incomplete(_) => ParseError::UnexpectedEof,
+        }
+    }
+}
+
+/// The error of jvmb's parsers: what went wrong, and the input left where
+/// it did.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorAt<I> {
+    pub input: I,
+    pub code: ParseError,
+    /// What was being parsed, outermost first, e.g. `method <init>()V`,
+    /// `Code`, `StackMapTable` and `entry 3`.
+    pub context: Vec<String>,
+}
+
+impl<I> ErrorAt<I> {
+    pub fn new(input: I, code: ParseError) -> Self {
+        ErrorAt {
+            input,
+            code,
+            context: Vec::new(),
+        }
+    }
+
+    /// The error as a nom failure, which stops alternatives being tried.
+    pub(crate) fn failure(input: I, code: ParseError) -> nom::Err<Self> {
+        nom::Err::Failure(Self::new(input, code))
+    }
+}
+
+impl ErrorAt<&[u8]> {
+    /// How far into `class`, the input the parse started from, the error
+    /// happened, wherever in it the input of the error lies.
+    pub fn offset_in(&self, class: &[u8]) -> usize {
+        let start = class.as_ptr() as usize;
+        let at = self.input.as_ptr() as usize;
+        if (start..=start + class.len()).contains(&at) {
+            at - start
+        } else {
+            class.len().saturating_sub(self.input.len())
+        }
+    }
+
+    /// The context, e.g. `method <init>()V > Code > StackMapTable > entry 3`.
+    pub fn breadcrumb(&self) -> String {
+        self.context.join(" > ")
+    }
+}
+
+/// `e`, an error of parsing the class file `class`, with the offset and
+/// context of a [`ParseError::Truncated`] filled in, which the parser
+/// running out only knows once the error reaches the class.
+pub(crate) fn locate<'a>(
+    e: nom::Err<ErrorAt<&'a [u8]>>,
+    class: &[u8],
+) -> nom::Err<ErrorAt<&'a [u8]>> {
+    e.map(|mut e| {
+        let offset = e.offset_in(class);
+        let breadcrumb = e.breadcrumb();
+        if let ParseError::Truncated {
+            at_offset, context, ..
+        } = &mut e.code
+        {
+            *at_offset = offset;
+            *context = breadcrumb;
+        }
+        e
+    })
+}
+
+/// A [`ParseError::Truncated`] for needing `expected` bytes of `buf`, where
+/// it is and what was being parsed still to be filled in by [`locate`].
+fn truncated(buf: &[u8], expected: usize) -> ErrorAt<&[u8]> {
+    ErrorAt::new(
+        buf,
+        ParseError::Truncated {
+            expected,
+            available: buf.len(),
+            at_offset: 0,
+            context: String::new(),
+        },
+    )
+}
+
+/// nom's `take`, failing with [`ParseError::Truncated`] when `buf` holds
+/// fewer than `n` bytes.
+pub(crate) fn take<'a>(n: usize) -> impl Fn(&'a [u8]) -> IResult<&'a [u8], &'a [u8]> {
+    move |buf| match buf.split_at_checked(n) {
+        Some((taken, rest)) => Ok((rest, taken)),
+        None => Err(nom::Err::Error(truncated(buf, n))),
+    }
+}
+
+macro_rules! numbers {
+    ($($name:ident: $ty:ident),*) => {$(
+        #[doc = concat!("nom's parser of a big-endian `", stringify!($ty), "`, failing with")]
+        /// [`ParseError::Truncated`] when `buf` holds too few bytes.
+        pub(crate) fn $name(buf: &[u8]) -> IResult<&[u8], $ty> {
+            match buf.split_first_chunk() {
+                Some((bytes, rest)) => Ok((rest, $ty::from_be_bytes(*bytes))),
+                None => Err(nom::Err::Error(truncated(buf, core::mem::size_of::<$ty>()))),
+            }
+        }
+    )*};
+}
+
+numbers!(u8: u8, be_u16: u16, be_u32: u32, be_u64: u64, be_i32: i32, be_i64: i64);
+
+impl<I> nom::error::ParseError<I> for ErrorAt<I> {
+    fn from_error_kind(input: I, kind: ErrorKind) -> Self {
+        let code = match kind {
+            ErrorKind::Eof => ParseError::UnexpectedEof,
+            kind => ParseError::Nom(kind),
+        };
+        ErrorAt::new(input, code)
+    }
+
+    fn append(_: I, _: ErrorKind, other: Self) -> Self {
+        other
+    }
+}
+
+/// nom's result, failing with [`ErrorAt`].
+pub type IResult<I, O> = nom::IResult<I, O, ErrorAt<I>>;
+
+/// Runs `parser` on the whole of `buf`, failing with
+/// [`ParseError::TrailingBytes`] at whatever it leaves.
+pub(crate) fn all_consuming<'a, O>(
+    buf: &'a [u8],
+    parser: impl FnOnce(&'a [u8]) -> IResult<&'a [u8], O>,
+) -> IResult<&'a [u8], O> {
+    let (rest, parsed) = parser(buf)?;
+    if !rest.is_empty() {
+        return Err(ErrorAt::failure(rest, ParseError::TrailingBytes));
+    }
+    Ok((rest, parsed))
+}
+
+/// The field or method starting at `member`, the `index`th of the class, as
+/// the context of an error in it: `field count:I` or `method <init>()V`, or
+/// `field 3` when its name or descriptor can't be told.
+pub(crate) fn member_context(
+    kind: &str,
+    index: u16,
+    member: &[u8],
+    constant_pool: &[ConstantPool],
+) -> String {
+    let utf8 = |at: usize| {
+        let index = u16::from_be_bytes([*member.get(at)?, *member.get(at + 1)?]);
+        ConstantPool::utf8(constant_pool, index)
+    };
+    match (utf8(2), utf8(4)) {
+        (Some(name), Some(descriptor)) if kind == "field" => {
+            format!("field {}:{}", name, descriptor)
+        }
+        (Some(name), Some(descriptor)) => format!("{} {}{}", kind, name, descriptor),
+        _ => format!("{} {}", kind, index),
+    }
+}
+
+/// `result`, with `what` as the outermost context of its error.
+pub(crate) fn within<I, O>(
+    result: Result<O, nom::Err<ErrorAt<I>>>,
+    what: impl FnOnce() -> String,
+) -> Result<O, nom::Err<ErrorAt<I>>> {
+    result.map_err(|e| {
+        e.map(|mut e| {
+            e.context.insert(0, what());
+            e
+        })
+    })
+}
+
+/// nom's `count`, except that it fails with [`ParseError::Truncated`]
+/// before reserving anything when `count` entries of at least `min_size`
+/// bytes each can't fit in the input, so that a count read from a hostile
+/// file can't make the parser reserve memory the file doesn't justify.
+pub(crate) fn count<'a, O>(
+    mut parser: impl FnMut(&'a [u8]) -> IResult<&'a [u8], O>,
+    count: usize,
+    min_size: usize,
+) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], Vec<O>> {
+    move |mut buf| {
+        let mut parsed = Vec::with_capacity(capacity(buf, count, min_size)?);
+        for _ in 0..count {
+            let (rest, entry) = parser(buf)?;
+            buf = rest;
+            parsed.push(entry);
+        }
+        Ok((buf, parsed))
+    }
+}
+
+/// [`count`], with `"<what> <n>"` as the context of an entry which fails,
+/// counting from 0.
+pub(crate) fn count_within<'a, O>(
+    mut parser: impl FnMut(&'a [u8]) -> IResult<&'a [u8], O>,
+    count: usize,
+    min_size: usize,
+    what: &'static str,
+) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], Vec<O>> {
+    move |mut buf| {
+        let mut parsed = Vec::with_capacity(capacity(buf, count, min_size)?);
+        for i in 0..count {
+            let (rest, entry) = within(parser(buf), || format!("{} {}", what, i))?;
+            buf = rest;
+            parsed.push(entry);
+        }
+        Ok((buf, parsed))
+    }
+}
+
+/// `count`, if that many entries of at least `min_size` bytes each fit in
+/// `buf`, to reserve room for them.
+pub(crate) fn capacity(
+    buf: &[u8],
+    count: usize,
+    min_size: usize,
+) -> Result<usize, nom::Err<ErrorAt<&[u8]>>> {
+    if count.saturating_mul(min_size) > buf.len() {
+        return Err(nom::Err::Failure(truncated(
+            buf,
+            count.saturating_mul(min_size),
+        )));
+    }
+    Ok(count)
+}
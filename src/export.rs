@@ -0,0 +1,685 @@
+//! The JSON form of a parsed class file, and the JSON Schema describing it.
+//!
+//! Shape rules, which hold for every object in the document:
+//!
+//! - every member listed in the schema is always present; a value which
+//!   doesn't apply is `null`, never omitted,
+//! - references into the constant pool are `{ "index": N, "resolved": ... }`
+//!   where `resolved` is the text the entry names, or `null` if it names
+//!   nothing or the index is invalid,
+//! - byte arrays and floating point bit patterns are lowercase hex strings,
+//! - `long` values are decimal strings, since they don't fit in a double.
+//!
+//! Any change to the shape bumps [`SCHEMA_VERSION`], and the emitters below
+//! are kept next to the schema fragments describing them so that the two are
+//! changed together.
+
+use crate::prelude::*;
+use crate::{
+    attribute::{Attribute, Code},
+    classfile::{ClassFile, ParseStage, Truncation},
+    constantpool::ConstantPool,
+    json::Json,
+    mutf8,
+    sha256::hex,
+    spec,
+};
+
+pub const SCHEMA_VERSION: u32 = 4;
+
+pub fn class_file(class_file: &ClassFile) -> Json {
+    let pool = &class_file.constant_pool;
+    Json::object([
+        ("schema_version", SCHEMA_VERSION.into()),
+        ("minor_version", class_file.minor_version.into()),
+        ("major_version", class_file.major_version.into()),
+        ("access_flags", class_file.access_flags.bits().into()),
+        ("this_class", cp_ref(pool, class_file.this_class)),
+        ("super_class", optional_cp_ref(pool, class_file.super_class)),
+        (
+            "interfaces",
+            Json::array(class_file.interfaces.iter().map(|&i| cp_ref(pool, i))),
+        ),
+        (
+            "constant_pool",
+            Json::array(
+                pool.iter()
+                    .enumerate()
+                    .filter(|(_, c)| **c != ConstantPool::Unusable)
+                    .map(|(i, c)| constant(pool, i as u16 + 1, c)),
+            ),
+        ),
+        (
+            "fields",
+            Json::array(class_file.fields.iter().map(|field| {
+                member(
+                    pool,
+                    field.access_flags().bits(),
+                    field.name_index(),
+                    field.descriptor_index(),
+                    field.attributes(),
+                )
+            })),
+        ),
+        (
+            "methods",
+            Json::array(class_file.methods.iter().map(|method| {
+                member(
+                    pool,
+                    method.access_flags().bits(),
+                    method.name_index(),
+                    method.descriptor_index(),
+                    method.attributes(),
+                )
+            })),
+        ),
+        ("attributes", attributes(pool, &class_file.attributes)),
+    ])
+}
+
+/// The JSON Schema (draft 2020-12) that [`class_file`] output conforms to.
+pub fn schema() -> Json {
+    Json::object([
+        (
+            "$schema",
+            "https://json-schema.org/draft/2020-12/schema".into(),
+        ),
+        (
+            "$id",
+            format!("https://github.com/Forlos/jvmb/schema/v{}", SCHEMA_VERSION).into(),
+        ),
+        ("title", "ClassFile".into()),
+        ("type", "object".into()),
+        (
+            "properties",
+            Json::object([
+                (
+                    "schema_version",
+                    Json::object([("const", SCHEMA_VERSION.into())]),
+                ),
+                ("minor_version", u16_schema()),
+                ("major_version", u16_schema()),
+                ("access_flags", u16_schema()),
+                ("this_class", reference("CpRef")),
+                ("super_class", nullable(reference("CpRef"))),
+                ("interfaces", array_of(reference("CpRef"))),
+                ("constant_pool", array_of(reference("Constant"))),
+                ("fields", array_of(reference("Member"))),
+                ("methods", array_of(reference("Member"))),
+                ("attributes", array_of(reference("Attribute"))),
+                ("truncation", truncation_schema()),
+            ]),
+        ),
+        (
+            "required",
+            Json::array([
+                "schema_version",
+                "minor_version",
+                "major_version",
+                "access_flags",
+                "this_class",
+                "super_class",
+                "interfaces",
+                "constant_pool",
+                "fields",
+                "methods",
+                "attributes",
+            ]),
+        ),
+        ("additionalProperties", false.into()),
+        (
+            "$defs",
+            Json::object([
+                ("CpRef", cp_ref_schema()),
+                ("Constant", constant_schema()),
+                ("Member", member_schema()),
+                ("Attribute", attribute_schema()),
+            ]),
+        ),
+    ])
+}
+
+fn cp_ref(pool: &[ConstantPool], index: u16) -> Json {
+    Json::object([
+        ("index", index.into()),
+        ("resolved", resolve(pool, index).into()),
+    ])
+}
+
+/// The `truncation` member added to a class salvaged from a damaged file,
+/// telling where the data stopped.
+pub fn truncation(truncation: &Truncation) -> Json {
+    Json::object([
+        ("stage", truncation.stage.name().into()),
+        ("offset", Json::Int(truncation.offset as i64)),
+    ])
+}
+
+fn truncation_schema() -> Json {
+    struct_schema(&[
+        (
+            "stage",
+            Json::object([(
+                "enum",
+                Json::array(ParseStage::ALL.iter().map(|stage| stage.name())),
+            )]),
+        ),
+        (
+            "offset",
+            Json::object([("type", "integer".into()), ("minimum", 0.into())]),
+        ),
+    ])
+}
+
+/// Index 0 means "none" for the few references which are optional.
+fn optional_cp_ref(pool: &[ConstantPool], index: u16) -> Json {
+    match index {
+        0 => Json::Null,
+        index => cp_ref(pool, index),
+    }
+}
+
+fn cp_ref_schema() -> Json {
+    struct_schema(&[
+        ("index", u16_schema()),
+        ("resolved", nullable(type_schema("string"))),
+    ])
+}
+
+/// The text a constant names: a class, member, string or descriptor.
+fn resolve(pool: &[ConstantPool], index: u16) -> Option<String> {
+    let constant = pool.get((index as usize).checked_sub(1)?)?;
+    match constant {
+        ConstantPool::UTF8(value) => Some(value.clone()),
+        ConstantPool::Utf16(units) => Some(String::from_utf16_lossy(units)),
+        ConstantPool::RawUtf8(bytes) => Some(mutf8::decode_lossy(bytes)),
+        ConstantPool::Class(index)
+        | ConstantPool::String(index)
+        | ConstantPool::MethodType(index)
+        | ConstantPool::Module(index)
+        | ConstantPool::Package(index) => resolve(pool, *index),
+        ConstantPool::NameAndType(name_index, descriptor_index) => Some(format!(
+            "{}:{}",
+            resolve(pool, *name_index)?,
+            resolve(pool, *descriptor_index)?
+        )),
+        ConstantPool::FieldRef(class_index, name_and_type_index)
+        | ConstantPool::MethodRef(class_index, name_and_type_index)
+        | ConstantPool::InterfaceMethodRef(class_index, name_and_type_index) => Some(format!(
+            "{}.{}",
+            resolve(pool, *class_index)?,
+            resolve(pool, *name_and_type_index)?
+        )),
+        ConstantPool::MethodHandle(_, reference_index) => resolve(pool, *reference_index),
+        ConstantPool::Dynamic(_, name_and_type_index)
+        | ConstantPool::InvokeDynamic(_, name_and_type_index) => {
+            resolve(pool, *name_and_type_index)
+        }
+        ConstantPool::Integer(_)
+        | ConstantPool::Float(_)
+        | ConstantPool::Long(_)
+        | ConstantPool::Double(_)
+        | ConstantPool::Unusable
+        | ConstantPool::Unknown { .. } => None,
+    }
+}
+
+fn constant(pool: &[ConstantPool], index: u16, constant: &ConstantPool) -> Json {
+    let kind = spec::constant_spec(constant.tag()).map_or("?", |spec| spec.name);
+    let mut json = Json::object([
+        ("index", index.into()),
+        ("tag", constant.tag().into()),
+        ("kind", kind.into()),
+    ]);
+    match constant {
+        ConstantPool::Class(name_index)
+        | ConstantPool::Module(name_index)
+        | ConstantPool::Package(name_index) => json.push("name", cp_ref(pool, *name_index)),
+        ConstantPool::FieldRef(class_index, name_and_type_index)
+        | ConstantPool::MethodRef(class_index, name_and_type_index)
+        | ConstantPool::InterfaceMethodRef(class_index, name_and_type_index) => {
+            json.push("class", cp_ref(pool, *class_index));
+            json.push("name_and_type", cp_ref(pool, *name_and_type_index));
+        }
+        ConstantPool::String(string_index) => json.push("string", cp_ref(pool, *string_index)),
+        ConstantPool::Integer(value) => json.push("value", *value),
+        ConstantPool::Float(bits) => {
+            json.push("bits", format!("{:08x}", bits.0));
+            json.push("value", bits.value() as f64);
+        }
+        ConstantPool::Long(value) => json.push("value", value.to_string()),
+        ConstantPool::Double(bits) => {
+            json.push("bits", format!("{:016x}", bits.0));
+            json.push("value", bits.value());
+        }
+        ConstantPool::NameAndType(name_index, descriptor_index) => {
+            json.push("name", cp_ref(pool, *name_index));
+            json.push("descriptor", cp_ref(pool, *descriptor_index));
+        }
+        ConstantPool::UTF8(value) => json.push("value", value.as_str()),
+        ConstantPool::Utf16(units) => {
+            json.push("value", String::from_utf16_lossy(units));
+            json.push("utf16", Json::array(units.iter().copied()));
+        }
+        ConstantPool::RawUtf8(bytes) => {
+            json.push("value", mutf8::decode_lossy(bytes));
+            json.push("bytes", Json::array(bytes.iter().copied()));
+        }
+        ConstantPool::MethodHandle(reference_kind, reference_index) => {
+            json.push("reference_kind", *reference_kind);
+            json.push("reference", cp_ref(pool, *reference_index));
+        }
+        ConstantPool::MethodType(descriptor_index) => {
+            json.push("descriptor", cp_ref(pool, *descriptor_index))
+        }
+        ConstantPool::Dynamic(bootstrap_method_attr_index, name_and_type_index)
+        | ConstantPool::InvokeDynamic(bootstrap_method_attr_index, name_and_type_index) => {
+            json.push("bootstrap_method_attr_index", *bootstrap_method_attr_index);
+            json.push("name_and_type", cp_ref(pool, *name_and_type_index));
+        }
+        ConstantPool::Unknown { body, .. } => json.push("body", hex(body)),
+        ConstantPool::Unusable => {}
+    }
+    json
+}
+
+fn constant_schema() -> Json {
+    let variant = |kinds: &[&str], fields: &[(&str, Json)]| {
+        let mut members = vec![
+            ("index", u16_schema()),
+            ("tag", type_schema("integer")),
+            (
+                "kind",
+                Json::object([("enum", Json::array(kinds.iter().copied()))]),
+            ),
+        ];
+        members.extend(fields.iter().cloned());
+        struct_schema(&members)
+    };
+    let cp_ref = || reference("CpRef");
+    let float = || {
+        vec![
+            ("bits", hex_schema()),
+            ("value", nullable(type_schema("number"))),
+        ]
+    };
+    Json::object([(
+        "oneOf",
+        Json::array([
+            variant(&["Class", "Module", "Package"], &[("name", cp_ref())]),
+            variant(
+                &["Fieldref", "Methodref", "InterfaceMethodref"],
+                &[("class", cp_ref()), ("name_and_type", cp_ref())],
+            ),
+            variant(&["String"], &[("string", cp_ref())]),
+            variant(&["Integer"], &[("value", type_schema("integer"))]),
+            variant(&["Float", "Double"], &float()),
+            variant(
+                &["Long"],
+                &[(
+                    "value",
+                    Json::object([("type", "string".into()), ("pattern", "^-?[0-9]+$".into())]),
+                )],
+            ),
+            variant(
+                &["NameAndType"],
+                &[("name", cp_ref()), ("descriptor", cp_ref())],
+            ),
+            variant(&["Utf8"], &[("value", type_schema("string"))]),
+            variant(
+                &["MethodHandle"],
+                &[
+                    ("reference_kind", type_schema("integer")),
+                    ("reference", cp_ref()),
+                ],
+            ),
+            variant(&["MethodType"], &[("descriptor", cp_ref())]),
+            variant(
+                &["Dynamic", "InvokeDynamic"],
+                &[
+                    ("bootstrap_method_attr_index", u16_schema()),
+                    ("name_and_type", cp_ref()),
+                ],
+            ),
+            variant(&["?"], &[("body", hex_schema())]),
+        ]),
+    )])
+}
+
+fn member(
+    pool: &[ConstantPool],
+    access_flags: u16,
+    name_index: u16,
+    descriptor_index: u16,
+    member_attributes: &[Attribute],
+) -> Json {
+    Json::object([
+        ("access_flags", access_flags.into()),
+        ("name", cp_ref(pool, name_index)),
+        ("descriptor", cp_ref(pool, descriptor_index)),
+        ("attributes", attributes(pool, member_attributes)),
+    ])
+}
+
+fn member_schema() -> Json {
+    struct_schema(&[
+        ("access_flags", u16_schema()),
+        ("name", reference("CpRef")),
+        ("descriptor", reference("CpRef")),
+        ("attributes", array_of(reference("Attribute"))),
+    ])
+}
+
+fn attributes(pool: &[ConstantPool], attributes: &[Attribute]) -> Json {
+    Json::array(attributes.iter().map(|a| attribute(pool, a)))
+}
+
+/// Attributes with no dedicated shape in this schema version carry only
+/// their name. Custom attributes also carry a `value`, which is whatever their
+/// serialize hook returns, or `null` without one.
+fn attribute(pool: &[ConstantPool], attribute: &Attribute) -> Json {
+    let cp_refs = |indices: &[u16]| Json::array(indices.iter().map(|&i| cp_ref(pool, i)));
+    let mut json = Json::object([("name", attribute.name().into())]);
+    match attribute {
+        Attribute::ConstantValue(index) => json.push("value", cp_ref(pool, *index)),
+        Attribute::Code(code) => push_code(&mut json, pool, code),
+        Attribute::Exceptions(exceptions) => {
+            json.push("exceptions", cp_refs(&exceptions.exception_index_table))
+        }
+        Attribute::InnerClasses(inner_classes) => json.push(
+            "classes",
+            Json::array(inner_classes.classes.iter().map(|class| {
+                Json::object([
+                    ("inner_class", cp_ref(pool, class.inner_class_info_index)),
+                    (
+                        "outer_class",
+                        optional_cp_ref(pool, class.outer_class_info_index),
+                    ),
+                    ("inner_name", optional_cp_ref(pool, class.inner_name_index)),
+                    ("access_flags", class.inner_class_access_flags.bits().into()),
+                ])
+            })),
+        ),
+        Attribute::EnclosingMethod(enclosing_method) => {
+            json.push("class", cp_ref(pool, enclosing_method.class_index));
+            json.push(
+                "method",
+                optional_cp_ref(pool, enclosing_method.method_index),
+            );
+        }
+        Attribute::Synthetic(data) => json.push("data", hex(data)),
+        Attribute::Signature(signature) => {
+            json.push("signature", cp_ref(pool, signature.signature_index))
+        }
+        Attribute::SourceFile(source_file) => {
+            json.push("source_file", cp_ref(pool, source_file.sourcefile_index))
+        }
+        Attribute::SourceDebugExtension(value) => json.push("value", mutf8::decode_lossy(value)),
+        Attribute::LineNumberTable(table) => json.push(
+            "entries",
+            Json::array(table.iter().map(|entry| {
+                Json::object([
+                    ("start_pc", entry.start_pc.into()),
+                    ("line_number", entry.line_number.into()),
+                ])
+            })),
+        ),
+        Attribute::LocalVariableTable(table) => json.push(
+            "entries",
+            Json::array(table.iter().map(|entry| {
+                Json::object([
+                    ("start_pc", entry.start_pc.into()),
+                    ("length", entry.length.into()),
+                    ("name", cp_ref(pool, entry.name_index)),
+                    ("descriptor", cp_ref(pool, entry.descriptor_index)),
+                    ("index", entry.index.into()),
+                ])
+            })),
+        ),
+        Attribute::LocalVariableTypeTable(table) => json.push(
+            "entries",
+            Json::array(table.iter().map(|entry| {
+                Json::object([
+                    ("start_pc", entry.start_pc.into()),
+                    ("length", entry.length.into()),
+                    ("name", cp_ref(pool, entry.name_index)),
+                    ("signature", cp_ref(pool, entry.signature_index)),
+                    ("index", entry.index.into()),
+                ])
+            })),
+        ),
+        Attribute::BootstrapMethods(methods) => json.push(
+            "methods",
+            Json::array(methods.iter().map(|method| {
+                Json::object([
+                    ("method_ref", cp_ref(pool, method.bootstrap_method_ref)),
+                    ("arguments", cp_refs(&method.bootstrap_arguments)),
+                ])
+            })),
+        ),
+        Attribute::MethodParameters(parameters) => json.push(
+            "parameters",
+            Json::array(parameters.iter().map(|parameter| {
+                Json::object([
+                    ("name", optional_cp_ref(pool, parameter.name_index)),
+                    ("access_flags", parameter.access_flags.bits().into()),
+                ])
+            })),
+        ),
+        Attribute::ModulePackages(packages) => json.push("packages", cp_refs(packages)),
+        Attribute::ModuleMainClass(index) => json.push("main_class", cp_ref(pool, *index)),
+        Attribute::NestHost(index) => json.push("host_class", cp_ref(pool, *index)),
+        Attribute::NestMembers(classes) | Attribute::PermittedSubclasses(classes) => {
+            json.push("classes", cp_refs(classes))
+        }
+        #[cfg(feature = "std")]
+        Attribute::Custom(custom) => json.push("value", custom.to_json().unwrap_or(Json::Null)),
+        Attribute::Unknown { info, .. } => json.push("data", hex(info)),
+        _ => {}
+    }
+    json
+}
+
+fn push_code(json: &mut Json, pool: &[ConstantPool], code: &Code) {
+    json.push("max_stack", code.max_stack);
+    json.push("max_locals", code.max_locals);
+    json.push("code", hex(&code.code));
+    json.push(
+        "exception_table",
+        Json::array(code.exception_table.iter().map(|exception| {
+            Json::object([
+                ("start_pc", exception.start_pc.into()),
+                ("end_pc", exception.end_pc.into()),
+                ("handler_pc", exception.handler_pc.into()),
+                ("catch_type", optional_cp_ref(pool, exception.catch_type)),
+            ])
+        })),
+    );
+    json.push("attributes", attributes(pool, &code.attributes));
+}
+
+/// Names of attributes sharing a shape, and the members it adds to `name`.
+type AttributeShape = (Vec<&'static str>, Vec<(&'static str, Json)>);
+
+/// The attributes with a dedicated shape in this schema version.
+fn attribute_shapes() -> Vec<AttributeShape> {
+    let cp_ref = || reference("CpRef");
+    let optional_cp_ref = || nullable(reference("CpRef"));
+    let cp_refs = || array_of(reference("CpRef"));
+    let entries = |fields: &[(&str, Json)]| array_of(struct_schema(fields));
+    vec![
+        (vec!["ConstantValue"], vec![("value", cp_ref())]),
+        (
+            vec!["Code"],
+            vec![
+                ("max_stack", u16_schema()),
+                ("max_locals", u16_schema()),
+                ("code", hex_schema()),
+                (
+                    "exception_table",
+                    entries(&[
+                        ("start_pc", u16_schema()),
+                        ("end_pc", u16_schema()),
+                        ("handler_pc", u16_schema()),
+                        ("catch_type", optional_cp_ref()),
+                    ]),
+                ),
+                ("attributes", array_of(reference("Attribute"))),
+            ],
+        ),
+        (vec!["Exceptions"], vec![("exceptions", cp_refs())]),
+        (
+            vec!["InnerClasses"],
+            vec![(
+                "classes",
+                entries(&[
+                    ("inner_class", cp_ref()),
+                    ("outer_class", optional_cp_ref()),
+                    ("inner_name", optional_cp_ref()),
+                    ("access_flags", u16_schema()),
+                ]),
+            )],
+        ),
+        (
+            vec!["EnclosingMethod"],
+            vec![("class", cp_ref()), ("method", optional_cp_ref())],
+        ),
+        (vec!["Synthetic"], vec![("data", hex_schema())]),
+        (vec!["Signature"], vec![("signature", cp_ref())]),
+        (vec!["SourceFile"], vec![("source_file", cp_ref())]),
+        (
+            vec!["SourceDebugExtension"],
+            vec![("value", type_schema("string"))],
+        ),
+        (
+            vec!["LineNumberTable"],
+            vec![(
+                "entries",
+                entries(&[("start_pc", u16_schema()), ("line_number", u16_schema())]),
+            )],
+        ),
+        (
+            vec!["LocalVariableTable"],
+            vec![(
+                "entries",
+                entries(&[
+                    ("start_pc", u16_schema()),
+                    ("length", u16_schema()),
+                    ("name", cp_ref()),
+                    ("descriptor", cp_ref()),
+                    ("index", u16_schema()),
+                ]),
+            )],
+        ),
+        (
+            vec!["LocalVariableTypeTable"],
+            vec![(
+                "entries",
+                entries(&[
+                    ("start_pc", u16_schema()),
+                    ("length", u16_schema()),
+                    ("name", cp_ref()),
+                    ("signature", cp_ref()),
+                    ("index", u16_schema()),
+                ]),
+            )],
+        ),
+        (
+            vec!["BootstrapMethods"],
+            vec![(
+                "methods",
+                entries(&[("method_ref", cp_ref()), ("arguments", cp_refs())]),
+            )],
+        ),
+        (
+            vec!["MethodParameters"],
+            vec![(
+                "parameters",
+                entries(&[("name", optional_cp_ref()), ("access_flags", u16_schema())]),
+            )],
+        ),
+        (vec!["ModulePackages"], vec![("packages", cp_refs())]),
+        (vec!["ModuleMainClass"], vec![("main_class", cp_ref())]),
+        (vec!["NestHost"], vec![("host_class", cp_ref())]),
+        (
+            vec!["NestMembers", "PermittedSubclasses"],
+            vec![("classes", cp_refs())],
+        ),
+    ]
+}
+
+fn attribute_schema() -> Json {
+    let shapes = attribute_shapes();
+    let shaped_names: Vec<&str> = shapes
+        .iter()
+        .flat_map(|(names, _)| names.iter().copied())
+        .collect();
+    let mut variants: Vec<Json> = shapes
+        .into_iter()
+        .map(|(names, fields)| {
+            let mut members = vec![("name", Json::object([("enum", Json::array(names))]))];
+            members.extend(fields);
+            struct_schema(&members)
+        })
+        .collect();
+    let unshaped_name = Json::object([
+        ("type", "string".into()),
+        ("not", Json::object([("enum", Json::array(shaped_names))])),
+    ]);
+    variants.push(struct_schema(&[("name", unshaped_name.clone())]));
+    // custom attributes, whose value may be anything
+    variants.push(struct_schema(&[
+        ("name", unshaped_name.clone()),
+        ("value", Json::Object(Vec::new())),
+    ]));
+    // unknown attributes, kept as stored
+    variants.push(struct_schema(&[
+        ("name", unshaped_name),
+        ("data", hex_schema()),
+    ]));
+    Json::object([("oneOf", Json::Array(variants))])
+}
+
+/// An object schema requiring exactly the given members.
+fn struct_schema(members: &[(&str, Json)]) -> Json {
+    Json::object([
+        ("type", "object".into()),
+        ("properties", Json::object(members.iter().cloned())),
+        (
+            "required",
+            Json::array(members.iter().map(|(name, _)| *name)),
+        ),
+        ("additionalProperties", false.into()),
+    ])
+}
+
+fn type_schema(name: &str) -> Json {
+    Json::object([("type", name.into())])
+}
+
+fn u16_schema() -> Json {
+    Json::object([
+        ("type", "integer".into()),
+        ("minimum", 0.into()),
+        ("maximum", u16::MAX.into()),
+    ])
+}
+
+fn hex_schema() -> Json {
+    Json::object([
+        ("type", "string".into()),
+        ("pattern", "^([0-9a-f]{2})*$".into()),
+    ])
+}
+
+fn nullable(schema: Json) -> Json {
+    Json::object([("anyOf", Json::array([schema, type_schema("null")]))])
+}
+
+fn array_of(items: Json) -> Json {
+    Json::object([("type", "array".into()), ("items", items)])
+}
+
+fn reference(name: &str) -> Json {
+    Json::object([("$ref", format!("#/$defs/{}", name).into())])
+}
@@ -1,13 +1,22 @@
-use nom::{multi::count, number::complete::be_u16, IResult};
+#[cfg(feature = "write")]
+use std::io;
 
+use crate::prelude::*;
+#[cfg(feature = "write")]
+use crate::write::WriteBe;
 use crate::{
-    attribute::{Attribute, AttributeInfo},
+    accessflags::FieldAccessFlags,
+    attribute::{Attribute, ResolvedAnnotation, Signature},
+    classfile::{ParseContext, SyntheticForm},
     constantpool::ConstantPool,
+    descriptor::{DescriptorError, FieldType},
+    error::{self, be_u16, IResult},
 };
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FieldInfo {
-    access_flags: u16,
+    access_flags: FieldAccessFlags,
     name_index: u16,
     descriptor_index: u16,
     attributes_count: u16,
@@ -15,34 +24,55 @@ pub struct FieldInfo {
 }
 
 impl FieldInfo {
-    pub fn parse<'a>(
+    /// Puts together a member whose attributes were parsed elsewhere.
+    pub(crate) fn from_parts(
+        access_flags: u16,
+        name_index: u16,
+        descriptor_index: u16,
+        attributes: Vec<Attribute>,
+    ) -> Self {
+        FieldInfo {
+            access_flags: FieldAccessFlags::new(access_flags),
+            name_index,
+            descriptor_index,
+            attributes_count: attributes.len() as u16,
+            attributes,
+        }
+    }
+
+    pub(crate) fn parse<'a>(
         mut buf: &'a [u8],
         fields_count: u16,
         constant_pool: &[ConstantPool],
+        context: &ParseContext,
     ) -> IResult<&'a [u8], Vec<FieldInfo>> {
-        let mut fields = Vec::with_capacity(fields_count as usize);
-        for _ in 0..fields_count {
-            let (temp_buf, constant) = Self::parse_field_info(buf, constant_pool)?;
+        let mut fields = Vec::with_capacity(error::capacity(buf, fields_count as usize, 8)?);
+        for i in 0..fields_count {
+            let (temp_buf, constant) =
+                error::within(Self::parse_field_info(buf, constant_pool, context), || {
+                    error::member_context("field", i, buf, constant_pool)
+                })?;
             buf = temp_buf;
             fields.push(constant);
         }
 
         Ok((buf, fields))
     }
-    fn parse_field_info<'a>(
+    pub(crate) fn parse_field_info<'a>(
         buf: &'a [u8],
         constant_pool: &[ConstantPool],
+        context: &ParseContext,
     ) -> IResult<&'a [u8], FieldInfo> {
         let (buf, access_flags) = be_u16(buf)?;
         let (buf, name_index) = be_u16(buf)?;
         let (buf, descriptor_index) = be_u16(buf)?;
         let (buf, attributes_count) = be_u16(buf)?;
-        let (buf, attributes) = count(AttributeInfo::parse, attributes_count as usize)(buf)?;
-        let attributes = Attribute::from_attribute_info(attributes, constant_pool);
+        let (buf, attributes) =
+            Attribute::parse_table(buf, attributes_count, constant_pool, context)?;
         Ok((
             buf,
             FieldInfo {
-                access_flags,
+                access_flags: FieldAccessFlags::new(access_flags),
                 name_index,
                 descriptor_index,
                 attributes_count,
@@ -51,3 +81,108 @@ impl FieldInfo {
         ))
     }
 }
+
+impl FieldInfo {
+    /// Whether the field is compiler-generated, marked either by the
+    /// `ACC_SYNTHETIC` flag or by a `Synthetic` attribute.
+    pub fn is_synthetic(&self) -> bool {
+        self.access_flags.is_synthetic() || Attribute::has_synthetic(&self.attributes)
+    }
+
+    pub fn access_flags(&self) -> FieldAccessFlags {
+        self.access_flags
+    }
+
+    pub fn name_index(&self) -> u16 {
+        self.name_index
+    }
+
+    pub fn descriptor_index(&self) -> u16 {
+        self.descriptor_index
+    }
+
+    /// The field's name, looked up in the class's constant pool. `None` if
+    /// `name_index` isn't a UTF8 entry.
+    pub fn name<'a>(&self, constant_pool: &'a [ConstantPool]) -> Option<&'a str> {
+        ConstantPool::utf8(constant_pool, self.name_index)
+    }
+
+    /// The field's descriptor, looked up in the class's constant pool.
+    pub fn descriptor<'a>(&self, constant_pool: &'a [ConstantPool]) -> Option<&'a str> {
+        ConstantPool::utf8(constant_pool, self.descriptor_index)
+    }
+
+    /// The field's type, parsed from its descriptor. `None` if
+    /// `descriptor_index` isn't a UTF8 entry.
+    pub fn parsed_descriptor(
+        &self,
+        constant_pool: &[ConstantPool],
+    ) -> Option<Result<FieldType, DescriptorError>> {
+        self.descriptor(constant_pool).map(FieldType::parse)
+    }
+
+    pub fn attributes(&self) -> &[Attribute] {
+        &self.attributes
+    }
+
+    /// The constant pool index of the field's initial value, as its
+    /// `ConstantValue` attribute gives it.
+    pub fn constant_value_index(&self) -> Option<u16> {
+        self.attributes
+            .iter()
+            .find_map(|attribute| match attribute {
+                Attribute::ConstantValue(index) => Some(*index),
+                _ => None,
+            })
+    }
+
+    /// The field's `Signature` attribute, its generic type.
+    pub fn signature(&self) -> Option<&Signature> {
+        Attribute::signature(&self.attributes)
+    }
+
+    /// The field's visible and then invisible annotations, with their
+    /// element values looked up.
+    pub fn annotations<'a>(
+        &'a self,
+        constant_pool: &'a [ConstantPool],
+    ) -> Vec<ResolvedAnnotation<'a>> {
+        Attribute::annotations(&self.attributes, constant_pool)
+    }
+
+    /// The attribute table. Attribute counts are recomputed from this when
+    /// the class is written back out.
+    pub fn attributes_mut(&mut self) -> &mut Vec<Attribute> {
+        &mut self.attributes
+    }
+
+    /// Renames the field to the UTF8 entry at `index`.
+    pub fn set_name_index(&mut self, index: u16) {
+        self.name_index = index;
+    }
+
+    /// Gives the field the descriptor held by the UTF8 entry at `index`.
+    pub fn set_descriptor_index(&mut self, index: u16) {
+        self.descriptor_index = index;
+    }
+
+    #[cfg(feature = "write")]
+    pub fn write(&self, out: &mut Vec<u8>, constant_pool: &[ConstantPool]) -> io::Result<()> {
+        out.put_u16(self.access_flags.bits());
+        out.put_u16(self.name_index);
+        out.put_u16(self.descriptor_index);
+        Attribute::write_all(&self.attributes, out, constant_pool)
+    }
+
+    pub(crate) fn set_synthetic_form(&mut self, form: SyntheticForm) {
+        form.apply(&mut self.access_flags, &mut self.attributes);
+        self.attributes_count = self.attributes.len() as u16;
+    }
+
+    #[cfg(feature = "write")]
+    pub(crate) fn remove_attributes(&mut self, names: &[&str]) -> usize {
+        let removed = Attribute::remove_named(&mut self.attributes, names);
+        self.attributes_count = self.attributes.len() as u16;
+        removed
+    }
+}
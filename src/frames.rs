@@ -0,0 +1,749 @@
+//! The types of the locals and operand stack before each instruction of a
+//! method, found by simulating the stack effect of every instruction
+//! forward from the frames the `StackMapTable` declares.
+//!
+//! Nothing is merged: wherever control flow joins, the declared frame is
+//! taken as is, and where there's no declared frame to start from (class
+//! files older than version 50, code after `jsr`) the types are left
+//! unknown until the next one.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
+
+use crate::{
+    attribute::{Code, StackMapFrame, VerificationTypeInfo},
+    budget::{AnalysisBudget, Meter, Partial},
+    cfg::ControlFlowGraph,
+    classfile::ClassFile,
+    constantpool::ConstantPool,
+    descriptor::MethodDescriptor,
+    instruction::{self, DecodeError, Instruction, WIDE},
+    methodinfo::MethodInfo,
+};
+
+/// The type of a local or stack slot. A long or double takes two slots, the
+/// second of which is `Top`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FrameType {
+    Top,
+    Int,
+    Float,
+    Long,
+    Double,
+    Null,
+    UninitializedThis,
+    /// An object created by the `new` instruction at this offset whose
+    /// constructor hasn't been called yet.
+    Uninitialized(u32),
+    /// A class's internal name or an array type's descriptor, as held by a
+    /// `CONSTANT_Class` entry.
+    Reference(String),
+}
+
+impl FrameType {
+    /// The type of a value of the field type `descriptor`.
+    fn from_descriptor(descriptor: &str) -> Option<Self> {
+        Some(match descriptor.as_bytes().first()? {
+            b'B' | b'C' | b'I' | b'S' | b'Z' => FrameType::Int,
+            b'F' => FrameType::Float,
+            b'J' => FrameType::Long,
+            b'D' => FrameType::Double,
+            b'L' => FrameType::Reference(descriptor[1..].strip_suffix(';')?.to_string()),
+            b'[' => FrameType::Reference(descriptor.to_string()),
+            _ => return None,
+        })
+    }
+
+    fn from_verification_type(
+        info: &VerificationTypeInfo,
+        constant_pool: &[ConstantPool],
+    ) -> Option<Self> {
+        Some(match info {
+            VerificationTypeInfo::TopVariableInfo => FrameType::Top,
+            VerificationTypeInfo::IntegerVariableInfo => FrameType::Int,
+            VerificationTypeInfo::FloatVariableInfo => FrameType::Float,
+            VerificationTypeInfo::LongVariableInfo => FrameType::Long,
+            VerificationTypeInfo::DoubleVariableInfo => FrameType::Double,
+            VerificationTypeInfo::NullVariableInfo => FrameType::Null,
+            VerificationTypeInfo::UninitializedThisVariableInfo => FrameType::UninitializedThis,
+            VerificationTypeInfo::UninitializedVariableInfo(offset) => {
+                FrameType::Uninitialized(*offset as u32)
+            }
+            VerificationTypeInfo::ObjectVariableInfo(index) => {
+                FrameType::Reference(ConstantPool::class_name(constant_pool, *index)?.to_string())
+            }
+        })
+    }
+
+    fn is_wide(&self) -> bool {
+        matches!(self, FrameType::Long | FrameType::Double)
+    }
+}
+
+/// The types before an instruction, both in slots.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    pub locals: Vec<FrameType>,
+    pub stack: Vec<FrameType>,
+}
+
+impl Frame {
+    /// Lays out types given the way frames list them, one entry for a long
+    /// or double, in slots.
+    fn slots(types: &[FrameType]) -> Vec<FrameType> {
+        let mut slots = Vec::with_capacity(types.len());
+        for frame_type in types {
+            slots.push(frame_type.clone());
+            if frame_type.is_wide() {
+                slots.push(FrameType::Top);
+            }
+        }
+        slots
+    }
+
+    fn push(&mut self, frame_type: FrameType) {
+        let wide = frame_type.is_wide();
+        self.stack.push(frame_type);
+        if wide {
+            self.stack.push(FrameType::Top);
+        }
+    }
+
+    fn pop(&mut self, slots: usize) -> Option<()> {
+        let len = self.stack.len().checked_sub(slots)?;
+        self.stack.truncate(len);
+        Some(())
+    }
+
+    /// Pops a value of one slot, or of two for a long or double.
+    fn pop_value(&mut self) -> Option<FrameType> {
+        let top = self.stack.pop()?;
+        match self.stack.last() {
+            Some(below) if top == FrameType::Top && below.is_wide() => self.stack.pop(),
+            _ => Some(top),
+        }
+    }
+
+    fn load(&mut self, index: usize) -> Option<()> {
+        let local = self.locals.get(index)?.clone();
+        self.push(local);
+        Some(())
+    }
+
+    fn store(&mut self, index: usize) -> Option<()> {
+        let value = self.pop_value()?;
+        let wide = value.is_wide();
+        let end = index + if wide { 2 } else { 1 };
+        if end > self.locals.len() {
+            return None;
+        }
+        // overwriting the second half of a long or double ruins the first
+        if index > 0 && self.locals[index - 1].is_wide() {
+            self.locals[index - 1] = FrameType::Top;
+        }
+        self.locals[index] = value;
+        if wide {
+            self.locals[index + 1] = FrameType::Top;
+        }
+        Some(())
+    }
+
+    /// Pops the arguments of the method with `descriptor`, and the object it's
+    /// invoked on if it `has_receiver`, then pushes its result. Returns the
+    /// receiver.
+    fn invoke(&mut self, descriptor: &str, has_receiver: bool) -> Option<Option<FrameType>> {
+        let parsed = MethodDescriptor::parse(descriptor).ok()?;
+        for _ in &parsed.parameters {
+            self.pop_value()?;
+        }
+        let receiver = if has_receiver {
+            Some(self.stack.pop()?)
+        } else {
+            None
+        };
+        let returns = &descriptor[descriptor.rfind(')')? + 1..];
+        if returns != "V" {
+            self.push(FrameType::from_descriptor(returns)?);
+        }
+        Some(receiver)
+    }
+
+    /// Replaces every copy of the uninitialized `object` with `initialized`
+    /// once its constructor has been called.
+    fn initialize(&mut self, object: &FrameType, initialized: FrameType) {
+        for slot in self.locals.iter_mut().chain(self.stack.iter_mut()) {
+            if slot == object {
+                *slot = initialized.clone();
+            }
+        }
+    }
+}
+
+/// Infers the frame before every instruction of `method` whose types are
+/// known. Methods without code have no frames.
+pub fn infer_frames(
+    class_file: &ClassFile,
+    method: &MethodInfo,
+) -> Result<BTreeMap<u32, Frame>, DecodeError> {
+    infer_frames_within(class_file, method, &AnalysisBudget::unlimited()).map(Partial::into_value)
+}
+
+/// [`infer_frames`] unless it takes more than `budget` allows, every frame
+/// counting as a state. Frames cut short are those before the instructions
+/// reached by then.
+pub fn infer_frames_within(
+    class_file: &ClassFile,
+    method: &MethodInfo,
+    budget: &AnalysisBudget,
+) -> Result<Partial<BTreeMap<u32, Frame>>, DecodeError> {
+    infer_frames_metered(class_file, method, &mut budget.meter())
+}
+
+fn infer_frames_metered(
+    class_file: &ClassFile,
+    method: &MethodInfo,
+    meter: &mut Meter,
+) -> Result<Partial<BTreeMap<u32, Frame>>, DecodeError> {
+    let code = match method.code() {
+        Some(code) => code,
+        None => return Ok(Partial::complete(BTreeMap::new())),
+    };
+    let instructions = match meter.decode(&code.code)? {
+        Ok(instructions) => instructions,
+        Err(exceeded) => return Ok(Partial::cut_short(BTreeMap::new(), exceeded)),
+    };
+    let declared = declared_frames(class_file, method, code.max_locals as usize);
+
+    // where control flow joins, only a declared frame can be trusted
+    let joins = join_points(code, &instructions);
+
+    let mut frames = BTreeMap::new();
+    let mut current = declared.get(&0).cloned();
+    for instruction in &instructions {
+        if let Some(frame) = declared.get(&instruction.offset) {
+            current = Some(frame.clone());
+        } else if joins.contains(&instruction.offset) {
+            current = None;
+        }
+        let mut frame = match current.take() {
+            Some(frame) => frame,
+            None => continue,
+        };
+        if let Err(exceeded) = meter.states(1) {
+            return Ok(Partial::cut_short(frames, exceeded));
+        }
+        frames.insert(instruction.offset, frame.clone());
+        if step(&mut frame, instruction, class_file, &code.code).is_some()
+            && falls_through(instruction)
+        {
+            current = Some(frame);
+        }
+    }
+    Ok(Partial::complete(frames))
+}
+
+/// The operand stack after an instruction, found by [`infer_stacks`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StackAfter {
+    /// The stack in slots, bottom first. `merged` when the instruction is
+    /// one where control flow joins, so that the types before it are those
+    /// of the frame declared there, which merges every path to it.
+    Known { stack: Vec<FrameType>, merged: bool },
+    /// The instruction can be reached but its types can't be told, as in
+    /// class files without a `StackMapTable`.
+    Unknown,
+    /// Neither the method entry nor any exception handler leads to the
+    /// instruction.
+    Unreachable,
+}
+
+/// How many values of a stack [`StackAfter`] displays, nearest the top.
+const MAX_SHOWN_VALUES: usize = 8;
+
+/// Displays the stack compactly, top last, as
+/// `[Ljava/io/PrintStream;, Ljava/lang/String;]`: primitives by their
+/// descriptor letter, long and double marked `(2)` for the two slots they
+/// take, and values below the 8 nearest the top counted rather than listed.
+impl fmt::Display for StackAfter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (stack, merged) = match self {
+            StackAfter::Known { stack, merged } => (stack, *merged),
+            StackAfter::Unknown => return f.write_str("unknown"),
+            StackAfter::Unreachable => return f.write_str("unreachable"),
+        };
+        let mut values = Vec::new();
+        for (i, slot) in stack.iter().enumerate() {
+            let second_half = *slot == FrameType::Top && i > 0 && stack[i - 1].is_wide();
+            if !second_half {
+                values.push(slot);
+            }
+        }
+        f.write_str("[")?;
+        let hidden = values.len().saturating_sub(MAX_SHOWN_VALUES);
+        if hidden > 0 {
+            write!(f, "… {} more", hidden)?;
+        }
+        for (i, value) in values[hidden..].iter().enumerate() {
+            if i > 0 || hidden > 0 {
+                f.write_str(", ")?;
+            }
+            match value {
+                FrameType::Top => f.write_str("top")?,
+                FrameType::Int => f.write_str("I")?,
+                FrameType::Float => f.write_str("F")?,
+                FrameType::Long => f.write_str("J(2)")?,
+                FrameType::Double => f.write_str("D(2)")?,
+                FrameType::Null => f.write_str("null")?,
+                FrameType::UninitializedThis => f.write_str("uninitialized this")?,
+                FrameType::Uninitialized(offset) => write!(f, "uninitialized new@{}", offset)?,
+                FrameType::Reference(name) if name.starts_with('[') => f.write_str(name)?,
+                FrameType::Reference(name) => write!(f, "L{};", name)?,
+            }
+        }
+        f.write_str("]")?;
+        if merged {
+            f.write_str(" (merged frame)")?;
+        }
+        Ok(())
+    }
+}
+
+/// Infers the operand stack after every instruction of `method`, by
+/// applying the instruction to the frame [`infer_frames`] finds before it.
+/// Methods without code have no stacks.
+pub fn infer_stacks(
+    class_file: &ClassFile,
+    method: &MethodInfo,
+) -> Result<BTreeMap<u32, StackAfter>, DecodeError> {
+    infer_stacks_within(class_file, method, &AnalysisBudget::unlimited()).map(Partial::into_value)
+}
+
+/// [`infer_stacks`] unless it takes more than `budget` allows, the frames
+/// and control flow graph it needs included, and every stack counting as a
+/// state. Stacks cut short are those after the instructions reached by
+/// then, and none when the frames or graph were cut short.
+pub fn infer_stacks_within(
+    class_file: &ClassFile,
+    method: &MethodInfo,
+    budget: &AnalysisBudget,
+) -> Result<Partial<BTreeMap<u32, StackAfter>>, DecodeError> {
+    let meter = &mut budget.meter();
+    let code = match method.code() {
+        Some(code) => code,
+        None => return Ok(Partial::complete(BTreeMap::new())),
+    };
+    let frames = infer_frames_metered(class_file, method, meter)?;
+    if let Some(exceeded) = frames.exceeded {
+        return Ok(Partial::cut_short(BTreeMap::new(), exceeded));
+    }
+    let frames = frames.value;
+    let instructions = match meter.decode(&code.code)? {
+        Ok(instructions) => instructions,
+        Err(exceeded) => return Ok(Partial::cut_short(BTreeMap::new(), exceeded)),
+    };
+    let joins = join_points(code, &instructions);
+    let cfg = ControlFlowGraph::build_metered(code, meter)?;
+    if let Some(exceeded) = cfg.exceeded {
+        return Ok(Partial::cut_short(BTreeMap::new(), exceeded));
+    }
+    let cfg = cfg.value;
+
+    // blocks reachable from the entry, or from a handler covering one
+    let mut reachable = BTreeSet::new();
+    let mut roots = vec![0];
+    while let Some(root) = roots.pop() {
+        for block in cfg.reachable(root) {
+            if reachable.insert(block) {
+                roots.extend(&cfg.blocks[block].handlers);
+            }
+        }
+    }
+
+    let mut stacks = BTreeMap::new();
+    for instruction in &instructions {
+        if let Err(exceeded) = meter.states(1) {
+            return Ok(Partial::cut_short(stacks, exceeded));
+        }
+        let offset = instruction.offset;
+        let after = match frames.get(&offset) {
+            Some(frame) => {
+                let mut frame = frame.clone();
+                match step(&mut frame, instruction, class_file, &code.code) {
+                    Some(()) => StackAfter::Known {
+                        stack: frame.stack,
+                        merged: joins.contains(&offset),
+                    },
+                    None => StackAfter::Unknown,
+                }
+            }
+            None => {
+                let block = cfg.blocks.partition_point(|block| block.start <= offset);
+                if block > 0 && reachable.contains(&(block - 1)) {
+                    StackAfter::Unknown
+                } else {
+                    StackAfter::Unreachable
+                }
+            }
+        };
+        stacks.insert(offset, after);
+    }
+    Ok(Partial::complete(stacks))
+}
+
+/// The offsets where control flow joins: branch targets and exception
+/// handlers. Targets before the start of the code are left out, as no
+/// instruction is there to join at.
+fn join_points(code: &Code, instructions: &[Instruction]) -> BTreeSet<u32> {
+    let mut joins: BTreeSet<u32> = instructions
+        .iter()
+        .flat_map(|instruction| instruction.branch_targets())
+        .filter_map(|target| u32::try_from(target).ok())
+        .collect();
+    joins.extend(
+        code.exception_table
+            .iter()
+            .map(|exception| exception.handler_pc as u32),
+    );
+    joins
+}
+
+/// The frames the `StackMapTable` declares by offset, with the implicit
+/// frame at offset 0 derived from the method's descriptor.
+fn declared_frames(
+    class_file: &ClassFile,
+    method: &MethodInfo,
+    max_locals: usize,
+) -> BTreeMap<u32, Frame> {
+    let constant_pool = &class_file.constant_pool;
+    let mut frames = BTreeMap::new();
+    let mut locals = match initial_locals(class_file, method) {
+        Some(locals) => locals,
+        None => return frames,
+    };
+    let frame = |locals: &[FrameType], stack: &[FrameType]| {
+        let mut slots = Frame::slots(locals);
+        slots.resize(max_locals.max(slots.len()), FrameType::Top);
+        Frame {
+            locals: slots,
+            stack: Frame::slots(stack),
+        }
+    };
+    frames.insert(0, frame(&locals, &[]));
+
+    let table = match method.code().and_then(|code| code.stack_map_table()) {
+        Some(table) => table,
+        None => return frames,
+    };
+    let resolve = |types: &[VerificationTypeInfo]| {
+        types
+            .iter()
+            .map(|info| FrameType::from_verification_type(info, constant_pool))
+            .collect::<Option<Vec<_>>>()
+    };
+    let mut offset: Option<u32> = None;
+    for entry in &table.entries {
+        let next = match offset {
+            None => entry.offset_delta() as u32,
+            Some(previous) => previous + entry.offset_delta() as u32 + 1,
+        };
+        offset = Some(next);
+        let stack = match entry {
+            StackMapFrame::SameFrame(_) | StackMapFrame::SameFrameExtended(_) => Vec::new(),
+            StackMapFrame::SameLocals1StackItemFrame { stack: item, .. }
+            | StackMapFrame::SameLocals1StackItemFrameExtended { stack: item, .. } => {
+                match resolve(std::slice::from_ref(item)) {
+                    Some(stack) => stack,
+                    None => return frames,
+                }
+            }
+            StackMapFrame::ChopFrame { chopped, .. } => {
+                let len = locals.len().saturating_sub(*chopped as usize);
+                locals.truncate(len);
+                Vec::new()
+            }
+            StackMapFrame::AppendFrame {
+                locals: appended, ..
+            } => match resolve(appended) {
+                Some(appended) => {
+                    locals.extend(appended);
+                    Vec::new()
+                }
+                None => return frames,
+            },
+            StackMapFrame::FullFrame {
+                locals: full_locals,
+                stack,
+                ..
+            } => match (resolve(full_locals), resolve(stack)) {
+                (Some(full_locals), Some(stack)) => {
+                    locals = full_locals;
+                    stack
+                }
+                _ => return frames,
+            },
+        };
+        frames.insert(next, frame(&locals, &stack));
+    }
+    frames
+}
+
+/// The locals on entry to `method`, listed the way frames list them.
+fn initial_locals(class_file: &ClassFile, method: &MethodInfo) -> Option<Vec<FrameType>> {
+    let constant_pool = &class_file.constant_pool;
+    let name = ConstantPool::utf8(constant_pool, method.name_index())?;
+    let descriptor = ConstantPool::utf8(constant_pool, method.descriptor_index())?;
+    let this_class = ConstantPool::class_name(constant_pool, class_file.this_class)?;
+    let mut locals = Vec::new();
+    if !method.access_flags().is_static() {
+        locals.push(if name == "<init>" && this_class != "java/lang/Object" {
+            FrameType::UninitializedThis
+        } else {
+            FrameType::Reference(this_class.to_string())
+        });
+    }
+    let parsed = MethodDescriptor::parse(descriptor).ok()?;
+    let mut rest = &descriptor[1..];
+    for _ in &parsed.parameters {
+        let len = field_descriptor_len(rest)?;
+        locals.push(FrameType::from_descriptor(&rest[..len])?);
+        rest = &rest[len..];
+    }
+    Some(locals)
+}
+
+/// The length of the field descriptor at the start of `descriptor`.
+fn field_descriptor_len(descriptor: &str) -> Option<usize> {
+    let dimensions = descriptor.bytes().take_while(|&b| b == b'[').count();
+    match descriptor.as_bytes().get(dimensions)? {
+        b'L' => Some(dimensions + descriptor[dimensions..].find(';')? + 1),
+        _ => Some(dimensions + 1),
+    }
+}
+
+/// Whether execution can continue with the next instruction.
+fn falls_through(instruction: &Instruction) -> bool {
+    !matches!(instruction.opcode, 0xa7..=0xb1 | 0xbf | 0xc8 | 0xc9)
+}
+
+/// Applies the stack effect of `instruction` to `frame`. `None` when the
+/// frame doesn't fit the instruction or the result can't be told.
+fn step(
+    frame: &mut Frame,
+    instruction: &Instruction,
+    class_file: &ClassFile,
+    code: &[u8],
+) -> Option<()> {
+    use FrameType::*;
+
+    let constant_pool = &class_file.constant_pool;
+    let operands = instruction.operands;
+    let local_index = || operands.first().map(|&index| index as usize);
+    let class_at = |index: u16| ConstantPool::class_name(constant_pool, index);
+    match instruction.opcode {
+        0x00 => {}
+        0x01 => frame.push(Null),
+        0x02..=0x08 | 0x10 | 0x11 => frame.push(Int),
+        0x09 | 0x0a => frame.push(Long),
+        0x0b..=0x0d => frame.push(Float),
+        0x0e | 0x0f => frame.push(Double),
+        0x12..=0x14 => {
+            let index = instruction.cp_index()?;
+            let value = match constant_pool.get((index as usize).checked_sub(1)?)? {
+                ConstantPool::Integer(_) => Int,
+                ConstantPool::Float(_) => Float,
+                ConstantPool::Long(_) => Long,
+                ConstantPool::Double(_) => Double,
+                ConstantPool::String(_) => Reference("java/lang/String".to_string()),
+                ConstantPool::Class(_) => Reference("java/lang/Class".to_string()),
+                ConstantPool::MethodType(_) => Reference("java/lang/invoke/MethodType".to_string()),
+                ConstantPool::MethodHandle(..) => {
+                    Reference("java/lang/invoke/MethodHandle".to_string())
+                }
+                ConstantPool::Dynamic(..) => {
+                    FrameType::from_descriptor(class_file.dynamic_site(index)?.descriptor)?
+                }
+                _ => return None,
+            };
+            frame.push(value);
+        }
+        0x15..=0x19 => frame.load(local_index()?)?,
+        opcode @ 0x1a..=0x2d => frame.load(((opcode - 0x1a) % 4) as usize)?,
+        opcode @ 0x2e..=0x35 => {
+            frame.pop(1)?;
+            let array = frame.stack.pop()?;
+            let element = match opcode {
+                0x2e | 0x33..=0x35 => Int,
+                0x2f => Long,
+                0x30 => Float,
+                0x31 => Double,
+                _ => match array {
+                    Null => Null,
+                    Reference(array) => FrameType::from_descriptor(array.strip_prefix('[')?)?,
+                    _ => return None,
+                },
+            };
+            frame.push(element);
+        }
+        0x36..=0x3a => frame.store(local_index()?)?,
+        opcode @ 0x3b..=0x4e => frame.store(((opcode - 0x3b) % 4) as usize)?,
+        0x4f..=0x56 => {
+            frame.pop_value()?;
+            frame.pop(2)?;
+        }
+        0x57 => frame.pop(1)?,
+        0x58 => frame.pop(2)?,
+        0x59..=0x5f => shuffle(&mut frame.stack, instruction.opcode)?,
+        opcode @ 0x60..=0x77 => {
+            let value = [Int, Long, Float, Double][(opcode % 4) as usize].clone();
+            let binary = opcode < 0x74;
+            frame.pop_value()?;
+            if binary {
+                frame.pop_value()?;
+            }
+            frame.push(value);
+        }
+        opcode @ 0x78..=0x83 => {
+            frame.pop_value()?;
+            frame.pop_value()?;
+            frame.push(if opcode % 2 == 0 { Int } else { Long });
+        }
+        0x84 => {}
+        opcode @ 0x85..=0x93 => {
+            frame.pop_value()?;
+            frame.push(match opcode {
+                0x88 | 0x8b | 0x8e | 0x91..=0x93 => Int,
+                0x85 | 0x8c | 0x8f => Long,
+                0x86 | 0x89 | 0x90 => Float,
+                _ => Double,
+            });
+        }
+        0x94..=0x98 => {
+            frame.pop_value()?;
+            frame.pop_value()?;
+            frame.push(Int);
+        }
+        0x99..=0x9e | 0xc6 | 0xc7 | 0xaa | 0xab | 0xc2 | 0xc3 => frame.pop(1)?,
+        0x9f..=0xa6 => frame.pop(2)?,
+        0xa7 | 0xc8 | 0xb1 => {}
+        0xac..=0xb0 | 0xbf => {
+            frame.pop_value()?;
+        }
+        0xb2..=0xb5 => {
+            let field = ConstantPool::member_ref(constant_pool, instruction.cp_index()?)?;
+            let value = FrameType::from_descriptor(field.descriptor)?;
+            match instruction.opcode {
+                0xb2 => frame.push(value),
+                0xb3 => {
+                    frame.pop_value()?;
+                }
+                0xb4 => {
+                    frame.pop(1)?;
+                    frame.push(value);
+                }
+                _ => {
+                    frame.pop_value()?;
+                    frame.pop(1)?;
+                }
+            }
+        }
+        0xb6..=0xb9 => {
+            let method = ConstantPool::member_ref(constant_pool, instruction.cp_index()?)?;
+            let receiver = frame.invoke(method.descriptor, instruction.opcode != 0xb8)?;
+            if let (Some(receiver), "<init>") = (receiver, method.name) {
+                let initialized = match &receiver {
+                    UninitializedThis => Reference(
+                        ConstantPool::class_name(constant_pool, class_file.this_class)?.to_string(),
+                    ),
+                    Uninitialized(offset) => {
+                        let new = instruction::decode(code.get(*offset as usize..)?)
+                            .next()?
+                            .ok()?;
+                        Reference(class_at(new.cp_index()?)?.to_string())
+                    }
+                    _ => return Some(()),
+                };
+                frame.initialize(&receiver, initialized);
+            }
+        }
+        0xba => {
+            let site = class_file.dynamic_site(instruction.cp_index()?)?;
+            frame.invoke(site.descriptor, false)?;
+        }
+        0xbb => frame.push(Uninitialized(instruction.offset)),
+        0xbc => {
+            frame.pop(1)?;
+            let element = match operands.first()? {
+                4 => "Z",
+                5 => "C",
+                6 => "F",
+                7 => "D",
+                8 => "B",
+                9 => "S",
+                10 => "I",
+                11 => "J",
+                _ => return None,
+            };
+            frame.push(Reference(format!("[{}", element)));
+        }
+        0xbd => {
+            frame.pop(1)?;
+            let element = class_at(instruction.cp_index()?)?;
+            frame.push(Reference(if element.starts_with('[') {
+                format!("[{}", element)
+            } else {
+                format!("[L{};", element)
+            }));
+        }
+        0xbe | 0xc1 => {
+            frame.pop(1)?;
+            frame.push(Int);
+        }
+        0xc0 => {
+            frame.pop(1)?;
+            frame.push(Reference(class_at(instruction.cp_index()?)?.to_string()));
+        }
+        WIDE => {
+            let opcode = *operands.first()?;
+            let index = u16::from_be_bytes([*operands.get(1)?, *operands.get(2)?]) as usize;
+            match opcode {
+                0x15..=0x19 => frame.load(index)?,
+                0x36..=0x3a => frame.store(index)?,
+                instruction::IINC => {}
+                _ => return None,
+            }
+        }
+        0xc5 => {
+            frame.pop(*operands.get(2)? as usize)?;
+            frame.push(Reference(class_at(instruction.cp_index()?)?.to_string()));
+        }
+        // jsr, ret and anything else leave the types unknown
+        _ => return None,
+    }
+    Some(())
+}
+
+/// Applies one of the `dup` family or `swap` to the slots of `stack`.
+fn shuffle(stack: &mut Vec<FrameType>, opcode: u8) -> Option<()> {
+    let (taken, copied) = match opcode {
+        0x59 => (1, 1),
+        0x5a => (2, 1),
+        0x5b => (3, 1),
+        0x5c => (2, 2),
+        0x5d => (3, 2),
+        0x5e => (4, 2),
+        _ => {
+            let len = stack.len();
+            if len < 2 {
+                return None;
+            }
+            stack.swap(len - 1, len - 2);
+            return Some(());
+        }
+    };
+    let start = stack.len().checked_sub(taken)?;
+    let top: Vec<FrameType> = stack[stack.len() - copied..].to_vec();
+    stack.splice(start..start, top);
+    Some(())
+}
@@ -0,0 +1,143 @@
+//! Readable names for the methods and classes compilers generate, for
+//! reports meant for people. These are best-effort guesses from naming
+//! patterns and, for accessors, from what the method body does; the raw
+//! names stay the ones to rely on.
+//!
+//! Recognized:
+//! - lambda bodies: javac's `lambda$handleRequest$2`, Kotlin's
+//!   `handleRequest$lambda-2` and `handleRequest$lambda$2`, Scala's
+//!   `$anonfun$handleRequest$2`, all shown as `λ2 in handleRequest`
+//! - accessors for private members of nestmates: javac's `access$000` and
+//!   Kotlin's `access$getName$p`, shown as `accessor for Outer.name`
+//! - the classes javac generates to switch over enums of other classes,
+//!   whose fields are named `$SwitchMap$<enum>`
+
+use crate::{
+    classfile::ClassFile, constantpool::ConstantPool, descriptor::MethodDescriptor, instruction,
+    methodinfo::MethodInfo, switchmap::SWITCH_MAP_PREFIX,
+};
+
+/// A friendlier name for `method` when it's one the compiler made up, or
+/// `None` when its own name is the best there is.
+pub fn friendly_method_name(class_file: &ClassFile, method: &MethodInfo) -> Option<String> {
+    let name = ConstantPool::utf8(&class_file.constant_pool, method.name_index())?;
+    if let Some((enclosing, number)) = lambda(name) {
+        let enclosing = match enclosing {
+            "static" => "static initializer",
+            "new" => "constructor",
+            enclosing => enclosing,
+        };
+        return Some(format!("λ{} in {}", number, enclosing));
+    }
+    let accessed = name.strip_prefix("access$")?;
+    if let Some(member) = accessed_member(class_file, method) {
+        return Some(format!("accessor for {}", member));
+    }
+    // Kotlin names its accessors after what they reach
+    let (property, suffix) = match accessed.strip_suffix("$p") {
+        Some(property) => match (property.strip_prefix("get"), property.strip_prefix("set")) {
+            (Some(property), _) => (property, ""),
+            (_, Some(property)) => (property, " (write)"),
+            _ => return None,
+        },
+        None if !accessed.bytes().all(|b| b.is_ascii_digit()) => (accessed, ""),
+        None => return None,
+    };
+    let mut chars = property.chars();
+    let first = chars.next()?;
+    Some(format!(
+        "accessor for {}{}{}",
+        first.to_lowercase(),
+        chars.as_str(),
+        suffix
+    ))
+}
+
+/// A friendlier name for the class when it's a switch map class.
+pub fn friendly_class_name(class_file: &ClassFile) -> Option<String> {
+    let constant_pool = &class_file.constant_pool;
+    let enums = class_file
+        .fields
+        .iter()
+        .map(|field| {
+            ConstantPool::utf8(constant_pool, field.name_index())?
+                .strip_prefix(SWITCH_MAP_PREFIX)
+                .map(|name| name.replace('$', "."))
+        })
+        .collect::<Option<Vec<_>>>()?;
+    if enums.is_empty() {
+        return None;
+    }
+    let this_class = ConstantPool::class_name(constant_pool, class_file.this_class)?;
+    let outer = this_class
+        .rsplit_once('$')
+        .map_or(this_class, |(outer, _)| outer);
+    Some(format!(
+        "switch map for {} in {}",
+        enums.join(", "),
+        outer.replace('/', ".")
+    ))
+}
+
+/// Splits the name of a lambda body into the name of the method it was
+/// written in and its number.
+fn lambda(name: &str) -> Option<(&str, &str)> {
+    let is_number = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+    let (enclosing, number) = if let Some(rest) = name.strip_prefix("lambda$") {
+        rest.rsplit_once('$')?
+    } else if let Some(rest) = name.strip_prefix("$anonfun$") {
+        let rest = rest.strip_suffix("$adapted").unwrap_or(rest);
+        rest.rsplit_once('$')?
+    } else if let Some((enclosing, number)) = name.rsplit_once("$lambda-") {
+        (enclosing, number)
+    } else {
+        name.rsplit_once("$lambda$")?
+    };
+    (is_number(number) && !enclosing.is_empty()).then_some((enclosing, number))
+}
+
+/// The field or method an accessor reaches, when the body does nothing
+/// but load its arguments, touch that one member and return. Reads and
+/// writes of the same field, as `x++` compiles to, count as one member.
+fn accessed_member(class_file: &ClassFile, method: &MethodInfo) -> Option<String> {
+    let constant_pool = &class_file.constant_pool;
+    let code = method.code()?;
+    let mut accessed = None;
+    let mut writes = false;
+    for instruction in instruction::decode(&code.code) {
+        let instruction = instruction.ok()?;
+        if !instruction.branch_targets().is_empty() {
+            return None;
+        }
+        let index = match instruction.opcode {
+            0xb2..=0xb9 => instruction.cp_index()?,
+            _ => continue,
+        };
+        if accessed.is_some_and(|accessed| accessed != index) {
+            return None;
+        }
+        accessed = Some(index);
+        writes |= matches!(instruction.opcode, 0xb3 | 0xb5);
+    }
+    let index = accessed?;
+    let member = ConstantPool::member_ref(constant_pool, index)?;
+    let owner = member
+        .class_name
+        .rsplit_once('/')
+        .map_or(member.class_name, |(_, simple_name)| simple_name);
+    Some(match constant_pool.get(index as usize - 1)? {
+        ConstantPool::FieldRef(..) if writes => {
+            format!("{}.{} (write)", owner, member.name)
+        }
+        ConstantPool::FieldRef(..) => format!("{}.{}", owner, member.name),
+        _ => {
+            let parameters = MethodDescriptor::parse(member.descriptor)
+                .ok()?
+                .java_parameters();
+            match member.name {
+                "<init>" => format!("new {}{}", owner, parameters),
+                name => format!("{}.{}{}", owner, name, parameters),
+            }
+        }
+    })
+}
@@ -0,0 +1,173 @@
+//! The bytes of a class file or jar, read into memory or, with the `mmap`
+//! feature, mapped from the file so that a large jar isn't copied before
+//! the few entries wanted of it are. Either way an [`Input`] derefs to the
+//! `&[u8]` every parser takes:
+//!
+//! ```no_run
+//! use jvmb::{borrowed::BorrowedClassFile, input::Input};
+//!
+//! let input = Input::map("Foo.class").unwrap();
+//! let (_, class_file) = BorrowedClassFile::parse(&input).unwrap();
+//! println!("{} methods", class_file.methods.len());
+//! // class_file borrows from input, so can't outlive the mapping
+//! ```
+
+use std::{
+    fmt,
+    fs::File,
+    io::{self, Read},
+    ops::Deref,
+    path::{Path, PathBuf},
+};
+
+use crate::error::ParseError;
+
+/// The whole of a file, read or mapped.
+#[derive(Debug)]
+pub struct Input {
+    bytes: Bytes,
+}
+
+#[derive(Debug)]
+enum Bytes {
+    Read(Vec<u8>),
+    #[cfg(feature = "mmap")]
+    Mapped(memmap2::Mmap),
+}
+
+impl Input {
+    /// Reads the file at `path` into memory.
+    pub fn read(path: impl AsRef<Path>) -> io::Result<Self> {
+        let mut file = File::open(path)?;
+        let mut buf = Vec::with_capacity(1 << 16);
+        file.read_to_end(&mut buf)?;
+        Ok(Input::from(buf))
+    }
+
+    /// Maps the file at `path` into memory. What can't be mapped, such as a
+    /// pipe, `/dev/stdin` or an empty file, is read instead, as everything
+    /// is without the `mmap` feature.
+    ///
+    /// The mapping sees any change made to the file while it's open; a
+    /// file truncated under it makes reading past the new end fault.
+    pub fn map(path: impl AsRef<Path>) -> io::Result<Self> {
+        #[cfg(feature = "mmap")]
+        {
+            let file = File::open(&path)?;
+            let metadata = file.metadata()?;
+            if metadata.is_file() && metadata.len() > 0 {
+                // SAFETY: the map is only ever read, and a file changed
+                // while mapped is documented above
+                let map = unsafe { memmap2::Mmap::map(&file)? };
+                return Ok(Input {
+                    bytes: Bytes::Mapped(map),
+                });
+            }
+        }
+        Input::read(path)
+    }
+
+    /// Reads the file at `path`, mapping it when `mmap` is set.
+    pub fn open(path: impl AsRef<Path>, mmap: bool) -> io::Result<Self> {
+        if mmap {
+            Input::map(path)
+        } else {
+            Input::read(path)
+        }
+    }
+
+    /// Reads all of standard input.
+    pub fn stdin() -> io::Result<Self> {
+        let mut buf = Vec::new();
+        io::stdin().lock().read_to_end(&mut buf)?;
+        Ok(Input::from(buf))
+    }
+
+    /// Whether the bytes are mapped from the file rather than read.
+    pub fn is_mapped(&self) -> bool {
+        match self.bytes {
+            Bytes::Read(_) => false,
+            #[cfg(feature = "mmap")]
+            Bytes::Mapped(_) => true,
+        }
+    }
+}
+
+impl From<Vec<u8>> for Input {
+    fn from(buf: Vec<u8>) -> Self {
+        Input {
+            bytes: Bytes::Read(buf),
+        }
+    }
+}
+
+impl Deref for Input {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match &self.bytes {
+            Bytes::Read(buf) => buf,
+            #[cfg(feature = "mmap")]
+            Bytes::Mapped(map) => map,
+        }
+    }
+}
+
+impl AsRef<[u8]> for Input {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+/// Why a file couldn't be read as a class.
+#[derive(Debug)]
+pub enum ReadFileError {
+    Io(io::Error),
+    /// The file at `path` couldn't be opened or read.
+    File {
+        path: PathBuf,
+        error: io::Error,
+    },
+    Parse(ParseError),
+}
+
+impl ReadFileError {
+    /// `error`, met reading the file at `path`.
+    pub(crate) fn file(path: &Path, error: io::Error) -> Self {
+        ReadFileError::File {
+            path: path.to_path_buf(),
+            error,
+        }
+    }
+}
+
+impl fmt::Display for ReadFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReadFileError::Io(e) => write!(f, "{}", e),
+            ReadFileError::File { path, error } => write!(f, "{}: {}", path.display(), error),
+            ReadFileError::Parse(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for ReadFileError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ReadFileError::Io(e) | ReadFileError::File { error: e, .. } => Some(e),
+            ReadFileError::Parse(e) => Some(e),
+        }
+    }
+}
+
+impl From<ParseError> for ReadFileError {
+    fn from(e: ParseError) -> Self {
+        ReadFileError::Parse(e)
+    }
+}
+
+impl From<io::Error> for ReadFileError {
+    fn from(e: io::Error) -> Self {
+        ReadFileError::Io(e)
+    }
+}
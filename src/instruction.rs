@@ -0,0 +1,247 @@
+//! Decoding of the bytecode held by a `Code` attribute into instructions.
+
+use std::fmt;
+
+pub const TABLESWITCH: u8 = 0xaa;
+pub const LOOKUPSWITCH: u8 = 0xab;
+pub const WIDE: u8 = 0xc4;
+pub const IINC: u8 = 0x84;
+
+#[rustfmt::skip]
+const MNEMONICS: [&str; 0xcb] = [
+    /* 0x00 */ "nop", "aconst_null", "iconst_m1", "iconst_0", "iconst_1", "iconst_2", "iconst_3", "iconst_4",
+    /* 0x08 */ "iconst_5", "lconst_0", "lconst_1", "fconst_0", "fconst_1", "fconst_2", "dconst_0", "dconst_1",
+    /* 0x10 */ "bipush", "sipush", "ldc", "ldc_w", "ldc2_w", "iload", "lload", "fload",
+    /* 0x18 */ "dload", "aload", "iload_0", "iload_1", "iload_2", "iload_3", "lload_0", "lload_1",
+    /* 0x20 */ "lload_2", "lload_3", "fload_0", "fload_1", "fload_2", "fload_3", "dload_0", "dload_1",
+    /* 0x28 */ "dload_2", "dload_3", "aload_0", "aload_1", "aload_2", "aload_3", "iaload", "laload",
+    /* 0x30 */ "faload", "daload", "aaload", "baload", "caload", "saload", "istore", "lstore",
+    /* 0x38 */ "fstore", "dstore", "astore", "istore_0", "istore_1", "istore_2", "istore_3", "lstore_0",
+    /* 0x40 */ "lstore_1", "lstore_2", "lstore_3", "fstore_0", "fstore_1", "fstore_2", "fstore_3", "dstore_0",
+    /* 0x48 */ "dstore_1", "dstore_2", "dstore_3", "astore_0", "astore_1", "astore_2", "astore_3", "iastore",
+    /* 0x50 */ "lastore", "fastore", "dastore", "aastore", "bastore", "castore", "sastore", "pop",
+    /* 0x58 */ "pop2", "dup", "dup_x1", "dup_x2", "dup2", "dup2_x1", "dup2_x2", "swap",
+    /* 0x60 */ "iadd", "ladd", "fadd", "dadd", "isub", "lsub", "fsub", "dsub",
+    /* 0x68 */ "imul", "lmul", "fmul", "dmul", "idiv", "ldiv", "fdiv", "ddiv",
+    /* 0x70 */ "irem", "lrem", "frem", "drem", "ineg", "lneg", "fneg", "dneg",
+    /* 0x78 */ "ishl", "lshl", "ishr", "lshr", "iushr", "lushr", "iand", "land",
+    /* 0x80 */ "ior", "lor", "ixor", "lxor", "iinc", "i2l", "i2f", "i2d",
+    /* 0x88 */ "l2i", "l2f", "l2d", "f2i", "f2l", "f2d", "d2i", "d2l",
+    /* 0x90 */ "d2f", "i2b", "i2c", "i2s", "lcmp", "fcmpl", "fcmpg", "dcmpl",
+    /* 0x98 */ "dcmpg", "ifeq", "ifne", "iflt", "ifge", "ifgt", "ifle", "if_icmpeq",
+    /* 0xa0 */ "if_icmpne", "if_icmplt", "if_icmpge", "if_icmpgt", "if_icmple", "if_acmpeq", "if_acmpne", "goto",
+    /* 0xa8 */ "jsr", "ret", "tableswitch", "lookupswitch", "ireturn", "lreturn", "freturn", "dreturn",
+    /* 0xb0 */ "areturn", "return", "getstatic", "putstatic", "getfield", "putfield", "invokevirtual", "invokespecial",
+    /* 0xb8 */ "invokestatic", "invokeinterface", "invokedynamic", "new", "newarray", "anewarray", "arraylength", "athrow",
+    /* 0xc0 */ "checkcast", "instanceof", "monitorenter", "monitorexit", "wide", "multianewarray", "ifnull", "ifnonnull",
+    /* 0xc8 */ "goto_w", "jsr_w", "breakpoint",
+];
+
+/// The mnemonic of `opcode`, or `None` for the reserved and unassigned ones.
+pub fn mnemonic(opcode: u8) -> Option<&'static str> {
+    MNEMONICS.get(opcode as usize).copied()
+}
+
+/// The number of operand bytes following `opcode`, for every instruction
+/// but the two switches and `wide`, whose length depends on their operands.
+fn operand_length(opcode: u8) -> usize {
+    match opcode {
+        0x10 | 0x12 | 0x15..=0x19 | 0x36..=0x3a | 0xa9 | 0xbc => 1,
+        0x11 | 0x13 | 0x14 | 0x84 | 0x99..=0xa8 | 0xb2..=0xb8 | 0xbb | 0xbd => 2,
+        0xc0 | 0xc1 | 0xc6 | 0xc7 => 2,
+        0xc5 => 3,
+        0xb9 | 0xba | 0xc8 | 0xc9 => 4,
+        _ => 0,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Instruction<'a> {
+    /// The offset of the opcode from the start of the code array.
+    pub offset: u32,
+    pub opcode: u8,
+    /// The bytes following the opcode, including any switch padding.
+    pub operands: &'a [u8],
+}
+
+impl<'a> Instruction<'a> {
+    /// Decodes a code array on its own, with no constant pool: operands
+    /// which refer to the pool are left as the raw indices
+    /// [`Instruction::cp_index`] reads. The same as [`decode`].
+    pub fn decode_stream(code: &'a [u8]) -> Instructions<'a> {
+        decode(code)
+    }
+
+    pub fn mnemonic(&self) -> &'static str {
+        mnemonic(self.opcode).unwrap_or("<invalid>")
+    }
+
+    /// The length of the whole instruction in bytes.
+    pub fn size(&self) -> usize {
+        1 + self.operands.len()
+    }
+
+    /// The constant pool index this instruction refers to, for the `ldc`
+    /// family, field and method instructions, `new` and the type checks.
+    pub fn cp_index(&self) -> Option<u16> {
+        match self.opcode {
+            0x12 => Some(self.operands[0] as u16),
+            0x13 | 0x14 | 0xb2..=0xba | 0xbb | 0xbd | 0xc0 | 0xc1 | 0xc5 => {
+                Some(u16::from_be_bytes([self.operands[0], self.operands[1]]))
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether this is one of the `invoke*` instructions.
+    pub fn is_invoke(&self) -> bool {
+        (0xb6..=0xba).contains(&self.opcode)
+    }
+
+    /// The absolute offsets this instruction may jump to, not counting
+    /// falling through to the next instruction: one for each target the
+    /// instruction holds, in order, so that a switch gives its default and
+    /// then one per case. In malformed code they may lie outside the code
+    /// array, before its start included, and callers must check.
+    pub fn branch_targets(&self) -> Vec<i64> {
+        let relative: Vec<i64> = match self.opcode {
+            0x99..=0xa8 | 0xc6 | 0xc7 => {
+                vec![i16::from_be_bytes([self.operands[0], self.operands[1]]) as i64]
+            }
+            0xc8 | 0xc9 => vec![read_i32(self.operands, 0) as i64],
+            TABLESWITCH => {
+                let pad = self.switch_padding();
+                let low = read_i32(self.operands, pad + 4) as i64;
+                let high = read_i32(self.operands, pad + 8) as i64;
+                let mut targets = vec![read_i32(self.operands, pad) as i64];
+                targets.extend(
+                    (0..=(high - low) as usize)
+                        .map(|i| read_i32(self.operands, pad + 12 + i * 4) as i64),
+                );
+                targets
+            }
+            LOOKUPSWITCH => {
+                let pad = self.switch_padding();
+                let npairs = read_i32(self.operands, pad + 4) as usize;
+                let mut targets = vec![read_i32(self.operands, pad) as i64];
+                targets
+                    .extend((0..npairs).map(|i| read_i32(self.operands, pad + 12 + i * 8) as i64));
+                targets
+            }
+            _ => Vec::new(),
+        };
+        relative
+            .into_iter()
+            .map(|delta| self.offset as i64 + delta)
+            .collect()
+    }
+
+    /// The padding after a switch opcode which aligns its operands to four
+    /// bytes from the start of the code array.
+    fn switch_padding(&self) -> usize {
+        (3 - self.offset as usize % 4) % 4
+    }
+}
+
+fn read_i32(buf: &[u8], at: usize) -> i32 {
+    i32::from_be_bytes([buf[at], buf[at + 1], buf[at + 2], buf[at + 3]])
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    InvalidOpcode { offset: u32, opcode: u8 },
+    Truncated { offset: u32 },
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::InvalidOpcode { offset, opcode } => {
+                write!(f, "invalid opcode 0x{:02x} at offset {}", opcode, offset)
+            }
+            DecodeError::Truncated { offset } => {
+                write!(
+                    f,
+                    "instruction at offset {} runs past the end of the code",
+                    offset
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// Iterates over the instructions of a code array. Decoding stops after the
+/// first error.
+#[derive(Debug, Clone)]
+pub struct Instructions<'a> {
+    code: &'a [u8],
+    offset: usize,
+}
+
+pub fn decode(code: &[u8]) -> Instructions<'_> {
+    Instructions { code, offset: 0 }
+}
+
+impl<'a> Instructions<'a> {
+    fn instruction_length(&self, opcode: u8) -> Option<usize> {
+        let offset = self.offset;
+        let operands = self.code.get(offset + 1..)?;
+        let length = match opcode {
+            TABLESWITCH | LOOKUPSWITCH => {
+                let pad = (3 - offset % 4) % 4;
+                let word = |i: usize| operands.get(pad + i * 4..pad + i * 4 + 4);
+                let word = |i| word(i).map(|w| i32::from_be_bytes([w[0], w[1], w[2], w[3]]));
+                let entries = if opcode == TABLESWITCH {
+                    let (low, high) = (word(1)? as i64, word(2)? as i64);
+                    if high < low {
+                        return None;
+                    }
+                    3 + (high - low + 1) as usize
+                } else {
+                    let npairs = word(1)?;
+                    2 + 2 * usize::try_from(npairs).ok()?
+                };
+                pad + entries * 4
+            }
+            WIDE => match *operands.first()? {
+                IINC => 5,
+                _ => 3,
+            },
+            opcode => operand_length(opcode),
+        };
+        Some(1 + length)
+    }
+}
+
+impl<'a> Iterator for Instructions<'a> {
+    type Item = Result<Instruction<'a>, DecodeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let opcode = *self.code.get(self.offset)?;
+        let offset = self.offset as u32;
+        if mnemonic(opcode).is_none() {
+            self.offset = self.code.len();
+            return Some(Err(DecodeError::InvalidOpcode { offset, opcode }));
+        }
+        let end = self
+            .instruction_length(opcode)
+            .map(|length| self.offset + length)
+            .filter(|&end| end <= self.code.len());
+        let end = match end {
+            Some(end) => end,
+            None => {
+                self.offset = self.code.len();
+                return Some(Err(DecodeError::Truncated { offset }));
+            }
+        };
+        let instruction = Instruction {
+            offset,
+            opcode,
+            operands: &self.code[self.offset + 1..end],
+        };
+        self.offset = end;
+        Some(Ok(instruction))
+    }
+}
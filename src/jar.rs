@@ -0,0 +1,865 @@
+//! Reading class files out of jar (zip) archives and jmods, and writing
+//! jars.
+
+use std::{
+    collections::HashMap,
+    fmt,
+    fs::File,
+    io::{self, Read, Seek, SeekFrom},
+    path::Path,
+    sync::{Mutex, PoisonError},
+};
+
+use nom::{
+    bytes::complete::{tag, take},
+    number::complete::{le_u16, le_u32},
+    IResult,
+};
+
+use crate::{
+    classfile::{ClassFile, ClassHeader, ParseOptions},
+    error::ParseError,
+    inflate::{inflate, InflateError},
+    input::Input,
+    modules::manifest_attribute,
+};
+
+pub(crate) const LOCAL_HEADER_SIGNATURE: u32 = 0x04034b50;
+const CENTRAL_HEADER_SIGNATURE: u32 = 0x02014b50;
+const END_OF_CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x06054b50;
+
+/// What every `.jmod` file starts with, ahead of the zip archive proper.
+const JMOD_MAGIC: &[u8] = b"JM\x01\x00";
+
+/// The fixed part of a local file header, ahead of the name and extra
+/// field.
+const LOCAL_HEADER_LEN: usize = 30;
+/// The end of central directory record, without its comment.
+const END_OF_CENTRAL_DIRECTORY_LEN: usize = 22;
+
+const METHOD_STORED: u16 = 0;
+const METHOD_DEFLATED: u16 = 8;
+
+/// Zip 2.0, enough for stored and deflated entries.
+const VERSION_NEEDED: u16 = 20;
+/// Where the classes of each release of a multi-release jar are kept, as
+/// `META-INF/versions/<n>/`.
+const VERSIONS_PREFIX: &str = "META-INF/versions/";
+
+/// 1980-01-01, the earliest date a zip can hold, so that the same input
+/// always gives the same jar.
+const DOS_EPOCH: u16 = (1 << 5) | 1;
+
+#[derive(Debug)]
+pub enum JarError {
+    Io(io::Error),
+    /// The archive structure itself is broken.
+    Malformed(&'static str),
+    UnsupportedCompression(u16),
+    Inflate(InflateError),
+}
+
+impl fmt::Display for JarError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JarError::Io(e) => write!(f, "{}", e),
+            JarError::Malformed(what) => write!(f, "malformed archive: {}", what),
+            JarError::UnsupportedCompression(method) => {
+                write!(f, "unsupported compression method {}", method)
+            }
+            JarError::Inflate(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for JarError {}
+
+impl From<io::Error> for JarError {
+    fn from(e: io::Error) -> Self {
+        JarError::Io(e)
+    }
+}
+
+impl From<InflateError> for JarError {
+    fn from(e: InflateError) -> Self {
+        JarError::Inflate(e)
+    }
+}
+
+/// An entry as described by the archive's central directory.
+#[derive(Debug, Clone)]
+pub struct JarEntry {
+    pub name: String,
+    /// The general purpose bit flags.
+    pub flags: u16,
+    pub method: u16,
+    pub crc32: u32,
+    pub compressed_size: u32,
+    pub uncompressed_size: u32,
+    pub local_header_offset: u32,
+}
+
+impl JarEntry {
+    pub fn is_dir(&self) -> bool {
+        self.name.ends_with('/')
+    }
+
+    pub fn is_class(&self) -> bool {
+        self.name.ends_with(".class")
+    }
+
+    /// The internal name of the class this entry holds, with the
+    /// `META-INF/versions/<n>/` prefix of a multi-release jar removed. `None`
+    /// for entries which aren't classes, module descriptors and anything else
+    /// under `META-INF`.
+    pub fn class_name(&self) -> Option<&str> {
+        let path = self.name.strip_suffix(".class")?;
+        let path = match path.strip_prefix("META-INF/") {
+            Some(rest) => rest.strip_prefix("versions/")?.split_once('/')?.1,
+            None => path,
+        };
+        (path != "module-info").then_some(path)
+    }
+
+    /// The release `n` of an entry under `META-INF/versions/<n>/`, the
+    /// variant of its class for Java `n` on in a multi-release jar; `None`
+    /// for every other entry.
+    pub fn release(&self) -> Option<u16> {
+        self.versioned().0
+    }
+
+    /// The release of the entry and its name with the `META-INF/versions/<n>/`
+    /// prefix removed, the name every variant of a class shares.
+    fn versioned(&self) -> (Option<u16>, &str) {
+        let versioned = self.name.strip_prefix(VERSIONS_PREFIX).and_then(|rest| {
+            let (release, path) = rest.split_once('/')?;
+            Some((Some(release.parse().ok()?), path))
+        });
+        versioned.unwrap_or((None, &self.name))
+    }
+
+    fn parse(buf: &[u8]) -> IResult<&[u8], Self> {
+        let (buf, _) = tag(CENTRAL_HEADER_SIGNATURE.to_le_bytes())(buf)?;
+        let (buf, _version_made_by) = le_u16(buf)?;
+        let (buf, _version_needed) = le_u16(buf)?;
+        let (buf, flags) = le_u16(buf)?;
+        let (buf, method) = le_u16(buf)?;
+        let (buf, _time) = le_u16(buf)?;
+        let (buf, _date) = le_u16(buf)?;
+        let (buf, crc32) = le_u32(buf)?;
+        let (buf, compressed_size) = le_u32(buf)?;
+        let (buf, uncompressed_size) = le_u32(buf)?;
+        let (buf, name_length) = le_u16(buf)?;
+        let (buf, extra_length) = le_u16(buf)?;
+        let (buf, comment_length) = le_u16(buf)?;
+        let (buf, _disk_number) = le_u16(buf)?;
+        let (buf, _internal_attributes) = le_u16(buf)?;
+        let (buf, _external_attributes) = le_u32(buf)?;
+        let (buf, local_header_offset) = le_u32(buf)?;
+        let (buf, name) = take(name_length)(buf)?;
+        let (buf, _extra) = take(extra_length)(buf)?;
+        let (buf, _comment) = take(comment_length)(buf)?;
+
+        Ok((
+            buf,
+            JarEntry {
+                name: String::from_utf8_lossy(name).into_owned(),
+                flags,
+                method,
+                crc32,
+                compressed_size,
+                uncompressed_size,
+                local_header_offset,
+            },
+        ))
+    }
+}
+
+/// The Java release a multi-release jar is read for, which decides the
+/// variant of each class standing for it: the one under the highest
+/// `META-INF/versions/<n>/` no newer than the release, or else the one
+/// outside `META-INF/versions`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Release {
+    /// The newest release, for which the variant of the highest version
+    /// stands.
+    #[default]
+    Latest,
+    /// Java `n`, e.g. 17.
+    Version(u16),
+}
+
+impl Release {
+    /// Whether a class variant of `release`, `None` outside
+    /// `META-INF/versions`, is seen by this release.
+    fn sees(self, release: Option<u16>) -> bool {
+        match (self, release) {
+            (_, None) | (Release::Latest, _) => true,
+            (Release::Version(version), Some(release)) => release <= version,
+        }
+    }
+}
+
+impl fmt::Display for Release {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Release::Latest => f.write_str("latest"),
+            Release::Version(version) => write!(f, "{}", version),
+        }
+    }
+}
+
+/// A jar held in memory, with its central directory parsed up front.
+///
+/// Of a multi-release jar, one whose manifest says `Multi-Release: true`,
+/// [`class_entries`](JarFile::class_entries), [`classes`](JarFile::classes)
+/// and [`class_entry`](JarFile::class_entry) see each class as the
+/// [`Release`] the jar is read for does, the latest unless
+/// [`with_release`](JarFile::with_release) picks another, while
+/// [`class_variants`](JarFile::class_variants) gives every variant:
+///
+/// ```
+/// use jvmb::jar::{JarFile, JarWriter, Release};
+///
+/// let mut writer = JarWriter::new();
+/// writer.add("META-INF/MANIFEST.MF", b"Manifest-Version: 1.0\r\nMulti-Release: true\r\n").unwrap();
+/// writer.add("com/example/Foo.class", b"for 8").unwrap();
+/// writer.add("com/example/Bar.class", b"for 8").unwrap();
+/// writer.add("META-INF/versions/17/com/example/Foo.class", b"for 17").unwrap();
+/// let jar = JarFile::from_bytes(writer.finish().unwrap()).unwrap();
+/// assert!(jar.is_multi_release());
+///
+/// let foo = |jar: &JarFile| jar.class_bytes("com/example/Foo").unwrap().unwrap();
+/// assert_eq!(foo(&jar), b"for 17");
+/// let jar = jar.with_release(Release::Version(17));
+/// assert_eq!(foo(&jar), b"for 17");
+/// let jar = jar.with_release(Release::Version(8));
+/// assert_eq!(foo(&jar), b"for 8");
+/// assert_eq!(jar.class_bytes("com/example/Baz").unwrap(), None);
+///
+/// let names = |jar: &JarFile| -> Vec<String> {
+///     jar.class_entries().map(|entry| entry.name.clone()).collect()
+/// };
+/// assert_eq!(names(&jar), ["com/example/Foo.class", "com/example/Bar.class"]);
+/// let jar = jar.with_release(Release::Latest);
+/// assert_eq!(
+///     names(&jar),
+///     ["com/example/Bar.class", "META-INF/versions/17/com/example/Foo.class"]
+/// );
+/// let releases: Vec<_> = jar.class_variants().map(|entry| entry.release()).collect();
+/// assert_eq!(releases, [None, None, Some(17)]);
+/// ```
+#[derive(Debug)]
+pub struct JarFile {
+    data: Input,
+    entries: Vec<JarEntry>,
+    multi_release: bool,
+    release: Release,
+}
+
+impl JarFile {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, JarError> {
+        Self::from_input(Input::read(path)?)
+    }
+
+    /// Opens the jar at `path` mapped into memory, as [`Input::map`] maps
+    /// it, so that only the entries read are ever copied out of it.
+    pub fn open_mapped(path: impl AsRef<Path>) -> Result<Self, JarError> {
+        Self::from_input(Input::map(path)?)
+    }
+
+    /// Opens a `.jmod` file of a JDK, whose classes are the entries under
+    /// `classes/`.
+    pub fn open_jmod(path: impl AsRef<Path>) -> Result<Self, JarError> {
+        let data = std::fs::read(path)?;
+        let archive = data
+            .strip_prefix(JMOD_MAGIC)
+            .ok_or(JarError::Malformed("not a jmod file"))?;
+        Self::from_bytes(archive.to_vec())
+    }
+
+    pub fn from_bytes(data: Vec<u8>) -> Result<Self, JarError> {
+        Self::from_input(Input::from(data))
+    }
+
+    pub fn from_input(data: Input) -> Result<Self, JarError> {
+        let (entries, _) = read_central_directory(&data)?;
+        let mut jar = JarFile {
+            data,
+            entries,
+            multi_release: false,
+            release: Release::default(),
+        };
+        jar.multi_release = jar.reads_multi_release();
+        Ok(jar)
+    }
+
+    /// Whether the manifest says `Multi-Release: true`. A manifest which
+    /// can't be read says nothing.
+    fn reads_multi_release(&self) -> bool {
+        let Some(entry) = self.entry("META-INF/MANIFEST.MF") else {
+            return false;
+        };
+        let Ok(manifest) = self.read(entry) else {
+            return false;
+        };
+        manifest_attribute(&String::from_utf8_lossy(&manifest), "Multi-Release")
+            .is_some_and(|value| value.eq_ignore_ascii_case("true"))
+    }
+
+    /// Whether the jar is multi-release, holding classes for later releases
+    /// under `META-INF/versions/<n>/`.
+    pub fn is_multi_release(&self) -> bool {
+        self.multi_release
+    }
+
+    /// The release the jar is read for.
+    pub fn release(&self) -> Release {
+        self.release
+    }
+
+    /// Reads the jar for `release`, should it be multi-release.
+    pub fn with_release(mut self, release: Release) -> Self {
+        self.release = release;
+        self
+    }
+
+    /// The whole archive, as it was read.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub fn entries(&self) -> &[JarEntry] {
+        &self.entries
+    }
+
+    /// The entries holding classes, in the order of the central directory.
+    /// Of a multi-release jar, only the variant of each class the
+    /// [`release`](JarFile::release) sees is among them.
+    pub fn class_entries(&self) -> impl Iterator<Item = &JarEntry> {
+        let seen = self.multi_release.then(|| self.seen_variants());
+        self.class_variants().filter(move |entry| match &seen {
+            Some(seen) => {
+                let (release, path) = entry.versioned();
+                seen.get(path) == Some(&release)
+            }
+            None => true,
+        })
+    }
+
+    /// Every entry holding a class, every variant of a class of a
+    /// multi-release jar among them, in the order of the central directory;
+    /// [`JarEntry::release`] tells the variants apart.
+    pub fn class_variants(&self) -> impl Iterator<Item = &JarEntry> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.is_class() && !entry.is_dir())
+    }
+
+    /// The release of the variant of each class the release of the jar sees,
+    /// by its name within its version.
+    fn seen_variants(&self) -> HashMap<&str, Option<u16>> {
+        let mut seen = HashMap::new();
+        for entry in self.class_variants() {
+            let (release, path) = entry.versioned();
+            if self.release.sees(release) {
+                let newest = seen.entry(path).or_insert(release);
+                *newest = (*newest).max(release);
+            }
+        }
+        seen
+    }
+
+    /// The entry of the class `class_name`, an internal name such as
+    /// `com/example/Foo`: of a multi-release jar, the variant the
+    /// [`release`](JarFile::release) sees.
+    pub fn class_entry(&self, class_name: &str) -> Option<&JarEntry> {
+        let path = format!("{}.class", class_name);
+        if !self.multi_release {
+            return self.entry(&path);
+        }
+        self.class_variants()
+            .filter_map(|entry| {
+                let (release, name) = entry.versioned();
+                (name == path && self.release.sees(release)).then_some((release, entry))
+            })
+            .max_by_key(|(release, _)| *release)
+            .map(|(_, entry)| entry)
+    }
+
+    /// Reads the class `class_name` as [`class_entry`](JarFile::class_entry)
+    /// finds it; `None` when the jar doesn't hold it.
+    pub fn class_bytes(&self, class_name: &str) -> Result<Option<Vec<u8>>, JarError> {
+        self.class_entry(class_name)
+            .map(|entry| self.read(entry))
+            .transpose()
+    }
+
+    pub fn entry(&self, name: &str) -> Option<&JarEntry> {
+        self.entries.iter().find(|entry| entry.name == name)
+    }
+
+    /// The contents of `entry` as the archive holds them, compressed if they
+    /// are.
+    pub fn raw(&self, entry: &JarEntry) -> Result<&[u8], JarError> {
+        let header = self
+            .data
+            .get(entry.local_header_offset as usize..)
+            .ok_or(JarError::Malformed("local header offset out of range"))?;
+        let (data, _) =
+            parse_local_header(header).map_err(|_| JarError::Malformed("bad local header"))?;
+        data.get(..entry.compressed_size as usize)
+            .ok_or(JarError::Malformed("entry data out of range"))
+    }
+
+    /// Reads and decompresses the contents of `entry`.
+    pub fn read(&self, entry: &JarEntry) -> Result<Vec<u8>, JarError> {
+        decompress(entry, self.raw(entry)?)
+    }
+
+    /// Parses every class entry [`class_entries`](JarFile::class_entries)
+    /// gives with `options`, in the order of the central directory, each
+    /// with its entry name. An entry which can't be read or parsed, an empty
+    /// one too, yields its error and the rest carry on. Module descriptors
+    /// are among them; their names end in `module-info.class`.
+    pub fn classes<'a>(
+        &'a self,
+        options: &'a ParseOptions,
+    ) -> impl Iterator<Item = (&'a str, Result<ClassFile, ReadClassError>)> + 'a {
+        self.class_entries().map(move |entry| {
+            let class_file = self
+                .read(entry)
+                .map_err(ReadClassError::from)
+                .and_then(|buf| parse_class(&buf, options));
+            (entry.name.as_str(), class_file)
+        })
+    }
+}
+
+fn parse_class(buf: &[u8], options: &ParseOptions) -> Result<ClassFile, ReadClassError> {
+    let (_, class_file) =
+        ClassFile::parse_with(buf, options).map_err(|e| ReadClassError::Parse(e.into()))?;
+    Ok(class_file)
+}
+
+/// Why an entry couldn't be read as a class.
+#[derive(Debug)]
+pub enum ReadClassError {
+    Jar(JarError),
+    Parse(ParseError),
+}
+
+impl fmt::Display for ReadClassError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReadClassError::Jar(e) => write!(f, "{}", e),
+            ReadClassError::Parse(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for ReadClassError {}
+
+impl From<JarError> for ReadClassError {
+    fn from(e: JarError) -> Self {
+        ReadClassError::Jar(e)
+    }
+}
+
+/// A jar read through `R` as its entries are asked for, rather than held in
+/// memory like a [`JarFile`]. Opening it reads the central directory alone,
+/// and listing its [`entries`](JarReader::entries) reads nothing more, so a
+/// pipeline which picks entries by name never reads nor inflates the rest.
+///
+/// With an `R` which is `Send`, the reader and its entries can be shared
+/// between threads, e.g. the handles picked handed to
+/// [`parallel::map`](crate::parallel::map): entries are read one at a time,
+/// and inflated and parsed on the thread asking for them.
+#[derive(Debug)]
+pub struct JarReader<R> {
+    reader: Mutex<R>,
+    entries: Vec<JarEntry>,
+}
+
+impl JarReader<File> {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, JarError> {
+        Self::new(File::open(path)?)
+    }
+}
+
+impl<R: Read + Seek> JarReader<R> {
+    /// Reads the central directory of the jar `reader` holds.
+    pub fn new(mut reader: R) -> Result<Self, JarError> {
+        let len = reader.seek(SeekFrom::End(0))?;
+        let tail_len = len.min((END_OF_CENTRAL_DIRECTORY_LEN + 0xFFFF) as u64);
+        reader.seek(SeekFrom::Start(len - tail_len))?;
+        let mut tail = vec![0; tail_len as usize];
+        reader.read_exact(&mut tail)?;
+        let eocd = find_end_of_central_directory(&tail)
+            .ok_or(JarError::Malformed("no end of central directory record"))?;
+        let (entry_count, directory_offset) = parse_end_of_central_directory(&tail[eocd..])
+            .map(|(_, fields)| fields)
+            .map_err(|_| JarError::Malformed("truncated end of central directory record"))?;
+
+        let directory_end = len - tail_len + eocd as u64;
+        let directory_len = directory_end
+            .checked_sub(u64::from(directory_offset))
+            .ok_or(JarError::Malformed("central directory offset out of range"))?;
+        reader.seek(SeekFrom::Start(u64::from(directory_offset)))?;
+        let mut directory = vec![0; directory_len as usize];
+        reader.read_exact(&mut directory)?;
+        let entries = parse_central_directory(&directory, entry_count)?;
+
+        Ok(JarReader {
+            reader: Mutex::new(reader),
+            entries,
+        })
+    }
+
+    /// Every entry, in the order of the central directory, with nothing of
+    /// it read yet.
+    pub fn entries(&self) -> impl Iterator<Item = EntryHandle<'_, R>> {
+        self.entries
+            .iter()
+            .map(move |entry| EntryHandle { jar: self, entry })
+    }
+
+    /// The entries holding classes, every variant of a class of a
+    /// multi-release jar among them, as [`JarFile::class_variants`] picks
+    /// them.
+    pub fn class_entries(&self) -> impl Iterator<Item = EntryHandle<'_, R>> {
+        self.entries()
+            .filter(|handle| handle.entry.is_class() && !handle.entry.is_dir())
+    }
+
+    /// Consumes the jar, returning the reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// The contents of `entry` as the archive holds them, read from its
+    /// local header on.
+    fn raw(&self, entry: &JarEntry) -> Result<Vec<u8>, JarError> {
+        // every read seeks first, so a thread which panicked reading leaves
+        // nothing behind to undo
+        let mut reader = self.reader.lock().unwrap_or_else(PoisonError::into_inner);
+        reader.seek(SeekFrom::Start(u64::from(entry.local_header_offset)))?;
+        let mut header = [0; LOCAL_HEADER_LEN];
+        reader.read_exact(&mut header)?;
+        if header[..4] != LOCAL_HEADER_SIGNATURE.to_le_bytes() {
+            return Err(JarError::Malformed("bad local header"));
+        }
+        let name_length = u16::from_le_bytes([header[26], header[27]]);
+        let extra_length = u16::from_le_bytes([header[28], header[29]]);
+        reader.seek(SeekFrom::Current(
+            i64::from(name_length) + i64::from(extra_length),
+        ))?;
+        let mut compressed = vec![0; entry.compressed_size as usize];
+        reader
+            .read_exact(&mut compressed)
+            .map_err(|e| match e.kind() {
+                io::ErrorKind::UnexpectedEof => JarError::Malformed("entry data out of range"),
+                _ => JarError::Io(e),
+            })?;
+        Ok(compressed)
+    }
+}
+
+/// An entry of a [`JarReader`], which is only read when its contents are
+/// asked for.
+#[derive(Debug)]
+pub struct EntryHandle<'a, R> {
+    jar: &'a JarReader<R>,
+    entry: &'a JarEntry,
+}
+
+impl<'a, R: Read + Seek> EntryHandle<'a, R> {
+    pub fn name(&self) -> &'a str {
+        &self.entry.name
+    }
+
+    /// The size of the contents once inflated.
+    pub fn size(&self) -> u32 {
+        self.entry.uncompressed_size
+    }
+
+    pub fn crc32(&self) -> u32 {
+        self.entry.crc32
+    }
+
+    pub fn entry(&self) -> &'a JarEntry {
+        self.entry
+    }
+
+    /// Reads and decompresses the contents.
+    pub fn read(&self) -> Result<Vec<u8>, JarError> {
+        decompress(self.entry, &self.jar.raw(self.entry)?)
+    }
+
+    /// Reads the contents and parses them as a class with `options`.
+    pub fn parse(&self, options: &ParseOptions) -> Result<ClassFile, ReadClassError> {
+        parse_class(&self.read()?, options)
+    }
+
+    /// Reads the contents and parses the start of the class, up to its
+    /// interfaces, which is enough to tell its name and supertypes.
+    pub fn parse_summary(&self) -> Result<ClassHeader, ReadClassError> {
+        let buf = self.read()?;
+        let (_, header) = ClassHeader::parse_with(&buf, &ParseOptions::default())
+            .map_err(|e| ReadClassError::Parse(e.into()))?;
+        Ok(header)
+    }
+}
+
+/// Builds a jar in memory. New entries are stored uncompressed; entries
+/// copied from another jar keep their compression. Archives needing zip64,
+/// over 65,535 entries or 4 GiB, can't be written.
+#[derive(Debug, Default)]
+pub struct JarWriter {
+    data: Vec<u8>,
+    entries: Vec<JarEntry>,
+}
+
+impl JarWriter {
+    pub fn new() -> Self {
+        JarWriter::default()
+    }
+
+    /// Adds an entry holding `contents`.
+    pub fn add(&mut self, name: &str, contents: &[u8]) -> io::Result<()> {
+        let size = zip_u32(contents.len(), "entry")?;
+        self.push(
+            JarEntry {
+                name: name.to_string(),
+                flags: 0,
+                method: METHOD_STORED,
+                crc32: crc32(contents),
+                compressed_size: size,
+                uncompressed_size: size,
+                local_header_offset: 0,
+            },
+            contents,
+        )
+    }
+
+    /// Adds `entry` under `name`, with `raw` its contents as
+    /// [`JarFile::raw`] returns them.
+    pub fn copy(&mut self, name: &str, entry: &JarEntry, raw: &[u8]) -> io::Result<()> {
+        self.push(
+            JarEntry {
+                name: name.to_string(),
+                // The sizes and CRC go in the local header, so there's no
+                // data descriptor after the contents.
+                flags: 0,
+                local_header_offset: 0,
+                ..entry.clone()
+            },
+            raw,
+        )
+    }
+
+    fn push(&mut self, mut entry: JarEntry, raw: &[u8]) -> io::Result<()> {
+        entry.local_header_offset = zip_u32(self.data.len(), "archive")?;
+        let name_length = zip_u16(entry.name.len(), "entry name")?;
+        let out = &mut self.data;
+        out.extend_from_slice(&LOCAL_HEADER_SIGNATURE.to_le_bytes());
+        out.extend_from_slice(&VERSION_NEEDED.to_le_bytes());
+        out.extend_from_slice(&entry.flags.to_le_bytes());
+        out.extend_from_slice(&entry.method.to_le_bytes());
+        out.extend_from_slice(&0u16.to_le_bytes()); // time
+        out.extend_from_slice(&DOS_EPOCH.to_le_bytes());
+        out.extend_from_slice(&entry.crc32.to_le_bytes());
+        out.extend_from_slice(&entry.compressed_size.to_le_bytes());
+        out.extend_from_slice(&entry.uncompressed_size.to_le_bytes());
+        out.extend_from_slice(&name_length.to_le_bytes());
+        out.extend_from_slice(&0u16.to_le_bytes()); // extra length
+        out.extend_from_slice(entry.name.as_bytes());
+        out.extend_from_slice(raw);
+        self.entries.push(entry);
+        Ok(())
+    }
+
+    /// Writes the central directory, returning the whole archive.
+    pub fn finish(mut self) -> io::Result<Vec<u8>> {
+        let directory_offset = zip_u32(self.data.len(), "archive")?;
+        let entry_count = zip_u16(self.entries.len(), "archive")?;
+        let out = &mut self.data;
+        for entry in &self.entries {
+            out.extend_from_slice(&CENTRAL_HEADER_SIGNATURE.to_le_bytes());
+            out.extend_from_slice(&VERSION_NEEDED.to_le_bytes()); // made by
+            out.extend_from_slice(&VERSION_NEEDED.to_le_bytes());
+            out.extend_from_slice(&entry.flags.to_le_bytes());
+            out.extend_from_slice(&entry.method.to_le_bytes());
+            out.extend_from_slice(&0u16.to_le_bytes()); // time
+            out.extend_from_slice(&DOS_EPOCH.to_le_bytes());
+            out.extend_from_slice(&entry.crc32.to_le_bytes());
+            out.extend_from_slice(&entry.compressed_size.to_le_bytes());
+            out.extend_from_slice(&entry.uncompressed_size.to_le_bytes());
+            out.extend_from_slice(&(entry.name.len() as u16).to_le_bytes());
+            out.extend_from_slice(&[0; 12]); // extra, comment, disk, attributes
+            out.extend_from_slice(&entry.local_header_offset.to_le_bytes());
+            out.extend_from_slice(entry.name.as_bytes());
+        }
+        let directory_size = zip_u32(out.len() - directory_offset as usize, "archive")?;
+        out.extend_from_slice(&END_OF_CENTRAL_DIRECTORY_SIGNATURE.to_le_bytes());
+        out.extend_from_slice(&[0; 4]); // disk numbers
+        out.extend_from_slice(&entry_count.to_le_bytes());
+        out.extend_from_slice(&entry_count.to_le_bytes());
+        out.extend_from_slice(&directory_size.to_le_bytes());
+        out.extend_from_slice(&directory_offset.to_le_bytes());
+        out.extend_from_slice(&0u16.to_le_bytes()); // comment length
+        Ok(self.data)
+    }
+}
+
+fn zip_u16(len: usize, what: &str) -> io::Result<u16> {
+    u16::try_from(len).map_err(|_| too_large(what))
+}
+
+fn zip_u32(len: usize, what: &str) -> io::Result<u32> {
+    u32::try_from(len).map_err(|_| too_large(what))
+}
+
+fn too_large(what: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("{} too large for a jar without zip64", what),
+    )
+}
+
+/// The CRC-32 zip files check entries with, as in ISO 3309.
+pub(crate) fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xEDB8_8320 & (crc & 1).wrapping_neg());
+        }
+    }
+    !crc
+}
+
+/// The entries of the central directory of the archive `data`, and the
+/// offset the directory starts at.
+pub(crate) fn read_central_directory(data: &[u8]) -> Result<(Vec<JarEntry>, u32), JarError> {
+    let eocd = find_end_of_central_directory(data)
+        .ok_or(JarError::Malformed("no end of central directory record"))?;
+    let (entry_count, directory_offset) = parse_end_of_central_directory(&data[eocd..])
+        .map(|(_, fields)| fields)
+        .map_err(|_| JarError::Malformed("truncated end of central directory record"))?;
+
+    let directory = data
+        .get(directory_offset as usize..)
+        .ok_or(JarError::Malformed("central directory offset out of range"))?;
+    let entries = parse_central_directory(directory, entry_count)?;
+    Ok((entries, directory_offset))
+}
+
+/// The `entry_count` entries of the central directory at the start of
+/// `buf`.
+fn parse_central_directory(mut buf: &[u8], entry_count: u16) -> Result<Vec<JarEntry>, JarError> {
+    let mut entries = Vec::with_capacity((entry_count as usize).min(buf.len() / 46));
+    for _ in 0..entry_count {
+        let (rest, entry) =
+            JarEntry::parse(buf).map_err(|_| JarError::Malformed("bad central directory entry"))?;
+        buf = rest;
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+/// The contents of `entry` from the `compressed` form the archive holds.
+pub(crate) fn decompress(entry: &JarEntry, compressed: &[u8]) -> Result<Vec<u8>, JarError> {
+    match entry.method {
+        METHOD_STORED => Ok(compressed.to_vec()),
+        METHOD_DEFLATED => Ok(inflate(compressed, entry.uncompressed_size as usize)?),
+        method => Err(JarError::UnsupportedCompression(method)),
+    }
+}
+
+fn find_end_of_central_directory(data: &[u8]) -> Option<usize> {
+    // The record is 22 bytes plus a comment of at most 64 KiB.
+    let signature = END_OF_CENTRAL_DIRECTORY_SIGNATURE.to_le_bytes();
+    let earliest = data
+        .len()
+        .saturating_sub(END_OF_CENTRAL_DIRECTORY_LEN + 0xFFFF);
+    (earliest..=data.len().checked_sub(END_OF_CENTRAL_DIRECTORY_LEN)?)
+        .rev()
+        .find(|&i| data[i..i + 4] == signature)
+}
+
+fn parse_end_of_central_directory(buf: &[u8]) -> IResult<&[u8], (u16, u32)> {
+    let (buf, _) = tag(END_OF_CENTRAL_DIRECTORY_SIGNATURE.to_le_bytes())(buf)?;
+    let (buf, _disk_number) = le_u16(buf)?;
+    let (buf, _directory_disk) = le_u16(buf)?;
+    let (buf, _disk_entries) = le_u16(buf)?;
+    let (buf, entry_count) = le_u16(buf)?;
+    let (buf, _directory_size) = le_u32(buf)?;
+    let (buf, directory_offset) = le_u32(buf)?;
+    Ok((buf, (entry_count, directory_offset)))
+}
+
+/// Skips over a local file header, returning the data that follows it.
+fn parse_local_header(buf: &[u8]) -> IResult<&[u8], ()> {
+    let (buf, _) = tag(LOCAL_HEADER_SIGNATURE.to_le_bytes())(buf)?;
+    let (buf, _) = take(22usize)(buf)?;
+    let (buf, name_length) = le_u16(buf)?;
+    let (buf, extra_length) = le_u16(buf)?;
+    let (buf, _) = take(name_length as usize + extra_length as usize)(buf)?;
+    Ok((buf, ()))
+}
+
+/// The fields of a local file header, which repeat those the central
+/// directory has for the entry following it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct LocalHeader {
+    pub flags: u16,
+    pub method: u16,
+    pub crc32: u32,
+    pub compressed_size: u32,
+    pub uncompressed_size: u32,
+    pub name: String,
+    /// The length of the header with its name and extra field, up to the
+    /// entry's contents.
+    pub len: usize,
+}
+
+impl LocalHeader {
+    pub fn parse(buf: &[u8]) -> IResult<&[u8], Self> {
+        let start = buf.len();
+        let (buf, _) = tag(LOCAL_HEADER_SIGNATURE.to_le_bytes())(buf)?;
+        let (buf, _version_needed) = le_u16(buf)?;
+        let (buf, flags) = le_u16(buf)?;
+        let (buf, method) = le_u16(buf)?;
+        let (buf, _time) = le_u16(buf)?;
+        let (buf, _date) = le_u16(buf)?;
+        let (buf, crc32) = le_u32(buf)?;
+        let (buf, compressed_size) = le_u32(buf)?;
+        let (buf, uncompressed_size) = le_u32(buf)?;
+        let (buf, name_length) = le_u16(buf)?;
+        let (buf, extra_length) = le_u16(buf)?;
+        let (buf, name) = take(name_length)(buf)?;
+        let (buf, _extra) = take(extra_length)(buf)?;
+
+        Ok((
+            buf,
+            LocalHeader {
+                flags,
+                method,
+                crc32,
+                compressed_size,
+                uncompressed_size,
+                name: String::from_utf8_lossy(name).into_owned(),
+                len: start - buf.len(),
+            },
+        ))
+    }
+
+    /// Whether the CRC and sizes follow the contents in a data descriptor,
+    /// leaving those of the header zero.
+    pub fn has_data_descriptor(&self) -> bool {
+        self.flags & 0x8 != 0
+    }
+}
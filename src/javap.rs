@@ -0,0 +1,849 @@
+//! A listing of a class laid out like the output of `javap -p -v`, which is
+//! what `jvmb <class>` prints: the declaration of the class with its
+//! version and flags, the constant pool with every reference resolved, and
+//! each field and method with its declaration, descriptor and flags. The
+//! code of a method follows it with `max_stack`, `max_locals`, the
+//! instructions and their constants, the exception table and the line
+//! numbers.
+//!
+//! Known deviations from javap:
+//!
+//! - the `Classfile` line, the modification time and the checksum, which
+//!   describe the file rather than the class, are left out,
+//! - of the attributes of fields, methods and code only `Signature`,
+//!   `ConstantValue`, `Code`, `Exceptions` and `LineNumberTable` are
+//!   printed, and of those of the class the ones without an entry of their
+//!   own in javap's listing, such as annotations, only by name,
+//! - the bound of a type parameter is left out when it's `java.lang.Object`,
+//! - `float` and `double` values use the shortest representation that round
+//!   trips, as in [`textify`](crate::textify).
+
+use std::{collections::BTreeMap, fmt::Write};
+
+use crate::{
+    accessflags::*,
+    attribute::{Attribute, Code},
+    classfile::ClassFile,
+    constantpool::{ConstantPool, ReferenceKind},
+    descriptor::MethodDescriptor,
+    fieldinfo::FieldInfo,
+    instruction::{self, Instruction, LOOKUPSWITCH, TABLESWITCH, WIDE},
+    methodinfo::MethodInfo,
+    mutf8, signature,
+    textify::{java_double, java_float},
+};
+
+/// The column comments start at, from the indentation of their line.
+const COMMENT_COLUMN: usize = 40;
+
+#[rustfmt::skip]
+const NEWARRAY_TYPES: [&str; 12] = [
+    "", "", "", "", "boolean", "char", "float", "double", "byte", "short", "int", "long",
+];
+
+const CLASS_MODIFIERS: &[(u16, &str)] = &[
+    (ACC_PUBLIC, "public"),
+    (ACC_FINAL, "final"),
+    (ACC_ABSTRACT, "abstract"),
+];
+
+const INNER_CLASS_MODIFIERS: &[(u16, &str)] = &[
+    (ACC_PUBLIC, "public"),
+    (ACC_PRIVATE, "private"),
+    (ACC_PROTECTED, "protected"),
+    (ACC_STATIC, "static"),
+    (ACC_FINAL, "final"),
+    (ACC_ABSTRACT, "abstract"),
+];
+
+const FIELD_MODIFIERS: &[(u16, &str)] = &[
+    (ACC_PUBLIC, "public"),
+    (ACC_PRIVATE, "private"),
+    (ACC_PROTECTED, "protected"),
+    (ACC_STATIC, "static"),
+    (ACC_FINAL, "final"),
+    (ACC_VOLATILE, "volatile"),
+    (ACC_TRANSIENT, "transient"),
+];
+
+const METHOD_MODIFIERS: &[(u16, &str)] = &[
+    (ACC_PUBLIC, "public"),
+    (ACC_PRIVATE, "private"),
+    (ACC_PROTECTED, "protected"),
+    (ACC_STATIC, "static"),
+    (ACC_FINAL, "final"),
+    (ACC_SYNCHRONIZED, "synchronized"),
+    (ACC_NATIVE, "native"),
+    (ACC_ABSTRACT, "abstract"),
+    (ACC_STRICT, "strictfp"),
+];
+
+pub fn javap(class_file: &ClassFile) -> String {
+    listing(class_file, Javap::class)
+}
+
+/// The declaration of the class, its version, flags and counts, which
+/// head the listing of [`javap`].
+pub fn javap_header(class_file: &ClassFile) -> String {
+    listing(class_file, Javap::header)
+}
+
+/// The constant pool, as [`javap`] lists it.
+pub fn javap_constant_pool(class_file: &ClassFile) -> String {
+    listing(class_file, Javap::constant_pool)
+}
+
+/// One field of `class_file`, as [`javap`] prints it among the others.
+pub fn javap_field(class_file: &ClassFile, field: &FieldInfo) -> String {
+    listing(class_file, |javap| javap.field(field))
+}
+
+/// One method of `class_file` and its code, as [`javap`] prints it among
+/// the others.
+pub fn javap_method(class_file: &ClassFile, method: &MethodInfo) -> String {
+    listing(class_file, |javap| javap.method(method))
+}
+
+/// The instructions of the code of `method`, as [`javap_method`] prints
+/// them, with a `// line <n>` comment before the first instruction of each
+/// line the `LineNumberTable` gives. `None` for a method without code.
+pub fn javap_code(class_file: &ClassFile, method: &MethodInfo) -> Option<String> {
+    let code = method.code()?;
+    Some(listing(class_file, |javap| javap.instructions(code, true)))
+}
+
+/// The attributes of the class, which end the listing of [`javap`].
+pub fn javap_attributes(class_file: &ClassFile) -> String {
+    listing(class_file, Javap::class_attributes)
+}
+
+fn listing<'a>(class_file: &'a ClassFile, print: impl FnOnce(&mut Javap<'a>)) -> String {
+    let mut javap = Javap {
+        out: String::new(),
+        class_file,
+        constant_pool: &class_file.constant_pool,
+    };
+    print(&mut javap);
+    javap.out
+}
+
+struct Javap<'a> {
+    out: String,
+    class_file: &'a ClassFile,
+    constant_pool: &'a [ConstantPool],
+}
+
+impl<'a> Javap<'a> {
+    fn utf8(&self, index: u16) -> &'a str {
+        ConstantPool::utf8(self.constant_pool, index).unwrap_or("?")
+    }
+
+    /// The UTF8 entry at `index` escaped as a string value, whichever form
+    /// the pool holds it in.
+    fn string(&self, index: u16) -> String {
+        self.constant(index)
+            .map_or_else(|| "?".to_string(), escape_constant)
+    }
+
+    fn class_name(&self, index: u16) -> &'a str {
+        ConstantPool::class_name(self.constant_pool, index).unwrap_or("?")
+    }
+
+    fn constant(&self, index: u16) -> Option<&'a ConstantPool> {
+        self.constant_pool.get((index as usize).checked_sub(1)?)
+    }
+
+    /// Writes `text`, then `comment` from [`COMMENT_COLUMN`] past `indent`,
+    /// without the trailing spaces of a string constant.
+    fn commented(&mut self, indent: usize, text: &str, comment: Option<&str>) {
+        match comment {
+            Some(comment) => {
+                let column = indent + COMMENT_COLUMN;
+                let padding = column.saturating_sub(text.chars().count()).max(1);
+                let line = format!("{}{}// {}", text, " ".repeat(padding), comment);
+                let _ = writeln!(self.out, "{}", line.trim_end());
+            }
+            None => {
+                let _ = writeln!(self.out, "{}", text);
+            }
+        }
+    }
+
+    fn class(&mut self) {
+        self.header();
+        self.constant_pool();
+        let class_file = self.class_file;
+        self.out.push_str("{\n");
+        for (i, field) in class_file.fields.iter().enumerate() {
+            if i > 0 {
+                self.out.push('\n');
+            }
+            self.field(field);
+        }
+        for (i, method) in class_file.methods.iter().enumerate() {
+            if i > 0 || !class_file.fields.is_empty() {
+                self.out.push('\n');
+            }
+            self.method(method);
+        }
+        self.out.push_str("}\n");
+        self.class_attributes();
+    }
+
+    fn header(&mut self) {
+        let class_file = self.class_file;
+        let source_file = class_file
+            .attributes
+            .iter()
+            .find_map(|attribute| match attribute {
+                Attribute::SourceFile(source_file) => Some(self.utf8(source_file.sourcefile_index)),
+                _ => None,
+            });
+        if let Some(source_file) = source_file {
+            let _ = writeln!(self.out, "Compiled from \"{}\"", escape(source_file));
+        }
+        let access_flags = class_file.access_flags;
+        let (keyword, modifiers) = if access_flags.is_interface() {
+            (
+                "interface",
+                modifiers(access_flags.bits() & !ACC_ABSTRACT, CLASS_MODIFIERS),
+            )
+        } else {
+            ("class", modifiers(access_flags.bits(), CLASS_MODIFIERS))
+        };
+        let _ = writeln!(
+            self.out,
+            "{}{} {}",
+            modifiers,
+            keyword,
+            signature::best_class_signature(class_file)
+        );
+        let _ = writeln!(self.out, "  minor version: {}", class_file.minor_version);
+        let _ = writeln!(self.out, "  major version: {}", class_file.major_version);
+        self.flags("  ", access_flags.bits(), &access_flags.to_string());
+        let this_class = format!("  this_class: #{}", class_file.this_class);
+        self.commented(2, &this_class, Some(self.class_name(class_file.this_class)));
+        let super_class = format!("  super_class: #{}", class_file.super_class);
+        if class_file.super_class == 0 {
+            self.commented(2, &super_class, None);
+        } else {
+            self.commented(
+                2,
+                &super_class,
+                Some(self.class_name(class_file.super_class)),
+            );
+        }
+        let _ = writeln!(
+            self.out,
+            "  interfaces: {}, fields: {}, methods: {}, attributes: {}",
+            class_file.interfaces.len(),
+            class_file.fields_count,
+            class_file.methods_count,
+            class_file.attributes.len()
+        );
+    }
+
+    fn class_attributes(&mut self) {
+        let class_file = self.class_file;
+        for attribute in &class_file.attributes {
+            match attribute {
+                Attribute::SourceFile(source_file) => {
+                    let source_file = self.utf8(source_file.sourcefile_index);
+                    let _ = writeln!(self.out, "SourceFile: \"{}\"", escape(source_file));
+                }
+                Attribute::Signature(_) => self.signature("", std::slice::from_ref(attribute)),
+                Attribute::NestHost(index) => {
+                    let _ = writeln!(self.out, "NestHost: class {}", self.class_name(*index));
+                }
+                Attribute::NestMembers(classes) | Attribute::PermittedSubclasses(classes) => {
+                    let _ = writeln!(self.out, "{}:", attribute.name());
+                    for &index in classes {
+                        let _ = writeln!(self.out, "  {}", self.class_name(index));
+                    }
+                }
+                Attribute::InnerClasses(inner_classes) => {
+                    self.out.push_str("InnerClasses:\n");
+                    for class in &inner_classes.classes {
+                        let flags = class.inner_class_access_flags.bits();
+                        let mut text = format!("  {}", modifiers(flags, INNER_CLASS_MODIFIERS));
+                        let mut comment = String::new();
+                        if class.inner_name_index != 0 {
+                            let _ = write!(text, "#{}= ", class.inner_name_index);
+                            let _ = write!(comment, "{}=", self.utf8(class.inner_name_index));
+                        }
+                        let _ = write!(text, "#{}", class.inner_class_info_index);
+                        let _ = write!(
+                            comment,
+                            "class {}",
+                            self.class_name(class.inner_class_info_index)
+                        );
+                        if class.outer_class_info_index != 0 {
+                            let _ = write!(text, " of #{}", class.outer_class_info_index);
+                            let _ = write!(
+                                comment,
+                                " of class {}",
+                                self.class_name(class.outer_class_info_index)
+                            );
+                        }
+                        text.push(';');
+                        self.commented(2, &text, Some(&comment));
+                    }
+                }
+                Attribute::EnclosingMethod(enclosing_method) => {
+                    let text = format!(
+                        "EnclosingMethod: #{}.#{}",
+                        enclosing_method.class_index, enclosing_method.method_index
+                    );
+                    let mut comment = self.class_name(enclosing_method.class_index).to_string();
+                    if let Some((name, _)) = ConstantPool::name_and_type(
+                        self.constant_pool,
+                        enclosing_method.method_index,
+                    ) {
+                        let _ = write!(comment, ".{}", name);
+                    }
+                    self.commented(0, &text, Some(&comment));
+                }
+                Attribute::BootstrapMethods(methods) => {
+                    self.out.push_str("BootstrapMethods:\n");
+                    for (i, method) in methods.iter().enumerate() {
+                        let handle = match self.constant(method.bootstrap_method_ref) {
+                            Some(ConstantPool::MethodHandle(reference_kind, reference_index)) => {
+                                self.method_handle(*reference_kind, *reference_index)
+                            }
+                            _ => "?".to_string(),
+                        };
+                        let _ = writeln!(
+                            self.out,
+                            "  {}: #{} {}",
+                            i, method.bootstrap_method_ref, handle
+                        );
+                        self.out.push_str("    Method arguments:\n");
+                        for &argument in &method.bootstrap_arguments {
+                            let value = match self.constant(argument) {
+                                Some(constant) => {
+                                    let (operands, comment) = self.describe(constant);
+                                    comment.unwrap_or(operands)
+                                }
+                                None => "?".to_string(),
+                            };
+                            let _ = writeln!(self.out, "      #{} {}", argument, value);
+                        }
+                    }
+                }
+                Attribute::Deprecated(_) => self.out.push_str("Deprecated: true\n"),
+                attribute => {
+                    let _ = writeln!(self.out, "{}", attribute.name());
+                }
+            }
+        }
+    }
+
+    fn flags(&mut self, indent: &str, bits: u16, names: &str) {
+        let _ = writeln!(
+            self.out,
+            "{}flags: (0x{:04x}){}{}",
+            indent,
+            bits,
+            if bits == 0 { "" } else { " " },
+            names
+        );
+    }
+
+    fn constant_pool(&mut self) {
+        self.out.push_str("Constant pool:\n");
+        let width = 1 + (self.constant_pool.len() + 1).to_string().len();
+        for (i, constant) in self.constant_pool.iter().enumerate() {
+            if *constant == ConstantPool::Unusable {
+                continue;
+            }
+            let index = format!("#{}", i + 1);
+            let (operands, comment) = self.describe(constant);
+            let text = format!(
+                "  {:>width$} = {:<18} {}",
+                index,
+                constant.kind_name(),
+                operands,
+                width = width
+            );
+            self.commented(2, text.trim_end(), comment.as_deref());
+        }
+    }
+
+    /// The operands of a pool entry as javap prints them, and the comment
+    /// resolving its references.
+    fn describe(&self, constant: &ConstantPool) -> (String, Option<String>) {
+        match constant {
+            ConstantPool::UTF8(_) | ConstantPool::Utf16(_) | ConstantPool::RawUtf8(_) => {
+                (escape_constant(constant), None)
+            }
+            ConstantPool::Integer(value) => (value.to_string(), None),
+            ConstantPool::Float(bits) => (format!("{}f", java_float(bits.value())), None),
+            ConstantPool::Long(value) => (format!("{}l", value), None),
+            ConstantPool::Double(bits) => (format!("{}d", java_double(bits.value())), None),
+            ConstantPool::Class(index) => (
+                format!("#{}", index),
+                Some(class_comment(self.utf8(*index))),
+            ),
+            ConstantPool::String(index) => (format!("#{}", index), Some(self.string(*index))),
+            ConstantPool::MethodType(index)
+            | ConstantPool::Module(index)
+            | ConstantPool::Package(index) => {
+                (format!("#{}", index), Some(self.utf8(*index).to_string()))
+            }
+            ConstantPool::FieldRef(class_index, name_and_type_index)
+            | ConstantPool::MethodRef(class_index, name_and_type_index)
+            | ConstantPool::InterfaceMethodRef(class_index, name_and_type_index) => (
+                format!("#{}.#{}", class_index, name_and_type_index),
+                Some(format!(
+                    "{}.{}",
+                    class_comment(self.class_name(*class_index)),
+                    self.name_and_type(*name_and_type_index)
+                )),
+            ),
+            ConstantPool::NameAndType(name_index, descriptor_index) => (
+                format!("#{}:#{}", name_index, descriptor_index),
+                Some(format!(
+                    "{}:{}",
+                    quote_name(self.utf8(*name_index)),
+                    self.utf8(*descriptor_index)
+                )),
+            ),
+            ConstantPool::MethodHandle(reference_kind, reference_index) => (
+                format!("{}:#{}", reference_kind, reference_index),
+                Some(self.method_handle(*reference_kind, *reference_index)),
+            ),
+            ConstantPool::Dynamic(bootstrap_index, name_and_type_index)
+            | ConstantPool::InvokeDynamic(bootstrap_index, name_and_type_index) => (
+                format!("#{}:#{}", bootstrap_index, name_and_type_index),
+                Some(format!(
+                    "#{}:{}",
+                    bootstrap_index,
+                    self.name_and_type(*name_and_type_index)
+                )),
+            ),
+            ConstantPool::Unknown { tag, body } => (
+                body.iter().map(|byte| format!("{:02x}", byte)).collect(),
+                Some(format!("unknown tag {}", tag)),
+            ),
+            ConstantPool::Unusable => (String::new(), None),
+        }
+    }
+
+    /// The `NameAndType` entry at `index` as `name:descriptor`.
+    fn name_and_type(&self, index: u16) -> String {
+        match ConstantPool::name_and_type(self.constant_pool, index) {
+            Some((name, descriptor)) => format!("{}:{}", quote_name(name), descriptor),
+            None => "?".to_string(),
+        }
+    }
+
+    /// The member a `Fieldref`, `Methodref` or `InterfaceMethodref` entry
+    /// refers to, as `class.name:descriptor`, leaving out the class when
+    /// it's this one and `qualify` isn't set.
+    fn member(&self, index: u16, qualify: bool) -> String {
+        match ConstantPool::member_ref(self.constant_pool, index) {
+            Some(member) => {
+                let name = format!("{}:{}", quote_name(member.name), member.descriptor);
+                let this_class = self.class_name(self.class_file.this_class);
+                if qualify || member.class_name != this_class {
+                    format!("{}.{}", class_comment(member.class_name), name)
+                } else {
+                    name
+                }
+            }
+            None => "?".to_string(),
+        }
+    }
+
+    fn method_handle(&self, reference_kind: u8, reference_index: u16) -> String {
+        let kind = ReferenceKind::from_tag(reference_kind).map_or("?", ReferenceKind::name);
+        format!("{} {}", kind, self.member(reference_index, true))
+    }
+
+    fn field(&mut self, field: &FieldInfo) {
+        let access_flags = field.access_flags();
+        let java_type = signature::best_field_signature(self.class_file, field)
+            .map_or_else(|| "?".to_string(), |java_type| java_type.to_string());
+        let _ = writeln!(
+            self.out,
+            "  {}{} {};",
+            modifiers(access_flags.bits(), FIELD_MODIFIERS),
+            java_type,
+            self.utf8(field.name_index())
+        );
+        let _ = writeln!(
+            self.out,
+            "    descriptor: {}",
+            self.utf8(field.descriptor_index())
+        );
+        self.flags("    ", access_flags.bits(), &access_flags.to_string());
+        if let Some(index) = field.constant_value_index() {
+            let value = self.ldc_comment(index);
+            let _ = writeln!(self.out, "    ConstantValue: {}", value);
+        }
+        self.signature("    ", field.attributes());
+    }
+
+    /// The `Signature` among `attributes`, if any, as its index resolved.
+    fn signature(&mut self, indent: &str, attributes: &[Attribute]) {
+        if let Some(signature) = Attribute::signature(attributes) {
+            let index = signature.signature_index;
+            let text = format!("{}Signature: #{}", indent, index);
+            self.commented(indent.len(), &text, Some(self.utf8(index)));
+        }
+    }
+
+    fn method(&mut self, method: &MethodInfo) {
+        let access_flags = method.access_flags();
+        let mut declaration = match signature::best_method_signature(self.class_file, method) {
+            Some(signature) => signature.to_string(),
+            None => format!(
+                "{}{}",
+                self.utf8(method.name_index()),
+                self.utf8(method.descriptor_index())
+            ),
+        };
+        if access_flags.is_varargs() {
+            if let Some(at) = declaration.rfind("[])") {
+                declaration.replace_range(at..at + 2, "...");
+            }
+        }
+        // the declaration of a static initializer is `static {}` already
+        let modifiers = if self.utf8(method.name_index()) == "<clinit>" {
+            String::new()
+        } else {
+            modifiers(access_flags.bits(), METHOD_MODIFIERS)
+        };
+        let _ = writeln!(self.out, "  {}{};", modifiers, declaration);
+        let descriptor = self.utf8(method.descriptor_index());
+        let _ = writeln!(self.out, "    descriptor: {}", descriptor);
+        self.flags("    ", access_flags.bits(), &access_flags.to_string());
+        if let Some(code) = method.code() {
+            let args_size = MethodDescriptor::parse(descriptor).map(|descriptor| {
+                let receiver = usize::from(!access_flags.is_static());
+                receiver + descriptor.parameters.len()
+            });
+            self.code(code, args_size.ok());
+        }
+        if let Some(exceptions) = method.exceptions() {
+            self.out.push_str("    Exceptions:\n");
+            let names: Vec<String> = exceptions
+                .exception_index_table
+                .iter()
+                .map(|&index| self.class_name(index).replace('/', "."))
+                .collect();
+            let _ = writeln!(self.out, "      throws {}", names.join(", "));
+        }
+        self.signature("    ", method.attributes());
+    }
+
+    fn code(&mut self, code: &Code, args_size: Option<usize>) {
+        self.out.push_str("    Code:\n");
+        let _ = writeln!(
+            self.out,
+            "      stack={}, locals={}, args_size={}",
+            code.max_stack,
+            code.max_locals,
+            args_size.map_or_else(|| "?".to_string(), |size| size.to_string())
+        );
+        self.instructions(code, false);
+        if !code.exception_table.is_empty() {
+            self.out.push_str("      Exception table:\n");
+            self.out.push_str("         from    to  target type\n");
+            for exception in &code.exception_table {
+                let catch_type = if exception.catch_type == 0 {
+                    "any".to_string()
+                } else {
+                    format!("Class {}", self.class_name(exception.catch_type))
+                };
+                let _ = writeln!(
+                    self.out,
+                    "         {:>5} {:>5} {:>5}   {}",
+                    exception.start_pc, exception.end_pc, exception.handler_pc, catch_type
+                );
+            }
+        }
+        if let Some(lines) = code.line_number_table() {
+            self.out.push_str("      LineNumberTable:\n");
+            for line in lines {
+                let _ = writeln!(
+                    self.out,
+                    "        line {}: {}",
+                    line.line_number, line.start_pc
+                );
+            }
+        }
+    }
+
+    /// The instructions of `code`, with `lines` the lines they start
+    /// interleaved as comments.
+    fn instructions(&mut self, code: &Code, lines: bool) {
+        let mut line_starts: BTreeMap<u16, Vec<u16>> = BTreeMap::new();
+        if lines {
+            for line in code.line_number_table().into_iter().flatten() {
+                line_starts
+                    .entry(line.start_pc)
+                    .or_default()
+                    .push(line.line_number);
+            }
+        }
+        for instruction in instruction::decode(&code.code) {
+            match instruction {
+                Ok(instruction) => {
+                    let offset = u16::try_from(instruction.offset).ok();
+                    for line in offset
+                        .and_then(|offset| line_starts.get(&offset))
+                        .into_iter()
+                        .flatten()
+                    {
+                        let _ = writeln!(self.out, "      // line {}", line);
+                    }
+                    self.instruction(&instruction)
+                }
+                Err(error) => {
+                    let _ = writeln!(self.out, "      // {}", error);
+                }
+            }
+        }
+    }
+
+    fn instruction(&mut self, instruction: &Instruction) {
+        let opcode = instruction.opcode;
+        let operands = instruction.operands;
+        let mnemonic = instruction.mnemonic();
+        let u16_operand = || u16::from_be_bytes([operands[0], operands[1]]);
+        let index = instruction.cp_index().unwrap_or(0);
+        let (mnemonic, text, comment) = match opcode {
+            // bipush, sipush
+            0x10 => (mnemonic, (operands[0] as i8).to_string(), None),
+            0x11 => (mnemonic, (u16_operand() as i16).to_string(), None),
+            // ldc, ldc_w, ldc2_w
+            0x12..=0x14 => (
+                mnemonic,
+                format!("#{}", index),
+                Some(self.ldc_comment(index)),
+            ),
+            // iload..aload, istore..astore, ret
+            0x15..=0x19 | 0x36..=0x3a | 0xa9 => (mnemonic, operands[0].to_string(), None),
+            0x84 => (
+                mnemonic,
+                format!("{}, {}", operands[0], operands[1] as i8),
+                None,
+            ),
+            // if*, goto, jsr, ifnull, ifnonnull, goto_w, jsr_w
+            0x99..=0xa8 | 0xc6..=0xc9 => {
+                let target = instruction.branch_targets()[0];
+                (mnemonic, target.to_string(), None)
+            }
+            TABLESWITCH | LOOKUPSWITCH => {
+                self.switch(instruction);
+                return;
+            }
+            // getstatic, putstatic, getfield, putfield
+            0xb2..=0xb5 => (
+                mnemonic,
+                format!("#{}", index),
+                Some(format!("Field {}", self.member(index, false))),
+            ),
+            // invokevirtual, invokespecial, invokestatic
+            0xb6..=0xb8 => {
+                let kind = match self.constant(index) {
+                    Some(ConstantPool::InterfaceMethodRef(..)) => "InterfaceMethod",
+                    _ => "Method",
+                };
+                (
+                    mnemonic,
+                    format!("#{}", index),
+                    Some(format!("{} {}", kind, self.member(index, false))),
+                )
+            }
+            0xb9 => (
+                mnemonic,
+                format!("#{},  {}", index, operands[2]),
+                Some(format!("InterfaceMethod {}", self.member(index, false))),
+            ),
+            0xba => {
+                let comment = match self.constant(index) {
+                    Some(ConstantPool::InvokeDynamic(bootstrap_index, name_and_type_index)) => {
+                        format!(
+                            "InvokeDynamic #{}:{}",
+                            bootstrap_index,
+                            self.name_and_type(*name_and_type_index)
+                        )
+                    }
+                    _ => "?".to_string(),
+                };
+                (mnemonic, format!("#{},  0", index), Some(comment))
+            }
+            // new, anewarray, checkcast, instanceof
+            0xbb | 0xbd | 0xc0 | 0xc1 => (
+                mnemonic,
+                format!("#{}", index),
+                Some(format!("class {}", class_comment(self.class_name(index)))),
+            ),
+            0xbc => {
+                let kind = NEWARRAY_TYPES.get(operands[0] as usize).copied();
+                (mnemonic, kind.unwrap_or("?").to_string(), None)
+            }
+            0xc5 => (
+                mnemonic,
+                format!("#{},  {}", index, operands[2]),
+                Some(format!("class {}", class_comment(self.class_name(index)))),
+            ),
+            // the instruction `wide` modifies, as e.g. `iload_w`
+            WIDE => {
+                let index = u16::from_be_bytes([operands[1], operands[2]]);
+                let mnemonic = instruction::mnemonic(operands[0]).unwrap_or("<invalid>");
+                let mnemonic = format!("{}_w", mnemonic);
+                let text = if operands[0] == instruction::IINC {
+                    let increment = i16::from_be_bytes([operands[3], operands[4]]);
+                    format!("{}, {}", index, increment)
+                } else {
+                    index.to_string()
+                };
+                let _ = writeln!(
+                    self.out,
+                    "{:>10}: {:<13} {}",
+                    instruction.offset, mnemonic, text
+                );
+                return;
+            }
+            _ => (mnemonic, String::new(), None),
+        };
+        let line = if text.is_empty() {
+            format!("{:>10}: {}", instruction.offset, mnemonic)
+        } else {
+            format!("{:>10}: {:<13} {}", instruction.offset, mnemonic, text)
+        };
+        self.commented(6, &line, comment.as_deref());
+    }
+
+    fn switch(&mut self, instruction: &Instruction) {
+        let operands = instruction.operands;
+        let pad = (3 - instruction.offset as usize % 4) % 4;
+        let word = |i: usize| read_i32(operands, pad + i * 4);
+        let targets = instruction.branch_targets();
+        let (keys, summary): (Vec<i32>, String) = if instruction.opcode == TABLESWITCH {
+            let (low, high) = (word(1), word(2));
+            ((low..=high).collect(), format!("{} to {}", low, high))
+        } else {
+            let npairs = word(1) as usize;
+            let keys = (0..npairs).map(|i| word(2 + i * 2)).collect();
+            (keys, npairs.to_string())
+        };
+        let _ = writeln!(
+            self.out,
+            "{:>10}: {:<13} {{ // {}",
+            instruction.offset,
+            instruction.mnemonic(),
+            summary
+        );
+        for (key, target) in keys.iter().zip(&targets[1..]) {
+            let _ = writeln!(self.out, "{:>24}: {}", key, target);
+        }
+        let _ = writeln!(self.out, "{:>24}: {}", "default", targets[0]);
+        let _ = writeln!(self.out, "{:>13}", "}");
+    }
+
+    /// The loadable constant at `index` with its kind, e.g. `String hello`
+    /// or `int 1`.
+    fn ldc_comment(&self, index: u16) -> String {
+        match self.constant(index) {
+            Some(ConstantPool::Integer(value)) => format!("int {}", value),
+            Some(ConstantPool::Float(bits)) => format!("float {}f", java_float(bits.value())),
+            Some(ConstantPool::Long(value)) => format!("long {}l", value),
+            Some(ConstantPool::Double(bits)) => format!("double {}d", java_double(bits.value())),
+            Some(ConstantPool::String(index)) => format!("String {}", self.string(*index)),
+            Some(ConstantPool::Class(index)) => {
+                format!("class {}", class_comment(self.utf8(*index)))
+            }
+            Some(ConstantPool::MethodType(index)) => format!("MethodType {}", self.utf8(*index)),
+            Some(ConstantPool::MethodHandle(reference_kind, reference_index)) => format!(
+                "MethodHandle {}",
+                self.method_handle(*reference_kind, *reference_index)
+            ),
+            Some(ConstantPool::Dynamic(bootstrap_index, name_and_type_index)) => format!(
+                "Dynamic #{}:{}",
+                bootstrap_index,
+                self.name_and_type(*name_and_type_index)
+            ),
+            _ => "?".to_string(),
+        }
+    }
+}
+
+/// The keywords of the flags set in `bits`, each followed by a space.
+fn modifiers(bits: u16, keywords: &[(u16, &str)]) -> String {
+    keywords
+        .iter()
+        .filter(|&&(flag, _)| bits & flag != 0)
+        .map(|&(_, keyword)| format!("{} ", keyword))
+        .collect()
+}
+
+/// A class name as javap writes it in comments: quoted for an array type.
+fn class_comment(name: &str) -> String {
+    if name.starts_with('[') {
+        format!("\"{}\"", name)
+    } else {
+        name.to_string()
+    }
+}
+
+/// A member name, quoted unless it's a Java identifier, as `"<init>"` is.
+fn quote_name(name: &str) -> String {
+    let is_identifier = name
+        .chars()
+        .next()
+        .is_some_and(|first| !first.is_ascii_digit())
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '$');
+    if is_identifier {
+        name.to_string()
+    } else {
+        format!("\"{}\"", name)
+    }
+}
+
+/// The string a UTF8 entry holds, escaped, or `?` for any other entry.
+/// An unpaired surrogate of a [`ConstantPool::Utf16`] entry is written as
+/// `\uXXXX` like any other code unit outside printable ASCII.
+fn escape_constant(constant: &ConstantPool) -> String {
+    match constant {
+        ConstantPool::UTF8(value) => escape(value),
+        ConstantPool::Utf16(units) => escape_utf16(units.iter().copied()),
+        ConstantPool::RawUtf8(bytes) => escape(&mutf8::decode_lossy(bytes)),
+        _ => "?".to_string(),
+    }
+}
+
+/// Escapes control characters, quotes and backslashes, and writes the
+/// UTF-16 code units of anything outside printable ASCII as `\uXXXX`.
+fn escape(value: &str) -> String {
+    escape_utf16(value.encode_utf16())
+}
+
+/// [`escape`] for UTF-16 code units, which may be unpaired surrogates.
+fn escape_utf16(units: impl Iterator<Item = u16>) -> String {
+    let mut out = String::new();
+    for unit in units {
+        match unit {
+            0x09 => out.push_str("\\t"),
+            0x0a => out.push_str("\\n"),
+            0x0d => out.push_str("\\r"),
+            0x22 => out.push_str("\\\""),
+            0x27 => out.push_str("\\'"),
+            0x5c => out.push_str("\\\\"),
+            0x20..=0x7e => out.push(unit as u8 as char),
+            _ => {
+                let _ = write!(out, "\\u{:04x}", unit);
+            }
+        }
+    }
+    out
+}
+
+fn read_i32(buf: &[u8], at: usize) -> i32 {
+    i32::from_be_bytes([buf[at], buf[at + 1], buf[at + 2], buf[at + 3]])
+}
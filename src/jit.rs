@@ -0,0 +1,124 @@
+//! Methods too large for HotSpot's JIT compiler to treat well, going by
+//! the size of their bytecode alone.
+//!
+//! With its defaults, HotSpot never compiles a method of more than 8000
+//! bytes (`HugeMethodLimit`, as long as `DontCompileHugeMethods` is on), so
+//! it stays interpreted however hot it gets, and never inlines one of more
+//! than 325 bytes (`FreqInlineSize`), however often it's called. The latter
+//! is worth telling apart for constructors: generated builders and data
+//! classes easily grow constructors past it, and every allocation through
+//! them then pays for a call.
+
+use std::fmt;
+
+use crate::{classfile::ClassFile, constantpool::ConstantPool};
+
+/// The sizes, in bytes of bytecode, past which a method is reported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JitThresholds {
+    /// HotSpot's `HugeMethodLimit`.
+    pub huge_method: u32,
+    /// HotSpot's `FreqInlineSize` on x86-64 and AArch64.
+    pub inline: u32,
+}
+
+impl Default for JitThresholds {
+    fn default() -> Self {
+        JitThresholds {
+            huge_method: 8000,
+            inline: 325,
+        }
+    }
+}
+
+/// The threshold a method crosses; only the largest is reported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum JitThreshold {
+    /// Too large to be compiled at all.
+    Huge,
+    /// Too large to be inlined.
+    NotInlinable,
+    /// A constructor too large to be inlined.
+    ConstructorNotInlinable,
+}
+
+impl JitThreshold {
+    pub fn name(self) -> &'static str {
+        match self {
+            JitThreshold::Huge => "huge",
+            JitThreshold::NotInlinable => "not-inlinable",
+            JitThreshold::ConstructorNotInlinable => "constructor-not-inlinable",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JitFinding {
+    pub method_name: String,
+    pub method_descriptor: String,
+    /// The length of the method's bytecode.
+    pub code_length: u32,
+    pub threshold: JitThreshold,
+    /// The size it's over.
+    pub limit: u32,
+}
+
+impl fmt::Display for JitFinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.threshold {
+            JitThreshold::Huge => write!(
+                f,
+                "{} bytes of code, more than the {} HotSpot will compile (HugeMethodLimit)",
+                self.code_length, self.limit
+            ),
+            JitThreshold::NotInlinable => write!(
+                f,
+                "{} bytes of code, more than the {} HotSpot will inline (FreqInlineSize)",
+                self.code_length, self.limit
+            ),
+            JitThreshold::ConstructorNotInlinable => write!(
+                f,
+                "constructor of {} bytes of code, more than the {} HotSpot will inline \
+                 (FreqInlineSize), so every allocation through it makes a call",
+                self.code_length, self.limit
+            ),
+        }
+    }
+}
+
+/// The methods of the class over `thresholds`, in declaration order,
+/// leaving out the static initializer.
+pub fn check_jit(class_file: &ClassFile, thresholds: &JitThresholds) -> Vec<JitFinding> {
+    let constant_pool = &class_file.constant_pool;
+    let mut findings = Vec::new();
+    for method in &class_file.methods {
+        let code_length = match method.code() {
+            Some(code) => code.code.len() as u32,
+            None => continue,
+        };
+        let method_name = ConstantPool::utf8(constant_pool, method.name_index()).unwrap_or("?");
+        // static initializers run once, interpreted either way
+        if method_name == "<clinit>" {
+            continue;
+        }
+        let (threshold, limit) = if code_length > thresholds.huge_method {
+            (JitThreshold::Huge, thresholds.huge_method)
+        } else if code_length > thresholds.inline && method_name == "<init>" {
+            (JitThreshold::ConstructorNotInlinable, thresholds.inline)
+        } else if code_length > thresholds.inline {
+            (JitThreshold::NotInlinable, thresholds.inline)
+        } else {
+            continue;
+        };
+        findings.push(JitFinding {
+            method_name: method_name.to_string(),
+            method_descriptor: ConstantPool::utf8(constant_pool, method.descriptor_index())
+                .unwrap_or("?")
+                .to_string(),
+            code_length,
+            threshold,
+            limit,
+        });
+    }
+    findings
+}
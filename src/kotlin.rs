@@ -0,0 +1,157 @@
+//! The `kotlin.Metadata` annotation kotlinc puts on every class it writes,
+//! which says what the class is to Kotlin: a class, the facade holding the
+//! top-level declarations of a file, a lambda, or one part of a facade
+//! spread over several files with `@JvmMultifileClass`.
+//!
+//! The declarations themselves are in `d1`, a protobuf message encoded into
+//! strings, which isn't decoded here; `d2` holds the strings it refers to.
+//!
+//! ```no_run
+//! use jvmb::{classfile::ClassFile, kotlin::KotlinKind};
+//!
+//! let bytes = std::fs::read("UtilsKt.class").unwrap();
+//! let (_, class_file) = ClassFile::parse_class_file(&bytes).unwrap();
+//! if let Some(metadata) = class_file.kotlin_metadata() {
+//!     if metadata.kind == KotlinKind::FileFacade {
+//!         println!("top-level declarations of a Kotlin file");
+//!     }
+//! }
+//! ```
+
+use core::fmt;
+
+use crate::attribute::ResolvedAnnotation;
+use crate::prelude::*;
+
+/// The descriptor of the annotation.
+pub const METADATA_DESCRIPTOR: &str = "Lkotlin/Metadata;";
+
+/// What a class is to Kotlin, from the `k` element.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KotlinKind {
+    /// A class, interface, object or the like, with its declarations.
+    Class,
+    /// The top-level declarations of a file, e.g. `UtilsKt`.
+    FileFacade,
+    /// A class with nothing to declare to Kotlin, such as a lambda or
+    /// `WhenMappings`.
+    SyntheticClass,
+    /// The class which the parts of a `@JvmMultifileClass` facade are
+    /// called through, naming its parts in `d1`.
+    MultiFileClassFacade,
+    /// The declarations of one file of a `@JvmMultifileClass` facade, which
+    /// names the facade in `xs`.
+    MultiFileClassPart,
+    /// A kind newer than jvmb knows.
+    Unknown(i32),
+}
+
+impl KotlinKind {
+    pub fn from_k(k: i32) -> Self {
+        match k {
+            1 => KotlinKind::Class,
+            2 => KotlinKind::FileFacade,
+            3 => KotlinKind::SyntheticClass,
+            4 => KotlinKind::MultiFileClassFacade,
+            5 => KotlinKind::MultiFileClassPart,
+            k => KotlinKind::Unknown(k),
+        }
+    }
+}
+
+impl fmt::Display for KotlinKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KotlinKind::Class => f.write_str("class"),
+            KotlinKind::FileFacade => f.write_str("file facade"),
+            KotlinKind::SyntheticClass => f.write_str("synthetic class"),
+            KotlinKind::MultiFileClassFacade => f.write_str("multi-file class facade"),
+            KotlinKind::MultiFileClassPart => f.write_str("multi-file class part"),
+            KotlinKind::Unknown(k) => write!(f, "unknown kind {}", k),
+        }
+    }
+}
+
+/// The elements of a `kotlin.Metadata` annotation, with the defaults of
+/// those left out filled in.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct KotlinMetadata<'a> {
+    /// `k`.
+    pub kind: KotlinKind,
+    /// `mv`, e.g. `[1, 9, 0]`.
+    pub metadata_version: Vec<i32>,
+    /// `d1`, the protobuf message of the declarations, or the internal
+    /// names of the parts of a multi-file class facade.
+    pub data1: Vec<&'a str>,
+    /// `d2`, the strings `d1` refers to.
+    pub data2: Vec<&'a str>,
+    /// `xs`, the internal name of the facade of a multi-file class part;
+    /// empty when not given.
+    pub extra_string: &'a str,
+    /// `pn`, the Kotlin package of the class where it differs from the JVM
+    /// one; empty when not given.
+    pub package_name: &'a str,
+    /// `xi`, flags.
+    pub extra_int: i32,
+}
+
+impl<'a> KotlinMetadata<'a> {
+    /// The metadata `annotation` holds, if it's a `kotlin.Metadata`. An
+    /// element of the wrong type is taken as left out.
+    pub fn from_annotation(annotation: &ResolvedAnnotation<'a>) -> Option<Self> {
+        if annotation.descriptor != METADATA_DESCRIPTOR {
+            return None;
+        }
+        let strings = |name| {
+            annotation
+                .element(name)
+                .and_then(|value| value.as_str_array())
+                .unwrap_or_default()
+        };
+        let string = |name| {
+            annotation
+                .element(name)
+                .and_then(|value| value.as_str())
+                .unwrap_or_default()
+        };
+        let int = |name| annotation.element(name).and_then(|value| value.as_int());
+        Some(KotlinMetadata {
+            kind: KotlinKind::from_k(int("k").unwrap_or(1)),
+            metadata_version: annotation
+                .element("mv")
+                .and_then(|value| value.as_int_array())
+                .unwrap_or_default(),
+            data1: strings("d1"),
+            data2: strings("d2"),
+            extra_string: string("xs"),
+            package_name: string("pn"),
+            extra_int: int("xi").unwrap_or(0),
+        })
+    }
+
+    /// `mv` as Kotlin writes versions, e.g. `1.9.0`.
+    pub fn version(&self) -> String {
+        let parts: Vec<String> = self.metadata_version.iter().map(i32::to_string).collect();
+        parts.join(".")
+    }
+
+    /// The internal names of the parts of a multi-file class facade, e.g.
+    /// `kotlin/collections/CollectionsKt___CollectionsKt`; empty for any
+    /// other kind.
+    pub fn multi_file_parts(&self) -> &[&'a str] {
+        match self.kind {
+            KotlinKind::MultiFileClassFacade => &self.data1,
+            _ => &[],
+        }
+    }
+
+    /// The internal name of the facade of a multi-file class part.
+    pub fn multi_file_facade(&self) -> Option<&'a str> {
+        match self.kind {
+            KotlinKind::MultiFileClassPart if !self.extra_string.is_empty() => {
+                Some(self.extra_string)
+            }
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,99 @@
+//! How the bytes of a method body are spent, instruction by instruction.
+//! Useful when a generated method runs up against the 64 KiB code limit.
+
+use crate::{
+    attribute::Code,
+    instruction::{self, DecodeError, Instruction, WIDE},
+};
+
+/// What an instruction's bytes are counted towards in a [`LayoutSummary`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InstructionCategory {
+    /// The `invoke*` instructions.
+    Invocation,
+    /// Conditional and unconditional jumps, subroutine calls and the
+    /// switches.
+    Branch,
+    /// Local variable and array element loads and stores.
+    LoadStore,
+    Other,
+}
+
+impl InstructionCategory {
+    pub fn of(instruction: &Instruction) -> Self {
+        // a wide instruction is categorized by the opcode it widens
+        let opcode = match instruction.opcode {
+            WIDE => instruction.operands.first().copied().unwrap_or(WIDE),
+            opcode => opcode,
+        };
+        match opcode {
+            0xb6..=0xba => InstructionCategory::Invocation,
+            0x99..=0xab | 0xc6..=0xc9 => InstructionCategory::Branch,
+            0x15..=0x56 => InstructionCategory::LoadStore,
+            _ => InstructionCategory::Other,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct InstructionLayout {
+    pub offset: u32,
+    pub mnemonic: &'static str,
+    /// The encoded length, including switch padding and any `wide` prefix.
+    pub size: usize,
+    /// The share of the code array taken up by this instruction and all
+    /// those before it, from 0 to 100.
+    pub cumulative_percent: f64,
+    pub category: InstructionCategory,
+}
+
+/// Bytes of code spent on each [`InstructionCategory`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LayoutSummary {
+    pub total: usize,
+    pub invocations: usize,
+    pub branches: usize,
+    pub loads_stores: usize,
+    pub other: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CodeLayout {
+    pub instructions: Vec<InstructionLayout>,
+    pub summary: LayoutSummary,
+}
+
+impl CodeLayout {
+    pub fn build(code: &Code) -> Result<Self, DecodeError> {
+        let total = code.code.len();
+        let mut summary = LayoutSummary {
+            total,
+            ..LayoutSummary::default()
+        };
+        let mut instructions = Vec::new();
+        let mut cumulative = 0;
+        for instruction in instruction::decode(&code.code) {
+            let instruction = instruction?;
+            let size = instruction.size();
+            let category = InstructionCategory::of(&instruction);
+            *match category {
+                InstructionCategory::Invocation => &mut summary.invocations,
+                InstructionCategory::Branch => &mut summary.branches,
+                InstructionCategory::LoadStore => &mut summary.loads_stores,
+                InstructionCategory::Other => &mut summary.other,
+            } += size;
+            cumulative += size;
+            instructions.push(InstructionLayout {
+                offset: instruction.offset,
+                mnemonic: instruction.mnemonic(),
+                size,
+                cumulative_percent: cumulative as f64 * 100.0 / total as f64,
+                category,
+            });
+        }
+        Ok(CodeLayout {
+            instructions,
+            summary,
+        })
+    }
+}
@@ -0,0 +1,202 @@
+//! A parser for JVM class files, with the analyses and tools built on it.
+//!
+//! Everything past the parser sits behind a cargo feature, so that a crate
+//! which only reads classes can build with `default-features = false`:
+//!
+//! | feature    | requires                                           | adds |
+//! |------------|----------------------------------------------------|------|
+//! | `core`     |                                                    | the parser and class file model, its [`borrowed`] form, JSON export |
+//! | `std`      | `core`                                             | [`input`] and the other file IO, attribute parsers of the caller's own, parse timings |
+//! | `disasm`   | `core`                                             | [`instruction`] decoding, [`javap`], [`textify`], [`layout`], [`stackmap`], [`symbols`], [`xref`], [`callgraph`], [`codemetrics`] |
+//! | `analysis` | `disasm`                                           | [`cfg`], [`frames`], [`verify`], [`pattern`], [`clones`] and the other analyses |
+//! | `write`    | `disasm`                                           | [`ClassFile::to_bytes`](classfile::ClassFile::to_bytes), editing, [`builder`] and [`remap`] |
+//! | `archive`  | `core`                                             | [`jar`] and jmod reading, [`archivecheck`], [`modules`], [`splitpackage`] |
+//! | `parallel` | `core`                                             | [`parallel`] mapping over classes on every core |
+//! | `mmap`     | `core`                                             | files mapped into memory by [`input`], through the `memmap2` crate |
+//! | `serde`    | `core`                                             | `Serialize` and `Deserialize` for the class file model, through the `serde` crate |
+//! | `sqlite`   | `analysis`                                         | [`SqliteExporter`](sqlexport::SqliteExporter), through the `rusqlite` crate with SQLite built in |
+//! | `cli`      | `analysis`, `write`, `archive`, `parallel`, `mmap` | the `jvmb` binary and its [`cli`], [`toml`] |
+//! | `tui`      | `cli`                                              | `jvmb browse`, a terminal class browser |
+//!
+//! `core` is always built, and without `std` needs only `alloc`, so that the
+//! parser runs in wasm or on an embedded target; every feature after `std`
+//! needs it. [`compat`], [`deps`] and [`report`] need both `analysis` and
+//! `archive`, and [`rename`] both `analysis` and `write`. `std` and `cli` are
+//! the defaults. `serde`, `mmap` and `sqlite` are the only features which pull
+//! in a dependency besides `nom`; the JSON of `jvmb --json` is jvmb's own, see
+//! [`export`], and doesn't need it.
+//!
+//! The types of the class file model are re-exported here, so that reading
+//! a class takes no more than:
+//!
+//! ```no_run
+//! use jvmb::ClassFile;
+//!
+//! let class_file = ClassFile::from_path("Foo.class").unwrap();
+//! for method in &class_file.methods {
+//!     let name = method.name(&class_file.constant_pool).unwrap_or_default();
+//!     let descriptor = method.descriptor(&class_file.constant_pool).unwrap_or_default();
+//!     if let Some(code) = method.code() {
+//!         println!("{}{}: {} bytes of code", name, descriptor, code.code.len());
+//!     }
+//! }
+//! ```
+//!
+//! [`ClassFile::from_bytes`] reads a class already in memory, and
+//! [`ClassFile::from_reader`] one from any [`Read`](std::io::Read). The
+//! nom parsers these are built on, such as
+//! [`ClassFile::parse_class_file`], stay public for composing with others.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+pub mod accessflags;
+pub mod annotationstats;
+#[cfg(feature = "archive")]
+pub mod archivecheck;
+pub mod attribute;
+pub mod borrowed;
+#[cfg(feature = "analysis")]
+pub mod budget;
+#[cfg(feature = "write")]
+pub mod builder;
+#[cfg(feature = "disasm")]
+pub mod callgraph;
+#[cfg(feature = "analysis")]
+pub mod casts;
+#[cfg(feature = "analysis")]
+pub mod cfg;
+pub mod classfile;
+#[cfg(feature = "analysis")]
+pub mod classindex;
+#[cfg(feature = "cli")]
+pub mod cli;
+#[cfg(feature = "analysis")]
+pub mod clones;
+#[cfg(feature = "disasm")]
+pub mod codemetrics;
+#[cfg(all(feature = "analysis", feature = "archive"))]
+pub mod compat;
+pub mod constantpool;
+#[cfg(feature = "analysis")]
+pub mod debuginfo;
+#[cfg(all(feature = "analysis", feature = "archive"))]
+pub mod deps;
+pub mod descriptor;
+#[cfg(feature = "analysis")]
+pub mod diff;
+pub mod error;
+pub mod export;
+pub mod fieldinfo;
+#[cfg(feature = "analysis")]
+pub mod frames;
+#[cfg(feature = "analysis")]
+pub mod friendly;
+#[cfg(feature = "archive")]
+pub mod inflate;
+#[cfg(feature = "std")]
+pub mod input;
+#[cfg(feature = "disasm")]
+pub mod instruction;
+#[cfg(feature = "archive")]
+pub mod jar;
+#[cfg(feature = "disasm")]
+pub mod javap;
+#[cfg(feature = "analysis")]
+pub mod jit;
+pub mod json;
+pub mod kotlin;
+#[cfg(feature = "disasm")]
+pub mod layout;
+#[cfg(feature = "analysis")]
+pub mod linemap;
+pub mod mapping;
+pub mod methodinfo;
+pub mod metrics;
+#[cfg(feature = "archive")]
+pub mod modules;
+pub mod mutf8;
+pub mod nest;
+pub mod ordering;
+#[cfg(feature = "parallel")]
+pub mod parallel;
+#[cfg(feature = "write")]
+pub mod patch;
+#[cfg(feature = "analysis")]
+pub mod pattern;
+#[cfg(feature = "analysis")]
+pub mod pipeline;
+pub mod policy;
+pub mod rdjson;
+#[cfg(feature = "analysis")]
+pub mod redact;
+#[cfg(feature = "analysis")]
+pub mod reflection;
+#[cfg(feature = "analysis")]
+pub mod regex;
+#[cfg(feature = "analysis")]
+pub mod regions;
+#[cfg(feature = "write")]
+pub mod remap;
+#[cfg(all(feature = "analysis", feature = "write"))]
+pub mod rename;
+#[cfg(all(feature = "analysis", feature = "archive"))]
+pub mod report;
+#[cfg(feature = "analysis")]
+pub mod rollup;
+pub mod sha256;
+pub mod signature;
+pub mod sizes;
+pub mod smap;
+#[cfg(feature = "analysis")]
+pub mod sourcepaths;
+pub mod spec;
+#[cfg(feature = "archive")]
+pub mod splitpackage;
+#[cfg(feature = "analysis")]
+pub mod sqlexport;
+#[cfg(feature = "disasm")]
+pub mod stackmap;
+#[cfg(feature = "analysis")]
+pub mod stringbuilding;
+pub mod stringtable;
+#[cfg(feature = "analysis")]
+pub mod switchmap;
+#[cfg(feature = "disasm")]
+pub mod symbols;
+#[cfg(feature = "disasm")]
+pub mod textify;
+#[cfg(feature = "cli")]
+pub mod toml;
+#[cfg(feature = "analysis")]
+pub mod trivialbody;
+pub mod typeannotation;
+pub mod validate;
+#[cfg(feature = "analysis")]
+pub mod verify;
+#[cfg(feature = "write")]
+mod write;
+#[cfg(feature = "disasm")]
+pub mod xref;
+
+pub use crate::{
+    attribute::{Attribute, Code},
+    classfile::{ClassFile, ParseOptions},
+    constantpool::ConstantPool,
+    error::ParseError,
+    fieldinfo::FieldInfo,
+    methodinfo::MethodInfo,
+};
+
+/// What the standard prelude adds to that of `core`, for the modules built
+/// without `std`.
+mod prelude {
+    pub(crate) use alloc::{
+        boxed::Box,
+        format,
+        string::{String, ToString},
+        vec,
+        vec::Vec,
+    };
+}
@@ -0,0 +1,99 @@
+//! Which lines of each source file have bytecode, as recorded by the
+//! `LineNumberTable`s of the classes compiled from it. Lines of a file
+//! which are missing had their code elided by the compiler, or had none.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{classfile::ClassFile, constantpool::ConstantPool};
+
+/// The lines of one class, by method name and descriptor (`run()V`).
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ClassLines {
+    pub methods: BTreeMap<String, BTreeSet<u16>>,
+}
+
+impl ClassLines {
+    pub fn lines(&self) -> BTreeSet<u16> {
+        self.methods.values().flatten().copied().collect()
+    }
+}
+
+/// The lines of one source file, by the internal name of each class
+/// compiled from it. Nested and local classes share the file of their
+/// outer class.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SourceLines {
+    pub classes: BTreeMap<String, ClassLines>,
+}
+
+impl SourceLines {
+    pub fn lines(&self) -> BTreeSet<u16> {
+        self.classes.values().flat_map(ClassLines::lines).collect()
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct LineMap {
+    /// Source files by path (`com/example/Foo.java`), made of the package
+    /// of the class and its `SourceFile` attribute.
+    pub files: BTreeMap<String, SourceLines>,
+    /// Classes without a `SourceFile` attribute, or with a method body
+    /// lacking a `LineNumberTable`, by internal name. Their lines can't be
+    /// mapped, so they're left out of `files`.
+    pub without_debug_info: BTreeSet<String>,
+}
+
+impl LineMap {
+    pub fn new() -> Self {
+        LineMap::default()
+    }
+
+    pub fn add_class(&mut self, class_file: &ClassFile) {
+        let constant_pool = &class_file.constant_pool;
+        let class_name = ConstantPool::class_name(constant_pool, class_file.this_class)
+            .unwrap_or("?")
+            .to_string();
+        let source_file = class_file.source_file();
+
+        let mut lines = ClassLines::default();
+        for method in &class_file.methods {
+            if method.code().is_none() {
+                continue;
+            }
+            let table = match method.line_number_table() {
+                Some(table) => table,
+                None => {
+                    self.without_debug_info.insert(class_name);
+                    return;
+                }
+            };
+            let name = format!(
+                "{}{}",
+                ConstantPool::utf8(constant_pool, method.name_index()).unwrap_or("?"),
+                ConstantPool::utf8(constant_pool, method.descriptor_index()).unwrap_or("")
+            );
+            lines
+                .methods
+                .entry(name)
+                .or_default()
+                .extend(table.iter().map(|line| line.line_number));
+        }
+
+        let source_file = match source_file {
+            Some(source_file) => source_file,
+            None => {
+                self.without_debug_info.insert(class_name);
+                return;
+            }
+        };
+        let path = match class_name.rsplit_once('/') {
+            Some((package, _)) => format!("{}/{}", package, source_file),
+            None => source_file.to_string(),
+        };
+        self.files
+            .entry(path)
+            .or_default()
+            .classes
+            .insert(class_name, lines);
+    }
+}
@@ -0,0 +1,337 @@
+//! The mapping files ProGuard and R8 write alongside an obfuscated build,
+//! for showing the original names of its classes and members.
+//!
+//! A mapping lists each class with the members it renamed, indented below
+//! it, in Java source notation:
+//!
+//! ```text
+//! # comments start with a hash
+//! com.example.RequestHandler -> a.b:
+//!     java.lang.String name -> a
+//!     1:4:void processRequest(com.example.Request,int):12:15 -> a
+//!     5:5:void com.example.Util.check(int):40:40 -> a
+//!     5:5:void processRequest(com.example.Request,int):16 -> a
+//! ```
+//!
+//! A method line may start with the range of lines its obfuscated code has
+//! and end with the range those came from. R8 lists a method inlined into
+//! another under the lines of the code it was inlined into, innermost
+//! first, qualified with its class when that's another one.
+//!
+//! Methods are told apart by their descriptors: one matches when its
+//! parameter and return types, with every class in them mapped back to its
+//! original name, are those the mapping gives, in order.
+
+use alloc::collections::BTreeMap;
+use core::{error::Error, fmt};
+
+use crate::descriptor::{FieldType, MethodDescriptor};
+use crate::prelude::*;
+
+/// A class of the mapping, with the members it renamed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClassMapping {
+    /// Both internal names, e.g. `com/example/RequestHandler` and `a/b`.
+    pub original: String,
+    pub obfuscated: String,
+    pub fields: Vec<FieldMapping>,
+    /// In the order of the mapping.
+    pub methods: Vec<MethodMapping>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldMapping {
+    /// The type as written in Java source, e.g. `java.lang.String`.
+    pub original_type: String,
+    pub original_name: String,
+    pub obfuscated_name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MethodMapping {
+    /// The lines of the obfuscated method this entry covers.
+    pub obfuscated_lines: Option<(u32, u32)>,
+    /// The lines of the original method those came from.
+    pub original_lines: Option<(u32, u32)>,
+    /// The internal name of the class the method was declared in, when it
+    /// was inlined from another.
+    pub original_class: Option<String>,
+    /// Whether the entry is for a method inlined into the obfuscated one,
+    /// rather than for that method itself.
+    pub inlined: bool,
+    /// The return type as written in Java source, `void` included.
+    pub return_type: String,
+    pub original_name: String,
+    /// The parameter types as written in Java source.
+    pub parameters: Vec<String>,
+    pub obfuscated_name: String,
+}
+
+/// A line of a mapping file which isn't in the format.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MappingError {
+    /// Counting from 1.
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for MappingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl Error for MappingError {}
+
+/// The original names of the classes and members of an obfuscated build,
+/// by obfuscated name.
+#[derive(Debug, Default, Clone)]
+pub struct NameMapper {
+    /// obfuscated internal name -> mapping
+    classes: BTreeMap<String, ClassMapping>,
+}
+
+impl NameMapper {
+    pub fn new() -> Self {
+        NameMapper::default()
+    }
+
+    /// Parses the text of a mapping file.
+    pub fn parse(text: &str) -> Result<Self, MappingError> {
+        let mut mapper = NameMapper::new();
+        let mut current: Option<ClassMapping> = None;
+        for (number, line) in text.lines().enumerate() {
+            let error = |message: &str| MappingError {
+                line: number + 1,
+                message: message.to_string(),
+            };
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+            if !line.starts_with(char::is_whitespace) {
+                let (original, obfuscated) = trimmed
+                    .strip_suffix(':')
+                    .and_then(|line| line.split_once(" -> "))
+                    .ok_or_else(|| error("expected `original -> obfuscated:`"))?;
+                mapper.insert(current.take());
+                current = Some(ClassMapping {
+                    original: original.trim().replace('.', "/"),
+                    obfuscated: obfuscated.trim().replace('.', "/"),
+                    fields: Vec::new(),
+                    methods: Vec::new(),
+                });
+                continue;
+            }
+            let class = current
+                .as_mut()
+                .ok_or_else(|| error("member before any class"))?;
+            let (original, obfuscated) = trimmed
+                .split_once(" -> ")
+                .ok_or_else(|| error("expected `original -> obfuscated`"))?;
+            let obfuscated = obfuscated.trim().to_string();
+            if original.contains('(') {
+                let mut method = parse_method(original, obfuscated)
+                    .ok_or_else(|| error("invalid method mapping"))?;
+                if method.original_class.as_ref() == Some(&class.original) {
+                    method.original_class = None;
+                }
+                // a frame is followed by the method it was inlined into, at
+                // the same lines
+                if let Some(previous) = class.methods.last_mut() {
+                    previous.inlined |= method.obfuscated_lines.is_some()
+                        && previous.obfuscated_lines == method.obfuscated_lines
+                        && previous.obfuscated_name == method.obfuscated_name;
+                }
+                class.methods.push(method);
+            } else {
+                let (original_type, original_name) = original
+                    .trim()
+                    .split_once(' ')
+                    .ok_or_else(|| error("expected `type name -> obfuscated`"))?;
+                class.fields.push(FieldMapping {
+                    original_type: original_type.to_string(),
+                    original_name: original_name.trim().to_string(),
+                    obfuscated_name: obfuscated,
+                });
+            }
+        }
+        mapper.insert(current);
+        Ok(mapper)
+    }
+
+    fn insert(&mut self, class: Option<ClassMapping>) {
+        if let Some(class) = class {
+            self.classes.insert(class.obfuscated.clone(), class);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.classes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.classes.is_empty()
+    }
+
+    /// The mapping of the class with the internal name `obfuscated`.
+    pub fn class(&self, obfuscated: &str) -> Option<&ClassMapping> {
+        self.classes.get(obfuscated)
+    }
+
+    /// The original internal name of a class, or `name` itself when the
+    /// mapping doesn't rename it.
+    pub fn class_name<'a>(&'a self, name: &'a str) -> &'a str {
+        self.class(name)
+            .map_or(name, |class| class.original.as_str())
+    }
+
+    /// The original name of the field `name` of `class`, whose descriptor is
+    /// `descriptor`, when the mapping renames it.
+    pub fn field_name(&self, class: &str, name: &str, descriptor: &str) -> Option<&str> {
+        let original_type = self.java_type(&FieldType::parse(descriptor).ok()?);
+        self.class(class)?
+            .fields
+            .iter()
+            .find(|field| field.obfuscated_name == name && field.original_type == original_type)
+            .map(|field| field.original_name.as_str())
+    }
+
+    /// The original name of the method `name` of `class`, whose descriptor
+    /// is `descriptor`, when the mapping renames it. Methods inlined into it
+    /// don't count.
+    pub fn method_name(&self, class: &str, name: &str, descriptor: &str) -> Option<&str> {
+        let descriptor = MethodDescriptor::parse(descriptor).ok()?;
+        let parameters: Vec<String> = descriptor
+            .parameters
+            .iter()
+            .map(|parameter| self.java_type(parameter))
+            .collect();
+        let return_type = descriptor.return_type.as_ref().map_or_else(
+            || "void".to_string(),
+            |return_type| self.java_type(return_type),
+        );
+        self.class(class)?
+            .methods
+            .iter()
+            .find(|method| {
+                method.obfuscated_name == name
+                    && !method.inlined
+                    && method.parameters == parameters
+                    && method.return_type == return_type
+            })
+            .map(|method| method.original_name.as_str())
+    }
+
+    /// The entries of the obfuscated method `name` of `class` whose lines
+    /// include `line`: the methods inlined there, innermost first, then the
+    /// method itself.
+    pub fn frames_at(&self, class: &str, name: &str, line: u32) -> Vec<&MethodMapping> {
+        self.class(class)
+            .map(|class| {
+                class
+                    .methods
+                    .iter()
+                    .filter(|method| {
+                        method.obfuscated_name == name
+                            && method
+                                .obfuscated_lines
+                                .is_some_and(|(start, end)| start <= line && line <= end)
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// The descriptor with every class in it mapped to its original name.
+    pub fn descriptor(&self, descriptor: &str) -> String {
+        let mut mapped = String::with_capacity(descriptor.len());
+        let mut rest = descriptor;
+        while let Some(start) = rest.find('L') {
+            let end = match rest[start..].find(';') {
+                Some(end) => start + end,
+                None => break,
+            };
+            mapped.push_str(&rest[..=start]);
+            mapped.push_str(self.class_name(&rest[start + 1..end]));
+            rest = &rest[end..];
+        }
+        mapped.push_str(rest);
+        mapped
+    }
+
+    /// The type as the mapping writes it, with its class mapped back.
+    fn java_type(&self, field_type: &FieldType) -> String {
+        match field_type {
+            FieldType::Object(name) => self.class_name(name).replace('/', "."),
+            FieldType::Array {
+                dimensions,
+                element_type,
+            } => self.java_type(element_type) + &"[]".repeat(usize::from(*dimensions)),
+            base => base.to_string(),
+        }
+    }
+}
+
+/// Parses the part of a method line before the arrow, e.g.
+/// `1:4:void processRequest(com.example.Request,int):12:15`.
+fn parse_method(original: &str, obfuscated_name: String) -> Option<MethodMapping> {
+    let original = original.trim();
+    let close = original.rfind(')')?;
+    let open = original[..close].find('(')?;
+    let parameters = original[open + 1..close]
+        .split(',')
+        .map(str::trim)
+        .filter(|parameter| !parameter.is_empty())
+        .map(str::to_string)
+        .collect();
+    let original_lines = line_range(original[close + 1..].strip_prefix(':'))?;
+
+    // the obfuscated lines are the numbers before the return type
+    let mut head = &original[..open];
+    let mut numbers = Vec::new();
+    while let Some((number, rest)) = head.split_once(':') {
+        match number.trim().parse::<u32>() {
+            Ok(number) => numbers.push(number),
+            Err(_) => break,
+        }
+        head = rest;
+    }
+    let obfuscated_lines = match numbers[..] {
+        [] => None,
+        [line] => Some((line, line)),
+        [start, end] => Some((start, end)),
+        _ => return None,
+    };
+    let (return_type, name) = head.trim().rsplit_once(' ')?;
+    let (original_class, original_name) = match name.rsplit_once('.') {
+        Some((class, name)) => (Some(class.replace('.', "/")), name),
+        None => (None, name),
+    };
+    Some(MethodMapping {
+        obfuscated_lines,
+        original_lines,
+        original_class,
+        inlined: false,
+        return_type: return_type.trim().to_string(),
+        original_name: original_name.to_string(),
+        parameters,
+        obfuscated_name,
+    })
+}
+
+/// Parses `12` or `12:15`, which may be absent but not malformed.
+fn line_range(text: Option<&str>) -> Option<Option<(u32, u32)>> {
+    let text = match text {
+        Some(text) => text,
+        None => return Some(None),
+    };
+    let range = match text.split_once(':') {
+        Some((start, end)) => (start.trim().parse().ok()?, end.trim().parse().ok()?),
+        None => {
+            let line = text.trim().parse().ok()?;
+            (line, line)
+        }
+    };
+    Some(Some(range))
+}
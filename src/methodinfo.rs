@@ -1,13 +1,74 @@
-use nom::{multi::count, number::complete::be_u16, IResult};
+#[cfg(feature = "write")]
+use std::io;
 
+use crate::prelude::*;
+#[cfg(feature = "write")]
+use crate::write::WriteBe;
 use crate::{
-    attribute::{Attribute, AttributeInfo},
-    constantpool::ConstantPool,
+    accessflags::MethodAccessFlags,
+    attribute::{
+        Attribute, Code, Exceptions, LineNumber, LocalVariable, ResolvedAnnotation, Signature,
+        StackMapTable, TypeAnnotations,
+    },
+    classfile::{ClassFile, ParseContext, SyntheticForm},
+    constantpool::{ConstantPool, ConstantPoolError},
+    descriptor::{DescriptorError, MethodDescriptor},
+    error::{self, be_u16, IResult},
 };
+#[cfg(feature = "disasm")]
+use crate::{
+    codemetrics::MethodMetrics,
+    descriptor::{BaseType, FieldType},
+    stackmap::{self, StackMapIssue},
+    xref::MemberRefs,
+};
+
+/// The kinds of method an interface can declare, which differ in what
+/// implementors and callers depend on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InterfaceMemberKind {
+    /// Must be implemented by every implementing class.
+    Abstract,
+    /// Has a body which implementors inherit unless they override it.
+    Default,
+    /// Called on the interface itself and never inherited.
+    Static,
+    /// A helper for the interface's other methods (Java 9+); not part of its
+    /// API.
+    Private,
+}
+
+impl InterfaceMemberKind {
+    /// Whether adding a method of this kind breaks existing implementors.
+    pub fn is_breaking_addition(self) -> bool {
+        self == InterfaceMemberKind::Abstract
+    }
+
+    /// Whether removing a method of this kind breaks existing implementors
+    /// or callers.
+    pub fn is_breaking_removal(self) -> bool {
+        self != InterfaceMemberKind::Private
+    }
+
+    /// Whether turning a method of this kind into one of kind `to` breaks
+    /// existing implementors or callers. Giving an abstract method a default
+    /// body is safe; taking it away is not, and neither is moving a method
+    /// between static and instance, or out of the API.
+    pub fn is_breaking_change(self, to: InterfaceMemberKind) -> bool {
+        use InterfaceMemberKind::*;
+        match (self, to) {
+            (from, to) if from == to => false,
+            (Private, to) => to.is_breaking_addition(),
+            (Abstract, Default) => false,
+            _ => true,
+        }
+    }
+}
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MethodInfo {
-    access_flags: u16,
+    access_flags: MethodAccessFlags,
     name_index: u16,
     descriptor_index: u16,
     attributes_count: u16,
@@ -15,35 +76,56 @@ pub struct MethodInfo {
 }
 
 impl MethodInfo {
-    pub fn parse<'a>(
+    /// Puts together a member whose attributes were parsed elsewhere.
+    pub(crate) fn from_parts(
+        access_flags: u16,
+        name_index: u16,
+        descriptor_index: u16,
+        attributes: Vec<Attribute>,
+    ) -> Self {
+        MethodInfo {
+            access_flags: MethodAccessFlags::new(access_flags),
+            name_index,
+            descriptor_index,
+            attributes_count: attributes.len() as u16,
+            attributes,
+        }
+    }
+
+    pub(crate) fn parse<'a>(
         mut buf: &'a [u8],
         fields_count: u16,
         constant_pool: &[ConstantPool],
+        context: &ParseContext,
     ) -> IResult<&'a [u8], Vec<MethodInfo>> {
-        let mut methods = Vec::with_capacity(fields_count as usize);
-        for _ in 0..fields_count {
-            let (temp_buf, constant) = Self::parse_method_info(buf, constant_pool)?;
+        let mut methods = Vec::with_capacity(error::capacity(buf, fields_count as usize, 8)?);
+        for i in 0..fields_count {
+            let (temp_buf, constant) =
+                error::within(Self::parse_method_info(buf, constant_pool, context), || {
+                    error::member_context("method", i, buf, constant_pool)
+                })?;
             buf = temp_buf;
             methods.push(constant);
         }
 
         Ok((buf, methods))
     }
-    pub fn parse_method_info<'a>(
+    pub(crate) fn parse_method_info<'a>(
         buf: &'a [u8],
         constant_pool: &[ConstantPool],
+        context: &ParseContext,
     ) -> IResult<&'a [u8], MethodInfo> {
         let (buf, access_flags) = be_u16(buf)?;
         let (buf, name_index) = be_u16(buf)?;
         let (buf, descriptor_index) = be_u16(buf)?;
         let (buf, attributes_count) = be_u16(buf)?;
-        let (buf, attributes) = count(AttributeInfo::parse, attributes_count as usize)(buf)?;
-        let attributes = Attribute::from_attribute_info(attributes, constant_pool);
+        let (buf, attributes) =
+            Attribute::parse_table(buf, attributes_count, constant_pool, context)?;
 
         Ok((
             buf,
             MethodInfo {
-                access_flags,
+                access_flags: MethodAccessFlags::new(access_flags),
                 name_index,
                 descriptor_index,
                 attributes_count,
@@ -52,3 +134,250 @@ impl MethodInfo {
         ))
     }
 }
+
+impl MethodInfo {
+    /// Whether the method is compiler-generated, marked either by the
+    /// `ACC_SYNTHETIC` flag or by a `Synthetic` attribute.
+    pub fn is_synthetic(&self) -> bool {
+        self.access_flags.is_synthetic() || Attribute::has_synthetic(&self.attributes)
+    }
+
+    pub fn access_flags(&self) -> MethodAccessFlags {
+        self.access_flags
+    }
+
+    pub fn name_index(&self) -> u16 {
+        self.name_index
+    }
+
+    pub fn descriptor_index(&self) -> u16 {
+        self.descriptor_index
+    }
+
+    /// The method's name, looked up in the class's constant pool. `None` if
+    /// `name_index` isn't a UTF8 entry.
+    pub fn name<'a>(&self, constant_pool: &'a [ConstantPool]) -> Option<&'a str> {
+        ConstantPool::utf8(constant_pool, self.name_index)
+    }
+
+    /// The method's descriptor, looked up in the class's constant pool.
+    pub fn descriptor<'a>(&self, constant_pool: &'a [ConstantPool]) -> Option<&'a str> {
+        ConstantPool::utf8(constant_pool, self.descriptor_index)
+    }
+
+    /// The method's parameter and return types, parsed from its descriptor.
+    /// `None` if `descriptor_index` isn't a UTF8 entry.
+    pub fn parsed_descriptor(
+        &self,
+        constant_pool: &[ConstantPool],
+    ) -> Option<Result<MethodDescriptor, DescriptorError>> {
+        self.descriptor(constant_pool).map(MethodDescriptor::parse)
+    }
+
+    pub fn attributes(&self) -> &[Attribute] {
+        &self.attributes
+    }
+
+    /// The method's visible and then invisible annotations, with their
+    /// element values looked up. Parameter annotations aren't included.
+    pub fn annotations<'a>(
+        &'a self,
+        constant_pool: &'a [ConstantPool],
+    ) -> Vec<ResolvedAnnotation<'a>> {
+        Attribute::annotations(&self.attributes, constant_pool)
+    }
+
+    /// What kind of interface member this is, or `None` if `class` isn't an
+    /// interface. A non-abstract instance method without a body is malformed
+    /// and is treated as abstract.
+    pub fn interface_member_kind(&self, class: &ClassFile) -> Option<InterfaceMemberKind> {
+        if !class.access_flags.is_interface() {
+            return None;
+        }
+        Some(if self.access_flags.is_private() {
+            InterfaceMemberKind::Private
+        } else if self.access_flags.is_static() {
+            InterfaceMemberKind::Static
+        } else if self.access_flags.is_abstract() || self.code().is_none() {
+            InterfaceMemberKind::Abstract
+        } else {
+            InterfaceMemberKind::Default
+        })
+    }
+
+    /// The method body, absent for `abstract` and `native` methods.
+    pub fn code(&self) -> Option<&Code> {
+        self.attributes
+            .iter()
+            .find_map(|attribute| match attribute {
+                Attribute::Code(code) => Some(code),
+                _ => None,
+            })
+    }
+
+    /// The `LineNumberTable` from the Code attribute if present.
+    pub fn line_number_table(&self) -> Option<&[LineNumber]> {
+        self.code()?.line_number_table()
+    }
+
+    /// The `LocalVariableTable` from the Code attribute if present.
+    pub fn local_variable_table(&self) -> Option<&[LocalVariable]> {
+        self.code()?.local_variable_table()
+    }
+
+    /// The `StackMapTable` from the Code attribute if present.
+    pub fn stack_map_table(&self) -> Option<&StackMapTable> {
+        self.code()?.stack_map_table()
+    }
+
+    /// The method's `Exceptions` attribute, the checked exceptions it
+    /// declares it throws.
+    ///
+    /// ```
+    /// use jvmb::{
+    ///     attribute::{Attribute, Exceptions},
+    ///     builder::ClassFileBuilder,
+    /// };
+    ///
+    /// let mut builder = ClassFileBuilder::new("Foo");
+    /// let exception = builder.constant_pool().class("java/io/IOException");
+    /// builder.add_method(0x0401, "read", "()I", None);
+    /// let mut class_file = builder.build().unwrap();
+    /// let method = &mut class_file.methods[0];
+    /// assert_eq!(method.exceptions(), None);
+    ///
+    /// method.attributes_mut().push(Attribute::Exceptions(Exceptions {
+    ///     exception_index_table: vec![exception],
+    /// }));
+    /// method.attributes_mut().push(Attribute::Deprecated(Vec::new()));
+    /// let exceptions = method.exceptions().unwrap();
+    /// assert_eq!(exceptions.exception_index_table, [exception]);
+    /// assert!(method.is_deprecated());
+    /// assert_eq!(method.signature(), None);
+    /// assert_eq!(method.code(), None);
+    /// ```
+    pub fn exceptions(&self) -> Option<&Exceptions> {
+        self.attributes
+            .iter()
+            .find_map(|attribute| match attribute {
+                Attribute::Exceptions(exceptions) => Some(exceptions),
+                _ => None,
+            })
+    }
+
+    /// The method's `Signature` attribute, its generic signature.
+    pub fn signature(&self) -> Option<&Signature> {
+        Attribute::signature(&self.attributes)
+    }
+
+    /// Whether the method has a `Deprecated` attribute, which `javac` adds
+    /// for `@deprecated` in its doc comment.
+    pub fn is_deprecated(&self) -> bool {
+        Attribute::has_deprecated(&self.attributes)
+    }
+
+    /// The internal names of the exceptions the method's `Exceptions`
+    /// attribute declares it throws, empty when it has none.
+    pub fn declared_exceptions<'a>(
+        &self,
+        constant_pool: &'a [ConstantPool],
+    ) -> Result<Vec<&'a str>, ConstantPoolError> {
+        self.exceptions()
+            .into_iter()
+            .flat_map(|exceptions| &exceptions.exception_index_table)
+            .map(|&index| ConstantPool::get_class_name(constant_pool, index))
+            .collect()
+    }
+
+    /// The methods and fields the method's code refers to, and its
+    /// `invokedynamic` call sites, resolved through `class`, the class the
+    /// method belongs to. Empty for methods without code.
+    #[cfg(feature = "disasm")]
+    pub fn referenced_members<'a>(&self, class: &'a ClassFile) -> MemberRefs<'a> {
+        MemberRefs::of(self, class)
+    }
+
+    /// The size and shape of the method's code; `None` for methods without
+    /// code, such as abstract and native ones.
+    #[cfg(feature = "disasm")]
+    pub fn metrics(&self) -> Option<MethodMetrics> {
+        self.code().map(MethodMetrics::of)
+    }
+
+    /// What's wrong with the frames of the method's `StackMapTable`, as
+    /// [`Code::validate_stack_map`] finds, with local counts checked from
+    /// the implicit first frame the descriptor gives. Empty for methods
+    /// without code or a table.
+    #[cfg(feature = "disasm")]
+    pub fn validate_stack_map(&self, constant_pool: &[ConstantPool]) -> Vec<StackMapIssue> {
+        let code = match self.code() {
+            Some(code) => code,
+            None => return Vec::new(),
+        };
+        let initial_locals = self
+            .parsed_descriptor(constant_pool)
+            .and_then(Result::ok)
+            .map(|descriptor| {
+                let this = (!self.access_flags.is_static()).then_some(1);
+                let parameters = descriptor
+                    .parameters
+                    .iter()
+                    .map(|parameter| match parameter {
+                        FieldType::Base(BaseType::Long | BaseType::Double) => 2,
+                        _ => 1,
+                    });
+                this.into_iter().chain(parameters).collect()
+            });
+        stackmap::validate(code, constant_pool, initial_locals)
+    }
+
+    /// Type annotations attached to the method itself: its return type,
+    /// receiver, parameters, type parameters and `throws` clause.
+    pub fn type_annotations(&self) -> Option<TypeAnnotations<'_>> {
+        TypeAnnotations::from_attributes(&self.attributes)
+    }
+
+    /// Type annotations from the Code attribute if present, i.e. those on
+    /// types used within the method body. These are kept apart from
+    /// [`MethodInfo::type_annotations`] since their targets are relative to
+    /// the bytecode.
+    pub fn code_type_annotations(&self) -> Option<TypeAnnotations<'_>> {
+        self.code()?.type_annotations()
+    }
+
+    /// The attribute table. Attribute counts are recomputed from this when
+    /// the class is written back out.
+    pub fn attributes_mut(&mut self) -> &mut Vec<Attribute> {
+        &mut self.attributes
+    }
+
+    /// Renames the method to the UTF8 entry at `index`.
+    pub fn set_name_index(&mut self, index: u16) {
+        self.name_index = index;
+    }
+
+    /// Gives the method the descriptor held by the UTF8 entry at `index`.
+    pub fn set_descriptor_index(&mut self, index: u16) {
+        self.descriptor_index = index;
+    }
+
+    #[cfg(feature = "write")]
+    pub fn write(&self, out: &mut Vec<u8>, constant_pool: &[ConstantPool]) -> io::Result<()> {
+        out.put_u16(self.access_flags.bits());
+        out.put_u16(self.name_index);
+        out.put_u16(self.descriptor_index);
+        Attribute::write_all(&self.attributes, out, constant_pool)
+    }
+
+    pub(crate) fn set_synthetic_form(&mut self, form: SyntheticForm) {
+        form.apply(&mut self.access_flags, &mut self.attributes);
+        self.attributes_count = self.attributes.len() as u16;
+    }
+
+    #[cfg(feature = "write")]
+    pub(crate) fn remove_attributes(&mut self, names: &[&str]) -> usize {
+        let removed = Attribute::remove_named(&mut self.attributes, names);
+        self.attributes_count = self.attributes.len() as u16;
+        removed
+    }
+}
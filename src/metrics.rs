@@ -0,0 +1,72 @@
+//! Where parsing spends its time, collected when
+//! [`ParseOptions::collect_metrics`] is set.
+//!
+//! [`ParseOptions::collect_metrics`]: crate::classfile::ParseOptions::collect_metrics
+
+use crate::prelude::*;
+use alloc::collections::BTreeMap;
+use core::time::Duration;
+
+/// The time spent in one phase of parsing and how many items it covered.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PhaseMetrics {
+    pub time: Duration,
+    pub count: usize,
+}
+
+impl PhaseMetrics {
+    pub fn record(&mut self, time: Duration, count: usize) {
+        self.time += time;
+        self.count += count;
+    }
+}
+
+impl ParseMetrics {
+    /// Adds the totals of `other`, collected by options of their own, such
+    /// as those of another thread.
+    pub fn add(&mut self, other: &ParseMetrics) {
+        let phases = [
+            (&mut self.total, &other.total),
+            (&mut self.constant_pool, &other.constant_pool),
+            (&mut self.fields, &other.fields),
+            (&mut self.methods, &other.methods),
+            (&mut self.class_attributes, &other.class_attributes),
+            (&mut self.decompression, &other.decompression),
+        ];
+        for (phase, other) in phases {
+            phase.record(other.time, other.count);
+        }
+        for (name, other) in &other.attributes {
+            self.attributes
+                .entry(name.clone())
+                .or_default()
+                .record(other.time, other.count);
+        }
+    }
+}
+
+/// Totals over every class parsed with the same options.
+///
+/// The phases nest the way the class file does: `fields` and `methods`
+/// include the attributes of the members, and the time of each `Code`
+/// attribute includes the attributes inside it. The top level phases
+/// (`constant_pool`, `fields`, `methods` and the class's own attributes)
+/// add up to at most `total`.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ParseMetrics {
+    /// Whole classes, counted by class.
+    pub total: PhaseMetrics,
+    /// Counted by constant pool entry.
+    pub constant_pool: PhaseMetrics,
+    /// Counted by field.
+    pub fields: PhaseMetrics,
+    /// Counted by method.
+    pub methods: PhaseMetrics,
+    /// Attributes anywhere in the class by name, counted by attribute.
+    pub attributes: BTreeMap<String, PhaseMetrics>,
+    /// The class's own attributes, counted by attribute.
+    pub class_attributes: PhaseMetrics,
+    /// Inflating jar entries, counted by entry. Parsing never fills this
+    /// in; it's left to whatever reads the jar.
+    pub decompression: PhaseMetrics,
+}
@@ -0,0 +1,600 @@
+//! The module identity of a jar, as the module system would determine it
+//! when the jar is put on the module path, and, with the `analysis`
+//! feature, a check of a modular jar's descriptor against its classes.
+
+#[cfg(feature = "analysis")]
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+#[cfg(feature = "analysis")]
+use crate::classindex::ClassIndex;
+use crate::{
+    attribute::Attribute,
+    classfile::ClassFile,
+    constantpool::ConstantPool,
+    error::ParseError,
+    jar::{JarEntry, JarError, JarFile},
+};
+
+/// Words which can't be used as a part of a module name.
+#[rustfmt::skip]
+const KEYWORDS: &[&str] = &[
+    "_", "abstract", "assert", "boolean", "break", "byte", "case", "catch", "char", "class",
+    "const", "continue", "default", "do", "double", "else", "enum", "extends", "false", "final",
+    "finally", "float", "for", "goto", "if", "implements", "import", "instanceof", "int",
+    "interface", "long", "native", "new", "null", "package", "private", "protected", "public",
+    "return", "short", "static", "strictfp", "super", "switch", "synchronized", "this", "throw",
+    "throws", "transient", "true", "try", "void", "volatile", "while",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModuleKind {
+    /// Declared by a `module-info.class`; holds the modules it requires.
+    Explicit { requires: Vec<String> },
+    /// Named by the `Automatic-Module-Name` manifest attribute.
+    AutomaticFromManifest,
+    /// Named after the jar file.
+    AutomaticFromFileName,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JarModule {
+    pub name: String,
+    pub kind: ModuleKind,
+}
+
+#[derive(Debug)]
+pub enum ModuleError {
+    Jar(JarError),
+    /// The `module-info.class` couldn't be parsed or has no `Module`
+    /// attribute.
+    BadDescriptor(String),
+    /// The name the jar would get isn't a legal module name.
+    InvalidName(String),
+    /// The jar has no `module-info.class`, where one was needed.
+    NotModular,
+}
+
+impl fmt::Display for ModuleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ModuleError::Jar(e) => write!(f, "{}", e),
+            ModuleError::BadDescriptor(reason) => write!(f, "bad module-info.class: {}", reason),
+            ModuleError::InvalidName(name) => write!(f, "{:?} is not a valid module name", name),
+            ModuleError::NotModular => f.write_str("not a modular jar: no module-info.class"),
+        }
+    }
+}
+
+impl std::error::Error for ModuleError {}
+
+impl From<JarError> for ModuleError {
+    fn from(e: JarError) -> Self {
+        ModuleError::Jar(e)
+    }
+}
+
+/// Works out the module `jar` defines, in the module system's order of
+/// precedence: a `module-info.class` (at the root, or else the one for the
+/// latest release in a multi-release jar), then an `Automatic-Module-Name`
+/// in the manifest, then a name derived from `file_name`.
+pub fn jar_module(file_name: &str, jar: &JarFile) -> Result<JarModule, ModuleError> {
+    if let Some(entry) = module_info_entry(jar) {
+        let buf = jar.read(entry)?;
+        let (_, class_file) = ClassFile::parse_class_file(&buf)
+            .map_err(|e| ModuleError::BadDescriptor(ParseError::from(e).to_string()))?;
+        return explicit_module(&class_file)
+            .ok_or_else(|| ModuleError::BadDescriptor("no Module attribute".to_string()));
+    }
+
+    if let Some(entry) = jar.entry("META-INF/MANIFEST.MF") {
+        let manifest = String::from_utf8_lossy(&jar.read(entry)?).into_owned();
+        if let Some(name) = manifest_attribute(&manifest, "Automatic-Module-Name") {
+            if !is_valid_module_name(&name) {
+                return Err(ModuleError::InvalidName(name));
+            }
+            return Ok(JarModule {
+                name,
+                kind: ModuleKind::AutomaticFromManifest,
+            });
+        }
+    }
+
+    let name = automatic_module_name(file_name);
+    if !is_valid_module_name(&name) {
+        return Err(ModuleError::InvalidName(name));
+    }
+    Ok(JarModule {
+        name,
+        kind: ModuleKind::AutomaticFromFileName,
+    })
+}
+
+/// What a modular jar's packages are to its module.
+#[cfg(feature = "analysis")]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct PackageStatus {
+    /// Classes of the package in the jar.
+    pub classes: usize,
+    pub exported: bool,
+    pub opened: bool,
+    /// Whether `ModulePackages` lists the package; `false` without one.
+    pub listed: bool,
+}
+
+/// A disagreement between a modular jar's `module-info.class` and the
+/// classes it holds or the services it names.
+#[cfg(feature = "analysis")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModuleFinding {
+    /// `exports` or `opens`, as `directive`, names a package without any
+    /// classes in the jar.
+    MissingPackage {
+        directive: &'static str,
+        package: String,
+    },
+    /// `ModulePackages` leaves out a package with classes in the jar.
+    UnlistedPackage { package: String },
+    /// `uses` or `provides`, as `directive`, names a service type found
+    /// neither in the jar nor on the classpath.
+    UnknownService {
+        directive: &'static str,
+        service: String,
+    },
+    /// A provider of `provides` which isn't a class of the jar.
+    MissingProvider { service: String, provider: String },
+    /// A provider which neither extends nor implements its service type.
+    NotAProvider { service: String, provider: String },
+}
+
+#[cfg(feature = "analysis")]
+impl ModuleFinding {
+    /// The stable code of the finding, e.g. `V0401`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ModuleFinding::MissingPackage { .. } => "V0401",
+            ModuleFinding::UnlistedPackage { .. } => "V0402",
+            ModuleFinding::UnknownService { .. } => "V0403",
+            ModuleFinding::MissingProvider { .. } => "V0404",
+            ModuleFinding::NotAProvider { .. } => "V0405",
+        }
+    }
+
+    /// Why the finding matters, in a sentence or two.
+    pub fn explanation(&self) -> &'static str {
+        match self {
+            ModuleFinding::MissingPackage { .. } => {
+                "The module system refuses to resolve a module exporting or opening a package \
+                 it doesn't contain, failing with \"Module ... does not contain package ...\"."
+            }
+            ModuleFinding::UnlistedPackage { .. } => {
+                "Tools which take the module's packages from ModulePackages rather than \
+                 scanning the jar, such as jlink, leave the package's classes out of the module."
+            }
+            ModuleFinding::UnknownService { .. } => {
+                "The service type can't be loaded where the module is resolved; add the jar \
+                 declaring it with --classpath if it's a dependency."
+            }
+            ModuleFinding::MissingProvider { .. } => {
+                "A provider must be a class of the module providing it; the module system \
+                 refuses the descriptor otherwise."
+            }
+            ModuleFinding::NotAProvider { .. } => {
+                "ServiceLoader fails with a ServiceConfigurationError when it instantiates a \
+                 provider which isn't a subtype of the service."
+            }
+        }
+    }
+}
+
+#[cfg(feature = "analysis")]
+impl fmt::Display for ModuleFinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ModuleFinding::MissingPackage { directive, package } => write!(
+                f,
+                "{} {}, which has no classes in the jar",
+                directive, package
+            ),
+            ModuleFinding::UnlistedPackage { package } => write!(
+                f,
+                "ModulePackages doesn't list {}, which has classes in the jar",
+                package
+            ),
+            ModuleFinding::UnknownService { directive, service } => write!(
+                f,
+                "{} {}, which is neither in the jar nor on the classpath",
+                directive, service
+            ),
+            ModuleFinding::MissingProvider { service, provider } => write!(
+                f,
+                "provides {} with {}, which isn't a class of the jar",
+                service, provider
+            ),
+            ModuleFinding::NotAProvider { service, provider } => write!(
+                f,
+                "provides {} with {}, which doesn't extend or implement it",
+                service, provider
+            ),
+        }
+    }
+}
+
+/// A modular jar's packages, by name with dots, and what's wrong with its
+/// descriptor.
+#[cfg(feature = "analysis")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModuleCheck {
+    pub name: String,
+    /// The jar entry of the descriptor, e.g. `module-info.class`.
+    pub entry: String,
+    /// The `SourceFile` of the descriptor, usually `module-info.java`.
+    pub source_file: Option<String>,
+    pub packages: BTreeMap<String, PackageStatus>,
+    pub findings: Vec<ModuleFinding>,
+}
+
+/// Checks the `module-info.class` of `jar` against the classes in it: that
+/// every package it exports or opens has classes in the jar, that its
+/// `ModulePackages`, if any, lists every package which does, and that the
+/// services it uses and provides are found in `index` (which should hold
+/// the jar's classes and those of its classpath) with providers of the jar
+/// extending or implementing them. Platform services (`java.*`, `javax.*`,
+/// `jdk.*`) are assumed present, and so are the platform classes in a
+/// provider's hierarchy.
+#[cfg(feature = "analysis")]
+pub fn check_module(jar: &JarFile, index: &ClassIndex) -> Result<ModuleCheck, ModuleError> {
+    let entry = module_info_entry(jar).ok_or(ModuleError::NotModular)?;
+    let buf = jar.read(entry)?;
+    let (_, class_file) = ClassFile::parse_class_file(&buf)
+        .map_err(|e| ModuleError::BadDescriptor(ParseError::from(e).to_string()))?;
+    let constant_pool = &class_file.constant_pool;
+    let mut module = None;
+    let mut listed = None;
+    let mut source_file = None;
+    for attribute in &class_file.attributes {
+        match attribute {
+            Attribute::Module(found) => module = Some(found),
+            Attribute::ModulePackages(indices) => listed = Some(indices),
+            Attribute::SourceFile(found) => {
+                source_file =
+                    ConstantPool::utf8(constant_pool, found.sourcefile_index).map(str::to_string)
+            }
+            _ => {}
+        }
+    }
+    let module =
+        module.ok_or_else(|| ModuleError::BadDescriptor("no Module attribute".to_string()))?;
+    let package =
+        |index| ConstantPool::package_name(constant_pool, index).map(|name| name.replace('/', "."));
+    let class = |index| ConstantPool::class_name(constant_pool, index).unwrap_or("?");
+
+    let mut classes = BTreeSet::new();
+    let mut packages: BTreeMap<String, PackageStatus> = BTreeMap::new();
+    for class_name in jar.entries().iter().filter_map(|entry| entry.class_name()) {
+        if classes.insert(class_name) {
+            let name = class_name
+                .rsplit_once('/')
+                .map_or("", |(package, _)| package);
+            packages.entry(name.replace('/', ".")).or_default().classes += 1;
+        }
+    }
+
+    let mut findings = Vec::new();
+    let exported = module
+        .exports
+        .iter()
+        .map(|exports| ("exports", exports.exports_index));
+    let opened = module
+        .opens
+        .iter()
+        .map(|opens| ("opens", opens.opens_index));
+    for (directive, index) in exported.chain(opened) {
+        let name = match package(index) {
+            Some(name) => name,
+            None => continue,
+        };
+        let status = packages.entry(name.clone()).or_default();
+        if directive == "exports" {
+            status.exported = true;
+        } else {
+            status.opened = true;
+        }
+        if status.classes == 0 {
+            findings.push(ModuleFinding::MissingPackage {
+                directive,
+                package: name,
+            });
+        }
+    }
+    if let Some(listed) = listed {
+        for name in listed.iter().filter_map(|&index| package(index)) {
+            packages.entry(name).or_default().listed = true;
+        }
+        for (name, status) in &packages {
+            if status.classes > 0 && !status.listed {
+                findings.push(ModuleFinding::UnlistedPackage {
+                    package: name.clone(),
+                });
+            }
+        }
+    }
+
+    let known = |service: &str| is_platform(service) || index.get(service).is_ok();
+    for &uses in &module.uses {
+        let service = class(uses);
+        if !known(service) {
+            findings.push(ModuleFinding::UnknownService {
+                directive: "uses",
+                service: service.replace('/', "."),
+            });
+        }
+    }
+    for provides in &module.provides {
+        let service = class(provides.provides_index);
+        if !known(service) {
+            findings.push(ModuleFinding::UnknownService {
+                directive: "provides",
+                service: service.replace('/', "."),
+            });
+        }
+        for &with in &provides.provides_with_index {
+            let provider = class(with);
+            let finding = if !classes.contains(provider) {
+                ModuleFinding::MissingProvider {
+                    service: service.replace('/', "."),
+                    provider: provider.replace('/', "."),
+                }
+            } else if extends(index, provider, service) == Some(false) {
+                ModuleFinding::NotAProvider {
+                    service: service.replace('/', "."),
+                    provider: provider.replace('/', "."),
+                }
+            } else {
+                continue;
+            };
+            findings.push(finding);
+        }
+    }
+
+    Ok(ModuleCheck {
+        name: ConstantPool::module_name(constant_pool, module.module_name_index)
+            .unwrap_or("?")
+            .to_string(),
+        entry: entry.name.clone(),
+        source_file,
+        packages,
+        findings,
+    })
+}
+
+/// Whether `class` is of the platform's own packages.
+#[cfg(feature = "analysis")]
+fn is_platform(class: &str) -> bool {
+    ["java/", "javax/", "jdk/"]
+        .iter()
+        .any(|prefix| class.starts_with(prefix))
+}
+
+/// Whether `class` extends or implements `service`, as
+/// [`ClassIndex::is_assignable`] tells, except that a class of the
+/// platform missing from the index is taken not to lead to a service which
+/// isn't: the platform's classes can't extend the application's.
+#[cfg(feature = "analysis")]
+fn extends(index: &ClassIndex, class: &str, service: &str) -> Option<bool> {
+    let mut complete = true;
+    let mut seen = BTreeSet::new();
+    let mut pending = vec![class];
+    while let Some(name) = pending.pop() {
+        if name == service {
+            return Some(true);
+        }
+        if !seen.insert(name) {
+            continue;
+        }
+        match index.get(name) {
+            Ok(class) => pending.extend(class.super_class().into_iter().chain(class.interfaces())),
+            Err(_) if is_platform(name) && !is_platform(service) => {}
+            Err(_) => complete = false,
+        }
+    }
+    complete.then_some(false)
+}
+
+fn module_info_entry(jar: &JarFile) -> Option<&JarEntry> {
+    jar.entry("module-info.class").or_else(|| {
+        jar.entries()
+            .iter()
+            .filter_map(|entry| {
+                let version = entry
+                    .name
+                    .strip_prefix("META-INF/versions/")?
+                    .strip_suffix("/module-info.class")?;
+                Some((version.parse::<u32>().ok()?, entry))
+            })
+            .max_by_key(|(version, _)| *version)
+            .map(|(_, entry)| entry)
+    })
+}
+
+/// The module declared by a `module-info` class, if it has a `Module`
+/// attribute.
+pub fn explicit_module(class_file: &ClassFile) -> Option<JarModule> {
+    let constant_pool = &class_file.constant_pool;
+    class_file
+        .attributes
+        .iter()
+        .find_map(|attribute| match attribute {
+            Attribute::Module(module) => Some(JarModule {
+                name: ConstantPool::module_name(constant_pool, module.module_name_index)?
+                    .to_string(),
+                kind: ModuleKind::Explicit {
+                    requires: module
+                        .requires
+                        .iter()
+                        .filter_map(|requires| {
+                            ConstantPool::module_name(constant_pool, requires.requires_index)
+                        })
+                        .map(str::to_string)
+                        .collect(),
+                },
+            }),
+            _ => None,
+        })
+}
+
+/// The value of `name` in the main section of a jar manifest, with
+/// continuation lines joined.
+pub fn manifest_attribute(manifest: &str, name: &str) -> Option<String> {
+    let mut value: Option<String> = None;
+    for line in manifest.lines() {
+        if line.is_empty() {
+            break;
+        }
+        match (line.strip_prefix(' '), value.as_mut()) {
+            (Some(continuation), Some(value)) => value.push_str(continuation),
+            (Some(_), None) => {}
+            (None, Some(_)) => break,
+            (None, None) => {
+                if let Some((key, rest)) = line.split_once(':') {
+                    if key.eq_ignore_ascii_case(name) {
+                        value = Some(rest.strip_prefix(' ').unwrap_or(rest).to_string());
+                    }
+                }
+            }
+        }
+    }
+    value.map(|value| value.trim_end().to_string())
+}
+
+/// The automatic module name the JDK derives from a jar's file name: the
+/// `.jar` extension and anything from the first `-` followed by a number
+/// onwards are dropped, every run of characters other than ASCII letters and
+/// digits becomes a single dot, and leading and trailing dots go. The result
+/// may still be invalid; see [`is_valid_module_name`].
+pub fn automatic_module_name(file_name: &str) -> String {
+    let file_name = file_name.rsplit(['/', '\\']).next().unwrap_or(file_name);
+    let mut name = file_name.strip_suffix(".jar").unwrap_or(file_name);
+
+    // -<digits> followed by a dot or the end of the name
+    let bytes = name.as_bytes();
+    let version_start = (0..bytes.len()).find(|&i| {
+        if bytes[i] != b'-' {
+            return false;
+        }
+        let digits = bytes[i + 1..]
+            .iter()
+            .take_while(|b| b.is_ascii_digit())
+            .count();
+        digits > 0 && matches!(bytes.get(i + 1 + digits), None | Some(b'.'))
+    });
+    if let Some(start) = version_start {
+        name = &name[..start];
+    }
+
+    let mut cleaned = String::with_capacity(name.len());
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() {
+            cleaned.push(c);
+        } else if !cleaned.ends_with('.') {
+            cleaned.push('.');
+        }
+    }
+    cleaned.trim_matches('.').to_string()
+}
+
+/// Whether `name` is a legal module name: dot separated Java identifiers,
+/// none of them a keyword.
+pub fn is_valid_module_name(name: &str) -> bool {
+    name.split('.').all(|part| {
+        let mut chars = part.chars();
+        chars
+            .next()
+            .is_some_and(|c| c.is_alphabetic() || c == '_' || c == '$')
+            && chars.all(|c| c.is_alphanumeric() || c == '_' || c == '$')
+            && !KEYWORDS.contains(&part)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn version_suffixes_are_dropped() {
+        for (file_name, name) in [
+            ("foo.jar", "foo"),
+            ("foo-2.jar", "foo"),
+            ("foo-1.2.3.jar", "foo"),
+            ("foo-1.0-SNAPSHOT.jar", "foo"),
+            ("guava-31.1-jre.jar", "guava"),
+            ("commons-lang3-3.12.0.jar", "commons.lang3"),
+            ("hk2-api-2.6.1.jar", "hk2.api"),
+            ("javax.inject-1.jar", "javax.inject"),
+            // a number the version starts with must end at a dot or the end
+            ("foo-v2.jar", "foo.v2"),
+            ("foo-1bar.jar", "foo.1bar"),
+            ("foo-bar-1x-2.0.jar", "foo.bar.1x"),
+        ] {
+            assert_eq!(automatic_module_name(file_name), name, "{}", file_name);
+        }
+    }
+
+    #[test]
+    fn other_characters_become_single_dots() {
+        for (file_name, name) in [
+            ("foo_bar.jar", "foo.bar"),
+            ("jakarta.ws.rs-api-2.1.6.jar", "jakarta.ws.rs.api"),
+            ("..foo--bar__baz..jar", "foo.bar.baz"),
+            ("foo bar+baz.jar", "foo.bar.baz"),
+            // not ASCII, so not kept
+            ("caf\u{e9}-lib.jar", "caf.lib"),
+            // only the last .jar goes
+            ("foo.jar.jar", "foo.jar"),
+            ("foo.zip", "foo.zip"),
+        ] {
+            assert_eq!(automatic_module_name(file_name), name, "{}", file_name);
+        }
+    }
+
+    #[test]
+    fn directories_are_ignored() {
+        assert_eq!(automatic_module_name("lib/ext/foo-1.0.jar"), "foo");
+        assert_eq!(automatic_module_name("C:\\lib\\foo-bar.jar"), "foo.bar");
+        assert_eq!(automatic_module_name("lib-1.0/foo.jar"), "foo");
+    }
+
+    #[test]
+    fn derived_names_may_be_invalid() {
+        assert!(is_valid_module_name("foo.bar"));
+        assert!(is_valid_module_name("commons.lang3"));
+        assert!(is_valid_module_name("foo_bar.$baz"));
+        assert!(!is_valid_module_name(&automatic_module_name(
+            "foo-1bar.jar"
+        )));
+        assert!(!is_valid_module_name(&automatic_module_name("foo-int.jar")));
+        assert!(!is_valid_module_name(&automatic_module_name("1.0.jar")));
+        assert!(!is_valid_module_name(""));
+        assert!(!is_valid_module_name("foo..bar"));
+    }
+
+    #[test]
+    fn manifest_attributes_join_continuation_lines() {
+        let manifest = "Manifest-Version: 1.0\r\n\
+                        automatic-module-name: com.example.very\r\n \
+                        long.name\r\n\
+                        Created-By: 17\r\n\
+                        \r\n\
+                        Name: com/example/\r\n\
+                        Automatic-Module-Name: other\r\n";
+        assert_eq!(
+            manifest_attribute(manifest, "Automatic-Module-Name").as_deref(),
+            Some("com.example.verylong.name")
+        );
+        assert_eq!(
+            manifest_attribute(manifest, "Created-By").as_deref(),
+            Some("17")
+        );
+        assert_eq!(manifest_attribute(manifest, "Main-Class"), None);
+    }
+}
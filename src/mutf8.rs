@@ -0,0 +1,244 @@
+//! The modified UTF-8 class files store strings in (JVMS §4.4.7). It is
+//! UTF-8, except that U+0000 is encoded in two bytes, `0xC0 0x80`, so that
+//! no string holds a zero byte, and a supplementary character is encoded as
+//! the two surrogates of its UTF-16 form, in three bytes each, rather than
+//! in four bytes.
+
+use crate::prelude::*;
+
+/// Decodes `bytes`, or returns `None` if they aren't modified UTF-8: a zero
+/// byte, a byte which can't start a character, a character cut short or
+/// encoded in more bytes than it takes, a four-byte form, or a surrogate
+/// without its pair, which a Rust string can't hold.
+pub fn decode(bytes: &[u8]) -> Option<String> {
+    if bytes.iter().all(|&byte| (1..0x80).contains(&byte)) {
+        return Some(String::from_utf8(bytes.to_vec()).expect("ASCII is UTF-8"));
+    }
+    let mut value = String::with_capacity(bytes.len());
+    let mut rest = bytes;
+    while !rest.is_empty() {
+        let (c, len) = next_char(rest)?;
+        value.push(c);
+        rest = &rest[len..];
+    }
+    Some(value)
+}
+
+/// Decodes `bytes` like [`decode`], with U+FFFD in place of every byte
+/// which doesn't begin a character, for showing strings which may not be
+/// modified UTF-8.
+pub fn decode_lossy(bytes: &[u8]) -> String {
+    let mut value = String::with_capacity(bytes.len());
+    let mut rest = bytes;
+    while !rest.is_empty() {
+        let (c, len) = next_char(rest).unwrap_or((char::REPLACEMENT_CHARACTER, 1));
+        value.push(c);
+        rest = &rest[len..];
+    }
+    value
+}
+
+/// Decodes `bytes` into UTF-16 code units, as Java holds strings, or
+/// returns `None` if they aren't modified UTF-8. Unlike [`decode`], a
+/// surrogate without its pair is kept.
+pub fn decode_utf16(bytes: &[u8]) -> Option<Vec<u16>> {
+    let mut units = Vec::with_capacity(bytes.len());
+    let mut rest = bytes;
+    while !rest.is_empty() {
+        let (unit, len) = next_unit(rest)?;
+        units.push(unit);
+        rest = &rest[len..];
+    }
+    Some(units)
+}
+
+/// Encodes UTF-16 code units in modified UTF-8, each on its own, so that
+/// [`decode_utf16`] gives them back.
+pub fn encode_utf16(units: &[u16]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(units.len());
+    for &unit in units {
+        match unit {
+            0x01..=0x7f => bytes.push(unit as u8),
+            0x00 | 0x80..=0x7ff => {
+                bytes.extend_from_slice(&[0xc0 | (unit >> 6) as u8, 0x80 | (unit & 0x3f) as u8])
+            }
+            _ => bytes.extend_from_slice(&three_bytes(unit)),
+        }
+    }
+    bytes
+}
+
+/// Encodes `value` in modified UTF-8.
+pub fn encode(value: &str) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(encoded_len(value));
+    for c in value.chars() {
+        match c {
+            '\0' => bytes.extend_from_slice(&[0xc0, 0x80]),
+            c if c.len_utf8() < 4 => bytes.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes()),
+            c => {
+                for unit in c.encode_utf16(&mut [0; 2]) {
+                    bytes.extend_from_slice(&three_bytes(*unit));
+                }
+            }
+        }
+    }
+    bytes
+}
+
+/// The number of bytes [`encode`] encodes `value` in.
+pub fn encoded_len(value: &str) -> usize {
+    value
+        .chars()
+        .map(|c| match c {
+            '\0' => 2,
+            c if c.len_utf8() < 4 => c.len_utf8(),
+            _ => 6,
+        })
+        .sum()
+}
+
+/// The character `bytes` start with and the number of bytes it takes.
+fn next_char(bytes: &[u8]) -> Option<(char, usize)> {
+    let continuation = |i: usize| {
+        bytes
+            .get(i)
+            .filter(|&&byte| byte & 0xc0 == 0x80)
+            .map(|&byte| u32::from(byte & 0x3f))
+    };
+    match bytes[0] {
+        0 => None,
+        byte @ 0x01..=0x7f => Some((char::from(byte), 1)),
+        byte @ 0xc0..=0xdf => {
+            let unit = u32::from(byte & 0x1f) << 6 | continuation(1)?;
+            // only U+0000 may take more bytes than it needs
+            if unit != 0 && unit < 0x80 {
+                return None;
+            }
+            Some((char::from_u32(unit)?, 2))
+        }
+        byte @ 0xe0..=0xef => {
+            let unit = u32::from(byte & 0x0f) << 12 | continuation(1)? << 6 | continuation(2)?;
+            if unit < 0x800 {
+                return None;
+            }
+            if !(0xd800..0xdc00).contains(&unit) {
+                return Some((char::from_u32(unit)?, 3));
+            }
+            // a high surrogate must be followed by a low one
+            let low = match *bytes.get(3..6)? {
+                [0xed, second @ 0xb0..=0xbf, third @ 0x80..=0xbf] => {
+                    0xd000 | u32::from(second & 0x3f) << 6 | u32::from(third & 0x3f)
+                }
+                _ => return None,
+            };
+            let c = 0x10000 + ((unit - 0xd800) << 10) + (low - 0xdc00);
+            Some((char::from_u32(c)?, 6))
+        }
+        _ => None,
+    }
+}
+
+/// The UTF-16 code unit `bytes` start with and the number of bytes it
+/// takes.
+fn next_unit(bytes: &[u8]) -> Option<(u16, usize)> {
+    let continuation = |i: usize| {
+        bytes
+            .get(i)
+            .filter(|&&byte| byte & 0xc0 == 0x80)
+            .map(|&byte| u16::from(byte & 0x3f))
+    };
+    match bytes[0] {
+        0 => None,
+        byte @ 0x01..=0x7f => Some((u16::from(byte), 1)),
+        byte @ 0xc0..=0xdf => {
+            let unit = u16::from(byte & 0x1f) << 6 | continuation(1)?;
+            // only U+0000 may take more bytes than it needs
+            if unit != 0 && unit < 0x80 {
+                return None;
+            }
+            Some((unit, 2))
+        }
+        byte @ 0xe0..=0xef => {
+            let unit = u16::from(byte & 0x0f) << 12 | continuation(1)? << 6 | continuation(2)?;
+            if unit < 0x800 {
+                return None;
+            }
+            Some((unit, 3))
+        }
+        _ => None,
+    }
+}
+
+/// The three-byte form of a UTF-16 code unit of U+0800 or above.
+fn three_bytes(unit: u16) -> [u8; 3] {
+    [
+        0xe0 | (unit >> 12) as u8,
+        0x80 | (unit >> 6 & 0x3f) as u8,
+        0x80 | (unit & 0x3f) as u8,
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Encodes `value`, checks the bytes and decodes them again.
+    fn round_trip(value: &str, bytes: &[u8]) {
+        assert_eq!(encode(value), bytes);
+        assert_eq!(encoded_len(value), bytes.len());
+        assert_eq!(decode(bytes).as_deref(), Some(value));
+        assert_eq!(decode_lossy(bytes), value);
+    }
+
+    #[test]
+    fn ascii_is_itself() {
+        round_trip("", b"");
+        round_trip("java/lang/Object", b"java/lang/Object");
+        round_trip("Hello, World!\n", b"Hello, World!\n");
+    }
+
+    #[test]
+    fn nul_takes_two_bytes() {
+        round_trip("\0", &[0xc0, 0x80]);
+        round_trip("a\0b", &[b'a', 0xc0, 0x80, b'b']);
+        // a zero byte is never modified UTF-8
+        assert_eq!(decode(&[b'a', 0, b'b']), None);
+    }
+
+    #[test]
+    fn supplementary_characters_are_surrogate_pairs() {
+        // U+1F600, D83D DE00 in UTF-16
+        round_trip("😀", &[0xed, 0xa0, 0xbd, 0xed, 0xb8, 0x80]);
+        // U+10000 and U+10FFFF, the first and last
+        round_trip("\u{10000}", &[0xed, 0xa0, 0x80, 0xed, 0xb0, 0x80]);
+        round_trip("\u{10ffff}", &[0xed, 0xaf, 0xbf, 0xed, 0xbf, 0xbf]);
+        round_trip("x😀y", &[b'x', 0xed, 0xa0, 0xbd, 0xed, 0xb8, 0x80, b'y']);
+        assert_eq!(decode_utf16(&encode("😀")), Some(vec![0xd83d, 0xde00]));
+        // the four bytes of standard UTF-8 aren't modified UTF-8
+        assert_eq!(decode("😀".as_bytes()), None);
+    }
+
+    #[test]
+    fn characters_of_two_and_three_bytes_are_as_in_utf8() {
+        round_trip("é€", "é€".as_bytes());
+    }
+
+    #[test]
+    fn lone_surrogates_are_kept_only_as_utf16() {
+        let bytes = encode_utf16(&[0xd800, 0x41]);
+        assert_eq!(bytes, [0xed, 0xa0, 0x80, 0x41]);
+        assert_eq!(decode(&bytes), None);
+        assert_eq!(decode_utf16(&bytes), Some(vec![0xd800, 0x41]));
+        assert_eq!(decode_lossy(&bytes), "\u{fffd}\u{fffd}\u{fffd}A");
+    }
+
+    #[test]
+    fn overlong_and_cut_short_forms_are_rejected() {
+        // 'A' in two bytes
+        assert_eq!(decode(&[0xc1, 0x81]), None);
+        // U+00E9 in three bytes
+        assert_eq!(decode(&[0xe0, 0x83, 0xa9]), None);
+        assert_eq!(decode(&[0xe2, 0x82]), None);
+        assert_eq!(decode(&[0xc3]), None);
+    }
+}
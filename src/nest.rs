@@ -0,0 +1,93 @@
+//! Whether the classes of a nest agree on who's in it. The JVM only grants
+//! private access between nestmates when the host lists the member and the
+//! member names the host, so a class dropped from a shaded jar or rewritten
+//! by a bytecode tool fails with an `IllegalAccessError` at run time.
+
+use core::fmt;
+
+use crate::classfile::ClassFile;
+use crate::prelude::*;
+
+/// A way the host and members of a nest disagree, found by [`check`].
+/// Names are internal, e.g. `com/example/Outer$Inner`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NestMismatch {
+    /// The host names a host of its own, so it's a member of another nest.
+    HostHasHost { host: String, its_host: String },
+    /// A member names another class as its host, or none at all.
+    WrongHost {
+        member: String,
+        /// `None` when the member has no `NestHost` attribute.
+        its_host: Option<String>,
+    },
+    /// A member names the host, but the host doesn't list it.
+    NotListed { member: String },
+    /// The host lists a member which wasn't among the classes checked.
+    Missing { member: String },
+}
+
+impl fmt::Display for NestMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NestMismatch::HostHasHost { host, its_host } => {
+                write!(f, "nest host {} is itself a member of {}", host, its_host)
+            }
+            NestMismatch::WrongHost {
+                member,
+                its_host: Some(its_host),
+            } => write!(f, "{} names {} as its nest host", member, its_host),
+            NestMismatch::WrongHost {
+                member,
+                its_host: None,
+            } => write!(f, "{} has no NestHost attribute", member),
+            NestMismatch::NotListed { member } => {
+                write!(f, "{} is not among the host's NestMembers", member)
+            }
+            NestMismatch::Missing { member } => {
+                write!(f, "nest member {} was not found", member)
+            }
+        }
+    }
+}
+
+/// Checks `host` and `members` against each other: the host must list
+/// every member and each member must name the host, and every member the
+/// host lists must be among `members`. Empty when they agree.
+pub fn check(host: &ClassFile, members: &[&ClassFile]) -> Vec<NestMismatch> {
+    let host_name = host.class_name().unwrap_or_default();
+    let listed = host.nest_members();
+    let mut mismatches = Vec::new();
+    if let Some(its_host) = host.nest_host() {
+        mismatches.push(NestMismatch::HostHasHost {
+            host: host_name.to_string(),
+            its_host: its_host.to_string(),
+        });
+    }
+
+    let mut found = Vec::new();
+    for member in members {
+        let name = member.class_name().unwrap_or_default();
+        found.push(name);
+        match member.nest_host() {
+            Some(its_host) if its_host == host_name => {
+                if !listed.contains(&name) {
+                    mismatches.push(NestMismatch::NotListed {
+                        member: name.to_string(),
+                    });
+                }
+            }
+            its_host => mismatches.push(NestMismatch::WrongHost {
+                member: name.to_string(),
+                its_host: its_host.map(str::to_string),
+            }),
+        }
+    }
+    for member in listed {
+        if !found.contains(&member) {
+            mismatches.push(NestMismatch::Missing {
+                member: member.to_string(),
+            });
+        }
+    }
+    mismatches
+}
@@ -0,0 +1,36 @@
+//! The order aggregated results are reported in. Every report depends only
+//! on the classes it was run over, never on hashing or on the order work
+//! happened to finish in, so that its output can be diffed between runs and
+//! kept as a golden file.
+//!
+//! The rules all reports follow:
+//!
+//! - Classes, packages, attribute names and other names are listed in
+//!   ascending byte order, as kept by a `BTreeMap` or `BTreeSet` or sorted
+//!   with [`by_name`]. Class and package names compare in their internal,
+//!   `/`-separated form.
+//! - Rankings, such as the largest clone groups or the heaviest methods, come
+//!   heaviest first, with ties broken by name; see [`rank`].
+//! - Findings about single classes, and the entries which failed, are listed
+//!   in scan order: inputs in the order given, the contents of a directory in
+//!   path order, and the entries of a jar in the order of its central
+//!   directory. Anything which scans in parallel has to restore that order.
+//!
+//! `HashMap` and `HashSet` are only used for lookups, never iterated into
+//! output.
+
+/// Sorts `items` heaviest first by `weight`, breaking ties by ascending
+/// `name`. Items equal in both keep their relative order.
+pub fn rank<T, W: Ord, N: Ord + ?Sized>(
+    items: &mut [T],
+    weight: impl Fn(&T) -> W,
+    name: impl Fn(&T) -> &N,
+) {
+    items.sort_by(|a, b| weight(b).cmp(&weight(a)).then_with(|| name(a).cmp(name(b))));
+}
+
+/// Sorts `items` by ascending `name`. Items of the same name keep their
+/// relative order.
+pub fn by_name<T, N: Ord + ?Sized>(items: &mut [T], name: impl Fn(&T) -> &N) {
+    items.sort_by(|a, b| name(a).cmp(name(b)));
+}
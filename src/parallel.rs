@@ -0,0 +1,63 @@
+//! Spreading work over the cores with threads of the standard library, for
+//! the scans which parse thousands of classes. Results always come back in
+//! the order of the inputs, whatever order the work finished in, as
+//! [`crate::ordering`] asks of every report.
+//!
+//! A `ClassFile` may hold custom attributes which can't cross threads, so
+//! the work done on each thread has to parse the class and reduce it to a
+//! result of its own.
+
+use std::{
+    num::NonZeroUsize,
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+};
+
+/// How many threads to use when no number is asked for: one per core the
+/// process may run on, or one when that can't be told.
+pub fn default_jobs() -> usize {
+    thread::available_parallelism().map_or(1, NonZeroUsize::get)
+}
+
+/// `f` applied to every item, on up to `jobs` threads, in the order of
+/// `items`. Threads take the next item as they finish one, so a few large
+/// items don't hold the rest up. With one job, or one item, everything
+/// runs on the calling thread.
+pub fn map<T, R, F>(items: &[T], jobs: usize, f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+{
+    let jobs = jobs.clamp(1, items.len().max(1));
+    if jobs == 1 {
+        return items.iter().map(f).collect();
+    }
+
+    let next = AtomicUsize::new(0);
+    let mut done: Vec<(usize, R)> = thread::scope(|scope| {
+        let workers: Vec<_> = (0..jobs)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut done = Vec::new();
+                    loop {
+                        let i = next.fetch_add(1, Ordering::Relaxed);
+                        match items.get(i) {
+                            Some(item) => done.push((i, f(item))),
+                            None => return done,
+                        }
+                    }
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| match worker.join() {
+                Ok(done) => done,
+                Err(payload) => std::panic::resume_unwind(payload),
+            })
+            .collect()
+    });
+    done.sort_by_key(|&(i, _)| i);
+    done.into_iter().map(|(_, result)| result).collect()
+}
@@ -0,0 +1,532 @@
+//! Patching the scalar constants of a class in place: the `int` a version
+//! number or a feature flag compiles to, a `long` timeout, a string.
+//!
+//! Compilers share one pool entry among every use of the same value, so an
+//! entry a field's `ConstantValue` points to is often also loaded by `ldc`
+//! elsewhere in the class, or used by an annotation. [`constant_references`]
+//! lists those uses, and [`set_field_constant`] refuses to change an entry
+//! the field shares unless asked to give the field an entry of its own.
+//!
+//! Only the entry's value changes, never its kind, so a `Long` or `Double`
+//! keeps its two slots and no index after it moves. An entry added for a
+//! field goes at the end of the pool, taking two slots if it's a `Long` or
+//! `Double`.
+
+use std::fmt;
+
+use crate::{
+    attribute::{Attribute, ElementValue},
+    classfile::ClassFile,
+    constantpool::{ConstantPool, DoubleBits, FloatBits},
+    instruction,
+};
+
+const LDC: u8 = 0x12;
+const LDC2_W: u8 = 0x14;
+
+/// A new value for a constant pool entry, of the kind of the entry.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScalarConstant {
+    /// Also the kind of `boolean`, `byte`, `char` and `short` constants.
+    Integer(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    /// The text of a UTF8 entry. For a field of type `String`, the text
+    /// its `CONSTANT_String` leads to.
+    Utf8(String),
+}
+
+impl ScalarConstant {
+    /// The name of the kind, as the JVM specification gives it.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ScalarConstant::Integer(_) => "Integer",
+            ScalarConstant::Long(_) => "Long",
+            ScalarConstant::Float(_) => "Float",
+            ScalarConstant::Double(_) => "Double",
+            ScalarConstant::Utf8(_) => "Utf8",
+        }
+    }
+
+    fn to_constant(&self) -> ConstantPool {
+        match self {
+            ScalarConstant::Integer(value) => ConstantPool::Integer(*value),
+            ScalarConstant::Long(value) => ConstantPool::Long(*value),
+            ScalarConstant::Float(value) => ConstantPool::Float(FloatBits(value.to_bits())),
+            ScalarConstant::Double(value) => ConstantPool::Double(DoubleBits(value.to_bits())),
+            ScalarConstant::Utf8(value) => ConstantPool::UTF8(value.clone()),
+        }
+    }
+}
+
+impl fmt::Display for ScalarConstant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScalarConstant::Integer(value) => write!(f, "{}", value),
+            ScalarConstant::Long(value) => write!(f, "{}L", value),
+            ScalarConstant::Float(value) => write!(f, "{}F", value),
+            ScalarConstant::Double(value) => write!(f, "{}D", value),
+            ScalarConstant::Utf8(value) => write!(f, "{:?}", value),
+        }
+    }
+}
+
+/// A use of a constant pool entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConstantReference {
+    /// The `ConstantValue` of a field.
+    FieldConstant { field: String },
+    /// An `ldc`, `ldc_w` or `ldc2_w`; `method` is its name and descriptor.
+    Ldc { method: String, offset: u32 },
+    /// The value of an annotation element or of an element's default;
+    /// `location` is where the annotation is, e.g. `field TIMEOUT_MS`.
+    AnnotationValue { location: String },
+    /// Another entry of the pool, e.g. the `CONSTANT_String` of a UTF8
+    /// entry; `kind` is its kind.
+    Constant { index: u16, kind: &'static str },
+    /// Anything else: a name, descriptor or signature, a bootstrap method
+    /// argument, an attribute's name.
+    Other,
+}
+
+impl fmt::Display for ConstantReference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConstantReference::FieldConstant { field } => {
+                write!(f, "the ConstantValue of field {}", field)
+            }
+            ConstantReference::Ldc { method, offset } => {
+                write!(f, "an ldc in {} at offset {}", method, offset)
+            }
+            ConstantReference::AnnotationValue { location } => {
+                write!(f, "an annotation value on {}", location)
+            }
+            ConstantReference::Constant { index, kind } => {
+                write!(f, "constant #{}, a {}", index, kind)
+            }
+            ConstantReference::Other => write!(f, "a name, descriptor or other structural use"),
+        }
+    }
+}
+
+/// What [`set_constant`] and [`set_field_constant`] do with an entry which
+/// has other uses than the one to change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SharedConstant {
+    /// Fail without changing anything.
+    Refuse,
+    /// Add an entry holding the new value for the use being changed, and
+    /// leave the shared entry as it is.
+    Duplicate,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SetConstantError {
+    /// The index is 0 or past the end of the pool.
+    NoSuchConstant(u16),
+    /// The entry isn't of the kind of the new value.
+    KindMismatch {
+        index: u16,
+        kind: &'static str,
+        value: ScalarConstant,
+    },
+    NoSuchField(String),
+    /// The field has no `ConstantValue` attribute.
+    NotConstant(String),
+    /// The entry has uses besides the one to change, listed in full.
+    Shared {
+        index: u16,
+        references: Vec<ConstantReference>,
+    },
+    /// Adding an entry would take the pool past 65535 slots.
+    PoolFull,
+}
+
+impl fmt::Display for SetConstantError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SetConstantError::NoSuchConstant(index) => write!(f, "no constant #{}", index),
+            SetConstantError::KindMismatch { index, kind, value } => write!(
+                f,
+                "constant #{} is of kind {}, not {} like {}",
+                index,
+                kind,
+                value.kind(),
+                value
+            ),
+            SetConstantError::NoSuchField(name) => write!(f, "no field {}", name),
+            SetConstantError::NotConstant(name) => {
+                write!(f, "field {} has no ConstantValue attribute", name)
+            }
+            SetConstantError::Shared { index, references } => {
+                write!(f, "constant #{} is shared by ", index)?;
+                for (i, reference) in references.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", reference)?;
+                }
+                Ok(())
+            }
+            SetConstantError::PoolFull => write!(f, "the constant pool is full"),
+        }
+    }
+}
+
+impl std::error::Error for SetConstantError {}
+
+/// Every use of the entry at `index` within the class: fields' constant
+/// values, `ldc` instructions and annotation values in declaration order,
+/// then other entries of the pool in index order, then [`Other`] once if
+/// it's used in any other way.
+///
+/// [`Other`]: ConstantReference::Other
+pub fn constant_references(class_file: &ClassFile, index: u16) -> Vec<ConstantReference> {
+    let constant_pool = &class_file.constant_pool;
+    let utf8 = |index| ConstantPool::utf8(constant_pool, index).unwrap_or("?");
+    let mut references = Vec::new();
+
+    annotation_references(&class_file.attributes, index, "class", &mut references);
+    for field in &class_file.fields {
+        let name = utf8(field.name_index());
+        for attribute in field.attributes() {
+            if let Attribute::ConstantValue(value_index) = attribute {
+                if *value_index == index {
+                    references.push(ConstantReference::FieldConstant {
+                        field: name.to_string(),
+                    });
+                }
+            }
+        }
+        let location = format!("field {}", name);
+        annotation_references(field.attributes(), index, &location, &mut references);
+    }
+    for method in &class_file.methods {
+        let name = format!(
+            "{}{}",
+            utf8(method.name_index()),
+            utf8(method.descriptor_index())
+        );
+        let loads = method
+            .code()
+            .into_iter()
+            .flat_map(|code| instruction::decode(&code.code).map_while(Result::ok))
+            .filter(|instruction| (LDC..=LDC2_W).contains(&instruction.opcode))
+            .filter(|instruction| instruction.cp_index() == Some(index));
+        for instruction in loads {
+            references.push(ConstantReference::Ldc {
+                method: name.clone(),
+                offset: instruction.offset,
+            });
+        }
+        let location = format!("method {}", name);
+        annotation_references(method.attributes(), index, &location, &mut references);
+        if let Some(code) = method.code() {
+            annotation_references(&code.attributes, index, &location, &mut references);
+        }
+    }
+
+    // the uses above are among those the class visits; any left over are
+    // other ones
+    let mut uses = 0;
+    class_file.visit_constants(&mut |visited| uses += usize::from(visited == index));
+    let other = uses > references.len();
+
+    for (i, constant) in constant_pool.iter().enumerate() {
+        if constant.referenced_indices().contains(&index) {
+            references.push(ConstantReference::Constant {
+                index: i as u16 + 1,
+                kind: constant.kind_name(),
+            });
+        }
+    }
+    if other {
+        references.push(ConstantReference::Other);
+    }
+    references
+}
+
+/// Sets the entry at `index` to `value`, which must be of the entry's kind,
+/// returning the index of the entry holding it. Every use of the entry sees
+/// the new value, except that a UTF8 entry which is also a name or
+/// descriptor is handled according to `shared`: rewriting it in place would
+/// corrupt the class, so it's refused, or its string constants and
+/// annotation values are given an entry of their own.
+pub fn set_constant(
+    class_file: &mut ClassFile,
+    index: u16,
+    value: ScalarConstant,
+    shared: SharedConstant,
+) -> Result<u16, SetConstantError> {
+    check_kind(class_file, index, &value)?;
+    if let ScalarConstant::Utf8(_) = value {
+        let references = constant_references(class_file, index);
+        // names are held by the entries of other kinds
+        let structural = references.iter().any(|reference| match reference {
+            ConstantReference::Constant { kind, .. } => *kind != "String",
+            reference => *reference == ConstantReference::Other,
+        });
+        if structural {
+            if shared == SharedConstant::Refuse {
+                return Err(SetConstantError::Shared { index, references });
+            }
+            let new_index = push_constant(class_file, value.to_constant())?;
+            for constant in &mut class_file.constant_pool {
+                if let ConstantPool::String(string_index) = constant {
+                    if *string_index == index {
+                        *string_index = new_index;
+                    }
+                }
+            }
+            repoint_annotation_values(&mut class_file.attributes, index, new_index);
+            for field in &mut class_file.fields {
+                repoint_annotation_values(field.attributes_mut(), index, new_index);
+            }
+            for method in &mut class_file.methods {
+                repoint_annotation_values(method.attributes_mut(), index, new_index);
+            }
+            return Ok(new_index);
+        }
+    }
+    class_file.constant_pool[index as usize - 1] = value.to_constant();
+    Ok(index)
+}
+
+/// Sets the constant value of the field `name` to `value`, returning the
+/// index of the entry holding it. For a `String` field that's the UTF8
+/// entry its `CONSTANT_String` leads to. When the entry has other uses than
+/// the field, the change is handled according to `shared`: refused, or made
+/// to an entry added for the field alone.
+pub fn set_field_constant(
+    class_file: &mut ClassFile,
+    name: &str,
+    value: ScalarConstant,
+    shared: SharedConstant,
+) -> Result<u16, SetConstantError> {
+    let constant_pool = &class_file.constant_pool;
+    let field = class_file
+        .fields
+        .iter()
+        .position(|field| ConstantPool::utf8(constant_pool, field.name_index()) == Some(name))
+        .ok_or_else(|| SetConstantError::NoSuchField(name.to_string()))?;
+    let value_index = class_file.fields[field]
+        .attributes()
+        .iter()
+        .find_map(|attribute| match attribute {
+            Attribute::ConstantValue(index) => Some(*index),
+            _ => None,
+        })
+        .ok_or_else(|| SetConstantError::NotConstant(name.to_string()))?;
+    let string = match constant_pool.get((value_index as usize).wrapping_sub(1)) {
+        Some(ConstantPool::String(utf8_index)) => Some(*utf8_index),
+        _ => None,
+    };
+    let index = string.unwrap_or(value_index);
+    check_kind(class_file, index, &value)?;
+
+    let own = ConstantReference::FieldConstant {
+        field: name.to_string(),
+    };
+    let mut references = constant_references(class_file, value_index);
+    if let Some(utf8_index) = string {
+        references.extend(
+            constant_references(class_file, utf8_index)
+                .into_iter()
+                .filter(|reference| {
+                    !matches!(reference, ConstantReference::Constant { index, .. } if *index == value_index)
+                }),
+        );
+    }
+    // the field's own ConstantValue doesn't share the entry
+    if let Some(position) = references.iter().position(|reference| *reference == own) {
+        references.remove(position);
+    }
+    if references.is_empty() {
+        class_file.constant_pool[index as usize - 1] = value.to_constant();
+        return Ok(index);
+    }
+    if shared == SharedConstant::Refuse {
+        references.insert(0, own);
+        return Err(SetConstantError::Shared {
+            index: value_index,
+            references,
+        });
+    }
+    let new_index = push_constant(class_file, value.to_constant())?;
+    let new_value_index = match string {
+        Some(_) => push_constant(class_file, ConstantPool::String(new_index))?,
+        None => new_index,
+    };
+    for attribute in class_file.fields[field].attributes_mut() {
+        if let Attribute::ConstantValue(index) = attribute {
+            *index = new_value_index;
+        }
+    }
+    Ok(new_index)
+}
+
+fn check_kind(
+    class_file: &ClassFile,
+    index: u16,
+    value: &ScalarConstant,
+) -> Result<(), SetConstantError> {
+    let constant = class_file
+        .constant_pool
+        .get((index as usize).wrapping_sub(1))
+        .ok_or(SetConstantError::NoSuchConstant(index))?;
+    if constant.tag() == value.to_constant().tag() {
+        Ok(())
+    } else {
+        Err(SetConstantError::KindMismatch {
+            index,
+            kind: constant.kind_name(),
+            value: value.clone(),
+        })
+    }
+}
+
+/// Appends `constant` to the pool, returning its index.
+fn push_constant(
+    class_file: &mut ClassFile,
+    constant: ConstantPool,
+) -> Result<u16, SetConstantError> {
+    let wide = matches!(constant, ConstantPool::Long(_) | ConstantPool::Double(_));
+    class_file.constant_pool_count = class_file
+        .constant_pool_count
+        .checked_add(if wide { 2 } else { 1 })
+        .ok_or(SetConstantError::PoolFull)?;
+    class_file.constant_pool.push(constant);
+    let index = class_file.constant_pool.len() as u16;
+    if wide {
+        class_file.constant_pool.push(ConstantPool::Unusable);
+    }
+    Ok(index)
+}
+
+/// Adds a reference for every annotation value among `attributes` which
+/// uses `index`, including those of record components.
+fn annotation_references(
+    attributes: &[Attribute],
+    index: u16,
+    location: &str,
+    references: &mut Vec<ConstantReference>,
+) {
+    let mut values = Vec::new();
+    for attribute in attributes {
+        match attribute {
+            Attribute::RuntimeVisibleAnnotations(annotations)
+            | Attribute::RuntimeInvisibleAnnotations(annotations) => {
+                for annotation in annotations {
+                    values.extend(annotation.element_value_pairs.iter().map(|(_, v)| v));
+                }
+            }
+            Attribute::RuntimeVisibleParameterAnnotations(parameters)
+            | Attribute::RuntimeInvisibleParameterAnnotations(parameters) => {
+                for annotation in parameters.iter().flat_map(|p| &p.annotations) {
+                    values.extend(annotation.element_value_pairs.iter().map(|(_, v)| v));
+                }
+            }
+            Attribute::RuntimeVisibleTypeAnnotations(annotations)
+            | Attribute::RuntimeInvisibleTypeAnnotations(annotations) => {
+                for annotation in annotations {
+                    values.extend(annotation.element_value_pairs.iter().map(|(_, v)| v));
+                }
+            }
+            Attribute::AnnotationDefault(value) => values.push(value),
+            Attribute::Record(components) => {
+                for component in components {
+                    annotation_references(&component.attributes, index, location, references);
+                }
+            }
+            _ => {}
+        }
+    }
+    let mut count = 0;
+    for value in values {
+        count_const_values(value, index, &mut count);
+    }
+    for _ in 0..count {
+        references.push(ConstantReference::AnnotationValue {
+            location: location.to_string(),
+        });
+    }
+}
+
+fn count_const_values(value: &ElementValue, index: u16, count: &mut usize) {
+    match value {
+        ElementValue::ConstValue {
+            const_value_index, ..
+        } => *count += usize::from(*const_value_index == index),
+        ElementValue::AnnotationValue(annotation) => {
+            for (_, value) in &annotation.element_value_pairs {
+                count_const_values(value, index, count);
+            }
+        }
+        ElementValue::ArrayValue(values) => {
+            for value in values {
+                count_const_values(value, index, count);
+            }
+        }
+        ElementValue::EnumConstValue { .. } | ElementValue::ClassInfoIndex(_) => {}
+    }
+}
+
+/// Points the annotation values among `attributes` which use `from` at
+/// `to`, including those of code and record components.
+fn repoint_annotation_values(attributes: &mut [Attribute], from: u16, to: u16) {
+    for attribute in attributes {
+        let values: Vec<&mut ElementValue> = match attribute {
+            Attribute::RuntimeVisibleAnnotations(annotations)
+            | Attribute::RuntimeInvisibleAnnotations(annotations) => annotations
+                .iter_mut()
+                .flat_map(|annotation| annotation.element_value_pairs.iter_mut().map(|(_, v)| v))
+                .collect(),
+            Attribute::RuntimeVisibleParameterAnnotations(parameters)
+            | Attribute::RuntimeInvisibleParameterAnnotations(parameters) => parameters
+                .iter_mut()
+                .flat_map(|parameter| &mut parameter.annotations)
+                .flat_map(|annotation| annotation.element_value_pairs.iter_mut().map(|(_, v)| v))
+                .collect(),
+            Attribute::RuntimeVisibleTypeAnnotations(annotations)
+            | Attribute::RuntimeInvisibleTypeAnnotations(annotations) => annotations
+                .iter_mut()
+                .flat_map(|annotation| annotation.element_value_pairs.iter_mut().map(|(_, v)| v))
+                .collect(),
+            Attribute::AnnotationDefault(value) => vec![value],
+            Attribute::Code(code) => {
+                repoint_annotation_values(&mut code.attributes, from, to);
+                Vec::new()
+            }
+            Attribute::Record(components) => {
+                for component in components {
+                    repoint_annotation_values(&mut component.attributes, from, to);
+                }
+                Vec::new()
+            }
+            _ => Vec::new(),
+        };
+        for value in values {
+            repoint_const_values(value, from, to);
+        }
+    }
+}
+
+fn repoint_const_values(value: &mut ElementValue, from: u16, to: u16) {
+    match value {
+        ElementValue::ConstValue {
+            const_value_index, ..
+        } if *const_value_index == from => *const_value_index = to,
+        ElementValue::AnnotationValue(annotation) => {
+            for (_, value) in &mut annotation.element_value_pairs {
+                repoint_const_values(value, from, to);
+            }
+        }
+        ElementValue::ArrayValue(values) => {
+            for value in values {
+                repoint_const_values(value, from, to);
+            }
+        }
+        _ => {}
+    }
+}
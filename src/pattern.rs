@@ -0,0 +1,580 @@
+//! Searching method bodies for sequences of instructions.
+//!
+//! A pattern is a list of instruction patterns separated by `;`, each a
+//! mnemonic with an optional operand, e.g.
+//! `ldc String $s; invokevirtual java/lang/String.equals`. The operand may
+//! also be put in parentheses: `ldc(String $s)`. A `;` only separates
+//! outside quotes and parentheses, so a method descriptor can be written as
+//! is, but a field descriptor ending in `;` has to be quoted.
+//!
+//! - A mnemonic may use `*` for any run of characters (`invoke*`), and a
+//!   lone `*` matches any instruction. The short forms `aload_0` to
+//!   `aload_3` and the like also match their general mnemonic, with the
+//!   local index as operand, and `wide` instructions match the instruction
+//!   they widen.
+//! - An operand is an optional constant kind (`String`, `Class`,
+//!   `Methodref`, ...), which the instruction's constant must have,
+//!   followed by a value: `*` for any, `$name` to capture it, `"text"` for
+//!   exactly that text, or anything else as a pattern with `*` wildcards.
+//!   Capturing the same name twice requires the same value both times.
+//!
+//! Operand values are the text of the constant an instruction refers to
+//! (the string itself for a `String`, `owner.name` followed by the
+//! descriptor for a member, with the descriptor optional in the pattern),
+//! the local variable index, the immediate value, the absolute branch
+//! target, `index,increment` for `iinc` and the element type for
+//! `newarray`.
+
+use std::{collections::BTreeMap, error::Error, fmt, str::FromStr};
+
+use crate::{
+    attribute::Code,
+    budget::{AnalysisBudget, Partial},
+    cfg::ControlFlowGraph,
+    constantpool::ConstantPool,
+    instruction::{self, DecodeError, Instruction, IINC, WIDE},
+    spec,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Value {
+    Any,
+    Capture(String),
+    Exactly(String),
+    Glob(String),
+}
+
+/// A pattern for a single instruction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstructionPattern {
+    /// `None` matches any instruction.
+    mnemonic: Option<String>,
+    kind: Option<String>,
+    value: Value,
+}
+
+impl InstructionPattern {
+    /// Matches any instruction.
+    pub fn any() -> Self {
+        InstructionPattern {
+            mnemonic: None,
+            kind: None,
+            value: Value::Any,
+        }
+    }
+
+    /// Matches instructions whose mnemonic matches `mnemonic`, in which `*`
+    /// stands for any run of characters.
+    pub fn new(mnemonic: impl Into<String>) -> Self {
+        InstructionPattern {
+            mnemonic: Some(mnemonic.into()),
+            ..InstructionPattern::any()
+        }
+    }
+
+    /// Requires the constant the instruction refers to to be of `kind`, as
+    /// named in the JVM specification without the `CONSTANT_` prefix.
+    pub fn kind(mut self, kind: impl Into<String>) -> Self {
+        self.kind = Some(kind.into());
+        self
+    }
+
+    /// Requires the operand to match `pattern`, in which `*` stands for any
+    /// run of characters.
+    pub fn value(mut self, pattern: impl Into<String>) -> Self {
+        self.value = Value::Glob(pattern.into());
+        self
+    }
+
+    /// Requires the operand to be exactly `text`.
+    pub fn exactly(mut self, text: impl Into<String>) -> Self {
+        self.value = Value::Exactly(text.into());
+        self
+    }
+
+    /// Captures the operand under `name`.
+    pub fn capture(mut self, name: impl Into<String>) -> Self {
+        self.value = Value::Capture(name.into());
+        self
+    }
+
+    fn parse(text: &str) -> Result<Self, PatternError> {
+        let text = text.trim();
+        if text == "*" {
+            return Ok(InstructionPattern::any());
+        }
+        let end = text
+            .find(|c: char| c.is_whitespace() || c == '(')
+            .unwrap_or(text.len());
+        let (mnemonic, operand) = text.split_at(end);
+        if !mnemonic.contains('*')
+            && !(0..=u8::MAX).any(|op| instruction::mnemonic(op) == Some(mnemonic))
+        {
+            return Err(PatternError::new(format!(
+                "unknown instruction {:?}",
+                mnemonic
+            )));
+        }
+        let operand = operand.trim();
+        let operand = match operand.strip_prefix('(') {
+            Some(inner) => inner
+                .strip_suffix(')')
+                .ok_or_else(|| PatternError::new(format!("unclosed operand in {:?}", text)))?,
+            None => operand,
+        };
+
+        let mut pattern = InstructionPattern::new(mnemonic);
+        let tokens = tokenize(operand)?;
+        let value = match tokens.as_slice() {
+            [] => return Ok(pattern),
+            [kind] if is_constant_kind(kind) => {
+                pattern.kind = Some(kind.clone());
+                return Ok(pattern);
+            }
+            [value] => value,
+            [kind, value] if is_constant_kind(kind) => {
+                pattern.kind = Some(kind.clone());
+                value
+            }
+            [kind, _] => {
+                return Err(PatternError::new(format!(
+                    "unknown constant kind {:?}",
+                    kind
+                )))
+            }
+            _ => {
+                return Err(PatternError::new(format!(
+                    "too many operands in {:?}",
+                    text
+                )))
+            }
+        };
+        pattern.value = if value == "*" {
+            Value::Any
+        } else if let Some(name) = value.strip_prefix('$') {
+            Value::Capture(name.to_string())
+        } else if let Some(quoted) = value.strip_prefix('"') {
+            Value::Exactly(quoted.to_string())
+        } else {
+            Value::Glob(value.clone())
+        };
+        Ok(pattern)
+    }
+
+    fn matches(&self, view: &View, captures: &mut BTreeMap<String, String>) -> bool {
+        if let Some(mnemonic) = &self.mnemonic {
+            if !glob_match(mnemonic, view.mnemonic)
+                && !view.base.is_some_and(|base| glob_match(mnemonic, base))
+            {
+                return false;
+            }
+        }
+        if let Some(kind) = &self.kind {
+            if view.kind != Some(kind.as_str()) {
+                return false;
+            }
+        }
+        let operand = match (&self.value, &view.operand) {
+            (Value::Any, _) => return true,
+            (_, None) => return false,
+            (_, Some(operand)) => operand,
+        };
+        match &self.value {
+            Value::Any => true,
+            Value::Capture(name) => match captures.get(name) {
+                Some(captured) => captured == operand,
+                None => {
+                    captures.insert(name.clone(), operand.clone());
+                    true
+                }
+            },
+            Value::Exactly(text) => text == operand,
+            Value::Glob(pattern) => {
+                glob_match(pattern, operand)
+                    || (!pattern.contains(['(', ':'])
+                        && glob_match(pattern, &operand[..view.name_length]))
+            }
+        }
+    }
+}
+
+/// A sequence of instruction patterns to look for in method bodies.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Pattern {
+    instructions: Vec<InstructionPattern>,
+    across_blocks: bool,
+}
+
+impl Pattern {
+    pub fn new() -> Self {
+        Pattern::default()
+    }
+
+    /// Parses the textual syntax described in the [module documentation](self).
+    pub fn parse(text: &str) -> Result<Self, PatternError> {
+        let mut pattern = Pattern::new();
+        for part in split_outside(text, ';') {
+            if part.trim().is_empty() {
+                return Err(PatternError::new("empty instruction pattern"));
+            }
+            pattern.instructions.push(InstructionPattern::parse(part)?);
+        }
+        Ok(pattern)
+    }
+
+    /// Appends an instruction pattern.
+    pub fn then(mut self, instruction: InstructionPattern) -> Self {
+        self.instructions.push(instruction);
+        self
+    }
+
+    /// Lets matches span several basic blocks. By default a match can only
+    /// be entered at its first instruction, so no instruction after the
+    /// first may be a branch target or the start of a handler or protected
+    /// range.
+    pub fn across_blocks(mut self, across_blocks: bool) -> Self {
+        self.across_blocks = across_blocks;
+        self
+    }
+
+    /// Every place in `code` where the pattern matches, in order of offset.
+    /// Matches may overlap. An empty pattern never matches.
+    pub fn find(
+        &self,
+        code: &Code,
+        constant_pool: &[ConstantPool],
+    ) -> Result<Vec<PatternMatch>, DecodeError> {
+        self.find_within(code, constant_pool, &AnalysisBudget::unlimited())
+            .map(Partial::into_value)
+    }
+
+    /// [`Pattern::find`] unless it takes more than `budget` allows, every
+    /// place the pattern is tried at counting as a state. Matches cut short
+    /// are those starting before the place reached by then, and none when
+    /// the control flow graph needed to keep matches within blocks was cut
+    /// short.
+    pub fn find_within(
+        &self,
+        code: &Code,
+        constant_pool: &[ConstantPool],
+        budget: &AnalysisBudget,
+    ) -> Result<Partial<Vec<PatternMatch>>, DecodeError> {
+        let meter = &mut budget.meter();
+        let instructions = match meter.decode(&code.code)? {
+            Ok(instructions) => instructions,
+            Err(exceeded) => return Ok(Partial::cut_short(Vec::new(), exceeded)),
+        };
+        let views: Vec<View> = instructions
+            .iter()
+            .map(|instruction| View::new(instruction, constant_pool))
+            .collect();
+        let block_starts: Vec<u32> = if self.across_blocks {
+            Vec::new()
+        } else {
+            let cfg = ControlFlowGraph::build_metered(code, meter)?;
+            if let Some(exceeded) = cfg.exceeded {
+                return Ok(Partial::cut_short(Vec::new(), exceeded));
+            }
+            cfg.value.blocks.iter().map(|block| block.start).collect()
+        };
+
+        let length = self.instructions.len();
+        let mut matches = Vec::new();
+        if length == 0 {
+            return Ok(Partial::complete(matches));
+        }
+        for start in 0..instructions.len().saturating_sub(length - 1) {
+            if let Err(exceeded) = meter.states(1) {
+                return Ok(Partial::cut_short(matches, exceeded));
+            }
+            let window = &instructions[start..start + length];
+            if window[1..]
+                .iter()
+                .any(|instruction| block_starts.binary_search(&instruction.offset).is_ok())
+            {
+                continue;
+            }
+            let mut captures = BTreeMap::new();
+            if self
+                .instructions
+                .iter()
+                .zip(&views[start..])
+                .all(|(pattern, view)| pattern.matches(view, &mut captures))
+            {
+                let last = &window[length - 1];
+                matches.push(PatternMatch {
+                    offset: window[0].offset,
+                    end: last.offset + last.size() as u32,
+                    captures,
+                });
+            }
+        }
+        Ok(Partial::complete(matches))
+    }
+}
+
+impl FromStr for Pattern {
+    type Err = PatternError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        Pattern::parse(text)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatternMatch {
+    /// The offset of the first instruction matched.
+    pub offset: u32,
+    /// The offset just past the last instruction matched.
+    pub end: u32,
+    /// The captured operands by name.
+    pub captures: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatternError {
+    pub message: String,
+}
+
+impl PatternError {
+    fn new(message: impl Into<String>) -> Self {
+        PatternError {
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid pattern: {}", self.message)
+    }
+}
+
+impl Error for PatternError {}
+
+/// An instruction as patterns see it.
+struct View {
+    mnemonic: &'static str,
+    /// The general mnemonic of a short form or widened instruction.
+    base: Option<&'static str>,
+    /// The kind of the constant the instruction refers to.
+    kind: Option<&'static str>,
+    operand: Option<String>,
+    /// How much of `operand` comes before a member's descriptor.
+    name_length: usize,
+}
+
+impl View {
+    fn new(instruction: &Instruction, constant_pool: &[ConstantPool]) -> Self {
+        const LOADS: [&str; 5] = ["iload", "lload", "fload", "dload", "aload"];
+        const STORES: [&str; 5] = ["istore", "lstore", "fstore", "dstore", "astore"];
+        #[rustfmt::skip]
+        const ARRAY_TYPES: [&str; 8] = ["boolean", "char", "float", "double", "byte", "short", "int", "long"];
+
+        let operands = instruction.operands;
+        let mut view = View {
+            mnemonic: instruction.mnemonic(),
+            base: None,
+            kind: None,
+            operand: None,
+            name_length: 0,
+        };
+        let local = |base: &'static str, index: u8| (Some(base), Some(index.to_string()));
+        (view.base, view.operand) = match instruction.opcode {
+            0x1a..=0x2d => local(
+                LOADS[(instruction.opcode - 0x1a) as usize / 4],
+                (instruction.opcode - 0x1a) % 4,
+            ),
+            0x3b..=0x4e => local(
+                STORES[(instruction.opcode - 0x3b) as usize / 4],
+                (instruction.opcode - 0x3b) % 4,
+            ),
+            0x15..=0x19 | 0x36..=0x3a | 0xa9 => (None, Some(operands[0].to_string())),
+            0x10 => (None, Some((operands[0] as i8).to_string())),
+            0x11 => (
+                None,
+                Some(i16::from_be_bytes([operands[0], operands[1]]).to_string()),
+            ),
+            IINC => (None, Some(format!("{},{}", operands[0], operands[1] as i8))),
+            0xbc => (
+                None,
+                ARRAY_TYPES
+                    .get((operands[0] as usize).wrapping_sub(4))
+                    .map(|name| name.to_string()),
+            ),
+            WIDE => {
+                let index = u16::from_be_bytes([operands[1], operands[2]]);
+                let operand = match operands[0] {
+                    IINC => format!(
+                        "{},{}",
+                        index,
+                        i16::from_be_bytes([operands[3], operands[4]])
+                    ),
+                    _ => index.to_string(),
+                };
+                (instruction::mnemonic(operands[0]), Some(operand))
+            }
+            _ => match instruction.cp_index() {
+                Some(index) => {
+                    view.kind = (index as usize)
+                        .checked_sub(1)
+                        .and_then(|i| constant_pool.get(i))
+                        .and_then(|constant| spec::constant_spec(constant.tag()))
+                        .map(|spec| spec.name);
+                    let (operand, name_length) = constant_text(constant_pool, index);
+                    view.name_length = name_length;
+                    (None, operand)
+                }
+                None => (
+                    None,
+                    instruction.branch_targets().first().map(i64::to_string),
+                ),
+            },
+        };
+        if view.name_length == 0 {
+            view.name_length = view.operand.as_ref().map_or(0, String::len);
+        }
+        view
+    }
+}
+
+/// The text of the constant at `index`, with the length of the part of it
+/// before any descriptor.
+fn constant_text(constant_pool: &[ConstantPool], index: u16) -> (Option<String>, usize) {
+    let utf8 = |index| ConstantPool::utf8(constant_pool, index).map(str::to_string);
+    let name_and_type = |index| match (index as usize)
+        .checked_sub(1)
+        .and_then(|i| constant_pool.get(i))
+    {
+        Some(ConstantPool::NameAndType(name_index, descriptor_index)) => {
+            Some((utf8(*name_index)?, utf8(*descriptor_index)?))
+        }
+        _ => None,
+    };
+    let constant = match (index as usize)
+        .checked_sub(1)
+        .and_then(|i| constant_pool.get(i))
+    {
+        Some(constant) => constant,
+        None => return (None, 0),
+    };
+    let text = match constant {
+        ConstantPool::String(index) | ConstantPool::MethodType(index) => utf8(*index),
+        ConstantPool::Class(_) => {
+            ConstantPool::class_name(constant_pool, index).map(str::to_string)
+        }
+        ConstantPool::Integer(value) => Some(value.to_string()),
+        ConstantPool::Float(bits) => Some(bits.value().to_string()),
+        ConstantPool::Long(value) => Some(value.to_string()),
+        ConstantPool::Double(bits) => Some(bits.value().to_string()),
+        ConstantPool::FieldRef(..)
+        | ConstantPool::MethodRef(..)
+        | ConstantPool::InterfaceMethodRef(..) => {
+            let member = match ConstantPool::member_ref(constant_pool, index) {
+                Some(member) => member,
+                None => return (None, 0),
+            };
+            let name = format!("{}.{}", member.class_name, member.name);
+            let separator = if matches!(constant, ConstantPool::FieldRef(..)) {
+                ":"
+            } else {
+                ""
+            };
+            let name_length = name.len();
+            return (
+                Some(format!("{}{}{}", name, separator, member.descriptor)),
+                name_length,
+            );
+        }
+        ConstantPool::Dynamic(_, name_and_type_index)
+        | ConstantPool::InvokeDynamic(_, name_and_type_index) => {
+            let (name, descriptor) = match name_and_type(*name_and_type_index) {
+                Some(name_and_type) => name_and_type,
+                None => return (None, 0),
+            };
+            let name_length = name.len();
+            return (Some(name + &descriptor), name_length);
+        }
+        ConstantPool::MethodHandle(_, reference_index) => {
+            return constant_text(constant_pool, *reference_index)
+        }
+        _ => None,
+    };
+    (text, 0)
+}
+
+fn is_constant_kind(name: &str) -> bool {
+    (0..=u8::MAX).any(|tag| spec::constant_spec(tag).is_some_and(|spec| spec.name == name))
+}
+
+/// Splits `text` on `separator` where it isn't inside double quotes or
+/// parentheses.
+fn split_outside(text: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let (mut start, mut depth, mut quoted, mut escaped) = (0, 0usize, false, false);
+    for (i, c) in text.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            _ if quoted => {}
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            c if c == separator && depth == 0 => {
+                parts.push(&text[start..i]);
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(&text[start..]);
+    parts
+}
+
+/// Splits an operand into whitespace separated tokens. A quoted token is
+/// returned unescaped with only its opening quote, to tell it apart.
+fn tokenize(text: &str) -> Result<Vec<String>, PatternError> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '"' {
+            chars.next();
+            let mut token = String::from('"');
+            loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some('\\') => token.extend(chars.next()),
+                    Some(c) => token.push(c),
+                    None => return Err(PatternError::new(format!("unclosed quote in {:?}", text))),
+                }
+            }
+            tokens.push(token);
+        } else {
+            let mut token = String::new();
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() {
+                    break;
+                }
+                token.push(c);
+                chars.next();
+            }
+            tokens.push(token);
+        }
+    }
+    Ok(tokens)
+}
+
+/// Whether `text` matches `pattern`, in which `*` stands for any run of
+/// characters.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == text,
+        Some((prefix, rest)) => {
+            text.starts_with(prefix)
+                && (prefix.len()..=text.len())
+                    .any(|start| text.is_char_boundary(start) && glob_match(rest, &text[start..]))
+        }
+    }
+}
@@ -0,0 +1,450 @@
+//! Running several analyses over the same classes, parsing each class once.
+//!
+//! Every [`Analysis`] declares what it [`Needs`] of a class. A [`Pipeline`]
+//! works out [`ParseOptions`] which read no more than the most demanding of
+//! its analyses needs, hands every class parsed with them to each analysis
+//! in turn, and gathers their results as the sections of one report.
+//!
+//! New analyses plug in by implementing [`Analysis`]; the built-in ones are
+//! in [`crate::report`]. An analysis which only finds problems in classes,
+//! each with a code and a severity, is simpler written as a [`Check`],
+//! which the pipeline runs the same way, resolving the severity of what it
+//! finds through the pipeline's [`Policy`]. A [`Registry`] names checks
+//! for a command line to choose from, and is how a binary of its own built
+//! on [`crate::cli`] adds checks to `jvmb report`.
+//!
+//! [`Check`], [`Finding`] and [`ClassContext`] are meant to be implemented
+//! and used by other crates, and change only with
+//! [`ANALYSIS_API_VERSION`].
+//!
+//! A pipeline given an [`AnalysisBudget`] passes it on to its analyses, which
+//! run within it per method. A section whose analysis ran out of budget for
+//! some of them says so in its [`SectionStatus`].
+//!
+//! A pipeline told to [group by package](Pipeline::group_by_package) also
+//! rolls up the [`Measure`]s of each analysis by package, and adds the
+//! [`Rollup`] to its section as `by_package`.
+
+use std::{
+    cell::{Ref, RefCell},
+    rc::Rc,
+};
+
+use crate::{
+    budget::AnalysisBudget,
+    classfile::{ClassFile, ParseOptions},
+    json::Json,
+    policy::Policy,
+    rdjson::Severity,
+    rollup::{Aggregate, Measure, Rollup, Value},
+};
+
+/// The version of [`Check`], [`Finding`] and [`ClassContext`]. Bumped by
+/// any change which could break a check written against them.
+pub const ANALYSIS_API_VERSION: u32 = 1;
+
+/// How much of a class an analysis looks at. Ordered from least to most.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Needs {
+    /// The constant pool, the members and their attributes, except the
+    /// bodies of `Code` attributes, which are left as
+    /// [`Attribute::Deferred`](crate::attribute::Attribute::Deferred).
+    Declarations,
+    /// Everything, method bodies included.
+    Code,
+}
+
+/// What an analysis found over all the classes it was shown.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Section {
+    pub name: &'static str,
+    /// One line for people, e.g. `3 errors, 1 warning in 2 classes`.
+    pub summary: String,
+    pub document: Json,
+    pub status: SectionStatus,
+    /// How many findings were errors or warnings, which fail `--check`.
+    pub violations: usize,
+}
+
+/// Whether a section holds everything its analysis would have found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SectionStatus {
+    Complete,
+    /// The analysis ran out of budget for `incomplete` of the methods or
+    /// classes it looked at, and reports what it had found in them by then.
+    Partial {
+        incomplete: usize,
+    },
+}
+
+impl SectionStatus {
+    /// `Partial` if anything was `incomplete`.
+    pub fn of(incomplete: usize) -> Self {
+        match incomplete {
+            0 => SectionStatus::Complete,
+            incomplete => SectionStatus::Partial { incomplete },
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SectionStatus::Complete => "complete",
+            SectionStatus::Partial { .. } => "partial",
+        }
+    }
+}
+
+pub trait Analysis {
+    /// The name of the analysis and of its section in the report.
+    fn name(&self) -> &'static str;
+
+    fn needs(&self) -> Needs;
+
+    /// Limits what the analysis may spend. Analyses which don't look at
+    /// method bodies in depth can ignore it, as they do by default.
+    fn set_budget(&mut self, _budget: &AnalysisBudget) {}
+
+    /// Looks at one class. `source` is the input the class was found in, a
+    /// jar, directory or class file, and `entry` the name of the class's own
+    /// file or jar entry.
+    fn visit(&mut self, source: &str, entry: &str, class_file: &ClassFile);
+
+    /// The results, once every class has been visited.
+    fn finish(self: Box<Self>) -> Section;
+
+    /// What the analysis reports per class, to roll up by package. None by
+    /// default.
+    fn measures(&self) -> &'static [Measure] {
+        &[]
+    }
+
+    /// Looks at one class like [`Analysis::visit`], and returns what it
+    /// found in it: a value for each of the [`Analysis::measures`], in
+    /// order.
+    fn visit_measured(&mut self, source: &str, entry: &str, class_file: &ClassFile) -> Vec<Value> {
+        self.visit(source, entry, class_file);
+        Vec::new()
+    }
+}
+
+/// A set of analyses run together, in the order they were added.
+#[derive(Default)]
+pub struct Pipeline {
+    analyses: Vec<Box<dyn Analysis>>,
+    budget: AnalysisBudget,
+    /// Set when grouping by package: the depth packages are cut to, if any.
+    package_depth: Option<Option<usize>>,
+    /// The rollup of each analysis, when grouping by package.
+    rollups: Vec<Rollup>,
+    /// Shared with every check added.
+    policy: Rc<RefCell<Policy>>,
+}
+
+impl Pipeline {
+    pub fn new() -> Self {
+        Pipeline::default()
+    }
+
+    pub fn add(&mut self, analysis: impl Analysis + 'static) -> &mut Self {
+        let mut analysis = Box::new(analysis);
+        analysis.set_budget(&self.budget);
+        if let Some(depth) = self.package_depth {
+            self.rollups.push(Rollup::new(analysis.measures(), depth));
+        }
+        self.analyses.push(analysis);
+        self
+    }
+
+    /// Adds a check, whose findings are resolved through the pipeline's
+    /// policy.
+    pub fn add_check(&mut self, check: Box<dyn Check>) -> &mut Self {
+        self.add(CheckAnalysis {
+            check,
+            policy: Rc::clone(&self.policy),
+            classes: 0,
+            counts: [0; 3],
+            findings: Vec::new(),
+        })
+    }
+
+    /// Sets the severities and suppressions of what the checks find,
+    /// those added since included.
+    pub fn set_policy(&mut self, policy: Policy) -> &mut Self {
+        *self.policy.borrow_mut() = policy;
+        self
+    }
+
+    /// The policy, with what each suppression has suppressed so far.
+    pub fn policy(&self) -> Ref<'_, Policy> {
+        self.policy.borrow()
+    }
+
+    /// Rolls up what every analysis, those added since included, finds in
+    /// each class by package, cut to the first `depth` segments if given.
+    /// Must be called before any class is visited.
+    pub fn group_by_package(&mut self, depth: Option<usize>) -> &mut Self {
+        self.package_depth = Some(depth);
+        self.rollups = self
+            .analyses
+            .iter()
+            .map(|analysis| Rollup::new(analysis.measures(), depth))
+            .collect();
+        self
+    }
+
+    /// Sets the budget of every analysis, those added since included.
+    pub fn set_budget(&mut self, budget: AnalysisBudget) -> &mut Self {
+        self.budget = budget;
+        for analysis in &mut self.analyses {
+            analysis.set_budget(&budget);
+        }
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.analyses.is_empty()
+    }
+
+    /// What the most demanding analysis needs.
+    pub fn needs(&self) -> Needs {
+        self.analyses
+            .iter()
+            .map(|analysis| analysis.needs())
+            .max()
+            .unwrap_or(Needs::Declarations)
+    }
+
+    /// Options to parse the classes for [`Pipeline::visit`] with.
+    pub fn parse_options(&self) -> ParseOptions {
+        let mut options = ParseOptions::new();
+        if self.needs() == Needs::Declarations {
+            options.defer_attribute("Code");
+        }
+        options
+    }
+
+    /// Shows a class to every analysis.
+    pub fn visit(&mut self, source: &str, entry: &str, class_file: &ClassFile) {
+        if self.package_depth.is_none() {
+            for analysis in &mut self.analyses {
+                analysis.visit(source, entry, class_file);
+            }
+            return;
+        }
+        for (analysis, rollup) in self.analyses.iter_mut().zip(&mut self.rollups) {
+            let values = analysis.visit_measured(source, entry, class_file);
+            rollup.add(class_file, values);
+        }
+    }
+
+    pub fn finish(self) -> Vec<Section> {
+        let mut rollups = self.rollups.into_iter();
+        self.analyses
+            .into_iter()
+            .map(|analysis| {
+                let mut section = analysis.finish();
+                if let Some(rollup) = rollups.next() {
+                    section.document.push("by_package", rollup.to_json());
+                }
+                section
+            })
+            .collect()
+    }
+}
+
+/// A class shown to a [`Check`].
+#[derive(Debug, Clone, Copy)]
+pub struct ClassContext<'a> {
+    /// The input the class was found in, a jar, directory or class file.
+    pub source: &'a str,
+    /// The name of the class's own file or jar entry.
+    pub entry: &'a str,
+    pub class_file: &'a ClassFile,
+}
+
+impl ClassContext<'_> {
+    /// The internal name of the class, e.g. `java/lang/String`, or `""` if
+    /// it can't be resolved.
+    pub fn class_name(&self) -> &str {
+        self.class_file.class_name().unwrap_or("")
+    }
+}
+
+/// A problem a check found in a class.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    /// The stable code of the problem, e.g. `L0001`, which severities and
+    /// suppressions name. Codes of other crates' checks should have a
+    /// prefix of their own.
+    pub code: String,
+    /// The severity unless the policy says otherwise.
+    pub severity: Severity,
+    /// Where in the class, e.g. `method run()V`, or `""` for the class.
+    pub location: String,
+    pub message: String,
+}
+
+/// An analysis which finds problems in one class at a time. A check is
+/// run by a pipeline like any other analysis, and its section holds what
+/// it found that the policy didn't suppress:
+///
+/// ```json
+/// {"errors": 1, "warnings": 0, "infos": 0, "findings": [
+///   {"entry": "a/B.class", "code": "X0001", "severity": "error",
+///    "location": "method run()V", "message": "..."}]}
+/// ```
+///
+/// counting the errors, warnings and infos of each class when rolling up
+/// by package.
+pub trait Check {
+    /// The name of the check and of its section in the report.
+    fn name(&self) -> &'static str;
+
+    fn needs(&self) -> Needs;
+
+    /// Limits what the check may spend, like [`Analysis::set_budget`].
+    fn set_budget(&mut self, _budget: &AnalysisBudget) {}
+
+    /// What the check finds in one class.
+    fn check(&mut self, class: &ClassContext) -> Vec<Finding>;
+
+    /// Adds to the section once every class has been checked, e.g. a
+    /// summary of its own or members of the document. Nothing by default.
+    fn finish(self: Box<Self>, _section: &mut Section) {}
+}
+
+/// Creates a check for each run.
+type CreateCheck = Box<dyn Fn() -> Box<dyn Check>>;
+
+/// Names checks to create, for a command line to choose from.
+#[derive(Default)]
+pub struct Registry {
+    checks: Vec<(&'static str, CreateCheck)>,
+}
+
+impl Registry {
+    pub fn new() -> Self {
+        Registry::default()
+    }
+
+    /// Registers the check `create` creates as `name`, replacing any
+    /// registered as `name` before.
+    pub fn register(
+        &mut self,
+        name: &'static str,
+        create: impl Fn() -> Box<dyn Check> + 'static,
+    ) -> &mut Self {
+        self.checks.retain(|(known, _)| *known != name);
+        self.checks.push((name, Box::new(create)));
+        self
+    }
+
+    /// The names registered, in the order they were.
+    pub fn names(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.checks.iter().map(|(name, _)| *name)
+    }
+
+    /// A new check of those registered as `name`.
+    pub fn create(&self, name: &str) -> Option<Box<dyn Check>> {
+        self.checks
+            .iter()
+            .find(|(known, _)| *known == name)
+            .map(|(_, create)| create())
+    }
+}
+
+const FINDING_MEASURES: &[Measure] = &[
+    Measure::new("errors", Aggregate::Sum),
+    Measure::new("warnings", Aggregate::Sum),
+    Measure::new("infos", Aggregate::Sum),
+];
+
+/// Runs a check as an analysis.
+struct CheckAnalysis {
+    check: Box<dyn Check>,
+    policy: Rc<RefCell<Policy>>,
+    classes: usize,
+    /// errors, warnings, infos
+    counts: [usize; 3],
+    findings: Vec<Json>,
+}
+
+impl Analysis for CheckAnalysis {
+    fn name(&self) -> &'static str {
+        self.check.name()
+    }
+
+    fn needs(&self) -> Needs {
+        self.check.needs()
+    }
+
+    fn set_budget(&mut self, budget: &AnalysisBudget) {
+        self.check.set_budget(budget);
+    }
+
+    fn visit(&mut self, source: &str, entry: &str, class_file: &ClassFile) {
+        self.visit_measured(source, entry, class_file);
+    }
+
+    fn measures(&self) -> &'static [Measure] {
+        FINDING_MEASURES
+    }
+
+    fn visit_measured(&mut self, source: &str, entry: &str, class_file: &ClassFile) -> Vec<Value> {
+        let class = ClassContext {
+            source,
+            entry,
+            class_file,
+        };
+        let mut counts = [0; 3];
+        self.classes += 1;
+        for finding in self.check.check(&class) {
+            let severity = match self.policy.borrow_mut().resolve(
+                &finding.code,
+                class.class_name(),
+                finding.severity,
+            ) {
+                Some(severity) => severity,
+                None => continue,
+            };
+            let (count, name) = match severity {
+                Severity::Error => (0, "error"),
+                Severity::Warning => (1, "warning"),
+                Severity::Info => (2, "info"),
+            };
+            counts[count] += 1;
+            self.findings.push(Json::object([
+                ("entry", entry.into()),
+                ("code", finding.code.into()),
+                ("severity", name.into()),
+                ("location", finding.location.into()),
+                ("message", finding.message.into()),
+            ]));
+        }
+        for (total, count) in self.counts.iter_mut().zip(counts) {
+            *total += count;
+        }
+        counts.iter().map(|&count| count.into()).collect()
+    }
+
+    fn finish(self: Box<Self>) -> Section {
+        let [errors, warnings, infos] = self.counts;
+        let mut section = Section {
+            name: self.check.name(),
+            summary: format!(
+                "{} errors, {} warnings in {} classes",
+                errors, warnings, self.classes
+            ),
+            document: Json::object([
+                ("errors", Json::Int(errors as i64)),
+                ("warnings", Json::Int(warnings as i64)),
+                ("infos", Json::Int(infos as i64)),
+                ("findings", Json::Array(self.findings)),
+            ]),
+            status: SectionStatus::Complete,
+            violations: errors + warnings,
+        };
+        self.check.finish(&mut section);
+        section
+    }
+}
@@ -0,0 +1,101 @@
+//! Which findings matter to a project: severities replacing the defaults of
+//! finding codes, and suppressions of codes, in every class or only those
+//! matching a glob.
+//!
+//! Codes are stable across releases: `V` for verification, e.g. `V0203`,
+//! `L` for lint and `M` for metrics. A finding is suppressed by the first
+//! suppression of its code whose glob matches the internal name of its
+//! class, e.g. `com/thirdparty/**`, and each suppression counts the
+//! findings it suppressed, so ones which no longer match anything can be
+//! found and removed.
+
+use crate::prelude::*;
+use crate::rdjson::Severity;
+
+#[derive(Debug, Clone, Default)]
+pub struct Policy {
+    /// code -> severity
+    severities: Vec<(String, Severity)>,
+    suppressions: Vec<Suppression>,
+}
+
+/// A suppression of one code, and how many findings it suppressed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Suppression {
+    pub code: String,
+    /// The glob the internal names of the classes must match, or `None`
+    /// for every class. `*` stands for any run of characters within a
+    /// package segment and `**` for any run at all.
+    pub classes: Option<String>,
+    pub matched: usize,
+}
+
+impl Policy {
+    /// Reports findings with `code` as `severity`, whatever their default.
+    pub fn set_severity(&mut self, code: &str, severity: Severity) {
+        self.severities.retain(|(known, _)| known != code);
+        self.severities.push((code.to_string(), severity));
+    }
+
+    /// Suppresses findings with `code` in the classes matching `classes`,
+    /// or in every class.
+    pub fn suppress(&mut self, code: &str, classes: Option<&str>) {
+        self.suppressions.push(Suppression {
+            code: code.to_string(),
+            classes: classes.map(str::to_string),
+            matched: 0,
+        });
+    }
+
+    /// The severity of a finding with `code` in the class `class_name`,
+    /// which it has by `default`, or `None` if it's suppressed.
+    pub fn resolve(&mut self, code: &str, class_name: &str, default: Severity) -> Option<Severity> {
+        let suppression = self.suppressions.iter_mut().find(|suppression| {
+            suppression.code == code
+                && suppression
+                    .classes
+                    .as_deref()
+                    .is_none_or(|classes| glob_match(classes, class_name))
+        });
+        if let Some(suppression) = suppression {
+            suppression.matched += 1;
+            return None;
+        }
+        Some(
+            self.severities
+                .iter()
+                .find(|(known, _)| known == code)
+                .map_or(default, |(_, severity)| *severity),
+        )
+    }
+
+    /// The suppressions, in the order they were made, with what each has
+    /// suppressed so far.
+    pub fn suppressions(&self) -> &[Suppression] {
+        &self.suppressions
+    }
+}
+
+fn glob_match(pattern: &str, text: &str) -> bool {
+    // `**/` may also stand for no package at all
+    if pattern
+        .strip_prefix("**/")
+        .is_some_and(|rest| glob_match(rest, text))
+    {
+        return true;
+    }
+    if let Some(rest) = pattern.strip_prefix("**") {
+        return (0..=text.len())
+            .any(|start| text.is_char_boundary(start) && glob_match(rest, &text[start..]));
+    }
+    if let Some(rest) = pattern.strip_prefix('*') {
+        let segment = text.find('/').unwrap_or(text.len());
+        return (0..=segment)
+            .any(|start| text.is_char_boundary(start) && glob_match(rest, &text[start..]));
+    }
+    match (pattern.chars().next(), text.chars().next()) {
+        (None, None) => true,
+        (Some(p), Some(t)) if p == t => glob_match(&pattern[p.len_utf8()..], &text[t.len_utf8()..]),
+        _ => false,
+    }
+}
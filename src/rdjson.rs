@@ -0,0 +1,183 @@
+//! Findings as reviewdog diagnostics, in the one-object-per-line rdjsonl
+//! shape code review bots read, placed in the source file of the class
+//! they're about.
+//!
+//! A finding about a method is placed at the first line of the method's
+//! `LineNumberTable`, in the file its class's `SourceFile` names under the
+//! directory of its package, e.g. `com/example/Foo.java`. One about the
+//! class itself is placed at line 1 of that file. When the class lacks the
+//! debug information, the finding falls back to line 1 of the class file
+//! it came from, with a note saying why.
+
+use crate::prelude::*;
+use crate::{
+    attribute::Attribute, classfile::ClassFile, constantpool::ConstantPool, json::Json,
+    methodinfo::MethodInfo,
+};
+
+/// The name findings are reported under, as their `source`.
+pub const SOURCE_NAME: &str = "jvmb";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+    Info,
+}
+
+impl Severity {
+    /// The severity named `name` in options and configuration: `error`,
+    /// `warning` (or `warn`) or `info`.
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "error" => Some(Severity::Error),
+            "warning" | "warn" => Some(Severity::Warning),
+            "info" => Some(Severity::Info),
+            _ => None,
+        }
+    }
+
+    /// The name of the severity in rdjson.
+    pub fn name(self) -> &'static str {
+        match self {
+            Severity::Error => "ERROR",
+            Severity::Warning => "WARNING",
+            Severity::Info => "INFO",
+        }
+    }
+}
+
+/// Where a finding is reported.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceLocation {
+    /// A source path relative to the root of the source tree, or the class
+    /// file the finding came from when it can't be placed in the source.
+    pub path: String,
+    /// The line, from 1.
+    pub line: u32,
+    /// Why the finding couldn't be placed in the source, if it couldn't.
+    pub note: Option<&'static str>,
+}
+
+impl SourceLocation {
+    /// Line 1 of `path`, a class file, for a finding which couldn't be
+    /// placed in the source because of `note`.
+    pub fn unmapped(path: &str, note: &'static str) -> Self {
+        SourceLocation {
+            path: path.to_string(),
+            line: 1,
+            note: Some(note),
+        }
+    }
+}
+
+/// Places the findings about one class in its source file.
+#[derive(Debug)]
+pub struct SourceLocator<'a> {
+    /// The display name of the class file, e.g. `app.jar!com/app/Main.class`.
+    entry: &'a str,
+    source_path: Option<String>,
+}
+
+impl<'a> SourceLocator<'a> {
+    pub fn new(class_file: &ClassFile, entry: &'a str) -> Self {
+        SourceLocator {
+            entry,
+            source_path: source_path(class_file),
+        }
+    }
+
+    /// Where a finding about the class as a whole goes.
+    pub fn class(&self) -> SourceLocation {
+        match &self.source_path {
+            Some(path) => SourceLocation {
+                path: path.clone(),
+                line: 1,
+                note: None,
+            },
+            None => SourceLocation::unmapped(self.entry, "the class has no SourceFile attribute"),
+        }
+    }
+
+    /// Where a finding about `method` goes: the first line of its code.
+    pub fn method(&self, method: &MethodInfo) -> SourceLocation {
+        let path = match &self.source_path {
+            Some(path) => path,
+            None => return self.class(),
+        };
+        let first_line = method
+            .line_number_table()
+            .and_then(|table| table.iter().map(|line| line.line_number).min());
+        match first_line {
+            Some(line) => SourceLocation {
+                path: path.clone(),
+                line: u32::from(line.max(1)),
+                note: None,
+            },
+            None if method.code().is_none() => {
+                SourceLocation::unmapped(self.entry, "the method has no code, so no line numbers")
+            }
+            None => SourceLocation::unmapped(self.entry, "the method has no LineNumberTable"),
+        }
+    }
+}
+
+/// One finding, as a line of rdjsonl.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// The stable code of the finding, e.g. `L0003`.
+    pub code: String,
+    pub message: String,
+    pub location: SourceLocation,
+}
+
+impl Diagnostic {
+    /// The diagnostic in rdjson. A note on the location is added to the
+    /// message, rdjson having nowhere else for it.
+    pub fn to_json(&self) -> Json {
+        let message = match self.location.note {
+            Some(note) => format!("{} (reported at the class file: {})", self.message, note),
+            None => self.message.clone(),
+        };
+        Json::object([
+            ("message", message.into()),
+            (
+                "location",
+                Json::object([
+                    ("path", self.location.path.as_str().into()),
+                    (
+                        "range",
+                        Json::object([(
+                            "start",
+                            Json::object([("line", self.location.line.into())]),
+                        )]),
+                    ),
+                ]),
+            ),
+            ("severity", self.severity.name().into()),
+            ("source", Json::object([("name", SOURCE_NAME.into())])),
+            ("code", Json::object([("value", self.code.as_str().into())])),
+        ])
+    }
+}
+
+/// The path of the source file of `class_file` under the root of the
+/// source tree, if it has a `SourceFile` attribute.
+fn source_path(class_file: &ClassFile) -> Option<String> {
+    let constant_pool = &class_file.constant_pool;
+    let source_file = class_file
+        .attributes
+        .iter()
+        .find_map(|attribute| match attribute {
+            Attribute::SourceFile(source_file) => {
+                ConstantPool::utf8(constant_pool, source_file.sourcefile_index)
+            }
+            _ => None,
+        })?;
+    let class_name = ConstantPool::class_name(constant_pool, class_file.this_class)?;
+    Some(match class_name.rsplit_once('/') {
+        Some((package, _)) => format!("{}/{}", package, source_file),
+        None => source_file.to_string(),
+    })
+}
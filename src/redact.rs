@@ -0,0 +1,65 @@
+use std::borrow::Cow;
+
+use crate::{
+    constantpool::ConstantPool,
+    regex::{Regex, RegexError},
+    sha256::{hex, Sha256},
+};
+
+/// Decides which string literals are hidden from output. Only values of
+/// `CONSTANT_String` entries are ever redacted, never names or descriptors.
+#[derive(Debug, Clone, Default)]
+pub struct Redactor {
+    patterns: Vec<Regex>,
+    all_strings: bool,
+}
+
+impl Redactor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_pattern(&mut self, pattern: &str) -> Result<(), RegexError> {
+        self.patterns.push(Regex::new(pattern)?);
+        Ok(())
+    }
+
+    pub fn redact_all_strings(&mut self, all_strings: bool) {
+        self.all_strings = all_strings;
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.all_strings || !self.patterns.is_empty()
+    }
+
+    pub fn should_redact(&self, value: &str) -> bool {
+        self.all_strings || self.patterns.iter().any(|pattern| pattern.is_match(value))
+    }
+
+    /// The text to show for a string literal: either the value itself or a
+    /// placeholder carrying a hash prefix, so equal secrets stay recognizable.
+    pub fn string<'a>(&self, value: &'a str) -> Cow<'a, str> {
+        if self.should_redact(value) {
+            let digest = Sha256::digest(value.as_bytes());
+            Cow::Owned(format!("«redacted:{}»", &hex(&digest)[..8]))
+        } else {
+            Cow::Borrowed(value)
+        }
+    }
+
+    /// Indices of the UTF8 entries holding string literals, i.e. the ones a
+    /// `CONSTANT_String` points at. These are the only entries whose text
+    /// may be redacted in a pool listing.
+    pub fn string_literal_indices(constant_pool: &[ConstantPool]) -> Vec<u16> {
+        let mut indices: Vec<u16> = constant_pool
+            .iter()
+            .filter_map(|constant| match constant {
+                ConstantPool::String(string_index) => Some(*string_index),
+                _ => None,
+            })
+            .collect();
+        indices.sort_unstable();
+        indices.dedup();
+        indices
+    }
+}
@@ -0,0 +1,123 @@
+//! Heuristics for what a class reaches reflectively, judged by the string
+//! literals it passes straight to the reflection and resource lookup APIs.
+//!
+//! A literal counts when it's loaded by `ldc` and reaches the call with
+//! nothing but other argument pushes in between. Names computed at runtime
+//! are invisible to this, so the results are a lower bound.
+
+use std::collections::BTreeSet;
+
+use crate::{attribute::Attribute, classfile::ClassFile, constantpool::ConstantPool, instruction};
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ReflectiveUses {
+    /// Binary names (`com.example.Outer$Inner`) passed to `Class.forName`.
+    pub classes: BTreeSet<String>,
+    /// Resource paths, relative to the class path root, passed to the
+    /// `getResource` family.
+    pub resources: BTreeSet<String>,
+}
+
+/// Finds the literal class names and resource paths the class looks up.
+/// Relative names given to `Class.getResource` are resolved against the
+/// package of the calling class, which is right whenever the lookup goes
+/// through the caller's own `Class`.
+pub fn reflective_uses(class_file: &ClassFile) -> ReflectiveUses {
+    let constant_pool = &class_file.constant_pool;
+    let package = ConstantPool::class_name(constant_pool, class_file.this_class)
+        .and_then(|name| name.rsplit_once('/'))
+        .map_or("", |(package, _)| package);
+    let mut uses = ReflectiveUses::default();
+
+    for code in class_file.methods.iter().filter_map(|method| method.code()) {
+        let mut literal: Option<&str> = None;
+        for instruction in instruction::decode(&code.code).flatten() {
+            if let (0x12 | 0x13, Some(index)) = (instruction.opcode, instruction.cp_index()) {
+                let constant = (index as usize)
+                    .checked_sub(1)
+                    .and_then(|i| constant_pool.get(i));
+                if let Some(ConstantPool::String(value)) = constant {
+                    literal = ConstantPool::utf8(constant_pool, *value);
+                }
+                continue;
+            }
+            if !instruction.is_invoke() {
+                if !instruction.branch_targets().is_empty() {
+                    literal = None;
+                }
+                continue;
+            }
+            let method = match instruction
+                .cp_index()
+                .and_then(|index| ConstantPool::member_ref(constant_pool, index))
+            {
+                Some(method) => method,
+                None => {
+                    literal = None;
+                    continue;
+                }
+            };
+            let takes_name = method.descriptor.starts_with("(Ljava/lang/String;");
+            match (literal, method.class_name, method.name) {
+                (Some(name), "java/lang/Class", "forName") if takes_name => {
+                    uses.classes.insert(name.to_string());
+                }
+                (Some(name), "java/lang/Class", "getResource" | "getResourceAsStream")
+                    if takes_name =>
+                {
+                    uses.resources.insert(match name.strip_prefix('/') {
+                        Some(absolute) => absolute.to_string(),
+                        None if package.is_empty() => name.to_string(),
+                        None => format!("{}/{}", package, name),
+                    });
+                }
+                (
+                    Some(name),
+                    "java/lang/ClassLoader",
+                    "getResource"
+                    | "getResourceAsStream"
+                    | "getResources"
+                    | "getSystemResource"
+                    | "getSystemResourceAsStream"
+                    | "getSystemResources",
+                ) if takes_name => {
+                    uses.resources.insert(name.to_string());
+                }
+                // fetching the class loader to pass along doesn't use up
+                // the name pushed before it
+                _ if method.descriptor.ends_with(")Ljava/lang/ClassLoader;") => continue,
+                _ => {}
+            }
+            literal = None;
+        }
+    }
+    uses
+}
+
+/// Whether the class, or any of its fields or methods, carries an annotation
+/// of the type with the given descriptor (`Lcom/example/Keep;`), visible at
+/// runtime or not.
+pub fn is_annotated_with(class_file: &ClassFile, type_descriptor: &str) -> bool {
+    let constant_pool = &class_file.constant_pool;
+    let annotated = |attributes: &[Attribute]| {
+        attributes.iter().any(|attribute| match attribute {
+            Attribute::RuntimeVisibleAnnotations(annotations)
+            | Attribute::RuntimeInvisibleAnnotations(annotations) => {
+                annotations.iter().any(|annotation| {
+                    ConstantPool::utf8(constant_pool, annotation.type_index)
+                        == Some(type_descriptor)
+                })
+            }
+            _ => false,
+        })
+    };
+    annotated(&class_file.attributes)
+        || class_file
+            .fields
+            .iter()
+            .any(|field| annotated(field.attributes()))
+        || class_file
+            .methods
+            .iter()
+            .any(|method| annotated(method.attributes()))
+}
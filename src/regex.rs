@@ -0,0 +1,363 @@
+//! A small backtracking regular expression engine covering the common
+//! syntax: literals, `.`, character classes, `\d \w \s` escapes, anchors,
+//! groups, alternation and the `* + ? {m,n}` quantifiers.
+
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegexError {
+    pub pattern: String,
+    pub offset: usize,
+    pub message: &'static str,
+}
+
+impl fmt::Display for RegexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid regex {:?} at offset {}: {}",
+            self.pattern, self.offset, self.message
+        )
+    }
+}
+
+impl std::error::Error for RegexError {}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Char(char),
+    Any,
+    Class {
+        ranges: Vec<(char, char)>,
+        negated: bool,
+    },
+    Start,
+    End,
+    Concat(Vec<Node>),
+    Alternation(Vec<Node>),
+    Repeat {
+        node: Box<Node>,
+        min: usize,
+        max: Option<usize>,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct Regex {
+    pattern: String,
+    root: Node,
+}
+
+impl Regex {
+    pub fn new(pattern: &str) -> Result<Self, RegexError> {
+        let mut parser = Parser {
+            pattern,
+            chars: pattern.chars().collect(),
+            pos: 0,
+        };
+        let root = parser.alternation()?;
+        if parser.pos != parser.chars.len() {
+            return Err(parser.error("unmatched ')'"));
+        }
+        Ok(Regex {
+            pattern: pattern.to_string(),
+            root,
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.pattern
+    }
+
+    /// Whether the pattern matches anywhere in `text`.
+    pub fn is_match(&self, text: &str) -> bool {
+        self.find(text).is_some()
+    }
+
+    /// Byte range of the leftmost match in `text`.
+    pub fn find(&self, text: &str) -> Option<(usize, usize)> {
+        let chars: Vec<char> = text.chars().collect();
+        let offsets: Vec<usize> = text
+            .char_indices()
+            .map(|(i, _)| i)
+            .chain(std::iter::once(text.len()))
+            .collect();
+        (0..=chars.len()).find_map(|start| {
+            let mut end = None;
+            matches(&self.root, &chars, start, &mut |pos| {
+                end = Some(pos);
+                true
+            });
+            end.map(|end| (offsets[start], offsets[end]))
+        })
+    }
+
+    /// Whether the pattern matches the whole of `text`.
+    pub fn is_full_match(&self, text: &str) -> bool {
+        let chars: Vec<char> = text.chars().collect();
+        matches(&self.root, &chars, 0, &mut |pos| pos == chars.len())
+    }
+}
+
+/// Matches `node` at `pos`, calling `next` with every position the match can
+/// end at until it returns true.
+fn matches(node: &Node, input: &[char], pos: usize, next: &mut dyn FnMut(usize) -> bool) -> bool {
+    match node {
+        Node::Char(c) => input.get(pos) == Some(c) && next(pos + 1),
+        Node::Any => pos < input.len() && next(pos + 1),
+        Node::Class { ranges, negated } => match input.get(pos) {
+            Some(c) => {
+                let in_class = ranges.iter().any(|&(lo, hi)| lo <= *c && *c <= hi);
+                in_class != *negated && next(pos + 1)
+            }
+            None => false,
+        },
+        Node::Start => pos == 0 && next(pos),
+        Node::End => pos == input.len() && next(pos),
+        Node::Concat(nodes) => matches_sequence(nodes, input, pos, next),
+        Node::Alternation(alternatives) => alternatives
+            .iter()
+            .any(|alternative| matches(alternative, input, pos, next)),
+        Node::Repeat { node, min, max } => matches_repeat(node, *min, *max, 0, input, pos, next),
+    }
+}
+
+fn matches_sequence(
+    nodes: &[Node],
+    input: &[char],
+    pos: usize,
+    next: &mut dyn FnMut(usize) -> bool,
+) -> bool {
+    match nodes.split_first() {
+        Some((first, rest)) => matches(first, input, pos, &mut |pos| {
+            matches_sequence(rest, input, pos, next)
+        }),
+        None => next(pos),
+    }
+}
+
+fn matches_repeat(
+    node: &Node,
+    min: usize,
+    max: Option<usize>,
+    count: usize,
+    input: &[char],
+    pos: usize,
+    next: &mut dyn FnMut(usize) -> bool,
+) -> bool {
+    if max.is_none_or(|max| count < max)
+        && matches(node, input, pos, &mut |end| {
+            // An empty iteration can't make progress, so stop repeating.
+            end != pos && matches_repeat(node, min, max, count + 1, input, end, next)
+        })
+    {
+        return true;
+    }
+    count >= min && next(pos)
+}
+
+struct Parser<'a> {
+    pattern: &'a str,
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: &'static str) -> RegexError {
+        RegexError {
+            pattern: self.pattern.to_string(),
+            offset: self.pos,
+            message,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn alternation(&mut self) -> Result<Node, RegexError> {
+        let mut alternatives = vec![self.concat()?];
+        while self.peek() == Some('|') {
+            self.pos += 1;
+            alternatives.push(self.concat()?);
+        }
+        Ok(if alternatives.len() == 1 {
+            alternatives.pop().unwrap()
+        } else {
+            Node::Alternation(alternatives)
+        })
+    }
+
+    fn concat(&mut self) -> Result<Node, RegexError> {
+        let mut nodes = Vec::new();
+        while let Some(c) = self.peek() {
+            if c == '|' || c == ')' {
+                break;
+            }
+            let atom = self.atom()?;
+            nodes.push(self.quantifier(atom)?);
+        }
+        Ok(Node::Concat(nodes))
+    }
+
+    fn quantifier(&mut self, atom: Node) -> Result<Node, RegexError> {
+        let (min, max) = match self.peek() {
+            Some('*') => (0, None),
+            Some('+') => (1, None),
+            Some('?') => (0, Some(1)),
+            Some('{') => {
+                let start = self.pos;
+                match self.bounds() {
+                    Some(bounds) => {
+                        return Ok(Node::Repeat {
+                            node: Box::new(atom),
+                            min: bounds.0,
+                            max: bounds.1,
+                        })
+                    }
+                    None => {
+                        self.pos = start;
+                        return Ok(atom);
+                    }
+                }
+            }
+            _ => return Ok(atom),
+        };
+        if matches!(atom, Node::Start | Node::End) {
+            return Err(self.error("nothing to repeat"));
+        }
+        self.pos += 1;
+        Ok(Node::Repeat {
+            node: Box::new(atom),
+            min,
+            max,
+        })
+    }
+
+    /// Parses `{m}`, `{m,}` or `{m,n}`, leaving the position untouched when
+    /// the braces aren't a valid quantifier so they're taken literally.
+    fn bounds(&mut self) -> Option<(usize, Option<usize>)> {
+        self.pos += 1;
+        let number = |parser: &mut Self| {
+            let start = parser.pos;
+            while parser.peek().is_some_and(|c| c.is_ascii_digit()) {
+                parser.pos += 1;
+            }
+            parser.chars[start..parser.pos]
+                .iter()
+                .collect::<String>()
+                .parse::<usize>()
+                .ok()
+        };
+        let min = number(self)?;
+        let max = match self.peek() {
+            Some(',') => {
+                self.pos += 1;
+                number(self)
+            }
+            _ => Some(min),
+        };
+        if self.peek() != Some('}') || max.is_some_and(|max| max < min) {
+            return None;
+        }
+        self.pos += 1;
+        Some((min, max))
+    }
+
+    fn atom(&mut self) -> Result<Node, RegexError> {
+        let c = self.peek().unwrap();
+        self.pos += 1;
+        match c {
+            '.' => Ok(Node::Any),
+            '^' => Ok(Node::Start),
+            '$' => Ok(Node::End),
+            '(' => {
+                // Non-capturing groups behave the same as plain ones here.
+                if self.chars[self.pos..].starts_with(&['?', ':']) {
+                    self.pos += 2;
+                }
+                let node = self.alternation()?;
+                if self.peek() != Some(')') {
+                    return Err(self.error("unclosed group"));
+                }
+                self.pos += 1;
+                Ok(node)
+            }
+            '[' => self.class(),
+            '\\' => self
+                .escape()
+                .map(|(ranges, negated)| match ranges.as_slice() {
+                    [(lo, hi)] if lo == hi && !negated => Node::Char(*lo),
+                    _ => Node::Class { ranges, negated },
+                }),
+            '*' | '+' | '?' => Err(self.error("nothing to repeat")),
+            c => Ok(Node::Char(c)),
+        }
+    }
+
+    fn escape(&mut self) -> Result<(Vec<(char, char)>, bool), RegexError> {
+        let c = self
+            .peek()
+            .ok_or_else(|| self.error("trailing backslash"))?;
+        self.pos += 1;
+        let digits = vec![('0', '9')];
+        let word = vec![('0', '9'), ('A', 'Z'), ('_', '_'), ('a', 'z')];
+        let space = vec![
+            (' ', ' '),
+            ('\t', '\t'),
+            ('\n', '\n'),
+            ('\r', '\r'),
+            ('\x0B', '\x0C'),
+        ];
+        Ok(match c {
+            'd' => (digits, false),
+            'D' => (digits, true),
+            'w' => (word, false),
+            'W' => (word, true),
+            's' => (space, false),
+            'S' => (space, true),
+            'n' => (vec![('\n', '\n')], false),
+            't' => (vec![('\t', '\t')], false),
+            'r' => (vec![('\r', '\r')], false),
+            c => (vec![(c, c)], false),
+        })
+    }
+
+    fn class(&mut self) -> Result<Node, RegexError> {
+        let negated = self.peek() == Some('^');
+        if negated {
+            self.pos += 1;
+        }
+        let mut ranges = Vec::new();
+        let mut first = true;
+        loop {
+            let c = self
+                .peek()
+                .ok_or_else(|| self.error("unclosed character class"))?;
+            self.pos += 1;
+            match c {
+                ']' if !first => break,
+                '\\' => {
+                    let (escaped, escaped_negated) = self.escape()?;
+                    if escaped_negated {
+                        return Err(self.error("negated escape inside character class"));
+                    }
+                    ranges.extend(escaped);
+                }
+                c if self.peek() == Some('-')
+                    && self.chars.get(self.pos + 1).is_some_and(|&c| c != ']') =>
+                {
+                    let hi = self.chars[self.pos + 1];
+                    self.pos += 2;
+                    if hi < c {
+                        return Err(self.error("invalid range in character class"));
+                    }
+                    ranges.push((c, hi));
+                }
+                c => ranges.push((c, c)),
+            }
+            first = false;
+        }
+        Ok(Node::Class { ranges, negated })
+    }
+}
@@ -0,0 +1,481 @@
+//! The `synchronized` blocks and try-with-resources statements javac
+//! compiles a method body's bytecode from, recovered from its shape, and
+//! a check that its monitors are entered and exited in pairs.
+//!
+//! A `synchronized` block keeps its lock in a local, enters the monitor and
+//! exits it on every way out of the block, the exceptional one through a
+//! catch-all handler covering the block which exits and rethrows:
+//!
+//! ```text
+//!     aload_1
+//!     dup
+//!     astore_2
+//!     monitorenter
+//! L0  ...                      // the block
+//!     aload_2
+//!     monitorexit
+//! L1  goto L3
+//! L2  astore_3                 // catch any in [L0, L1) and [L2, L4)
+//!     aload_2
+//!     monitorexit
+//! L4  aload_3
+//!     athrow
+//! ```
+//!
+//! javac 11 and later compile a try-with-resources statement into a body
+//! caught by a `Throwable` handler which closes the resource, adding what
+//! `close` throws to the suppressed exceptions of what the body threw:
+//!
+//! ```text
+//! L0  ...                      // the body
+//! L1  aload_1
+//!     invokevirtual java/io/Reader.close ()V
+//!     goto L5
+//! L2  astore_2                 // catch Throwable in [L0, L1)
+//!     aload_1
+//!     invokevirtual java/io/Reader.close ()V
+//! L3  goto L4
+//!     astore_3                 // catch Throwable in [L2, L3)
+//!     aload_2
+//!     aload_3
+//!     invokevirtual java/lang/Throwable.addSuppressed (Ljava/lang/Throwable;)V
+//! L4  aload_2
+//!     athrow
+//! ```
+//!
+//! Only the handler which adds suppressed exceptions is relied on, so code
+//! which calls `addSuppressed` the same way itself reads as one too.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
+
+use crate::{
+    attribute::Code,
+    cfg::ControlFlowGraph,
+    constantpool::ConstantPool,
+    frames::{Frame, FrameType},
+    instruction::{self, DecodeError, Instruction, WIDE},
+};
+
+const ALOAD: u8 = 0x19;
+const ASTORE: u8 = 0x3a;
+const ATHROW: u8 = 0xbf;
+const INVOKEVIRTUAL: u8 = 0xb6;
+const INVOKEINTERFACE: u8 = 0xb9;
+const MONITORENTER: u8 = 0xc2;
+const MONITOREXIT: u8 = 0xc3;
+
+const THROWABLE: &str = "java/lang/Throwable";
+
+/// A region of a method body, from `start` up to `end`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Region {
+    pub start: u32,
+    pub end: u32,
+    pub kind: RegionKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegionKind {
+    /// A `synchronized` block, starting at its `monitorenter` and ending
+    /// after its last `monitorexit` on the way out of the block, not
+    /// counting the cleanup `handler`.
+    Synchronized {
+        /// The type of the value locked, as inferred before `monitorenter`.
+        lock: Option<FrameType>,
+        /// The local the lock is kept in.
+        local: Option<u16>,
+        /// Every `monitorexit` found to exit this monitor, the handler's too.
+        exits: Vec<u32>,
+        /// The catch-all handler exiting the monitor when the block throws.
+        handler: Option<u32>,
+    },
+    /// The body of a try-with-resources statement.
+    TryWithResources {
+        /// The class whose `close` method closes the resource.
+        resource: String,
+        /// The local the resource is kept in.
+        local: Option<u16>,
+        /// The handler closing the resource when the body throws.
+        handler: u32,
+    },
+}
+
+impl fmt::Display for Region {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            RegionKind::Synchronized {
+                lock,
+                local,
+                handler,
+                ..
+            } => {
+                f.write_str("synchronized")?;
+                if let Some(FrameType::Reference(lock)) = lock {
+                    write!(f, " on {}", lock)?;
+                }
+                if let Some(local) = local {
+                    write!(f, " in local {}", local)?;
+                }
+                write!(f, " until {}", self.end)?;
+                if let Some(handler) = handler {
+                    write!(f, ", cleanup at {}", handler)?;
+                }
+                Ok(())
+            }
+            RegionKind::TryWithResources {
+                resource,
+                local,
+                handler,
+            } => {
+                write!(f, "try-with-resources on {}", resource)?;
+                if let Some(local) = local {
+                    write!(f, " in local {}", local)?;
+                }
+                write!(f, " until {}, cleanup at {}", self.end, handler)
+            }
+        }
+    }
+}
+
+/// A way in which the monitors of a method body aren't entered and exited
+/// in pairs. A JVM may refuse such code, or leave the monitor locked.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MonitorIssue {
+    /// A `monitorexit` reached without holding a monitor.
+    ExitWithoutEnter { offset: u32 },
+    /// A return reached still holding the monitor entered at `enter`.
+    ReturnHoldingMonitor { offset: u32, enter: u32 },
+    /// An instruction holding the monitor entered at `enter` which no
+    /// catch-all handler covers, so that an exception there leaves the
+    /// monitor locked. Only the first such instruction is reported.
+    Unprotected { offset: u32, enter: u32 },
+    /// An instruction reached holding different numbers of monitors.
+    InconsistentDepth { offset: u32, depths: (usize, usize) },
+}
+
+impl fmt::Display for MonitorIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MonitorIssue::ExitWithoutEnter { offset } => {
+                write!(f, "monitorexit at {} holds no monitor", offset)
+            }
+            MonitorIssue::ReturnHoldingMonitor { offset, enter } => write!(
+                f,
+                "return at {} holds the monitor entered at {}",
+                offset, enter
+            ),
+            MonitorIssue::Unprotected { offset, enter } => write!(
+                f,
+                "an exception at {} leaves the monitor entered at {} locked",
+                offset, enter
+            ),
+            MonitorIssue::InconsistentDepth {
+                offset,
+                depths: (first, second),
+            } => write!(
+                f,
+                "{} is reached holding {} and {} monitors",
+                offset, first, second
+            ),
+        }
+    }
+}
+
+/// Finds the `synchronized` blocks and try-with-resources bodies of `code`,
+/// in order of their start. `frames` are those
+/// [`infer_frames`](crate::frames::infer_frames) finds for the method, for
+/// the types of the locks; without them, the locks' types are unknown.
+pub fn find_regions(
+    code: &Code,
+    constant_pool: &[ConstantPool],
+    frames: &BTreeMap<u32, Frame>,
+) -> Result<Vec<Region>, DecodeError> {
+    let instructions = instruction::decode(&code.code).collect::<Result<Vec<_>, _>>()?;
+    let flow = MonitorFlow::analyze(code, constant_pool, &instructions)?;
+    let mut regions = Vec::new();
+
+    for (i, instruction) in instructions.iter().enumerate() {
+        if instruction.opcode != MONITORENTER {
+            continue;
+        }
+        let enter = instruction.offset;
+        let exits: Vec<u32> = flow
+            .exits
+            .get(&enter)
+            .map(|exits| exits.iter().copied().collect())
+            .unwrap_or_default();
+        let cleanup = code
+            .exception_table
+            .iter()
+            .find(|exception| exception.catch_type == 0 && exception.start_pc as u32 == enter + 1);
+        let end = match cleanup {
+            Some(cleanup) => cleanup.end_pc as u32,
+            None => exits.last().map_or(enter + 1, |exit| exit + 1),
+        };
+        regions.push(Region {
+            start: enter,
+            end,
+            kind: RegionKind::Synchronized {
+                lock: frames
+                    .get(&enter)
+                    .and_then(|frame| frame.stack.last().cloned()),
+                local: i
+                    .checked_sub(1)
+                    .and_then(|previous| stored_local(&instructions[previous])),
+                exits,
+                handler: cleanup.map(|cleanup| cleanup.handler_pc as u32),
+            },
+        });
+    }
+
+    for suppressing in &code.exception_table {
+        if !catches_throwable(constant_pool, suppressing.catch_type, false)
+            || !adds_suppressed(constant_pool, &instructions, suppressing.handler_pc as u32)
+        {
+            continue;
+        }
+        let (start, end) = (suppressing.start_pc as u32, suppressing.end_pc as u32);
+        let close = instructions
+            .iter()
+            .enumerate()
+            .find_map(|(i, instruction)| {
+                if instruction.offset < start || end <= instruction.offset {
+                    return None;
+                }
+                let class_name = closes(constant_pool, instruction)?;
+                let local = i
+                    .checked_sub(1)
+                    .and_then(|previous| loaded_local(&instructions[previous]));
+                Some((class_name, local))
+            });
+        let (resource, local) = match close {
+            Some(close) => close,
+            None => continue,
+        };
+        // the handler of the body, whose code closes the resource
+        let handler = code
+            .exception_table
+            .iter()
+            .filter(|exception| {
+                catches_throwable(constant_pool, exception.catch_type, true)
+                    && exception.handler_pc != suppressing.handler_pc
+                    && exception.handler_pc <= suppressing.start_pc
+                    && exception.end_pc <= suppressing.start_pc
+            })
+            .map(|exception| exception.handler_pc)
+            .max();
+        let handler = match handler {
+            Some(handler) => handler,
+            None => continue,
+        };
+        // the body is split around the code of the statements it nests
+        let ranges = code
+            .exception_table
+            .iter()
+            .filter(|exception| exception.handler_pc == handler);
+        let region = Region {
+            start: ranges
+                .clone()
+                .map(|body| body.start_pc)
+                .min()
+                .unwrap_or(handler) as u32,
+            end: ranges.map(|body| body.end_pc).max().unwrap_or(handler) as u32,
+            kind: RegionKind::TryWithResources {
+                resource: resource.to_string(),
+                local,
+                handler: handler as u32,
+            },
+        };
+        if !regions
+            .iter()
+            .any(|other: &Region| (other.start, other.end) == (region.start, region.end))
+        {
+            regions.push(region);
+        }
+    }
+
+    regions.sort_by_key(|region| region.start);
+    Ok(regions)
+}
+
+/// Checks that every path through `code` exits the monitors it enters, in
+/// the reverse order, before it returns, and that an exception anywhere in
+/// between is caught by a handler which can exit them.
+pub fn check_monitors(
+    code: &Code,
+    constant_pool: &[ConstantPool],
+) -> Result<Vec<MonitorIssue>, DecodeError> {
+    let instructions = instruction::decode(&code.code).collect::<Result<Vec<_>, _>>()?;
+    let flow = MonitorFlow::analyze(code, constant_pool, &instructions)?;
+    Ok(flow.issues.into_iter().collect())
+}
+
+/// The monitors held before every instruction, followed along the control
+/// flow graph from the method entry.
+struct MonitorFlow {
+    /// The `monitorexit` instructions exiting each `monitorenter`.
+    exits: BTreeMap<u32, BTreeSet<u32>>,
+    issues: BTreeSet<MonitorIssue>,
+}
+
+impl MonitorFlow {
+    fn analyze(
+        code: &Code,
+        constant_pool: &[ConstantPool],
+        instructions: &[Instruction],
+    ) -> Result<Self, DecodeError> {
+        let cfg = ControlFlowGraph::build(code)?;
+        let mut flow = MonitorFlow {
+            exits: BTreeMap::new(),
+            issues: BTreeSet::new(),
+        };
+        if cfg.blocks.is_empty() {
+            return Ok(flow);
+        }
+        // the `monitorenter` of every monitor held entering a block, the
+        // innermost last
+        let mut entered: Vec<Option<Vec<u32>>> = vec![None; cfg.blocks.len()];
+        let mut unprotected = BTreeSet::new();
+        entered[0] = Some(Vec::new());
+        let mut pending = vec![0];
+
+        while let Some(index) = pending.pop() {
+            let block = &cfg.blocks[index];
+            let mut held = entered[index].clone().unwrap_or_default();
+            let mut reach =
+                |target: usize, held: &[u32], flow: &mut MonitorFlow| match &entered[target] {
+                    Some(known) if known.len() != held.len() => {
+                        flow.issues.insert(MonitorIssue::InconsistentDepth {
+                            offset: cfg.blocks[target].start,
+                            depths: (known.len(), held.len()),
+                        });
+                    }
+                    Some(_) => {}
+                    None => {
+                        entered[target] = Some(held.to_vec());
+                        pending.push(target);
+                    }
+                };
+            let first = instructions.partition_point(|i| i.offset < block.start);
+            let last = instructions.partition_point(|i| i.offset < block.end);
+            for instruction in &instructions[first..last] {
+                let offset = instruction.offset;
+                let mut caught = false;
+                for exception in &code.exception_table {
+                    if offset < exception.start_pc as u32 || exception.end_pc as u32 <= offset {
+                        continue;
+                    }
+                    if let Some(handler) = cfg.block_at(exception.handler_pc as u32) {
+                        reach(handler, &held, &mut flow);
+                    }
+                    if catches_throwable(constant_pool, exception.catch_type, true) {
+                        caught = true;
+                        break;
+                    }
+                }
+                if let Some(&enter) = held.last() {
+                    if !caught && unprotected.insert(enter) {
+                        flow.issues
+                            .insert(MonitorIssue::Unprotected { offset, enter });
+                    }
+                }
+                match instruction.opcode {
+                    MONITORENTER => held.push(offset),
+                    MONITOREXIT => match held.pop() {
+                        Some(enter) => {
+                            flow.exits.entry(enter).or_default().insert(offset);
+                        }
+                        None => {
+                            flow.issues
+                                .insert(MonitorIssue::ExitWithoutEnter { offset });
+                        }
+                    },
+                    // ireturn to return
+                    0xac..=0xb1 => {
+                        if let Some(&enter) = held.last() {
+                            flow.issues
+                                .insert(MonitorIssue::ReturnHoldingMonitor { offset, enter });
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            for &successor in &block.successors {
+                reach(successor, &held, &mut flow);
+            }
+        }
+        Ok(flow)
+    }
+}
+
+/// Whether a handler of `catch_type` catches every `Throwable`, or with
+/// `any`, is a catch-all handler too.
+fn catches_throwable(constant_pool: &[ConstantPool], catch_type: u16, any: bool) -> bool {
+    match catch_type {
+        0 => any,
+        catch_type => ConstantPool::class_name(constant_pool, catch_type) == Some(THROWABLE),
+    }
+}
+
+/// Whether the handler at `handler_pc` calls `Throwable.addSuppressed`
+/// before it first leaves the code following it.
+fn adds_suppressed(
+    constant_pool: &[ConstantPool],
+    instructions: &[Instruction],
+    handler_pc: u32,
+) -> bool {
+    let first = instructions.partition_point(|i| i.offset < handler_pc);
+    for instruction in &instructions[first..] {
+        if instruction.opcode == INVOKEVIRTUAL {
+            let method = instruction
+                .cp_index()
+                .and_then(|index| ConstantPool::member_ref(constant_pool, index));
+            if let Some(method) = method {
+                if method.class_name == THROWABLE && method.name == "addSuppressed" {
+                    return true;
+                }
+            }
+        }
+        // goto, the returns and athrow
+        if matches!(instruction.opcode, 0xa7 | 0xac..=0xb1 | ATHROW | 0xc8) {
+            return false;
+        }
+    }
+    false
+}
+
+/// The class `instruction` calls `close()` of, if it does.
+fn closes<'a>(constant_pool: &'a [ConstantPool], instruction: &Instruction) -> Option<&'a str> {
+    if instruction.opcode != INVOKEVIRTUAL && instruction.opcode != INVOKEINTERFACE {
+        return None;
+    }
+    let method = ConstantPool::member_ref(constant_pool, instruction.cp_index()?)?;
+    (method.name == "close" && method.descriptor == "()V").then_some(method.class_name)
+}
+
+/// The local `instruction` loads a reference from, if it's an `aload`.
+fn loaded_local(instruction: &Instruction) -> Option<u16> {
+    reference_local(instruction, ALOAD, 0x2a)
+}
+
+/// The local `instruction` stores a reference in, if it's an `astore`.
+fn stored_local(instruction: &Instruction) -> Option<u16> {
+    reference_local(instruction, ASTORE, 0x4b)
+}
+
+/// The local of the instruction `opcode`, whose forms for the locals 0 to 3
+/// start at `short_form`, in any of its forms.
+fn reference_local(instruction: &Instruction, opcode: u8, short_form: u8) -> Option<u16> {
+    let operands = instruction.operands;
+    match instruction.opcode {
+        op if op == opcode => operands.first().map(|&index| index as u16),
+        op if (short_form..short_form + 4).contains(&op) => Some((op - short_form) as u16),
+        WIDE if operands.first() == Some(&opcode) => {
+            Some(u16::from_be_bytes([*operands.get(1)?, *operands.get(2)?]))
+        }
+        _ => None,
+    }
+}
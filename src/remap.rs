@@ -0,0 +1,654 @@
+//! Renaming classes and members throughout a class: in its constant pool,
+//! its own declarations, and every descriptor, signature and attribute
+//! naming them, as a [`Remapper`] says.
+//!
+//! Entries of the pool are never changed in place, since a UTF8 entry may be
+//! shared by a name which is renamed and one which isn't, or by a string
+//! literal: every use of a name which changes is pointed at an entry holding
+//! the new one instead, found in the pool or added to its end. Entries left
+//! unused behind stay. String literals are never rewritten, including those
+//! naming classes for reflection.
+
+use std::{error::Error, fmt};
+
+use crate::{
+    attribute::{Annotation, Attribute, ElementValue},
+    classfile::ClassFile,
+    constantpool::ConstantPool,
+};
+
+const LAMBDA_METAFACTORY: &str = "java/lang/invoke/LambdaMetafactory";
+
+/// The new names of classes and members, `None` for those keeping theirs.
+/// Classes are internal names; members are asked for by the class a
+/// reference names them in, which may be a subclass of the one declaring
+/// them, with their original name and descriptor.
+pub trait Remapper {
+    fn class(&self, name: &str) -> Option<String>;
+
+    fn field(&self, _owner: &str, _name: &str, _descriptor: &str) -> Option<String> {
+        None
+    }
+
+    fn method(&self, _owner: &str, _name: &str, _descriptor: &str) -> Option<String> {
+        None
+    }
+}
+
+/// A function giving the new names of classes alone, such as a closure.
+impl<F: Fn(&str) -> Option<String>> Remapper for F {
+    fn class(&self, name: &str) -> Option<String> {
+        self(name)
+    }
+}
+
+/// A move of classes from one package to another, as shading tools relocate
+/// a dependency: `com/thirdparty/**` to `com/mycompany/shaded/thirdparty/**`
+/// moves every class of `com/thirdparty` and its subpackages, keeping the
+/// rest of their names. A slice of them renames a class as the first one
+/// matching it does.
+///
+/// ```
+/// use jvmb::remap::{Relocation, Remapper};
+///
+/// let relocation =
+///     Relocation::new("com/thirdparty/**", "com/mycompany/shaded/thirdparty/**").unwrap();
+/// assert_eq!(
+///     relocation.class("com/thirdparty/json/Parser").as_deref(),
+///     Some("com/mycompany/shaded/thirdparty/json/Parser"),
+/// );
+/// assert_eq!(relocation.class("com/thirdpartyx/Foo"), None);
+/// assert_eq!(Relocation::new("com/thirdparty/**", "com/mycompany/Foo"), None);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Relocation {
+    /// The package as a prefix ending in `/`, or the class.
+    from: String,
+    to: String,
+}
+
+impl Relocation {
+    /// Moves the package `from` to `to`, both internal names ending in
+    /// `/**`, or renames the class `from` to `to` when neither does. `None`
+    /// when one does and the other doesn't, or either is empty.
+    pub fn new(from: &str, to: &str) -> Option<Self> {
+        match (from.strip_suffix("**"), to.strip_suffix("**")) {
+            (Some(from), Some(to)) if from.ends_with('/') && to.ends_with('/') => {
+                (from.len() > 1 && to.len() > 1).then(|| Relocation {
+                    from: from.to_string(),
+                    to: to.to_string(),
+                })
+            }
+            (None, None) if !from.is_empty() && !to.is_empty() && !from.ends_with('/') => {
+                Some(Relocation {
+                    from: from.to_string(),
+                    to: to.to_string(),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// A rule written `<from> -> <to>`, as shading tools take them.
+    pub fn parse(rule: &str) -> Option<Self> {
+        let (from, to) = rule.split_once("->")?;
+        Relocation::new(from.trim(), to.trim())
+    }
+}
+
+impl Remapper for Relocation {
+    fn class(&self, name: &str) -> Option<String> {
+        if self.from.ends_with('/') {
+            let rest = name.strip_prefix(self.from.as_str())?;
+            Some(format!("{}{}", self.to, rest))
+        } else {
+            (name == self.from).then(|| self.to.clone())
+        }
+    }
+}
+
+impl Remapper for [Relocation] {
+    fn class(&self, name: &str) -> Option<String> {
+        self.iter().find_map(|relocation| relocation.class(name))
+    }
+}
+
+/// A class whose pool has no room for the entries its new names need.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PoolFull;
+
+impl fmt::Display for PoolFull {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("the constant pool is full")
+    }
+}
+
+impl Error for PoolFull {}
+
+/// Renames everything in `class_file` that `remapper` renames. Besides the
+/// pool and the class's fields and methods, that covers the signatures,
+/// inner class names, enclosing methods, local variables, record components
+/// and annotations, and the interface method names of lambdas, which
+/// `LambdaMetafactory` binds by name.
+///
+/// The names of annotation elements aren't renamed, as their descriptors
+/// aren't known where they're used, and neither are the constants of enum
+/// values in annotations: those are looked up by `Enum.name()`, the string
+/// the enum's initializer gives it, which renaming its field doesn't change.
+pub fn remap(class_file: &mut ClassFile, remapper: &dyn Remapper) -> Result<(), PoolFull> {
+    let original = class_file.constant_pool.clone();
+    let original_len = original.len();
+    let this_class = ConstantPool::class_name(&original, class_file.this_class)
+        .unwrap_or_default()
+        .to_string();
+    let lambda_names = lambda_names(class_file, &original, remapper);
+
+    let mut pool = Pool {
+        original: &original,
+        constant_pool: std::mem::take(&mut class_file.constant_pool),
+        remapper,
+    };
+    for (i, constant) in original.iter().enumerate() {
+        let index = i as u16 + 1;
+        let remapped = match *constant {
+            ConstantPool::Class(name_index) => pool
+                .renamed(name_index, |name| map_class_name(remapper, name))
+                .map(ConstantPool::Class),
+            ConstantPool::FieldRef(class_index, name_and_type_index) => pool
+                .member(class_index, name_and_type_index, false)
+                .map(|nat| ConstantPool::FieldRef(class_index, nat)),
+            ConstantPool::MethodRef(class_index, name_and_type_index) => pool
+                .member(class_index, name_and_type_index, true)
+                .map(|nat| ConstantPool::MethodRef(class_index, nat)),
+            ConstantPool::InterfaceMethodRef(class_index, name_and_type_index) => pool
+                .member(class_index, name_and_type_index, true)
+                .map(|nat| ConstantPool::InterfaceMethodRef(class_index, nat)),
+            ConstantPool::MethodType(descriptor_index) => pool
+                .renamed(descriptor_index, |descriptor| {
+                    map_signature(remapper, descriptor)
+                })
+                .map(ConstantPool::MethodType),
+            ConstantPool::Dynamic(bootstrap_index, name_and_type_index) => pool
+                .name_and_type(name_and_type_index, None)
+                .map(|nat| ConstantPool::Dynamic(bootstrap_index, nat)),
+            ConstantPool::InvokeDynamic(bootstrap_index, name_and_type_index) => {
+                let name = lambda_names
+                    .iter()
+                    .find(|(lambda, _)| *lambda == index)
+                    .map(|(_, name)| name.as_str());
+                pool.name_and_type(name_and_type_index, name)
+                    .map(|nat| ConstantPool::InvokeDynamic(bootstrap_index, nat))
+            }
+            _ => None,
+        };
+        if let Some(remapped) = remapped {
+            pool.constant_pool[i] = remapped;
+        }
+    }
+
+    for field in &mut class_file.fields {
+        let name = utf8(&original, field.name_index());
+        let descriptor = utf8(&original, field.descriptor_index());
+        if let Some(new_name) = remapper.field(&this_class, name, descriptor) {
+            field.set_name_index(pool.utf8(&new_name));
+        }
+        if let Some(index) = pool.renamed(field.descriptor_index(), |descriptor| {
+            map_signature(remapper, descriptor)
+        }) {
+            field.set_descriptor_index(index);
+        }
+        pool.attributes(field.attributes_mut(), &this_class);
+    }
+    for method in &mut class_file.methods {
+        let name = utf8(&original, method.name_index());
+        let descriptor = utf8(&original, method.descriptor_index());
+        if !name.starts_with('<') {
+            if let Some(new_name) = remapper.method(&this_class, name, descriptor) {
+                method.set_name_index(pool.utf8(&new_name));
+            }
+        }
+        if let Some(index) = pool.renamed(method.descriptor_index(), |descriptor| {
+            map_signature(remapper, descriptor)
+        }) {
+            method.set_descriptor_index(index);
+        }
+        pool.attributes(method.attributes_mut(), &this_class);
+    }
+    pool.attributes(&mut class_file.attributes, &this_class);
+
+    let added = pool.constant_pool.len() - original_len;
+    class_file.constant_pool = pool.constant_pool;
+    class_file.constant_pool_count = u16::try_from(added)
+        .ok()
+        .and_then(|added| class_file.constant_pool_count.checked_add(added))
+        .ok_or(PoolFull)?;
+    Ok(())
+}
+
+/// The pool of the class being remapped, with the original one to look
+/// names up in.
+struct Pool<'a> {
+    original: &'a [ConstantPool],
+    constant_pool: Vec<ConstantPool>,
+    remapper: &'a dyn Remapper,
+}
+
+impl Pool<'_> {
+    /// The index of a UTF8 entry holding `value`.
+    fn utf8(&mut self, value: &str) -> u16 {
+        ConstantPool::intern_utf8(&mut self.constant_pool, value)
+    }
+
+    /// The index of a UTF8 entry holding what `rename` makes of the
+    /// original one at `index`, or `None` when that's unchanged.
+    fn renamed(&mut self, index: u16, rename: impl FnOnce(&str) -> Option<String>) -> Option<u16> {
+        let value = ConstantPool::utf8(self.original, index)?;
+        match rename(value) {
+            Some(renamed) if renamed != value => Some(self.utf8(&renamed)),
+            _ => None,
+        }
+    }
+
+    /// The `NameAndType` a member reference should point at, when it
+    /// changes.
+    fn member(&mut self, class_index: u16, name_and_type_index: u16, method: bool) -> Option<u16> {
+        let owner = ConstantPool::class_name(self.original, class_index)?;
+        let (name, descriptor) = ConstantPool::name_and_type(self.original, name_and_type_index)?;
+        let new_name = if method {
+            self.remapper.method(owner, name, descriptor)
+        } else {
+            self.remapper.field(owner, name, descriptor)
+        };
+        self.name_and_type(name_and_type_index, new_name.as_deref())
+    }
+
+    /// A `NameAndType` like the one at `index`, with `new_name` if given
+    /// and its descriptor remapped, or `None` when that's unchanged.
+    fn name_and_type(&mut self, index: u16, new_name: Option<&str>) -> Option<u16> {
+        let (name, descriptor) = ConstantPool::name_and_type(self.original, index)?;
+        let new_descriptor = map_signature(self.remapper, descriptor);
+        if new_name.is_none() && new_descriptor.is_none() {
+            return None;
+        }
+        let name_index = self.utf8(new_name.unwrap_or(name));
+        let descriptor_index = self.utf8(new_descriptor.as_deref().unwrap_or(descriptor));
+        let existing = self.constant_pool.iter().position(|constant| {
+            *constant == ConstantPool::NameAndType(name_index, descriptor_index)
+        });
+        Some(match existing {
+            Some(position) => position as u16 + 1,
+            None => {
+                self.constant_pool
+                    .push(ConstantPool::NameAndType(name_index, descriptor_index));
+                self.constant_pool.len() as u16
+            }
+        })
+    }
+
+    /// Points the names, descriptors and signatures among `attributes`,
+    /// those of code and record components included, at their new values.
+    fn attributes(&mut self, attributes: &mut [Attribute], this_class: &str) {
+        let remapper = self.remapper;
+        let signature = |signature: &str| map_signature(remapper, signature);
+        for attribute in attributes {
+            match attribute {
+                Attribute::Code(code) => self.attributes(&mut code.attributes, this_class),
+                Attribute::Signature(attribute) => {
+                    if let Some(index) = self.renamed(attribute.signature_index, signature) {
+                        attribute.signature_index = index;
+                    }
+                }
+                Attribute::InnerClasses(inner_classes) => {
+                    for inner in &mut inner_classes.classes {
+                        let name =
+                            ConstantPool::class_name(self.original, inner.inner_class_info_index);
+                        let outer =
+                            ConstantPool::class_name(self.original, inner.outer_class_info_index);
+                        let renamed = name.and_then(|name| remapper.class(name));
+                        if let (Some(renamed), true) = (renamed, inner.inner_name_index != 0) {
+                            let simple = simple_name(
+                                &renamed,
+                                outer.map(|outer| {
+                                    remapper.class(outer).unwrap_or_else(|| outer.to_string())
+                                }),
+                            );
+                            inner.inner_name_index = self.utf8(&simple);
+                        }
+                    }
+                }
+                Attribute::EnclosingMethod(enclosing) => {
+                    let owner = ConstantPool::class_name(self.original, enclosing.class_index);
+                    let new_name = owner.and_then(|owner| {
+                        let (name, descriptor) =
+                            ConstantPool::name_and_type(self.original, enclosing.method_index)?;
+                        remapper.method(owner, name, descriptor)
+                    });
+                    if let Some(index) =
+                        self.name_and_type(enclosing.method_index, new_name.as_deref())
+                    {
+                        enclosing.method_index = index;
+                    }
+                }
+                Attribute::LocalVariableTable(variables) => {
+                    for variable in variables {
+                        if let Some(index) = self.renamed(variable.descriptor_index, signature) {
+                            variable.descriptor_index = index;
+                        }
+                    }
+                }
+                Attribute::LocalVariableTypeTable(variables) => {
+                    for variable in variables {
+                        if let Some(index) = self.renamed(variable.signature_index, signature) {
+                            variable.signature_index = index;
+                        }
+                    }
+                }
+                Attribute::RuntimeVisibleAnnotations(annotations)
+                | Attribute::RuntimeInvisibleAnnotations(annotations) => {
+                    for annotation in annotations {
+                        self.annotation(annotation);
+                    }
+                }
+                Attribute::RuntimeVisibleParameterAnnotations(parameters)
+                | Attribute::RuntimeInvisibleParameterAnnotations(parameters) => {
+                    for annotation in parameters.iter_mut().flat_map(|p| &mut p.annotations) {
+                        self.annotation(annotation);
+                    }
+                }
+                Attribute::RuntimeVisibleTypeAnnotations(annotations)
+                | Attribute::RuntimeInvisibleTypeAnnotations(annotations) => {
+                    for annotation in annotations {
+                        if let Some(index) = self.renamed(annotation.type_index, signature) {
+                            annotation.type_index = index;
+                        }
+                        for (_, value) in &mut annotation.element_value_pairs {
+                            self.element_value(value);
+                        }
+                    }
+                }
+                Attribute::AnnotationDefault(value) => self.element_value(value),
+                Attribute::Record(components) => {
+                    for component in components {
+                        let name = utf8(self.original, component.name_index);
+                        let descriptor = utf8(self.original, component.descriptor_index);
+                        if let Some(new_name) = remapper.field(this_class, name, descriptor) {
+                            component.name_index = self.utf8(&new_name);
+                        }
+                        if let Some(index) = self.renamed(component.descriptor_index, signature) {
+                            component.descriptor_index = index;
+                        }
+                        self.attributes(&mut component.attributes, this_class);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn annotation(&mut self, annotation: &mut Annotation) {
+        let remapper = self.remapper;
+        if let Some(index) = self.renamed(annotation.type_index, |descriptor| {
+            map_signature(remapper, descriptor)
+        }) {
+            annotation.type_index = index;
+        }
+        for (_, value) in &mut annotation.element_value_pairs {
+            self.element_value(value);
+        }
+    }
+
+    fn element_value(&mut self, value: &mut ElementValue) {
+        let remapper = self.remapper;
+        match value {
+            ElementValue::EnumConstValue {
+                type_name_index, ..
+            } => {
+                if let Some(index) = self.renamed(*type_name_index, |descriptor| {
+                    map_signature(remapper, descriptor)
+                }) {
+                    *type_name_index = index;
+                }
+            }
+            ElementValue::ClassInfoIndex(index) => {
+                if let Some(renamed) =
+                    self.renamed(*index, |descriptor| map_signature(remapper, descriptor))
+                {
+                    *index = renamed;
+                }
+            }
+            ElementValue::AnnotationValue(annotation) => self.annotation(annotation),
+            ElementValue::ArrayValue(values) => {
+                for value in values {
+                    self.element_value(value);
+                }
+            }
+            ElementValue::ConstValue { .. } => {}
+        }
+    }
+}
+
+fn utf8(constant_pool: &[ConstantPool], index: u16) -> &str {
+    ConstantPool::utf8(constant_pool, index).unwrap_or_default()
+}
+
+/// The `InvokeDynamic` entries of `LambdaMetafactory` call sites whose
+/// interface method `remapper` renames, with the new name. The method is
+/// the one of the call site's name in the interface it returns, with the
+/// erased descriptor the first static argument gives.
+fn lambda_names(
+    class_file: &ClassFile,
+    original: &[ConstantPool],
+    remapper: &dyn Remapper,
+) -> Vec<(u16, String)> {
+    let bootstrap_methods = class_file.bootstrap_methods();
+    let mut names = Vec::new();
+    for (i, constant) in original.iter().enumerate() {
+        let (bootstrap_index, name_and_type_index) = match constant {
+            ConstantPool::InvokeDynamic(bootstrap_index, name_and_type_index) => {
+                (*bootstrap_index, *name_and_type_index)
+            }
+            _ => continue,
+        };
+        let bootstrap_method = match bootstrap_methods.get(bootstrap_index as usize) {
+            Some(bootstrap_method) => bootstrap_method,
+            None => continue,
+        };
+        let is_metafactory =
+            ConstantPool::method_handle(original, bootstrap_method.bootstrap_method_ref)
+                .is_some_and(|(_, member)| member.class_name == LAMBDA_METAFACTORY);
+        let interface_method = bootstrap_method
+            .bootstrap_arguments
+            .first()
+            .and_then(
+                |&index| match original.get((index as usize).checked_sub(1)?)? {
+                    ConstantPool::MethodType(descriptor_index) => {
+                        ConstantPool::utf8(original, *descriptor_index)
+                    }
+                    _ => None,
+                },
+            );
+        let (name, descriptor) = match ConstantPool::name_and_type(original, name_and_type_index) {
+            Some(name_and_type) if is_metafactory => name_and_type,
+            _ => continue,
+        };
+        let interface = descriptor
+            .rsplit_once(')')
+            .and_then(|(_, returned)| returned.strip_prefix('L')?.strip_suffix(';'));
+        if let (Some(interface), Some(interface_method)) = (interface, interface_method) {
+            if let Some(new_name) = remapper.method(interface, name, interface_method) {
+                names.push((i as u16 + 1, new_name));
+            }
+        }
+    }
+    names
+}
+
+/// The name a `CONSTANT_Class` entry holding `name`, a class or an array
+/// descriptor, should hold, or `None` when it keeps its own.
+fn map_class_name(remapper: &dyn Remapper, name: &str) -> Option<String> {
+    if name.starts_with('[') {
+        map_signature(remapper, name)
+    } else {
+        remapper.class(name)
+    }
+}
+
+/// The simple name an `InnerClasses` entry gives the class `renamed`, the
+/// part after its outer class's name and `$` when it's still nested in it,
+/// or else after its package and any `$`.
+fn simple_name(renamed: &str, outer: Option<String>) -> String {
+    if let Some(simple) = outer.and_then(|outer| {
+        renamed
+            .strip_prefix(outer.as_str())
+            .and_then(|rest| rest.strip_prefix('$'))
+            .map(str::to_string)
+    }) {
+        return simple;
+    }
+    let name = renamed.rsplit('/').next().unwrap_or(renamed);
+    name.rsplit('$').next().unwrap_or(name).to_string()
+}
+
+/// The descriptor or generic signature, of a class, field or method, with
+/// every class in it renamed, or `None` when nothing in it is, or it doesn't
+/// parse.
+pub fn map_signature(remapper: &dyn Remapper, signature: &str) -> Option<String> {
+    let mut mapper = SignatureMapper {
+        remapper,
+        chars: signature.chars().collect(),
+        pos: 0,
+        out: String::with_capacity(signature.len()),
+        changed: false,
+    };
+    mapper.signature()?;
+    mapper.changed.then_some(mapper.out)
+}
+
+struct SignatureMapper<'a> {
+    remapper: &'a dyn Remapper,
+    chars: Vec<char>,
+    pos: usize,
+    out: String,
+    changed: bool,
+}
+
+impl SignatureMapper<'_> {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    /// Copies the next character, which must be `expected` if given.
+    fn copy(&mut self, expected: Option<char>) -> Option<char> {
+        let c = self.peek()?;
+        if expected.is_some_and(|expected| expected != c) {
+            return None;
+        }
+        self.out.push(c);
+        self.pos += 1;
+        Some(c)
+    }
+
+    /// An identifier, up to but not including any of `ends`.
+    fn identifier(&mut self, ends: &[char]) -> Option<String> {
+        let start = self.pos;
+        while !ends.contains(&self.peek()?) {
+            self.pos += 1;
+        }
+        Some(self.chars[start..self.pos].iter().collect())
+    }
+
+    fn signature(&mut self) -> Option<()> {
+        if self.peek() == Some('<') {
+            self.copy(None);
+            while self.peek()? != '>' {
+                let name = self.identifier(&[':'])?;
+                self.out.push_str(&name);
+                while self.peek() == Some(':') {
+                    self.copy(None);
+                    // an empty class bound is followed by the next `:`
+                    if matches!(self.peek()?, 'L' | 'T' | '[') {
+                        self.type_signature()?;
+                    }
+                }
+            }
+            self.copy(Some('>'));
+        }
+        if self.peek() == Some('(') {
+            self.copy(None);
+            while self.peek()? != ')' {
+                self.type_signature()?;
+            }
+            self.copy(Some(')'));
+            self.type_signature()?;
+            while self.peek() == Some('^') {
+                self.copy(None);
+                self.type_signature()?;
+            }
+        } else {
+            while self.peek().is_some() {
+                self.type_signature()?;
+            }
+        }
+        (self.pos == self.chars.len()).then_some(())
+    }
+
+    fn type_signature(&mut self) -> Option<()> {
+        match self.copy(None)? {
+            'B' | 'C' | 'D' | 'F' | 'I' | 'J' | 'S' | 'Z' | 'V' => Some(()),
+            '[' => self.type_signature(),
+            'T' => {
+                let name = self.identifier(&[';'])?;
+                self.out.push_str(&name);
+                self.copy(Some(';')).map(drop)
+            }
+            'L' => self.class_type(),
+            _ => None,
+        }
+    }
+
+    /// The rest of a class type after its `L`, with type arguments and
+    /// inner classes.
+    fn class_type(&mut self) -> Option<()> {
+        let mut name = self.identifier(&['<', ';', '.'])?;
+        let mut renamed = self.remapper.class(&name);
+        self.changed |= renamed.is_some();
+        self.out.push_str(renamed.as_deref().unwrap_or(&name));
+        loop {
+            if self.peek()? == '<' {
+                self.copy(None);
+                while self.peek()? != '>' {
+                    match self.peek()? {
+                        '*' => {
+                            self.copy(None);
+                        }
+                        '+' | '-' => {
+                            self.copy(None);
+                            self.type_signature()?;
+                        }
+                        _ => self.type_signature()?,
+                    }
+                }
+                self.copy(Some('>'));
+            }
+            match self.copy(None)? {
+                ';' => return Some(()),
+                '.' => {
+                    // the inner class is named by what follows its outer
+                    // class's name and `$`
+                    let simple = self.identifier(&['<', ';', '.'])?;
+                    let outer = renamed.unwrap_or_else(|| name.clone());
+                    name = format!("{}${}", name, simple);
+                    renamed = self.remapper.class(&name);
+                    let nested = renamed.as_deref().and_then(|renamed| {
+                        renamed
+                            .strip_prefix(outer.as_str())
+                            .and_then(|rest| rest.strip_prefix('$'))
+                    });
+                    self.changed |= nested.is_some();
+                    self.out.push_str(nested.unwrap_or(&simple));
+                }
+                _ => return None,
+            }
+        }
+    }
+}
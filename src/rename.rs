@@ -0,0 +1,467 @@
+//! Renaming classes and members across a set of classes, from rules naming
+//! them one by one or matching class names with a pattern, so that the
+//! classes still link up: the [`Remapper`] a [`Renamer`] makes renames a
+//! method in the subclasses overriding it and wherever it's called through
+//! a subtype, and a field wherever it's referred to through one.
+//!
+//! Every rule is checked against a [`ClassIndex`] of the classes before
+//! anything is renamed, and everything that would clash is reported at
+//! once: a new name already taken, two things renamed to the same name, a
+//! rule for something the classes don't have, or for a method overriding
+//! one the rules leave alone.
+
+use std::{collections::HashMap, error::Error, fmt};
+
+use crate::{
+    accessflags::{ACC_PRIVATE, ACC_STATIC},
+    classindex::{ClassIndex, MemberSummary},
+    regex::Regex,
+    remap::Remapper,
+    symbols::{field_id, method_id},
+};
+
+/// What to rename, and to what.
+#[derive(Debug, Clone, Default)]
+pub struct RenameRules {
+    classes: Vec<(String, String)>,
+    patterns: Vec<(Regex, String)>,
+    fields: Vec<(Member, String)>,
+    methods: Vec<(Member, String)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Member {
+    class: String,
+    name: String,
+    descriptor: String,
+}
+
+/// A rule which can't be added.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleError {
+    /// The rule, as `<from> -> <to>`.
+    pub rule: String,
+    pub message: &'static str,
+}
+
+impl fmt::Display for RuleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.rule, self.message)
+    }
+}
+
+impl Error for RuleError {}
+
+impl RenameRules {
+    pub fn new() -> Self {
+        RenameRules::default()
+    }
+
+    /// Renames the class `from` to `to`, both internal names.
+    pub fn class(&mut self, from: &str, to: &str) -> Result<(), RuleError> {
+        if !is_class_name(from) || !is_class_name(to) {
+            return Err(rule_error(from, to, "expected internal class names"));
+        }
+        self.classes.push((from.to_string(), to.to_string()));
+        Ok(())
+    }
+
+    /// Renames every class whose name `pattern` matches, and no rule for
+    /// the class alone renames, replacing the leftmost match with `to`.
+    /// The first pattern matching a class is the one used.
+    pub fn pattern(&mut self, pattern: Regex, to: &str) {
+        self.patterns.push((pattern, to.to_string()));
+    }
+
+    /// Renames the field or method `id`, as [`symbols`](crate::symbols)
+    /// names them, e.g. `a/b/c#a(I)V` or `a/b/c#b:I`, to `to`.
+    pub fn member(&mut self, id: &str, to: &str) -> Result<(), RuleError> {
+        let (class, member) = id
+            .split_once('#')
+            .filter(|(class, _)| is_class_name(class))
+            .ok_or_else(|| rule_error(id, to, "expected <class>#<name><descriptor>"))?;
+        if !is_member_name(to) {
+            return Err(rule_error(id, to, "expected a field or method name"));
+        }
+        let (rules, name, descriptor) = if let Some(open) = member.find('(') {
+            (&mut self.methods, &member[..open], &member[open..])
+        } else if let Some((name, descriptor)) = member.split_once(':') {
+            (&mut self.fields, name, descriptor)
+        } else {
+            return Err(rule_error(
+                id,
+                to,
+                "expected a method descriptor or ':' and a field descriptor",
+            ));
+        };
+        if !is_member_name(name) || name.starts_with('<') || descriptor.is_empty() {
+            return Err(rule_error(id, to, "expected <class>#<name><descriptor>"));
+        }
+        let member = Member {
+            class: class.to_string(),
+            name: name.to_string(),
+            descriptor: descriptor.to_string(),
+        };
+        rules.push((member, to.to_string()));
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.classes.is_empty()
+            && self.patterns.is_empty()
+            && self.fields.is_empty()
+            && self.methods.is_empty()
+    }
+}
+
+fn rule_error(from: &str, to: &str, message: &'static str) -> RuleError {
+    RuleError {
+        rule: format!("{} -> {}", from, to),
+        message,
+    }
+}
+
+fn is_class_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.contains(['.', ';', '[', '#'])
+        && name.split('/').all(|segment| !segment.is_empty())
+}
+
+fn is_member_name(name: &str) -> bool {
+    !name.is_empty() && !name.contains(['.', ';', '[', '/', '(', ')', ':', '#'])
+}
+
+/// Why the rules can't be applied as they are.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Conflict {
+    /// A rule names a class the classes don't include.
+    NoSuchClass(String),
+    /// A rule names a member its class doesn't declare.
+    NoSuchMember(String),
+    /// The new name of `from` is that of `existing`, which keeps it.
+    Taken {
+        from: String,
+        to: String,
+        existing: String,
+    },
+    /// `first` and `second` would both be renamed to `to`.
+    Both {
+        first: String,
+        second: String,
+        to: String,
+    },
+    /// A method rule names an override of a method the rules leave alone,
+    /// which would stop overriding it.
+    Overrides { id: String, overridden: String },
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Conflict::NoSuchClass(class) => write!(f, "{}: no such class", class),
+            Conflict::NoSuchMember(id) => write!(f, "{}: no such member", id),
+            Conflict::Taken { from, to, existing } => {
+                write!(
+                    f,
+                    "{} can't be renamed to {}: {} exists",
+                    from, to, existing
+                )
+            }
+            Conflict::Both { first, second, to } => {
+                write!(
+                    f,
+                    "{} and {} would both be renamed to {}",
+                    first, second, to
+                )
+            }
+            Conflict::Overrides { id, overridden } => write!(
+                f,
+                "{} overrides {}, which would have to be renamed too",
+                id, overridden
+            ),
+        }
+    }
+}
+
+/// The new names rules give the classes of an index, checked for
+/// conflicts.
+#[derive(Debug)]
+pub struct Renamer<'a> {
+    index: &'a ClassIndex,
+    classes: HashMap<String, String>,
+    /// (name, descriptor) -> the classes declaring a member renamed, with
+    /// the new name
+    fields: HashMap<(String, String), Vec<(String, String)>>,
+    methods: HashMap<(String, String), Vec<(String, String)>>,
+}
+
+impl<'a> Renamer<'a> {
+    /// Applies `rules` to the classes of `index`, or lists every conflict
+    /// among them.
+    pub fn new(rules: &RenameRules, index: &'a ClassIndex) -> Result<Self, Vec<Conflict>> {
+        let mut conflicts = Vec::new();
+        let mut renamer = Renamer {
+            index,
+            classes: HashMap::new(),
+            fields: HashMap::new(),
+            methods: HashMap::new(),
+        };
+
+        for (from, to) in &rules.classes {
+            if index.get(from).map(|class| class.name()) != Ok(from.as_str()) {
+                conflicts.push(Conflict::NoSuchClass(from.clone()));
+            } else if from != to {
+                renamer.classes.insert(from.clone(), to.clone());
+            }
+        }
+        for class in index.classes() {
+            let name = class.name();
+            if renamer.classes.contains_key(name)
+                || rules.classes.iter().any(|(from, _)| from == name)
+            {
+                continue;
+            }
+            let renamed = rules.patterns.iter().find_map(|(pattern, to)| {
+                let (start, end) = pattern.find(name)?;
+                Some(format!("{}{}{}", &name[..start], to, &name[end..]))
+            });
+            if let Some(renamed) = renamed.filter(|renamed| renamed != name) {
+                renamer.classes.insert(name.to_string(), renamed);
+            }
+        }
+        let mut targets: HashMap<&str, &str> = HashMap::new();
+        let mut renamed_classes: Vec<(&String, &String)> = renamer.classes.iter().collect();
+        renamed_classes.sort();
+        for (from, to) in renamed_classes {
+            if let Some(first) = targets.insert(to, from) {
+                conflicts.push(Conflict::Both {
+                    first: first.to_string(),
+                    second: from.clone(),
+                    to: to.clone(),
+                });
+            } else if index.get(to).is_ok_and(|class| class.name() == to)
+                && !renamer.classes.contains_key(to.as_str())
+            {
+                conflicts.push(Conflict::Taken {
+                    from: from.clone(),
+                    to: to.clone(),
+                    existing: to.clone(),
+                });
+            }
+        }
+
+        for (field, to) in &rules.fields {
+            if renamer.declared(field, false).is_none() {
+                conflicts.push(missing(field, false, index));
+                continue;
+            }
+            add_renamed(&mut renamer.fields, field, to);
+        }
+        for (method, to) in &rules.methods {
+            let declared = match renamer.declared(method, true) {
+                Some(declared) => declared,
+                None => {
+                    conflicts.push(missing(method, true, index));
+                    continue;
+                }
+            };
+            if declared.access_flags() & (ACC_PRIVATE | ACC_STATIC) == 0 {
+                if let Some(overridden) = renamer.overridden(method, rules) {
+                    conflicts.push(Conflict::Overrides {
+                        id: method_id(&method.class, &method.name, &method.descriptor),
+                        overridden,
+                    });
+                    continue;
+                }
+            }
+            add_renamed(&mut renamer.methods, method, to);
+            let overrides = index
+                .overrides_of(&method.class, &method.name, &method.descriptor)
+                .unwrap_or_default();
+            for overriding in overrides {
+                for descriptor in overriding.bridge.iter().chain([&overriding.descriptor]) {
+                    let member = Member {
+                        class: overriding.class.clone(),
+                        name: method.name.clone(),
+                        descriptor: descriptor.clone(),
+                    };
+                    add_renamed(&mut renamer.methods, &member, to);
+                }
+            }
+        }
+        conflicts.extend(renamer.member_conflicts(false));
+        conflicts.extend(renamer.member_conflicts(true));
+
+        if conflicts.is_empty() {
+            Ok(renamer)
+        } else {
+            Err(conflicts)
+        }
+    }
+
+    /// The field or method `member` as its class declares it.
+    fn declared(&self, member: &Member, method: bool) -> Option<MemberSummary<'a>> {
+        let class = self.index.get(&member.class).ok()?;
+        if class.name() != member.class {
+            return None;
+        }
+        let mut members: Box<dyn Iterator<Item = MemberSummary<'a>>> = if method {
+            Box::new(class.methods())
+        } else {
+            Box::new(class.fields())
+        };
+        members.find(|declared| {
+            declared.name() == member.name && declared.descriptor() == member.descriptor
+        })
+    }
+
+    /// A method of a supertype of `method`'s class which `method`
+    /// overrides, and which no rule renames the same way.
+    fn overridden(&self, method: &Member, rules: &RenameRules) -> Option<String> {
+        self.index.classes().find_map(|class| {
+            let name = class.name();
+            if name == method.class || self.index.is_assignable(&method.class, name) != Some(true) {
+                return None;
+            }
+            let declares = class.methods().any(|declared| {
+                declared.name() == method.name
+                    && declared.descriptor() == method.descriptor
+                    && declared.access_flags() & (ACC_PRIVATE | ACC_STATIC) == 0
+            });
+            let renamed = rules.methods.iter().any(|(rule, _)| {
+                rule.class == name
+                    && rule.name == method.name
+                    && rule.descriptor == method.descriptor
+            });
+            (declares && !renamed).then(|| method_id(name, &method.name, &method.descriptor))
+        })
+    }
+
+    /// The members renamed to a name another one of the same descriptor
+    /// already has in a class related to theirs, or which two renamed
+    /// members would share.
+    fn member_conflicts(&self, method: bool) -> Vec<Conflict> {
+        let renamed = if method { &self.methods } else { &self.fields };
+        let id = |class: &str, name: &str, descriptor: &str| {
+            if method {
+                method_id(class, name, descriptor)
+            } else {
+                field_id(class, name, descriptor)
+            }
+        };
+        let mut all: Vec<(&str, &str, &str, &str)> = renamed
+            .iter()
+            .flat_map(|((name, descriptor), classes)| {
+                classes.iter().map(move |(class, to)| {
+                    (
+                        class.as_str(),
+                        name.as_str(),
+                        descriptor.as_str(),
+                        to.as_str(),
+                    )
+                })
+            })
+            .collect();
+        all.sort_unstable();
+
+        let mut conflicts = Vec::new();
+        let mut targets: HashMap<(&str, &str, &str), &str> = HashMap::new();
+        for &(class, name, descriptor, to) in &all {
+            let from = id(class, name, descriptor);
+            if let Some(first) = targets.insert((class, to, descriptor), name) {
+                conflicts.push(Conflict::Both {
+                    first: id(class, first, descriptor),
+                    second: from,
+                    to: to.to_string(),
+                });
+                continue;
+            }
+            let existing = self.index.classes().find_map(|other| {
+                let related = other.name() == class
+                    || self.index.is_assignable(class, other.name()) == Some(true)
+                    || (method && self.index.is_assignable(other.name(), class) == Some(true));
+                if !related {
+                    return None;
+                }
+                let mut members: Box<dyn Iterator<Item = MemberSummary>> = if method {
+                    Box::new(other.methods())
+                } else {
+                    Box::new(other.fields())
+                };
+                let declared =
+                    members.any(|member| member.name() == to && member.descriptor() == descriptor);
+                let renamed_away = renamed
+                    .get(&(to.to_string(), descriptor.to_string()))
+                    .is_some_and(|classes| classes.iter().any(|(c, _)| c == other.name()));
+                (declared && !renamed_away).then(|| id(other.name(), to, descriptor))
+            });
+            if let Some(existing) = existing {
+                conflicts.push(Conflict::Taken {
+                    from,
+                    to: to.to_string(),
+                    existing,
+                });
+            }
+        }
+        conflicts
+    }
+
+    /// The new name of a member referred to through `owner`: its own, or
+    /// that of the renamed member of a supertype it resolves to.
+    fn member(
+        &self,
+        renamed: &HashMap<(String, String), Vec<(String, String)>>,
+        owner: &str,
+        name: &str,
+        descriptor: &str,
+    ) -> Option<String> {
+        let classes = renamed.get(&(name.to_string(), descriptor.to_string()))?;
+        classes
+            .iter()
+            .find(|(class, _)| class == owner)
+            .or_else(|| {
+                classes
+                    .iter()
+                    .find(|(class, _)| self.index.is_assignable(owner, class) == Some(true))
+            })
+            .map(|(_, to)| to.clone())
+    }
+}
+
+fn add_renamed(
+    renamed: &mut HashMap<(String, String), Vec<(String, String)>>,
+    member: &Member,
+    to: &str,
+) {
+    let classes = renamed
+        .entry((member.name.clone(), member.descriptor.clone()))
+        .or_default();
+    if !classes.iter().any(|(class, _)| *class == member.class) {
+        classes.push((member.class.clone(), to.to_string()));
+    }
+}
+
+fn missing(member: &Member, method: bool, index: &ClassIndex) -> Conflict {
+    if index.get(&member.class).map(|class| class.name()) != Ok(member.class.as_str()) {
+        return Conflict::NoSuchClass(member.class.clone());
+    }
+    Conflict::NoSuchMember(if method {
+        method_id(&member.class, &member.name, &member.descriptor)
+    } else {
+        field_id(&member.class, &member.name, &member.descriptor)
+    })
+}
+
+impl Remapper for Renamer<'_> {
+    fn class(&self, name: &str) -> Option<String> {
+        self.classes.get(name).cloned()
+    }
+
+    fn field(&self, owner: &str, name: &str, descriptor: &str) -> Option<String> {
+        self.member(&self.fields, owner, name, descriptor)
+    }
+
+    fn method(&self, owner: &str, name: &str, descriptor: &str) -> Option<String> {
+        self.member(&self.methods, owner, name, descriptor)
+    }
+}
@@ -0,0 +1,398 @@
+//! The analyses `jvmb report` combines, each also available as a command of
+//! its own: `verify`, `lint`, `metrics` and `deps`. Their sections hold what
+//! the standalone commands print, as JSON. `verify` and `lint` are
+//! [`Check`]s, and their sections have the shape of every check's.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{
+    attribute::Attribute,
+    budget::AnalysisBudget,
+    classfile::ClassFile,
+    classindex::ClassIndex,
+    constantpool::ConstantPool,
+    deps::{archive_name, locate, package_dependencies, SystemModules, NOT_FOUND},
+    json::Json,
+    pipeline::{Analysis, Check, ClassContext, Finding, Needs, Section, SectionStatus},
+    rdjson::Severity,
+    rollup::{Aggregate, Measure, Value},
+    verify,
+};
+
+/// The version of the report document. Any change to its shape, including
+/// to the shape of a section, bumps it.
+pub const REPORT_VERSION: u32 = 4;
+
+/// The names of the built-in sections, in the order they're reported.
+pub const SECTIONS: &[&str] = &["verify", "lint", "metrics", "deps"];
+
+/// The whole report over `inputs`.
+pub fn document(inputs: &[&str], sections: &[Section]) -> Json {
+    Json::object([
+        ("report_version", REPORT_VERSION.into()),
+        ("inputs", Json::array(inputs.iter().copied())),
+        (
+            "section_status",
+            Json::Object(
+                sections
+                    .iter()
+                    .map(|section| (section.name.to_string(), section.status.name().into()))
+                    .collect(),
+            ),
+        ),
+        (
+            "sections",
+            Json::Object(
+                sections
+                    .iter()
+                    .map(|section| (section.name.to_string(), section.document.clone()))
+                    .collect(),
+            ),
+        ),
+    ])
+}
+
+// What each analysis reports per class, for rolling up by package. The
+// checks report the errors, warnings and infos they find.
+const METRICS_MEASURES: &[Measure] = &[
+    Measure::new("interfaces", Aggregate::Sum),
+    Measure::new("fields", Aggregate::Sum),
+    Measure::new("methods", Aggregate::Sum),
+    Measure::new("methods_with_code", Aggregate::Sum),
+    Measure::new("constant_pool_entries", Aggregate::Avg),
+    Measure::new("major_version", Aggregate::Max),
+];
+
+const DEPS_MEASURES: &[Measure] = &[Measure::new("depends_on", Aggregate::Dependencies)];
+
+/// The spec violations [`verify::verify`] finds.
+#[derive(Debug, Default)]
+pub struct Verify;
+
+impl Verify {
+    pub fn new() -> Self {
+        Verify
+    }
+}
+
+impl Check for Verify {
+    fn name(&self) -> &'static str {
+        "verify"
+    }
+
+    fn needs(&self) -> Needs {
+        Needs::Code
+    }
+
+    fn check(&mut self, class: &ClassContext) -> Vec<Finding> {
+        verify::verify(class.class_file)
+            .into_iter()
+            .map(|violation| Finding {
+                code: violation.code.to_string(),
+                severity: violation.severity.into(),
+                location: violation.location,
+                message: violation.message,
+            })
+            .collect()
+    }
+}
+
+/// The catch-all exception handlers which appear to swallow what they
+/// catch, as `jvmb lint` reports them, and the methods the budget ran out
+/// for before they were looked through.
+#[derive(Debug, Default)]
+pub struct Lint {
+    budget: AnalysisBudget,
+    incomplete: Vec<Json>,
+}
+
+impl Lint {
+    pub fn new() -> Self {
+        Lint::default()
+    }
+}
+
+impl Check for Lint {
+    fn name(&self) -> &'static str {
+        "lint"
+    }
+
+    fn needs(&self) -> Needs {
+        Needs::Code
+    }
+
+    fn set_budget(&mut self, budget: &AnalysisBudget) {
+        self.budget = *budget;
+    }
+
+    fn check(&mut self, class: &ClassContext) -> Vec<Finding> {
+        let constant_pool = &class.class_file.constant_pool;
+        let mut findings = Vec::new();
+        for method in &class.class_file.methods {
+            let code = match method.code() {
+                Some(code) => code,
+                None => continue,
+            };
+            let label = format!(
+                "{}{}",
+                ConstantPool::utf8(constant_pool, method.name_index()).unwrap_or("?"),
+                ConstantPool::utf8(constant_pool, method.descriptor_index()).unwrap_or("")
+            );
+            let handlers = code.suspicious_handlers_within(constant_pool, &self.budget);
+            if let Some(exceeded) = handlers.exceeded {
+                self.incomplete.push(Json::object([
+                    ("entry", class.entry.into()),
+                    ("method", label.as_str().into()),
+                    ("limit", exceeded.limit.name().into()),
+                ]));
+            }
+            for handler in handlers.value {
+                findings.push(Finding {
+                    code: "L0001".to_string(),
+                    severity: Severity::Error,
+                    location: format!("method {}", label),
+                    message: format!(
+                        "handler {} (pc {}): {}",
+                        handler.index,
+                        code.exception_table[handler.index].handler_pc,
+                        handler.reason
+                    ),
+                });
+            }
+        }
+        findings
+    }
+
+    fn finish(self: Box<Self>, section: &mut Section) {
+        if !self.incomplete.is_empty() {
+            section.summary += &format!(", {} methods not fully checked", self.incomplete.len());
+        }
+        section.status = SectionStatus::of(self.incomplete.len());
+        section
+            .document
+            .push("incomplete", Json::Array(self.incomplete));
+    }
+}
+
+/// Counts of what the classes declare.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    classes: usize,
+    interfaces: usize,
+    fields: usize,
+    methods: usize,
+    methods_with_code: usize,
+    constant_pool_entries: usize,
+    major_versions: BTreeMap<u16, usize>,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Metrics::default()
+    }
+}
+
+impl Analysis for Metrics {
+    fn name(&self) -> &'static str {
+        "metrics"
+    }
+
+    fn needs(&self) -> Needs {
+        Needs::Declarations
+    }
+
+    fn visit(&mut self, source: &str, entry: &str, class_file: &ClassFile) {
+        self.visit_measured(source, entry, class_file);
+    }
+
+    fn measures(&self) -> &'static [Measure] {
+        METRICS_MEASURES
+    }
+
+    fn visit_measured(&mut self, _: &str, _: &str, class_file: &ClassFile) -> Vec<Value> {
+        let interface = class_file.access_flags.is_interface();
+        let methods_with_code = class_file
+            .methods
+            .iter()
+            .filter(|method| has_code(method.attributes()))
+            .count();
+        self.classes += 1;
+        self.interfaces += usize::from(interface);
+        self.fields += class_file.fields.len();
+        self.methods += class_file.methods.len();
+        self.methods_with_code += methods_with_code;
+        self.constant_pool_entries += class_file.constant_pool.len();
+        *self
+            .major_versions
+            .entry(class_file.major_version)
+            .or_default() += 1;
+        vec![
+            usize::from(interface).into(),
+            class_file.fields.len().into(),
+            class_file.methods.len().into(),
+            methods_with_code.into(),
+            class_file.constant_pool.len().into(),
+            Value::Number(class_file.major_version.into()),
+        ]
+    }
+
+    fn finish(self: Box<Self>) -> Section {
+        Section {
+            name: "metrics",
+            summary: format!(
+                "{} classes ({} interfaces), {} fields, {} methods",
+                self.classes, self.interfaces, self.fields, self.methods
+            ),
+            document: Json::object([
+                ("classes", Json::Int(self.classes as i64)),
+                ("interfaces", Json::Int(self.interfaces as i64)),
+                ("fields", Json::Int(self.fields as i64)),
+                ("methods", Json::Int(self.methods as i64)),
+                (
+                    "methods_with_code",
+                    Json::Int(self.methods_with_code as i64),
+                ),
+                (
+                    "constant_pool_entries",
+                    Json::Int(self.constant_pool_entries as i64),
+                ),
+                (
+                    "major_versions",
+                    Json::Object(
+                        self.major_versions
+                            .iter()
+                            .map(|(version, count)| (version.to_string(), Json::Int(*count as i64)))
+                            .collect(),
+                    ),
+                ),
+            ]),
+            status: SectionStatus::Complete,
+            violations: 0,
+        }
+    }
+}
+
+/// The class dependencies `jvmb deps` prints, by input archive.
+#[derive(Debug)]
+pub struct Deps {
+    system: Option<SystemModules>,
+    classpath: ClassIndex,
+    /// The inputs seen so far, for finding classes among them.
+    inputs: ClassIndex,
+    /// source -> (origin, target), in the order the sources came.
+    edges: Vec<(String, BTreeSet<(String, String)>)>,
+}
+
+impl Deps {
+    /// Classes are found in the `system` modules, then among the inputs,
+    /// then in the `classpath`, as with `jvmb deps`.
+    pub fn new(system: Option<SystemModules>, classpath: ClassIndex) -> Self {
+        Deps {
+            system,
+            classpath,
+            inputs: ClassIndex::new(),
+            edges: Vec::new(),
+        }
+    }
+}
+
+impl Analysis for Deps {
+    fn name(&self) -> &'static str {
+        "deps"
+    }
+
+    fn needs(&self) -> Needs {
+        Needs::Declarations
+    }
+
+    fn visit(&mut self, source: &str, entry: &str, class_file: &ClassFile) {
+        self.visit_measured(source, entry, class_file);
+    }
+
+    /// The classes of other packages each class depends on, rolled up into
+    /// the packages each package depends on.
+    fn measures(&self) -> &'static [Measure] {
+        DEPS_MEASURES
+    }
+
+    fn visit_measured(&mut self, source: &str, _: &str, class_file: &ClassFile) -> Vec<Value> {
+        self.inputs.add_class(class_file, source);
+        if self.edges.last().is_none_or(|(last, _)| last != source) {
+            self.edges.push((source.to_string(), BTreeSet::new()));
+        }
+        let (_, edges) = self.edges.last_mut().expect("pushed above");
+        let dependencies = match package_dependencies(class_file) {
+            Some((origin, dependencies)) => {
+                edges.extend(
+                    dependencies
+                        .iter()
+                        .map(|target| (origin.to_string(), target.clone())),
+                );
+                dependencies
+            }
+            None => BTreeSet::new(),
+        };
+        vec![Value::Set(dependencies)]
+    }
+
+    fn finish(self: Box<Self>) -> Section {
+        let indexes = [&self.inputs, &self.classpath];
+        let mut total = 0;
+        let mut not_found = 0;
+        let archives = self
+            .edges
+            .iter()
+            .map(|(source, edges)| {
+                let archive = archive_name(source);
+                let edges: BTreeSet<(String, String, String)> = edges
+                    .iter()
+                    .map(|(origin, target)| {
+                        (
+                            origin.replace('/', "."),
+                            target.replace('/', "."),
+                            locate(target, self.system.as_ref(), &indexes),
+                        )
+                    })
+                    .collect();
+                total += edges.len();
+                not_found += edges
+                    .iter()
+                    .filter(|(_, _, found_in)| found_in == NOT_FOUND)
+                    .count();
+                let depends_on: BTreeSet<&str> = edges
+                    .iter()
+                    .map(|(_, _, found_in)| found_in.as_str())
+                    .filter(|&found_in| found_in != archive)
+                    .collect();
+                Json::object([
+                    ("archive", archive.as_str().into()),
+                    ("depends_on", Json::array(depends_on)),
+                    (
+                        "dependencies",
+                        Json::array(edges.iter().map(|(origin, target, found_in)| {
+                            Json::object([
+                                ("origin", origin.as_str().into()),
+                                ("target", target.as_str().into()),
+                                ("found_in", found_in.as_str().into()),
+                            ])
+                        })),
+                    ),
+                ])
+            })
+            .collect();
+        Section {
+            name: "deps",
+            summary: format!("{} class dependencies, {} not found", total, not_found),
+            document: Json::object([("archives", Json::Array(archives))]),
+            status: SectionStatus::Complete,
+            violations: 0,
+        }
+    }
+}
+
+/// Whether a method has a body, read or deferred.
+fn has_code(attributes: &[Attribute]) -> bool {
+    attributes
+        .iter()
+        .any(|attribute| attribute.name() == "Code")
+}
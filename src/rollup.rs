@@ -0,0 +1,188 @@
+//! Rolling up what analyses find in each class by package.
+//!
+//! An [`Analysis`](crate::pipeline::Analysis) lists the [`Measure`]s it
+//! reports per class and returns a [`Value`] for each of them as it visits a
+//! class. A [`Rollup`] combines the values of the classes of each package as
+//! the measure's [`Aggregate`] says, with packages cut to a depth to group
+//! subpackages together: at depth 2, `com.foo.bar` and `com.foo.baz` are
+//! both rolled up into `com.foo`.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{classfile::ClassFile, constantpool::ConstantPool, json::Json};
+
+/// The group of the classes in the unnamed package, as `jdeps` names it.
+pub const UNNAMED: &str = "<unnamed>";
+
+/// How the values of a measure are combined over the classes of a package.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Aggregate {
+    Sum,
+    Max,
+    /// The mean over the classes of the package.
+    Avg,
+    /// The union of the sets.
+    Union,
+    /// The union of the packages of the classes in the sets, grouped the
+    /// way classes are, less the package itself: dependencies between
+    /// classes rolled up into dependencies between packages.
+    Dependencies,
+}
+
+impl Aggregate {
+    pub fn name(self) -> &'static str {
+        match self {
+            Aggregate::Sum => "sum",
+            Aggregate::Max => "max",
+            Aggregate::Avg => "avg",
+            Aggregate::Union => "union",
+            Aggregate::Dependencies => "dependencies",
+        }
+    }
+}
+
+/// A result an analysis reports per class.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Measure {
+    pub name: &'static str,
+    pub aggregate: Aggregate,
+}
+
+impl Measure {
+    pub const fn new(name: &'static str, aggregate: Aggregate) -> Self {
+        Measure { name, aggregate }
+    }
+}
+
+/// What an analysis found in one class for one of its measures: a number
+/// for [`Aggregate::Sum`], [`Aggregate::Max`] and [`Aggregate::Avg`], a set
+/// for the others.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    Number(i64),
+    Set(BTreeSet<String>),
+}
+
+impl From<usize> for Value {
+    fn from(number: usize) -> Self {
+        Value::Number(number as i64)
+    }
+}
+
+/// The group `class_name` is rolled up into: its package, dotted, cut to
+/// the first `depth` segments if given, or [`UNNAMED`].
+pub fn package_group(class_name: &str, depth: Option<usize>) -> String {
+    let package = match class_name.rsplit_once('/') {
+        Some((package, _)) => package,
+        None => return UNNAMED.to_string(),
+    };
+    let segments = package.split('/');
+    let segments: Vec<&str> = match depth {
+        Some(depth) => segments.take(depth).collect(),
+        None => segments.collect(),
+    };
+    segments.join(".")
+}
+
+/// The values of the measures of one analysis, combined by package.
+#[derive(Debug, Clone)]
+pub struct Rollup {
+    depth: Option<usize>,
+    measures: &'static [Measure],
+    groups: BTreeMap<String, Group>,
+}
+
+#[derive(Debug, Clone)]
+struct Group {
+    classes: usize,
+    /// The running sum or maximum of each numeric measure, in the order of
+    /// the measures; unused for the others.
+    numbers: Vec<i64>,
+    sets: Vec<BTreeSet<String>>,
+}
+
+impl Rollup {
+    /// Rolls up `measures` by package, cut to `depth` segments if given.
+    pub fn new(measures: &'static [Measure], depth: Option<usize>) -> Self {
+        Rollup {
+            depth,
+            measures,
+            groups: BTreeMap::new(),
+        }
+    }
+
+    /// Adds what was found in `class_file`, a value for each measure in
+    /// order. Values of the wrong kind for their measure, and any past the
+    /// last measure, are ignored.
+    pub fn add(&mut self, class_file: &ClassFile, values: Vec<Value>) {
+        let class_name = ConstantPool::class_name(&class_file.constant_pool, class_file.this_class)
+            .unwrap_or_default();
+        let name = package_group(class_name, self.depth);
+        let depth = self.depth;
+        let group = self.groups.entry(name.clone()).or_insert_with(|| Group {
+            classes: 0,
+            numbers: vec![0; self.measures.len()],
+            sets: vec![BTreeSet::new(); self.measures.len()],
+        });
+        group.classes += 1;
+        for (i, (measure, value)) in self.measures.iter().zip(values).enumerate() {
+            match (measure.aggregate, value) {
+                (Aggregate::Sum | Aggregate::Avg, Value::Number(number)) => {
+                    group.numbers[i] += number
+                }
+                (Aggregate::Max, Value::Number(number)) => {
+                    group.numbers[i] = if group.classes == 1 {
+                        number
+                    } else {
+                        group.numbers[i].max(number)
+                    }
+                }
+                (Aggregate::Union, Value::Set(set)) => group.sets[i].extend(set),
+                (Aggregate::Dependencies, Value::Set(classes)) => group.sets[i].extend(
+                    classes
+                        .iter()
+                        .map(|class| package_group(class, depth))
+                        .filter(|package| *package != name),
+                ),
+                _ => {}
+            }
+        }
+    }
+
+    /// `{"package_depth", "aggregates": {<measure>: <aggregate>},
+    /// "packages": [{"package", "classes", <measure>...}]}`, with packages
+    /// in name order and the `avg` of a measure rounded to two decimals.
+    pub fn to_json(&self) -> Json {
+        let aggregates = Json::Object(
+            self.measures
+                .iter()
+                .map(|measure| (measure.name.to_string(), measure.aggregate.name().into()))
+                .collect(),
+        );
+        let packages = self.groups.iter().map(|(name, group)| {
+            let mut package = Json::object([
+                ("package", name.as_str().into()),
+                ("classes", Json::Int(group.classes as i64)),
+            ]);
+            for (i, measure) in self.measures.iter().enumerate() {
+                let value = match measure.aggregate {
+                    Aggregate::Sum | Aggregate::Max => Json::Int(group.numbers[i]),
+                    Aggregate::Avg => {
+                        let mean = group.numbers[i] as f64 / group.classes as f64;
+                        Json::Float((mean * 100.0).round() / 100.0)
+                    }
+                    Aggregate::Union | Aggregate::Dependencies => {
+                        Json::array(group.sets[i].iter().map(String::as_str))
+                    }
+                };
+                package.push(measure.name, value);
+            }
+            package
+        });
+        Json::object([
+            ("package_depth", self.depth.map(|depth| depth as i64).into()),
+            ("aggregates", aggregates),
+            ("packages", Json::array(packages)),
+        ])
+    }
+}
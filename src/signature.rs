@@ -0,0 +1,932 @@
+//! Generic signatures, as held by `Signature` attributes, and the best
+//! signature of a class, field or method for display.
+//!
+//! A `Signature` attribute describes the generic types of a declaration,
+//! but only where the source has them: the parameters the compiler adds to
+//! a constructor, such as the name and ordinal of an enum constant or the
+//! outer instance of an inner class, are left out, and elements with no
+//! generics usually have no attribute at all. [`best_method_signature`] and
+//! its siblings reconcile the attribute with the descriptor, taking the
+//! generic types where the attribute has them and the erased ones from the
+//! descriptor everywhere else.
+
+use core::fmt;
+
+use crate::prelude::*;
+use crate::{
+    accessflags::{ACC_INTERFACE, ACC_MANDATED, ACC_STATIC, ACC_SYNTHETIC},
+    attribute::Attribute,
+    classfile::ClassFile,
+    constantpool::ConstantPool,
+    descriptor::{BaseType, FieldType, MethodDescriptor},
+    fieldinfo::FieldInfo,
+    methodinfo::MethodInfo,
+};
+
+/// How deep type variables bounded by other type variables are followed
+/// when erasing, which also stops cycles in malformed signatures.
+const MAX_BOUND_DEPTH: usize = 8;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignatureError {
+    pub signature: String,
+    /// Byte offset into the signature where parsing failed.
+    pub offset: usize,
+}
+
+impl fmt::Display for SignatureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid signature {:?} at offset {}",
+            self.signature, self.offset
+        )
+    }
+}
+
+impl core::error::Error for SignatureError {}
+
+/// A type as a signature gives it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TypeSignature {
+    Base(BaseType),
+    Class(ClassTypeSignature),
+    /// A type variable, by name.
+    Variable(String),
+    Array(Box<TypeSignature>),
+}
+
+/// A class type with the type arguments of its outer classes, e.g.
+/// `java/util/Map<TK;TV;>` or `Outer<TE;>.Inner`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClassTypeSignature {
+    /// The outermost class first, its name including the package.
+    pub segments: Vec<ClassTypeSegment>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClassTypeSegment {
+    pub name: String,
+    pub type_arguments: Vec<TypeArgument>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TypeArgument {
+    /// `*`, written `?` in source.
+    Any,
+    Exactly(TypeSignature),
+    Extends(TypeSignature),
+    Super(TypeSignature),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeParameter {
+    pub name: String,
+    /// Absent when the parameter is only bounded by interfaces.
+    pub class_bound: Option<TypeSignature>,
+    pub interface_bounds: Vec<TypeSignature>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClassSignature {
+    pub type_parameters: Vec<TypeParameter>,
+    /// `None` only for `java/lang/Object` and module descriptors.
+    pub super_class: Option<ClassTypeSignature>,
+    pub interfaces: Vec<ClassTypeSignature>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MethodSignature {
+    pub type_parameters: Vec<TypeParameter>,
+    pub parameters: Vec<TypeSignature>,
+    /// `None` for `void` methods.
+    pub return_type: Option<TypeSignature>,
+    pub throws: Vec<TypeSignature>,
+}
+
+/// The signature of a field, a record component or a local variable,
+/// which is the signature of a type.
+pub type FieldSignature = TypeSignature;
+
+/// A signature parsed without knowing what declares it, see
+/// [`ParsedSignature::parse`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParsedSignature {
+    Class(ClassSignature),
+    Method(MethodSignature),
+    Field(FieldSignature),
+}
+
+impl ParsedSignature {
+    /// Parses a signature as what its shape says it is: a method signature
+    /// has parameters in parentheses, and a class signature type
+    /// parameters, interfaces or both. A class signature with neither, e.g.
+    /// `Ljava/util/ArrayList<Ljava/lang/String;>;`, can't be told from the
+    /// signature of a field of that type, and is parsed as the latter; use
+    /// [`ClassSignature::parse`] when the declaration is known to be a
+    /// class.
+    pub fn parse(signature: &str) -> Result<Self, SignatureError> {
+        let mut parser = Parser::new(signature);
+        parser.type_parameters()?;
+        if parser.peek() == Some(b'(') {
+            return MethodSignature::parse(signature).map(ParsedSignature::Method);
+        }
+        if parser.pos > 0 {
+            return ClassSignature::parse(signature).map(ParsedSignature::Class);
+        }
+        TypeSignature::parse(signature)
+            .map(ParsedSignature::Field)
+            .or_else(|field_error| {
+                // report where the signature got furthest as either
+                ClassSignature::parse(signature)
+                    .map(ParsedSignature::Class)
+                    .map_err(|class_error| {
+                        if class_error.offset > field_error.offset {
+                            class_error
+                        } else {
+                            field_error
+                        }
+                    })
+            })
+    }
+}
+
+impl TypeSignature {
+    /// Parses a field signature.
+    pub fn parse(signature: &str) -> Result<Self, SignatureError> {
+        let mut parser = Parser::new(signature);
+        let parsed = parser.type_signature()?;
+        parser.end()?;
+        Ok(parsed)
+    }
+
+    /// The type a descriptor gives, which has no generics.
+    pub fn from_field_type(field_type: &FieldType) -> Self {
+        match field_type {
+            FieldType::Base(base_type) => TypeSignature::Base(*base_type),
+            FieldType::Object(name) => TypeSignature::Class(ClassTypeSignature::erased(name)),
+            FieldType::Array {
+                dimensions,
+                element_type,
+            } => (0..*dimensions).fold(
+                TypeSignature::from_field_type(element_type),
+                |element, _| TypeSignature::Array(Box::new(element)),
+            ),
+        }
+    }
+
+    /// The descriptor type this erases to, given the type parameters in
+    /// scope, innermost first. `None` for a type variable declared out of
+    /// scope, as by the outer class of an inner class.
+    fn erasure(&self, scopes: &[&[TypeParameter]], depth: usize) -> Option<FieldType> {
+        match self {
+            TypeSignature::Base(base_type) => Some(FieldType::Base(*base_type)),
+            TypeSignature::Class(class) => Some(FieldType::Object(class.erasure())),
+            TypeSignature::Variable(name) => {
+                let parameter = scopes
+                    .iter()
+                    .find_map(|scope| scope.iter().find(|parameter| parameter.name == *name))?;
+                let bound = parameter
+                    .class_bound
+                    .as_ref()
+                    .or(parameter.interface_bounds.first());
+                match bound {
+                    Some(bound) if depth < MAX_BOUND_DEPTH => bound.erasure(scopes, depth + 1),
+                    Some(_) => None,
+                    None => Some(FieldType::Object("java/lang/Object".to_string())),
+                }
+            }
+            TypeSignature::Array(element) => Some(match element.erasure(scopes, depth)? {
+                FieldType::Array {
+                    dimensions,
+                    element_type,
+                } => FieldType::Array {
+                    dimensions: dimensions.checked_add(1)?,
+                    element_type,
+                },
+                element => FieldType::Array {
+                    dimensions: 1,
+                    element_type: Box::new(element),
+                },
+            }),
+        }
+    }
+}
+
+impl ClassTypeSignature {
+    fn erased(name: &str) -> Self {
+        ClassTypeSignature {
+            segments: vec![ClassTypeSegment {
+                name: name.to_string(),
+                type_arguments: Vec::new(),
+            }],
+        }
+    }
+
+    /// The internal name of the class, e.g. `Outer$Inner`.
+    pub fn erasure(&self) -> String {
+        let names: Vec<&str> = self
+            .segments
+            .iter()
+            .map(|segment| segment.name.as_str())
+            .collect();
+        names.join("$")
+    }
+}
+
+impl ClassSignature {
+    pub fn parse(signature: &str) -> Result<Self, SignatureError> {
+        let mut parser = Parser::new(signature);
+        let type_parameters = parser.type_parameters()?;
+        let super_class = Some(parser.class_type()?);
+        let mut interfaces = Vec::new();
+        while !parser.at_end() {
+            interfaces.push(parser.class_type()?);
+        }
+        Ok(ClassSignature {
+            type_parameters,
+            super_class,
+            interfaces,
+        })
+    }
+}
+
+impl MethodSignature {
+    pub fn parse(signature: &str) -> Result<Self, SignatureError> {
+        let mut parser = Parser::new(signature);
+        let type_parameters = parser.type_parameters()?;
+        parser.expect(b'(')?;
+        let mut parameters = Vec::new();
+        while !parser.eat(b')') {
+            parameters.push(parser.type_signature()?);
+        }
+        let return_type = if parser.eat(b'V') {
+            None
+        } else {
+            Some(parser.type_signature()?)
+        };
+        let mut throws = Vec::new();
+        while parser.eat(b'^') {
+            throws.push(parser.type_signature()?);
+        }
+        parser.end()?;
+        Ok(MethodSignature {
+            type_parameters,
+            parameters,
+            return_type,
+            throws,
+        })
+    }
+
+    fn from_descriptor(descriptor: &MethodDescriptor) -> Self {
+        MethodSignature {
+            type_parameters: Vec::new(),
+            parameters: descriptor
+                .parameters
+                .iter()
+                .map(TypeSignature::from_field_type)
+                .collect(),
+            return_type: descriptor
+                .return_type
+                .as_ref()
+                .map(TypeSignature::from_field_type),
+            throws: Vec::new(),
+        }
+    }
+}
+
+/// The best signature of a class, see [`best_class_signature`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BestClassSignature {
+    /// The internal name.
+    pub name: String,
+    pub is_interface: bool,
+    pub signature: ClassSignature,
+    /// Whether the types come from the `Signature` attribute.
+    pub from_attribute: bool,
+}
+
+/// The best signature of a method, see [`best_method_signature`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BestMethodSignature {
+    /// The method's name, and for a constructor the internal name of its
+    /// class.
+    pub name: String,
+    pub is_constructor: bool,
+    /// One parameter for every one in the descriptor.
+    pub signature: MethodSignature,
+    /// For each parameter, whether the compiler added it: in the descriptor
+    /// but not in the source, such as the outer instance of an inner class.
+    pub implicit: Vec<bool>,
+    /// Whether the generic types come from the `Signature` attribute.
+    pub from_attribute: bool,
+}
+
+impl BestMethodSignature {
+    /// The parameters the source declares as written there, e.g.
+    /// `(java.util.List<T>, int)`.
+    pub fn java_parameters(&self) -> String {
+        let parameters: Vec<String> = self
+            .signature
+            .parameters
+            .iter()
+            .zip(&self.implicit)
+            .filter(|(_, &implicit)| !implicit)
+            .map(|(parameter, _)| parameter.to_string())
+            .collect();
+        format!("({})", parameters.join(", "))
+    }
+}
+
+/// The class's signature: its `Signature` attribute, or without one, its
+/// superclass and interfaces.
+pub fn best_class_signature(class_file: &ClassFile) -> BestClassSignature {
+    let constant_pool = &class_file.constant_pool;
+    let class_name =
+        |index| ConstantPool::class_name(constant_pool, index).map(ClassTypeSignature::erased);
+    let parsed = signature(&class_file.attributes, constant_pool)
+        .and_then(|signature| ClassSignature::parse(signature).ok());
+    BestClassSignature {
+        name: ConstantPool::class_name(constant_pool, class_file.this_class)
+            .unwrap_or("?")
+            .to_string(),
+        is_interface: class_file.access_flags.is_interface(),
+        from_attribute: parsed.is_some(),
+        signature: parsed.unwrap_or_else(|| ClassSignature {
+            type_parameters: Vec::new(),
+            super_class: class_name(class_file.super_class),
+            interfaces: class_file
+                .interfaces
+                .iter()
+                .filter_map(|&index| class_name(index))
+                .collect(),
+        }),
+    }
+}
+
+/// The field's type: from its `Signature` attribute, or without one, or
+/// one which doesn't parse, from its descriptor. `None` when neither
+/// parses.
+pub fn best_field_signature(class_file: &ClassFile, field: &FieldInfo) -> Option<TypeSignature> {
+    let constant_pool = &class_file.constant_pool;
+    signature(field.attributes(), constant_pool)
+        .and_then(|signature| TypeSignature::parse(signature).ok())
+        .or_else(|| {
+            let descriptor = ConstantPool::utf8(constant_pool, field.descriptor_index())?;
+            Some(TypeSignature::from_field_type(
+                &FieldType::parse(descriptor).ok()?,
+            ))
+        })
+}
+
+/// The method's signature, with the generic types of its `Signature`
+/// attribute and a parameter for every one in its descriptor. `None` when
+/// the descriptor doesn't parse.
+///
+/// The attribute leaves out the parameters the compiler adds. Which of the
+/// descriptor's parameters those are is taken from the `MethodParameters`
+/// attribute when there is one. Otherwise they're the first two of an enum
+/// constructor (the constant's name and ordinal), the first of an inner
+/// class's constructor (the outer instance), and any left after those at
+/// the end (the captured variables of a local class). Each type of the
+/// attribute has to erase to the descriptor's type it's matched with; if
+/// that fails the first placement which fits is taken, and if none does the
+/// attribute is ignored.
+pub fn best_method_signature(
+    class_file: &ClassFile,
+    method: &MethodInfo,
+) -> Option<BestMethodSignature> {
+    let constant_pool = &class_file.constant_pool;
+    let name = ConstantPool::utf8(constant_pool, method.name_index())?;
+    let descriptor = MethodDescriptor::parse(ConstantPool::utf8(
+        constant_pool,
+        method.descriptor_index(),
+    )?)
+    .ok()?;
+    let is_constructor = name == "<init>";
+    let name = if is_constructor {
+        ConstantPool::class_name(constant_pool, class_file.this_class)?
+    } else {
+        name
+    };
+    let exceptions = || {
+        method
+            .attributes()
+            .iter()
+            .filter_map(|attribute| match attribute {
+                Attribute::Exceptions(exceptions) => Some(&exceptions.exception_index_table),
+                _ => None,
+            })
+            .flatten()
+            .filter_map(|&index| ConstantPool::class_name(constant_pool, index))
+            .map(|name| TypeSignature::Class(ClassTypeSignature::erased(name)))
+            .collect()
+    };
+    let erased = || BestMethodSignature {
+        name: name.to_string(),
+        is_constructor,
+        signature: MethodSignature {
+            throws: exceptions(),
+            ..MethodSignature::from_descriptor(&descriptor)
+        },
+        implicit: vec![false; descriptor.parameters.len()],
+        from_attribute: false,
+    };
+
+    let parsed = match signature(method.attributes(), constant_pool)
+        .and_then(|signature| MethodSignature::parse(signature).ok())
+    {
+        Some(parsed) => parsed,
+        None => return Some(erased()),
+    };
+    let total = descriptor.parameters.len();
+    let declared = parsed.parameters.len();
+    if declared > total {
+        return Some(erased());
+    }
+
+    let class_type_parameters = best_class_signature(class_file).signature.type_parameters;
+    let scopes = [
+        parsed.type_parameters.as_slice(),
+        class_type_parameters.as_slice(),
+    ];
+    // whether a type of the attribute fits the descriptor's
+    let fits_type = |parameter: &TypeSignature, expected: &FieldType| {
+        match parameter.erasure(&scopes, 0) {
+            Some(erased) => erased == *expected,
+            // a type variable of an outer class erases to some reference
+            None => !matches!(expected, FieldType::Base(_)),
+        }
+    };
+    // whether the attribute's parameters fit the descriptor's from `first`
+    let fits = |first: usize| {
+        parsed
+            .parameters
+            .iter()
+            .zip(&descriptor.parameters[first..])
+            .all(|(parameter, expected)| fits_type(parameter, expected))
+    };
+
+    let implicit = explicit_parameters(method, total, declared).filter(|explicit| {
+        let expected = descriptor
+            .parameters
+            .iter()
+            .zip(explicit)
+            .filter(|(_, &explicit)| explicit)
+            .map(|(expected, _)| expected);
+        parsed
+            .parameters
+            .iter()
+            .zip(expected)
+            .all(|(parameter, expected)| fits_type(parameter, expected))
+    });
+    let implicit: Vec<bool> = match implicit {
+        Some(explicit) => explicit.iter().map(|explicit| !explicit).collect(),
+        None => {
+            let leading = if is_constructor {
+                leading_implicit(class_file).min(total - declared)
+            } else {
+                0
+            };
+            let first = if fits(leading) {
+                leading
+            } else {
+                match (0..=total - declared).find(|&first| fits(first)) {
+                    Some(first) => first,
+                    None => return Some(erased()),
+                }
+            };
+            (0..total)
+                .map(|i| i < first || i >= first + declared)
+                .collect()
+        }
+    };
+
+    let mut from_attribute = parsed.parameters.into_iter();
+    let parameters = descriptor
+        .parameters
+        .iter()
+        .zip(&implicit)
+        .map(|(field_type, &implicit)| match implicit {
+            true => TypeSignature::from_field_type(field_type),
+            false => from_attribute
+                .next()
+                .unwrap_or_else(|| TypeSignature::from_field_type(field_type)),
+        })
+        .collect();
+    Some(BestMethodSignature {
+        name: name.to_string(),
+        is_constructor,
+        signature: MethodSignature {
+            type_parameters: parsed.type_parameters,
+            parameters,
+            return_type: parsed.return_type,
+            throws: if parsed.throws.is_empty() {
+                exceptions()
+            } else {
+                parsed.throws
+            },
+        },
+        implicit,
+        from_attribute: true,
+    })
+}
+
+/// For each of the `total` parameters, whether the `MethodParameters`
+/// attribute says the source declares it, when it agrees with the
+/// `declared` count of the `Signature` attribute.
+fn explicit_parameters(method: &MethodInfo, total: usize, declared: usize) -> Option<Vec<bool>> {
+    let parameters = method
+        .attributes()
+        .iter()
+        .find_map(|attribute| match attribute {
+            Attribute::MethodParameters(parameters) => Some(parameters),
+            _ => None,
+        })?;
+    let explicit: Vec<bool> = parameters
+        .iter()
+        .map(|parameter| {
+            !parameter
+                .access_flags
+                .intersects(ACC_SYNTHETIC | ACC_MANDATED)
+        })
+        .collect();
+    (explicit.len() == total && explicit.iter().filter(|&&explicit| explicit).count() == declared)
+        .then_some(explicit)
+}
+
+/// How many parameters the compiler puts before those of the source in a
+/// constructor of the class: the name and ordinal of an enum constant, or
+/// the outer instance of an inner class.
+fn leading_implicit(class_file: &ClassFile) -> usize {
+    let constant_pool = &class_file.constant_pool;
+    if class_file.access_flags.is_enum()
+        && ConstantPool::class_name(constant_pool, class_file.super_class) == Some("java/lang/Enum")
+    {
+        return 2;
+    }
+    let inner = class_file
+        .attributes
+        .iter()
+        .filter_map(|attribute| match attribute {
+            Attribute::InnerClasses(inner_classes) => Some(&inner_classes.classes),
+            _ => None,
+        })
+        .flatten()
+        .find(|inner| inner.inner_class_info_index == class_file.this_class);
+    match inner {
+        // a member or local class not declared static, which has an outer
+        // instance unless it was declared in a static context
+        Some(inner)
+            if !inner
+                .inner_class_access_flags
+                .intersects(ACC_STATIC | ACC_INTERFACE) =>
+        {
+            let has_outer_instance = class_file.fields.iter().any(|field| {
+                field.access_flags().is_synthetic()
+                    && ConstantPool::utf8(constant_pool, field.name_index())
+                        .is_some_and(|name| name.starts_with("this$"))
+            });
+            usize::from(has_outer_instance || inner.outer_class_info_index != 0)
+        }
+        _ => 0,
+    }
+}
+
+/// The value of the `Signature` attribute among `attributes`, if any.
+pub(crate) fn signature<'a>(
+    attributes: &[Attribute],
+    constant_pool: &'a [ConstantPool],
+) -> Option<&'a str> {
+    attributes.iter().find_map(|attribute| match attribute {
+        Attribute::Signature(signature) => {
+            ConstantPool::utf8(constant_pool, signature.signature_index)
+        }
+        _ => None,
+    })
+}
+
+/// Renders the type the way it's written in Java source, e.g.
+/// `java.util.Map<K, ? extends V>[]`.
+impl fmt::Display for TypeSignature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TypeSignature::Base(base_type) => f.write_str(base_type.java_name()),
+            TypeSignature::Class(class) => write!(f, "{}", class),
+            TypeSignature::Variable(name) => f.write_str(name),
+            TypeSignature::Array(element) => write!(f, "{}[]", element),
+        }
+    }
+}
+
+impl fmt::Display for ClassTypeSignature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, segment) in self.segments.iter().enumerate() {
+            if i > 0 {
+                f.write_str(".")?;
+            }
+            f.write_str(&segment.name.replace('/', "."))?;
+            if !segment.type_arguments.is_empty() {
+                let arguments: Vec<String> = segment
+                    .type_arguments
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect();
+                write!(f, "<{}>", arguments.join(", "))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for TypeArgument {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TypeArgument::Any => f.write_str("?"),
+            TypeArgument::Exactly(bound) => write!(f, "{}", bound),
+            TypeArgument::Extends(bound) => write!(f, "? extends {}", bound),
+            TypeArgument::Super(bound) => write!(f, "? super {}", bound),
+        }
+    }
+}
+
+/// Renders the parameter as declared, e.g. `T extends java.lang.Number &
+/// java.lang.Comparable<T>`. A bound of `java.lang.Object` is left out.
+impl fmt::Display for TypeParameter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name)?;
+        let bounds: Vec<String> = self
+            .class_bound
+            .iter()
+            .filter(|bound| !is_object(bound))
+            .chain(&self.interface_bounds)
+            .map(ToString::to_string)
+            .collect();
+        if !bounds.is_empty() {
+            write!(f, " extends {}", bounds.join(" & "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Renders the declaration the way `javap` does, without modifiers, e.g.
+/// `Outer<E extends java.lang.Number> extends Base<E> implements
+/// java.lang.Runnable`. Names are binary names with dots.
+impl fmt::Display for BestClassSignature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name.replace('/', "."))?;
+        write_type_parameters(f, &self.signature.type_parameters)?;
+        let super_class = self
+            .signature
+            .super_class
+            .as_ref()
+            .filter(|super_class| super_class.erasure() != "java/lang/Object");
+        if let Some(super_class) = super_class {
+            write!(f, " extends {}", super_class)?;
+        }
+        if !self.signature.interfaces.is_empty() {
+            let interfaces: Vec<String> = self
+                .signature
+                .interfaces
+                .iter()
+                .map(ToString::to_string)
+                .collect();
+            let keyword = if self.is_interface {
+                "extends"
+            } else {
+                "implements"
+            };
+            write!(f, " {} {}", keyword, interfaces.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Renders the declaration the way `javap` does, without modifiers and
+/// leaving out implicit parameters, e.g. `<T> T[] toArray(T[])` or
+/// `Outer$Inner(java.util.List<java.lang.String>, int)`.
+impl fmt::Display for BestMethodSignature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.name == "<clinit>" {
+            return f.write_str("static {}");
+        }
+        let type_parameters = &self.signature.type_parameters;
+        if !type_parameters.is_empty() {
+            write_type_parameters(f, type_parameters)?;
+            f.write_str(" ")?;
+        }
+        if !self.is_constructor {
+            match &self.signature.return_type {
+                Some(return_type) => write!(f, "{} ", return_type)?,
+                None => f.write_str("void ")?,
+            }
+        }
+        write!(
+            f,
+            "{}{}",
+            self.name.replace('/', "."),
+            self.java_parameters()
+        )?;
+        if !self.signature.throws.is_empty() {
+            let throws: Vec<String> = self
+                .signature
+                .throws
+                .iter()
+                .map(ToString::to_string)
+                .collect();
+            write!(f, " throws {}", throws.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+fn write_type_parameters(
+    f: &mut fmt::Formatter<'_>,
+    type_parameters: &[TypeParameter],
+) -> fmt::Result {
+    if type_parameters.is_empty() {
+        return Ok(());
+    }
+    let type_parameters: Vec<String> = type_parameters.iter().map(ToString::to_string).collect();
+    write!(f, "<{}>", type_parameters.join(", "))
+}
+
+fn is_object(bound: &TypeSignature) -> bool {
+    matches!(bound, TypeSignature::Class(class) if class.erasure() == "java/lang/Object")
+}
+
+struct Parser<'a> {
+    signature: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(signature: &'a str) -> Self {
+        Parser { signature, pos: 0 }
+    }
+
+    fn error(&self) -> SignatureError {
+        SignatureError {
+            signature: self.signature.to_string(),
+            offset: self.pos,
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.signature.as_bytes().get(self.pos).copied()
+    }
+
+    fn at_end(&self) -> bool {
+        self.pos == self.signature.len()
+    }
+
+    fn end(&self) -> Result<(), SignatureError> {
+        if self.at_end() {
+            Ok(())
+        } else {
+            Err(self.error())
+        }
+    }
+
+    fn eat(&mut self, expected: u8) -> bool {
+        let found = self.peek() == Some(expected);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, expected: u8) -> Result<(), SignatureError> {
+        if self.eat(expected) {
+            Ok(())
+        } else {
+            Err(self.error())
+        }
+    }
+
+    fn identifier(&mut self) -> Result<&'a str, SignatureError> {
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|b| !matches!(b, b'.' | b';' | b'[' | b'/' | b'<' | b'>' | b':'))
+        {
+            self.pos += 1;
+        }
+        if self.pos == start {
+            return Err(self.error());
+        }
+        Ok(&self.signature[start..self.pos])
+    }
+
+    fn type_parameters(&mut self) -> Result<Vec<TypeParameter>, SignatureError> {
+        let mut type_parameters = Vec::new();
+        if !self.eat(b'<') {
+            return Ok(type_parameters);
+        }
+        while !self.eat(b'>') {
+            let name = self.identifier()?.to_string();
+            self.expect(b':')?;
+            let class_bound = match self.peek() {
+                Some(b':') | Some(b'>') => None,
+                _ => Some(self.reference_type_signature()?),
+            };
+            let mut interface_bounds = Vec::new();
+            while self.eat(b':') {
+                interface_bounds.push(self.reference_type_signature()?);
+            }
+            type_parameters.push(TypeParameter {
+                name,
+                class_bound,
+                interface_bounds,
+            });
+        }
+        if type_parameters.is_empty() {
+            return Err(self.error());
+        }
+        Ok(type_parameters)
+    }
+
+    fn type_signature(&mut self) -> Result<TypeSignature, SignatureError> {
+        match self.peek() {
+            Some(b'L') => Ok(TypeSignature::Class(self.class_type()?)),
+            Some(b'T') => {
+                self.pos += 1;
+                let name = self.identifier()?.to_string();
+                self.expect(b';')?;
+                Ok(TypeSignature::Variable(name))
+            }
+            Some(b'[') => {
+                self.pos += 1;
+                Ok(TypeSignature::Array(Box::new(self.type_signature()?)))
+            }
+            Some(b) => match BaseType::from_char(b as char) {
+                Some(base_type) => {
+                    self.pos += 1;
+                    Ok(TypeSignature::Base(base_type))
+                }
+                None => Err(self.error()),
+            },
+            None => Err(self.error()),
+        }
+    }
+
+    /// A type which isn't primitive, as type arguments and bounds are.
+    fn reference_type_signature(&mut self) -> Result<TypeSignature, SignatureError> {
+        match self.peek() {
+            Some(b'L' | b'T' | b'[') => self.type_signature(),
+            _ => Err(self.error()),
+        }
+    }
+
+    fn class_type(&mut self) -> Result<ClassTypeSignature, SignatureError> {
+        self.expect(b'L')?;
+        // the package, if any, is part of the first segment's name
+        let start = self.pos;
+        loop {
+            self.identifier()?;
+            if !self.eat(b'/') {
+                break;
+            }
+        }
+        let mut segments = vec![ClassTypeSegment {
+            name: self.signature[start..self.pos].to_string(),
+            type_arguments: self.type_arguments()?,
+        }];
+        while self.eat(b'.') {
+            segments.push(ClassTypeSegment {
+                name: self.identifier()?.to_string(),
+                type_arguments: self.type_arguments()?,
+            });
+        }
+        self.expect(b';')?;
+        Ok(ClassTypeSignature { segments })
+    }
+
+    fn type_arguments(&mut self) -> Result<Vec<TypeArgument>, SignatureError> {
+        let mut type_arguments = Vec::new();
+        if !self.eat(b'<') {
+            return Ok(type_arguments);
+        }
+        while !self.eat(b'>') {
+            type_arguments.push(match self.peek() {
+                Some(b'*') => {
+                    self.pos += 1;
+                    TypeArgument::Any
+                }
+                Some(b'+') => {
+                    self.pos += 1;
+                    TypeArgument::Extends(self.reference_type_signature()?)
+                }
+                Some(b'-') => {
+                    self.pos += 1;
+                    TypeArgument::Super(self.reference_type_signature()?)
+                }
+                _ => TypeArgument::Exactly(self.reference_type_signature()?),
+            });
+        }
+        if type_arguments.is_empty() {
+            return Err(self.error());
+        }
+        Ok(type_arguments)
+    }
+}
@@ -0,0 +1,242 @@
+//! Where the bytes of a class file go, for finding what bloats a jar.
+//!
+//! The sizes are those of the file as stored rather than of the class as
+//! parsed: attributes are measured by their stored lengths, and the parts of
+//! the file always add up to its length, bytes after the class included.
+
+use alloc::collections::BTreeMap;
+
+use crate::prelude::*;
+use crate::{
+    attribute::AttributeInfo,
+    borrowed::{BorrowedClassFile, BorrowedCode, BorrowedConstant, BorrowedMember},
+    classfile::ParseOptions,
+    constantpool::ConstantPool,
+    error::{ErrorAt, ParseError},
+    mutf8, ordering, spec,
+};
+
+const CONSTANT_UTF8: u8 = 1;
+
+/// How many there are of something and the bytes they take up.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Tally {
+    pub count: usize,
+    pub bytes: usize,
+}
+
+impl Tally {
+    fn add(&mut self, bytes: usize) {
+        self.count += 1;
+        self.bytes += bytes;
+    }
+}
+
+/// A part of the class file, in the order of the file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Section {
+    /// `header` (the magic, the version and the constant count), `constant
+    /// pool`, `class` (the flags, this and super), `interfaces`, `fields`,
+    /// `methods`, `attributes` or `trailing`, each table with its count.
+    pub name: &'static str,
+    pub bytes: usize,
+}
+
+/// The size of a method.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MethodSize {
+    /// Its name and descriptor, e.g. `run()V`.
+    pub name: String,
+    /// The whole `method_info`, attributes and all.
+    pub bytes: usize,
+    /// The length of its bytecode, `None` when it has no `Code`.
+    pub code_length: Option<usize>,
+}
+
+/// The size of a UTF8 constant.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Utf8Size {
+    pub index: u16,
+    pub value: String,
+    /// The whole entry, tag and length included.
+    pub bytes: usize,
+}
+
+/// Where the bytes of a class file go.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SizeReport {
+    pub file_size: usize,
+    /// The parts of the file, adding up to `file_size`.
+    pub sections: Vec<Section>,
+    /// The constant pool entries by kind, as the JVMS names them, e.g.
+    /// `Methodref`.
+    pub constants: BTreeMap<&'static str, Tally>,
+    /// The attributes by name, those of the class, its members and their
+    /// `Code` alike, each with its name index and length. A `Code` is
+    /// counted without the attributes in it, so that the figures add up to
+    /// every byte of attributes in the file.
+    pub attributes: BTreeMap<String, Tally>,
+    /// In the order of the class.
+    pub methods: Vec<MethodSize>,
+    /// In the order of the pool.
+    pub utf8: Vec<Utf8Size>,
+}
+
+impl SizeReport {
+    /// Measures the class file in `buf`:
+    ///
+    /// ```
+    /// use jvmb::sizes::SizeReport;
+    ///
+    /// let class = [
+    ///     0xCA, 0xFE, 0xBA, 0xBE, 0, 0, 0, 52, 0, 3, // magic, version, 2 constants
+    ///     1, 0, 1, b'A', // #1 = Utf8 A
+    ///     7, 0, 1, // #2 = Class #1
+    ///     0, 0x21, 0, 2, 0, 0, // flags, this, super
+    ///     0, 0, 0, 0, 0, 0, 0, 0, // no interfaces, fields, methods or attributes
+    ///     0xFF, // a stray byte
+    /// ];
+    /// let report = SizeReport::measure(&class).unwrap();
+    /// let sections: Vec<_> = report.sections.iter().map(|s| (s.name, s.bytes)).collect();
+    /// assert_eq!(
+    ///     sections,
+    ///     [
+    ///         ("header", 10),
+    ///         ("constant pool", 7),
+    ///         ("class", 6),
+    ///         ("interfaces", 2),
+    ///         ("fields", 2),
+    ///         ("methods", 2),
+    ///         ("attributes", 2),
+    ///         ("trailing", 1),
+    ///     ]
+    /// );
+    /// let total: usize = report.sections.iter().map(|s| s.bytes).sum();
+    /// assert_eq!(total, class.len());
+    /// ```
+    pub fn measure(buf: &[u8]) -> Result<SizeReport, ParseError> {
+        Self::measure_with(buf, &ParseOptions::default()).map_err(ParseError::from)
+    }
+
+    /// Measures the class file in `buf`, read as far as
+    /// [`BorrowedClassFile::parse_with`] reads it with `options`.
+    pub fn measure_with<'a>(
+        buf: &'a [u8],
+        options: &ParseOptions,
+    ) -> Result<SizeReport, nom::Err<ErrorAt<&'a [u8]>>> {
+        let (rest, class_file) = BorrowedClassFile::parse_with(buf, options)?;
+        let constant_pool = &class_file.constant_pool;
+        let mut report = SizeReport {
+            file_size: buf.len(),
+            sections: Vec::new(),
+            constants: BTreeMap::new(),
+            attributes: BTreeMap::new(),
+            methods: Vec::new(),
+            utf8: Vec::new(),
+        };
+
+        let mut pool_bytes = 0;
+        for (i, constant) in constant_pool.iter().enumerate() {
+            let (kind, bytes) = match constant {
+                BorrowedConstant::Other(ConstantPool::Unusable) => continue,
+                BorrowedConstant::UTF8(value) => {
+                    let bytes = 3 + mutf8::encoded_len(value);
+                    report.utf8.push(Utf8Size {
+                        index: i as u16 + 1,
+                        value: value.to_string(),
+                        bytes,
+                    });
+                    (utf8_kind(), bytes)
+                }
+                BorrowedConstant::Other(constant) => (constant.kind_name(), constant.encoded_len()),
+            };
+            report.constants.entry(kind).or_default().add(bytes);
+            pool_bytes += bytes;
+        }
+
+        let mut members = |members: &[BorrowedMember], methods: bool| {
+            let mut total = 2;
+            for member in members {
+                let bytes = 8 + report.attribute_table(&member.attributes, constant_pool);
+                total += bytes;
+                if methods {
+                    let name = member.name(constant_pool).unwrap_or_default();
+                    let descriptor = member.descriptor(constant_pool).unwrap_or_default();
+                    report.methods.push(MethodSize {
+                        name: format!("{}{}", name, descriptor),
+                        bytes,
+                        code_length: member
+                            .code(constant_pool)
+                            .and_then(Result::ok)
+                            .map(|code| code.code.len()),
+                    });
+                }
+            }
+            total
+        };
+        let fields = members(&class_file.fields, false);
+        let methods = members(&class_file.methods, true);
+        let attributes = 2 + report.attribute_table(&class_file.attributes, constant_pool);
+
+        for (name, bytes) in [
+            ("header", 10),
+            ("constant pool", pool_bytes),
+            ("class", 6),
+            ("interfaces", 2 + 2 * class_file.interfaces.len()),
+            ("fields", fields),
+            ("methods", methods),
+            ("attributes", attributes),
+            ("trailing", rest.len()),
+        ] {
+            if bytes > 0 {
+                report.sections.push(Section { name, bytes });
+            }
+        }
+        Ok(report)
+    }
+
+    /// The methods, largest first.
+    pub fn largest_methods(&self) -> Vec<&MethodSize> {
+        let mut methods: Vec<&MethodSize> = self.methods.iter().collect();
+        ordering::rank(&mut methods, |method| method.bytes, |method| &method.name);
+        methods
+    }
+
+    /// The UTF8 constants, largest first.
+    pub fn largest_utf8(&self) -> Vec<&Utf8Size> {
+        let mut utf8: Vec<&Utf8Size> = self.utf8.iter().collect();
+        ordering::rank(&mut utf8, |utf8| utf8.bytes, |utf8| &utf8.index);
+        utf8
+    }
+
+    /// Counts the attributes of a table, and those in their `Code`, and
+    /// returns the bytes of the table less its count.
+    fn attribute_table(
+        &mut self,
+        attributes: &[AttributeInfo],
+        constant_pool: &[BorrowedConstant],
+    ) -> usize {
+        let mut total = 0;
+        for attribute in attributes {
+            let name = BorrowedConstant::utf8(constant_pool, attribute.attribute_name_index)
+                .unwrap_or("?");
+            let bytes = 6 + attribute.info.len();
+            total += bytes;
+            let nested = match name {
+                "Code" => BorrowedCode::parse(attribute.info).map_or(0, |(_, code)| {
+                    self.attribute_table(&code.attributes, constant_pool)
+                }),
+                _ => 0,
+            };
+            self.attributes
+                .entry(name.to_string())
+                .or_default()
+                .add(bytes - nested);
+        }
+        total
+    }
+}
+
+fn utf8_kind() -> &'static str {
+    spec::constant_spec(CONSTANT_UTF8).map_or("Utf8", |spec| spec.name)
+}
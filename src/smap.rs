@@ -0,0 +1,300 @@
+//! The Source Map (SMAP) of JSR-45, which a `SourceDebugExtension` holds
+//! to map the lines of a class back to the sources it was generated from:
+//! the JSP a servlet was compiled from, or the files kotlinc inlined
+//! functions from.
+//!
+//! An SMAP names the output file and the default stratum, then gives the
+//! files and lines of each stratum, a view of the sources for one
+//! language:
+//!
+//! ```text
+//! SMAP
+//! TypesJVM.kt
+//! Kotlin
+//! *S Kotlin
+//! *F
+//! + 1 TypesJVM.kt
+//! kotlin/reflect/TypesJVMKt
+//! + 2 _Collections.kt
+//! kotlin/collections/CollectionsKt___CollectionsKt
+//! *L
+//! 1#1,230:1
+//! 1620#2,3:233
+//! *S KotlinDebug
+//! *F
+//! + 1 TypesJVM.kt
+//! kotlin/reflect/TypesJVMKt
+//! *L
+//! 71#1:233,3
+//! *E
+//! ```
+//!
+//! A line entry `1620#2,3:233` maps input lines 1620 to 1622 of file 2 to
+//! output lines 233 to 235; `71#1:233,3` maps input line 71 to the three
+//! output lines from 233. A file id left out is that of the entry before.
+//! Vendor sections and those of later versions of the format are skipped.
+
+use crate::prelude::*;
+use core::{error::Error, fmt};
+
+/// A parsed SMAP.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Smap {
+    /// The name of the file the SMAP maps the lines of, usually the
+    /// source file of the class.
+    pub output_file: String,
+    /// The stratum a debugger shows when asked for none in particular.
+    pub default_stratum: String,
+    /// In the order of the SMAP.
+    pub strata: Vec<Stratum>,
+}
+
+/// The files and lines of one stratum.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Stratum {
+    pub id: String,
+    pub files: Vec<SmapFile>,
+    /// In the order of the SMAP.
+    pub lines: Vec<LineInfo>,
+}
+
+/// An input source of a stratum.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SmapFile {
+    /// What line entries refer to the file by.
+    pub id: u32,
+    /// The file's name, e.g. `TypesJVM.kt`.
+    pub name: String,
+    /// The file's path, when given; kotlinc gives the internal name of the
+    /// class compiled from the file, e.g. `kotlin/reflect/TypesJVMKt`.
+    pub path: Option<String>,
+}
+
+/// A range of input lines and the output lines they map to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LineInfo {
+    pub input_start: u32,
+    pub file_id: u32,
+    /// How many input lines, from `input_start`, the entry maps.
+    pub repeat_count: u32,
+    pub output_start: u32,
+    /// How many output lines each input line maps to.
+    pub output_increment: u32,
+}
+
+impl LineInfo {
+    /// The input line `output_line` maps back to, if the entry covers it.
+    pub fn map(&self, output_line: u32) -> Option<u32> {
+        let offset = output_line.checked_sub(self.output_start)?;
+        let covered = u64::from(self.repeat_count) * u64::from(self.output_increment.max(1));
+        if u64::from(offset) >= covered {
+            return None;
+        }
+        match self.output_increment {
+            0 => Some(self.input_start),
+            increment => self.input_start.checked_add(offset / increment),
+        }
+    }
+}
+
+impl Stratum {
+    /// The file with `id`.
+    pub fn file(&self, id: u32) -> Option<&SmapFile> {
+        self.files.iter().find(|file| file.id == id)
+    }
+
+    /// The file and input line `output_line` maps back to, by the first
+    /// line entry covering it.
+    pub fn map_line(&self, output_line: u32) -> Option<(&SmapFile, u32)> {
+        self.lines.iter().find_map(|line| {
+            let input_line = line.map(output_line)?;
+            Some((self.file(line.file_id)?, input_line))
+        })
+    }
+}
+
+impl Smap {
+    /// Parses the text of an SMAP.
+    pub fn parse(text: &str) -> Result<Self, SmapError> {
+        let mut lines = text
+            .lines()
+            .map(|line| line.trim_end_matches('\r'))
+            .enumerate()
+            .map(|(number, line)| (number + 1, line));
+        let mut header = |what: &str| match lines.next() {
+            Some((_, line)) => Ok(line.to_string()),
+            None => Err(SmapError::new(
+                text.lines().count() + 1,
+                format!("expected {}", what),
+            )),
+        };
+        if header("`SMAP`")? != "SMAP" {
+            return Err(SmapError::new(1, "expected `SMAP`"));
+        }
+        let output_file = header("the output file name")?;
+        let default_stratum = header("the default stratum")?;
+
+        let mut smap = Smap {
+            output_file,
+            default_stratum,
+            strata: Vec::new(),
+        };
+        let mut section = ' ';
+        let mut file_id = 0;
+        while let Some((number, line)) = lines.next() {
+            if let Some(marker) = line.strip_prefix('*') {
+                let mut chars = marker.chars();
+                section = chars.next().unwrap_or(' ');
+                match section {
+                    'S' => {
+                        let id = chars.as_str().trim();
+                        if id.is_empty() {
+                            return Err(SmapError::new(number, "expected a stratum id"));
+                        }
+                        smap.strata.push(Stratum {
+                            id: id.to_string(),
+                            files: Vec::new(),
+                            lines: Vec::new(),
+                        });
+                        file_id = 0;
+                    }
+                    'O' | 'C' => {
+                        return Err(SmapError::new(number, "embedded SMAPs aren't supported"))
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+            let stratum = match (section, smap.strata.last_mut()) {
+                ('F' | 'L', Some(stratum)) => stratum,
+                ('F' | 'L', None) => {
+                    return Err(SmapError::new(number, "expected `*S` before this section"))
+                }
+                _ => continue,
+            };
+            if section == 'F' {
+                let (with_path, entry) = match line.strip_prefix('+') {
+                    Some(entry) => (true, entry.trim_start()),
+                    None => (false, line),
+                };
+                let (id, name) = entry
+                    .split_once(' ')
+                    .and_then(|(id, name)| Some((id.parse().ok()?, name)))
+                    .ok_or_else(|| SmapError::new(number, "expected `id name`"))?;
+                let path = if with_path {
+                    let (_, path) = lines
+                        .next()
+                        .ok_or_else(|| SmapError::new(number + 1, "expected a path"))?;
+                    Some(path.to_string())
+                } else {
+                    None
+                };
+                stratum.files.push(SmapFile {
+                    id,
+                    name: name.to_string(),
+                    path,
+                });
+            } else if !line.trim().is_empty() {
+                let line_info = parse_line_info(line, file_id).ok_or_else(|| {
+                    SmapError::new(
+                        number,
+                        "expected `input[#file][,repeat]:output[,increment]`",
+                    )
+                })?;
+                file_id = line_info.file_id;
+                stratum.lines.push(line_info);
+            }
+        }
+        Ok(smap)
+    }
+
+    /// The stratum with `id`.
+    pub fn stratum(&self, id: &str) -> Option<&Stratum> {
+        self.strata.iter().find(|stratum| stratum.id == id)
+    }
+
+    /// The file and input line `output_line` maps back to in `stratum`.
+    /// For a call kotlinc inlined, the `Kotlin` stratum gives the line of
+    /// the inlined function and `KotlinDebug` that of the call:
+    ///
+    /// ```
+    /// use jvmb::smap::Smap;
+    ///
+    /// let smap = Smap::parse(
+    ///     "SMAP\nTypesJVM.kt\nKotlin\n\
+    ///      *S Kotlin\n*F\n+ 1 TypesJVM.kt\nkotlin/reflect/TypesJVMKt\n\
+    ///      + 3 _Collections.kt\nkotlin/collections/CollectionsKt___CollectionsKt\n\
+    ///      *L\n1#1,230:1\n1620#3,3:233\n1620#3,3:237\n\
+    ///      *S KotlinDebug\n*F\n+ 1 TypesJVM.kt\nkotlin/reflect/TypesJVMKt\n\
+    ///      *L\n69#1:233,3\n71#1:237,3\n*E\n",
+    /// )
+    /// .unwrap();
+    /// let (file, line) = smap.map_line("Kotlin", 239).unwrap();
+    /// assert_eq!((file.name.as_str(), line), ("_Collections.kt", 1622));
+    /// let (file, line) = smap.map_line("KotlinDebug", 239).unwrap();
+    /// assert_eq!((file.name.as_str(), line), ("TypesJVM.kt", 71));
+    /// assert_eq!(smap.map_line("KotlinDebug", 240), None);
+    /// ```
+    pub fn map_line(&self, stratum: &str, output_line: u32) -> Option<(&SmapFile, u32)> {
+        self.stratum(stratum)?.map_line(output_line)
+    }
+}
+
+/// `InputStartLine[#LineFileID][,RepeatCount]:OutputStartLine[,OutputLineIncrement]`,
+/// with the file id `file_id` when it's left out.
+fn parse_line_info(line: &str, file_id: u32) -> Option<LineInfo> {
+    let (input, output) = line.trim().split_once(':')?;
+    let (input, repeat_count) = match input.split_once(',') {
+        Some((input, repeat_count)) => (input, repeat_count.parse().ok()?),
+        None => (input, 1),
+    };
+    let (input_start, file_id) = match input.split_once('#') {
+        Some((input_start, file_id)) => (input_start, file_id.parse().ok()?),
+        None => (input, file_id),
+    };
+    let (output_start, output_increment) = match output.split_once(',') {
+        Some((output_start, increment)) => (output_start, increment.parse().ok()?),
+        None => (output, 1),
+    };
+    Some(LineInfo {
+        input_start: input_start.parse().ok()?,
+        file_id,
+        repeat_count,
+        output_start: output_start.parse().ok()?,
+        output_increment,
+    })
+}
+
+/// A line of an SMAP which isn't in the format.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SmapError {
+    /// Counting from 1.
+    pub line: usize,
+    pub message: String,
+}
+
+impl SmapError {
+    fn new(line: usize, message: impl Into<String>) -> Self {
+        SmapError {
+            line,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for SmapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl Error for SmapError {}
+
+/// A class's `SourceDebugExtension`, as text and as an SMAP.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceDebugExtension {
+    /// The attribute's bytes decoded as modified UTF-8, with what isn't
+    /// replaced by U+FFFD.
+    pub text: String,
+    pub smap: Result<Smap, SmapError>,
+}
@@ -0,0 +1,230 @@
+//! Checks of the `SourceFile` and `SourceDebugExtension` attributes for what
+//! leaks the build environment or trips up tools reading them, and, with
+//! the `write` feature, scrubbing it.
+//!
+//! Compilers write `SourceFile` as the bare name of the source, `Foo.java`;
+//! some build tools and obfuscators write the path they compiled from,
+//! absolute or relative, instead. The `SourceDebugExtension` of JSR-45
+//! (SMAP) is text of lines ending in LF, which a tool on Windows may have
+//! written with CRLF or in another charset than UTF-8.
+
+use std::fmt;
+
+#[cfg(feature = "write")]
+use crate::remap::PoolFull;
+use crate::{
+    attribute::Attribute, classfile::ClassFile, constantpool::ConstantPool, mutf8, regex::Regex,
+};
+
+/// What a `SourceFile` value is expected to match by default: a simple
+/// name with an extension, as `javac`, `kotlinc` and `scalac` write it.
+pub const DEFAULT_SOURCE_FILE_PATTERN: &str = r"^[\w$-]+\.\w+$";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SourceInfoIssue {
+    /// A `SourceFile` value with a directory or drive in it.
+    SourceFilePath(String),
+    /// A `SourceFile` value not matching the expected pattern.
+    UnexpectedSourceFile { value: String, pattern: String },
+    /// A `SourceDebugExtension` with lines ending in CRLF, alone or mixed
+    /// with those ending in LF.
+    DebugExtensionLineEndings { crlf: usize, lf: usize },
+    /// A `SourceDebugExtension` which isn't valid modified UTF-8.
+    DebugExtensionNotUtf8,
+}
+
+impl fmt::Display for SourceInfoIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SourceInfoIssue::SourceFilePath(value) => {
+                write!(f, "SourceFile {:?} is a path", value)
+            }
+            SourceInfoIssue::UnexpectedSourceFile { value, pattern } => {
+                write!(f, "SourceFile {:?} doesn't match {}", value, pattern)
+            }
+            SourceInfoIssue::DebugExtensionLineEndings { crlf, lf: 0 } => {
+                write!(
+                    f,
+                    "SourceDebugExtension has CRLF line endings ({} lines)",
+                    crlf
+                )
+            }
+            SourceInfoIssue::DebugExtensionLineEndings { crlf, lf } => write!(
+                f,
+                "SourceDebugExtension has mixed line endings ({} CRLF, {} LF)",
+                crlf, lf
+            ),
+            SourceInfoIssue::DebugExtensionNotUtf8 => {
+                f.write_str("SourceDebugExtension isn't valid modified UTF-8")
+            }
+        }
+    }
+}
+
+/// Finds what's amiss with the `SourceFile` and `SourceDebugExtension`
+/// attributes of `class_file`, with `SourceFile` values expected to match
+/// `pattern`. A path is reported as such rather than as a mismatch.
+pub fn check_source_info(class_file: &ClassFile, pattern: &Regex) -> Vec<SourceInfoIssue> {
+    let mut issues = Vec::new();
+    for attribute in &class_file.attributes {
+        match attribute {
+            Attribute::SourceFile(source_file) => {
+                let value =
+                    ConstantPool::utf8(&class_file.constant_pool, source_file.sourcefile_index)
+                        .unwrap_or_default();
+                if is_path(value) {
+                    issues.push(SourceInfoIssue::SourceFilePath(value.to_string()));
+                } else if !pattern.is_match(value) {
+                    issues.push(SourceInfoIssue::UnexpectedSourceFile {
+                        value: value.to_string(),
+                        pattern: pattern.as_str().to_string(),
+                    });
+                }
+            }
+            Attribute::SourceDebugExtension(debug_extension) => {
+                if mutf8::decode(debug_extension).is_none() {
+                    issues.push(SourceInfoIssue::DebugExtensionNotUtf8);
+                }
+                let (crlf, lf) = line_endings(debug_extension);
+                if crlf > 0 {
+                    issues.push(SourceInfoIssue::DebugExtensionLineEndings { crlf, lf });
+                }
+            }
+            _ => {}
+        }
+    }
+    issues
+}
+
+/// What [`scrub`] does besides reducing `SourceFile` paths to their last
+/// component.
+#[cfg(feature = "write")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ScrubOptions {
+    /// Drop `SourceDebugExtension` attributes rather than normalizing their
+    /// line endings to LF.
+    pub drop_debug_extension: bool,
+}
+
+/// A change [`scrub`] made to a class.
+#[cfg(feature = "write")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScrubChange {
+    SourceFile { from: String, to: String },
+    DebugExtensionDropped { length: usize },
+    DebugExtensionLineEndings { lines: usize },
+}
+
+#[cfg(feature = "write")]
+impl fmt::Display for ScrubChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScrubChange::SourceFile { from, to } => {
+                write!(f, "SourceFile {:?} -> {:?}", from, to)
+            }
+            ScrubChange::DebugExtensionDropped { length } => {
+                write!(f, "SourceDebugExtension dropped ({} bytes)", length)
+            }
+            ScrubChange::DebugExtensionLineEndings { lines } => write!(
+                f,
+                "SourceDebugExtension line endings normalized ({} lines)",
+                lines
+            ),
+        }
+    }
+}
+
+/// Rewrites every `SourceFile` of `class_file` which is a path to the name
+/// of the file, and drops or normalizes its `SourceDebugExtension` as
+/// `options` say, returning what changed.
+///
+/// As [`remap`](crate::remap::remap) does, the UTF8 entry of a `SourceFile`
+/// is never changed in place, since a string literal may share it: the
+/// attribute is pointed at an entry holding the name instead, found in the
+/// pool or added to its end.
+#[cfg(feature = "write")]
+pub fn scrub(
+    class_file: &mut ClassFile,
+    options: ScrubOptions,
+) -> Result<Vec<ScrubChange>, PoolFull> {
+    let mut changes = Vec::new();
+    let original_len = class_file.constant_pool.len();
+    let constant_pool = &mut class_file.constant_pool;
+    class_file
+        .attributes
+        .retain_mut(|attribute| match attribute {
+            Attribute::SourceFile(source_file) => {
+                let value = ConstantPool::utf8(constant_pool, source_file.sourcefile_index)
+                    .unwrap_or_default();
+                if is_path(value) {
+                    let name = file_name(value).to_string();
+                    changes.push(ScrubChange::SourceFile {
+                        from: value.to_string(),
+                        to: name.clone(),
+                    });
+                    source_file.sourcefile_index = ConstantPool::intern_utf8(constant_pool, &name);
+                }
+                true
+            }
+            Attribute::SourceDebugExtension(debug_extension) => {
+                if options.drop_debug_extension {
+                    changes.push(ScrubChange::DebugExtensionDropped {
+                        length: debug_extension.len(),
+                    });
+                    return false;
+                }
+                let (crlf, _) = line_endings(debug_extension);
+                if crlf > 0 {
+                    let mut normalized = Vec::with_capacity(debug_extension.len() - crlf);
+                    for (i, &byte) in debug_extension.iter().enumerate() {
+                        if byte != b'\r' || debug_extension.get(i + 1) != Some(&b'\n') {
+                            normalized.push(byte);
+                        }
+                    }
+                    *debug_extension = normalized;
+                    changes.push(ScrubChange::DebugExtensionLineEndings { lines: crlf });
+                }
+                true
+            }
+            _ => true,
+        });
+    let added = class_file.constant_pool.len() - original_len;
+    class_file.constant_pool_count = u16::try_from(added)
+        .ok()
+        .and_then(|added| class_file.constant_pool_count.checked_add(added))
+        .ok_or(PoolFull)?;
+    Ok(changes)
+}
+
+/// Whether a `SourceFile` value names a directory or drive as well as a
+/// file.
+fn is_path(value: &str) -> bool {
+    value.contains(['/', '\\']) || value.as_bytes().get(1) == Some(&b':')
+}
+
+/// The last component of the path `value`, with Windows' separators and
+/// drives as well as `/`.
+#[cfg(feature = "write")]
+fn file_name(value: &str) -> &str {
+    let value = value.rsplit(['/', '\\']).next().unwrap_or(value);
+    match value.as_bytes().get(1) {
+        Some(b':') => &value[2..],
+        _ => value,
+    }
+}
+
+/// How many lines of `text` end in CRLF, and how many in a bare LF.
+fn line_endings(text: &[u8]) -> (usize, usize) {
+    let mut crlf = 0;
+    let mut lf = 0;
+    for (i, &byte) in text.iter().enumerate() {
+        if byte == b'\n' {
+            if i > 0 && text[i - 1] == b'\r' {
+                crlf += 1;
+            } else {
+                lf += 1;
+            }
+        }
+    }
+    (crlf, lf)
+}
@@ -0,0 +1,571 @@
+//! Built-in reference data about constant pool and attribute kinds, taken
+//! from chapter 4 of the Java Virtual Machine Specification.
+
+use crate::prelude::*;
+use core::fmt;
+
+/// The structures an attribute may legally appear in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttributeLocation {
+    ClassFile,
+    FieldInfo,
+    MethodInfo,
+    Code,
+    RecordComponent,
+}
+
+impl AttributeLocation {
+    pub fn name(self) -> &'static str {
+        match self {
+            AttributeLocation::ClassFile => "ClassFile",
+            AttributeLocation::FieldInfo => "field_info",
+            AttributeLocation::MethodInfo => "method_info",
+            AttributeLocation::Code => "Code",
+            AttributeLocation::RecordComponent => "record_component_info",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct AttributeSpec {
+    pub name: &'static str,
+    pub section: &'static str,
+    /// First class file major version the attribute is defined for.
+    pub since_major: u16,
+    pub locations: &'static [AttributeLocation],
+    /// Whether the attribute may appear more than once in the same place.
+    /// The JVMS allows at most one of most.
+    pub repeatable: bool,
+    pub description: &'static str,
+}
+
+#[derive(Debug)]
+pub struct ConstantSpec {
+    pub tag: u8,
+    pub name: &'static str,
+    pub section: &'static str,
+    /// First class file major version the constant kind is defined for.
+    pub since_major: u16,
+    pub description: &'static str,
+}
+
+/// The newest class file major version the tables here describe, that of
+/// Java 23. Newer classes may use constants and attributes jvmb doesn't
+/// know.
+pub const LATEST_MAJOR_VERSION: u16 = 67;
+
+/// The oldest class file major version there is, that of Java 1.1 (and
+/// of 1.0.2 before it).
+pub const OLDEST_MAJOR_VERSION: u16 = 45;
+
+/// The Java release a class file major version belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum JavaVersion {
+    Java1_1,
+    Java1_2,
+    Java1_3,
+    Java1_4,
+    Java5,
+    Java6,
+    Java7,
+    Java8,
+    Java9,
+    Java10,
+    Java11,
+    Java12,
+    Java13,
+    Java14,
+    Java15,
+    Java16,
+    Java17,
+    Java18,
+    Java19,
+    Java20,
+    Java21,
+    Java22,
+    Java23,
+    /// A major version before [`OLDEST_MAJOR_VERSION`] or past
+    /// [`LATEST_MAJOR_VERSION`].
+    Unknown(u16),
+}
+
+/// The releases in order, from major version 45 on.
+const RELEASES: [JavaVersion; 23] = [
+    JavaVersion::Java1_1,
+    JavaVersion::Java1_2,
+    JavaVersion::Java1_3,
+    JavaVersion::Java1_4,
+    JavaVersion::Java5,
+    JavaVersion::Java6,
+    JavaVersion::Java7,
+    JavaVersion::Java8,
+    JavaVersion::Java9,
+    JavaVersion::Java10,
+    JavaVersion::Java11,
+    JavaVersion::Java12,
+    JavaVersion::Java13,
+    JavaVersion::Java14,
+    JavaVersion::Java15,
+    JavaVersion::Java16,
+    JavaVersion::Java17,
+    JavaVersion::Java18,
+    JavaVersion::Java19,
+    JavaVersion::Java20,
+    JavaVersion::Java21,
+    JavaVersion::Java22,
+    JavaVersion::Java23,
+];
+
+impl JavaVersion {
+    pub fn from_major(major_version: u16) -> Self {
+        major_version
+            .checked_sub(OLDEST_MAJOR_VERSION)
+            .and_then(|i| RELEASES.get(i as usize))
+            .copied()
+            .unwrap_or(JavaVersion::Unknown(major_version))
+    }
+
+    /// The class file major version of the release.
+    pub fn major_version(self) -> u16 {
+        match self {
+            JavaVersion::Unknown(major_version) => major_version,
+            known => {
+                let i = RELEASES.iter().position(|&release| release == known);
+                OLDEST_MAJOR_VERSION + i.unwrap_or_default() as u16
+            }
+        }
+    }
+}
+
+/// Renders the release as it's usually named, e.g. `Java 1.4` or `Java 17`.
+impl fmt::Display for JavaVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JavaVersion::Unknown(_) => f.write_str("unknown Java release"),
+            JavaVersion::Java1_1
+            | JavaVersion::Java1_2
+            | JavaVersion::Java1_3
+            | JavaVersion::Java1_4 => {
+                write!(f, "Java 1.{}", self.major_version() - 44)
+            }
+            _ => write!(f, "Java {}", self.major_version() - 44),
+        }
+    }
+}
+
+use AttributeLocation::*;
+
+const MEMBERS: &[AttributeLocation] = &[ClassFile, FieldInfo, MethodInfo];
+const ANNOTATABLE: &[AttributeLocation] = &[ClassFile, FieldInfo, MethodInfo, RecordComponent];
+
+pub static ATTRIBUTES: &[AttributeSpec] = &[
+    AttributeSpec {
+        name: "ConstantValue",
+        section: "4.7.2",
+        since_major: 45,
+        locations: &[FieldInfo],
+        repeatable: false,
+        description: "Value of a constant field, as an index of a loadable constant.",
+    },
+    AttributeSpec {
+        name: "Code",
+        section: "4.7.3",
+        since_major: 45,
+        locations: &[MethodInfo],
+        repeatable: false,
+        description: "Bytecode of a method along with its exception table and limits.",
+    },
+    AttributeSpec {
+        name: "StackMapTable",
+        section: "4.7.4",
+        since_major: 50,
+        locations: &[Code],
+        repeatable: false,
+        description: "Verification type frames used by the type-checking verifier.",
+    },
+    AttributeSpec {
+        name: "Exceptions",
+        section: "4.7.5",
+        since_major: 45,
+        locations: &[MethodInfo],
+        repeatable: false,
+        description: "Checked exceptions a method is declared to throw.",
+    },
+    AttributeSpec {
+        name: "InnerClasses",
+        section: "4.7.6",
+        since_major: 45,
+        locations: &[ClassFile],
+        repeatable: false,
+        description: "Nested classes referenced by this class and their original flags.",
+    },
+    AttributeSpec {
+        name: "EnclosingMethod",
+        section: "4.7.7",
+        since_major: 49,
+        locations: &[ClassFile],
+        repeatable: false,
+        description: "Class and method enclosing a local or anonymous class.",
+    },
+    AttributeSpec {
+        name: "Synthetic",
+        section: "4.7.8",
+        since_major: 45,
+        locations: MEMBERS,
+        repeatable: true,
+        description: "Marks an element as compiler-generated; superseded by ACC_SYNTHETIC.",
+    },
+    AttributeSpec {
+        name: "Signature",
+        section: "4.7.9",
+        since_major: 49,
+        locations: ANNOTATABLE,
+        repeatable: false,
+        description: "Generic signature of a class, member or record component.",
+    },
+    AttributeSpec {
+        name: "SourceFile",
+        section: "4.7.10",
+        since_major: 45,
+        locations: &[ClassFile],
+        repeatable: false,
+        description: "Name of the source file the class was compiled from.",
+    },
+    AttributeSpec {
+        name: "SourceDebugExtension",
+        section: "4.7.11",
+        since_major: 49,
+        locations: &[ClassFile],
+        repeatable: false,
+        description: "Free-form debugging information, usually a JSR-45 SMAP.",
+    },
+    AttributeSpec {
+        name: "LineNumberTable",
+        section: "4.7.12",
+        since_major: 45,
+        locations: &[Code],
+        repeatable: true,
+        description: "Mapping from bytecode offsets to source line numbers.",
+    },
+    AttributeSpec {
+        name: "LocalVariableTable",
+        section: "4.7.13",
+        since_major: 45,
+        locations: &[Code],
+        repeatable: true,
+        description: "Names and descriptors of local variables and their live ranges.",
+    },
+    AttributeSpec {
+        name: "LocalVariableTypeTable",
+        section: "4.7.14",
+        since_major: 49,
+        locations: &[Code],
+        repeatable: true,
+        description: "Generic signatures of local variables whose type uses generics.",
+    },
+    AttributeSpec {
+        name: "Deprecated",
+        section: "4.7.15",
+        since_major: 45,
+        locations: MEMBERS,
+        repeatable: true,
+        description: "Marks an element as deprecated.",
+    },
+    AttributeSpec {
+        name: "RuntimeVisibleAnnotations",
+        section: "4.7.16",
+        since_major: 49,
+        locations: ANNOTATABLE,
+        repeatable: false,
+        description: "Annotations retained at runtime and visible through reflection.",
+    },
+    AttributeSpec {
+        name: "RuntimeInvisibleAnnotations",
+        section: "4.7.17",
+        since_major: 49,
+        locations: ANNOTATABLE,
+        repeatable: false,
+        description: "Annotations kept in the class file but not exposed through reflection.",
+    },
+    AttributeSpec {
+        name: "RuntimeVisibleParameterAnnotations",
+        section: "4.7.18",
+        since_major: 49,
+        locations: &[MethodInfo],
+        repeatable: false,
+        description: "Runtime-visible annotations on formal parameters.",
+    },
+    AttributeSpec {
+        name: "RuntimeInvisibleParameterAnnotations",
+        section: "4.7.19",
+        since_major: 49,
+        locations: &[MethodInfo],
+        repeatable: false,
+        description: "Runtime-invisible annotations on formal parameters.",
+    },
+    AttributeSpec {
+        name: "RuntimeVisibleTypeAnnotations",
+        section: "4.7.20",
+        since_major: 52,
+        locations: &[ClassFile, FieldInfo, MethodInfo, Code, RecordComponent],
+        repeatable: false,
+        description: "Runtime-visible annotations on uses of types.",
+    },
+    AttributeSpec {
+        name: "RuntimeInvisibleTypeAnnotations",
+        section: "4.7.21",
+        since_major: 52,
+        locations: &[ClassFile, FieldInfo, MethodInfo, Code, RecordComponent],
+        repeatable: false,
+        description: "Runtime-invisible annotations on uses of types.",
+    },
+    AttributeSpec {
+        name: "AnnotationDefault",
+        section: "4.7.22",
+        since_major: 49,
+        locations: &[MethodInfo],
+        repeatable: false,
+        description: "Default value of an annotation interface element.",
+    },
+    AttributeSpec {
+        name: "BootstrapMethods",
+        section: "4.7.23",
+        since_major: 51,
+        locations: &[ClassFile],
+        repeatable: false,
+        description: "Bootstrap methods used by invokedynamic and dynamic constants.",
+    },
+    AttributeSpec {
+        name: "MethodParameters",
+        section: "4.7.24",
+        since_major: 52,
+        locations: &[MethodInfo],
+        repeatable: false,
+        description: "Names and flags of formal parameters.",
+    },
+    AttributeSpec {
+        name: "Module",
+        section: "4.7.25",
+        since_major: 53,
+        locations: &[ClassFile],
+        repeatable: false,
+        description: "Module declaration: requires, exports, opens, uses and provides.",
+    },
+    AttributeSpec {
+        name: "ModulePackages",
+        section: "4.7.26",
+        since_major: 53,
+        locations: &[ClassFile],
+        repeatable: false,
+        description: "All packages of a module, exported or not.",
+    },
+    AttributeSpec {
+        name: "ModuleMainClass",
+        section: "4.7.27",
+        since_major: 53,
+        locations: &[ClassFile],
+        repeatable: false,
+        description: "Main class of a module.",
+    },
+    AttributeSpec {
+        name: "NestHost",
+        section: "4.7.28",
+        since_major: 55,
+        locations: &[ClassFile],
+        repeatable: false,
+        description: "Host of the nest this class is a member of.",
+    },
+    AttributeSpec {
+        name: "NestMembers",
+        section: "4.7.29",
+        since_major: 55,
+        locations: &[ClassFile],
+        repeatable: false,
+        description: "Classes authorized to claim membership in this class's nest.",
+    },
+    AttributeSpec {
+        name: "Record",
+        section: "4.7.30",
+        since_major: 60,
+        locations: &[ClassFile],
+        repeatable: false,
+        description: "Components of a record class.",
+    },
+    AttributeSpec {
+        name: "PermittedSubclasses",
+        section: "4.7.31",
+        since_major: 61,
+        locations: &[ClassFile],
+        repeatable: false,
+        description: "Classes allowed to directly extend or implement a sealed class.",
+    },
+];
+
+pub static CONSTANTS: &[ConstantSpec] = &[
+    ConstantSpec {
+        tag: 1,
+        name: "Utf8",
+        section: "4.4.7",
+        since_major: 45,
+        description: "Modified UTF-8 string used for names, descriptors and literals.",
+    },
+    ConstantSpec {
+        tag: 3,
+        name: "Integer",
+        section: "4.4.4",
+        since_major: 45,
+        description: "32-bit int constant.",
+    },
+    ConstantSpec {
+        tag: 4,
+        name: "Float",
+        section: "4.4.4",
+        since_major: 45,
+        description: "32-bit IEEE 754 float constant.",
+    },
+    ConstantSpec {
+        tag: 5,
+        name: "Long",
+        section: "4.4.5",
+        since_major: 45,
+        description: "64-bit long constant; takes up two pool slots.",
+    },
+    ConstantSpec {
+        tag: 6,
+        name: "Double",
+        section: "4.4.5",
+        since_major: 45,
+        description: "64-bit IEEE 754 double constant; takes up two pool slots.",
+    },
+    ConstantSpec {
+        tag: 7,
+        name: "Class",
+        section: "4.4.1",
+        since_major: 45,
+        description: "Class, interface or array type, by internal name.",
+    },
+    ConstantSpec {
+        tag: 8,
+        name: "String",
+        section: "4.4.3",
+        since_major: 45,
+        description: "java.lang.String literal.",
+    },
+    ConstantSpec {
+        tag: 9,
+        name: "Fieldref",
+        section: "4.4.2",
+        since_major: 45,
+        description: "Symbolic reference to a field: owner class plus name and type.",
+    },
+    ConstantSpec {
+        tag: 10,
+        name: "Methodref",
+        section: "4.4.2",
+        since_major: 45,
+        description: "Symbolic reference to a class method: owner plus name and type.",
+    },
+    ConstantSpec {
+        tag: 11,
+        name: "InterfaceMethodref",
+        section: "4.4.2",
+        since_major: 45,
+        description: "Symbolic reference to an interface method: owner plus name and type.",
+    },
+    ConstantSpec {
+        tag: 12,
+        name: "NameAndType",
+        section: "4.4.6",
+        since_major: 45,
+        description: "Member name paired with a field or method descriptor.",
+    },
+    ConstantSpec {
+        tag: 15,
+        name: "MethodHandle",
+        section: "4.4.8",
+        since_major: 51,
+        description: "Method handle: reference kind plus a field or method reference.",
+    },
+    ConstantSpec {
+        tag: 16,
+        name: "MethodType",
+        section: "4.4.9",
+        since_major: 51,
+        description: "Method type, given by a method descriptor.",
+    },
+    ConstantSpec {
+        tag: 17,
+        name: "Dynamic",
+        section: "4.4.10",
+        since_major: 55,
+        description: "Dynamically-computed constant produced by a bootstrap method.",
+    },
+    ConstantSpec {
+        tag: 18,
+        name: "InvokeDynamic",
+        section: "4.4.10",
+        since_major: 51,
+        description: "Call site of an invokedynamic instruction, linked by a bootstrap method.",
+    },
+    ConstantSpec {
+        tag: 19,
+        name: "Module",
+        section: "4.4.11",
+        since_major: 53,
+        description: "Module name, only allowed in module-info classes.",
+    },
+    ConstantSpec {
+        tag: 20,
+        name: "Package",
+        section: "4.4.12",
+        since_major: 53,
+        description: "Package name, only allowed in module-info classes.",
+    },
+];
+
+pub fn attribute_spec(name: &str) -> Option<&'static AttributeSpec> {
+    ATTRIBUTES.iter().find(|spec| spec.name == name)
+}
+
+pub fn constant_spec(tag: u8) -> Option<&'static ConstantSpec> {
+    CONSTANTS.iter().find(|spec| spec.tag == tag)
+}
+
+/// Looks up either an attribute or a constant kind by name, ignoring case
+/// (`explain stackmaptable`, `explain methodref`).
+pub fn explain(name: &str) -> Option<String> {
+    if let Some(spec) = ATTRIBUTES
+        .iter()
+        .find(|spec| spec.name.eq_ignore_ascii_case(name))
+    {
+        let locations = spec
+            .locations
+            .iter()
+            .map(|location| location.name())
+            .collect::<Vec<_>>()
+            .join(", ");
+        return Some(format!(
+            "{} attribute (JVMS §{}, since class file version {})\n  {}\n  Allowed in: {}{}",
+            spec.name,
+            spec.section,
+            spec.since_major,
+            spec.description,
+            locations,
+            if spec.repeatable {
+                ""
+            } else {
+                ", at most once"
+            }
+        ));
+    }
+    let name = name.strip_prefix("CONSTANT_").unwrap_or(name);
+    CONSTANTS
+        .iter()
+        .find(|spec| spec.name.eq_ignore_ascii_case(name))
+        .map(|spec| {
+            format!(
+                "CONSTANT_{} (tag {}, JVMS §{}, since class file version {})\n  {}",
+                spec.name, spec.tag, spec.section, spec.since_major, spec.description
+            )
+        })
+}
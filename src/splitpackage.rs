@@ -0,0 +1,63 @@
+//! Packages whose classes are spread over more than one jar. The module
+//! system refuses to load these, and on the class path they make behaviour
+//! depend on the order of the jars.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::jar::JarFile;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SplitPackage {
+    /// The package name, dot separated.
+    pub package: String,
+    /// Every jar with classes in the package, in the order they were added,
+    /// along with how many classes each has there.
+    pub jars: Vec<(String, usize)>,
+}
+
+/// The packages found in a set of jars, and which jars have classes in each.
+#[derive(Debug, Default)]
+pub struct PackageIndex {
+    jar_names: Vec<String>,
+    /// package -> jar (by position in `jar_names`) -> class names
+    packages: BTreeMap<String, BTreeMap<usize, BTreeSet<String>>>,
+}
+
+impl PackageIndex {
+    pub fn new() -> Self {
+        PackageIndex::default()
+    }
+
+    /// Records the classes in `jar` under `name`. A class with versions for
+    /// several releases of a multi-release jar is counted once, and classes
+    /// in the unnamed package are ignored since no module can contain them.
+    pub fn add_jar(&mut self, name: &str, jar: &JarFile) {
+        let position = self.jar_names.len();
+        self.jar_names.push(name.to_string());
+        for class_name in jar.entries().iter().filter_map(|entry| entry.class_name()) {
+            if let Some((package, _)) = class_name.rsplit_once('/') {
+                self.packages
+                    .entry(package.replace('/', "."))
+                    .or_default()
+                    .entry(position)
+                    .or_default()
+                    .insert(class_name.to_string());
+            }
+        }
+    }
+
+    /// The packages with classes in more than one jar, by name.
+    pub fn split_packages(&self) -> Vec<SplitPackage> {
+        self.packages
+            .iter()
+            .filter(|(_, jars)| jars.len() > 1)
+            .map(|(package, jars)| SplitPackage {
+                package: package.clone(),
+                jars: jars
+                    .iter()
+                    .map(|(&position, classes)| (self.jar_names[position].clone(), classes.len()))
+                    .collect(),
+            })
+            .collect()
+    }
+}
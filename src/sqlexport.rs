@@ -0,0 +1,680 @@
+//! Parsed classes as an SQL script, for loading into SQLite and querying ad
+//! hoc, e.g. `sqlite3 classes.db < classes.sql`:
+//!
+//! ```sql
+//! -- methods over 5000 bytes of code calling into java.lang.reflect
+//! SELECT DISTINCT m.class, m.name, m.descriptor
+//! FROM members m JOIN member_refs r
+//!   ON (r.source, r.class, r.method_name, r.method_descriptor)
+//!    = (m.source, m.class, m.name, m.descriptor)
+//! WHERE m.code_length > 5000 AND r.owner LIKE 'java/lang/reflect/%';
+//! ```
+//!
+//! Every row is keyed by the source it came from, the jar, directory or
+//! class file named as an input, and the classes of each source are written
+//! in a transaction of their own which first deletes whatever an earlier
+//! script left of that source. A load which is interrupted keeps the sources
+//! it committed, and loading a script again, or one of some of the inputs,
+//! replaces them rather than adding to them. Rows are inserted in batches of
+//! [`BATCH_ROWS`].
+//!
+//! The tables are created if they don't exist. `schema_version` holds
+//! [`SCHEMA_VERSION`], and `schema` describes every column; both are
+//! generated from [`TABLES`], so they can't drift apart from the tables.
+//! Any change to the tables bumps [`SCHEMA_VERSION`].
+//!
+//! With the `sqlite` feature, [`SqliteExporter`] writes the same rows into a
+//! database itself, through `rusqlite` and an SQLite built in.
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+use crate::{
+    attribute::Attribute,
+    classfile::ClassFile,
+    classindex::{ClassIndex, ClassSummary},
+    constantpool::ConstantPool,
+    instruction,
+    sha256::hex,
+};
+
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// The most rows an `INSERT` statement inserts.
+pub const BATCH_ROWS: usize = 500;
+
+pub struct Table {
+    pub name: &'static str,
+    /// Name, type and description of each column, in order.
+    pub columns: &'static [(&'static str, &'static str, &'static str)],
+    /// Keys and foreign keys.
+    pub constraints: &'static [&'static str],
+    /// The columns of each index.
+    pub indices: &'static [&'static str],
+}
+
+/// The tables, parents before their children.
+pub const TABLES: &[Table] = &[
+    Table {
+        name: "sources",
+        columns: &[
+            (
+                "name",
+                "TEXT NOT NULL",
+                "the jar, directory or class file the classes were read from",
+            ),
+            (
+                "classes",
+                "INTEGER NOT NULL",
+                "the number of classes read from it",
+            ),
+        ],
+        constraints: &["PRIMARY KEY (name)"],
+        indices: &[],
+    },
+    Table {
+        name: "classes",
+        columns: &[
+            (
+                "source",
+                "TEXT NOT NULL",
+                "the source the class was read from",
+            ),
+            (
+                "name",
+                "TEXT NOT NULL",
+                "the internal name, e.g. java/lang/String",
+            ),
+            ("access_flags", "INTEGER NOT NULL", "the access flags"),
+            ("super_class", "TEXT", "the internal name of the superclass"),
+            (
+                "major_version",
+                "INTEGER",
+                "the class file version, if known",
+            ),
+            (
+                "minor_version",
+                "INTEGER",
+                "the class file minor version, if known",
+            ),
+            ("source_file", "TEXT", "the SourceFile attribute"),
+        ],
+        constraints: &[
+            "PRIMARY KEY (source, name)",
+            "FOREIGN KEY (source) REFERENCES sources (name) ON DELETE CASCADE",
+        ],
+        indices: &["name", "super_class"],
+    },
+    Table {
+        name: "members",
+        columns: &[
+            ("source", "TEXT NOT NULL", "the source of the class"),
+            (
+                "class",
+                "TEXT NOT NULL",
+                "the internal name of the declaring class",
+            ),
+            ("kind", "TEXT NOT NULL", "field or method"),
+            ("name", "TEXT NOT NULL", "the name"),
+            ("descriptor", "TEXT NOT NULL", "the descriptor"),
+            ("access_flags", "INTEGER NOT NULL", "the access flags"),
+            (
+                "code_length",
+                "INTEGER",
+                "the bytes of code of a method with a Code attribute, if known",
+            ),
+        ],
+        constraints: &[
+            "PRIMARY KEY (source, class, name, descriptor)",
+            "FOREIGN KEY (source, class) REFERENCES classes (source, name) ON DELETE CASCADE",
+        ],
+        indices: &["name", "code_length"],
+    },
+    Table {
+        name: "member_refs",
+        columns: &[
+            ("source", "TEXT NOT NULL", "the source of the class"),
+            (
+                "class",
+                "TEXT NOT NULL",
+                "the internal name of the class the instruction is in",
+            ),
+            (
+                "method_name",
+                "TEXT NOT NULL",
+                "the name of the method the instruction is in",
+            ),
+            (
+                "method_descriptor",
+                "TEXT NOT NULL",
+                "the descriptor of that method",
+            ),
+            (
+                "offset",
+                "INTEGER NOT NULL",
+                "the offset of the instruction",
+            ),
+            (
+                "instruction",
+                "TEXT NOT NULL",
+                "the mnemonic, e.g. invokevirtual",
+            ),
+            (
+                "owner",
+                "TEXT NOT NULL",
+                "the internal name of the class the member is looked up in",
+            ),
+            ("name", "TEXT NOT NULL", "the name of the member"),
+            (
+                "descriptor",
+                "TEXT NOT NULL",
+                "the descriptor of the member",
+            ),
+        ],
+        constraints: &[
+            "FOREIGN KEY (source, class, method_name, method_descriptor) \
+             REFERENCES members (source, class, name, descriptor) ON DELETE CASCADE",
+        ],
+        indices: &[
+            "owner, name",
+            "source, class, method_name, method_descriptor",
+        ],
+    },
+    Table {
+        name: "string_constants",
+        columns: &[
+            ("source", "TEXT NOT NULL", "the source of the class"),
+            (
+                "class",
+                "TEXT NOT NULL",
+                "the internal name of the class whose constant pool has the string",
+            ),
+            ("value", "TEXT NOT NULL", "the string"),
+        ],
+        constraints: &[
+            "FOREIGN KEY (source, class) REFERENCES classes (source, name) ON DELETE CASCADE",
+        ],
+        indices: &["value", "source, class"],
+    },
+    Table {
+        name: "attributes",
+        columns: &[
+            ("source", "TEXT NOT NULL", "the source of the class"),
+            ("class", "TEXT NOT NULL", "the internal name of the class"),
+            (
+                "member_name",
+                "TEXT",
+                "the name of the field or method the attribute is on, or NULL for the class",
+            ),
+            (
+                "member_descriptor",
+                "TEXT",
+                "the descriptor of that field or method",
+            ),
+            ("name", "TEXT NOT NULL", "the name of the attribute"),
+        ],
+        constraints: &[
+            "FOREIGN KEY (source, class) REFERENCES classes (source, name) ON DELETE CASCADE",
+        ],
+        indices: &["name", "source, class"],
+    },
+];
+
+/// Writes classes as an SQL script, one source at a time.
+pub struct SqlExporter<W: Write> {
+    out: W,
+    source: Option<String>,
+    /// The classes of the current source so far, the first of each name
+    /// counting.
+    classes: HashSet<String>,
+    /// The SQL values of the rows not yet written, per table of [`TABLES`].
+    rows: Vec<Vec<String>>,
+}
+
+impl SqlExporter<BufWriter<File>> {
+    /// Writes the classes and members of `index` to the file at `path`.
+    /// The index keeps no code, constant pool or attributes, so the class
+    /// file versions, `source_file` and `code_length` are NULL and
+    /// `member_refs`, `string_constants` and `attributes` are left empty;
+    /// [`add_class`](SqlExporter::add_class) fills in everything.
+    pub fn export(index: &ClassIndex, path: &Path) -> io::Result<()> {
+        let mut exporter = SqlExporter::new(BufWriter::new(File::create(path)?))?;
+        for (source, classes) in by_source(index) {
+            exporter.begin_source(source)?;
+            for class in classes {
+                exporter.add_summary(&class)?;
+            }
+            exporter.end_source()?;
+        }
+        exporter.finish()?.flush()
+    }
+}
+
+impl<W: Write> SqlExporter<W> {
+    /// Starts the script with the tables, unless they exist, and the schema.
+    pub fn new(mut out: W) -> io::Result<Self> {
+        writeln!(out, "PRAGMA foreign_keys = ON;")?;
+        writeln!(out, "BEGIN;")?;
+        writeln!(
+            out,
+            "CREATE TABLE IF NOT EXISTS schema_version (version INTEGER NOT NULL);"
+        )?;
+        writeln!(out, "DELETE FROM schema_version;")?;
+        writeln!(
+            out,
+            "INSERT INTO schema_version VALUES ({});",
+            SCHEMA_VERSION
+        )?;
+        writeln!(
+            out,
+            "CREATE TABLE IF NOT EXISTS schema (table_name TEXT NOT NULL, \
+             column_name TEXT NOT NULL, type TEXT NOT NULL, description TEXT NOT NULL, \
+             PRIMARY KEY (table_name, column_name));"
+        )?;
+        writeln!(out, "DELETE FROM schema;")?;
+        for table in TABLES {
+            let definitions = table
+                .columns
+                .iter()
+                .map(|(name, sql_type, _)| format!("{} {}", name, sql_type))
+                .chain(table.constraints.iter().map(|c| c.to_string()))
+                .collect::<Vec<_>>()
+                .join(", ");
+            writeln!(
+                out,
+                "CREATE TABLE IF NOT EXISTS {} ({});",
+                table.name, definitions
+            )?;
+            for columns in table.indices {
+                writeln!(
+                    out,
+                    "CREATE INDEX IF NOT EXISTS {}_{} ON {} ({});",
+                    table.name,
+                    columns.replace(", ", "_"),
+                    table.name,
+                    columns
+                )?;
+            }
+            for (name, sql_type, description) in table.columns {
+                writeln!(
+                    out,
+                    "INSERT INTO schema VALUES ({}, {}, {}, {});",
+                    text(table.name),
+                    text(name),
+                    text(sql_type),
+                    text(description)
+                )?;
+            }
+        }
+        writeln!(out, "COMMIT;")?;
+        Ok(SqlExporter {
+            out,
+            source: None,
+            classes: HashSet::new(),
+            rows: vec![Vec::new(); TABLES.len()],
+        })
+    }
+
+    /// Starts the transaction of the classes read from `source`, replacing
+    /// those of any earlier script, and ends that of the previous source.
+    pub fn begin_source(&mut self, source: &str) -> io::Result<()> {
+        self.end_source()?;
+        writeln!(self.out, "BEGIN;")?;
+        writeln!(
+            self.out,
+            "DELETE FROM sources WHERE name = {};",
+            text(source)
+        )?;
+        writeln!(
+            self.out,
+            "INSERT INTO sources VALUES ({}, 0);",
+            text(source)
+        )?;
+        self.source = Some(source.to_string());
+        Ok(())
+    }
+
+    /// Writes the rows still batched and commits the current source.
+    pub fn end_source(&mut self) -> io::Result<()> {
+        let source = match self.source.take() {
+            Some(source) => source,
+            None => return Ok(()),
+        };
+        self.flush_rows()?;
+        writeln!(
+            self.out,
+            "UPDATE sources SET classes = {} WHERE name = {};",
+            self.classes.len(),
+            text(&source)
+        )?;
+        writeln!(self.out, "COMMIT;")?;
+        self.classes.clear();
+        Ok(())
+    }
+
+    /// Ends the script, returning the writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.end_source()?;
+        Ok(self.out)
+    }
+
+    /// Adds a class of the current source. A class of a name the source
+    /// already had is skipped, as it would be on a class path.
+    ///
+    /// # Panics
+    ///
+    /// Without a current source.
+    pub fn add_class(&mut self, class_file: &ClassFile) -> io::Result<()> {
+        let constant_pool = &class_file.constant_pool;
+        let utf8 = |index| ConstantPool::utf8(constant_pool, index).unwrap_or_default();
+        let name =
+            ConstantPool::class_name(constant_pool, class_file.this_class).unwrap_or_default();
+        if !self.classes.insert(name.to_string()) {
+            return Ok(());
+        }
+        let source = text(self.source.as_deref().expect("a source to add to"));
+        let class = text(name);
+        let source_file = class_file
+            .attributes
+            .iter()
+            .find_map(|attribute| match attribute {
+                Attribute::SourceFile(source_file) => Some(utf8(source_file.sourcefile_index)),
+                _ => None,
+            });
+        self.push(
+            "classes",
+            [
+                source.clone(),
+                class.clone(),
+                class_file.access_flags.bits().to_string(),
+                optional_text(ConstantPool::class_name(
+                    constant_pool,
+                    class_file.super_class,
+                )),
+                class_file.major_version.to_string(),
+                class_file.minor_version.to_string(),
+                optional_text(source_file),
+            ],
+        )?;
+        self.push_attributes(&source, &class, None, &class_file.attributes)?;
+
+        for field in &class_file.fields {
+            let member = (utf8(field.name_index()), utf8(field.descriptor_index()));
+            self.push(
+                "members",
+                [
+                    source.clone(),
+                    class.clone(),
+                    text("field"),
+                    text(member.0),
+                    text(member.1),
+                    field.access_flags().bits().to_string(),
+                    "NULL".to_string(),
+                ],
+            )?;
+            self.push_attributes(&source, &class, Some(member), field.attributes())?;
+        }
+
+        for method in &class_file.methods {
+            let member = (utf8(method.name_index()), utf8(method.descriptor_index()));
+            let code = method.code();
+            self.push(
+                "members",
+                [
+                    source.clone(),
+                    class.clone(),
+                    text("method"),
+                    text(member.0),
+                    text(member.1),
+                    method.access_flags().bits().to_string(),
+                    code.map_or_else(|| "NULL".to_string(), |code| code.code.len().to_string()),
+                ],
+            )?;
+            self.push_attributes(&source, &class, Some(member), method.attributes())?;
+            let instructions = code
+                .into_iter()
+                .flat_map(|code| instruction::decode(&code.code).map_while(Result::ok));
+            for instruction in instructions {
+                // getstatic through invokeinterface
+                if !(0xb2..=0xb9).contains(&instruction.opcode) {
+                    continue;
+                }
+                let reference = match instruction
+                    .cp_index()
+                    .and_then(|index| ConstantPool::member_ref(constant_pool, index))
+                {
+                    Some(reference) => reference,
+                    None => continue,
+                };
+                self.push(
+                    "member_refs",
+                    [
+                        source.clone(),
+                        class.clone(),
+                        text(member.0),
+                        text(member.1),
+                        instruction.offset.to_string(),
+                        text(instruction.mnemonic()),
+                        text(reference.class_name),
+                        text(reference.name),
+                        text(reference.descriptor),
+                    ],
+                )?;
+            }
+        }
+
+        for constant in constant_pool {
+            if let ConstantPool::String(index) = constant {
+                self.push(
+                    "string_constants",
+                    [source.clone(), class.clone(), text(utf8(*index))],
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds a class of the current source as far as the index knows it.
+    fn add_summary(&mut self, summary: &ClassSummary) -> io::Result<()> {
+        if !self.classes.insert(summary.name().to_string()) {
+            return Ok(());
+        }
+        let source = text(self.source.as_deref().expect("a source to add to"));
+        let class = text(summary.name());
+        self.push(
+            "classes",
+            [
+                source.clone(),
+                class.clone(),
+                summary.access_flags().bits().to_string(),
+                optional_text(summary.super_class()),
+                "NULL".to_string(),
+                "NULL".to_string(),
+                "NULL".to_string(),
+            ],
+        )?;
+        let members = summary
+            .fields()
+            .map(|field| ("field", field))
+            .chain(summary.methods().map(|method| ("method", method)));
+        for (kind, member) in members {
+            self.push(
+                "members",
+                [
+                    source.clone(),
+                    class.clone(),
+                    text(kind),
+                    text(member.name()),
+                    text(member.descriptor()),
+                    member.access_flags().to_string(),
+                    "NULL".to_string(),
+                ],
+            )?;
+        }
+        Ok(())
+    }
+
+    fn push_attributes(
+        &mut self,
+        source: &str,
+        class: &str,
+        member: Option<(&str, &str)>,
+        attributes: &[Attribute],
+    ) -> io::Result<()> {
+        for attribute in attributes {
+            self.push(
+                "attributes",
+                [
+                    source.to_string(),
+                    class.to_string(),
+                    optional_text(member.map(|(name, _)| name)),
+                    optional_text(member.map(|(_, descriptor)| descriptor)),
+                    text(attribute.name()),
+                ],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Batches a row of SQL values for `table`, writing every batched row
+    /// once there are [`BATCH_ROWS`] of them.
+    fn push<const N: usize>(&mut self, table: &str, values: [String; N]) -> io::Result<()> {
+        let position = TABLES
+            .iter()
+            .position(|t| t.name == table)
+            .expect("a table of TABLES");
+        self.rows[position].push(format!("({})", values.join(", ")));
+        if self.rows.iter().map(Vec::len).sum::<usize>() >= BATCH_ROWS {
+            self.flush_rows()?;
+        }
+        Ok(())
+    }
+
+    /// Writes the batched rows, parents before children.
+    fn flush_rows(&mut self) -> io::Result<()> {
+        for (table, rows) in TABLES.iter().zip(&mut self.rows) {
+            if rows.is_empty() {
+                continue;
+            }
+            // a malformed class may declare a member twice
+            let or_ignore = if table.name == "members" {
+                " OR IGNORE"
+            } else {
+                ""
+            };
+            writeln!(
+                self.out,
+                "INSERT{} INTO {} VALUES\n{};",
+                or_ignore,
+                table.name,
+                rows.join(",\n")
+            )?;
+            rows.clear();
+        }
+        Ok(())
+    }
+}
+
+/// Writes classes into an SQLite database, one source at a time, each in a
+/// transaction of its own: the script [`SqlExporter`] writes, run as it is
+/// written rather than saved.
+#[cfg(feature = "sqlite")]
+pub struct SqliteExporter {
+    connection: rusqlite::Connection,
+    script: SqlExporter<Vec<u8>>,
+}
+
+#[cfg(feature = "sqlite")]
+impl SqliteExporter {
+    /// Writes the classes and members of `index` into the database at
+    /// `path`, creating it if need be; see [`SqlExporter::export`].
+    pub fn export(index: &ClassIndex, path: &Path) -> io::Result<()> {
+        let mut exporter = SqliteExporter::open(path)?;
+        for (source, classes) in by_source(index) {
+            exporter.begin_source(source)?;
+            for class in classes {
+                exporter.script.add_summary(&class)?;
+                exporter.run()?;
+            }
+            exporter.end_source()?;
+        }
+        exporter.finish().map(drop)
+    }
+
+    /// Opens the database at `path`, creating it if need be, and the tables
+    /// in it unless they exist.
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let connection = rusqlite::Connection::open(path).map_err(io::Error::other)?;
+        let mut exporter = SqliteExporter {
+            connection,
+            script: SqlExporter::new(Vec::new())?,
+        };
+        exporter.run()?;
+        Ok(exporter)
+    }
+
+    /// See [`SqlExporter::begin_source`].
+    pub fn begin_source(&mut self, source: &str) -> io::Result<()> {
+        self.script.begin_source(source)?;
+        self.run()
+    }
+
+    /// See [`SqlExporter::end_source`].
+    pub fn end_source(&mut self) -> io::Result<()> {
+        self.script.end_source()?;
+        self.run()
+    }
+
+    /// See [`SqlExporter::add_class`].
+    pub fn add_class(&mut self, class_file: &ClassFile) -> io::Result<()> {
+        self.script.add_class(class_file)?;
+        self.run()
+    }
+
+    /// Commits the current source, returning the connection.
+    pub fn finish(mut self) -> io::Result<rusqlite::Connection> {
+        self.end_source()?;
+        Ok(self.connection)
+    }
+
+    /// Runs the statements written since the last run. The script is only
+    /// ever written a statement at a time, so they are whole.
+    fn run(&mut self) -> io::Result<()> {
+        if self.script.out.is_empty() {
+            return Ok(());
+        }
+        let statements = core::str::from_utf8(&self.script.out).map_err(io::Error::other)?;
+        self.connection
+            .execute_batch(statements)
+            .map_err(io::Error::other)?;
+        self.script.out.clear();
+        Ok(())
+    }
+}
+
+/// The classes of `index`, by the source they were read from.
+fn by_source(index: &ClassIndex) -> BTreeMap<&str, Vec<ClassSummary<'_>>> {
+    let mut by_source: BTreeMap<&str, Vec<ClassSummary>> = BTreeMap::new();
+    for class in index.classes() {
+        by_source.entry(class.source()).or_default().push(class);
+    }
+    by_source
+}
+
+/// `value` as an SQL string literal. One with a NUL character, which the
+/// sqlite3 shell would cut short, is written as a blob cast to text.
+fn text(value: &str) -> String {
+    if value.contains('\0') {
+        format!("CAST(X'{}' AS TEXT)", hex(value.as_bytes()))
+    } else {
+        format!("'{}'", value.replace('\'', "''"))
+    }
+}
+
+fn optional_text(value: Option<&str>) -> String {
+    value.map_or_else(|| "NULL".to_string(), text)
+}
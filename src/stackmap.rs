@@ -0,0 +1,246 @@
+//! Checks of a `StackMapTable` against the code it describes, for classes
+//! whose frames an instrumenting agent or obfuscator left out of step with
+//! the bytecode. The JVM's own verifier only says that a frame is bad, not
+//! which or why.
+
+use std::{collections::BTreeSet, fmt};
+
+use crate::{
+    attribute::{Code, StackMapFrame, VerificationTypeInfo},
+    constantpool::ConstantPool,
+    instruction,
+};
+
+/// The opcode of `new`.
+const NEW: u8 = 0xbb;
+
+/// Something wrong with a frame of a `StackMapTable`. `frame` is the
+/// frame's index in the table and `offset` the bytecode offset it applies
+/// to, summed up from the offset deltas of it and the frames before it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StackMapIssue {
+    /// The offset is at or past the end of the code.
+    OffsetPastEnd { frame: usize, offset: u32 },
+    /// The offset falls inside an instruction rather than at its start.
+    NotAnInstruction { frame: usize, offset: u32 },
+    /// An `Object` type refers to a constant pool entry which isn't a
+    /// `CONSTANT_Class`.
+    NotAClass {
+        frame: usize,
+        offset: u32,
+        index: u16,
+    },
+    /// An `Uninitialized` type gives an offset which isn't that of a `new`
+    /// instruction.
+    NotANew {
+        frame: usize,
+        offset: u32,
+        new_offset: u16,
+    },
+    /// The frame's locals take more slots than the method has.
+    TooManyLocals {
+        frame: usize,
+        offset: u32,
+        locals: usize,
+        max_locals: u16,
+    },
+    /// The frame's stack takes more slots than the method allows.
+    StackTooDeep {
+        frame: usize,
+        offset: u32,
+        stack: usize,
+        max_stack: u16,
+    },
+}
+
+impl StackMapIssue {
+    /// The index of the frame in the table.
+    pub fn frame(&self) -> usize {
+        match self {
+            StackMapIssue::OffsetPastEnd { frame, .. }
+            | StackMapIssue::NotAnInstruction { frame, .. }
+            | StackMapIssue::NotAClass { frame, .. }
+            | StackMapIssue::NotANew { frame, .. }
+            | StackMapIssue::TooManyLocals { frame, .. }
+            | StackMapIssue::StackTooDeep { frame, .. } => *frame,
+        }
+    }
+
+    /// The bytecode offset the frame applies to.
+    pub fn offset(&self) -> u32 {
+        match self {
+            StackMapIssue::OffsetPastEnd { offset, .. }
+            | StackMapIssue::NotAnInstruction { offset, .. }
+            | StackMapIssue::NotAClass { offset, .. }
+            | StackMapIssue::NotANew { offset, .. }
+            | StackMapIssue::TooManyLocals { offset, .. }
+            | StackMapIssue::StackTooDeep { offset, .. } => *offset,
+        }
+    }
+}
+
+impl fmt::Display for StackMapIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "frame {} at offset {}: ", self.frame(), self.offset())?;
+        match self {
+            StackMapIssue::OffsetPastEnd { .. } => write!(f, "past the end of the code"),
+            StackMapIssue::NotAnInstruction { .. } => {
+                write!(f, "not the start of an instruction")
+            }
+            StackMapIssue::NotAClass { index, .. } => {
+                write!(f, "object type #{} is not a class entry", index)
+            }
+            StackMapIssue::NotANew { new_offset, .. } => write!(
+                f,
+                "uninitialized type names offset {}, which is not a new",
+                new_offset
+            ),
+            StackMapIssue::TooManyLocals {
+                locals, max_locals, ..
+            } => write!(
+                f,
+                "{} local slots, more than max_locals {}",
+                locals, max_locals
+            ),
+            StackMapIssue::StackTooDeep {
+                stack, max_stack, ..
+            } => write!(
+                f,
+                "{} stack slots, more than max_stack {}",
+                stack, max_stack
+            ),
+        }
+    }
+}
+
+/// Checks the frames of `code`'s `StackMapTable`, if it has one, in order.
+/// `initial_locals` holds the slots each local of the implicit frame at
+/// offset 0 takes; when it's not known, local counts are only checked from
+/// the first full frame on.
+pub(crate) fn validate(
+    code: &Code,
+    constant_pool: &[ConstantPool],
+    initial_locals: Option<Vec<usize>>,
+) -> Vec<StackMapIssue> {
+    let mut issues = Vec::new();
+    let table = match code.stack_map_table() {
+        Some(table) => table,
+        None => return issues,
+    };
+    // Where decoding fails, the offsets from there on count as inside an
+    // instruction.
+    let instructions: Vec<(u32, u8)> = instruction::decode(&code.code)
+        .map_while(Result::ok)
+        .map(|instruction| (instruction.offset, instruction.opcode))
+        .collect();
+    let starts: BTreeSet<u32> = instructions.iter().map(|&(offset, _)| offset).collect();
+    let is_new = |offset: u16| {
+        instructions
+            .binary_search_by_key(&(offset as u32), |&(offset, _)| offset)
+            .is_ok_and(|i| instructions[i].1 == NEW)
+    };
+
+    // Each local is counted by its slots, two for a long or double, with
+    // the width of each kept so a chop frame knows how many to drop.
+    let mut locals = initial_locals;
+    let mut offset: Option<u32> = None;
+    for (frame, entry) in table.entries.iter().enumerate() {
+        let here = match offset {
+            None => entry.offset_delta() as u32,
+            Some(previous) => previous + entry.offset_delta() as u32 + 1,
+        };
+        offset = Some(here);
+        if here >= code.code.len() as u32 {
+            issues.push(StackMapIssue::OffsetPastEnd {
+                frame,
+                offset: here,
+            });
+        } else if !starts.contains(&here) {
+            issues.push(StackMapIssue::NotAnInstruction {
+                frame,
+                offset: here,
+            });
+        }
+
+        let (appended, stack): (&[VerificationTypeInfo], &[VerificationTypeInfo]) = match entry {
+            StackMapFrame::SameFrame(_) | StackMapFrame::SameFrameExtended(_) => (&[], &[]),
+            StackMapFrame::SameLocals1StackItemFrame { stack, .. }
+            | StackMapFrame::SameLocals1StackItemFrameExtended { stack, .. } => {
+                (&[], std::slice::from_ref(stack))
+            }
+            StackMapFrame::ChopFrame { chopped, .. } => {
+                if let Some(locals) = &mut locals {
+                    let len = locals.len().saturating_sub(*chopped as usize);
+                    locals.truncate(len);
+                }
+                (&[], &[])
+            }
+            StackMapFrame::AppendFrame {
+                locals: appended, ..
+            } => (appended, &[]),
+            StackMapFrame::FullFrame {
+                locals: full,
+                stack,
+                ..
+            } => {
+                locals = Some(Vec::new());
+                (full, stack)
+            }
+        };
+        if let Some(locals) = &mut locals {
+            locals.extend(appended.iter().map(slots));
+        }
+
+        for info in appended.iter().chain(stack) {
+            match *info {
+                VerificationTypeInfo::ObjectVariableInfo(index)
+                    if ConstantPool::class_name(constant_pool, index).is_none() =>
+                {
+                    issues.push(StackMapIssue::NotAClass {
+                        frame,
+                        offset: here,
+                        index,
+                    })
+                }
+                VerificationTypeInfo::UninitializedVariableInfo(new_offset)
+                    if !is_new(new_offset) =>
+                {
+                    issues.push(StackMapIssue::NotANew {
+                        frame,
+                        offset: here,
+                        new_offset,
+                    })
+                }
+                _ => {}
+            }
+        }
+
+        let local_slots: usize = locals.iter().flatten().sum();
+        if local_slots > code.max_locals as usize {
+            issues.push(StackMapIssue::TooManyLocals {
+                frame,
+                offset: here,
+                locals: local_slots,
+                max_locals: code.max_locals,
+            });
+        }
+        let stack_slots: usize = stack.iter().map(slots).sum();
+        if stack_slots > code.max_stack as usize {
+            issues.push(StackMapIssue::StackTooDeep {
+                frame,
+                offset: here,
+                stack: stack_slots,
+                max_stack: code.max_stack,
+            });
+        }
+    }
+    issues
+}
+
+/// The slots a value of the type takes.
+fn slots(info: &VerificationTypeInfo) -> usize {
+    match info {
+        VerificationTypeInfo::LongVariableInfo | VerificationTypeInfo::DoubleVariableInfo => 2,
+        _ => 1,
+    }
+}
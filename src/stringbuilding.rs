@@ -0,0 +1,242 @@
+//! Where methods build strings: `StringBuilder` and `StringBuffer` append
+//! chains, `String.format`, `String.concat`, and the `invokedynamic`
+//! concatenations javac emits from Java 9 on, with their recipes decoded.
+//!
+//! The same source compiles to append chains for release 8 and to
+//! `StringConcatFactory` call sites after that, so a method's
+//! [`MethodStringBuilding::weight`] counts the pieces joined rather than
+//! the calls made, and comes out about the same either way.
+
+use crate::{
+    classfile::{ClassFile, DynamicSite},
+    constantpool::ConstantPool,
+    descriptor::MethodDescriptor,
+    instruction,
+};
+
+const STRING_CONCAT_FACTORY: &str = "java/lang/invoke/StringConcatFactory";
+
+/// Marks an argument of the call site in a `makeConcatWithConstants` recipe.
+const TAG_ARGUMENT: char = '\u{1}';
+/// Marks the next static argument of the bootstrap method in a recipe.
+const TAG_CONSTANT: char = '\u{2}';
+
+/// An `invokedynamic` instruction bootstrapped by `StringConcatFactory`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConcatSite {
+    pub offset: u32,
+    /// The recipe with constants spliced in and each argument shown as its
+    /// type in braces, e.g. `Hello {String}, you are {int}`. Braces in the
+    /// text itself are doubled. `None` when the recipe can't be decoded.
+    pub recipe: Option<String>,
+    /// The arguments and runs of text joined.
+    pub pieces: usize,
+}
+
+/// The string building of one method.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct MethodStringBuilding {
+    pub name: String,
+    pub descriptor: String,
+    /// `StringBuilder` and `StringBuffer` instances constructed.
+    pub builders: usize,
+    /// Calls to their `append` methods.
+    pub appends: usize,
+    /// Calls to `String.format` and `String.formatted`.
+    pub formats: usize,
+    /// Calls to `String.concat`.
+    pub concats: usize,
+    pub concat_sites: Vec<ConcatSite>,
+}
+
+impl MethodStringBuilding {
+    /// Roughly the number of pieces the method joins into strings: one for
+    /// every append, format and `concat`, and the pieces of every
+    /// `invokedynamic` concatenation.
+    pub fn weight(&self) -> usize {
+        self.appends
+            + self.formats
+            + self.concats
+            + self
+                .concat_sites
+                .iter()
+                .map(|site| site.pieces)
+                .sum::<usize>()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.builders == 0
+            && self.appends == 0
+            && self.formats == 0
+            && self.concats == 0
+            && self.concat_sites.is_empty()
+    }
+}
+
+/// The string building of every method of the class which does any, in
+/// declaration order.
+pub fn string_building(class_file: &ClassFile) -> Vec<MethodStringBuilding> {
+    let constant_pool = &class_file.constant_pool;
+    let mut methods = Vec::new();
+    for method in &class_file.methods {
+        let code = match method.code() {
+            Some(code) => code,
+            None => continue,
+        };
+        let mut building = MethodStringBuilding {
+            name: ConstantPool::utf8(constant_pool, method.name_index())
+                .unwrap_or_default()
+                .to_string(),
+            descriptor: ConstantPool::utf8(constant_pool, method.descriptor_index())
+                .unwrap_or_default()
+                .to_string(),
+            ..MethodStringBuilding::default()
+        };
+        for instruction in instruction::decode(&code.code).flatten() {
+            let index = match instruction.cp_index() {
+                Some(index) if instruction.is_invoke() => index,
+                _ => continue,
+            };
+            if instruction.opcode == 0xba {
+                if let Some(site) = class_file
+                    .dynamic_site(index)
+                    .filter(|site| site.bootstrap.class_name == STRING_CONCAT_FACTORY)
+                {
+                    building.concat_sites.push(ConcatSite {
+                        offset: instruction.offset,
+                        recipe: concat_recipe(constant_pool, &site),
+                        pieces: concat_pieces(constant_pool, &site),
+                    });
+                }
+                continue;
+            }
+            let method = match ConstantPool::member_ref(constant_pool, index) {
+                Some(method) => method,
+                None => continue,
+            };
+            match (method.class_name, method.name) {
+                ("java/lang/StringBuilder" | "java/lang/StringBuffer", "<init>") => {
+                    building.builders += 1
+                }
+                ("java/lang/StringBuilder" | "java/lang/StringBuffer", "append") => {
+                    building.appends += 1
+                }
+                ("java/lang/String", "format" | "formatted") => building.formats += 1,
+                ("java/lang/String", "concat") => building.concats += 1,
+                _ => {}
+            }
+        }
+        if !building.is_empty() {
+            methods.push(building);
+        }
+    }
+    methods
+}
+
+/// Renders the recipe of a `StringConcatFactory` call site. `makeConcat`
+/// has none and joins its arguments as they are.
+pub fn concat_recipe(constant_pool: &[ConstantPool], site: &DynamicSite) -> Option<String> {
+    let argument_types = argument_types(site)?;
+    let mut argument_types = argument_types.iter();
+    let mut recipe = String::new();
+    match site.bootstrap.name {
+        "makeConcat" => {
+            for argument_type in argument_types {
+                recipe.push_str(&placeholder(argument_type));
+            }
+        }
+        "makeConcatWithConstants" => {
+            let (&template, constants) = site.arguments.split_first()?;
+            let mut constants = constants.iter();
+            for c in string_constant(constant_pool, template)?.chars() {
+                match c {
+                    TAG_ARGUMENT => recipe.push_str(&placeholder(argument_types.next()?)),
+                    TAG_CONSTANT => {
+                        let constant = loadable_constant(constant_pool, *constants.next()?)?;
+                        recipe.push_str(&escape_braces(&constant));
+                    }
+                    '{' => recipe.push_str("{{"),
+                    '}' => recipe.push_str("}}"),
+                    c => recipe.push(c),
+                }
+            }
+        }
+        _ => return None,
+    }
+    Some(recipe)
+}
+
+/// The arguments and runs of text a `StringConcatFactory` call site joins.
+/// When the recipe can't be read, each argument counts as one piece.
+fn concat_pieces(constant_pool: &[ConstantPool], site: &DynamicSite) -> usize {
+    let arguments = argument_types(site).map_or(0, |types| types.len());
+    let template = match (site.bootstrap.name, site.arguments.first()) {
+        ("makeConcatWithConstants", Some(&template)) => string_constant(constant_pool, template),
+        _ => None,
+    };
+    let template = match template {
+        Some(template) => template,
+        None => return arguments,
+    };
+    let mut pieces = 0;
+    let mut in_text = false;
+    for c in template.chars() {
+        match c {
+            TAG_ARGUMENT => {
+                pieces += 1;
+                in_text = false;
+            }
+            _ if !in_text => {
+                pieces += 1;
+                in_text = true;
+            }
+            _ => {}
+        }
+    }
+    pieces
+}
+
+/// The simple names of the types of the call site's arguments.
+fn argument_types(site: &DynamicSite) -> Option<Vec<String>> {
+    let descriptor = MethodDescriptor::parse(site.descriptor).ok()?;
+    Some(
+        descriptor
+            .parameters
+            .iter()
+            .map(|parameter| {
+                let name = parameter.to_string();
+                match name.rsplit_once('.') {
+                    Some((_, simple_name)) => simple_name.to_string(),
+                    None => name,
+                }
+            })
+            .collect(),
+    )
+}
+
+fn placeholder(argument_type: &str) -> String {
+    format!("{{{}}}", argument_type)
+}
+
+fn escape_braces(text: &str) -> String {
+    text.replace('{', "{{").replace('}', "}}")
+}
+
+fn string_constant(constant_pool: &[ConstantPool], index: u16) -> Option<&str> {
+    match constant_pool.get((index as usize).checked_sub(1)?)? {
+        ConstantPool::String(value) => ConstantPool::utf8(constant_pool, *value),
+        _ => None,
+    }
+}
+
+/// The text a constant static argument contributes to the string.
+fn loadable_constant(constant_pool: &[ConstantPool], index: u16) -> Option<String> {
+    match constant_pool.get((index as usize).checked_sub(1)?)? {
+        ConstantPool::String(value) => ConstantPool::utf8(constant_pool, *value).map(String::from),
+        ConstantPool::Integer(value) => Some(value.to_string()),
+        ConstantPool::Long(value) => Some(value.to_string()),
+        ConstantPool::Float(bits) => Some(bits.value().to_string()),
+        ConstantPool::Double(bits) => Some(bits.value().to_string()),
+        _ => None,
+    }
+}
@@ -0,0 +1,139 @@
+//! Interned strings for indexes which hold the names of many classes. Every
+//! string is stored once, in a single buffer, and referred to by a 4-byte
+//! [`Symbol`], so that an index of 100k classes holds a handful of
+//! allocations rather than one per name and descriptor.
+
+use crate::prelude::*;
+use core::{fmt, mem, num::NonZeroU32};
+
+/// A string interned in a [`StringTable`], valid only for that table.
+/// `Option<Symbol>` takes no more room than `Symbol`.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Symbol(NonZeroU32);
+
+impl Symbol {
+    fn index(self) -> usize {
+        self.0.get() as usize - 1
+    }
+}
+
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Symbol({})", self.index())
+    }
+}
+
+#[derive(Default, Clone)]
+pub struct StringTable {
+    /// Every string, back to back.
+    text: String,
+    /// The end of each string in `text`, by symbol; each starts where the
+    /// one before it ends.
+    ends: Vec<u32>,
+    /// An open-addressed hash set of the symbols, 0 marking a free slot.
+    /// Its length is 0 or a power of two at least twice the symbol count.
+    slots: Vec<u32>,
+}
+
+impl StringTable {
+    pub fn new() -> Self {
+        StringTable::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.ends.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ends.is_empty()
+    }
+
+    /// The symbol of `value`, adding it if it isn't in the table yet.
+    ///
+    /// Panics if the table would grow past 4 GiB of text.
+    pub fn intern(&mut self, value: &str) -> Symbol {
+        if let Some(symbol) = self.get(value) {
+            return symbol;
+        }
+        if (self.ends.len() + 1) * 2 > self.slots.len() {
+            self.grow();
+        }
+        self.text.push_str(value);
+        let end = u32::try_from(self.text.len()).expect("string table over 4 GiB");
+        self.ends.push(end);
+        let symbol = Symbol(NonZeroU32::new(self.ends.len() as u32).expect("non-zero length"));
+        let slot = self.free_slot(hash(value));
+        self.slots[slot] = symbol.0.get();
+        symbol
+    }
+
+    /// The symbol of `value`, if it's in the table.
+    pub fn get(&self, value: &str) -> Option<Symbol> {
+        if self.slots.is_empty() {
+            return None;
+        }
+        let mask = self.slots.len() - 1;
+        let mut slot = hash(value) as usize & mask;
+        loop {
+            let symbol = Symbol(NonZeroU32::new(self.slots[slot])?);
+            if self.resolve(symbol) == value {
+                return Some(symbol);
+            }
+            slot = (slot + 1) & mask;
+        }
+    }
+
+    /// The string `symbol` stands for. Panics if it's from another table
+    /// with more strings.
+    pub fn resolve(&self, symbol: Symbol) -> &str {
+        let index = symbol.index();
+        let start = match index {
+            0 => 0,
+            _ => self.ends[index - 1] as usize,
+        };
+        &self.text[start..self.ends[index] as usize]
+    }
+
+    /// Roughly how many bytes of heap the table holds, counting what's
+    /// allocated rather than what's used.
+    pub fn approx_bytes(&self) -> usize {
+        self.text.capacity()
+            + self.ends.capacity() * mem::size_of::<u32>()
+            + self.slots.capacity() * mem::size_of::<u32>()
+    }
+
+    fn grow(&mut self) {
+        let len = (self.slots.len() * 2).max(16);
+        self.slots = vec![0; len];
+        for index in 0..self.ends.len() {
+            let symbol = Symbol(NonZeroU32::new(index as u32 + 1).expect("non-zero index"));
+            let slot = self.free_slot(hash(self.resolve(symbol)));
+            self.slots[slot] = symbol.0.get();
+        }
+    }
+
+    fn free_slot(&self, hash: u32) -> usize {
+        let mask = self.slots.len() - 1;
+        let mut slot = hash as usize & mask;
+        while self.slots[slot] != 0 {
+            slot = (slot + 1) & mask;
+        }
+        slot
+    }
+}
+
+impl fmt::Debug for StringTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StringTable")
+            .field("strings", &self.len())
+            .field("bytes", &self.text.len())
+            .finish()
+    }
+}
+
+/// FNV-1a, which is quick on the short strings names are.
+fn hash(value: &str) -> u32 {
+    value.bytes().fold(0x811c_9dc5, |hash, byte| {
+        (hash ^ u32::from(byte)).wrapping_mul(0x0100_0193)
+    })
+}
@@ -0,0 +1,226 @@
+//! The maps javac compiles a `switch` over an enum of another class into.
+//!
+//! Switching on the enum's ordinals directly would break once the enum's
+//! constants are reordered, so javac switches over case numbers of its own
+//! instead. A synthetic class, usually `Outer$1`, holds an `int[]` field
+//! `$SwitchMap$<enum>` per enum, indexed by ordinal, which its `<clinit>`
+//! fills in one constant at a time:
+//!
+//! ```text
+//! getstatic Outer$1.$SwitchMap$pkg$Color : [I
+//! getstatic pkg/Color.RED : Lpkg/Color;
+//! invokevirtual pkg/Color.ordinal()I
+//! iconst_1
+//! iastore
+//! ```
+//!
+//! and the switch itself looks its case number up by the ordinal:
+//!
+//! ```text
+//! getstatic Outer$1.$SwitchMap$pkg$Color : [I
+//! aload_0
+//! invokevirtual pkg/Color.ordinal()I
+//! iaload
+//! tableswitch
+//! ```
+//!
+//! [`enum_switch_maps`] reads the maps back out of a switch map class, and
+//! [`enum_switches`] names the constants behind the cases of the switches
+//! in a method, given the maps of the classes an index holds.
+
+use std::collections::BTreeMap;
+
+use crate::{
+    classfile::ClassFile,
+    classindex::ClassIndex,
+    constantpool::ConstantPool,
+    instruction::{self, Instruction, LOOKUPSWITCH, TABLESWITCH},
+    methodinfo::MethodInfo,
+};
+
+pub(crate) const SWITCH_MAP_PREFIX: &str = "$SwitchMap$";
+
+const GETSTATIC: u8 = 0xb2;
+const INVOKEVIRTUAL: u8 = 0xb6;
+const IALOAD: u8 = 0x2e;
+const IASTORE: u8 = 0x4f;
+
+/// The case numbers one switch map field gives the constants of an enum.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnumSwitchMap {
+    /// The name of the field, e.g. `$SwitchMap$pkg$Color`.
+    pub field_name: String,
+    /// The internal name of the enum, e.g. `pkg/Color`.
+    pub enum_class: String,
+    /// The name of the constant each case number stands for.
+    pub cases: BTreeMap<i32, String>,
+}
+
+/// A `tableswitch` or `lookupswitch` over the case numbers of a switch map.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnumSwitch {
+    /// The offset of the switch instruction.
+    pub offset: u32,
+    /// The internal name of the enum switched over.
+    pub enum_class: String,
+    /// The constant each key of the switch stands for, in the order of the
+    /// keys. Keys the map doesn't have are left out.
+    pub cases: Vec<(i32, String)>,
+}
+
+/// The switch maps the `<clinit>` of `class_file` fills in, in the order of
+/// their fields. A class without `$SwitchMap$` fields has none; the entries
+/// of a map which aren't filled in the way javac does it are left out.
+pub fn enum_switch_maps(class_file: &ClassFile) -> Vec<EnumSwitchMap> {
+    let constant_pool = &class_file.constant_pool;
+    let this_class = ConstantPool::class_name(constant_pool, class_file.this_class);
+    let mut maps: Vec<EnumSwitchMap> = class_file
+        .fields
+        .iter()
+        .filter_map(|field| ConstantPool::utf8(constant_pool, field.name_index()))
+        .filter(|name| name.starts_with(SWITCH_MAP_PREFIX))
+        .map(|name| EnumSwitchMap {
+            field_name: name.to_string(),
+            enum_class: String::new(),
+            cases: BTreeMap::new(),
+        })
+        .collect();
+    if maps.is_empty() {
+        return maps;
+    }
+    let code = class_file.methods.iter().find_map(|method| {
+        (ConstantPool::utf8(constant_pool, method.name_index()) == Some("<clinit>"))
+            .then(|| method.code())
+            .flatten()
+    });
+    let instructions: Vec<Instruction> = match code {
+        Some(code) => instruction::decode(&code.code)
+            .map_while(Result::ok)
+            .collect(),
+        None => Vec::new(),
+    };
+    let field = |instruction: &Instruction, opcode| {
+        (instruction.opcode == opcode)
+            .then(|| instruction.cp_index())
+            .flatten()
+            .and_then(|index| ConstantPool::member_ref(constant_pool, index))
+    };
+    for window in instructions.windows(5) {
+        let map = match field(&window[0], GETSTATIC) {
+            Some(map) if Some(map.class_name) == this_class && map.descriptor == "[I" => map,
+            _ => continue,
+        };
+        let constant = match field(&window[1], GETSTATIC) {
+            Some(constant) => constant,
+            None => continue,
+        };
+        let ordinal = match field(&window[2], INVOKEVIRTUAL) {
+            Some(ordinal) => ordinal,
+            None => continue,
+        };
+        if (ordinal.class_name, ordinal.name, ordinal.descriptor)
+            != (constant.class_name, "ordinal", "()I")
+            || constant.descriptor != format!("L{};", constant.class_name)
+            || window[4].opcode != IASTORE
+        {
+            continue;
+        }
+        let case = match int_constant(&window[3]) {
+            Some(case) => case,
+            None => continue,
+        };
+        if let Some(entry) = maps.iter_mut().find(|entry| entry.field_name == map.name) {
+            if entry.enum_class.is_empty() {
+                entry.enum_class = constant.class_name.to_string();
+            }
+            if entry.enum_class == constant.class_name {
+                entry.cases.insert(case, constant.name.to_string());
+            }
+        }
+    }
+    maps.retain(|map| !map.enum_class.is_empty());
+    maps
+}
+
+/// The switches of `method` over the case numbers of a switch map `index`
+/// holds, in order of offset, with the constants their keys stand for.
+pub fn enum_switches(
+    class_file: &ClassFile,
+    method: &MethodInfo,
+    index: &ClassIndex,
+) -> Vec<EnumSwitch> {
+    let constant_pool = &class_file.constant_pool;
+    let code = match method.code() {
+        Some(code) => code,
+        None => return Vec::new(),
+    };
+    let mut switches = Vec::new();
+    // the map of the last switch map field read, until a switch uses it
+    let mut pending: Option<&EnumSwitchMap> = None;
+    let mut previous: Option<u8> = None;
+    for instruction in instruction::decode(&code.code).map_while(Result::ok) {
+        match instruction.opcode {
+            GETSTATIC => {
+                let map = instruction
+                    .cp_index()
+                    .and_then(|index| ConstantPool::member_ref(constant_pool, index))
+                    .filter(|member| member.name.starts_with(SWITCH_MAP_PREFIX))
+                    .and_then(|member| index.enum_switch_map(member.class_name, member.name));
+                if map.is_some() {
+                    pending = map;
+                }
+            }
+            TABLESWITCH | LOOKUPSWITCH if previous == Some(IALOAD) => {
+                if let Some(map) = pending.take() {
+                    let cases = switch_keys(&instruction)
+                        .into_iter()
+                        .filter_map(|key| Some((key, map.cases.get(&key)?.clone())))
+                        .collect();
+                    switches.push(EnumSwitch {
+                        offset: instruction.offset,
+                        enum_class: map.enum_class.clone(),
+                        cases,
+                    });
+                }
+            }
+            _ => {}
+        }
+        previous = Some(instruction.opcode);
+    }
+    switches
+}
+
+/// The value `iconst_<n>`, `bipush` or `sipush` pushes.
+fn int_constant(instruction: &Instruction) -> Option<i32> {
+    let operands = instruction.operands;
+    match instruction.opcode {
+        opcode @ 0x02..=0x08 => Some(i32::from(opcode) - 3),
+        0x10 => Some(i32::from(*operands.first()? as i8)),
+        0x11 => Some(i32::from(i16::from_be_bytes([
+            *operands.first()?,
+            *operands.get(1)?,
+        ]))),
+        _ => None,
+    }
+}
+
+/// The keys of a `tableswitch` or `lookupswitch`, in the order they're
+/// listed.
+fn switch_keys(instruction: &Instruction) -> Vec<i32> {
+    let operands = instruction.operands;
+    let pad = (3 - instruction.offset as usize % 4) % 4;
+    let word = |i: usize| {
+        operands
+            .get(pad + i * 4..pad + i * 4 + 4)
+            .map(|bytes| i32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    };
+    if instruction.opcode == TABLESWITCH {
+        match (word(1), word(2)) {
+            (Some(low), Some(high)) => (low..=high).collect(),
+            _ => Vec::new(),
+        }
+    } else {
+        let count = word(1).unwrap_or(0).max(0) as usize;
+        (0..count).filter_map(|i| word(2 + i * 2)).collect()
+    }
+}
@@ -0,0 +1,251 @@
+//! The symbols a class defines and the members it refers to, flattened into
+//! the shape source indexers consume: definitions with their source lines
+//! and references with the method and offset they're made from.
+//!
+//! Every symbol has an ID built from names alone, so that the ID of a
+//! reference in one class equals the ID of the definition in another:
+//!
+//! - a class: its internal name, `com/example/Foo`
+//! - a field: `com/example/Foo#name:Ljava/lang/String;`
+//! - a method: `com/example/Foo#run(I)V`
+//!
+//! A reference names the class it was compiled against, which may inherit
+//! the member rather than declare it; [`SymbolIndex::resolve`] follows
+//! superclasses to the declaration.
+
+use std::collections::HashMap;
+
+use crate::{
+    attribute::LineNumber,
+    classfile::ClassFile,
+    constantpool::ConstantPool,
+    instruction,
+    stringtable::{StringTable, Symbol},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SymbolKind {
+    Class,
+    Field,
+    Method,
+}
+
+impl SymbolKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            SymbolKind::Class => "class",
+            SymbolKind::Field => "field",
+            SymbolKind::Method => "method",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Definition {
+    pub id: String,
+    pub kind: SymbolKind,
+    pub access_flags: u16,
+    /// The first line of a method's code, from its `LineNumberTable`.
+    /// Class files record no lines for classes, fields or methods without
+    /// code.
+    pub line: Option<u16>,
+}
+
+/// A field or method instruction naming a member.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reference {
+    /// The ID of the member referred to, as named by the instruction.
+    pub target: String,
+    pub kind: SymbolKind,
+    /// The ID of the method the instruction is in.
+    pub from: String,
+    /// The bytecode offset of the instruction.
+    pub offset: u32,
+    /// The source line of the instruction, from the `LineNumberTable`.
+    pub line: Option<u16>,
+}
+
+/// What [`ClassFile::symbols`] finds in one class.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolTable {
+    /// The internal name of the class, which is also its ID.
+    pub class: String,
+    pub super_class: Option<String>,
+    /// The `SourceFile` attribute, without the package.
+    pub source_file: Option<String>,
+    /// The class first, then its fields and methods in declaration order.
+    pub definitions: Vec<Definition>,
+    /// In method declaration order, then by offset.
+    pub references: Vec<Reference>,
+}
+
+impl SymbolTable {
+    pub fn new(class_file: &ClassFile) -> Self {
+        let constant_pool = &class_file.constant_pool;
+        let utf8 = |index| ConstantPool::utf8(constant_pool, index).unwrap_or("?");
+        let class = ConstantPool::class_name(constant_pool, class_file.this_class)
+            .unwrap_or("?")
+            .to_string();
+        let mut definitions = vec![Definition {
+            id: class.clone(),
+            kind: SymbolKind::Class,
+            access_flags: class_file.access_flags.bits(),
+            line: None,
+        }];
+        definitions.extend(class_file.fields.iter().map(|field| Definition {
+            id: field_id(
+                &class,
+                utf8(field.name_index()),
+                utf8(field.descriptor_index()),
+            ),
+            kind: SymbolKind::Field,
+            access_flags: field.access_flags().bits(),
+            line: None,
+        }));
+
+        let mut references = Vec::new();
+        for method in &class_file.methods {
+            let id = method_id(
+                &class,
+                utf8(method.name_index()),
+                utf8(method.descriptor_index()),
+            );
+            let lines = method.line_number_table().unwrap_or(&[]);
+            definitions.push(Definition {
+                id: id.clone(),
+                kind: SymbolKind::Method,
+                access_flags: method.access_flags().bits(),
+                line: lines.iter().map(|line| line.line_number).min(),
+            });
+            let code = match method.code() {
+                Some(code) => code,
+                None => continue,
+            };
+            // decoding stops at the first error, keeping what came before
+            for instruction in instruction::decode(&code.code).map_while(Result::ok) {
+                let is_member = matches!(instruction.opcode, 0xb2..=0xb9);
+                let target = instruction
+                    .cp_index()
+                    .filter(|_| is_member)
+                    .and_then(|index| ConstantPool::member_ref(constant_pool, index));
+                if let Some(target) = target {
+                    let (target, kind) = if instruction.is_invoke() {
+                        let target = method_id(target.class_name, target.name, target.descriptor);
+                        (target, SymbolKind::Method)
+                    } else {
+                        let target = field_id(target.class_name, target.name, target.descriptor);
+                        (target, SymbolKind::Field)
+                    };
+                    references.push(Reference {
+                        target,
+                        kind,
+                        from: id.clone(),
+                        offset: instruction.offset,
+                        line: line_at(lines, instruction.offset),
+                    });
+                }
+            }
+        }
+
+        SymbolTable {
+            class,
+            super_class: ConstantPool::class_name(constant_pool, class_file.super_class)
+                .map(str::to_string),
+            source_file: class_file.source_file().map(str::to_string),
+            definitions,
+            references,
+        }
+    }
+}
+
+/// The symbol tables of many classes, such as those of a jar, for looking
+/// definitions and references up across them.
+#[derive(Debug, Default, Clone)]
+pub struct SymbolIndex {
+    /// In the order the classes were added.
+    tables: Vec<SymbolTable>,
+    /// The IDs of every definition.
+    ids: StringTable,
+    /// ID -> (table, definition)
+    definitions: HashMap<Symbol, (u32, u32)>,
+}
+
+impl SymbolIndex {
+    pub fn new() -> Self {
+        SymbolIndex::default()
+    }
+
+    /// Adds a class's table. A symbol defined twice, as by two copies of a
+    /// class, keeps its first definition.
+    pub fn add(&mut self, table: SymbolTable) {
+        let table_index = u32::try_from(self.tables.len()).expect("under 4G tables");
+        for (i, definition) in (0..).zip(&table.definitions) {
+            let id = self.ids.intern(&definition.id);
+            self.definitions.entry(id).or_insert((table_index, i));
+        }
+        self.tables.push(table);
+    }
+
+    pub fn tables(&self) -> &[SymbolTable] {
+        &self.tables
+    }
+
+    /// The definition of the symbol `id` and the table it's in.
+    pub fn definition(&self, id: &str) -> Option<(&SymbolTable, &Definition)> {
+        let &(table, definition) = self.definitions.get(&self.ids.get(id)?)?;
+        let table = &self.tables[table as usize];
+        Some((table, &table.definitions[definition as usize]))
+    }
+
+    /// The definition a reference to `id` leads to: the symbol itself, or
+    /// failing that the member of the same name and descriptor in the
+    /// nearest superclass among the indexed classes which declares it.
+    pub fn resolve(&self, id: &str) -> Option<(&SymbolTable, &Definition)> {
+        if let Some(found) = self.definition(id) {
+            return Some(found);
+        }
+        let (class, member) = id.split_once('#')?;
+        let mut class = self.definition(class)?.0;
+        // a cycle of superclasses can't be longer than the index
+        for _ in 0..self.tables.len() {
+            class = self.definition(class.super_class.as_deref()?)?.0;
+            if let Some(found) = self.definition(&format!("{}#{}", class.class, member)) {
+                return Some(found);
+            }
+        }
+        None
+    }
+
+    /// Every reference to the symbol `id` exactly as named, with the table
+    /// it's in, in the order the classes were added.
+    pub fn references_to<'a>(
+        &'a self,
+        id: &'a str,
+    ) -> impl Iterator<Item = (&'a SymbolTable, &'a Reference)> + 'a {
+        self.tables.iter().flat_map(move |table| {
+            table
+                .references
+                .iter()
+                .filter(move |reference| reference.target == id)
+                .map(move |reference| (table, reference))
+        })
+    }
+}
+
+pub fn field_id(class: &str, name: &str, descriptor: &str) -> String {
+    format!("{}#{}:{}", class, name, descriptor)
+}
+
+pub fn method_id(class: &str, name: &str, descriptor: &str) -> String {
+    format!("{}#{}{}", class, name, descriptor)
+}
+
+/// The line of the instruction at `offset`: that of the last entry starting
+/// at or before it. Entries needn't be in order.
+fn line_at(lines: &[LineNumber], offset: u32) -> Option<u16> {
+    lines
+        .iter()
+        .filter(|line| u32::from(line.start_pc) <= offset)
+        .max_by_key(|line| line.start_pc)
+        .map(|line| line.line_number)
+}
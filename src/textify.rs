@@ -0,0 +1,1187 @@
+//! A textual dump of a class laid out like the output of ASM's `Textifier`,
+//! so that existing tooling which diffs Textifier dumps can consume ours.
+//!
+//! The dump follows what `ClassReader` feeds a `Textifier` with default
+//! parsing options: short forms such as `iload_1` and `ldc_w` are expanded
+//! (`ILOAD 1`, `LDC`), `goto_w` and `jsr_w` print as `GOTO` and `JSR`, stack
+//! map frames are shown in their compressed form, and labels are named `L0`,
+//! `L1`, ... in order of first mention. Access flags include ASM's pseudo
+//! flags for the `Deprecated` and `Record` attributes.
+//!
+//! Known deviations from Textifier:
+//!
+//! - generic signatures are printed as `// signature` lines, but the
+//!   `// declaration:` line rendering them as Java source is omitted,
+//! - type annotations, non-standard attributes and module descriptors are
+//!   not printed,
+//! - `float` and `double` values use the shortest representation that round
+//!   trips, which matches `Double.toString` from JDK 19 on but may differ in
+//!   the last digits from older JDKs,
+//! - `LDC` of a dynamic constant prints its name, descriptor and bootstrap
+//!   method index rather than the full bootstrap method,
+//! - a branch target before the start of the code, which `ClassReader`
+//!   rejects, prints as its offset where the label would be.
+//!
+//! [`textify_with`] can also leave parts of huge classes out, see
+//! [`PrintOptions`], and [`textify_commented`] add comments to instructions;
+//! the dump is then no longer Textifier's.
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt::{LowerExp, Write},
+    str::FromStr,
+};
+
+use crate::{
+    accessflags::*,
+    attribute::{
+        Annotation, Attribute, BootstrapMethod, Code, ElementValue, StackMapFrame,
+        VerificationTypeInfo,
+    },
+    classfile::ClassFile,
+    constantpool::ConstantPool,
+    fieldinfo::FieldInfo,
+    instruction::{self, Instruction, LOOKUPSWITCH, TABLESWITCH, WIDE},
+    methodinfo::MethodInfo,
+    mutf8,
+};
+
+/// ASM's pseudo access flag for classes with a `Record` attribute.
+const ACC_RECORD: u32 = 0x10000;
+/// ASM's pseudo access flag for elements with a `Deprecated` attribute.
+const ACC_DEPRECATED: u32 = 0x20000;
+
+#[rustfmt::skip]
+const NEWARRAY_TYPES: [&str; 12] = [
+    "", "", "", "", "T_BOOLEAN", "T_CHAR", "T_FLOAT", "T_DOUBLE", "T_BYTE", "T_SHORT", "T_INT",
+    "T_LONG",
+];
+
+const HANDLE_KINDS: [&str; 10] = [
+    "",
+    "GETFIELD",
+    "GETSTATIC",
+    "PUTFIELD",
+    "PUTSTATIC",
+    "INVOKEVIRTUAL",
+    "INVOKESTATIC",
+    "INVOKESPECIAL",
+    "NEWINVOKESPECIAL",
+    "INVOKEINTERFACE",
+];
+
+/// Limits on how much of a class [`textify_with`] prints, for generated
+/// classes too large to read whole. Whatever is left out is replaced by a
+/// comment saying exactly how much was, so nothing goes missing silently.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PrintOptions {
+    /// Methods with more instructions than this print the first half of
+    /// that many and the last half.
+    pub max_instructions_per_method: Option<usize>,
+    /// String constants longer than this many characters print only those.
+    pub max_string_len: Option<usize>,
+    /// Classes with more methods print only the first this many.
+    pub max_methods: Option<usize>,
+}
+
+impl PrintOptions {
+    /// No limits at all.
+    pub fn full() -> Self {
+        PrintOptions::default()
+    }
+}
+
+pub fn textify(class_file: &ClassFile) -> String {
+    textify_with(class_file, &PrintOptions::full())
+}
+
+/// The dump of [`textify`], with the limits of `options`.
+pub fn textify_with(class_file: &ClassFile, options: &PrintOptions) -> String {
+    textify_commented(class_file, options, &|_| BTreeMap::new())
+}
+
+/// The dump of [`textify_with`], with comments to the right of
+/// instructions. `comments` gives those of a method by instruction offset.
+pub fn textify_commented(
+    class_file: &ClassFile,
+    options: &PrintOptions,
+    comments: &dyn Fn(&MethodInfo) -> BTreeMap<u32, String>,
+) -> String {
+    let mut textifier = Textifier {
+        out: String::new(),
+        constant_pool: &class_file.constant_pool,
+        bootstrap_methods: class_file.bootstrap_methods(),
+        options: *options,
+        comments,
+    };
+    textifier.class(class_file);
+    textifier.out
+}
+
+/// The dump of one field of `class_file`, as [`textify`] prints it among
+/// the others.
+pub fn textify_field(class_file: &ClassFile, field: &FieldInfo) -> String {
+    let mut textifier = Textifier {
+        out: String::new(),
+        constant_pool: &class_file.constant_pool,
+        bootstrap_methods: class_file.bootstrap_methods(),
+        options: PrintOptions::full(),
+        comments: &|_| BTreeMap::new(),
+    };
+    textifier.field(field);
+    textifier.out
+}
+
+/// The dump of one method of `class_file`, with its code, as
+/// [`textify_with`] prints it among the others.
+pub fn textify_method(
+    class_file: &ClassFile,
+    method: &MethodInfo,
+    options: &PrintOptions,
+) -> String {
+    let mut textifier = Textifier {
+        out: String::new(),
+        constant_pool: &class_file.constant_pool,
+        bootstrap_methods: class_file.bootstrap_methods(),
+        options: *options,
+        comments: &|_| BTreeMap::new(),
+    };
+    textifier.method(method, class_file.access_flags.is_interface());
+    textifier.out
+}
+
+/// The column comments on instructions start at, unless the instruction
+/// runs past it.
+const COMMENT_COLUMN: usize = 48;
+
+struct Textifier<'a> {
+    out: String,
+    constant_pool: &'a [ConstantPool],
+    bootstrap_methods: &'a [BootstrapMethod],
+    options: PrintOptions,
+    comments: &'a dyn Fn(&MethodInfo) -> BTreeMap<u32, String>,
+}
+
+impl<'a> Textifier<'a> {
+    fn utf8(&self, index: u16) -> &'a str {
+        ConstantPool::utf8(self.constant_pool, index).unwrap_or("null")
+    }
+
+    fn class_name(&self, index: u16) -> &'a str {
+        ConstantPool::class_name(self.constant_pool, index).unwrap_or("null")
+    }
+
+    fn class(&mut self, class_file: &ClassFile) {
+        let attributes = &class_file.attributes;
+        let mut access = asm_access(class_file.access_flags.bits(), attributes);
+        if class_file.record_components().is_some() {
+            access |= ACC_RECORD;
+        }
+        let version = (class_file.minor_version as u32) << 16 | class_file.major_version as u32;
+
+        let _ = writeln!(
+            self.out,
+            "// class version {}.{} ({})",
+            class_file.major_version, class_file.minor_version, version
+        );
+        if access & ACC_DEPRECATED != 0 {
+            self.out.push_str("// DEPRECATED\n");
+        }
+        if access & ACC_RECORD != 0 {
+            self.out.push_str("// RECORD\n");
+        }
+        let _ = writeln!(self.out, "// access flags 0x{:X}", access);
+        self.signature("", attributes);
+        self.out.push_str(&access_modifiers(
+            access & !((ACC_SUPER | ACC_MODULE) as u32),
+        ));
+        if access & ACC_ANNOTATION as u32 != 0 {
+            self.out.push_str("@interface ");
+        } else if access & ACC_INTERFACE as u32 != 0 {
+            self.out.push_str("interface ");
+        } else if access & ACC_ENUM as u32 == 0 {
+            self.out.push_str("class ");
+        }
+        self.out.push_str(self.class_name(class_file.this_class));
+        let super_name = self.class_name(class_file.super_class);
+        if class_file.super_class != 0 && super_name != "java/lang/Object" {
+            let _ = write!(self.out, " extends {}", super_name);
+        }
+        if !class_file.interfaces.is_empty() {
+            let interfaces: Vec<_> = class_file
+                .interfaces
+                .iter()
+                .map(|&index| self.class_name(index))
+                .collect();
+            let _ = write!(self.out, " implements {}", interfaces.join(" "));
+        }
+        self.out.push_str(" {\n\n");
+
+        for attribute in attributes {
+            match attribute {
+                Attribute::SourceFile(source_file) => {
+                    let file = self.utf8(source_file.sourcefile_index);
+                    let _ = writeln!(self.out, "  // compiled from: {}", file);
+                }
+                Attribute::SourceDebugExtension(debug) => {
+                    let _ = writeln!(self.out, "  // debug info: {}", mutf8::decode_lossy(debug));
+                }
+                _ => {}
+            }
+        }
+        for attribute in attributes {
+            match attribute {
+                Attribute::NestHost(index) => {
+                    let _ = writeln!(self.out, "  NESTHOST {}", self.class_name(*index));
+                }
+                Attribute::EnclosingMethod(enclosing) => {
+                    let owner = self.class_name(enclosing.class_index);
+                    let _ = write!(self.out, "  OUTERCLASS {} ", owner);
+                    match self.name_and_type(enclosing.method_index) {
+                        Some((name, descriptor)) => {
+                            let _ = writeln!(self.out, "{} {}", name, descriptor);
+                        }
+                        None => self.out.push_str("null\n"),
+                    }
+                }
+                _ => {}
+            }
+        }
+        self.annotations("  ", attributes, true);
+        for attribute in attributes {
+            match attribute {
+                Attribute::NestMembers(members) => {
+                    for &member in members {
+                        let _ = writeln!(self.out, "  NESTMEMBER {}", self.class_name(member));
+                    }
+                }
+                Attribute::PermittedSubclasses(subclasses) => {
+                    for &subclass in subclasses {
+                        let name = self.class_name(subclass);
+                        let _ = writeln!(self.out, "  PERMITTEDSUBCLASS {}", name);
+                    }
+                }
+                _ => {}
+            }
+        }
+        if let Some(inner_classes) = class_file.inner_classes() {
+            for inner in &inner_classes.classes {
+                let access = inner.inner_class_access_flags.bits() & !ACC_SUPER;
+                let _ = writeln!(self.out, "  // access flags 0x{:X}", access);
+                let outer = self.class_name(inner.outer_class_info_index);
+                let inner_name = self.utf8(inner.inner_name_index);
+                let _ = writeln!(
+                    self.out,
+                    "  {}INNERCLASS {} {} {}",
+                    access_modifiers(inner.inner_class_access_flags.bits() as u32),
+                    self.class_name(inner.inner_class_info_index),
+                    outer,
+                    inner_name
+                );
+            }
+        }
+        if let Some(components) = class_file.record_components() {
+            for component in components {
+                self.out.push_str("  RECORDCOMPONENT ");
+                self.signature("  ", &component.attributes);
+                let resolved = component.resolved(self.constant_pool);
+                let _ = writeln!(self.out, "{} {}", resolved.descriptor, resolved.name);
+                for annotation in &resolved.visible_annotations {
+                    let annotation = self.annotation(annotation.annotation);
+                    let _ = writeln!(self.out, "  {}", annotation);
+                }
+                for annotation in &resolved.invisible_annotations {
+                    let annotation = self.annotation(annotation.annotation);
+                    let _ = writeln!(self.out, "  {} // invisible", annotation);
+                }
+            }
+        }
+
+        for field in &class_file.fields {
+            self.field(field);
+        }
+        let is_interface = class_file.access_flags.is_interface();
+        let shown = self
+            .options
+            .max_methods
+            .unwrap_or(usize::MAX)
+            .min(class_file.methods.len());
+        for method in &class_file.methods[..shown] {
+            self.method(method, is_interface);
+        }
+        if shown < class_file.methods.len() {
+            let _ = writeln!(
+                self.out,
+                "\n  // {}",
+                elided(class_file.methods.len() - shown, "methods")
+            );
+        }
+        self.out.push_str("}\n");
+    }
+
+    fn field(&mut self, field: &FieldInfo) {
+        let access = asm_access(field.access_flags().bits(), field.attributes());
+        self.out.push('\n');
+        if access & ACC_DEPRECATED != 0 {
+            self.out.push_str("  // DEPRECATED\n");
+        }
+        let _ = writeln!(self.out, "  // access flags 0x{:X}", access);
+        self.signature("  ", field.attributes());
+        let _ = write!(
+            self.out,
+            "  {}{} {}",
+            access_modifiers(access),
+            self.utf8(field.descriptor_index()),
+            self.utf8(field.name_index())
+        );
+        if let Some(index) = field.constant_value_index() {
+            let value = match self.constant(index) {
+                Some(ConstantPool::String(string_index)) => {
+                    let (value, rest) = self.truncated(self.utf8(*string_index));
+                    format!("\"{}\"{}", value, rest)
+                }
+                _ => self.loadable_constant(index),
+            };
+            let _ = write!(self.out, " = {}", value);
+        }
+        self.out.push('\n');
+        self.annotations("  ", field.attributes(), false);
+    }
+
+    fn method(&mut self, method: &MethodInfo, in_interface: bool) {
+        let access = asm_access(method.access_flags().bits(), method.attributes());
+        self.out.push('\n');
+        if access & ACC_DEPRECATED != 0 {
+            self.out.push_str("  // DEPRECATED\n");
+        }
+        let _ = writeln!(self.out, "  // access flags 0x{:X}", access);
+        self.signature("  ", method.attributes());
+        self.out.push_str("  ");
+        self.out.push_str(&access_modifiers(
+            access & !((ACC_VOLATILE | ACC_TRANSIENT) as u32),
+        ));
+        if access & ACC_NATIVE as u32 != 0 {
+            self.out.push_str("native ");
+        }
+        if access & ACC_VARARGS as u32 != 0 {
+            self.out.push_str("varargs ");
+        }
+        if access & ACC_BRIDGE as u32 != 0 {
+            self.out.push_str("bridge ");
+        }
+        if in_interface && access & (ACC_ABSTRACT | ACC_STATIC) as u32 == 0 {
+            self.out.push_str("default ");
+        }
+        self.out.push_str(self.utf8(method.name_index()));
+        self.out.push_str(self.utf8(method.descriptor_index()));
+        if let Some(exceptions) = method.exceptions() {
+            if !exceptions.exception_index_table.is_empty() {
+                self.out.push_str(" throws ");
+                for &exception in &exceptions.exception_index_table {
+                    let _ = write!(self.out, "{} ", self.class_name(exception));
+                }
+            }
+        }
+        self.out.push('\n');
+
+        for attribute in method.attributes() {
+            if let Attribute::MethodParameters(parameters) = attribute {
+                for parameter in parameters {
+                    let name = match parameter.name_index {
+                        0 => "<no name>",
+                        index => self.utf8(index),
+                    };
+                    let _ = writeln!(
+                        self.out,
+                        "    // parameter {} {}",
+                        access_modifiers(parameter.access_flags.bits() as u32),
+                        name
+                    );
+                }
+            }
+        }
+        for attribute in method.attributes() {
+            if let Attribute::AnnotationDefault(value) = attribute {
+                let value = self.element_value(value);
+                let _ = writeln!(self.out, "    default={}", value);
+            }
+        }
+        self.annotations("  ", method.attributes(), false);
+        for attribute in method.attributes() {
+            let (parameters, visible) = match attribute {
+                Attribute::RuntimeVisibleParameterAnnotations(parameters) => (parameters, true),
+                Attribute::RuntimeInvisibleParameterAnnotations(parameters) => (parameters, false),
+                _ => continue,
+            };
+            for (i, parameter) in parameters.iter().enumerate() {
+                for annotation in &parameter.annotations {
+                    let annotation = self.annotation(annotation);
+                    let comment = if visible { "" } else { "invisible, " };
+                    let _ = writeln!(self.out, "    {} // {}parameter {}", annotation, comment, i);
+                }
+            }
+        }
+
+        if let Some(code) = method.code() {
+            let comments = (self.comments)(method);
+            self.code(code, &comments);
+        }
+    }
+
+    fn code(&mut self, code: &Code, comments: &BTreeMap<u32, String>) {
+        let instructions: Vec<_> = instruction::decode(&code.code).collect();
+        let code_length = code.code.len() as u32;
+
+        let mut targets = BTreeSet::new();
+        for instruction in instructions.iter().flatten() {
+            targets.extend(
+                instruction
+                    .branch_targets()
+                    .into_iter()
+                    .filter_map(|target| u32::try_from(target).ok()),
+            );
+        }
+        for exception in &code.exception_table {
+            targets.extend(
+                [exception.start_pc, exception.end_pc, exception.handler_pc].map(u32::from),
+            );
+        }
+        let mut line_numbers: BTreeMap<u32, Vec<u16>> = BTreeMap::new();
+        let mut local_variable_types = HashMap::new();
+        let mut frames: BTreeMap<u32, Vec<&StackMapFrame>> = BTreeMap::new();
+        for attribute in &code.attributes {
+            match attribute {
+                Attribute::LineNumberTable(table) => {
+                    for line in table {
+                        line_numbers
+                            .entry(line.start_pc as u32)
+                            .or_default()
+                            .push(line.line_number);
+                    }
+                }
+                Attribute::LocalVariableTable(table) => {
+                    for variable in table {
+                        targets.insert(variable.start_pc as u32);
+                        targets.insert(variable.start_pc as u32 + variable.length as u32);
+                    }
+                }
+                Attribute::LocalVariableTypeTable(table) => {
+                    for variable in table {
+                        targets.insert(variable.start_pc as u32);
+                        targets.insert(variable.start_pc as u32 + variable.length as u32);
+                        local_variable_types.insert(
+                            (variable.start_pc, variable.index),
+                            variable.signature_index,
+                        );
+                    }
+                }
+                Attribute::StackMapTable(table) => {
+                    let mut offset = None;
+                    for frame in &table.entries {
+                        let next = match offset {
+                            None => frame.offset_delta() as u32,
+                            Some(previous) => previous + frame.offset_delta() as u32 + 1,
+                        };
+                        offset = Some(next);
+                        targets.insert(next);
+                        targets.extend(uninitialized_offsets(frame));
+                        frames.entry(next).or_default().push(frame);
+                    }
+                }
+                _ => {}
+            }
+        }
+        targets.extend(line_numbers.keys());
+
+        let mut labels = Labels::default();
+        for exception in &code.exception_table {
+            let catch_type = match exception.catch_type {
+                0 => "null",
+                index => self.class_name(index),
+            };
+            let _ = writeln!(
+                self.out,
+                "    TRYCATCHBLOCK {} {} {} {}",
+                labels.name(exception.start_pc as u32),
+                labels.name(exception.end_pc as u32),
+                labels.name(exception.handler_pc as u32),
+                catch_type
+            );
+        }
+
+        // with a limit, the instructions in [head, tail) are left out
+        let count = instructions.len();
+        let (head, tail) = match self.options.max_instructions_per_method {
+            Some(max) if count > max => (max - max / 2, count - max / 2),
+            _ => (count, count),
+        };
+        let label_at = |out: &mut String, labels: &mut Labels, offset: u32| {
+            if !targets.contains(&offset) {
+                return;
+            }
+            let _ = writeln!(out, "   {}", labels.name(offset));
+            for line in line_numbers.get(&offset).into_iter().flatten() {
+                let _ = writeln!(out, "    LINENUMBER {} {}", line, labels.name(offset));
+            }
+        };
+        for (i, instruction) in instructions.iter().enumerate() {
+            if (head..tail).contains(&i) {
+                if i == head {
+                    let _ = writeln!(self.out, "    // {}", elided(tail - head, "instructions"));
+                }
+                continue;
+            }
+            let instruction = match instruction {
+                Ok(instruction) => instruction,
+                Err(e) => {
+                    let _ = writeln!(self.out, "    // {}", e);
+                    break;
+                }
+            };
+            label_at(&mut self.out, &mut labels, instruction.offset);
+            for frame in frames.get(&instruction.offset).into_iter().flatten() {
+                let frame = self.frame(frame, &mut labels);
+                let _ = writeln!(self.out, "    {}", frame);
+            }
+            self.instruction(instruction, &mut labels);
+            if let Some(comment) = comments.get(&instruction.offset) {
+                self.out.pop();
+                let line_start = self.out.rfind('\n').map_or(0, |i| i + 1);
+                let width = self.out[line_start..].chars().count();
+                let _ = writeln!(
+                    self.out,
+                    "{:pad$} // {}",
+                    "",
+                    comment,
+                    pad = COMMENT_COLUMN.saturating_sub(width)
+                );
+            }
+        }
+        label_at(&mut self.out, &mut labels, code_length);
+
+        for attribute in &code.attributes {
+            if let Attribute::LocalVariableTable(table) = attribute {
+                for variable in table {
+                    let start = variable.start_pc as u32;
+                    let _ = writeln!(
+                        self.out,
+                        "    LOCALVARIABLE {} {} {} {} {}",
+                        self.utf8(variable.name_index),
+                        self.utf8(variable.descriptor_index),
+                        labels.name(start),
+                        labels.name(start + variable.length as u32),
+                        variable.index
+                    );
+                    if let Some(&signature) =
+                        local_variable_types.get(&(variable.start_pc, variable.index))
+                    {
+                        let _ = writeln!(self.out, "    // signature {}", self.utf8(signature));
+                    }
+                }
+            }
+        }
+        let _ = writeln!(self.out, "    MAXSTACK = {}", code.max_stack);
+        let _ = writeln!(self.out, "    MAXLOCALS = {}", code.max_locals);
+    }
+
+    fn instruction(&mut self, instruction: &Instruction, labels: &mut Labels) {
+        let opcode = instruction.opcode;
+        let operands = instruction.operands;
+        let mnemonic = instruction.mnemonic().to_uppercase();
+        let u16_operand = || u16::from_be_bytes([operands[0], operands[1]]);
+        let text = match opcode {
+            // bipush, sipush
+            0x10 => format!("BIPUSH {}", operands[0] as i8),
+            0x11 => format!("SIPUSH {}", u16_operand() as i16),
+            // ldc, ldc_w, ldc2_w
+            0x12..=0x14 => {
+                let index = instruction.cp_index().unwrap_or(0);
+                format!("LDC {}", self.ldc_constant(index))
+            }
+            // iload..aload, istore..astore, ret
+            0x15..=0x19 | 0x36..=0x3a | 0xa9 => format!("{} {}", mnemonic, operands[0]),
+            // iload_0..aload_3
+            0x1a..=0x2d => {
+                let kind =
+                    ["ILOAD", "LLOAD", "FLOAD", "DLOAD", "ALOAD"][(opcode - 0x1a) as usize / 4];
+                format!("{} {}", kind, (opcode - 0x1a) % 4)
+            }
+            // istore_0..astore_3
+            0x3b..=0x4e => {
+                let kind = ["ISTORE", "LSTORE", "FSTORE", "DSTORE", "ASTORE"]
+                    [(opcode - 0x3b) as usize / 4];
+                format!("{} {}", kind, (opcode - 0x3b) % 4)
+            }
+            0x84 => format!("IINC {} {}", operands[0], operands[1] as i8),
+            // if*, goto, jsr, ifnull, ifnonnull, goto_w, jsr_w
+            0x99..=0xa8 | 0xc6..=0xc9 => {
+                let mnemonic = match opcode {
+                    0xc8 => "GOTO".to_string(),
+                    0xc9 => "JSR".to_string(),
+                    _ => mnemonic,
+                };
+                let target = instruction.branch_targets()[0];
+                format!("{} {}", mnemonic, labels.target(target))
+            }
+            TABLESWITCH | LOOKUPSWITCH => {
+                let mut text = format!("{}\n", mnemonic);
+                let pad = (3 - instruction.offset as usize % 4) % 4;
+                let word = |i: usize| read_i32(operands, pad + i * 4);
+                let targets = instruction.branch_targets();
+                let keys: Vec<i32> = if opcode == TABLESWITCH {
+                    (word(1)..=word(2)).collect()
+                } else {
+                    (0..word(1) as usize).map(|i| word(2 + i * 2)).collect()
+                };
+                for (key, &target) in keys.iter().zip(&targets[1..]) {
+                    let _ = writeln!(text, "      {}: {}", key, labels.target(target));
+                }
+                let _ = write!(text, "      default: {}", labels.target(targets[0]));
+                text
+            }
+            // getstatic, putstatic, getfield, putfield
+            0xb2..=0xb5 => {
+                let index = instruction.cp_index().unwrap_or(0);
+                match ConstantPool::member_ref(self.constant_pool, index) {
+                    Some(member) => format!(
+                        "{} {}.{} : {}",
+                        mnemonic, member.class_name, member.name, member.descriptor
+                    ),
+                    None => format!("{} #{}", mnemonic, index),
+                }
+            }
+            // invokevirtual, invokespecial, invokestatic, invokeinterface
+            0xb6..=0xb9 => {
+                let index = instruction.cp_index().unwrap_or(0);
+                match ConstantPool::member_ref(self.constant_pool, index) {
+                    Some(member) => {
+                        let is_interface = matches!(
+                            self.constant(index),
+                            Some(ConstantPool::InterfaceMethodRef(..))
+                        );
+                        format!(
+                            "{} {}.{} {}{}",
+                            mnemonic,
+                            member.class_name,
+                            member.name,
+                            member.descriptor,
+                            if is_interface && opcode != 0xb9 {
+                                " (itf)"
+                            } else {
+                                ""
+                            }
+                        )
+                    }
+                    None => format!("{} #{}", mnemonic, index),
+                }
+            }
+            0xba => self.invokedynamic(instruction.cp_index().unwrap_or(0)),
+            // new, anewarray, checkcast, instanceof
+            0xbb | 0xbd | 0xc0 | 0xc1 => {
+                let index = instruction.cp_index().unwrap_or(0);
+                format!("{} {}", mnemonic, self.class_name(index))
+            }
+            0xbc => {
+                let kind = NEWARRAY_TYPES.get(operands[0] as usize).copied();
+                format!("NEWARRAY {}", kind.unwrap_or("?"))
+            }
+            0xc5 => {
+                let index = instruction.cp_index().unwrap_or(0);
+                format!("MULTIANEWARRAY {} {}", self.class_name(index), operands[2])
+            }
+            WIDE => {
+                let index = u16::from_be_bytes([operands[1], operands[2]]);
+                match operands[0] {
+                    instruction::IINC => {
+                        let increment = i16::from_be_bytes([operands[3], operands[4]]);
+                        format!("IINC {} {}", index, increment)
+                    }
+                    opcode => {
+                        let mnemonic = instruction::mnemonic(opcode).unwrap_or("<invalid>");
+                        format!("{} {}", mnemonic.to_uppercase(), index)
+                    }
+                }
+            }
+            _ => mnemonic,
+        };
+        let _ = writeln!(self.out, "    {}", text);
+    }
+
+    fn invokedynamic(&self, index: u16) -> String {
+        let (bootstrap_index, name_and_type_index) = match self.constant(index) {
+            Some(ConstantPool::InvokeDynamic(bootstrap, name_and_type)) => {
+                (*bootstrap, *name_and_type)
+            }
+            _ => return format!("INVOKEDYNAMIC #{}", index),
+        };
+        let (name, descriptor) = self
+            .name_and_type(name_and_type_index)
+            .unwrap_or(("null", "null"));
+        let mut text = format!("INVOKEDYNAMIC {}{} [\n", name, descriptor);
+        let bootstrap = match self.bootstrap_methods.get(bootstrap_index as usize) {
+            Some(bootstrap) => bootstrap,
+            None => {
+                let _ = write!(
+                    text,
+                    "      // bootstrap method #{}\n    ]",
+                    bootstrap_index
+                );
+                return text;
+            }
+        };
+        let _ = writeln!(
+            text,
+            "      {}",
+            self.handle(bootstrap.bootstrap_method_ref)
+        );
+        text.push_str("      // arguments:");
+        if bootstrap.bootstrap_arguments.is_empty() {
+            text.push_str(" none");
+        } else {
+            let arguments: Vec<_> = bootstrap
+                .bootstrap_arguments
+                .iter()
+                .map(|&argument| match self.constant(argument) {
+                    Some(ConstantPool::MethodHandle(..)) => self.handle(argument),
+                    _ => self.loadable_constant(argument),
+                })
+                .map(|argument| format!("      {}", argument))
+                .collect();
+            text.push('\n');
+            text.push_str(&arguments.join(", \n"));
+        }
+        text.push_str("\n    ]");
+        text
+    }
+
+    /// A method handle as Textifier prints bootstrap methods and arguments:
+    /// a comment naming its kind, then the member it refers to.
+    fn handle(&self, index: u16) -> String {
+        let (kind, reference_index) = match self.constant(index) {
+            Some(ConstantPool::MethodHandle(kind, reference_index)) => (*kind, *reference_index),
+            _ => return format!("#{}", index),
+        };
+        let mut text = format!(
+            "// handle kind 0x{:x} : {}\n      ",
+            kind,
+            HANDLE_KINDS.get(kind as usize).copied().unwrap_or("?")
+        );
+        if let Some(member) = ConstantPool::member_ref(self.constant_pool, reference_index) {
+            let is_method = kind >= 5;
+            let _ = write!(text, "{}.{}", member.class_name, member.name);
+            if is_method {
+                text.push_str(member.descriptor);
+            } else {
+                let _ = write!(text, "({})", member.descriptor);
+            }
+        }
+        if matches!(
+            self.constant(reference_index),
+            Some(ConstantPool::InterfaceMethodRef(..))
+        ) {
+            text.push_str(" itf");
+        }
+        text
+    }
+
+    /// The operand of an `LDC`, which prints method handles the way ASM's
+    /// `Handle.toString` does.
+    fn ldc_constant(&self, index: u16) -> String {
+        match self.constant(index) {
+            Some(ConstantPool::MethodHandle(kind, reference_index)) => {
+                match ConstantPool::member_ref(self.constant_pool, *reference_index) {
+                    Some(member) => {
+                        let itf = matches!(
+                            self.constant(*reference_index),
+                            Some(ConstantPool::InterfaceMethodRef(..))
+                        );
+                        format!(
+                            "{}.{}{} ({}{})",
+                            member.class_name,
+                            member.name,
+                            member.descriptor,
+                            kind,
+                            if itf { " itf" } else { "" }
+                        )
+                    }
+                    None => format!("#{}", index),
+                }
+            }
+            _ => self.loadable_constant(index),
+        }
+    }
+
+    /// A loadable constant other than a method handle, rendered the way
+    /// Textifier renders the corresponding Java object.
+    fn loadable_constant(&self, index: u16) -> String {
+        match self.constant(index) {
+            Some(ConstantPool::String(string_index)) => self.string(self.utf8(*string_index)),
+            Some(ConstantPool::Integer(value)) => value.to_string(),
+            Some(ConstantPool::Long(value)) => value.to_string(),
+            Some(ConstantPool::Float(bits)) => java_float(bits.value()),
+            Some(ConstantPool::Double(bits)) => java_double(bits.value()),
+            Some(ConstantPool::Class(name_index)) => {
+                let name = self.utf8(*name_index);
+                if name.starts_with('[') {
+                    format!("{}.class", name)
+                } else {
+                    format!("L{};.class", name)
+                }
+            }
+            Some(ConstantPool::MethodType(descriptor_index)) => {
+                self.utf8(*descriptor_index).to_string()
+            }
+            Some(ConstantPool::Dynamic(bootstrap_index, name_and_type_index)) => {
+                let (name, descriptor) = self
+                    .name_and_type(*name_and_type_index)
+                    .unwrap_or(("null", "null"));
+                format!("{} : {} #{}", name, descriptor, bootstrap_index)
+            }
+            _ => format!("#{}", index),
+        }
+    }
+
+    fn frame(&self, frame: &StackMapFrame, labels: &mut Labels) -> String {
+        let mut types = |types: &[VerificationTypeInfo]| {
+            types
+                .iter()
+                .map(|info| self.verification_type(info, labels))
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        match frame {
+            StackMapFrame::SameFrame(_) | StackMapFrame::SameFrameExtended(_) => {
+                "FRAME SAME".to_string()
+            }
+            StackMapFrame::SameLocals1StackItemFrame { stack, .. }
+            | StackMapFrame::SameLocals1StackItemFrameExtended { stack, .. } => {
+                format!("FRAME SAME1 {}", types(std::slice::from_ref(stack)))
+            }
+            StackMapFrame::ChopFrame { chopped, .. } => format!("FRAME CHOP {}", chopped),
+            StackMapFrame::AppendFrame { locals, .. } => {
+                format!("FRAME APPEND [{}]", types(locals))
+            }
+            StackMapFrame::FullFrame { locals, stack, .. } => {
+                format!("FRAME FULL [{}] [{}]", types(locals), types(stack))
+            }
+        }
+    }
+
+    fn verification_type(&self, info: &VerificationTypeInfo, labels: &mut Labels) -> String {
+        match info {
+            VerificationTypeInfo::TopVariableInfo => "T".to_string(),
+            VerificationTypeInfo::IntegerVariableInfo => "I".to_string(),
+            VerificationTypeInfo::FloatVariableInfo => "F".to_string(),
+            VerificationTypeInfo::DoubleVariableInfo => "D".to_string(),
+            VerificationTypeInfo::LongVariableInfo => "J".to_string(),
+            VerificationTypeInfo::NullVariableInfo => "N".to_string(),
+            VerificationTypeInfo::UninitializedThisVariableInfo => "U".to_string(),
+            VerificationTypeInfo::ObjectVariableInfo(index) => self.class_name(*index).to_string(),
+            VerificationTypeInfo::UninitializedVariableInfo(offset) => labels.name(*offset as u32),
+        }
+    }
+
+    /// Prints the `Signature` attribute, if any, as a comment line.
+    fn signature(&mut self, indent: &str, attributes: &[Attribute]) {
+        if let Some(signature) = Attribute::signature(attributes) {
+            let signature = self.utf8(signature.signature_index);
+            let _ = writeln!(self.out, "{}// signature {}", indent, signature);
+        }
+    }
+
+    /// Prints visible annotations, then invisible ones, each on its own line.
+    fn annotations(&mut self, indent: &str, attributes: &[Attribute], blank_line: bool) {
+        for visible in [true, false] {
+            for attribute in attributes {
+                let annotations = match attribute {
+                    Attribute::RuntimeVisibleAnnotations(annotations) if visible => annotations,
+                    Attribute::RuntimeInvisibleAnnotations(annotations) if !visible => annotations,
+                    _ => continue,
+                };
+                for annotation in annotations {
+                    if blank_line {
+                        self.out.push('\n');
+                    }
+                    let annotation = self.annotation(annotation);
+                    let comment = if visible { "" } else { " // invisible" };
+                    let _ = writeln!(self.out, "{}{}{}", indent, annotation, comment);
+                }
+            }
+        }
+    }
+
+    fn annotation(&self, annotation: &Annotation) -> String {
+        let values: Vec<_> = annotation
+            .element_value_pairs
+            .iter()
+            .map(|(name_index, value)| {
+                format!("{}={}", self.utf8(*name_index), self.element_value(value))
+            })
+            .collect();
+        format!(
+            "@{}({})",
+            self.utf8(annotation.type_index),
+            values.join(", ")
+        )
+    }
+
+    fn element_value(&self, value: &ElementValue) -> String {
+        match value {
+            ElementValue::ConstValue {
+                tag,
+                const_value_index: index,
+            } => {
+                let int = || match self.constant(*index) {
+                    Some(ConstantPool::Integer(value)) => *value,
+                    _ => 0,
+                };
+                match tag {
+                    b'B' => format!("(byte){}", int() as i8),
+                    b'C' => format!("(char){}", int() as u16),
+                    b'S' => format!("(short){}", int() as i16),
+                    b'Z' => (int() != 0).to_string(),
+                    b'I' => int().to_string(),
+                    b'J' => format!("{}L", self.loadable_constant(*index)),
+                    b'F' => format!("{}F", self.loadable_constant(*index)),
+                    b'D' => format!("{}D", self.loadable_constant(*index)),
+                    _ => self.string(self.utf8(*index)),
+                }
+            }
+            ElementValue::EnumConstValue {
+                type_name_index,
+                const_name_index,
+            } => format!(
+                "{}.{}",
+                self.utf8(*type_name_index),
+                self.utf8(*const_name_index)
+            ),
+            ElementValue::ClassInfoIndex(index) => format!("{}.class", self.utf8(*index)),
+            ElementValue::AnnotationValue(annotation) => self.annotation(annotation),
+            ElementValue::ArrayValue(values) => {
+                let values: Vec<_> = values
+                    .iter()
+                    .map(|value| self.element_value(value))
+                    .collect();
+                format!("{{{}}}", values.join(", "))
+            }
+        }
+    }
+
+    /// `value` quoted, cut short to the string length limit.
+    fn string(&self, value: &str) -> String {
+        let (value, rest) = self.truncated(value);
+        java_string(value) + &rest
+    }
+
+    /// The part of `value` within the string length limit, and the marker to
+    /// follow it with when that's not all of it.
+    fn truncated<'s>(&self, value: &'s str) -> (&'s str, String) {
+        let max = match self.options.max_string_len {
+            Some(max) => max,
+            None => return (value, String::new()),
+        };
+        match value.char_indices().nth(max) {
+            Some((end, _)) => (
+                &value[..end],
+                format!(" {}", elided(value[end..].chars().count(), "chars")),
+            ),
+            None => (value, String::new()),
+        }
+    }
+
+    fn constant(&self, index: u16) -> Option<&'a ConstantPool> {
+        self.constant_pool.get((index as usize).checked_sub(1)?)
+    }
+
+    fn name_and_type(&self, index: u16) -> Option<(&'a str, &'a str)> {
+        match self.constant(index)? {
+            ConstantPool::NameAndType(name_index, descriptor_index) => {
+                Some((self.utf8(*name_index), self.utf8(*descriptor_index)))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Label names handed out in order of first mention, as Textifier does.
+#[derive(Default)]
+struct Labels {
+    names: HashMap<u32, usize>,
+}
+
+impl Labels {
+    fn name(&mut self, offset: u32) -> String {
+        let next = self.names.len();
+        format!("L{}", self.names.entry(offset).or_insert(next))
+    }
+
+    /// The label of a branch target, or the target itself when it's before
+    /// the start of the code, where no label can be.
+    fn target(&mut self, target: i64) -> String {
+        match u32::try_from(target) {
+            Ok(offset) => self.name(offset),
+            Err(_) => target.to_string(),
+        }
+    }
+}
+
+/// The access flags as ASM reports them, with the `Deprecated` and
+/// `Synthetic` attributes folded in.
+fn asm_access(access_flags: u16, attributes: &[Attribute]) -> u32 {
+    let mut access = access_flags as u32;
+    if Attribute::has_synthetic(attributes) {
+        access |= ACC_SYNTHETIC as u32;
+    }
+    if Attribute::has_deprecated(attributes) {
+        access |= ACC_DEPRECATED;
+    }
+    access
+}
+
+/// The modifiers Textifier prints for `access`, each followed by a space.
+fn access_modifiers(access: u32) -> String {
+    [
+        (ACC_PUBLIC, "public "),
+        (ACC_PRIVATE, "private "),
+        (ACC_PROTECTED, "protected "),
+        (ACC_FINAL, "final "),
+        (ACC_STATIC, "static "),
+        (ACC_SYNCHRONIZED, "synchronized "),
+        (ACC_VOLATILE, "volatile "),
+        (ACC_TRANSIENT, "transient "),
+        (ACC_ABSTRACT, "abstract "),
+        (ACC_STRICT, "strictfp "),
+        (ACC_SYNTHETIC, "synthetic "),
+        (ACC_MODULE, "mandated "),
+        (ACC_ENUM, "enum "),
+    ]
+    .iter()
+    .filter(|(flag, _)| access & *flag as u32 != 0)
+    .map(|(_, modifier)| *modifier)
+    .collect()
+}
+
+/// The marker for `count` things left out, e.g. `… 12,345 instructions
+/// elided …`.
+fn elided(count: usize, things: &str) -> String {
+    let digits = count.to_string();
+    let mut grouped = String::new();
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    format!("… {} {} elided …", grouped, things)
+}
+
+/// Offsets of the `new` instructions named by uninitialized types in `frame`.
+fn uninitialized_offsets(frame: &StackMapFrame) -> Vec<u32> {
+    let types: Vec<&VerificationTypeInfo> = match frame {
+        StackMapFrame::SameLocals1StackItemFrame { stack, .. }
+        | StackMapFrame::SameLocals1StackItemFrameExtended { stack, .. } => vec![stack],
+        StackMapFrame::AppendFrame { locals, .. } => locals.iter().collect(),
+        StackMapFrame::FullFrame { locals, stack, .. } => locals.iter().chain(stack).collect(),
+        _ => Vec::new(),
+    };
+    types
+        .into_iter()
+        .filter_map(|info| match info {
+            VerificationTypeInfo::UninitializedVariableInfo(offset) => Some(*offset as u32),
+            _ => None,
+        })
+        .collect()
+}
+
+fn read_i32(buf: &[u8], at: usize) -> i32 {
+    i32::from_be_bytes([buf[at], buf[at + 1], buf[at + 2], buf[at + 3]])
+}
+
+/// Quotes a string the way ASM's `Printer.appendString` does, escaping
+/// UTF-16 code units outside printable ASCII as `\uXXXX`.
+fn java_string(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for unit in value.encode_utf16() {
+        match unit {
+            0x0a => out.push_str("\\n"),
+            0x0d => out.push_str("\\r"),
+            0x5c => out.push_str("\\\\"),
+            0x22 => out.push_str("\\\""),
+            0x20..=0x7f => out.push(unit as u8 as char),
+            _ => {
+                let _ = write!(out, "\\u{:04x}", unit);
+            }
+        }
+    }
+    out.push('"');
+    out
+}
+
+pub(crate) fn java_float(value: f32) -> String {
+    java_decimal(value.is_nan(), value.is_infinite(), &java_digits(value))
+}
+
+pub(crate) fn java_double(value: f64) -> String {
+    java_decimal(value.is_nan(), value.is_infinite(), &java_digits(value))
+}
+
+/// `value` in Rust's `{:e}` notation, in the fewest digits which read back
+/// as `value` but, as `Double.toString` and `Float.toString` pick them,
+/// never fewer than two: one digit gives way to the two-digit decimal
+/// closest to `value`, so that `Double.MIN_VALUE`, which `5e-324` reads back
+/// as, is 4.9E-324 rather than 5.0E-324.
+fn java_digits<T: LowerExp + FromStr + PartialEq>(value: T) -> String {
+    let shortest = format!("{:e}", value);
+    let mantissa = shortest.trim_start_matches('-').split('e').next();
+    if mantissa.is_some_and(|mantissa| mantissa.len() == 1) {
+        let closest = format!("{:.1e}", value);
+        if closest.parse::<T>().is_ok_and(|parsed| parsed == value) {
+            return closest;
+        }
+    }
+    shortest
+}
+
+/// Reformats the digits in `exponential` (Rust's `{:e}` output) into Java's
+/// `Double.toString` notation: plain decimals for magnitudes in
+/// [10^-3, 10^7), and `d.dddE±n` outside that range.
+fn java_decimal(is_nan: bool, is_infinite: bool, exponential: &str) -> String {
+    let (negative, exponential) = match exponential.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, exponential),
+    };
+    let sign = if negative { "-" } else { "" };
+    if is_nan {
+        return "NaN".to_string();
+    }
+    if is_infinite {
+        return format!("{}Infinity", sign);
+    }
+
+    let (mantissa, exponent) = exponential.split_once('e').unwrap_or((exponential, "0"));
+    let digits = mantissa.replace('.', "");
+    // the zero of a two-digit 1.0 is the fraction javap adds anyway
+    let digits = match digits.trim_end_matches('0') {
+        "" => "0",
+        digits => digits,
+    };
+    let exponent: i32 = exponent.parse().unwrap_or(0);
+    let (first, rest) = digits.split_at(1);
+    let rest = if rest.is_empty() { "0" } else { rest };
+
+    if digits == "0" || (-3..7).contains(&exponent) {
+        if exponent < 0 {
+            let zeros = "0".repeat((-exponent - 1) as usize);
+            format!("{}0.{}{}", sign, zeros, digits)
+        } else {
+            let point = exponent as usize + 1;
+            let padded = format!("{:0<width$}", digits, width = point);
+            let (integer, fraction) = padded.split_at(point);
+            let fraction = if fraction.is_empty() { "0" } else { fraction };
+            format!("{}{}.{}", sign, integer, fraction)
+        }
+    } else {
+        format!("{}{}.{}E{}", sign, first, rest, exponent)
+    }
+}
@@ -0,0 +1,370 @@
+//! A reader for the part of TOML configuration files need: tables, dotted
+//! keys, strings, integers, booleans and arrays of those. Floats, dates,
+//! inline tables and arrays of tables are rejected as unsupported.
+
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TomlError {
+    /// 1-based.
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for TomlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for TomlError {}
+
+/// A TOML value. Tables keep their keys in the order they were written.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Toml {
+    String(String),
+    Integer(i64),
+    Boolean(bool),
+    Array(Vec<Toml>),
+    Table(Vec<(String, Toml)>),
+}
+
+impl Toml {
+    /// Parses a whole document into its root table.
+    pub fn parse(text: &str) -> Result<Self, TomlError> {
+        let mut parser = Parser {
+            chars: text.chars().collect(),
+            pos: 0,
+            line: 1,
+        };
+        let mut root = Vec::new();
+        let mut headers: Vec<Vec<String>> = Vec::new();
+        let mut current = Vec::new();
+        loop {
+            parser.skip_blank_lines();
+            match parser.peek() {
+                None => break,
+                Some('[') => {
+                    parser.pos += 1;
+                    if parser.peek() == Some('[') {
+                        return Err(parser.error("arrays of tables are not supported"));
+                    }
+                    parser.skip_spaces();
+                    let path = parser.key()?;
+                    parser.skip_spaces();
+                    parser.expect(']')?;
+                    if headers.contains(&path) {
+                        return Err(
+                            parser.error(format!("table [{}] defined twice", path.join(".")))
+                        );
+                    }
+                    table_mut(&mut root, &path).map_err(|message| parser.error(message))?;
+                    headers.push(path.clone());
+                    current = path;
+                }
+                Some(_) => {
+                    let key = parser.key()?;
+                    parser.skip_spaces();
+                    parser.expect('=')?;
+                    parser.skip_spaces();
+                    let value = parser.value()?;
+                    let (name, parents) = key.split_last().expect("keys have a part");
+                    let path: Vec<String> = current.iter().chain(parents).cloned().collect();
+                    let table =
+                        table_mut(&mut root, &path).map_err(|message| parser.error(message))?;
+                    if table.iter().any(|(existing, _)| existing == name) {
+                        let full: Vec<&str> =
+                            path.iter().chain([name]).map(String::as_str).collect();
+                        return Err(parser.error(format!("key {} defined twice", full.join("."))));
+                    }
+                    table.push((name.clone(), value));
+                }
+            }
+            parser.end_of_line()?;
+        }
+        Ok(Toml::Table(root))
+    }
+
+    /// What kind of value this is, for error messages.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Toml::String(_) => "string",
+            Toml::Integer(_) => "integer",
+            Toml::Boolean(_) => "boolean",
+            Toml::Array(_) => "array",
+            Toml::Table(_) => "table",
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Toml> {
+        match self {
+            Toml::Table(entries) => entries
+                .iter()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+}
+
+/// The table at `path` below `root`, created along with any missing parent.
+fn table_mut<'a>(
+    root: &'a mut Vec<(String, Toml)>,
+    path: &[String],
+) -> Result<&'a mut Vec<(String, Toml)>, String> {
+    let mut table = root;
+    for (depth, name) in path.iter().enumerate() {
+        let position = match table.iter().position(|(existing, _)| existing == name) {
+            Some(position) => position,
+            None => {
+                table.push((name.clone(), Toml::Table(Vec::new())));
+                table.len() - 1
+            }
+        };
+        table = match &mut table[position].1 {
+            Toml::Table(entries) => entries,
+            value => {
+                return Err(format!(
+                    "{} is of type {}, not a table",
+                    path[..=depth].join("."),
+                    value.type_name()
+                ))
+            }
+        };
+    }
+    Ok(table)
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+}
+
+impl Parser {
+    fn error(&self, message: impl Into<String>) -> TomlError {
+        TomlError {
+            line: self.line,
+            message: message.into(),
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        if c == '\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), TomlError> {
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.next();
+                Ok(())
+            }
+            Some(c) => Err(self.error(format!("expected '{}', found '{}'", expected, c))),
+            None => Err(self.error(format!(
+                "expected '{}', found the end of the file",
+                expected
+            ))),
+        }
+    }
+
+    fn skip_spaces(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t')) {
+            self.pos += 1;
+        }
+    }
+
+    fn skip_comment(&mut self) {
+        if self.peek() == Some('#') {
+            while !matches!(self.peek(), None | Some('\n')) {
+                self.pos += 1;
+            }
+        }
+    }
+
+    /// Skips whitespace, comments and newlines, as between entries and the
+    /// values of an array.
+    fn skip_blank_lines(&mut self) {
+        loop {
+            self.skip_spaces();
+            self.skip_comment();
+            match self.peek() {
+                Some('\n') => {
+                    self.next();
+                }
+                Some('\r') if self.chars.get(self.pos + 1) == Some(&'\n') => self.pos += 1,
+                _ => return,
+            }
+        }
+    }
+
+    fn end_of_line(&mut self) -> Result<(), TomlError> {
+        self.skip_spaces();
+        self.skip_comment();
+        if self.peek() == Some('\r') {
+            self.pos += 1;
+        }
+        match self.next() {
+            None | Some('\n') => Ok(()),
+            Some(c) => Err(self.error(format!("expected the end of the line, found '{}'", c))),
+        }
+    }
+
+    /// A key of one or more dotted parts, each bare or quoted.
+    fn key(&mut self) -> Result<Vec<String>, TomlError> {
+        let mut parts = Vec::new();
+        loop {
+            let part = match self.peek() {
+                Some('"') => self.basic_string()?,
+                Some('\'') => self.literal_string()?,
+                _ => {
+                    let start = self.pos;
+                    while self
+                        .peek()
+                        .is_some_and(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+                    {
+                        self.pos += 1;
+                    }
+                    if self.pos == start {
+                        return Err(match self.peek() {
+                            Some(c) => self.error(format!("expected a key, found '{}'", c)),
+                            None => self.error("expected a key"),
+                        });
+                    }
+                    self.chars[start..self.pos].iter().collect()
+                }
+            };
+            parts.push(part);
+            self.skip_spaces();
+            if self.peek() != Some('.') {
+                return Ok(parts);
+            }
+            self.pos += 1;
+            self.skip_spaces();
+        }
+    }
+
+    fn value(&mut self) -> Result<Toml, TomlError> {
+        match self.peek() {
+            Some('"') => Ok(Toml::String(self.basic_string()?)),
+            Some('\'') => Ok(Toml::String(self.literal_string()?)),
+            Some('[') => {
+                self.pos += 1;
+                let mut values = Vec::new();
+                loop {
+                    self.skip_blank_lines();
+                    if self.peek() == Some(']') {
+                        break;
+                    }
+                    values.push(self.value()?);
+                    self.skip_blank_lines();
+                    match self.peek() {
+                        Some(',') => self.pos += 1,
+                        Some(']') => break,
+                        _ => return Err(self.error("expected ',' or ']' in array")),
+                    }
+                }
+                self.pos += 1;
+                Ok(Toml::Array(values))
+            }
+            Some('{') => Err(self.error("inline tables are not supported")),
+            Some(_) => {
+                let start = self.pos;
+                while self
+                    .peek()
+                    .is_some_and(|c| !matches!(c, ' ' | '\t' | '\r' | '\n' | '#' | ',' | ']'))
+                {
+                    self.pos += 1;
+                }
+                let word: String = self.chars[start..self.pos].iter().collect();
+                match word.as_str() {
+                    "true" => Ok(Toml::Boolean(true)),
+                    "false" => Ok(Toml::Boolean(false)),
+                    _ => integer(&word)
+                        .map(Toml::Integer)
+                        .ok_or_else(|| self.error(format!("unsupported value: {}", word))),
+                }
+            }
+            None => Err(self.error("expected a value")),
+        }
+    }
+
+    fn basic_string(&mut self) -> Result<String, TomlError> {
+        self.pos += 1;
+        let mut value = String::new();
+        loop {
+            match self.next() {
+                Some('"') => return Ok(value),
+                Some('\\') => {
+                    let escaped = match self.next() {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('n') => '\n',
+                        Some('t') => '\t',
+                        Some('r') => '\r',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some(u @ ('u' | 'U')) => {
+                            let digits = if u == 'u' { 4 } else { 8 };
+                            let hex: String =
+                                self.chars.iter().skip(self.pos).take(digits).collect();
+                            self.pos += digits;
+                            u32::from_str_radix(&hex, 16)
+                                .ok()
+                                .filter(|_| hex.len() == digits)
+                                .and_then(char::from_u32)
+                                .ok_or_else(|| {
+                                    self.error(format!("invalid escape \\{}{}", u, hex))
+                                })?
+                        }
+                        Some(c) => return Err(self.error(format!("invalid escape \\{}", c))),
+                        None => return Err(self.error("unterminated string")),
+                    };
+                    value.push(escaped);
+                }
+                Some('\n') | None => return Err(self.error("unterminated string")),
+                Some(c) => value.push(c),
+            }
+        }
+    }
+
+    fn literal_string(&mut self) -> Result<String, TomlError> {
+        self.pos += 1;
+        let mut value = String::new();
+        loop {
+            match self.next() {
+                Some('\'') => return Ok(value),
+                Some('\n') | None => return Err(self.error("unterminated string")),
+                Some(c) => value.push(c),
+            }
+        }
+    }
+}
+
+/// A decimal, hexadecimal, octal or binary integer, with `_` separators.
+fn integer(word: &str) -> Option<i64> {
+    let digits = word.replace('_', "");
+    let (negative, digits) = match digits.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, digits.strip_prefix('+').unwrap_or(&digits)),
+    };
+    let (radix, digits) = match digits.get(..2) {
+        Some("0x") => (16, &digits[2..]),
+        Some("0o") => (8, &digits[2..]),
+        Some("0b") => (2, &digits[2..]),
+        _ => (10, digits),
+    };
+    if digits.is_empty() || !digits.chars().all(|c| c.is_digit(radix)) {
+        return None;
+    }
+    let value = i64::from_str_radix(digits, radix).ok()?;
+    Some(if negative { -value } else { value })
+}
@@ -0,0 +1,376 @@
+//! Recovering the Java source of method bodies too simple to need a
+//! decompiler: returning a constant or a field, handing the arguments
+//! unchanged to another method, or doing nothing at all.
+//!
+//! Matching is strict. A body is recovered only when its decoded
+//! instructions are exactly one of these shapes, with no exception
+//! handlers, and anything else gives `None` so that the caller falls back
+//! to a placeholder body such as `throw new UnsupportedOperationException();`.
+
+use std::fmt::Write;
+
+use crate::{
+    classfile::ClassFile,
+    constantpool::ConstantPool,
+    descriptor::{BaseType, FieldType, MethodDescriptor},
+    instruction::{self, Instruction},
+    methodinfo::MethodInfo,
+};
+
+/// A method body recovered from its bytecode.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TrivialBody {
+    /// A `void` method which returns straight away.
+    Empty,
+    /// `return` of a literal, already written as Java, e.g. `42L`,
+    /// `"x"` or `null`.
+    Constant(String),
+    /// `return` of a field: an instance field of `this`, or a static field
+    /// of `class_name`.
+    Field {
+        class_name: String,
+        name: String,
+        is_static: bool,
+    },
+    /// A call passing the method's own arguments, in order, to another
+    /// method, returning what it returns.
+    Delegate {
+        class_name: String,
+        name: String,
+        /// Which `invoke*` instruction makes the call.
+        opcode: u8,
+        returns: bool,
+    },
+}
+
+impl TrivialBody {
+    /// The body as Java statements, without the enclosing braces.
+    /// `parameter_names` name the method's parameters, in order, for a
+    /// [`TrivialBody::Delegate`] to pass on; the other bodies ignore them.
+    pub fn java_statement(&self, parameter_names: &[String]) -> String {
+        match self {
+            TrivialBody::Empty => String::new(),
+            TrivialBody::Constant(literal) => format!("return {};", literal),
+            TrivialBody::Field {
+                name,
+                is_static: false,
+                ..
+            } => format!("return this.{};", name),
+            TrivialBody::Field {
+                class_name, name, ..
+            } => format!("return {}.{};", java_class_name(class_name), name),
+            TrivialBody::Delegate {
+                class_name,
+                name,
+                opcode,
+                returns,
+            } => {
+                let receiver = match *opcode {
+                    INVOKESTATIC => java_class_name(class_name),
+                    INVOKESPECIAL if !class_name.is_empty() => "super".to_string(),
+                    _ => "this".to_string(),
+                };
+                let mut statement = String::new();
+                if *returns {
+                    statement.push_str("return ");
+                }
+                let _ = write!(
+                    statement,
+                    "{}.{}({});",
+                    receiver,
+                    name,
+                    parameter_names.join(", ")
+                );
+                statement
+            }
+        }
+    }
+}
+
+const INVOKEVIRTUAL: u8 = 0xb6;
+const INVOKESPECIAL: u8 = 0xb7;
+const INVOKESTATIC: u8 = 0xb8;
+const INVOKEINTERFACE: u8 = 0xb9;
+
+/// Recovers the body of `method`, or `None` when it is abstract or native,
+/// or its code is anything but one of the shapes [`TrivialBody`] lists.
+/// Constructors and static initializers are never recovered, since their
+/// bodies can't be written back as a plain method's.
+///
+/// A delegate to a method of the class itself leaves `class_name` empty,
+/// so that the body reads `this.name(...)`; an `invokespecial` of another
+/// class calls `super`.
+pub fn trivial_body(class_file: &ClassFile, method: &MethodInfo) -> Option<TrivialBody> {
+    let constant_pool = &class_file.constant_pool;
+    let name = method.name(constant_pool)?;
+    if name.starts_with('<') {
+        return None;
+    }
+    let descriptor = method.parsed_descriptor(constant_pool)?.ok()?;
+    let code = method.code()?;
+    if !code.exception_table.is_empty() {
+        return None;
+    }
+    let instructions = instruction::decode(&code.code)
+        .collect::<Result<Vec<_>, _>>()
+        .ok()?;
+    let (last, body) = instructions.split_last()?;
+    if last.opcode != return_opcode(descriptor.return_type.as_ref()) {
+        return None;
+    }
+    let is_static = method.access_flags().is_static();
+
+    match body {
+        [] if descriptor.return_type.is_none() => Some(TrivialBody::Empty),
+        [push] => {
+            let return_type = descriptor.return_type.as_ref()?;
+            if let Some(literal) = constant_literal(constant_pool, push, return_type) {
+                return Some(TrivialBody::Constant(literal));
+            }
+            if push.opcode != GETSTATIC {
+                return None;
+            }
+            let field = ConstantPool::member_ref(constant_pool, push.cp_index()?)?;
+            (FieldType::parse(field.descriptor).ok()? == *return_type).then(|| TrivialBody::Field {
+                class_name: field.class_name.to_string(),
+                name: field.name.to_string(),
+                is_static: true,
+            })
+        }
+        [this, get]
+            if !is_static && get.opcode == GETFIELD && load_slot(this) == Some((0, 'A')) =>
+        {
+            let field = ConstantPool::member_ref(constant_pool, get.cp_index()?)?;
+            if FieldType::parse(field.descriptor).ok()? != *descriptor.return_type.as_ref()? {
+                return None;
+            }
+            Some(TrivialBody::Field {
+                class_name: field.class_name.to_string(),
+                name: field.name.to_string(),
+                is_static: false,
+            })
+        }
+        [loads @ .., invoke] if invoke.is_invoke() && invoke.opcode != 0xba => {
+            delegate(class_file, is_static, &descriptor, loads, invoke)
+        }
+        _ => None,
+    }
+}
+
+const GETSTATIC: u8 = 0xb2;
+const GETFIELD: u8 = 0xb4;
+
+/// Matches loads of `this`, unless the method is static, then of every
+/// parameter in turn, followed by a call taking exactly those arguments.
+fn delegate(
+    class_file: &ClassFile,
+    is_static: bool,
+    descriptor: &MethodDescriptor,
+    loads: &[Instruction],
+    invoke: &Instruction,
+) -> Option<TrivialBody> {
+    let constant_pool = &class_file.constant_pool;
+    let mut expected = Vec::new();
+    let mut slot = 0;
+    if !is_static {
+        expected.push((0, 'A'));
+        slot = 1;
+    }
+    for parameter in &descriptor.parameters {
+        expected.push((slot, load_kind(parameter)));
+        slot += match parameter {
+            FieldType::Base(BaseType::Long | BaseType::Double) => 2,
+            _ => 1,
+        };
+    }
+    let actual = loads.iter().map(load_slot).collect::<Option<Vec<_>>>()?;
+    if actual != expected {
+        return None;
+    }
+
+    let target = ConstantPool::member_ref(constant_pool, invoke.cp_index()?)?;
+    if target.name.starts_with('<') {
+        return None;
+    }
+    let target_descriptor = MethodDescriptor::parse(target.descriptor).ok()?;
+    if target_descriptor.parameters.len() != descriptor.parameters.len()
+        || target_descriptor.return_type != descriptor.return_type
+    {
+        return None;
+    }
+    let this_class = ConstantPool::class_name(constant_pool, class_file.this_class)?;
+    let opcode = invoke.opcode;
+    match opcode {
+        INVOKESTATIC => {}
+        INVOKEVIRTUAL | INVOKEINTERFACE if !is_static => {}
+        // Only a call of the class's own private methods, or of its
+        // superclass's, reads as `this.` or `super.`.
+        INVOKESPECIAL
+            if !is_static
+                && (target.class_name == this_class
+                    || Some(target.class_name)
+                        == ConstantPool::class_name(constant_pool, class_file.super_class)) => {}
+        _ => return None,
+    }
+    let class_name = match opcode {
+        INVOKESTATIC => target.class_name.to_string(),
+        _ => own_or(target.class_name, this_class),
+    };
+    Some(TrivialBody::Delegate {
+        class_name,
+        name: target.name.to_string(),
+        opcode,
+        returns: descriptor.return_type.is_some(),
+    })
+}
+
+/// Empty when `class_name` is the class itself.
+fn own_or(class_name: &str, this_class: &str) -> String {
+    if class_name == this_class {
+        String::new()
+    } else {
+        class_name.to_string()
+    }
+}
+
+/// The local variable slot an `*load` instruction reads, and the kind of
+/// value it loads, as the letter `I`, `J`, `F`, `D` or `A`.
+fn load_slot(instruction: &Instruction) -> Option<(u16, char)> {
+    const KINDS: [char; 5] = ['I', 'J', 'F', 'D', 'A'];
+    match instruction.opcode {
+        opcode @ 0x15..=0x19 => Some((
+            instruction.operands[0] as u16,
+            KINDS[(opcode - 0x15) as usize],
+        )),
+        opcode @ 0x1a..=0x2d => Some((
+            ((opcode - 0x1a) % 4) as u16,
+            KINDS[((opcode - 0x1a) / 4) as usize],
+        )),
+        _ => None,
+    }
+}
+
+fn load_kind(field_type: &FieldType) -> char {
+    match field_type {
+        FieldType::Base(BaseType::Long) => 'J',
+        FieldType::Base(BaseType::Float) => 'F',
+        FieldType::Base(BaseType::Double) => 'D',
+        FieldType::Base(_) => 'I',
+        _ => 'A',
+    }
+}
+
+fn return_opcode(return_type: Option<&FieldType>) -> u8 {
+    match return_type.map(load_kind) {
+        Some('I') => 0xac,
+        Some('J') => 0xad,
+        Some('F') => 0xae,
+        Some('D') => 0xaf,
+        Some(_) => 0xb0,
+        None => 0xb1,
+    }
+}
+
+/// The Java literal an instruction pushes, checked against the type the
+/// method returns.
+fn constant_literal(
+    constant_pool: &[ConstantPool],
+    push: &Instruction,
+    return_type: &FieldType,
+) -> Option<String> {
+    let kind = load_kind(return_type);
+    let int = |value: i32| match return_type {
+        FieldType::Base(BaseType::Boolean) => match value {
+            0 => Some("false".to_string()),
+            1 => Some("true".to_string()),
+            _ => None,
+        },
+        _ => Some(value.to_string()),
+    };
+    match (push.opcode, kind) {
+        (0x01, 'A') => Some("null".to_string()),
+        (opcode @ 0x02..=0x08, 'I') => int(opcode as i32 - 3),
+        (opcode @ 0x09..=0x0a, 'J') => Some(format!("{}L", opcode - 0x09)),
+        (opcode @ 0x0b..=0x0d, 'F') => Some(format!("{}.0f", opcode - 0x0b)),
+        (opcode @ 0x0e..=0x0f, 'D') => Some(format!("{}.0", opcode - 0x0e)),
+        (0x10, 'I') => int(push.operands[0] as i8 as i32),
+        (0x11, 'I') => int(i16::from_be_bytes([push.operands[0], push.operands[1]]) as i32),
+        (0x12..=0x14, _) => {
+            let index = push.cp_index()?;
+            match (constant_pool.get((index as usize).checked_sub(1)?)?, kind) {
+                (ConstantPool::Integer(value), 'I') => int(*value),
+                (ConstantPool::Long(value), 'J') => Some(format!("{}L", value)),
+                (ConstantPool::Float(bits), 'F') => Some(float_literal(bits.value())),
+                (ConstantPool::Double(bits), 'D') => Some(double_literal(bits.value())),
+                (ConstantPool::String(value), 'A') if is_string_assignable(return_type) => {
+                    ConstantPool::utf8(constant_pool, *value).map(string_literal)
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+fn is_string_assignable(field_type: &FieldType) -> bool {
+    matches!(
+        field_type,
+        FieldType::Object(name) if matches!(
+            name.as_str(),
+            "java/lang/String"
+                | "java/lang/Object"
+                | "java/lang/CharSequence"
+                | "java/lang/Comparable"
+                | "java/io/Serializable"
+        )
+    )
+}
+
+fn float_literal(value: f32) -> String {
+    if value.is_nan() {
+        "Float.NaN".to_string()
+    } else if value.is_infinite() {
+        let sign = if value < 0.0 { "NEGATIVE" } else { "POSITIVE" };
+        format!("Float.{}_INFINITY", sign)
+    } else {
+        format!("{:?}f", value)
+    }
+}
+
+fn double_literal(value: f64) -> String {
+    if value.is_nan() {
+        "Double.NaN".to_string()
+    } else if value.is_infinite() {
+        let sign = if value < 0.0 { "NEGATIVE" } else { "POSITIVE" };
+        format!("Double.{}_INFINITY", sign)
+    } else {
+        format!("{:?}", value)
+    }
+}
+
+/// `value` as a Java string literal, with everything outside printable
+/// ASCII escaped.
+fn string_literal(value: &str) -> String {
+    let mut literal = String::from("\"");
+    for unit in value.encode_utf16() {
+        match unit {
+            0x22 => literal.push_str("\\\""),
+            0x5c => literal.push_str("\\\\"),
+            0x0a => literal.push_str("\\n"),
+            0x0d => literal.push_str("\\r"),
+            0x09 => literal.push_str("\\t"),
+            0x20..=0x7e => literal.push(unit as u8 as char),
+            _ => {
+                let _ = write!(literal, "\\u{:04x}", unit);
+            }
+        }
+    }
+    literal.push('"');
+    literal
+}
+
+/// A class name as Java source writes it, taking `$` for the separator of
+/// nested classes.
+fn java_class_name(class_name: &str) -> String {
+    class_name.replace(['/', '$'], ".")
+}
@@ -0,0 +1,452 @@
+//! Turns the target of a type annotation into a readable description of the
+//! annotated type use, such as "element type of the type of parameter 1 of
+//! `put(java.lang.Object[])`".
+
+use core::fmt;
+
+use crate::prelude::*;
+use crate::{
+    attribute::{
+        LocalVar, LocalVariable, Path, ResolvedElementValue, TargetInfo, TypeAnnotation, TypePath,
+    },
+    constantpool::ConstantPool,
+    descriptor::FieldType,
+};
+
+/// The type use a type annotation applies to, from its `target_type` and
+/// `target_info` together.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TypeAnnotationTarget<'a> {
+    /// The type parameter at this index of a generic class or interface.
+    ClassTypeParameter(u8),
+    /// The type parameter at this index of a generic method or constructor.
+    MethodTypeParameter(u8),
+    /// The type in the `extends` clause of a class.
+    Superclass,
+    /// The type at this index in the `implements` clause of a class, or the
+    /// `extends` clause of an interface.
+    Interface(u16),
+    /// A bound of a class type parameter. Bound 0 is the class bound and
+    /// the others are interface bounds.
+    ClassTypeParameterBound {
+        type_parameter: u8,
+        bound: u8,
+    },
+    MethodTypeParameterBound {
+        type_parameter: u8,
+        bound: u8,
+    },
+    /// The type of a field or record component.
+    Field,
+    /// The return type of a method, or the type of a newly constructed
+    /// object.
+    ReturnType,
+    /// The receiver type of a method or constructor.
+    Receiver,
+    /// The type of the formal parameter at this index, not counting any
+    /// implicit ones.
+    FormalParameter(u8),
+    /// The type at this index in the `throws` clause.
+    Throws(u16),
+    /// The type of a local variable, live over the given code ranges.
+    LocalVariable(&'a [LocalVar]),
+    /// The type of a resource variable in a `try`-with-resources.
+    ResourceVariable(&'a [LocalVar]),
+    /// The type of the exception parameter of the handler at this index in
+    /// the exception table.
+    Catch(u16),
+    /// The type in an `instanceof` at this bytecode offset.
+    InstanceOf(u16),
+    /// The type in a `new` at this bytecode offset.
+    New(u16),
+    /// The type of a `::new` reference at this bytecode offset.
+    ConstructorReference(u16),
+    /// The type of a `::name` reference at this bytecode offset.
+    MethodReference(u16),
+    /// The type at `type_argument` in a cast at `offset`, which is 0 unless
+    /// it's an intersection cast.
+    Cast {
+        offset: u16,
+        type_argument: u8,
+    },
+    ConstructorInvocationTypeArgument {
+        offset: u16,
+        type_argument: u8,
+    },
+    MethodInvocationTypeArgument {
+        offset: u16,
+        type_argument: u8,
+    },
+    ConstructorReferenceTypeArgument {
+        offset: u16,
+        type_argument: u8,
+    },
+    MethodReferenceTypeArgument {
+        offset: u16,
+        type_argument: u8,
+    },
+}
+
+/// A type annotation with its target interpreted and its type and element
+/// values looked up.
+#[derive(Debug, Clone)]
+pub struct ResolvedTypeAnnotation<'a> {
+    /// E.g. `Lorg/checkerframework/checker/nullness/qual/Nullable;`, or
+    /// empty when it can't be resolved.
+    pub descriptor: &'a str,
+    /// `None` when the `target_info` doesn't fit the `target_type`.
+    pub target: Option<TypeAnnotationTarget<'a>>,
+    /// Where in the targeted type the annotation sits.
+    pub target_path: &'a TypePath,
+    /// As [`crate::attribute::ResolvedAnnotation::elements`].
+    pub elements: Vec<(&'a str, ResolvedElementValue<'a>)>,
+}
+
+impl ResolvedTypeAnnotation<'_> {
+    /// The annotation type as written in Java source, or the descriptor
+    /// itself when it doesn't parse.
+    pub fn type_name(&self) -> String {
+        FieldType::parse(self.descriptor)
+            .map(|field_type| field_type.to_string())
+            .unwrap_or_else(|_| self.descriptor.to_string())
+    }
+}
+
+/// What is known about the element a type annotation is attached to.
+#[derive(Debug, Default)]
+pub struct TypeAnnotationContext<'a> {
+    /// How the annotated element reads in prose, e.g. `class Foo`,
+    /// `field name` or `toString()`.
+    pub element: String,
+    /// Names of the element's type parameters, from its Signature attribute.
+    pub type_parameters: Vec<String>,
+    /// Internal names of the class's direct superinterfaces.
+    pub interfaces: Vec<String>,
+    /// The local variable table of the method's Code attribute.
+    pub local_variables: &'a [LocalVariable],
+    pub constant_pool: &'a [ConstantPool],
+}
+
+impl TypeAnnotation {
+    /// The type use this annotation applies to, or `None` when the
+    /// `target_info` doesn't fit the `target_type`.
+    pub fn target(&self) -> Option<TypeAnnotationTarget<'_>> {
+        use TypeAnnotationTarget as Target;
+
+        Some(match (self.target_type, &self.target_info) {
+            (0x00, TargetInfo::TypeParameter(index)) => Target::ClassTypeParameter(*index),
+            (0x01, TargetInfo::TypeParameter(index)) => Target::MethodTypeParameter(*index),
+            (0x10, TargetInfo::SuperType(0xFFFF)) => Target::Superclass,
+            (0x10, TargetInfo::SuperType(index)) => Target::Interface(*index),
+            (
+                0x11,
+                TargetInfo::TypeParameterBound {
+                    type_parameter_index,
+                    bound_index,
+                },
+            ) => Target::ClassTypeParameterBound {
+                type_parameter: *type_parameter_index,
+                bound: *bound_index,
+            },
+            (
+                0x12,
+                TargetInfo::TypeParameterBound {
+                    type_parameter_index,
+                    bound_index,
+                },
+            ) => Target::MethodTypeParameterBound {
+                type_parameter: *type_parameter_index,
+                bound: *bound_index,
+            },
+            (0x13, TargetInfo::Empty) => Target::Field,
+            (0x14, TargetInfo::Empty) => Target::ReturnType,
+            (0x15, TargetInfo::Empty) => Target::Receiver,
+            (0x16, TargetInfo::FormalParameter(index)) => Target::FormalParameter(*index),
+            (0x17, TargetInfo::Throws(index)) => Target::Throws(*index),
+            (0x40, TargetInfo::LocalVar(table)) => Target::LocalVariable(table),
+            (0x41, TargetInfo::LocalVar(table)) => Target::ResourceVariable(table),
+            (0x42, TargetInfo::Catch(index)) => Target::Catch(*index),
+            (0x43, TargetInfo::Offset(offset)) => Target::InstanceOf(*offset),
+            (0x44, TargetInfo::Offset(offset)) => Target::New(*offset),
+            (0x45, TargetInfo::Offset(offset)) => Target::ConstructorReference(*offset),
+            (0x46, TargetInfo::Offset(offset)) => Target::MethodReference(*offset),
+            (
+                target_type @ 0x47..=0x4B,
+                TargetInfo::TypeArgument {
+                    offset,
+                    type_argument_index,
+                },
+            ) => {
+                let (offset, type_argument) = (*offset, *type_argument_index);
+                match target_type {
+                    0x47 => Target::Cast {
+                        offset,
+                        type_argument,
+                    },
+                    0x48 => Target::ConstructorInvocationTypeArgument {
+                        offset,
+                        type_argument,
+                    },
+                    0x49 => Target::MethodInvocationTypeArgument {
+                        offset,
+                        type_argument,
+                    },
+                    0x4A => Target::ConstructorReferenceTypeArgument {
+                        offset,
+                        type_argument,
+                    },
+                    _ => Target::MethodReferenceTypeArgument {
+                        offset,
+                        type_argument,
+                    },
+                }
+            }
+            _ => return None,
+        })
+    }
+
+    /// The annotation with its target interpreted and its type and element
+    /// values looked up in `constant_pool`.
+    pub fn resolved<'a>(&'a self, constant_pool: &'a [ConstantPool]) -> ResolvedTypeAnnotation<'a> {
+        ResolvedTypeAnnotation {
+            descriptor: ConstantPool::utf8(constant_pool, self.type_index).unwrap_or_default(),
+            target: self.target(),
+            target_path: &self.target_path,
+            elements: ResolvedElementValue::pairs(&self.element_value_pairs, true, constant_pool),
+        }
+    }
+
+    /// Describes which type use this annotation applies to.
+    pub fn location(&self, context: &TypeAnnotationContext) -> String {
+        let element = &context.element;
+        let type_parameter = |index: u8| {
+            context
+                .type_parameters
+                .get(index as usize)
+                .cloned()
+                .unwrap_or_else(|| format!("#{}", index))
+        };
+
+        let mut location = match (&self.target_info, self.target_type) {
+            (TargetInfo::TypeParameter(index), _) => {
+                format!("type parameter {} of {}", type_parameter(*index), element)
+            }
+            (TargetInfo::SuperType(0xFFFF), _) => format!("superclass of {}", element),
+            (TargetInfo::SuperType(index), _) => match context.interfaces.get(*index as usize) {
+                Some(name) => format!("implemented interface {} of {}", name, element),
+                None => format!("{} implemented interface of {}", ordinal(*index), element),
+            },
+            (
+                TargetInfo::TypeParameterBound {
+                    type_parameter_index: index,
+                    bound_index: 0,
+                },
+                _,
+            ) => format!(
+                "class bound of type parameter {} of {}",
+                type_parameter(*index),
+                element
+            ),
+            (
+                TargetInfo::TypeParameterBound {
+                    type_parameter_index: index,
+                    bound_index: bound,
+                },
+                _,
+            ) => format!(
+                "{} interface bound of type parameter {} of {}",
+                ordinal(*bound as u16 - 1),
+                type_parameter(*index),
+                element
+            ),
+            (TargetInfo::Empty, 0x13) => format!("type of {}", element),
+            (TargetInfo::Empty, 0x14) if element.starts_with("<init>") => {
+                format!("constructed object of {}", element)
+            }
+            (TargetInfo::Empty, 0x14) => format!("return type of {}", element),
+            (TargetInfo::Empty, _) => format!("receiver type of {}", element),
+            (TargetInfo::FormalParameter(index), _) => {
+                format!(
+                    "type of the {} parameter of {}",
+                    ordinal(*index as u16),
+                    element
+                )
+            }
+            (TargetInfo::Throws(index), _) => {
+                format!("{} thrown type of {}", ordinal(*index), element)
+            }
+            (TargetInfo::LocalVar(table), target_type) => {
+                let kind = if target_type == 0x41 {
+                    "resource variable"
+                } else {
+                    "local variable"
+                };
+                let variables = table
+                    .iter()
+                    .map(|var| {
+                        let name = context
+                            .local_variables
+                            .iter()
+                            .find(|local| {
+                                local.index == var.index && local.start_pc == var.start_pc
+                            })
+                            .and_then(|local| {
+                                ConstantPool::utf8(context.constant_pool, local.name_index)
+                            });
+                        match name {
+                            Some(name) => format!("{} (slot {})", name, var.index),
+                            None => format!("slot {}", var.index),
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                format!("type of {} {} in {}", kind, variables.join(", "), element)
+            }
+            (TargetInfo::Catch(index), _) => {
+                format!("exception parameter of handler {} in {}", index, element)
+            }
+            (TargetInfo::Offset(offset), target_type) => {
+                let what = match target_type {
+                    0x43 => "the instanceof",
+                    0x44 => "the new",
+                    0x45 => "the constructor reference",
+                    _ => "the method reference",
+                };
+                format!("type in {} at offset {} in {}", what, offset, element)
+            }
+            (
+                TargetInfo::TypeArgument {
+                    offset,
+                    type_argument_index: 0,
+                },
+                0x47,
+            ) => {
+                format!("type of the cast at offset {} in {}", offset, element)
+            }
+            (
+                TargetInfo::TypeArgument {
+                    offset,
+                    type_argument_index: index,
+                },
+                0x47,
+            ) => format!(
+                "{} type of the intersection cast at offset {} in {}",
+                ordinal(*index as u16),
+                offset,
+                element
+            ),
+            (
+                TargetInfo::TypeArgument {
+                    offset,
+                    type_argument_index: index,
+                },
+                target_type,
+            ) => {
+                let what = match target_type {
+                    0x48 => "the constructor invocation",
+                    0x49 => "the method invocation",
+                    0x4A => "the constructor reference",
+                    _ => "the method reference",
+                };
+                format!(
+                    "type argument {} of {} at offset {} in {}",
+                    index, what, offset, element
+                )
+            }
+        };
+
+        for step in &self.target_path.path {
+            location = match step.type_path_kind {
+                0 => format!("element type of {}", location),
+                1 => format!("nested type of {}", location),
+                2 => format!("wildcard bound of {}", location),
+                _ => format!("type argument {} of {}", step.type_argument_index, location),
+            };
+        }
+
+        location
+    }
+}
+
+/// The steps as `javap` lists them, e.g. `TYPE_ARGUMENT(0), ARRAY`, or
+/// nothing for the type itself.
+impl fmt::Display for TypePath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, step) in self.path.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{}", step)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Path {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.type_path_kind {
+            0 => f.write_str("ARRAY"),
+            1 => f.write_str("INNER_TYPE"),
+            2 => f.write_str("WILDCARD"),
+            3 => write!(f, "TYPE_ARGUMENT({})", self.type_argument_index),
+            kind => write!(f, "UNKNOWN({})", kind),
+        }
+    }
+}
+
+/// Names of the type parameters declared at the start of a class or method
+/// signature, e.g. `["K", "V"]` for `<K:Ljava/lang/Object;V:Ljava/lang/Object;>...`.
+pub fn type_parameter_names(signature: &str) -> Vec<String> {
+    let bytes = signature.as_bytes();
+    let mut names = Vec::new();
+    if bytes.first() != Some(&b'<') {
+        return names;
+    }
+
+    let mut i = 1;
+    while i < bytes.len() && bytes[i] != b'>' {
+        let start = i;
+        while i < bytes.len() && bytes[i] != b':' {
+            i += 1;
+        }
+        names.push(signature[start..i].to_string());
+        // Class bound (possibly empty) followed by any interface bounds.
+        while i < bytes.len() && bytes[i] == b':' {
+            i = skip_reference_type(bytes, i + 1);
+        }
+    }
+
+    names
+}
+
+fn skip_reference_type(bytes: &[u8], mut i: usize) -> usize {
+    match bytes.get(i) {
+        Some(b'[') => skip_reference_type(bytes, i + 1),
+        Some(b'L') | Some(b'T') => {
+            let mut depth = 0;
+            while i < bytes.len() {
+                match bytes[i] {
+                    b'<' => depth += 1,
+                    b'>' => depth -= 1,
+                    b';' if depth == 0 => return i + 1,
+                    _ => {}
+                }
+                i += 1;
+            }
+            i
+        }
+        // Primitive array element or an empty class bound.
+        Some(b':') | Some(b'>') | None => i,
+        Some(_) => i + 1,
+    }
+}
+
+fn ordinal(index: u16) -> String {
+    let n = index as u32 + 1;
+    let suffix = match (n % 10, n % 100) {
+        (1, x) if x != 11 => "st",
+        (2, x) if x != 12 => "nd",
+        (3, x) if x != 13 => "rd",
+        _ => "th",
+    };
+    format!("{}{}", n, suffix)
+}
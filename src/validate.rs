@@ -0,0 +1,582 @@
+//! Checks that every constant pool index a parsed class holds leads to an
+//! entry of the kind the JVMS calls for there.
+//!
+//! The parser takes most indices as they come, so that a class damaged in
+//! one place can still be read in the others; a bad one only shows when
+//! it's resolved. [`validate`] finds them all up front. The names of
+//! attributes aren't among them, as the parser looks those up itself and
+//! fails on a bad one. UTF8 constants a lenient parse kept as
+//! [`RawUtf8`](ConstantPool::RawUtf8) are reported where they are.
+
+use core::fmt;
+
+use crate::prelude::*;
+use crate::{
+    attribute::{
+        Annotation, Attribute, ElementValue, StackMapFrame, TypeAnnotation, VerificationTypeInfo,
+    },
+    classfile::ClassFile,
+    constantpool::{ConstantPool, ConstantPoolError},
+    mutf8,
+};
+
+/// An index which doesn't lead to an entry of the kind needed, a
+/// `ConstantValue` of another type than its field, or a UTF8 constant
+/// which isn't modified UTF-8.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ValidationError {
+    /// Where the index is, e.g. `method 3 > Code > exception 0` or
+    /// `constant pool #12`.
+    pub location: String,
+    pub message: String,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.location, self.message)
+    }
+}
+
+impl core::error::Error for ValidationError {}
+
+/// Every bad constant pool index of `class_file`, in the order of the
+/// file: those of the constant pool itself, of the class, its fields,
+/// methods and attributes, and, with the `disasm` feature, of the
+/// instructions of its code.
+///
+/// ```
+/// use jvmb::{builder::ClassFileBuilder, validate};
+///
+/// let mut class_file = ClassFileBuilder::new("Foo").build().unwrap();
+/// assert!(validate::validate(&class_file).is_empty());
+///
+/// // point the superclass at the UTF8 entry holding the class's name
+/// let name_index = match class_file.constant_pool[class_file.this_class as usize - 1] {
+///     jvmb::ConstantPool::Class(name_index) => name_index,
+///     _ => unreachable!(),
+/// };
+/// class_file.super_class = name_index;
+/// class_file.interfaces = vec![999];
+/// let errors: Vec<String> = validate::validate(&class_file)
+///     .iter()
+///     .map(ToString::to_string)
+///     .collect();
+/// assert_eq!(
+///     errors,
+///     [
+///         format!("class > super_class: constant pool entry {} is Utf8, expected Class", name_index),
+///         format!(
+///             "class > interface 0: constant pool index 999 is out of range (1 to {})",
+///             class_file.constant_pool.len()
+///         ),
+///     ]
+/// );
+/// ```
+pub fn validate(class_file: &ClassFile) -> Vec<ValidationError> {
+    let mut validator = Validator {
+        constant_pool: &class_file.constant_pool,
+        errors: Vec::new(),
+    };
+    validator.check_constant_pool();
+    validator.class("class > this_class", class_file.this_class);
+    if class_file.super_class != 0 {
+        validator.class("class > super_class", class_file.super_class);
+    }
+    for (i, &interface) in class_file.interfaces.iter().enumerate() {
+        validator.class(&format!("class > interface {}", i), interface);
+    }
+    for (i, field) in class_file.fields.iter().enumerate() {
+        let location = format!("field {}", i);
+        validator.utf8(&format!("{} > name", location), field.name_index());
+        validator.utf8(
+            &format!("{} > descriptor", location),
+            field.descriptor_index(),
+        );
+        let descriptor = ConstantPool::utf8(validator.constant_pool, field.descriptor_index());
+        for attribute in field.attributes() {
+            if let (Attribute::ConstantValue(index), Some(descriptor)) = (attribute, descriptor) {
+                validator.constant_value(
+                    &format!("{} > ConstantValue", location),
+                    *index,
+                    descriptor,
+                );
+            }
+        }
+        validator.check_attributes(field.attributes(), &location);
+    }
+    for (i, method) in class_file.methods.iter().enumerate() {
+        let location = format!("method {}", i);
+        validator.utf8(&format!("{} > name", location), method.name_index());
+        validator.utf8(
+            &format!("{} > descriptor", location),
+            method.descriptor_index(),
+        );
+        validator.check_attributes(method.attributes(), &location);
+    }
+    validator.check_attributes(&class_file.attributes, "class");
+    validator.errors
+}
+
+struct Validator<'a> {
+    constant_pool: &'a [ConstantPool],
+    errors: Vec<ValidationError>,
+}
+
+impl Validator<'_> {
+    fn error(&mut self, location: &str, message: String) {
+        self.errors.push(ValidationError {
+            location: location.to_string(),
+            message,
+        });
+    }
+
+    /// Checks that `index` leads to an entry which is `expected`, as `is`
+    /// tells.
+    fn expect(
+        &mut self,
+        location: &str,
+        index: u16,
+        expected: &'static str,
+        is: impl Fn(&ConstantPool) -> bool,
+    ) {
+        let error = match ConstantPool::entry(self.constant_pool, index) {
+            Ok(constant) if is(constant) => return,
+            Ok(constant) => ConstantPoolError::WrongKind {
+                index,
+                expected,
+                found: constant.kind_name(),
+            },
+            Err(error) => error,
+        };
+        self.error(location, error.to_string());
+    }
+
+    fn utf8(&mut self, location: &str, index: u16) {
+        self.expect(location, index, "Utf8", |constant| {
+            matches!(
+                constant,
+                ConstantPool::UTF8(_) | ConstantPool::Utf16(_) | ConstantPool::RawUtf8(_)
+            )
+        });
+    }
+
+    fn class(&mut self, location: &str, index: u16) {
+        self.expect(location, index, "Class", |constant| {
+            matches!(constant, ConstantPool::Class(_))
+        });
+    }
+
+    fn name_and_type(&mut self, location: &str, index: u16) {
+        self.expect(location, index, "NameAndType", |constant| {
+            matches!(constant, ConstantPool::NameAndType(..))
+        });
+    }
+
+    fn module(&mut self, location: &str, index: u16) {
+        self.expect(location, index, "Module", |constant| {
+            matches!(constant, ConstantPool::Module(_))
+        });
+    }
+
+    fn package(&mut self, location: &str, index: u16) {
+        self.expect(location, index, "Package", |constant| {
+            matches!(constant, ConstantPool::Package(_))
+        });
+    }
+
+    /// Checks a UTF8 index for which 0 stands for none.
+    fn optional_utf8(&mut self, location: &str, index: u16) {
+        if index != 0 {
+            self.utf8(location, index);
+        }
+    }
+
+    fn optional_class(&mut self, location: &str, index: u16) {
+        if index != 0 {
+            self.class(location, index);
+        }
+    }
+
+    /// Checks the indices entries hold into the rest of the pool.
+    fn check_constant_pool(&mut self) {
+        let constant_pool = self.constant_pool;
+        for (i, constant) in constant_pool.iter().enumerate() {
+            let location = format!("constant pool #{}", i + 1);
+            match *constant {
+                ConstantPool::Class(name_index)
+                | ConstantPool::String(name_index)
+                | ConstantPool::MethodType(name_index) => self.utf8(&location, name_index),
+                ConstantPool::FieldRef(class_index, name_and_type_index)
+                | ConstantPool::MethodRef(class_index, name_and_type_index)
+                | ConstantPool::InterfaceMethodRef(class_index, name_and_type_index) => {
+                    self.class(&location, class_index);
+                    self.name_and_type(&location, name_and_type_index);
+                }
+                ConstantPool::NameAndType(name_index, descriptor_index) => {
+                    self.utf8(&location, name_index);
+                    self.utf8(&location, descriptor_index);
+                }
+                ConstantPool::MethodHandle(..) => {
+                    if let Err(error) =
+                        ConstantPool::resolve_method_handle(constant_pool, i as u16 + 1)
+                    {
+                        self.error(&location, error.to_string());
+                    }
+                }
+                ConstantPool::Dynamic(_, name_and_type_index)
+                | ConstantPool::InvokeDynamic(_, name_and_type_index) => {
+                    self.name_and_type(&location, name_and_type_index)
+                }
+                ConstantPool::Module(name_index) | ConstantPool::Package(name_index) => {
+                    self.utf8(&location, name_index)
+                }
+                ConstantPool::RawUtf8(ref bytes) => self.error(
+                    &location,
+                    format!(
+                        "UTF8 constant isn't valid modified UTF-8: {:?}",
+                        mutf8::decode_lossy(bytes)
+                    ),
+                ),
+                _ => {}
+            }
+        }
+    }
+
+    /// Checks that the constant of a field of type `descriptor` is of that
+    /// type, a `String` for a `java.lang.String` (JVMS §4.7.2).
+    fn constant_value(&mut self, location: &str, index: u16, descriptor: &str) {
+        let (expected, is): (&'static str, fn(&ConstantPool) -> bool) = match descriptor {
+            "I" | "S" | "C" | "B" | "Z" => ("Integer", |constant| {
+                matches!(constant, ConstantPool::Integer(_))
+            }),
+            "J" => ("Long", |constant| matches!(constant, ConstantPool::Long(_))),
+            "F" => ("Float", |constant| {
+                matches!(constant, ConstantPool::Float(_))
+            }),
+            "D" => ("Double", |constant| {
+                matches!(constant, ConstantPool::Double(_))
+            }),
+            "Ljava/lang/String;" => ("String", |constant| {
+                matches!(constant, ConstantPool::String(_))
+            }),
+            _ => {
+                return self.error(
+                    location,
+                    format!("a field of type {} can't have a ConstantValue", descriptor),
+                )
+            }
+        };
+        self.expect(location, index, expected, is);
+    }
+
+    fn check_attributes(&mut self, attributes: &[Attribute], location: &str) {
+        for attribute in attributes {
+            let location = format!("{} > {}", location, attribute.name());
+            let location = location.as_str();
+            match attribute {
+                Attribute::Code(code) => {
+                    for (i, exception) in code.exception_table.iter().enumerate() {
+                        let location = format!("{} > exception {}", location, i);
+                        self.optional_class(&location, exception.catch_type);
+                    }
+                    #[cfg(feature = "disasm")]
+                    self.check_code(&code.code, location);
+                    self.check_attributes(&code.attributes, location);
+                }
+                Attribute::StackMapTable(table) => {
+                    for (i, frame) in table.entries.iter().enumerate() {
+                        let types: Vec<&VerificationTypeInfo> = match frame {
+                            StackMapFrame::SameLocals1StackItemFrame { stack, .. }
+                            | StackMapFrame::SameLocals1StackItemFrameExtended { stack, .. } => {
+                                vec![stack]
+                            }
+                            StackMapFrame::AppendFrame { locals, .. } => locals.iter().collect(),
+                            StackMapFrame::FullFrame { locals, stack, .. } => {
+                                locals.iter().chain(stack).collect()
+                            }
+                            _ => Vec::new(),
+                        };
+                        for info in types {
+                            if let VerificationTypeInfo::ObjectVariableInfo(index) = info {
+                                self.class(&format!("{} > frame {}", location, i), *index);
+                            }
+                        }
+                    }
+                }
+                Attribute::Exceptions(exceptions) => {
+                    for &index in &exceptions.exception_index_table {
+                        self.class(location, index);
+                    }
+                }
+                Attribute::InnerClasses(inner_classes) => {
+                    for (i, class) in inner_classes.classes.iter().enumerate() {
+                        let location = format!("{} > class {}", location, i);
+                        self.class(&location, class.inner_class_info_index);
+                        self.optional_class(&location, class.outer_class_info_index);
+                        self.optional_utf8(&location, class.inner_name_index);
+                    }
+                }
+                Attribute::EnclosingMethod(enclosing) => {
+                    self.class(location, enclosing.class_index);
+                    if enclosing.method_index != 0 {
+                        self.name_and_type(location, enclosing.method_index);
+                    }
+                }
+                Attribute::Signature(signature) => self.utf8(location, signature.signature_index),
+                Attribute::SourceFile(source_file) => {
+                    self.utf8(location, source_file.sourcefile_index)
+                }
+                Attribute::LocalVariableTable(variables) => {
+                    for (i, variable) in variables.iter().enumerate() {
+                        let location = format!("{} > variable {}", location, i);
+                        self.utf8(&location, variable.name_index);
+                        self.utf8(&location, variable.descriptor_index);
+                    }
+                }
+                Attribute::LocalVariableTypeTable(variables) => {
+                    for (i, variable) in variables.iter().enumerate() {
+                        let location = format!("{} > variable {}", location, i);
+                        self.utf8(&location, variable.name_index);
+                        self.utf8(&location, variable.signature_index);
+                    }
+                }
+                Attribute::RuntimeVisibleAnnotations(annotations)
+                | Attribute::RuntimeInvisibleAnnotations(annotations) => {
+                    for (i, annotation) in annotations.iter().enumerate() {
+                        self.annotation(annotation, &format!("{} > annotation {}", location, i));
+                    }
+                }
+                Attribute::RuntimeVisibleParameterAnnotations(parameters)
+                | Attribute::RuntimeInvisibleParameterAnnotations(parameters) => {
+                    for (i, parameter) in parameters.iter().enumerate() {
+                        for (j, annotation) in parameter.annotations.iter().enumerate() {
+                            let location =
+                                format!("{} > parameter {} > annotation {}", location, i, j);
+                            self.annotation(annotation, &location);
+                        }
+                    }
+                }
+                Attribute::RuntimeVisibleTypeAnnotations(annotations)
+                | Attribute::RuntimeInvisibleTypeAnnotations(annotations) => {
+                    for (i, annotation) in annotations.iter().enumerate() {
+                        let TypeAnnotation {
+                            type_index,
+                            element_value_pairs,
+                            ..
+                        } = annotation;
+                        let location = format!("{} > annotation {}", location, i);
+                        self.utf8(&location, *type_index);
+                        self.element_value_pairs(element_value_pairs, &location);
+                    }
+                }
+                Attribute::AnnotationDefault(value) => self.element_value(value, location),
+                Attribute::BootstrapMethods(bootstrap_methods) => {
+                    for (i, bootstrap_method) in bootstrap_methods.iter().enumerate() {
+                        let location = format!("{} > bootstrap method {}", location, i);
+                        self.expect(
+                            &location,
+                            bootstrap_method.bootstrap_method_ref,
+                            "MethodHandle",
+                            |constant| matches!(constant, ConstantPool::MethodHandle(..)),
+                        );
+                        for &argument in &bootstrap_method.bootstrap_arguments {
+                            self.expect(
+                                &location,
+                                argument,
+                                "a loadable constant",
+                                ConstantPool::is_loadable,
+                            );
+                        }
+                    }
+                }
+                Attribute::MethodParameters(parameters) => {
+                    for (i, parameter) in parameters.iter().enumerate() {
+                        let location = format!("{} > parameter {}", location, i);
+                        self.optional_utf8(&location, parameter.name_index);
+                    }
+                }
+                Attribute::Module(module) => {
+                    self.module(location, module.module_name_index);
+                    self.optional_utf8(location, module.module_version_index);
+                    for (i, requires) in module.requires.iter().enumerate() {
+                        let location = format!("{} > requires {}", location, i);
+                        self.module(&location, requires.requires_index);
+                        self.optional_utf8(&location, requires.requires_version_index);
+                    }
+                    for (i, exports) in module.exports.iter().enumerate() {
+                        let location = format!("{} > exports {}", location, i);
+                        self.package(&location, exports.exports_index);
+                        for &index in &exports.exports_to_index {
+                            self.module(&location, index);
+                        }
+                    }
+                    for (i, opens) in module.opens.iter().enumerate() {
+                        let location = format!("{} > opens {}", location, i);
+                        self.package(&location, opens.opens_index);
+                        for &index in &opens.opens_to_index {
+                            self.module(&location, index);
+                        }
+                    }
+                    for &index in &module.uses {
+                        self.class(&format!("{} > uses", location), index);
+                    }
+                    for (i, provides) in module.provides.iter().enumerate() {
+                        let location = format!("{} > provides {}", location, i);
+                        self.class(&location, provides.provides_index);
+                        for &index in &provides.provides_with_index {
+                            self.class(&location, index);
+                        }
+                    }
+                }
+                Attribute::ModulePackages(packages) => {
+                    for &index in packages {
+                        self.package(location, index);
+                    }
+                }
+                Attribute::ModuleMainClass(index) | Attribute::NestHost(index) => {
+                    self.class(location, *index)
+                }
+                Attribute::NestMembers(classes) | Attribute::PermittedSubclasses(classes) => {
+                    for &index in classes {
+                        self.class(location, index);
+                    }
+                }
+                Attribute::Record(components) => {
+                    for (i, component) in components.iter().enumerate() {
+                        let location = format!("{} > component {}", location, i);
+                        self.utf8(&format!("{} > name", location), component.name_index);
+                        self.utf8(
+                            &format!("{} > descriptor", location),
+                            component.descriptor_index,
+                        );
+                        self.check_attributes(&component.attributes, &location);
+                    }
+                }
+                // a field's is checked against its type with the field
+                Attribute::ConstantValue(_)
+                | Attribute::Synthetic(_)
+                | Attribute::SourceDebugExtension(_)
+                | Attribute::LineNumberTable(_)
+                | Attribute::Deprecated(_)
+                | Attribute::Unknown { .. }
+                | Attribute::Deferred(_) => {}
+                #[cfg(feature = "std")]
+                Attribute::Custom(_) => {}
+            }
+        }
+    }
+
+    fn annotation(&mut self, annotation: &Annotation, location: &str) {
+        self.utf8(location, annotation.type_index);
+        self.element_value_pairs(&annotation.element_value_pairs, location);
+    }
+
+    fn element_value_pairs(&mut self, pairs: &[(u16, ElementValue)], location: &str) {
+        for (name_index, value) in pairs {
+            self.utf8(location, *name_index);
+            self.element_value(value, location);
+        }
+    }
+
+    /// Checks a value, whose constant must be of the type its tag gives
+    /// (JVMS §4.7.16.1).
+    fn element_value(&mut self, value: &ElementValue, location: &str) {
+        match value {
+            ElementValue::ConstValue {
+                tag,
+                const_value_index,
+            } => {
+                let (expected, is): (&'static str, fn(&ConstantPool) -> bool) = match tag {
+                    b'J' => ("Long", |constant| matches!(constant, ConstantPool::Long(_))),
+                    b'F' => ("Float", |constant| {
+                        matches!(constant, ConstantPool::Float(_))
+                    }),
+                    b'D' => ("Double", |constant| {
+                        matches!(constant, ConstantPool::Double(_))
+                    }),
+                    b's' => ("Utf8", |constant| {
+                        matches!(
+                            constant,
+                            ConstantPool::UTF8(_)
+                                | ConstantPool::Utf16(_)
+                                | ConstantPool::RawUtf8(_)
+                        )
+                    }),
+                    _ => ("Integer", |constant| {
+                        matches!(constant, ConstantPool::Integer(_))
+                    }),
+                };
+                self.expect(location, *const_value_index, expected, is);
+            }
+            ElementValue::EnumConstValue {
+                type_name_index,
+                const_name_index,
+            } => {
+                self.utf8(location, *type_name_index);
+                self.utf8(location, *const_name_index);
+            }
+            ElementValue::ClassInfoIndex(index) => self.utf8(location, *index),
+            ElementValue::AnnotationValue(annotation) => self.annotation(annotation, location),
+            ElementValue::ArrayValue(values) => {
+                for value in values {
+                    self.element_value(value, location);
+                }
+            }
+        }
+    }
+
+    /// Checks the operands of the instructions which refer to the pool, up
+    /// to the first which doesn't decode.
+    #[cfg(feature = "disasm")]
+    fn check_code(&mut self, code: &[u8], location: &str) {
+        use crate::instruction;
+
+        for instruction in instruction::decode(code).map_while(Result::ok) {
+            let index = match instruction.cp_index() {
+                Some(index) => index,
+                None => continue,
+            };
+            let location = format!("{} > offset {}", location, instruction.offset);
+            let (expected, is): (&'static str, fn(&ConstantPool) -> bool) = match instruction.opcode
+            {
+                // ldc, ldc_w
+                0x12 | 0x13 => ("a loadable constant of one slot", |constant| {
+                    constant.is_loadable()
+                        && !matches!(constant, ConstantPool::Long(_) | ConstantPool::Double(_))
+                }),
+                // ldc2_w
+                0x14 => ("Long, Double or Dynamic", |constant| {
+                    matches!(
+                        constant,
+                        ConstantPool::Long(_) | ConstantPool::Double(_) | ConstantPool::Dynamic(..)
+                    )
+                }),
+                0xb2..=0xb5 => ("Fieldref", |constant| {
+                    matches!(constant, ConstantPool::FieldRef(..))
+                }),
+                // invokevirtual
+                0xb6 => ("Methodref", |constant| {
+                    matches!(constant, ConstantPool::MethodRef(..))
+                }),
+                // invokespecial, invokestatic
+                0xb7 | 0xb8 => ("Methodref or InterfaceMethodref", |constant| {
+                    matches!(
+                        constant,
+                        ConstantPool::MethodRef(..) | ConstantPool::InterfaceMethodRef(..)
+                    )
+                }),
+                0xb9 => ("InterfaceMethodref", |constant| {
+                    matches!(constant, ConstantPool::InterfaceMethodRef(..))
+                }),
+                0xba => ("InvokeDynamic", |constant| {
+                    matches!(constant, ConstantPool::InvokeDynamic(..))
+                }),
+                // new, anewarray, checkcast, instanceof, multianewarray
+                _ => ("Class", |constant| {
+                    matches!(constant, ConstantPool::Class(_))
+                }),
+            };
+            self.expect(&location, index, expected, is);
+        }
+    }
+}
@@ -0,0 +1,295 @@
+use crate::{
+    attribute::{Attribute, Code},
+    classfile::ClassFile,
+    constantpool::{ConstantPool, ReferenceKind},
+    rdjson,
+    spec::{self, AttributeLocation},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl From<Severity> for rdjson::Severity {
+    fn from(severity: Severity) -> Self {
+        match severity {
+            Severity::Warning => rdjson::Severity::Warning,
+            Severity::Error => rdjson::Severity::Error,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Violation {
+    /// The stable code of the check, e.g. `V0203`.
+    pub code: &'static str,
+    pub severity: Severity,
+    /// Where in the class the problem was found, e.g. `method 3 > Code`.
+    pub location: String,
+    pub message: String,
+}
+
+impl Violation {
+    /// The index of the method the problem was found in, if it was in one.
+    pub fn method(&self) -> Option<usize> {
+        let location = self.location.strip_prefix("method ")?;
+        let index = location
+            .split_once(' ')
+            .map_or(location, |(index, _)| index);
+        index.parse().ok()
+    }
+}
+
+/// Checks a parsed class for spec violations which the parser itself lets
+/// through.
+pub fn verify(class_file: &ClassFile) -> Vec<Violation> {
+    let mut verifier = Verifier {
+        major_version: class_file.major_version,
+        violations: Vec::new(),
+    };
+
+    for (i, constant) in class_file.constant_pool.iter().enumerate() {
+        if let Some(spec) = spec::constant_spec(constant.tag()) {
+            if class_file.major_version < spec.since_major {
+                verifier.warn(
+                    "V0101",
+                    &format!("constant pool #{}", i + 1),
+                    format!(
+                        "CONSTANT_{} requires class file version {}, found {}",
+                        spec.name, spec.since_major, class_file.major_version
+                    ),
+                );
+            }
+        }
+    }
+    verifier.check_bootstrap_methods(class_file);
+    verifier.check_attributes(
+        &class_file.attributes,
+        AttributeLocation::ClassFile,
+        "class",
+    );
+    for (i, field) in class_file.fields.iter().enumerate() {
+        verifier.check_attributes(
+            field.attributes(),
+            AttributeLocation::FieldInfo,
+            &format!("field {}", i),
+        );
+    }
+    for (i, method) in class_file.methods.iter().enumerate() {
+        verifier.check_attributes(
+            method.attributes(),
+            AttributeLocation::MethodInfo,
+            &format!("method {}", i),
+        );
+    }
+
+    verifier.violations
+}
+
+struct Verifier {
+    major_version: u16,
+    violations: Vec<Violation>,
+}
+
+impl Verifier {
+    fn warn(&mut self, code: &'static str, location: &str, message: String) {
+        self.violations.push(Violation {
+            code,
+            severity: Severity::Warning,
+            location: location.to_string(),
+            message,
+        });
+    }
+
+    fn error(&mut self, code: &'static str, location: &str, message: String) {
+        self.violations.push(Violation {
+            code,
+            severity: Severity::Error,
+            location: location.to_string(),
+            message,
+        });
+    }
+
+    /// Checks the `Dynamic` and `InvokeDynamic` entries against the
+    /// `BootstrapMethods` attribute they index (JVMS §4.7.23). A slot out of
+    /// range or a static argument which isn't loadable fails class loading;
+    /// a bootstrap method handle of a kind which can't take the lookup as
+    /// its first argument, or one nothing uses, is only suspicious.
+    fn check_bootstrap_methods(&mut self, class_file: &ClassFile) {
+        let constant_pool = &class_file.constant_pool;
+        let has_attribute = class_file
+            .attributes
+            .iter()
+            .any(|attribute| matches!(attribute, Attribute::BootstrapMethods(_)));
+        let bootstrap_methods = class_file.bootstrap_methods();
+        let mut used = vec![false; bootstrap_methods.len()];
+        for (i, constant) in constant_pool.iter().enumerate() {
+            let slot = match constant {
+                ConstantPool::Dynamic(slot, _) | ConstantPool::InvokeDynamic(slot, _) => *slot,
+                _ => continue,
+            };
+            let location = format!("constant pool #{}", i + 1);
+            let kind = spec::constant_spec(constant.tag()).map_or("?", |spec| spec.name);
+            if !has_attribute {
+                self.error(
+                    "V0201",
+                    &location,
+                    format!(
+                        "CONSTANT_{} requires a BootstrapMethods attribute, found none",
+                        kind
+                    ),
+                );
+            } else if let Some(used) = used.get_mut(slot as usize) {
+                *used = true;
+            } else {
+                self.error(
+                    "V0202",
+                    &location,
+                    format!(
+                        "CONSTANT_{} refers to bootstrap method {}, but there are {}",
+                        kind,
+                        slot,
+                        bootstrap_methods.len()
+                    ),
+                );
+            }
+        }
+
+        for (i, bootstrap_method) in bootstrap_methods.iter().enumerate() {
+            let location = format!("class > BootstrapMethods {}", i);
+            let constant = |index: u16| {
+                (index as usize)
+                    .checked_sub(1)
+                    .and_then(|index| constant_pool.get(index))
+            };
+            match constant(bootstrap_method.bootstrap_method_ref) {
+                Some(ConstantPool::MethodHandle(kind, _))
+                    if matches!(
+                        ReferenceKind::from_tag(*kind),
+                        Some(ReferenceKind::InvokeStatic | ReferenceKind::NewInvokeSpecial)
+                    ) => {}
+                Some(ConstantPool::MethodHandle(kind, _)) => self.warn(
+                    "V0203",
+                    &location,
+                    format!(
+                        "bootstrap method handle #{} is of kind {}, expected REF_invokeStatic \
+                         or REF_newInvokeSpecial",
+                        bootstrap_method.bootstrap_method_ref, kind
+                    ),
+                ),
+                _ => self.error(
+                    "V0204",
+                    &location,
+                    format!(
+                        "bootstrap method #{} is not a CONSTANT_MethodHandle",
+                        bootstrap_method.bootstrap_method_ref
+                    ),
+                ),
+            }
+            for &argument in &bootstrap_method.bootstrap_arguments {
+                if !constant(argument).is_some_and(ConstantPool::is_loadable) {
+                    self.error(
+                        "V0205",
+                        &location,
+                        format!("static argument #{} is not a loadable constant", argument),
+                    );
+                }
+            }
+            if !used[i] {
+                self.warn(
+                    "V0206",
+                    &location,
+                    "bootstrap method is not used by any CONSTANT_Dynamic or \
+                     CONSTANT_InvokeDynamic"
+                        .to_string(),
+                );
+            }
+        }
+    }
+
+    fn check_attributes(
+        &mut self,
+        attributes: &[Attribute],
+        kind: AttributeLocation,
+        location: &str,
+    ) {
+        for (i, attribute) in attributes.iter().enumerate() {
+            // The JVM silently ignores attributes in the wrong place or from a
+            // newer class file version, so these are only warnings.
+            if let Some(spec) = spec::attribute_spec(attribute.name()) {
+                if !spec.locations.contains(&kind) {
+                    self.warn(
+                        "V0301",
+                        location,
+                        format!(
+                            "{} attribute is not allowed in {} (JVMS §{})",
+                            spec.name,
+                            kind.name(),
+                            spec.section
+                        ),
+                    );
+                }
+                // reported at the first of them only
+                let name = attribute.name();
+                let seen_before = attributes[..i].iter().any(|other| other.name() == name);
+                if !spec.repeatable && !seen_before {
+                    let count = attributes
+                        .iter()
+                        .filter(|other| other.name() == name)
+                        .count();
+                    if count > 1 {
+                        self.error(
+                            "V0304",
+                            location,
+                            format!(
+                                "{} attribute appears {} times, at most once is allowed (JVMS §{})",
+                                spec.name, count, spec.section
+                            ),
+                        );
+                    }
+                }
+                if self.major_version < spec.since_major {
+                    self.warn(
+                        "V0302",
+                        location,
+                        format!(
+                            "{} attribute requires class file version {}, found {}",
+                            spec.name, spec.since_major, self.major_version
+                        ),
+                    );
+                }
+            }
+
+            match attribute {
+                Attribute::Synthetic(info) | Attribute::Deprecated(info) if !info.is_empty() => {
+                    self.warn(
+                        "V0303",
+                        location,
+                        format!(
+                            "{} attribute has length {}, expected 0",
+                            attribute.name(),
+                            info.len()
+                        ),
+                    )
+                }
+                Attribute::Code(Code { attributes, .. }) => self.check_attributes(
+                    attributes,
+                    AttributeLocation::Code,
+                    &format!("{} > Code", location),
+                ),
+                Attribute::Record(components) => {
+                    for (i, component) in components.iter().enumerate() {
+                        self.check_attributes(
+                            &component.attributes,
+                            AttributeLocation::RecordComponent,
+                            &format!("{} > Record component {}", location, i),
+                        );
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
@@ -0,0 +1,54 @@
+//! Big-endian output helpers shared by the `write` functions of the model
+//! types.
+
+use std::io;
+
+pub(crate) trait WriteBe {
+    fn put_u8(&mut self, value: u8);
+    fn put_u16(&mut self, value: u16);
+    fn put_u32(&mut self, value: u32);
+    fn put_u64(&mut self, value: u64);
+
+    /// Writes the length of a table as a `u16`, failing if it doesn't fit.
+    fn put_count(&mut self, len: usize, what: &str) -> io::Result<()>;
+
+    /// Writes the length of a table as a `u8`, failing if it doesn't fit.
+    fn put_count_u8(&mut self, len: usize, what: &str) -> io::Result<()>;
+}
+
+impl WriteBe for Vec<u8> {
+    fn put_u8(&mut self, value: u8) {
+        self.push(value);
+    }
+
+    fn put_u16(&mut self, value: u16) {
+        self.extend_from_slice(&value.to_be_bytes());
+    }
+
+    fn put_u32(&mut self, value: u32) {
+        self.extend_from_slice(&value.to_be_bytes());
+    }
+
+    fn put_u64(&mut self, value: u64) {
+        self.extend_from_slice(&value.to_be_bytes());
+    }
+
+    fn put_count(&mut self, len: usize, what: &str) -> io::Result<()> {
+        let len = u16::try_from(len).map_err(|_| too_long(what, len))?;
+        self.put_u16(len);
+        Ok(())
+    }
+
+    fn put_count_u8(&mut self, len: usize, what: &str) -> io::Result<()> {
+        let len = u8::try_from(len).map_err(|_| too_long(what, len))?;
+        self.put_u8(len);
+        Ok(())
+    }
+}
+
+fn too_long(what: &str, len: usize) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("{} has {} entries, too many for a class file", what, len),
+    )
+}
@@ -0,0 +1,131 @@
+//! The methods and fields a method's code refers to, read off its
+//! instructions: what it calls, what it reads and writes, and what its
+//! `invokedynamic` call sites bootstrap.
+
+use crate::{
+    classfile::{BootstrapArgument, ClassFile, DynamicSite},
+    constantpool::{ConstantPool, MemberRef},
+    instruction::{self, Instruction},
+    methodinfo::MethodInfo,
+};
+
+const INVOKEDYNAMIC: u8 = 0xba;
+
+/// An instruction naming a field or method.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemberUse<'a> {
+    /// The bytecode offset of the instruction.
+    pub offset: u32,
+    pub opcode: u8,
+    pub member: MemberRef<'a>,
+}
+
+impl MemberUse<'_> {
+    pub fn mnemonic(&self) -> &'static str {
+        instruction::mnemonic(self.opcode).unwrap_or("<invalid>")
+    }
+}
+
+/// An `invokedynamic` instruction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DynamicCall<'a> {
+    /// The bytecode offset of the instruction.
+    pub offset: u32,
+    pub name: &'a str,
+    pub descriptor: &'a str,
+    /// The call site resolved through the class's `BootstrapMethods`, `None`
+    /// when it can't be.
+    pub site: Option<DynamicSite<'a>>,
+}
+
+impl<'a> DynamicCall<'a> {
+    /// The members the static arguments of the bootstrap method hand
+    /// over, such as the method a lambda or method reference is made from.
+    pub fn method_handles(&self, constant_pool: &'a [ConstantPool]) -> Vec<MemberRef<'a>> {
+        self.site
+            .map(|site| site.resolved_arguments(constant_pool))
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|argument| match argument? {
+                BootstrapArgument::MethodHandle { member, .. } => Some(member),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// What [`MethodInfo::referenced_members`] finds, each list in code order.
+/// Instructions whose operand doesn't resolve are left out.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct MemberRefs<'a> {
+    /// `invokevirtual`, `invokespecial`, `invokestatic` and
+    /// `invokeinterface`.
+    pub calls: Vec<MemberUse<'a>>,
+    /// `getfield`, `putfield`, `getstatic` and `putstatic`.
+    pub fields: Vec<MemberUse<'a>>,
+    pub dynamic_calls: Vec<DynamicCall<'a>>,
+}
+
+impl<'a> MemberRefs<'a> {
+    /// The references made by the code of `method`, a method of `class`.
+    /// Decoding stops at the first malformed instruction, keeping what came
+    /// before.
+    pub fn of(method: &MethodInfo, class: &'a ClassFile) -> Self {
+        let mut refs = MemberRefs::default();
+        let code = match method.code() {
+            Some(code) => code,
+            None => return refs,
+        };
+        let constant_pool = &class.constant_pool;
+        for instruction in instruction::decode(&code.code).map_while(Result::ok) {
+            let index = match instruction.cp_index() {
+                Some(index) if (0xb2..=INVOKEDYNAMIC).contains(&instruction.opcode) => index,
+                _ => continue,
+            };
+            if instruction.opcode == INVOKEDYNAMIC {
+                if let Some(call) = dynamic_call(&instruction, index, class) {
+                    refs.dynamic_calls.push(call);
+                }
+                continue;
+            }
+            let member = match ConstantPool::member_ref(constant_pool, index) {
+                Some(member) => member,
+                None => continue,
+            };
+            let member_use = MemberUse {
+                offset: instruction.offset,
+                opcode: instruction.opcode,
+                member,
+            };
+            if instruction.is_invoke() {
+                refs.calls.push(member_use);
+            } else {
+                refs.fields.push(member_use);
+            }
+        }
+        refs
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.calls.is_empty() && self.fields.is_empty() && self.dynamic_calls.is_empty()
+    }
+}
+
+fn dynamic_call<'a>(
+    instruction: &Instruction,
+    index: u16,
+    class: &'a ClassFile,
+) -> Option<DynamicCall<'a>> {
+    let name_and_type_index = match class.constant_pool.get((index as usize).checked_sub(1)?)? {
+        ConstantPool::InvokeDynamic(_, name_and_type_index) => *name_and_type_index,
+        _ => return None,
+    };
+    let (name, descriptor) =
+        ConstantPool::name_and_type(&class.constant_pool, name_and_type_index)?;
+    Some(DynamicCall {
+        offset: instruction.offset,
+        name,
+        descriptor,
+        site: class.dynamic_site(index),
+    })
+}
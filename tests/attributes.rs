@@ -0,0 +1,126 @@
+mod common;
+
+use common::RawClass;
+use jvmb::{
+    attribute::Attribute,
+    classfile::{ClassFile, ParseOptions},
+    error::ParseError,
+};
+
+/// `record Point(int x, int y)` as javac lays it out: final, extending
+/// `java/lang/Record`, with a private final field and a `Record` component
+/// for each of `x` and `y`.
+fn point() -> Vec<u8> {
+    let mut class = RawClass::new("Point", "java/lang/Record");
+    class.access_flags = 0x0031;
+    let mut components = 2u16.to_be_bytes().to_vec();
+    for name in ["x", "y"] {
+        class.field(0x0012, name, "I", &[]);
+        components.extend_from_slice(&class.utf8(name).to_be_bytes());
+        components.extend_from_slice(&class.utf8("I").to_be_bytes());
+        components.extend_from_slice(&[0, 0]);
+    }
+    let record = class.attribute("Record", &components);
+    class.class_attribute(record);
+    class.bytes()
+}
+
+#[test]
+fn record_components_have_their_names_and_descriptors() {
+    let class_file = ClassFile::from_bytes(&point()).unwrap();
+    assert!(matches!(class_file.attributes[..], [Attribute::Record(_)]));
+    let components: Vec<(&str, &str)> = class_file
+        .record_components()
+        .expect("a record")
+        .iter()
+        .map(|component| {
+            let resolved = component.resolved(&class_file.constant_pool);
+            (resolved.name, resolved.descriptor)
+        })
+        .collect();
+    assert_eq!(components, [("x", "I"), ("y", "I")]);
+}
+
+/// A class whose field `f` has the `ConstantValue` attribute `info`,
+/// declared `length` bytes long.
+fn constant_value(length: u32, info: &[u8]) -> Vec<u8> {
+    let mut class = RawClass::new("Constants", "java/lang/Object");
+    let value = class.entry(3, &42i32.to_be_bytes());
+    let mut body = value.to_be_bytes().to_vec();
+    body.extend_from_slice(info);
+    let attribute = class.attribute_with_length("ConstantValue", length, &body);
+    class.field(0x0018, "f", "I", &[attribute]);
+    class.bytes()
+}
+
+#[test]
+fn bytes_left_over_in_an_attribute_are_a_length_mismatch() {
+    let bytes = constant_value(5, &[0xff, 0xff, 0xff]);
+    assert_eq!(
+        ClassFile::from_bytes(&bytes).unwrap_err(),
+        ParseError::AttributeLengthMismatch {
+            name: "ConstantValue".to_string(),
+            declared: 5,
+            consumed: 2,
+        }
+    );
+
+    // dropped when lenient
+    let (_, class_file) = ClassFile::parse_with(&bytes, &ParseOptions::lenient()).unwrap();
+    assert!(matches!(
+        class_file.fields[0].attributes()[..],
+        [Attribute::ConstantValue(_)]
+    ));
+}
+
+#[test]
+fn an_attribute_running_past_its_length_is_truncated() {
+    // the index of the constant cut in half by a length of 1, with the
+    // rest of the class following it
+    let bytes = constant_value(1, &[]);
+    assert_eq!(
+        ClassFile::from_bytes(&bytes).unwrap_err(),
+        ParseError::AttributeTruncated {
+            name: "ConstantValue".to_string(),
+            declared: 1,
+        }
+    );
+    assert!(ClassFile::parse_with(&bytes, &ParseOptions::lenient()).is_err());
+}
+
+/// A class whose method `run` has `Synthetic` and `Deprecated` attributes
+/// with a payload of `info`.
+fn marked(info: &[u8]) -> Vec<u8> {
+    let mut class = RawClass::new("Marked", "java/lang/Object");
+    let synthetic = class.attribute("Synthetic", info);
+    let deprecated = class.attribute("Deprecated", info);
+    class.method(0x0401, "run", "()V", &[synthetic, deprecated]);
+    class.bytes()
+}
+
+#[test]
+fn synthetic_and_deprecated_payloads_are_kept_when_lenient() {
+    let (_, class_file) = ClassFile::parse_with(&marked(&[]), &ParseOptions::new()).unwrap();
+    assert_eq!(
+        class_file.methods[0].attributes(),
+        [Attribute::Synthetic(vec![]), Attribute::Deprecated(vec![])]
+    );
+
+    let bytes = marked(&[1, 2]);
+    assert_eq!(
+        ClassFile::from_bytes(&bytes).unwrap_err(),
+        ParseError::AttributeLengthMismatch {
+            name: "Synthetic".to_string(),
+            declared: 2,
+            consumed: 0,
+        }
+    );
+    let (_, class_file) = ClassFile::parse_with(&bytes, &ParseOptions::lenient()).unwrap();
+    assert_eq!(
+        class_file.methods[0].attributes(),
+        [
+            Attribute::Synthetic(vec![1, 2]),
+            Attribute::Deprecated(vec![1, 2])
+        ]
+    );
+}
@@ -0,0 +1,144 @@
+#![cfg(all(feature = "analysis", feature = "write"))]
+
+mod common;
+
+use common::fixture;
+use jvmb::{
+    attribute::{Attribute, BootstrapMethod},
+    classfile::ClassFile,
+    constantpool::ConstantPool,
+    verify::{verify, Severity},
+};
+
+/// A javac class with one `InvokeDynamic`, for a string concatenation, and
+/// one bootstrap method.
+fn concatenating() -> ClassFile {
+    let class_file = ClassFile::from_bytes(&fixture("clones/Invoices")).unwrap();
+    assert_eq!(class_file.bootstrap_methods().len(), 1);
+    class_file
+}
+
+/// `class_file` written out, read back and verified, as the codes,
+/// severities and locations of its bootstrap method violations.
+fn violations(class_file: &ClassFile) -> Vec<(&'static str, Severity, String)> {
+    let class_file = ClassFile::from_bytes(&class_file.to_bytes().unwrap()).unwrap();
+    verify(&class_file)
+        .into_iter()
+        .filter(|violation| violation.code.starts_with("V02"))
+        .map(|violation| (violation.code, violation.severity, violation.location))
+        .collect()
+}
+
+/// The index of the `InvokeDynamic` entry.
+fn invoke_dynamic(class_file: &ClassFile) -> usize {
+    class_file
+        .constant_pool
+        .iter()
+        .position(|constant| matches!(constant, ConstantPool::InvokeDynamic(..)))
+        .unwrap()
+}
+
+fn bootstrap_methods(class_file: &mut ClassFile) -> &mut Vec<BootstrapMethod> {
+    class_file
+        .attributes
+        .iter_mut()
+        .find_map(|attribute| match attribute {
+            Attribute::BootstrapMethods(methods) => Some(methods),
+            _ => None,
+        })
+        .unwrap()
+}
+
+#[test]
+fn javac_bootstrap_methods_are_consistent() {
+    assert_eq!(violations(&concatenating()), []);
+}
+
+#[test]
+fn a_constant_pointing_past_the_bootstrap_methods_fails() {
+    let mut class_file = concatenating();
+    let index = invoke_dynamic(&class_file);
+    match &mut class_file.constant_pool[index] {
+        ConstantPool::InvokeDynamic(slot, _) => *slot = 3,
+        _ => unreachable!(),
+    }
+    let location = format!("constant pool #{}", index + 1);
+    assert_eq!(
+        violations(&class_file),
+        [
+            ("V0202", Severity::Error, location),
+            // which leaves the only bootstrap method unused
+            (
+                "V0206",
+                Severity::Warning,
+                "class > BootstrapMethods 0".to_string()
+            ),
+        ]
+    );
+}
+
+#[test]
+fn a_constant_without_the_attribute_fails() {
+    let mut class_file = concatenating();
+    class_file
+        .attributes
+        .retain(|attribute| !matches!(attribute, Attribute::BootstrapMethods(_)));
+    class_file.attributes_count -= 1;
+    let location = format!("constant pool #{}", invoke_dynamic(&class_file) + 1);
+    assert_eq!(
+        violations(&class_file),
+        [("V0201", Severity::Error, location)]
+    );
+}
+
+#[test]
+fn an_orphan_bootstrap_method_is_a_warning() {
+    let mut class_file = concatenating();
+    let methods = bootstrap_methods(&mut class_file);
+    methods.push(methods[0].clone());
+    assert_eq!(
+        violations(&class_file),
+        [(
+            "V0206",
+            Severity::Warning,
+            "class > BootstrapMethods 1".to_string()
+        )]
+    );
+}
+
+#[test]
+fn bootstrap_handles_and_arguments_are_checked() {
+    let mut class_file = concatenating();
+    let utf8 = class_file
+        .constant_pool
+        .iter()
+        .position(|constant| matches!(constant, ConstantPool::UTF8(_)))
+        .unwrap() as u16
+        + 1;
+    let handle = bootstrap_methods(&mut class_file)[0].bootstrap_method_ref;
+    bootstrap_methods(&mut class_file)[0]
+        .bootstrap_arguments
+        .push(utf8);
+    match &mut class_file.constant_pool[handle as usize - 1] {
+        // REF_invokeVirtual
+        ConstantPool::MethodHandle(kind, _) => *kind = 5,
+        _ => unreachable!(),
+    }
+    let location = "class > BootstrapMethods 0".to_string();
+    assert_eq!(
+        violations(&class_file),
+        [
+            ("V0203", Severity::Warning, location.clone()),
+            ("V0205", Severity::Error, location.clone()),
+        ]
+    );
+
+    bootstrap_methods(&mut class_file)[0].bootstrap_method_ref = utf8;
+    assert_eq!(
+        violations(&class_file),
+        [
+            ("V0204", Severity::Error, location.clone()),
+            ("V0205", Severity::Error, location),
+        ]
+    );
+}
@@ -0,0 +1,143 @@
+#![cfg(feature = "analysis")]
+
+mod common;
+
+use std::time::{Duration, Instant};
+
+use common::RawClass;
+use jvmb::{
+    budget::{AnalysisBudget, BudgetExceeded, Limit},
+    cfg::ControlFlowGraph,
+    classfile::ClassFile,
+    frames::{infer_frames_within, infer_stacks_within},
+    pattern::Pattern,
+};
+
+/// How many ways the switch of [`fan_out`] branches.
+const TARGETS: usize = 10_000;
+
+/// A class whose static method `run(I)V` is a `tableswitch` over
+/// [`TARGETS`] cases, each branching to a `return` of its own, with a
+/// `StackMapTable` declaring the frame of every one.
+fn fan_out() -> ClassFile {
+    // iload_0, tableswitch and its padding
+    let mut code = vec![0x1a, 0xaa, 0, 0];
+    let table_end = code.len() + 12 + 4 * TARGETS;
+    // the default is the last return
+    code.extend((table_end as i32 + TARGETS as i32 - 1).to_be_bytes());
+    code.extend(0i32.to_be_bytes());
+    code.extend((TARGETS as i32 - 1).to_be_bytes());
+    for case in 0..TARGETS {
+        code.extend((table_end as i32 - 1 + case as i32).to_be_bytes());
+    }
+    code.extend([0xb1; TARGETS]);
+
+    // same_frame_extended at the first return, and same_frame at each after
+    let mut frames = (TARGETS as u16).to_be_bytes().to_vec();
+    frames.push(251);
+    frames.extend((table_end as u16).to_be_bytes());
+    frames.extend([0; TARGETS - 1]);
+
+    let mut class = RawClass::new("FanOut", "java/lang/Object");
+    let stack_map_table = class.attribute("StackMapTable", &frames);
+    let code = class.code(1, 1, &code, &[stack_map_table]);
+    class.method(0x0009, "run", "(I)V", &[code]);
+    ClassFile::from_bytes(&class.bytes()).unwrap()
+}
+
+fn exceeded(limit: Limit) -> Option<BudgetExceeded> {
+    Some(BudgetExceeded { limit })
+}
+
+#[test]
+fn an_unlimited_budget_sees_the_whole_switch() {
+    let class_file = fan_out();
+    let code = class_file.methods[0].code().unwrap();
+    let cfg = ControlFlowGraph::build_within(code, &AnalysisBudget::unlimited()).unwrap();
+    assert!(cfg.is_complete());
+    assert_eq!(cfg.value.blocks.len(), TARGETS + 1);
+    let frames = infer_frames_within(
+        &class_file,
+        &class_file.methods[0],
+        &AnalysisBudget::unlimited(),
+    )
+    .unwrap();
+    assert!(frames.is_complete());
+    // iload_0, the switch and every return
+    assert_eq!(frames.value.len(), TARGETS + 2);
+}
+
+#[test]
+fn the_graph_stops_at_its_block_limit() {
+    let class_file = fan_out();
+    let code = class_file.methods[0].code().unwrap();
+    let budget = AnalysisBudget {
+        max_blocks: Some(100),
+        ..AnalysisBudget::default()
+    };
+    let cfg = ControlFlowGraph::build_within(code, &budget).unwrap();
+    assert_eq!(cfg.exceeded, exceeded(Limit::Blocks));
+    assert!(cfg.value.blocks.len() <= 100, "{}", cfg.value.blocks.len());
+
+    let budget = AnalysisBudget {
+        max_instructions: Some(1000),
+        ..AnalysisBudget::default()
+    };
+    let cfg = ControlFlowGraph::build_within(code, &budget).unwrap();
+    assert_eq!(cfg.exceeded, exceeded(Limit::Instructions));
+    assert!(cfg.value.blocks.is_empty());
+}
+
+#[test]
+fn the_interpreter_stops_at_its_state_limit() {
+    let class_file = fan_out();
+    let method = &class_file.methods[0];
+    let budget = AnalysisBudget {
+        max_states: Some(50),
+        ..AnalysisBudget::default()
+    };
+    let frames = infer_frames_within(&class_file, method, &budget).unwrap();
+    assert_eq!(frames.exceeded, exceeded(Limit::States));
+    assert!(frames.value.len() <= 50, "{}", frames.value.len());
+
+    let stacks = infer_stacks_within(&class_file, method, &budget).unwrap();
+    assert!(!stacks.is_complete());
+    assert!(stacks.value.len() <= 50);
+}
+
+#[test]
+fn patterns_stop_at_their_state_limit() {
+    let class_file = fan_out();
+    let code = class_file.methods[0].code().unwrap();
+    let pattern = Pattern::parse("return").unwrap();
+    let budget = AnalysisBudget {
+        max_states: Some(TARGETS / 2),
+        ..AnalysisBudget::default()
+    };
+    let matches = pattern
+        .find_within(code, &class_file.constant_pool, &budget)
+        .unwrap();
+    assert_eq!(matches.exceeded, exceeded(Limit::States));
+    assert!(matches.value.len() < TARGETS / 2);
+    let all = pattern.find(code, &class_file.constant_pool).unwrap();
+    assert_eq!(all.len(), TARGETS);
+    assert_eq!(matches.value, all[..matches.value.len()]);
+}
+
+#[test]
+fn a_passed_deadline_stops_at_once() {
+    let class_file = fan_out();
+    let method = &class_file.methods[0];
+    let budget = AnalysisBudget {
+        deadline: Some(Instant::now()),
+        ..AnalysisBudget::default()
+    };
+    let started = Instant::now();
+    let frames = infer_frames_within(&class_file, method, &budget).unwrap();
+    assert_eq!(frames.exceeded, exceeded(Limit::Deadline));
+    assert!(frames.value.is_empty());
+    assert!(started.elapsed() < Duration::from_secs(1));
+
+    let cfg = ControlFlowGraph::build_within(method.code().unwrap(), &budget).unwrap();
+    assert_eq!(cfg.exceeded, exceeded(Limit::Deadline));
+}
@@ -0,0 +1,89 @@
+#![cfg(feature = "write")]
+
+mod common;
+
+use common::fixture;
+use jvmb::{
+    classfile::ClassFile,
+    constantpool::ConstantPool,
+    descriptor::{BaseType, ClassRef, FieldType},
+    javap::javap,
+    remap::remap,
+};
+
+fn arrays() -> ClassFile {
+    ClassFile::from_bytes(&fixture("arrays/Arrays")).unwrap()
+}
+
+fn class_refs(class: &ClassFile) -> Vec<ClassRef> {
+    (1..=class.constant_pool.len() as u16)
+        .filter_map(|index| ConstantPool::class_ref(&class.constant_pool, index))
+        .collect()
+}
+
+fn array_of(dimensions: u8, element_type: FieldType) -> ClassRef {
+    ClassRef::Array(FieldType::Array {
+        dimensions,
+        element_type: Box::new(element_type),
+    })
+}
+
+#[test]
+fn class_constants_are_told_apart_from_array_types() {
+    let refs = class_refs(&arrays());
+    for expected in [
+        ClassRef::Object("java/util/List".to_string()),
+        array_of(1, FieldType::Object("java/lang/String".to_string())),
+        array_of(2, FieldType::Base(BaseType::Int)),
+        array_of(1, FieldType::Object("java/lang/Object".to_string())),
+        array_of(2, FieldType::Object("java/lang/String".to_string())),
+    ] {
+        assert!(refs.contains(&expected), "{:?} in {:?}", expected, refs);
+    }
+    assert_eq!(
+        array_of(2, FieldType::Base(BaseType::Int)).class_name(),
+        None
+    );
+}
+
+#[test]
+fn referenced_classes_look_through_array_types() {
+    let classes = arrays().referenced_classes();
+    for name in ["java/lang/String", "java/lang/Object", "java/util/List"] {
+        assert!(classes.contains(name), "{} in {:?}", name, classes);
+    }
+    assert!(
+        classes.iter().all(|name| !name.starts_with('[')),
+        "{:?}",
+        classes
+    );
+}
+
+#[test]
+fn remapping_rewrites_the_element_type_of_arrays() {
+    let mut class = arrays();
+    remap(&mut class, &|name: &str| {
+        (name == "java/lang/String").then(|| "text/Str".to_string())
+    })
+    .unwrap();
+    let names: Vec<_> = (1..=class.constant_pool.len() as u16)
+        .filter_map(|index| ConstantPool::class_name(&class.constant_pool, index))
+        .collect();
+    assert!(names.contains(&"[Ltext/Str;"), "{:?}", names);
+    assert!(names.contains(&"[[Ltext/Str;"), "{:?}", names);
+    assert!(names.iter().all(|name| !name.contains("java/lang/String")));
+}
+
+#[test]
+fn disassembly_quotes_array_types_as_javap_does() {
+    let text = javap(&arrays());
+    for comment in [
+        "// class \"[Ljava/lang/String;\"",
+        "// class \"[[I\"",
+        "// class \"[Ljava/lang/Object;\"",
+        "// class java/util/List",
+        "// class \"[[Ljava/lang/String;\"",
+    ] {
+        assert!(text.contains(comment), "{} in\n{}", comment, text);
+    }
+}
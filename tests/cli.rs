@@ -0,0 +1,363 @@
+#![cfg(feature = "cli")]
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::{Command, Output},
+};
+
+use jvmb::{accessflags::ACC_PUBLIC, builder::ClassFileBuilder, jar::JarWriter};
+
+/// Runs the `jvmb` binary with `args`.
+fn jvmb(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_jvmb"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+/// Writes `bytes` to a file of this test's own in the temporary directory.
+fn write_temp(name: &str, bytes: &[u8]) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("jvmb-cli-{}-{}", std::process::id(), name));
+    fs::write(&path, bytes).unwrap();
+    path
+}
+
+/// A class `name` with one public `int` field called `field`.
+fn class_with_field(name: &str, field: &str) -> Vec<u8> {
+    let mut builder = ClassFileBuilder::new(name);
+    builder.add_field(ACC_PUBLIC, field, "I");
+    builder.build().unwrap().to_bytes().unwrap()
+}
+
+/// The path of the class file `name` under `tests/fixtures`.
+fn fixture(name: &str) -> String {
+    format!(
+        "{}/tests/fixtures/{}.class",
+        env!("CARGO_MANIFEST_DIR"),
+        name
+    )
+}
+
+fn exit_code(output: &Output) -> i32 {
+    output.status.code().expect("jvmb was killed by a signal")
+}
+
+#[test]
+fn success_exits_with_0() {
+    let class = write_temp("Success.class", &class_with_field("Success", "x"));
+    let output = jvmb(&["header", class.to_str().unwrap()]);
+    assert_eq!(exit_code(&output), 0, "{:?}", output);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("public class Success"));
+    fs::remove_file(class).unwrap();
+}
+
+#[test]
+fn parse_failure_exits_with_2() {
+    let corrupt = write_temp("Corrupt.class", b"not a class file");
+    let output = jvmb(&["header", corrupt.to_str().unwrap()]);
+    assert_eq!(exit_code(&output), 2, "{:?}", output);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("bad magic number"));
+
+    // cut short after the magic and version
+    let mut truncated = class_with_field("Truncated", "x");
+    truncated.truncate(12);
+    let truncated = write_temp("Truncated.class", &truncated);
+    let output = jvmb(&["header", truncated.to_str().unwrap()]);
+    assert_eq!(exit_code(&output), 2, "{:?}", output);
+
+    fs::remove_file(corrupt).unwrap();
+    fs::remove_file(truncated).unwrap();
+}
+
+#[test]
+fn usage_error_exits_with_1() {
+    for args in [
+        &[][..],
+        &["no-such-command", "Foo.class"][..],
+        &["header"][..],
+        &["extract", "Foo.class", "--what", "nonsense"][..],
+    ] {
+        let output = jvmb(args);
+        assert_eq!(exit_code(&output), 1, "jvmb {:?}: {:?}", args, output);
+        assert!(!output.stderr.is_empty());
+    }
+}
+
+#[test]
+fn diff_check_exits_with_4_on_differences() {
+    let old = write_temp("DiffOld.class", &class_with_field("Diff", "x"));
+    let new = write_temp("DiffNew.class", &class_with_field("Diff", "y"));
+    let (old_path, new_path) = (old.to_str().unwrap(), new.to_str().unwrap());
+    assert_eq!(
+        exit_code(&jvmb(&["diff", "--check", old_path, old_path])),
+        0
+    );
+    assert_eq!(
+        exit_code(&jvmb(&["diff", "--check", old_path, new_path])),
+        4
+    );
+    fs::remove_file(old).unwrap();
+    fs::remove_file(new).unwrap();
+}
+
+#[test]
+fn annotations_says_where_type_annotations_sit() {
+    let output = jvmb(&["annotations", &fixture("typeannos/Annotated")]);
+    assert_eq!(exit_code(&output), 0, "{:?}", output);
+    let nullable = "@typeannos.Annotated$Nullable on";
+    let expected = [
+        "class bound of type parameter T of class typeannos/Annotated",
+        "type of field name",
+        "return type of toString()",
+        "element type of type of the 1st parameter of put(java.lang.Object[])",
+        "type of the 1st parameter of put(java.lang.Object[])",
+        "type argument 0 of type of the 1st parameter of first(java.util.List<U>)",
+        "type of the cast at offset 1 in cast(java.lang.Object)",
+        "type in the instanceof at offset 3 in check(java.lang.Object)",
+        "type of local variable local (slot 2) in check(java.lang.Object)",
+    ]
+    .map(|location| format!("{} {}\n", nullable, location))
+    .concat();
+    assert_eq!(String::from_utf8_lossy(&output.stdout), expected);
+}
+
+#[test]
+fn concatenated_classes_print_until_the_one_cut_short() {
+    let mut bytes = fs::read(fixture("records/Point")).unwrap();
+    let point = bytes.len();
+    bytes.extend(fs::read(fixture("arrays/Arrays")).unwrap());
+    let arrays = bytes.len();
+    let branches = fs::read(fixture("frames/Branches")).unwrap();
+    bytes.extend_from_slice(&branches[..branches.len() / 2]);
+    let dump = write_temp("Concatenated.bin", &bytes);
+
+    let output = jvmb(&[dump.to_str().unwrap(), "--concatenated"]);
+    assert_eq!(exit_code(&output), 2, "{:?}", output);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.starts_with(&format!("// bytes 0..{}\n", point)));
+    assert!(stdout.contains(&format!("// bytes {}..{}\n", point, arrays)));
+    assert!(stdout.contains("public class arrays.Arrays"));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains(&format!("class file at offset {} failed to parse", arrays)),
+        "{}",
+        stderr
+    );
+    fs::remove_file(dump).unwrap();
+}
+
+#[test]
+fn split_packages_exits_with_3_unless_allowed() {
+    let jars: Vec<PathBuf> = [
+        ("SplitA.jar", "com/shared/A"),
+        ("SplitB.jar", "com/shared/B"),
+    ]
+    .iter()
+    .map(|(jar, class)| {
+        let mut writer = JarWriter::new();
+        let bytes = class_with_field(class, "x");
+        writer.add(&format!("{}.class", class), &bytes).unwrap();
+        write_temp(jar, &writer.finish().unwrap())
+    })
+    .collect();
+    let (a, b) = (jars[0].to_str().unwrap(), jars[1].to_str().unwrap());
+
+    let output = jvmb(&["split-packages", a, b]);
+    assert_eq!(exit_code(&output), 3, "{:?}", output);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        format!("com.shared\n       1  {}\n       1  {}\n", a, b)
+    );
+    let output = jvmb(&["split-packages", "--allow", "com.*", a, b]);
+    assert_eq!(exit_code(&output), 0, "{:?}", output);
+    assert!(String::from_utf8_lossy(&output.stdout).starts_with("com.shared (allowed)\n"));
+    assert_eq!(exit_code(&jvmb(&["split-packages", a])), 0);
+    for jar in jars {
+        fs::remove_file(jar).unwrap();
+    }
+}
+
+/// A JDK with only a `java.base` module, of one empty class in each of
+/// `packages`, under `name` in the temporary directory.
+fn java_home(name: &str, packages: &[&str]) -> PathBuf {
+    let mut writer = JarWriter::new();
+    for package in packages {
+        let class = format!("{}/Stub", package);
+        let bytes = ClassFileBuilder::new(&class)
+            .build()
+            .unwrap()
+            .to_bytes()
+            .unwrap();
+        writer
+            .add(&format!("classes/{}.class", class), &bytes)
+            .unwrap();
+    }
+    let mut jmod = b"JM\x01\x00".to_vec();
+    jmod.extend(writer.finish().unwrap());
+    let java_home = write_temp(name, b"").with_extension("d");
+    fs::create_dir_all(java_home.join("jmods")).unwrap();
+    fs::write(java_home.join("jmods/java.base.jmod"), jmod).unwrap();
+    java_home
+}
+
+/// The lines of `text`, sorted.
+fn sorted_lines(text: &str) -> Vec<&str> {
+    let mut lines: Vec<&str> = text.lines().collect();
+    lines.sort_unstable();
+    lines
+}
+
+#[test]
+fn deps_matches_jdeps() {
+    let java_home = java_home(
+        "JavaHome",
+        &["java/io", "java/lang", "java/lang/invoke", "java/util"],
+    );
+    let dir = format!("{}/tests/fixtures/deps", env!("CARGO_MANIFEST_DIR"));
+    let (app, lib) = (format!("{}/app.jar", dir), format!("{}/lib.jar", dir));
+    for (flags, golden) in [(&[][..], "app.jdeps.txt"), (&["-s"][..], "app.jdeps-s.txt")] {
+        let mut args = vec!["deps", "--system", java_home.to_str().unwrap()];
+        args.extend(flags);
+        args.extend(["--classpath", &lib, &app]);
+        let output = jvmb(&args);
+        assert_eq!(exit_code(&output), 0, "{:?}", output);
+        let expected = fs::read_to_string(format!("{}/{}", dir, golden)).unwrap();
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert_eq!(sorted_lines(&stdout), sorted_lines(&expected), "{}", golden);
+    }
+    fs::remove_dir_all(java_home).unwrap();
+}
+
+/// A jar of every class file under `tests/fixtures`, and an entry which
+/// isn't a class.
+fn fixtures_jar() -> Vec<u8> {
+    fn add(writer: &mut JarWriter, root: &Path, dir: &Path) {
+        let mut entries: Vec<PathBuf> = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        entries.sort();
+        for path in entries {
+            if path.is_dir() {
+                add(writer, root, &path);
+            } else if path
+                .extension()
+                .is_some_and(|extension| extension == "class")
+            {
+                let name = path.strip_prefix(root).unwrap().to_str().unwrap();
+                writer.add(name, &fs::read(&path).unwrap()).unwrap();
+            }
+        }
+    }
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    let mut writer = JarWriter::new();
+    add(&mut writer, &root, &root);
+    writer
+        .add("broken/Broken.class", b"not a class file")
+        .unwrap();
+    writer.finish().unwrap()
+}
+
+#[test]
+fn aggregated_output_is_the_same_every_run_on_any_number_of_threads() {
+    let jar = write_temp("Fixtures.jar", &fixtures_jar());
+    let jar = jar.to_str().unwrap();
+    let stdout = |args: &[&str]| {
+        let output = jvmb(args);
+        assert!(!output.stdout.is_empty(), "jvmb {:?}: {:?}", args, output);
+        (output.stdout, output.stderr)
+    };
+    for report in ["versions", "deps", "strings", "errors"] {
+        let serial = stdout(&["scan", "--report", report, "--jobs", "1", jar]);
+        for _ in 0..2 {
+            assert_eq!(
+                stdout(&["scan", "--report", report, "--jobs", "4", jar]),
+                serial,
+                "{}",
+                report
+            );
+        }
+    }
+    for args in [
+        &["report", "--json", jar][..],
+        &["clones", "--min-size", "1", "--similarity", "0.5", jar][..],
+        &["string-building", jar][..],
+    ] {
+        assert_eq!(stdout(args), stdout(args), "{:?}", args);
+    }
+    fs::remove_file(jar).unwrap();
+}
+
+#[test]
+fn show_stack_matches_its_golden_dump() {
+    let output = jvmb(&["disasm", "--show-stack", &fixture("stack/Greeting")]);
+    assert_eq!(exit_code(&output), 0, "{:?}", output);
+    let golden = format!(
+        "{}/tests/fixtures/stack/Greeting.show-stack.txt",
+        env!("CARGO_MANIFEST_DIR")
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        fs::read_to_string(golden).unwrap()
+    );
+}
+
+#[test]
+fn lint_flags_and_scrub_paths_removes_a_source_file_path() {
+    let mut writer = JarWriter::new();
+    let leaky = fs::read(fixture("sourcepaths/Leaky")).unwrap();
+    writer.add("sourcepaths/Leaky.class", &leaky).unwrap();
+    writer
+        .add(
+            "sourcepaths/Clean.class",
+            &class_with_field("sourcepaths/Clean", "x"),
+        )
+        .unwrap();
+    let input = write_temp("Leaky.jar", &writer.finish().unwrap());
+    let output = input.with_extension("scrubbed.jar");
+    let (input, output) = (input.to_str().unwrap(), output.to_str().unwrap());
+
+    let lint = jvmb(&["lint", "--source-paths", input]);
+    assert!(
+        String::from_utf8_lossy(&lint.stdout)
+            .contains(r#"SourceFile "C:\\build\\src\\sourcepaths\\Leaky.java" is a path"#),
+        "{:?}",
+        lint
+    );
+
+    let scrubbed = jvmb(&["scrub-paths", input, "-o", output]);
+    assert_eq!(exit_code(&scrubbed), 0, "{:?}", scrubbed);
+    assert_eq!(
+        String::from_utf8_lossy(&scrubbed.stdout),
+        format!(
+            "{}: {}\n{}: 1 of 2 classes changed\n",
+            "sourcepaths/Leaky.class",
+            r#"SourceFile "C:\\build\\src\\sourcepaths\\Leaky.java" -> "Leaky.java""#,
+            output
+        )
+    );
+    let lint = jvmb(&["lint", "--source-paths", output]);
+    assert!(
+        !String::from_utf8_lossy(&lint.stdout).contains("SourceFile"),
+        "{:?}",
+        lint
+    );
+    let again = jvmb(&["scrub-paths", output, "-o", input]);
+    assert!(String::from_utf8_lossy(&again.stdout).ends_with(": 0 of 2 classes changed\n"));
+    fs::remove_file(input).unwrap();
+    fs::remove_file(output).unwrap();
+}
+
+#[test]
+fn json_prints_the_parsed_class() {
+    let output = jvmb(&[&fixture("records/Point"), "--json"]);
+    assert_eq!(exit_code(&output), 0, "{:?}", output);
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["major_version"], 61);
+    assert_eq!(json["this_class"]["resolved"], "records/Point");
+    let methods = json["methods"].as_array().unwrap();
+    assert!(methods
+        .iter()
+        .any(|method| method["name"]["resolved"] == "<init>"));
+}
@@ -0,0 +1,72 @@
+#![cfg(feature = "analysis")]
+
+mod common;
+
+use common::fixture;
+use jvmb::{
+    classfile::ClassFile,
+    clones::{CloneDetector, CloneGroup},
+};
+
+/// The groups among the methods of the fixture classes of at least
+/// `min_size` bytes.
+fn groups(min_size: usize, similarity: Option<f64>) -> Vec<CloneGroup> {
+    let mut detector = CloneDetector::new(min_size);
+    for name in ["clones/Orders", "clones/Invoices"] {
+        let class_file = ClassFile::from_bytes(&fixture(name)).unwrap();
+        detector.add_class(&class_file).unwrap();
+    }
+    detector.groups(similarity)
+}
+
+fn names(group: &CloneGroup) -> Vec<&str> {
+    group
+        .members
+        .iter()
+        .map(|member| member.name.as_str())
+        .collect()
+}
+
+#[test]
+fn copies_match_across_differently_laid_out_pools() {
+    let groups = groups(10, None);
+    assert_eq!(groups.len(), 1, "{:#?}", groups);
+    assert_eq!(
+        names(&groups[0]),
+        ["clones/Invoices.total([I)I", "clones/Orders.total([I)I"]
+    );
+    assert!(groups[0].identical);
+    assert_eq!(groups[0].duplicated_bytes(), 46);
+}
+
+#[test]
+fn near_copies_join_only_above_the_threshold() {
+    let loose = groups(10, Some(0.5));
+    assert_eq!(loose.len(), 1, "{:#?}", loose);
+    assert_eq!(
+        names(&loose[0]),
+        [
+            "clones/Invoices.discounted([I)I",
+            "clones/Invoices.total([I)I",
+            "clones/Orders.total([I)I",
+        ]
+    );
+    assert!(!loose[0].identical);
+    // all but the 52 bytes of discounted
+    assert_eq!(loose[0].duplicated_bytes(), 46 + 46);
+
+    assert_eq!(groups(10, Some(0.9)), groups(10, None));
+}
+
+#[test]
+fn small_methods_are_left_out() {
+    // the getters read fields of different classes, so only the empty
+    // constructors are copies of each other
+    let groups = groups(1, None);
+    assert_eq!(groups.len(), 2, "{:#?}", groups);
+    assert_eq!(
+        names(&groups[1]),
+        ["clones/Invoices.<init>()V", "clones/Orders.<init>()V"]
+    );
+    assert_eq!(groups[1].duplicated_bytes(), 5);
+}
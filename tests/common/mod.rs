@@ -0,0 +1,172 @@
+//! Assembles class files byte by byte, for the malformed and unusual ones
+//! the builder won't make.
+
+#![allow(dead_code)]
+
+/// A class file being assembled. Entries are added to the constant pool as
+/// they are asked for, so indices come back from the methods adding them.
+pub struct RawClass {
+    pub version: (u16, u16),
+    pub access_flags: u16,
+    pool: Vec<u8>,
+    /// The index of the next entry.
+    next: u16,
+    pub this_class: u16,
+    super_class: u16,
+    fields: Vec<Vec<u8>>,
+    methods: Vec<Vec<u8>>,
+    attributes: Vec<Vec<u8>>,
+}
+
+impl RawClass {
+    /// A class `name` extending `super_name`, of Java 17.
+    pub fn new(name: &str, super_name: &str) -> Self {
+        let mut class = RawClass {
+            version: (61, 0),
+            access_flags: 0x0021,
+            pool: Vec::new(),
+            next: 1,
+            this_class: 0,
+            super_class: 0,
+            fields: Vec::new(),
+            methods: Vec::new(),
+            attributes: Vec::new(),
+        };
+        class.this_class = class.class(name);
+        class.super_class = class.class(super_name);
+        class
+    }
+
+    /// Adds an entry of `tag` with `body`, taking two slots for a Long or
+    /// a Double, and returns its index.
+    pub fn entry(&mut self, tag: u8, body: &[u8]) -> u16 {
+        let index = self.next;
+        self.pool.push(tag);
+        self.pool.extend_from_slice(body);
+        self.next += if tag == 5 || tag == 6 { 2 } else { 1 };
+        index
+    }
+
+    /// The index the next entry added will have.
+    pub fn next_index(&self) -> u16 {
+        self.next
+    }
+
+    pub fn utf8(&mut self, value: &str) -> u16 {
+        let mut body = (value.len() as u16).to_be_bytes().to_vec();
+        body.extend_from_slice(value.as_bytes());
+        self.entry(1, &body)
+    }
+
+    pub fn class(&mut self, name: &str) -> u16 {
+        let name = self.utf8(name);
+        self.entry(7, &name.to_be_bytes())
+    }
+
+    /// An attribute `name` with `body`, its length that of the body.
+    pub fn attribute(&mut self, name: &str, body: &[u8]) -> Vec<u8> {
+        self.attribute_with_length(name, body.len() as u32, body)
+    }
+
+    /// An attribute `name` with `body`, declaring `length` whatever the
+    /// body's is.
+    pub fn attribute_with_length(&mut self, name: &str, length: u32, body: &[u8]) -> Vec<u8> {
+        let mut attribute = self.utf8(name).to_be_bytes().to_vec();
+        attribute.extend_from_slice(&length.to_be_bytes());
+        attribute.extend_from_slice(body);
+        attribute
+    }
+
+    /// A `Code` attribute with no exception table.
+    pub fn code(
+        &mut self,
+        max_stack: u16,
+        max_locals: u16,
+        code: &[u8],
+        attributes: &[Vec<u8>],
+    ) -> Vec<u8> {
+        let mut body = Vec::new();
+        body.extend_from_slice(&max_stack.to_be_bytes());
+        body.extend_from_slice(&max_locals.to_be_bytes());
+        body.extend_from_slice(&(code.len() as u32).to_be_bytes());
+        body.extend_from_slice(code);
+        body.extend_from_slice(&[0, 0]);
+        push_table(&mut body, attributes);
+        self.attribute("Code", &body)
+    }
+
+    pub fn field(
+        &mut self,
+        access_flags: u16,
+        name: &str,
+        descriptor: &str,
+        attributes: &[Vec<u8>],
+    ) {
+        let member = self.member(access_flags, name, descriptor, attributes);
+        self.fields.push(member);
+    }
+
+    pub fn method(
+        &mut self,
+        access_flags: u16,
+        name: &str,
+        descriptor: &str,
+        attributes: &[Vec<u8>],
+    ) {
+        let member = self.member(access_flags, name, descriptor, attributes);
+        self.methods.push(member);
+    }
+
+    pub fn class_attribute(&mut self, attribute: Vec<u8>) {
+        self.attributes.push(attribute);
+    }
+
+    pub fn bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![0xca, 0xfe, 0xba, 0xbe];
+        bytes.extend_from_slice(&self.version.1.to_be_bytes());
+        bytes.extend_from_slice(&self.version.0.to_be_bytes());
+        bytes.extend_from_slice(&self.next.to_be_bytes());
+        bytes.extend_from_slice(&self.pool);
+        bytes.extend_from_slice(&self.access_flags.to_be_bytes());
+        bytes.extend_from_slice(&self.this_class.to_be_bytes());
+        bytes.extend_from_slice(&self.super_class.to_be_bytes());
+        bytes.extend_from_slice(&[0, 0]);
+        push_table(&mut bytes, &self.fields);
+        push_table(&mut bytes, &self.methods);
+        push_table(&mut bytes, &self.attributes);
+        bytes
+    }
+
+    fn member(
+        &mut self,
+        access_flags: u16,
+        name: &str,
+        descriptor: &str,
+        attributes: &[Vec<u8>],
+    ) -> Vec<u8> {
+        let mut member = access_flags.to_be_bytes().to_vec();
+        member.extend_from_slice(&self.utf8(name).to_be_bytes());
+        member.extend_from_slice(&self.utf8(descriptor).to_be_bytes());
+        push_table(&mut member, attributes);
+        member
+    }
+}
+
+/// Writes the count of `items`, then each of them.
+pub fn push_table(out: &mut Vec<u8>, items: &[Vec<u8>]) {
+    out.extend_from_slice(&(items.len() as u16).to_be_bytes());
+    for item in items {
+        out.extend_from_slice(item);
+    }
+}
+
+/// The bytes of the class file `name` under `tests/fixtures`, e.g.
+/// `records/Point`.
+pub fn fixture(name: &str) -> Vec<u8> {
+    let path = format!(
+        "{}/tests/fixtures/{}.class",
+        env!("CARGO_MANIFEST_DIR"),
+        name
+    );
+    std::fs::read(&path).unwrap_or_else(|e| panic!("{}: {}", path, e))
+}
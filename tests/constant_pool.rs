@@ -0,0 +1,224 @@
+#![cfg(feature = "write")]
+
+mod common;
+
+use common::fixture;
+use jvmb::{
+    attribute::Attribute,
+    builder::ClassFileBuilder,
+    classfile::{ClassFile, UnusedConstant},
+    constantpool::{ConstantPool, DoubleBits, FloatBits},
+};
+
+/// Parses `bytes`, writes the class back out and parses that again.
+fn round_trip(bytes: &[u8]) -> ClassFile {
+    let written = ClassFile::from_bytes(bytes).unwrap().to_bytes().unwrap();
+    assert_eq!(written, bytes);
+    ClassFile::from_bytes(&written).unwrap()
+}
+
+#[test]
+fn signalling_nan_bits_survive_parse_write_parse() {
+    // quiet bit clear, payload 1: signalling NaNs, which going through an
+    // FPU register may quieten
+    let float_bits = 0x7f80_0001;
+    let double_bits = 0x7ff0_0000_0000_0001;
+    let mut builder = ClassFileBuilder::new("Foo");
+    let float = builder.constant_pool().float(f32::from_bits(float_bits));
+    let double = builder.constant_pool().double(f64::from_bits(double_bits));
+    let bytes = builder.build().unwrap().to_bytes().unwrap();
+
+    let class_file = round_trip(&bytes);
+    match &class_file.constant_pool[float as usize - 1] {
+        ConstantPool::Float(bits) => {
+            assert!(bits.is_nan());
+            assert_eq!(bits.value().to_bits(), float_bits);
+        }
+        constant => panic!("expected a Float, found {:?}", constant),
+    }
+    match &class_file.constant_pool[double as usize - 1] {
+        ConstantPool::Double(bits) => {
+            assert!(bits.is_nan());
+            assert_eq!(bits.value().to_bits(), double_bits);
+        }
+        constant => panic!("expected a Double, found {:?}", constant),
+    }
+}
+
+#[test]
+fn nans_with_different_payloads_stay_apart() {
+    let mut builder = ClassFileBuilder::new("Foo");
+    let quiet = builder.constant_pool().float(f32::NAN);
+    let signalling = builder.constant_pool().float(f32::from_bits(0x7f80_0001));
+    assert_ne!(quiet, signalling);
+    let class_file = round_trip(&builder.build().unwrap().to_bytes().unwrap());
+    assert_eq!(
+        class_file.constant_pool[quiet as usize - 1],
+        ConstantPool::Float(FloatBits(f32::NAN.to_bits()))
+    );
+    assert_eq!(
+        class_file.constant_pool[signalling as usize - 1],
+        ConstantPool::Float(FloatBits(0x7f80_0001))
+    );
+    assert_ne!(
+        ConstantPool::Double(DoubleBits(0.0f64.to_bits())),
+        ConstantPool::Double(DoubleBits((-0.0f64).to_bits()))
+    );
+}
+
+/// A `CONSTANT_Utf8` entry.
+fn utf8(value: &str) -> Vec<u8> {
+    let mut entry = vec![1];
+    entry.extend((value.len() as u16).to_be_bytes());
+    entry.extend(value.as_bytes());
+    entry
+}
+
+/// A class like the one javac compiles from
+///
+/// ```java
+/// class Constants {
+///     static final double RATE = 1.5;
+///     static final long LIMIT = 42;
+///     int after() { return 1; }
+/// }
+/// ```
+///
+/// less the constructor, with the `double` and the `long` first in its
+/// constant pool, so that every other entry comes after both two-slot
+/// constants.
+fn constants_class() -> Vec<u8> {
+    let mut bytes = vec![0xca, 0xfe, 0xba, 0xbe, 0, 0, 0, 52];
+    bytes.extend(17u16.to_be_bytes());
+    bytes.push(6); // #1, #2: Double
+    bytes.extend(1.5f64.to_bits().to_be_bytes());
+    bytes.push(5); // #3, #4: Long
+    bytes.extend(42i64.to_be_bytes());
+    for value in [
+        "RATE",          // #5
+        "D",             // #6
+        "LIMIT",         // #7
+        "J",             // #8
+        "ConstantValue", // #9
+        "after",         // #10
+        "()I",           // #11
+        "Code",          // #12
+        "Constants",     // #13
+    ] {
+        bytes.extend(utf8(value));
+    }
+    bytes.extend([7, 0, 13]); // #14: Class Constants
+    bytes.extend(utf8("java/lang/Object")); // #15
+    bytes.extend([7, 0, 15]); // #16: Class java/lang/Object
+                              // flags, this class, super class, no interfaces
+    bytes.extend([0x00, 0x20, 0, 14, 0, 16, 0, 0]);
+    // two static final fields, each with a ConstantValue
+    bytes.extend([0, 2]);
+    bytes.extend([0x00, 0x18, 0, 5, 0, 6, 0, 1, 0, 9, 0, 0, 0, 2, 0, 1]);
+    bytes.extend([0x00, 0x18, 0, 7, 0, 8, 0, 1, 0, 9, 0, 0, 0, 2, 0, 3]);
+    // after()I: iconst_1, ireturn
+    bytes.extend([0, 1, 0x00, 0x00, 0, 10, 0, 11, 0, 1]);
+    bytes.extend([
+        0, 12, 0, 0, 0, 14, 0, 1, 0, 1, 0, 0, 0, 2, 0x04, 0xac, 0, 0, 0, 0,
+    ]);
+    // no class attributes
+    bytes.extend([0, 0]);
+    bytes
+}
+
+#[test]
+fn entries_after_long_and_double_resolve() {
+    let class_file = ClassFile::from_bytes(&constants_class()).unwrap();
+    let constant_pool = &class_file.constant_pool;
+    assert_eq!(constant_pool.len(), 16);
+    assert_eq!(
+        constant_pool[0],
+        ConstantPool::Double(DoubleBits(1.5f64.to_bits()))
+    );
+    assert_eq!(constant_pool[1], ConstantPool::Unusable);
+    assert_eq!(constant_pool[2], ConstantPool::Long(42));
+    assert_eq!(constant_pool[3], ConstantPool::Unusable);
+    assert_eq!(ConstantPool::utf8(constant_pool, 5), Some("RATE"));
+    assert_eq!(
+        ConstantPool::class_name(constant_pool, class_file.this_class),
+        Some("Constants")
+    );
+    assert_eq!(
+        ConstantPool::class_name(constant_pool, class_file.super_class),
+        Some("java/lang/Object")
+    );
+
+    let fields: Vec<_> = class_file
+        .fields
+        .iter()
+        .map(|field| {
+            (
+                field.name(constant_pool).unwrap(),
+                field.descriptor(constant_pool).unwrap(),
+                field.attributes(),
+            )
+        })
+        .collect();
+    assert_eq!(fields.len(), 2);
+    assert_eq!((fields[0].0, fields[0].1), ("RATE", "D"));
+    assert_eq!((fields[1].0, fields[1].1), ("LIMIT", "J"));
+    match fields[0].2 {
+        [Attribute::ConstantValue(index)] => assert_eq!(
+            constant_pool[*index as usize - 1],
+            ConstantPool::Double(DoubleBits(1.5f64.to_bits()))
+        ),
+        attributes => panic!("expected a ConstantValue, found {:?}", attributes),
+    }
+    match fields[1].2 {
+        [Attribute::ConstantValue(index)] => {
+            assert_eq!(constant_pool[*index as usize - 1], ConstantPool::Long(42))
+        }
+        attributes => panic!("expected a ConstantValue, found {:?}", attributes),
+    }
+
+    let method = &class_file.methods[0];
+    assert_eq!(method.name(constant_pool), Some("after"));
+    assert_eq!(method.descriptor(constant_pool), Some("()I"));
+    assert_eq!(method.code().unwrap().code, [0x04, 0xac]);
+
+    // the same indices hold after writing the class back
+    assert_eq!(class_file.to_bytes().unwrap(), constants_class());
+}
+
+#[test]
+fn orphans_added_to_a_javac_pool_are_exactly_the_unused_constants() {
+    let mut class_file = ClassFile::from_bytes(&fixture("arrays/Arrays")).unwrap();
+    assert_eq!(class_file.unused_constants(), []);
+
+    let method_ref = class_file
+        .constant_pool
+        .iter()
+        .find(|constant| matches!(constant, ConstantPool::MethodRef(..)))
+        .unwrap()
+        .clone();
+    class_file
+        .constant_pool
+        .push(ConstantPool::UTF8("orphan".to_string()));
+    class_file.constant_pool.push(method_ref);
+    class_file.constant_pool_count += 2;
+    let utf8 = class_file.constant_pool.len() as u16 - 1;
+
+    let class_file = ClassFile::from_bytes(&class_file.to_bytes().unwrap()).unwrap();
+    assert_eq!(
+        class_file.unused_constants(),
+        [
+            UnusedConstant {
+                index: utf8,
+                // CONSTANT_Utf8
+                tag: 1,
+                byte_cost: 9,
+            },
+            UnusedConstant {
+                index: utf8 + 1,
+                // CONSTANT_Methodref
+                tag: 10,
+                byte_cost: 5,
+            },
+        ]
+    );
+}
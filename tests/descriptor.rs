@@ -0,0 +1,195 @@
+mod common;
+
+use common::RawClass;
+use jvmb::{
+    classfile::ClassFile,
+    descriptor::{BaseType, DescriptorError, FieldType, MethodDescriptor},
+};
+
+fn object(name: &str) -> FieldType {
+    FieldType::Object(name.to_string())
+}
+
+fn array(dimensions: u8, element_type: FieldType) -> FieldType {
+    FieldType::Array {
+        dimensions,
+        element_type: Box::new(element_type),
+    }
+}
+
+/// The offset at which `descriptor` fails to parse as a field type.
+fn field_error(descriptor: &str) -> usize {
+    let error = FieldType::parse(descriptor).unwrap_err();
+    assert_eq!(error.descriptor, descriptor);
+    error.offset
+}
+
+/// The offset at which `descriptor` fails to parse as a method descriptor.
+fn method_error(descriptor: &str) -> usize {
+    let error = MethodDescriptor::parse(descriptor).unwrap_err();
+    assert_eq!(error.descriptor, descriptor);
+    error.offset
+}
+
+#[test]
+fn nested_arrays_are_one_type_of_many_dimensions() {
+    let field_type = FieldType::parse("[[D").unwrap();
+    assert_eq!(field_type, array(2, FieldType::Base(BaseType::Double)));
+    assert_eq!(field_type.to_string(), "double[][]");
+    assert_eq!(field_type.class_name(), None);
+
+    let field_type = FieldType::parse("[[[Ljava/util/Map$Entry;").unwrap();
+    assert_eq!(field_type, array(3, object("java/util/Map$Entry")));
+    assert_eq!(field_type.to_string(), "java.util.Map$Entry[][][]");
+    assert_eq!(field_type.class_name(), Some("java/util/Map$Entry"));
+
+    let most = format!("{}I", "[".repeat(255));
+    assert_eq!(
+        FieldType::parse(&most).unwrap(),
+        array(255, FieldType::Base(BaseType::Int))
+    );
+    assert_eq!(field_error(&format!("[{}", most)), 255);
+}
+
+#[test]
+fn void_and_no_parameters() {
+    let descriptor = MethodDescriptor::parse("()V").unwrap();
+    assert_eq!(descriptor.parameters, []);
+    assert_eq!(descriptor.return_type, None);
+    assert_eq!(descriptor.java_parameters(), "()");
+
+    let descriptor = MethodDescriptor::parse("()[Ljava/lang/String;").unwrap();
+    assert_eq!(descriptor.parameters, []);
+    assert_eq!(
+        descriptor.return_type,
+        Some(array(1, object("java/lang/String")))
+    );
+}
+
+#[test]
+fn every_kind_of_parameter_in_order() {
+    let descriptor =
+        MethodDescriptor::parse("(BCDFIJSZLjava/lang/String;[I[[Ljava/lang/Object;)J").unwrap();
+    assert_eq!(
+        descriptor.parameters,
+        [
+            FieldType::Base(BaseType::Byte),
+            FieldType::Base(BaseType::Char),
+            FieldType::Base(BaseType::Double),
+            FieldType::Base(BaseType::Float),
+            FieldType::Base(BaseType::Int),
+            FieldType::Base(BaseType::Long),
+            FieldType::Base(BaseType::Short),
+            FieldType::Base(BaseType::Boolean),
+            object("java/lang/String"),
+            array(1, FieldType::Base(BaseType::Int)),
+            array(2, object("java/lang/Object")),
+        ]
+    );
+    assert_eq!(
+        descriptor.return_type,
+        Some(FieldType::Base(BaseType::Long))
+    );
+    assert_eq!(
+        descriptor.java_parameters(),
+        "(byte, char, double, float, int, long, short, boolean, java.lang.String, int[], \
+         java.lang.Object[][])"
+    );
+}
+
+#[test]
+fn trailing_bytes_are_rejected_where_they_start() {
+    assert_eq!(field_error("I;"), 1);
+    assert_eq!(field_error("Ljava/lang/String;I"), 18);
+    assert_eq!(field_error("[II"), 2);
+    assert_eq!(method_error("()VV"), 2);
+    assert_eq!(method_error("(I)II"), 4);
+    assert_eq!(method_error("()Ljava/lang/String;;"), 20);
+}
+
+#[test]
+fn truncated_descriptors_are_rejected_where_they_end() {
+    assert_eq!(field_error(""), 0);
+    assert_eq!(field_error("[["), 2);
+    assert_eq!(method_error("(I"), 2);
+    assert_eq!(method_error("("), 1);
+    assert_eq!(method_error("(I)"), 3);
+    assert_eq!(method_error(""), 0);
+    assert_eq!(method_error("I)V"), 0);
+}
+
+#[test]
+fn an_unterminated_or_empty_class_name_is_rejected_at_its_l() {
+    assert_eq!(field_error("Ljava/lang/String"), 0);
+    assert_eq!(field_error("[[Ljava/lang/String"), 2);
+    assert_eq!(field_error("L;"), 0);
+    assert_eq!(method_error("(ILjava/lang/String)V"), 2);
+    assert_eq!(method_error("()Ljava/lang/String"), 2);
+}
+
+#[test]
+fn void_and_unknown_letters_are_not_field_types() {
+    assert_eq!(field_error("V"), 0);
+    assert_eq!(field_error("[V"), 1);
+    assert_eq!(method_error("(V)V"), 1);
+    assert_eq!(method_error("(IX)V"), 2);
+    assert_eq!(method_error("()[V"), 3);
+}
+
+#[test]
+fn errors_name_the_descriptor_and_offset() {
+    assert_eq!(
+        MethodDescriptor::parse("(I").unwrap_err(),
+        DescriptorError {
+            descriptor: "(I".to_string(),
+            offset: 2,
+        }
+    );
+    assert_eq!(
+        MethodDescriptor::parse("(I").unwrap_err().to_string(),
+        "invalid descriptor \"(I\" at offset 2"
+    );
+}
+
+#[test]
+fn members_parse_their_own_descriptors() {
+    let mut raw = RawClass::new("p/Members", "java/lang/Object");
+    raw.field(0, "grid", "[[D", &[]);
+    raw.field(0, "broken", "[", &[]);
+    raw.method(0, "sum", "(I[JLjava/lang/String;)V", &[]);
+    raw.method(0, "broken", "(I", &[]);
+    let class_file = ClassFile::from_bytes(&raw.bytes()).unwrap();
+    let constant_pool = &class_file.constant_pool;
+
+    assert_eq!(
+        class_file.fields[0].parsed_descriptor(constant_pool),
+        Some(Ok(array(2, FieldType::Base(BaseType::Double))))
+    );
+    assert_eq!(
+        class_file.fields[1]
+            .parsed_descriptor(constant_pool)
+            .unwrap()
+            .unwrap_err()
+            .offset,
+        1
+    );
+    assert_eq!(
+        class_file.methods[0].parsed_descriptor(constant_pool),
+        Some(Ok(MethodDescriptor {
+            parameters: vec![
+                FieldType::Base(BaseType::Int),
+                array(1, FieldType::Base(BaseType::Long)),
+                object("java/lang/String"),
+            ],
+            return_type: None,
+        }))
+    );
+    assert_eq!(
+        class_file.methods[1]
+            .parsed_descriptor(constant_pool)
+            .unwrap()
+            .unwrap_err()
+            .offset,
+        2
+    );
+}
@@ -0,0 +1,27 @@
+package arrays;
+
+import java.util.List;
+
+/** Class constants naming array types, as literals, casts and creations. */
+public class Arrays {
+    static Class<?> literal() {
+        return String[].class;
+    }
+
+    static boolean matrix(Object value) {
+        return value instanceof int[][];
+    }
+
+    static Object[] cast(Object value) {
+        return (Object[]) value;
+    }
+
+    @SuppressWarnings("unchecked")
+    static List<String>[] lists() {
+        return new List[3];
+    }
+
+    static String[][] grid() {
+        return new String[2][3];
+    }
+}
@@ -0,0 +1,36 @@
+package clones;
+
+// the same total as Orders, with the constant pool laid out differently,
+// and a near copy of it which discounts each price
+public class Invoices {
+    static final String LABEL = "invoice";
+    private int count;
+
+    public int getCount() {
+        return count;
+    }
+
+    static String label() {
+        return LABEL + ":" + System.nanoTime();
+    }
+
+    static int discounted(int[] prices) {
+        int sum = 0;
+        for (int price : prices) {
+            if (price > 0) {
+                sum += Math.min(price, 1000) * 9 / 10;
+            }
+        }
+        return sum;
+    }
+
+    static int total(int[] prices) {
+        int sum = 0;
+        for (int price : prices) {
+            if (price > 0) {
+                sum += Math.min(price, 1000);
+            }
+        }
+        return sum;
+    }
+}
@@ -0,0 +1,23 @@
+package clones;
+
+public class Orders {
+    private int count;
+
+    public int getCount() {
+        return count;
+    }
+
+    static int total(int[] prices) {
+        int sum = 0;
+        for (int price : prices) {
+            if (price > 0) {
+                sum += Math.min(price, 1000);
+            }
+        }
+        return sum;
+    }
+
+    static String describe(String name) {
+        return name.isEmpty() ? "none" : name.trim().toLowerCase();
+    }
+}
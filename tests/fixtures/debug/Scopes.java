@@ -0,0 +1,27 @@
+package debug;
+
+import java.util.List;
+
+// sibling blocks whose locals share a slot, and a loop whose update
+// comes after its body in the code but on a line before it
+public class Scopes {
+    static int measure(List<String> names) {
+        int total = 0;
+        {
+            int value = names.size();
+            total += value;
+        }
+        {
+            String value = names.get(0);
+            total += value.length();
+        }
+        {
+            List<String> value = List.copyOf(names);
+            total += value.size();
+        }
+        for (int i = 0; i < total; i++) {
+            total--;
+        }
+        return total;
+    }
+}
@@ -0,0 +1,8 @@
+package app;
+
+class Helper {
+    void help() {
+        Runnable task = () -> {};
+        task.run();
+    }
+}
@@ -0,0 +1,13 @@
+package app;
+
+import java.util.List;
+import lib.Greeter;
+import missing.Gone;
+
+public class Main {
+    public static void main(String[] args) {
+        System.out.println(new Greeter().greet(List.of(args)));
+        Gone.run();
+        new Helper().help();
+    }
+}
@@ -0,0 +1,9 @@
+package lib;
+
+import java.util.List;
+
+public class Greeter {
+    public String greet(List<String> names) {
+        return "hello " + String.join(", ", names);
+    }
+}
@@ -0,0 +1,6 @@
+package missing;
+
+// compiled against, but left out of both jars
+public class Gone {
+    public static void run() {}
+}
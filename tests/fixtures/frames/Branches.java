@@ -0,0 +1,155 @@
+package frames;
+
+import java.util.List;
+
+/**
+ * Methods whose StackMapTables use every frame form javac writes. They're
+ * all static, so a method's first frame follows from its descriptor alone.
+ */
+public final class Branches {
+    private Branches() {}
+
+    static int loops(int[] values) {
+        int sum = 0;
+        for (int i = 0; i < values.length; i++) {
+            if (values[i] < 0) {
+                continue;
+            }
+            sum += values[i];
+        }
+        for (int value : values) {
+            while (value > 10) {
+                value /= 2;
+            }
+            sum ^= value;
+        }
+        return sum;
+    }
+
+    static String tableSwitch(int day) {
+        switch (day) {
+            case 1: return "mon";
+            case 2: return "tue";
+            case 3: return "wed";
+            case 5: return "fri";
+            default: return "other";
+        }
+    }
+
+    static int lookupSwitch(String name) {
+        switch (name) {
+            case "alpha": return 1;
+            case "omega": return 1000;
+            default: return -1;
+        }
+    }
+
+    static double wide(long count, double scale, boolean round) {
+        double total = 0;
+        long remaining = count;
+        while (remaining-- > 0) {
+            total += round ? Math.round(scale) : scale;
+        }
+        return total > count ? total : -total;
+    }
+
+    static int exceptions(List<String> items) {
+        int parsed = 0;
+        for (String item : items) {
+            try {
+                parsed += Integer.parseInt(item);
+            } catch (NumberFormatException e) {
+                parsed--;
+            } finally {
+                parsed *= 2;
+            }
+        }
+        return parsed;
+    }
+
+    static Object chop(int n) {
+        if (n > 0) {
+            int a = n * 2;
+            long b = a;
+            if (b > n) {
+                String c = "big";
+                return c;
+            }
+        }
+        Object result = n == 0 ? null : Integer.valueOf(n);
+        outer:
+        for (int i = 0; i < n; i++) {
+            for (int j = 0; j < i; j++) {
+                if (i * j > n) {
+                    break outer;
+                }
+            }
+        }
+        return result instanceof Integer i && i > 3 ? i : result;
+    }
+
+    static int manyLocals(int a, int b, int c, int d) {
+        int e = a + b;
+        int f = c + d;
+        int g = e * f;
+        int h = g - a;
+        if (h > 0) {
+            int i = h + 1;
+            int j = i + 1;
+            int k = j + 1;
+            int l = k + 1;
+            return l > 0 ? l : k;
+        }
+        return g;
+    }
+
+    static int stackItem(int a, int b) {
+        return a + (b > 0 ? b : -b);
+    }
+
+    static long extended(long seed, boolean flag) {
+        int x = (int) seed;
+        if (seed < 0) {
+            x = -x;
+        }
+        if (flag) {
+            x = x * 31 + 7;
+            x = x * 31 + 11;
+            x = x * 31 + 13;
+            x = x * 31 + 17;
+            x = x * 31 + 19;
+            x = x * 31 + 23;
+            x = x * 31 + 29;
+            x = x * 31 + 31;
+            x = x * 31 + 37;
+            x = x * 31 + 41;
+        }
+        return seed + (flag
+                ? x ^ (x >>> 1) ^ (x >>> 2) ^ (x >>> 3) ^ (x >>> 4) ^ (x >>> 5)
+                        ^ (x >>> 6) ^ (x >>> 7) ^ (x >>> 8) ^ (x >>> 9) ^ (x >>> 10)
+                        ^ (x >>> 11) ^ (x >>> 12) ^ (x >>> 13) ^ (x >>> 14)
+                : x);
+    }
+
+    static int chops(int n) {
+        int total = 0;
+        if (n > 1) {
+            int a = n;
+            int b = a * 2;
+            if (b > 3) {
+                total++;
+            }
+            total += a + b;
+        }
+        if (n > 2) {
+            int a = n;
+            int b = a * 2;
+            int c = b * 2;
+            if (c > 3) {
+                total++;
+            }
+            total += a + b + c;
+        }
+        return total;
+    }
+}
@@ -0,0 +1,20 @@
+package generics;
+
+import java.io.IOException;
+import java.util.AbstractMap;
+import java.util.List;
+import java.util.Map;
+
+// signatures with every kind of bound, wildcard and inner class type
+public abstract class Registry<K extends Comparable<K>, T extends Number & Comparable<? super T>, E extends Runnable>
+        extends AbstractMap<K, List<T>> implements Iterable<Map.Entry<K, T>> {
+    Map<String, List<? extends Number>> byName;
+    Registry<K, T, E>.Slot<String[]> slot;
+    List<?>[][] any;
+
+    class Slot<V> {}
+
+    <X extends Exception, R> R lookup(Map<? super K, ?> keys, List<int[]>[] lists, E task) throws X, IOException {
+        return null;
+    }
+}
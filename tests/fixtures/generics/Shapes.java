@@ -0,0 +1,41 @@
+package generics;
+
+import java.util.List;
+import java.util.Map;
+
+// constructors whose descriptors have parameters their Signature leaves out
+public class Shapes<S extends Number> {
+    enum Kind {
+        ROUND(List.of("circle")),
+        SQUARE(List.of());
+
+        private final List<String> names;
+
+        Kind(List<String> names) {
+            this.names = names;
+        }
+    }
+
+    class Inner<T> {
+        Inner(Map<S, T> sizes, int scale) {}
+    }
+
+    static <K extends Comparable<? super K>, V> K largest(Map<K, ? extends List<V>> groups) {
+        return null;
+    }
+
+    Runnable local(String label, int count) {
+        class Counter implements Runnable {
+            final List<S> seen;
+
+            Counter(List<S> seen) {
+                this.seen = seen;
+            }
+
+            public void run() {
+                System.out.println(label + count + seen);
+            }
+        }
+        return new Counter(List.of());
+    }
+}
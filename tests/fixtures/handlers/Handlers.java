@@ -0,0 +1,56 @@
+package handlers;
+
+import java.io.IOException;
+import java.io.InputStream;
+
+/** Catch-all handlers which swallow what they catch, and some which don't. */
+public class Handlers {
+    static void swallow(Runnable task) {
+        try {
+            task.run();
+        } catch (Throwable t) {
+        }
+    }
+
+    static void log(Runnable task) {
+        try {
+            task.run();
+        } catch (Throwable t) {
+            System.err.println(t);
+        }
+    }
+
+    static void rethrow(Runnable task) {
+        try {
+            task.run();
+        } catch (Throwable t) {
+            throw new IllegalStateException(t);
+        }
+    }
+
+    static void sneaky(Runnable task) {
+        try {
+            task.run();
+        } catch (Throwable t) {
+            throwUnchecked(t);
+        }
+    }
+
+    static void specific(Runnable task) {
+        try {
+            task.run();
+        } catch (RuntimeException e) {
+        }
+    }
+
+    static int resources(InputStream in) throws IOException {
+        try (in) {
+            return in.read();
+        }
+    }
+
+    @SuppressWarnings("unchecked")
+    static <T extends Throwable> void throwUnchecked(Throwable t) throws T {
+        throw (T) t;
+    }
+}
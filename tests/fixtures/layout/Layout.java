@@ -0,0 +1,12 @@
+package layout;
+
+public class Layout {
+    static int step(int i, int k) {
+        switch (k) {
+            case 0: i += 1000; break;
+            case 1: i = i * 3; break;
+            case 2: i = Math.abs(i); break;
+        }
+        return i;
+    }
+}
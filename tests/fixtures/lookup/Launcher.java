@@ -0,0 +1,7 @@
+package lookup;
+
+public class Launcher {
+    public static void main(String[] args) {}
+
+    static void main(String arg) {}
+}
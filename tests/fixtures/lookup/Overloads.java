@@ -0,0 +1,19 @@
+package lookup;
+
+// overloads told apart by their descriptors, and a main java can't launch
+public class Overloads {
+    int count;
+    String name;
+
+    void add(int value) {}
+
+    void add(long value) {}
+
+    void add(String value) {}
+
+    int add(int a, int b) {
+        return a + b;
+    }
+
+    public void main(String[] args) {}
+}
@@ -0,0 +1,11 @@
+package overrides.base;
+
+public class Handler {
+    public Object handle(String event) {
+        return event;
+    }
+
+    void internal() {}
+
+    public final void sealed() {}
+}
@@ -0,0 +1,12 @@
+package overrides.base;
+
+// in Handler's package, so its internal() overrides
+public class Local extends Handler {
+    @Override
+    public Object handle(String event) {
+        return null;
+    }
+
+    @Override
+    void internal() {}
+}
@@ -0,0 +1,18 @@
+package overrides.other;
+
+import overrides.base.Handler;
+
+// handle narrows its return type, so javac adds a bridge returning Object;
+// internal() can't see Handler's, so it's a method of its own
+public class Remote extends Handler {
+    @Override
+    public String handle(String event) {
+        return event.trim();
+    }
+
+    void internal() {}
+
+    public static Object handle(Object event) {
+        return event;
+    }
+}
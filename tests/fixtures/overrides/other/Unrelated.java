@@ -0,0 +1,8 @@
+package overrides.other;
+
+// the same method, in a class which isn't a Handler
+public class Unrelated {
+    public Object handle(String event) {
+        return event;
+    }
+}
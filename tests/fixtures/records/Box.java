@@ -0,0 +1,19 @@
+package records;
+
+import java.lang.annotation.ElementType;
+import java.lang.annotation.Retention;
+import java.lang.annotation.RetentionPolicy;
+import java.lang.annotation.Target;
+import java.util.List;
+
+/** A generic record whose components carry annotations. */
+public record Box<T>(@Box.NonNull List<T> items, @Box.Audit("x") int count) {
+    @Retention(RetentionPolicy.RUNTIME)
+    @Target({ElementType.RECORD_COMPONENT, ElementType.FIELD, ElementType.METHOD, ElementType.PARAMETER})
+    public @interface NonNull {}
+
+    @Target({ElementType.RECORD_COMPONENT, ElementType.FIELD, ElementType.METHOD, ElementType.PARAMETER})
+    public @interface Audit {
+        String value();
+    }
+}
@@ -0,0 +1,3 @@
+package records;
+
+public record Point(int x, String name) {}
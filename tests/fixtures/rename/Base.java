@@ -0,0 +1,11 @@
+package rename;
+
+public class Base {
+    public void a(int value) {
+        System.out.println(value);
+    }
+
+    public void b(int value) {
+        a(value + 1);
+    }
+}
@@ -0,0 +1,10 @@
+package rename;
+
+public class Caller {
+    static void call(Base base, Sub sub) {
+        base.a(1);
+        sub.a(2);
+        Runnable task = () -> sub.a(3);
+        task.run();
+    }
+}
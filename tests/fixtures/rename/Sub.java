@@ -0,0 +1,8 @@
+package rename;
+
+public class Sub extends Base {
+    @Override
+    public void a(int value) {
+        super.a(value * 2);
+    }
+}
@@ -0,0 +1,33 @@
+package roundtrip;
+
+// two-slot constants, strings which modified UTF-8 encodes unlike UTF-8,
+// and branches which need stack map frames
+public class Constants {
+    static final long BIG = 0x1234_5678_9abc_def0L;
+    static final double RATIO = 2.718281828459045;
+    static final String NUL = "a\0b";
+    static final String GREETING = "grüße, ☃ and 😀";
+
+    static double scale(long count, double factor, int[] weights) {
+        double total = 0;
+        for (int i = 0; i < weights.length; i++) {
+            if (weights[i] > 0) {
+                total += weights[i] * factor;
+            } else {
+                total -= BIG;
+            }
+        }
+        return count > 0 ? total / count * RATIO : -1.5;
+    }
+
+    static String label(Object value) {
+        switch (String.valueOf(value)) {
+            case "grüße":
+                return GREETING;
+            case "":
+                return NUL;
+            default:
+                return value instanceof Long ? "long " + value : "?";
+        }
+    }
+}
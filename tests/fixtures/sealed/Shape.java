@@ -0,0 +1,8 @@
+package sealed;
+
+// a sealed interface hosting the nest of the two records it permits
+public sealed interface Shape permits Shape.Circle, Shape.Square {
+    record Circle(double radius) implements Shape {}
+
+    record Square(double side) implements Shape {}
+}
@@ -0,0 +1,9 @@
+package sourcepaths;
+
+public class Leaky {
+    static final String BUILT_FROM = "Leaky.java";
+
+    int answer() {
+        return 42;
+    }
+}
@@ -0,0 +1,12 @@
+package stack;
+
+public class Greeting {
+    static long greet(String name, long count) {
+        if (name == null) {
+            System.out.println("nobody");
+        } else {
+            System.out.println(name.trim());
+        }
+        return count * 2;
+    }
+}
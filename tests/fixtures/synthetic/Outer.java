@@ -0,0 +1,19 @@
+package synthetic;
+
+/**
+ * javac marks what it generates here with ACC_SYNTHETIC: the `this$0`
+ * field of Inner and the method holding the lambda's body.
+ */
+public class Outer {
+    private int value;
+
+    class Inner {
+        int get() {
+            return value;
+        }
+    }
+
+    Runnable task() {
+        return () -> value++;
+    }
+}
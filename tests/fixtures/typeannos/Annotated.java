@@ -0,0 +1,36 @@
+package typeannos;
+
+import java.lang.annotation.ElementType;
+import java.lang.annotation.Retention;
+import java.lang.annotation.RetentionPolicy;
+import java.lang.annotation.Target;
+import java.util.List;
+
+/** Type annotations in the positions checker-framework style code uses. */
+public class Annotated<T extends @Annotated.Nullable Object> {
+    @Target(ElementType.TYPE_USE)
+    @Retention(RetentionPolicy.RUNTIME)
+    public @interface Nullable {}
+
+    @Nullable String name;
+
+    @Override
+    public @Nullable String toString() {
+        return name;
+    }
+
+    void put(@Nullable Object @Nullable [] values) {}
+
+    <U extends Comparable<U>> U first(List<@Nullable U> items) {
+        return items.get(0);
+    }
+
+    Object cast(Object value) {
+        return (@Nullable String) value;
+    }
+
+    boolean check(Object value) {
+        @Nullable Object local = value;
+        return local instanceof @Nullable String;
+    }
+}
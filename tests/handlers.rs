@@ -0,0 +1,51 @@
+#![cfg(feature = "analysis")]
+
+mod common;
+
+use common::fixture;
+use jvmb::classfile::ClassFile;
+
+/// The handlers [`jvmb::attribute::Code::suspicious_handlers`] flags in
+/// each method of the fixture, by method name.
+fn flagged() -> Vec<(String, Vec<String>)> {
+    let class_file = ClassFile::from_bytes(&fixture("handlers/Handlers")).unwrap();
+    let pool = &class_file.constant_pool;
+    class_file
+        .methods
+        .iter()
+        .filter_map(|method| {
+            let code = method.code()?;
+            let reasons = code
+                .suspicious_handlers(pool)
+                .into_iter()
+                .map(|handler| handler.reason)
+                .collect();
+            Some((method.name(pool)?.to_string(), reasons))
+        })
+        .collect()
+}
+
+fn reasons<'a>(flagged: &'a [(String, Vec<String>)], method: &str) -> &'a [String] {
+    &flagged.iter().find(|(name, _)| name == method).unwrap().1
+}
+
+#[test]
+fn swallowing_catch_alls_are_flagged() {
+    let flagged = flagged();
+    assert_eq!(
+        reasons(&flagged, "swallow"),
+        ["catches java/lang/Throwable and discards it"]
+    );
+    assert_eq!(
+        reasons(&flagged, "log"),
+        ["catches java/lang/Throwable and neither rethrows nor calls a throwing method"]
+    );
+}
+
+#[test]
+fn rethrowing_and_narrow_handlers_are_not() {
+    let flagged = flagged();
+    for method in ["rethrow", "sneaky", "specific", "resources"] {
+        assert!(reasons(&flagged, method).is_empty(), "{}", method);
+    }
+}
@@ -0,0 +1,271 @@
+//! Crafted class files which claim more than they hold, or nest without
+//! end, fail to parse quickly, without panicking and without allocating
+//! for what they claim.
+
+mod common;
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use common::RawClass;
+use jvmb::{
+    classfile::{ClassFile, ParseOptions},
+    error::ParseError,
+};
+
+/// The system allocator, recording the largest allocation made.
+struct Tracking;
+
+static LARGEST: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Tracking {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        LARGEST.fetch_max(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        LARGEST.fetch_max(new_size, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Tracking = Tracking;
+
+/// Far more than any input here takes, and far less than any claims.
+const ALLOCATION_LIMIT: usize = 1 << 20;
+
+/// The error parsing `bytes` fails with, by default and when lenient,
+/// which are the same.
+fn rejected(bytes: &[u8]) -> ParseError {
+    let e = ClassFile::from_bytes(bytes).unwrap_err();
+    let lenient = ClassFile::parse_with(bytes, &ParseOptions::lenient());
+    assert!(lenient.is_err(), "lenient parse accepted {:?}", bytes);
+    let largest = LARGEST.load(Ordering::Relaxed);
+    assert!(largest < ALLOCATION_LIMIT, "allocated {} bytes", largest);
+    e
+}
+
+/// A class with a constant, a field with a `ConstantValue`, and a method
+/// with code, a `LineNumberTable` and an annotation.
+fn sound() -> Vec<u8> {
+    let mut class = RawClass::new("Sound", "java/lang/Object");
+    let value = class.entry(3, &7i32.to_be_bytes());
+    let constant_value = class.attribute("ConstantValue", &value.to_be_bytes());
+    class.field(0x0018, "SEVEN", "I", &[constant_value]);
+    let line_numbers = class.attribute("LineNumberTable", &[0, 1, 0, 0, 0, 3]);
+    // return
+    let code = class.code(0, 1, &[0xb1], &[line_numbers]);
+    let mut annotation = annotation(&mut class);
+    annotation.extend_from_slice(&[0, 0]);
+    let annotations = class.attribute(
+        "RuntimeVisibleAnnotations",
+        &[&[0, 1], &annotation[..]].concat(),
+    );
+    class.method(0x0009, "run", "()V", &[code, annotations]);
+    class.bytes()
+}
+
+/// The start of an annotation of type `LMarker;`, before its count of
+/// element value pairs.
+fn annotation(class: &mut RawClass) -> Vec<u8> {
+    class.utf8("LMarker;").to_be_bytes().to_vec()
+}
+
+#[test]
+fn every_truncation_is_rejected() {
+    let bytes = sound();
+    assert!(ClassFile::from_bytes(&bytes).is_ok());
+    for len in 0..bytes.len() {
+        match rejected(&bytes[..len]) {
+            ParseError::Truncated { .. } => {}
+            e => panic!("{} bytes: {}", len, e),
+        }
+    }
+}
+
+/// A class whose method `run` has the attribute `attribute` makes.
+fn method_with(attribute: impl FnOnce(&mut RawClass) -> Vec<u8>) -> Vec<u8> {
+    let mut class = RawClass::new("Hostile", "java/lang/Object");
+    let attribute = attribute(&mut class);
+    class.method(0x0009, "run", "()V", &[attribute]);
+    class.bytes()
+}
+
+#[test]
+fn counts_larger_than_the_input_are_rejected() {
+    // the constant pool count, of a pool ending with the input
+    let bytes = RawClass::new("Hostile", "java/lang/Object").bytes();
+    let mut pool = bytes[..bytes.len() - 14].to_vec();
+    pool[8..10].copy_from_slice(&[0xff, 0xff]);
+    assert!(matches!(rejected(&pool), ParseError::Truncated { .. }));
+
+    // the fields and methods counts, at the end of a class with neither
+    for at in [bytes.len() - 6, bytes.len() - 4] {
+        let mut bytes = bytes.clone();
+        bytes[at..at + 2].copy_from_slice(&[0xff, 0xff]);
+        assert!(matches!(rejected(&bytes), ParseError::Truncated { .. }));
+    }
+
+    // a 4 GiB Code attribute
+    let code = [0, 1, 0, 1, 0, 0, 0, 1, 0xb1, 0, 0, 0, 0];
+    let bytes = method_with(|class| class.attribute_with_length("Code", u32::MAX, &code));
+    assert!(matches!(rejected(&bytes), ParseError::Truncated { .. }));
+
+    // 4 GiB of code, 65535 annotations and 65535 line numbers, in
+    // attributes of the length they take
+    let bytes = method_with(|class| {
+        class.attribute(
+            "Code",
+            &[0, 1, 0, 1, 0xff, 0xff, 0xff, 0xff, 0xb1, 0, 0, 0, 0],
+        )
+    });
+    assert!(matches!(
+        rejected(&bytes),
+        ParseError::AttributeTruncated { .. }
+    ));
+    let bytes = method_with(|class| class.attribute("RuntimeVisibleAnnotations", &[0xff, 0xff]));
+    assert!(matches!(
+        rejected(&bytes),
+        ParseError::AttributeTruncated { .. }
+    ));
+    let bytes = method_with(|class| {
+        let line_numbers = class.attribute("LineNumberTable", &[0xff, 0xff, 0, 0, 0, 1]);
+        class.code(0, 1, &[0xb1], &[line_numbers])
+    });
+    assert!(matches!(
+        rejected(&bytes),
+        ParseError::AttributeTruncated { .. }
+    ));
+}
+
+/// A `RuntimeVisibleAnnotations` attribute of one annotation, whose element
+/// `value` is `element_value`.
+fn annotated(class: &mut RawClass, element_value: &[u8]) -> Vec<u8> {
+    let mut body = vec![0, 1];
+    body.extend_from_slice(&annotation(class));
+    body.extend_from_slice(&[0, 1]);
+    body.extend_from_slice(&class.utf8("value").to_be_bytes());
+    body.extend_from_slice(element_value);
+    class.attribute("RuntimeVisibleAnnotations", &body)
+}
+
+#[test]
+fn tags_the_format_doesnt_define_are_rejected() {
+    let mut class = RawClass::new("Hostile", "java/lang/Object");
+    class.entry(0xff, &[0; 8]);
+    assert_eq!(
+        rejected(&class.bytes()),
+        ParseError::UnknownConstantTag(0xff)
+    );
+
+    let bytes = method_with(|class| annotated(class, &[b'x', 0, 1]));
+    assert_eq!(
+        rejected(&bytes),
+        ParseError::UnknownTag {
+            structure: "element value",
+            tag: b'x',
+        }
+    );
+
+    // a full frame whose one local is of type 9
+    let bytes = method_with(|class| {
+        let frames = class.attribute("StackMapTable", &[0, 1, 255, 0, 0, 0, 1, 9, 0, 0]);
+        class.code(0, 1, &[0xb1], &[frames])
+    });
+    assert_eq!(
+        rejected(&bytes),
+        ParseError::UnknownTag {
+            structure: "verification type",
+            tag: 9,
+        }
+    );
+
+    // a type annotation targeting type 0x50
+    let bytes = method_with(|class| {
+        let mut body = vec![0, 1, 0x50, 0, 0];
+        body.extend_from_slice(&annotation(class));
+        body.extend_from_slice(&[0, 0]);
+        class.attribute("RuntimeVisibleTypeAnnotations", &body)
+    });
+    assert_eq!(
+        rejected(&bytes),
+        ParseError::UnknownTag {
+            structure: "type annotation target",
+            tag: 0x50,
+        }
+    );
+}
+
+#[test]
+fn nesting_without_end_is_rejected() {
+    // an array of an array of ... an int, 100000 deep
+    let mut element_value = b"[\0\x01".repeat(100_000);
+    let bytes = method_with(|class| {
+        let int = class.entry(3, &0i32.to_be_bytes());
+        element_value.push(b'I');
+        element_value.extend_from_slice(&int.to_be_bytes());
+        annotated(class, &element_value)
+    });
+    assert_eq!(
+        rejected(&bytes),
+        ParseError::NestingTooDeep {
+            structure: "element value",
+        }
+    );
+
+    // a Code attribute in a Code attribute in ... 1000 deep
+    let bytes = method_with(|class| {
+        let mut code = class.code(0, 1, &[0xb1], &[]);
+        for _ in 0..1000 {
+            code = class.code(0, 1, &[0xb1], &[code]);
+        }
+        code
+    });
+    assert_eq!(
+        rejected(&bytes),
+        ParseError::NestingTooDeep {
+            structure: "attribute",
+        }
+    );
+}
+
+/// Entries referring to themselves, or to each other in a loop, parse, as
+/// the constant pool's references are followed only when needed. Following
+/// them then fails rather than looping.
+#[test]
+fn constant_pool_cycles_fail_to_resolve() {
+    let mut class = RawClass::new("Hostile", "java/lang/Object");
+    // a NameAndType whose name and type are itself
+    let name_and_type = class.next_index();
+    class.entry(
+        12,
+        &[name_and_type.to_be_bytes(), name_and_type.to_be_bytes()].concat(),
+    );
+    // a Class named by a Methodref of that class, which this class is
+    let owner = class.next_index();
+    let method_ref = owner + 1;
+    class.entry(7, &method_ref.to_be_bytes());
+    class.entry(
+        10,
+        &[owner.to_be_bytes(), name_and_type.to_be_bytes()].concat(),
+    );
+    class.this_class = owner;
+    let [high, low] = method_ref.to_be_bytes();
+    // invokestatic, return
+    let code = class.code(0, 0, &[0xb8, high, low, 0xb1], &[]);
+    class.method(0x0009, "run", "()V", &[code]);
+
+    let class_file = ClassFile::from_bytes(&class.bytes()).unwrap();
+    assert!(class_file.class_name().is_err());
+    assert!(!class_file.validate().is_empty());
+    #[cfg(feature = "disasm")]
+    assert!(jvmb::javap::javap(&class_file).contains("invokestatic"));
+}
@@ -0,0 +1,39 @@
+#![cfg(feature = "disasm")]
+
+use jvmb::instruction::{self, Instruction};
+
+fn only(code: &[u8]) -> Instruction<'_> {
+    let mut instructions = instruction::decode(code);
+    let instruction = instructions.next().unwrap().unwrap();
+    assert!(instructions.next().is_none());
+    instruction
+}
+
+fn word(value: i32) -> [u8; 4] {
+    value.to_be_bytes()
+}
+
+#[test]
+fn a_branch_before_the_code_keeps_its_target() {
+    // goto -1
+    assert_eq!(only(&[0xa7, 0xff, 0xff]).branch_targets(), [-1]);
+    // goto_w -5
+    assert_eq!(only(&[0xc8, 0xff, 0xff, 0xff, 0xfb]).branch_targets(), [-5]);
+}
+
+#[test]
+fn switches_give_one_target_per_slot_default_first() {
+    // tableswitch 0 to 2 at offset 0, so three bytes of padding
+    let mut code = vec![0xaa, 0, 0, 0];
+    for value in [44, 0, 2, 40, -8, 44] {
+        code.extend_from_slice(&word(value));
+    }
+    assert_eq!(only(&code).branch_targets(), [44, 40, -8, 44]);
+
+    // lookupswitch of 5 and 9
+    let mut code = vec![0xab, 0, 0, 0];
+    for value in [28, 2, 5, -3, 9, 20] {
+        code.extend_from_slice(&word(value));
+    }
+    assert_eq!(only(&code).branch_targets(), [28, -3, 20]);
+}
@@ -0,0 +1,148 @@
+#![cfg(all(feature = "archive", feature = "write"))]
+
+use std::{
+    fs::File,
+    io::{self, Cursor, Read, Seek, SeekFrom},
+    ops::Range,
+    sync::{Arc, Mutex},
+};
+
+use jvmb::{
+    builder::ClassFileBuilder,
+    jar::{JarEntry, JarReader, JarWriter},
+};
+
+/// The ranges of the reads a [`CountingReader`] has passed on.
+type Reads = Arc<Mutex<Vec<Range<u64>>>>;
+
+/// A reader recording the range of every read it passes on.
+struct CountingReader<R> {
+    inner: R,
+    position: u64,
+    reads: Reads,
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        let end = self.position + read as u64;
+        self.reads.lock().unwrap().push(self.position..end);
+        self.position = end;
+        Ok(read)
+    }
+}
+
+impl<R: Seek> Seek for CountingReader<R> {
+    fn seek(&mut self, to: SeekFrom) -> io::Result<u64> {
+        self.position = self.inner.seek(to)?;
+        Ok(self.position)
+    }
+}
+
+/// A jar of three classes in `app/`, three in `lib/` and a resource, read
+/// through a [`CountingReader`] which has recorded nothing yet.
+fn counted_jar() -> (JarReader<CountingReader<Cursor<Vec<u8>>>>, Reads) {
+    let mut writer = JarWriter::new();
+    for name in ["app/A", "app/B", "lib/X", "app/C", "lib/Y", "lib/Z"] {
+        let mut builder = ClassFileBuilder::new(name);
+        builder.super_class("java/lang/Exception");
+        let bytes = builder.build().unwrap().to_bytes().unwrap();
+        writer.add(&format!("{}.class", name), &bytes).unwrap();
+    }
+    writer.add("lib/messages.properties", b"a=b\n").unwrap();
+    let reads = Arc::new(Mutex::new(Vec::new()));
+    let reader = JarReader::new(CountingReader {
+        inner: Cursor::new(writer.finish().unwrap()),
+        position: 0,
+        reads: reads.clone(),
+    })
+    .unwrap();
+    // opening it reads the last 64 KiB, where the central directory may
+    // start, which in a jar this small is all of it
+    reads.lock().unwrap().clear();
+    (reader, reads)
+}
+
+/// The bytes of `entry`, its local header on, that reading its contents
+/// reads.
+fn stored_range(entry: &JarEntry) -> Range<u64> {
+    let start = u64::from(entry.local_header_offset);
+    // the local header, then the name, with no extra field
+    start..start + 30 + entry.name.len() as u64 + u64::from(entry.compressed_size)
+}
+
+fn was_read(reads: &Mutex<Vec<Range<u64>>>, range: &Range<u64>) -> bool {
+    reads
+        .lock()
+        .unwrap()
+        .iter()
+        .any(|read| read.start < range.end && range.start < read.end)
+}
+
+#[test]
+fn skipped_entries_are_never_read() {
+    let (reader, reads) = counted_jar();
+    let mut picked = Vec::new();
+    let mut skipped = Vec::new();
+    for handle in reader.class_entries() {
+        if !handle.name().starts_with("app/") {
+            skipped.push(stored_range(handle.entry()));
+            continue;
+        }
+        let header = handle.parse_summary().unwrap();
+        picked.push((
+            header.name().unwrap().to_string(),
+            stored_range(handle.entry()),
+        ));
+    }
+    assert_eq!(skipped.len(), 3);
+    let names: Vec<&str> = picked.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(names, ["app/A", "app/B", "app/C"]);
+    for (name, range) in &picked {
+        assert!(was_read(&reads, range), "{} wasn't read", name);
+    }
+    for range in &skipped {
+        assert!(!was_read(&reads, range), "skipped {:?} was read", range);
+    }
+    let resource = reader
+        .entries()
+        .find(|handle| handle.name() == "lib/messages.properties")
+        .unwrap();
+    assert!(!was_read(&reads, &stored_range(resource.entry())));
+}
+
+#[test]
+fn handles_read_their_entry_whatever_was_read_before() {
+    let (reader, _) = counted_jar();
+    let handles: Vec<_> = reader.class_entries().collect();
+    // out of order, and each twice
+    for handle in handles.iter().rev().chain(&handles) {
+        let class_file = handle.parse(&Default::default()).unwrap();
+        let name = class_file.class_name().unwrap();
+        assert_eq!(format!("{}.class", name), handle.name());
+    }
+}
+
+#[test]
+fn jar_reader_can_be_shared_between_threads() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<JarReader<File>>();
+    assert_send_sync::<JarReader<CountingReader<Cursor<Vec<u8>>>>>();
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn skipped_entries_are_never_read_in_parallel() {
+    let (reader, reads) = counted_jar();
+    let (picked, skipped): (Vec<_>, Vec<_>) = reader
+        .class_entries()
+        .partition(|handle| handle.name().starts_with("lib/"));
+    let names = jvmb::parallel::map(&picked, 4, |handle| {
+        let header = handle.parse_summary().unwrap();
+        header.name().unwrap().to_string()
+    });
+    assert_eq!(names, ["lib/X", "lib/Y", "lib/Z"]);
+    for handle in &skipped {
+        assert!(!was_read(&reads, &stored_range(handle.entry())));
+    }
+}
@@ -0,0 +1,218 @@
+#![cfg(feature = "write")]
+
+use jvmb::{
+    accessflags::{ACC_FINAL, ACC_STATIC},
+    attribute::{Attribute, BootstrapMethod, Code},
+    builder::ClassFileBuilder,
+    classfile::ClassFile,
+    constantpool::ConstantPool,
+    javap::{javap, javap_code},
+    textify::textify_field,
+};
+
+/// A class whose string "a\uD800b", with an unpaired surrogate, is the
+/// constant value of a field and an argument of a bootstrap method.
+fn unpaired_surrogate_class() -> ClassFile {
+    let mut builder = ClassFileBuilder::new("Strings");
+    let constant_pool = builder.constant_pool();
+    let utf8 = constant_pool.utf8("placeholder");
+    let string = constant_pool.string("placeholder");
+    let bootstrap = constant_pool.method_ref(
+        "java/lang/invoke/StringConcatFactory",
+        "makeConcatWithConstants",
+        "(Ljava/lang/invoke/MethodHandles$Lookup;Ljava/lang/String;\
+         Ljava/lang/invoke/MethodType;Ljava/lang/String;[Ljava/lang/Object;)\
+         Ljava/lang/invoke/CallSite;",
+    );
+    constant_pool.utf8("ConstantValue");
+    constant_pool.utf8("BootstrapMethods");
+    builder.add_field(ACC_STATIC | ACC_FINAL, "LONE", "Ljava/lang/String;");
+    let mut class_file = builder.build().unwrap();
+
+    class_file.constant_pool[utf8 as usize - 1] = ConstantPool::Utf16(vec![0x61, 0xd800, 0x62]);
+    // REF_invokeStatic
+    class_file
+        .constant_pool
+        .push(ConstantPool::MethodHandle(6, bootstrap));
+    let handle = class_file.constant_pool.len() as u16;
+    class_file.fields[0]
+        .attributes_mut()
+        .push(Attribute::ConstantValue(string));
+    class_file
+        .attributes
+        .push(Attribute::BootstrapMethods(vec![BootstrapMethod {
+            bootstrap_method_ref: handle,
+            bootstrap_arguments: vec![string],
+        }]));
+    // as it would be read from a class file
+    ClassFile::from_bytes(&class_file.to_bytes().unwrap()).unwrap()
+}
+
+#[test]
+fn unpaired_surrogates_are_escaped() {
+    let class_file = unpaired_surrogate_class();
+    let listing = javap(&class_file);
+    let lines: Vec<&str> = listing.lines().map(str::trim).collect();
+    let has = |prefix: &str, suffix: &str| {
+        lines
+            .iter()
+            .any(|line| line.starts_with(prefix) && line.ends_with(suffix))
+    };
+    assert!(has("#", "= Utf8               a\\ud800b"), "{}", listing);
+    assert!(has("#", "// a\\ud800b"), "{}", listing);
+    assert!(
+        lines.contains(&"ConstantValue: String a\\ud800b"),
+        "{}",
+        listing
+    );
+    let arguments = lines
+        .iter()
+        .position(|line| *line == "Method arguments:")
+        .expect("a bootstrap method");
+    assert!(lines[arguments + 1].ends_with(" a\\ud800b"), "{}", listing);
+    assert!(!listing.contains("String ?"), "{}", listing);
+}
+
+/// A class with a `static final` field for each of `doubles` and `floats`,
+/// with that constant value.
+fn constants_class(doubles: &[f64], floats: &[f32]) -> ClassFile {
+    let mut builder = ClassFileBuilder::new("Constants");
+    let mut values = Vec::new();
+    for (i, &value) in doubles.iter().enumerate() {
+        values.push(builder.constant_pool().double(value));
+        builder.add_field(ACC_STATIC | ACC_FINAL, &format!("D{}", i), "D");
+    }
+    for (i, &value) in floats.iter().enumerate() {
+        values.push(builder.constant_pool().float(value));
+        builder.add_field(ACC_STATIC | ACC_FINAL, &format!("F{}", i), "F");
+    }
+    builder.constant_pool().utf8("ConstantValue");
+    let mut class_file = builder.build().unwrap();
+    for (field, value) in class_file.fields.iter_mut().zip(values) {
+        field.attributes_mut().push(Attribute::ConstantValue(value));
+    }
+    class_file
+}
+
+#[test]
+fn doubles_and_floats_print_as_java_prints_them() {
+    let doubles = [
+        (f64::from_bits(1), "4.9E-324"), // Double.MIN_VALUE
+        (f64::MIN_POSITIVE, "2.2250738585072014E-308"),
+        (-f64::from_bits(1), "-4.9E-324"),
+        (f64::MAX, "1.7976931348623157E308"),
+        (1.0, "1.0"),
+        (100.0, "100.0"),
+        (0.001, "0.001"),
+        (1e7, "1.0E7"),
+        (0.0, "0.0"),
+    ];
+    let floats = [
+        (f32::from_bits(1), "1.4E-45"), // Float.MIN_VALUE
+        (f32::MIN_POSITIVE, "1.1754944E-38"),
+        (0.1, "0.1"),
+        (1e10, "1.0E10"),
+    ];
+    let class_file = constants_class(
+        &doubles.map(|(value, _)| value),
+        &floats.map(|(value, _)| value),
+    );
+    let listing = javap(&class_file);
+    let lines: Vec<&str> = listing.lines().map(str::trim).collect();
+    for (i, (_, text)) in doubles.iter().enumerate() {
+        let field = &class_file.fields[i];
+        assert!(
+            lines.contains(&format!("ConstantValue: double {}d", text).as_str()),
+            "{}",
+            listing
+        );
+        assert_eq!(
+            textify_field(&class_file, field).lines().last(),
+            Some(format!("  final static D D{} = {}", i, text).as_str())
+        );
+    }
+    for (i, (_, text)) in floats.iter().enumerate() {
+        let field = &class_file.fields[doubles.len() + i];
+        assert!(
+            lines.contains(&format!("ConstantValue: float {}f", text).as_str()),
+            "{}",
+            listing
+        );
+        assert_eq!(
+            textify_field(&class_file, field).lines().last(),
+            Some(format!("  final static F F{} = {}", i, text).as_str())
+        );
+    }
+}
+
+/// A method whose tableswitch, at 1, has a case jumping to -8, before the
+/// code array, and whose lookupswitch, at 47, has keys of different widths.
+fn switches_class() -> ClassFile {
+    let i32s = |values: &[i32]| -> Vec<u8> {
+        values
+            .iter()
+            .flat_map(|value| value.to_be_bytes())
+            .collect()
+    };
+    // iload_0, tableswitch 0 to 2
+    let mut code = vec![0x1a, 0xaa, 0, 0];
+    code.extend(i32s(&[43, 0, 2, 39, -9, 43]));
+    code.resize(40, 0x00);
+    // 40: iconst_0, ireturn, nop, nop, 44: iconst_1, ireturn
+    code.extend([0x03, 0xac, 0x00, 0x00, 0x04, 0xac]);
+    // 46: iload_0, 47: lookupswitch of two pairs
+    code.extend([0x1a, 0xab]);
+    code.extend(i32s(&[25, 2, -1, -7, 100_000, 25]));
+    // 72: iconst_2, ireturn
+    code.extend([0x05, 0xac]);
+    let mut builder = ClassFileBuilder::new("Switches");
+    builder.add_method(ACC_STATIC, "pick", "(I)I", Some(Code::new(1, 1, code)));
+    ClassFile::from_bytes(&builder.build().unwrap().to_bytes().unwrap()).unwrap()
+}
+
+#[test]
+fn switch_targets_before_the_code_print_as_javap_prints_them() {
+    let class_file = switches_class();
+    let code = javap_code(&class_file, &class_file.methods[0]).unwrap();
+    let switches: Vec<&str> = code
+        .lines()
+        .skip_while(|line| !line.contains("tableswitch"))
+        .take_while(|line| !line.contains("40: iconst_0"))
+        .chain(
+            code.lines()
+                .skip_while(|line| !line.contains("lookupswitch"))
+                .take_while(|line| !line.contains("72: iconst_2")),
+        )
+        .collect();
+    // as `javap -v` of javac 17 prints the class
+    assert_eq!(
+        switches,
+        [
+            "         1: tableswitch   { // 0 to 2",
+            "                       0: 40",
+            "                       1: -8",
+            "                       2: 44",
+            "                 default: 44",
+            "            }",
+            "        28: nop",
+            "        29: nop",
+            "        30: nop",
+            "        31: nop",
+            "        32: nop",
+            "        33: nop",
+            "        34: nop",
+            "        35: nop",
+            "        36: nop",
+            "        37: nop",
+            "        38: nop",
+            "        39: nop",
+            "        47: lookupswitch  { // 2",
+            "                      -1: 40",
+            "                  100000: 72",
+            "                 default: 72",
+            "            }",
+        ],
+        "{}",
+        code
+    );
+}
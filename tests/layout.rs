@@ -0,0 +1,68 @@
+#![cfg(feature = "disasm")]
+
+mod common;
+
+use common::fixture;
+use jvmb::{
+    classfile::ClassFile,
+    layout::{InstructionCategory, LayoutSummary},
+};
+
+#[test]
+fn switch_padding_and_wide_prefixes_count_towards_their_instruction() {
+    let class_file = ClassFile::from_bytes(&fixture("layout/Layout")).unwrap();
+    let method = class_file
+        .methods
+        .iter()
+        .find(|method| method.name(&class_file.constant_pool) == Some("step"))
+        .unwrap();
+    let layout = method.code().unwrap().layout().unwrap();
+
+    let sizes: Vec<_> = layout
+        .instructions
+        .iter()
+        .map(|instruction| (instruction.offset, instruction.mnemonic, instruction.size))
+        .collect();
+    assert_eq!(
+        sizes,
+        [
+            (0, "iload_1", 1),
+            // two bytes of padding, default, low, high and three targets
+            (1, "tableswitch", 27),
+            // wide, iinc, a two byte index and a two byte increment
+            (28, "wide", 6),
+            (34, "goto", 3),
+            (37, "iload_0", 1),
+            (38, "iconst_3", 1),
+            (39, "imul", 1),
+            (40, "istore_0", 1),
+            (41, "goto", 3),
+            (44, "iload_0", 1),
+            (45, "invokestatic", 3),
+            (48, "istore_0", 1),
+            (49, "iload_0", 1),
+            (50, "ireturn", 1),
+        ]
+    );
+    assert_eq!(layout.instructions[1].category, InstructionCategory::Branch);
+    // a wide iinc is an iinc, not a load or store
+    assert_eq!(layout.instructions[2].category, InstructionCategory::Other);
+    assert_eq!(
+        layout.instructions[2].cumulative_percent,
+        34.0 * 100.0 / 51.0
+    );
+    assert_eq!(
+        layout.instructions.last().unwrap().cumulative_percent,
+        100.0
+    );
+    assert_eq!(
+        layout.summary,
+        LayoutSummary {
+            total: 51,
+            invocations: 3,
+            branches: 33,
+            loads_stores: 6,
+            other: 9,
+        }
+    );
+}
@@ -0,0 +1,129 @@
+mod common;
+
+use common::fixture;
+use jvmb::{
+    attribute::{Attribute, Code, LineNumber},
+    classfile::ClassFile,
+    constantpool::ConstantPool,
+};
+
+/// A javac class compiled with `-g`, whose method `measure` has a loop
+/// with its update on a line before its body.
+fn scopes() -> ClassFile {
+    ClassFile::from_bytes(&fixture("debug/Scopes")).unwrap()
+}
+
+fn measure(class_file: &mut ClassFile) -> &mut Code {
+    let constant_pool = &class_file.constant_pool;
+    let method = class_file
+        .methods
+        .iter_mut()
+        .find(|method| ConstantPool::utf8(constant_pool, method.name_index()) == Some("measure"))
+        .unwrap();
+    method
+        .attributes_mut()
+        .iter_mut()
+        .find_map(|attribute| match attribute {
+            Attribute::Code(code) => Some(code),
+            _ => None,
+        })
+        .unwrap()
+}
+
+fn line_number_tables(code: &mut Code) -> impl Iterator<Item = &mut Vec<LineNumber>> {
+    code.attributes
+        .iter_mut()
+        .filter_map(|attribute| match attribute {
+            Attribute::LineNumberTable(table) => Some(table),
+            _ => None,
+        })
+}
+
+/// The line of every pc of `code`, where it has one.
+fn lines(code: &Code) -> Vec<(u16, u16)> {
+    (0..code.code.len() as u16)
+        .filter_map(|pc| Some((pc, code.line_number_for_pc(pc)?)))
+        .collect()
+}
+
+// javap -l of javac 17:
+//     line 9: 0, line 11: 2, line 12: 9, line 15: 13, line 16: 24,
+//     line 19: 31, line 20: 36, line 22: 45, line 23: 52, line 22: 55,
+//     line 25: 61
+#[test]
+fn each_pc_is_on_the_line_of_the_last_entry_at_or_before_it() {
+    let mut class_file = scopes();
+    let code = measure(&mut class_file);
+    for (pc, line) in [
+        (0, 9),
+        (1, 9),
+        (2, 11),
+        (8, 11),
+        (9, 12),
+        (31, 19),
+        (45, 22),
+        (51, 22),
+        (52, 23),
+        (54, 23),
+        // the loop's update, back on the line of the for
+        (55, 22),
+        (58, 22),
+        (61, 25),
+        (62, 25),
+        // past the end of the code, still the last entry's
+        (1000, 25),
+    ] {
+        assert_eq!(code.line_number_for_pc(pc), Some(line), "pc {}", pc);
+    }
+}
+
+#[test]
+fn unsorted_and_split_tables_give_the_same_lines() {
+    let mut class_file = scopes();
+    let code = measure(&mut class_file);
+    let expected = lines(code);
+
+    line_number_tables(code).next().unwrap().reverse();
+    assert_eq!(lines(code), expected);
+
+    // the first half in one table and the second in another, after it
+    let table = line_number_tables(code).next().unwrap();
+    let second = table.split_off(table.len() / 2);
+    code.attributes.push(Attribute::LineNumberTable(second));
+    assert_eq!(line_number_tables(code).count(), 2);
+    assert_eq!(lines(code), expected);
+}
+
+#[test]
+fn a_pc_before_the_first_entry_has_no_line() {
+    let mut class_file = scopes();
+    let code = measure(&mut class_file);
+    let table = line_number_tables(code).next().unwrap();
+    table.retain(|line| line.start_pc >= 9);
+    assert_eq!(code.line_number_for_pc(0), None);
+    assert_eq!(code.line_number_for_pc(8), None);
+    assert_eq!(code.line_number_for_pc(9), Some(12));
+}
+
+#[test]
+fn without_debug_info_there_are_no_lines_or_source_file() {
+    let mut class_file = scopes();
+    assert_eq!(class_file.source_file(), Some("Scopes.java"));
+    class_file
+        .attributes
+        .retain(|attribute| !matches!(attribute, Attribute::SourceFile(_)));
+    assert_eq!(class_file.source_file(), None);
+
+    let code = measure(&mut class_file);
+    code.attributes
+        .retain(|attribute| !matches!(attribute, Attribute::LineNumberTable(_)));
+    assert_eq!(lines(code), []);
+}
+
+#[test]
+fn a_stack_frame_reads_as_file_and_line() {
+    let mut class_file = scopes();
+    let source_file = class_file.source_file().unwrap().to_string();
+    let line = measure(&mut class_file).line_number_for_pc(53).unwrap();
+    assert_eq!(format!("{}:{}", source_file, line), "Scopes.java:23");
+}
@@ -0,0 +1,127 @@
+mod common;
+
+use common::fixture;
+use jvmb::{
+    attribute::{Attribute, ResolvedLocalVariable},
+    classfile::ClassFile,
+    constantpool::ConstantPool,
+    methodinfo::MethodInfo,
+};
+
+/// A javac class compiled with `-g`, whose method `measure` has three
+/// sibling blocks each declaring a `value`, of `int`, `String` and
+/// `List<String>`, all in slot 2, and then a loop counter `i` there too.
+fn scopes() -> ClassFile {
+    ClassFile::from_bytes(&fixture("debug/Scopes")).unwrap()
+}
+
+fn measure(class_file: &ClassFile) -> &MethodInfo {
+    class_file
+        .methods
+        .iter()
+        .find(|method| {
+            ConstantPool::utf8(&class_file.constant_pool, method.name_index()) == Some("measure")
+        })
+        .unwrap()
+}
+
+/// The name and type of slot `index` at `pc`, and the generic type if any.
+fn local(class_file: &ClassFile, index: u16, pc: u16) -> Option<(&str, &str, Option<&str>)> {
+    measure(class_file)
+        .code()
+        .unwrap()
+        .local_variable_at(index, pc, &class_file.constant_pool)
+        .map(|local| (local.name, local.descriptor, local.signature))
+}
+
+// javap -l of javac 17:
+//     Start  Length  Slot  Name   Signature
+//         9       4     2 value   I
+//        24       7     2 value   Ljava/lang/String;
+//        36       9     2 value   Ljava/util/List;
+//        47      14     2     i   I
+//         0      63     0 names   Ljava/util/List;
+//         2      61     1 total   I
+// and of its LocalVariableTypeTable:
+//        36       9     2 value   Ljava/util/List<Ljava/lang/String;>;
+//         0      63     0 names   Ljava/util/List<Ljava/lang/String;>;
+#[test]
+fn a_reused_slot_holds_the_variable_of_each_scope_in_turn() {
+    let class_file = scopes();
+    let list = Some("Ljava/util/List<Ljava/lang/String;>;");
+    for (pc, expected) in [
+        // before the first block stores to it
+        (8, None),
+        (9, Some(("value", "I", None))),
+        (12, Some(("value", "I", None))),
+        (13, None),
+        (24, Some(("value", "Ljava/lang/String;", None))),
+        (30, Some(("value", "Ljava/lang/String;", None))),
+        (31, None),
+        (36, Some(("value", "Ljava/util/List;", list))),
+        (44, Some(("value", "Ljava/util/List;", list))),
+        (45, None),
+        (47, Some(("i", "I", None))),
+        (60, Some(("i", "I", None))),
+        // the end of a range is past it
+        (61, None),
+    ] {
+        assert_eq!(local(&class_file, 2, pc), expected, "pc {}", pc);
+    }
+}
+
+#[test]
+fn parameters_and_outer_variables_span_the_rest_of_the_method() {
+    let class_file = scopes();
+    for pc in [0, 30, 62] {
+        assert_eq!(
+            local(&class_file, 0, pc),
+            Some((
+                "names",
+                "Ljava/util/List;",
+                Some("Ljava/util/List<Ljava/lang/String;>;")
+            ))
+        );
+    }
+    assert_eq!(local(&class_file, 1, 1), None);
+    assert_eq!(local(&class_file, 1, 2), Some(("total", "I", None)));
+    assert_eq!(local(&class_file, 3, 50), None);
+}
+
+#[test]
+fn the_whole_entry_is_resolved() {
+    let class_file = scopes();
+    let code = measure(&class_file).code().unwrap();
+    assert_eq!(
+        code.local_variable_at(2, 40, &class_file.constant_pool),
+        Some(ResolvedLocalVariable {
+            index: 2,
+            start_pc: 36,
+            length: 9,
+            name: "value",
+            descriptor: "Ljava/util/List;",
+            signature: Some("Ljava/util/List<Ljava/lang/String;>;"),
+        })
+    );
+}
+
+#[test]
+fn without_a_type_table_there_is_no_generic_type() {
+    let mut class_file = scopes();
+    let constant_pool = &class_file.constant_pool;
+    let method = class_file
+        .methods
+        .iter_mut()
+        .find(|method| ConstantPool::utf8(constant_pool, method.name_index()) == Some("measure"))
+        .unwrap();
+    for attribute in method.attributes_mut() {
+        if let Attribute::Code(code) = attribute {
+            code.attributes
+                .retain(|attribute| !matches!(attribute, Attribute::LocalVariableTypeTable(_)));
+        }
+    }
+    assert_eq!(
+        local(&class_file, 2, 40),
+        Some(("value", "Ljava/util/List;", None))
+    );
+}
@@ -0,0 +1,81 @@
+mod common;
+
+use common::{fixture, RawClass};
+use jvmb::{accessflags::ACC_PUBLIC, classfile::ClassFile};
+
+fn class(name: &str) -> ClassFile {
+    ClassFile::from_bytes(&fixture(name)).unwrap()
+}
+
+#[test]
+fn overloads_are_told_apart_by_their_descriptors() {
+    let class_file = class("lookup/Overloads");
+    let pool = &class_file.constant_pool;
+    for descriptor in ["(I)V", "(J)V", "(Ljava/lang/String;)V", "(II)I"] {
+        let method = class_file.find_method("add", descriptor).unwrap();
+        assert_eq!(method.name(pool), Some("add"));
+        assert_eq!(method.descriptor(pool), Some(descriptor));
+    }
+    assert!(class_file.find_method("add", "(D)V").is_none());
+    assert!(class_file.find_method("remove", "(I)V").is_none());
+    // a descriptor is matched whole, not as a prefix
+    assert!(class_file.find_method("add", "(I)").is_none());
+}
+
+#[test]
+fn every_overload_is_found_in_declaration_order() {
+    let class_file = class("lookup/Overloads");
+    let descriptors: Vec<&str> = class_file
+        .find_methods("add")
+        .iter()
+        .map(|method| method.descriptor(&class_file.constant_pool).unwrap())
+        .collect();
+    assert_eq!(
+        descriptors,
+        ["(I)V", "(J)V", "(Ljava/lang/String;)V", "(II)I"]
+    );
+    assert_eq!(class_file.find_methods("<init>").len(), 1);
+    assert!(class_file.find_methods("remove").is_empty());
+}
+
+#[test]
+fn fields_are_found_by_name() {
+    let class_file = class("lookup/Overloads");
+    let pool = &class_file.constant_pool;
+    assert_eq!(
+        class_file.find_field("count").unwrap().descriptor(pool),
+        Some("I")
+    );
+    assert_eq!(
+        class_file.find_field("name").unwrap().descriptor(pool),
+        Some("Ljava/lang/String;")
+    );
+    assert!(class_file.find_field("add").is_none());
+}
+
+#[test]
+fn of_two_fields_of_one_name_the_first_is_found() {
+    let mut raw = RawClass::new("p/Twice", "java/lang/Object");
+    raw.field(ACC_PUBLIC, "value", "I", &[]);
+    raw.field(ACC_PUBLIC, "value", "J", &[]);
+    let class_file = ClassFile::from_bytes(&raw.bytes()).unwrap();
+    assert_eq!(
+        class_file
+            .find_field("value")
+            .unwrap()
+            .descriptor(&class_file.constant_pool),
+        Some("I")
+    );
+}
+
+#[test]
+fn only_a_public_static_main_of_a_string_array_launches() {
+    let launcher = class("lookup/Launcher");
+    assert!(launcher.has_main_method());
+    // beside a main of one string, which doesn't count
+    assert_eq!(launcher.find_methods("main").len(), 2);
+    // public but not static
+    assert!(!class("lookup/Overloads").has_main_method());
+    // no main at all
+    assert!(!class("records/Point").has_main_method());
+}
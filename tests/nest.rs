@@ -0,0 +1,126 @@
+mod common;
+
+use common::fixture;
+use jvmb::{
+    attribute::Attribute,
+    classfile::ClassFile,
+    constantpool::ConstantPool,
+    nest::{check, NestMismatch},
+};
+
+/// A class of the javac nest of the sealed interface `sealed/Shape`,
+/// which permits and hosts its two records, `Circle` and `Square`.
+fn shape(name: &str) -> ClassFile {
+    ClassFile::from_bytes(&fixture(&format!("sealed/{}", name))).unwrap()
+}
+
+/// Points the `NestHost` of `class_file` at a new class entry of `host`.
+fn set_nest_host(class_file: &mut ClassFile, host: &str) {
+    let name = ConstantPool::intern_utf8(&mut class_file.constant_pool, host);
+    class_file.constant_pool.push(ConstantPool::Class(name));
+    let class = class_file.constant_pool.len() as u16;
+    for attribute in &mut class_file.attributes {
+        if let Attribute::NestHost(index) = attribute {
+            *index = class;
+        }
+    }
+}
+
+#[test]
+fn a_sealed_interface_resolves_its_permitted_records_and_nest() {
+    let host = shape("Shape");
+    assert!(host.is_sealed());
+    assert_eq!(
+        host.permitted_subclasses(),
+        ["sealed/Shape$Circle", "sealed/Shape$Square"]
+    );
+    // javac lists nest members in the reverse order
+    assert_eq!(
+        host.nest_members(),
+        ["sealed/Shape$Square", "sealed/Shape$Circle"]
+    );
+    assert_eq!(host.nest_host(), None);
+
+    let circle = shape("Shape$Circle");
+    assert!(!circle.is_sealed());
+    assert_eq!(circle.permitted_subclasses(), Vec::<&str>::new());
+    assert_eq!(circle.nest_members(), Vec::<&str>::new());
+    assert_eq!(circle.nest_host(), Some("sealed/Shape"));
+}
+
+#[test]
+fn the_javac_nest_is_consistent() {
+    let (host, circle, square) = (shape("Shape"), shape("Shape$Circle"), shape("Shape$Square"));
+    assert_eq!(check(&host, &[&circle, &square]), []);
+    assert_eq!(check(&host, &[&square, &circle]), []);
+}
+
+#[test]
+fn a_member_naming_another_host_is_reported() {
+    let (host, square) = (shape("Shape"), shape("Shape$Square"));
+    let mut circle = shape("Shape$Circle");
+    set_nest_host(&mut circle, "sealed/Other");
+    assert_eq!(circle.nest_host(), Some("sealed/Other"));
+
+    let mismatches = check(&host, &[&circle, &square]);
+    assert_eq!(
+        mismatches,
+        [NestMismatch::WrongHost {
+            member: "sealed/Shape$Circle".to_string(),
+            its_host: Some("sealed/Other".to_string()),
+        }]
+    );
+    assert_eq!(
+        mismatches[0].to_string(),
+        "sealed/Shape$Circle names sealed/Other as its nest host"
+    );
+
+    circle
+        .attributes
+        .retain(|attribute| !matches!(attribute, Attribute::NestHost(_)));
+    assert_eq!(
+        check(&host, &[&circle, &square])[0].to_string(),
+        "sealed/Shape$Circle has no NestHost attribute"
+    );
+}
+
+#[test]
+fn members_missing_from_either_side_are_reported() {
+    let (circle, square) = (shape("Shape$Circle"), shape("Shape$Square"));
+    let mut host = shape("Shape");
+    assert_eq!(
+        check(&host, &[&circle]),
+        [NestMismatch::Missing {
+            member: "sealed/Shape$Square".to_string(),
+        }]
+    );
+
+    for attribute in &mut host.attributes {
+        if let Attribute::NestMembers(members) = attribute {
+            members.remove(0);
+        }
+    }
+    let mismatches = check(&host, &[&circle, &square]);
+    assert_eq!(
+        mismatches,
+        [NestMismatch::NotListed {
+            member: "sealed/Shape$Square".to_string(),
+        }]
+    );
+    assert_eq!(
+        mismatches[0].to_string(),
+        "sealed/Shape$Square is not among the host's NestMembers"
+    );
+}
+
+#[test]
+fn a_host_which_is_itself_a_member_is_reported() {
+    let host = shape("Shape$Circle");
+    assert_eq!(
+        check(&host, &[]),
+        [NestMismatch::HostHasHost {
+            host: "sealed/Shape$Circle".to_string(),
+            its_host: "sealed/Shape".to_string(),
+        }]
+    );
+}
@@ -0,0 +1,75 @@
+#![cfg(feature = "analysis")]
+
+mod common;
+
+use common::fixture;
+use jvmb::{
+    classfile::ClassFile,
+    classindex::{ClassIndex, Override},
+};
+
+/// An index of the classes under `tests/fixtures/overrides`.
+fn index() -> ClassIndex {
+    let mut index = ClassIndex::new();
+    for name in [
+        "overrides/base/Handler",
+        "overrides/base/Local",
+        "overrides/other/Remote",
+        "overrides/other/Unrelated",
+    ] {
+        let class_file = ClassFile::from_bytes(&fixture(name)).unwrap();
+        index.add_class(&class_file, name);
+    }
+    index
+}
+
+fn overrides_of(method_name: &str, descriptor: &str) -> Vec<Override> {
+    index()
+        .overrides_of("overrides/base/Handler", method_name, descriptor)
+        .unwrap()
+}
+
+#[test]
+fn a_covariant_override_is_found_through_its_bridge() {
+    assert_eq!(
+        overrides_of("handle", "(Ljava/lang/String;)Ljava/lang/Object;"),
+        [
+            Override {
+                class: "overrides/base/Local".to_string(),
+                descriptor: "(Ljava/lang/String;)Ljava/lang/Object;".to_string(),
+                bridge: None,
+            },
+            // not the static handle(Object), nor Unrelated's handle
+            Override {
+                class: "overrides/other/Remote".to_string(),
+                descriptor: "(Ljava/lang/String;)Ljava/lang/String;".to_string(),
+                bridge: Some("(Ljava/lang/String;)Ljava/lang/Object;".to_string()),
+            },
+        ]
+    );
+}
+
+#[test]
+fn package_private_methods_are_only_overridden_in_their_package() {
+    assert_eq!(
+        overrides_of("internal", "()V"),
+        [Override {
+            class: "overrides/base/Local".to_string(),
+            descriptor: "()V".to_string(),
+            bridge: None,
+        }]
+    );
+}
+
+#[test]
+fn final_constructors_and_missing_methods_have_no_overrides() {
+    assert_eq!(overrides_of("sealed", "()V"), []);
+    assert_eq!(overrides_of("<init>", "()V"), []);
+    assert_eq!(
+        overrides_of("handle", "(Ljava/lang/Object;)Ljava/lang/Object;"),
+        []
+    );
+    assert!(index()
+        .overrides_of("overrides/base/Missing", "handle", "()V")
+        .is_err());
+}
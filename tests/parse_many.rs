@@ -0,0 +1,54 @@
+mod common;
+
+use common::fixture;
+use jvmb::{classfile::ClassFile, constantpool::ConstantPool, error::ParseError};
+
+fn concatenated(names: &[&str]) -> (Vec<u8>, Vec<usize>) {
+    let mut bytes = Vec::new();
+    let mut starts = Vec::new();
+    for name in names {
+        starts.push(bytes.len());
+        bytes.extend_from_slice(&fixture(name));
+    }
+    (bytes, starts)
+}
+
+fn name(class_file: &ClassFile) -> &str {
+    ConstantPool::class_name(&class_file.constant_pool, class_file.this_class).unwrap()
+}
+
+#[test]
+fn each_class_comes_with_its_range() {
+    let names = ["records/Point", "arrays/Arrays", "frames/Branches"];
+    let (bytes, starts) = concatenated(&names);
+    let parsed: Vec<_> = ClassFile::parse_many(&bytes)
+        .map(Result::unwrap)
+        .map(|(range, class_file)| (range, name(&class_file).to_string()))
+        .collect();
+    assert_eq!(
+        parsed,
+        [
+            (starts[0]..starts[1], "records/Point".to_string()),
+            (starts[1]..starts[2], "arrays/Arrays".to_string()),
+            (starts[2]..bytes.len(), "frames/Branches".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn a_truncated_last_class_ends_with_an_error_at_its_start() {
+    let names = ["records/Point", "arrays/Arrays", "frames/Branches"];
+    let (mut bytes, starts) = concatenated(&names);
+    bytes.truncate(bytes.len() - 10);
+    let mut classes = ClassFile::parse_many(&bytes);
+    assert_eq!(classes.next().unwrap().unwrap().0, starts[0]..starts[1]);
+    assert_eq!(classes.next().unwrap().unwrap().0, starts[1]..starts[2]);
+    let error = classes.next().unwrap().unwrap_err();
+    assert_eq!(error.offset, starts[2]);
+    assert!(
+        matches!(error.error, ParseError::Truncated { .. }),
+        "{:?}",
+        error.error
+    );
+    assert!(classes.next().is_none());
+}
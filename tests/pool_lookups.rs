@@ -0,0 +1,216 @@
+mod common;
+
+use common::RawClass;
+use jvmb::{
+    classfile::ClassFile,
+    constantpool::{ConstantPool, ConstantPoolError, MemberRef},
+    error::ParseError,
+};
+
+/// Indices of the entries of [`pool`].
+struct Entries {
+    this_class: u16,
+    run: u16,
+    name_and_type: u16,
+    method_ref: u16,
+    interface_method_ref: u16,
+    field_ref: u16,
+    long: u16,
+    /// A `Methodref` whose class is a UTF8 entry.
+    method_ref_of_utf8: u16,
+    /// A `Methodref` whose `NameAndType` names a class entry.
+    method_ref_of_bad_name: u16,
+}
+
+/// A class whose pool has a reference of each kind to `run()V` of
+/// `java/lang/Object`, a `Long`, and two references leading to entries of
+/// the wrong kind.
+fn pool() -> (Vec<ConstantPool>, Entries) {
+    let mut raw = RawClass::new("p/Lookups", "java/lang/Object");
+    let object = raw.class("java/lang/Object");
+    let run = raw.utf8("run");
+    let descriptor = raw.utf8("()V");
+    let pair = |a: u16, b: u16| [a.to_be_bytes(), b.to_be_bytes()].concat();
+    let name_and_type = raw.entry(12, &pair(run, descriptor));
+    let method_ref = raw.entry(10, &pair(object, name_and_type));
+    let interface_method_ref = raw.entry(11, &pair(object, name_and_type));
+    let field_ref = raw.entry(9, &pair(object, name_and_type));
+    let long = raw.entry(5, &42i64.to_be_bytes());
+    let method_ref_of_utf8 = raw.entry(10, &pair(run, name_and_type));
+    let bad_name_and_type = raw.entry(12, &pair(raw.this_class, descriptor));
+    let method_ref_of_bad_name = raw.entry(10, &pair(object, bad_name_and_type));
+    let entries = Entries {
+        this_class: raw.this_class,
+        run,
+        name_and_type,
+        method_ref,
+        interface_method_ref,
+        field_ref,
+        long,
+        method_ref_of_utf8,
+        method_ref_of_bad_name,
+    };
+    let class_file = ClassFile::from_bytes(&raw.bytes()).unwrap();
+    (class_file.constant_pool, entries)
+}
+
+const RUN: MemberRef<'static> = MemberRef {
+    class_name: "java/lang/Object",
+    name: "run",
+    descriptor: "()V",
+};
+
+#[test]
+fn lookups_follow_the_chain_to_names() {
+    let (pool, entries) = pool();
+    assert_eq!(ConstantPool::get_utf8(&pool, entries.run), Ok("run"));
+    assert_eq!(
+        ConstantPool::get_class_name(&pool, entries.this_class),
+        Ok("p/Lookups")
+    );
+    assert_eq!(
+        ConstantPool::get_name_and_type(&pool, entries.name_and_type),
+        Ok(("run", "()V"))
+    );
+    assert_eq!(
+        ConstantPool::resolve_method_ref(&pool, entries.method_ref),
+        Ok(RUN)
+    );
+    assert_eq!(
+        ConstantPool::resolve_method_ref(&pool, entries.interface_method_ref),
+        Ok(RUN)
+    );
+    assert_eq!(
+        ConstantPool::resolve_field_ref(&pool, entries.field_ref),
+        Ok(RUN)
+    );
+}
+
+#[test]
+fn an_entry_of_the_wrong_kind_is_named_rather_than_misread() {
+    let (pool, entries) = pool();
+    let error = ConstantPool::get_utf8(&pool, entries.this_class).unwrap_err();
+    assert_eq!(
+        error,
+        ConstantPoolError::WrongKind {
+            index: entries.this_class,
+            expected: "Utf8",
+            found: "Class",
+        }
+    );
+    assert_eq!(
+        error.to_string(),
+        format!(
+            "constant pool entry {} is Class, expected Utf8",
+            entries.this_class
+        )
+    );
+    assert_eq!(
+        ConstantPool::get_class_name(&pool, entries.run),
+        Err(ConstantPoolError::WrongKind {
+            index: entries.run,
+            expected: "Class",
+            found: "Utf8",
+        })
+    );
+    assert_eq!(
+        ConstantPool::resolve_method_ref(&pool, entries.field_ref),
+        Err(ConstantPoolError::WrongKind {
+            index: entries.field_ref,
+            expected: "Methodref or InterfaceMethodref",
+            found: "Fieldref",
+        })
+    );
+    assert_eq!(
+        ConstantPool::resolve_field_ref(&pool, entries.method_ref),
+        Err(ConstantPoolError::WrongKind {
+            index: entries.method_ref,
+            expected: "Fieldref",
+            found: "Methodref",
+        })
+    );
+    assert_eq!(
+        ConstantPool::get_name_and_type(&pool, entries.long),
+        Err(ConstantPoolError::WrongKind {
+            index: entries.long,
+            expected: "NameAndType",
+            found: "Long",
+        })
+    );
+}
+
+#[test]
+fn the_slot_after_a_long_is_unusable() {
+    let (pool, entries) = pool();
+    assert_eq!(
+        ConstantPool::get_utf8(&pool, entries.long + 1),
+        Err(ConstantPoolError::WrongKind {
+            index: entries.long + 1,
+            expected: "Utf8",
+            found: "unusable slot",
+        })
+    );
+}
+
+#[test]
+fn a_wrong_entry_further_down_the_chain_is_the_one_reported() {
+    let (pool, entries) = pool();
+    assert_eq!(
+        ConstantPool::resolve_method_ref(&pool, entries.method_ref_of_utf8),
+        Err(ConstantPoolError::WrongKind {
+            index: entries.run,
+            expected: "Class",
+            found: "Utf8",
+        })
+    );
+    assert_eq!(
+        ConstantPool::resolve_method_ref(&pool, entries.method_ref_of_bad_name),
+        Err(ConstantPoolError::WrongKind {
+            index: entries.this_class,
+            expected: "Utf8",
+            found: "Class",
+        })
+    );
+}
+
+#[test]
+fn index_zero_and_past_the_end_are_out_of_range() {
+    let (pool, _) = pool();
+    let count = pool.len();
+    for index in [0, count as u16 + 1, u16::MAX] {
+        let error = ConstantPool::entry(&pool, index).unwrap_err();
+        assert_eq!(error, ConstantPoolError::OutOfRange { index, count });
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "constant pool index {} is out of range (1 to {})",
+                index, count
+            )
+        );
+    }
+    assert!(ConstantPool::entry(&pool, count as u16).is_ok());
+}
+
+#[test]
+fn an_attribute_named_by_a_class_entry_fails_to_parse() {
+    let mut raw = RawClass::new("p/Misnamed", "java/lang/Object");
+    let mut attribute = raw.this_class.to_be_bytes().to_vec();
+    attribute.extend_from_slice(&0u32.to_be_bytes());
+    raw.class_attribute(attribute);
+    let error = ClassFile::from_bytes(&raw.bytes()).unwrap_err();
+    assert_eq!(
+        error,
+        ParseError::InvalidConstantPoolIndex(ConstantPoolError::WrongKind {
+            index: raw.this_class,
+            expected: "Utf8",
+            found: "Class",
+        })
+    );
+    assert_eq!(
+        error.to_string(),
+        format!(
+            "constant pool entry {} is Class, expected Utf8",
+            raw.this_class
+        )
+    );
+}
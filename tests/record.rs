@@ -0,0 +1,112 @@
+use jvmb::attribute::Attribute;
+use jvmb::classfile::ClassFile;
+use jvmb::constantpool::ConstantPool;
+
+fn fixture(name: &str) -> Vec<u8> {
+    let path = format!(
+        "{}/tests/fixtures/{}.class",
+        env!("CARGO_MANIFEST_DIR"),
+        name
+    );
+    std::fs::read(&path).unwrap_or_else(|e| panic!("{}: {}", path, e))
+}
+
+#[test]
+fn record_components_resolve_their_signatures_and_annotations() {
+    let bytes = fixture("records/Box");
+    let (_, class) = ClassFile::parse_class_file(&bytes).unwrap();
+    let components = class
+        .attributes
+        .iter()
+        .find_map(|attribute| match attribute {
+            Attribute::Record(components) => Some(components),
+            _ => None,
+        })
+        .expect("Record attribute");
+    let resolved: Vec<_> = components
+        .iter()
+        .map(|component| component.resolved(&class.constant_pool))
+        .collect();
+    let [items, count] = &resolved[..] else {
+        panic!("{:?}", resolved);
+    };
+
+    assert_eq!(items.name, "items");
+    assert_eq!(items.descriptor, "Ljava/util/List;");
+    assert_eq!(
+        items.signature.as_ref().unwrap().to_string(),
+        "java.util.List<T>"
+    );
+    let visible: Vec<_> = items
+        .visible_annotations
+        .iter()
+        .map(|annotation| annotation.descriptor)
+        .collect();
+    assert_eq!(visible, ["Lrecords/Box$NonNull;"]);
+    assert!(items.invisible_annotations.is_empty());
+
+    assert_eq!(count.name, "count");
+    assert_eq!(count.descriptor, "I");
+    assert_eq!(count.signature.as_ref().unwrap().to_string(), "int");
+    assert!(count.visible_annotations.is_empty());
+    let [audit] = &count.invisible_annotations[..] else {
+        panic!("{:?}", count.invisible_annotations);
+    };
+    assert_eq!(audit.descriptor, "Lrecords/Box$Audit;");
+    assert_eq!(audit.type_name(), "records.Box$Audit");
+    assert_eq!(audit.annotation.element_value_pairs.len(), 1);
+}
+
+#[test]
+fn record_attribute_holds_each_component() {
+    let bytes = fixture("records/Point");
+    let (_, class) = ClassFile::parse_class_file(&bytes).unwrap();
+    let pool = &class.constant_pool;
+    let components = class
+        .attributes
+        .iter()
+        .find_map(|attribute| match attribute {
+            Attribute::Record(components) => Some(components),
+            _ => None,
+        })
+        .expect("Record attribute");
+    let components: Vec<_> = components
+        .iter()
+        .map(|component| {
+            (
+                ConstantPool::utf8(pool, component.name_index).unwrap(),
+                ConstantPool::utf8(pool, component.descriptor_index).unwrap(),
+                component.attributes.len(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        components,
+        [("x", "I", 0), ("name", "Ljava/lang/String;", 0)]
+    );
+}
+
+#[test]
+fn record_component_attributes_parse() {
+    let bytes = fixture("records/Box");
+    let (_, class) = ClassFile::parse_class_file(&bytes).unwrap();
+    let components = class
+        .attributes
+        .iter()
+        .find_map(|attribute| match attribute {
+            Attribute::Record(components) => Some(components),
+            _ => None,
+        })
+        .unwrap();
+    assert!(matches!(
+        &components[0].attributes[..],
+        [
+            Attribute::Signature(_),
+            Attribute::RuntimeVisibleAnnotations(_)
+        ]
+    ));
+    assert!(matches!(
+        &components[1].attributes[..],
+        [Attribute::RuntimeInvisibleAnnotations(_)]
+    ));
+}
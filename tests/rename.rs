@@ -0,0 +1,137 @@
+#![cfg(all(feature = "analysis", feature = "write"))]
+
+mod common;
+
+use common::fixture;
+use jvmb::{
+    classfile::ClassFile,
+    classindex::ClassIndex,
+    constantpool::ConstantPool,
+    remap::remap,
+    rename::{Conflict, RenameRules, Renamer},
+    verify::{verify, Severity},
+};
+
+const CLASSES: [&str; 3] = ["rename/Base", "rename/Sub", "rename/Caller"];
+
+fn classes() -> Vec<ClassFile> {
+    CLASSES
+        .iter()
+        .map(|name| ClassFile::from_bytes(&fixture(name)).unwrap())
+        .collect()
+}
+
+fn index(classes: &[ClassFile]) -> ClassIndex {
+    let mut index = ClassIndex::new();
+    for class_file in classes {
+        index.add_class(class_file, "rename.jar");
+    }
+    index
+}
+
+/// The methods `class_file` declares, and those it refers to, as
+/// `name descriptor` and `owner.name descriptor`.
+fn methods(class_file: &ClassFile) -> (Vec<String>, Vec<String>) {
+    let constant_pool = &class_file.constant_pool;
+    let declared = class_file
+        .methods
+        .iter()
+        .map(|method| {
+            format!(
+                "{}{}",
+                method.name(constant_pool).unwrap(),
+                method.descriptor(constant_pool).unwrap()
+            )
+        })
+        .collect();
+    let referenced = constant_pool
+        .iter()
+        .filter_map(|constant| match constant {
+            ConstantPool::MethodRef(class, name_and_type) => {
+                let owner = ConstantPool::class_name(constant_pool, *class)?;
+                let (name, descriptor) =
+                    ConstantPool::name_and_type(constant_pool, *name_and_type)?;
+                Some(format!("{}.{}{}", owner, name, descriptor))
+            }
+            _ => None,
+        })
+        .filter(|method| method.starts_with("rename/"))
+        .collect();
+    (declared, referenced)
+}
+
+#[test]
+fn a_method_is_renamed_with_its_override_and_call_sites() {
+    let mut classes = classes();
+    let index = index(&classes);
+    let mut rules = RenameRules::new();
+    rules.member("rename/Base#a(I)V", "handle").unwrap();
+    let renamer = Renamer::new(&rules, &index).unwrap();
+
+    for class_file in &mut classes {
+        remap(class_file, &renamer).unwrap();
+        let bytes = class_file.to_bytes().unwrap();
+        *class_file = ClassFile::from_bytes(&bytes).unwrap();
+        let errors: Vec<_> = verify(class_file)
+            .into_iter()
+            .filter(|violation| violation.severity == Severity::Error)
+            .collect();
+        assert!(errors.is_empty(), "{:?}", errors);
+    }
+
+    let (declared, referenced) = methods(&classes[0]);
+    assert_eq!(declared, ["<init>()V", "handle(I)V", "b(I)V"]);
+    assert_eq!(referenced, ["rename/Base.handle(I)V"]);
+    let (declared, referenced) = methods(&classes[1]);
+    assert_eq!(declared, ["<init>()V", "handle(I)V"]);
+    assert_eq!(
+        referenced,
+        ["rename/Base.<init>()V", "rename/Base.handle(I)V"]
+    );
+    // through Base, and through Sub from the lambda's body too
+    let (_, referenced) = methods(&classes[2]);
+    assert_eq!(
+        referenced,
+        [
+            "rename/Base.handle(I)V",
+            "rename/Sub.handle(I)V",
+            "rename/Caller.lambda$call$0(Lrename/Sub;)V",
+        ]
+    );
+}
+
+#[test]
+fn conflicts_are_reported_together_before_anything_is_renamed() {
+    let classes = classes();
+    let index = index(&classes);
+    let mut rules = RenameRules::new();
+    // b(I)V is taken, and there's no c(I)V nor Missing
+    rules.member("rename/Base#a(I)V", "b").unwrap();
+    rules.member("rename/Base#c(I)V", "d").unwrap();
+    rules.class("rename/Missing", "rename/Found").unwrap();
+    let taken = |from: &str| Conflict::Taken {
+        from: from.to_string(),
+        to: "b".to_string(),
+        existing: "rename/Base#b(I)V".to_string(),
+    };
+    assert_eq!(
+        Renamer::new(&rules, &index).unwrap_err(),
+        [
+            Conflict::NoSuchClass("rename/Missing".to_string()),
+            Conflict::NoSuchMember("rename/Base#c(I)V".to_string()),
+            taken("rename/Base#a(I)V"),
+            // which Sub inherits, so its override can't take the name either
+            taken("rename/Sub#a(I)V"),
+        ]
+    );
+
+    // the override alone would stop overriding
+    let mut rules = RenameRules::new();
+    rules.member("rename/Sub#a(I)V", "handle").unwrap();
+    let conflicts = Renamer::new(&rules, &index).unwrap_err();
+    assert!(
+        matches!(&conflicts[..], [Conflict::Overrides { .. }]),
+        "{:#?}",
+        conflicts
+    );
+}
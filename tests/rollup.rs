@@ -0,0 +1,153 @@
+#![cfg(all(feature = "analysis", feature = "archive"))]
+
+mod common;
+
+use common::RawClass;
+use jvmb::{
+    classfile::ClassFile,
+    classindex::ClassIndex,
+    json::Json,
+    pipeline::Pipeline,
+    report::{Deps, Metrics},
+};
+
+/// Four classes in three packages:
+///
+/// - `com/shop/cart/Cart`, with two fields and 9 constants
+/// - `com/shop/cart/Line` of Java 8, extending `com/shop/pay/Price`, with
+///   an abstract method and 6 constants
+/// - the interface `com/shop/pay/Payment`, with a field of type `Cart` and
+///   6 constants
+/// - `org/util/Strings`, with an abstract method taking a `Payment` and 6
+///   constants
+fn classes() -> Vec<ClassFile> {
+    let mut cart = RawClass::new("com/shop/cart/Cart", "java/lang/Object");
+    cart.field(0x0002, "items", "Ljava/util/List;", &[]);
+    cart.field(0x0002, "count", "I", &[]);
+    cart.utf8("unused");
+
+    let mut line = RawClass::new("com/shop/cart/Line", "com/shop/pay/Price");
+    line.version = (52, 0);
+    line.access_flags = 0x0421;
+    line.method(0x0401, "total", "()I", &[]);
+
+    let mut payment = RawClass::new("com/shop/pay/Payment", "java/lang/Object");
+    payment.access_flags = 0x0601;
+    payment.field(0x0019, "CART", "Lcom/shop/cart/Cart;", &[]);
+
+    let mut strings = RawClass::new("org/util/Strings", "java/lang/Object");
+    strings.access_flags = 0x0421;
+    strings.method(0x0401, "join", "(Lcom/shop/pay/Payment;)V", &[]);
+
+    [cart, line, payment, strings]
+        .into_iter()
+        .map(|class| ClassFile::from_bytes(&class.bytes()).unwrap())
+        .collect()
+}
+
+/// The `by_package` rollups of the metrics and deps sections, with
+/// packages cut to `depth`.
+fn rollups(depth: Option<usize>) -> Vec<Json> {
+    let mut pipeline = Pipeline::new();
+    pipeline.group_by_package(depth);
+    pipeline.add(Metrics::new());
+    pipeline.add(Deps::new(None, ClassIndex::new()));
+    for class_file in classes() {
+        pipeline.visit("shop.jar", "entry", &class_file);
+    }
+    pipeline
+        .finish()
+        .into_iter()
+        .map(|section| section.document.get("by_package").unwrap().clone())
+        .collect()
+}
+
+/// The members of the packages of `rollup`, `package` and `classes` left
+/// out, as `{package: {measure: value}}` in compact JSON.
+fn packages(rollup: &Json) -> String {
+    let Some(Json::Array(packages)) = rollup.get("packages") else {
+        panic!("no packages in {}", rollup);
+    };
+    let packages = packages.iter().map(|package| {
+        let Json::Object(members) = package else {
+            panic!("{}", package);
+        };
+        let name = package.get("package").unwrap().to_string();
+        let classes = package.get("classes").unwrap();
+        let measures = members
+            .iter()
+            .filter(|(member, _)| member != "package" && member != "classes")
+            .map(|(member, value)| format!("{}:{}", member, value))
+            .collect::<Vec<_>>()
+            .join(",");
+        format!("{}({}):{{{}}}", name.trim_matches('"'), classes, measures)
+    });
+    packages.collect::<Vec<_>>().join(" ")
+}
+
+#[test]
+fn metrics_roll_up_by_their_aggregates() {
+    let rollups = rollups(None);
+    assert_eq!(
+        rollups[0].get("aggregates").unwrap().to_string(),
+        "{\"interfaces\":\"sum\",\"fields\":\"sum\",\"methods\":\"sum\",\
+         \"methods_with_code\":\"sum\",\"constant_pool_entries\":\"avg\",\
+         \"major_version\":\"max\"}"
+    );
+    assert_eq!(
+        packages(&rollups[0]),
+        [
+            // (9 + 6) / 2 constants, and the later of Java 8 and 17
+            "com.shop.cart(2):{interfaces:0,fields:2,methods:1,methods_with_code:0,\
+             constant_pool_entries:7.5,major_version:61}",
+            "com.shop.pay(1):{interfaces:1,fields:1,methods:0,methods_with_code:0,\
+             constant_pool_entries:6.0,major_version:61}",
+            "org.util(1):{interfaces:0,fields:0,methods:1,methods_with_code:0,\
+             constant_pool_entries:6.0,major_version:61}",
+        ]
+        .join(" ")
+    );
+}
+
+#[test]
+fn dependencies_roll_up_into_package_edges() {
+    assert_eq!(
+        packages(&rollups(None)[1]),
+        [
+            r#"com.shop.cart(2):{depends_on:["com.shop.pay","java.lang","java.util"]}"#,
+            r#"com.shop.pay(1):{depends_on:["com.shop.cart","java.lang"]}"#,
+            r#"org.util(1):{depends_on:["com.shop.pay","java.lang"]}"#,
+        ]
+        .join(" ")
+    );
+}
+
+#[test]
+fn packages_cut_to_a_depth_roll_up_together() {
+    let by_two = rollups(Some(2));
+    assert_eq!(by_two[0].get("package_depth"), Some(&Json::Int(2)));
+    assert_eq!(
+        packages(&by_two[0]),
+        [
+            // (9 + 6 + 6) / 3 constants
+            "com.shop(3):{interfaces:1,fields:3,methods:1,methods_with_code:0,\
+             constant_pool_entries:7.0,major_version:61}",
+            "org.util(1):{interfaces:0,fields:0,methods:1,methods_with_code:0,\
+             constant_pool_entries:6.0,major_version:61}",
+        ]
+        .join(" ")
+    );
+    // the edges between cart and pay are now within com.shop
+    assert_eq!(
+        packages(&by_two[1]),
+        [
+            r#"com.shop(3):{depends_on:["java.lang","java.util"]}"#,
+            r#"org.util(1):{depends_on:["com.shop","java.lang"]}"#,
+        ]
+        .join(" ")
+    );
+    assert_eq!(
+        packages(&rollups(Some(1))[1]),
+        r#"com(3):{depends_on:["java"]} org(1):{depends_on:["com","java"]}"#
+    );
+}
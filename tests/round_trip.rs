@@ -0,0 +1,163 @@
+#![cfg(feature = "write")]
+
+mod common;
+
+use std::{fs, path::Path};
+
+use common::fixture;
+use jvmb::{
+    accessflags::{ClassAccessFlags, ACC_FINAL},
+    attribute::Attribute,
+    classfile::ClassFile,
+    constantpool::ConstantPool,
+};
+
+/// A javac class with `Long` and `Double` constants, strings whose
+/// modified UTF-8 differs from UTF-8, and methods with stack map frames.
+fn constants() -> Vec<u8> {
+    fixture("roundtrip/Constants")
+}
+
+fn class_files(dir: &Path, found: &mut Vec<String>) {
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            class_files(&path, found);
+        } else if path
+            .extension()
+            .is_some_and(|extension| extension == "class")
+        {
+            found.push(path.to_string_lossy().into_owned());
+        }
+    }
+}
+
+#[test]
+fn every_javac_fixture_writes_back_byte_for_byte() {
+    let mut paths = Vec::new();
+    class_files(
+        &Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures"),
+        &mut paths,
+    );
+    assert!(paths.len() > 20, "{:?}", paths);
+    for path in paths {
+        let bytes = fs::read(&path).unwrap();
+        let class_file = ClassFile::from_bytes(&bytes).unwrap();
+        assert!(class_file.to_bytes().unwrap() == bytes, "{}", path);
+    }
+}
+
+#[test]
+fn two_slot_constants_modified_utf8_and_frames_write_back_byte_for_byte() {
+    let bytes = constants();
+    let class_file = ClassFile::from_bytes(&bytes).unwrap();
+    let pool = &class_file.constant_pool;
+
+    let two_slot: Vec<usize> = pool
+        .iter()
+        .enumerate()
+        .filter(|(_, constant)| matches!(constant, ConstantPool::Long(_) | ConstantPool::Double(_)))
+        .map(|(i, _)| i)
+        .collect();
+    assert_eq!(two_slot.len(), 4);
+    for i in two_slot {
+        assert_eq!(pool[i + 1], ConstantPool::Unusable);
+    }
+    assert!(pool.contains(&ConstantPool::Long(0x1234_5678_9abc_def0)));
+    // NUL is written as two bytes, and a supplementary character as two
+    // three-byte surrogates
+    assert!(bytes.windows(4).any(|window| window == b"a\xc0\x80b"));
+    assert!(pool.contains(&ConstantPool::UTF8("a\0b".to_string())));
+    assert!(pool.contains(&ConstantPool::UTF8("grüße, ☃ and 😀".to_string())));
+    let frames: usize = class_file
+        .methods
+        .iter()
+        .filter_map(|method| method.stack_map_table())
+        .map(|table| table.entries.len())
+        .sum();
+    assert!(frames > 5, "{}", frames);
+
+    assert!(class_file.to_bytes().unwrap() == bytes);
+}
+
+#[test]
+fn a_tweaked_class_differs_only_where_it_was_tweaked() {
+    let bytes = constants();
+    let mut class_file = ClassFile::from_bytes(&bytes).unwrap();
+    class_file.major_version = 65;
+    class_file.access_flags =
+        ClassAccessFlags::from(u16::from(class_file.access_flags) | ACC_FINAL);
+    let written = class_file.to_bytes().unwrap();
+
+    assert_eq!(written.len(), bytes.len());
+    let changed: Vec<usize> = (0..bytes.len())
+        .filter(|&i| written[i] != bytes[i])
+        .collect();
+    let access_flags = 10 + pool_size(&bytes);
+    assert_eq!(changed, [7, access_flags + 1]);
+    assert_eq!(written[7], 65);
+    assert_eq!(written[access_flags + 1], bytes[access_flags + 1] | 0x10);
+}
+
+#[test]
+fn counts_and_lengths_come_from_the_data() {
+    let bytes = constants();
+    let mut class_file = ClassFile::from_bytes(&bytes).unwrap();
+    class_file.constant_pool_count = 0;
+    class_file.interfaces_count = 7;
+    class_file.fields_count = 0;
+    class_file.methods_count = 1;
+    class_file.attributes_count = 0;
+    assert!(class_file.to_bytes().unwrap() == bytes);
+
+    // without the frames of a method its Code attribute is shorter, and
+    // reads back with the rest of the method as it was
+    let index = class_file
+        .methods
+        .iter()
+        .position(|method| method.stack_map_table().is_some())
+        .unwrap();
+    let Some(Attribute::Code(code)) = class_file.methods[index]
+        .attributes_mut()
+        .iter_mut()
+        .find(|attribute| matches!(attribute, Attribute::Code(_)))
+    else {
+        unreachable!();
+    };
+    code.attributes
+        .retain(|attribute| !matches!(attribute, Attribute::StackMapTable(_)));
+    let written = class_file.to_bytes().unwrap();
+    assert!(written.len() < bytes.len());
+    let reread = ClassFile::from_bytes(&written).unwrap();
+    assert_eq!(reread.methods[index].stack_map_table(), None);
+    let (written_code, code) = (
+        reread.methods[index].code().unwrap(),
+        class_file.methods[index].code().unwrap(),
+    );
+    assert_eq!(written_code.attributes, code.attributes);
+    // the count read back is that of the attributes left, not the one
+    // parsed with the table
+    assert_eq!(code.attributes_count, 3);
+    assert_eq!(written_code.attributes_count, 2);
+    assert_eq!(reread.to_bytes().unwrap(), written);
+}
+
+/// The size in bytes of the constant pool of the class `bytes`.
+fn pool_size(bytes: &[u8]) -> usize {
+    let count = u16::from_be_bytes([bytes[8], bytes[9]]);
+    let mut at = 10;
+    let mut index = 1;
+    while index < count {
+        let tag = bytes[at];
+        at += 1 + match tag {
+            1 => 2 + usize::from(u16::from_be_bytes([bytes[at + 1], bytes[at + 2]])),
+            7 | 8 | 16 | 19 | 20 => 2,
+            15 => 3,
+            3 | 4 | 9 | 10 | 11 | 12 | 17 | 18 => 4,
+            5 | 6 => 8,
+            tag => panic!("tag {}", tag),
+        };
+        index += if tag == 5 || tag == 6 { 2 } else { 1 };
+    }
+    at - 10
+}
@@ -0,0 +1,171 @@
+mod common;
+
+use common::fixture;
+use jvmb::{
+    classfile::{ClassFile, ParseStage, Truncation},
+    error::ParseError,
+};
+
+/// Where each part of a class file starts.
+struct Layout {
+    /// Every constant pool entry.
+    constants: Vec<usize>,
+    fields: Vec<usize>,
+    methods: Vec<usize>,
+    /// The class's attributes, and the end of the class.
+    attributes: Vec<usize>,
+}
+
+fn u16_at(bytes: &[u8], at: usize) -> usize {
+    u16::from_be_bytes([bytes[at], bytes[at + 1]]) as usize
+}
+
+fn u32_at(bytes: &[u8], at: usize) -> usize {
+    u32::from_be_bytes(bytes[at..at + 4].try_into().unwrap()) as usize
+}
+
+/// Skips a count of attributes at `at`.
+fn skip_attributes(bytes: &[u8], mut at: usize) -> usize {
+    let count = u16_at(bytes, at);
+    at += 2;
+    for _ in 0..count {
+        at += 6 + u32_at(bytes, at + 2);
+    }
+    at
+}
+
+/// Finds the parts of `bytes`, a class file with no `long` or `double`
+/// constants.
+fn layout(bytes: &[u8]) -> Layout {
+    let mut at = 10;
+    let constants = (1..u16_at(bytes, 8))
+        .map(|_| {
+            let start = at;
+            at += 1 + match bytes[at] {
+                1 => 2 + u16_at(bytes, at + 1),
+                7 | 8 | 16 | 19 | 20 => 2,
+                15 => 3,
+                tag => {
+                    assert!(![5, 6].contains(&tag), "two slot constant");
+                    4
+                }
+            };
+            start
+        })
+        .collect();
+    at += 6;
+    at += 2 + 2 * u16_at(bytes, at);
+    let members = |at: &mut usize| -> Vec<usize> {
+        let count = u16_at(bytes, *at);
+        *at += 2;
+        (0..count)
+            .map(|_| {
+                let start = *at;
+                *at = skip_attributes(bytes, *at + 6);
+                start
+            })
+            .collect()
+    };
+    let fields = members(&mut at);
+    let methods = members(&mut at);
+    let count = u16_at(bytes, at);
+    at += 2;
+    let mut attributes = vec![at];
+    for _ in 0..count {
+        at += 6 + u32_at(bytes, at + 2);
+        attributes.push(at);
+    }
+    assert_eq!(at, bytes.len());
+    Layout {
+        constants,
+        fields,
+        methods,
+        attributes,
+    }
+}
+
+/// Parses `bytes` cut to `len`, which must fail as truncated, and salvages
+/// what it can, which must have stopped in `stage` at `offset`.
+fn salvage(bytes: &[u8], len: usize, stage: ParseStage, offset: usize) -> ClassFile {
+    let error = ClassFile::from_bytes(&bytes[..len]).unwrap_err();
+    assert!(
+        matches!(error, ParseError::Truncated { .. }),
+        "cut at {}: {:?}",
+        len,
+        error
+    );
+    let salvaged = ClassFile::parse_salvage(&bytes[..len]);
+    assert_eq!(salvaged.truncation, Some(Truncation { stage, offset }));
+    salvaged.class_file
+}
+
+#[test]
+fn a_whole_class_salvages_to_itself() {
+    let bytes = fixture("records/Point");
+    let salvaged = ClassFile::parse_salvage(&bytes);
+    assert_eq!(salvaged.truncation, None);
+    assert_eq!(salvaged.class_file, ClassFile::from_bytes(&bytes).unwrap());
+}
+
+#[test]
+fn a_cut_in_the_pool_keeps_the_constants_before_it() {
+    let bytes = fixture("records/Point");
+    let whole = ClassFile::from_bytes(&bytes).unwrap();
+    let constants = layout(&bytes).constants;
+    let cut = constants.len() / 2;
+    let class_file = salvage(
+        &bytes,
+        constants[cut] + 2,
+        ParseStage::ConstantPool,
+        constants[cut],
+    );
+    assert_eq!(class_file.constant_pool, whole.constant_pool[..cut]);
+    assert_eq!(class_file.constant_pool_count, whole.constant_pool_count);
+    assert_eq!((class_file.this_class, class_file.fields.len()), (0, 0));
+}
+
+#[test]
+fn a_cut_in_a_method_keeps_the_members_before_it() {
+    let bytes = fixture("records/Point");
+    let whole = ClassFile::from_bytes(&bytes).unwrap();
+    let layout = layout(&bytes);
+    // in the code of the third method
+    let class_file = salvage(
+        &bytes,
+        layout.methods[2] + 20,
+        ParseStage::Methods,
+        layout.methods[2],
+    );
+    assert_eq!(class_file.constant_pool, whole.constant_pool);
+    assert_eq!(class_file.this_class, whole.this_class);
+    assert_eq!(class_file.fields, whole.fields);
+    assert_eq!(class_file.methods, whole.methods[..2]);
+    assert_eq!(class_file.methods_count, whole.methods_count);
+    assert!(class_file.attributes.is_empty());
+
+    // in the access flags of the first field
+    let class_file = salvage(
+        &bytes,
+        layout.fields[0] + 1,
+        ParseStage::Fields,
+        layout.fields[0],
+    );
+    assert!(class_file.fields.is_empty());
+    assert_eq!(class_file.interfaces, whole.interfaces);
+}
+
+#[test]
+fn a_cut_in_a_class_attribute_keeps_the_attributes_before_it() {
+    let bytes = fixture("records/Point");
+    let whole = ClassFile::from_bytes(&bytes).unwrap();
+    let attributes = layout(&bytes).attributes;
+    assert!(attributes.len() > 2);
+    for (kept, &start) in attributes[..attributes.len() - 1].iter().enumerate() {
+        // in its length, then just short of its end
+        for len in [start + 4, attributes[kept + 1] - 1] {
+            let class_file = salvage(&bytes, len, ParseStage::Attributes, start);
+            assert_eq!(class_file.methods, whole.methods);
+            assert_eq!(class_file.attributes, whole.attributes[..kept]);
+        }
+    }
+}
@@ -0,0 +1,184 @@
+//! The `--json` output of every fixture conforms to the checked-in JSON
+//! Schema, and the schema `jvmb schema` prints is the checked-in one.
+
+#![cfg(feature = "cli")]
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use jvmb::{classfile::ClassFile, export, json::Json, regex::Regex};
+
+fn jvmb(args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_jvmb"))
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    String::from_utf8(output.stdout).unwrap()
+}
+
+fn fixtures(dir: &Path, out: &mut Vec<PathBuf>) {
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            fixtures(&path, out);
+        } else if path
+            .extension()
+            .is_some_and(|extension| extension == "class")
+        {
+            out.push(path);
+        }
+    }
+}
+
+/// Checks `value` against `schema`, with `$ref`s resolved in `root`,
+/// for the keywords jvmb's schema uses. Returns where it doesn't conform.
+fn check(root: &Json, schema: &Json, value: &Json, at: &str) -> Result<(), String> {
+    let fail = |what: &str| Err(format!("{}: {} in {}", at, what, value));
+    if let Some(Json::String(reference)) = schema.get("$ref") {
+        let name = reference.strip_prefix("#/$defs/").unwrap();
+        let target = root.get("$defs").and_then(|defs| defs.get(name)).unwrap();
+        check(root, target, value, at)?;
+    }
+    if let Some(Json::String(kind)) = schema.get("type") {
+        let matches = matches!(
+            (kind.as_str(), value),
+            ("object", Json::Object(_))
+                | ("array", Json::Array(_))
+                | ("string", Json::String(_))
+                | ("integer", Json::Int(_))
+                | ("number", Json::Int(_) | Json::Float(_))
+                | ("boolean", Json::Bool(_))
+                | ("null", Json::Null)
+        );
+        if !matches {
+            return fail(&format!("not of type {}", kind));
+        }
+    }
+    if let Some(expected) = schema.get("const") {
+        if expected != value {
+            return fail(&format!("not {}", expected));
+        }
+    }
+    if let Some(Json::Array(options)) = schema.get("enum") {
+        if !options.contains(value) {
+            return fail("not one of the enum");
+        }
+    }
+    if let Json::Int(n) = value {
+        if let Some(Json::Int(minimum)) = schema.get("minimum") {
+            if n < minimum {
+                return fail("below the minimum");
+            }
+        }
+        if let Some(Json::Int(maximum)) = schema.get("maximum") {
+            if n > maximum {
+                return fail("above the maximum");
+            }
+        }
+    }
+    if let (Some(Json::String(pattern)), Json::String(text)) = (schema.get("pattern"), value) {
+        if !Regex::new(pattern).unwrap().is_match(text) {
+            return fail(&format!("not matching {}", pattern));
+        }
+    }
+    if let (Some(items), Json::Array(values)) = (schema.get("items"), value) {
+        for (i, item) in values.iter().enumerate() {
+            check(root, items, item, &format!("{}[{}]", at, i))?;
+        }
+    }
+    if let Json::Object(members) = value {
+        let properties = schema.get("properties");
+        if let Some(Json::Array(required)) = schema.get("required") {
+            for name in required {
+                let Json::String(name) = name else { panic!() };
+                if value.get(name).is_none() {
+                    return fail(&format!("missing {}", name));
+                }
+            }
+        }
+        for (name, member) in members {
+            match properties.and_then(|properties| properties.get(name)) {
+                Some(property) => check(root, property, member, &format!("{}.{}", at, name))?,
+                None if schema.get("additionalProperties") == Some(&Json::Bool(false)) => {
+                    return fail(&format!("unexpected {}", name));
+                }
+                None => {}
+            }
+        }
+    }
+    if let Some(Json::Array(options)) = schema.get("anyOf") {
+        if !options
+            .iter()
+            .any(|option| check(root, option, value, at).is_ok())
+        {
+            return fail("matching none of anyOf");
+        }
+    }
+    if let Some(Json::Array(options)) = schema.get("oneOf") {
+        let matching = options
+            .iter()
+            .filter(|option| check(root, option, value, at).is_ok())
+            .count();
+        if matching != 1 {
+            return fail(&format!("matching {} of oneOf", matching));
+        }
+    }
+    if let Some(not) = schema.get("not") {
+        if check(root, not, value, at).is_ok() {
+            return fail("matching not");
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn schema_command_prints_the_checked_in_schema() {
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/schema/jvmb.schema.json");
+    assert_eq!(jvmb(&["schema"]), fs::read_to_string(path).unwrap());
+}
+
+#[test]
+fn json_output_of_every_fixture_conforms_to_the_schema() {
+    let schema = export::schema();
+    let mut paths = Vec::new();
+    fixtures(
+        &Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures"),
+        &mut paths,
+    );
+    assert!(!paths.is_empty());
+    for path in paths {
+        let class_file = ClassFile::from_path(&path).unwrap();
+        let document = export::class_file(&class_file);
+        assert_eq!(
+            jvmb(&[path.to_str().unwrap(), "--json"]),
+            format!("{}\n", document.pretty()),
+            "{}",
+            path.display()
+        );
+        if let Err(problem) = check(&schema, &schema, &document, "$") {
+            panic!("{}: {}", path.display(), problem);
+        }
+    }
+}
+
+#[test]
+fn the_checker_rejects_what_the_schema_rules_out() {
+    let schema = export::schema();
+    let path = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/records/Point.class"
+    );
+    let mut document = export::class_file(&ClassFile::from_path(path).unwrap());
+    document.push("extra", Json::Null);
+    assert!(check(&schema, &schema, &document, "$").is_err());
+
+    let Json::Object(members) = &mut document else {
+        panic!()
+    };
+    members.retain(|(name, _)| name != "extra" && name != "this_class");
+    assert!(check(&schema, &schema, &document, "$").is_err());
+}
@@ -0,0 +1,97 @@
+#![cfg(feature = "cli")]
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::{Command, Output},
+};
+
+use jvmb::{accessflags::ACC_PUBLIC, attribute::Code, builder::ClassFileBuilder};
+
+fn selftest(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_jvmb"))
+        .arg("selftest")
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+/// An empty directory of this test's own in the temporary directory.
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("jvmb-selftest-{}-{}", std::process::id(), name));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Writes the fixture classes, `fixtures/A` and `fixtures/B`, to `dir`.
+fn write_fixtures(dir: &Path) {
+    for name in ["A", "B"] {
+        let mut builder = ClassFileBuilder::new(&format!("fixtures/{}", name));
+        builder.add_field(ACC_PUBLIC, "value", "I");
+        // return
+        builder.add_method(ACC_PUBLIC, "run", "()V", Some(Code::new(0, 1, vec![0xb1])));
+        let bytes = builder.build().unwrap().to_bytes().unwrap();
+        fs::write(dir.join(format!("{}.class", name)), bytes).unwrap();
+    }
+}
+
+#[test]
+fn fixture_classes_pass() {
+    let dir = temp_dir("pass");
+    write_fixtures(&dir);
+    let output = selftest(&[dir.to_str().unwrap()]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(0), "{}", stdout);
+    assert!(stdout.starts_with(&format!("jvmb {}\n", env!("CARGO_PKG_VERSION"))));
+    assert!(stdout.contains("2 classes tested: 2 passed, 0 failed"));
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn a_failing_fixture_is_reported_by_its_code() {
+    let dir = temp_dir("fail");
+    write_fixtures(&dir);
+    fs::write(dir.join("C.class"), b"not a class").unwrap();
+    let output = selftest(&[dir.to_str().unwrap()]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(3), "{}", stdout);
+    assert!(stdout.contains("C.class: [parse/BadMagic]"));
+    assert!(stdout.contains("3 classes tested: 2 passed, 1 failed"));
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn a_jdk_with_nothing_to_test_is_a_usage_error() {
+    let jdk = temp_dir("empty-jdk");
+    let output = selftest(&["--system", jdk.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(1), "{:?}", output);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("neither jmods"));
+    fs::remove_dir_all(jdk).unwrap();
+}
+
+/// A JDK without jmods, made of a link to the `java` on the `PATH`, has
+/// its classes dumped by running it. Passes without testing anything when
+/// there's no `java`.
+#[cfg(unix)]
+#[test]
+fn a_jdk_without_jmods_has_its_classes_dumped() {
+    let java = std::env::var_os("PATH").and_then(|path| {
+        std::env::split_paths(&path)
+            .map(|dir| dir.join("java"))
+            .find(|java| java.is_file())
+    });
+    let Some(java) = java else {
+        eprintln!("no java on the PATH, not tested");
+        return;
+    };
+    let jdk = temp_dir("no-jmods");
+    fs::create_dir(jdk.join("bin")).unwrap();
+    std::os::unix::fs::symlink(fs::canonicalize(java).unwrap(), jdk.join("bin/java")).unwrap();
+    let output = selftest(&["--system", jdk.to_str().unwrap(), "--sample", "3"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(0), "{}{:?}", stdout, output);
+    assert!(stdout.contains("3 of 20 classes dumped, no jmods"));
+    assert!(stdout.contains("3 classes tested: 3 passed, 0 failed"));
+    fs::remove_dir_all(jdk).unwrap();
+}
@@ -0,0 +1,63 @@
+#![cfg(feature = "serde")]
+
+mod common;
+
+use common::fixture;
+use jvmb::{classfile::ClassFile, constantpool::ConstantPool};
+use serde_json::{json, Value};
+
+/// A javac class with frames of every form, as parsed and as JSON.
+fn branches() -> (ClassFile, Value) {
+    let class_file = ClassFile::from_bytes(&fixture("frames/Branches")).unwrap();
+    let value = serde_json::to_value(&class_file).unwrap();
+    (class_file, value)
+}
+
+#[test]
+fn known_values_appear_in_the_json() {
+    let (class_file, value) = branches();
+    assert_eq!(value["major_version"], 61);
+    assert_eq!(value["minor_version"], 0);
+    let name_index = class_file.methods[0].name_index();
+    assert_eq!(value["methods"][0]["name_index"], name_index);
+    assert_eq!(
+        ConstantPool::utf8(&class_file.constant_pool, name_index),
+        Some("<init>")
+    );
+    assert_eq!(
+        value["constant_pool"][usize::from(name_index) - 1],
+        json!({ "UTF8": "<init>" })
+    );
+}
+
+/// The first value anywhere in `value` under the key `key`.
+fn find<'a>(value: &'a Value, key: &str) -> Option<&'a Value> {
+    match value {
+        Value::Object(map) => map
+            .get(key)
+            .or_else(|| map.values().find_map(|value| find(value, key))),
+        Value::Array(values) => values.iter().find_map(|value| find(value, key)),
+        _ => None,
+    }
+}
+
+#[test]
+fn full_frames_have_named_fields() {
+    let (_, value) = branches();
+    let frame = find(&value, "FullFrame").unwrap();
+    let mut fields: Vec<&str> = frame
+        .as_object()
+        .unwrap()
+        .keys()
+        .map(String::as_str)
+        .collect();
+    fields.sort_unstable();
+    assert_eq!(fields, ["locals", "offset_delta", "stack"]);
+}
+
+#[test]
+fn a_class_reads_back_from_its_json() {
+    let (class_file, value) = branches();
+    let read: ClassFile = serde_json::from_value(value).unwrap();
+    assert_eq!(read, class_file);
+}
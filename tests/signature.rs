@@ -0,0 +1,437 @@
+mod common;
+
+use common::fixture;
+use jvmb::{
+    attribute::Attribute,
+    classfile::ClassFile,
+    constantpool::ConstantPool,
+    descriptor::BaseType,
+    signature::{
+        best_class_signature, best_method_signature, BestMethodSignature, ClassSignature,
+        ClassTypeSignature, MethodSignature, ParsedSignature, TypeArgument, TypeParameter,
+        TypeSignature,
+    },
+};
+
+/// The best signature of the method `name` of the fixture class `class`.
+fn method(class: &str, name: &str) -> BestMethodSignature {
+    let class_file = ClassFile::from_bytes(&fixture(class)).unwrap();
+    let method = class_file
+        .methods
+        .iter()
+        .find(|method| {
+            ConstantPool::utf8(&class_file.constant_pool, method.name_index()) == Some(name)
+        })
+        .unwrap();
+    best_method_signature(&class_file, method).unwrap()
+}
+
+// The expected renderings are those of `javap -p`, less the modifiers.
+
+#[test]
+fn enum_constructors_leave_out_the_name_and_ordinal() {
+    let constructor = method("generics/Shapes$Kind", "<init>");
+    assert_eq!(
+        constructor.to_string(),
+        "generics.Shapes$Kind(java.util.List<java.lang.String>)"
+    );
+    assert_eq!(constructor.implicit, [true, true, false]);
+    assert!(constructor.from_attribute);
+    assert_eq!(
+        method("generics/Shapes$Kind", "valueOf").to_string(),
+        "generics.Shapes$Kind valueOf(java.lang.String)"
+    );
+}
+
+#[test]
+fn inner_class_constructors_leave_out_the_outer_instance() {
+    let constructor = method("generics/Shapes$Inner", "<init>");
+    assert_eq!(
+        constructor.to_string(),
+        "generics.Shapes$Inner(java.util.Map<S, T>, int)"
+    );
+    assert_eq!(constructor.implicit, [true, false, false]);
+
+    // and local classes the variables they capture too
+    let constructor = method("generics/Shapes$1Counter", "<init>");
+    assert_eq!(
+        constructor.to_string(),
+        "generics.Shapes$1Counter(java.util.List<S>)"
+    );
+    assert_eq!(constructor.implicit, [true, false, true, true]);
+}
+
+#[test]
+fn generic_methods_keep_their_type_parameters() {
+    let largest = method("generics/Shapes", "largest");
+    assert_eq!(
+        largest.to_string(),
+        "<K extends java.lang.Comparable<? super K>, V> K \
+         largest(java.util.Map<K, ? extends java.util.List<V>>)"
+    );
+    assert_eq!(largest.implicit, [false]);
+
+    // no Signature, so from the descriptor
+    let local = method("generics/Shapes", "local");
+    assert_eq!(
+        local.to_string(),
+        "java.lang.Runnable local(java.lang.String, int)"
+    );
+    assert!(!local.from_attribute);
+}
+
+#[test]
+fn classes_render_as_javap_declares_them() {
+    for (class, declaration) in [
+        (
+            "generics/Shapes",
+            "generics.Shapes<S extends java.lang.Number>",
+        ),
+        ("generics/Shapes$Inner", "generics.Shapes$Inner<T>"),
+        (
+            "generics/Shapes$Kind",
+            "generics.Shapes$Kind extends java.lang.Enum<generics.Shapes$Kind>",
+        ),
+        (
+            "generics/Shapes$1Counter",
+            "generics.Shapes$1Counter implements java.lang.Runnable",
+        ),
+    ] {
+        let class_file = ClassFile::from_bytes(&fixture(class)).unwrap();
+        assert_eq!(best_class_signature(&class_file).to_string(), declaration);
+    }
+}
+
+/// The `Signature` attributes of the fixture class `class`, of the class
+/// itself, its fields and its methods, as written and as parsed.
+fn signatures(class: &str) -> Vec<(String, ParsedSignature)> {
+    let class_file = ClassFile::from_bytes(&fixture(class)).unwrap();
+    let constant_pool = &class_file.constant_pool;
+    class_file
+        .attributes
+        .iter()
+        .chain(
+            class_file
+                .fields
+                .iter()
+                .flat_map(|field| field.attributes()),
+        )
+        .chain(
+            class_file
+                .methods
+                .iter()
+                .flat_map(|method| method.attributes()),
+        )
+        .filter_map(|attribute| match attribute {
+            Attribute::Signature(signature) => Some((
+                signature.signature(constant_pool).unwrap().to_string(),
+                signature.parse_signature(constant_pool).unwrap().unwrap(),
+            )),
+            _ => None,
+        })
+        .collect()
+}
+
+/// The signature of `parsed`, written back as a class file holds it.
+fn write_signature(parsed: &ParsedSignature) -> String {
+    fn type_parameters(out: &mut String, parameters: &[TypeParameter]) {
+        if parameters.is_empty() {
+            return;
+        }
+        out.push('<');
+        for parameter in parameters {
+            out.push_str(&parameter.name);
+            out.push(':');
+            if let Some(bound) = &parameter.class_bound {
+                type_signature(out, bound);
+            }
+            for bound in &parameter.interface_bounds {
+                out.push(':');
+                type_signature(out, bound);
+            }
+        }
+        out.push('>');
+    }
+
+    fn class_type(out: &mut String, class: &ClassTypeSignature) {
+        out.push('L');
+        for (i, segment) in class.segments.iter().enumerate() {
+            if i > 0 {
+                out.push('.');
+            }
+            out.push_str(&segment.name);
+            if !segment.type_arguments.is_empty() {
+                out.push('<');
+                for argument in &segment.type_arguments {
+                    match argument {
+                        TypeArgument::Any => out.push('*'),
+                        TypeArgument::Exactly(bound) => type_signature(out, bound),
+                        TypeArgument::Extends(bound) => {
+                            out.push('+');
+                            type_signature(out, bound);
+                        }
+                        TypeArgument::Super(bound) => {
+                            out.push('-');
+                            type_signature(out, bound);
+                        }
+                    }
+                }
+                out.push('>');
+            }
+        }
+        out.push(';');
+    }
+
+    fn type_signature(out: &mut String, signature: &TypeSignature) {
+        match signature {
+            TypeSignature::Base(base_type) => out.push(match base_type {
+                BaseType::Byte => 'B',
+                BaseType::Char => 'C',
+                BaseType::Double => 'D',
+                BaseType::Float => 'F',
+                BaseType::Int => 'I',
+                BaseType::Long => 'J',
+                BaseType::Short => 'S',
+                BaseType::Boolean => 'Z',
+            }),
+            TypeSignature::Class(class) => class_type(out, class),
+            TypeSignature::Variable(name) => {
+                out.push('T');
+                out.push_str(name);
+                out.push(';');
+            }
+            TypeSignature::Array(element) => {
+                out.push('[');
+                type_signature(out, element);
+            }
+        }
+    }
+
+    let mut out = String::new();
+    match parsed {
+        ParsedSignature::Class(class) => {
+            type_parameters(&mut out, &class.type_parameters);
+            class_type(&mut out, class.super_class.as_ref().unwrap());
+            for interface in &class.interfaces {
+                class_type(&mut out, interface);
+            }
+        }
+        ParsedSignature::Method(method) => {
+            type_parameters(&mut out, &method.type_parameters);
+            out.push('(');
+            for parameter in &method.parameters {
+                type_signature(&mut out, parameter);
+            }
+            out.push(')');
+            match &method.return_type {
+                Some(return_type) => type_signature(&mut out, return_type),
+                None => out.push('V'),
+            }
+            for thrown in &method.throws {
+                out.push('^');
+                type_signature(&mut out, thrown);
+            }
+        }
+        ParsedSignature::Field(field) => type_signature(&mut out, field),
+    }
+    out
+}
+
+fn class(name: &str, type_arguments: Vec<TypeArgument>) -> TypeSignature {
+    TypeSignature::Class(ClassTypeSignature {
+        segments: vec![jvmb::signature::ClassTypeSegment {
+            name: name.to_string(),
+            type_arguments,
+        }],
+    })
+}
+
+fn variable(name: &str) -> TypeSignature {
+    TypeSignature::Variable(name.to_string())
+}
+
+#[test]
+fn javac_signatures_parse_and_write_back_as_they_were() {
+    for class in [
+        "generics/Registry",
+        "generics/Registry$Slot",
+        "generics/Shapes",
+    ] {
+        for (signature, parsed) in signatures(class) {
+            assert_eq!(write_signature(&parsed), signature, "{:?}", parsed);
+        }
+    }
+    let kinds: Vec<&str> = signatures("generics/Registry")
+        .iter()
+        .map(|(_, parsed)| match parsed {
+            ParsedSignature::Class(_) => "class",
+            ParsedSignature::Method(_) => "method",
+            ParsedSignature::Field(_) => "field",
+        })
+        .collect();
+    assert_eq!(kinds, ["class", "field", "field", "field", "method"]);
+}
+
+#[test]
+fn type_parameters_keep_their_class_and_interface_bounds() {
+    let (_, ParsedSignature::Class(registry)) = &signatures("generics/Registry")[0] else {
+        panic!();
+    };
+    assert_eq!(
+        registry.type_parameters,
+        [
+            TypeParameter {
+                name: "K".to_string(),
+                class_bound: None,
+                interface_bounds: vec![class(
+                    "java/lang/Comparable",
+                    vec![TypeArgument::Exactly(variable("K"))]
+                )],
+            },
+            TypeParameter {
+                name: "T".to_string(),
+                class_bound: Some(class("java/lang/Number", vec![])),
+                interface_bounds: vec![class(
+                    "java/lang/Comparable",
+                    vec![TypeArgument::Super(variable("T"))]
+                )],
+            },
+            TypeParameter {
+                name: "E".to_string(),
+                class_bound: None,
+                interface_bounds: vec![class("java/lang/Runnable", vec![])],
+            },
+        ]
+    );
+    // javap's declaration, less the modifiers
+    assert_eq!(
+        best_class_signature(&ClassFile::from_bytes(&fixture("generics/Registry")).unwrap())
+            .to_string(),
+        "generics.Registry<K extends java.lang.Comparable<K>, \
+         T extends java.lang.Number & java.lang.Comparable<? super T>, \
+         E extends java.lang.Runnable> \
+         extends java.util.AbstractMap<K, java.util.List<T>> \
+         implements java.lang.Iterable<java.util.Map$Entry<K, T>>"
+    );
+}
+
+#[test]
+fn fields_keep_nested_wildcards_and_inner_class_type_arguments() {
+    let fields: Vec<String> = signatures("generics/Registry")[1..4]
+        .iter()
+        .map(|(_, parsed)| match parsed {
+            ParsedSignature::Field(field) => field.to_string(),
+            _ => panic!("{:?}", parsed),
+        })
+        .collect();
+    assert_eq!(
+        fields,
+        [
+            "java.util.Map<java.lang.String, java.util.List<? extends java.lang.Number>>",
+            "generics.Registry<K, T, E>.Slot<java.lang.String[]>",
+            "java.util.List<?>[][]",
+        ]
+    );
+
+    let (_, ParsedSignature::Field(TypeSignature::Class(slot))) =
+        &signatures("generics/Registry")[2]
+    else {
+        panic!();
+    };
+    assert_eq!(slot.segments.len(), 2);
+    assert_eq!(slot.segments[0].name, "generics/Registry");
+    assert_eq!(
+        slot.segments[0].type_arguments,
+        ["K", "T", "E"].map(|name| TypeArgument::Exactly(variable(name)))
+    );
+    assert_eq!(slot.segments[1].name, "Slot");
+    assert_eq!(slot.erasure(), "generics/Registry$Slot");
+}
+
+#[test]
+fn methods_keep_type_variables_in_parameters_returns_and_throws() {
+    let (_, ParsedSignature::Method(lookup)) = &signatures("generics/Registry")[4] else {
+        panic!();
+    };
+    assert_eq!(
+        lookup,
+        &MethodSignature {
+            type_parameters: vec![
+                TypeParameter {
+                    name: "X".to_string(),
+                    class_bound: Some(class("java/lang/Exception", vec![])),
+                    interface_bounds: vec![],
+                },
+                TypeParameter {
+                    name: "R".to_string(),
+                    class_bound: Some(class("java/lang/Object", vec![])),
+                    interface_bounds: vec![],
+                },
+            ],
+            parameters: vec![
+                class(
+                    "java/util/Map",
+                    vec![TypeArgument::Super(variable("K")), TypeArgument::Any]
+                ),
+                TypeSignature::Array(Box::new(class(
+                    "java/util/List",
+                    vec![TypeArgument::Exactly(TypeSignature::Array(Box::new(
+                        TypeSignature::Base(BaseType::Int)
+                    )))]
+                ))),
+                variable("E"),
+            ],
+            return_type: Some(variable("R")),
+            throws: vec![variable("X"), class("java/io/IOException", vec![])],
+        }
+    );
+    assert_eq!(
+        method("generics/Registry", "lookup").to_string(),
+        "<X extends java.lang.Exception, R> R lookup(java.util.Map<? super K, ?>, \
+         java.util.List<int[]>[], E) throws X, java.io.IOException"
+    );
+}
+
+#[test]
+fn a_class_signature_with_only_a_super_class_reads_as_a_field() {
+    let signature = "Ljava/util/ArrayList<Ljava/lang/String;>;";
+    assert!(matches!(
+        ParsedSignature::parse(signature),
+        Ok(ParsedSignature::Field(TypeSignature::Class(_)))
+    ));
+    let class = ClassSignature::parse(signature).unwrap();
+    assert_eq!(class.type_parameters, []);
+    assert_eq!(class.interfaces, []);
+    assert_eq!(
+        class.super_class.unwrap().to_string(),
+        "java.util.ArrayList<java.lang.String>"
+    );
+}
+
+#[test]
+fn malformed_signatures_fail_where_they_go_wrong() {
+    for (signature, offset) in [
+        // unterminated type arguments
+        ("Ljava/util/List<TT;", 19),
+        // a type variable without its semicolon runs to the end
+        ("<T:Ljava/lang/Object;>(TT)V", 27),
+        // no return type
+        ("(I)", 3),
+        // a throws clause of nothing
+        ("()V^", 4),
+        // a primitive type argument or bound
+        ("Ljava/util/List<I>;", 16),
+        ("Ljava/util/List<+Z>;", 17),
+        ("<T:I>Ljava/lang/Object;", 3),
+        // trailing bytes
+        ("TT;;", 3),
+    ] {
+        let error = ParsedSignature::parse(signature).unwrap_err();
+        assert_eq!(
+            (error.signature.as_str(), error.offset),
+            (signature, offset),
+            "{}",
+            error
+        );
+    }
+}
@@ -0,0 +1,212 @@
+#![cfg(all(feature = "analysis", feature = "write"))]
+
+mod common;
+
+use common::fixture;
+use jvmb::{
+    attribute::Attribute,
+    classfile::ClassFile,
+    constantpool::ConstantPool,
+    regex::Regex,
+    sourcepaths::{
+        check_source_info, scrub, ScrubChange, ScrubOptions, SourceInfoIssue,
+        DEFAULT_SOURCE_FILE_PATTERN,
+    },
+};
+
+const BUILT_AT: &str = r"C:\build\src\sourcepaths\Leaky.java";
+
+/// A javac class whose `SourceFile` was hand-edited to point at a new
+/// entry of the absolute Windows path it was built at. Its old entry,
+/// `Leaky.java`, is still there for a string constant holding the name.
+fn leaky() -> ClassFile {
+    ClassFile::from_bytes(&fixture("sourcepaths/Leaky")).unwrap()
+}
+
+fn pattern() -> Regex {
+    Regex::new(DEFAULT_SOURCE_FILE_PATTERN).unwrap()
+}
+
+fn source_file(class_file: &ClassFile) -> (u16, &str) {
+    class_file
+        .attributes
+        .iter()
+        .find_map(|attribute| match attribute {
+            Attribute::SourceFile(source_file) => Some((
+                source_file.sourcefile_index,
+                ConstantPool::utf8(&class_file.constant_pool, source_file.sourcefile_index)
+                    .unwrap(),
+            )),
+            _ => None,
+        })
+        .unwrap()
+}
+
+/// `class_file` with a `SourceDebugExtension` of `smap`, its name added to
+/// the pool so that it can be written out.
+fn with_debug_extension(mut class_file: ClassFile, smap: &[u8]) -> ClassFile {
+    ConstantPool::intern_utf8(&mut class_file.constant_pool, "SourceDebugExtension");
+    class_file.constant_pool_count += 1;
+    class_file
+        .attributes
+        .push(Attribute::SourceDebugExtension(smap.to_vec()));
+    class_file
+}
+
+#[test]
+fn an_absolute_windows_path_is_flagged_as_a_path() {
+    assert_eq!(
+        check_source_info(&leaky(), &pattern()),
+        [SourceInfoIssue::SourceFilePath(BUILT_AT.to_string())]
+    );
+}
+
+#[test]
+fn a_name_not_matching_the_pattern_is_flagged_as_unexpected() {
+    let mut class_file = leaky();
+    scrub(&mut class_file, ScrubOptions::default()).unwrap();
+    assert_eq!(check_source_info(&class_file, &pattern()), []);
+
+    let pattern = Regex::new(r"^\w+\.kt$").unwrap();
+    assert_eq!(
+        check_source_info(&class_file, &pattern),
+        [SourceInfoIssue::UnexpectedSourceFile {
+            value: "Leaky.java".to_string(),
+            pattern: r"^\w+\.kt$".to_string(),
+        }]
+    );
+}
+
+#[test]
+fn crlf_and_mixed_line_endings_of_a_debug_extension_are_flagged() {
+    let crlf = with_debug_extension(leaky(), b"SMAP\r\nLeaky.java\r\nJava\r\n*E\r\n");
+    let mixed = with_debug_extension(leaky(), b"SMAP\r\nLeaky.java\nJava\n*E\r\n");
+    let issues = check_source_info(&crlf, &pattern());
+    assert_eq!(
+        issues[1],
+        SourceInfoIssue::DebugExtensionLineEndings { crlf: 4, lf: 0 }
+    );
+    assert_eq!(
+        issues[1].to_string(),
+        "SourceDebugExtension has CRLF line endings (4 lines)"
+    );
+    assert_eq!(
+        check_source_info(&mixed, &pattern())[1].to_string(),
+        "SourceDebugExtension has mixed line endings (2 CRLF, 2 LF)"
+    );
+    let latin1 = with_debug_extension(leaky(), b"SMAP\nL\xe9aky.java\n");
+    assert_eq!(
+        check_source_info(&latin1, &pattern())[1],
+        SourceInfoIssue::DebugExtensionNotUtf8
+    );
+}
+
+#[test]
+fn scrubbing_reduces_the_path_to_its_name_and_leaves_the_constant_alone() {
+    let mut class_file = leaky();
+    let constant_pool = class_file.constant_pool.clone();
+    let literal = class_file
+        .fields
+        .iter()
+        .flat_map(|field| field.attributes())
+        .find_map(|attribute| match attribute {
+            Attribute::ConstantValue(index) => match constant_pool[usize::from(*index) - 1] {
+                ConstantPool::String(string_index) => Some(string_index),
+                _ => None,
+            },
+            _ => None,
+        })
+        .unwrap();
+
+    let changes = scrub(&mut class_file, ScrubOptions::default()).unwrap();
+    assert_eq!(
+        changes,
+        [ScrubChange::SourceFile {
+            from: BUILT_AT.to_string(),
+            to: "Leaky.java".to_string(),
+        }]
+    );
+    assert_eq!(
+        changes[0].to_string(),
+        r#"SourceFile "C:\\build\\src\\sourcepaths\\Leaky.java" -> "Leaky.java""#
+    );
+    // The name is already in the pool for the constant, so it's shared
+    // rather than added, and the path's entry is left in place.
+    assert_eq!(class_file.constant_pool, constant_pool);
+    assert_eq!(source_file(&class_file), (literal, "Leaky.java"));
+
+    let class_file = ClassFile::from_bytes(&class_file.to_bytes().unwrap()).unwrap();
+    assert_eq!(source_file(&class_file).1, "Leaky.java");
+    assert_eq!(
+        ConstantPool::utf8(&class_file.constant_pool, literal),
+        Some("Leaky.java")
+    );
+}
+
+#[test]
+fn scrubbing_a_path_whose_name_isnt_in_the_pool_adds_it() {
+    let mut class_file = leaky();
+    let (index, _) = source_file(&class_file);
+    let ConstantPool::UTF8(value) = &mut class_file.constant_pool[usize::from(index) - 1] else {
+        unreachable!();
+    };
+    *value = "/home/ci/src/sourcepaths/Leaky.kt".into();
+    let count = class_file.constant_pool_count;
+
+    let changes = scrub(&mut class_file, ScrubOptions::default()).unwrap();
+    assert_eq!(
+        changes,
+        [ScrubChange::SourceFile {
+            from: "/home/ci/src/sourcepaths/Leaky.kt".to_string(),
+            to: "Leaky.kt".to_string(),
+        }]
+    );
+    assert_eq!(class_file.constant_pool_count, count + 1);
+    assert_eq!(source_file(&class_file), (count, "Leaky.kt"));
+    let class_file = ClassFile::from_bytes(&class_file.to_bytes().unwrap()).unwrap();
+    assert_eq!(source_file(&class_file), (count, "Leaky.kt"));
+}
+
+#[test]
+fn scrubbing_normalizes_or_drops_the_debug_extension() {
+    let smap = b"SMAP\r\nLeaky.java\nJava\r\n*E\r\n";
+    let mut class_file = with_debug_extension(leaky(), smap);
+    let changes = scrub(&mut class_file, ScrubOptions::default()).unwrap();
+    assert_eq!(
+        changes[1],
+        ScrubChange::DebugExtensionLineEndings { lines: 3 }
+    );
+    assert!(class_file.attributes.iter().any(|attribute| matches!(
+        attribute,
+        Attribute::SourceDebugExtension(smap) if smap == b"SMAP\nLeaky.java\nJava\n*E\n"
+    )));
+
+    let mut class_file = with_debug_extension(leaky(), smap);
+    let options = ScrubOptions {
+        drop_debug_extension: true,
+    };
+    let changes = scrub(&mut class_file, options).unwrap();
+    assert_eq!(
+        changes[1],
+        ScrubChange::DebugExtensionDropped { length: smap.len() }
+    );
+    assert!(!class_file
+        .attributes
+        .iter()
+        .any(|attribute| matches!(attribute, Attribute::SourceDebugExtension(_))));
+}
+
+#[test]
+fn scrubbing_twice_changes_nothing_the_second_time() {
+    let mut class_file = with_debug_extension(leaky(), b"SMAP\r\n*E\r\n");
+    assert_eq!(
+        scrub(&mut class_file, ScrubOptions::default())
+            .unwrap()
+            .len(),
+        2
+    );
+    let bytes = class_file.to_bytes().unwrap();
+    assert_eq!(scrub(&mut class_file, ScrubOptions::default()).unwrap(), []);
+    assert_eq!(class_file.to_bytes().unwrap(), bytes);
+    assert_eq!(check_source_info(&class_file, &pattern()), []);
+}
@@ -0,0 +1,59 @@
+#![cfg(all(feature = "archive", feature = "write"))]
+
+use jvmb::{
+    builder::ClassFileBuilder,
+    jar::{JarFile, JarWriter},
+    splitpackage::{PackageIndex, SplitPackage},
+};
+
+/// A jar holding an empty class under each of `entries`, which are jar entry
+/// names less the `.class`.
+fn jar(entries: &[&str]) -> JarFile {
+    let mut writer = JarWriter::new();
+    for entry in entries {
+        let name = entry
+            .rsplit_once("/versions/")
+            .map_or(*entry, |(_, rest)| rest.split_once('/').unwrap().1);
+        let bytes = ClassFileBuilder::new(name)
+            .build()
+            .unwrap()
+            .to_bytes()
+            .unwrap();
+        writer.add(&format!("{}.class", entry), &bytes).unwrap();
+    }
+    writer
+        .add("com/shared/messages.properties", b"a=b\n")
+        .unwrap();
+    JarFile::from_bytes(writer.finish().unwrap()).unwrap()
+}
+
+#[test]
+fn a_package_in_two_jars_is_split() {
+    let core = jar(&[
+        "com/shared/A",
+        "com/shared/B",
+        "META-INF/versions/11/com/shared/A",
+        "com/core/Only",
+        "Unnamed",
+    ]);
+    let extra = jar(&["com/shared/C", "com/extra/Only", "Unnamed"]);
+
+    let mut index = PackageIndex::new();
+    index.add_jar("core.jar", &core);
+    index.add_jar("extra.jar", &extra);
+    assert_eq!(
+        index.split_packages(),
+        [SplitPackage {
+            package: "com.shared".to_string(),
+            // the versioned A is the same class as the plain one
+            jars: vec![("core.jar".to_string(), 2), ("extra.jar".to_string(), 1)],
+        }]
+    );
+}
+
+#[test]
+fn a_jar_on_its_own_splits_nothing() {
+    let mut index = PackageIndex::new();
+    index.add_jar("core.jar", &jar(&["com/shared/A", "com/shared/B"]));
+    assert_eq!(index.split_packages(), []);
+}
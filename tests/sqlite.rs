@@ -0,0 +1,213 @@
+#![cfg(all(feature = "sqlite", feature = "write"))]
+
+use std::{fs, path::PathBuf};
+
+use jvmb::{
+    accessflags::{ACC_PUBLIC, ACC_STATIC},
+    attribute::Code,
+    builder::ClassFileBuilder,
+    classfile::ClassFile,
+    classindex::ClassIndex,
+    sqlexport::{SqliteExporter, SCHEMA_VERSION},
+};
+use rusqlite::Connection;
+
+/// A path in the temporary directory for a database of this test's own,
+/// with nothing there yet.
+fn database(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("jvmb-sqlite-{}-{}", std::process::id(), name));
+    let _ = fs::remove_file(&path);
+    path
+}
+
+/// `app/Reflective`, whose `call()V` invokes `Method.invoke` and loads the
+/// string "secret".
+fn reflective() -> ClassFile {
+    let mut builder = ClassFileBuilder::new("app/Reflective");
+    let invoke = builder.constant_pool().method_ref(
+        "java/lang/reflect/Method",
+        "invoke",
+        "(Ljava/lang/Object;[Ljava/lang/Object;)Ljava/lang/Object;",
+    );
+    let secret = builder.constant_pool().string("secret");
+    let [invoke_high, invoke_low] = invoke.to_be_bytes();
+    let code = vec![
+        0x12, // ldc
+        secret as u8,
+        0x57, // pop
+        0x01, // aconst_null
+        0x01, // aconst_null
+        0x01, // aconst_null
+        0xb6, // invokevirtual
+        invoke_high,
+        invoke_low,
+        0x57, // pop
+        0xb1, // return
+    ];
+    builder.add_method(ACC_PUBLIC, "call", "()V", Some(Code::new(3, 1, code)));
+    builder.add_field(ACC_PUBLIC | ACC_STATIC, "count", "I");
+    builder.build().unwrap()
+}
+
+fn plain(name: &str) -> ClassFile {
+    let mut builder = ClassFileBuilder::new(name);
+    builder.add_method(ACC_PUBLIC, "run", "()V", Some(Code::new(0, 1, vec![0xb1])));
+    builder.build().unwrap()
+}
+
+fn count(connection: &Connection, sql: &str) -> i64 {
+    connection.query_row(sql, [], |row| row.get(0)).unwrap()
+}
+
+#[test]
+fn queries_run_against_the_exported_classes() {
+    let path = database("queries.db");
+    let mut exporter = SqliteExporter::open(&path).unwrap();
+    exporter.begin_source("app.jar").unwrap();
+    exporter.add_class(&reflective()).unwrap();
+    exporter.add_class(&plain("app/Plain")).unwrap();
+    exporter.finish().unwrap();
+
+    let connection = Connection::open(&path).unwrap();
+    assert_eq!(
+        count(&connection, "SELECT version FROM schema_version"),
+        i64::from(SCHEMA_VERSION)
+    );
+    assert!(
+        count(
+            &connection,
+            "SELECT COUNT(*) FROM schema WHERE table_name = 'members'"
+        ) > 0
+    );
+    assert_eq!(
+        count(
+            &connection,
+            "SELECT classes FROM sources WHERE name = 'app.jar'"
+        ),
+        2
+    );
+
+    let mut statement = connection
+        .prepare(
+            "SELECT DISTINCT m.class, m.name, m.descriptor \
+             FROM members m JOIN member_refs r \
+               ON (r.source, r.class, r.method_name, r.method_descriptor) \
+                = (m.source, m.class, m.name, m.descriptor) \
+             WHERE m.code_length > 5 AND r.owner LIKE 'java/lang/reflect/%'",
+        )
+        .unwrap();
+    let callers: Vec<(String, String, String)> = statement
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    drop(statement);
+    assert_eq!(
+        callers,
+        [(
+            "app/Reflective".to_string(),
+            "call".to_string(),
+            "()V".to_string()
+        )]
+    );
+
+    let class: String = connection
+        .query_row(
+            "SELECT class FROM string_constants WHERE value = 'secret'",
+            [],
+            |row| row.get(0),
+        )
+        .unwrap();
+    assert_eq!(class, "app/Reflective");
+    assert_eq!(
+        count(
+            &connection,
+            "SELECT COUNT(*) FROM attributes WHERE name = 'Code' AND class = 'app/Plain'"
+        ),
+        1
+    );
+    drop(connection);
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn exporting_a_source_again_replaces_it_and_an_unfinished_one_is_rolled_back() {
+    let path = database("resume.db");
+    let mut exporter = SqliteExporter::open(&path).unwrap();
+    exporter.begin_source("a.jar").unwrap();
+    exporter.add_class(&plain("a/One")).unwrap();
+    exporter.add_class(&plain("a/Two")).unwrap();
+    exporter.begin_source("b.jar").unwrap();
+    exporter.add_class(&plain("b/One")).unwrap();
+    exporter.finish().unwrap();
+
+    // a.jar again, now with one class, and c.jar cut short
+    let mut exporter = SqliteExporter::open(&path).unwrap();
+    exporter.begin_source("a.jar").unwrap();
+    exporter.add_class(&plain("a/One")).unwrap();
+    exporter.begin_source("c.jar").unwrap();
+    exporter.add_class(&plain("c/One")).unwrap();
+    drop(exporter);
+
+    let connection = Connection::open(&path).unwrap();
+    assert_eq!(
+        count(
+            &connection,
+            "SELECT COUNT(*) FROM classes WHERE source = 'a.jar'"
+        ),
+        1
+    );
+    assert_eq!(
+        count(
+            &connection,
+            "SELECT COUNT(*) FROM members WHERE source = 'a.jar'"
+        ),
+        1
+    );
+    assert_eq!(
+        count(
+            &connection,
+            "SELECT COUNT(*) FROM classes WHERE source = 'b.jar'"
+        ),
+        1
+    );
+    assert_eq!(
+        count(
+            &connection,
+            "SELECT COUNT(*) FROM sources WHERE name = 'c.jar'"
+        ),
+        0
+    );
+    drop(connection);
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn export_writes_the_index() {
+    let path = database("index.db");
+    let mut index = ClassIndex::new();
+    index.add_class(&reflective(), "app.jar");
+    index.add_class(&plain("lib/Plain"), "lib.jar");
+    SqliteExporter::export(&index, &path).unwrap();
+
+    let connection = Connection::open(&path).unwrap();
+    assert_eq!(count(&connection, "SELECT COUNT(*) FROM sources"), 2);
+    assert_eq!(
+        count(
+            &connection,
+            "SELECT COUNT(*) FROM members \
+             WHERE source = 'app.jar' AND class = 'app/Reflective' AND kind = 'field'"
+        ),
+        1
+    );
+    // the index keeps no code
+    assert_eq!(
+        count(
+            &connection,
+            "SELECT COUNT(*) FROM members WHERE code_length IS NOT NULL"
+        ),
+        0
+    );
+    drop(connection);
+    fs::remove_file(path).unwrap();
+}
@@ -0,0 +1,158 @@
+#![cfg(feature = "write")]
+
+use jvmb::attribute::{StackMapFrame, VerificationTypeInfo};
+use jvmb::classfile::ClassFile;
+use jvmb::constantpool::ConstantPool;
+
+fn fixture(name: &str) -> Vec<u8> {
+    let path = format!(
+        "{}/tests/fixtures/{}.class",
+        env!("CARGO_MANIFEST_DIR"),
+        name
+    );
+    std::fs::read(&path).unwrap_or_else(|e| panic!("{}: {}", path, e))
+}
+
+fn class_index(constant_pool: &[ConstantPool], name: &str) -> u16 {
+    (1..=constant_pool.len() as u16)
+        .find(|&index| ConstantPool::class_name(constant_pool, index) == Some(name))
+        .unwrap_or_else(|| panic!("no CONSTANT_Class for {}", name))
+}
+
+/// The locals a static method starts with, one entry per parameter.
+fn parameter_locals(constant_pool: &[ConstantPool], descriptor: &str) -> Vec<VerificationTypeInfo> {
+    let params = &descriptor[1..descriptor.find(')').unwrap()];
+    let mut locals = Vec::new();
+    let mut rest = params;
+    while !rest.is_empty() {
+        let dims = rest.len() - rest.trim_start_matches('[').len();
+        let end = match rest.as_bytes()[dims] {
+            b'L' => rest.find(';').unwrap() + 1,
+            _ => dims + 1,
+        };
+        let (param, tail) = rest.split_at(end);
+        locals.push(match param {
+            "I" | "Z" | "B" | "C" | "S" => VerificationTypeInfo::IntegerVariableInfo,
+            "F" => VerificationTypeInfo::FloatVariableInfo,
+            "J" => VerificationTypeInfo::LongVariableInfo,
+            "D" => VerificationTypeInfo::DoubleVariableInfo,
+            _ if dims > 0 => {
+                VerificationTypeInfo::ObjectVariableInfo(class_index(constant_pool, param))
+            }
+            _ => VerificationTypeInfo::ObjectVariableInfo(class_index(
+                constant_pool,
+                &param[1..param.len() - 1],
+            )),
+        });
+        rest = tail;
+    }
+    locals
+}
+
+#[test]
+fn javac_frames_round_trip_byte_for_byte() {
+    let bytes = fixture("frames/Branches");
+    let (_, class) = ClassFile::parse_class_file(&bytes).unwrap();
+    assert_eq!(class.to_bytes().unwrap(), bytes);
+}
+
+#[test]
+fn javac_frames_cover_every_frame_form() {
+    let bytes = fixture("frames/Branches");
+    let (_, class) = ClassFile::parse_class_file(&bytes).unwrap();
+    let mut seen = Vec::new();
+    for method in &class.methods {
+        if let Some(table) = method.stack_map_table() {
+            for frame in &table.entries {
+                seen.push(std::mem::discriminant(frame));
+            }
+        }
+    }
+    let all = [
+        StackMapFrame::SameFrame(0),
+        StackMapFrame::SameLocals1StackItemFrame {
+            offset_delta: 0,
+            stack: VerificationTypeInfo::TopVariableInfo,
+        },
+        StackMapFrame::SameLocals1StackItemFrameExtended {
+            offset_delta: 0,
+            stack: VerificationTypeInfo::TopVariableInfo,
+        },
+        StackMapFrame::ChopFrame {
+            chopped: 1,
+            offset_delta: 0,
+        },
+        StackMapFrame::SameFrameExtended(0),
+        StackMapFrame::AppendFrame {
+            offset_delta: 0,
+            locals: Vec::new(),
+        },
+        StackMapFrame::FullFrame {
+            offset_delta: 0,
+            locals: Vec::new(),
+            stack: Vec::new(),
+        },
+    ];
+    for form in &all {
+        assert!(seen.contains(&std::mem::discriminant(form)), "{:?}", form);
+    }
+}
+
+#[test]
+fn compact_picks_the_frames_javac_wrote() {
+    let bytes = fixture("frames/Branches");
+    let (_, class) = ClassFile::parse_class_file(&bytes).unwrap();
+    let pool = &class.constant_pool;
+    let mut checked = 0;
+    for method in &class.methods {
+        let table = match method.stack_map_table() {
+            Some(table) => table,
+            None => continue,
+        };
+        let descriptor = ConstantPool::utf8(pool, method.descriptor_index()).unwrap();
+        let mut locals = parameter_locals(pool, descriptor);
+        for frame in &table.entries {
+            let previous = locals.clone();
+            let (offset_delta, stack) = match frame {
+                StackMapFrame::SameFrame(delta) => (*delta as u16, Vec::new()),
+                StackMapFrame::SameLocals1StackItemFrame {
+                    offset_delta,
+                    stack,
+                } => (*offset_delta as u16, vec![stack.clone()]),
+                StackMapFrame::SameLocals1StackItemFrameExtended {
+                    offset_delta,
+                    stack,
+                } => (*offset_delta, vec![stack.clone()]),
+                StackMapFrame::ChopFrame {
+                    chopped,
+                    offset_delta,
+                } => {
+                    locals.truncate(locals.len() - *chopped as usize);
+                    (*offset_delta, Vec::new())
+                }
+                StackMapFrame::SameFrameExtended(delta) => (*delta, Vec::new()),
+                StackMapFrame::AppendFrame {
+                    offset_delta,
+                    locals: added,
+                } => {
+                    locals.extend(added.iter().cloned());
+                    (*offset_delta, Vec::new())
+                }
+                StackMapFrame::FullFrame {
+                    offset_delta,
+                    locals: full_locals,
+                    stack,
+                } => {
+                    locals = full_locals.clone();
+                    (*offset_delta, stack.clone())
+                }
+            };
+            assert_eq!(
+                &StackMapFrame::compact(offset_delta, &previous, &locals, &stack),
+                frame
+            );
+            checked += 1;
+        }
+    }
+    assert!(checked > 20, "only {} frames checked", checked);
+}
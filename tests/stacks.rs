@@ -0,0 +1,67 @@
+#![cfg(feature = "analysis")]
+
+mod common;
+
+use common::RawClass;
+use jvmb::{
+    classfile::ClassFile,
+    frames::{infer_stacks, StackAfter},
+};
+
+/// The stacks after the instructions of the static method `run()V` whose
+/// code is `code`, displayed.
+fn stacks(max_stack: u16, code: &[u8]) -> Vec<(u32, String)> {
+    let mut class = RawClass::new("Stacks", "java/lang/Object");
+    let code = class.code(max_stack, 0, code, &[]);
+    class.method(0x0008, "run", "()V", &[code]);
+    let class_file = ClassFile::from_bytes(&class.bytes()).unwrap();
+    infer_stacks(&class_file, &class_file.methods[0])
+        .unwrap()
+        .into_iter()
+        .map(|(offset, stack)| (offset, stack.to_string()))
+        .collect()
+}
+
+#[test]
+fn code_after_a_return_is_unreachable() {
+    // iconst_1, pop, return, iconst_2, pop, return
+    assert_eq!(
+        stacks(1, &[0x04, 0x57, 0xb1, 0x05, 0x57, 0xb1]),
+        [
+            (0, "[I]".to_string()),
+            (1, "[]".to_string()),
+            (2, "[]".to_string()),
+            (3, "unreachable".to_string()),
+            (4, "unreachable".to_string()),
+            (5, "unreachable".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn deep_stacks_show_the_values_nearest_the_top() {
+    // lconst_0, then nine iconst_0, and a return
+    let mut code = vec![0x09];
+    code.extend([0x03; 9]);
+    code.push(0xb1);
+    let stacks = stacks(11, &code);
+    assert_eq!(stacks[0], (0, "[J(2)]".to_string()));
+    assert_eq!(stacks[1], (1, "[J(2), I]".to_string()));
+    assert_eq!(stacks[7], (7, "[J(2), I, I, I, I, I, I, I]".to_string()));
+    assert_eq!(
+        stacks[8],
+        (8, "[… 1 more, I, I, I, I, I, I, I, I]".to_string())
+    );
+    assert_eq!(
+        stacks[9],
+        (9, "[… 2 more, I, I, I, I, I, I, I, I]".to_string())
+    );
+    assert_eq!(
+        StackAfter::Known {
+            stack: Vec::new(),
+            merged: true
+        }
+        .to_string(),
+        "[] (merged frame)"
+    );
+}
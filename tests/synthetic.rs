@@ -0,0 +1,101 @@
+#![cfg(all(feature = "analysis", feature = "write"))]
+
+mod common;
+
+use common::{fixture, RawClass};
+use jvmb::{
+    accessflags::ACC_SYNTHETIC,
+    attribute::Attribute,
+    classfile::{ClassFile, ParseOptions, SyntheticForm},
+    verify::verify,
+};
+
+/// A class of Java 1.4 marked the way compilers of that time did: with
+/// `Synthetic` attributes on the class, its field `f` and its method `m`,
+/// each `info` long.
+fn marked_by_attribute(info: &[u8]) -> Vec<u8> {
+    let mut class = RawClass::new("Old", "java/lang/Object");
+    class.version = (48, 0);
+    let attribute = class.attribute("Synthetic", info);
+    class.field(0x0000, "f", "I", std::slice::from_ref(&attribute));
+    class.method(0x0400, "m", "()V", std::slice::from_ref(&attribute));
+    class.class_attribute(attribute);
+    class.bytes()
+}
+
+#[test]
+fn the_flag_marks_what_javac_generates() {
+    let outer = ClassFile::from_bytes(&fixture("synthetic/Outer")).unwrap();
+    let synthetic: Vec<_> = outer
+        .methods
+        .iter()
+        .filter(|method| method.is_synthetic())
+        .map(|method| method.name(&outer.constant_pool).unwrap())
+        .collect();
+    assert_eq!(synthetic, ["lambda$task$0"]);
+    assert!(!outer.is_synthetic());
+
+    let inner = ClassFile::from_bytes(&fixture("synthetic/Outer$Inner")).unwrap();
+    let [this] = &inner.fields[..] else {
+        panic!("{:?}", inner.fields);
+    };
+    assert!(this.is_synthetic());
+    assert_ne!(this.access_flags().bits() & ACC_SYNTHETIC, 0);
+    assert!(!Attribute::has_synthetic(this.attributes()));
+}
+
+#[test]
+fn the_attribute_marks_what_old_compilers_generated() {
+    let class = ClassFile::from_bytes(&marked_by_attribute(&[])).unwrap();
+    assert!(class.is_synthetic());
+    assert!(class.fields[0].is_synthetic());
+    assert!(class.methods[0].is_synthetic());
+    assert_eq!(class.access_flags.bits() & ACC_SYNTHETIC, 0);
+    assert_eq!(class.fields[0].access_flags().bits() & ACC_SYNTHETIC, 0);
+    assert_eq!(class.methods[0].access_flags().bits() & ACC_SYNTHETIC, 0);
+}
+
+#[test]
+fn either_form_is_written_on_request() {
+    assert_eq!(
+        SyntheticForm::for_major_version(48),
+        SyntheticForm::Attribute
+    );
+    assert_eq!(SyntheticForm::for_major_version(49), SyntheticForm::Flag);
+
+    let mut class = ClassFile::from_bytes(&marked_by_attribute(&[])).unwrap();
+    class.set_synthetic_form(SyntheticForm::Flag);
+    let class = ClassFile::from_bytes(&class.to_bytes().unwrap()).unwrap();
+    assert_ne!(class.access_flags.bits() & ACC_SYNTHETIC, 0);
+    assert_ne!(class.fields[0].access_flags().bits() & ACC_SYNTHETIC, 0);
+    assert_ne!(class.methods[0].access_flags().bits() & ACC_SYNTHETIC, 0);
+    assert!(!Attribute::has_synthetic(&class.attributes));
+    assert!(class.fields[0].attributes().is_empty());
+    assert!(class.methods[0].attributes().is_empty());
+
+    let mut inner = ClassFile::from_bytes(&fixture("synthetic/Outer$Inner")).unwrap();
+    inner.set_synthetic_form(SyntheticForm::Attribute);
+    let inner = ClassFile::from_bytes(&inner.to_bytes().unwrap()).unwrap();
+    let this = &inner.fields[0];
+    assert!(this.is_synthetic());
+    assert_eq!(this.access_flags().bits() & ACC_SYNTHETIC, 0);
+    assert_eq!(this.attributes(), [Attribute::Synthetic(vec![])]);
+}
+
+#[test]
+fn verify_warns_of_a_synthetic_attribute_with_a_body() {
+    let (_, class) =
+        ClassFile::parse_with(&marked_by_attribute(&[0]), &ParseOptions::lenient()).unwrap();
+    assert!(class.is_synthetic());
+    let warnings: Vec<_> = verify(&class)
+        .into_iter()
+        .filter(|violation| violation.code == "V0303")
+        .map(|violation| violation.location)
+        .collect();
+    assert_eq!(warnings.len(), 3, "{:?}", warnings);
+
+    let class = ClassFile::from_bytes(&marked_by_attribute(&[])).unwrap();
+    assert!(verify(&class)
+        .iter()
+        .all(|violation| violation.code != "V0303"));
+}
@@ -0,0 +1,79 @@
+//! The Textifier-style dump compared with checked-in dumps, whitespace
+//! at the ends of lines aside.
+
+#![cfg(feature = "disasm")]
+
+mod common;
+
+use std::fs;
+
+use common::{fixture, RawClass};
+use jvmb::{classfile::ClassFile, textify::textify};
+
+fn normalized(text: &str) -> Vec<&str> {
+    text.lines().map(str::trim_end).collect()
+}
+
+fn assert_golden(name: &str) {
+    let class_file = ClassFile::from_bytes(&fixture(name)).unwrap();
+    let path = format!(
+        "{}/tests/fixtures/{}.textify.txt",
+        env!("CARGO_MANIFEST_DIR"),
+        name
+    );
+    let expected = fs::read_to_string(&path).unwrap();
+    assert_eq!(normalized(&textify(&class_file)), normalized(&expected));
+}
+
+#[test]
+fn record_matches_its_golden_dump() {
+    assert_golden("records/Point");
+}
+
+#[test]
+fn branches_match_their_golden_dump() {
+    assert_golden("frames/Branches");
+}
+
+#[test]
+fn targets_before_the_code_print_as_offsets_in_their_slots() {
+    let mut class = RawClass::new("Back", "java/lang/Object");
+    // goto -1
+    let code = class.code(0, 0, &[0xa7, 0xff, 0xff], &[]);
+    class.method(0x0008, "back", "()V", &[code]);
+
+    // iconst_0, then a tableswitch at 1 of 0 to 2, padded with two bytes,
+    // whose case 1 jumps to -8 and the rest to the return at 28
+    let mut switch = vec![0x03, 0xaa, 0, 0];
+    for word in [27, 0, 2, 27, -9, 27] {
+        switch.extend_from_slice(&i32::to_be_bytes(word));
+    }
+    switch.push(0xb1);
+    let code = class.code(1, 0, &switch, &[]);
+    class.method(0x0008, "cases", "()V", &[code]);
+
+    let class_file = ClassFile::from_bytes(&class.bytes()).unwrap();
+    let text = textify(&class_file);
+    let expected = "
+  // access flags 0x8
+  static back()V
+    GOTO -1
+    MAXSTACK = 0
+    MAXLOCALS = 0
+
+  // access flags 0x8
+  static cases()V
+    ICONST_0
+    TABLESWITCH
+      0: L0
+      1: -8
+      2: L0
+      default: L0
+   L0
+    RETURN
+    MAXSTACK = 1
+    MAXLOCALS = 0
+}
+";
+    assert!(text.ends_with(expected), "{}", text);
+}
@@ -0,0 +1,102 @@
+mod common;
+
+use common::RawClass;
+use jvmb::{classfile::ClassFile, error::ParseError};
+
+/// The string constant, of which the annotation's element is also made.
+const VALUE: &str = "a constant, long enough to cut in half";
+
+/// A class whose method `run` loads a string constant, and is annotated
+/// `@Marker(VALUE)`.
+fn class_file() -> Vec<u8> {
+    let mut class = RawClass::new("Cut", "java/lang/Object");
+    let value = class.utf8(VALUE);
+    let string = class.entry(8, &value.to_be_bytes());
+    let mut annotation = vec![0, 1];
+    annotation.extend_from_slice(&class.utf8("LMarker;").to_be_bytes());
+    annotation.extend_from_slice(&[0, 1]);
+    annotation.extend_from_slice(&class.utf8("value").to_be_bytes());
+    annotation.push(b's');
+    annotation.extend_from_slice(&value.to_be_bytes());
+    let annotations = class.attribute("RuntimeVisibleAnnotations", &annotation);
+    // ldc, pop, return
+    let code = class.code(1, 0, &[0x12, string as u8, 0x57, 0xb1], &[]);
+    class.method(0x0009, "run", "()V", &[code, annotations]);
+    class.bytes()
+}
+
+fn position(bytes: &[u8], part: &[u8]) -> usize {
+    bytes
+        .windows(part.len())
+        .position(|window| window == part)
+        .unwrap()
+}
+
+/// Asserts that `bytes` cut to `len` fails as cut short by what `context`
+/// was being parsed, at a field of `expected` bytes, of which those from
+/// `at_offset` are there.
+fn assert_truncated(bytes: &[u8], len: usize, expected: usize, at_offset: usize, context: &str) {
+    assert_eq!(
+        ClassFile::from_bytes(&bytes[..len]).unwrap_err(),
+        ParseError::Truncated {
+            expected,
+            available: len - at_offset,
+            at_offset,
+            context: context.to_string(),
+        }
+    );
+}
+
+#[test]
+fn a_class_cut_in_a_constant_is_truncated() {
+    let bytes = class_file();
+    assert!(ClassFile::from_bytes(&bytes).is_ok());
+    let value = position(&bytes, VALUE.as_bytes());
+    assert_truncated(&bytes, value + 10, VALUE.len(), value, "constant #5");
+    // in its length
+    assert_truncated(&bytes, value - 1, 2, value - 2, "constant #5");
+}
+
+#[test]
+fn a_class_cut_in_an_attribute_is_truncated() {
+    let bytes = class_file();
+    // ldc and its index come before pop and return
+    let code = position(&bytes, &[0x57, 0xb1]) - 2;
+    // max_stack, max_locals and code_length come before the code
+    let body = code - 8;
+    let length = body - 4;
+    // in the Code attribute's length, short of the 6 bytes the name and
+    // length of each of the method's two attributes take
+    assert_truncated(&bytes, length + 2, 12, length - 2, "method run()V");
+    // in its code, short of the length declared
+    assert_truncated(&bytes, code + 1, 16, body, "method run()V");
+}
+
+#[test]
+fn a_class_cut_in_an_annotation_is_truncated() {
+    let bytes = class_file();
+    // the element, its index, and the class's count of attributes
+    let element = bytes.len() - 3 - 2;
+    assert_eq!(bytes[element], b's');
+    // its count, type, count of elements and name come before the element
+    let body = element - 8;
+    assert_truncated(&bytes, element + 2, 11, body, "method run()V");
+}
+
+#[test]
+fn an_annotation_running_past_its_attribute_is_attribute_truncated() {
+    let mut bytes = class_file();
+    // the attribute a byte shorter, so the element's index ends past it
+    let element = bytes.len() - 3 - 2;
+    let length = element - 8 - 4;
+    let declared = u32::from_be_bytes(bytes[length..length + 4].try_into().unwrap()) - 1;
+    bytes[length..length + 4].copy_from_slice(&declared.to_be_bytes());
+    bytes.remove(element + 1);
+    assert_eq!(
+        ClassFile::from_bytes(&bytes).unwrap_err(),
+        ParseError::AttributeTruncated {
+            name: "RuntimeVisibleAnnotations".to_string(),
+            declared,
+        }
+    );
+}